@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+# Compares benchmarks of the working tree with a saved baseline of another revision.
+#
+# Usage: ci_scripts/bench-baseline.sh [REVISION]
+#
+# Benchmarks of REVISION (`main` by default) are run in a temporary worktree and saved as a
+# criterion baseline named after it, unless it's saved already. Then benchmarks of the working
+# tree are compared with it. Remove `REVISION` directories under `target/criterion` to measure
+# REVISION again.
+set -e
+
+REVISION="${1:-main}"
+BENCH_PACKAGES=(-p nssa -p sequencer_core)
+ROOT_DIR="$(git rev-parse --show-toplevel)"
+export CARGO_TARGET_DIR="${CARGO_TARGET_DIR:-${ROOT_DIR}/target}"
+
+if ! find "${CARGO_TARGET_DIR}/criterion" -type d -name "${REVISION}" 2>/dev/null | grep -q .; then
+  WORKTREE_DIR="$(mktemp -d)"
+  trap 'git -C "${ROOT_DIR}" worktree remove --force "${WORKTREE_DIR}"' EXIT
+  git -C "${ROOT_DIR}" worktree add --detach "${WORKTREE_DIR}" "${REVISION}"
+  (cd "${WORKTREE_DIR}" && cargo bench "${BENCH_PACKAGES[@]}" -- --save-baseline "${REVISION}")
+fi
+
+# Benchmarks, which REVISION doesn't have, are run without comparison
+cd "${ROOT_DIR}"
+cargo bench "${BENCH_PACKAGES[@]}" -- --baseline-lenient "${REVISION}"
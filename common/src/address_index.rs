@@ -0,0 +1,459 @@
+//! Index of transactions by public accounts they touch, paged from the newest transaction to the
+//! oldest one.
+//!
+//! Pages are delimited by [`AddressTxCursor`], which is the position of the last returned
+//! transaction. Blocks are only appended, so transactions of blocks applied between page fetches
+//! are newer than every cursor and never shift the following pages.
+
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use nssa::{AccountId, public_transaction::NativeTransfer};
+use nssa_core::{program::ProgramId, units::Amount};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    HashType,
+    json_number::decimal_string,
+    transaction::{EncodedTransaction, NSSATransaction},
+};
+
+/// Number of transactions in a page, if it isn't requested
+pub const DEFAULT_ADDRESS_TXS_PAGE_SIZE: usize = 100;
+
+/// Larger requested pages are cut to this size
+pub const MAX_ADDRESS_TXS_PAGE_SIZE: usize = 1000;
+
+/// Position of a transaction in the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TxPosition {
+    pub block_id: u64,
+    /// Index of the transaction in its block
+    pub index: u32,
+}
+
+/// Where the next page of [`AddressTxIndex::page`] starts
+///
+/// It's opaque for clients, which only pass it back as they got it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressTxCursor(TxPosition);
+
+impl Display for AddressTxCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}{:08x}", self.0.block_id, self.0.index)
+    }
+}
+
+impl FromStr for AddressTxCursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid cursor {s:?}");
+        if s.len() != 24 || !s.is_ascii() {
+            return Err(invalid());
+        }
+        let block_id = u64::from_str_radix(&s[..16], 16).map_err(|_| invalid())?;
+        let index = u32::from_str_radix(&s[16..], 16).map_err(|_| invalid())?;
+        Ok(Self(TxPosition { block_id, index }))
+    }
+}
+
+impl Serialize for AddressTxCursor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AddressTxCursor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Direction of a native transfer relative to the indexed account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxDirection {
+    /// The account receives the transfer
+    In,
+    /// The account sends the transfer, transfers to itself are outgoing
+    Out,
+}
+
+/// Transaction touching an indexed account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressTxEntry {
+    pub position: TxPosition,
+    pub hash: HashType,
+    /// Called program, known only for public transactions
+    pub program_id: Option<ProgramId>,
+    /// Direction and amount, if the transaction is a native transfer of the account
+    pub transfer: Option<(TxDirection, Amount)>,
+}
+
+/// Filter of indexed transactions, which matches everything by default
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressTxFilter {
+    /// Only native transfers in this direction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<TxDirection>,
+    /// Only native transfers of at least this amount
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "decimal_string::option"
+    )]
+    pub min_amount: Option<Amount>,
+    /// Only transactions of blocks from this one (inclusive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_block: Option<u64>,
+    /// Only transactions of blocks up to this one (inclusive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_block: Option<u64>,
+    /// Only public transactions calling this program
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program_id: Option<ProgramId>,
+}
+
+impl AddressTxFilter {
+    pub fn matches(&self, entry: &AddressTxEntry) -> bool {
+        let block_id = entry.position.block_id;
+        if self.from_block.is_some_and(|from| block_id < from)
+            || self.to_block.is_some_and(|to| block_id > to)
+        {
+            return false;
+        }
+        if self
+            .program_id
+            .is_some_and(|program_id| entry.program_id != Some(program_id))
+        {
+            return false;
+        }
+        if self.direction.is_some() || self.min_amount.is_some() {
+            let Some((direction, amount)) = entry.transfer else {
+                return false;
+            };
+            if self.direction.is_some_and(|expected| direction != expected)
+                || self.min_amount.is_some_and(|min| amount < min)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Page of transactions from the newest one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressTxPage {
+    pub entries: Vec<AddressTxEntry>,
+    /// Cursor of the next page, `None` if this one is the last
+    pub next_cursor: Option<AddressTxCursor>,
+}
+
+/// Transactions of every public account in chain order
+#[derive(Debug, Clone, Default)]
+pub struct AddressTxIndex {
+    entries: HashMap<AccountId, Vec<AddressTxEntry>>,
+}
+
+impl AddressTxIndex {
+    /// Indexes `tx` at `position` for every public account it touches
+    pub fn record(&mut self, tx: &EncodedTransaction, position: TxPosition) {
+        let Ok(nssa_tx) = NSSATransaction::try_from(tx) else {
+            return;
+        };
+
+        let (program_id, transfer) = match &nssa_tx {
+            NSSATransaction::Public(public_tx) => (
+                Some(public_tx.message().program_id()),
+                match NativeTransfer::decode(public_tx.message()) {
+                    Some(NativeTransfer::Transfer {
+                        sender,
+                        recipient,
+                        amount,
+                    }) => Some((sender, recipient, amount)),
+                    Some(NativeTransfer::Initialize { .. }) | None => None,
+                },
+            ),
+            NSSATransaction::PrivacyPreserving(_) | NSSATransaction::ProgramDeployment(_) => {
+                (None, None)
+            }
+        };
+
+        let hash = tx.hash();
+        for account_id in nssa_tx.public_account_ids() {
+            let transfer = transfer.and_then(|(sender, recipient, amount)| {
+                if account_id == sender {
+                    Some((TxDirection::Out, amount))
+                } else if account_id == recipient {
+                    Some((TxDirection::In, amount))
+                } else {
+                    None
+                }
+            });
+            self.insert(
+                account_id,
+                AddressTxEntry {
+                    position,
+                    hash,
+                    program_id,
+                    transfer,
+                },
+            );
+        }
+    }
+
+    /// Appends `entry` to transactions of `account_id`
+    ///
+    /// Entries must come in chain order, the ones not after the last indexed transaction of the
+    /// account are ignored, so an account listed twice in a transaction is indexed once.
+    pub fn insert(&mut self, account_id: AccountId, entry: AddressTxEntry) {
+        let entries = self.entries.entry(account_id).or_default();
+        if entries
+            .last()
+            .is_some_and(|last| last.position >= entry.position)
+        {
+            return;
+        }
+        entries.push(entry);
+    }
+
+    /// Entry of `account_id` for the transaction at `position`, if it's indexed
+    pub fn get(&self, account_id: &AccountId, position: TxPosition) -> Option<&AddressTxEntry> {
+        let entries = self.entries.get(account_id)?;
+        let index = entries
+            .binary_search_by_key(&position, |entry| entry.position)
+            .ok()?;
+        Some(&entries[index])
+    }
+
+    /// Every indexed entry with its account, accounts come in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&AccountId, &AddressTxEntry)> {
+        self.entries
+            .iter()
+            .flat_map(|(account_id, entries)| entries.iter().map(move |entry| (account_id, entry)))
+    }
+
+    /// Up to `limit` transactions of `account_id` matching `filter`, from the newest one before
+    /// `cursor`, or from the newest one at all without it
+    ///
+    /// `limit` is cut to [`MAX_ADDRESS_TXS_PAGE_SIZE`].
+    pub fn page(
+        &self,
+        account_id: &AccountId,
+        filter: &AddressTxFilter,
+        cursor: Option<AddressTxCursor>,
+        limit: usize,
+    ) -> AddressTxPage {
+        let limit = limit.clamp(1, MAX_ADDRESS_TXS_PAGE_SIZE);
+        let entries = self.entries.get(account_id).map_or(&[][..], Vec::as_slice);
+
+        let mut end = entries.len();
+        if let Some(AddressTxCursor(position)) = cursor {
+            end = end.min(entries.partition_point(|entry| entry.position < position));
+        }
+        if let Some(to_block) = filter.to_block {
+            end = end.min(entries.partition_point(|entry| entry.position.block_id <= to_block));
+        }
+        let start = filter.from_block.map_or(0, |from_block| {
+            entries.partition_point(|entry| entry.position.block_id < from_block)
+        });
+
+        let mut matching = entries[start.min(end)..end]
+            .iter()
+            .rev()
+            .filter(|entry| filter.matches(entry));
+        let page: Vec<_> = matching.by_ref().take(limit).cloned().collect();
+        let next_cursor = match page.last() {
+            Some(last) if matching.next().is_some() => Some(AddressTxCursor(last.position)),
+            _ => None,
+        };
+
+        AddressTxPage {
+            entries: page,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: AccountId = AccountId::new([1; 32]);
+    const TXS_PER_BLOCK: u32 = 3;
+
+    fn entry(block_id: u64, index: u32) -> AddressTxEntry {
+        let direction = if index.is_multiple_of(2) {
+            TxDirection::In
+        } else {
+            TxDirection::Out
+        };
+        AddressTxEntry {
+            position: TxPosition { block_id, index },
+            hash: [0; 32],
+            program_id: Some(ProgramId::new([index; 8])),
+            transfer: Some((direction, Amount::from(block_id))),
+        }
+    }
+
+    fn index_with_blocks(blocks: std::ops::Range<u64>) -> AddressTxIndex {
+        let mut index = AddressTxIndex::default();
+        add_blocks(&mut index, blocks);
+        index
+    }
+
+    fn add_blocks(index: &mut AddressTxIndex, blocks: std::ops::Range<u64>) {
+        for block_id in blocks {
+            for tx_index in 0..TXS_PER_BLOCK {
+                index.insert(ACCOUNT, entry(block_id, tx_index));
+            }
+        }
+    }
+
+    fn positions(entries: &[AddressTxEntry]) -> Vec<TxPosition> {
+        entries.iter().map(|entry| entry.position).collect()
+    }
+
+    #[test]
+    fn test_pages_cover_every_transaction_once_while_blocks_are_applied() {
+        let mut index = index_with_blocks(1..1001);
+        let expected: Vec<_> = (1..1001)
+            .rev()
+            .flat_map(|block_id| {
+                (0..TXS_PER_BLOCK)
+                    .rev()
+                    .map(move |index| TxPosition { block_id, index })
+            })
+            .collect();
+
+        let mut fetched = vec![];
+        let mut cursor = None;
+        let mut next_block_id = 1001;
+        loop {
+            let page = index.page(&ACCOUNT, &AddressTxFilter::default(), cursor, 128);
+            fetched.extend(positions(&page.entries));
+            // Blocks land between page fetches
+            add_blocks(&mut index, next_block_id..next_block_id + 5);
+            next_block_id += 5;
+
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+
+        assert_eq!(fetched, expected);
+    }
+
+    #[test]
+    fn test_filtered_pages_cover_every_matching_transaction_once() {
+        let index = index_with_blocks(1..2001);
+        let filter = AddressTxFilter {
+            direction: Some(TxDirection::In),
+            min_amount: Some(Amount(100)),
+            from_block: Some(50),
+            to_block: Some(1500),
+            program_id: Some(ProgramId::new([2; 8])),
+        };
+        let expected: Vec<_> = (100..=1500)
+            .rev()
+            .map(|block_id| TxPosition { block_id, index: 2 })
+            .collect();
+
+        let mut fetched = vec![];
+        let mut cursor = None;
+        loop {
+            let page = index.page(&ACCOUNT, &filter, cursor, 100);
+            assert!(page.entries.iter().all(|entry| filter.matches(entry)));
+            fetched.extend(positions(&page.entries));
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+
+        assert_eq!(fetched, expected);
+    }
+
+    #[test]
+    fn test_page_size_is_capped() {
+        let index = index_with_blocks(1..1001);
+
+        let page = index.page(&ACCOUNT, &AddressTxFilter::default(), None, usize::MAX);
+
+        assert_eq!(page.entries.len(), MAX_ADDRESS_TXS_PAGE_SIZE);
+        assert!(page.next_cursor.is_some());
+    }
+
+    #[test]
+    fn test_last_full_page_has_no_next_cursor() {
+        let index = index_with_blocks(1..3);
+
+        let page = index.page(&ACCOUNT, &AddressTxFilter::default(), None, 6);
+
+        assert_eq!(page.entries.len(), 6);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn test_unknown_account_has_empty_page() {
+        let index = index_with_blocks(1..3);
+
+        let page = index.page(
+            &AccountId::new([2; 32]),
+            &AddressTxFilter::default(),
+            None,
+            10,
+        );
+
+        assert_eq!(
+            page,
+            AddressTxPage {
+                entries: vec![],
+                next_cursor: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_entry_is_found_by_position() {
+        let index = index_with_blocks(1..4);
+
+        assert_eq!(
+            index.get(
+                &ACCOUNT,
+                TxPosition {
+                    block_id: 2,
+                    index: 1
+                }
+            ),
+            Some(&entry(2, 1))
+        );
+        assert_eq!(
+            index.get(
+                &ACCOUNT,
+                TxPosition {
+                    block_id: 4,
+                    index: 0
+                }
+            ),
+            None
+        );
+        assert_eq!(index.iter().count(), 3 * TXS_PER_BLOCK as usize);
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = AddressTxCursor(TxPosition {
+            block_id: u64::MAX,
+            index: 7,
+        });
+
+        assert_eq!(cursor.to_string().parse::<AddressTxCursor>(), Ok(cursor));
+        assert!("not a cursor".parse::<AddressTxCursor>().is_err());
+        assert!("ä".repeat(12).parse::<AddressTxCursor>().is_err());
+    }
+}
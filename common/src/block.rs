@@ -1,7 +1,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use sha2::{Digest, Sha256, digest::FixedOutput};
 
-use crate::transaction::EncodedTransaction;
+use crate::{rpc_types::BlockDigest, transaction::EncodedTransaction};
 
 pub type HashType = [u8; 32];
 
@@ -22,6 +22,18 @@ impl OwnHasher {
 pub type BlockHash = [u8; 32];
 pub type BlockId = u64;
 pub type TimeStamp = u64;
+/// Version of chain parameters, which a block is built with. It's `0` for parameters of genesis
+/// and is incremented by every scheduled upgrade of them.
+pub type ParamsVersion = u32;
+
+/// Milliseconds between logical timestamps of consecutive blocks
+pub const LOGICAL_BLOCK_INTERVAL_MILLIS: TimeStamp = 1000;
+
+/// Timestamp of block `block_id` in deterministic dev mode, which is derived from its height
+/// instead of the clock, so the same blocks are built on every run
+pub fn logical_timestamp(block_id: BlockId) -> TimeStamp {
+    block_id * LOGICAL_BLOCK_INTERVAL_MILLIS
+}
 
 #[derive(Debug, Clone)]
 pub struct BlockHeader {
@@ -29,6 +41,10 @@ pub struct BlockHeader {
     pub prev_block_hash: BlockHash,
     pub hash: BlockHash,
     pub timestamp: TimeStamp,
+    pub params_version: ParamsVersion,
+    /// Whether proofs of the block are produced by [`nssa::ProverBackend::DevFake`], so they
+    /// prove nothing. Only local chains produce such blocks.
+    pub dev_proved: bool,
     pub signature: nssa::Signature,
 }
 
@@ -43,18 +59,104 @@ pub struct Block {
     pub body: BlockBody,
 }
 
-#[derive(Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct HashableBlockData {
     pub block_id: BlockId,
     pub prev_block_hash: BlockHash,
     pub timestamp: TimeStamp,
     pub transactions: Vec<EncodedTransaction>,
+    pub params_version: ParamsVersion,
+    pub dev_proved: bool,
+}
+
+/// Flag byte of dev-proved blocks, encoded after parameters version
+const DEV_PROVED_FLAG: u8 = 1;
+
+/// Parameters version is encoded after transactions and only if it's not `0`, so blocks built
+/// before the first upgrade keep their encoding, hashes and signatures. Dev-proved blocks are
+/// followed by [`DEV_PROVED_FLAG`], which needs the version to be encoded even if it's `0`, so
+/// such blocks never have the encoding and the hash of a real one.
+impl BorshSerialize for HashableBlockData {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.block_id.serialize(writer)?;
+        self.prev_block_hash.serialize(writer)?;
+        self.timestamp.serialize(writer)?;
+        self.transactions.serialize(writer)?;
+        if self.params_version != 0 || self.dev_proved {
+            self.params_version.serialize(writer)?;
+        }
+        if self.dev_proved {
+            DEV_PROVED_FLAG.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for HashableBlockData {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let block_id = BlockId::deserialize_reader(reader)?;
+        let prev_block_hash = BlockHash::deserialize_reader(reader)?;
+        let timestamp = TimeStamp::deserialize_reader(reader)?;
+        let transactions = Vec::<EncodedTransaction>::deserialize_reader(reader)?;
+
+        let mut version_bytes = [0; size_of::<ParamsVersion>()];
+        let read = reader.read(&mut version_bytes)?;
+        if read == 0 {
+            return Ok(Self {
+                block_id,
+                prev_block_hash,
+                timestamp,
+                transactions,
+                params_version: 0,
+                dev_proved: false,
+            });
+        }
+        reader.read_exact(&mut version_bytes[read..])?;
+        let params_version = ParamsVersion::from_le_bytes(version_bytes);
+
+        let mut flag = [0; 1];
+        let dev_proved = match reader.read(&mut flag)? {
+            0 => false,
+            _ if flag[0] == DEV_PROVED_FLAG => true,
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Invalid block flag {}", flag[0]),
+                ));
+            }
+        };
+        // The same block would have two encodings and two hashes otherwise
+        if params_version == 0 && !dev_proved {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Parameters version 0 must not be encoded",
+            ));
+        }
+
+        Ok(Self {
+            block_id,
+            prev_block_hash,
+            timestamp,
+            transactions,
+            params_version,
+            dev_proved,
+        })
+    }
 }
 
 impl HashableBlockData {
     pub fn into_block(self, signing_key: &nssa::PrivateKey) -> Block {
+        self.into_block_with_aux_random(signing_key, nssa::entropy::random_bytes())
+    }
+
+    /// Signs the block with the given auxiliary randomness, so its signature is reproducible
+    pub fn into_block_with_aux_random(
+        self,
+        signing_key: &nssa::PrivateKey,
+        aux_random: [u8; 32],
+    ) -> Block {
         let data_bytes = borsh::to_vec(&self).unwrap();
-        let signature = nssa::Signature::new(signing_key, &data_bytes);
+        let signature = nssa::Signature::new_with_aux_random(signing_key, &data_bytes, aux_random);
         let hash = OwnHasher::hash(&data_bytes);
         Block {
             header: BlockHeader {
@@ -62,6 +164,8 @@ impl HashableBlockData {
                 prev_block_hash: self.prev_block_hash,
                 hash,
                 timestamp: self.timestamp,
+                params_version: self.params_version,
+                dev_proved: self.dev_proved,
                 signature,
             },
             body: BlockBody {
@@ -69,6 +173,93 @@ impl HashableBlockData {
             },
         }
     }
+
+    /// Hash of the block, which is built from this data
+    pub fn hash(&self) -> BlockHash {
+        OwnHasher::hash(&borsh::to_vec(self).unwrap())
+    }
+
+    pub fn digest(&self) -> BlockDigest {
+        BlockDigest::new(&self.hash(), self.transactions.len())
+    }
+
+    /// Checks that this is the block, which `advertised` digest was served for
+    ///
+    /// It's much cheaper than validating signatures and proofs of the block, so it's done first
+    /// to tell apart a sequencer serving inconsistent data from an invalid block.
+    pub fn check_digest(&self, advertised: &BlockDigest) -> Result<(), InconsistentBlockError> {
+        let digest = self.digest();
+        if digest != *advertised {
+            return Err(InconsistentBlockError {
+                block_id: self.block_id,
+                digest,
+                advertised: advertised.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Fetched block doesn't match what the sequencer advertised for it, see
+/// [`HashableBlockData::check_digest`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Sequencer served inconsistent data: block {block_id} has hash {} with {} transactions, but \
+     hash {} with {} transactions was advertised",
+    digest.hash,
+    digest.tx_count,
+    advertised.hash,
+    advertised.tx_count
+)]
+pub struct InconsistentBlockError {
+    pub block_id: BlockId,
+    pub digest: BlockDigest,
+    pub advertised: BlockDigest,
+}
+
+/// Reason to reject a block because of its timestamp, see [`BlockHeader::check_timestamp`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BlockTimestampError {
+    #[error(
+        "Block timestamp {timestamp} is more than {max_skew_millis}ms ahead of local time {now}"
+    )]
+    InFuture {
+        timestamp: TimeStamp,
+        now: TimeStamp,
+        max_skew_millis: u64,
+    },
+    #[error("Block timestamp {timestamp} is before timestamp {prev_timestamp} of previous block")]
+    BeforePrevious {
+        timestamp: TimeStamp,
+        prev_timestamp: TimeStamp,
+    },
+}
+
+impl BlockHeader {
+    /// Checks that the block isn't stamped before the previous block, and not more than
+    /// `max_skew_millis` ahead of local time `now`, which tolerates clocks of nodes and the
+    /// sequencer being slightly off
+    pub fn check_timestamp(
+        &self,
+        prev_timestamp: TimeStamp,
+        now: TimeStamp,
+        max_skew_millis: u64,
+    ) -> Result<(), BlockTimestampError> {
+        if self.timestamp < prev_timestamp {
+            return Err(BlockTimestampError::BeforePrevious {
+                timestamp: self.timestamp,
+                prev_timestamp,
+            });
+        }
+        if self.timestamp > now.saturating_add(max_skew_millis) {
+            return Err(BlockTimestampError::InFuture {
+                timestamp: self.timestamp,
+                now,
+                max_skew_millis,
+            });
+        }
+        Ok(())
+    }
 }
 
 impl From<Block> for HashableBlockData {
@@ -78,13 +269,18 @@ impl From<Block> for HashableBlockData {
             prev_block_hash: value.header.prev_block_hash,
             timestamp: value.header.timestamp,
             transactions: value.body.transactions,
+            params_version: value.header.params_version,
+            dev_proved: value.header.dev_proved,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{block::HashableBlockData, test_utils};
+    use crate::{
+        block::{BlockTimestampError, HashableBlockData, InconsistentBlockError},
+        test_utils,
+    };
 
     #[test]
     fn test_encoding_roundtrip() {
@@ -95,4 +291,134 @@ mod tests {
         let block_from_bytes = borsh::from_slice::<HashableBlockData>(&bytes).unwrap();
         assert_eq!(hashable, block_from_bytes);
     }
+
+    #[test]
+    fn test_params_version_is_encoded_only_after_upgrade() {
+        let transactions = vec![test_utils::produce_dummy_empty_transaction()];
+        let mut block =
+            HashableBlockData::from(test_utils::produce_dummy_block(1, None, transactions));
+        let genesis_params_bytes = borsh::to_vec(&block).unwrap();
+
+        block.params_version = 2;
+        let bytes = borsh::to_vec(&block).unwrap();
+        assert_eq!(bytes[..genesis_params_bytes.len()], genesis_params_bytes);
+        assert_eq!(bytes[genesis_params_bytes.len()..], 2u32.to_le_bytes());
+        assert_eq!(
+            borsh::from_slice::<HashableBlockData>(&bytes).unwrap(),
+            block
+        );
+
+        let mut explicit_zero = genesis_params_bytes.clone();
+        explicit_zero.extend_from_slice(&0u32.to_le_bytes());
+        assert!(borsh::from_slice::<HashableBlockData>(&explicit_zero).is_err());
+        // Truncated version
+        assert!(borsh::from_slice::<HashableBlockData>(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_dev_proved_block_never_has_encoding_of_real_one() {
+        let transactions = vec![test_utils::produce_dummy_empty_transaction()];
+        let mut block =
+            HashableBlockData::from(test_utils::produce_dummy_block(1, None, transactions));
+        let real_bytes = borsh::to_vec(&block).unwrap();
+        let real_hash = block.hash();
+
+        block.dev_proved = true;
+        let bytes = borsh::to_vec(&block).unwrap();
+        assert_eq!(bytes[..real_bytes.len()], real_bytes);
+        assert_eq!(bytes[real_bytes.len()..], [0, 0, 0, 0, 1]);
+        assert_ne!(block.hash(), real_hash);
+        assert_eq!(
+            borsh::from_slice::<HashableBlockData>(&bytes).unwrap(),
+            block
+        );
+
+        block.params_version = 2;
+        let bytes = borsh::to_vec(&block).unwrap();
+        assert_eq!(bytes[real_bytes.len()..], [2, 0, 0, 0, 1]);
+        assert_eq!(
+            borsh::from_slice::<HashableBlockData>(&bytes).unwrap(),
+            block
+        );
+
+        let mut unknown_flag = bytes.clone();
+        *unknown_flag.last_mut().unwrap() = 2;
+        assert!(borsh::from_slice::<HashableBlockData>(&unknown_flag).is_err());
+    }
+
+    /// Hash of block 1 of the canned scenario. It changes only with encoding of blocks,
+    /// transactions or signatures, so update it only for intended consensus changes.
+    #[test]
+    fn test_deterministic_dev_mode_block_1_hash_snapshot() {
+        let (genesis, block_1) = test_utils::deterministic_dev_blocks(42);
+
+        assert_eq!(
+            hex::encode(block_1.header.hash),
+            "66b6a6adc1b3a34769ca6e03cc29457fa1399a489644a9418b3669376ea7ebbd"
+        );
+        assert_eq!(block_1.header.prev_block_hash, genesis.header.hash);
+
+        // The whole run is byte-reproducible, not only the hash
+        let (_, rerun_block_1) = test_utils::deterministic_dev_blocks(42);
+        assert_eq!(
+            borsh::to_vec(&HashableBlockData::from(rerun_block_1.clone())).unwrap(),
+            borsh::to_vec(&HashableBlockData::from(block_1.clone())).unwrap()
+        );
+        assert_eq!(rerun_block_1.header.signature, block_1.header.signature);
+        assert_ne!(
+            test_utils::deterministic_dev_blocks(43).1.header.hash,
+            block_1.header.hash
+        );
+    }
+
+    #[test]
+    fn test_check_timestamp() {
+        let mut header = test_utils::produce_dummy_block(1, None, vec![]).header;
+        header.timestamp = 10_000;
+
+        assert_eq!(header.check_timestamp(10_000, 10_000, 0), Ok(()));
+        // Sequencer clock may be a bit ahead of the local one
+        assert_eq!(header.check_timestamp(0, 5_000, 5_000), Ok(()));
+        assert_eq!(
+            header.check_timestamp(0, 4_999, 5_000),
+            Err(BlockTimestampError::InFuture {
+                timestamp: 10_000,
+                now: 4_999,
+                max_skew_millis: 5_000,
+            })
+        );
+        assert_eq!(
+            header.check_timestamp(10_001, 20_000, 0),
+            Err(BlockTimestampError::BeforePrevious {
+                timestamp: 10_000,
+                prev_timestamp: 10_001,
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_digest() {
+        let transactions = vec![test_utils::produce_dummy_empty_transaction()];
+        let block = HashableBlockData::from(test_utils::produce_dummy_block(2, None, transactions));
+        let advertised = block.digest();
+        assert_eq!(advertised.tx_count, 1);
+        assert_eq!(block.check_digest(&advertised), Ok(()));
+
+        // Contents of the block changed after its digest was advertised
+        let mut changed = HashableBlockData::from(test_utils::produce_dummy_block(2, None, vec![]));
+        changed.timestamp = block.timestamp;
+        let err = changed.check_digest(&advertised).unwrap_err();
+        assert_eq!(
+            err,
+            InconsistentBlockError {
+                block_id: 2,
+                digest: changed.digest(),
+                advertised,
+            }
+        );
+        assert!(
+            err.to_string()
+                .starts_with("Sequencer served inconsistent data: block 2 has hash")
+        );
+    }
 }
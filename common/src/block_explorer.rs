@@ -0,0 +1,261 @@
+//! Denormalized views of chain data, convenient for block explorers.
+
+use nssa::{instruction_decoder::DecoderRegistry, public_transaction::NativeTransfer};
+use nssa_core::{program::ProgramId, units::Amount};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    HashType,
+    block::{Block, BlockId, TimeStamp},
+    json_number::decimal_string,
+    receipt::TransactionReceipt,
+    transaction::{EncodedTransaction, NSSATransaction, TxKind},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpandedBlock {
+    pub block_id: BlockId,
+    /// Hex encoded hash of previous block
+    pub prev_block_hash: String,
+    /// Hex encoded block hash
+    pub hash: String,
+    pub timestamp: TimeStamp,
+    pub transactions: Vec<ExpandedTransaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpandedTransaction {
+    /// Hex encoded transaction hash
+    pub hash: String,
+    pub tx_kind: TxKind,
+    /// Called program, known only for public transactions
+    pub program_id: Option<ProgramId>,
+    /// Public accounts touched by transaction
+    pub account_ids: Vec<String>,
+    /// Decoded native token transfer, if transaction is one
+    pub transfer: Option<TransferView>,
+    /// Instruction of a public transaction, decoded if the program is known, see
+    /// [`DecoderRegistry`]
+    #[serde(default)]
+    pub instruction: Option<serde_json::Value>,
+    /// Fee paid by the deployer, known only for program deployments
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "decimal_string::option"
+    )]
+    pub fee: Option<Amount>,
+    /// Balance changes of touched accounts, known if the sequencer has the receipt of the
+    /// transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<TransactionReceipt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferView {
+    pub sender: String,
+    pub recipient: String,
+    #[serde(with = "decimal_string")]
+    pub amount: Amount,
+}
+
+impl ExpandedTransaction {
+    pub fn from_encoded(
+        tx: &EncodedTransaction,
+        decoders: &DecoderRegistry,
+    ) -> Result<Self, nssa::error::NssaError> {
+        let nssa_tx = NSSATransaction::try_from(tx)?;
+
+        let fee = match &nssa_tx {
+            NSSATransaction::ProgramDeployment(deployment_tx) => Some(deployment_tx.fee().into()),
+            NSSATransaction::Public(_) | NSSATransaction::PrivacyPreserving(_) => None,
+        };
+        let (program_id, transfer, instruction) = match &nssa_tx {
+            NSSATransaction::Public(public_tx) => {
+                let transfer = match NativeTransfer::decode(public_tx.message()) {
+                    Some(NativeTransfer::Transfer {
+                        sender,
+                        recipient,
+                        amount,
+                    }) => Some(TransferView {
+                        sender: sender.to_string(),
+                        recipient: recipient.to_string(),
+                        amount,
+                    }),
+                    Some(NativeTransfer::Initialize { .. }) | None => None,
+                };
+
+                let message = public_tx.message();
+                let instruction = decoders.decode(
+                    &message.program_id(),
+                    message.instruction_data(),
+                    message.account_ids(),
+                );
+
+                (Some(message.program_id()), transfer, Some(instruction))
+            }
+            NSSATransaction::PrivacyPreserving(_) | NSSATransaction::ProgramDeployment(_) => {
+                (None, None, None)
+            }
+        };
+
+        Ok(Self {
+            hash: hex::encode(tx.hash()),
+            tx_kind: tx.tx_kind,
+            program_id,
+            account_ids: nssa_tx
+                .public_account_ids()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            transfer,
+            instruction,
+            fee,
+            receipt: None,
+        })
+    }
+}
+
+impl ExpandedBlock {
+    /// Expanded `block`, which instructions are decoded with `decoders`
+    pub fn from_block(
+        block: &Block,
+        decoders: &DecoderRegistry,
+    ) -> Result<Self, nssa::error::NssaError> {
+        Self::from_block_with_receipts(block, decoders, |_| None)
+    }
+
+    /// Same as [`Self::from_block`], with receipts of transactions, which `receipt_of` returns
+    /// by their hash
+    pub fn from_block_with_receipts(
+        block: &Block,
+        decoders: &DecoderRegistry,
+        receipt_of: impl Fn(&HashType) -> Option<TransactionReceipt>,
+    ) -> Result<Self, nssa::error::NssaError> {
+        Ok(Self {
+            block_id: block.header.block_id,
+            prev_block_hash: hex::encode(block.header.prev_block_hash),
+            hash: hex::encode(block.header.hash),
+            timestamp: block.header.timestamp,
+            transactions: block
+                .body
+                .transactions
+                .iter()
+                .map(|tx| {
+                    Ok(ExpandedTransaction {
+                        receipt: receipt_of(&tx.hash()),
+                        ..ExpandedTransaction::from_encoded(tx, decoders)?
+                    })
+                })
+                .collect::<Result<_, nssa::error::NssaError>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_transaction_native_token_transfer, produce_dummy_block};
+
+    #[test]
+    fn test_expanded_block_matches_manual_decoding() {
+        let signing_key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let tx = create_transaction_native_token_transfer([1; 32], 0, [2; 32], 42, signing_key);
+        let block = produce_dummy_block(1, None, vec![tx.clone()]);
+
+        let expanded = ExpandedBlock::from_block(&block, &DecoderRegistry::default()).unwrap();
+
+        let sender = nssa::AccountId::new([1; 32]).to_string();
+        let recipient = nssa::AccountId::new([2; 32]).to_string();
+
+        assert_eq!(expanded.block_id, 1);
+        assert_eq!(expanded.hash, hex::encode(block.header.hash));
+        assert_eq!(
+            expanded.transactions,
+            vec![ExpandedTransaction {
+                hash: hex::encode(tx.hash()),
+                tx_kind: TxKind::Public,
+                program_id: Some(nssa::program::Program::authenticated_transfer_program().id()),
+                account_ids: vec![sender.clone(), recipient.clone()],
+                transfer: Some(TransferView {
+                    sender: sender.clone(),
+                    recipient: recipient.clone(),
+                    amount: Amount(42),
+                }),
+                instruction: Some(serde_json::json!({
+                    "call": "native_transfer",
+                    "sender": sender,
+                    "recipient": recipient,
+                    "amount": "42",
+                })),
+                fee: None,
+                receipt: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_instructions_of_unknown_programs_are_hex() {
+        let signing_key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let tx = create_transaction_native_token_transfer([1; 32], 0, [2; 32], 42, signing_key);
+        let block = produce_dummy_block(1, None, vec![tx]);
+
+        let expanded = ExpandedBlock::from_block(&block, &DecoderRegistry::empty()).unwrap();
+
+        let instruction = expanded.transactions[0].instruction.as_ref().unwrap();
+        assert_eq!(instruction["call"], "unknown");
+        assert_eq!(instruction["data"], "2a000000000000000000000000000000");
+    }
+
+    #[test]
+    fn test_deployment_fee_is_expanded() {
+        let signing_key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let deployer = nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(&signing_key));
+        let message = nssa::program_deployment_transaction::Message::new(
+            deployer,
+            0,
+            0,
+            Amount(10),
+            vec![0; 2000],
+        );
+        let witness_set =
+            nssa::program_deployment_transaction::WitnessSet::for_message(&message, &signing_key);
+        let deployment_tx = nssa::ProgramDeploymentTransaction::new(message, witness_set);
+        let tx = EncodedTransaction::from(NSSATransaction::ProgramDeployment(deployment_tx));
+
+        let expanded = ExpandedTransaction::from_encoded(&tx, &DecoderRegistry::default()).unwrap();
+
+        assert_eq!(expanded.tx_kind, TxKind::ProgramDeployment);
+        assert_eq!(expanded.account_ids, vec![deployer.to_string()]);
+        assert_eq!(expanded.fee, Some(Amount(2)));
+    }
+
+    #[test]
+    fn test_receipts_are_attached_by_hash() {
+        let signing_key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let tx = create_transaction_native_token_transfer([1; 32], 0, [2; 32], 42, signing_key);
+        let block = produce_dummy_block(1, None, vec![tx.clone()]);
+        let receipt = TransactionReceipt::new(
+            [
+                (nssa::AccountId::new([1; 32]), 100, 58),
+                (nssa::AccountId::new([2; 32]), 0, 42),
+            ]
+            .map(|(account_id, pre, post)| (account_id, Amount(pre), Amount(post))),
+            &nssa::gas::PaidFees::default(),
+        );
+
+        let expanded =
+            ExpandedBlock::from_block_with_receipts(&block, &DecoderRegistry::default(), |hash| {
+                (*hash == tx.hash()).then(|| receipt.clone())
+            })
+            .unwrap();
+        assert_eq!(expanded.transactions[0].receipt, Some(receipt));
+
+        // Transactions without receipts are expanded as before
+        let expanded = ExpandedBlock::from_block(&block, &DecoderRegistry::default()).unwrap();
+        let json = serde_json::to_value(&expanded.transactions[0]).unwrap();
+        assert!(json.get("receipt").is_none());
+        let read: ExpandedTransaction = serde_json::from_value(json).unwrap();
+        assert_eq!(read.receipt, None);
+    }
+}
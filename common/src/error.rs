@@ -1,6 +1,13 @@
 use serde::Deserialize;
 
-use crate::rpc_primitives::errors::RpcError;
+use crate::{
+    rpc_primitives::errors::{
+        BLOCK_MISSING_ERROR_CODE, BLOCK_NOT_YET_PRODUCED_ERROR_CODE, BLOCK_PRUNED_ERROR_CODE,
+        INSUFFICIENT_UNRESERVED_BALANCE_ERROR_CODE, MEMPOOL_CONGESTED_ERROR_CODE,
+        METHOD_NOT_FOUND_ERROR_CODE, RpcError, SERVER_BUSY_ERROR_CODE, STALE_SNAPSHOT_ERROR_CODE,
+    },
+    rpc_types::{ApiVersion, BalanceShortfall},
+};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct SequencerRpcError {
@@ -17,6 +24,128 @@ pub enum SequencerClientError {
     SerdeError(serde_json::Error),
     #[error("Internal error")]
     InternalError(SequencerRpcError),
+    #[error(
+        "Incompatible API version: client {client}, sequencer {server} supporting clients from {server_min_supported}"
+    )]
+    IncompatibleApiVersion {
+        client: ApiVersion,
+        server: ApiVersion,
+        server_min_supported: ApiVersion,
+    },
+}
+
+impl SequencerClientError {
+    /// Sequencer didn't handle the request in time, it may be retried later
+    pub fn is_server_busy(&self) -> bool {
+        matches!(self, Self::InternalError(err) if err.error.code == SERVER_BUSY_ERROR_CODE)
+    }
+
+    /// Sequencer doesn't serve the method, e.g. it's older than the method
+    pub fn is_method_not_found(&self) -> bool {
+        matches!(self, Self::InternalError(err) if err.error.code == METHOD_NOT_FOUND_ERROR_CODE)
+    }
+
+    /// Sequencer is unreachable or overloaded, the request may succeed after reconnection
+    pub fn is_connection_error(&self) -> bool {
+        matches!(self, Self::HTTPError(_)) || self.is_server_busy()
+    }
+
+    /// Delay, after which a submission rejected due to mempool congestion may be retried
+    pub fn mempool_congestion_retry_after(&self) -> Option<std::time::Duration> {
+        let Self::InternalError(err) = self else {
+            return None;
+        };
+        if err.error.code != MEMPOOL_CONGESTED_ERROR_CODE {
+            return None;
+        }
+
+        let retry_after_millis = err
+            .error
+            .data
+            .as_ref()
+            .and_then(|data| data["retry_after_millis"].as_u64())
+            .unwrap_or_default();
+        Some(std::time::Duration::from_millis(retry_after_millis))
+    }
+
+    /// First block, which is still stored by sequencer, if the requested one was pruned
+    pub fn block_pruned_first_unpruned_block_id(&self) -> Option<u64> {
+        let Self::InternalError(err) = self else {
+            return None;
+        };
+        if err.error.code != BLOCK_PRUNED_ERROR_CODE {
+            return None;
+        }
+
+        err.error
+            .data
+            .as_ref()
+            .and_then(|data| data["first_unpruned_block_id"].as_u64())
+    }
+
+    /// Block, which sequencer state was at, if it didn't reach the requested minimum block in time
+    pub fn stale_snapshot_block_id(&self) -> Option<u64> {
+        let Self::InternalError(err) = self else {
+            return None;
+        };
+        if err.error.code != STALE_SNAPSHOT_ERROR_CODE {
+            return None;
+        }
+
+        err.error
+            .data
+            .as_ref()
+            .and_then(|data| data["block_id"].as_u64())
+    }
+
+    /// Reservations of the balance of the sender, if the submission was rejected, because they
+    /// leave too little of it
+    pub fn balance_shortfall(&self) -> Option<BalanceShortfall> {
+        let Self::InternalError(err) = self else {
+            return None;
+        };
+        if err.error.code != INSUFFICIENT_UNRESERVED_BALANCE_ERROR_CODE {
+            return None;
+        }
+
+        serde_json::from_value(err.error.data.clone()?).ok()
+    }
+
+    /// Reason, why the requested block wasn't served, if it was reported by sequencer
+    pub fn block_unavailable(&self) -> Option<BlockUnavailable> {
+        let Self::InternalError(err) = self else {
+            return None;
+        };
+        let data_field = |name: &str| {
+            err.error
+                .data
+                .as_ref()
+                .and_then(|data| data[name].as_u64())
+                .unwrap_or_default()
+        };
+
+        match err.error.code {
+            BLOCK_NOT_YET_PRODUCED_ERROR_CODE => Some(BlockUnavailable::NotYetProduced {
+                tip: data_field("last_block_id"),
+            }),
+            BLOCK_PRUNED_ERROR_CODE => Some(BlockUnavailable::Pruned {
+                earliest_available: data_field("first_unpruned_block_id"),
+            }),
+            BLOCK_MISSING_ERROR_CODE => Some(BlockUnavailable::Missing),
+            _ => None,
+        }
+    }
+}
+
+/// Reason, why sequencer didn't serve a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockUnavailable {
+    /// Block id is after the last block `tip`
+    NotYetProduced { tip: u64 },
+    /// Block body was pruned, blocks from `earliest_available` are still stored
+    Pruned { earliest_available: u64 },
+    /// Block should be stored, but sequencer lost it
+    Missing,
 }
 
 impl From<reqwest::Error> for SequencerClientError {
@@ -49,4 +178,6 @@ pub enum ExecutionFailureKind {
     SequencerClientError(#[from] SequencerClientError),
     #[error("Can not pay for operation")]
     InsufficientFundsError,
+    #[error("Transfer breaks rules of native transfers: {0}")]
+    InvalidTransfer(nssa::error::NssaError),
 }
@@ -0,0 +1,128 @@
+//! Faults, which [`MockChain`] injects while serving its clients.
+//!
+//! Clients misbehave under failures of the sequencer, like dropped requests, restarts or stalled
+//! storage, which happy path tests never exercise. Scenario tests plan such failures ahead with a
+//! [`FaultPlan`] and compare the outcome with a fault-free run. Only the mock has hooks for the
+//! faults, the sequencer and its client have none.
+//!
+//! [`MockChain`]: crate::mock_chain::MockChain
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use nssa::AccountId;
+
+#[derive(Debug, Clone, Default)]
+pub struct FaultPlan {
+    /// Numbers of upcoming calls to fail by method
+    dropped_calls: HashMap<String, usize>,
+    /// Delays of every call by method
+    delayed_calls: HashMap<String, Duration>,
+    /// Number of upcoming calls of any method to fail
+    blackout_calls: usize,
+    /// Block, right after producing which the sequencer restarts, and number of calls it misses
+    restart: Option<(u64, usize)>,
+    /// Blocks, which next fetch serves corrupted bytes
+    corrupted_block_fetches: HashSet<u64>,
+    /// Block, which storage write pauses at
+    storage_pause: Option<u64>,
+    /// Accounts, which proven reads serve a balance not matching the proof for
+    tampered_proven_balances: HashSet<AccountId>,
+}
+
+impl FaultPlan {
+    /// Next `times` calls of `method` fail, as if the sequencer was unreachable
+    pub fn drop_calls(mut self, method: &str, times: usize) -> Self {
+        *self.dropped_calls.entry(method.to_string()).or_default() += times;
+        self
+    }
+
+    /// Every call of `method` is served after `delay`
+    pub fn delay_calls(mut self, method: &str, delay: Duration) -> Self {
+        self.delayed_calls.insert(method.to_string(), delay);
+        self
+    }
+
+    /// Next `calls` calls of any method fail, as if the sequencer was unreachable
+    pub fn blackout(mut self, calls: usize) -> Self {
+        self.blackout_calls += calls;
+        self
+    }
+
+    /// Right after producing block `block_id` the sequencer restarts, failing the next
+    /// `missed_calls` calls. Pending transactions survive the restart, as they do with the
+    /// mempool write-ahead log of the sequencer.
+    pub fn restart_at_block(mut self, block_id: u64, missed_calls: usize) -> Self {
+        self.restart = Some((block_id, missed_calls));
+        self
+    }
+
+    /// Next fetch of block `block_id` serves corrupted bytes, but the digest of the stored block
+    pub fn corrupt_block_fetch(mut self, block_id: u64) -> Self {
+        self.corrupted_block_fetches.insert(block_id);
+        self
+    }
+
+    /// Storage write of block `block_id` pauses after its transactions are applied. Until it's
+    /// resumed, the chain is served as it was before the block.
+    pub fn pause_storage_at_block(mut self, block_id: u64) -> Self {
+        self.storage_pause = Some(block_id);
+        self
+    }
+
+    /// Every `get_account_with_proof` of `account_id` serves a higher balance than the proven
+    /// one, as a dishonest sequencer would
+    pub fn tamper_proven_balance(mut self, account_id: AccountId) -> Self {
+        self.tampered_proven_balances.insert(account_id);
+        self
+    }
+
+    pub(crate) fn call_delay(&self, method: &str) -> Duration {
+        self.delayed_calls.get(method).copied().unwrap_or_default()
+    }
+
+    /// Whether the call of `method` fails, using up the fault
+    pub(crate) fn take_dropped_call(&mut self, method: &str) -> bool {
+        if self.blackout_calls > 0 {
+            self.blackout_calls -= 1;
+            return true;
+        }
+
+        match self.dropped_calls.get_mut(method) {
+            Some(times) if *times > 0 => {
+                *times -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn on_block_produced(&mut self, block_id: u64) {
+        if let Some((restart_block_id, missed_calls)) = self.restart
+            && restart_block_id == block_id
+        {
+            self.restart = None;
+            self.blackout_calls += missed_calls;
+        }
+    }
+
+    /// Whether the fetch of block `block_id` serves corrupted bytes, using up the fault
+    pub(crate) fn take_block_fetch_corruption(&mut self, block_id: u64) -> bool {
+        self.corrupted_block_fetches.remove(&block_id)
+    }
+
+    pub(crate) fn is_proven_balance_tampered(&self, account_id: &AccountId) -> bool {
+        self.tampered_proven_balances.contains(account_id)
+    }
+
+    /// Whether the storage write of block `block_id` pauses, using up the fault
+    pub(crate) fn take_storage_pause(&mut self, block_id: u64) -> bool {
+        if self.storage_pause != Some(block_id) {
+            return false;
+        }
+        self.storage_pause = None;
+        true
+    }
+}
@@ -0,0 +1,254 @@
+//! JSON representations of integers, which JavaScript clients read without loss of precision.
+//!
+//! Most JSON parsers read numbers as doubles, so integers above [`MAX_SAFE_INTEGER`] are
+//! silently rounded. Balances and amounts are always written as decimal strings, while nonces are
+//! written as numbers unless they are too large.
+
+use std::{fmt::Display, marker::PhantomData, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+/// Largest integer exactly representable as a double, `2^53 - 1`
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Integer written as a decimal string, used for `u128` balances and amounts
+///
+/// Numbers are accepted on input as well.
+pub mod decimal_string {
+    use super::*;
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr<Err: Display> + From<u64>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DecimalStringVisitor(PhantomData))
+    }
+
+    /// Same as [`decimal_string`](super::decimal_string) for an optional value, which is `null`
+    /// if absent
+    pub mod option {
+        use super::super::*;
+
+        pub fn serialize<T: Display, S: Serializer>(
+            value: &Option<T>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => serializer.collect_str(value),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            T: FromStr<Err: Display> + From<u64>,
+            D: Deserializer<'de>,
+        {
+            let value = Option::<DecimalString<T>>::deserialize(deserializer)?;
+            Ok(value.map(|value| value.0))
+        }
+    }
+}
+
+/// `u64` written as a number up to [`MAX_SAFE_INTEGER`] and as a decimal string above it, used
+/// for nonces
+///
+/// Both forms are accepted on input regardless of the value.
+pub mod safe_integer {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        SafeInteger(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        SafeInteger::deserialize(deserializer).map(|value| value.0)
+    }
+
+    /// Same as [`safe_integer`](super::safe_integer) for every element of a vector
+    pub mod vec {
+        use super::super::*;
+
+        pub fn serialize<S: Serializer>(values: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(values.iter().copied().map(SafeInteger))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<u64>, D::Error> {
+            let values = Vec::<SafeInteger>::deserialize(deserializer)?;
+            Ok(values.into_iter().map(|value| value.0).collect())
+        }
+    }
+}
+
+struct DecimalStringVisitor<T>(PhantomData<T>);
+
+impl<T> de::Visitor<'_> for DecimalStringVisitor<T>
+where
+    T: FromStr<Err: Display> + From<u64>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a non-negative integer or a decimal string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+        Ok(T::from(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        // `FromStr` of integers accepts a leading plus sign, which isn't a decimal string
+        if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(E::invalid_value(de::Unexpected::Str(value), &self));
+        }
+        value.parse().map_err(E::custom)
+    }
+}
+
+struct DecimalString<T>(T);
+
+impl<'de, T> Deserialize<'de> for DecimalString<T>
+where
+    T: FromStr<Err: Display> + From<u64>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer
+            .deserialize_any(DecimalStringVisitor(PhantomData))
+            .map(Self)
+    }
+}
+
+struct SafeInteger(u64);
+
+impl Serialize for SafeInteger {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0 <= MAX_SAFE_INTEGER {
+            serializer.serialize_u64(self.0)
+        } else {
+            serializer.collect_str(&self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SafeInteger {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer
+            .deserialize_any(DecimalStringVisitor(PhantomData))
+            .map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Fields {
+        #[serde(with = "decimal_string")]
+        balance: u128,
+        #[serde(with = "safe_integer")]
+        nonce: u64,
+        #[serde(with = "safe_integer::vec")]
+        nonces: Vec<u64>,
+    }
+
+    #[test]
+    fn test_small_values() {
+        let fields = Fields {
+            balance: 100,
+            nonce: 5,
+            nonces: vec![0, MAX_SAFE_INTEGER],
+        };
+        let value = json!({ "balance": "100", "nonce": 5, "nonces": [0, MAX_SAFE_INTEGER] });
+
+        assert_eq!(serde_json::to_value(&fields).unwrap(), value);
+        assert_eq!(serde_json::from_value::<Fields>(value).unwrap(), fields);
+    }
+
+    #[test]
+    fn test_values_above_max_safe_integer() {
+        let fields = Fields {
+            balance: u128::MAX,
+            nonce: MAX_SAFE_INTEGER + 1,
+            nonces: vec![5, u64::MAX],
+        };
+        let value = json!({
+            "balance": "340282366920938463463374607431768211455",
+            "nonce": "9007199254740992",
+            "nonces": [5, "18446744073709551615"]
+        });
+
+        assert_eq!(serde_json::to_value(&fields).unwrap(), value);
+        assert_eq!(serde_json::from_value::<Fields>(value).unwrap(), fields);
+    }
+
+    #[test]
+    fn test_both_forms_are_accepted() {
+        let value = json!({ "balance": 100, "nonce": "5", "nonces": ["0", 1] });
+
+        assert_eq!(
+            serde_json::from_value::<Fields>(value).unwrap(),
+            Fields {
+                balance: 100,
+                nonce: 5,
+                nonces: vec![0, 1],
+            }
+        );
+    }
+
+    #[test]
+    fn test_malformed_values_are_rejected() {
+        for value in [
+            json!({ "balance": "-1", "nonce": 0, "nonces": [] }),
+            json!({ "balance": "+1", "nonce": 0, "nonces": [] }),
+            json!({ "balance": "", "nonce": 0, "nonces": [] }),
+            json!({ "balance": 1.5, "nonce": 0, "nonces": [] }),
+            json!({ "balance": "1", "nonce": -1, "nonces": [] }),
+            json!({ "balance": "1", "nonce": "18446744073709551616", "nonces": [] }),
+        ] {
+            assert!(serde_json::from_value::<Fields>(value).is_err());
+        }
+    }
+
+    #[test]
+    fn test_optional_decimal_string() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Fee {
+            #[serde(default, with = "decimal_string::option")]
+            fee: Option<u128>,
+        }
+
+        assert_eq!(
+            serde_json::to_value(Fee {
+                fee: Some(u128::MAX)
+            })
+            .unwrap(),
+            json!({ "fee": "340282366920938463463374607431768211455" })
+        );
+        assert_eq!(
+            serde_json::to_value(Fee { fee: None }).unwrap(),
+            json!({ "fee": null })
+        );
+        assert_eq!(
+            serde_json::from_value::<Fee>(json!({ "fee": 5 })).unwrap(),
+            Fee { fee: Some(5) }
+        );
+        assert_eq!(
+            serde_json::from_value::<Fee>(json!({})).unwrap(),
+            Fee { fee: None }
+        );
+        assert!(serde_json::from_value::<Fee>(json!({ "fee": "-5" })).is_err());
+    }
+}
@@ -1,12 +1,24 @@
+pub mod address_index;
 pub mod block;
+pub mod block_explorer;
 pub mod error;
+pub mod json_number;
+pub mod receipt;
 pub mod rpc_primitives;
+pub mod rpc_types;
+pub mod sequencer_api;
 pub mod sequencer_client;
+pub mod signed_header;
+pub mod soft_confirmation;
 pub mod transaction;
 
 // Module for tests utility functions
 // TODO: Compile only for tests
 pub mod test_utils;
+// In-memory sequencer for tests of its clients
+pub mod mock_chain;
+// Faults injected by the in-memory sequencer
+pub mod fault_plan;
 pub type HashType = [u8; 32];
 
 pub const PINATA_BASE58: &str = "EfQhKQAkX2FJiwNii2WFQsGndjvF1Mzd7RuVe7QdPLw7";
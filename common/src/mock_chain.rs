@@ -0,0 +1,1478 @@
+// Helpers fail with the same error, as `SequencerApi` methods do
+#![allow(clippy::result_large_err)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use nssa::{
+    Account, AccountId, ProgramDeploymentTransaction,
+    gas::PaidFees,
+    program::Program,
+    public_transaction::{NativeTransfer, PublicTransaction},
+};
+use nssa_core::{program::ProgramId, units::Amount};
+
+use crate::{
+    address_index::{
+        AddressTxCursor, AddressTxFilter, AddressTxIndex, DEFAULT_ADDRESS_TXS_PAGE_SIZE, TxPosition,
+    },
+    block::{Block, HashableBlockData, ParamsVersion},
+    error::{SequencerClientError, SequencerRpcError},
+    fault_plan::FaultPlan,
+    receipt::TransactionReceipt,
+    rpc_primitives::errors::RpcError,
+    rpc_types::{
+        API_VERSION, BlockDigest, CommitmentAnchor, GetAccountBalanceResponse, GetAccountResponse,
+        GetAccountWithProofResponse, GetAccountsNoncesResponse, GetAccountsResponse,
+        GetApiVersionResponse, GetBlockDataResponse, GetBlockHeadersResponse,
+        GetBlockRangeDataResponse, GetChainInfoResponse, GetLastBlockResponse,
+        GetLatestAnchorsResponse, GetPendingTransactionsResponse, GetPendingTransfersResponse,
+        GetPriorityFeesResponse, GetSequencerInfoResponse, GetSyncStatusResponse,
+        GetTransactionByHashResponse, GetTransactionStatusResponse,
+        GetTransactionsByAddressResponse, MAX_BLOCK_HEADERS_PER_REQUEST, MIN_SUPPORTED_API_VERSION,
+        PendingTransactionSummary, PendingTransfer, PriorityFeeDistribution, ReadContext,
+        SendTxResponse, SequencerFeature, SyncState, SyncStatus, TransactionStatus,
+        ValidateTransactionResponse,
+    },
+    sequencer_api::SequencerApi,
+    signed_header::SignedBlockHeader,
+    test_utils::sequencer_sign_key_for_testing,
+    transaction::{EncodedTransaction, NSSATransaction, TxKind},
+};
+
+/// Id of the genesis block of [`MockChain`]
+pub const MOCK_GENESIS_ID: u64 = 1;
+
+/// Commitment set digest of [`MockChain`], which keeps no commitments
+pub const MOCK_COMMITMENT_SET_DIGEST: nssa_core::CommitmentSetDigest = [0; 32];
+
+/// In-memory chain, which serves [`SequencerApi`] without a sequencer, so clients can be tested
+/// deterministically and without network
+///
+/// Submitted transactions wait in mempool until [`MockChain::produce_block`] is called, or are
+/// included right away with [`MockChain::with_auto_block_production`]. Only native transfers
+/// change balances, they follow the rules of [`NativeTransfer::check`]. Other public transactions
+/// just bump nonces of their signers. Program deployments charge their fees and bump nonces of
+/// their deployers, but programs aren't deployed. Privacy preserving transactions are included
+/// without changing the state, as the mock neither proves nor executes programs. Receipts of the
+/// other ones are kept, no fees are paid besides deployment fees.
+///
+/// Account proofs are served against a state root of the account tree and of an empty commitment
+/// set digest, see [`MOCK_COMMITMENT_SET_DIGEST`].
+///
+/// Failures of the sequencer are injected with [`MockChain::with_fault_plan`].
+pub struct MockChain {
+    state: Mutex<MockChainState>,
+}
+
+struct MockChainState {
+    accounts: HashMap<AccountId, Account>,
+    blocks: Vec<Block>,
+    mempool: Vec<EncodedTransaction>,
+    /// Including block ids by hex encoded transaction hashes
+    included: HashMap<String, u64>,
+    /// Receipts of included transactions by hex encoded hashes, privacy preserving ones have none
+    receipts: HashMap<String, TransactionReceipt>,
+    /// Reasons of rejection by hex encoded transaction hashes
+    dropped: HashMap<String, String>,
+    address_txs: AddressTxIndex,
+    /// Ids of produced blocks, which are not served, as if storage lost them
+    lost_blocks: HashSet<u64>,
+    auto_produce_blocks: bool,
+    unavailable: bool,
+    /// Whether `get_accounts` is served and advertised
+    batch_accounts: bool,
+    /// Whether `get_pending_transfers` is served and advertised
+    pending_transfers: bool,
+    /// Whether `get_pending_transactions` is served and advertised
+    pending_transactions: bool,
+    /// Delay of every account query, as if it went over network
+    account_query_latency: Duration,
+    /// Accounts, which queries fail
+    failing_accounts: HashSet<AccountId>,
+    faults: FaultPlan,
+    paused_write: Option<PausedWrite>,
+    /// Version of chain parameters, which the next block is built with
+    params_version: ParamsVersion,
+    /// Rate of storage deposits, which sets the minimal amount of transfers creating accounts
+    storage_deposit_per_byte: Amount,
+}
+
+/// Everything, which producing a block writes
+struct ChainData {
+    accounts: HashMap<AccountId, Account>,
+    blocks: Vec<Block>,
+    mempool: Vec<EncodedTransaction>,
+    included: HashMap<String, u64>,
+    receipts: HashMap<String, TransactionReceipt>,
+    dropped: HashMap<String, String>,
+    address_txs: AddressTxIndex,
+}
+
+/// Block production, which storage write is paused
+struct PausedWrite {
+    /// Chain after the write
+    written: ChainData,
+    /// Number of pending transactions before the write, the later ones arrived during the pause
+    mempool_len: usize,
+}
+
+impl MockChain {
+    /// Chain, where `initial_data` accounts are owned by the authenticated transfer program and
+    /// have their balances
+    pub fn new(initial_data: &[(AccountId, Amount)]) -> Self {
+        let accounts = initial_data
+            .iter()
+            .map(|(account_id, balance)| {
+                let account = Account {
+                    program_owner: Program::authenticated_transfer_program().id(),
+                    balance: *balance,
+                    ..Account::default()
+                };
+                (*account_id, account)
+            })
+            .collect();
+        let genesis = HashableBlockData {
+            block_id: MOCK_GENESIS_ID,
+            prev_block_hash: [0; 32],
+            timestamp: 0,
+            transactions: vec![],
+            params_version: 0,
+            dev_proved: false,
+        };
+
+        Self {
+            state: Mutex::new(MockChainState {
+                accounts,
+                blocks: vec![genesis.into_block(&sequencer_sign_key_for_testing())],
+                mempool: vec![],
+                included: HashMap::new(),
+                receipts: HashMap::new(),
+                dropped: HashMap::new(),
+                address_txs: AddressTxIndex::default(),
+                lost_blocks: HashSet::new(),
+                auto_produce_blocks: false,
+                unavailable: false,
+                batch_accounts: false,
+                pending_transfers: false,
+                pending_transactions: false,
+                account_query_latency: Duration::ZERO,
+                failing_accounts: HashSet::new(),
+                faults: FaultPlan::default(),
+                paused_write: None,
+                params_version: 0,
+                storage_deposit_per_byte: Amount::ZERO,
+            }),
+        }
+    }
+
+    /// Every submitted transaction is included into its own block right away
+    pub fn with_auto_block_production(self) -> Self {
+        self.lock().auto_produce_blocks = true;
+        self
+    }
+
+    /// Many accounts are served at once, see [`SequencerFeature::BatchAccounts`]
+    pub fn with_batch_accounts(self) -> Self {
+        self.lock().batch_accounts = true;
+        self
+    }
+
+    /// Pending native transfers are served by account, see
+    /// [`SequencerFeature::PendingTransfers`]
+    pub fn with_pending_transfers(self) -> Self {
+        self.lock().pending_transfers = true;
+        self
+    }
+
+    /// Summaries of pending transactions are served, see
+    /// [`SequencerFeature::PendingTransactions`]
+    pub fn with_pending_transactions(self) -> Self {
+        self.lock().pending_transactions = true;
+        self
+    }
+
+    /// Requests are served with faults of `plan`
+    pub fn with_fault_plan(self, plan: FaultPlan) -> Self {
+        self.lock().faults = plan;
+        self
+    }
+
+    /// Transfers creating accounts move at least the minimum of `storage_deposit_per_byte`, see
+    /// [`nssa::public_transaction::min_account_creation_amount`]
+    pub fn with_storage_deposit_per_byte(self, storage_deposit_per_byte: Amount) -> Self {
+        self.lock().storage_deposit_per_byte = storage_deposit_per_byte;
+        self
+    }
+
+    /// Every `get_account` and `get_accounts` call takes `latency` before it's served
+    pub fn with_account_query_latency(self, latency: Duration) -> Self {
+        self.lock().account_query_latency = latency;
+        self
+    }
+
+    /// Queries of `account_id` fail, as well as batches including it
+    pub fn fail_account_queries(&self, account_id: AccountId) {
+        self.lock().failing_accounts.insert(account_id);
+    }
+
+    /// While `unavailable`, all requests fail as if the sequencer was busy
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.lock().unavailable = unavailable;
+    }
+
+    /// Includes all pending transactions into a new block and returns its id
+    ///
+    /// Transactions, which can't be applied, are dropped. While storage is paused, nothing is
+    /// written and id of the last written block is returned.
+    pub fn produce_block(&self) -> u64 {
+        self.lock().produce_block()
+    }
+
+    /// Completes the storage write paused by [`FaultPlan::pause_storage_at_block`], returns id of
+    /// the written block or `None` if storage isn't paused
+    pub fn resume_storage(&self) -> Option<u64> {
+        self.lock().resume_storage()
+    }
+
+    pub fn account(&self, account_id: &AccountId) -> Account {
+        self.lock().account(account_id)
+    }
+
+    pub fn last_block_id(&self) -> u64 {
+        self.lock().last_block_id()
+    }
+
+    /// Number of pending transactions
+    pub fn mempool_len(&self) -> usize {
+        self.lock().mempool.len()
+    }
+
+    /// Changes contents of the stored block, but not its hash, as a storage bug would, so the
+    /// block no longer matches the digest advertised for it
+    pub fn corrupt_block(&self, block_id: u64) {
+        let mut state = self.lock();
+        let index = usize::try_from(block_id - MOCK_GENESIS_ID).unwrap();
+        let block = &mut state.blocks[index];
+        block.header.timestamp += 1;
+        block.body.transactions.pop();
+    }
+
+    /// Blocks produced from now on are built with the next version of chain parameters
+    pub fn bump_params_version(&self) {
+        self.lock().params_version += 1;
+    }
+
+    /// Stops serving the produced block, as if storage lost it, leaving a hole in the chain
+    pub fn lose_block(&self, block_id: u64) {
+        self.lock().lost_blocks.insert(block_id);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockChainState> {
+        self.state.lock().unwrap()
+    }
+
+    /// State of the chain to serve `method` by, after its planned delay, if it's available and
+    /// the call isn't planned to fail
+    async fn state(
+        &self,
+        method: &str,
+    ) -> Result<MutexGuard<'_, MockChainState>, SequencerClientError> {
+        let delay = self.lock().faults.call_delay(method);
+        tokio::time::sleep(delay).await;
+
+        let mut state = self.lock();
+        if state.unavailable || state.faults.take_dropped_call(method) {
+            return Err(rpc_error(RpcError::server_busy(
+                method,
+                Duration::from_secs(1),
+            )));
+        }
+        Ok(state)
+    }
+
+    async fn ensure_available(&self, method: &str) -> Result<(), SequencerClientError> {
+        self.state(method).await.map(drop)
+    }
+
+    /// State of the chain to serve an account query by, after its latency has passed
+    async fn account_query_state(
+        &self,
+        method: &str,
+    ) -> Result<MutexGuard<'_, MockChainState>, SequencerClientError> {
+        let latency = self.lock().account_query_latency;
+        tokio::time::sleep(latency).await;
+        self.state(method).await
+    }
+}
+
+impl MockChainState {
+    fn account(&self, account_id: &AccountId) -> Account {
+        self.accounts.get(account_id).cloned().unwrap_or_default()
+    }
+
+    /// Account, unless its queries are set to fail
+    fn queried_account(&self, account_id: &str) -> Result<Account, SequencerClientError> {
+        let account_id = parse_account_id(account_id)?;
+        if self.failing_accounts.contains(&account_id) {
+            return Err(rpc_error(RpcError::new_internal_error(
+                None,
+                &format!("Failed to read account {account_id}"),
+            )));
+        }
+        Ok(self.account(&account_id))
+    }
+
+    fn last_block_id(&self) -> u64 {
+        self.blocks.last().map_or(0, |block| block.header.block_id)
+    }
+
+    /// Context of reads at the last block, the mock has no state root, so hash of the last block
+    /// stands for it. Blocks are produced only on demand, so reads after the last block fail
+    /// right away instead of waiting for it
+    fn read_context(&self, min_block_id: Option<u64>) -> Result<ReadContext, SequencerClientError> {
+        let block_id = self.last_block_id();
+        if let Some(min_block_id) = min_block_id
+            && min_block_id > block_id
+        {
+            return Err(rpc_error(RpcError::stale_snapshot(min_block_id, block_id)));
+        }
+
+        Ok(ReadContext {
+            block_id,
+            state_root: self
+                .blocks
+                .last()
+                .map(|block| hex::encode(block.header.hash))
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Tree of the stored public accounts, default ones are left out as the sequencer does
+    fn account_tree(&self) -> nssa::account_tree::AccountTree {
+        self.accounts
+            .iter()
+            .filter(|(_, account)| **account != Account::default())
+            .collect()
+    }
+
+    /// Header of stored block `block_id` signed by the sequencer, the last one with the state root
+    fn signed_header(&self, block_id: u64) -> Result<SignedBlockHeader, SequencerClientError> {
+        let last_block_id = self.last_block_id();
+        if block_id > last_block_id {
+            return Err(rpc_error(RpcError::block_not_yet_produced(
+                block_id,
+                last_block_id,
+            )));
+        }
+        let header = &self
+            .block(block_id)
+            .ok_or_else(|| rpc_error(RpcError::block_missing(block_id)))?
+            .header;
+        let state_root = (block_id == last_block_id).then(|| {
+            nssa::V02State::state_root_of(&self.account_tree().root(), &MOCK_COMMITMENT_SET_DIGEST)
+        });
+
+        Ok(SignedBlockHeader::sign(
+            header.block_id,
+            header.prev_block_hash,
+            header.hash,
+            header.timestamp,
+            state_root,
+            &sequencer_sign_key_for_testing(),
+        ))
+    }
+
+    fn block(&self, block_id: u64) -> Option<&Block> {
+        let index = block_id.checked_sub(MOCK_GENESIS_ID)?;
+        self.blocks.get(usize::try_from(index).ok()?)
+    }
+
+    /// Served block with the digest advertised for it, which hash is the stored one
+    fn served_block(
+        &self,
+        block_id: u64,
+    ) -> Result<(HashableBlockData, BlockDigest), SequencerClientError> {
+        let last_block_id = self.last_block_id();
+        if block_id > last_block_id {
+            return Err(rpc_error(RpcError::block_not_yet_produced(
+                block_id,
+                last_block_id,
+            )));
+        }
+        if self.lost_blocks.contains(&block_id) {
+            return Err(rpc_error(RpcError::block_missing(block_id)));
+        }
+        let block = self.block(block_id).cloned().ok_or_else(|| {
+            rpc_error(RpcError::new_internal_error(
+                None,
+                &format!("Block {block_id} precedes the genesis"),
+            ))
+        })?;
+
+        let digest = BlockDigest::new(&block.header.hash, block.body.transactions.len());
+        Ok((HashableBlockData::from(block), digest))
+    }
+
+    /// Borsh serialized served block with its digest, bytes are corrupted if it's planned
+    fn fetched_block(
+        &mut self,
+        block_id: u64,
+    ) -> Result<(Vec<u8>, BlockDigest), SequencerClientError> {
+        let (block, digest) = self.served_block(block_id)?;
+        let mut bytes = borsh::to_vec(&block).unwrap();
+        if self.faults.take_block_fetch_corruption(block_id) {
+            bytes.iter_mut().for_each(|byte| *byte ^= 0xff);
+        }
+        Ok((bytes, digest))
+    }
+
+    fn produce_block(&mut self) -> u64 {
+        // Nothing is written until the paused write is resumed
+        if self.paused_write.is_some() {
+            return self.last_block_id();
+        }
+
+        let block_id = self.last_block_id() + 1;
+        let before_write = self
+            .faults
+            .take_storage_pause(block_id)
+            .then(|| self.chain_data());
+        self.write_block(block_id);
+
+        match before_write {
+            Some(before_write) => {
+                let mempool_len = before_write.mempool.len();
+                let written = self.replace_chain_data(before_write);
+                self.paused_write = Some(PausedWrite {
+                    written,
+                    mempool_len,
+                });
+                self.last_block_id()
+            }
+            None => {
+                self.faults.on_block_produced(block_id);
+                block_id
+            }
+        }
+    }
+
+    fn resume_storage(&mut self) -> Option<u64> {
+        let PausedWrite {
+            mut written,
+            mempool_len,
+        } = self.paused_write.take()?;
+        written.mempool.extend(self.mempool.drain(mempool_len..));
+        self.replace_chain_data(written);
+
+        let block_id = self.last_block_id();
+        self.faults.on_block_produced(block_id);
+        Some(block_id)
+    }
+
+    fn chain_data(&self) -> ChainData {
+        ChainData {
+            accounts: self.accounts.clone(),
+            blocks: self.blocks.clone(),
+            mempool: self.mempool.clone(),
+            included: self.included.clone(),
+            receipts: self.receipts.clone(),
+            dropped: self.dropped.clone(),
+            address_txs: self.address_txs.clone(),
+        }
+    }
+
+    /// Replaces the chain with `data`, returns the replaced one
+    fn replace_chain_data(&mut self, data: ChainData) -> ChainData {
+        ChainData {
+            accounts: std::mem::replace(&mut self.accounts, data.accounts),
+            blocks: std::mem::replace(&mut self.blocks, data.blocks),
+            mempool: std::mem::replace(&mut self.mempool, data.mempool),
+            included: std::mem::replace(&mut self.included, data.included),
+            receipts: std::mem::replace(&mut self.receipts, data.receipts),
+            dropped: std::mem::replace(&mut self.dropped, data.dropped),
+            address_txs: std::mem::replace(&mut self.address_txs, data.address_txs),
+        }
+    }
+
+    /// Includes all pending transactions into block `block_id`
+    fn write_block(&mut self, block_id: u64) {
+        let mut transactions = vec![];
+        for tx in std::mem::take(&mut self.mempool) {
+            let hash = hex::encode(tx.hash());
+            match self.apply_transaction(&tx) {
+                Ok(receipt) => {
+                    if let Some(receipt) = receipt {
+                        self.receipts.insert(hash.clone(), receipt);
+                    }
+                    self.included.insert(hash, block_id);
+                    let position = TxPosition {
+                        block_id,
+                        index: transactions.len() as u32,
+                    };
+                    self.address_txs.record(&tx, position);
+                    transactions.push(tx);
+                }
+                Err(reason) => {
+                    self.dropped.insert(hash, reason);
+                }
+            }
+        }
+
+        let prev_block = self.blocks.last().expect("Genesis is always present");
+        let block_data = HashableBlockData {
+            block_id,
+            prev_block_hash: prev_block.header.hash,
+            timestamp: prev_block.header.timestamp + 1,
+            transactions,
+            params_version: self.params_version,
+            dev_proved: false,
+        };
+        self.blocks
+            .push(block_data.into_block(&sequencer_sign_key_for_testing()));
+    }
+
+    /// Applies `tx`, returning its receipt unless it's privacy preserving
+    fn apply_transaction(
+        &mut self,
+        tx: &EncodedTransaction,
+    ) -> Result<Option<TransactionReceipt>, String> {
+        match NSSATransaction::try_from(tx).map_err(|err| err.to_string())? {
+            NSSATransaction::Public(tx) => self.apply_public_transaction(&tx).map(Some),
+            NSSATransaction::ProgramDeployment(tx) => self.apply_program_deployment(&tx).map(Some),
+            NSSATransaction::PrivacyPreserving(_) => Ok(None),
+        }
+    }
+
+    /// Deployment fee is taken from the deployer, there is no fee recipient
+    fn apply_program_deployment(
+        &mut self,
+        tx: &ProgramDeploymentTransaction,
+    ) -> Result<TransactionReceipt, String> {
+        let message = tx.message();
+        if !tx.witness_set().is_valid_for(message) {
+            return Err("Deployment must be signed by its deployer only".to_string());
+        }
+        let deployer = message.deployer();
+        let account = self.account(&deployer);
+        if message.nonce() != account.nonce {
+            return Err(format!(
+                "Invalid nonce {} of account {deployer}, expected {}",
+                message.nonce(),
+                account.nonce
+            ));
+        }
+        let fee = Amount::from(tx.fee());
+        if fee > message.max_fee() || fee > account.balance {
+            return Err(format!(
+                "Deployment fee {fee} isn't covered by max fee {} or balance {}",
+                message.max_fee(),
+                account.balance
+            ));
+        }
+
+        let pre_balance = account.balance;
+        let account = self.accounts.entry(deployer).or_default();
+        account.balance = account.balance.saturating_sub(fee);
+        account.increment_nonce();
+
+        let fees = PaidFees {
+            payer: Some(deployer),
+            deployment_fee: tx.fee(),
+            ..PaidFees::default()
+        };
+        Ok(TransactionReceipt::new(
+            [(deployer, pre_balance, account.balance)],
+            &fees,
+        ))
+    }
+
+    /// Transactions are executed without fees
+    fn apply_public_transaction(
+        &mut self,
+        tx: &PublicTransaction,
+    ) -> Result<TransactionReceipt, String> {
+        let message = tx.message();
+        let signers = tx.signer_account_ids();
+        if signers.len() != message.nonces().len() {
+            return Err("Number of signatures doesn't match number of nonces".to_string());
+        }
+        for (signer, nonce) in signers.iter().zip(message.nonces()) {
+            let expected = self.account(signer).nonce;
+            if *nonce != expected {
+                return Err(format!(
+                    "Invalid nonce {nonce} of account {signer}, expected {expected}"
+                ));
+            }
+        }
+
+        let transfer = NativeTransfer::decode(message);
+        if let Some(transfer) = &transfer {
+            let recipient = transfer
+                .recipient()
+                .map(|recipient| self.account(&recipient))
+                .unwrap_or_default();
+            transfer
+                .check(&recipient, self.storage_deposit_per_byte)
+                .map_err(|err| err.to_string())?;
+        }
+        let mut touched_account_ids = signers.clone();
+        if let Some(NativeTransfer::Transfer { recipient, .. }) = &transfer {
+            touched_account_ids.push(*recipient);
+        }
+        let pre_balances: Vec<_> = touched_account_ids
+            .iter()
+            .map(|account_id| self.account(account_id).balance)
+            .collect();
+
+        match transfer {
+            Some(NativeTransfer::Transfer {
+                sender,
+                recipient,
+                amount,
+            }) => {
+                if !signers.contains(&sender) {
+                    return Err(format!("Transfer isn't signed by sender {sender}"));
+                }
+                let sender_balance = self.account(&sender).balance;
+                if sender_balance < amount {
+                    return Err(format!(
+                        "Balance {sender_balance} of account {sender} is less than {amount}"
+                    ));
+                }
+                let sender_account = self.accounts.entry(sender).or_default();
+                sender_account.balance = sender_account.balance.saturating_sub(amount);
+                let recipient_account = self.claim(recipient);
+                recipient_account.balance = recipient_account.balance.saturating_add(amount);
+            }
+            Some(NativeTransfer::Initialize { account_id }) => {
+                self.claim(account_id);
+            }
+            None => {}
+        }
+
+        for signer in signers {
+            self.accounts.entry(signer).or_default().increment_nonce();
+        }
+
+        let balances = touched_account_ids
+            .into_iter()
+            .zip(pre_balances)
+            .map(|(account_id, pre_balance)| {
+                (account_id, pre_balance, self.account(&account_id).balance)
+            })
+            .collect::<Vec<_>>();
+        Ok(TransactionReceipt::new(balances, &PaidFees::default()))
+    }
+
+    /// Account, which is claimed by the authenticated transfer program if it was default
+    fn claim(&mut self, account_id: AccountId) -> &mut Account {
+        let account = self.accounts.entry(account_id).or_default();
+        if *account == Account::default() {
+            account.program_owner = Program::authenticated_transfer_program().id();
+        }
+        account
+    }
+
+    fn transaction_status(&self, hash: &str) -> TransactionStatus {
+        if let Some(block_id) = self.included.get(hash) {
+            return TransactionStatus::Included {
+                block_id: *block_id,
+                receipt: self.receipts.get(hash).cloned(),
+            };
+        }
+        if let Some(reason) = self.dropped.get(hash) {
+            return TransactionStatus::Dropped {
+                reason: reason.clone(),
+            };
+        }
+
+        match self
+            .mempool
+            .iter()
+            .position(|tx| hex::encode(tx.hash()) == hash)
+        {
+            Some(position) => TransactionStatus::Pending {
+                position: position as u64,
+                depth: self.mempool.len() as u64,
+                estimated_inclusion_block: self.last_block_id() + 1,
+                estimated_wait_millis: 0,
+            },
+            None => TransactionStatus::Unknown,
+        }
+    }
+
+    fn included_transaction(&self, hash: &str) -> Option<&EncodedTransaction> {
+        let block = self.block(*self.included.get(hash)?)?;
+        block
+            .body
+            .transactions
+            .iter()
+            .find(|tx| hex::encode(tx.hash()) == hash)
+    }
+}
+
+fn rpc_error(error: RpcError) -> SequencerClientError {
+    SequencerRpcError {
+        jsonrpc: "2.0".to_string(),
+        error,
+        id: 1,
+    }
+    .into()
+}
+
+/// Checks `send_tx` and `validate_transaction` run on `transaction`, returning the id of the
+/// program a deployment deploys
+fn check_submission(
+    transaction: &EncodedTransaction,
+) -> Result<Option<String>, SequencerClientError> {
+    let decoded = NSSATransaction::try_from(transaction).map_err(|err| {
+        rpc_error(RpcError::new_internal_error(
+            None,
+            &format!("Failed to decode transaction: {err}"),
+        ))
+    })?;
+    match decoded {
+        NSSATransaction::ProgramDeployment(tx) => {
+            let program = tx
+                .validate_program(nssa::program::DEFAULT_MAX_PROGRAM_SIZE)
+                .map_err(|err| {
+                    rpc_error(RpcError::new_internal_error(
+                        None,
+                        &format!("Invalid program: {err}"),
+                    ))
+                })?;
+            Ok(Some(program.id().to_string()))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_account_id(account_id: &str) -> Result<AccountId, SequencerClientError> {
+    account_id.parse().map_err(|_| {
+        rpc_error(RpcError::new_internal_error(
+            None,
+            &format!("Invalid account id {account_id}"),
+        ))
+    })
+}
+
+#[async_trait]
+impl SequencerApi for MockChain {
+    async fn get_block(&self, block_id: u64) -> Result<GetBlockDataResponse, SequencerClientError> {
+        let mut state = self.state("get_block").await?;
+        let (block, digest) = state.fetched_block(block_id)?;
+
+        Ok(GetBlockDataResponse {
+            block,
+            digest: Some(digest),
+        })
+    }
+
+    async fn get_block_range(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> Result<GetBlockRangeDataResponse, SequencerClientError> {
+        let mut state = self.state("get_block_range").await?;
+        let (blocks, digests) = range
+            .map(|block_id| state.fetched_block(block_id))
+            .collect::<Result<_, _>>()?;
+
+        Ok(GetBlockRangeDataResponse { blocks, digests })
+    }
+
+    async fn get_last_block(&self) -> Result<GetLastBlockResponse, SequencerClientError> {
+        let state = self.state("get_last_block").await?;
+        let last_block = state.last_block_id();
+
+        Ok(GetLastBlockResponse {
+            last_block,
+            last_block_digest: state
+                .served_block(last_block)
+                .ok()
+                .map(|(_, digest)| digest),
+        })
+    }
+
+    async fn get_account_balance_at(
+        &self,
+        account_id: String,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountBalanceResponse, SequencerClientError> {
+        let state = self.state("get_account_balance").await?;
+        let context = state.read_context(min_block_id)?;
+
+        Ok(GetAccountBalanceResponse {
+            balance: state.account(&parse_account_id(&account_id)?).balance,
+            context: Some(context),
+        })
+    }
+
+    async fn get_accounts_nonces_at(
+        &self,
+        account_ids: Vec<String>,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountsNoncesResponse, SequencerClientError> {
+        let state = self.state("get_accounts_nonces").await?;
+        let context = state.read_context(min_block_id)?;
+        let nonces = account_ids
+            .iter()
+            .map(|account_id| Ok(state.account(&parse_account_id(account_id)?).nonce))
+            .collect::<Result<_, SequencerClientError>>()?;
+
+        Ok(GetAccountsNoncesResponse {
+            nonces,
+            context: Some(context),
+        })
+    }
+
+    async fn get_account_at(
+        &self,
+        account_id: String,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountResponse, SequencerClientError> {
+        let state = self.account_query_state("get_account").await?;
+        let context = state.read_context(min_block_id)?;
+
+        Ok(GetAccountResponse {
+            account: state.queried_account(&account_id)?,
+            storage_deposit: Amount::ZERO,
+            context: Some(context),
+        })
+    }
+
+    async fn get_accounts_at(
+        &self,
+        account_ids: Vec<String>,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountsResponse, SequencerClientError> {
+        let state = self.account_query_state("get_accounts").await?;
+        if !state.batch_accounts {
+            return Err(rpc_error(RpcError::method_not_found(
+                "get_accounts".to_string(),
+            )));
+        }
+        let context = state.read_context(min_block_id)?;
+        let accounts = account_ids
+            .iter()
+            .map(|account_id| {
+                Ok(GetAccountResponse {
+                    account: state.queried_account(account_id)?,
+                    storage_deposit: Amount::ZERO,
+                    context: None,
+                })
+            })
+            .collect::<Result<_, SequencerClientError>>()?;
+
+        Ok(GetAccountsResponse {
+            accounts,
+            context: Some(context),
+        })
+    }
+
+    async fn get_transaction_by_hash(
+        &self,
+        hash: String,
+    ) -> Result<GetTransactionByHashResponse, SequencerClientError> {
+        let state = self.state("get_transaction_by_hash").await?;
+
+        Ok(GetTransactionByHashResponse {
+            transaction: state
+                .included_transaction(&hash)
+                .map(|tx| BASE64.encode(borsh::to_vec(tx).unwrap())),
+        })
+    }
+
+    async fn get_transaction_status(
+        &self,
+        hash: String,
+    ) -> Result<GetTransactionStatusResponse, SequencerClientError> {
+        Ok(GetTransactionStatusResponse {
+            status: self
+                .state("get_transaction_status")
+                .await?
+                .transaction_status(&hash),
+        })
+    }
+
+    async fn get_transactions_by_address(
+        &self,
+        account_id: String,
+        cursor: Option<AddressTxCursor>,
+        limit: Option<u64>,
+        filter: AddressTxFilter,
+    ) -> Result<GetTransactionsByAddressResponse, SequencerClientError> {
+        let account_id = parse_account_id(&account_id)?;
+        let limit = limit.map_or(DEFAULT_ADDRESS_TXS_PAGE_SIZE, |limit| {
+            usize::try_from(limit).unwrap_or(usize::MAX)
+        });
+        let state = self.state("get_transactions_by_address").await?;
+
+        Ok(state
+            .address_txs
+            .page(&account_id, &filter, cursor, limit)
+            .into())
+    }
+
+    async fn get_pending_transfers(
+        &self,
+        account_id: String,
+    ) -> Result<GetPendingTransfersResponse, SequencerClientError> {
+        let state = self.state("get_pending_transfers").await?;
+        if !state.pending_transfers {
+            return Err(rpc_error(RpcError::method_not_found(
+                "get_pending_transfers".to_string(),
+            )));
+        }
+
+        Ok(GetPendingTransfersResponse {
+            transfers: state
+                .mempool
+                .iter()
+                .filter_map(|tx| {
+                    PendingTransfer::from_transaction(
+                        &tx.hash(),
+                        &NSSATransaction::try_from(tx).ok()?,
+                    )
+                })
+                .filter(|transfer| transfer.involves(&account_id))
+                .collect(),
+        })
+    }
+
+    async fn get_pending_transactions(
+        &self,
+    ) -> Result<GetPendingTransactionsResponse, SequencerClientError> {
+        let state = self.state("get_pending_transactions").await?;
+        if !state.pending_transactions {
+            return Err(rpc_error(RpcError::method_not_found(
+                "get_pending_transactions".to_string(),
+            )));
+        }
+
+        Ok(GetPendingTransactionsResponse {
+            last_block: state.last_block_id(),
+            transactions: state
+                .mempool
+                .iter()
+                .filter_map(|tx| {
+                    Some(PendingTransactionSummary::from_transaction(
+                        &tx.hash(),
+                        &NSSATransaction::try_from(tx).ok()?,
+                    ))
+                })
+                .collect(),
+        })
+    }
+
+    async fn get_priority_fees(&self) -> Result<GetPriorityFeesResponse, SequencerClientError> {
+        let state = self.state("get_priority_fees").await?;
+        let mut fees = state
+            .mempool
+            .iter()
+            .map(|tx| match NSSATransaction::try_from(tx) {
+                Ok(NSSATransaction::Public(tx)) => tx.message().priority_fee(),
+                _ => Amount::ZERO,
+            })
+            .collect::<Vec<_>>();
+        fees.sort_unstable();
+
+        Ok(GetPriorityFeesResponse {
+            distribution: PriorityFeeDistribution {
+                pending: fees.len() as u64,
+                min: fees.first().copied().unwrap_or_default(),
+                median: fees.get(fees.len() / 2).copied().unwrap_or_default(),
+                max: fees.last().copied().unwrap_or_default(),
+            },
+        })
+    }
+
+    async fn get_sync_status(&self) -> Result<GetSyncStatusResponse, SequencerClientError> {
+        let last_block = self.state("get_sync_status").await?.last_block_id();
+
+        Ok(GetSyncStatusResponse {
+            status: SyncStatus {
+                state: SyncState::Synced,
+                start_height: last_block,
+                current_height: last_block,
+                target_height: last_block,
+                blocks_per_sec: 0.0,
+                eta_secs: None,
+            },
+        })
+    }
+
+    async fn get_block_headers(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> Result<GetBlockHeadersResponse, SequencerClientError> {
+        let state = self.state("get_block_headers").await?;
+        if range.end().saturating_sub(*range.start()) >= MAX_BLOCK_HEADERS_PER_REQUEST {
+            return Err(rpc_error(RpcError::invalid_params(format!(
+                "at most {MAX_BLOCK_HEADERS_PER_REQUEST} headers per request"
+            ))));
+        }
+
+        // Headers end early at the last block, but at least one is requested
+        let end_block_id = (*range.end()).min(state.last_block_id().max(*range.start()));
+        let headers = (*range.start()..=end_block_id)
+            .map(|block_id| state.signed_header(block_id))
+            .collect::<Result<_, _>>()?;
+
+        Ok(GetBlockHeadersResponse { headers })
+    }
+
+    async fn get_account_with_proof(
+        &self,
+        account_id: String,
+    ) -> Result<GetAccountWithProofResponse, SequencerClientError> {
+        let state = self.account_query_state("get_account_with_proof").await?;
+        let mut account = state.queried_account(&account_id)?;
+        let account_id = parse_account_id(&account_id)?;
+        let proof = state
+            .account_tree()
+            .proof(&account_id, |id| state.accounts.get(id));
+        if state.faults.is_proven_balance_tampered(&account_id) {
+            account.balance = account.balance.saturating_add(Amount(1));
+        }
+
+        Ok(GetAccountWithProofResponse {
+            account,
+            proof: borsh::to_vec(&proof).expect("Account proof is serializable"),
+            commitment_set_digest: hex::encode(MOCK_COMMITMENT_SET_DIGEST),
+            header: state.signed_header(state.last_block_id())?,
+        })
+    }
+
+    async fn send_tx(
+        &self,
+        transaction: EncodedTransaction,
+    ) -> Result<SendTxResponse, SequencerClientError> {
+        let mut state = self.state("send_tx").await?;
+        let program_id = check_submission(&transaction)?;
+
+        let tx_hash = hex::encode(transaction.hash());
+        let already_submitted = state.transaction_status(&tx_hash) != TransactionStatus::Unknown;
+        if !already_submitted {
+            state.mempool.push(transaction);
+            if state.auto_produce_blocks {
+                state.produce_block();
+            }
+        }
+
+        let status = state.transaction_status(&tx_hash);
+        let (mempool_position, mempool_depth, estimated_inclusion_block) = match &status {
+            TransactionStatus::Pending {
+                position,
+                depth,
+                estimated_inclusion_block,
+                ..
+            } => (
+                Some(*position),
+                Some(*depth),
+                Some(*estimated_inclusion_block),
+            ),
+            _ => (None, None, None),
+        };
+
+        Ok(SendTxResponse {
+            status: if already_submitted {
+                "Transaction already submitted"
+            } else {
+                "Transaction submitted"
+            }
+            .to_string(),
+            tx_hash,
+            mempool_position,
+            mempool_depth,
+            estimated_inclusion_block,
+            tx_status: Some(status),
+            soft_confirmation: None,
+            program_id,
+        })
+    }
+
+    async fn validate_transaction(
+        &self,
+        transaction: EncodedTransaction,
+    ) -> Result<ValidateTransactionResponse, SequencerClientError> {
+        let state = self.state("validate_transaction").await?;
+        let program_id = check_submission(&transaction)?;
+
+        let tx_hash = hex::encode(transaction.hash());
+        let status = state.transaction_status(&tx_hash);
+        let already_submitted = status != TransactionStatus::Unknown;
+
+        Ok(ValidateTransactionResponse {
+            status: if already_submitted {
+                "Transaction already submitted"
+            } else {
+                "Transaction submitted"
+            }
+            .to_string(),
+            tx_hash,
+            tx_status: already_submitted.then_some(status),
+            program_id,
+        })
+    }
+
+    /// Private state isn't kept by the mock, so no commitment has a proof
+    async fn get_proof_for_commitment(
+        &self,
+        _commitment: nssa_core::Commitment,
+    ) -> Result<Option<nssa_core::MembershipProof>, SequencerClientError> {
+        self.ensure_available("get_proof_for_commitment").await?;
+        Ok(None)
+    }
+
+    async fn get_latest_anchors(&self) -> Result<GetLatestAnchorsResponse, SequencerClientError> {
+        let last_block = self.state("get_latest_anchors").await?.last_block_id();
+
+        Ok(GetLatestAnchorsResponse {
+            anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+            anchors: vec![CommitmentAnchor {
+                block_id: last_block,
+                digest: hex::encode(MOCK_COMMITMENT_SET_DIGEST),
+            }],
+        })
+    }
+
+    async fn get_program_ids(&self) -> Result<HashMap<String, ProgramId>, SequencerClientError> {
+        self.ensure_available("get_program_ids").await?;
+
+        Ok(HashMap::from([
+            (
+                "authenticated_transfer".to_string(),
+                Program::authenticated_transfer_program().id(),
+            ),
+            ("token".to_string(), Program::token().id()),
+            ("escrow".to_string(), Program::escrow().id()),
+            ("pinata".to_string(), Program::pinata().id()),
+            (
+                "privacy_preserving_circuit".to_string(),
+                ProgramId::new(nssa::PRIVACY_PRESERVING_CIRCUIT_ID),
+            ),
+        ]))
+    }
+
+    async fn get_api_version(&self) -> Result<GetApiVersionResponse, SequencerClientError> {
+        self.ensure_available("get_api_version").await?;
+
+        Ok(GetApiVersionResponse {
+            api_version: API_VERSION,
+            min_supported_api_version: MIN_SUPPORTED_API_VERSION,
+        })
+    }
+
+    async fn get_sequencer_info(&self) -> Result<GetSequencerInfoResponse, SequencerClientError> {
+        let state = self.state("get_sequencer_info").await?;
+        let public_key = nssa::PublicKey::new_from_private_key(&sequencer_sign_key_for_testing());
+
+        Ok(GetSequencerInfoResponse {
+            version: "mock".to_string(),
+            api_version: API_VERSION,
+            tx_kinds: TxKind::SUPPORTED.into_iter().map(TxKind::tag).collect(),
+            features: [
+                state
+                    .batch_accounts
+                    .then_some(SequencerFeature::BatchAccounts),
+                state
+                    .pending_transfers
+                    .then_some(SequencerFeature::PendingTransfers),
+                state
+                    .pending_transactions
+                    .then_some(SequencerFeature::PendingTransactions),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            public_key: Some(hex::encode(public_key.value())),
+            genesis_hash: state
+                .block(MOCK_GENESIS_ID)
+                .map(|genesis| hex::encode(genesis.header.hash)),
+            chain_id: Some(0),
+        })
+    }
+
+    async fn get_chain_info(&self) -> Result<GetChainInfoResponse, SequencerClientError> {
+        let state = self.state("get_chain_info").await?;
+
+        Ok(GetChainInfoResponse {
+            genesis_id: MOCK_GENESIS_ID,
+            last_block: state.last_block_id(),
+            max_num_tx_in_block: usize::MAX,
+            params_version: state.params_version,
+            tx_ordering_policy: "arrival_seq_asc".to_string(),
+            program_allow_list: None,
+            forced_params_changes: vec![],
+            storage_deposit_per_byte: state.storage_deposit_per_byte,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::BlockUnavailable,
+        test_utils::{
+            account_id_for_index, create_transaction_native_token_transfer, signing_key_for_index,
+        },
+    };
+
+    fn transfer(nonce: u64, amount: u128) -> EncodedTransaction {
+        create_transaction_native_token_transfer(
+            *account_id_for_index(0).value(),
+            nonce,
+            *account_id_for_index(1).value(),
+            amount,
+            signing_key_for_index(0),
+        )
+    }
+
+    /// Receipt of the first [`transfer`], which sender had `balance`
+    fn transfer_receipt(balance: u128, amount: u128) -> TransactionReceipt {
+        TransactionReceipt::new(
+            [
+                (
+                    account_id_for_index(0),
+                    Amount(balance),
+                    Amount(balance - amount),
+                ),
+                (account_id_for_index(1), Amount::ZERO, Amount(amount)),
+            ],
+            &PaidFees::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_transfer_is_applied_when_block_is_produced() {
+        let chain = MockChain::new(&[(account_id_for_index(0), Amount(100))]);
+        let tx = transfer(0, 30);
+        let hash = hex::encode(tx.hash());
+
+        let response = chain.send_tx(tx).await.unwrap();
+        assert_eq!(response.mempool_position, Some(0));
+        assert_eq!(
+            chain
+                .get_transaction_status(hash.clone())
+                .await
+                .unwrap()
+                .status,
+            TransactionStatus::Pending {
+                position: 0,
+                depth: 1,
+                estimated_inclusion_block: 2,
+                estimated_wait_millis: 0,
+            }
+        );
+
+        assert_eq!(chain.produce_block(), 2);
+        assert_eq!(chain.account(&account_id_for_index(0)).balance, Amount(70));
+        assert_eq!(chain.account(&account_id_for_index(0)).nonce, 1);
+        assert_eq!(chain.account(&account_id_for_index(1)).balance, Amount(30));
+        assert_eq!(
+            chain
+                .get_transaction_status(hash.clone())
+                .await
+                .unwrap()
+                .status,
+            TransactionStatus::Included {
+                block_id: 2,
+                receipt: Some(transfer_receipt(100, 30))
+            }
+        );
+        assert!(
+            chain
+                .get_transaction_by_hash(hash)
+                .await
+                .unwrap()
+                .transaction
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validation_gives_verdict_of_submission_without_submitting() {
+        let chain = MockChain::new(&[(account_id_for_index(0), Amount(100))]);
+        let tx = transfer(0, 30);
+        let hash = hex::encode(tx.hash());
+
+        let verdict = chain.validate_transaction(tx.clone()).await.unwrap();
+        assert_eq!(verdict.tx_status, None);
+        assert_eq!(
+            chain.get_transaction_status(hash).await.unwrap().status,
+            TransactionStatus::Unknown
+        );
+
+        let response = chain.send_tx(tx.clone()).await.unwrap();
+        assert_eq!(verdict.status, response.status);
+        assert_eq!(verdict.tx_hash, response.tx_hash);
+
+        let verdict = chain.validate_transaction(tx.clone()).await.unwrap();
+        let response = chain.send_tx(tx).await.unwrap();
+        assert_eq!(verdict.status, response.status);
+        assert_eq!(verdict.tx_status, response.tx_status);
+    }
+
+    #[tokio::test]
+    async fn test_transactions_with_stale_nonce_or_overspending_are_dropped() {
+        let chain =
+            MockChain::new(&[(account_id_for_index(0), Amount(100))]).with_auto_block_production();
+        chain.send_tx(transfer(0, 30)).await.unwrap();
+
+        let stale = chain.send_tx(transfer(0, 10)).await.unwrap();
+        assert!(matches!(
+            stale.tx_status,
+            Some(TransactionStatus::Dropped { .. })
+        ));
+        let overspending = chain.send_tx(transfer(1, 1000)).await.unwrap();
+        assert!(matches!(
+            overspending.tx_status,
+            Some(TransactionStatus::Dropped { .. })
+        ));
+        assert_eq!(chain.account(&account_id_for_index(0)).balance, Amount(70));
+    }
+
+    #[tokio::test]
+    async fn test_unserved_blocks_report_why() {
+        let chain = MockChain::new(&[]);
+        chain.produce_block();
+        chain.produce_block();
+        chain.lose_block(2);
+
+        assert!(chain.get_block(1).await.is_ok());
+        assert_eq!(
+            chain.get_block(2).await.unwrap_err().block_unavailable(),
+            Some(BlockUnavailable::Missing)
+        );
+        assert_eq!(
+            chain.get_block(4).await.unwrap_err().block_unavailable(),
+            Some(BlockUnavailable::NotYetProduced { tip: 3 })
+        );
+        assert_eq!(
+            chain
+                .get_block_range(1..=3)
+                .await
+                .unwrap_err()
+                .block_unavailable(),
+            Some(BlockUnavailable::Missing)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reads_report_their_block_and_refuse_future_ones() {
+        let chain = MockChain::new(&[(account_id_for_index(0), Amount(100))]);
+        let account_id = account_id_for_index(0).to_string();
+
+        let response = chain
+            .get_account_balance_at(account_id.clone(), Some(MOCK_GENESIS_ID))
+            .await
+            .unwrap();
+        assert_eq!(response.context.unwrap().block_id, MOCK_GENESIS_ID);
+
+        let stale = chain
+            .get_account_balance_at(account_id.clone(), Some(2))
+            .await
+            .unwrap_err();
+        assert_eq!(stale.stale_snapshot_block_id(), Some(MOCK_GENESIS_ID));
+
+        chain.produce_block();
+        let response = chain
+            .get_accounts_nonces_at(vec![account_id], Some(2))
+            .await
+            .unwrap();
+        assert_eq!(response.context.unwrap().block_id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_planned_faults_are_used_up() {
+        let chain = MockChain::new(&[]).with_fault_plan(
+            FaultPlan::default()
+                .drop_calls("get_last_block", 1)
+                .restart_at_block(2, 2)
+                .corrupt_block_fetch(MOCK_GENESIS_ID),
+        );
+
+        assert!(chain.get_last_block().await.unwrap_err().is_server_busy());
+        assert!(chain.get_last_block().await.is_ok());
+
+        let corrupted = chain.get_block(MOCK_GENESIS_ID).await.unwrap();
+        let digest = corrupted.digest.unwrap();
+        assert!(
+            borsh::from_slice::<HashableBlockData>(&corrupted.block)
+                .map_or(true, |block| block.check_digest(&digest).is_err())
+        );
+        let served = chain.get_block(MOCK_GENESIS_ID).await.unwrap();
+        let block = borsh::from_slice::<HashableBlockData>(&served.block).unwrap();
+        assert!(block.check_digest(&digest).is_ok());
+
+        // Restarting sequencer misses calls of any method
+        chain.produce_block();
+        assert!(chain.get_last_block().await.unwrap_err().is_server_busy());
+        assert!(chain.get_chain_info().await.unwrap_err().is_server_busy());
+        assert_eq!(chain.get_last_block().await.unwrap().last_block, 2);
+    }
+
+    #[tokio::test]
+    async fn test_paused_storage_write_is_served_after_resume() {
+        let chain = MockChain::new(&[(account_id_for_index(0), Amount(100))])
+            .with_fault_plan(FaultPlan::default().pause_storage_at_block(2));
+        let first = transfer(0, 30);
+        let first_hash = hex::encode(first.hash());
+        chain.send_tx(first).await.unwrap();
+
+        assert_eq!(chain.produce_block(), MOCK_GENESIS_ID);
+        assert_eq!(chain.account(&account_id_for_index(0)).balance, Amount(100));
+        assert!(matches!(
+            chain
+                .get_transaction_status(first_hash.clone())
+                .await
+                .unwrap()
+                .status,
+            TransactionStatus::Pending { .. }
+        ));
+        // Transaction arriving during the pause waits for the next block
+        chain.send_tx(transfer(1, 20)).await.unwrap();
+        assert_eq!(chain.produce_block(), MOCK_GENESIS_ID);
+
+        assert_eq!(chain.resume_storage(), Some(2));
+        assert_eq!(chain.resume_storage(), None);
+        assert_eq!(chain.account(&account_id_for_index(0)).balance, Amount(70));
+        assert_eq!(
+            chain
+                .get_transaction_status(first_hash)
+                .await
+                .unwrap()
+                .status,
+            TransactionStatus::Included {
+                block_id: 2,
+                receipt: Some(transfer_receipt(100, 30))
+            }
+        );
+        assert_eq!(chain.mempool_len(), 1);
+
+        assert_eq!(chain.produce_block(), 3);
+        assert_eq!(chain.account(&account_id_for_index(0)).balance, Amount(50));
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_chain_reports_server_busy() {
+        let chain = MockChain::new(&[]);
+
+        chain.set_unavailable(true);
+        assert!(chain.get_last_block().await.unwrap_err().is_server_busy());
+
+        chain.set_unavailable(false);
+        assert_eq!(
+            chain.get_last_block().await.unwrap().last_block,
+            MOCK_GENESIS_ID
+        );
+    }
+}
@@ -0,0 +1,435 @@
+//! Balance changes of an applied transaction, explained double-entry style.
+//!
+//! Every public account, which balance a transaction changed or which paid or received its fees,
+//! has an entry with its balance before and after the transaction. Components of an entry add up
+//! to the change, credits counting positively and debits negatively. Fees are taken from the
+//! payer and credited to the fee recipient as they are, whatever else changed is a transfer.
+//!
+//! Receipts stored before components were introduced have none, they still deserialize.
+
+use std::fmt::Display;
+
+use nssa::{AccountId, Amount, Gas, gas::PaidFees};
+use serde::{Deserialize, Serialize};
+
+use crate::json_number::decimal_string;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionReceipt {
+    #[serde(default)]
+    pub balance_changes: Vec<BalanceChange>,
+}
+
+/// Change of the public balance of one account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceChange {
+    pub account_id: String,
+    #[serde(with = "decimal_string")]
+    pub pre_balance: Amount,
+    #[serde(with = "decimal_string")]
+    pub post_balance: Amount,
+    #[serde(default)]
+    pub components: Vec<BalanceComponent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceComponent {
+    pub kind: BalanceComponentKind,
+    #[serde(with = "decimal_string")]
+    pub amount: Amount,
+    pub sign: BalanceSign,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceComponentKind {
+    /// Balance moved by the executed program
+    Transfer,
+    /// Charged gas of a metered execution or the deployment fee
+    Fee,
+    /// Priority fee
+    Priority,
+    /// Gas deposited for the declared maximum of cycles
+    Deposit,
+    /// Unused part of the gas deposit
+    Refund,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceSign {
+    Credit,
+    Debit,
+}
+
+impl TransactionReceipt {
+    /// Receipt of a transaction, which paid `fees` and changed balances of accounts from the
+    /// first to the second one of `balances`
+    ///
+    /// Fee payer and recipient are expected among `balances`. Accounts listed more than once
+    /// are kept once, accounts not affected by the transaction are left out.
+    pub fn new(
+        balances: impl IntoIterator<Item = (AccountId, Amount, Amount)>,
+        fees: &PaidFees,
+    ) -> Self {
+        let mut balance_changes: Vec<BalanceChange> = vec![];
+        for (account_id, pre_balance, post_balance) in balances {
+            let account_id = account_id.to_string();
+            if balance_changes
+                .iter()
+                .any(|change| change.account_id == account_id)
+            {
+                continue;
+            }
+
+            let mut components = fee_components(&account_id, fees);
+            let (credits, debits) = sums(&components);
+            // Whatever fees don't explain was moved by the program
+            let with_debits = post_balance.saturating_add(debits);
+            let with_credits = pre_balance.saturating_add(credits);
+            if with_debits != with_credits {
+                let (amount, sign) = if with_debits > with_credits {
+                    (
+                        with_debits.saturating_sub(with_credits),
+                        BalanceSign::Credit,
+                    )
+                } else {
+                    (with_credits.saturating_sub(with_debits), BalanceSign::Debit)
+                };
+                components.insert(
+                    0,
+                    BalanceComponent {
+                        kind: BalanceComponentKind::Transfer,
+                        amount,
+                        sign,
+                    },
+                );
+            }
+
+            if !components.is_empty() {
+                balance_changes.push(BalanceChange {
+                    account_id,
+                    pre_balance,
+                    post_balance,
+                    components,
+                });
+            }
+        }
+
+        Self { balance_changes }
+    }
+}
+
+impl BalanceChange {
+    /// Whether components add up to the change from the pre to the post balance
+    pub fn is_balanced(&self) -> bool {
+        let (credits, debits) = sums(&self.components);
+        self.pre_balance.checked_add(credits) == self.post_balance.checked_add(debits)
+    }
+}
+
+impl Display for BalanceComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{} {}", self.sign, self.amount, self.kind)
+    }
+}
+
+impl Display for BalanceComponentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            Self::Transfer => "transfer",
+            Self::Fee => "fee",
+            Self::Priority => "priority",
+            Self::Deposit => "deposit",
+            Self::Refund => "refund",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+impl Display for BalanceSign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Credit => write!(f, "+"),
+            Self::Debit => write!(f, "-"),
+        }
+    }
+}
+
+/// Components of fees paid or received by `account_id`, amounts of zero are left out
+fn fee_components(account_id: &str, fees: &PaidFees) -> Vec<BalanceComponent> {
+    let is = |fee_account: Option<AccountId>| {
+        fee_account.is_some_and(|fee_account| fee_account.to_string() == account_id)
+    };
+    let component = |kind, amount, sign| BalanceComponent { kind, amount, sign };
+
+    let mut components = vec![];
+    if is(fees.payer) {
+        if let Some(gas) = fees.gas {
+            components.push(component(
+                BalanceComponentKind::Deposit,
+                gas.deposit.into(),
+                BalanceSign::Debit,
+            ));
+            components.push(component(
+                BalanceComponentKind::Refund,
+                gas.refund.into(),
+                BalanceSign::Credit,
+            ));
+        }
+        components.push(component(
+            BalanceComponentKind::Fee,
+            fees.deployment_fee.into(),
+            BalanceSign::Debit,
+        ));
+        components.push(component(
+            BalanceComponentKind::Priority,
+            fees.priority_fee,
+            BalanceSign::Debit,
+        ));
+    }
+    if is(fees.recipient) {
+        components.push(component(
+            BalanceComponentKind::Fee,
+            Amount::from(fees.gas.map_or(Gas::ZERO, |gas| gas.charged))
+                .saturating_add(fees.deployment_fee.into()),
+            BalanceSign::Credit,
+        ));
+        components.push(component(
+            BalanceComponentKind::Priority,
+            fees.priority_fee,
+            BalanceSign::Credit,
+        ));
+    }
+
+    components.retain(|component| !component.amount.is_zero());
+    components
+}
+
+/// Sums of credited and debited amounts
+fn sums(components: &[BalanceComponent]) -> (Amount, Amount) {
+    components.iter().fold(
+        (Amount::ZERO, Amount::ZERO),
+        |(credits, debits), component| match component.sign {
+            BalanceSign::Credit => (credits.saturating_add(component.amount), debits),
+            BalanceSign::Debit => (credits, debits.saturating_add(component.amount)),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use nssa::{
+        Cycles,
+        gas::{GasCalculator, GasSettlement},
+    };
+
+    use super::*;
+
+    fn component(kind: BalanceComponentKind, amount: u128, sign: BalanceSign) -> BalanceComponent {
+        BalanceComponent {
+            kind,
+            amount: Amount(amount),
+            sign,
+        }
+    }
+
+    #[test]
+    fn test_fees_and_transfers_to_two_recipients_are_broken_down() {
+        use BalanceComponentKind::*;
+        use BalanceSign::*;
+
+        let payer = AccountId::new([1; 32]);
+        let first_recipient = AccountId::new([2; 32]);
+        let second_recipient = AccountId::new([3; 32]);
+        let fee_recipient = AccountId::new([4; 32]);
+        let gas = GasCalculator::default().settle(Cycles(10 * 1024), Cycles(2 * 1024 + 1));
+        let fees = PaidFees {
+            payer: Some(payer),
+            recipient: Some(fee_recipient),
+            gas: Some(gas),
+            priority_fee: Amount(5),
+            deployment_fee: Gas::ZERO,
+        };
+        assert_eq!(
+            gas,
+            GasSettlement {
+                cycles: Cycles(2 * 1024 + 1),
+                deposit: Gas(10),
+                charged: Gas(3),
+                refund: Gas(7),
+            }
+        );
+
+        let receipt = TransactionReceipt::new(
+            [
+                (payer, 1000, 1000 - 100 - 50 - 3 - 5),
+                (first_recipient, 0, 100),
+                (second_recipient, 20, 70),
+                (fee_recipient, 7, 7 + 3 + 5),
+                // Listed twice, as it's both an account of the transaction and fee recipient
+                (fee_recipient, 7, 7 + 3 + 5),
+            ]
+            .map(|(account_id, pre, post)| (account_id, Amount(pre), Amount(post))),
+            &fees,
+        );
+
+        assert_eq!(
+            receipt.balance_changes,
+            vec![
+                BalanceChange {
+                    account_id: payer.to_string(),
+                    pre_balance: Amount(1000),
+                    post_balance: Amount(842),
+                    components: vec![
+                        component(Transfer, 150, Debit),
+                        component(Deposit, 10, Debit),
+                        component(Refund, 7, Credit),
+                        component(Priority, 5, Debit),
+                    ],
+                },
+                BalanceChange {
+                    account_id: first_recipient.to_string(),
+                    pre_balance: Amount(0),
+                    post_balance: Amount(100),
+                    components: vec![component(Transfer, 100, Credit)],
+                },
+                BalanceChange {
+                    account_id: second_recipient.to_string(),
+                    pre_balance: Amount(20),
+                    post_balance: Amount(70),
+                    components: vec![component(Transfer, 50, Credit)],
+                },
+                BalanceChange {
+                    account_id: fee_recipient.to_string(),
+                    pre_balance: Amount(7),
+                    post_balance: Amount(15),
+                    components: vec![component(Fee, 3, Credit), component(Priority, 5, Credit)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_components_sum_to_balance_change() {
+        let payer = AccountId::new([1; 32]);
+        let other = AccountId::new([2; 32]);
+        let fee_recipient = AccountId::new([3; 32]);
+        let gas = GasCalculator::default();
+
+        for (transfer, max_cycles, cycles, priority_fee, deployment_fee) in [
+            (0, None, 0, 0, 0),
+            (100, None, 0, 0, 0),
+            (100, Some(4096), 1, 0, 0),
+            (100, Some(4096), 4096, 7, 0),
+            (0, None, 0, 0, 42),
+            (0, Some(1024), 1024, 1, 0),
+        ] {
+            let settlement =
+                max_cycles.map(|max_cycles| gas.settle(Cycles(max_cycles), Cycles(cycles)));
+            let fees = PaidFees {
+                payer: Some(payer),
+                recipient: Some(fee_recipient),
+                gas: settlement,
+                priority_fee: Amount(priority_fee),
+                deployment_fee: Gas(deployment_fee),
+            };
+            let fee = fees.total().0;
+            // Payer may also be the one receiving the transfer or the fees
+            for (to, recipient) in [
+                (other, fee_recipient),
+                (payer, fee_recipient),
+                (other, payer),
+            ] {
+                let fees = PaidFees {
+                    recipient: Some(recipient),
+                    ..fees
+                };
+                let mut balances = [(payer, 1000), (other, 50), (fee_recipient, 5)];
+                let mut post = balances;
+                for (account_id, balance) in &mut post {
+                    if *account_id == payer {
+                        *balance -= transfer + fee;
+                    }
+                    if *account_id == to {
+                        *balance += transfer;
+                    }
+                    if *account_id == recipient {
+                        *balance += fee;
+                    }
+                }
+                balances.sort();
+                post.sort();
+
+                let receipt = TransactionReceipt::new(
+                    balances
+                        .iter()
+                        .zip(&post)
+                        .map(|((account_id, pre), (_, post))| {
+                            (*account_id, Amount(*pre), Amount(*post))
+                        }),
+                    &fees,
+                );
+                for change in &receipt.balance_changes {
+                    assert!(change.is_balanced(), "{change:?} of {fees:?}");
+                }
+                for ((account_id, pre), (_, post)) in balances.iter().zip(&post) {
+                    let listed = receipt
+                        .balance_changes
+                        .iter()
+                        .any(|change| change.account_id == account_id.to_string());
+                    assert!(listed || pre == post);
+                }
+            }
+        }
+
+        let unbalanced = BalanceChange {
+            account_id: payer.to_string(),
+            pre_balance: Amount(10),
+            post_balance: Amount(5),
+            components: vec![component(
+                BalanceComponentKind::Transfer,
+                4,
+                BalanceSign::Debit,
+            )],
+        };
+        assert!(!unbalanced.is_balanced());
+    }
+
+    #[test]
+    fn test_receipts_without_components_are_read() {
+        let receipt: TransactionReceipt = serde_json::from_str("{}").unwrap();
+        assert_eq!(receipt, TransactionReceipt::default());
+
+        let change: BalanceChange = serde_json::from_str(
+            r#"{"account_id": "11111111111111111111111111111111", "pre_balance": "10", "post_balance": "5"}"#,
+        )
+        .unwrap();
+        assert!(change.components.is_empty());
+    }
+
+    #[test]
+    fn test_receipt_format() {
+        let change = BalanceChange {
+            account_id: "11111111111111111111111111111111".to_string(),
+            pre_balance: Amount(10),
+            post_balance: Amount(5),
+            components: vec![component(
+                BalanceComponentKind::Priority,
+                5,
+                BalanceSign::Debit,
+            )],
+        };
+
+        assert_eq!(
+            serde_json::to_value(&change).unwrap(),
+            serde_json::json!({
+                "account_id": "11111111111111111111111111111111",
+                "pre_balance": "10",
+                "post_balance": "5",
+                "components": [{"kind": "priority", "amount": "5", "sign": "debit"}],
+            })
+        );
+        assert_eq!(change.components[0].to_string(), "-5 priority");
+    }
+}
@@ -38,6 +38,31 @@ pub enum RpcRequestValidationErrorKind {
     ParseError { error_message: String },
 }
 
+/// Code of [`RpcError::method_not_found`], e.g. the method is newer than the server
+pub const METHOD_NOT_FOUND_ERROR_CODE: i64 = -32_601;
+
+/// Code of [`RpcError::server_busy`], the request may be retried later
+pub const SERVER_BUSY_ERROR_CODE: i64 = -32_003;
+
+/// Code of [`RpcError::mempool_congested`], the submission may be retried after a hinted delay
+pub const MEMPOOL_CONGESTED_ERROR_CODE: i64 = -32_004;
+
+/// Code of [`RpcError::block_pruned`], the block existed, but its body is no longer stored
+pub const BLOCK_PRUNED_ERROR_CODE: i64 = -32_005;
+
+/// Code of [`RpcError::block_not_yet_produced`], the block id is after the last block
+pub const BLOCK_NOT_YET_PRODUCED_ERROR_CODE: i64 = -32_006;
+
+/// Code of [`RpcError::block_missing`], the block should be stored, but sequencer lost it
+pub const BLOCK_MISSING_ERROR_CODE: i64 = -32_007;
+
+/// Code of [`RpcError::stale_snapshot`], the state didn't reach the requested block in time
+pub const STALE_SNAPSHOT_ERROR_CODE: i64 = -32_008;
+
+/// Code of [`RpcError::insufficient_unreserved_balance`], the submission may succeed once pending
+/// transactions of the sender are included or the sender is funded
+pub const INSUFFICIENT_UNRESERVED_BALANCE_ERROR_CODE: i64 = -32_009;
+
 /// A general Server Error
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
 pub enum ServerError {
@@ -132,10 +157,131 @@ impl RpcError {
         }
     }
 
+    /// Create an error of a request, which wasn't handled within its time budget.
+    pub fn server_busy(method: &str, timeout: std::time::Duration) -> Self {
+        let timeout_millis = timeout.as_millis() as u64;
+        RpcError {
+            code: SERVER_BUSY_ERROR_CODE,
+            message: "Server busy, retry".to_owned(),
+            data: Some(serde_json::json!({
+                "method": method,
+                "timeout_millis": timeout_millis,
+            })),
+            error_struct: Some(RpcErrorKind::HandlerError(serde_json::json!({
+                "name": "SERVER_BUSY",
+                "info": {
+                    "method": method,
+                    "timeout_millis": timeout_millis,
+                }
+            }))),
+        }
+    }
+
+    /// Create an error of a submission rejected, because the sequencer admits transactions slower
+    /// than they arrive
+    pub fn mempool_congested(retry_after: std::time::Duration) -> Self {
+        let retry_after_millis = retry_after.as_millis() as u64;
+        RpcError {
+            code: MEMPOOL_CONGESTED_ERROR_CODE,
+            message: "Mempool congested, retry later".to_owned(),
+            data: Some(serde_json::json!({ "retry_after_millis": retry_after_millis })),
+            error_struct: Some(RpcErrorKind::HandlerError(serde_json::json!({
+                "name": "MEMPOOL_CONGESTED",
+                "info": { "retry_after_millis": retry_after_millis }
+            }))),
+        }
+    }
+
+    /// Create an error of a request of block, which body was pruned from storage
+    pub fn block_pruned(block_id: u64, first_unpruned_block_id: u64) -> Self {
+        RpcError {
+            code: BLOCK_PRUNED_ERROR_CODE,
+            message: "Block pruned".to_owned(),
+            data: Some(serde_json::json!({
+                "block_id": block_id,
+                "first_unpruned_block_id": first_unpruned_block_id,
+            })),
+            error_struct: Some(RpcErrorKind::HandlerError(serde_json::json!({
+                "name": "BLOCK_PRUNED",
+                "info": {
+                    "block_id": block_id,
+                    "first_unpruned_block_id": first_unpruned_block_id,
+                }
+            }))),
+        }
+    }
+
+    /// Create an error of a request of block after the last one
+    pub fn block_not_yet_produced(block_id: u64, last_block_id: u64) -> Self {
+        RpcError {
+            code: BLOCK_NOT_YET_PRODUCED_ERROR_CODE,
+            message: "Block not yet produced".to_owned(),
+            data: Some(serde_json::json!({
+                "block_id": block_id,
+                "last_block_id": last_block_id,
+            })),
+            error_struct: Some(RpcErrorKind::HandlerError(serde_json::json!({
+                "name": "BLOCK_NOT_YET_PRODUCED",
+                "info": {
+                    "block_id": block_id,
+                    "last_block_id": last_block_id,
+                }
+            }))),
+        }
+    }
+
+    /// Create an error of a request of block, which should be stored, but is missing from storage
+    pub fn block_missing(block_id: u64) -> Self {
+        RpcError {
+            code: BLOCK_MISSING_ERROR_CODE,
+            message: "Block missing from storage".to_owned(),
+            data: Some(serde_json::json!({ "block_id": block_id })),
+            error_struct: Some(RpcErrorKind::HandlerError(serde_json::json!({
+                "name": "BLOCK_MISSING",
+                "info": { "block_id": block_id }
+            }))),
+        }
+    }
+
+    /// Create an error of a read, which requires state of block `min_block_id` at least, while
+    /// state is still at block `block_id` after waiting
+    pub fn stale_snapshot(min_block_id: u64, block_id: u64) -> Self {
+        RpcError {
+            code: STALE_SNAPSHOT_ERROR_CODE,
+            message: "Stale snapshot".to_owned(),
+            data: Some(serde_json::json!({
+                "min_block_id": min_block_id,
+                "block_id": block_id,
+            })),
+            error_struct: Some(RpcErrorKind::HandlerError(serde_json::json!({
+                "name": "STALE_SNAPSHOT",
+                "info": {
+                    "min_block_id": min_block_id,
+                    "block_id": block_id,
+                }
+            }))),
+        }
+    }
+
+    /// Create an error of a submission, which value and fees exceed the balance of its sender not
+    /// reserved by its pending transactions, with the breakdown of the reservations
+    pub fn insufficient_unreserved_balance(shortfall: impl serde::Serialize) -> Self {
+        let shortfall = serde_json::to_value(shortfall).unwrap();
+        RpcError {
+            code: INSUFFICIENT_UNRESERVED_BALANCE_ERROR_CODE,
+            message: "Insufficient unreserved balance".to_owned(),
+            data: Some(shortfall.clone()),
+            error_struct: Some(RpcErrorKind::HandlerError(serde_json::json!({
+                "name": "INSUFFICIENT_UNRESERVED_BALANCE",
+                "info": shortfall
+            }))),
+        }
+    }
+
     /// Create a method not found error.
     pub fn method_not_found(method: String) -> Self {
         RpcError {
-            code: -32_601,
+            code: METHOD_NOT_FOUND_ERROR_CODE,
             message: "Method not found".to_owned(),
             data: Some(Value::String(method.clone())),
             error_struct: Some(RpcErrorKind::RequestValidationError(
@@ -1,9 +1,10 @@
+use std::{collections::HashMap, time::Duration};
+
 use serde::{Deserialize, Serialize};
 
 pub mod errors;
 pub mod message;
 pub mod parser;
-pub mod requests;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RpcLimitsConfig {
@@ -19,12 +20,52 @@ impl Default for RpcLimitsConfig {
     }
 }
 
+/// Time budgets of request handlers, requests exceeding them are answered with
+/// [`errors::RpcError::server_busy`]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RpcTimeoutsConfig {
+    /// Budget of methods reading state, in milliseconds
+    pub read_timeout_millis: u64,
+    /// Budget of methods submitting or validating transactions, in milliseconds
+    pub submission_timeout_millis: u64,
+    /// Budgets of particular methods overriding the above ones, in milliseconds
+    pub method_timeout_millis: HashMap<String, u64>,
+    /// How long reads with `min_block_id` wait for the state to reach that block, before they're
+    /// answered with [`errors::RpcError::stale_snapshot`], in milliseconds
+    pub snapshot_wait_millis: u64,
+}
+
+impl Default for RpcTimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            read_timeout_millis: 2_000,
+            submission_timeout_millis: 10_000,
+            method_timeout_millis: HashMap::new(),
+            snapshot_wait_millis: 1_000,
+        }
+    }
+}
+
+impl RpcTimeoutsConfig {
+    pub fn timeout(&self, method: &str, is_submission: bool) -> Duration {
+        let millis = match self.method_timeout_millis.get(method) {
+            Some(millis) => *millis,
+            None if is_submission => self.submission_timeout_millis,
+            None => self.read_timeout_millis,
+        };
+        Duration::from_millis(millis)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RpcConfig {
     pub addr: String,
     pub cors_allowed_origins: Vec<String>,
     #[serde(default)]
     pub limits_config: RpcLimitsConfig,
+    #[serde(default)]
+    pub timeouts_config: RpcTimeoutsConfig,
 }
 
 impl Default for RpcConfig {
@@ -33,6 +74,7 @@ impl Default for RpcConfig {
             addr: "0.0.0.0:3040".to_owned(),
             cors_allowed_origins: vec!["*".to_owned()],
             limits_config: RpcLimitsConfig::default(),
+            timeouts_config: RpcTimeoutsConfig::default(),
         }
     }
 }
@@ -0,0 +1,1832 @@
+//! Request and response payloads of sequencer JSON-RPC methods, shared by server and clients.
+//!
+//! Any change of the wire format must bump [`API_VERSION`].
+
+use std::{collections::HashMap, fmt::Display};
+
+use nssa::public_transaction::NativeTransfer;
+use nssa_core::{account::Nonce, program::ProgramId, units::Amount};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    HashType,
+    address_index::{AddressTxCursor, AddressTxEntry, AddressTxFilter, AddressTxPage, TxDirection},
+    block::{BlockHash, ParamsVersion},
+    block_explorer::ExpandedBlock,
+    json_number::{decimal_string, safe_integer},
+    parse_request,
+    receipt::TransactionReceipt,
+    rpc_primitives::{
+        errors::RpcParseError,
+        parser::{RpcRequest, parse_params},
+    },
+    signed_header::SignedBlockHeader,
+    soft_confirmation::SoftConfirmation,
+    transaction::{NSSATransaction, TxKind},
+};
+
+/// Version of the wire types in this module
+///
+/// Major version is bumped on incompatible changes, minor one on backward compatible ones.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Display for ApiVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+pub const API_VERSION: ApiVersion = ApiVersion { major: 4, minor: 0 };
+
+/// Oldest version of the other side this build can talk to
+///
+/// Priority fees of public transactions are amounts, encoded as `u128`, in 4.0.
+pub const MIN_SUPPORTED_API_VERSION: ApiVersion = ApiVersion { major: 4, minor: 0 };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiCompatibility {
+    Compatible,
+    /// Major versions differ, but both sides support each other
+    MajorMismatch,
+    Incompatible,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HelloRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RegisterAccountRequest {
+    pub account_id: [u8; 32],
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SendTxRequest {
+    #[serde(with = "base64_deser")]
+    pub transaction: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBlockDataRequest {
+    pub block_id: u64,
+}
+
+/// Get a range of blocks from `start_block_id` to `end_block_id` (inclusive)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBlockRangeDataRequest {
+    pub start_block_id: u64,
+    pub end_block_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetGenesisIdRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetLastBlockRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetInitialTestnetAccountsRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAccountBalanceRequest {
+    pub account_id: String,
+    /// Oldest block the state may be read at, see [`ReadContext`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_block_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetTransactionByHashRequest {
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAccountsNoncesRequest {
+    pub account_ids: Vec<String>,
+    /// Oldest block the state may be read at, see [`ReadContext`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_block_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAccountRequest {
+    pub account_id: String,
+    /// Oldest block the state may be read at, see [`ReadContext`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_block_id: Option<u64>,
+}
+
+/// Maximum number of accounts in one `get_accounts` request
+pub const MAX_ACCOUNTS_PER_REQUEST: usize = 256;
+
+/// Served by sequencers with [`SequencerFeature::BatchAccounts`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAccountsRequest {
+    pub account_ids: Vec<String>,
+    /// Oldest block the state may be read at, see [`ReadContext`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_block_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetProofForCommitmentRequest {
+    pub commitment: nssa_core::Commitment,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetProgramIdsRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBlockExpandedRequest {
+    pub block_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAddressSummaryRequest {
+    pub account_id: String,
+}
+
+/// Page of transactions touching a public account, from the newest one
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetTransactionsByAddressRequest {
+    pub account_id: String,
+    /// Cursor returned with the previous page, the first page is requested without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<AddressTxCursor>,
+    /// Number of transactions in the page, see
+    /// [`DEFAULT_ADDRESS_TXS_PAGE_SIZE`](crate::address_index::DEFAULT_ADDRESS_TXS_PAGE_SIZE)
+    /// and [`MAX_ADDRESS_TXS_PAGE_SIZE`](crate::address_index::MAX_ADDRESS_TXS_PAGE_SIZE)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    #[serde(default)]
+    pub filter: AddressTxFilter,
+}
+
+/// Native transfers waiting for inclusion, which send to or from a public account. Served by
+/// sequencers with [`SequencerFeature::PendingTransfers`].
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetPendingTransfersRequest {
+    pub account_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetChainStatsRequest {
+    /// Oldest block the state may be read at, see [`ReadContext`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_block_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetChainInfoRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetApiVersionRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetSequencerInfoRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetTransactionStatusRequest {
+    /// Hex encoded transaction hash
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PreviewNextBlockRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBlockProofRequest {
+    pub block_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetSyncStatusRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetPriorityFeesRequest {}
+
+/// Summaries of all transactions waiting for inclusion. Served by sequencers with
+/// [`SequencerFeature::PendingTransactions`].
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetPendingTransactionsRequest {}
+
+/// Roots of the commitment set, which private transactions may be proven against
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetLatestAnchorsRequest {}
+
+/// Maximum number of headers in one `get_block_headers` request
+pub const MAX_BLOCK_HEADERS_PER_REQUEST: u64 = 1024;
+
+/// Signed headers of blocks from `start_block_id` to `end_block_id` inclusive
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBlockHeadersRequest {
+    pub start_block_id: u64,
+    pub end_block_id: u64,
+}
+
+/// Public account with its proof against the state root of the last block
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAccountWithProofRequest {
+    pub account_id: String,
+}
+
+/// Runs admission checks of `send_tx` on the transaction without submitting it
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ValidateTransactionRequest {
+    #[serde(with = "base64_deser")]
+    pub transaction: Vec<u8>,
+}
+
+/// Submits the transaction into the system lane, only served by the admin RPC of the sequencer
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SendSystemTxRequest {
+    #[serde(with = "base64_deser")]
+    pub transaction: Vec<u8>,
+}
+
+/// Verifies indexes of stored transactions against a scan of stored blocks and replaces them
+/// with the rebuilt ones, only served by the admin RPC of the sequencer
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RebuildIndexesRequest {}
+
+/// Mempool lifecycle events of the transaction, only served by the admin RPC of the sequencer
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetTxAuditRequest {
+    /// Hex encoded transaction hash
+    pub hash: String,
+}
+
+parse_request!(HelloRequest);
+parse_request!(RegisterAccountRequest);
+parse_request!(SendTxRequest);
+parse_request!(GetBlockDataRequest);
+parse_request!(GetBlockRangeDataRequest);
+parse_request!(GetGenesisIdRequest);
+parse_request!(GetLastBlockRequest);
+parse_request!(GetInitialTestnetAccountsRequest);
+parse_request!(GetAccountBalanceRequest);
+parse_request!(GetTransactionByHashRequest);
+parse_request!(GetAccountsNoncesRequest);
+parse_request!(GetProofForCommitmentRequest);
+parse_request!(GetAccountRequest);
+parse_request!(GetAccountsRequest);
+parse_request!(GetProgramIdsRequest);
+parse_request!(GetBlockExpandedRequest);
+parse_request!(GetAddressSummaryRequest);
+parse_request!(GetTransactionsByAddressRequest);
+parse_request!(GetPendingTransfersRequest);
+parse_request!(GetChainStatsRequest);
+parse_request!(GetChainInfoRequest);
+parse_request!(GetApiVersionRequest);
+parse_request!(GetSequencerInfoRequest);
+parse_request!(GetTransactionStatusRequest);
+parse_request!(PreviewNextBlockRequest);
+parse_request!(GetBlockProofRequest);
+parse_request!(GetSyncStatusRequest);
+parse_request!(GetPriorityFeesRequest);
+parse_request!(GetPendingTransactionsRequest);
+parse_request!(GetLatestAnchorsRequest);
+parse_request!(GetBlockHeadersRequest);
+parse_request!(GetAccountWithProofRequest);
+parse_request!(ValidateTransactionRequest);
+parse_request!(SendSystemTxRequest);
+parse_request!(RebuildIndexesRequest);
+parse_request!(GetTxAuditRequest);
+
+/// Snapshot of the state, which a read is served from
+///
+/// Reads of different calls may be served from different blocks. A client, which combines them,
+/// passes the block of the first read as `min_block_id` of the following ones, so they aren't
+/// served from an older state. Sequencer waits a bounded time for a block it hasn't applied yet
+/// and fails with [`RpcError::stale_snapshot`](crate::rpc_primitives::errors::RpcError::stale_snapshot)
+/// if it doesn't get there.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReadContext {
+    /// Last block applied to the state
+    pub block_id: u64,
+    /// Hex encoded root of the state, see [`nssa::V02State::state_root`]
+    pub state_root: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HelloResponse {
+    pub greeting: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RegisterAccountResponse {
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SendTxResponse {
+    pub status: String,
+    pub tx_hash: String,
+    /// Position of the transaction among pending ones under the ordering policy, starting from 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mempool_position: Option<u64>,
+    /// Number of pending transactions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mempool_depth: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_inclusion_block: Option<u64>,
+    /// Status of the transaction after submission
+    ///
+    /// Submission is idempotent, so resubmitting a pending or included transaction succeeds and
+    /// reports its existing status.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_status: Option<TransactionStatus>,
+    /// Promise of the sequencer to include the transaction, given on its admission
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_confirmation: Option<SoftConfirmation>,
+    /// Id of the program of a deployment, computed from its image, e.g. `prog_1a2b...`
+    ///
+    /// Deployments of programs, which are deployed already, aren't submitted, so they have the
+    /// id of the existing program, but no `tx_status`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program_id: Option<String>,
+}
+
+impl SendTxResponse {
+    /// Whether the transaction deploys a program, which is deployed already, so it isn't
+    /// submitted
+    pub fn is_already_deployed(&self) -> bool {
+        self.program_id.is_some() && self.tx_status.is_none()
+    }
+}
+
+/// Verdict of `send_tx` on a transaction, which isn't submitted
+///
+/// Transactions, which fail admission checks, are rejected with the same error as on submission.
+/// Congestion of the mempool isn't predicted, submission may still be asked to retry later.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ValidateTransactionResponse {
+    /// Status `send_tx` would respond with
+    pub status: String,
+    pub tx_hash: String,
+    /// Current status of a transaction, which is pending or included already
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_status: Option<TransactionStatus>,
+    /// Id of the program of a deployment, see [`SendTxResponse::program_id`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program_id: Option<String>,
+}
+
+/// What the sequencer advertises about a block, so clients can cheaply check that the block
+/// they fetched is the advertised one before validating it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockDigest {
+    /// Hex encoded hash of the block
+    ///
+    /// Block hash commits to the hash of the previous block, so it's an accumulator of the whole
+    /// chain up to the block.
+    pub hash: String,
+    /// Number of transactions in the block
+    pub tx_count: u64,
+}
+
+impl BlockDigest {
+    pub fn new(hash: &BlockHash, tx_count: usize) -> Self {
+        Self {
+            hash: hex::encode(hash),
+            tx_count: tx_count as u64,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBlockDataResponse {
+    #[serde(with = "base64_deser")]
+    pub block: Vec<u8>,
+    /// Not advertised by sequencers older than API 2.3
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<BlockDigest>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBlockRangeDataResponse {
+    #[serde(with = "base64_deser::vec")]
+    pub blocks: Vec<Vec<u8>>,
+    /// Digests of `blocks` in the same order, not advertised by sequencers older than API 2.3
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub digests: Vec<BlockDigest>,
+}
+
+mod base64_deser {
+    use base64::{Engine as _, engine::general_purpose};
+    use serde::{self, Deserialize, Deserializer, Serializer, ser::SerializeSeq as _};
+
+    pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let base64_string = general_purpose::STANDARD.encode(bytes);
+        serializer.serialize_str(&base64_string)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let base64_string: String = Deserialize::deserialize(deserializer)?;
+        general_purpose::STANDARD
+            .decode(&base64_string)
+            .map_err(serde::de::Error::custom)
+    }
+
+    pub mod vec {
+        use super::*;
+
+        pub fn serialize<S>(bytes_vec: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut seq = serializer.serialize_seq(Some(bytes_vec.len()))?;
+            for bytes in bytes_vec {
+                let s = general_purpose::STANDARD.encode(bytes);
+                seq.serialize_element(&s)?;
+            }
+            seq.end()
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let base64_strings: Vec<String> = Deserialize::deserialize(deserializer)?;
+            base64_strings
+                .into_iter()
+                .map(|s| {
+                    general_purpose::STANDARD
+                        .decode(&s)
+                        .map_err(serde::de::Error::custom)
+                })
+                .collect()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetGenesisIdResponse {
+    pub genesis_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetLastBlockResponse {
+    pub last_block: u64,
+    /// Digest of the last block, not advertised by sequencers older than API 2.3
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_block_digest: Option<BlockDigest>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAccountBalanceResponse {
+    #[serde(with = "decimal_string")]
+    pub balance: Amount,
+    /// Snapshot the response is read from, not reported by sequencers older than API 2.10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<ReadContext>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAccountsNoncesResponse {
+    #[serde(with = "safe_integer::vec")]
+    pub nonces: Vec<Nonce>,
+    /// Snapshot the response is read from, not reported by sequencers older than API 2.10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<ReadContext>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetTransactionByHashResponse {
+    pub transaction: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAccountResponse {
+    #[serde(with = "account_json")]
+    pub account: nssa::Account,
+    /// Part of the balance locked for the data of the account, which can't be spent until the
+    /// data is removed. Zero if reported by sequencer older than API 2.5
+    #[serde(default, with = "decimal_string")]
+    pub storage_deposit: Amount,
+    /// Snapshot the response is read from, not reported by sequencers older than API 2.10 and
+    /// for accounts of [`GetAccountsResponse`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<ReadContext>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetBlockHeadersResponse {
+    /// In order of block ids, ending early at the last block
+    pub headers: Vec<SignedBlockHeader>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetAccountWithProofResponse {
+    #[serde(with = "account_json")]
+    pub account: nssa::Account,
+    /// Borsh encoded [`nssa::account_tree::AccountProof`] of the account
+    #[serde(with = "base64_deser")]
+    pub proof: Vec<u8>,
+    /// Hex encoded digest of the commitment set, which the state root also covers
+    pub commitment_set_digest: String,
+    /// Header of the last block with the state root, which the proof leads to
+    pub header: SignedBlockHeader,
+}
+
+impl GetAccountWithProofResponse {
+    /// State root, which the proof leads to with the served account, `None` if the proof is
+    /// malformed
+    ///
+    /// The account is proven if it's the state root of the header.
+    pub fn proven_state_root(&self, account_id: &nssa::AccountId) -> Option<HashType> {
+        let proof = borsh::from_slice::<nssa::account_tree::AccountProof>(&self.proof).ok()?;
+        let commitment_set_digest = hex::decode(&self.commitment_set_digest)
+            .ok()?
+            .try_into()
+            .ok()?;
+        let account_tree_root = proof.root(account_id, &self.account)?;
+        Some(nssa::V02State::state_root_of(
+            &account_tree_root,
+            &commitment_set_digest,
+        ))
+    }
+}
+
+/// Accounts in order of the requested ids
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAccountsResponse {
+    pub accounts: Vec<GetAccountResponse>,
+    /// Snapshot the response is read from, not reported by sequencers older than API 2.10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<ReadContext>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetProofForCommitmentResponse {
+    pub membership_proof: Option<nssa_core::MembershipProof>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetProgramIdsResponse {
+    pub program_ids: HashMap<String, ProgramId>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBlockExpandedResponse {
+    pub block: ExpandedBlock,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAddressSummaryResponse {
+    #[serde(with = "decimal_string")]
+    pub balance: Amount,
+    /// Number of transactions touching the account
+    pub tx_count: u64,
+    pub first_seen_block: Option<u64>,
+    pub last_seen_block: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetTransactionsByAddressResponse {
+    /// From the newest one
+    pub transactions: Vec<AddressTransaction>,
+    pub has_more: bool,
+    /// Cursor of the next page, `null` if there are no more transactions
+    pub next_cursor: Option<AddressTxCursor>,
+}
+
+impl From<AddressTxPage> for GetTransactionsByAddressResponse {
+    fn from(page: AddressTxPage) -> Self {
+        Self {
+            transactions: page.entries.into_iter().map(Into::into).collect(),
+            has_more: page.next_cursor.is_some(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AddressTransaction {
+    /// Hex encoded transaction hash
+    pub hash: String,
+    pub block_id: u64,
+    /// Called program, known only for public transactions
+    pub program_id: Option<ProgramId>,
+    /// Direction of a native transfer of the account
+    pub direction: Option<TxDirection>,
+    /// Amount of a native transfer of the account
+    #[serde(default, with = "decimal_string::option")]
+    pub amount: Option<Amount>,
+}
+
+impl From<AddressTxEntry> for AddressTransaction {
+    fn from(entry: AddressTxEntry) -> Self {
+        Self {
+            hash: hex::encode(entry.hash),
+            block_id: entry.position.block_id,
+            program_id: entry.program_id,
+            direction: entry.transfer.map(|(direction, _)| direction),
+            amount: entry.transfer.map(|(_, amount)| amount),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetPendingTransfersResponse {
+    /// In order of inclusion under the ordering policy
+    pub transfers: Vec<PendingTransfer>,
+}
+
+/// Rejection of a submission, which value and fees exceed the balance of its sender not reserved
+/// by transactions of the sender waiting for inclusion, see
+/// [`NSSATransaction::max_sender_outflow`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BalanceShortfall {
+    pub sender: String,
+    /// Balance of the sender at the last block
+    #[serde(with = "decimal_string")]
+    pub balance: Amount,
+    /// Part of the balance reserved by `pending`
+    #[serde(with = "decimal_string")]
+    pub reserved: Amount,
+    /// Value and fees of the rejected transaction
+    #[serde(with = "decimal_string")]
+    pub required: Amount,
+    /// Transactions of the sender waiting for inclusion, which reserve the balance
+    pub pending: Vec<BalanceReservation>,
+}
+
+/// Balance reserved by a transaction waiting for inclusion
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BalanceReservation {
+    /// Hex encoded transaction hash
+    pub hash: String,
+    #[serde(with = "decimal_string")]
+    pub amount: Amount,
+}
+
+impl Display for BalanceShortfall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Insufficient unreserved balance of {}: transaction requires {}, balance is {}, {} of \
+             it reserved by {} pending transactions",
+            self.sender,
+            self.required,
+            self.balance,
+            self.reserved,
+            self.pending.len()
+        )?;
+        for reservation in &self.pending {
+            write!(
+                f,
+                "\n  {} reserves {}",
+                reservation.hash, reservation.amount
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Native transfer waiting for inclusion
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingTransfer {
+    /// Hex encoded transaction hash
+    pub hash: String,
+    pub sender: String,
+    pub recipient: String,
+    #[serde(with = "decimal_string")]
+    pub amount: Amount,
+}
+
+impl PendingTransfer {
+    /// Transfer of `tx` with hash `hash`, if it's a public native transfer
+    pub fn from_transaction(hash: &HashType, tx: &NSSATransaction) -> Option<Self> {
+        let NSSATransaction::Public(public_tx) = tx else {
+            return None;
+        };
+        match NativeTransfer::decode(public_tx.message())? {
+            NativeTransfer::Transfer {
+                sender,
+                recipient,
+                amount,
+            } => Some(Self {
+                hash: hex::encode(hash),
+                sender: sender.to_string(),
+                recipient: recipient.to_string(),
+                amount,
+            }),
+            NativeTransfer::Initialize { .. } => None,
+        }
+    }
+
+    /// Whether `account_id` sends or receives the transfer
+    pub fn involves(&self, account_id: &str) -> bool {
+        self.sender == account_id || self.recipient == account_id
+    }
+}
+
+/// Size of public state, empty accounts are not stored
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StateSize {
+    pub accounts: u64,
+    /// Total size of data of public accounts
+    pub data_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetChainStatsResponse {
+    pub last_block: u64,
+    pub tx_count: u64,
+    /// Total supply of the native token, including shielded one
+    #[serde(with = "decimal_string")]
+    pub total_supply: Amount,
+    /// Zero if reported by sequencer older than API 1.7
+    #[serde(default)]
+    pub state_size: StateSize,
+    /// Balance moved to the burn account, which is included into the total supply. Zero if
+    /// reported by sequencer older than API 2.2
+    #[serde(default, with = "decimal_string")]
+    pub total_burned: Amount,
+    /// Balance locked as storage deposits of public accounts, which is included into the total
+    /// supply. Zero if reported by sequencer older than API 2.5
+    #[serde(default, with = "decimal_string")]
+    pub total_storage_deposit: Amount,
+    /// Failed attempts to produce a block since the sequencer started. Zero if reported by
+    /// sequencer older than API 2.6
+    #[serde(default)]
+    pub failed_block_attempts: u64,
+    /// Alerts of block production stalled with pending transactions since the sequencer
+    /// started. Zero if reported by sequencer older than API 2.6
+    #[serde(default)]
+    pub block_production_stall_alerts: u64,
+    /// Snapshot the response is read from, not reported by sequencers older than API 2.10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<ReadContext>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetChainInfoResponse {
+    pub genesis_id: u64,
+    pub last_block: u64,
+    /// Maximum number of transactions in the next block
+    pub max_num_tx_in_block: usize,
+    /// Version of chain parameters, which the next block is built with. Zero if reported by
+    /// sequencer older than API 2.4.
+    #[serde(default)]
+    pub params_version: ParamsVersion,
+    /// Name of the policy, which defines order of transactions in blocks
+    pub tx_ordering_policy: String,
+    /// Programs, which transactions may call, `None` if reported by sequencer older than API 2.1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program_allow_list: Option<nssa::ProgramAllowList>,
+    /// Changes of chain parameters, which the operator forced over stored blocks, in order of
+    /// heights. Empty if reported by sequencer older than API 2.15.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forced_params_changes: Vec<ForcedParamsChange>,
+    /// Storage deposit per byte of account data, which the next block is built with. Transfers
+    /// creating accounts move at least
+    /// [`min_account_creation_amount`](nssa::public_transaction::min_account_creation_amount)
+    /// of it. Zero if reported by sequencer older than API 3.1.
+    #[serde(default, with = "decimal_string")]
+    pub storage_deposit_per_byte: Amount,
+}
+
+/// Change of genesis and chain parameters of a running chain, which doesn't follow the
+/// configured upgrades
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ForcedParamsChange {
+    /// Last block built with the previous parameters
+    pub block_id: u64,
+    /// Hex encoded fingerprint of the previous parameters
+    pub previous_fingerprint: String,
+    /// Hex encoded fingerprint of the new parameters
+    pub fingerprint: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetApiVersionResponse {
+    pub api_version: ApiVersion,
+    /// Oldest client version the server can talk to
+    pub min_supported_api_version: ApiVersion,
+}
+
+impl GetApiVersionResponse {
+    /// Compatibility of a client of `client_version`, supporting servers from
+    /// `client_min_supported_version`, with the server
+    pub fn compatibility(
+        &self,
+        client_version: ApiVersion,
+        client_min_supported_version: ApiVersion,
+    ) -> ApiCompatibility {
+        if client_version < self.min_supported_api_version
+            || self.api_version < client_min_supported_version
+        {
+            ApiCompatibility::Incompatible
+        } else if client_version.major != self.api_version.major {
+            ApiCompatibility::MajorMismatch
+        } else {
+            ApiCompatibility::Compatible
+        }
+    }
+}
+
+/// Optional functionality of a sequencer
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SequencerFeature {
+    /// Blocks are proven in background, see `get_block_proof`
+    LazyProofs,
+    PrivateTransactions,
+    /// Many accounts are served at once, see `get_accounts`
+    BatchAccounts,
+    /// Native transfers waiting for inclusion are served by account, see
+    /// `get_pending_transfers`. Opt-in, as it reveals pending transactions before inclusion.
+    PendingTransfers,
+    /// Summaries of all transactions waiting for inclusion are served, see
+    /// `get_pending_transactions`. Opt-in for the same reason.
+    PendingTransactions,
+    /// Feature unknown to this build
+    #[serde(other)]
+    Unknown,
+}
+
+impl Display for SequencerFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::LazyProofs => "lazy_proofs",
+            Self::PrivateTransactions => "private_transactions",
+            Self::BatchAccounts => "batch_accounts",
+            Self::PendingTransfers => "pending_transfers",
+            Self::PendingTransactions => "pending_transactions",
+            Self::Unknown => "unknown",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetSequencerInfoResponse {
+    /// Version of the sequencer build
+    pub version: String,
+    pub api_version: ApiVersion,
+    /// Tags of transaction kinds the sequencer accepts, see [`TxKind::tag`]
+    pub tx_kinds: Vec<u8>,
+    pub features: Vec<SequencerFeature>,
+    /// Hex encoded public key, which signs blocks and soft confirmations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// Hex encoded hash of the genesis block, which identifies the chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genesis_hash: Option<String>,
+    /// Id of the chain, which program deployments must be signed for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+}
+
+impl GetSequencerInfoResponse {
+    /// Transaction kinds accepted by the sequencer, which this build can't decode
+    pub fn unsupported_tx_kinds(&self) -> Vec<TxKind> {
+        self.tx_kinds
+            .iter()
+            .map(|tag| TxKind::from_tag(*tag))
+            .filter(|kind| !kind.is_supported())
+            .collect()
+    }
+}
+
+/// Status of a submitted transaction as seen by the sequencer
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum TransactionStatus {
+    /// Transaction waits for inclusion
+    Pending {
+        /// Position among pending transactions under the ordering policy, starting from 0
+        position: u64,
+        /// Number of pending transactions
+        depth: u64,
+        estimated_inclusion_block: u64,
+        /// Estimated time until inclusion, derived from the block interval
+        estimated_wait_millis: u64,
+    },
+    Included {
+        block_id: u64,
+        /// Balance changes of touched accounts, known if the sequencer kept the receipt
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        receipt: Option<TransactionReceipt>,
+    },
+    /// Transaction was rejected by the sequencer and will never be included
+    Dropped { reason: String },
+    /// Sequencer never saw the transaction or already forgot it
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetTransactionStatusResponse {
+    pub status: TransactionStatus,
+}
+
+/// Block, which would be produced from the currently pending transactions
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PreviewNextBlockResponse {
+    pub block_id: u64,
+    /// Hex encoded hashes of transactions in order of application
+    pub tx_hashes: Vec<String>,
+    pub total_gas: u64,
+}
+
+/// Proving status of a block
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum BlockProofStatus {
+    /// Block is committed and waits for proving
+    Pending,
+    Proven {
+        /// Base64 encoded borsh serialized block proof
+        proof: String,
+    },
+    Failed {
+        reason: String,
+    },
+    /// Block doesn't exist or was committed without lazy proving
+    NotRequested,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBlockProofResponse {
+    pub status: BlockProofStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    /// Sequencer is applying stored blocks and doesn't produce new ones yet
+    Syncing,
+    Synced,
+}
+
+/// Progress of catching up with the chain tip
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SyncStatus {
+    pub state: SyncState,
+    /// Height the sync started from
+    pub start_height: u64,
+    pub current_height: u64,
+    pub target_height: u64,
+    /// Applied blocks per second over the recent window
+    pub blocks_per_sec: f64,
+    /// Estimated seconds left, `None` if the rate isn't known yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eta_secs: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetSyncStatusResponse {
+    pub status: SyncStatus,
+}
+
+/// Root of the commitment set at the start of a block, see [`nssa::Anchor`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommitmentAnchor {
+    pub block_id: u64,
+    /// Hex encoded digest of the commitment set
+    pub digest: String,
+}
+
+impl From<nssa::Anchor> for CommitmentAnchor {
+    fn from(anchor: nssa::Anchor) -> Self {
+        Self {
+            block_id: anchor.block_id,
+            digest: hex::encode(anchor.digest),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetLatestAnchorsResponse {
+    /// Number of the latest blocks, which anchors are accepted. Transactions proven against
+    /// older ones are rejected and must be proven again.
+    pub anchor_window: u64,
+    /// From the newest one
+    pub anchors: Vec<CommitmentAnchor>,
+}
+
+/// Priority fees of pending transactions, all zeros if there are none
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PriorityFeeDistribution {
+    /// Number of pending transactions
+    pub pending: u64,
+    #[serde(with = "decimal_string")]
+    pub min: Amount,
+    #[serde(with = "decimal_string")]
+    pub median: Amount,
+    #[serde(with = "decimal_string")]
+    pub max: Amount,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetPriorityFeesResponse {
+    pub distribution: PriorityFeeDistribution,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetPendingTransactionsResponse {
+    /// Last block at the time of the snapshot
+    pub last_block: u64,
+    /// In order of inclusion under the ordering policy, followed by parked ones
+    pub transactions: Vec<PendingTransactionSummary>,
+}
+
+/// Transaction waiting for inclusion
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingTransactionSummary {
+    /// Hex encoded transaction hash
+    pub hash: String,
+    /// First signer, `None` for transactions without signers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// Nonce of the sender, which the transaction consumes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Nonce>,
+    #[serde(with = "decimal_string")]
+    pub priority_fee: Amount,
+}
+
+impl PendingTransactionSummary {
+    pub fn from_transaction(hash: &HashType, tx: &NSSATransaction) -> Self {
+        let (sender, nonce) = tx.sender_and_nonce().unzip();
+        Self {
+            hash: hex::encode(hash),
+            sender: sender.map(|sender| sender.to_string()),
+            nonce,
+            priority_fee: tx.priority_fee(),
+        }
+    }
+}
+
+/// Entries of an index of stored transactions, which differ from the ones rebuilt from stored
+/// blocks
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexDiscrepancies {
+    /// Entries of stored transactions, which the index lacks
+    pub missing: u64,
+    /// Entries, which don't correspond to any stored transaction
+    pub extra: u64,
+    /// Entries of stored transactions, which have other values in the index
+    pub mismatched: u64,
+}
+
+impl IndexDiscrepancies {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Discrepancies of indexes of stored transactions found by `rebuild_indexes`, which replaced
+/// them with the rebuilt ones
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexReport {
+    /// Last stored block, which indexes are rebuilt up to
+    pub last_block: u64,
+    pub scanned_blocks: u64,
+    /// Blocks including transactions by hash
+    pub tx_hashes: IndexDiscrepancies,
+    /// Transactions by public accounts they touch
+    pub address_txs: IndexDiscrepancies,
+    /// Aggregated activity of public accounts
+    pub account_activity: IndexDiscrepancies,
+    pub receipts: IndexDiscrepancies,
+}
+
+impl IndexReport {
+    /// Whether indexes matched stored blocks before they were rebuilt
+    pub fn is_consistent(&self) -> bool {
+        self.tx_hashes.is_empty()
+            && self.address_txs.is_empty()
+            && self.account_activity.is_empty()
+            && self.receipts.is_empty()
+    }
+}
+
+/// Event in the mempool lifecycle of a transaction, recorded by the sequencer for `get_tx_audit`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TxAuditEvent {
+    /// Transaction was taken from mempool to wait for inclusion
+    Admitted {
+        #[serde(with = "decimal_string")]
+        priority_fee: Amount,
+        /// Position among transactions waiting for inclusion right after admission, as in
+        /// [`TransactionStatus::Pending`]
+        position: u64,
+    },
+    /// Transaction was dropped and will never be included
+    Evicted {
+        reason: String,
+    },
+    /// Private transaction was dropped, as its anchor left the anchor window while it waited
+    Expired {
+        reason: String,
+    },
+    /// Transaction was dropped, as another one of the same sender and nonce was included
+    Replaced {
+        /// Hex encoded hash of the included transaction
+        by: String,
+    },
+    Included {
+        block_id: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxAuditRecord {
+    /// Unix time of the event in milliseconds
+    pub timestamp_millis: u64,
+    #[serde(flatten)]
+    pub event: TxAuditEvent,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetTxAuditResponse {
+    /// Events in order of recording, empty if the sequencer never saw the transaction or already
+    /// forgot it
+    pub events: Vec<TxAuditRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetInitialTestnetAccountsResponse {
+    /// Hex encoded account id
+    pub account_id: String,
+    #[serde(with = "decimal_string")]
+    pub balance: u64,
+}
+
+/// Account with balance and nonce readable by JavaScript clients, see [`crate::json_number`]
+///
+/// [`nssa::Account`] itself keeps plain numbers, as it's also passed to programs.
+mod account_json {
+    use nssa_core::account::{Account, Data};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct AccountJson {
+        program_owner: ProgramId,
+        #[serde(with = "decimal_string")]
+        balance: Amount,
+        data: Data,
+        #[serde(with = "safe_integer")]
+        nonce: Nonce,
+    }
+
+    pub fn serialize<S: Serializer>(account: &Account, serializer: S) -> Result<S::Ok, S::Error> {
+        AccountJson {
+            program_owner: account.program_owner,
+            balance: account.balance,
+            data: account.data.clone(),
+            nonce: account.nonce,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Account, D::Error> {
+        let account = AccountJson::deserialize(deserializer)?;
+        Ok(Account {
+            program_owner: account.program_owner,
+            balance: account.balance,
+            data: account.data,
+            nonce: account.nonce,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Checks that `snapshot` is exactly the wire format of `T`
+    fn assert_wire_format<T: Serialize + DeserializeOwned>(snapshot: Value) {
+        let value: T = serde_json::from_value(snapshot.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&value).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_request_wire_format() {
+        assert_wire_format::<HelloRequest>(json!({}));
+        assert_wire_format::<RegisterAccountRequest>(json!({ "account_id": vec![7u8; 32] }));
+        assert_wire_format::<SendTxRequest>(json!({ "transaction": "AQID" }));
+        assert_wire_format::<GetBlockDataRequest>(json!({ "block_id": 3 }));
+        assert_wire_format::<GetBlockRangeDataRequest>(
+            json!({ "start_block_id": 1, "end_block_id": 3 }),
+        );
+        assert_wire_format::<GetGenesisIdRequest>(json!({}));
+        assert_wire_format::<GetLastBlockRequest>(json!({}));
+        assert_wire_format::<GetInitialTestnetAccountsRequest>(json!({}));
+        assert_wire_format::<GetAccountBalanceRequest>(json!({ "account_id": "abc" }));
+        assert_wire_format::<GetAccountBalanceRequest>(
+            json!({ "account_id": "abc", "min_block_id": 7 }),
+        );
+        assert_wire_format::<GetTransactionByHashRequest>(json!({ "hash": "00ff" }));
+        assert_wire_format::<GetAccountsNoncesRequest>(json!({ "account_ids": ["abc", "def"] }));
+        assert_wire_format::<GetAccountRequest>(json!({ "account_id": "abc" }));
+        assert_wire_format::<GetAccountsRequest>(json!({ "account_ids": ["abc", "def"] }));
+        assert_wire_format::<GetAccountsRequest>(
+            json!({ "account_ids": ["abc"], "min_block_id": 7 }),
+        );
+        assert_wire_format::<GetProofForCommitmentRequest>(json!({ "commitment": vec![5u8; 32] }));
+        assert_wire_format::<GetProgramIdsRequest>(json!({}));
+        assert_wire_format::<GetBlockExpandedRequest>(json!({ "block_id": 3 }));
+        assert_wire_format::<GetAddressSummaryRequest>(json!({ "account_id": "abc" }));
+        assert_wire_format::<GetTransactionsByAddressRequest>(
+            json!({ "account_id": "abc", "filter": {} }),
+        );
+        assert_wire_format::<GetTransactionsByAddressRequest>(json!({
+            "account_id": "abc",
+            "cursor": "000000000000000700000002",
+            "limit": 50,
+            "filter": {
+                "direction": "in",
+                "min_amount": "10",
+                "from_block": 1,
+                "to_block": 9,
+                "program_id": "prog_0100000002000000030000000400000005000000060000000700000008000000"
+            }
+        }));
+        assert_wire_format::<GetPendingTransfersRequest>(json!({ "account_id": "abc" }));
+        assert_wire_format::<GetChainStatsRequest>(json!({}));
+        assert_wire_format::<GetChainStatsRequest>(json!({ "min_block_id": 7 }));
+        assert_wire_format::<GetChainInfoRequest>(json!({}));
+        assert_wire_format::<GetApiVersionRequest>(json!({}));
+        assert_wire_format::<GetSequencerInfoRequest>(json!({}));
+        assert_wire_format::<GetTransactionStatusRequest>(json!({ "hash": "00ff" }));
+        assert_wire_format::<PreviewNextBlockRequest>(json!({}));
+        assert_wire_format::<GetBlockProofRequest>(json!({ "block_id": 7 }));
+        assert_wire_format::<GetSyncStatusRequest>(json!({}));
+        assert_wire_format::<GetPriorityFeesRequest>(json!({}));
+        assert_wire_format::<GetPendingTransactionsRequest>(json!({}));
+        assert_wire_format::<GetBlockHeadersRequest>(
+            json!({ "start_block_id": 1, "end_block_id": 3 }),
+        );
+        assert_wire_format::<GetAccountWithProofRequest>(json!({ "account_id": "abc" }));
+        assert_wire_format::<ValidateTransactionRequest>(json!({ "transaction": "AQID" }));
+        assert_wire_format::<SendSystemTxRequest>(json!({ "transaction": "AQID" }));
+        assert_wire_format::<RebuildIndexesRequest>(json!({}));
+        assert_wire_format::<GetTxAuditRequest>(json!({ "hash": "00ff" }));
+        assert_wire_format::<GetLatestAnchorsRequest>(json!({}));
+    }
+
+    #[test]
+    fn test_response_wire_format() {
+        assert_wire_format::<HelloResponse>(json!({ "greeting": "hi" }));
+        assert_wire_format::<RegisterAccountResponse>(json!({ "status": "ok" }));
+        assert_wire_format::<SendTxResponse>(json!({ "status": "ok", "tx_hash": "00ff" }));
+        assert_wire_format::<SendTxResponse>(json!({
+            "status": "ok",
+            "tx_hash": "00ff",
+            "mempool_position": 3,
+            "mempool_depth": 5,
+            "estimated_inclusion_block": 7
+        }));
+        assert_wire_format::<SendTxResponse>(json!({
+            "status": "ok",
+            "tx_hash": "00ff",
+            "tx_status": { "state": "included", "block_id": 7 }
+        }));
+        assert_wire_format::<SendTxResponse>(json!({
+            "status": "ok",
+            "tx_hash": "00ff",
+            "soft_confirmation": {
+                "tx_hash": "00ff",
+                "received_at": 1700000000000_u64,
+                "promised_by_block": 12,
+                "signature": "abcd"
+            }
+        }));
+        assert_wire_format::<ValidateTransactionResponse>(
+            json!({ "status": "ok", "tx_hash": "00ff" }),
+        );
+        assert_wire_format::<ValidateTransactionResponse>(json!({
+            "status": "ok",
+            "tx_hash": "00ff",
+            "tx_status": { "state": "pending", "position": 0, "depth": 1,
+                "estimated_inclusion_block": 7, "estimated_wait_millis": 1000 }
+        }));
+        assert_wire_format::<GetBlockDataResponse>(json!({ "block": "AQID" }));
+        assert_wire_format::<GetBlockDataResponse>(json!({
+            "block": "AQID",
+            "digest": { "hash": "00ff", "tx_count": 2 }
+        }));
+        assert_wire_format::<GetBlockRangeDataResponse>(json!({ "blocks": ["AQID", "BA=="] }));
+        assert_wire_format::<GetBlockRangeDataResponse>(json!({
+            "blocks": ["AQID"],
+            "digests": [{ "hash": "00ff", "tx_count": 2 }]
+        }));
+        assert_wire_format::<GetGenesisIdResponse>(json!({ "genesis_id": 1 }));
+        assert_wire_format::<GetLastBlockResponse>(json!({ "last_block": 3 }));
+        assert_wire_format::<GetLastBlockResponse>(json!({
+            "last_block": 3,
+            "last_block_digest": { "hash": "00ff", "tx_count": 0 }
+        }));
+        assert_wire_format::<GetAccountBalanceResponse>(json!({ "balance": "100" }));
+        assert_wire_format::<GetAccountsNoncesResponse>(json!({ "nonces": [0, 5] }));
+        assert_wire_format::<GetAccountsNoncesResponse>(json!({
+            "nonces": [0, 5],
+            "context": { "block_id": 7, "state_root": "00ff" }
+        }));
+        assert_wire_format::<GetTransactionByHashResponse>(json!({ "transaction": "AQID" }));
+        assert_wire_format::<GetTransactionByHashResponse>(json!({ "transaction": null }));
+        assert_wire_format::<GetAccountResponse>(json!({
+            "account": {
+                "program_owner": "prog_0100000002000000030000000400000005000000060000000700000008000000",
+                "balance": "100",
+                "data": [1, 2],
+                "nonce": 5
+            },
+            "storage_deposit": "20"
+        }));
+        assert_wire_format::<GetAccountsResponse>(json!({
+            "accounts": [{
+                "account": {
+                    "program_owner": "prog_0100000002000000030000000400000005000000060000000700000008000000",
+                    "balance": "100",
+                    "data": [],
+                    "nonce": 5
+                },
+                "storage_deposit": "0"
+            }]
+        }));
+        assert_wire_format::<GetProofForCommitmentResponse>(
+            json!({ "membership_proof": [3, [vec![1u8; 32]]] }),
+        );
+        assert_wire_format::<GetProofForCommitmentResponse>(json!({ "membership_proof": null }));
+        assert_wire_format::<GetProgramIdsResponse>(
+            json!({ "program_ids": { "token": "prog_0100000002000000030000000400000005000000060000000700000008000000" } }),
+        );
+        assert_wire_format::<GetBlockExpandedResponse>(json!({
+            "block": {
+                "block_id": 2,
+                "prev_block_hash": "00ff",
+                "hash": "ff00",
+                "timestamp": 1000,
+                "transactions": [{
+                    "hash": "abcd",
+                    "tx_kind": "Public",
+                    "program_id": "prog_0100000002000000030000000400000005000000060000000700000008000000",
+                    "account_ids": ["abc", "def"],
+                    "transfer": { "sender": "abc", "recipient": "def", "amount": "10" },
+                    "instruction": { "call": "native_transfer", "amount": "10" }
+                }]
+            }
+        }));
+        assert_wire_format::<GetAddressSummaryResponse>(json!({
+            "balance": "100",
+            "tx_count": 2,
+            "first_seen_block": 1,
+            "last_seen_block": null
+        }));
+        assert_wire_format::<GetTransactionsByAddressResponse>(json!({
+            "transactions": [{
+                "hash": "abcd",
+                "block_id": 7,
+                "program_id": "prog_0100000002000000030000000400000005000000060000000700000008000000",
+                "direction": "out",
+                "amount": "10"
+            }, {
+                "hash": "ef01",
+                "block_id": 6,
+                "program_id": null,
+                "direction": null,
+                "amount": null
+            }],
+            "has_more": true,
+            "next_cursor": "000000000000000600000000"
+        }));
+        assert_wire_format::<GetTransactionsByAddressResponse>(
+            json!({ "transactions": [], "has_more": false, "next_cursor": null }),
+        );
+        assert_wire_format::<GetPendingTransfersResponse>(json!({
+            "transfers": [{
+                "hash": "00ff",
+                "sender": "abc",
+                "recipient": "def",
+                "amount": "340282366920938463463374607431768211455"
+            }]
+        }));
+        assert_wire_format::<GetChainStatsResponse>(json!({
+            "last_block": 3,
+            "tx_count": 2,
+            "total_supply": "30000",
+            "state_size": { "accounts": 4, "data_bytes": 8 },
+            "total_burned": "10",
+            "total_storage_deposit": "16",
+            "failed_block_attempts": 2,
+            "block_production_stall_alerts": 1
+        }));
+        assert_wire_format::<GetChainInfoResponse>(json!({
+            "genesis_id": 1,
+            "last_block": 3,
+            "max_num_tx_in_block": 10,
+            "params_version": 0,
+            "tx_ordering_policy": "fifo",
+            "storage_deposit_per_byte": "0"
+        }));
+        assert_wire_format::<GetChainInfoResponse>(json!({
+            "genesis_id": 1,
+            "last_block": 3,
+            "max_num_tx_in_block": 10,
+            "params_version": 2,
+            "tx_ordering_policy": "fifo",
+            "program_allow_list": {
+                "builtin_programs": ["authenticated_transfer", "pinata"],
+                "deployed_programs": false
+            },
+            "forced_params_changes": [{
+                "block_id": 7,
+                "previous_fingerprint": "00ff",
+                "fingerprint": "ff00"
+            }],
+            "storage_deposit_per_byte": "5"
+        }));
+        assert_wire_format::<GetApiVersionResponse>(json!({
+            "api_version": { "major": 1, "minor": 2 },
+            "min_supported_api_version": { "major": 1, "minor": 0 }
+        }));
+        assert_wire_format::<GetSequencerInfoResponse>(json!({
+            "version": "0.1.0",
+            "api_version": { "major": 1, "minor": 10 },
+            "tx_kinds": [0, 1, 2],
+            "features": ["lazy_proofs", "private_transactions", "batch_accounts", "pending_transfers"]
+        }));
+        assert_wire_format::<GetSequencerInfoResponse>(json!({
+            "version": "0.1.0",
+            "api_version": { "major": 2, "minor": 16 },
+            "tx_kinds": [3, 4, 5],
+            "features": ["pending_transactions"]
+        }));
+        assert_wire_format::<GetSequencerInfoResponse>(json!({
+            "version": "0.1.0",
+            "api_version": { "major": 1, "minor": 12 },
+            "tx_kinds": [0, 1, 2],
+            "features": [],
+            "public_key": "00ff"
+        }));
+        assert_wire_format::<GetSequencerInfoResponse>(json!({
+            "version": "0.1.0",
+            "api_version": { "major": 1, "minor": 13 },
+            "tx_kinds": [0, 1, 2],
+            "features": [],
+            "public_key": "00ff",
+            "genesis_hash": "ff00"
+        }));
+        assert_wire_format::<GetSequencerInfoResponse>(json!({
+            "version": "0.1.0",
+            "api_version": { "major": 2, "minor": 0 },
+            "tx_kinds": [3, 4, 2],
+            "features": [],
+            "public_key": "00ff",
+            "genesis_hash": "ff00"
+        }));
+        assert_wire_format::<GetSequencerInfoResponse>(json!({
+            "version": "0.1.0",
+            "api_version": { "major": 2, "minor": 9 },
+            "tx_kinds": [3, 4, 5],
+            "features": [],
+            "public_key": "00ff",
+            "genesis_hash": "ff00",
+            "chain_id": 7
+        }));
+        assert_wire_format::<GetTransactionStatusResponse>(json!({
+            "status": {
+                "state": "pending",
+                "position": 3,
+                "depth": 5,
+                "estimated_inclusion_block": 7,
+                "estimated_wait_millis": 2000
+            }
+        }));
+        assert_wire_format::<GetTransactionStatusResponse>(
+            json!({ "status": { "state": "included", "block_id": 7 } }),
+        );
+        assert_wire_format::<GetTransactionStatusResponse>(
+            json!({ "status": { "state": "dropped", "reason": "InvalidSignature" } }),
+        );
+        assert_wire_format::<GetTransactionStatusResponse>(
+            json!({ "status": { "state": "unknown" } }),
+        );
+        assert_wire_format::<PreviewNextBlockResponse>(json!({
+            "block_id": 7,
+            "tx_hashes": ["00ff", "ff00"],
+            "total_gas": 300
+        }));
+        assert_wire_format::<GetBlockProofResponse>(json!({ "status": { "state": "pending" } }));
+        assert_wire_format::<GetBlockProofResponse>(json!({
+            "status": { "state": "proven", "proof": "AAEC" }
+        }));
+        assert_wire_format::<GetBlockProofResponse>(json!({
+            "status": { "state": "failed", "reason": "oops" }
+        }));
+        assert_wire_format::<GetBlockProofResponse>(json!({
+            "status": { "state": "not_requested" }
+        }));
+        assert_wire_format::<GetSyncStatusResponse>(json!({
+            "status": {
+                "state": "syncing",
+                "start_height": 1,
+                "current_height": 500,
+                "target_height": 1000,
+                "blocks_per_sec": 12.5,
+                "eta_secs": 40
+            }
+        }));
+        assert_wire_format::<GetSyncStatusResponse>(json!({
+            "status": {
+                "state": "synced",
+                "start_height": 1,
+                "current_height": 1000,
+                "target_height": 1000,
+                "blocks_per_sec": 0.0,
+                "eta_secs": 0
+            }
+        }));
+        assert_wire_format::<GetPriorityFeesResponse>(json!({
+            "distribution": { "pending": 3, "min": "0", "median": "5", "max": "20" }
+        }));
+        assert_wire_format::<GetPendingTransactionsResponse>(json!({
+            "last_block": 7,
+            "transactions": [
+                { "hash": "00ff", "sender": "abc", "nonce": 3, "priority_fee": "5" },
+                { "hash": "ff00", "priority_fee": "0" }
+            ]
+        }));
+        assert_wire_format::<GetLatestAnchorsResponse>(json!({
+            "anchor_window": 100,
+            "anchors": [
+                { "block_id": 8, "digest": "00ff" },
+                { "block_id": 7, "digest": "0f0f" }
+            ]
+        }));
+        let no_discrepancies = json!({ "missing": 0, "extra": 0, "mismatched": 0 });
+        assert_wire_format::<IndexReport>(json!({
+            "last_block": 7,
+            "scanned_blocks": 8,
+            "tx_hashes": { "missing": 1, "extra": 2, "mismatched": 0 },
+            "address_txs": no_discrepancies,
+            "account_activity": no_discrepancies,
+            "receipts": { "missing": 0, "extra": 0, "mismatched": 3 }
+        }));
+        let header = json!({
+            "block_id": 7,
+            "prev_block_hash": "00ff",
+            "hash": "ff00",
+            "timestamp": 1000,
+            "signature": "abcd"
+        });
+        assert_wire_format::<GetBlockHeadersResponse>(json!({ "headers": [header] }));
+        assert_wire_format::<GetAccountWithProofResponse>(json!({
+            "account": {
+                "program_owner": "prog_0100000002000000030000000400000005000000060000000700000008000000",
+                "balance": "100",
+                "data": [],
+                "nonce": 5
+            },
+            "proof": "AQID",
+            "commitment_set_digest": "00ff",
+            "header": {
+                "block_id": 7,
+                "prev_block_hash": "00ff",
+                "hash": "ff00",
+                "timestamp": 1000,
+                "state_root": "0f0f",
+                "signature": "abcd"
+            }
+        }));
+        assert_wire_format::<GetInitialTestnetAccountsResponse>(
+            json!({ "account_id": "abc", "balance": "100" }),
+        );
+        assert_wire_format::<BalanceShortfall>(json!({
+            "sender": "abc",
+            "balance": "100",
+            "reserved": "60",
+            "required": "60",
+            "pending": [{ "hash": "00ff", "amount": "60" }]
+        }));
+        assert_wire_format::<GetTxAuditResponse>(json!({
+            "events": [
+                {
+                    "timestamp_millis": 1000,
+                    "event": "admitted",
+                    "priority_fee": "5",
+                    "position": 0
+                },
+                { "timestamp_millis": 2000, "event": "replaced", "by": "00ff" }
+            ]
+        }));
+    }
+
+    #[test]
+    fn test_balance_shortfall_is_reported_with_breakdown() {
+        let shortfall = BalanceShortfall {
+            sender: "abc".to_owned(),
+            balance: Amount(100),
+            reserved: Amount(60),
+            required: Amount(60),
+            pending: vec![BalanceReservation {
+                hash: "00ff".to_owned(),
+                amount: Amount(60),
+            }],
+        };
+        let err =
+            crate::error::SequencerClientError::InternalError(crate::error::SequencerRpcError {
+                jsonrpc: "2.0".to_owned(),
+                error: crate::rpc_primitives::errors::RpcError::insufficient_unreserved_balance(
+                    &shortfall,
+                ),
+                id: 1,
+            });
+
+        assert_eq!(err.balance_shortfall(), Some(shortfall.clone()));
+        assert_eq!(
+            shortfall.to_string(),
+            "Insufficient unreserved balance of abc: transaction requires 60, balance is 100, 60 \
+             of it reserved by 1 pending transactions\n  00ff reserves 60"
+        );
+    }
+
+    /// Checks that `value` survives JSON round-trip unchanged
+    fn assert_round_trip<T: Serialize + DeserializeOwned + std::fmt::Debug>(value: T) {
+        let json = serde_json::to_value(&value).unwrap();
+        let decoded: T = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), json);
+        assert_eq!(format!("{decoded:?}"), format!("{value:?}"));
+    }
+
+    #[test]
+    fn test_balances_and_nonces_round_trip() {
+        for (balance, nonce) in [
+            (0, 0),
+            (100, 5),
+            (
+                u128::from(crate::json_number::MAX_SAFE_INTEGER) + 1,
+                crate::json_number::MAX_SAFE_INTEGER + 1,
+            ),
+            (u128::MAX, Nonce::MAX),
+        ] {
+            let balance = Amount(balance);
+            assert_round_trip(GetAccountBalanceResponse {
+                balance,
+                context: None,
+            });
+            assert_round_trip(GetAccountsNoncesResponse {
+                nonces: vec![nonce, 0, Nonce::MAX],
+                context: None,
+            });
+            assert_round_trip(GetAccountResponse {
+                account: nssa::Account {
+                    balance,
+                    nonce,
+                    ..Default::default()
+                },
+                storage_deposit: balance,
+                context: None,
+            });
+            assert_round_trip(GetAddressSummaryResponse {
+                balance,
+                tx_count: 2,
+                first_seen_block: Some(1),
+                last_seen_block: None,
+            });
+            assert_round_trip(GetChainStatsResponse {
+                last_block: 3,
+                tx_count: 2,
+                total_supply: balance,
+                state_size: StateSize::default(),
+                total_burned: balance,
+                total_storage_deposit: balance,
+                failed_block_attempts: 2,
+                block_production_stall_alerts: 1,
+                context: None,
+            });
+            assert_round_trip(GetInitialTestnetAccountsResponse {
+                account_id: "abc".to_string(),
+                balance: nonce,
+            });
+            assert_round_trip(crate::block_explorer::TransferView {
+                sender: "abc".to_string(),
+                recipient: "def".to_string(),
+                amount: balance,
+            });
+        }
+    }
+
+    #[test]
+    fn test_large_balances_and_nonces_are_strings() {
+        let json = serde_json::to_value(GetAccountResponse {
+            account: nssa::Account {
+                balance: Amount::MAX,
+                nonce: Nonce::MAX,
+                ..Default::default()
+            },
+            storage_deposit: Amount::ZERO,
+            context: None,
+        })
+        .unwrap();
+
+        assert_eq!(
+            json["account"]["balance"],
+            "340282366920938463463374607431768211455"
+        );
+        assert_eq!(json["account"]["nonce"], "18446744073709551615");
+        assert_eq!(
+            serde_json::to_value(GetAccountsNoncesResponse {
+                nonces: vec![crate::json_number::MAX_SAFE_INTEGER, Nonce::MAX],
+                context: None,
+            })
+            .unwrap(),
+            json!({ "nonces": [9007199254740991_u64, "18446744073709551615"] })
+        );
+    }
+
+    #[test]
+    fn test_api_version_compatibility() {
+        let version = |major, minor| ApiVersion { major, minor };
+        let server = GetApiVersionResponse {
+            api_version: version(2, 1),
+            min_supported_api_version: version(1, 3),
+        };
+
+        assert_eq!(
+            server.compatibility(version(2, 0), version(2, 0)),
+            ApiCompatibility::Compatible
+        );
+        assert_eq!(
+            server.compatibility(version(1, 3), version(1, 0)),
+            ApiCompatibility::MajorMismatch
+        );
+        // Server doesn't support such an old client
+        assert_eq!(
+            server.compatibility(version(1, 2), version(1, 0)),
+            ApiCompatibility::Incompatible
+        );
+        // Client doesn't support such an old server
+        assert_eq!(
+            server.compatibility(version(3, 0), version(3, 0)),
+            ApiCompatibility::Incompatible
+        );
+    }
+
+    #[test]
+    fn test_sequencer_info_of_newer_sequencer() {
+        let info: GetSequencerInfoResponse = serde_json::from_value(json!({
+            "version": "9.0.0",
+            "api_version": { "major": 9, "minor": 0 },
+            "tx_kinds": [7, 8, 9, 10],
+            "features": ["lazy_proofs", "quantum_proofs"]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            info.features,
+            [SequencerFeature::LazyProofs, SequencerFeature::Unknown]
+        );
+        assert_eq!(info.unsupported_tx_kinds(), [TxKind::Unknown(10)]);
+    }
+
+    #[test]
+    fn test_tampered_account_does_not_lead_to_proven_state_root() {
+        let account_id = nssa::AccountId::new([1; 32]);
+        let account = nssa::Account {
+            balance: Amount(100),
+            ..nssa::Account::default()
+        };
+        let mut tree = nssa::account_tree::AccountTree::new();
+        tree.insert(account_id, &account);
+        tree.insert(nssa::AccountId::new([2; 32]), &account);
+        let digest = [3; 32];
+        let state_root = nssa::V02State::state_root_of(&tree.root(), &digest);
+
+        let signing_key = nssa::PrivateKey::try_new([7; 32]).unwrap();
+        let mut response = GetAccountWithProofResponse {
+            account,
+            proof: borsh::to_vec(&tree.proof(&account_id, |_| None)).unwrap(),
+            commitment_set_digest: hex::encode(digest),
+            header: SignedBlockHeader::sign(1, [0; 32], [1; 32], 0, Some(state_root), &signing_key),
+        };
+        assert_eq!(response.proven_state_root(&account_id), Some(state_root));
+
+        response.account.balance = response.account.balance.saturating_add(Amount(1));
+        assert_ne!(response.proven_state_root(&account_id), Some(state_root));
+
+        response.proof = vec![1, 2, 3];
+        assert_eq!(response.proven_state_root(&account_id), None);
+    }
+}
@@ -0,0 +1,371 @@
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use async_trait::async_trait;
+use log::warn;
+use nssa_core::program::ProgramId;
+
+use crate::{
+    address_index::{AddressTxCursor, AddressTxFilter},
+    error::SequencerClientError,
+    rpc_types::{
+        API_VERSION, ApiCompatibility, GetAccountBalanceResponse, GetAccountResponse,
+        GetAccountWithProofResponse, GetAccountsNoncesResponse, GetAccountsResponse,
+        GetApiVersionResponse, GetBlockDataResponse, GetBlockHeadersResponse,
+        GetBlockRangeDataResponse, GetChainInfoResponse, GetLastBlockResponse,
+        GetLatestAnchorsResponse, GetPendingTransactionsResponse, GetPendingTransfersResponse,
+        GetPriorityFeesResponse, GetSequencerInfoResponse, GetSyncStatusResponse,
+        GetTransactionByHashResponse, GetTransactionStatusResponse,
+        GetTransactionsByAddressResponse, MIN_SUPPORTED_API_VERSION, SendTxResponse,
+        ValidateTransactionResponse,
+    },
+    sequencer_client::SequencerClient,
+    transaction::{EncodedTransaction, NSSATransaction},
+};
+
+/// Sequencer methods used by clients, so they can talk to [`SequencerClient`] as well as to
+/// an in-memory chain in tests, see [`crate::mock_chain::MockChain`]
+#[async_trait]
+pub trait SequencerApi: Send + Sync {
+    async fn get_block(&self, block_id: u64) -> Result<GetBlockDataResponse, SequencerClientError>;
+
+    async fn get_block_range(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> Result<GetBlockRangeDataResponse, SequencerClientError>;
+
+    async fn get_last_block(&self) -> Result<GetLastBlockResponse, SequencerClientError>;
+
+    /// `account_id` must be a valid hex-string for 32 bytes. State is read at block `min_block_id`
+    /// or later, see [`crate::rpc_types::ReadContext`]
+    async fn get_account_balance_at(
+        &self,
+        account_id: String,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountBalanceResponse, SequencerClientError>;
+
+    /// `account_ids` must be a list of valid hex-strings for 32 bytes. State is read at block
+    /// `min_block_id` or later
+    async fn get_accounts_nonces_at(
+        &self,
+        account_ids: Vec<String>,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountsNoncesResponse, SequencerClientError>;
+
+    async fn get_account_at(
+        &self,
+        account_id: String,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountResponse, SequencerClientError>;
+
+    /// Accounts in order of `account_ids`, see
+    /// [`SequencerFeature::BatchAccounts`](crate::rpc_types::SequencerFeature::BatchAccounts)
+    async fn get_accounts_at(
+        &self,
+        account_ids: Vec<String>,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountsResponse, SequencerClientError>;
+
+    async fn get_transaction_by_hash(
+        &self,
+        hash: String,
+    ) -> Result<GetTransactionByHashResponse, SequencerClientError>;
+
+    async fn get_transaction_status(
+        &self,
+        hash: String,
+    ) -> Result<GetTransactionStatusResponse, SequencerClientError>;
+
+    /// Page of transactions touching public account `account_id`, from the newest one before
+    /// `cursor`, see [`crate::address_index`]
+    async fn get_transactions_by_address(
+        &self,
+        account_id: String,
+        cursor: Option<AddressTxCursor>,
+        limit: Option<u64>,
+        filter: AddressTxFilter,
+    ) -> Result<GetTransactionsByAddressResponse, SequencerClientError>;
+
+    /// Native transfers waiting for inclusion, which send to or from public account
+    /// `account_id`. Served only by sequencers with
+    /// [`SequencerFeature::PendingTransfers`](crate::rpc_types::SequencerFeature::PendingTransfers)
+    async fn get_pending_transfers(
+        &self,
+        account_id: String,
+    ) -> Result<GetPendingTransfersResponse, SequencerClientError>;
+
+    async fn get_priority_fees(&self) -> Result<GetPriorityFeesResponse, SequencerClientError>;
+
+    /// Summaries of all transactions waiting for inclusion. Served only by sequencers with
+    /// [`crate::rpc_types::SequencerFeature::PendingTransactions`]
+    async fn get_pending_transactions(
+        &self,
+    ) -> Result<GetPendingTransactionsResponse, SequencerClientError>;
+
+    async fn get_sync_status(&self) -> Result<GetSyncStatusResponse, SequencerClientError>;
+
+    /// Signed headers of blocks in `range`, see [`crate::signed_header`]
+    async fn get_block_headers(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> Result<GetBlockHeadersResponse, SequencerClientError>;
+
+    /// Public account with its proof against the state root of the last block
+    async fn get_account_with_proof(
+        &self,
+        account_id: String,
+    ) -> Result<GetAccountWithProofResponse, SequencerClientError>;
+
+    async fn send_tx(
+        &self,
+        transaction: EncodedTransaction,
+    ) -> Result<SendTxResponse, SequencerClientError>;
+
+    /// Verdict `send_tx` would give on `transaction`, without submitting it
+    async fn validate_transaction(
+        &self,
+        transaction: EncodedTransaction,
+    ) -> Result<ValidateTransactionResponse, SequencerClientError>;
+
+    async fn get_proof_for_commitment(
+        &self,
+        commitment: nssa_core::Commitment,
+    ) -> Result<Option<nssa_core::MembershipProof>, SequencerClientError>;
+
+    /// Roots of the commitment set, which private transactions may be proven against, see
+    /// [`nssa::Anchor`]
+    async fn get_latest_anchors(&self) -> Result<GetLatestAnchorsResponse, SequencerClientError>;
+
+    async fn get_program_ids(&self) -> Result<HashMap<String, ProgramId>, SequencerClientError>;
+
+    async fn get_api_version(&self) -> Result<GetApiVersionResponse, SequencerClientError>;
+
+    async fn get_sequencer_info(&self) -> Result<GetSequencerInfoResponse, SequencerClientError>;
+
+    async fn get_chain_info(&self) -> Result<GetChainInfoResponse, SequencerClientError>;
+
+    async fn get_account_balance(
+        &self,
+        account_id: String,
+    ) -> Result<GetAccountBalanceResponse, SequencerClientError> {
+        self.get_account_balance_at(account_id, None).await
+    }
+
+    async fn get_accounts_nonces(
+        &self,
+        account_ids: Vec<String>,
+    ) -> Result<GetAccountsNoncesResponse, SequencerClientError> {
+        self.get_accounts_nonces_at(account_ids, None).await
+    }
+
+    async fn get_account(
+        &self,
+        account_id: String,
+    ) -> Result<GetAccountResponse, SequencerClientError> {
+        self.get_account_at(account_id, None).await
+    }
+
+    async fn get_accounts(
+        &self,
+        account_ids: Vec<String>,
+    ) -> Result<GetAccountsResponse, SequencerClientError> {
+        self.get_accounts_at(account_ids, None).await
+    }
+
+    async fn send_tx_public(
+        &self,
+        transaction: nssa::PublicTransaction,
+    ) -> Result<SendTxResponse, SequencerClientError> {
+        self.send_tx(NSSATransaction::Public(transaction).into())
+            .await
+    }
+
+    async fn send_tx_private(
+        &self,
+        transaction: nssa::PrivacyPreservingTransaction,
+    ) -> Result<SendTxResponse, SequencerClientError> {
+        self.send_tx(NSSATransaction::PrivacyPreserving(transaction).into())
+            .await
+    }
+
+    async fn send_tx_program(
+        &self,
+        transaction: nssa::ProgramDeploymentTransaction,
+    ) -> Result<SendTxResponse, SequencerClientError> {
+        self.send_tx(NSSATransaction::ProgramDeployment(transaction).into())
+            .await
+    }
+
+    /// Negotiate API version with sequencer
+    ///
+    /// Warns on major version mismatch and fails if versions are incompatible.
+    async fn check_api_version(&self) -> Result<GetApiVersionResponse, SequencerClientError> {
+        let server_version = self.get_api_version().await?;
+
+        match server_version.compatibility(API_VERSION, MIN_SUPPORTED_API_VERSION) {
+            ApiCompatibility::Compatible => {}
+            ApiCompatibility::MajorMismatch => warn!(
+                "Sequencer API version {} differs from client version {API_VERSION}",
+                server_version.api_version
+            ),
+            ApiCompatibility::Incompatible => {
+                return Err(SequencerClientError::IncompatibleApiVersion {
+                    client: API_VERSION,
+                    server: server_version.api_version,
+                    server_min_supported: server_version.min_supported_api_version,
+                });
+            }
+        }
+
+        Ok(server_version)
+    }
+}
+
+#[async_trait]
+impl SequencerApi for SequencerClient {
+    async fn get_block(&self, block_id: u64) -> Result<GetBlockDataResponse, SequencerClientError> {
+        SequencerClient::get_block(self, block_id).await
+    }
+
+    async fn get_block_range(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> Result<GetBlockRangeDataResponse, SequencerClientError> {
+        SequencerClient::get_block_range(self, range).await
+    }
+
+    async fn get_last_block(&self) -> Result<GetLastBlockResponse, SequencerClientError> {
+        SequencerClient::get_last_block(self).await
+    }
+
+    async fn get_account_balance_at(
+        &self,
+        account_id: String,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountBalanceResponse, SequencerClientError> {
+        SequencerClient::get_account_balance_at(self, account_id, min_block_id).await
+    }
+
+    async fn get_accounts_nonces_at(
+        &self,
+        account_ids: Vec<String>,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountsNoncesResponse, SequencerClientError> {
+        SequencerClient::get_accounts_nonces_at(self, account_ids, min_block_id).await
+    }
+
+    async fn get_account_at(
+        &self,
+        account_id: String,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountResponse, SequencerClientError> {
+        SequencerClient::get_account_at(self, account_id, min_block_id).await
+    }
+
+    async fn get_accounts_at(
+        &self,
+        account_ids: Vec<String>,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountsResponse, SequencerClientError> {
+        SequencerClient::get_accounts_at(self, account_ids, min_block_id).await
+    }
+
+    async fn get_transaction_by_hash(
+        &self,
+        hash: String,
+    ) -> Result<GetTransactionByHashResponse, SequencerClientError> {
+        SequencerClient::get_transaction_by_hash(self, hash).await
+    }
+
+    async fn get_transaction_status(
+        &self,
+        hash: String,
+    ) -> Result<GetTransactionStatusResponse, SequencerClientError> {
+        SequencerClient::get_transaction_status(self, hash).await
+    }
+
+    async fn get_transactions_by_address(
+        &self,
+        account_id: String,
+        cursor: Option<AddressTxCursor>,
+        limit: Option<u64>,
+        filter: AddressTxFilter,
+    ) -> Result<GetTransactionsByAddressResponse, SequencerClientError> {
+        SequencerClient::get_transactions_by_address(self, account_id, cursor, limit, filter).await
+    }
+
+    async fn get_pending_transfers(
+        &self,
+        account_id: String,
+    ) -> Result<GetPendingTransfersResponse, SequencerClientError> {
+        SequencerClient::get_pending_transfers(self, account_id).await
+    }
+
+    async fn get_priority_fees(&self) -> Result<GetPriorityFeesResponse, SequencerClientError> {
+        SequencerClient::get_priority_fees(self).await
+    }
+
+    async fn get_pending_transactions(
+        &self,
+    ) -> Result<GetPendingTransactionsResponse, SequencerClientError> {
+        SequencerClient::get_pending_transactions(self).await
+    }
+
+    async fn get_sync_status(&self) -> Result<GetSyncStatusResponse, SequencerClientError> {
+        SequencerClient::get_sync_status(self).await
+    }
+
+    async fn get_block_headers(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> Result<GetBlockHeadersResponse, SequencerClientError> {
+        SequencerClient::get_block_headers(self, range).await
+    }
+
+    async fn get_account_with_proof(
+        &self,
+        account_id: String,
+    ) -> Result<GetAccountWithProofResponse, SequencerClientError> {
+        SequencerClient::get_account_with_proof(self, account_id).await
+    }
+
+    async fn send_tx(
+        &self,
+        transaction: EncodedTransaction,
+    ) -> Result<SendTxResponse, SequencerClientError> {
+        SequencerClient::send_tx(self, transaction).await
+    }
+
+    async fn validate_transaction(
+        &self,
+        transaction: EncodedTransaction,
+    ) -> Result<ValidateTransactionResponse, SequencerClientError> {
+        SequencerClient::validate_transaction(self, transaction).await
+    }
+
+    async fn get_proof_for_commitment(
+        &self,
+        commitment: nssa_core::Commitment,
+    ) -> Result<Option<nssa_core::MembershipProof>, SequencerClientError> {
+        SequencerClient::get_proof_for_commitment(self, commitment).await
+    }
+
+    async fn get_latest_anchors(&self) -> Result<GetLatestAnchorsResponse, SequencerClientError> {
+        SequencerClient::get_latest_anchors(self).await
+    }
+
+    async fn get_program_ids(&self) -> Result<HashMap<String, ProgramId>, SequencerClientError> {
+        SequencerClient::get_program_ids(self).await
+    }
+
+    async fn get_api_version(&self) -> Result<GetApiVersionResponse, SequencerClientError> {
+        SequencerClient::get_api_version(self).await
+    }
+
+    async fn get_sequencer_info(&self) -> Result<GetSequencerInfoResponse, SequencerClientError> {
+        SequencerClient::get_sequencer_info(self).await
+    }
+
+    async fn get_chain_info(&self) -> Result<GetChainInfoResponse, SequencerClientError> {
+        SequencerClient::get_chain_info(self).await
+    }
+}
@@ -1,31 +1,44 @@
 use std::{collections::HashMap, ops::RangeInclusive};
 
 use anyhow::Result;
-use nssa_core::program::ProgramId;
-use reqwest::Client;
+use log::warn;
+use nssa_core::{fmt::FmtShort as _, program::ProgramId};
+use reqwest::{Client, RequestBuilder};
 use serde::Deserialize;
 use serde_json::Value;
 
-use super::rpc_primitives::requests::{
-    GetAccountBalanceRequest, GetAccountBalanceResponse, GetBlockDataRequest, GetBlockDataResponse,
-    GetGenesisIdRequest, GetGenesisIdResponse, GetInitialTestnetAccountsRequest,
-};
 use crate::{
+    address_index::{AddressTxCursor, AddressTxFilter},
     error::{SequencerClientError, SequencerRpcError},
-    rpc_primitives::{
-        self,
-        requests::{
-            GetAccountRequest, GetAccountResponse, GetAccountsNoncesRequest,
-            GetAccountsNoncesResponse, GetBlockRangeDataRequest, GetBlockRangeDataResponse,
-            GetInitialTestnetAccountsResponse, GetLastBlockRequest, GetLastBlockResponse,
-            GetProgramIdsRequest, GetProgramIdsResponse, GetProofForCommitmentRequest,
-            GetProofForCommitmentResponse, GetTransactionByHashRequest,
-            GetTransactionByHashResponse, SendTxRequest, SendTxResponse,
-        },
+    rpc_primitives,
+    rpc_types::{
+        GetAccountBalanceRequest, GetAccountBalanceResponse, GetAccountRequest, GetAccountResponse,
+        GetAccountWithProofRequest, GetAccountWithProofResponse, GetAccountsNoncesRequest,
+        GetAccountsNoncesResponse, GetAccountsRequest, GetAccountsResponse,
+        GetAddressSummaryRequest, GetAddressSummaryResponse, GetApiVersionRequest,
+        GetApiVersionResponse, GetBlockDataRequest, GetBlockDataResponse, GetBlockExpandedRequest,
+        GetBlockExpandedResponse, GetBlockHeadersRequest, GetBlockHeadersResponse,
+        GetBlockProofRequest, GetBlockProofResponse, GetBlockRangeDataRequest,
+        GetBlockRangeDataResponse, GetChainInfoRequest, GetChainInfoResponse, GetChainStatsRequest,
+        GetChainStatsResponse, GetGenesisIdRequest, GetGenesisIdResponse,
+        GetInitialTestnetAccountsRequest, GetInitialTestnetAccountsResponse, GetLastBlockRequest,
+        GetLastBlockResponse, GetLatestAnchorsRequest, GetLatestAnchorsResponse,
+        GetPendingTransactionsRequest, GetPendingTransactionsResponse, GetPendingTransfersRequest,
+        GetPendingTransfersResponse, GetPriorityFeesRequest, GetPriorityFeesResponse,
+        GetProgramIdsRequest, GetProgramIdsResponse, GetProofForCommitmentRequest,
+        GetProofForCommitmentResponse, GetSequencerInfoRequest, GetSequencerInfoResponse,
+        GetSyncStatusRequest, GetSyncStatusResponse, GetTransactionByHashRequest,
+        GetTransactionByHashResponse, GetTransactionStatusRequest, GetTransactionStatusResponse,
+        GetTransactionsByAddressRequest, GetTransactionsByAddressResponse, GetTxAuditRequest,
+        GetTxAuditResponse, PreviewNextBlockRequest, PreviewNextBlockResponse, SendTxRequest,
+        SendTxResponse, ValidateTransactionRequest, ValidateTransactionResponse,
     },
     transaction::{EncodedTransaction, NSSATransaction},
 };
 
+/// Number of attempts to submit a transaction, if sequencer doesn't respond in time
+pub const SEND_TX_MAX_ATTEMPTS: usize = 3;
+
 #[derive(Clone)]
 pub struct SequencerClient {
     pub client: reqwest::Client,
@@ -57,35 +70,27 @@ impl SequencerClient {
         method: &str,
         payload: Value,
     ) -> Result<Value, SequencerClientError> {
-        let request =
-            rpc_primitives::message::Request::from_payload_version_2_0(method.to_string(), payload);
-
         let mut call_builder = self.client.post(&self.sequencer_addr);
 
         if let Some((username, password)) = &self.basic_auth {
             call_builder = call_builder.basic_auth(username, password.as_deref());
         }
 
-        let call_res = call_builder.json(&request).send().await?;
-
-        let response_vall = call_res.json::<Value>().await?;
-
-        #[derive(Debug, Clone, Deserialize)]
-        #[allow(dead_code)]
-        pub struct SequencerRpcResponse {
-            pub jsonrpc: String,
-            pub result: serde_json::Value,
-            pub id: u64,
-        }
+        send_request(call_builder, method, payload).await
+    }
 
-        if let Ok(response) = serde_json::from_value::<SequencerRpcResponse>(response_vall.clone())
-        {
-            Ok(response.result)
-        } else {
-            let err_resp = serde_json::from_value::<SequencerRpcError>(response_vall)?;
+    /// Calls `method` of the admin RPC of the sequencer, served at `/admin` to requests carrying
+    /// `auth_token` as a bearer token
+    pub async fn call_admin_method_with_payload(
+        &self,
+        method: &str,
+        payload: Value,
+        auth_token: &str,
+    ) -> Result<Value, SequencerClientError> {
+        let admin_addr = format!("{}/admin", self.sequencer_addr.trim_end_matches('/'));
+        let call_builder = self.client.post(admin_addr).bearer_auth(auth_token);
 
-            Err(err_resp.into())
-        }
+        send_request(call_builder, method, payload).await
     }
 
     /// Get block data at `block_id` from sequencer
@@ -143,7 +148,19 @@ impl SequencerClient {
         &self,
         account_id: String,
     ) -> Result<GetAccountBalanceResponse, SequencerClientError> {
-        let block_req = GetAccountBalanceRequest { account_id };
+        self.get_account_balance_at(account_id, None).await
+    }
+
+    /// Same as [`Self::get_account_balance`], but read at block `min_block_id` or later
+    pub async fn get_account_balance_at(
+        &self,
+        account_id: String,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountBalanceResponse, SequencerClientError> {
+        let block_req = GetAccountBalanceRequest {
+            account_id,
+            min_block_id,
+        };
 
         let req = serde_json::to_value(block_req)?;
 
@@ -162,7 +179,19 @@ impl SequencerClient {
         &self,
         account_ids: Vec<String>,
     ) -> Result<GetAccountsNoncesResponse, SequencerClientError> {
-        let block_req = GetAccountsNoncesRequest { account_ids };
+        self.get_accounts_nonces_at(account_ids, None).await
+    }
+
+    /// Same as [`Self::get_accounts_nonces`], but read at block `min_block_id` or later
+    pub async fn get_accounts_nonces_at(
+        &self,
+        account_ids: Vec<String>,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountsNoncesResponse, SequencerClientError> {
+        let block_req = GetAccountsNoncesRequest {
+            account_ids,
+            min_block_id,
+        };
 
         let req = serde_json::to_value(block_req)?;
 
@@ -179,7 +208,19 @@ impl SequencerClient {
         &self,
         account_id: String,
     ) -> Result<GetAccountResponse, SequencerClientError> {
-        let block_req = GetAccountRequest { account_id };
+        self.get_account_at(account_id, None).await
+    }
+
+    /// Same as [`Self::get_account`], but read at block `min_block_id` or later
+    pub async fn get_account_at(
+        &self,
+        account_id: String,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountResponse, SequencerClientError> {
+        let block_req = GetAccountRequest {
+            account_id,
+            min_block_id,
+        };
 
         let req = serde_json::to_value(block_req)?;
 
@@ -190,6 +231,36 @@ impl SequencerClient {
         Ok(resp_deser)
     }
 
+    /// Get several accounts in one call, `account_ids` must be a list of valid hex-strings for
+    /// 32 bytes. Served only by sequencers with
+    /// [`SequencerFeature::BatchAccounts`](crate::rpc_types::SequencerFeature::BatchAccounts)
+    pub async fn get_accounts(
+        &self,
+        account_ids: Vec<String>,
+    ) -> Result<GetAccountsResponse, SequencerClientError> {
+        self.get_accounts_at(account_ids, None).await
+    }
+
+    /// Same as [`Self::get_accounts`], but read at block `min_block_id` or later
+    pub async fn get_accounts_at(
+        &self,
+        account_ids: Vec<String>,
+        min_block_id: Option<u64>,
+    ) -> Result<GetAccountsResponse, SequencerClientError> {
+        let accounts_req = GetAccountsRequest {
+            account_ids,
+            min_block_id,
+        };
+
+        let req = serde_json::to_value(accounts_req)?;
+
+        let resp = self.call_method_with_payload("get_accounts", req).await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
     /// Get transaction details for `hash`.
     pub async fn get_transaction_by_hash(
         &self,
@@ -208,40 +279,236 @@ impl SequencerClient {
         Ok(resp_deser)
     }
 
-    /// Send transaction to sequencer
-    pub async fn send_tx_public(
+    /// Get status of transaction by its hex encoded hash
+    pub async fn get_transaction_status(
         &self,
-        transaction: nssa::PublicTransaction,
-    ) -> Result<SendTxResponse, SequencerClientError> {
-        let transaction = EncodedTransaction::from(NSSATransaction::Public(transaction));
+        hash: String,
+    ) -> Result<GetTransactionStatusResponse, SequencerClientError> {
+        let status_req = GetTransactionStatusRequest { hash };
 
-        let tx_req = SendTxRequest {
-            transaction: borsh::to_vec(&transaction).unwrap(),
-        };
+        let req = serde_json::to_value(status_req)?;
 
-        let req = serde_json::to_value(tx_req)?;
+        let resp = self
+            .call_method_with_payload("get_transaction_status", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
 
-        let resp = self.call_method_with_payload("send_tx", req).await?;
+        Ok(resp_deser)
+    }
+
+    /// Get block, which would be produced from currently pending transactions
+    pub async fn preview_next_block(
+        &self,
+    ) -> Result<PreviewNextBlockResponse, SequencerClientError> {
+        let req = serde_json::to_value(PreviewNextBlockRequest {})?;
+
+        let resp = self
+            .call_method_with_payload("preview_next_block", req)
+            .await?;
 
         let resp_deser = serde_json::from_value(resp)?;
 
         Ok(resp_deser)
     }
 
+    /// Get proving status of the block, with its proof if it's proven
+    pub async fn get_block_proof(
+        &self,
+        block_id: u64,
+    ) -> Result<GetBlockProofResponse, SequencerClientError> {
+        let req = serde_json::to_value(GetBlockProofRequest { block_id })?;
+
+        let resp = self
+            .call_method_with_payload("get_block_proof", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get progress of the sequencer catching up with stored blocks
+    pub async fn get_sync_status(&self) -> Result<GetSyncStatusResponse, SequencerClientError> {
+        let req = serde_json::to_value(GetSyncStatusRequest {})?;
+
+        let resp = self
+            .call_method_with_payload("get_sync_status", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get distribution of priority fees of pending transactions
+    pub async fn get_priority_fees(&self) -> Result<GetPriorityFeesResponse, SequencerClientError> {
+        let req = serde_json::to_value(GetPriorityFeesRequest {})?;
+
+        let resp = self
+            .call_method_with_payload("get_priority_fees", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get summaries of all transactions waiting for inclusion
+    pub async fn get_pending_transactions(
+        &self,
+    ) -> Result<GetPendingTransactionsResponse, SequencerClientError> {
+        let req = serde_json::to_value(GetPendingTransactionsRequest {})?;
+
+        let resp = self
+            .call_method_with_payload("get_pending_transactions", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get roots of the commitment set, which private transactions may be proven against
+    pub async fn get_latest_anchors(
+        &self,
+    ) -> Result<GetLatestAnchorsResponse, SequencerClientError> {
+        let req = serde_json::to_value(GetLatestAnchorsRequest {})?;
+
+        let resp = self
+            .call_method_with_payload("get_latest_anchors", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get signed headers of blocks in `range`, see [`crate::signed_header`]
+    pub async fn get_block_headers(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> Result<GetBlockHeadersResponse, SequencerClientError> {
+        let req = serde_json::to_value(GetBlockHeadersRequest {
+            start_block_id: *range.start(),
+            end_block_id: *range.end(),
+        })?;
+
+        let resp = self
+            .call_method_with_payload("get_block_headers", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get public account with its proof against the state root of the last block,
+    /// `account_id` must be a valid hex-string for 32 bytes
+    pub async fn get_account_with_proof(
+        &self,
+        account_id: String,
+    ) -> Result<GetAccountWithProofResponse, SequencerClientError> {
+        let req = serde_json::to_value(GetAccountWithProofRequest { account_id })?;
+
+        let resp = self
+            .call_method_with_payload("get_account_with_proof", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Send transaction to sequencer
+    pub async fn send_tx_public(
+        &self,
+        transaction: nssa::PublicTransaction,
+    ) -> Result<SendTxResponse, SequencerClientError> {
+        self.send_tx(EncodedTransaction::from(NSSATransaction::Public(
+            transaction,
+        )))
+        .await
+    }
+
     /// Send transaction to sequencer
     pub async fn send_tx_private(
         &self,
         transaction: nssa::PrivacyPreservingTransaction,
     ) -> Result<SendTxResponse, SequencerClientError> {
-        let transaction = EncodedTransaction::from(NSSATransaction::PrivacyPreserving(transaction));
+        self.send_tx(EncodedTransaction::from(
+            NSSATransaction::PrivacyPreserving(transaction),
+        ))
+        .await
+    }
 
+    /// Send encoded transaction to sequencer
+    ///
+    /// Submission is idempotent, so it's retried up to [`SEND_TX_MAX_ATTEMPTS`] times if
+    /// sequencer doesn't respond in time or reports being busy.
+    pub async fn send_tx(
+        &self,
+        transaction: EncodedTransaction,
+    ) -> Result<SendTxResponse, SequencerClientError> {
         let tx_req = SendTxRequest {
             transaction: borsh::to_vec(&transaction).unwrap(),
         };
 
         let req = serde_json::to_value(tx_req)?;
 
-        let resp = self.call_method_with_payload("send_tx", req).await?;
+        let mut attempt = 1;
+        let resp = loop {
+            match self.call_method_with_payload("send_tx", req.clone()).await {
+                Err(SequencerClientError::HTTPError(err))
+                    if err.is_timeout() && attempt < SEND_TX_MAX_ATTEMPTS =>
+                {
+                    warn!(
+                        "Submission of transaction {} timed out, resubmitting",
+                        transaction.hash().fmt_short()
+                    );
+                    attempt += 1;
+                }
+                Err(err)
+                    if err.mempool_congestion_retry_after().is_some()
+                        && attempt < SEND_TX_MAX_ATTEMPTS =>
+                {
+                    let retry_after = err.mempool_congestion_retry_after().unwrap_or_default();
+                    warn!(
+                        "Mempool is congested, resubmitting transaction {} in {}ms",
+                        transaction.hash().fmt_short(),
+                        retry_after.as_millis()
+                    );
+                    tokio::time::sleep(retry_after).await;
+                    attempt += 1;
+                }
+                Err(err) if err.is_server_busy() && attempt < SEND_TX_MAX_ATTEMPTS => {
+                    warn!(
+                        "Sequencer is busy with submission of transaction {}, resubmitting",
+                        transaction.hash().fmt_short()
+                    );
+                    attempt += 1;
+                }
+                resp => break resp?,
+            }
+        };
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Run admission checks of `send_tx` on encoded transaction without submitting it
+    pub async fn validate_transaction(
+        &self,
+        transaction: EncodedTransaction,
+    ) -> Result<ValidateTransactionResponse, SequencerClientError> {
+        let req = serde_json::to_value(ValidateTransactionRequest {
+            transaction: borsh::to_vec(&transaction).unwrap(),
+        })?;
+
+        let resp = self
+            .call_method_with_payload("validate_transaction", req)
+            .await?;
 
         let resp_deser = serde_json::from_value(resp)?;
 
@@ -307,19 +574,10 @@ impl SequencerClient {
         &self,
         transaction: nssa::ProgramDeploymentTransaction,
     ) -> Result<SendTxResponse, SequencerClientError> {
-        let transaction = EncodedTransaction::from(NSSATransaction::ProgramDeployment(transaction));
-
-        let tx_req = SendTxRequest {
-            transaction: borsh::to_vec(&transaction).unwrap(),
-        };
-
-        let req = serde_json::to_value(tx_req)?;
-
-        let resp = self.call_method_with_payload("send_tx", req).await?;
-
-        let resp_deser = serde_json::from_value(resp)?;
-
-        Ok(resp_deser)
+        self.send_tx(EncodedTransaction::from(
+            NSSATransaction::ProgramDeployment(transaction),
+        ))
+        .await
     }
 
     /// Get Ids of the programs used by the node
@@ -341,4 +599,290 @@ impl SequencerClient {
 
         Ok(resp_deser)
     }
+
+    /// Get block with decoded transactions from sequencer
+    pub async fn get_block_expanded(
+        &self,
+        block_id: u64,
+    ) -> Result<GetBlockExpandedResponse, SequencerClientError> {
+        let block_req = GetBlockExpandedRequest { block_id };
+
+        let req = serde_json::to_value(block_req)?;
+
+        let resp = self
+            .call_method_with_payload("get_block_expanded", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get balance and activity summary of account from sequencer
+    pub async fn get_address_summary(
+        &self,
+        account_id: String,
+    ) -> Result<GetAddressSummaryResponse, SequencerClientError> {
+        let summary_req = GetAddressSummaryRequest { account_id };
+
+        let req = serde_json::to_value(summary_req)?;
+
+        let resp = self
+            .call_method_with_payload("get_address_summary", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get a page of transactions touching account from sequencer
+    pub async fn get_transactions_by_address(
+        &self,
+        account_id: String,
+        cursor: Option<AddressTxCursor>,
+        limit: Option<u64>,
+        filter: AddressTxFilter,
+    ) -> Result<GetTransactionsByAddressResponse, SequencerClientError> {
+        let txs_req = GetTransactionsByAddressRequest {
+            account_id,
+            cursor,
+            limit,
+            filter,
+        };
+
+        let req = serde_json::to_value(txs_req)?;
+
+        let resp = self
+            .call_method_with_payload("get_transactions_by_address", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get native transfers of account waiting for inclusion from sequencer
+    pub async fn get_pending_transfers(
+        &self,
+        account_id: String,
+    ) -> Result<GetPendingTransfersResponse, SequencerClientError> {
+        let transfers_req = GetPendingTransfersRequest { account_id };
+
+        let req = serde_json::to_value(transfers_req)?;
+
+        let resp = self
+            .call_method_with_payload("get_pending_transfers", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get chain statistics from sequencer
+    pub async fn get_chain_stats(&self) -> Result<GetChainStatsResponse, SequencerClientError> {
+        let stats_req = GetChainStatsRequest { min_block_id: None };
+
+        let req = serde_json::to_value(stats_req)?;
+
+        let resp = self
+            .call_method_with_payload("get_chain_stats", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get API version of sequencer
+    pub async fn get_api_version(&self) -> Result<GetApiVersionResponse, SequencerClientError> {
+        let version_req = GetApiVersionRequest {};
+
+        let req = serde_json::to_value(version_req)?;
+
+        let resp = self
+            .call_method_with_payload("get_api_version", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get version, supported transaction kinds and features of sequencer
+    pub async fn get_sequencer_info(
+        &self,
+    ) -> Result<GetSequencerInfoResponse, SequencerClientError> {
+        let info_req = GetSequencerInfoRequest {};
+
+        let req = serde_json::to_value(info_req)?;
+
+        let resp = self
+            .call_method_with_payload("get_sequencer_info", req)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get chain parameters and policies of sequencer
+    pub async fn get_chain_info(&self) -> Result<GetChainInfoResponse, SequencerClientError> {
+        let info_req = GetChainInfoRequest {};
+
+        let req = serde_json::to_value(info_req)?;
+
+        let resp = self.call_method_with_payload("get_chain_info", req).await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+
+    /// Get mempool lifecycle events of transaction by its hex encoded hash from the admin RPC
+    pub async fn get_tx_audit(
+        &self,
+        hash: String,
+        auth_token: &str,
+    ) -> Result<GetTxAuditResponse, SequencerClientError> {
+        let audit_req = GetTxAuditRequest { hash };
+
+        let req = serde_json::to_value(audit_req)?;
+
+        let resp = self
+            .call_admin_method_with_payload("get_tx_audit", req, auth_token)
+            .await?;
+
+        let resp_deser = serde_json::from_value(resp)?;
+
+        Ok(resp_deser)
+    }
+}
+
+/// Sends JSON RPC request of `method` with `payload` through `call_builder`
+async fn send_request(
+    call_builder: RequestBuilder,
+    method: &str,
+    payload: Value,
+) -> Result<Value, SequencerClientError> {
+    let request =
+        rpc_primitives::message::Request::from_payload_version_2_0(method.to_string(), payload);
+
+    let call_res = call_builder.json(&request).send().await?;
+
+    let response_vall = call_res.json::<Value>().await?;
+
+    #[derive(Debug, Clone, Deserialize)]
+    #[allow(dead_code)]
+    pub struct SequencerRpcResponse {
+        pub jsonrpc: String,
+        pub result: serde_json::Value,
+        pub id: u64,
+    }
+
+    if let Ok(response) = serde_json::from_value::<SequencerRpcResponse>(response_vall.clone()) {
+        Ok(response.result)
+    } else {
+        let err_resp = serde_json::from_value::<SequencerRpcError>(response_vall)?;
+
+        Err(err_resp.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::{
+        io::{AsyncReadExt as _, AsyncWriteExt as _},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::*;
+
+    /// Reads one HTTP request from `stream` and returns its body
+    async fn read_request_body(stream: &mut TcpStream) -> Vec<u8> {
+        let mut request = vec![];
+        let mut buf = [0; 1024];
+        let headers_end = loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            if let Some(pos) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+        let headers = String::from_utf8_lossy(&request[..headers_end]).to_lowercase();
+        let content_length: usize = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        while request.len() < headers_end + content_length {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+        }
+        request[headers_end..].to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_send_tx_is_resubmitted_after_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let tx = crate::test_utils::produce_dummy_empty_transaction();
+        let tx_hash = hex::encode(tx.hash());
+
+        // Stub sequencer, which loses response to the first submission
+        let server = tokio::spawn({
+            let tx_hash = tx_hash.clone();
+            async move {
+                let (mut lost, _) = listener.accept().await.unwrap();
+                let first_body = read_request_body(&mut lost).await;
+
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let second_body = read_request_body(&mut stream).await;
+                let body = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "result": {
+                        "status": "Transaction already submitted",
+                        "tx_hash": tx_hash,
+                        "tx_status": { "state": "included", "block_id": 7 }
+                    },
+                    "id": 1
+                })
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                drop(lost);
+
+                (first_body, second_body)
+            }
+        });
+
+        let client = SequencerClient {
+            client: Client::builder()
+                .timeout(Duration::from_millis(200))
+                .build()
+                .unwrap(),
+            sequencer_addr: format!("http://{addr}"),
+            basic_auth: None,
+        };
+        let response = client.send_tx(tx).await.unwrap();
+
+        assert_eq!(response.tx_hash, tx_hash);
+        assert_eq!(
+            response.tx_status,
+            Some(crate::rpc_types::TransactionStatus::Included {
+                block_id: 7,
+                receipt: None
+            })
+        );
+        let (first_body, second_body) = server.await.unwrap();
+        assert_eq!(first_body, second_body);
+    }
 }
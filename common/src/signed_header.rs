@@ -0,0 +1,325 @@
+//! Block headers signed by the sequencer, which light clients verify without fetching blocks.
+//!
+//! A client trusting a checkpoint, i.e. a block id with its hash, follows the chain from it by
+//! headers alone: each one must be signed by the sequencer and link to the previous one by
+//! `prev_block_hash`. The header of the last block also carries the state root after it, which
+//! account proofs are checked against, see [`nssa::account_tree::AccountProof`].
+
+use nssa::{PrivateKey, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    HashType,
+    block::{BlockHash, BlockId, TimeStamp},
+};
+
+const SIGNED_BLOCK_HEADER_PREFIX: &[u8; 32] = b"/LSSA/v0.1/SignedBlockHeader/\x00\x00\x00";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignedBlockHeader {
+    pub block_id: BlockId,
+    /// Hex encoded hash of the previous block
+    pub prev_block_hash: String,
+    /// Hex encoded hash of the block
+    pub hash: String,
+    pub timestamp: TimeStamp,
+    /// Hex encoded root of the state after the block, see [`nssa::V02State::state_root`]
+    ///
+    /// Only the sequencer state at the last block is known, so headers of older blocks have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_root: Option<String>,
+    /// Hex encoded signature of the sequencer over the other fields
+    pub signature: String,
+}
+
+impl SignedBlockHeader {
+    pub fn sign(
+        block_id: BlockId,
+        prev_block_hash: BlockHash,
+        hash: BlockHash,
+        timestamp: TimeStamp,
+        state_root: Option<HashType>,
+        signing_key: &PrivateKey,
+    ) -> Self {
+        let signature = Signature::new(
+            signing_key,
+            &signed_bytes(
+                block_id,
+                &prev_block_hash,
+                &hash,
+                timestamp,
+                state_root.as_ref(),
+            ),
+        );
+
+        Self {
+            block_id,
+            prev_block_hash: hex::encode(prev_block_hash),
+            hash: hex::encode(hash),
+            timestamp,
+            state_root: state_root.map(hex::encode),
+            signature: hex::encode(signature.value()),
+        }
+    }
+
+    /// Checks that the header is signed by the sequencer with `public_key`
+    ///
+    /// Malformed headers are invalid.
+    pub fn is_valid_for(&self, public_key: &PublicKey) -> bool {
+        let Some((prev_block_hash, hash, state_root)) = self.decoded_hashes() else {
+            return false;
+        };
+        let Some(signature) = decode_hex::<64>(&self.signature)
+            .and_then(|bytes| borsh::from_slice::<Signature>(&bytes).ok())
+        else {
+            return false;
+        };
+
+        signature.is_valid_for(
+            &signed_bytes(
+                self.block_id,
+                &prev_block_hash,
+                &hash,
+                self.timestamp,
+                state_root.as_ref(),
+            ),
+            public_key,
+        )
+    }
+
+    /// Decoded state root, `None` if the header has none or it's malformed
+    pub fn state_root_bytes(&self) -> Option<HashType> {
+        decode_hex(self.state_root.as_ref()?)
+    }
+
+    /// Decoded previous block hash, block hash and state root, `None` if any is malformed
+    fn decoded_hashes(&self) -> Option<(BlockHash, BlockHash, Option<HashType>)> {
+        let state_root = match &self.state_root {
+            Some(state_root) => Some(decode_hex(state_root)?),
+            None => None,
+        };
+        Some((
+            decode_hex(&self.prev_block_hash)?,
+            decode_hex(&self.hash)?,
+            state_root,
+        ))
+    }
+}
+
+/// Block, which a light client trusts without verification, e.g. the genesis one
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub block_id: BlockId,
+    /// Hex encoded hash of the block
+    pub hash: String,
+}
+
+impl From<&SignedBlockHeader> for Checkpoint {
+    fn from(header: &SignedBlockHeader) -> Self {
+        Self {
+            block_id: header.block_id,
+            hash: header.hash.clone(),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum HeaderChainError {
+    #[error("Header of block {block_id} isn't signed by the sequencer")]
+    InvalidSignature { block_id: BlockId },
+    #[error("Expected header of block {expected}, got one of block {block_id}")]
+    UnexpectedBlockId {
+        expected: BlockId,
+        block_id: BlockId,
+    },
+    #[error("Header of block {block_id} doesn't link to the previous block")]
+    BrokenLink { block_id: BlockId },
+}
+
+/// Checks that `headers` continue the chain right after `checkpoint`, each signed by the
+/// sequencer with `public_key`, returns the checkpoint at the last of them
+///
+/// Headers must be consecutive, the empty chain leaves the checkpoint as it is.
+pub fn verify_header_chain(
+    checkpoint: &Checkpoint,
+    headers: &[SignedBlockHeader],
+    public_key: &PublicKey,
+) -> Result<Checkpoint, HeaderChainError> {
+    let mut tip = checkpoint.clone();
+    for header in headers {
+        let expected = tip.block_id + 1;
+        if header.block_id != expected {
+            return Err(HeaderChainError::UnexpectedBlockId {
+                expected,
+                block_id: header.block_id,
+            });
+        }
+        if !header.is_valid_for(public_key) {
+            return Err(HeaderChainError::InvalidSignature {
+                block_id: header.block_id,
+            });
+        }
+        if !header.prev_block_hash.eq_ignore_ascii_case(&tip.hash) {
+            return Err(HeaderChainError::BrokenLink {
+                block_id: header.block_id,
+            });
+        }
+        tip = Checkpoint::from(header);
+    }
+    Ok(tip)
+}
+
+fn signed_bytes(
+    block_id: BlockId,
+    prev_block_hash: &BlockHash,
+    hash: &BlockHash,
+    timestamp: TimeStamp,
+    state_root: Option<&HashType>,
+) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(32 + 8 + 32 + 32 + 8 + 1 + 32);
+    bytes.extend_from_slice(SIGNED_BLOCK_HEADER_PREFIX);
+    bytes.extend_from_slice(&block_id.to_le_bytes());
+    bytes.extend_from_slice(prev_block_hash);
+    bytes.extend_from_slice(hash);
+    bytes.extend_from_slice(&timestamp.to_le_bytes());
+    match state_root {
+        Some(state_root) => {
+            bytes.push(1);
+            bytes.extend_from_slice(state_root);
+        }
+        None => bytes.push(0),
+    }
+    bytes
+}
+
+fn decode_hex<const N: usize>(value: &str) -> Option<[u8; N]> {
+    hex::decode(value).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequencer_key() -> PrivateKey {
+        PrivateKey::try_new([7; 32]).unwrap()
+    }
+
+    fn public_key() -> PublicKey {
+        PublicKey::new_from_private_key(&sequencer_key())
+    }
+
+    fn block_hash(block_id: BlockId) -> BlockHash {
+        [u8::try_from(block_id).unwrap(); 32]
+    }
+
+    fn header(block_id: BlockId) -> SignedBlockHeader {
+        SignedBlockHeader::sign(
+            block_id,
+            block_hash(block_id - 1),
+            block_hash(block_id),
+            1_700_000_000_000 + block_id,
+            None,
+            &sequencer_key(),
+        )
+    }
+
+    fn checkpoint(block_id: BlockId) -> Checkpoint {
+        Checkpoint {
+            block_id,
+            hash: hex::encode(block_hash(block_id)),
+        }
+    }
+
+    #[test]
+    fn test_tampered_header_is_invalid() {
+        let with_state_root = SignedBlockHeader::sign(
+            3,
+            block_hash(2),
+            block_hash(3),
+            0,
+            Some([9; 32]),
+            &sequencer_key(),
+        );
+        assert!(with_state_root.is_valid_for(&public_key()));
+        assert_eq!(with_state_root.state_root_bytes(), Some([9; 32]));
+
+        let other_key = PublicKey::new_from_private_key(&PrivateKey::try_new([8; 32]).unwrap());
+        assert!(!with_state_root.is_valid_for(&other_key));
+
+        let tampered = [
+            SignedBlockHeader {
+                state_root: Some(hex::encode([10; 32])),
+                ..with_state_root.clone()
+            },
+            SignedBlockHeader {
+                state_root: None,
+                ..with_state_root.clone()
+            },
+            SignedBlockHeader {
+                hash: hex::encode(block_hash(4)),
+                ..with_state_root.clone()
+            },
+            SignedBlockHeader {
+                timestamp: 1,
+                ..with_state_root.clone()
+            },
+            SignedBlockHeader {
+                signature: "not hex".to_string(),
+                ..with_state_root.clone()
+            },
+        ];
+        for header in tampered {
+            assert!(!header.is_valid_for(&public_key()), "{header:?}");
+        }
+    }
+
+    #[test]
+    fn test_header_chain_is_followed_from_checkpoint() {
+        let headers = (2..=5).map(header).collect::<Vec<_>>();
+
+        assert_eq!(
+            verify_header_chain(&checkpoint(1), &headers, &public_key()),
+            Ok(checkpoint(5))
+        );
+        assert_eq!(
+            verify_header_chain(&checkpoint(5), &[], &public_key()),
+            Ok(checkpoint(5))
+        );
+    }
+
+    #[test]
+    fn test_broken_header_chain_is_rejected() {
+        let headers = (2..=5).map(header).collect::<Vec<_>>();
+
+        assert_eq!(
+            verify_header_chain(&checkpoint(2), &headers, &public_key()),
+            Err(HeaderChainError::UnexpectedBlockId {
+                expected: 3,
+                block_id: 2
+            })
+        );
+
+        let forked = Checkpoint {
+            block_id: 1,
+            hash: hex::encode([0xff; 32]),
+        };
+        assert_eq!(
+            verify_header_chain(&forked, &headers, &public_key()),
+            Err(HeaderChainError::BrokenLink { block_id: 2 })
+        );
+
+        let mut forged = headers.clone();
+        forged[2] = SignedBlockHeader::sign(
+            4,
+            block_hash(3),
+            [0xee; 32],
+            0,
+            None,
+            &PrivateKey::try_new([8; 32]).unwrap(),
+        );
+        assert_eq!(
+            verify_header_chain(&checkpoint(1), &forged, &public_key()),
+            Err(HeaderChainError::InvalidSignature { block_id: 4 })
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Signed promises of the sequencer to include submitted transactions.
+//!
+//! On admission of a transaction into mempool the sequencer signs its hash together with the
+//! block, which includes the transaction at the latest. If the transaction is dropped or not
+//! included by then, the confirmation proves that the sequencer broke its promise.
+
+use nssa::{PrivateKey, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    HashType,
+    block::{BlockId, TimeStamp},
+};
+
+const SOFT_CONFIRMATION_PREFIX: &[u8; 32] = b"/LSSA/v0.1/SoftConfirmation/\x00\x00\x00\x00";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SoftConfirmation {
+    /// Hex encoded hash of the transaction
+    pub tx_hash: String,
+    /// Unix time of the admission in milliseconds
+    pub received_at: TimeStamp,
+    /// Block, by which the transaction is promised to be included
+    pub promised_by_block: BlockId,
+    /// Hex encoded signature of the sequencer over the other fields
+    pub signature: String,
+}
+
+impl SoftConfirmation {
+    pub fn sign(
+        tx_hash: HashType,
+        received_at: TimeStamp,
+        promised_by_block: BlockId,
+        signing_key: &PrivateKey,
+    ) -> Self {
+        let signature = Signature::new(
+            signing_key,
+            &signed_bytes(&tx_hash, received_at, promised_by_block),
+        );
+
+        Self {
+            tx_hash: hex::encode(tx_hash),
+            received_at,
+            promised_by_block,
+            signature: hex::encode(signature.value()),
+        }
+    }
+
+    /// Checks that the confirmation is signed by the sequencer with `public_key`
+    ///
+    /// Malformed confirmations are invalid.
+    pub fn is_valid_for(&self, public_key: &PublicKey) -> bool {
+        let Some(tx_hash) = decode_hex::<32>(&self.tx_hash) else {
+            return false;
+        };
+        let Some(signature) = decode_hex::<64>(&self.signature)
+            .and_then(|bytes| borsh::from_slice::<Signature>(&bytes).ok())
+        else {
+            return false;
+        };
+
+        signature.is_valid_for(
+            &signed_bytes(&tx_hash, self.received_at, self.promised_by_block),
+            public_key,
+        )
+    }
+}
+
+fn signed_bytes(tx_hash: &HashType, received_at: TimeStamp, promised_by_block: BlockId) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(32 + 32 + 8 + 8);
+    bytes.extend_from_slice(SOFT_CONFIRMATION_PREFIX);
+    bytes.extend_from_slice(tx_hash);
+    bytes.extend_from_slice(&received_at.to_le_bytes());
+    bytes.extend_from_slice(&promised_by_block.to_le_bytes());
+    bytes
+}
+
+fn decode_hex<const N: usize>(value: &str) -> Option<[u8; N]> {
+    hex::decode(value).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequencer_key() -> PrivateKey {
+        PrivateKey::try_new([7; 32]).unwrap()
+    }
+
+    fn confirmation() -> SoftConfirmation {
+        SoftConfirmation::sign([1; 32], 1_700_000_000_000, 12, &sequencer_key())
+    }
+
+    #[test]
+    fn test_confirmation_is_valid_for_sequencer_key() {
+        let public_key = PublicKey::new_from_private_key(&sequencer_key());
+
+        assert!(confirmation().is_valid_for(&public_key));
+    }
+
+    #[test]
+    fn test_confirmation_is_invalid_for_other_key() {
+        let other_key = PublicKey::new_from_private_key(&PrivateKey::try_new([8; 32]).unwrap());
+
+        assert!(!confirmation().is_valid_for(&other_key));
+    }
+
+    #[test]
+    fn test_tampered_confirmation_is_invalid() {
+        let public_key = PublicKey::new_from_private_key(&sequencer_key());
+
+        let tampered = [
+            SoftConfirmation {
+                tx_hash: hex::encode([2; 32]),
+                ..confirmation()
+            },
+            SoftConfirmation {
+                received_at: confirmation().received_at + 1,
+                ..confirmation()
+            },
+            SoftConfirmation {
+                promised_by_block: confirmation().promised_by_block + 1,
+                ..confirmation()
+            },
+            SoftConfirmation {
+                signature: hex::encode([0; 64]),
+                ..confirmation()
+            },
+            SoftConfirmation {
+                signature: "not hex".to_string(),
+                ..confirmation()
+            },
+            SoftConfirmation {
+                tx_hash: "00ff".to_string(),
+                ..confirmation()
+            },
+        ];
+
+        for confirmation in tampered {
+            assert!(!confirmation.is_valid_for(&public_key), "{confirmation:?}");
+        }
+    }
+}
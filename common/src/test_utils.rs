@@ -1,3 +1,5 @@
+use nssa_core::{account::Nonce, units::Amount};
+
 use crate::{
     block::{Block, HashableBlockData},
     transaction::{EncodedTransaction, NSSATransaction},
@@ -28,6 +30,8 @@ pub fn produce_dummy_block(
         prev_block_hash: prev_hash.unwrap_or_default(),
         timestamp: id * 100,
         transactions,
+        params_version: 0,
+        dev_proved: false,
     };
 
     block_data.into_block(&sequencer_sign_key_for_testing())
@@ -55,10 +59,28 @@ pub fn produce_dummy_empty_transaction() -> EncodedTransaction {
 
 pub fn create_transaction_native_token_transfer(
     from: [u8; 32],
-    nonce: u128,
+    nonce: Nonce,
     to: [u8; 32],
     balance_to_move: u128,
     signing_key: nssa::PrivateKey,
+) -> EncodedTransaction {
+    create_transaction_native_token_transfer_with_priority_fee(
+        from,
+        nonce,
+        to,
+        balance_to_move,
+        Amount::ZERO,
+        signing_key,
+    )
+}
+
+pub fn create_transaction_native_token_transfer_with_priority_fee(
+    from: [u8; 32],
+    nonce: Nonce,
+    to: [u8; 32],
+    balance_to_move: u128,
+    priority_fee: Amount,
+    signing_key: nssa::PrivateKey,
 ) -> EncodedTransaction {
     let account_ids = vec![nssa::AccountId::new(from), nssa::AccountId::new(to)];
     let nonces = vec![nonce];
@@ -69,10 +91,111 @@ pub fn create_transaction_native_token_transfer(
         nonces,
         balance_to_move,
     )
-    .unwrap();
+    .unwrap()
+    .with_priority_fee(priority_fee);
     let witness_set = nssa::public_transaction::WitnessSet::for_message(&message, &[&signing_key]);
 
     let nssa_tx = nssa::PublicTransaction::new(message, witness_set);
 
     EncodedTransaction::from(NSSATransaction::Public(nssa_tx))
 }
+
+/// Genesis and block 1 of the canned scenario of deterministic dev mode, where the first of two
+/// keys drawn from the RNG of `seed` transfers to the second one
+///
+/// The transfer calls a deployed program id instead of a builtin one, so the blocks don't change
+/// with builds of builtin programs.
+pub fn deterministic_dev_blocks(seed: u64) -> (Block, Block) {
+    use rand::Rng as _;
+
+    let mut rng = nssa::entropy::dev_rng(seed);
+    let block_signing_key = nssa::PrivateKey::new_from_rng(&mut rng);
+    let from_key = nssa::PrivateKey::new_from_rng(&mut rng);
+    let to_key = nssa::PrivateKey::new_from_rng(&mut rng);
+
+    let allow_list = nssa::ProgramAllowList {
+        builtin_programs: vec![],
+        deployed_programs: true,
+    };
+    let genesis = HashableBlockData {
+        block_id: 0,
+        prev_block_hash: allow_list.commitment(),
+        timestamp: crate::block::logical_timestamp(0),
+        transactions: vec![],
+        params_version: 0,
+        dev_proved: false,
+    }
+    .into_block_with_aux_random(&block_signing_key, rng.r#gen());
+
+    let message = nssa::public_transaction::Message::try_new(
+        nssa::ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
+        vec![
+            nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(&from_key)),
+            nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(&to_key)),
+        ],
+        vec![0],
+        100u128,
+    )
+    .unwrap();
+    let witness_set = nssa::public_transaction::WitnessSet::for_message_with_aux_random(
+        &message,
+        &[&from_key],
+        rng.r#gen(),
+    );
+    let transfer = NSSATransaction::Public(nssa::PublicTransaction::new(message, witness_set));
+
+    let block_1 = HashableBlockData {
+        block_id: 1,
+        prev_block_hash: genesis.header.hash,
+        timestamp: crate::block::logical_timestamp(1),
+        transactions: vec![EncodedTransaction::from(transfer)],
+        params_version: 0,
+        dev_proved: false,
+    }
+    .into_block_with_aux_random(&block_signing_key, rng.r#gen());
+
+    (genesis, block_1)
+}
+
+// Fixtures of many accounts and transactions, shared by tests and benchmarks
+
+/// Signing key, which is the same for the same `index` and differs between indices
+pub fn signing_key_for_index(index: u32) -> nssa::PrivateKey {
+    let mut key = [1; 32];
+    key[..4].copy_from_slice(&index.to_le_bytes());
+    nssa::PrivateKey::try_new(key).unwrap()
+}
+
+/// Account of [`signing_key_for_index`]
+pub fn account_id_for_index(index: u32) -> nssa::AccountId {
+    nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(
+        &signing_key_for_index(index),
+    ))
+}
+
+/// State, where accounts `0..num_accounts` of [`account_id_for_index`] have `balance` each
+pub fn state_with_funded_accounts(num_accounts: u32, balance: Amount) -> nssa::V02State {
+    let initial_data = (0..num_accounts)
+        .map(|index| (account_id_for_index(index), balance))
+        .collect::<Vec<_>>();
+    nssa::V02State::new_with_genesis_accounts(&initial_data, &[])
+}
+
+/// Transfers of `amount` from every account `0..num_txs` of [`account_id_for_index`] to the
+/// next one
+///
+/// Every transaction has its own sender with zero nonce, so all of them are valid in one block
+/// on top of [`state_with_funded_accounts`].
+pub fn independent_native_transfers(num_txs: u32, amount: u128) -> Vec<EncodedTransaction> {
+    (0..num_txs)
+        .map(|index| {
+            create_transaction_native_token_transfer(
+                *account_id_for_index(index).value(),
+                0,
+                *account_id_for_index(index + 1).value(),
+                amount,
+                signing_key_for_index(index),
+            )
+        })
+        .collect()
+}
@@ -1,5 +1,9 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use log::info;
+use nssa_core::{
+    fmt::FmtShort as _,
+    units::{Amount, Gas},
+};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, digest::FixedOutput};
 
@@ -12,6 +16,75 @@ pub enum NSSATransaction {
     ProgramDeployment(nssa::ProgramDeploymentTransaction),
 }
 
+impl NSSATransaction {
+    /// Public accounts read or modified by transaction
+    pub fn public_account_ids(&self) -> Vec<nssa::AccountId> {
+        match self {
+            Self::Public(tx) => {
+                let message = tx.message();
+                [message.account_ids(), message.readonly_account_ids()].concat()
+            }
+            Self::PrivacyPreserving(tx) => tx.message().public_account_ids().to_vec(),
+            Self::ProgramDeployment(tx) => vec![tx.message().deployer()],
+        }
+    }
+
+    /// Priority fee paid by the sender, only public transactions may pay it
+    pub fn priority_fee(&self) -> Amount {
+        match self {
+            Self::Public(tx) => tx.message().priority_fee(),
+            Self::PrivacyPreserving(_) | Self::ProgramDeployment(_) => Amount::ZERO,
+        }
+    }
+
+    /// Most of the balance of the sender the transaction may move: value of a native transfer,
+    /// priority fee and the most gas it may be charged, or the fee of a deployment. Value moved by
+    /// other programs isn't known before execution, so it isn't counted.
+    pub fn max_sender_outflow(&self) -> Amount {
+        match self {
+            Self::Public(tx) => {
+                let message = tx.message();
+                let value = match nssa::public_transaction::NativeTransfer::decode(message) {
+                    Some(nssa::public_transaction::NativeTransfer::Transfer { amount, .. }) => {
+                        amount
+                    }
+                    _ => Amount::ZERO,
+                };
+                let max_gas = message.max_cycles().map_or(Gas::ZERO, |max_cycles| {
+                    nssa::gas::GasCalculator::default().cycles_to_gas(max_cycles)
+                });
+                value
+                    .saturating_add(message.priority_fee())
+                    .saturating_add(max_gas.into())
+            }
+            Self::ProgramDeployment(tx) => tx.fee().into(),
+            Self::PrivacyPreserving(_) => Amount::ZERO,
+        }
+    }
+
+    /// First block the transaction may be included into, only public transactions may be
+    /// time-locked
+    pub fn valid_from_block(&self) -> Option<u64> {
+        match self {
+            Self::Public(tx) => tx.message().valid_from_block(),
+            Self::PrivacyPreserving(_) | Self::ProgramDeployment(_) => None,
+        }
+    }
+
+    /// First signer of transaction and nonce it uses, if transaction is signed
+    pub fn sender_and_nonce(&self) -> Option<(nssa::AccountId, nssa_core::account::Nonce)> {
+        let (signers, nonces) = match self {
+            Self::Public(tx) => (tx.signer_account_ids(), tx.message().nonces()),
+            Self::PrivacyPreserving(tx) => (tx.signer_account_ids(), tx.message().nonces()),
+            Self::ProgramDeployment(tx) => {
+                return Some((tx.message().deployer(), tx.message().nonce()));
+            }
+        };
+
+        Some((*signers.first()?, *nonces.first()?))
+    }
+}
+
 impl From<nssa::PublicTransaction> for NSSATransaction {
     fn from(value: nssa::PublicTransaction) -> Self {
         Self::Public(value)
@@ -30,13 +103,125 @@ impl From<nssa::ProgramDeploymentTransaction> for NSSATransaction {
     }
 }
 
-#[derive(
-    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize,
-)]
+/// Version of transaction encoding
+///
+/// Kinds introduced by newer versions are decoded as [`TxKind::Unknown`], so blocks containing
+/// them can still be read.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum TxKind {
     Public,
     PrivacyPreserving,
     ProgramDeployment,
+    /// Public transaction with `u128` nonces, see [`nssa::encoding::legacy`]
+    LegacyPublic,
+    /// Privacy preserving transaction with `u128` nonces, see [`nssa::encoding::legacy`]
+    LegacyPrivacyPreserving,
+    /// Program deployment without a deployer, see [`nssa::encoding::legacy`]
+    LegacyProgramDeployment,
+    /// Public transaction without a signature scheme in its witness set, see
+    /// [`nssa::encoding::legacy`]
+    UnschemedPublic,
+    /// Privacy preserving transaction without a signature scheme in its witness set, see
+    /// [`nssa::encoding::legacy`]
+    UnschemedPrivacyPreserving,
+    /// Program deployment without a signature scheme in its witness set, see
+    /// [`nssa::encoding::legacy`]
+    UnschemedProgramDeployment,
+    /// Public transaction with a `u64` priority fee, see [`nssa::encoding::legacy`]
+    NarrowFeePublic,
+    /// Kind unsupported by this build, with its tag
+    Unknown(u8),
+}
+
+impl TxKind {
+    /// Kinds this build accepts
+    pub const SUPPORTED: [TxKind; 3] = [
+        TxKind::Public,
+        TxKind::PrivacyPreserving,
+        TxKind::ProgramDeployment,
+    ];
+
+    /// Kinds this build can decode, legacy ones are only read from old blocks
+    pub const DECODABLE: [TxKind; 10] = [
+        TxKind::Public,
+        TxKind::PrivacyPreserving,
+        TxKind::ProgramDeployment,
+        TxKind::LegacyPublic,
+        TxKind::LegacyPrivacyPreserving,
+        TxKind::LegacyProgramDeployment,
+        TxKind::UnschemedPublic,
+        TxKind::UnschemedPrivacyPreserving,
+        TxKind::UnschemedProgramDeployment,
+        TxKind::NarrowFeePublic,
+    ];
+
+    pub fn tag(self) -> u8 {
+        match self {
+            Self::LegacyPublic => 0,
+            Self::LegacyPrivacyPreserving => 1,
+            Self::LegacyProgramDeployment => 2,
+            Self::UnschemedPublic => 3,
+            Self::UnschemedPrivacyPreserving => 4,
+            Self::UnschemedProgramDeployment => 5,
+            Self::NarrowFeePublic => 6,
+            Self::PrivacyPreserving => 7,
+            Self::ProgramDeployment => 8,
+            Self::Public => 9,
+            Self::Unknown(tag) => tag,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Self {
+        Self::DECODABLE
+            .into_iter()
+            .find(|kind| kind.tag() == tag)
+            .unwrap_or(Self::Unknown(tag))
+    }
+
+    pub fn is_supported(self) -> bool {
+        !matches!(self, Self::Unknown(_))
+    }
+
+    /// Whether the kind was replaced by a newer one and is no longer accepted
+    pub fn is_legacy(self) -> bool {
+        matches!(
+            self,
+            Self::LegacyPublic
+                | Self::LegacyPrivacyPreserving
+                | Self::LegacyProgramDeployment
+                | Self::UnschemedPublic
+                | Self::UnschemedPrivacyPreserving
+                | Self::UnschemedProgramDeployment
+                | Self::NarrowFeePublic
+        )
+    }
+
+    /// Whether the kind is encoded with `u128` nonces, so its messages and accounts are in the
+    /// legacy encoding. Unschemed kinds encode them as now.
+    pub fn predates_u64_nonces(self) -> bool {
+        matches!(
+            self,
+            Self::LegacyPublic | Self::LegacyPrivacyPreserving | Self::LegacyProgramDeployment
+        )
+    }
+
+    /// Whether the kind is a public transaction encoded with a `u64` priority fee, so it's signed
+    /// and identified by that encoding of its message
+    pub fn predates_amount_priority_fees(self) -> bool {
+        matches!(self, Self::UnschemedPublic | Self::NarrowFeePublic)
+    }
+}
+
+impl BorshSerialize for TxKind {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        BorshSerialize::serialize(&self.tag(), writer)
+    }
+}
+
+impl BorshDeserialize for TxKind {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        u8::deserialize_reader(reader).map(Self::from_tag)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
@@ -81,13 +266,88 @@ impl TryFrom<&EncodedTransaction> for NSSATransaction {
                 nssa::ProgramDeploymentTransaction::from_bytes(&value.encoded_transaction_data)
                     .map(|tx| tx.into())
             }
+            TxKind::LegacyPublic => {
+                nssa::PublicTransaction::from_legacy_bytes(&value.encoded_transaction_data)
+                    .map(|tx| tx.into())
+            }
+            TxKind::LegacyPrivacyPreserving => {
+                nssa::PrivacyPreservingTransaction::from_legacy_bytes(
+                    &value.encoded_transaction_data,
+                )
+                .map(|tx| tx.into())
+            }
+            TxKind::LegacyProgramDeployment => {
+                nssa::ProgramDeploymentTransaction::from_legacy_bytes(
+                    &value.encoded_transaction_data,
+                )
+                .map(|tx| tx.into())
+            }
+            TxKind::UnschemedPublic => {
+                nssa::PublicTransaction::from_unschemed_bytes(&value.encoded_transaction_data)
+                    .map(|tx| tx.into())
+            }
+            TxKind::UnschemedPrivacyPreserving => {
+                nssa::PrivacyPreservingTransaction::from_unschemed_bytes(
+                    &value.encoded_transaction_data,
+                )
+                .map(|tx| tx.into())
+            }
+            TxKind::UnschemedProgramDeployment => {
+                nssa::ProgramDeploymentTransaction::from_unschemed_bytes(
+                    &value.encoded_transaction_data,
+                )
+                .map(|tx| tx.into())
+            }
+            TxKind::NarrowFeePublic => {
+                nssa::PublicTransaction::from_narrow_fee_bytes(&value.encoded_transaction_data)
+                    .map(|tx| tx.into())
+            }
+            TxKind::Unknown(tag) => Err(nssa::error::NssaError::InvalidInput(format!(
+                "Unsupported transaction kind {tag}"
+            ))),
         }
     }
 }
 
 impl EncodedTransaction {
-    /// Computes and returns the SHA-256 hash of the JSON-serialized representation of `self`.
+    /// Identifier of the transaction
+    ///
+    /// Public transactions are identified by [`nssa::PublicTransaction::hash`], which doesn't
+    /// depend on signatures or their scheme, legacy ones by the same hash of their messages as
+    /// encoded, see [`nssa::PublicTransaction::legacy_hash`]. Other transactions and undecodable
+    /// ones are identified by the SHA-256 hash of the borsh-serialized `self`.
     pub fn hash(&self) -> HashType {
+        match self.tx_kind {
+            TxKind::Public => {
+                if let Ok(tx) = nssa::PublicTransaction::from_bytes(&self.encoded_transaction_data)
+                {
+                    return tx.hash();
+                }
+            }
+            TxKind::UnschemedPublic => {
+                if let Ok(hash) =
+                    nssa::PublicTransaction::unschemed_hash(&self.encoded_transaction_data)
+                {
+                    return hash;
+                }
+            }
+            TxKind::NarrowFeePublic => {
+                if let Ok(hash) =
+                    nssa::PublicTransaction::narrow_fee_hash(&self.encoded_transaction_data)
+                {
+                    return hash;
+                }
+            }
+            TxKind::LegacyPublic => {
+                if let Ok(hash) =
+                    nssa::PublicTransaction::legacy_hash(&self.encoded_transaction_data)
+                {
+                    return hash;
+                }
+            }
+            _ => {}
+        }
+
         let bytes_to_hash = borsh::to_vec(&self).unwrap();
         let mut hasher = sha2::Sha256::new();
         hasher.update(&bytes_to_hash);
@@ -95,7 +355,7 @@ impl EncodedTransaction {
     }
 
     pub fn log(&self) {
-        info!("Transaction hash is {:?}", hex::encode(self.hash()));
+        info!("Transaction hash is {}", self.hash().fmt_short());
         info!("Transaction tx_kind is {:?}", self.tx_kind);
     }
 }
@@ -106,7 +366,7 @@ mod tests {
 
     use crate::{
         HashType,
-        transaction::{EncodedTransaction, TxKind},
+        transaction::{EncodedTransaction, NSSATransaction, TxKind},
     };
 
     fn test_transaction_body() -> EncodedTransaction {
@@ -117,7 +377,7 @@ mod tests {
     }
 
     #[test]
-    fn test_transaction_hash_is_sha256_of_json_bytes() {
+    fn test_undecodable_transaction_hash_is_sha256_of_borsh_bytes() {
         let body = test_transaction_body();
         let expected_hash = {
             let data = borsh::to_vec(&body).unwrap();
@@ -131,6 +391,41 @@ mod tests {
         assert_eq!(expected_hash, hash);
     }
 
+    #[test]
+    fn test_public_transaction_hash_does_not_depend_on_witness() {
+        let signed = || {
+            let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+            let message = nssa::public_transaction::Message::try_new(
+                nssa::program::Program::authenticated_transfer_program().id(),
+                vec![nssa::AccountId::from(
+                    &nssa::PublicKey::new_from_private_key(&key),
+                )],
+                vec![0],
+                10_u128,
+            )
+            .unwrap();
+            let witness_set = nssa::public_transaction::WitnessSet::for_message(&message, &[&key]);
+            nssa::PublicTransaction::new(message, witness_set)
+        };
+        let tx = signed();
+        let resigned = signed();
+
+        let body = EncodedTransaction::from(NSSATransaction::Public(tx.clone()));
+        let resigned_body = EncodedTransaction::from(NSSATransaction::Public(resigned));
+        assert_ne!(body, resigned_body);
+
+        assert_eq!(body.hash(), tx.hash());
+        assert_eq!(body.hash(), resigned_body.hash());
+    }
+
+    #[test]
+    fn test_transaction_with_trailing_bytes_is_not_decoded() {
+        let mut body = crate::test_utils::produce_dummy_empty_transaction();
+        body.encoded_transaction_data.push(0);
+
+        assert!(NSSATransaction::try_from(&body).is_err());
+    }
+
     #[test]
     fn test_to_bytes_from_bytes() {
         let body = test_transaction_body();
@@ -140,4 +435,41 @@ mod tests {
 
         assert_eq!(body, body_new);
     }
+
+    #[test]
+    fn test_tx_kind_encoding_is_unchanged() {
+        let tags = [9, 7, 8, 0, 1, 2, 3, 4, 5, 6];
+        for (kind, tag) in TxKind::DECODABLE.into_iter().zip(tags) {
+            assert_eq!(borsh::to_vec(&kind).unwrap(), [tag]);
+            assert_eq!(TxKind::from_tag(tag), kind);
+        }
+    }
+
+    #[test]
+    fn test_legacy_kinds_are_decodable_but_not_accepted() {
+        assert!(TxKind::LegacyPublic.is_supported());
+        assert!(TxKind::LegacyPublic.is_legacy());
+        assert!(TxKind::UnschemedPublic.is_supported());
+        assert!(TxKind::UnschemedPublic.is_legacy());
+        assert!(!TxKind::UnschemedPublic.predates_u64_nonces());
+        assert!(TxKind::NarrowFeePublic.is_legacy());
+        assert!(TxKind::NarrowFeePublic.predates_amount_priority_fees());
+        assert!(!TxKind::Public.predates_amount_priority_fees());
+        assert!(!TxKind::SUPPORTED.iter().any(|kind| kind.is_legacy()));
+    }
+
+    #[test]
+    fn test_transaction_of_unknown_kind_is_decoded() {
+        let body = EncodedTransaction {
+            tx_kind: TxKind::Unknown(10),
+            encoded_transaction_data: vec![1, 2, 3, 4],
+        };
+
+        let body_bytes = borsh::to_vec(&body).unwrap();
+        let body_new = borsh::from_slice::<EncodedTransaction>(&body_bytes).unwrap();
+
+        assert_eq!(body_new.tx_kind, TxKind::Unknown(10));
+        assert!(!body_new.tx_kind.is_supported());
+        assert!(crate::transaction::NSSATransaction::try_from(&body_new).is_err());
+    }
 }
@@ -0,0 +1,20 @@
+use anyhow::Result;
+use clap::Parser as _;
+use integration_tests::localnet::{LocalnetArgs, run};
+
+pub const NUM_THREADS: usize = 4;
+
+fn main() -> Result<()> {
+    let args = LocalnetArgs::parse();
+
+    env_logger::init();
+
+    actix::System::with_tokio_rt(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(NUM_THREADS)
+            .enable_all()
+            .build()
+            .unwrap()
+    })
+    .block_on(run(args))
+}
@@ -16,11 +16,14 @@ use sequencer_runner::startup_sequencer;
 use tempfile::TempDir;
 use tokio::task::JoinHandle;
 
-use crate::test_suite_map::{prepare_function_map, tps_test};
+use crate::test_suite_map::{
+    dev_prover_localnet_test, localnet_test, prepare_function_map, sync_reconnect_test, tps_test,
+};
 
 #[macro_use]
 extern crate proc_macro_test_attribute;
 
+pub mod localnet;
 pub mod test_suite_map;
 
 mod tps_test_utils;
@@ -95,8 +98,7 @@ pub async fn post_test(residual: (ServerHandle, JoinHandle<Result<()>>, TempDir)
     sequencer_loop_handle.abort();
     seq_http_server_handle.stop(true).await;
 
-    let wallet_home = wallet::helperfunctions::get_home().unwrap();
-    let persistent_data_home = wallet_home.join("storage.json");
+    let persistent_data_home = wallet::helperfunctions::get_wallet_file().unwrap();
 
     // Removing persistent accounts after run to not affect other executions
     // Not necessary an error, if fails as there is tests for failure scenario
@@ -126,7 +128,14 @@ pub async fn main_tests_runner() -> Result<()> {
             }
             // Run TPS test with its own specific config
             tps_test().await;
+            // Localnet generates its own configs
+            localnet_test().await;
+            dev_prover_localnet_test().await;
+            sync_reconnect_test().await;
         }
+        "localnet_test" => localnet_test().await,
+        "dev_prover_localnet_test" => dev_prover_localnet_test().await,
+        "sync_reconnect_test" => sync_reconnect_test().await,
         _ => {
             let fn_pointer = function_map.get(&test_name).expect("Unknown test name");
 
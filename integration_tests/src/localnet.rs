@@ -0,0 +1,271 @@
+//! Local development network
+//!
+//! Starts a sequencer with a freshly generated genesis in the current process and writes its
+//! config together with a wallet funded by the genesis into one directory, so the wallet CLI can
+//! be used against it right away.
+
+use std::path::{Path, PathBuf};
+
+use actix_web::dev::ServerHandle;
+use anyhow::Result;
+use clap::Parser;
+use log::info;
+use nssa::{AccountId, Amount, PrivateKey, ProverBackend, PublicKey};
+use sequencer_core::config::{
+    AccountInitialData, DEFAULT_DEPLOYMENT_SMOKE_TEST_CYCLES, DeterministicDevModeConfig,
+    SequencerConfig,
+};
+use sequencer_runner::startup_sequencer;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use wallet::{
+    HOME_DIR_ENV_VAR, STORAGE_FILE_NAME,
+    chain_storage::WalletChainStore,
+    config::{InitialAccountData, InitialAccountDataPublic, WalletConfig},
+    helperfunctions::{create_persistent_storage, produce_data_for_storage},
+};
+
+/// Password the localnet wallet keys are derived from
+pub const LOCALNET_WALLET_PASSWORD: &str = "localnet";
+
+#[derive(Parser, Debug, Clone)]
+#[clap(version, about)]
+pub struct LocalnetArgs {
+    /// Directory to write configs, wallet and sequencer storage to, must be empty or missing
+    #[arg(long)]
+    pub dir: PathBuf,
+    /// Interval between blocks in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    pub block_time: u64,
+    /// Number of funded wallet accounts
+    #[arg(long, default_value_t = 2)]
+    pub accounts: usize,
+    /// Genesis balance of every funded account
+    #[arg(long, default_value_t = 1_000_000)]
+    pub fund_amount: u128,
+    /// Prove in zkVM and accept real proofs only. By default the wallet only executes programs
+    /// and produces fake proofs, which the sequencer accepts and flags its blocks as dev-proved.
+    #[arg(long)]
+    pub real_proofs: bool,
+    /// Seed of deterministic dev mode. If set, keys of the localnet and of its transactions are
+    /// drawn from it and blocks are stamped with logical timestamps, so the same flow builds the
+    /// same blocks on every run.
+    #[arg(long)]
+    pub deterministic_seed: Option<u64>,
+}
+
+impl LocalnetArgs {
+    /// Backend the localnet wallet proves with
+    pub fn prover_backend(&self) -> ProverBackend {
+        if self.real_proofs {
+            ProverBackend::Real
+        } else {
+            ProverBackend::DevFake
+        }
+    }
+}
+
+/// Running localnet, stopped by [`Localnet::shutdown`]
+pub struct Localnet {
+    /// Address of the sequencer HTTP endpoints
+    pub sequencer_addr: String,
+    /// Home dir of the localnet wallet
+    pub wallet_dir: PathBuf,
+    /// Keys and ids of the funded accounts
+    pub accounts: Vec<(PrivateKey, AccountId)>,
+    sequencer_config: SequencerConfig,
+    http_server_handle: ServerHandle,
+    main_loop_handle: JoinHandle<Result<()>>,
+}
+
+impl Localnet {
+    pub async fn start(args: &LocalnetArgs) -> Result<Self> {
+        if std::fs::read_dir(&args.dir).is_ok_and(|mut entries| entries.next().is_some()) {
+            anyhow::bail!(
+                "Localnet directory {} is not empty, refusing to reuse it",
+                args.dir.display()
+            );
+        }
+
+        let sequencer_dir = args.dir.join("sequencer");
+        let wallet_dir = args.dir.join("wallet");
+        std::fs::create_dir_all(&sequencer_dir)?;
+        std::fs::create_dir_all(&wallet_dir)?;
+
+        if let Some(seed) = args.deterministic_seed {
+            nssa::entropy::use_deterministic_seed(seed);
+        }
+
+        let port = free_port()?;
+        let sequencer_addr = format!("http://127.0.0.1:{port}");
+
+        let accounts = (0..args.accounts)
+            .map(|_| {
+                let private_key = PrivateKey::new_os_random();
+                let account_id = AccountId::from(&PublicKey::new_from_private_key(&private_key));
+                (private_key, account_id)
+            })
+            .collect::<Vec<_>>();
+
+        let sequencer_config = SequencerConfig {
+            home: sequencer_dir.join("storage"),
+            override_rust_log: None,
+            genesis_id: 1,
+            is_genesis_random: args.deterministic_seed.is_none(),
+            max_num_tx_in_block: 100,
+            mempool_max_size: 10000,
+            block_create_timeout_millis: args.block_time,
+            port,
+            initial_accounts: accounts
+                .iter()
+                .map(|(_, account_id)| AccountInitialData {
+                    account_id: account_id.to_string(),
+                    balance: Amount(args.fund_amount),
+                })
+                .collect(),
+            initial_commitments: vec![],
+            signing_key: *PrivateKey::new_os_random().value(),
+            check_state_invariants: true,
+            lazy_proving: None,
+            mempool_wal: false,
+            ingest_queue_size: 1000,
+            prune: None,
+            soft_confirmation_margin_blocks: 10,
+            event_sink: None,
+            program_allow_list: sequencer_core::config::default_program_allow_list(),
+            max_block_timestamp_skew_millis: 30_000,
+            max_pending_per_sender: None,
+            max_consecutive_per_sender: None,
+            max_time_lock_blocks: 100_000,
+            max_parked_per_sender: 16,
+            upgrades: vec![],
+            storage_deposit_per_byte: Amount::ZERO,
+            max_instruction_data_size: nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+            max_program_size: nssa::program::DEFAULT_MAX_PROGRAM_SIZE,
+            anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+            chain_id: 0,
+            accept_dev_proofs: args.prover_backend() == ProverBackend::DevFake,
+            deterministic_dev_mode: args
+                .deterministic_seed
+                .map(|seed| DeterministicDevModeConfig { seed }),
+            serve_pending_transfers: true,
+            serve_pending_transactions: true,
+            system_lane: None,
+            tx_audit: Default::default(),
+            deployment_smoke_test_cycles: Some(DEFAULT_DEPLOYMENT_SMOKE_TEST_CYCLES),
+            force_params_change: false,
+            repair_indexes: false,
+        };
+        write_json(
+            &sequencer_dir.join("sequencer_config.json"),
+            &sequencer_config,
+        )?;
+
+        let wallet_config = WalletConfig {
+            sequencer_addr: sequencer_addr.clone(),
+            seq_poll_timeout_millis: args.block_time,
+            initial_accounts: accounts
+                .iter()
+                .map(|(private_key, account_id)| {
+                    InitialAccountData::Public(InitialAccountDataPublic {
+                        account_id: account_id.to_string(),
+                        pub_sign_key: private_key.clone(),
+                    })
+                })
+                .collect(),
+            prover_backend: args.prover_backend(),
+            ..WalletConfig::default()
+        };
+        write_json(&wallet_dir.join("wallet_config.json"), &wallet_config)?;
+
+        let wallet_storage =
+            WalletChainStore::new_storage(wallet_config, LOCALNET_WALLET_PASSWORD.to_owned())?;
+        create_persistent_storage(
+            &wallet_dir.join(STORAGE_FILE_NAME),
+            &produce_data_for_storage(&wallet_storage.user_data, 0),
+        )
+        .await?;
+
+        let (http_server_handle, main_loop_handle) =
+            startup_sequencer(sequencer_config.clone()).await?;
+
+        Ok(Self {
+            sequencer_addr,
+            wallet_dir,
+            accounts,
+            sequencer_config,
+            http_server_handle,
+            main_loop_handle,
+        })
+    }
+
+    /// Print how to connect to the localnet
+    pub fn print_connection_info(&self, fund_amount: u128) {
+        println!("Localnet is running, press Ctrl-C to stop");
+        println!("Sequencer: {}", self.sequencer_addr);
+        println!(
+            "Wallet: export {HOME_DIR_ENV_VAR}={}, password {LOCALNET_WALLET_PASSWORD:?}",
+            self.wallet_dir.display()
+        );
+        if self.sequencer_config.accept_dev_proofs {
+            println!("Proofs are fake, blocks with them are flagged as dev-proved");
+        }
+        println!("Funded accounts:");
+        for (_, account_id) in &self.accounts {
+            println!("  Public/{account_id} with balance {fund_amount}");
+        }
+    }
+
+    /// Stop the sequencer and start it again from its storage after `downtime` at the same address
+    ///
+    /// Storage is released only once the ingest task notices the stopped HTTP server, so
+    /// `downtime` must leave it some time.
+    pub async fn restart_sequencer(&mut self, downtime: std::time::Duration) -> Result<()> {
+        self.stop_sequencer().await;
+        tokio::time::sleep(downtime).await;
+
+        let (http_server_handle, main_loop_handle) =
+            startup_sequencer(self.sequencer_config.clone()).await?;
+        self.http_server_handle = http_server_handle;
+        self.main_loop_handle = main_loop_handle;
+
+        Ok(())
+    }
+
+    /// Stop block production and the HTTP server, releasing sequencer storage
+    pub async fn shutdown(mut self) {
+        self.stop_sequencer().await;
+    }
+
+    async fn stop_sequencer(&mut self) {
+        self.main_loop_handle.abort();
+        self.http_server_handle.stop(true).await;
+        // Sequencer core is dropped together with the aborted task
+        let _ = (&mut self.main_loop_handle).await;
+    }
+}
+
+/// Run localnet until Ctrl-C
+pub async fn run(args: LocalnetArgs) -> Result<()> {
+    let localnet = Localnet::start(&args).await?;
+    localnet.print_connection_info(args.fund_amount);
+
+    tokio::signal::ctrl_c().await?;
+
+    info!("Shutting down localnet");
+    localnet.shutdown().await;
+
+    Ok(())
+}
+
+/// Port free at the moment, the sequencer binds it right after
+fn free_port() -> Result<u16> {
+    Ok(std::net::TcpListener::bind("127.0.0.1:0")?
+        .local_addr()?
+        .port())
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    std::fs::write(path, serde_json::to_vec_pretty(value)?)?;
+    Ok(())
+}
@@ -8,35 +8,40 @@ use std::{
 
 use actix_web::dev::ServerHandle;
 use anyhow::Result;
-use common::{PINATA_BASE58, sequencer_client::SequencerClient};
+use common::{PINATA_BASE58, block::HashableBlockData, sequencer_client::SequencerClient};
 use key_protocol::key_management::key_tree::chain_index::ChainIndex;
 use log::info;
-use nssa::{AccountId, program::Program};
+use nssa::{AccountId, Amount, PublicTransaction, program::Program, public_transaction as putx};
 use nssa_core::{NullifierPublicKey, encryption::shared_key_derivation::Secp256k1Point};
 use sequencer_runner::startup_sequencer;
 use tempfile::TempDir;
 use tokio::task::JoinHandle;
 use wallet::{
     WalletCore,
+    api::{OwnedAccount, TransferOptions},
     cli::{
         Command, SubcommandReturnValue,
         account::{AccountSubcommand, NewSubcommand},
         config::ConfigSubcommand,
         programs::{
-            native_token_transfer::AuthTransferSubcommand, pinata::PinataProgramAgnosticSubcommand,
-            token::TokenProgramAgnosticSubcommand,
+            deployment::ProgramSubcommand, native_token_transfer::AuthTransferSubcommand,
+            pinata::PinataProgramAgnosticSubcommand, token::TokenProgramAgnosticSubcommand,
         },
+        sync_continuously,
     },
-    config::PersistentStorage,
-    helperfunctions::{fetch_config, fetch_persistent_storage},
+    config::{InitialAccountData, InitialAccountDataPublic, PersistentStorage, WalletConfig},
+    helperfunctions::{AccountPrivacyKind, fetch_config, fetch_persistent_storage},
+    program_facades::native_token_transfer::NativeTokenTransfer,
 };
 
 use crate::{
     ACC_RECEIVER, ACC_RECEIVER_PRIVATE, ACC_SENDER, ACC_SENDER_PRIVATE,
     NSSA_PROGRAM_FOR_TEST_DATA_CHANGER, TIME_TO_WAIT_FOR_BLOCK_SECONDS,
-    fetch_privacy_preserving_tx, make_private_account_input_from_str,
-    make_public_account_input_from_str, post_test, pre_test,
-    replace_home_dir_with_temp_dir_in_configs, tps_test_utils::TpsTestManager,
+    fetch_privacy_preserving_tx,
+    localnet::{Localnet, LocalnetArgs},
+    make_private_account_input_from_str, make_public_account_input_from_str, post_test, pre_test,
+    replace_home_dir_with_temp_dir_in_configs,
+    tps_test_utils::TpsTestManager,
     verify_commitment_is_in_state,
 };
 
@@ -53,7 +58,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: Some(make_public_account_input_from_str(ACC_RECEIVER)),
             to_npk: None,
             to_ipk: None,
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         let wallet_config = fetch_config().await.unwrap();
@@ -78,8 +84,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         info!("Balance of sender : {acc_1_balance:#?}");
         info!("Balance of receiver : {acc_2_balance:#?}");
 
-        assert_eq!(acc_1_balance.balance, 9900);
-        assert_eq!(acc_2_balance.balance, 20100);
+        assert_eq!(acc_1_balance.balance, Amount(9900));
+        assert_eq!(acc_2_balance.balance, Amount(20100));
 
         info!("Success!");
     }
@@ -87,7 +93,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
     #[nssa_integration_test]
     pub async fn test_success_move_to_another_account() {
         info!("########## test_success_move_to_another_account ##########");
-        let command = Command::Account(AccountSubcommand::New(NewSubcommand::Public { cci: None }));
+        let command = Command::Account(AccountSubcommand::New(NewSubcommand::Public {
+            cci: None,
+            account: None,
+        }));
 
         let wallet_config = fetch_config().await.unwrap();
 
@@ -97,7 +106,7 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
 
         let PersistentStorage {
             accounts: persistent_accounts,
-            last_synced_block: _,
+            ..
         } = fetch_persistent_storage().await.unwrap();
 
         let mut new_persistent_account_id = String::new();
@@ -121,7 +130,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             )),
             to_npk: None,
             to_ipk: None,
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         wallet::cli::execute_subcommand(command).await.unwrap();
@@ -142,8 +152,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         info!("Balance of sender : {acc_1_balance:#?}");
         info!("Balance of receiver : {acc_2_balance:#?}");
 
-        assert_eq!(acc_1_balance.balance, 9900);
-        assert_eq!(acc_2_balance.balance, 100);
+        assert_eq!(acc_1_balance.balance, Amount(9900));
+        assert_eq!(acc_2_balance.balance, Amount(100));
 
         info!("Success!");
     }
@@ -156,7 +166,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: Some(make_public_account_input_from_str(ACC_RECEIVER)),
             to_npk: None,
             to_ipk: None,
-            amount: 1000000,
+            amount: Amount(1000000),
+            change_address: None,
         });
 
         let wallet_config = fetch_config().await.unwrap();
@@ -183,8 +194,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         info!("Balance of sender : {acc_1_balance:#?}");
         info!("Balance of receiver : {acc_2_balance:#?}");
 
-        assert_eq!(acc_1_balance.balance, 10000);
-        assert_eq!(acc_2_balance.balance, 20000);
+        assert_eq!(acc_1_balance.balance, Amount(10000));
+        assert_eq!(acc_2_balance.balance, Amount(20000));
 
         info!("Success!");
     }
@@ -197,7 +208,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: Some(make_public_account_input_from_str(ACC_RECEIVER)),
             to_npk: None,
             to_ipk: None,
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         let wallet_config = fetch_config().await.unwrap();
@@ -222,8 +234,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         info!("Balance of sender : {acc_1_balance:#?}");
         info!("Balance of receiver : {acc_2_balance:#?}");
 
-        assert_eq!(acc_1_balance.balance, 9900);
-        assert_eq!(acc_2_balance.balance, 20100);
+        assert_eq!(acc_1_balance.balance, Amount(9900));
+        assert_eq!(acc_2_balance.balance, Amount(20100));
 
         info!("First TX Success!");
 
@@ -232,7 +244,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: Some(make_public_account_input_from_str(ACC_RECEIVER)),
             to_npk: None,
             to_ipk: None,
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         wallet::cli::execute_subcommand(command).await.unwrap();
@@ -253,8 +266,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         info!("Balance of sender : {acc_1_balance:#?}");
         info!("Balance of receiver : {acc_2_balance:#?}");
 
-        assert_eq!(acc_1_balance.balance, 9800);
-        assert_eq!(acc_2_balance.balance, 20200);
+        assert_eq!(acc_1_balance.balance, Amount(9800));
+        assert_eq!(acc_2_balance.balance, Amount(20200));
 
         info!("Second TX Success!");
     }
@@ -275,7 +288,7 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             account.program_owner,
             Program::authenticated_transfer_program().id()
         );
-        assert_eq!(account.balance, 10000);
+        assert_eq!(account.balance, Amount(10000));
         assert!(account.data.is_empty());
         assert_eq!(account.nonce, 0);
     }
@@ -291,7 +304,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         let SubcommandReturnValue::RegisterAccount {
             account_id: definition_account_id,
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
-            NewSubcommand::Public { cci: None },
+            NewSubcommand::Public {
+                cci: None,
+                account: None,
+            },
         )))
         .await
         .unwrap()
@@ -302,7 +318,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         let SubcommandReturnValue::RegisterAccount {
             account_id: supply_account_id,
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
-            NewSubcommand::Public { cci: None },
+            NewSubcommand::Public {
+                cci: None,
+                account: None,
+            },
         )))
         .await
         .unwrap()
@@ -313,7 +332,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         let SubcommandReturnValue::RegisterAccount {
             account_id: recipient_account_id,
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
-            NewSubcommand::Public { cci: None },
+            NewSubcommand::Public {
+                cci: None,
+                account: None,
+            },
         )))
         .await
         .unwrap()
@@ -448,7 +470,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         let SubcommandReturnValue::RegisterAccount {
             account_id: definition_account_id,
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
-            NewSubcommand::Public { cci: None },
+            NewSubcommand::Public {
+                cci: None,
+                account: None,
+            },
         )))
         .await
         .unwrap()
@@ -618,6 +643,7 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
             NewSubcommand::Public {
                 cci: Some(ChainIndex::root()),
+                account: None,
             },
         )))
         .await
@@ -786,7 +812,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         let SubcommandReturnValue::RegisterAccount {
             account_id: definition_account_id,
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
-            NewSubcommand::Public { cci: None },
+            NewSubcommand::Public {
+                cci: None,
+                account: None,
+            },
         )))
         .await
         .unwrap()
@@ -922,7 +951,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         let SubcommandReturnValue::RegisterAccount {
             account_id: definition_account_id,
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
-            NewSubcommand::Public { cci: None },
+            NewSubcommand::Public {
+                cci: None,
+                account: None,
+            },
         )))
         .await
         .unwrap()
@@ -933,7 +965,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         let SubcommandReturnValue::RegisterAccount {
             account_id: supply_account_id,
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
-            NewSubcommand::Public { cci: None },
+            NewSubcommand::Public {
+                cci: None,
+                account: None,
+            },
         )))
         .await
         .unwrap()
@@ -1058,7 +1093,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         let SubcommandReturnValue::RegisterAccount {
             account_id: definition_account_id,
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
-            NewSubcommand::Public { cci: None },
+            NewSubcommand::Public {
+                cci: None,
+                account: None,
+            },
         )))
         .await
         .unwrap()
@@ -1080,7 +1118,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
         let SubcommandReturnValue::RegisterAccount {
             account_id: recipient_account_id,
         } = wallet::cli::execute_subcommand(Command::Account(AccountSubcommand::New(
-            NewSubcommand::Public { cci: None },
+            NewSubcommand::Public {
+                cci: None,
+                account: None,
+            },
         )))
         .await
         .unwrap()
@@ -1203,7 +1244,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: Some(make_private_account_input_from_str(&to.to_string())),
             to_npk: None,
             to_ipk: None,
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         wallet::cli::execute_subcommand(command).await.unwrap();
@@ -1241,7 +1283,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: None,
             to_npk: Some(to_npk_string),
             to_ipk: Some(hex::encode(to_ipk.0)),
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         let SubcommandReturnValue::PrivacyPreservingTransfer { tx_hash } =
@@ -1310,7 +1353,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: None,
             to_npk: Some(hex::encode(to_keys.nullifer_public_key.0)),
             to_ipk: Some(hex::encode(to_keys.incoming_viewing_public_key.0)),
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         let sub_ret = wallet::cli::execute_subcommand(command).await.unwrap();
@@ -1338,7 +1382,7 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
 
         let to_res_acc = wallet_storage.get_account_private(&to_account_id).unwrap();
 
-        assert_eq!(to_res_acc.balance, 100);
+        assert_eq!(to_res_acc.balance, Amount(100));
 
         info!("Success!");
     }
@@ -1423,7 +1467,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: Some(make_public_account_input_from_str(&to.to_string())),
             to_npk: None,
             to_ipk: None,
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         let wallet_config = fetch_config().await.unwrap();
@@ -1433,7 +1478,7 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             .unwrap();
 
         let from_acc = wallet_storage.get_account_private(&from).unwrap();
-        assert_eq!(from_acc.balance, 10000);
+        assert_eq!(from_acc.balance, Amount(10000));
 
         wallet::cli::execute_subcommand(command).await.unwrap();
 
@@ -1455,8 +1500,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             .await
             .unwrap();
 
-        assert_eq!(from_acc.balance, 10000 - 100);
-        assert_eq!(acc_2_balance.balance, 20100);
+        assert_eq!(from_acc.balance, Amount(10000 - 100));
+        assert_eq!(acc_2_balance.balance, Amount(20100));
 
         info!("Success!");
     }
@@ -1472,7 +1517,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: Some(make_private_account_input_from_str(&to.to_string())),
             to_npk: None,
             to_ipk: None,
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         let wallet_config = fetch_config().await.unwrap();
@@ -1497,8 +1543,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             .await
             .unwrap();
 
-        assert_eq!(acc_from_balance.balance, 9900);
-        assert_eq!(acc_to.balance, 20000 + 100);
+        assert_eq!(acc_from_balance.balance, Amount(9900));
+        assert_eq!(acc_to.balance, Amount(20000 + 100));
 
         info!("Success!");
     }
@@ -1516,7 +1562,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             to: None,
             to_npk: Some(to_npk_string),
             to_ipk: Some(hex::encode(to_ipk.0)),
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         let wallet_config = fetch_config().await.unwrap();
@@ -1543,7 +1590,7 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             verify_commitment_is_in_state(tx.message.new_commitments[0].clone(), &seq_client).await
         );
 
-        assert_eq!(acc_1_balance.balance, 9900);
+        assert_eq!(acc_1_balance.balance, Amount(9900));
 
         info!("Success!");
     }
@@ -1586,8 +1633,11 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             .unwrap()
             .balance;
 
-        assert_eq!(pinata_balance_post, pinata_balance_pre - pinata_prize);
-        assert_eq!(winner_balance_post, 10000 + pinata_prize);
+        assert_eq!(
+            pinata_balance_post,
+            Amount(pinata_balance_pre.0 - pinata_prize)
+        );
+        assert_eq!(winner_balance_post, Amount(10000 + pinata_prize));
 
         info!("Success!");
     }
@@ -1598,24 +1648,43 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
 
         let binary_filepath: PathBuf = NSSA_PROGRAM_FOR_TEST_DATA_CHANGER.parse().unwrap();
 
-        let command = Command::DeployProgram {
-            binary_filepath: binary_filepath.clone(),
-        };
-
-        wallet::cli::execute_subcommand(command).await.unwrap();
-
         let wallet_config = fetch_config().await.unwrap();
         let seq_client = SequencerClient::new(wallet_config.sequencer_addr.clone()).unwrap();
+        let deployer_pre = seq_client
+            .get_account(ACC_SENDER.to_string())
+            .await
+            .unwrap()
+            .account;
+
+        let command = Command::Program(ProgramSubcommand::Deploy {
+            elf: binary_filepath.clone(),
+            from: make_public_account_input_from_str(ACC_SENDER),
+        });
+
+        wallet::cli::execute_subcommand(command).await.unwrap();
 
         info!("Waiting for next block creation");
         tokio::time::sleep(Duration::from_secs(TIME_TO_WAIT_FOR_BLOCK_SECONDS)).await;
 
+        // Deployer pays for the bytecode and spends its nonce
+        let bytecode = std::fs::read(&binary_filepath).unwrap();
+        let fee = nssa::gas::GasCalculator::default().deployment_gas(bytecode.len());
+        let deployer_post = seq_client
+            .get_account(ACC_SENDER.to_string())
+            .await
+            .unwrap()
+            .account;
+        assert_eq!(
+            deployer_post.balance,
+            deployer_pre.balance.saturating_sub(fee.into())
+        );
+        assert_eq!(deployer_post.nonce, deployer_pre.nonce + 1);
+
         // The program is the data changer and takes one account as input.
         // We pass an uninitialized account and we expect after execution to be owned by the data
         // changer program (NSSA account claiming mechanism) with data equal to [0] (due to program
         // logic)
         //
-        let bytecode = std::fs::read(binary_filepath).unwrap();
         let data_changer = Program::new(bytecode).unwrap();
         let account_id: AccountId = "11".repeat(16).parse().unwrap();
         let message = nssa::public_transaction::Message::try_new(
@@ -1638,17 +1707,70 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             .unwrap()
             .account;
         assert_eq!(post_state_account.program_owner, data_changer.id());
-        assert_eq!(post_state_account.balance, 0);
+        assert_eq!(post_state_account.balance, Amount::ZERO);
         assert_eq!(post_state_account.data.as_ref(), &[0]);
         assert_eq!(post_state_account.nonce, 0);
 
         info!("Success!");
     }
 
+    #[nssa_integration_test]
+    pub async fn test_sdk_program_deployment() {
+        info!("########## test deployment of program written with SDK ##########");
+
+        let binary_dir = tempfile::tempdir().unwrap();
+        let binary_filepath = binary_dir.path().join("counter.bin");
+        std::fs::write(&binary_filepath, test_program_methods::COUNTER_ELF).unwrap();
+
+        let command = Command::Program(ProgramSubcommand::Deploy {
+            elf: binary_filepath.clone(),
+            from: make_public_account_input_from_str(ACC_SENDER),
+        });
+        wallet::cli::execute_subcommand(command).await.unwrap();
+
+        let wallet_config = fetch_config().await.unwrap();
+        let seq_client = SequencerClient::new(wallet_config.sequencer_addr.clone()).unwrap();
+
+        info!("Waiting for next block creation");
+        tokio::time::sleep(Duration::from_secs(TIME_TO_WAIT_FOR_BLOCK_SECONDS)).await;
+
+        let counter = Program::new(test_program_methods::COUNTER_ELF.to_vec()).unwrap();
+        let account_id: AccountId = "22".repeat(16).parse().unwrap();
+        for increment in [3_u64, 4] {
+            let message = nssa::public_transaction::Message::try_new(
+                counter.id(),
+                vec![account_id],
+                vec![],
+                increment,
+            )
+            .unwrap();
+            let witness_set = nssa::public_transaction::WitnessSet::for_message(&message, &[]);
+            let transaction = nssa::PublicTransaction::new(message, witness_set);
+            seq_client.send_tx_public(transaction).await.unwrap();
+
+            info!("Waiting for next block creation");
+            tokio::time::sleep(Duration::from_secs(TIME_TO_WAIT_FOR_BLOCK_SECONDS)).await;
+        }
+
+        // Account is claimed by the counter on the first call and incremented by both calls
+        let account = seq_client
+            .get_account(account_id.to_string())
+            .await
+            .unwrap()
+            .account;
+        assert_eq!(account.program_owner, counter.id());
+        assert_eq!(account.data.as_ref(), &7_u64.to_le_bytes());
+
+        info!("Success!");
+    }
+
     #[nssa_integration_test]
     pub async fn test_authenticated_transfer_initialize_function() {
         info!("########## test initialize account for authenticated transfer ##########");
-        let command = Command::Account(AccountSubcommand::New(NewSubcommand::Public { cci: None }));
+        let command = Command::Account(AccountSubcommand::New(NewSubcommand::Public {
+            cci: None,
+            account: None,
+        }));
         let SubcommandReturnValue::RegisterAccount { account_id } =
             wallet::cli::execute_subcommand(command).await.unwrap()
         else {
@@ -1671,7 +1793,7 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
 
         let expected_program_owner = Program::authenticated_transfer_program().id();
         let expected_nonce = 1;
-        let expected_balance = 0;
+        let expected_balance = Amount::ZERO;
 
         assert_eq!(account.program_owner, expected_program_owner);
         assert_eq!(account.balance, expected_balance);
@@ -1731,7 +1853,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             .unwrap();
         assert!(verify_commitment_is_in_state(new_commitment1, &seq_client).await);
 
-        assert_eq!(pinata_balance_post, pinata_balance_pre - pinata_prize);
+        assert_eq!(
+            pinata_balance_post,
+            Amount(pinata_balance_pre.0 - pinata_prize)
+        );
 
         info!("Success!");
     }
@@ -1791,7 +1916,10 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             .unwrap();
         assert!(verify_commitment_is_in_state(new_commitment1, &seq_client).await);
 
-        assert_eq!(pinata_balance_post, pinata_balance_pre - pinata_prize);
+        assert_eq!(
+            pinata_balance_post,
+            Amount(pinata_balance_pre.0 - pinata_prize)
+        );
 
         info!("Success!");
     }
@@ -1860,7 +1988,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             )),
             to_npk: None,
             to_ipk: None,
-            amount: 100,
+            amount: Amount(100),
+            change_address: None,
         });
 
         wallet::cli::execute_subcommand(command).await.unwrap();
@@ -1872,7 +2001,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             )),
             to_npk: None,
             to_ipk: None,
-            amount: 101,
+            amount: Amount(101),
+            change_address: None,
         });
 
         wallet::cli::execute_subcommand(command).await.unwrap();
@@ -1881,6 +2011,7 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
 
         let command = Command::Account(AccountSubcommand::New(NewSubcommand::Public {
             cci: Some(ChainIndex::root()),
+            account: None,
         }));
 
         let sub_ret = wallet::cli::execute_subcommand(command).await.unwrap();
@@ -1893,6 +2024,7 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
 
         let command = Command::Account(AccountSubcommand::New(NewSubcommand::Public {
             cci: Some(ChainIndex::from_str("/0").unwrap()),
+            account: None,
         }));
 
         let sub_ret = wallet::cli::execute_subcommand(command).await.unwrap();
@@ -1910,7 +2042,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             )),
             to_npk: None,
             to_ipk: None,
-            amount: 102,
+            amount: Amount(102),
+            change_address: None,
         });
 
         wallet::cli::execute_subcommand(command).await.unwrap();
@@ -1922,7 +2055,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             )),
             to_npk: None,
             to_ipk: None,
-            amount: 103,
+            amount: Amount(103),
+            change_address: None,
         });
 
         wallet::cli::execute_subcommand(command).await.unwrap();
@@ -1975,8 +2109,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             Program::authenticated_transfer_program().id()
         );
 
-        assert_eq!(acc1.value.1.balance, 100);
-        assert_eq!(acc2.value.1.balance, 101);
+        assert_eq!(acc1.value.1.balance, Amount(100));
+        assert_eq!(acc2.value.1.balance, Amount(101));
 
         info!("########## TREE CHECKS END ##########");
 
@@ -1987,7 +2121,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             )),
             to_npk: None,
             to_ipk: None,
-            amount: 10,
+            amount: Amount(10),
+            change_address: None,
         });
 
         wallet::cli::execute_subcommand(command).await.unwrap();
@@ -1999,7 +2134,8 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             )),
             to_npk: None,
             to_ipk: None,
-            amount: 11,
+            amount: Amount(11),
+            change_address: None,
         });
 
         wallet::cli::execute_subcommand(command).await.unwrap();
@@ -2029,8 +2165,108 @@ pub fn prepare_function_map() -> HashMap<String, TestFunction> {
             .await
             .expect("Acc 4 must be present in public state");
 
-        assert_eq!(acc3.balance, 91);
-        assert_eq!(acc4.balance, 114);
+        assert_eq!(acc3.balance, Amount(91));
+        assert_eq!(acc4.balance, Amount(114));
+
+        info!("Success!");
+    }
+
+    #[nssa_integration_test]
+    pub async fn test_wallet_core_library_api() {
+        info!("########## test_wallet_core_library_api ##########");
+        let wallet_config = fetch_config().await.unwrap();
+        let mut wallet_core = WalletCore::start_from_config_update_chain(wallet_config)
+            .await
+            .unwrap();
+        let inclusion_timeout = Duration::from_secs(2 * TIME_TO_WAIT_FOR_BLOCK_SECONDS);
+
+        let sender: AccountId = ACC_SENDER.parse().unwrap();
+        let receiver: AccountId = ACC_RECEIVER.parse().unwrap();
+        let sender_private: AccountId = ACC_SENDER_PRIVATE.parse().unwrap();
+        let receiver_private: AccountId = ACC_RECEIVER_PRIVATE.parse().unwrap();
+
+        let accounts = wallet_core.list_accounts();
+        for (account_id, privacy) in [
+            (sender, AccountPrivacyKind::Public),
+            (receiver, AccountPrivacyKind::Public),
+            (sender_private, AccountPrivacyKind::Private),
+            (receiver_private, AccountPrivacyKind::Private),
+        ] {
+            assert!(accounts.contains(&OwnedAccount {
+                account_id,
+                privacy,
+                chain_index: None,
+            }));
+        }
+
+        info!("Public transfer");
+        let sender_balance = wallet_core.balance(sender).await.unwrap();
+        let receiver_balance = wallet_core.balance(receiver).await.unwrap();
+        let hash = wallet_core
+            .transfer(sender, receiver, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        let block_id = wallet_core
+            .wait_for_inclusion(&hash, inclusion_timeout)
+            .await
+            .unwrap();
+        assert_eq!(
+            wallet_core.balance(sender).await.unwrap(),
+            Amount(sender_balance.0 - 100)
+        );
+        assert_eq!(
+            wallet_core.balance(receiver).await.unwrap(),
+            Amount(receiver_balance.0 + 100)
+        );
+
+        info!("Private transfer");
+        let sender_balance = wallet_core.balance(sender_private).await.unwrap();
+        let receiver_balance = wallet_core.balance(receiver_private).await.unwrap();
+        let hash = wallet_core
+            .transfer(
+                sender_private,
+                receiver_private,
+                Amount(10),
+                TransferOptions::default(),
+            )
+            .await
+            .unwrap();
+        let private_block_id = wallet_core
+            .wait_for_inclusion(&hash, inclusion_timeout)
+            .await
+            .unwrap();
+        assert!(private_block_id >= block_id);
+
+        // Private balances are updated only by sync
+        assert_eq!(
+            wallet_core.balance(sender_private).await.unwrap(),
+            sender_balance
+        );
+        let synced_block = wallet_core.sync().await.unwrap();
+        assert!(synced_block >= private_block_id);
+        assert_eq!(wallet_core.last_synced_block, synced_block);
+        assert_eq!(
+            wallet_core.balance(sender_private).await.unwrap(),
+            Amount(sender_balance.0 - 10)
+        );
+        assert_eq!(
+            wallet_core.balance(receiver_private).await.unwrap(),
+            Amount(receiver_balance.0 + 10)
+        );
+
+        info!("Transfer from foreign account");
+        let result = wallet_core
+            .transfer(
+                AccountId::new([42; 32]),
+                receiver,
+                Amount(1),
+                TransferOptions::default(),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(common::error::ExecutionFailureKind::KeyNotFoundError)
+        ));
 
         info!("Success!");
     }
@@ -2119,3 +2355,231 @@ pub async fn tps_test() {
 
     post_test(res).await;
 }
+
+/// Time budget of the whole localnet test, from start to shutdown
+const LOCALNET_TEST_TIME_BUDGET: Duration = Duration::from_secs(60);
+
+/// Starts a localnet, sends one transfer through its HTTP endpoints and shuts it down
+pub async fn localnet_test() {
+    info!("########## localnet_test ##########");
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fund_amount = 10_000;
+    let args = LocalnetArgs {
+        dir: temp_dir.path().join("localnet"),
+        block_time: 1000,
+        accounts: 2,
+        fund_amount,
+        real_proofs: false,
+        deterministic_seed: None,
+    };
+
+    let test = async {
+        let localnet = Localnet::start(&args).await.unwrap();
+        let seq_client = SequencerClient::new(localnet.sequencer_addr.clone()).unwrap();
+        let (sender_key, sender) = &localnet.accounts[0];
+        let (_, receiver) = &localnet.accounts[1];
+
+        let message = putx::Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![*sender, *receiver],
+            vec![0],
+            100_u128,
+        )
+        .unwrap();
+        let witness_set = putx::WitnessSet::for_message(&message, &[sender_key]);
+        seq_client
+            .send_tx_public(PublicTransaction::new(message, witness_set))
+            .await
+            .unwrap();
+
+        info!("Waiting for the transfer to be included");
+        loop {
+            let receiver_balance = seq_client
+                .get_account_balance(receiver.to_string())
+                .await
+                .unwrap()
+                .balance;
+            if receiver_balance == Amount(fund_amount + 100) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+
+        localnet.shutdown().await;
+    };
+
+    tokio::time::timeout(LOCALNET_TEST_TIME_BUDGET, test)
+        .await
+        .expect("Localnet test exceeded its time budget");
+
+    info!("Localnet test finished successfully");
+}
+
+/// Time budget of the whole dev prover test, from start to shutdown. Proving the shielded
+/// transfer in zkVM alone takes longer.
+const DEV_PROVER_TEST_TIME_BUDGET: Duration = Duration::from_secs(60);
+
+/// Sends a shielded transfer, proven by the fake prover backend, through a localnet and checks,
+/// that its block is flagged as dev-proved
+pub async fn dev_prover_localnet_test() {
+    info!("########## dev_prover_localnet_test ##########");
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fund_amount = 10_000;
+    let args = LocalnetArgs {
+        dir: temp_dir.path().join("localnet"),
+        block_time: 300,
+        accounts: 1,
+        fund_amount,
+        real_proofs: false,
+        deterministic_seed: None,
+    };
+
+    let test = async {
+        let localnet = Localnet::start(&args).await.unwrap();
+        let seq_client = SequencerClient::new(localnet.sequencer_addr.clone()).unwrap();
+        let (sender_key, sender) = &localnet.accounts[0];
+        let wallet_config = WalletConfig {
+            sequencer_addr: localnet.sequencer_addr.clone(),
+            seq_poll_timeout_millis: args.block_time,
+            initial_accounts: vec![InitialAccountData::Public(InitialAccountDataPublic {
+                account_id: sender.to_string(),
+                pub_sign_key: sender_key.clone(),
+            })],
+            prover_backend: args.prover_backend(),
+            ..WalletConfig::default()
+        };
+        let mut wallet_core =
+            WalletCore::start_from_config_new_storage(wallet_config, "test_pass".to_owned())
+                .await
+                .unwrap();
+        let (receiver, _) = wallet_core.create_new_account_private(None);
+
+        let started = Instant::now();
+        let (response, _) = NativeTokenTransfer(&wallet_core)
+            .send_shielded_transfer(*sender, receiver, Amount(100))
+            .await
+            .unwrap();
+        info!(
+            "Shielded transfer is proven and sent in {:?}",
+            started.elapsed()
+        );
+
+        info!("Waiting for the transfer to be included");
+        let tx_hash = hex::decode(&response.tx_hash).unwrap();
+        let mut block_id = seq_client.get_last_block().await.unwrap().last_block;
+        let block = loop {
+            let last_block = seq_client.get_last_block().await.unwrap().last_block;
+            if block_id > last_block {
+                tokio::time::sleep(Duration::from_millis(args.block_time)).await;
+                continue;
+            }
+            let block_bytes = seq_client.get_block(block_id).await.unwrap().block;
+            let block = borsh::from_slice::<HashableBlockData>(&block_bytes).unwrap();
+            if block
+                .transactions
+                .iter()
+                .any(|tx| tx.hash().as_slice() == tx_hash)
+            {
+                break block;
+            }
+            block_id += 1;
+        };
+        assert!(block.dev_proved);
+        let sender_balance = seq_client
+            .get_account_balance(sender.to_string())
+            .await
+            .unwrap()
+            .balance;
+        assert_eq!(sender_balance, Amount(fund_amount - 100));
+
+        localnet.shutdown().await;
+    };
+
+    tokio::time::timeout(DEV_PROVER_TEST_TIME_BUDGET, test)
+        .await
+        .expect("Dev prover test exceeded its time budget");
+
+    // Wallet storage is written to the wallet home of the test run, as in other tests
+    let _ = std::fs::remove_file(wallet::helperfunctions::get_wallet_file().unwrap())
+        .inspect_err(|err| log::warn!("Failed to remove persistent data with err {err:#?}"));
+
+    info!("Dev prover test finished successfully");
+}
+
+/// Time budget of the whole sync reconnect test, from start to shutdown
+const SYNC_RECONNECT_TEST_TIME_BUDGET: Duration = Duration::from_secs(60);
+
+/// Restarts the sequencer while the wallet is continuously syncing from it and checks, that the
+/// wallet catches up with the restarted sequencer on its own
+pub async fn sync_reconnect_test() {
+    info!("########## sync_reconnect_test ##########");
+    let temp_dir = tempfile::tempdir().unwrap();
+    let args = LocalnetArgs {
+        dir: temp_dir.path().join("localnet"),
+        block_time: 300,
+        accounts: 1,
+        fund_amount: 10_000,
+        real_proofs: false,
+        deterministic_seed: None,
+    };
+
+    let test = async {
+        let mut localnet = Localnet::start(&args).await.unwrap();
+        let seq_client = SequencerClient::new(localnet.sequencer_addr.clone()).unwrap();
+        let wallet_config = WalletConfig {
+            sequencer_addr: localnet.sequencer_addr.clone(),
+            seq_poll_timeout_millis: args.block_time,
+            seq_reconnect_max_delay_millis: 1000,
+            ..WalletConfig::default()
+        };
+        let mut wallet_core =
+            WalletCore::start_from_config_new_storage(wallet_config, "test_pass".to_owned())
+                .await
+                .unwrap();
+
+        let restart = async {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            let last_block_before_restart = seq_client.get_last_block().await.unwrap().last_block;
+
+            info!("Restarting sequencer at block {last_block_before_restart}");
+            localnet
+                .restart_sequencer(Duration::from_secs(3))
+                .await
+                .unwrap();
+
+            info!("Waiting for the restarted sequencer to produce blocks");
+            loop {
+                let last_block = seq_client.get_last_block().await.unwrap().last_block;
+                if last_block > last_block_before_restart + 3 {
+                    break last_block;
+                }
+                tokio::time::sleep(Duration::from_millis(args.block_time)).await;
+            }
+        };
+
+        let last_block_after_restart = tokio::select! {
+            result = sync_continuously(&mut wallet_core) => {
+                panic!("Continuous sync stopped: {result:?}")
+            }
+            last_block = restart => last_block,
+        };
+
+        info!("Waiting for the wallet to catch up with block {last_block_after_restart}");
+        let synced =
+            tokio::time::timeout(Duration::from_secs(5), sync_continuously(&mut wallet_core)).await;
+        assert!(synced.is_err(), "Continuous sync stopped: {synced:?}");
+        assert!(wallet_core.last_synced_block >= last_block_after_restart);
+
+        localnet.shutdown().await;
+    };
+
+    tokio::time::timeout(SYNC_RECONNECT_TEST_TIME_BUDGET, test)
+        .await
+        .expect("Sync reconnect test exceeded its time budget");
+
+    // Wallet storage is written to the wallet home of the test run, as in other tests
+    let _ = std::fs::remove_file(wallet::helperfunctions::get_wallet_file().unwrap())
+        .inspect_err(|err| log::warn!("Failed to remove persistent data with err {err:#?}"));
+
+    info!("Sync reconnect test finished successfully");
+}
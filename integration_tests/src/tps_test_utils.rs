@@ -2,7 +2,8 @@ use std::time::Duration;
 
 use key_protocol::key_management::ephemeral_key_holder::EphemeralKeyHolder;
 use nssa::{
-    Account, AccountId, PrivacyPreservingTransaction, PrivateKey, PublicKey, PublicTransaction,
+    Account, AccountId, Amount, PrivacyPreservingTransaction, PrivateKey, PublicKey,
+    PublicTransaction,
     privacy_preserving_transaction::{self as pptx, circuit},
     program::Program,
     public_transaction as putx,
@@ -56,7 +57,7 @@ impl TpsTestManager {
                 let message = putx::Message::try_new(
                     program.id(),
                     [pair[0].1, pair[1].1].to_vec(),
-                    [0u128].to_vec(),
+                    [0u64].to_vec(),
                     amount,
                 )
                 .unwrap();
@@ -79,7 +80,7 @@ impl TpsTestManager {
             .iter()
             .map(|(_, account_id)| AccountInitialData {
                 account_id: account_id.to_string(),
-                balance: 10,
+                balance: Amount(10),
             })
             .collect();
 
@@ -88,7 +89,7 @@ impl TpsTestManager {
         let sender_nsk = [1; 32];
         let sender_npk = NullifierPublicKey::from(&sender_nsk);
         let account = Account {
-            balance: 100,
+            balance: Amount(100),
             nonce: 0xdeadbeef,
             program_owner: Program::authenticated_transfer_program().id(),
             data: Data::default(),
@@ -110,6 +111,34 @@ impl TpsTestManager {
             initial_accounts: initial_public_accounts,
             initial_commitments: vec![initial_commitment],
             signing_key: [37; 32],
+            check_state_invariants: false,
+            lazy_proving: None,
+            mempool_wal: false,
+            ingest_queue_size: 1000,
+            prune: None,
+            soft_confirmation_margin_blocks: 10,
+            event_sink: None,
+            program_allow_list: sequencer_core::config::default_program_allow_list(),
+            max_block_timestamp_skew_millis: 30_000,
+            max_pending_per_sender: None,
+            max_consecutive_per_sender: None,
+            max_time_lock_blocks: 100_000,
+            max_parked_per_sender: 16,
+            upgrades: vec![],
+            storage_deposit_per_byte: Amount::ZERO,
+            max_instruction_data_size: nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+            max_program_size: nssa::program::DEFAULT_MAX_PROGRAM_SIZE,
+            anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+            chain_id: 0,
+            accept_dev_proofs: false,
+            deterministic_dev_mode: None,
+            serve_pending_transfers: false,
+            serve_pending_transactions: false,
+            system_lane: None,
+            tx_audit: Default::default(),
+            deployment_smoke_test_cycles: None,
+            force_params_change: false,
+            repair_indexes: false,
         }
     }
 }
@@ -127,7 +156,7 @@ fn build_privacy_transaction() -> PrivacyPreservingTransaction {
     let sender_npk = NullifierPublicKey::from(&sender_nsk);
     let sender_pre = AccountWithMetadata::new(
         Account {
-            balance: 100,
+            balance: Amount(100),
             nonce: 0xdeadbeef,
             program_owner: program.id(),
             data: Data::default(),
@@ -0,0 +1,280 @@
+//! Checks log statements of all workspace crates for dumps of keys and full-width values.
+//!
+//! Keys must not be logged with `{:?}` or `{:#?}`, ids and hashes should be logged with
+//! `fmt_short()` instead of `hex::encode()`. Arguments are told to be keys by their names, e.g.
+//! `signing_key`, `nsk` or `PrivateKey::new(..)`.
+
+use std::{fs, path::Path};
+
+const CHECKED_CRATES: [&str; 10] = [
+    "common",
+    "integration_tests",
+    "key_protocol",
+    "mempool",
+    "nssa",
+    "sequencer_core",
+    "sequencer_rpc",
+    "sequencer_runner",
+    "storage",
+    "wallet",
+];
+const LOG_MACROS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+/// Words of identifiers, which name keys and other secrets
+const KEY_WORDS: [&str; 15] = [
+    "key", "keys", "secret", "secrets", "seed", "nsk", "npk", "ssk", "isk", "ipk", "vsk", "vpk",
+    "ovk", "esk", "epk",
+];
+
+struct LogStatement {
+    path: String,
+    line: usize,
+    /// Arguments of the macro invocation, split at top-level commas
+    args: Vec<String>,
+}
+
+impl LogStatement {
+    fn text(&self) -> String {
+        self.args.join(", ")
+    }
+
+    /// Violations of the rules in module docs
+    fn violations(&self) -> Vec<String> {
+        let mut violations = vec![];
+        if self.args.iter().any(|arg| arg.contains("hex::encode(")) {
+            violations.push("`hex::encode()` of a full-width value, use `fmt_short()`".to_string());
+        }
+
+        // Format string is the first argument, unless a target precedes it
+        let mut args = self
+            .args
+            .iter()
+            .map(|arg| arg.trim())
+            .skip_while(|arg| arg.starts_with("target:"));
+        let Some(format_string) = args.next().and_then(string_literal) else {
+            return violations;
+        };
+        let (positional, named): (Vec<_>, Vec<_>) = args.partition(|arg| named_arg(arg).is_none());
+
+        let mut next_positional = 0;
+        for (name, spec) in placeholders(&format_string) {
+            let argument = if name.is_empty() {
+                next_positional += 1;
+                positional
+                    .get(next_positional - 1)
+                    .map(|arg| arg.to_string())
+            } else if let Ok(idx) = name.parse::<usize>() {
+                positional.get(idx).map(|arg| arg.to_string())
+            } else {
+                Some(
+                    named
+                        .iter()
+                        .find_map(|arg| named_arg(arg).filter(|(arg_name, _)| *arg_name == name))
+                        .map_or(name.clone(), |(_, value)| value.to_string()),
+                )
+            };
+            if let Some(argument) = argument
+                && spec.contains('?')
+                && names_key(&argument)
+            {
+                violations.push(format!("`{{{name}:{spec}}}` of key `{argument}`"));
+            }
+        }
+        violations
+    }
+}
+
+/// Log statements of `.rs` files under `dir`
+fn log_statements(dir: &Path, statements: &mut Vec<LogStatement>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            log_statements(&path, statements);
+            continue;
+        }
+        if path.extension().is_none_or(|ext| ext != "rs") {
+            continue;
+        }
+
+        let source = fs::read_to_string(&path).unwrap();
+        for (start, _) in source.match_indices("!(") {
+            let name_start = source[..start]
+                .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .map_or(0, |idx| idx + 1);
+            if !LOG_MACROS.contains(&&source[name_start..start]) {
+                continue;
+            }
+            let line_start = source[..name_start].rfind('\n').map_or(0, |idx| idx + 1);
+            if source[line_start..name_start]
+                .trim_start()
+                .starts_with("//")
+            {
+                continue;
+            }
+
+            statements.push(LogStatement {
+                path: path.display().to_string(),
+                line: source[..start].lines().count(),
+                args: split_args(&source[start + 2..]),
+            });
+        }
+    }
+}
+
+/// Splits arguments of a macro invocation up to its closing parenthesis at top-level commas
+fn split_args(source: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                current.push(c);
+                while let Some(c) = chars.next() {
+                    current.push(c);
+                    match c {
+                        '\\' => current.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth == 0 => break,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        args.push(current);
+    }
+    args.into_iter()
+        .map(|arg| arg.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+/// Contents of `arg`, if it's a plain string literal
+fn string_literal(arg: &str) -> Option<String> {
+    arg.strip_prefix('"')?
+        .strip_suffix('"')
+        .map(ToString::to_string)
+}
+
+/// Name and value of a named argument `name = value`
+fn named_arg(arg: &str) -> Option<(&str, &str)> {
+    let (name, value) = arg.split_once('=')?;
+    let name = name.trim();
+    (!value.starts_with('=') && !name.is_empty() && name.chars().all(is_ident_char))
+        .then(|| (name, value.trim()))
+}
+
+/// Argument names and format specs of placeholders of `format_string`
+fn placeholders(format_string: &str) -> Vec<(String, String)> {
+    let mut placeholders = vec![];
+    let mut chars = format_string.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            continue;
+        }
+        let placeholder = chars.by_ref().take_while(|&c| c != '}').collect::<String>();
+        let (name, spec) = placeholder.split_once(':').unwrap_or((&placeholder, ""));
+        placeholders.push((name.trim().to_string(), spec.to_string()));
+    }
+    placeholders
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether any identifier of `expr` has a word of [`KEY_WORDS`], in snake or camel case
+fn names_key(expr: &str) -> bool {
+    expr.split(|c: char| !is_ident_char(c))
+        .flat_map(|ident| ident.split('_'))
+        .flat_map(camel_case_words)
+        .any(|word| KEY_WORDS.contains(&word.as_str()))
+}
+
+fn camel_case_words(ident: &str) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+    for c in ident.chars() {
+        match words.last_mut() {
+            Some(word) if !c.is_uppercase() => word.push(c),
+            _ => words.push(c.to_lowercase().collect()),
+        }
+    }
+    words
+}
+
+#[test]
+fn test_no_full_key_debug_logging() {
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let mut statements = vec![];
+    for crate_name in CHECKED_CRATES {
+        log_statements(&workspace.join(crate_name).join("src"), &mut statements);
+    }
+    assert!(!statements.is_empty(), "No log statements found");
+
+    let offending = statements
+        .iter()
+        .flat_map(|statement| {
+            statement.violations().into_iter().map(move |violation| {
+                format!(
+                    "{}:{}: {violation} in {}",
+                    statement.path,
+                    statement.line,
+                    statement.text()
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+
+    assert!(
+        offending.is_empty(),
+        "Log statements must not debug-print keys or hex-encode values, use `fmt_short()`:\n{}",
+        offending.join("\n")
+    );
+}
+
+#[test]
+fn test_violations_are_found_in_arguments() {
+    let violations = |source: &str| {
+        LogStatement {
+            path: String::new(),
+            line: 0,
+            args: split_args(source),
+        }
+        .violations()
+    };
+
+    assert_eq!(
+        violations(r#""Keys of {account_id} are missing")"#).len(),
+        0
+    );
+    assert_eq!(violations(r#""Stored data at {path:#?}")"#).len(), 0);
+    assert_eq!(violations(r#""Key is {}", key.fmt_short())"#).len(), 0);
+    assert_eq!(violations(r#""Keystore at {:?}", keystore_path)"#).len(), 0);
+    assert_eq!(violations(r#""{{key:?}} {:?}", account)"#).len(), 0);
+
+    assert_eq!(violations(r#""Signing with {:?}", signing_key)"#).len(), 1);
+    assert_eq!(violations(r#""Derived {:#?}", self.key_chain)"#).len(), 1);
+    assert_eq!(violations(r#""Derived {nsk:?}")"#).len(), 1);
+    assert_eq!(
+        violations(r#""{} {:?}", "a", PrivateKey::new(seed))"#).len(),
+        1
+    );
+    assert_eq!(violations(r#""{1:?} {0}", account, public_key)"#).len(), 1);
+    assert_eq!(violations(r#""{k:?}", k = ssk)"#).len(), 1);
+    assert_eq!(violations(r#"target: "wallet", "{:?}", npk)"#).len(), 1);
+    assert_eq!(violations(r#""Hash {}", hex::encode(hash))"#).len(), 1);
+}
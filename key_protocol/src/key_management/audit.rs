@@ -0,0 +1,50 @@
+use std::fmt::Display;
+
+use crate::key_management::key_tree::chain_index::ChainIndex;
+
+/// Problem with stored keys of an account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyProblem {
+    /// Keys derive another account id
+    AccountIdMismatch { derived: nssa::AccountId },
+    /// Public or derived keys don't correspond to the secret key
+    InconsistentKeys { reason: String },
+    /// Tree node differs from the one derived from its parent
+    NotDerivedFromParent,
+    /// Account is recorded in the tree, but its keys are missing
+    MissingNode,
+}
+
+/// Problem found by auditing stored keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyAuditIssue {
+    pub account_id: nssa::AccountId,
+    /// Position in the key tree, `None` for preconfigured accounts
+    pub chain_index: Option<ChainIndex>,
+    pub problem: KeyProblem,
+}
+
+impl KeyAuditIssue {
+    /// Only tree derived keys can be re-derived from seed
+    pub fn is_repairable(&self) -> bool {
+        self.chain_index.is_some()
+    }
+}
+
+impl Display for KeyAuditIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Account {}", self.account_id)?;
+        if let Some(chain_index) = &self.chain_index {
+            write!(f, " at {}", chain_index.to_path_string())?;
+        }
+
+        match &self.problem {
+            KeyProblem::AccountIdMismatch { derived } => {
+                write!(f, ": keys belong to account {derived}")
+            }
+            KeyProblem::InconsistentKeys { reason } => write!(f, ": {reason}"),
+            KeyProblem::NotDerivedFromParent => write!(f, ": keys are not derived from parent"),
+            KeyProblem::MissingNode => write!(f, ": keys are missing"),
+        }
+    }
+}
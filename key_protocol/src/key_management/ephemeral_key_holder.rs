@@ -2,7 +2,6 @@ use nssa_core::{
     NullifierPublicKey, SharedSecretKey,
     encryption::{EphemeralPublicKey, EphemeralSecretKey, IncomingViewingPublicKey},
 };
-use rand::{RngCore, rngs::OsRng};
 use sha2::Digest;
 
 #[derive(Debug)]
@@ -16,7 +15,7 @@ pub fn produce_one_sided_shared_secret_receiver(
     ipk: &IncomingViewingPublicKey,
 ) -> (SharedSecretKey, EphemeralPublicKey) {
     let mut esk = [0; 32];
-    OsRng.fill_bytes(&mut esk);
+    nssa::entropy::fill_bytes(&mut esk);
     (
         SharedSecretKey::new(&esk, ipk),
         EphemeralPublicKey::from_scalar(esk),
@@ -26,7 +25,7 @@ pub fn produce_one_sided_shared_secret_receiver(
 impl EphemeralKeyHolder {
     pub fn new(receiver_nullifier_public_key: &NullifierPublicKey) -> Self {
         let mut nonce_bytes = [0; 16];
-        OsRng.fill_bytes(&mut nonce_bytes);
+        nssa::entropy::fill_bytes(&mut nonce_bytes);
         let mut hasher = sha2::Sha256::new();
         hasher.update(receiver_nullifier_public_key);
         hasher.update(nonce_bytes);
@@ -3,6 +3,9 @@ use std::{fmt::Display, str::FromStr};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+/// Indices starting from this one are reserved for hardened derivation
+pub const HARDENED_INDEX_OFFSET: u32 = 1 << 31;
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Hash)]
 pub struct ChainIndex(Vec<u32>);
 
@@ -12,12 +15,63 @@ pub enum ChainIndexError {
     NoRootFound,
     #[error("Failed to parse segment into a number")]
     ParseIntError(#[from] std::num::ParseIntError),
+    #[error("Path contains an empty segment")]
+    EmptySegment,
+    #[error("Index {0} is reserved for hardened derivation")]
+    IndexOutOfRange(u32),
+    #[error("Hardened derivation is not supported")]
+    HardenedNotSupported,
+}
+
+/// Chain of addresses of an account
+///
+/// Accounts are children of the root, `m/<account>`. Their child 0 is the external chain of
+/// addresses given out to receive payments, and child 1 is the internal chain of change addresses,
+/// so an address is at `m/<account>/<chain>/<index>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AddressChain {
+    External,
+    Internal,
 }
 
+impl AddressChain {
+    pub const ALL: [AddressChain; 2] = [AddressChain::External, AddressChain::Internal];
+
+    /// Index of the chain node under its account
+    pub fn index(self) -> u32 {
+        match self {
+            AddressChain::External => 0,
+            AddressChain::Internal => 1,
+        }
+    }
+
+    pub fn from_index(index: u32) -> Option<Self> {
+        match index {
+            0 => Some(AddressChain::External),
+            1 => Some(AddressChain::Internal),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AddressChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressChain::External => write!(f, "external"),
+            AddressChain::Internal => write!(f, "internal"),
+        }
+    }
+}
+
+/// Accepts both the derivation path syntax `m/0/5` and the legacy one `/0/5`
 impl FromStr for ChainIndex {
     type Err = ChainIndexError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('m') {
+            return Self::from_path_str(s);
+        }
+
         if !s.starts_with('/') {
             return Err(ChainIndexError::NoRootFound);
         }
@@ -64,6 +118,44 @@ impl ChainIndex {
         ChainIndex::default()
     }
 
+    /// Parses derivation path syntax: `m` for root, `m/0/5` for its descendants
+    pub fn from_path_str(s: &str) -> Result<Self, ChainIndexError> {
+        let segments = s.strip_prefix('m').ok_or(ChainIndexError::NoRootFound)?;
+        if segments.is_empty() {
+            return Ok(ChainIndex::root());
+        }
+
+        let segments = segments
+            .strip_prefix('/')
+            .ok_or(ChainIndexError::NoRootFound)?;
+
+        segments
+            .split('/')
+            .map(|segment| {
+                if segment.is_empty() {
+                    return Err(ChainIndexError::EmptySegment);
+                }
+                if segment.ends_with('\'') {
+                    return Err(ChainIndexError::HardenedNotSupported);
+                }
+
+                let cci: u32 = segment.parse()?;
+                if cci >= HARDENED_INDEX_OFFSET {
+                    return Err(ChainIndexError::IndexOutOfRange(cci));
+                }
+                Ok(cci)
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Derivation path syntax, which is the user-facing form of chain index, e.g. `m/0/5`
+    pub fn to_path_string(&self) -> String {
+        std::iter::once("m".to_string())
+            .chain(self.0.iter().map(u32::to_string))
+            .join("/")
+    }
+
     pub fn chain(&self) -> &[u32] {
         &self.0
     }
@@ -102,6 +194,44 @@ impl ChainIndex {
         ChainIndex(chain)
     }
 
+    /// Account node `m/<account>`
+    pub fn account(account: u32) -> ChainIndex {
+        ChainIndex(vec![account])
+    }
+
+    /// Node of `chain` of the account at `self`
+    pub fn address_chain(&self, chain: AddressChain) -> ChainIndex {
+        self.nth_child(chain.index())
+    }
+
+    /// Address `index` of `chain` of the account at `self`
+    pub fn chain_address(&self, chain: AddressChain, index: u32) -> ChainIndex {
+        self.address_chain(chain).nth_child(index)
+    }
+
+    /// Splits an address path `m/<account>/<chain>/<index>` into the account node, the chain and
+    /// the index of the address. `None` for nodes outside of address chains.
+    pub fn as_chain_address(&self) -> Option<(ChainIndex, AddressChain, u32)> {
+        let &[account, chain, index] = self.0.as_slice() else {
+            return None;
+        };
+
+        Some((
+            ChainIndex::account(account),
+            AddressChain::from_index(chain)?,
+            index,
+        ))
+    }
+
+    /// Account node, which `self` belongs to: itself for account nodes and the account of its
+    /// chain for addresses
+    pub fn owning_account(&self) -> Option<ChainIndex> {
+        match self.0.as_slice() {
+            [_] => Some(self.clone()),
+            _ => self.as_chain_address().map(|(account, _, _)| account),
+        }
+    }
+
     pub fn depth(&self) -> u32 {
         self.0.iter().map(|cci| cci + 1).sum()
     }
@@ -192,6 +322,71 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_path_str_round_trip() {
+        for (path, chain) in [("m", vec![]), ("m/0", vec![0]), ("m/0/5/2", vec![0, 5, 2])] {
+            let chain_id = ChainIndex::from_path_str(path).unwrap();
+
+            assert_eq!(chain_id.chain(), chain.as_slice());
+            assert_eq!(chain_id.to_path_string(), path);
+        }
+    }
+
+    #[test]
+    fn test_from_str_accepts_both_syntaxes() {
+        let chain_id = ChainIndex(vec![0, 5, 2]);
+
+        assert_eq!(ChainIndex::from_str("m/0/5/2").unwrap(), chain_id);
+        assert_eq!(ChainIndex::from_str("/0/5/2").unwrap(), chain_id);
+        assert_eq!(
+            ChainIndex::from_str(&chain_id.to_string()).unwrap(),
+            chain_id
+        );
+        assert_eq!(ChainIndex::from_str("m").unwrap(), ChainIndex::root());
+    }
+
+    #[test]
+    fn test_path_str_rejects_reserved_index() {
+        let max_index = HARDENED_INDEX_OFFSET - 1;
+        assert_eq!(
+            ChainIndex::from_path_str(&format!("m/{max_index}"))
+                .unwrap()
+                .chain(),
+            &[max_index]
+        );
+
+        let chain_index_error = ChainIndex::from_path_str(&format!("m/0/{HARDENED_INDEX_OFFSET}"))
+            .err()
+            .unwrap();
+
+        assert!(matches!(
+            chain_index_error,
+            ChainIndexError::IndexOutOfRange(HARDENED_INDEX_OFFSET)
+        ));
+    }
+
+    #[test]
+    fn test_path_str_rejects_malformed_paths() {
+        for path in ["m/", "m//1", "m/1/", "/m/1"] {
+            assert!(
+                ChainIndex::from_path_str(path).is_err(),
+                "{path} must be rejected"
+            );
+        }
+        assert!(matches!(
+            ChainIndex::from_path_str("m/1//2").err().unwrap(),
+            ChainIndexError::EmptySegment
+        ));
+        assert!(matches!(
+            ChainIndex::from_path_str("m/1'").err().unwrap(),
+            ChainIndexError::HardenedNotSupported
+        ));
+        assert!(matches!(
+            ChainIndex::from_path_str("m1").err().unwrap(),
+            ChainIndexError::NoRootFound
+        ));
+    }
+
     #[test]
     fn test_chain_id_next_in_line_correct() {
         let chain_id = ChainIndex::from_str("/257").unwrap();
@@ -289,6 +484,56 @@ mod tests {
         assert_eq!(collapsed, None)
     }
 
+    #[test]
+    fn test_chain_address_paths() {
+        let account = ChainIndex::account(3);
+
+        assert_eq!(account.to_path_string(), "m/3");
+        assert_eq!(
+            account
+                .address_chain(AddressChain::External)
+                .to_path_string(),
+            "m/3/0"
+        );
+        assert_eq!(
+            account
+                .chain_address(AddressChain::External, 7)
+                .to_path_string(),
+            "m/3/0/7"
+        );
+        assert_eq!(
+            account
+                .chain_address(AddressChain::Internal, 0)
+                .to_path_string(),
+            "m/3/1/0"
+        );
+    }
+
+    #[test]
+    fn test_as_chain_address() {
+        let account = ChainIndex::account(2);
+
+        for chain in AddressChain::ALL {
+            let address = account.chain_address(chain, 5);
+            assert_eq!(
+                address.as_chain_address(),
+                Some((account.clone(), chain, 5))
+            );
+            assert_eq!(address.owning_account(), Some(account.clone()));
+        }
+
+        assert_eq!(account.owning_account(), Some(account.clone()));
+        for not_address in ["m", "m/2", "m/2/0", "m/2/2/5", "m/2/0/5/1"] {
+            let chain_index = ChainIndex::from_path_str(not_address).unwrap();
+            assert_eq!(chain_index.as_chain_address(), None, "{not_address}");
+        }
+        assert_eq!(ChainIndex::root().owning_account(), None);
+        assert_eq!(
+            ChainIndex::from_path_str("m/2/0").unwrap().owning_account(),
+            None
+        );
+    }
+
     #[test]
     fn test_shuffle() {
         for id in ChainIndex::chain_ids_at_depth(5) {
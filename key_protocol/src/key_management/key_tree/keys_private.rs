@@ -128,6 +128,15 @@ impl KeyNode for ChildKeysPrivate {
     fn account_id(&self) -> nssa::AccountId {
         nssa::AccountId::from(&self.value.0.nullifer_public_key)
     }
+
+    fn audit(&self) -> anyhow::Result<()> {
+        self.value.0.audit()
+    }
+
+    fn replaced_by(self, mut derived: Self) -> Self {
+        derived.value.1 = self.value.1;
+        derived
+    }
 }
 
 impl<'a> From<&'a ChildKeysPrivate> for &'a (KeyChain, nssa::Account) {
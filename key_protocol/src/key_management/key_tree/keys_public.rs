@@ -64,6 +64,14 @@ impl KeyNode for ChildKeysPublic {
     fn account_id(&self) -> nssa::AccountId {
         nssa::AccountId::from(&self.cpk)
     }
+
+    fn audit(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.cpk == nssa::PublicKey::new_from_private_key(&self.csk),
+            "Public key doesn't correspond to the private key"
+        );
+        Ok(())
+    }
 }
 
 impl<'a> From<&'a ChildKeysPublic> for &'a nssa::PrivateKey {
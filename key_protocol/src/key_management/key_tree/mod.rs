@@ -1,15 +1,18 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     sync::Arc,
 };
 
 use anyhow::Result;
-use common::sequencer_client::SequencerClient;
+use common::sequencer_api::SequencerApi;
 use serde::{Deserialize, Serialize};
 
 use crate::key_management::{
+    audit::{KeyAuditIssue, KeyProblem},
     key_tree::{
-        chain_index::ChainIndex, keys_private::ChildKeysPrivate, keys_public::ChildKeysPublic,
+        chain_index::{AddressChain, ChainIndex},
+        keys_private::ChildKeysPrivate,
+        keys_public::ChildKeysPublic,
         traits::KeyNode,
     },
     secret_holders::SeedHolder,
@@ -152,6 +155,165 @@ impl<N: KeyNode> KeyTree<N> {
         self.fill_node(&self.find_next_slot_layered())
     }
 
+    /// Derives the next address of `chain` of `account`, together with the chain node if it's
+    /// missing
+    ///
+    /// `None` if the account node is missing.
+    pub fn generate_new_chain_address(
+        &mut self,
+        account: &ChainIndex,
+        chain: AddressChain,
+    ) -> Option<(nssa::AccountId, ChainIndex)> {
+        let chain_node = account.address_chain(chain);
+        if !self.key_map.contains_key(&chain_node) {
+            self.fill_node(&chain_node)?;
+        }
+
+        self.generate_new_node(&chain_node)
+    }
+
+    /// Derives address `index` of `chain` of `account`, together with the chain node if it's
+    /// missing, or returns the existing one
+    ///
+    /// `None` if the account node is missing.
+    pub fn fill_chain_address(
+        &mut self,
+        account: &ChainIndex,
+        chain: AddressChain,
+        index: u32,
+    ) -> Option<(nssa::AccountId, ChainIndex)> {
+        let chain_node = account.address_chain(chain);
+        if !self.key_map.contains_key(&chain_node) {
+            self.fill_node(&chain_node)?;
+        }
+
+        let address = chain_node.nth_child(index);
+        match self.key_map.get(&address) {
+            Some(node) => Some((node.account_id(), address)),
+            None => self.fill_node(&address),
+        }
+    }
+
+    /// Next `gap_limit` addresses of `chain` of `account` after the last derived one, with their
+    /// indices. Nothing is inserted into the tree.
+    ///
+    /// Another wallet with the same seed may have handed them out already, so activity on them
+    /// shows that the chain has to be extended. `None` if the account node is missing.
+    pub fn chain_lookahead(
+        &self,
+        account: &ChainIndex,
+        chain: AddressChain,
+        gap_limit: u32,
+    ) -> Option<Vec<(nssa::AccountId, u32)>> {
+        let chain_node_index = account.address_chain(chain);
+        let derived;
+        let chain_node = match self.key_map.get(&chain_node_index) {
+            Some(node) => node,
+            None => {
+                derived = self.derive_from_parent(&chain_node_index)?;
+                &derived
+            }
+        };
+
+        let next_index = self
+            .key_map
+            .keys()
+            .filter(|chain_index| chain_index.parent().as_ref() == Some(&chain_node_index))
+            .filter_map(|address| address.chain().last().copied())
+            .max()
+            .map_or(0, |index| index + 1);
+
+        Some(
+            (next_index..next_index.saturating_add(gap_limit))
+                .map(|index| (chain_node.nth_child(index).account_id(), index))
+                .collect(),
+        )
+    }
+
+    /// Derives every missing address of `chain` of `account` up to `index` inclusive, so the
+    /// chain has no gaps before it, and returns the derived ones
+    ///
+    /// `None` if the account node is missing.
+    pub fn extend_chain_to(
+        &mut self,
+        account: &ChainIndex,
+        chain: AddressChain,
+        index: u32,
+    ) -> Option<Vec<(nssa::AccountId, ChainIndex)>> {
+        let chain_node = account.address_chain(chain);
+        let mut derived = vec![];
+        for index in 0..=index {
+            if !self.key_map.contains_key(&chain_node.nth_child(index)) {
+                derived.push(self.fill_chain_address(account, chain, index)?);
+            }
+        }
+
+        Some(derived)
+    }
+
+    /// Gap limit scan of both chains of `account`
+    ///
+    /// Derives addresses of every chain until `gap_limit` consecutive ones aren't used, then
+    /// removes the unused ones after the last used address. Addresses past the scanned ones are
+    /// left as they are. `None` if the account node is missing.
+    pub fn scan_account_chains_with(
+        &mut self,
+        account: &ChainIndex,
+        gap_limit: u32,
+        mut is_used: impl FnMut(&N) -> bool,
+    ) -> Option<()> {
+        for chain in AddressChain::ALL {
+            let chain_node_existed = self.key_map.contains_key(&account.address_chain(chain));
+
+            let mut used_len = 0;
+            let mut index = 0;
+            while index - used_len < gap_limit {
+                let (account_id, _) = self.fill_chain_address(account, chain, index)?;
+                if self.get_node(account_id).is_some_and(&mut is_used) {
+                    used_len = index + 1;
+                }
+                index += 1;
+            }
+
+            self.truncate_chain(account, chain, used_len..index, chain_node_existed);
+        }
+
+        Some(())
+    }
+
+    /// Removes scanned unused addresses of `chain` of `account`, and the chain node if it's left
+    /// empty and wasn't there before the scan
+    ///
+    /// Addresses with children are kept.
+    fn truncate_chain(
+        &mut self,
+        account: &ChainIndex,
+        chain: AddressChain,
+        unused: std::ops::Range<u32>,
+        keep_chain_node: bool,
+    ) {
+        let chain_node = account.address_chain(chain);
+        for index in unused {
+            let address = chain_node.nth_child(index);
+            if !self.key_map.contains_key(&address.nth_child(0))
+                && let Some(account_id) = self.key_map.get(&address).map(KeyNode::account_id)
+            {
+                self.remove(account_id);
+            }
+        }
+
+        let chain_is_empty = !self
+            .key_map
+            .keys()
+            .any(|chain_index| chain_index.parent().as_ref() == Some(&chain_node));
+        if chain_is_empty
+            && !keep_chain_node
+            && let Some(account_id) = self.key_map.get(&chain_node).map(KeyNode::account_id)
+        {
+            self.remove(account_id);
+        }
+    }
+
     pub fn get_node(&self, account_id: nssa::AccountId) -> Option<&N> {
         self.account_id_map
             .get(&account_id)
@@ -174,6 +336,131 @@ impl<N: KeyNode> KeyTree<N> {
         self.key_map.remove(&chain_index)
     }
 
+    /// Accounts recorded in the tree without keys, which can only be watched
+    pub fn accounts_without_keys(&self) -> Vec<nssa::AccountId> {
+        self.account_id_map
+            .iter()
+            .filter(|(_, chain_index)| !self.key_map.contains_key(chain_index))
+            .map(|(account_id, _)| *account_id)
+            .collect()
+    }
+
+    /// Checks that every account of the tree has keys, which derive its id and are derived from
+    /// keys of the parent node
+    ///
+    /// Root can only be checked against seed, see [`Self::repair`].
+    pub fn audit(&self) -> Vec<KeyAuditIssue> {
+        let mut accounts = self.account_id_map.iter().collect::<Vec<_>>();
+        accounts.sort_by_key(|(_, chain_index)| *chain_index);
+
+        let mut issues = vec![];
+        for (account_id, chain_index) in accounts {
+            let mut report = |problem| {
+                issues.push(KeyAuditIssue {
+                    account_id: *account_id,
+                    chain_index: Some(chain_index.clone()),
+                    problem,
+                })
+            };
+
+            let Some(node) = self.key_map.get(chain_index) else {
+                report(KeyProblem::MissingNode);
+                continue;
+            };
+            if let Err(err) = node.audit() {
+                report(KeyProblem::InconsistentKeys {
+                    reason: err.to_string(),
+                });
+            }
+            if node.account_id() != *account_id {
+                report(KeyProblem::AccountIdMismatch {
+                    derived: node.account_id(),
+                });
+            }
+            if let Some(expected) = self.derive_from_parent(chain_index)
+                && (expected.account_id() != node.account_id()
+                    || expected.chain_code() != node.chain_code())
+            {
+                report(KeyProblem::NotDerivedFromParent);
+            }
+        }
+
+        issues
+    }
+
+    /// Re-derives every node from `seed`, replacing the ones, which differ from derived, and
+    /// returns ids of accounts at replaced nodes
+    ///
+    /// Fails without changes if root is consistent, but isn't derived from `seed`, which means
+    /// that `seed` is wrong.
+    pub fn repair(&mut self, seed: &SeedHolder) -> Result<Vec<nssa::AccountId>> {
+        let seed: [u8; 64] = seed
+            .seed
+            .clone()
+            .try_into()
+            .expect("SeedHolder seed is 64 bytes long");
+
+        let derived_root = N::root(seed);
+        if let Some(root) = self.key_map.get(&ChainIndex::root()) {
+            anyhow::ensure!(
+                root.audit().is_err() || root.account_id() == derived_root.account_id(),
+                "Key tree is not derived from the given seed"
+            );
+        }
+
+        // Parents go before their children
+        let chain_indices = self
+            .key_map
+            .keys()
+            .chain(self.account_id_map.values())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        let mut repaired = vec![];
+        for chain_index in chain_indices {
+            let expected = if chain_index == ChainIndex::root() {
+                N::root(seed)
+            } else {
+                let Some(expected) = self.derive_from_parent(&chain_index) else {
+                    continue;
+                };
+                expected
+            };
+
+            let recorded_account_ids = self
+                .account_id_map
+                .iter()
+                .filter(|(_, index)| **index == chain_index)
+                .map(|(account_id, _)| *account_id)
+                .collect::<Vec<_>>();
+            let node_is_valid = self.key_map.get(&chain_index).is_some_and(|node| {
+                node.audit().is_ok()
+                    && node.account_id() == expected.account_id()
+                    && node.chain_code() == expected.chain_code()
+            });
+            if node_is_valid && recorded_account_ids == [expected.account_id()] {
+                continue;
+            }
+
+            let node = match self.key_map.remove(&chain_index) {
+                Some(node) => node.replaced_by(expected),
+                None => expected,
+            };
+            for account_id in &recorded_account_ids {
+                self.account_id_map.remove(account_id);
+            }
+            repaired.push(node.account_id());
+            self.insert(node.account_id(), chain_index, node);
+        }
+
+        Ok(repaired)
+    }
+
+    fn derive_from_parent(&self, chain_index: &ChainIndex) -> Option<N> {
+        let parent = self.key_map.get(&chain_index.parent()?)?;
+        Some(parent.nth_child(*chain_index.chain().last()?))
+    }
+
     /// Populates tree with children.
     ///
     /// For given `depth` adds children to a tree such that their `ChainIndex::depth(&self) <
@@ -227,6 +514,16 @@ impl KeyTree<ChildKeysPrivate> {
         }
     }
 
+    /// Gap limit scan of both chains of `account`, see [`Self::scan_account_chains_with`]
+    ///
+    /// Address is used if its account isn't default, so chain must be parsed for addresses within
+    /// the gap limit beforehand.
+    pub fn scan_account_chains(&mut self, account: &ChainIndex, gap_limit: u32) -> Option<()> {
+        self.scan_account_chains_with(account, gap_limit, |node| {
+            node.value.1 != nssa::Account::default()
+        })
+    }
+
     /// Cleanup of non-initialized accounts in a private tree
     ///
     /// If account is default, removes them, stops at first non-default account.
@@ -265,7 +562,7 @@ impl KeyTree<ChildKeysPublic> {
     pub async fn cleanup_tree_remove_ininit_for_depth(
         &mut self,
         depth: u32,
-        client: Arc<SequencerClient>,
+        client: Arc<dyn SequencerApi>,
     ) -> Result<()> {
         let mut id_stack = vec![ChainIndex::root()];
 
@@ -290,6 +587,56 @@ impl KeyTree<ChildKeysPublic> {
         Ok(())
     }
 
+    /// Gap limit scan of both chains of `account`, see [`Self::scan_account_chains_with`]
+    ///
+    /// `are_used` tells which of the given addresses are used, it gets all addresses up to the
+    /// end of the current gap at once, so they can be checked concurrently. Fails if the account
+    /// node is missing or `are_used` fails.
+    pub async fn scan_account_chains<F>(
+        &mut self,
+        account: &ChainIndex,
+        gap_limit: u32,
+        mut are_used: impl FnMut(Vec<nssa::AccountId>) -> F,
+    ) -> Result<()>
+    where
+        F: Future<Output = Result<Vec<bool>>>,
+    {
+        for chain in AddressChain::ALL {
+            let chain_node_existed = self.key_map.contains_key(&account.address_chain(chain));
+
+            let mut used_len = 0;
+            let mut index = 0;
+            while index - used_len < gap_limit {
+                let mut account_ids = vec![];
+                for index in index..used_len + gap_limit {
+                    let Some((account_id, _)) = self.fill_chain_address(account, chain, index)
+                    else {
+                        anyhow::bail!("Account node {} is missing", account.to_path_string());
+                    };
+                    account_ids.push(account_id);
+                }
+                let num_addresses = account_ids.len();
+
+                let used = are_used(account_ids).await?;
+                anyhow::ensure!(
+                    used.len() == num_addresses,
+                    "Usage of {} addresses is known, expected {num_addresses}",
+                    used.len()
+                );
+                for is_used in used {
+                    if is_used {
+                        used_len = index + 1;
+                    }
+                    index += 1;
+                }
+            }
+
+            self.truncate_chain(account, chain, used_len..index, chain_node_existed);
+        }
+
+        Ok(())
+    }
+
     /// Cleanup of non-initialized accounts in a public tree
     ///
     /// If account is default, removes them, stops at first non-default account.
@@ -300,7 +647,7 @@ impl KeyTree<ChildKeysPublic> {
     pub async fn cleanup_tree_remove_uninit_layered(
         &mut self,
         depth: u32,
-        client: Arc<SequencerClient>,
+        client: Arc<dyn SequencerApi>,
     ) -> Result<()> {
         'outer: for i in (1..(depth as usize)).rev() {
             println!("Cleanup of tree at depth {i}");
@@ -327,7 +674,7 @@ impl KeyTree<ChildKeysPublic> {
 mod tests {
     use std::{collections::HashSet, str::FromStr};
 
-    use nssa::AccountId;
+    use nssa::{AccountId, Amount};
 
     use super::*;
 
@@ -540,25 +887,25 @@ mod tests {
             .key_map
             .get_mut(&ChainIndex::from_str("/1").unwrap())
             .unwrap();
-        acc.value.1.balance = 2;
+        acc.value.1.balance = Amount(2);
 
         let acc = tree
             .key_map
             .get_mut(&ChainIndex::from_str("/2").unwrap())
             .unwrap();
-        acc.value.1.balance = 3;
+        acc.value.1.balance = Amount(3);
 
         let acc = tree
             .key_map
             .get_mut(&ChainIndex::from_str("/0/1").unwrap())
             .unwrap();
-        acc.value.1.balance = 5;
+        acc.value.1.balance = Amount(5);
 
         let acc = tree
             .key_map
             .get_mut(&ChainIndex::from_str("/1/0").unwrap())
             .unwrap();
-        acc.value.1.balance = 6;
+        acc.value.1.balance = Amount(6);
 
         tree.cleanup_tree_remove_uninit_layered(10);
 
@@ -583,24 +930,214 @@ mod tests {
             .key_map
             .get(&ChainIndex::from_str("/1").unwrap())
             .unwrap();
-        assert_eq!(acc.value.1.balance, 2);
+        assert_eq!(acc.value.1.balance, Amount(2));
 
         let acc = tree
             .key_map
             .get(&ChainIndex::from_str("/2").unwrap())
             .unwrap();
-        assert_eq!(acc.value.1.balance, 3);
+        assert_eq!(acc.value.1.balance, Amount(3));
 
         let acc = tree
             .key_map
             .get(&ChainIndex::from_str("/0/1").unwrap())
             .unwrap();
-        assert_eq!(acc.value.1.balance, 5);
+        assert_eq!(acc.value.1.balance, Amount(5));
 
         let acc = tree
             .key_map
             .get(&ChainIndex::from_str("/1/0").unwrap())
             .unwrap();
-        assert_eq!(acc.value.1.balance, 6);
+        assert_eq!(acc.value.1.balance, Amount(6));
+    }
+
+    #[test]
+    fn test_audit_detects_and_repairs_swapped_public_keys() {
+        let seed_holder = seed_holder_for_tests();
+        let mut tree = KeyTreePublic::new(&seed_holder);
+        tree.generate_tree_for_depth(3);
+        assert!(tree.audit().is_empty());
+
+        let first = ChainIndex::from_str("/0").unwrap();
+        let second = ChainIndex::from_str("/1").unwrap();
+        let first_csk = tree.key_map[&first].csk.clone();
+        let second_csk = tree.key_map[&second].csk.clone();
+        tree.key_map.get_mut(&first).unwrap().csk = second_csk;
+        tree.key_map.get_mut(&second).unwrap().csk = first_csk;
+
+        let issues = tree.audit();
+        let corrupted = issues
+            .iter()
+            .filter_map(|issue| issue.chain_index.clone())
+            .collect::<HashSet<_>>();
+        assert!(corrupted.contains(&first));
+        assert!(corrupted.contains(&second));
+        assert!(issues.iter().all(|issue| issue.is_repairable()));
+
+        let expected_first = tree.key_map[&ChainIndex::root()].nth_child(0).account_id();
+        let repaired = tree.repair(&seed_holder).unwrap();
+        assert!(repaired.contains(&expected_first));
+        assert!(tree.audit().is_empty());
+        assert_eq!(tree.account_id_map[&expected_first], first);
+        assert_eq!(tree.key_map[&first].account_id(), expected_first);
+    }
+
+    #[test]
+    fn test_repair_keeps_private_account_data() {
+        let seed_holder = seed_holder_for_tests();
+        let mut tree = KeyTreePrivate::new(&seed_holder);
+        let (account_id, chain_index) = tree.generate_new_node(&ChainIndex::root()).unwrap();
+        tree.key_map.get_mut(&chain_index).unwrap().value.1.balance = Amount(7);
+
+        // Keys of another account end up at the node
+        let other = tree.key_map[&chain_index].nth_child(0);
+        tree.key_map.get_mut(&chain_index).unwrap().value.0 = other.value.0;
+        assert!(!tree.audit().is_empty());
+
+        assert_eq!(tree.repair(&seed_holder).unwrap(), vec![account_id]);
+        assert!(tree.audit().is_empty());
+        let node = tree.get_node(account_id).unwrap();
+        assert_eq!(node.account_id(), account_id);
+        assert_eq!(node.value.1.balance, Amount(7));
+    }
+
+    #[test]
+    fn test_repair_rejects_wrong_seed() {
+        let mut tree = KeyTreePublic::new(&seed_holder_for_tests());
+        tree.generate_tree_for_depth(2);
+
+        let wrong_seed = SeedHolder {
+            seed: [7; 64].to_vec(),
+        };
+        assert!(tree.repair(&wrong_seed).is_err());
+        assert!(tree.audit().is_empty());
+    }
+
+    #[test]
+    fn test_chain_addresses_follow_account_layout() {
+        let mut tree = KeyTreePublic::new(&seed_holder_for_tests());
+        let account = ChainIndex::account(0);
+        assert_eq!(
+            tree.generate_new_chain_address(&account, AddressChain::External),
+            None
+        );
+
+        tree.generate_new_node(&ChainIndex::root()).unwrap();
+        let paths = [
+            AddressChain::External,
+            AddressChain::External,
+            AddressChain::Internal,
+        ]
+        .map(|chain| {
+            tree.generate_new_chain_address(&account, chain)
+                .unwrap()
+                .1
+                .to_path_string()
+        });
+        assert_eq!(paths, ["m/0/0/0", "m/0/0/1", "m/0/1/0"]);
+
+        let change = &tree.key_map[&account.chain_address(AddressChain::Internal, 0)];
+        assert_eq!(
+            tree.key_map[&account]
+                .nth_child(1)
+                .nth_child(0)
+                .account_id(),
+            change.account_id()
+        );
+        assert!(tree.audit().is_empty());
+    }
+
+    #[test]
+    fn test_chain_lookahead_extends_on_activity() {
+        let mut tree = KeyTreePublic::new(&seed_holder_for_tests());
+        let account = ChainIndex::account(0);
+        assert_eq!(
+            tree.chain_lookahead(&account, AddressChain::External, 3),
+            None
+        );
+
+        tree.generate_new_node(&ChainIndex::root()).unwrap();
+        let num_nodes = tree.key_map.len();
+        let lookahead = tree
+            .chain_lookahead(&account, AddressChain::External, 3)
+            .unwrap();
+        assert_eq!(tree.key_map.len(), num_nodes);
+        assert_eq!(
+            lookahead
+                .iter()
+                .map(|(_, index)| *index)
+                .collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+
+        // Activity on the third address derives everything before it
+        let derived = tree
+            .extend_chain_to(&account, AddressChain::External, 2)
+            .unwrap();
+        assert_eq!(
+            derived
+                .iter()
+                .map(|(account_id, _)| *account_id)
+                .collect::<Vec<_>>(),
+            lookahead
+                .iter()
+                .map(|(account_id, _)| *account_id)
+                .collect::<Vec<_>>()
+        );
+        assert!(
+            tree.extend_chain_to(&account, AddressChain::External, 1)
+                .unwrap()
+                .is_empty()
+        );
+
+        let lookahead = tree
+            .chain_lookahead(&account, AddressChain::External, 2)
+            .unwrap();
+        assert_eq!(
+            tree.generate_new_chain_address(&account, AddressChain::External),
+            Some((
+                lookahead[0].0,
+                account.chain_address(AddressChain::External, 3)
+            ))
+        );
+        assert!(tree.audit().is_empty());
+    }
+
+    #[test]
+    fn test_scan_account_chains_covers_both_chains() {
+        let mut tree = KeyTreePrivate::new(&seed_holder_for_tests());
+        let account = ChainIndex::account(0);
+        tree.generate_new_node(&ChainIndex::root()).unwrap();
+
+        // Addresses found by sync
+        for (chain, index) in [(AddressChain::External, 1), (AddressChain::Internal, 3)] {
+            let (account_id, _) = tree.fill_chain_address(&account, chain, index).unwrap();
+            tree.get_node_mut(account_id).unwrap().value.1.balance = Amount(10);
+        }
+
+        tree.scan_account_chains(&account, 4).unwrap();
+
+        let addresses = tree
+            .key_map
+            .keys()
+            .filter(|chain_index| chain_index.as_chain_address().is_some())
+            .map(ChainIndex::to_path_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            addresses,
+            [
+                "m/0/0/0", "m/0/0/1", "m/0/1/0", "m/0/1/1", "m/0/1/2", "m/0/1/3"
+            ]
+        );
+        assert!(tree.audit().is_empty());
+
+        // Nothing is left from scanning an unused account
+        let (_, empty_account) = tree.generate_new_node(&ChainIndex::root()).unwrap();
+        let num_nodes = tree.key_map.len();
+        tree.scan_account_chains(&empty_account, 4).unwrap();
+        assert_eq!(tree.key_map.len(), num_nodes);
+        assert_eq!(tree.account_id_map.len(), num_nodes);
+
+        assert_eq!(tree.scan_account_chains(&ChainIndex::account(5), 4), None);
     }
 }
@@ -11,4 +11,17 @@ pub trait KeyNode {
     fn child_index(&self) -> Option<u32>;
 
     fn account_id(&self) -> nssa::AccountId;
+
+    /// Checks that public keys of the node correspond to its secret keys
+    fn audit(&self) -> anyhow::Result<()>;
+
+    /// Node, which replaces this one when it's re-derived from seed
+    ///
+    /// Data, which isn't derived from keys, is kept.
+    fn replaced_by(self, derived: Self) -> Self
+    where
+        Self: Sized,
+    {
+        derived
+    }
 }
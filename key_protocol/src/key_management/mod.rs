@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 
 pub type PublicAccountSigningKey = [u8; 32];
 
+pub mod audit;
 pub mod ephemeral_key_holder;
 pub mod key_tree;
 pub mod secret_holders;
@@ -59,6 +60,37 @@ impl KeyChain {
         }
     }
 
+    /// Checks that all keys are derived from the secret spending key
+    pub fn audit(&self) -> anyhow::Result<()> {
+        let expected_holder = self.secret_spending_key.produce_private_key_holder();
+        let holder = &self.private_key_holder;
+
+        // Shared secrets can't be derived with an invalid scalar
+        anyhow::ensure!(
+            k256::SecretKey::from_bytes(&holder.incoming_viewing_secret_key.into()).is_ok(),
+            "Incoming viewing secret key is not a valid secp256k1 scalar"
+        );
+
+        anyhow::ensure!(
+            holder.nullifier_secret_key == expected_holder.nullifier_secret_key
+                && holder.incoming_viewing_secret_key
+                    == expected_holder.incoming_viewing_secret_key
+                && holder.outgoing_viewing_secret_key
+                    == expected_holder.outgoing_viewing_secret_key,
+            "Private keys are not derived from the secret spending key"
+        );
+        anyhow::ensure!(
+            self.nullifer_public_key == holder.generate_nullifier_public_key(),
+            "Nullifier public key doesn't correspond to the nullifier secret key"
+        );
+        anyhow::ensure!(
+            self.incoming_viewing_public_key == holder.generate_incoming_viewing_public_key(),
+            "Incoming viewing public key doesn't correspond to the incoming viewing secret key"
+        );
+
+        Ok(())
+    }
+
     pub fn calculate_shared_secret_receiver(
         &self,
         ephemeral_public_key_sender: EphemeralPublicKey,
@@ -4,9 +4,9 @@ use nssa_core::{
     NullifierPublicKey, NullifierSecretKey,
     encryption::{IncomingViewingPublicKey, Scalar},
 };
-use rand::{RngCore, rngs::OsRng};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, digest::FixedOutput};
+use zeroize::Zeroize as _;
 
 const NSSA_ENTROPY_BYTES: [u8; 32] = [0; 32];
 
@@ -36,26 +36,38 @@ pub struct PrivateKeyHolder {
 
 impl SeedHolder {
     pub fn new_os_random() -> Self {
+        Self::from_mnemonic(&Self::generate_mnemonic())
+    }
+
+    /// Mnemonic of 24 words for entropy from [`nssa::entropy`], which can be written down to
+    /// recover the seed with [`SeedHolder::from_mnemonic`]
+    pub fn generate_mnemonic() -> Mnemonic {
         let mut enthopy_bytes: [u8; 32] = [0; 32];
-        OsRng.fill_bytes(&mut enthopy_bytes);
+        nssa::entropy::fill_bytes(&mut enthopy_bytes);
 
         let mnemonic = Mnemonic::from_entropy(&enthopy_bytes)
             .expect("Enthropy must be a multiple of 32 bytes");
-        let seed_wide = mnemonic.to_seed("mnemonic");
+        enthopy_bytes.zeroize();
 
-        Self {
-            seed: seed_wide.to_vec(),
-        }
+        mnemonic
+    }
+
+    pub fn from_mnemonic(mnemonic: &Mnemonic) -> Self {
+        let mut seed_wide = mnemonic.to_seed("mnemonic");
+        let seed = seed_wide.to_vec();
+        seed_wide.zeroize();
+
+        Self { seed }
     }
 
     pub fn new_mnemonic(passphrase: String) -> Self {
         let mnemonic = Mnemonic::from_entropy(&NSSA_ENTROPY_BYTES)
             .expect("Enthropy must be a multiple of 32 bytes");
-        let seed_wide = mnemonic.to_seed(passphrase);
+        let mut seed_wide = mnemonic.to_seed(passphrase);
+        let seed = seed_wide.to_vec();
+        seed_wide.zeroize();
 
-        Self {
-            seed: seed_wide.to_vec(),
-        }
+        Self { seed }
     }
 
     pub fn generate_secret_spending_key_hash(&self) -> HashType {
@@ -127,6 +139,12 @@ impl PrivateKeyHolder {
     }
 }
 
+impl Drop for SeedHolder {
+    fn drop(&mut self) {
+        self.seed.zeroize();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,6 +187,18 @@ mod tests {
         let _ = top_secret_key_holder.generate_outgoing_viewing_secret_key();
     }
 
+    #[test]
+    fn seed_is_recovered_from_written_down_mnemonic() {
+        let mnemonic = SeedHolder::generate_mnemonic();
+        let written_down = mnemonic.to_string();
+
+        let seed_holder1 = SeedHolder::from_mnemonic(&mnemonic);
+        let seed_holder2 = SeedHolder::from_mnemonic(&Mnemonic::parse(written_down).unwrap());
+
+        assert_eq!(mnemonic.word_count(), 24);
+        assert_eq!(seed_holder1.seed, seed_holder2.seed);
+    }
+
     #[test]
     fn two_seeds_generated_same_from_same_mnemonic() {
         let mnemonic = "test_pass";
@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
+use itertools::Itertools as _;
 use k256::AffinePoint;
 use serde::{Deserialize, Serialize};
 
 use crate::key_management::{
     KeyChain,
+    audit::{KeyAuditIssue, KeyProblem},
     key_tree::{KeyTreePrivate, KeyTreePublic, chain_index::ChainIndex},
     secret_holders::SeedHolder,
 };
@@ -25,36 +27,24 @@ pub struct NSSAUserData {
     pub private_key_tree: KeyTreePrivate,
 }
 
-impl NSSAUserData {
-    fn valid_public_key_transaction_pairing_check(
-        accounts_keys_map: &HashMap<nssa::AccountId, nssa::PrivateKey>,
-    ) -> bool {
-        let mut check_res = true;
-        for (account_id, key) in accounts_keys_map {
-            let expected_account_id =
-                nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(key));
-            if &expected_account_id != account_id {
-                println!("{}, {}", expected_account_id, account_id);
-                check_res = false;
-            }
-        }
-        check_res
-    }
-
-    fn valid_private_key_transaction_pairing_check(
-        accounts_keys_map: &HashMap<nssa::AccountId, (KeyChain, nssa_core::account::Account)>,
-    ) -> bool {
-        let mut check_res = true;
-        for (account_id, (key, _)) in accounts_keys_map {
-            let expected_account_id = nssa::AccountId::from(&key.nullifer_public_key);
-            if expected_account_id != *account_id {
-                println!("{}, {}", expected_account_id, account_id);
-                check_res = false;
-            }
-        }
-        check_res
-    }
+/// Preconfigured accounts, which keys are invalid or belong to other accounts
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Keys of {} preconfigured accounts don't match them:\n{}",
+    issues.len(),
+    issues.iter().map(|issue| format!("  {issue}")).join("\n")
+)]
+pub struct AccountKeysMismatch {
+    pub issues: Vec<KeyAuditIssue>,
+}
 
+impl NSSAUserData {
+    /// User data with preconfigured accounts, which must be stored under the account ids their
+    /// keys derive
+    ///
+    /// Fails listing every mismatched account, as signatures or decryption with keys of another
+    /// account are rejected only after transactions are sent. Accounts of the key trees without
+    /// keys are watch-only, which is logged as a warning.
     pub fn new_with_accounts(
         default_accounts_keys: HashMap<nssa::AccountId, nssa::PrivateKey>,
         default_accounts_key_chains: HashMap<
@@ -63,25 +53,30 @@ impl NSSAUserData {
         >,
         public_key_tree: KeyTreePublic,
         private_key_tree: KeyTreePrivate,
-    ) -> Result<Self> {
-        if !Self::valid_public_key_transaction_pairing_check(&default_accounts_keys) {
-            anyhow::bail!(
-                "Key transaction pairing check not satisfied, there is account_ids, which is not derived from keys"
-            );
-        }
-
-        if !Self::valid_private_key_transaction_pairing_check(&default_accounts_key_chains) {
-            anyhow::bail!(
-                "Key transaction pairing check not satisfied, there is account_ids, which is not derived from keys"
-            );
-        }
-
-        Ok(Self {
+    ) -> Result<Self, AccountKeysMismatch> {
+        let user_data = Self {
             default_pub_account_signing_keys: default_accounts_keys,
             default_user_private_accounts: default_accounts_key_chains,
             public_key_tree,
             private_key_tree,
-        })
+        };
+
+        let mut issues = user_data.audit_default_accounts();
+        if !issues.is_empty() {
+            issues.sort_by_key(|issue| issue.account_id);
+            return Err(AccountKeysMismatch { issues });
+        }
+
+        for account_id in user_data
+            .public_key_tree
+            .accounts_without_keys()
+            .into_iter()
+            .chain(user_data.private_key_tree.accounts_without_keys())
+        {
+            log::warn!("Keys of account {account_id} are missing, it's watch-only");
+        }
+
+        Ok(user_data)
     }
 
     /// Generated new private key for public transaction signatures
@@ -165,6 +160,62 @@ impl NSSAUserData {
                 .map(Into::into)
         }
     }
+
+    /// Checks that stored keys of all accounts are consistent and derive their account ids
+    pub fn audit(&self) -> Vec<KeyAuditIssue> {
+        let mut issues = self.audit_default_accounts();
+        issues.extend(self.public_key_tree.audit());
+        issues.extend(self.private_key_tree.audit());
+        issues
+    }
+
+    /// Checks keys of preconfigured accounts, which are not in the key trees
+    fn audit_default_accounts(&self) -> Vec<KeyAuditIssue> {
+        let mut issues = vec![];
+
+        for (account_id, key) in &self.default_pub_account_signing_keys {
+            let derived = nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(key));
+            if derived != *account_id {
+                issues.push(KeyAuditIssue {
+                    account_id: *account_id,
+                    chain_index: None,
+                    problem: KeyProblem::AccountIdMismatch { derived },
+                });
+            }
+        }
+
+        for (account_id, (key_chain, _)) in &self.default_user_private_accounts {
+            let mut report = |problem| {
+                issues.push(KeyAuditIssue {
+                    account_id: *account_id,
+                    chain_index: None,
+                    problem,
+                })
+            };
+
+            if let Err(err) = key_chain.audit() {
+                report(KeyProblem::InconsistentKeys {
+                    reason: err.to_string(),
+                });
+            }
+            let derived = nssa::AccountId::from(&key_chain.nullifer_public_key);
+            if derived != *account_id {
+                report(KeyProblem::AccountIdMismatch { derived });
+            }
+        }
+
+        issues
+    }
+
+    /// Re-derives key trees from `seed`, see
+    /// [`KeyTree::repair`](crate::key_management::key_tree::KeyTree::repair)
+    ///
+    /// Returns ids of accounts, which keys were replaced.
+    pub fn repair(&mut self, seed: &SeedHolder) -> Result<Vec<nssa::AccountId>> {
+        let mut repaired = self.public_key_tree.repair(seed)?;
+        repaired.extend(self.private_key_tree.repair(seed)?);
+        Ok(repaired)
+    }
 }
 
 impl Default for NSSAUserData {
@@ -202,4 +253,137 @@ mod tests {
             .0;
         println!("{key_chain:#?}");
     }
+
+    fn public_account_id(key: &nssa::PrivateKey) -> nssa::AccountId {
+        nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(key))
+    }
+
+    fn default_trees() -> (KeyTreePublic, KeyTreePrivate) {
+        let seed = SeedHolder::new_mnemonic("default".to_string());
+        (KeyTreePublic::new(&seed), KeyTreePrivate::new(&seed))
+    }
+
+    #[test]
+    fn test_swapped_public_keys_are_rejected() {
+        let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let other_key = nssa::PrivateKey::try_new([2; 32]).unwrap();
+        let third_key = nssa::PrivateKey::try_new([3; 32]).unwrap();
+        let (public_tree, private_tree) = default_trees();
+
+        let err = NSSAUserData::new_with_accounts(
+            HashMap::from([
+                (public_account_id(&key), other_key.clone()),
+                (public_account_id(&other_key), key.clone()),
+                (public_account_id(&third_key), third_key),
+            ]),
+            HashMap::new(),
+            public_tree,
+            private_tree,
+        )
+        .unwrap_err();
+
+        let mut expected = vec![
+            KeyAuditIssue {
+                account_id: public_account_id(&key),
+                chain_index: None,
+                problem: KeyProblem::AccountIdMismatch {
+                    derived: public_account_id(&other_key),
+                },
+            },
+            KeyAuditIssue {
+                account_id: public_account_id(&other_key),
+                chain_index: None,
+                problem: KeyProblem::AccountIdMismatch {
+                    derived: public_account_id(&key),
+                },
+            },
+        ];
+        expected.sort_by_key(|issue| issue.account_id);
+        assert_eq!(err.issues, expected);
+        assert!(
+            err.to_string()
+                .contains(&public_account_id(&key).to_string())
+        );
+        assert!(
+            err.to_string()
+                .contains(&public_account_id(&other_key).to_string())
+        );
+    }
+
+    #[test]
+    fn test_private_key_with_invalid_scalar_is_rejected() {
+        let mut key_chain = KeyChain::new_mnemonic("test".to_string());
+        key_chain.private_key_holder.incoming_viewing_secret_key = [0xff; 32];
+        let account_id = nssa::AccountId::from(&key_chain.nullifer_public_key);
+        let (public_tree, private_tree) = default_trees();
+
+        let err = NSSAUserData::new_with_accounts(
+            HashMap::new(),
+            HashMap::from([(
+                account_id,
+                (key_chain, nssa_core::account::Account::default()),
+            )]),
+            public_tree,
+            private_tree,
+        )
+        .unwrap_err();
+
+        assert_eq!(err.issues.len(), 1);
+        assert_eq!(err.issues[0].account_id, account_id);
+        assert_eq!(
+            err.issues[0].problem,
+            KeyProblem::InconsistentKeys {
+                reason: "Incoming viewing secret key is not a valid secp256k1 scalar".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_consistent_accounts_are_accepted() {
+        let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let key_chain = KeyChain::new_mnemonic("test".to_string());
+        let private_account_id = nssa::AccountId::from(&key_chain.nullifer_public_key);
+        let (public_tree, private_tree) = default_trees();
+
+        let user_data = NSSAUserData::new_with_accounts(
+            HashMap::from([(public_account_id(&key), key.clone())]),
+            HashMap::from([(
+                private_account_id,
+                (key_chain, nssa_core::account::Account::default()),
+            )]),
+            public_tree,
+            private_tree,
+        )
+        .unwrap();
+
+        assert_eq!(
+            user_data.get_pub_account_signing_key(&public_account_id(&key)),
+            Some(&key)
+        );
+        assert!(user_data.get_private_account(&private_account_id).is_some());
+    }
+
+    #[test]
+    fn test_audit_detects_swapped_default_key() {
+        let mut user_data = NSSAUserData::default();
+        assert!(user_data.audit().is_empty());
+
+        let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let other_key = nssa::PrivateKey::try_new([2; 32]).unwrap();
+        let account_id = nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(&key));
+        user_data
+            .default_pub_account_signing_keys
+            .insert(account_id, other_key.clone());
+
+        let issues = user_data.audit();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].account_id, account_id);
+        assert_eq!(
+            issues[0].problem,
+            KeyProblem::AccountIdMismatch {
+                derived: nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(&other_key)),
+            }
+        );
+        assert!(!issues[0].is_repairable());
+    }
 }
@@ -1,23 +1,49 @@
-use tokio::sync::mpsc::{Receiver, Sender};
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use tokio::sync::mpsc::{Permit, Receiver, Sender, error::SendError};
+
+/// Item in mempool together with its position in submission order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemPoolEntry<T> {
+    /// Sequence number of submission, starting from 0
+    pub arrival_seq: u64,
+    pub item: T,
+}
 
 pub struct MemPool<T> {
-    receiver: Receiver<T>,
+    receiver: Receiver<MemPoolEntry<T>>,
 }
 
 impl<T> MemPool<T> {
     pub fn new(max_size: usize) -> (Self, MemPoolHandle<T>) {
+        Self::new_with_first_arrival_seq(max_size, 0)
+    }
+
+    /// Same as [`Self::new`], but arrival sequence numbers start from `first_arrival_seq`, so
+    /// items restored from elsewhere may take the lower ones
+    pub fn new_with_first_arrival_seq(
+        max_size: usize,
+        first_arrival_seq: u64,
+    ) -> (Self, MemPoolHandle<T>) {
         let (sender, receiver) = tokio::sync::mpsc::channel(max_size);
 
         let mem_pool = Self { receiver };
-        let sender = MemPoolHandle::new(sender);
+        let sender = MemPoolHandle::new(sender, first_arrival_seq);
         (mem_pool, sender)
     }
 
     pub fn pop(&mut self) -> Option<T> {
+        self.pop_entry().map(|entry| entry.item)
+    }
+
+    pub fn pop_entry(&mut self) -> Option<MemPoolEntry<T>> {
         use tokio::sync::mpsc::error::TryRecvError;
 
         match self.receiver.try_recv() {
-            Ok(item) => Some(item),
+            Ok(entry) => Some(entry),
             Err(TryRecvError::Empty) => None,
             Err(TryRecvError::Disconnected) => {
                 panic!("Mempool senders disconnected, cannot receive items, this is a bug")
@@ -27,17 +53,72 @@ impl<T> MemPool<T> {
 }
 
 pub struct MemPoolHandle<T> {
-    sender: Sender<T>,
+    sender: Sender<MemPoolEntry<T>>,
+    next_arrival_seq: Arc<AtomicU64>,
+}
+
+// Derived `Clone` would require `T: Clone`
+impl<T> Clone for MemPoolHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            next_arrival_seq: Arc::clone(&self.next_arrival_seq),
+        }
+    }
 }
 
 impl<T> MemPoolHandle<T> {
-    fn new(sender: Sender<T>) -> Self {
-        Self { sender }
+    fn new(sender: Sender<MemPoolEntry<T>>, first_arrival_seq: u64) -> Self {
+        Self {
+            sender,
+            next_arrival_seq: Arc::new(AtomicU64::new(first_arrival_seq)),
+        }
     }
 
     /// Send an item to the mempool blocking if max size is reached
-    pub async fn push(&self, item: T) -> Result<(), tokio::sync::mpsc::error::SendError<T>> {
-        self.sender.send(item).await
+    ///
+    /// Arrival sequence number is assigned before blocking.
+    pub async fn push(&self, item: T) -> Result<(), SendError<T>> {
+        let arrival_seq = self.next_arrival_seq.fetch_add(1, Ordering::Relaxed);
+        self.sender
+            .send(MemPoolEntry { arrival_seq, item })
+            .await
+            .map_err(|SendError(entry)| SendError(entry.item))
+    }
+
+    /// Number of items waiting in the mempool, including reserved slots
+    pub fn len(&self) -> usize {
+        self.sender.max_capacity() - self.sender.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Waits for a free slot in the mempool, which is released if the permit is dropped
+    ///
+    /// Unlike [`Self::push`], the item is sent without waiting, so cancelling the caller at an
+    /// await point never leaves the item half-sent.
+    pub async fn reserve(&self) -> Result<MemPoolPermit<'_, T>, SendError<()>> {
+        let permit = self.sender.reserve().await?;
+        Ok(MemPoolPermit {
+            permit,
+            next_arrival_seq: &self.next_arrival_seq,
+        })
+    }
+}
+
+/// Slot in the mempool reserved by [`MemPoolHandle::reserve`]
+pub struct MemPoolPermit<'a, T> {
+    permit: Permit<'a, MemPoolEntry<T>>,
+    next_arrival_seq: &'a AtomicU64,
+}
+
+impl<T> MemPoolPermit<'_, T> {
+    /// Sends an item to the reserved slot, arrival sequence number is assigned now
+    pub fn push(self, item: T) {
+        let arrival_seq = self.next_arrival_seq.fetch_add(1, Ordering::Relaxed);
+        self.permit.send(MemPoolEntry { arrival_seq, item });
     }
 }
 
@@ -96,4 +177,75 @@ mod tests {
         assert_eq!(pool.pop(), Some(1));
         assert_eq!(pool.pop(), Some(2));
     }
+
+    #[test]
+    async fn test_arrival_seq_follows_submission_order() {
+        let (mut pool, handle) = MemPool::new(10);
+
+        handle.push("a").await.unwrap();
+        handle.push("b").await.unwrap();
+
+        assert_eq!(
+            pool.pop_entry(),
+            Some(MemPoolEntry {
+                arrival_seq: 0,
+                item: "a"
+            })
+        );
+        assert_eq!(
+            pool.pop_entry(),
+            Some(MemPoolEntry {
+                arrival_seq: 1,
+                item: "b"
+            })
+        );
+    }
+
+    #[test]
+    async fn test_len_counts_waiting_items_of_all_handles() {
+        let (mut pool, handle) = MemPool::new(10);
+        let other_handle = handle.clone();
+        assert!(handle.is_empty());
+
+        handle.push(1).await.unwrap();
+        other_handle.push(2).await.unwrap();
+        assert_eq!(handle.len(), 2);
+
+        pool.pop();
+        assert_eq!(other_handle.len(), 1);
+    }
+
+    #[test]
+    async fn test_dropped_permit_releases_slot() {
+        let (mut pool, handle) = MemPool::new(1);
+
+        let permit = handle.reserve().await.unwrap();
+        drop(permit);
+        assert_eq!(pool.pop(), None);
+
+        // Would block forever if the slot was still taken
+        handle.reserve().await.unwrap().push(1);
+        assert_eq!(
+            pool.pop_entry(),
+            Some(MemPoolEntry {
+                arrival_seq: 0,
+                item: 1
+            })
+        );
+    }
+
+    #[test]
+    async fn test_arrival_seq_starts_from_given_one() {
+        let (mut pool, handle) = MemPool::new_with_first_arrival_seq(10, 5);
+
+        handle.push("a").await.unwrap();
+
+        assert_eq!(
+            pool.pop_entry(),
+            Some(MemPoolEntry {
+                arrival_seq: 5,
+                item: "a"
+            })
+        );
+    }
 }
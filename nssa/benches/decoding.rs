@@ -0,0 +1,30 @@
+use common::{
+    test_utils::independent_native_transfers,
+    transaction::{EncodedTransaction, NSSATransaction},
+};
+use criterion::{Criterion, criterion_group, criterion_main};
+
+const NUM_TXS: u32 = 1000;
+
+/// Transactions from their wire format, as received by the sequencer
+fn bench_decode(c: &mut Criterion) {
+    let encoded_txs: Vec<Vec<u8>> = independent_native_transfers(NUM_TXS, 10)
+        .iter()
+        .map(|tx| borsh::to_vec(tx).unwrap())
+        .collect();
+
+    c.bench_function(&format!("decode_{NUM_TXS}_transactions"), |b| {
+        b.iter(|| {
+            encoded_txs
+                .iter()
+                .map(|bytes| {
+                    let tx = borsh::from_slice::<EncodedTransaction>(bytes).unwrap();
+                    NSSATransaction::try_from(&tx).unwrap()
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+}
+
+criterion_group!(benches, bench_decode);
+criterion_main!(benches);
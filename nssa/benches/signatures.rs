@@ -0,0 +1,46 @@
+use common::{test_utils::independent_native_transfers, transaction::NSSATransaction};
+use criterion::{Criterion, criterion_group, criterion_main};
+use nssa::{PublicKey, Signature};
+use rayon::prelude::*;
+
+const NUM_SIGNATURES: u32 = 1000;
+
+/// Signed message bytes, signatures and public keys of transfers
+fn signed_messages() -> Vec<(Vec<u8>, Signature, PublicKey)> {
+    independent_native_transfers(NUM_SIGNATURES, 10)
+        .iter()
+        .map(|tx| {
+            let NSSATransaction::Public(tx) = NSSATransaction::try_from(tx).unwrap() else {
+                unreachable!("Transfers are public transactions")
+            };
+            let (signature, public_key) = tx.witness_set().signatures_and_public_keys()[0].clone();
+            (tx.message().to_bytes(), signature, public_key)
+        })
+        .collect()
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let signed_messages = signed_messages();
+    let mut group = c.benchmark_group(format!("verify_{NUM_SIGNATURES}_signatures"));
+
+    group.bench_function("individual", |b| {
+        b.iter(|| {
+            signed_messages
+                .iter()
+                .all(|(message, signature, public_key)| signature.is_valid_for(message, public_key))
+        })
+    });
+    // Signatures of a block are verified together in parallel, as in block validation
+    group.bench_function("batch", |b| {
+        b.iter(|| {
+            signed_messages
+                .par_iter()
+                .all(|(message, signature, public_key)| signature.is_valid_for(message, public_key))
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify);
+criterion_main!(benches);
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use common::{
+    test_utils::{independent_native_transfers, state_with_funded_accounts},
+    transaction::NSSATransaction,
+};
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use nssa::{Account, AccountId, Amount, PublicTransaction, account_tree::AccountTree};
+use sha2::{Digest as _, Sha256};
+
+const NUM_ACCOUNTS: u32 = 100_000;
+const NUM_TXS_IN_BLOCK: u32 = 1000;
+
+fn block_transactions() -> Vec<PublicTransaction> {
+    independent_native_transfers(NUM_TXS_IN_BLOCK, 10)
+        .iter()
+        .map(|tx| {
+            let NSSATransaction::Public(tx) = NSSATransaction::try_from(tx).unwrap() else {
+                unreachable!("Transfers are public transactions")
+            };
+            tx
+        })
+        .collect()
+}
+
+/// Applies a block the way the sequencer does after pre-checking its transactions
+fn bench_apply_block(c: &mut Criterion) {
+    let state = state_with_funded_accounts(NUM_ACCOUNTS, Amount(1000));
+    let txs = block_transactions();
+    let mut group = c.benchmark_group("apply_block");
+    // Every transaction executes a program, so an iteration takes seconds
+    group.sample_size(10);
+
+    group.bench_function(
+        format!("{NUM_TXS_IN_BLOCK}_txs_{NUM_ACCOUNTS}_accounts"),
+        |b| {
+            b.iter_batched(
+                || state.clone(),
+                |mut state| {
+                    for tx in &txs {
+                        state
+                            .transition_from_prevalidated_public_transaction(tx)
+                            .unwrap();
+                    }
+                    state
+                },
+                BatchSize::LargeInput,
+            )
+        },
+    );
+
+    group.finish();
+}
+
+/// Root of the public account tree after a block touching `NUM_TXS_IN_BLOCK` accounts, which
+/// should barely depend on the number of accounts
+fn bench_state_root_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("state_root_update");
+
+    for num_accounts in [10_000, 100_000, 1_000_000] {
+        let accounts = (0..num_accounts).map(account).collect::<Vec<_>>();
+        let tree = accounts
+            .iter()
+            .map(|(account_id, account)| (account_id, account))
+            .collect::<AccountTree>();
+        let touched = accounts
+            .iter()
+            .step_by((num_accounts / NUM_TXS_IN_BLOCK) as usize)
+            .map(|(account_id, account)| {
+                let mut account = account.clone();
+                account.balance = account.balance.saturating_add(Amount(1));
+                (*account_id, account)
+            })
+            .collect::<Vec<_>>();
+
+        group.bench_function(format!("{num_accounts}_accounts"), |b| {
+            b.iter_batched(
+                || tree.clone(),
+                |mut tree| {
+                    for (account_id, account) in &touched {
+                        tree.insert(*account_id, account);
+                    }
+                    tree.root()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+/// Root of the public account tree computed from scratch, for comparison with
+/// [`bench_state_root_update`]
+fn bench_state_root_full_recompute(c: &mut Criterion) {
+    let accounts = (0..NUM_ACCOUNTS).map(account).collect::<HashMap<_, _>>();
+
+    c.bench_function(
+        &format!("state_root_full_recompute_{NUM_ACCOUNTS}_accounts"),
+        |b| b.iter(|| AccountTree::compute_root(&accounts)),
+    );
+}
+
+fn account(index: u32) -> (AccountId, Account) {
+    let account_id = AccountId::new(Sha256::digest(index.to_le_bytes()).into());
+    let account = Account {
+        balance: Amount(1000),
+        ..Account::default()
+    };
+    (account_id, account)
+}
+
+criterion_group!(
+    benches,
+    bench_apply_block,
+    bench_state_root_update,
+    bench_state_root_full_recompute
+);
+criterion_main!(benches);
@@ -7,11 +7,14 @@ use borsh::{BorshDeserialize, BorshSerialize};
 pub use data::Data;
 use serde::{Deserialize, Serialize};
 
-use crate::program::ProgramId;
+use crate::{program::ProgramId, units::Amount};
 
 pub mod data;
 
-pub type Nonce = u128;
+/// Number of transactions authorized by the account
+///
+/// Nonce is never wrapped around: an account at [`Nonce::MAX`] can no longer transact.
+pub type Nonce = u64;
 
 /// Account to be used both in public and private contexts
 #[derive(
@@ -20,11 +23,32 @@ pub type Nonce = u128;
 #[cfg_attr(any(feature = "host", test), derive(Debug))]
 pub struct Account {
     pub program_owner: ProgramId,
-    pub balance: u128,
+    pub balance: Amount,
     pub data: Data,
     pub nonce: Nonce,
 }
 
+impl Account {
+    /// Increments the nonce after a transaction authorized by the account
+    ///
+    /// Returns `false` and keeps the nonce if it's exhausted.
+    pub fn increment_nonce(&mut self) -> bool {
+        match self.nonce.checked_add(1) {
+            Some(nonce) => {
+                self.nonce = nonce;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Part of the balance, which is locked while the account keeps its data, at
+    /// `rate_per_byte` of data, see [`crate::program::is_storage_deposit_kept`]
+    pub fn storage_deposit(&self, rate_per_byte: Amount) -> Amount {
+        rate_per_byte.saturating_mul(self.data.len() as u128)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[cfg_attr(any(feature = "host", test), derive(Debug, PartialEq, Eq))]
 pub struct AccountWithMetadata {
@@ -54,7 +78,7 @@ pub struct AccountId {
 }
 
 impl AccountId {
-    pub fn new(value: [u8; 32]) -> Self {
+    pub const fn new(value: [u8; 32]) -> Self {
         Self { value }
     }
 
@@ -111,7 +135,7 @@ mod tests {
     fn test_zero_balance_account_data_creation() {
         let new_acc = Account::default();
 
-        assert_eq!(new_acc.balance, 0);
+        assert_eq!(new_acc.balance, Amount::ZERO);
     }
 
     #[test]
@@ -121,6 +145,19 @@ mod tests {
         assert_eq!(new_acc.nonce, 0);
     }
 
+    #[test]
+    fn test_exhausted_nonce_can_not_be_incremented() {
+        let mut account = Account {
+            nonce: Nonce::MAX - 1,
+            ..Account::default()
+        };
+
+        assert!(account.increment_nonce());
+        assert_eq!(account.nonce, Nonce::MAX);
+        assert!(!account.increment_nonce());
+        assert_eq!(account.nonce, Nonce::MAX);
+    }
+
     #[test]
     fn test_empty_data_account_data_creation() {
         let new_acc = Account::default();
@@ -139,8 +176,8 @@ mod tests {
     #[test]
     fn test_account_with_metadata_constructor() {
         let account = Account {
-            program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
-            balance: 1337,
+            program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
+            balance: Amount(1337),
             data: b"testing_account_with_metadata_constructor"
                 .to_vec()
                 .try_into()
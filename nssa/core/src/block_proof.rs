@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// Public values, which a proof of a block commits to as its journal
+///
+/// Verifier checks every field against the values it computed itself, so a proof is bound to
+/// the block it's served for and to the states before and after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockProofJournal {
+    /// Root of the state, which the block is applied to
+    pub pre_state_root: [u8; 32],
+    /// Root of the state after the block
+    pub post_state_root: [u8; 32],
+    pub block_hash: [u8; 32],
+    /// Number of transactions in the block
+    pub tx_count: u32,
+}
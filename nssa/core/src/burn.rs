@@ -0,0 +1,26 @@
+//! Burn account, which balance can never be moved, so transferring balance to it destroys it.
+
+use crate::{
+    account::{Account, AccountId},
+    program::ProgramId,
+};
+
+/// Id of the burn account
+///
+/// It isn't derived from a public key, so no transaction can be signed for it.
+pub const BURN_ACCOUNT_ID: AccountId =
+    AccountId::new(*b"/NSSA/v0.2/AccountId/Burn/\x00\x00\x00\x00\x00\x00");
+
+/// Owner of the burn account
+///
+/// No program has this id, and only the owner may decrease balance of an account, so balance of
+/// the burn account only grows.
+pub const BURN_PROGRAM_ID: ProgramId = ProgramId::new([u32::MAX - 1; 8]);
+
+/// Burn account at genesis, which is owned by [`BURN_PROGRAM_ID`], so it can't be claimed
+pub fn burn_account() -> Account {
+    Account {
+        program_owner: BURN_PROGRAM_ID,
+        ..Account::default()
+    }
+}
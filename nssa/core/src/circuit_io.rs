@@ -44,6 +44,7 @@ mod tests {
     use crate::{
         Commitment, Nullifier, NullifierPublicKey,
         account::{Account, AccountId, AccountWithMetadata},
+        units::Amount,
     };
 
     #[test]
@@ -52,8 +53,8 @@ mod tests {
             public_pre_states: vec![
                 AccountWithMetadata::new(
                     Account {
-                        program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
-                        balance: 12345678901234567890,
+                        program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
+                        balance: Amount(12345678901234567890),
                         data: b"test data".to_vec().try_into().unwrap(),
                         nonce: 18446744073709551614,
                     },
@@ -62,18 +63,18 @@ mod tests {
                 ),
                 AccountWithMetadata::new(
                     Account {
-                        program_owner: [9, 9, 9, 8, 8, 8, 7, 7],
-                        balance: 123123123456456567112,
+                        program_owner: ProgramId::new([9, 9, 9, 8, 8, 8, 7, 7]),
+                        balance: Amount(123123123456456567112),
                         data: b"test data".to_vec().try_into().unwrap(),
-                        nonce: 9999999999999999999999,
+                        nonce: 9999999999999999999,
                     },
                     false,
                     AccountId::new([1; 32]),
                 ),
             ],
             public_post_states: vec![Account {
-                program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
-                balance: 100,
+                program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
+                balance: Amount(100),
                 data: b"post state data".to_vec().try_into().unwrap(),
                 nonce: 18446744073709551615,
             }],
@@ -0,0 +1,64 @@
+//! Clock account, which lets programs read id of the block their transaction is included into.
+
+use crate::{
+    account::{Account, AccountId, AccountWithMetadata},
+    program::ProgramId,
+};
+
+/// Id of the clock account
+///
+/// Its data is the id of the block being produced, as little-endian `u64`.
+pub const CLOCK_ACCOUNT_ID: AccountId =
+    AccountId::new(*b"/NSSA/v0.2/AccountId/Clock/\x00\x00\x00\x00\x00");
+
+/// Owner of the clock account
+///
+/// No program has this id, so programs can't modify the clock.
+pub const CLOCK_PROGRAM_ID: ProgramId = ProgramId::new([u32::MAX; 8]);
+
+/// Clock account at block `block_id`
+pub fn clock_account(block_id: u64) -> Account {
+    Account {
+        program_owner: CLOCK_PROGRAM_ID,
+        data: block_id
+            .to_le_bytes()
+            .to_vec()
+            .try_into()
+            .expect("8 bytes should fit into Data"),
+        ..Account::default()
+    }
+}
+
+/// Id of the current block, if `pre_state` is the clock account
+pub fn read_block_id(pre_state: &AccountWithMetadata) -> Option<u64> {
+    if pre_state.account_id != CLOCK_ACCOUNT_ID
+        || pre_state.account.program_owner != CLOCK_PROGRAM_ID
+    {
+        return None;
+    }
+
+    let bytes = pre_state.account.data.as_ref().try_into().ok()?;
+    Some(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_block_id() {
+        let clock = AccountWithMetadata {
+            account: clock_account(42),
+            is_authorized: false,
+            account_id: CLOCK_ACCOUNT_ID,
+        };
+        assert_eq!(read_block_id(&clock), Some(42));
+
+        // Same data in any other account isn't a clock
+        let fake_clock = AccountWithMetadata {
+            account_id: AccountId::new([1; 32]),
+            ..clock
+        };
+        assert_eq!(read_block_id(&fake_clock), None);
+    }
+}
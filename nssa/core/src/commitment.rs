@@ -12,12 +12,12 @@ pub struct Commitment(pub(super) [u8; 32]);
 /// ```python
 /// from hashlib import sha256
 /// hasher = sha256()
-/// hasher.update(bytes([0] * 32 + [0] * 32 + [0] * 16 + [0] * 16 + list(sha256().digest())))
+/// hasher.update(bytes([0] * 32 + [0] * 32 + [0] * 16 + [0] * 8 + list(sha256().digest())))
 /// DUMMY_COMMITMENT = hasher.digest()
 /// ```
 pub const DUMMY_COMMITMENT: Commitment = Commitment([
-    130, 75, 48, 230, 171, 101, 121, 141, 159, 118, 21, 74, 135, 248, 16, 255, 238, 156, 61, 24,
-    165, 33, 34, 172, 227, 30, 215, 20, 85, 47, 230, 29,
+    112, 166, 136, 152, 183, 28, 170, 34, 165, 52, 100, 209, 239, 75, 224, 104, 146, 156, 135, 136,
+    160, 222, 237, 20, 65, 247, 128, 61, 28, 122, 121, 5,
 ]);
 
 /// The hash of the dummy commitment
@@ -28,8 +28,8 @@ pub const DUMMY_COMMITMENT: Commitment = Commitment([
 /// DUMMY_COMMITMENT_HASH = hasher.digest()
 /// ```
 pub const DUMMY_COMMITMENT_HASH: [u8; 32] = [
-    170, 10, 217, 228, 20, 35, 189, 177, 238, 235, 97, 129, 132, 89, 96, 247, 86, 91, 222, 214, 38,
-    194, 216, 67, 56, 251, 208, 226, 0, 117, 149, 39,
+    194, 92, 221, 139, 160, 7, 168, 28, 111, 162, 202, 84, 110, 210, 50, 58, 3, 86, 118, 92, 13,
+    174, 198, 156, 142, 249, 56, 238, 45, 25, 88, 216,
 ];
 
 impl Commitment {
@@ -40,10 +40,8 @@ impl Commitment {
         bytes.extend_from_slice(&npk.to_byte_array());
         let account_bytes_with_hashed_data = {
             let mut this = Vec::new();
-            for word in &account.program_owner {
-                this.extend_from_slice(&word.to_le_bytes());
-            }
-            this.extend_from_slice(&account.balance.to_le_bytes());
+            this.extend_from_slice(&account.program_owner.to_bytes());
+            this.extend_from_slice(&account.balance.0.to_le_bytes());
             this.extend_from_slice(&account.nonce.to_le_bytes());
             let hashed_data: [u8; 32] = Impl::hash_bytes(&account.data)
                 .as_bytes()
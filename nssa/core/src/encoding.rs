@@ -10,19 +10,20 @@ use crate::Nullifier;
 use crate::encryption::shared_key_derivation::Secp256k1Point;
 #[cfg(feature = "host")]
 use crate::error::NssaCoreError;
+#[cfg(any(feature = "host", test))]
+use crate::program::ProgramId;
 use crate::{
     Commitment, NullifierPublicKey,
     account::{Account, AccountId},
     encryption::Ciphertext,
+    units::Amount,
 };
 
 impl Account {
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
-        for word in &self.program_owner {
-            bytes.extend_from_slice(&word.to_le_bytes());
-        }
-        bytes.extend_from_slice(&self.balance.to_le_bytes());
+        bytes.extend_from_slice(&self.program_owner.to_bytes());
+        bytes.extend_from_slice(&self.balance.0.to_le_bytes());
         bytes.extend_from_slice(&self.nonce.to_le_bytes());
         let data_length: u32 = self.data.len() as u32;
         bytes.extend_from_slice(&data_length.to_le_bytes());
@@ -34,23 +35,21 @@ impl Account {
     pub fn from_cursor(cursor: &mut Cursor<&[u8]>) -> Result<Self, NssaCoreError> {
         use crate::account::data::Data;
 
-        let mut u32_bytes = [0u8; 4];
+        let mut u64_bytes = [0u8; 8];
         let mut u128_bytes = [0u8; 16];
 
         // program owner
-        let mut program_owner = [0u32; 8];
-        for word in &mut program_owner {
-            cursor.read_exact(&mut u32_bytes)?;
-            *word = u32::from_le_bytes(u32_bytes);
-        }
+        let mut program_owner_bytes = [0u8; 32];
+        cursor.read_exact(&mut program_owner_bytes)?;
+        let program_owner = ProgramId::from_bytes(program_owner_bytes);
 
         // balance
         cursor.read_exact(&mut u128_bytes)?;
-        let balance = u128::from_le_bytes(u128_bytes);
+        let balance = Amount(u128::from_le_bytes(u128_bytes));
 
         // nonce
-        cursor.read_exact(&mut u128_bytes)?;
-        let nonce = u128::from_le_bytes(u128_bytes);
+        cursor.read_exact(&mut u64_bytes)?;
+        let nonce = u64::from_le_bytes(u64_bytes);
 
         // data
         let data = Data::from_cursor(cursor)?;
@@ -145,8 +144,8 @@ mod tests {
     #[test]
     fn test_enconding() {
         let account = Account {
-            program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
-            balance: 123456789012345678901234567890123456,
+            program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
+            balance: Amount(123456789012345678901234567890123456),
             nonce: 42,
             data: b"hola mundo".to_vec().try_into().unwrap(),
         };
@@ -155,8 +154,7 @@ mod tests {
         let expected_bytes = [
             1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 5, 0, 0, 0, 6, 0, 0, 0, 7, 0, 0, 0, 8,
             0, 0, 0, 192, 186, 220, 114, 113, 65, 236, 234, 222, 15, 215, 191, 227, 198, 23, 0, 42,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 10, 0, 0, 0, 104, 111, 108, 97, 32, 109,
-            117, 110, 100, 111,
+            0, 0, 0, 0, 0, 0, 0, 10, 0, 0, 0, 104, 111, 108, 97, 32, 109, 117, 110, 100, 111,
         ];
 
         let bytes = account.to_bytes();
@@ -206,8 +204,8 @@ mod tests {
     #[test]
     fn test_account_to_bytes_roundtrip() {
         let account = Account {
-            program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
-            balance: 123456789012345678901234567890123456,
+            program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
+            balance: Amount(123456789012345678901234567890123456),
             nonce: 42,
             data: b"hola mundo".to_vec().try_into().unwrap(),
         };
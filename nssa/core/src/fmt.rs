@@ -0,0 +1,72 @@
+//! Compact formatting of ids and hashes for logs.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::account::AccountId;
+
+/// Number of bytes shown from each end of the value
+const SHOWN_BYTES: usize = 4;
+
+/// Displays bytes as hex of the first and the last 4 bytes, e.g. `0a1b2c3d…4e5f6a7b`
+///
+/// Values not longer than 8 bytes are displayed in full.
+#[derive(Clone, Copy)]
+pub struct ShortHex<'a>(pub &'a [u8]);
+
+impl Display for ShortHex<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let write_hex = |f: &mut Formatter<'_>, bytes: &[u8]| {
+            bytes.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+        };
+
+        if self.0.len() <= 2 * SHOWN_BYTES {
+            return write_hex(f, self.0);
+        }
+        write_hex(f, &self.0[..SHOWN_BYTES])?;
+        f.write_str("…")?;
+        write_hex(f, &self.0[self.0.len() - SHOWN_BYTES..])
+    }
+}
+
+impl fmt::Debug for ShortHex<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+/// Shortened representation for logs
+pub trait FmtShort {
+    fn fmt_short(&self) -> ShortHex<'_>;
+}
+
+impl FmtShort for AccountId {
+    fn fmt_short(&self) -> ShortHex<'_> {
+        ShortHex(self.value())
+    }
+}
+
+/// Hashes, e.g. of transactions and blocks
+impl FmtShort for [u8; 32] {
+    fn fmt_short(&self) -> ShortHex<'_> {
+        ShortHex(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_hex_keeps_both_ends() {
+        let mut hash = [0; 32];
+        hash[0] = 0xab;
+        hash[31] = 0xcd;
+
+        assert_eq!(hash.fmt_short().to_string(), "ab000000…000000cd");
+    }
+
+    #[test]
+    fn test_short_hex_of_short_value_is_full() {
+        assert_eq!(ShortHex(&[1, 2, 3]).to_string(), "010203");
+    }
+}
@@ -1,11 +1,17 @@
 pub mod account;
+mod block_proof;
+pub mod burn;
 mod circuit_io;
+pub mod clock;
 mod commitment;
 mod encoding;
 pub mod encryption;
+pub mod fmt;
 mod nullifier;
 pub mod program;
+pub mod units;
 
+pub use block_proof::BlockProofJournal;
 pub use circuit_io::{PrivacyPreservingCircuitInput, PrivacyPreservingCircuitOutput};
 pub use commitment::{
     Commitment, CommitmentSetDigest, DUMMY_COMMITMENT, DUMMY_COMMITMENT_HASH, MembershipProof,
@@ -1,13 +1,150 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use risc0_zkvm::{DeserializeOwned, guest::env, serde::Deserializer};
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "host")]
 use crate::account::AccountId;
 use crate::account::{Account, AccountWithMetadata};
+use crate::units::Amount;
+
+/// Id of a program, i.e. the image id of its binary
+///
+/// It's a different kind of value, than an [`crate::account::AccountId`], though both take 32
+/// bytes. It's displayed and serialized into human readable formats as [`PROGRAM_ID_PREFIX`]
+/// followed by hex of its bytes, so the two are never mixed up. Binary encodings are the ones of
+/// its words.
+#[derive(
+    Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, BorshSerialize, BorshDeserialize,
+)]
+pub struct ProgramId([u32; 8]);
 
-pub type ProgramId = [u32; 8];
 pub type InstructionData = Vec<u32>;
-pub const DEFAULT_PROGRAM_ID: ProgramId = [0; 8];
+pub const DEFAULT_PROGRAM_ID: ProgramId = ProgramId::new([0; 8]);
+
+/// Prefix of displayed program ids
+pub const PROGRAM_ID_PREFIX: &str = "prog_";
+
+impl ProgramId {
+    /// Program id with the words of the image id, as they're generated for program binaries
+    pub const fn new(image_id: [u32; 8]) -> Self {
+        Self(image_id)
+    }
+
+    /// Words of the image id, the zkVM verifies executions against
+    pub const fn image_id(&self) -> [u32; 8] {
+        self.0
+    }
+
+    /// Little-endian bytes of the words, the order the zkVM hashes them in
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(self.0) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        let mut words = [0; 8];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+        Self(words)
+    }
+}
+
+impl From<ProgramId> for risc0_zkvm::sha::Digest {
+    fn from(value: ProgramId) -> Self {
+        value.0.into()
+    }
+}
+
+impl std::fmt::Display for ProgramId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(PROGRAM_ID_PREFIX)?;
+        for byte in self.to_bytes() {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for ProgramId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ProgramId({self})")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProgramIdError {
+    #[error("Program id must start with {PROGRAM_ID_PREFIX:?}")]
+    MissingPrefix,
+    #[error("Program id must be hex of 32 bytes")]
+    InvalidHex,
+}
+
+impl std::str::FromStr for ProgramId {
+    type Err = ProgramIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s
+            .strip_prefix(PROGRAM_ID_PREFIX)
+            .ok_or(ProgramIdError::MissingPrefix)?;
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(ProgramIdError::InvalidHex);
+        }
+        let mut bytes = [0; 32];
+        for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+            // Digits are ASCII, so they're valid UTF-8
+            let digits = std::str::from_utf8(digits).unwrap();
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| ProgramIdError::InvalidHex)?;
+        }
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+impl Serialize for ProgramId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            Serialize::serialize(&self.0, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ProgramId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = ProgramId;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "program id starting with {PROGRAM_ID_PREFIX:?}")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<ProgramId, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            // Program ids were words before they got their own type
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                seq: A,
+            ) -> Result<ProgramId, A::Error> {
+                Deserialize::deserialize(serde::de::value::SeqAccessDeserializer::new(seq))
+                    .map(ProgramId)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(Visitor)
+        } else {
+            Deserialize::deserialize(deserializer).map(ProgramId)
+        }
+    }
+}
 
 pub struct ProgramInput<T> {
     pub pre_states: Vec<AccountWithMetadata>,
@@ -38,9 +175,7 @@ impl From<(&ProgramId, &PdaSeed)> for AccountId {
 
         let mut bytes = [0; 96];
         bytes[0..32].copy_from_slice(PROGRAM_DERIVED_ACCOUNT_ID_PREFIX);
-        let program_id_bytes: &[u8] =
-            bytemuck::try_cast_slice(value.0).expect("ProgramId should be castable to &[u8]");
-        bytes[32..64].copy_from_slice(program_id_bytes);
+        bytes[32..64].copy_from_slice(&value.0.to_bytes());
         bytes[64..].copy_from_slice(&value.1.0);
         AccountId::new(
             Impl::hash_bytes(&bytes)
@@ -126,6 +261,17 @@ pub fn read_nssa_inputs<T: DeserializeOwned>() -> ProgramInput<T> {
     }
 }
 
+/// Reads inputs keeping the raw words of the instruction, for programs which tell their
+/// instructions apart by length
+pub fn read_nssa_inputs_words() -> ProgramInput<InstructionData> {
+    let pre_states: Vec<AccountWithMetadata> = env::read();
+    let instruction: InstructionData = env::read();
+    ProgramInput {
+        pre_states,
+        instruction,
+    }
+}
+
 pub fn write_nssa_outputs(
     pre_states: Vec<AccountWithMetadata>,
     post_states: Vec<AccountPostState>,
@@ -151,77 +297,171 @@ pub fn write_nssa_outputs_with_chained_call(
     env::commit(&output);
 }
 
+/// Rule of well-behaved program execution violated by program output
+#[derive(Debug, thiserror::Error, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionConstraintError {
+    /// Every pre state must have exactly one post state, so accounts can't be added or removed
+    #[error("{post_states} post states for {pre_states} pre states")]
+    PostStatesCountMismatch {
+        pre_states: usize,
+        post_states: usize,
+    },
+    /// An account may appear only once, otherwise post states of its copies would overwrite
+    /// each other and could mint balance
+    #[error("account at index {index} appears more than once")]
+    DuplicateAccount { index: usize },
+    /// Nonces are incremented by the protocol only
+    #[error("nonce of account at index {index} changed")]
+    NonceChanged { index: usize },
+    /// Only the owning program can reassign an account, default accounts get their owner by
+    /// claims only
+    #[error("program owner of account at index {index} not owned by the program changed")]
+    ProgramOwnerChanged { index: usize },
+    /// Accounts are reassigned only with consent of their signer
+    #[error("account at index {index} reassigned without authorization")]
+    UnauthorizedAssignment { index: usize },
+    /// Data is meaningful to its owner only, so accounts with data can't be reassigned
+    #[error("account at index {index} with data reassigned")]
+    AssignmentOfAccountWithData { index: usize },
+    /// Only accounts with default program owner can be claimed
+    #[error("claim of account at index {index} owned by a program")]
+    ClaimOfOwnedAccount { index: usize },
+    /// Only the owning program can decrease balance, no matter whether the account is
+    /// authorized
+    #[error("balance of account at index {index} not owned by the program decreased")]
+    UnauthorizedBalanceDecrease { index: usize },
+    /// Only the owning program can change data of initialized accounts
+    #[error("data of account at index {index} not owned by the program changed")]
+    UnauthorizedDataChange { index: usize },
+    /// Data of a default account can only be set together with a claim, otherwise the account
+    /// would end up initialized without an owner and couldn't be changed anymore
+    #[error("data of default account at index {index} changed without a claim")]
+    UnclaimedDataChange { index: usize },
+    /// Initialized accounts must keep a program owner
+    #[error("initialized account at index {index} has default program owner")]
+    DefaultProgramOwner { index: usize },
+    /// Programs can only move balance between accounts, not mint or burn it
+    #[error("total balance is not preserved")]
+    BalanceNotPreserved,
+}
+
 /// Validates well-behaved program execution
 ///
 /// # Parameters
 /// - `pre_states`: The list of input accounts, each annotated with authorization metadata.
 /// - `post_states`: The list of resulting accounts after executing the program logic.
 /// - `executing_program_id`: The identifier of the program that was executed.
+///
+/// # Errors
+/// Returns the first violated rule, see [`ExecutionConstraintError`].
 pub fn validate_execution(
     pre_states: &[AccountWithMetadata],
     post_states: &[AccountPostState],
     executing_program_id: ProgramId,
-) -> bool {
+) -> Result<(), ExecutionConstraintError> {
     // 1. Lengths must match
     if pre_states.len() != post_states.len() {
-        return false;
+        return Err(ExecutionConstraintError::PostStatesCountMismatch {
+            pre_states: pre_states.len(),
+            post_states: post_states.len(),
+        });
+    }
+
+    // 2. Accounts must be unique
+    for (index, pre) in pre_states.iter().enumerate() {
+        if pre_states[..index]
+            .iter()
+            .any(|other| other.account_id == pre.account_id)
+        {
+            return Err(ExecutionConstraintError::DuplicateAccount { index });
+        }
     }
 
-    for (pre, post) in pre_states.iter().zip(post_states) {
-        // 2. Nonce must remain unchanged
+    for (index, (pre, post)) in pre_states.iter().zip(post_states).enumerate() {
+        // 3. Nonce must remain unchanged
         if pre.account.nonce != post.account.nonce {
-            return false;
+            return Err(ExecutionConstraintError::NonceChanged { index });
         }
 
-        // 3. Program ownership changes are not allowed
+        // 4. Program ownership changes only allowed if owned by executing program, authorized
+        //    and the account has no data before and after
         if pre.account.program_owner != post.account.program_owner {
-            return false;
+            if pre.account.program_owner != executing_program_id {
+                return Err(ExecutionConstraintError::ProgramOwnerChanged { index });
+            }
+            if !pre.is_authorized {
+                return Err(ExecutionConstraintError::UnauthorizedAssignment { index });
+            }
+            if !pre.account.data.is_empty() || !post.account.data.is_empty() {
+                return Err(ExecutionConstraintError::AssignmentOfAccountWithData { index });
+            }
         }
 
         let account_program_owner = pre.account.program_owner;
 
-        // 4. Decreasing balance only allowed if owned by executing program
+        // 5. Only accounts with default program owner can be claimed
+        if post.requires_claim() && account_program_owner != DEFAULT_PROGRAM_ID {
+            return Err(ExecutionConstraintError::ClaimOfOwnedAccount { index });
+        }
+
+        // 6. Decreasing balance only allowed if owned by executing program
         if post.account.balance < pre.account.balance
             && account_program_owner != executing_program_id
         {
-            return false;
+            return Err(ExecutionConstraintError::UnauthorizedBalanceDecrease { index });
         }
 
-        // 5. Data changes only allowed if owned by executing program or if account pre state has
+        // 7. Data changes only allowed if owned by executing program or if account pre state has
         //    default values
         if pre.account.data != post.account.data
             && pre.account != Account::default()
             && account_program_owner != executing_program_id
         {
-            return false;
+            return Err(ExecutionConstraintError::UnauthorizedDataChange { index });
+        }
+
+        // 8. Data of default accounts can only be set by claiming them
+        if pre.account.data != post.account.data
+            && pre.account == Account::default()
+            && !post.requires_claim()
+        {
+            return Err(ExecutionConstraintError::UnclaimedDataChange { index });
         }
 
-        // 6. If a post state has default program owner, the pre state must have been a default
+        // 9. If a post state has default program owner, the pre state must have been a default
         //    account
         if post.account.program_owner == DEFAULT_PROGRAM_ID && pre.account != Account::default() {
-            return false;
+            return Err(ExecutionConstraintError::DefaultProgramOwner { index });
         }
     }
 
-    // 7. Total balance is preserved
+    // 10. Total balance is preserved
 
-    let Some(total_balance_pre_states) =
+    let total_balance_pre_states =
         WrappedBalanceSum::from_balances(pre_states.iter().map(|pre| pre.account.balance))
-    else {
-        return false;
-    };
+            .ok_or(ExecutionConstraintError::BalanceNotPreserved)?;
 
-    let Some(total_balance_post_states) =
+    let total_balance_post_states =
         WrappedBalanceSum::from_balances(post_states.iter().map(|post| post.account.balance))
-    else {
-        return false;
-    };
+            .ok_or(ExecutionConstraintError::BalanceNotPreserved)?;
 
     if total_balance_pre_states != total_balance_post_states {
-        return false;
+        return Err(ExecutionConstraintError::BalanceNotPreserved);
     }
 
-    true
+    Ok(())
+}
+
+/// Checks that `post` keeps the storage deposit of its data locked, see
+/// [`Account::storage_deposit`]
+///
+/// Data may only grow if the balance covers the deposit of all of it, and balance may only be
+/// spent down to the deposit. Shrinking or clearing data refunds the deposit of removed bytes.
+/// Accounts, which don't cover their deposit after the rate was raised, may still shrink their
+/// data and receive balance.
+pub fn is_storage_deposit_kept(pre: &Account, post: &Account, rate_per_byte: Amount) -> bool {
+    post.balance >= post.storage_deposit(rate_per_byte)
+        || (post.data.len() <= pre.data.len() && post.balance >= pre.balance)
 }
 
 /// Representation of a number as `lo + hi * 2^128`.
@@ -236,11 +476,11 @@ impl WrappedBalanceSum {
     ///
     /// Returns [`None`] if balance sum overflows `lo + hi * 2^128` representation, which is not
     /// expected in practical scenarios.
-    fn from_balances(balances: impl Iterator<Item = u128>) -> Option<Self> {
+    fn from_balances(balances: impl Iterator<Item = Amount>) -> Option<Self> {
         let mut wrapped = WrappedBalanceSum { lo: 0, hi: 0 };
 
         for balance in balances {
-            let (new_sum, did_overflow) = wrapped.lo.overflowing_add(balance);
+            let (new_sum, did_overflow) = wrapped.lo.overflowing_add(balance.0);
             if did_overflow {
                 wrapped.hi = wrapped.hi.checked_add(1)?;
             }
@@ -258,8 +498,8 @@ mod tests {
     #[test]
     fn test_post_state_new_with_claim_constructor() {
         let account = Account {
-            program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
-            balance: 1337,
+            program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
+            balance: Amount(1337),
             data: vec![0xde, 0xad, 0xbe, 0xef].try_into().unwrap(),
             nonce: 10,
         };
@@ -273,8 +513,8 @@ mod tests {
     #[test]
     fn test_post_state_new_without_claim_constructor() {
         let account = Account {
-            program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
-            balance: 1337,
+            program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
+            balance: Amount(1337),
             data: vec![0xde, 0xad, 0xbe, 0xef].try_into().unwrap(),
             nonce: 10,
         };
@@ -288,8 +528,8 @@ mod tests {
     #[test]
     fn test_post_state_account_getter() {
         let mut account = Account {
-            program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
-            balance: 1337,
+            program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
+            balance: Amount(1337),
             data: vec![0xde, 0xad, 0xbe, 0xef].try_into().unwrap(),
             nonce: 10,
         };
@@ -299,4 +539,502 @@ mod tests {
         assert_eq!(account_post_state.account(), &account);
         assert_eq!(account_post_state.account_mut(), &mut account);
     }
+
+    #[test]
+    fn test_program_id_display_roundtrip() {
+        let program_id = ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let string = program_id.to_string();
+
+        assert_eq!(
+            string,
+            "prog_0100000002000000030000000400000005000000060000000700000008000000"
+        );
+        assert_eq!(string.parse::<ProgramId>().unwrap(), program_id);
+    }
+
+    #[test]
+    fn test_program_id_is_serialized_as_prefixed_string() {
+        let program_id = ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let json = serde_json::to_value(program_id).unwrap();
+
+        assert_eq!(json, serde_json::json!(program_id.to_string()));
+        assert_eq!(
+            serde_json::from_value::<ProgramId>(json).unwrap(),
+            program_id
+        );
+    }
+
+    #[test]
+    fn test_program_id_is_deserialized_from_legacy_words() {
+        let program_id: ProgramId = serde_json::from_str("[1, 2, 3, 4, 5, 6, 7, 8]").unwrap();
+
+        assert_eq!(program_id, ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn test_address_is_not_deserialized_into_program_id() {
+        let address = "\"11111111111111111111111111111111\"";
+        let hex_without_prefix = format!("\"{}\"", "00".repeat(32));
+
+        assert!(serde_json::from_str::<ProgramId>(address).is_err());
+        assert!(serde_json::from_str::<ProgramId>(&hex_without_prefix).is_err());
+        assert!(matches!(
+            "11111111111111111111111111111111".parse::<ProgramId>(),
+            Err(ProgramIdError::MissingPrefix)
+        ));
+    }
+
+    #[test]
+    fn test_program_id_is_written_as_words_in_zkvm() {
+        let program_id = ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let words = risc0_zkvm::serde::to_vec(&program_id).unwrap();
+
+        assert_eq!(words, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(
+            risc0_zkvm::serde::from_slice::<ProgramId, _>(&words).unwrap(),
+            program_id
+        );
+    }
+
+    mod storage_deposit {
+        use super::*;
+
+        const RATE: Amount = Amount(10);
+
+        fn account(balance: u128, data_len: usize) -> Account {
+            Account {
+                program_owner: ProgramId::new([1; 8]),
+                balance: Amount(balance),
+                data: vec![0; data_len].try_into().unwrap(),
+                nonce: 0,
+            }
+        }
+
+        #[test]
+        fn test_growth_without_sufficient_deposit_is_rejected() {
+            let pre = account(100, 5);
+
+            assert!(is_storage_deposit_kept(&pre, &account(100, 10), RATE));
+            assert!(!is_storage_deposit_kept(&pre, &account(100, 11), RATE));
+            // Account created with data needs the deposit as well
+            assert!(!is_storage_deposit_kept(
+                &Account::default(),
+                &account(0, 1),
+                RATE
+            ));
+        }
+
+        #[test]
+        fn test_deposit_is_not_spendable() {
+            let pre = account(100, 5);
+
+            assert!(is_storage_deposit_kept(&pre, &account(50, 5), RATE));
+            assert!(!is_storage_deposit_kept(&pre, &account(49, 5), RATE));
+        }
+
+        #[test]
+        fn test_shrink_refunds_deposit_of_removed_bytes() {
+            let pre = account(100, 10);
+
+            assert!(!is_storage_deposit_kept(&pre, &account(59, 10), RATE));
+            assert!(is_storage_deposit_kept(&pre, &account(60, 4), RATE));
+            assert!(!is_storage_deposit_kept(&pre, &account(39, 4), RATE));
+        }
+
+        #[test]
+        fn test_close_refunds_whole_deposit() {
+            let pre = account(100, 10);
+
+            assert!(is_storage_deposit_kept(&pre, &account(0, 0), RATE));
+        }
+
+        #[test]
+        fn test_accounts_stored_before_rate_raise_are_not_stuck() {
+            let pre = account(10, 10);
+
+            // Receiving balance and shrinking data are allowed, spending and growing are not
+            assert!(is_storage_deposit_kept(&pre, &account(20, 10), RATE));
+            assert!(is_storage_deposit_kept(&pre, &account(10, 5), RATE));
+            assert!(!is_storage_deposit_kept(&pre, &account(9, 10), RATE));
+            assert!(!is_storage_deposit_kept(&pre, &account(20, 11), RATE));
+        }
+    }
+
+    mod constraints {
+        use proptest::{prelude::*, sample::Index};
+
+        use super::*;
+        use crate::account::{AccountId, data::DATA_MAX_LENGTH_IN_BYTES};
+
+        const EXECUTING_PROGRAM_ID: ProgramId = ProgramId::new([1; 8]);
+        const OTHER_PROGRAM_ID: ProgramId = ProgramId::new([2; 8]);
+
+        type Execution = (Vec<AccountWithMetadata>, Vec<AccountPostState>);
+
+        fn data(bytes: Vec<u8>) -> crate::account::Data {
+            bytes.try_into().unwrap()
+        }
+
+        /// Default account or an account owned by a program. Initialized accounts without owner
+        /// are rejected no matter the post state and are covered separately.
+        fn arb_account() -> impl Strategy<Value = Account> {
+            prop_oneof![
+                Just(Account::default()),
+                (
+                    prop_oneof![Just(EXECUTING_PROGRAM_ID), Just(OTHER_PROGRAM_ID)],
+                    any::<u64>(),
+                    prop::collection::vec(any::<u8>(), 0..8),
+                    0..4_u64,
+                )
+                    .prop_map(|(program_owner, balance, bytes, nonce)| Account {
+                        program_owner,
+                        balance: balance.into(),
+                        data: data(bytes),
+                        nonce,
+                    }),
+            ]
+        }
+
+        fn arb_pre_states() -> impl Strategy<Value = Vec<AccountWithMetadata>> {
+            prop::collection::vec((arb_account(), any::<bool>()), 1..6).prop_map(|accounts| {
+                accounts
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (account, is_authorized))| AccountWithMetadata {
+                        account,
+                        is_authorized,
+                        account_id: AccountId::new([i as u8; 32]),
+                    })
+                    .collect()
+            })
+        }
+
+        /// Execution of the program with [`EXECUTING_PROGRAM_ID`], which moves balance out of
+        /// its accounts to arbitrary ones, rewrites data of its accounts and claims default
+        /// accounts
+        fn arb_compliant_execution() -> impl Strategy<Value = Execution> {
+            arb_pre_states()
+                .prop_flat_map(|pre_states| {
+                    let n = pre_states.len();
+                    let choices = prop::collection::vec(
+                        (
+                            any::<u128>(),
+                            0..n,
+                            prop::collection::vec(any::<u8>(), 0..8),
+                            any::<bool>(),
+                        ),
+                        n,
+                    );
+                    (Just(pre_states), choices)
+                })
+                .prop_map(|(pre_states, choices)| {
+                    let mut posts: Vec<Account> =
+                        pre_states.iter().map(|pre| pre.account.clone()).collect();
+                    let mut claims = vec![false; pre_states.len()];
+
+                    for (i, (take_seed, recipient, bytes, rewrite)) in
+                        choices.into_iter().enumerate()
+                    {
+                        let pre = &pre_states[i].account;
+                        if pre.program_owner == EXECUTING_PROGRAM_ID {
+                            let take = Amount(take_seed % (pre.balance.0 + 1));
+                            posts[i].balance = posts[i].balance.checked_sub(take).unwrap();
+                            posts[recipient].balance =
+                                posts[recipient].balance.checked_add(take).unwrap();
+                            if rewrite {
+                                posts[i].data = data(bytes);
+                            }
+                        } else if *pre == Account::default() && rewrite {
+                            posts[i].data = data(bytes);
+                            claims[i] = true;
+                        }
+                    }
+
+                    let post_states = posts
+                        .into_iter()
+                        .zip(claims)
+                        .map(|(account, claim)| {
+                            if claim {
+                                AccountPostState::new_claimed(account)
+                            } else {
+                                AccountPostState::new(account)
+                            }
+                        })
+                        .collect();
+                    (pre_states, post_states)
+                })
+        }
+
+        fn validate((pre_states, post_states): &Execution) -> Result<(), ExecutionConstraintError> {
+            validate_execution(pre_states, post_states, EXECUTING_PROGRAM_ID)
+        }
+
+        proptest! {
+            #[test]
+            fn test_compliant_execution_is_accepted(execution in arb_compliant_execution()) {
+                prop_assert_eq!(validate(&execution), Ok(()));
+            }
+
+            #[test]
+            fn test_balance_inflation_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+                extra in 1..=u128::from(u64::MAX),
+            ) {
+                let i = index.index(execution.1.len());
+                let account = execution.1[i].account_mut();
+                account.balance = account.balance.checked_add(Amount(extra)).unwrap();
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::BalanceNotPreserved)
+                );
+            }
+
+            #[test]
+            fn test_added_post_state_is_rejected(
+                mut execution in arb_compliant_execution(),
+                account in arb_account(),
+            ) {
+                execution.1.push(AccountPostState::new(account));
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::PostStatesCountMismatch {
+                        pre_states: execution.0.len(),
+                        post_states: execution.0.len() + 1,
+                    })
+                );
+            }
+
+            #[test]
+            fn test_removed_post_state_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+            ) {
+                let i = index.index(execution.1.len());
+                execution.1.remove(i);
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::PostStatesCountMismatch {
+                        pre_states: execution.0.len(),
+                        post_states: execution.0.len() - 1,
+                    })
+                );
+            }
+
+            #[test]
+            fn test_duplicate_account_is_rejected(
+                mut execution in arb_compliant_execution(),
+                duplicate: Index,
+                original: Index,
+            ) {
+                prop_assume!(execution.0.len() >= 2);
+                let i = duplicate.index(execution.0.len() - 1) + 1;
+                let j = original.index(i);
+                execution.0[i].account_id = execution.0[j].account_id;
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::DuplicateAccount { index: i })
+                );
+            }
+
+            #[test]
+            fn test_nonce_tampering_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+                delta in 1..=u64::MAX,
+            ) {
+                let i = index.index(execution.1.len());
+                let post = execution.1[i].account_mut();
+                post.nonce = post.nonce.wrapping_add(delta);
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::NonceChanged { index: i })
+                );
+            }
+
+            #[test]
+            fn test_program_owner_reassignment_of_not_owned_account_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+                program_owner in prop_oneof![
+                    Just(DEFAULT_PROGRAM_ID),
+                    Just(EXECUTING_PROGRAM_ID),
+                    Just(OTHER_PROGRAM_ID),
+                    any::<[u32; 8]>().prop_map(ProgramId::new),
+                ],
+            ) {
+                let i = index.index(execution.1.len());
+                let pre_owner = execution.0[i].account.program_owner;
+                prop_assume!(pre_owner != EXECUTING_PROGRAM_ID && pre_owner != program_owner);
+                execution.1[i].account_mut().program_owner = program_owner;
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::ProgramOwnerChanged { index: i })
+                );
+            }
+
+            #[test]
+            fn test_assignment_of_empty_authorized_account_is_accepted(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+            ) {
+                let i = index.index(execution.1.len());
+                let pre = &mut execution.0[i];
+                prop_assume!(pre.account.program_owner == EXECUTING_PROGRAM_ID);
+                pre.is_authorized = true;
+                pre.account.data = data(vec![]);
+                let post = execution.1[i].account_mut();
+                post.data = data(vec![]);
+                post.program_owner = OTHER_PROGRAM_ID;
+
+                prop_assert_eq!(validate(&execution), Ok(()));
+            }
+
+            #[test]
+            fn test_unauthorized_assignment_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+            ) {
+                let i = index.index(execution.1.len());
+                let pre = &mut execution.0[i];
+                prop_assume!(pre.account.program_owner == EXECUTING_PROGRAM_ID);
+                pre.is_authorized = false;
+                execution.1[i].account_mut().program_owner = OTHER_PROGRAM_ID;
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::UnauthorizedAssignment { index: i })
+                );
+            }
+
+            #[test]
+            fn test_assignment_of_account_with_data_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+            ) {
+                let i = index.index(execution.1.len());
+                let pre = &mut execution.0[i];
+                prop_assume!(pre.account.program_owner == EXECUTING_PROGRAM_ID);
+                prop_assume!(
+                    !pre.account.data.is_empty() || !execution.1[i].account().data.is_empty()
+                );
+                pre.is_authorized = true;
+                execution.1[i].account_mut().program_owner = OTHER_PROGRAM_ID;
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::AssignmentOfAccountWithData { index: i })
+                );
+            }
+
+            #[test]
+            fn test_claim_of_owned_account_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+            ) {
+                let i = index.index(execution.1.len());
+                prop_assume!(execution.0[i].account.program_owner != DEFAULT_PROGRAM_ID);
+                execution.1[i] = AccountPostState::new_claimed(execution.1[i].account().clone());
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::ClaimOfOwnedAccount { index: i })
+                );
+            }
+
+            #[test]
+            fn test_balance_decrease_of_not_owned_account_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+            ) {
+                // Authorization doesn't matter, pre states are authorized at random
+                let i = index.index(execution.1.len());
+                let pre = &execution.0[i].account;
+                prop_assume!(pre.program_owner != EXECUTING_PROGRAM_ID && !pre.balance.is_zero());
+                execution.1[i].account_mut().balance = pre.balance.checked_sub(Amount(1)).unwrap();
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::UnauthorizedBalanceDecrease { index: i })
+                );
+            }
+
+            #[test]
+            fn test_data_change_of_not_owned_account_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+                byte: u8,
+            ) {
+                let i = index.index(execution.1.len());
+                let pre = &execution.0[i].account;
+                prop_assume!(
+                    pre.program_owner != EXECUTING_PROGRAM_ID && *pre != Account::default()
+                );
+                let mut bytes = pre.data.to_vec();
+                bytes.push(byte);
+                execution.1[i].account_mut().data = data(bytes);
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::UnauthorizedDataChange { index: i })
+                );
+            }
+
+            #[test]
+            fn test_data_change_of_default_account_without_claim_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+                bytes in prop::collection::vec(any::<u8>(), 1..8),
+            ) {
+                let i = index.index(execution.1.len());
+                prop_assume!(execution.0[i].account == Account::default());
+                let mut post = execution.1[i].account().clone();
+                post.data = data(bytes);
+                execution.1[i] = AccountPostState::new(post);
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::UnclaimedDataChange { index: i })
+                );
+            }
+
+            #[test]
+            fn test_initialized_account_without_owner_is_rejected(
+                mut execution in arb_compliant_execution(),
+                index: Index,
+                balance in 1..=u128::from(u64::MAX),
+                claim: bool,
+            ) {
+                let i = index.index(execution.1.len());
+                let account = Account {
+                    balance: Amount(balance),
+                    ..Account::default()
+                };
+                execution.0[i].account = account.clone();
+                execution.1[i] = if claim {
+                    AccountPostState::new_claimed(account)
+                } else {
+                    AccountPostState::new(account)
+                };
+
+                prop_assert_eq!(
+                    validate(&execution),
+                    Err(ExecutionConstraintError::DefaultProgramOwner { index: i })
+                );
+            }
+
+            #[test]
+            fn test_data_beyond_limit_is_unrepresentable(extra in 1..1024_usize) {
+                let bytes = vec![0; DATA_MAX_LENGTH_IN_BYTES + extra];
+
+                prop_assert!(crate::account::Data::try_from(bytes).is_err());
+            }
+        }
+    }
 }
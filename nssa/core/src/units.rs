@@ -0,0 +1,234 @@
+//! Units of value and execution, kept apart by type, so amounts, gas and cycles can't be passed
+//! in place of each other.
+//!
+//! Arithmetic is explicit: only checked and saturating operations are provided. All types are
+//! encoded as the underlying number by both serde and borsh.
+
+use std::{fmt::Display, num::ParseIntError, str::FromStr};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// Amount of native tokens, e.g. a balance, a transferred value or a fee
+#[derive(
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+#[serde(transparent)]
+pub struct Amount(pub u128);
+
+/// Gas, which is paid in native tokens one to one, see [`Amount::from`]
+#[derive(
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+#[serde(transparent)]
+pub struct Gas(pub u64);
+
+/// Number of zkVM cycles
+#[derive(
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+#[serde(transparent)]
+pub struct Cycles(pub u64);
+
+macro_rules! impl_unit {
+    ($unit:ident, $inner:ty) => {
+        impl $unit {
+            pub const ZERO: Self = Self(0);
+            pub const MAX: Self = Self(<$inner>::MAX);
+
+            pub const fn get(self) -> $inner {
+                self.0
+            }
+
+            pub const fn is_zero(self) -> bool {
+                self.0 == 0
+            }
+
+            pub fn checked_add(self, rhs: Self) -> Option<Self> {
+                self.0.checked_add(rhs.0).map(Self)
+            }
+
+            pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+                self.0.checked_sub(rhs.0).map(Self)
+            }
+
+            pub fn saturating_add(self, rhs: Self) -> Self {
+                Self(self.0.saturating_add(rhs.0))
+            }
+
+            pub fn saturating_sub(self, rhs: Self) -> Self {
+                Self(self.0.saturating_sub(rhs.0))
+            }
+
+            /// Sum of `values`, `None` if it overflows
+            pub fn checked_sum(values: impl IntoIterator<Item = Self>) -> Option<Self> {
+                values
+                    .into_iter()
+                    .try_fold(Self::ZERO, |sum, value| sum.checked_add(value))
+            }
+        }
+
+        impl Display for $unit {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl FromStr for $unit {
+            type Err = ParseIntError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse().map(Self)
+            }
+        }
+    };
+}
+
+impl_unit!(Amount, u128);
+impl_unit!(Gas, u64);
+impl_unit!(Cycles, u64);
+
+impl Amount {
+    /// `self` times `factor`, e.g. of a rate per unit, `None` if it overflows
+    pub fn checked_mul(self, factor: u128) -> Option<Self> {
+        self.0.checked_mul(factor).map(Self)
+    }
+
+    pub fn saturating_mul(self, factor: u128) -> Self {
+        Self(self.0.saturating_mul(factor))
+    }
+}
+
+impl From<u64> for Amount {
+    fn from(value: u64) -> Self {
+        Self(u128::from(value))
+    }
+}
+
+impl From<Gas> for Amount {
+    fn from(gas: Gas) -> Self {
+        Self::from(gas.0)
+    }
+}
+
+impl From<u64> for Gas {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<u64> for Cycles {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_arithmetic_detects_overflow() {
+        assert_eq!(Amount(2).checked_add(Amount(3)), Some(Amount(5)));
+        assert_eq!(Amount::MAX.checked_add(Amount(1)), None);
+        assert_eq!(Amount(3).checked_sub(Amount(2)), Some(Amount(1)));
+        assert_eq!(Amount(2).checked_sub(Amount(3)), None);
+        assert_eq!(Amount(3).checked_mul(4), Some(Amount(12)));
+        assert_eq!(Amount::MAX.checked_mul(2), None);
+        assert_eq!(Gas(u64::MAX).checked_add(Gas(1)), None);
+        assert_eq!(Cycles(0).checked_sub(Cycles(1)), None);
+    }
+
+    #[test]
+    fn test_saturating_arithmetic_clamps() {
+        assert_eq!(Amount::MAX.saturating_add(Amount(1)), Amount::MAX);
+        assert_eq!(Amount(2).saturating_sub(Amount(3)), Amount::ZERO);
+        assert_eq!(Amount::MAX.saturating_mul(2), Amount::MAX);
+        assert_eq!(Cycles(u64::MAX).saturating_add(Cycles(1)), Cycles::MAX);
+    }
+
+    #[test]
+    fn test_checked_sum() {
+        assert_eq!(
+            Amount::checked_sum([Amount(1), Amount(2), Amount(3)]),
+            Some(Amount(6))
+        );
+        assert_eq!(Amount::checked_sum([]), Some(Amount::ZERO));
+        assert_eq!(Amount::checked_sum([Amount::MAX, Amount(1)]), None);
+    }
+
+    #[test]
+    fn test_gas_is_paid_one_to_one() {
+        assert_eq!(Amount::from(Gas(u64::MAX)), Amount(u128::from(u64::MAX)));
+    }
+
+    #[test]
+    fn test_encoded_as_underlying_number() {
+        assert_eq!(serde_json::to_string(&Amount(100)).unwrap(), "100");
+        assert_eq!(
+            serde_json::from_str::<Amount>("12345678901234567890").unwrap(),
+            Amount(12_345_678_901_234_567_890)
+        );
+        assert_eq!(serde_json::to_string(&Gas(7)).unwrap(), "7");
+        assert_eq!(serde_json::to_string(&Cycles(7)).unwrap(), "7");
+        assert_eq!(
+            borsh::to_vec(&Amount(u128::MAX - 1)).unwrap(),
+            borsh::to_vec(&(u128::MAX - 1)).unwrap()
+        );
+        assert_eq!(
+            borsh::to_vec(&Cycles(42)).unwrap(),
+            borsh::to_vec(&42_u64).unwrap()
+        );
+        assert_eq!(
+            risc0_zkvm::serde::to_vec(&Amount(37)).unwrap(),
+            risc0_zkvm::serde::to_vec(&37_u128).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        let amount: Amount = "340282366920938463463374607431768211455".parse().unwrap();
+        assert_eq!(amount, Amount::MAX);
+        assert_eq!(
+            amount.to_string(),
+            "340282366920938463463374607431768211455"
+        );
+        assert!("-1".parse::<Amount>().is_err());
+    }
+}
@@ -1,8 +1,10 @@
 use nssa_core::{
     account::{Account, AccountWithMetadata},
     program::{
-        AccountPostState, DEFAULT_PROGRAM_ID, ProgramInput, read_nssa_inputs, write_nssa_outputs,
+        AccountPostState, DEFAULT_PROGRAM_ID, ProgramId, ProgramInput, read_nssa_inputs_words,
+        write_nssa_outputs,
     },
+    units::Amount,
 };
 
 /// Initializes a default account under the ownership of this program.
@@ -24,30 +26,53 @@ fn initialize_account(pre_state: AccountWithMetadata) {
     write_nssa_outputs(vec![pre_state], vec![account_to_claim]);
 }
 
+/// Assigns an account of this program to `program_owner`, so the program can initialize it.
+fn assign_account(pre_state: AccountWithMetadata, program_owner: ProgramId) {
+    // Continue only if the owner authorized this operation
+    if !pre_state.is_authorized {
+        return;
+    }
+
+    // Continue only if the account has no data, which would be meaningless to the new owner
+    if !pre_state.account.data.is_empty() {
+        return;
+    }
+
+    let mut assigned_account = pre_state.account.clone();
+    assigned_account.program_owner = program_owner;
+    write_nssa_outputs(
+        vec![pre_state],
+        vec![AccountPostState::new(assigned_account)],
+    );
+}
+
 /// Transfers `balance_to_move` native balance from `sender` to `recipient`.
-fn transfer(sender: AccountWithMetadata, recipient: AccountWithMetadata, balance_to_move: u128) {
+fn transfer(sender: AccountWithMetadata, recipient: AccountWithMetadata, balance_to_move: Amount) {
     // Continue only if the sender has authorized this operation
     if !sender.is_authorized {
         return;
     }
 
     // Continue only if the sender has enough balance
-    if sender.account.balance < balance_to_move {
+    let Some(sender_balance) = sender.account.balance.checked_sub(balance_to_move) else {
         return;
-    }
+    };
 
     // Create accounts post states, with updated balances
     let sender_post = {
         // Modify sender's balance
         let mut sender_post_account = sender.account.clone();
-        sender_post_account.balance -= balance_to_move;
+        sender_post_account.balance = sender_balance;
         AccountPostState::new(sender_post_account)
     };
 
     let recipient_post = {
         // Modify recipient's balance
         let mut recipient_post_account = recipient.account.clone();
-        recipient_post_account.balance += balance_to_move;
+        recipient_post_account.balance = recipient_post_account
+            .balance
+            .checked_add(balance_to_move)
+            .expect("Recipient balance overflow.");
 
         // Claim recipient account if it has default program owner
         if recipient_post_account.program_owner == DEFAULT_PROGRAM_ID {
@@ -66,13 +91,24 @@ fn main() {
     // Read input accounts.
     let ProgramInput {
         pre_states,
-        instruction: balance_to_move,
-    } = read_nssa_inputs();
+        instruction,
+    } = read_nssa_inputs_words();
+
+    // Assignments carry the words of the new owner, other instructions a balance
+    if let ([account_to_assign], Ok(image_id)) = (
+        pre_states.as_slice(),
+        <[u32; 8]>::try_from(instruction.as_slice()),
+    ) {
+        assign_account(account_to_assign.clone(), ProgramId::new(image_id));
+        return;
+    }
+    let balance_to_move: u128 =
+        risc0_zkvm::serde::from_slice(&instruction).expect("invalid instruction");
 
     match (pre_states.as_slice(), balance_to_move) {
         ([account_to_claim], 0) => initialize_account(account_to_claim.clone()),
         ([sender, recipient], balance_to_move) => {
-            transfer(sender.clone(), recipient.clone(), balance_to_move)
+            transfer(sender.clone(), recipient.clone(), Amount(balance_to_move))
         }
         _ => panic!("invalid params"),
     }
@@ -0,0 +1,398 @@
+use nssa_core::{
+    account::{Account, AccountId, AccountWithMetadata, Data},
+    clock::read_block_id,
+    program::{
+        AccountPostState, ChainedCall, DEFAULT_PROGRAM_ID, ProgramId, ProgramInput,
+        read_nssa_inputs, write_nssa_outputs, write_nssa_outputs_with_chained_call,
+    },
+    units::Amount,
+};
+use risc0_zkvm::serde::to_vec;
+
+// The escrow program has three functions:
+// 1. Create escrow. Arguments to this function are:
+//      * Two accounts: [funder_account, escrow_account]. The funder must be authorized and the
+//        escrow account must be a **default** account, which is claimed by the escrow program.
+//      * An instruction data of 89 bytes with the following layout: [0x00 || amount (little-endian
+//        16 bytes) || timeout block id (little-endian 8 bytes) || recipient account id (32 bytes)
+//        || escrow program id (8 little-endian u32 words)]. A guest can't learn its own program id,
+//        which is needed to describe the escrow account in the chained call to the authenticated
+//        transfer program, which moves `amount` from the funder to the escrow.
+// 2. Claim. Arguments to this function are:
+//      * Three accounts: [escrow_account, recipient_account, clock_account]. The recipient must be
+//        authorized and initialized.
+//      * An instruction data of 1 byte: [0x01].
+//      Succeeds only strictly before the timeout block.
+// 3. Refund. Arguments to this function are:
+//      * Three accounts: [escrow_account, funder_account, clock_account]. The funder must be
+//        authorized.
+//      * An instruction data of 1 byte: [0x02].
+//      Succeeds only at or after the timeout block.
+// Claim and refund move the whole escrow balance and settle the escrow, so it can't be claimed or
+// refunded again.
+
+const ESCROW_DATA_SIZE: usize = 73;
+
+const ESCROW_LOCKED: u8 = 0;
+const ESCROW_SETTLED: u8 = 1;
+
+type Instruction = Vec<u8>;
+
+struct Escrow {
+    status: u8,
+    funder: AccountId,
+    recipient: AccountId,
+    timeout: u64,
+}
+
+impl Escrow {
+    fn into_data(self) -> Data {
+        let mut bytes = [0; ESCROW_DATA_SIZE];
+        bytes[0] = self.status;
+        bytes[1..33].copy_from_slice(self.funder.value());
+        bytes[33..65].copy_from_slice(self.recipient.value());
+        bytes[65..].copy_from_slice(&self.timeout.to_le_bytes());
+        bytes
+            .to_vec()
+            .try_into()
+            .expect("73 bytes should fit into Data")
+    }
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != ESCROW_DATA_SIZE {
+            return None;
+        }
+
+        Some(Self {
+            status: data[0],
+            funder: AccountId::new(data[1..33].try_into().unwrap()),
+            recipient: AccountId::new(data[33..65].try_into().unwrap()),
+            timeout: u64::from_le_bytes(data[65..].try_into().unwrap()),
+        })
+    }
+}
+
+fn create(
+    pre_states: &[AccountWithMetadata],
+    amount: Amount,
+    timeout: u64,
+    recipient: AccountId,
+    escrow_program_id: ProgramId,
+) -> (Vec<AccountPostState>, ChainedCall) {
+    let [funder, escrow] = pre_states else {
+        panic!("Invalid number of input accounts");
+    };
+
+    assert!(
+        funder.is_authorized,
+        "Funder must authorize escrow creation"
+    );
+    assert!(!amount.is_zero(), "Escrow amount must be positive");
+    assert_eq!(
+        escrow.account,
+        Account::default(),
+        "Escrow account must be uninitialized"
+    );
+
+    let escrow_data = Escrow {
+        status: ESCROW_LOCKED,
+        funder: funder.account_id,
+        recipient,
+        timeout,
+    }
+    .into_data();
+
+    let mut escrow_post = escrow.account.clone();
+    escrow_post.data = escrow_data;
+
+    // Escrow account as it will be after the claim, when the transfer is executed
+    let escrow_for_chained_call = AccountWithMetadata {
+        account: Account {
+            program_owner: escrow_program_id,
+            ..escrow_post.clone()
+        },
+        ..escrow.clone()
+    };
+    let transfer = ChainedCall {
+        program_id: funder.account.program_owner,
+        instruction_data: to_vec(&amount).unwrap(),
+        pre_states: vec![funder.clone(), escrow_for_chained_call],
+        pda_seeds: vec![],
+    };
+
+    (
+        vec![
+            AccountPostState::new(funder.account.clone()),
+            AccountPostState::new_claimed(escrow_post),
+        ],
+        transfer,
+    )
+}
+
+/// Moves the whole escrow balance to `payee` and settles the escrow
+fn settle(escrow: &AccountWithMetadata, payee: &AccountWithMetadata) -> (Account, Account) {
+    let mut escrow_post = escrow.account.clone();
+    let mut payee_post = payee.account.clone();
+
+    payee_post.balance = payee_post
+        .balance
+        .checked_add(escrow_post.balance)
+        .expect("Payee balance overflow.");
+    escrow_post.balance = Amount::ZERO;
+
+    let mut escrow_data = Escrow::parse(&escrow_post.data).expect("Invalid escrow account");
+    escrow_data.status = ESCROW_SETTLED;
+    escrow_post.data = escrow_data.into_data();
+
+    (escrow_post, payee_post)
+}
+
+fn claim(pre_states: &[AccountWithMetadata]) -> Vec<AccountPostState> {
+    let [escrow, recipient, clock] = pre_states else {
+        panic!("Invalid number of input accounts");
+    };
+
+    let escrow_data = Escrow::parse(&escrow.account.data).expect("Invalid escrow account");
+    let block_id = read_block_id(clock).expect("Invalid clock account");
+
+    assert_eq!(
+        escrow_data.status, ESCROW_LOCKED,
+        "Escrow is already settled"
+    );
+    assert_eq!(
+        recipient.account_id, escrow_data.recipient,
+        "Only the designated recipient can claim"
+    );
+    assert!(recipient.is_authorized, "Recipient must authorize claim");
+    assert_ne!(
+        recipient.account.program_owner, DEFAULT_PROGRAM_ID,
+        "Recipient account must be initialized"
+    );
+    assert!(block_id < escrow_data.timeout, "Escrow has timed out");
+
+    let (escrow_post, recipient_post) = settle(escrow, recipient);
+    vec![
+        AccountPostState::new(escrow_post),
+        AccountPostState::new(recipient_post),
+        AccountPostState::new(clock.account.clone()),
+    ]
+}
+
+fn refund(pre_states: &[AccountWithMetadata]) -> Vec<AccountPostState> {
+    let [escrow, funder, clock] = pre_states else {
+        panic!("Invalid number of input accounts");
+    };
+
+    let escrow_data = Escrow::parse(&escrow.account.data).expect("Invalid escrow account");
+    let block_id = read_block_id(clock).expect("Invalid clock account");
+
+    assert_eq!(
+        escrow_data.status, ESCROW_LOCKED,
+        "Escrow is already settled"
+    );
+    assert_eq!(
+        funder.account_id, escrow_data.funder,
+        "Only the funder can refund"
+    );
+    assert!(funder.is_authorized, "Funder must authorize refund");
+    assert!(
+        block_id >= escrow_data.timeout,
+        "Escrow has not timed out yet"
+    );
+
+    let (escrow_post, funder_post) = settle(escrow, funder);
+    vec![
+        AccountPostState::new(escrow_post),
+        AccountPostState::new(funder_post),
+        AccountPostState::new(clock.account.clone()),
+    ]
+}
+
+fn main() {
+    let ProgramInput {
+        pre_states,
+        instruction,
+    } = read_nssa_inputs::<Instruction>();
+
+    match instruction.first() {
+        Some(0) => {
+            // Parse instruction
+            assert_eq!(instruction.len(), 89, "Invalid instruction length");
+            let amount = Amount(u128::from_le_bytes(instruction[1..17].try_into().unwrap()));
+            let timeout = u64::from_le_bytes(instruction[17..25].try_into().unwrap());
+            let recipient = AccountId::new(instruction[25..57].try_into().unwrap());
+            let escrow_program_id = ProgramId::from_bytes(instruction[57..].try_into().unwrap());
+
+            // Execute
+            let (post_states, transfer) =
+                create(&pre_states, amount, timeout, recipient, escrow_program_id);
+            write_nssa_outputs_with_chained_call(pre_states, post_states, vec![transfer]);
+        }
+        Some(1) => {
+            assert_eq!(instruction.len(), 1, "Invalid instruction length");
+            let post_states = claim(&pre_states);
+            write_nssa_outputs(pre_states, post_states);
+        }
+        Some(2) => {
+            assert_eq!(instruction.len(), 1, "Invalid instruction length");
+            let post_states = refund(&pre_states);
+            write_nssa_outputs(pre_states, post_states);
+        }
+        _ => panic!("Invalid instruction"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nssa_core::{
+        account::{Account, AccountId, AccountWithMetadata},
+        clock::{CLOCK_ACCOUNT_ID, clock_account},
+        program::ProgramId,
+        units::Amount,
+    };
+
+    use crate::{ESCROW_LOCKED, ESCROW_SETTLED, Escrow, claim, create, refund};
+
+    const ESCROW_PROGRAM_ID: ProgramId = ProgramId::new([7; 8]);
+    const TRANSFER_PROGRAM_ID: ProgramId = ProgramId::new([8; 8]);
+    const TIMEOUT: u64 = 10;
+
+    fn account(id: u8, balance: u128, is_authorized: bool) -> AccountWithMetadata {
+        AccountWithMetadata {
+            account: Account {
+                program_owner: TRANSFER_PROGRAM_ID,
+                balance: Amount(balance),
+                ..Account::default()
+            },
+            is_authorized,
+            account_id: AccountId::new([id; 32]),
+        }
+    }
+
+    fn escrow(status: u8) -> AccountWithMetadata {
+        AccountWithMetadata {
+            account: Account {
+                program_owner: ESCROW_PROGRAM_ID,
+                balance: Amount(100),
+                data: Escrow {
+                    status,
+                    funder: AccountId::new([1; 32]),
+                    recipient: AccountId::new([2; 32]),
+                    timeout: TIMEOUT,
+                }
+                .into_data(),
+                ..Account::default()
+            },
+            is_authorized: false,
+            account_id: AccountId::new([3; 32]),
+        }
+    }
+
+    fn clock(block_id: u64) -> AccountWithMetadata {
+        AccountWithMetadata {
+            account: clock_account(block_id),
+            is_authorized: false,
+            account_id: CLOCK_ACCOUNT_ID,
+        }
+    }
+
+    #[test]
+    fn test_create_locks_escrow_and_transfers_amount() {
+        let funder = account(1, 500, true);
+        let new_escrow = AccountWithMetadata {
+            account: Account::default(),
+            is_authorized: false,
+            account_id: AccountId::new([3; 32]),
+        };
+
+        let (post_states, transfer) = create(
+            &[funder.clone(), new_escrow],
+            Amount(100),
+            TIMEOUT,
+            AccountId::new([2; 32]),
+            ESCROW_PROGRAM_ID,
+        );
+
+        assert!(post_states[1].requires_claim());
+        let escrow_data = Escrow::parse(&post_states[1].account().data).unwrap();
+        assert_eq!(escrow_data.status, ESCROW_LOCKED);
+        assert_eq!(escrow_data.funder, funder.account_id);
+        assert_eq!(transfer.program_id, TRANSFER_PROGRAM_ID);
+        assert_eq!(
+            transfer.pre_states[1].account.program_owner,
+            ESCROW_PROGRAM_ID
+        );
+    }
+
+    #[should_panic(expected = "Funder must authorize escrow creation")]
+    #[test]
+    fn test_create_requires_funder_authorization() {
+        create(
+            &[account(1, 500, false), account(3, 0, false)],
+            Amount(100),
+            TIMEOUT,
+            AccountId::new([2; 32]),
+            ESCROW_PROGRAM_ID,
+        );
+    }
+
+    #[test]
+    fn test_claim_before_timeout() {
+        let post_states = claim(&[
+            escrow(ESCROW_LOCKED),
+            account(2, 5, true),
+            clock(TIMEOUT - 1),
+        ]);
+
+        assert_eq!(post_states[0].account().balance, Amount(0));
+        assert_eq!(post_states[1].account().balance, Amount(105));
+        let escrow_data = Escrow::parse(&post_states[0].account().data).unwrap();
+        assert_eq!(escrow_data.status, ESCROW_SETTLED);
+    }
+
+    #[should_panic(expected = "Escrow has timed out")]
+    #[test]
+    fn test_claim_exactly_at_timeout_fails() {
+        claim(&[escrow(ESCROW_LOCKED), account(2, 5, true), clock(TIMEOUT)]);
+    }
+
+    #[should_panic(expected = "Escrow is already settled")]
+    #[test]
+    fn test_double_claim_fails() {
+        claim(&[escrow(ESCROW_SETTLED), account(2, 105, true), clock(1)]);
+    }
+
+    #[should_panic(expected = "Only the designated recipient can claim")]
+    #[test]
+    fn test_claim_by_other_account_fails() {
+        claim(&[escrow(ESCROW_LOCKED), account(4, 5, true), clock(1)]);
+    }
+
+    #[should_panic(expected = "Invalid clock account")]
+    #[test]
+    fn test_claim_with_fake_clock_fails() {
+        let fake_clock = AccountWithMetadata {
+            account_id: AccountId::new([5; 32]),
+            ..clock(1)
+        };
+        claim(&[escrow(ESCROW_LOCKED), account(2, 5, true), fake_clock]);
+    }
+
+    #[should_panic(expected = "Escrow has not timed out yet")]
+    #[test]
+    fn test_refund_before_timeout_fails() {
+        refund(&[
+            escrow(ESCROW_LOCKED),
+            account(1, 5, true),
+            clock(TIMEOUT - 1),
+        ]);
+    }
+
+    #[test]
+    fn test_refund_at_timeout() {
+        let post_states = refund(&[escrow(ESCROW_LOCKED), account(1, 5, true), clock(TIMEOUT)]);
+
+        assert_eq!(post_states[0].account().balance, Amount(0));
+        assert_eq!(post_states[1].account().balance, Amount(105));
+    }
+}
@@ -47,8 +47,8 @@ fn transfer(sender: AccountWithMetadata, recipient: AccountWithMetadata, balance
     let mut sender_post = sender.account.clone();
     let mut recipient_post = recipient.account.clone();
 
-    sender_post.balance -= balance_to_move + malicious_offset;
-    recipient_post.balance += balance_to_move + malicious_offset;
+    sender_post.balance.0 -= balance_to_move + malicious_offset;
+    recipient_post.balance.0 += balance_to_move + malicious_offset;
 
     write_nssa_outputs(
         vec![sender, recipient],
@@ -1,7 +1,10 @@
-use nssa_core::program::{AccountPostState, ProgramInput, read_nssa_inputs, write_nssa_outputs};
+use nssa_core::{
+    program::{AccountPostState, ProgramInput, read_nssa_inputs, write_nssa_outputs},
+    units::Amount,
+};
 use risc0_zkvm::sha::{Impl, Sha256};
 
-const PRIZE: u128 = 150;
+const PRIZE: Amount = Amount(150);
 
 type Instruction = u128;
 
@@ -62,13 +65,19 @@ fn main() {
 
     let mut pinata_post = pinata.account.clone();
     let mut winner_post = winner.account.clone();
-    pinata_post.balance -= PRIZE;
+    pinata_post.balance = pinata_post
+        .balance
+        .checked_sub(PRIZE)
+        .expect("Pinata balance underflow.");
     pinata_post.data = data
         .next_data()
         .to_vec()
         .try_into()
         .expect("33 bytes should fit into Data");
-    winner_post.balance += PRIZE;
+    winner_post.balance = winner_post
+        .balance
+        .checked_add(PRIZE)
+        .expect("Winner balance overflow.");
 
     write_nssa_outputs(
         vec![pinata, winner],
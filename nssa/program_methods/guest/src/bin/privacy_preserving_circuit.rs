@@ -41,8 +41,8 @@ fn main() {
 
     // Check that the program is well behaved.
     // See the # Programs section for the definition of the `validate_execution` method.
-    if !validate_execution(&pre_states, &post_states, program_id) {
-        panic!("Bad behaved program");
+    if let Err(err) = validate_execution(&pre_states, &post_states, program_id) {
+        panic!("Bad behaved program: {err}");
     }
 
     let n_accounts = pre_states.len();
@@ -71,7 +71,8 @@ fn main() {
 
                 let mut post = post_states[i].account().clone();
                 if pre_states[i].is_authorized {
-                    post.nonce += 1;
+                    let incremented = post.increment_nonce();
+                    assert!(incremented, "Nonce of account {i} is exhausted");
                 }
                 if post.program_owner == DEFAULT_PROGRAM_ID {
                     // Claim account
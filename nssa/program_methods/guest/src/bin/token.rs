@@ -300,7 +300,10 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use nssa_core::account::{Account, AccountId, AccountWithMetadata};
+    use nssa_core::{
+        account::{Account, AccountId, AccountWithMetadata},
+        program::ProgramId,
+    };
 
     use crate::{
         TOKEN_DEFINITION_DATA_SIZE, TOKEN_HOLDING_DATA_SIZE, TOKEN_HOLDING_TYPE,
@@ -347,7 +350,7 @@ mod tests {
         let pre_states = vec![
             AccountWithMetadata {
                 account: Account {
-                    program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
+                    program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
                     ..Account::default()
                 },
                 is_authorized: true,
@@ -373,7 +376,7 @@ mod tests {
             },
             AccountWithMetadata {
                 account: Account {
-                    program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
+                    program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
                     ..Account::default()
                 },
                 is_authorized: true,
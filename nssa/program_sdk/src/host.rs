@@ -0,0 +1,170 @@
+use anyhow::{Context as _, Result};
+use nssa_core::program::validate_execution;
+use risc0_zkvm::{ExecutorEnv, default_executor, serde::to_vec};
+use serde::Serialize;
+
+use crate::{AccountWithMetadata, ProgramId, ProgramOutput};
+
+/// Maximum number of cycles of a simulated execution, same as for public execution on chain
+const MAX_NUM_CYCLES: u64 = 1024 * 1024 * 32;
+
+/// Execute program `elf` without proving, as the chain does for public transactions
+///
+/// Fails if the program panics or if its output wouldn't be accepted by the chain.
+pub fn simulate_program<T: Serialize>(
+    elf: &[u8],
+    pre_states: &[AccountWithMetadata],
+    instruction: &T,
+) -> Result<ProgramOutput> {
+    simulate_program_with_readonly_accounts(elf, pre_states, &[], instruction)
+}
+
+/// Same as [`simulate_program`], but also passes `readonly_pre_states` to the program after
+/// `pre_states`, as the chain does for read-only accounts of a transaction
+pub fn simulate_program_with_readonly_accounts<T: Serialize>(
+    elf: &[u8],
+    pre_states: &[AccountWithMetadata],
+    readonly_pre_states: &[AccountWithMetadata],
+    instruction: &T,
+) -> Result<ProgramOutput> {
+    let instruction_data = to_vec(instruction).context("Failed to serialize instruction")?;
+    let all_pre_states = [pre_states, readonly_pre_states].concat();
+
+    let mut env_builder = ExecutorEnv::builder();
+    env_builder.session_limit(Some(MAX_NUM_CYCLES));
+    env_builder
+        .write(&(all_pre_states.clone(), instruction_data))
+        .context("Failed to write program inputs")?;
+    let env = env_builder.build()?;
+
+    let session_info = default_executor()
+        .execute(env, elf)
+        .context("Program execution failed")?;
+    let output: ProgramOutput = session_info
+        .journal
+        .decode()
+        .context("Failed to decode program output")?;
+
+    anyhow::ensure!(
+        output.pre_states == all_pre_states,
+        "Program committed pre-states different from the given ones"
+    );
+    anyhow::ensure!(
+        output.post_states.len() == pre_states.len(),
+        "Program must commit post-states of writable accounts only"
+    );
+    validate_execution(
+        &output.pre_states[..pre_states.len()],
+        &output.post_states,
+        program_id(elf)?,
+    )
+    .context("Program output violates execution rules")?;
+
+    Ok(output)
+}
+
+fn program_id(elf: &[u8]) -> Result<ProgramId> {
+    let binary = risc0_binfmt::ProgramBinary::decode(elf).context("Invalid program bytecode")?;
+    Ok(ProgramId::new(binary.compute_image_id()?.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_program_methods::COUNTER_ELF;
+
+    use super::*;
+    use crate::{Account, AccountId, Amount};
+
+    fn counter_account(count: u64) -> AccountWithMetadata {
+        let account = Account {
+            program_owner: program_id(COUNTER_ELF).unwrap(),
+            data: count.to_le_bytes().to_vec().try_into().unwrap(),
+            ..Account::default()
+        };
+        AccountWithMetadata::new(account, false, AccountId::new([1; 32]))
+    }
+
+    #[test]
+    fn test_simulate_counter() {
+        let output = simulate_program(COUNTER_ELF, &[counter_account(5)], &3_u64).unwrap();
+
+        assert_eq!(
+            output.post_states[0].account().data.as_ref(),
+            8_u64.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn test_simulate_counter_claims_new_account() {
+        let new_account =
+            AccountWithMetadata::new(Account::default(), false, AccountId::new([2; 32]));
+
+        let output = simulate_program(COUNTER_ELF, &[new_account], &1_u64).unwrap();
+
+        assert!(output.post_states[0].requires_claim());
+        assert_eq!(
+            output.post_states[0].account().data.as_ref(),
+            1_u64.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn test_simulate_counter_on_assigned_account() {
+        // Assignment keeps the balance and leaves the account without data
+        let assigned_account = Account {
+            program_owner: program_id(COUNTER_ELF).unwrap(),
+            balance: Amount(100),
+            ..Account::default()
+        };
+        let assigned = AccountWithMetadata::new(assigned_account, true, AccountId::new([3; 32]));
+
+        let output = simulate_program(COUNTER_ELF, &[assigned], &2_u64).unwrap();
+
+        assert!(!output.post_states[0].requires_claim());
+        assert_eq!(output.post_states[0].account().balance, Amount(100));
+        assert_eq!(
+            output.post_states[0].account().data.as_ref(),
+            2_u64.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn test_simulate_rejects_panicking_program() {
+        // Counter expects exactly one account
+        assert!(simulate_program(COUNTER_ELF, &[], &1_u64).is_err());
+    }
+
+    #[test]
+    fn test_simulate_rejects_modification_of_readonly_account() {
+        // Counter commits a post-state for every account it gets
+        let result = simulate_program_with_readonly_accounts(
+            COUNTER_ELF,
+            &[],
+            &[counter_account(5)],
+            &1_u64,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invoke_serializes_instruction() {
+        let recipient = counter_account(0);
+        let call = crate::invoke(ProgramId::new([7; 8]), vec![recipient.clone()], &5_u128);
+
+        assert_eq!(call.program_id, ProgramId::new([7; 8]));
+        assert_eq!(call.pre_states, [recipient]);
+        assert_eq!(call.instruction_data, to_vec(&5_u128).unwrap());
+        assert!(call.pda_seeds.is_empty());
+    }
+
+    #[test]
+    fn test_account_serialization_round_trip() {
+        let account = counter_account(42);
+
+        let words = to_vec(&account).unwrap();
+        let decoded: AccountWithMetadata = risc0_zkvm::serde::from_slice(&words).unwrap();
+
+        assert_eq!(decoded, account);
+    }
+}
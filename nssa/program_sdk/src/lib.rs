@@ -0,0 +1,122 @@
+//! SDK for writing programs, which can be deployed to and executed by NSSA.
+//!
+//! A program is a risc0 guest, which reads pre-states of accounts and instruction, and commits
+//! post-states of the same accounts:
+//!
+//! ```ignore
+//! use nssa_program_sdk::{commit_post_states, read_instruction_data, read_pre_states};
+//!
+//! fn main() {
+//!     let pre_states = read_pre_states();
+//!     let increment: u64 = read_instruction_data();
+//!     let post_states = ...;
+//!     commit_post_states(pre_states, &post_states);
+//! }
+//! ```
+//!
+//! A program can call other programs, e.g. the authenticated transfer program to pay out, by
+//! committing the calls built with [`invoke`] via [`commit_post_states_and_invoke`]. The chain
+//! executes them one by one after the program, against its post-states, and the whole
+//! transaction fails if any of them does.
+//!
+//! A program owns the default accounts it claims, and accounts assigned to it. An account owned
+//! by the authenticated transfer program is assigned with the words of the target program id as
+//! instruction, e.g. by `wallet account assign --address ... --program ...`. Assignment needs the
+//! signature of the account and keeps its balance, but only accounts without data can be
+//! assigned. Programs can't reassign accounts they don't own.
+//!
+//! Accounts, which a transaction passes as read-only, come after the writable ones in pre-states.
+//! A program can read them, but must not commit their post-states.
+//!
+//! With `host` feature enabled, [`simulate_program`] runs a program the same way the chain
+//! executes public transactions, but without proving.
+#![cfg_attr(not(feature = "host"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+pub use nssa_core::{
+    account::{Account, AccountId, AccountWithMetadata, Data, Nonce},
+    program::{
+        AccountPostState, ChainedCall, DEFAULT_PROGRAM_ID, InstructionData, PdaSeed, ProgramId,
+        ProgramOutput, write_nssa_outputs, write_nssa_outputs_with_chained_call,
+    },
+    units::Amount,
+};
+use risc0_zkvm::{
+    guest::env,
+    serde::{Deserializer, to_vec},
+};
+use serde::{Serialize, de::DeserializeOwned};
+
+#[cfg(feature = "host")]
+mod host;
+
+#[cfg(feature = "host")]
+pub use host::{simulate_program, simulate_program_with_readonly_accounts};
+
+/// Read pre-states of accounts, passed to the program
+///
+/// Must be called once, before [`read_instruction_data`].
+pub fn read_pre_states() -> Vec<AccountWithMetadata> {
+    env::read()
+}
+
+/// Read instruction, passed to the program
+///
+/// Must be called once, after [`read_pre_states`].
+pub fn read_instruction_data<T: DeserializeOwned>() -> T {
+    let instruction_words: InstructionData = env::read();
+    T::deserialize(&mut Deserializer::new(instruction_words.as_ref()))
+        .expect("Instruction must match the type expected by the program")
+}
+
+/// Commit post-states of accounts without claiming any of them
+///
+/// `post_states` must be in the same order as `pre_states`. Read-only accounts of the transaction
+/// come after the writable ones and must have no post-states. Use [`write_nssa_outputs`] to claim
+/// accounts.
+pub fn commit_post_states(pre_states: Vec<AccountWithMetadata>, post_states: &[Account]) {
+    let post_states = post_states
+        .iter()
+        .cloned()
+        .map(AccountPostState::new)
+        .collect();
+    write_nssa_outputs(pre_states, post_states);
+}
+
+/// Build a call of program `program_id` with `instruction` on the given accounts
+///
+/// `pre_states` are the states of the accounts as the called program should see them, i.e. after
+/// this program and calls before this one. Accounts can only be marked authorized if they are
+/// authorized for this program.
+pub fn invoke<T: Serialize>(
+    program_id: ProgramId,
+    pre_states: Vec<AccountWithMetadata>,
+    instruction: &T,
+) -> ChainedCall {
+    ChainedCall {
+        program_id,
+        instruction_data: to_vec(instruction).expect("Instruction must be serializable"),
+        pre_states,
+        pda_seeds: Vec::new(),
+    }
+}
+
+/// Commit post-states of accounts without claiming any of them, and calls of other programs to be
+/// executed after this program
+///
+/// Calls are executed in the given order, see [`invoke`].
+pub fn commit_post_states_and_invoke(
+    pre_states: Vec<AccountWithMetadata>,
+    post_states: &[Account],
+    calls: Vec<ChainedCall>,
+) {
+    let post_states = post_states
+        .iter()
+        .cloned()
+        .map(AccountPostState::new)
+        .collect();
+    write_nssa_outputs_with_chained_call(pre_states, post_states, calls);
+}
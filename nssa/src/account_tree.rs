@@ -0,0 +1,517 @@
+//! Sparse Merkle tree over public accounts, which keeps hashes of its nodes, so updating an
+//! account rehashes only the path to its leaf.
+//!
+//! Accounts are leaves at paths given by bits of their ids, most significant first. A subtree
+//! with a single account has the hash of its leaf, so a path ends where ids stop sharing a
+//! prefix, and a tree of `n` accounts with random ids has depth of about `log2(n)`.
+//!
+//! An [`AccountProof`] shows the account at the end of its path to anyone knowing the root only.
+//! Default accounts aren't stored, so for them the path ends at an empty subtree or at the leaf
+//! of another account sharing the prefix.
+
+use std::sync::Arc;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use nssa_core::account::{Account, AccountId};
+use sha2::{Digest as _, Sha256};
+
+pub type Hash = [u8; 32];
+
+/// Hash of a tree without accounts
+pub const EMPTY_HASH: Hash = [0; 32];
+
+const LEAF_TAG: u8 = 0;
+const INTERNAL_TAG: u8 = 1;
+
+fn leaf_hash(account_id: &AccountId, account: &Account) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_TAG]);
+    hasher.update(account_id.value());
+    hasher.update(account.to_bytes());
+    hasher.finalize().into()
+}
+
+fn internal_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([INTERNAL_TAG]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Bit of `account_id` choosing the child of a node at `depth`
+fn bit(account_id: &AccountId, depth: usize) -> usize {
+    let byte = account_id.value()[depth / 8];
+    usize::from((byte >> (7 - depth % 8)) & 1)
+}
+
+#[derive(Clone, Default)]
+enum Node {
+    #[default]
+    Empty,
+    Leaf {
+        account_id: AccountId,
+        hash: Hash,
+    },
+    /// Node with at least two accounts below it
+    ///
+    /// Children are shared between clones of the tree and copied on write.
+    Internal {
+        children: [Arc<Node>; 2],
+        hash: Hash,
+    },
+}
+
+impl Node {
+    fn hash(&self) -> Hash {
+        match self {
+            Self::Empty => EMPTY_HASH,
+            Self::Leaf { hash, .. } | Self::Internal { hash, .. } => *hash,
+        }
+    }
+
+    fn insert(&mut self, account_id: AccountId, hash: Hash, depth: usize) {
+        match self {
+            Self::Empty => *self = Self::Leaf { account_id, hash },
+            Self::Leaf {
+                account_id: existing_id,
+                hash: existing_hash,
+            } if *existing_id == account_id => *existing_hash = hash,
+            Self::Leaf {
+                account_id: existing_id,
+                ..
+            } => {
+                // Paths of the accounts are split by an internal node, which is split further
+                // if the accounts share the next bit too
+                let index = bit(existing_id, depth);
+                let mut children = [Arc::new(Self::Empty), Arc::new(Self::Empty)];
+                children[index] = Arc::new(std::mem::take(self));
+                *self = Self::Internal {
+                    children,
+                    hash: EMPTY_HASH,
+                };
+                self.insert(account_id, hash, depth);
+            }
+            Self::Internal {
+                children,
+                hash: node_hash,
+            } => {
+                Arc::make_mut(&mut children[bit(&account_id, depth)]).insert(
+                    account_id,
+                    hash,
+                    depth + 1,
+                );
+                *node_hash = internal_hash(&children[0].hash(), &children[1].hash());
+            }
+        }
+    }
+
+    fn remove(&mut self, account_id: &AccountId, depth: usize) {
+        match self {
+            Self::Empty => {}
+            Self::Leaf {
+                account_id: existing_id,
+                ..
+            } => {
+                if existing_id == account_id {
+                    *self = Self::Empty;
+                }
+            }
+            Self::Internal { children, hash } => {
+                Arc::make_mut(&mut children[bit(account_id, depth)]).remove(account_id, depth + 1);
+                // Node with a single account left below it is replaced by its leaf
+                let single_leaf = match (&*children[0], &*children[1]) {
+                    (Self::Empty, leaf @ Self::Leaf { .. })
+                    | (leaf @ Self::Leaf { .. }, Self::Empty) => Some(leaf.clone()),
+                    _ => None,
+                };
+                match single_leaf {
+                    Some(leaf) => *self = leaf,
+                    None => *hash = internal_hash(&children[0].hash(), &children[1].hash()),
+                }
+            }
+        }
+    }
+}
+
+/// Path of an account from the root of an [`AccountTree`], see [`AccountTree::proof`]
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct AccountProof {
+    /// Hashes of siblings of the nodes on the path, from the root down
+    pub siblings: Vec<Hash>,
+    /// Another account, which the path ends at, as the proven account isn't stored. `None` if
+    /// the path ends at the account or at an empty subtree.
+    ///
+    /// The whole account is carried, so its leaf can't be substituted with a hash of an internal
+    /// node.
+    pub other_account: Option<(AccountId, Account)>,
+}
+
+impl AccountProof {
+    /// Root of the tree, which has `account` at `account_id`, if the proof is well-formed
+    ///
+    /// The account is proven by comparing the result with a trusted root.
+    pub fn root(&self, account_id: &AccountId, account: &Account) -> Option<Hash> {
+        let depth = self.siblings.len();
+        if depth > 8 * size_of::<Hash>() {
+            return None;
+        }
+
+        let mut hash = if *account == Account::default() {
+            match &self.other_account {
+                None => EMPTY_HASH,
+                // Leaf of the other account is at the path only if ids share the prefix, and
+                // default accounts have no leaves
+                Some((other_id, other_account)) => {
+                    if other_id == account_id
+                        || *other_account == Account::default()
+                        || (0..depth).any(|depth| bit(other_id, depth) != bit(account_id, depth))
+                    {
+                        return None;
+                    }
+                    leaf_hash(other_id, other_account)
+                }
+            }
+        } else {
+            if self.other_account.is_some() {
+                return None;
+            }
+            leaf_hash(account_id, account)
+        };
+
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            hash = match bit(account_id, depth) {
+                0 => internal_hash(&hash, sibling),
+                _ => internal_hash(sibling, &hash),
+            };
+        }
+        Some(hash)
+    }
+}
+
+/// Hash of the subtree at `depth` with `leaves`, sorted by account id
+fn subtree_hash(leaves: &[(AccountId, Hash)], depth: usize) -> Hash {
+    match leaves {
+        [] => EMPTY_HASH,
+        [(_, hash)] => *hash,
+        _ => {
+            let split = leaves.partition_point(|(account_id, _)| bit(account_id, depth) == 0);
+            internal_hash(
+                &subtree_hash(&leaves[..split], depth + 1),
+                &subtree_hash(&leaves[split..], depth + 1),
+            )
+        }
+    }
+}
+
+/// Sparse Merkle tree over public accounts, see [`crate::account_tree`]
+///
+/// Cloning is cheap, as clones share nodes until they are updated.
+#[derive(Clone, Default)]
+pub struct AccountTree {
+    root: Node,
+}
+
+impl AccountTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn root(&self) -> Hash {
+        self.root.hash()
+    }
+
+    /// Inserts `account` or replaces the stored account with the same id
+    pub fn insert(&mut self, account_id: AccountId, account: &Account) {
+        self.root
+            .insert(account_id, leaf_hash(&account_id, account), 0);
+    }
+
+    /// Removes account with `account_id`, if any
+    pub fn remove(&mut self, account_id: &AccountId) {
+        self.root.remove(account_id, 0);
+    }
+
+    /// Proof of the account stored at `account_id`, or of the default one if none is
+    ///
+    /// Leaves keep hashes only, so the account of another leaf, which the path may end at, is
+    /// looked up with `stored_account`.
+    pub fn proof<'a>(
+        &self,
+        account_id: &AccountId,
+        stored_account: impl FnOnce(&AccountId) -> Option<&'a Account>,
+    ) -> AccountProof {
+        let mut siblings = vec![];
+        let mut node = &self.root;
+        loop {
+            match node {
+                Node::Empty => {
+                    return AccountProof {
+                        siblings,
+                        other_account: None,
+                    };
+                }
+                Node::Leaf {
+                    account_id: leaf_id,
+                    ..
+                } => {
+                    let other_account = (leaf_id != account_id).then(|| {
+                        let account = stored_account(leaf_id)
+                            .expect("Accounts of leaves are stored")
+                            .clone();
+                        (*leaf_id, account)
+                    });
+                    return AccountProof {
+                        siblings,
+                        other_account,
+                    };
+                }
+                Node::Internal { children, .. } => {
+                    let index = bit(account_id, siblings.len());
+                    siblings.push(children[1 - index].hash());
+                    node = &children[index];
+                }
+            }
+        }
+    }
+
+    /// Root of a tree with `accounts`, computed from scratch without keeping nodes
+    pub fn compute_root<'a>(
+        accounts: impl IntoIterator<Item = (&'a AccountId, &'a Account)>,
+    ) -> Hash {
+        let mut leaves = accounts
+            .into_iter()
+            .map(|(account_id, account)| (*account_id, leaf_hash(account_id, account)))
+            .collect::<Vec<_>>();
+        leaves.sort_unstable_by_key(|(account_id, _)| *account_id);
+        subtree_hash(&leaves, 0)
+    }
+}
+
+impl<'a> FromIterator<(&'a AccountId, &'a Account)> for AccountTree {
+    fn from_iter<T: IntoIterator<Item = (&'a AccountId, &'a Account)>>(accounts: T) -> Self {
+        let mut tree = Self::new();
+        for (account_id, account) in accounts {
+            tree.insert(*account_id, account);
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::Amount;
+
+    fn account_id(index: u32) -> AccountId {
+        AccountId::new(Sha256::digest(index.to_le_bytes()).into())
+    }
+
+    fn account(balance: u128) -> Account {
+        Account {
+            balance: Amount(balance),
+            ..Account::default()
+        }
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        assert_eq!(AccountTree::new().root(), EMPTY_HASH);
+        assert_eq!(AccountTree::compute_root([]), EMPTY_HASH);
+    }
+
+    #[test]
+    fn test_single_account_root_is_its_leaf() {
+        let mut tree = AccountTree::new();
+        tree.insert(account_id(0), &account(5));
+
+        assert_eq!(tree.root(), leaf_hash(&account_id(0), &account(5)));
+    }
+
+    #[test]
+    fn test_updates_match_full_recompute() {
+        let mut tree = AccountTree::new();
+        let mut accounts = BTreeMap::new();
+
+        for index in 0..200 {
+            tree.insert(account_id(index), &account(index.into()));
+            accounts.insert(account_id(index), account(index.into()));
+            assert_eq!(tree.root(), AccountTree::compute_root(&accounts));
+        }
+        for index in (0..200).step_by(3) {
+            tree.insert(account_id(index), &account(1000));
+            accounts.insert(account_id(index), account(1000));
+        }
+        assert_eq!(tree.root(), AccountTree::compute_root(&accounts));
+        for index in (0..200).rev().step_by(2) {
+            tree.remove(&account_id(index));
+            accounts.remove(&account_id(index));
+            assert_eq!(tree.root(), AccountTree::compute_root(&accounts));
+        }
+        for index in 0..200 {
+            tree.remove(&account_id(index));
+        }
+        assert_eq!(tree.root(), EMPTY_HASH);
+    }
+
+    #[test]
+    fn test_accounts_with_long_common_prefix() {
+        let mut ids = [[0; 32]; 3];
+        ids[1][31] = 1;
+        ids[2][31] = 3;
+        let accounts = ids
+            .map(AccountId::new)
+            .map(|account_id| (account_id, account(1)));
+        let mut tree = accounts
+            .iter()
+            .map(|(account_id, account)| (account_id, account))
+            .collect::<AccountTree>();
+
+        assert_eq!(
+            tree.root(),
+            AccountTree::compute_root(accounts.iter().map(|(id, account)| (id, account)))
+        );
+
+        tree.remove(&accounts[1].0);
+        tree.remove(&accounts[2].0);
+        assert_eq!(tree.root(), leaf_hash(&accounts[0].0, &accounts[0].1));
+    }
+
+    #[test]
+    fn test_clone_is_not_affected_by_updates() {
+        let tree = (0..10)
+            .map(|index| (account_id(index), account(1)))
+            .collect::<BTreeMap<_, _>>()
+            .iter()
+            .collect::<AccountTree>();
+        let root = tree.root();
+
+        let mut updated = tree.clone();
+        updated.insert(account_id(3), &account(2));
+        updated.remove(&account_id(4));
+
+        assert_eq!(tree.root(), root);
+        assert_ne!(updated.root(), root);
+    }
+
+    #[test]
+    fn test_proofs_show_stored_and_default_accounts() {
+        let accounts = (0..50)
+            .map(|index| (account_id(index), account(u128::from(index) + 1)))
+            .collect::<BTreeMap<_, _>>();
+        let tree = accounts.iter().collect::<AccountTree>();
+        let root = tree.root();
+
+        for (account_id, stored) in &accounts {
+            let proof = tree.proof(account_id, |id| accounts.get(id));
+            assert_eq!(proof.root(account_id, stored), Some(root));
+            // Tampered account isn't proven, nor is it absent
+            assert_ne!(proof.root(account_id, &account(1000)), Some(root));
+            assert_ne!(proof.root(account_id, &Account::default()), Some(root));
+        }
+
+        // Accounts, which aren't stored, are proven default
+        for index in 50..100 {
+            let proof = tree.proof(&account_id(index), |id| accounts.get(id));
+            assert_eq!(
+                proof.root(&account_id(index), &Account::default()),
+                Some(root)
+            );
+            assert_ne!(proof.root(&account_id(index), &account(1)), Some(root));
+        }
+
+        let empty = AccountTree::new();
+        let proof = empty.proof(&account_id(0), |_| None);
+        assert_eq!(
+            proof.root(&account_id(0), &Account::default()),
+            Some(EMPTY_HASH)
+        );
+    }
+
+    #[test]
+    fn test_proof_of_another_account_does_not_prove_absence() {
+        let accounts = (0..10)
+            .map(|index| (account_id(index), account(1)))
+            .collect::<BTreeMap<_, _>>();
+        let tree = accounts.iter().collect::<AccountTree>();
+        let mut proof = tree.proof(&account_id(3), |id| accounts.get(id));
+        proof.other_account = Some((account_id(3), account(1)));
+
+        assert_eq!(proof.root(&account_id(3), &Account::default()), None);
+        assert_eq!(proof.root(&account_id(3), &account(1)), None);
+    }
+
+    #[test]
+    fn test_absence_proof_from_internal_node_is_rejected() {
+        let accounts = (0..50)
+            .map(|index| (account_id(index), account(1)))
+            .collect::<BTreeMap<_, _>>();
+        let tree = accounts.iter().collect::<AccountTree>();
+        let root = tree.root();
+
+        // Parent of a stored leaf is an internal node at the end of a shorter path
+        let stored_id = accounts.keys().next().unwrap();
+        let mut siblings = tree.proof(stored_id, |id| accounts.get(id)).siblings;
+        siblings.pop();
+        let mut node = &tree.root;
+        for depth in 0..siblings.len() {
+            let Node::Internal { children, .. } = node else {
+                unreachable!()
+            };
+            node = &children[bit(stored_id, depth)];
+        }
+        let absent_id = (50..)
+            .map(account_id)
+            .find(|id| (0..siblings.len()).all(|depth| bit(id, depth) == bit(stored_id, depth)))
+            .unwrap();
+
+        // Hash of the internal node in place of a leaf would lead to the root
+        let forged_root = siblings.iter().enumerate().rev().fold(
+            node.hash(),
+            |hash, (depth, sibling)| match bit(&absent_id, depth) {
+                0 => internal_hash(&hash, sibling),
+                _ => internal_hash(sibling, &hash),
+            },
+        );
+        assert_eq!(forged_root, root);
+        let forged = borsh::to_vec(&(&siblings, Some((*stored_id, node.hash())))).unwrap();
+        assert_eq!(
+            borsh::from_slice::<AccountProof>(&forged)
+                .ok()
+                .and_then(|proof| proof.root(&absent_id, &Account::default())),
+            None
+        );
+
+        // Neither do accounts below the internal node stand in for it
+        for (other_id, other) in &accounts {
+            let proof = AccountProof {
+                siblings: siblings.clone(),
+                other_account: Some((*other_id, other.clone())),
+            };
+            assert_ne!(proof.root(&absent_id, &Account::default()), Some(root));
+        }
+    }
+
+    #[test]
+    fn test_root_does_not_depend_on_insertion_order() {
+        let forward = (0..50)
+            .map(|index| (account_id(index), account(1)))
+            .collect::<Vec<_>>();
+
+        let mut tree = AccountTree::new();
+        for (account_id, account) in forward.iter().rev() {
+            tree.insert(*account_id, account);
+        }
+
+        assert_eq!(
+            tree.root(),
+            forward
+                .iter()
+                .map(|(id, account)| (id, account))
+                .collect::<AccountTree>()
+                .root()
+        );
+    }
+}
@@ -0,0 +1,504 @@
+//! Decoding of transactions encoded while nonces were `u128`, so blocks produced before nonces
+//! became `u64` can still be read. The same goes for program deployments encoded before they were
+//! signed and paid for by a deployer.
+//!
+//! Legacy transactions are converted to the current types, failing if a nonce doesn't fit into
+//! `u64`. Signatures and proofs of converted transactions are over the legacy encoding, so they
+//! don't verify and converted transactions can't be executed. Converted deployments aren't signed
+//! at all.
+//!
+//! Transactions encoded before witness sets carried a [`SignatureScheme`] are decoded here as
+//! well, with the schnorr scheme, the only one back then. Messages of privacy preserving
+//! transactions and deployments are encoded as now, so their signatures still verify. They aren't
+//! accepted anymore all the same, so every transaction has a single encoding.
+//!
+//! Public transactions encoded while priority fees were `u64` are decoded with the fee widened
+//! to an [`Amount`]. Like with legacy nonces, their signatures are over the narrow encoding and
+//! don't verify, and they are identified by the hash of their messages as encoded, see
+//! [`PublicTransaction::narrow_fee_hash`].
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use nssa_core::{
+    Commitment, CommitmentSetDigest, Nullifier,
+    account::{Account, Data, Nonce},
+    program::{InstructionData, ProgramId},
+    units::{Amount, Cycles},
+};
+use sha2::{Digest as _, digest::FixedOutput as _};
+
+use crate::{
+    AccountId, PrivacyPreservingTransaction, ProgramDeploymentTransaction, PublicKey,
+    PublicTransaction, Signature,
+    encoding::from_canonical_bytes,
+    error::NssaError,
+    privacy_preserving_transaction::{self, circuit::Proof, message::EncryptedAccountData},
+    program_deployment_transaction, public_transaction,
+    signature::SignatureScheme,
+};
+
+/// Witness set of public transactions and deployments encoded before it carried a scheme
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyWitnessSet {
+    signatures_and_public_keys: Vec<(Signature, PublicKey)>,
+}
+
+/// Witness set of privacy preserving transactions encoded before it carried a scheme
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyPrivacyPreservingWitnessSet {
+    signatures_and_public_keys: Vec<(Signature, PublicKey)>,
+    proof: Proof,
+}
+
+impl From<LegacyWitnessSet> for public_transaction::WitnessSet {
+    fn from(value: LegacyWitnessSet) -> Self {
+        Self {
+            scheme: SignatureScheme::Schnorr,
+            signatures_and_public_keys: value.signatures_and_public_keys,
+        }
+    }
+}
+
+impl From<LegacyWitnessSet> for program_deployment_transaction::WitnessSet {
+    fn from(value: LegacyWitnessSet) -> Self {
+        Self {
+            scheme: SignatureScheme::Schnorr,
+            signatures_and_public_keys: value.signatures_and_public_keys,
+        }
+    }
+}
+
+impl From<LegacyPrivacyPreservingWitnessSet>
+    for privacy_preserving_transaction::witness_set::WitnessSet
+{
+    fn from(value: LegacyPrivacyPreservingWitnessSet) -> Self {
+        Self {
+            scheme: SignatureScheme::Schnorr,
+            signatures_and_public_keys: value.signatures_and_public_keys,
+            proof: value.proof,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyAccount {
+    program_owner: ProgramId,
+    balance: u128,
+    data: Data,
+    nonce: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyPublicMessage {
+    program_id: ProgramId,
+    account_ids: Vec<AccountId>,
+    readonly_account_ids: Vec<AccountId>,
+    nonces: Vec<u128>,
+    instruction_data: InstructionData,
+    priority_fee: u64,
+    valid_from_block: Option<u64>,
+    max_cycles: Option<u64>,
+}
+
+/// Public message encoded while priority fees were `u64`, with and without a signature scheme
+#[derive(BorshSerialize, BorshDeserialize)]
+struct NarrowFeePublicMessage {
+    program_id: ProgramId,
+    account_ids: Vec<AccountId>,
+    readonly_account_ids: Vec<AccountId>,
+    nonces: Vec<Nonce>,
+    instruction_data: InstructionData,
+    priority_fee: u64,
+    valid_from_block: Option<u64>,
+    max_cycles: Option<Cycles>,
+}
+
+impl From<NarrowFeePublicMessage> for public_transaction::Message {
+    fn from(value: NarrowFeePublicMessage) -> Self {
+        Self {
+            program_id: value.program_id,
+            account_ids: value.account_ids,
+            readonly_account_ids: value.readonly_account_ids,
+            nonces: value.nonces,
+            instruction_data: value.instruction_data,
+            priority_fee: Amount::from(value.priority_fee),
+            valid_from_block: value.valid_from_block,
+            max_cycles: value.max_cycles,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyPublicTransaction {
+    message: LegacyPublicMessage,
+    witness_set: LegacyWitnessSet,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyPrivacyPreservingMessage {
+    public_account_ids: Vec<AccountId>,
+    nonces: Vec<u128>,
+    public_post_states: Vec<LegacyAccount>,
+    encrypted_private_post_states: Vec<EncryptedAccountData>,
+    new_commitments: Vec<Commitment>,
+    new_nullifiers: Vec<(Nullifier, CommitmentSetDigest)>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyPrivacyPreservingTransaction {
+    message: LegacyPrivacyPreservingMessage,
+    witness_set: LegacyPrivacyPreservingWitnessSet,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyProgramDeploymentMessage {
+    bytecode: Vec<u8>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyProgramDeploymentTransaction {
+    message: LegacyProgramDeploymentMessage,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct UnschemedPublicTransaction {
+    message: NarrowFeePublicMessage,
+    witness_set: LegacyWitnessSet,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct NarrowFeePublicTransaction {
+    message: NarrowFeePublicMessage,
+    witness_set: public_transaction::WitnessSet,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct UnschemedPrivacyPreservingTransaction {
+    message: privacy_preserving_transaction::message::Message,
+    witness_set: LegacyPrivacyPreservingWitnessSet,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct UnschemedProgramDeploymentTransaction {
+    message: program_deployment_transaction::Message,
+    witness_set: LegacyWitnessSet,
+}
+
+fn narrow_nonce(nonce: u128) -> Result<Nonce, NssaError> {
+    Nonce::try_from(nonce)
+        .map_err(|_| NssaError::InvalidInput(format!("Legacy nonce {nonce} doesn't fit into u64")))
+}
+
+fn narrow_nonces(nonces: Vec<u128>) -> Result<Vec<Nonce>, NssaError> {
+    nonces.into_iter().map(narrow_nonce).collect()
+}
+
+impl TryFrom<LegacyAccount> for Account {
+    type Error = NssaError;
+
+    fn try_from(value: LegacyAccount) -> Result<Self, Self::Error> {
+        Ok(Self {
+            program_owner: value.program_owner,
+            balance: Amount(value.balance),
+            data: value.data,
+            nonce: narrow_nonce(value.nonce)?,
+        })
+    }
+}
+
+impl PublicTransaction {
+    /// Decodes a transaction in the legacy encoding, see [`crate::encoding::legacy`]
+    pub fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
+        let LegacyPublicTransaction {
+            message,
+            witness_set,
+        } = from_canonical_bytes(bytes)?;
+        let message = public_transaction::Message {
+            program_id: message.program_id,
+            account_ids: message.account_ids,
+            readonly_account_ids: message.readonly_account_ids,
+            nonces: narrow_nonces(message.nonces)?,
+            instruction_data: message.instruction_data,
+            priority_fee: Amount::from(message.priority_fee),
+            valid_from_block: message.valid_from_block,
+            max_cycles: message.max_cycles.map(Cycles),
+        };
+        Ok(Self::new(message, witness_set.into()))
+    }
+
+    /// Decodes a transaction encoded before witness sets carried a scheme, see
+    /// [`crate::encoding::legacy`]
+    pub fn from_unschemed_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
+        let UnschemedPublicTransaction {
+            message,
+            witness_set,
+        } = from_canonical_bytes(bytes)?;
+        Ok(Self::new(message.into(), witness_set.into()))
+    }
+
+    /// Decodes a transaction encoded while priority fees were `u64`, see
+    /// [`crate::encoding::legacy`]
+    pub fn from_narrow_fee_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
+        let NarrowFeePublicTransaction {
+            message,
+            witness_set,
+        } = from_canonical_bytes(bytes)?;
+        Ok(Self::new(message.into(), witness_set))
+    }
+
+    /// Identifier of a transaction in the legacy encoding, which is the hash of its legacy
+    /// message bytes as before the conversion
+    pub fn legacy_hash(bytes: &[u8]) -> Result<[u8; 32], NssaError> {
+        let tx: LegacyPublicTransaction = from_canonical_bytes(bytes)?;
+        message_hash(&tx.message)
+    }
+
+    /// Identifier of a transaction encoded before witness sets carried a scheme, which is the
+    /// hash of its message bytes as before the conversion
+    pub fn unschemed_hash(bytes: &[u8]) -> Result<[u8; 32], NssaError> {
+        let tx: UnschemedPublicTransaction = from_canonical_bytes(bytes)?;
+        message_hash(&tx.message)
+    }
+
+    /// Identifier of a transaction encoded while priority fees were `u64`, which is the hash of
+    /// its message bytes as before the conversion
+    pub fn narrow_fee_hash(bytes: &[u8]) -> Result<[u8; 32], NssaError> {
+        let tx: NarrowFeePublicTransaction = from_canonical_bytes(bytes)?;
+        message_hash(&tx.message)
+    }
+}
+
+fn message_hash(message: &impl BorshSerialize) -> Result<[u8; 32], NssaError> {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(borsh::to_vec(message)?);
+    Ok(hasher.finalize_fixed().into())
+}
+
+impl PrivacyPreservingTransaction {
+    /// Decodes a transaction in the legacy encoding, see [`crate::encoding::legacy`]
+    pub fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
+        let LegacyPrivacyPreservingTransaction {
+            message,
+            witness_set,
+        } = from_canonical_bytes(bytes)?;
+        let message = privacy_preserving_transaction::message::Message {
+            public_account_ids: message.public_account_ids,
+            nonces: narrow_nonces(message.nonces)?,
+            public_post_states: message
+                .public_post_states
+                .into_iter()
+                .map(Account::try_from)
+                .collect::<Result<_, _>>()?,
+            encrypted_private_post_states: message.encrypted_private_post_states,
+            new_commitments: message.new_commitments,
+            new_nullifiers: message.new_nullifiers,
+        };
+        Ok(Self::new(message, witness_set.into()))
+    }
+
+    /// Decodes a transaction encoded before witness sets carried a scheme, see
+    /// [`crate::encoding::legacy`]
+    pub fn from_unschemed_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
+        let UnschemedPrivacyPreservingTransaction {
+            message,
+            witness_set,
+        } = from_canonical_bytes(bytes)?;
+        Ok(Self::new(message, witness_set.into()))
+    }
+}
+
+impl ProgramDeploymentTransaction {
+    /// Decodes a deployment in the legacy encoding, see [`crate::encoding::legacy`]
+    ///
+    /// Legacy deployments have no deployer, so the converted one has the zero account id as its
+    /// deployer and no signature.
+    pub fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
+        let LegacyProgramDeploymentTransaction { message } = from_canonical_bytes(bytes)?;
+        let message = program_deployment_transaction::Message::new(
+            AccountId::new([0; 32]),
+            0,
+            0,
+            Amount::ZERO,
+            message.bytecode,
+        );
+        let witness_set = program_deployment_transaction::WitnessSet {
+            scheme: SignatureScheme::Schnorr,
+            signatures_and_public_keys: vec![],
+        };
+        Ok(Self::new(message, witness_set))
+    }
+
+    /// Decodes a deployment encoded before witness sets carried a scheme, see
+    /// [`crate::encoding::legacy`]
+    pub fn from_unschemed_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
+        let UnschemedProgramDeploymentTransaction {
+            message,
+            witness_set,
+        } = from_canonical_bytes(bytes)?;
+        Ok(Self::new(message, witness_set.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PrivateKey, PublicKey, Signature, program::Program};
+
+    fn legacy_transaction(nonce: u128) -> LegacyPublicTransaction {
+        let message = LegacyPublicMessage {
+            program_id: Program::authenticated_transfer_program().id(),
+            account_ids: vec![AccountId::new([1; 32]), AccountId::new([2; 32])],
+            readonly_account_ids: vec![],
+            nonces: vec![nonce],
+            instruction_data: Program::serialize_instruction(10_u128).unwrap(),
+            priority_fee: 3,
+            valid_from_block: Some(7),
+            max_cycles: None,
+        };
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let witness_set = LegacyWitnessSet {
+            signatures_and_public_keys: vec![(
+                Signature::new(&key, &borsh::to_vec(&message).unwrap()),
+                PublicKey::new_from_private_key(&key),
+            )],
+        };
+        LegacyPublicTransaction {
+            message,
+            witness_set,
+        }
+    }
+
+    #[test]
+    fn test_legacy_public_transaction_is_converted() {
+        let legacy = legacy_transaction(5);
+        let bytes = borsh::to_vec(&legacy).unwrap();
+
+        let tx = PublicTransaction::from_legacy_bytes(&bytes).unwrap();
+
+        assert_eq!(tx.message().nonces(), [5]);
+        assert_eq!(tx.message().account_ids(), legacy.message.account_ids);
+        assert_eq!(tx.message().priority_fee(), Amount(3));
+        assert_eq!(tx.message().valid_from_block(), Some(7));
+        assert_eq!(
+            tx.witness_set().signatures_and_public_keys(),
+            legacy.witness_set.signatures_and_public_keys
+        );
+        // Signatures are over the legacy message
+        assert!(!tx.witness_set().is_valid_for(tx.message()));
+    }
+
+    #[test]
+    fn test_legacy_hash_is_hash_of_legacy_message() {
+        let legacy = legacy_transaction(5);
+        let bytes = borsh::to_vec(&legacy).unwrap();
+
+        let expected: [u8; 32] =
+            sha2::Sha256::digest(borsh::to_vec(&legacy.message).unwrap()).into();
+        assert_eq!(PublicTransaction::legacy_hash(&bytes).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_legacy_nonce_wider_than_u64_is_rejected() {
+        let bytes = borsh::to_vec(&legacy_transaction(u128::from(u64::MAX) + 1)).unwrap();
+
+        assert!(matches!(
+            PublicTransaction::from_legacy_bytes(&bytes),
+            Err(NssaError::InvalidInput(_))
+        ));
+    }
+
+    fn narrow_fee_message(key: &PrivateKey) -> NarrowFeePublicMessage {
+        NarrowFeePublicMessage {
+            program_id: Program::authenticated_transfer_program().id(),
+            account_ids: vec![AccountId::from(&PublicKey::new_from_private_key(key))],
+            readonly_account_ids: vec![],
+            nonces: vec![5],
+            instruction_data: Program::serialize_instruction(10_u128).unwrap(),
+            priority_fee: u64::MAX,
+            valid_from_block: None,
+            max_cycles: Some(Cycles(1000)),
+        }
+    }
+
+    #[test]
+    fn test_unschemed_public_transaction_is_converted_to_schnorr() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let message = narrow_fee_message(&key);
+        let message_bytes = borsh::to_vec(&message).unwrap();
+        let signatures_and_public_keys = vec![(
+            Signature::new(&key, &message_bytes),
+            PublicKey::new_from_private_key(&key),
+        )];
+        let unschemed = UnschemedPublicTransaction {
+            message,
+            witness_set: LegacyWitnessSet {
+                signatures_and_public_keys: signatures_and_public_keys.clone(),
+            },
+        };
+        let bytes = borsh::to_vec(&unschemed).unwrap();
+
+        let tx = PublicTransaction::from_unschemed_bytes(&bytes).unwrap();
+
+        assert_eq!(tx.message().priority_fee(), Amount::from(u64::MAX));
+        assert_eq!(tx.message().max_cycles(), Some(Cycles(1000)));
+        assert_eq!(tx.witness_set().scheme(), SignatureScheme::Schnorr);
+        assert_eq!(
+            tx.witness_set().signatures_and_public_keys(),
+            signatures_and_public_keys
+        );
+        // Signatures are over the message with the `u64` fee
+        assert!(!tx.witness_set().is_valid_for(tx.message()));
+        let expected: [u8; 32] = sha2::Sha256::digest(&message_bytes).into();
+        assert_eq!(PublicTransaction::unschemed_hash(&bytes).unwrap(), expected);
+        assert!(PublicTransaction::from_unschemed_bytes(&tx.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_narrow_fee_public_transaction_is_converted() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let message = narrow_fee_message(&key);
+        let message_bytes = borsh::to_vec(&message).unwrap();
+        let witness_set = public_transaction::WitnessSet {
+            scheme: SignatureScheme::Schnorr,
+            signatures_and_public_keys: vec![(
+                Signature::new(&key, &message_bytes),
+                PublicKey::new_from_private_key(&key),
+            )],
+        };
+        let narrow_fee = NarrowFeePublicTransaction {
+            message,
+            witness_set: witness_set.clone(),
+        };
+        let bytes = borsh::to_vec(&narrow_fee).unwrap();
+
+        let tx = PublicTransaction::from_narrow_fee_bytes(&bytes).unwrap();
+
+        assert_eq!(tx.message().priority_fee(), Amount::from(u64::MAX));
+        assert_eq!(tx.witness_set(), &witness_set);
+        assert!(!tx.witness_set().is_valid_for(tx.message()));
+        let expected: [u8; 32] = sha2::Sha256::digest(&message_bytes).into();
+        assert_eq!(
+            PublicTransaction::narrow_fee_hash(&bytes).unwrap(),
+            expected
+        );
+        assert_ne!(tx.hash(), expected);
+        // Current encoding widens the fee
+        assert!(PublicTransaction::from_narrow_fee_bytes(&tx.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_legacy_program_deployment_is_converted_unsigned() {
+        let legacy = LegacyProgramDeploymentTransaction {
+            message: LegacyProgramDeploymentMessage {
+                bytecode: vec![0xca, 0xfe],
+            },
+        };
+        let bytes = borsh::to_vec(&legacy).unwrap();
+
+        let tx = ProgramDeploymentTransaction::from_legacy_bytes(&bytes).unwrap();
+
+        assert_eq!(tx.message().bytecode(), [0xca, 0xfe]);
+        assert!(!tx.witness_set().is_valid_for(tx.message()));
+        // Current encoding of a deployment isn't legacy one
+        assert!(ProgramDeploymentTransaction::from_legacy_bytes(&tx.to_bytes()).is_err());
+    }
+}
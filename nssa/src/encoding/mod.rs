@@ -1,3 +1,24 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::NssaError;
+
+pub mod legacy;
 pub mod privacy_preserving_transaction;
 pub mod program_deployment_transaction;
 pub mod public_transaction;
+
+/// Decodes `bytes`, which must be the only encoding of the value
+///
+/// Borsh rejects trailing bytes, anything else re-encoding to different bytes is rejected here.
+/// Otherwise a relay could re-encode a transaction without changing its meaning.
+pub(crate) fn from_canonical_bytes<T: BorshSerialize + BorshDeserialize>(
+    bytes: &[u8],
+) -> Result<T, NssaError> {
+    let value = borsh::from_slice::<T>(bytes)?;
+    if borsh::to_vec(&value)? != bytes {
+        return Err(NssaError::InvalidInput(
+            "Non-canonical encoding of transaction".into(),
+        ));
+    }
+    Ok(value)
+}
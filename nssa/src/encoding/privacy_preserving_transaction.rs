@@ -1,5 +1,5 @@
 use crate::{
-    PrivacyPreservingTransaction, error::NssaError,
+    PrivacyPreservingTransaction, encoding::from_canonical_bytes, error::NssaError,
     privacy_preserving_transaction::message::Message,
 };
 
@@ -9,7 +9,7 @@ impl Message {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
-        Ok(borsh::from_slice(bytes)?)
+        from_canonical_bytes(bytes)
     }
 }
 
@@ -19,6 +19,6 @@ impl PrivacyPreservingTransaction {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
-        Ok(borsh::from_slice(bytes)?)
+        from_canonical_bytes(bytes)
     }
 }
@@ -1,4 +1,17 @@
-use crate::{ProgramDeploymentTransaction, error::NssaError};
+use crate::{
+    ProgramDeploymentTransaction, encoding::from_canonical_bytes, error::NssaError,
+    program_deployment_transaction::Message,
+};
+
+impl Message {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).expect("Autoderived borsh serialization failure")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
+        from_canonical_bytes(bytes)
+    }
+}
 
 impl ProgramDeploymentTransaction {
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -6,18 +19,32 @@ impl ProgramDeploymentTransaction {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
-        Ok(borsh::from_slice(bytes)?)
+        from_canonical_bytes(bytes)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{ProgramDeploymentTransaction, program_deployment_transaction::Message};
+    use crate::{
+        AccountId, Amount, PrivateKey, ProgramDeploymentTransaction, PublicKey,
+        program_deployment_transaction::{Message, WitnessSet},
+    };
 
     #[test]
     fn test_roundtrip() {
-        let message = Message::new(vec![0xca, 0xfe, 0xca, 0xfe, 0x01, 0x02, 0x03]);
-        let tx = ProgramDeploymentTransaction::new(message);
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let deployer = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let message = Message::new(
+            deployer,
+            3,
+            7,
+            Amount(100),
+            vec![0xca, 0xfe, 0xca, 0xfe, 0x01, 0x02, 0x03],
+        );
+        assert_eq!(Message::from_bytes(&message.to_bytes()).unwrap(), message);
+
+        let witness_set = WitnessSet::for_message(&message, &key);
+        let tx = ProgramDeploymentTransaction::new(message, witness_set);
         let bytes = tx.to_bytes();
         let tx_from_bytes = ProgramDeploymentTransaction::from_bytes(&bytes).unwrap();
         assert_eq!(tx, tx_from_bytes);
@@ -1,7 +1,10 @@
-use crate::{PublicTransaction, error::NssaError, public_transaction::Message};
+use crate::{
+    PublicTransaction, encoding::from_canonical_bytes, error::NssaError,
+    public_transaction::Message,
+};
 
 impl Message {
-    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+    pub fn to_bytes(&self) -> Vec<u8> {
         borsh::to_vec(&self).expect("Autoderived borsh serialization failure")
     }
 }
@@ -12,6 +15,6 @@ impl PublicTransaction {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
-        Ok(borsh::from_slice(bytes)?)
+        from_canonical_bytes(bytes)
     }
 }
@@ -0,0 +1,67 @@
+//! Source of randomness of generated keys, auxiliary randomness of signatures and salts
+//!
+//! It's OS randomness, unless deterministic dev mode seeds it with [`use_deterministic_seed`].
+//! Then every value is drawn from a ChaCha RNG, so runs of a local chain, which draw values in
+//! the same order, are byte-reproducible. Never use it outside of dev and test builds, as anyone
+//! knowing the seed knows all generated keys.
+
+use std::sync::Mutex;
+
+use rand::{RngCore, SeedableRng, rngs::OsRng};
+use rand_chacha::ChaCha20Rng;
+
+/// RNG of deterministic dev mode
+pub type DevRng = ChaCha20Rng;
+
+static DETERMINISTIC_RNG: Mutex<Option<DevRng>> = Mutex::new(None);
+
+/// RNG, which produces the same values for the same `seed` on every platform
+pub fn dev_rng(seed: u64) -> DevRng {
+    DevRng::seed_from_u64(seed)
+}
+
+/// Draws all further values from [`dev_rng`] of `seed` instead of OS randomness
+pub fn use_deterministic_seed(seed: u64) {
+    *DETERMINISTIC_RNG.lock().unwrap() = Some(dev_rng(seed));
+}
+
+/// Draws all further values from OS randomness again
+pub fn use_os_randomness() {
+    *DETERMINISTIC_RNG.lock().unwrap() = None;
+}
+
+/// Whether values are drawn from a seeded RNG
+pub fn is_deterministic() -> bool {
+    DETERMINISTIC_RNG.lock().unwrap().is_some()
+}
+
+/// Fills `dest` with random bytes of the current source
+pub fn fill_bytes(dest: &mut [u8]) {
+    match DETERMINISTIC_RNG.lock().unwrap().as_mut() {
+        Some(rng) => rng.fill_bytes(dest),
+        None => OsRng.fill_bytes(dest),
+    }
+}
+
+/// Random bytes of the current source
+pub fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    fill_bytes(&mut bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dev_rng_is_reproducible() {
+        let mut first = dev_rng(42);
+        let mut second = dev_rng(42);
+        let mut other = dev_rng(43);
+
+        let value = first.next_u64();
+        assert_eq!(value, second.next_u64());
+        assert_ne!(value, other.next_u64());
+    }
+}
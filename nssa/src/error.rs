@@ -22,6 +22,9 @@ pub enum NssaError {
     #[error("Invalid Public Key")]
     InvalidPublicKey,
 
+    #[error("Unknown signature scheme {0}")]
+    UnknownSignatureScheme(u8),
+
     #[error("Risc0 error: {0}")]
     ProgramWriteInputFailed(String),
 
@@ -31,6 +34,9 @@ pub enum NssaError {
     #[error("Risc0 error: {0}")]
     ProgramProveFailed(String),
 
+    #[error("Program execution exceeded the limit of {0} cycles")]
+    CycleLimitExceeded(u64),
+
     #[error("Invalid transaction: {0}")]
     TransactionDeserializationError(String),
 
@@ -40,12 +46,21 @@ pub enum NssaError {
     #[error("Program output deserialization error: {0}")]
     ProgramOutputDeserializationError(String),
 
+    #[error("Journal encoding error: {0}")]
+    JournalEncodingError(String),
+
+    #[error("Journal decoding error: {0}")]
+    JournalDecodingError(String),
+
     #[error("Circuit output deserialization error: {0}")]
     CircuitOutputDeserializationError(String),
 
     #[error("Invalid privacy preserving execution circuit proof")]
     InvalidPrivacyPreservingProof,
 
+    #[error("Proof is produced by the dev prover backend, which this node doesn't accept")]
+    DevProofRejected,
+
     #[error("Circuit proving error")]
     CircuitProvingError(String),
 
@@ -55,6 +70,48 @@ pub enum NssaError {
     #[error("Program already exists")]
     ProgramAlreadyExists,
 
+    #[error("Program bytecode of {size} bytes exceeds the maximum of {max_size}")]
+    ProgramTooLarge { size: usize, max_size: usize },
+
+    #[error("Program fails to run: {0}")]
+    ProgramSmokeTestFailed(String),
+
     #[error("Chain of calls is too long")]
     MaxChainedCallsDepthExceeded,
+
+    #[error(
+        "Account {account_id} would keep {balance}, but storage deposit of its data is {required}"
+    )]
+    InsufficientStorageDeposit {
+        account_id: nssa_core::account::AccountId,
+        balance: nssa_core::units::Amount,
+        required: nssa_core::units::Amount,
+    },
+
+    #[error("Account {account_id} can't transfer to itself")]
+    SelfTransfer {
+        account_id: nssa_core::account::AccountId,
+    },
+
+    #[error(
+        "Transfer of {amount} creates account {account_id}, but creation of accounts requires at \
+         least {min_amount}"
+    )]
+    AccountCreationBelowMinimum {
+        account_id: nssa_core::account::AccountId,
+        amount: nssa_core::units::Amount,
+        min_amount: nssa_core::units::Amount,
+    },
+
+    #[error("Invalid instruction: {0}")]
+    InvalidInstruction(#[from] crate::program::InstructionError),
+
+    #[error("Program {0:?} is not allowed on this network")]
+    ProgramNotAllowed(nssa_core::program::ProgramId),
+
+    #[error(
+        "Commitment set root is not an anchor of the last {anchor_window} blocks, the transaction \
+         must be proven again against a recent one"
+    )]
+    AnchorExpired { anchor_window: u64 },
 }
@@ -0,0 +1,187 @@
+//! Pricing of public execution by zkVM cycles
+//!
+//! A public transaction may declare `max_cycles` it's allowed to execute. Its first signer must be
+//! able to deposit gas for all of them upfront, execution is aborted once they are exhausted, and
+//! only gas of the actually executed cycles is charged, the rest of the deposit is refunded.
+//!
+//! Aborted transactions, including ones that exceeded their cycles, are never applied, so their
+//! deposit isn't forfeited. Transactions without `max_cycles` are not metered and are limited by
+//! [`GasCalculator::max_cycles_per_transaction`] only.
+//!
+//! Program deployments aren't executed, their deployer pays gas of the deployed bytecode instead,
+//! see [`GasCalculator::deployment_gas`].
+
+use nssa_core::{
+    account::AccountId,
+    units::{Amount, Cycles, Gas},
+};
+
+use crate::program::MAX_NUM_CYCLES_PUBLIC_EXECUTION;
+
+/// Converts zkVM cycles into gas, which is paid in native tokens one to one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasCalculator {
+    /// Number of cycles paid by one unit of gas
+    pub cycles_per_gas: Cycles,
+    /// Maximum number of cycles a transaction may declare
+    pub max_cycles_per_transaction: Cycles,
+    /// Number of bytes of deployed bytecode paid by one unit of gas
+    pub bytecode_bytes_per_gas: u64,
+}
+
+impl Default for GasCalculator {
+    fn default() -> Self {
+        Self {
+            cycles_per_gas: Cycles(1024),
+            max_cycles_per_transaction: Cycles(MAX_NUM_CYCLES_PUBLIC_EXECUTION),
+            bytecode_bytes_per_gas: 1024,
+        }
+    }
+}
+
+/// Outcome of a metered execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasSettlement {
+    /// Number of executed cycles
+    pub cycles: Cycles,
+    /// Gas deposited upfront for the declared maximum of cycles
+    pub deposit: Gas,
+    /// Gas of the executed cycles, paid to the fee recipient
+    pub charged: Gas,
+    /// Unused part of the deposit returned to the payer
+    pub refund: Gas,
+}
+
+/// Fees moved by an applied transaction from its payer to the fee recipient
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PaidFees {
+    /// First signer, `None` if nothing was paid
+    pub payer: Option<AccountId>,
+    /// Fee recipient of the state, `None` if nothing was paid
+    pub recipient: Option<AccountId>,
+    /// Settlement of a metered execution, its deposit is taken and the refund returned to the
+    /// payer, only the charged part reaches the recipient
+    pub gas: Option<GasSettlement>,
+    pub priority_fee: Amount,
+    /// Gas of deployed bytecode, see [`GasCalculator::deployment_gas`]
+    pub deployment_fee: Gas,
+}
+
+impl PaidFees {
+    /// Total amount moved from the payer to the recipient
+    pub fn total(&self) -> Amount {
+        Amount::from(self.gas.map_or(Gas::ZERO, |gas| gas.charged))
+            .saturating_add(self.priority_fee)
+            .saturating_add(self.deployment_fee.into())
+    }
+}
+
+impl GasCalculator {
+    /// Gas of `cycles`, rounded up, so any execution costs at least one gas
+    pub fn cycles_to_gas(&self, cycles: Cycles) -> Gas {
+        Gas(cycles.0.div_ceil(self.cycles_per_gas.0))
+    }
+
+    /// Gas of deploying `bytecode_len` bytes of bytecode, rounded up like [`Self::cycles_to_gas`]
+    pub fn deployment_gas(&self, bytecode_len: usize) -> Gas {
+        Gas((bytecode_len as u64).div_ceil(self.bytecode_bytes_per_gas))
+    }
+
+    /// Maximum number of cycles, which gas deposit is covered by `balance`
+    pub fn max_cycles_for_deposit(&self, balance: Amount) -> Cycles {
+        let cycles = balance.0.saturating_mul(u128::from(self.cycles_per_gas.0));
+        Cycles(u64::try_from(cycles).unwrap_or(u64::MAX)).min(self.max_cycles_per_transaction)
+    }
+
+    /// Splits the deposit for `max_cycles` into the charged and the refunded parts after
+    /// `cycles` were executed. Expects `cycles` to not exceed `max_cycles`.
+    pub fn settle(&self, max_cycles: Cycles, cycles: Cycles) -> GasSettlement {
+        let deposit = self.cycles_to_gas(max_cycles);
+        let charged = self.cycles_to_gas(cycles);
+        GasSettlement {
+            cycles,
+            deposit,
+            charged,
+            refund: deposit
+                .checked_sub(charged)
+                .expect("Executed cycles should not exceed the declared maximum"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycles_to_gas_rounds_up() {
+        let gas = GasCalculator::default();
+
+        assert_eq!(gas.cycles_to_gas(Cycles(0)), Gas(0));
+        assert_eq!(gas.cycles_to_gas(Cycles(1)), Gas(1));
+        assert_eq!(gas.cycles_to_gas(Cycles(1024)), Gas(1));
+        assert_eq!(gas.cycles_to_gas(Cycles(1025)), Gas(2));
+        assert_eq!(gas.cycles_to_gas(Cycles::MAX), Gas(u64::MAX / 1024 + 1));
+    }
+
+    #[test]
+    fn test_deployment_gas_rounds_up() {
+        let gas = GasCalculator::default();
+
+        assert_eq!(gas.deployment_gas(0), Gas(0));
+        assert_eq!(gas.deployment_gas(1), Gas(1));
+        assert_eq!(gas.deployment_gas(1024), Gas(1));
+        assert_eq!(gas.deployment_gas(200 * 1024 + 1), Gas(201));
+    }
+
+    #[test]
+    fn test_max_cycles_for_deposit_is_covered_by_balance() {
+        let gas = GasCalculator::default();
+
+        assert_eq!(gas.max_cycles_for_deposit(Amount(0)), Cycles(0));
+        assert_eq!(gas.max_cycles_for_deposit(Amount(3)), Cycles(3 * 1024));
+        assert_eq!(
+            gas.cycles_to_gas(gas.max_cycles_for_deposit(Amount(3))),
+            Gas(3)
+        );
+        // Deposit of one more cycle isn't covered
+        let one_more = gas
+            .max_cycles_for_deposit(Amount(3))
+            .checked_add(Cycles(1))
+            .unwrap();
+        assert_eq!(gas.cycles_to_gas(one_more), Gas(4));
+        assert_eq!(
+            gas.max_cycles_for_deposit(Amount::MAX),
+            Cycles(MAX_NUM_CYCLES_PUBLIC_EXECUTION)
+        );
+    }
+
+    #[test]
+    fn test_settle_refunds_unused_deposit() {
+        let gas = GasCalculator::default();
+
+        assert_eq!(
+            gas.settle(Cycles(10 * 1024), Cycles(2 * 1024 + 1)),
+            GasSettlement {
+                cycles: Cycles(2 * 1024 + 1),
+                deposit: Gas(10),
+                charged: Gas(3),
+                refund: Gas(7),
+            }
+        );
+        assert_eq!(gas.settle(Cycles(1024), Cycles(1024)).refund, Gas(0));
+        assert_eq!(gas.settle(Cycles(1000), Cycles(0)).refund, Gas(1));
+    }
+
+    #[test]
+    fn test_total_of_paid_fees() {
+        let fees = PaidFees {
+            gas: Some(GasCalculator::default().settle(Cycles(10 * 1024), Cycles(1024))),
+            priority_fee: Amount(5),
+            deployment_fee: Gas(2),
+            ..PaidFees::default()
+        };
+
+        assert_eq!(fees.total(), Amount(8));
+    }
+}
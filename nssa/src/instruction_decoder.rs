@@ -0,0 +1,539 @@
+//! Decoding of instructions of known programs into JSON, for block explorers and wallets.
+//!
+//! Instruction data is opaque to the chain, so [`DecoderRegistry`] maps program ids to decoders,
+//! which know the instruction formats of their programs. Decoders of builtin programs are
+//! registered by default, and nodes or wallets embedding this crate may register more. Instruction
+//! of a program without a decoder, or one its decoder rejects, is shown as hex with the program id.
+//!
+//! Decoded instructions are JSON objects with the `call` field naming the call. Amounts are
+//! decimal strings and account ids are base58 strings.
+
+use std::{collections::HashMap, sync::Arc};
+
+use nssa_core::{account::AccountId, burn::BURN_ACCOUNT_ID, program::ProgramId};
+use serde_json::{Value, json};
+
+use crate::BuiltinProgram;
+
+/// Reason an instruction isn't a valid call of the decoder's program
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{0}")]
+pub struct DecodeError(pub String);
+
+/// Decoder of instructions of one program
+pub trait InstructionDecoder: Send + Sync {
+    /// JSON view of `instruction_data` of a call with `account_ids`, in order of the message
+    fn decode(
+        &self,
+        instruction_data: &[u32],
+        account_ids: &[AccountId],
+    ) -> Result<Value, DecodeError>;
+}
+
+/// Decoders by program id
+#[derive(Clone)]
+pub struct DecoderRegistry {
+    decoders: HashMap<ProgramId, Arc<dyn InstructionDecoder>>,
+}
+
+impl Default for DecoderRegistry {
+    /// Registry with decoders of builtin programs
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(
+            BuiltinProgram::AuthenticatedTransfer.id(),
+            AuthenticatedTransferDecoder,
+        );
+        registry.register(BuiltinProgram::Token.id(), TokenDecoder);
+        registry.register(BuiltinProgram::Escrow.id(), EscrowDecoder);
+        registry.register(BuiltinProgram::Pinata.id(), PinataDecoder);
+        registry.register(BuiltinProgram::PinataToken.id(), PinataDecoder);
+        registry
+    }
+}
+
+impl std::fmt::Debug for DecoderRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.decoders.keys()).finish()
+    }
+}
+
+impl DecoderRegistry {
+    /// Registry without any decoders, so every instruction is shown as hex
+    pub fn empty() -> Self {
+        Self {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Decodes instructions of `program_id` with `decoder`, replacing the previous one
+    pub fn register(&mut self, program_id: ProgramId, decoder: impl InstructionDecoder + 'static) {
+        self.decoders.insert(program_id, Arc::new(decoder));
+    }
+
+    pub fn is_registered(&self, program_id: &ProgramId) -> bool {
+        self.decoders.contains_key(program_id)
+    }
+
+    /// JSON view of a call of `program_id`, falling back to hex if it can't be decoded
+    pub fn decode(
+        &self,
+        program_id: &ProgramId,
+        instruction_data: &[u32],
+        account_ids: &[AccountId],
+    ) -> Value {
+        let Some(decoder) = self.decoders.get(program_id) else {
+            return raw_instruction(program_id, instruction_data, None);
+        };
+
+        decoder
+            .decode(instruction_data, account_ids)
+            .unwrap_or_else(|err| raw_instruction(program_id, instruction_data, Some(err)))
+    }
+}
+
+/// Displayed program id and hex encoded instruction data as little-endian words
+fn raw_instruction(
+    program_id: &ProgramId,
+    instruction_data: &[u32],
+    error: Option<DecodeError>,
+) -> Value {
+    let mut raw = json!({
+        "call": "unknown",
+        "program_id": program_id.to_string(),
+        "data": hex_words(instruction_data),
+    });
+    if let Some(DecodeError(reason)) = error {
+        raw["error"] = Value::String(reason);
+    }
+    raw
+}
+
+fn hex_words(words: &[u32]) -> String {
+    words
+        .iter()
+        .map(|word| hex::encode(word.to_le_bytes()))
+        .collect()
+}
+
+/// Bytes of an instruction, which is serialized with one word per byte
+pub(crate) fn instruction_bytes(instruction_data: &[u32]) -> Result<Vec<u8>, DecodeError> {
+    instruction_data
+        .iter()
+        .map(|&word| {
+            u8::try_from(word).map_err(|_| DecodeError(format!("Word {word} is not a byte")))
+        })
+        .collect()
+}
+
+fn accounts<const N: usize>(account_ids: &[AccountId]) -> Result<[String; N], DecodeError> {
+    let account_ids: &[AccountId; N] = account_ids
+        .try_into()
+        .map_err(|_| DecodeError(format!("Expected {N} accounts, got {}", account_ids.len())))?;
+    Ok(account_ids.map(|account_id| account_id.to_string()))
+}
+
+fn u128_at(bytes: &[u8], offset: usize) -> u128 {
+    u128::from_le_bytes(bytes[offset..offset + 16].try_into().unwrap())
+}
+
+/// Native transfers, including ones to the burn account, claims of default accounts and their
+/// assignments to other programs
+struct AuthenticatedTransferDecoder;
+
+impl InstructionDecoder for AuthenticatedTransferDecoder {
+    fn decode(
+        &self,
+        instruction_data: &[u32],
+        account_ids: &[AccountId],
+    ) -> Result<Value, DecodeError> {
+        if let ([account_id], Ok(image_id)) = (account_ids, <[u32; 8]>::try_from(instruction_data))
+        {
+            return Ok(json!({
+                "call": "native_assign",
+                "account_id": account_id.to_string(),
+                "program_id": ProgramId::new(image_id).to_string(),
+            }));
+        }
+
+        let amount: u128 = risc0_zkvm::serde::from_slice(instruction_data)
+            .map_err(|err| DecodeError(format!("Invalid amount: {err}")))?;
+
+        match (account_ids, amount) {
+            ([account_id], 0) => Ok(json!({
+                "call": "native_initialize",
+                "account_id": account_id.to_string(),
+            })),
+            ([sender, BURN_ACCOUNT_ID], amount) => Ok(json!({
+                "call": "native_burn",
+                "sender": sender.to_string(),
+                "amount": amount.to_string(),
+            })),
+            ([sender, recipient], amount) => Ok(json!({
+                "call": "native_transfer",
+                "sender": sender.to_string(),
+                "recipient": recipient.to_string(),
+                "amount": amount.to_string(),
+            })),
+            _ => Err(DecodeError(format!(
+                "Unexpected {} accounts for amount {amount}",
+                account_ids.len()
+            ))),
+        }
+    }
+}
+
+/// Minting of new tokens, token transfers and initialization of token accounts, see the token
+/// program for the layout
+struct TokenDecoder;
+
+impl InstructionDecoder for TokenDecoder {
+    fn decode(
+        &self,
+        instruction_data: &[u32],
+        account_ids: &[AccountId],
+    ) -> Result<Value, DecodeError> {
+        let bytes = instruction_bytes(instruction_data)?;
+        if bytes.len() != 23 {
+            return Err(DecodeError(format!(
+                "Expected 23 bytes of instruction, got {}",
+                bytes.len()
+            )));
+        }
+
+        match bytes[0] {
+            0 => {
+                let [definition, holding] = accounts(account_ids)?;
+                Ok(json!({
+                    "call": "token_new_definition",
+                    "definition": definition,
+                    "holding": holding,
+                    "name": String::from_utf8_lossy(&bytes[17..]),
+                    "total_supply": u128_at(&bytes, 1).to_string(),
+                }))
+            }
+            1 => {
+                let [sender, recipient] = accounts(account_ids)?;
+                Ok(json!({
+                    "call": "token_transfer",
+                    "sender": sender,
+                    "recipient": recipient,
+                    "amount": u128_at(&bytes, 1).to_string(),
+                }))
+            }
+            2 => {
+                let [definition, account_id] = accounts(account_ids)?;
+                Ok(json!({
+                    "call": "token_initialize_account",
+                    "definition": definition,
+                    "account_id": account_id,
+                }))
+            }
+            function => Err(DecodeError(format!("Unknown token function {function}"))),
+        }
+    }
+}
+
+/// Creation, claims and refunds of escrows, see the escrow program for the layout
+struct EscrowDecoder;
+
+impl InstructionDecoder for EscrowDecoder {
+    fn decode(
+        &self,
+        instruction_data: &[u32],
+        account_ids: &[AccountId],
+    ) -> Result<Value, DecodeError> {
+        // Instruction is a byte vector, which is prefixed with its length
+        let Some((&len, bytes)) = instruction_data.split_first() else {
+            return Err(DecodeError("Instruction is empty".to_string()));
+        };
+        let bytes = instruction_bytes(bytes)?;
+        if usize::try_from(len).ok() != Some(bytes.len()) {
+            return Err(DecodeError(format!(
+                "Instruction length {len} doesn't match {} bytes",
+                bytes.len()
+            )));
+        }
+
+        match bytes.as_slice() {
+            [0, rest @ ..] if rest.len() == 88 => {
+                let [funder, escrow] = accounts(account_ids)?;
+                Ok(json!({
+                    "call": "escrow_create",
+                    "funder": funder,
+                    "escrow": escrow,
+                    "recipient": AccountId::new(rest[24..56].try_into().unwrap()).to_string(),
+                    "amount": u128_at(rest, 0).to_string(),
+                    "timeout": u64::from_le_bytes(rest[16..24].try_into().unwrap()),
+                }))
+            }
+            [1] => {
+                let [escrow, recipient, _clock] = accounts(account_ids)?;
+                Ok(json!({
+                    "call": "escrow_claim",
+                    "escrow": escrow,
+                    "recipient": recipient,
+                }))
+            }
+            [2] => {
+                let [escrow, funder, _clock] = accounts(account_ids)?;
+                Ok(json!({
+                    "call": "escrow_refund",
+                    "escrow": escrow,
+                    "funder": funder,
+                }))
+            }
+            _ => Err(DecodeError(format!(
+                "Unknown escrow instruction of {} bytes",
+                bytes.len()
+            ))),
+        }
+    }
+}
+
+/// Claims of the faucet prize of both pinata programs, the winner is the last account
+struct PinataDecoder;
+
+impl InstructionDecoder for PinataDecoder {
+    fn decode(
+        &self,
+        instruction_data: &[u32],
+        account_ids: &[AccountId],
+    ) -> Result<Value, DecodeError> {
+        let solution: u128 = risc0_zkvm::serde::from_slice(instruction_data)
+            .map_err(|err| DecodeError(format!("Invalid solution: {err}")))?;
+        let winner = account_ids
+            .last()
+            .ok_or_else(|| DecodeError("Expected winner account".to_string()))?;
+
+        Ok(json!({
+            "call": "pinata_claim",
+            "winner": winner.to_string(),
+            "solution": solution.to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words<T: serde::Serialize>(instruction: &T) -> Vec<u32> {
+        risc0_zkvm::serde::to_vec(instruction).unwrap()
+    }
+
+    fn decode(program: BuiltinProgram, instruction: &[u32], account_ids: &[AccountId]) -> Value {
+        DecoderRegistry::default().decode(&program.id(), instruction, account_ids)
+    }
+
+    #[test]
+    fn test_authenticated_transfer_decoder() {
+        let sender = AccountId::new([1; 32]);
+        let recipient = AccountId::new([2; 32]);
+        let program = BuiltinProgram::AuthenticatedTransfer;
+
+        assert_eq!(
+            decode(program, &words(&42_u128), &[sender, recipient]),
+            json!({
+                "call": "native_transfer",
+                "sender": sender.to_string(),
+                "recipient": recipient.to_string(),
+                "amount": "42",
+            })
+        );
+        assert_eq!(
+            decode(program, &words(&0_u128), &[sender]),
+            json!({ "call": "native_initialize", "account_id": sender.to_string() })
+        );
+        assert_eq!(
+            decode(program, &words(&u128::MAX), &[sender, BURN_ACCOUNT_ID]),
+            json!({
+                "call": "native_burn",
+                "sender": sender.to_string(),
+                "amount": u128::MAX.to_string(),
+            })
+        );
+        let program_id = ProgramId::new([7; 8]);
+        assert_eq!(
+            decode(program, &program_id.image_id(), &[sender]),
+            json!({
+                "call": "native_assign",
+                "account_id": sender.to_string(),
+                "program_id": program_id.to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_token_decoder() {
+        let first = AccountId::new([1; 32]);
+        let second = AccountId::new([2; 32]);
+        let mut instruction = [0_u8; 23];
+        instruction[1..17].copy_from_slice(&1000_u128.to_le_bytes());
+        instruction[17..].copy_from_slice(b"TOKEN!");
+
+        assert_eq!(
+            decode(
+                BuiltinProgram::Token,
+                &words(&instruction),
+                &[first, second]
+            ),
+            json!({
+                "call": "token_new_definition",
+                "definition": first.to_string(),
+                "holding": second.to_string(),
+                "name": "TOKEN!",
+                "total_supply": "1000",
+            })
+        );
+
+        instruction[0] = 1;
+        assert_eq!(
+            decode(
+                BuiltinProgram::Token,
+                &words(&instruction),
+                &[first, second]
+            )["amount"],
+            "1000"
+        );
+
+        let mut instruction = [0_u8; 23];
+        instruction[0] = 2;
+        assert_eq!(
+            decode(
+                BuiltinProgram::Token,
+                &words(&instruction),
+                &[first, second]
+            ),
+            json!({
+                "call": "token_initialize_account",
+                "definition": first.to_string(),
+                "account_id": second.to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_escrow_decoder() {
+        let funder = AccountId::new([1; 32]);
+        let escrow = AccountId::new([2; 32]);
+        let recipient = AccountId::new([3; 32]);
+        let clock = AccountId::new([4; 32]);
+
+        let mut instruction = vec![0];
+        instruction.extend_from_slice(&500_u128.to_le_bytes());
+        instruction.extend_from_slice(&77_u64.to_le_bytes());
+        instruction.extend_from_slice(recipient.value());
+        instruction.extend_from_slice(&BuiltinProgram::Escrow.id().to_bytes());
+
+        assert_eq!(
+            decode(
+                BuiltinProgram::Escrow,
+                &words(&instruction),
+                &[funder, escrow]
+            ),
+            json!({
+                "call": "escrow_create",
+                "funder": funder.to_string(),
+                "escrow": escrow.to_string(),
+                "recipient": recipient.to_string(),
+                "amount": "500",
+                "timeout": 77,
+            })
+        );
+        assert_eq!(
+            decode(
+                BuiltinProgram::Escrow,
+                &words(&vec![1_u8]),
+                &[escrow, recipient, clock]
+            ),
+            json!({
+                "call": "escrow_claim",
+                "escrow": escrow.to_string(),
+                "recipient": recipient.to_string(),
+            })
+        );
+        assert_eq!(
+            decode(
+                BuiltinProgram::Escrow,
+                &words(&vec![2_u8]),
+                &[escrow, funder, clock]
+            )["call"],
+            "escrow_refund"
+        );
+    }
+
+    #[test]
+    fn test_pinata_decoder() {
+        let pinata = AccountId::new([1; 32]);
+        let winner = AccountId::new([2; 32]);
+
+        for program in [BuiltinProgram::Pinata, BuiltinProgram::PinataToken] {
+            assert_eq!(
+                decode(program, &words(&12345_u128), &[pinata, winner]),
+                json!({
+                    "call": "pinata_claim",
+                    "winner": winner.to_string(),
+                    "solution": "12345",
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_program_falls_back_to_hex() {
+        let program_id = ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+
+        assert_eq!(
+            DecoderRegistry::default().decode(&program_id, &[0xdead_beef, 1], &[]),
+            json!({
+                "call": "unknown",
+                "program_id": "prog_0100000002000000030000000400000005000000060000000700000008000000",
+                "data": "efbeadde01000000",
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejected_instruction_falls_back_to_hex_with_reason() {
+        let program_id = BuiltinProgram::Token.id();
+
+        let decoded =
+            DecoderRegistry::default().decode(&program_id, &[7; 23], &[AccountId::new([1; 32])]);
+
+        assert_eq!(decoded["call"], "unknown");
+        assert_eq!(decoded["data"], hex_words(&[7; 23]));
+        assert_eq!(decoded["error"], "Unknown token function 7");
+    }
+
+    #[test]
+    fn test_registered_decoder_is_used() {
+        struct MemoDecoder;
+
+        impl InstructionDecoder for MemoDecoder {
+            fn decode(
+                &self,
+                instruction_data: &[u32],
+                _: &[AccountId],
+            ) -> Result<Value, DecodeError> {
+                Ok(json!({ "call": "memo", "words": instruction_data.len() }))
+            }
+        }
+
+        let program_id = ProgramId::new([9; 8]);
+        let mut registry = DecoderRegistry::empty();
+        assert!(!registry.is_registered(&program_id));
+        assert_eq!(
+            registry.decode(&BuiltinProgram::Token.id(), &[], &[])["call"],
+            "unknown"
+        );
+
+        registry.register(program_id, MemoDecoder);
+
+        assert!(registry.is_registered(&program_id));
+        assert_eq!(
+            registry.decode(&program_id, &[1, 2], &[]),
+            json!({ "call": "memo", "words": 2 })
+        );
+    }
+}
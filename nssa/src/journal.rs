@@ -0,0 +1,49 @@
+//! Journals of zkVM receipts, which guests write with `env::commit`
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::error::NssaError;
+
+/// Encodes `value` the same way a guest commits it to the journal
+pub fn encode_journal<T: Serialize>(value: &T) -> Result<Vec<u8>, NssaError> {
+    let words = risc0_zkvm::serde::to_vec(value)
+        .map_err(|e| NssaError::JournalEncodingError(e.to_string()))?;
+    Ok(words.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
+
+/// Decodes a value, which a guest committed to `journal`
+pub fn decode_journal<T: DeserializeOwned>(journal: &[u8]) -> Result<T, NssaError> {
+    risc0_zkvm::serde::from_slice(journal)
+        .map_err(|e| NssaError::JournalDecodingError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use nssa_core::BlockProofJournal;
+    use risc0_zkvm::Journal;
+
+    use super::*;
+
+    #[test]
+    fn test_encoded_journal_is_decoded_as_committed_by_guest() {
+        let journal = BlockProofJournal {
+            pre_state_root: [1; 32],
+            post_state_root: [2; 32],
+            block_hash: [3; 32],
+            tx_count: 7,
+        };
+
+        let bytes = encode_journal(&journal).unwrap();
+
+        let decoded: BlockProofJournal = Journal::new(bytes.clone()).decode().unwrap();
+        assert_eq!(decoded, journal);
+        assert_eq!(
+            decode_journal::<BlockProofJournal>(&bytes).unwrap(),
+            journal
+        );
+        assert!(matches!(
+            decode_journal::<BlockProofJournal>(&bytes[..bytes.len() - 4]),
+            Err(NssaError::JournalDecodingError(_))
+        ));
+    }
+}
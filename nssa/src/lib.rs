@@ -7,22 +7,37 @@ pub mod program_methods {
 #[allow(clippy::single_component_path_imports)]
 use program_methods;
 
+pub mod account_tree;
 pub mod encoding;
+pub mod entropy;
 pub mod error;
+pub mod gas;
+pub mod instruction_decoder;
+pub mod journal;
 mod merkle_tree;
 pub mod privacy_preserving_transaction;
 pub mod program;
+pub mod program_allow_list;
 pub mod program_deployment_transaction;
+pub mod prover;
 pub mod public_transaction;
-mod signature;
+pub mod signature;
 mod state;
 
-pub use nssa_core::account::{Account, AccountId};
+pub use nssa_core::{
+    account::{Account, AccountId},
+    burn::BURN_ACCOUNT_ID,
+    program::ProgramId,
+    units::{Amount, Cycles, Gas},
+};
 pub use privacy_preserving_transaction::{
-    PrivacyPreservingTransaction, circuit::execute_and_prove,
+    PrivacyPreservingTransaction,
+    circuit::{ProveOptions, execute_and_prove, execute_and_prove_with_options},
 };
+pub use program_allow_list::{BuiltinProgram, ProgramAllowList};
 pub use program_deployment_transaction::ProgramDeploymentTransaction;
 pub use program_methods::PRIVACY_PRESERVING_CIRCUIT_ID;
+pub use prover::ProverBackend;
 pub use public_transaction::PublicTransaction;
-pub use signature::{PrivateKey, PublicKey, Signature};
-pub use state::V02State;
+pub use signature::{PrivateKey, PublicKey, Signature, SignatureScheme, Signer};
+pub use state::{Anchor, DEFAULT_ANCHOR_WINDOW, StateSize, V02State};
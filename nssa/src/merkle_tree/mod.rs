@@ -19,6 +19,7 @@ fn hash_value(value: &Value) -> Node {
     hasher.finalize().into()
 }
 
+#[derive(Clone)]
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub struct MerkleTree {
     nodes: Vec<Node>,
@@ -2,33 +2,104 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use nssa_core::{
     MembershipProof, NullifierPublicKey, NullifierSecretKey, PrivacyPreservingCircuitInput,
     PrivacyPreservingCircuitOutput, SharedSecretKey,
-    account::AccountWithMetadata,
+    account::{AccountWithMetadata, Nonce},
     program::{InstructionData, ProgramOutput},
 };
-use risc0_zkvm::{ExecutorEnv, InnerReceipt, Receipt, default_prover};
+use risc0_zkvm::{ExecutorEnv, ExecutorEnvBuilder, InnerReceipt, Receipt};
 
 use crate::{
     error::NssaError,
-    program::Program,
+    program::{Program, is_cycle_limit_exceeded},
     program_methods::{PRIVACY_PRESERVING_CIRCUIT_ELF, PRIVACY_PRESERVING_CIRCUIT_ID},
+    prover::{ProverBackend, is_dev_receipt, verify_receipt},
 };
 
 /// Proof of the privacy preserving execution circuit
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct Proof(pub(crate) Vec<u8>);
 
+/// Prover settings of [`execute_and_prove_with_options`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProveOptions {
+    /// Maximum number of cycles of the program execution, unbounded if not set
+    pub cycle_limit: Option<u64>,
+    /// Size of proven segments as a power of 2 cycles, the prover default if not set.
+    /// Lower values reduce memory consumption of proving.
+    pub segment_po2: Option<u32>,
+    /// Frames written to the program stdin after its inputs, readable with
+    /// `risc0_zkvm::guest::env::read_frame`
+    pub write_frames: Vec<Vec<u8>>,
+    /// Backend producing the receipts, real proofs by default
+    pub backend: ProverBackend,
+}
+
+impl ProveOptions {
+    pub fn with_cycle_limit(mut self, cycle_limit: u64) -> Self {
+        self.cycle_limit = Some(cycle_limit);
+        self
+    }
+
+    pub fn with_segment_po2(mut self, segment_po2: u32) -> Self {
+        self.segment_po2 = Some(segment_po2);
+        self
+    }
+
+    pub fn with_write_frame(mut self, frame: Vec<u8>) -> Self {
+        self.write_frames.push(frame);
+        self
+    }
+
+    pub fn with_backend(mut self, backend: ProverBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    fn apply_segment_po2(&self, env_builder: &mut ExecutorEnvBuilder) {
+        if let Some(segment_po2) = self.segment_po2 {
+            env_builder.segment_limit_po2(segment_po2);
+        }
+    }
+}
+
 /// Generates a proof of the execution of a NSSA program inside the privacy preserving execution
 /// circuit
 pub fn execute_and_prove(
     pre_states: &[AccountWithMetadata],
     instruction_data: &InstructionData,
     visibility_mask: &[u8],
-    private_account_nonces: &[u128],
+    private_account_nonces: &[Nonce],
     private_account_keys: &[(NullifierPublicKey, SharedSecretKey)],
     private_account_auth: &[(NullifierSecretKey, MembershipProof)],
     program: &Program,
 ) -> Result<(PrivacyPreservingCircuitOutput, Proof), NssaError> {
-    let inner_receipt = execute_and_prove_program(program, pre_states, instruction_data)?;
+    let (circuit_output, proof, _program_cycles) = execute_and_prove_with_options(
+        pre_states,
+        instruction_data,
+        visibility_mask,
+        private_account_nonces,
+        private_account_keys,
+        private_account_auth,
+        program,
+        &ProveOptions::default(),
+    )?;
+    Ok((circuit_output, proof))
+}
+
+/// Same as [`execute_and_prove`], but proves with the given `options`. Also returns the number
+/// of user cycles of the program execution.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_and_prove_with_options(
+    pre_states: &[AccountWithMetadata],
+    instruction_data: &InstructionData,
+    visibility_mask: &[u8],
+    private_account_nonces: &[Nonce],
+    private_account_keys: &[(NullifierPublicKey, SharedSecretKey)],
+    private_account_auth: &[(NullifierSecretKey, MembershipProof)],
+    program: &Program,
+    options: &ProveOptions,
+) -> Result<(PrivacyPreservingCircuitOutput, Proof, u64), NssaError> {
+    let (inner_receipt, program_cycles) =
+        execute_and_prove_program(program, pre_states, instruction_data, options)?;
 
     let program_output: ProgramOutput = inner_receipt
         .journal
@@ -48,46 +119,81 @@ pub fn execute_and_prove(
     let mut env_builder = ExecutorEnv::builder();
     env_builder.add_assumption(inner_receipt);
     env_builder.write(&circuit_input).unwrap();
+    options.apply_segment_po2(&mut env_builder);
     let env = env_builder.build().unwrap();
-    let prover = default_prover();
-    let prove_info = prover
-        .prove(env, PRIVACY_PRESERVING_CIRCUIT_ELF)
+    let (receipt, _circuit_cycles) = options
+        .backend
+        .prove(
+            env,
+            PRIVACY_PRESERVING_CIRCUIT_ELF,
+            PRIVACY_PRESERVING_CIRCUIT_ID,
+        )
         .map_err(|e| NssaError::CircuitProvingError(e.to_string()))?;
 
-    let proof = Proof(borsh::to_vec(&prove_info.receipt.inner)?);
+    let proof = Proof(borsh::to_vec(&receipt.inner)?);
 
-    let circuit_output: PrivacyPreservingCircuitOutput = prove_info
-        .receipt
+    let circuit_output: PrivacyPreservingCircuitOutput = receipt
         .journal
         .decode()
         .map_err(|e| NssaError::CircuitOutputDeserializationError(e.to_string()))?;
 
-    Ok((circuit_output, proof))
+    Ok((circuit_output, proof, program_cycles))
 }
 
-fn execute_and_prove_program(
+/// Proves execution of `program` and returns its receipt together with the number of user
+/// cycles of the execution
+pub(crate) fn execute_and_prove_program(
     program: &Program,
     pre_states: &[AccountWithMetadata],
     instruction_data: &InstructionData,
-) -> Result<Receipt, NssaError> {
+    options: &ProveOptions,
+) -> Result<(Receipt, u64), NssaError> {
     // Write inputs to the program
     let mut env_builder = ExecutorEnv::builder();
     Program::write_inputs(pre_states, instruction_data, &mut env_builder)?;
-    let env = env_builder.build().unwrap();
-
-    // Prove the program
-    let prover = default_prover();
-    Ok(prover
-        .prove(env, program.elf())
-        .map_err(|e| NssaError::ProgramProveFailed(e.to_string()))?
-        .receipt)
+    for frame in &options.write_frames {
+        env_builder.write_frame(frame);
+    }
+    env_builder.session_limit(options.cycle_limit);
+    options.apply_segment_po2(&mut env_builder);
+    let env = env_builder
+        .build()
+        .map_err(|e| NssaError::ProgramWriteInputFailed(e.to_string()))?;
+
+    // Prove the program. User cycles are the ones limited by the session limit and charged for
+    options
+        .backend
+        .prove(env, program.elf(), program.id())
+        .map_err(|e| match options.cycle_limit {
+            Some(cycle_limit) if is_cycle_limit_exceeded(&e) => {
+                NssaError::CycleLimitExceeded(cycle_limit)
+            }
+            _ => NssaError::ProgramProveFailed(e.to_string()),
+        })
 }
 
 impl Proof {
-    pub(crate) fn is_valid_for(&self, circuit_output: &PrivacyPreservingCircuitOutput) -> bool {
-        let inner: InnerReceipt = borsh::from_slice(&self.0).unwrap();
-        let receipt = Receipt::new(inner, circuit_output.to_bytes());
-        receipt.verify(PRIVACY_PRESERVING_CIRCUIT_ID).is_ok()
+    /// Verifies the proof of `circuit_output`, proofs of [`ProverBackend::DevFake`] are valid
+    /// only if `accept_dev_proofs`
+    pub(crate) fn check_for(
+        &self,
+        circuit_output: &PrivacyPreservingCircuitOutput,
+        accept_dev_proofs: bool,
+    ) -> Result<(), NssaError> {
+        let Ok(inner) = borsh::from_slice::<InnerReceipt>(&self.0) else {
+            return Err(NssaError::InvalidPrivacyPreservingProof);
+        };
+        verify_receipt(
+            &Receipt::new(inner, circuit_output.to_bytes()),
+            PRIVACY_PRESERVING_CIRCUIT_ID,
+            accept_dev_proofs,
+            NssaError::InvalidPrivacyPreservingProof,
+        )
+    }
+
+    /// Whether the proof is produced by [`ProverBackend::DevFake`]
+    pub fn is_dev(&self) -> bool {
+        borsh::from_slice::<InnerReceipt>(&self.0).is_ok_and(|inner| is_dev_receipt(&inner))
     }
 }
 
@@ -96,6 +202,7 @@ mod tests {
     use nssa_core::{
         Commitment, DUMMY_COMMITMENT_HASH, EncryptionScheme, Nullifier,
         account::{Account, AccountId, AccountWithMetadata, data::Data},
+        units::Amount,
     };
 
     use super::*;
@@ -115,7 +222,7 @@ mod tests {
         let sender = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -132,14 +239,14 @@ mod tests {
 
         let expected_sender_post = Account {
             program_owner: program.id(),
-            balance: 100 - balance_to_move,
+            balance: Amount(100 - balance_to_move),
             nonce: 1,
             data: Data::default(),
         };
 
         let expected_recipient_post = Account {
             program_owner: program.id(),
-            balance: balance_to_move,
+            balance: Amount(balance_to_move),
             nonce: 0xdeadbeef,
             data: Data::default(),
         };
@@ -160,7 +267,7 @@ mod tests {
         )
         .unwrap();
 
-        assert!(proof.is_valid_for(&output));
+        assert!(proof.check_for(&output, false).is_ok());
 
         let [sender_pre] = output.public_pre_states.try_into().unwrap();
         let [sender_post] = output.public_post_states.try_into().unwrap();
@@ -188,7 +295,7 @@ mod tests {
 
         let sender_pre = AccountWithMetadata::new(
             Account {
-                balance: 100,
+                balance: Amount(100),
                 nonce: 0xdeadbeef,
                 program_owner: program.id(),
                 data: Data::default(),
@@ -223,13 +330,13 @@ mod tests {
 
         let expected_private_account_1 = Account {
             program_owner: program.id(),
-            balance: 100 - balance_to_move,
+            balance: Amount(100 - balance_to_move),
             nonce: 0xdeadbeef1,
             ..Default::default()
         };
         let expected_private_account_2 = Account {
             program_owner: program.id(),
-            balance: balance_to_move,
+            balance: Amount(balance_to_move),
             nonce: 0xdeadbeef2,
             ..Default::default()
         };
@@ -261,7 +368,7 @@ mod tests {
         )
         .unwrap();
 
-        assert!(proof.is_valid_for(&output));
+        assert!(proof.check_for(&output, false).is_ok());
         assert!(output.public_pre_states.is_empty());
         assert!(output.public_post_states.is_empty());
         assert_eq!(output.new_commitments, expected_new_commitments);
@@ -286,4 +393,60 @@ mod tests {
         .unwrap();
         assert_eq!(recipient_post, expected_private_account_2);
     }
+
+    fn prove_looper(iterations: u64, options: &ProveOptions) -> Result<(Receipt, u64), NssaError> {
+        let account = AccountWithMetadata::new(Account::default(), false, AccountId::new([0; 32]));
+        execute_and_prove_program(
+            &Program::looper(),
+            &[account],
+            &Program::serialize_instruction(iterations).unwrap(),
+            options,
+        )
+    }
+
+    #[test]
+    fn test_prove_program_returns_cycle_count() {
+        let (_, short_cycles) = prove_looper(0, &ProveOptions::default()).unwrap();
+        let (_, long_cycles) = prove_looper(100_000, &ProveOptions::default()).unwrap();
+
+        assert!(short_cycles > 0);
+        assert!(long_cycles > short_cycles);
+    }
+
+    #[test]
+    fn test_prove_program_exceeding_cycle_limit() {
+        let options = ProveOptions::default().with_cycle_limit(1 << 16);
+
+        let result = prove_looper(10_000_000, &options);
+
+        assert!(matches!(result, Err(NssaError::CycleLimitExceeded(limit)) if limit == 1 << 16));
+        // Limit is only checked, when it's set
+        assert!(prove_looper(0, &options).is_ok());
+    }
+
+    #[test]
+    fn test_prove_program_with_segment_po2() {
+        let options = ProveOptions::default().with_segment_po2(16);
+
+        let (receipt, _) = prove_looper(100_000, &options).unwrap();
+
+        assert!(receipt.verify(Program::looper().id()).is_ok());
+    }
+
+    #[test]
+    fn test_prove_program_with_write_frame() {
+        let account = AccountWithMetadata::new(Account::default(), false, AccountId::new([0; 32]));
+        let options = ProveOptions::default().with_write_frame(vec![1, 2, 3]);
+
+        let (receipt, _) = execute_and_prove_program(
+            &Program::frame_reader(),
+            &[account],
+            &Program::serialize_instruction(()).unwrap(),
+            &options,
+        )
+        .unwrap();
+
+        let output: ProgramOutput = receipt.journal.decode().unwrap();
+        assert_eq!(output.post_states[0].account().data.as_ref(), [1, 2, 3]);
+    }
 }
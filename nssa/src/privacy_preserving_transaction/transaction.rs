@@ -3,7 +3,7 @@ use std::collections::{HashMap, HashSet};
 use borsh::{BorshDeserialize, BorshSerialize};
 use nssa_core::{
     Commitment, CommitmentSetDigest, Nullifier, PrivacyPreservingCircuitOutput,
-    account::{Account, AccountWithMetadata},
+    account::{Account, AccountWithMetadata, Nonce},
 };
 
 use super::{message::Message, witness_set::WitnessSet};
@@ -84,6 +84,11 @@ impl PrivacyPreservingTransaction {
             if current_nonce != *nonce {
                 return Err(NssaError::InvalidInput("Nonce mismatch".into()));
             }
+            if current_nonce == Nonce::MAX {
+                return Err(NssaError::InvalidInput(format!(
+                    "Nonce of account {account_id} is exhausted"
+                )));
+            }
         }
 
         // Build pre_states for proof verification
@@ -107,6 +112,7 @@ impl PrivacyPreservingTransaction {
             &message.encrypted_private_post_states,
             &message.new_commitments,
             &message.new_nullifiers,
+            state.accept_dev_proofs(),
         )?;
 
         // 5. Commitment freshness
@@ -115,12 +121,17 @@ impl PrivacyPreservingTransaction {
         // 6. Nullifier uniqueness
         state.check_nullifiers_are_valid(&message.new_nullifiers)?;
 
-        Ok(message
+        let public_state_diff = message
             .public_account_ids
             .iter()
             .cloned()
             .zip(message.public_post_states.clone())
-            .collect())
+            .collect();
+
+        // 7. Public accounts keep storage deposits, private ones are not stored by nodes
+        state.check_storage_deposits(&public_state_diff)?;
+
+        Ok(public_state_diff)
     }
 
     pub fn message(&self) -> &Message {
@@ -131,12 +142,8 @@ impl PrivacyPreservingTransaction {
         &self.witness_set
     }
 
-    pub(crate) fn signer_account_ids(&self) -> Vec<AccountId> {
-        self.witness_set
-            .signatures_and_public_keys()
-            .iter()
-            .map(|(_, public_key)| AccountId::from(public_key))
-            .collect()
+    pub fn signer_account_ids(&self) -> Vec<AccountId> {
+        self.witness_set.signer_account_ids()
     }
 }
 
@@ -147,6 +154,7 @@ fn check_privacy_preserving_circuit_proof_is_valid(
     encrypted_private_post_states: &[EncryptedAccountData],
     new_commitments: &[Commitment],
     new_nullifiers: &[(Nullifier, CommitmentSetDigest)],
+    accept_dev_proofs: bool,
 ) -> Result<(), NssaError> {
     let output = PrivacyPreservingCircuitOutput {
         public_pre_states: public_pre_states.to_vec(),
@@ -159,10 +167,7 @@ fn check_privacy_preserving_circuit_proof_is_valid(
         new_commitments: new_commitments.to_vec(),
         new_nullifiers: new_nullifiers.to_vec(),
     };
-    proof
-        .is_valid_for(&output)
-        .then_some(())
-        .ok_or(NssaError::InvalidPrivacyPreservingProof)
+    proof.check_for(&output, accept_dev_proofs)
 }
 
 use std::hash::Hash;
@@ -1,48 +1,64 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 
 use crate::{
-    PrivateKey, PublicKey, Signature,
+    AccountId, PrivateKey, PublicKey, Signature,
     privacy_preserving_transaction::{circuit::Proof, message::Message},
+    signature::{self, SignatureScheme, Signer},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct WitnessSet {
+    pub(crate) scheme: SignatureScheme,
     pub(crate) signatures_and_public_keys: Vec<(Signature, PublicKey)>,
     pub(crate) proof: Proof,
 }
 
 impl WitnessSet {
     pub fn for_message(message: &Message, proof: Proof, private_keys: &[&PrivateKey]) -> Self {
-        let message_bytes = message.to_bytes();
-        let signatures_and_public_keys = private_keys
-            .iter()
-            .map(|&key| {
-                (
-                    Signature::new(key, &message_bytes),
-                    PublicKey::new_from_private_key(key),
-                )
-            })
-            .collect();
+        Self::for_message_with_signers(message, proof, private_keys)
+    }
+
+    /// Same as [`Self::for_message`], but signs with any signers of the same scheme
+    pub fn for_message_with_signers<S: Signer>(
+        message: &Message,
+        proof: Proof,
+        signers: &[&S],
+    ) -> Self {
         Self {
+            scheme: S::SCHEME,
+            signatures_and_public_keys: signature::sign_message(&message.to_bytes(), signers, None),
             proof,
-            signatures_and_public_keys,
         }
     }
 
     pub fn signatures_are_valid_for(&self, message: &Message) -> bool {
-        let message_bytes = message.to_bytes();
-        for (signature, public_key) in self.signatures_and_public_keys() {
-            if !signature.is_valid_for(&message_bytes, public_key) {
-                return false;
-            }
-        }
-        true
+        signature::verify_batch(
+            self.scheme,
+            &self.scheme.sighash(&message.to_bytes()),
+            self.signatures_and_public_keys
+                .iter()
+                .map(|(signature, public_key)| {
+                    (public_key.value().as_slice(), signature.value().as_slice())
+                }),
+        )
+    }
+
+    pub fn scheme(&self) -> SignatureScheme {
+        self.scheme
     }
 
     pub fn signatures_and_public_keys(&self) -> &[(Signature, PublicKey)] {
         &self.signatures_and_public_keys
     }
 
+    /// Accounts of the signers, in the order of signatures
+    pub fn signer_account_ids(&self) -> Vec<AccountId> {
+        self.signatures_and_public_keys
+            .iter()
+            .map(|(_, public_key)| signature::account_id(self.scheme, public_key.value()))
+            .collect()
+    }
+
     pub fn proof(&self) -> &Proof {
         &self.proof
     }
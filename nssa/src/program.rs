@@ -6,15 +6,42 @@ use risc0_zkvm::{ExecutorEnv, ExecutorEnvBuilder, default_executor, serde::to_ve
 use serde::Serialize;
 
 use crate::{
+    BuiltinProgram,
     error::NssaError,
-    program_methods::{AUTHENTICATED_TRANSFER_ELF, MODIFIED_TRANSFER_ELF, PINATA_ELF, TOKEN_ELF},
+    instruction_decoder::instruction_bytes,
+    program_methods::{
+        AUTHENTICATED_TRANSFER_ELF, ESCROW_ELF, MODIFIED_TRANSFER_ELF, PINATA_ELF, TOKEN_ELF,
+    },
 };
 
-/// Maximum number of cycles for a public execution.
-/// TODO: Make this variable when fees are implemented
-const MAX_NUM_CYCLES_PUBLIC_EXECUTION: u64 = 1024 * 1024 * 32; // 32M cycles
+/// Maximum number of cycles for a public execution, transactions may declare a lower one
+pub(crate) const MAX_NUM_CYCLES_PUBLIC_EXECUTION: u64 = 1024 * 1024 * 32; // 32M cycles
+
+/// Maximum number of words of instruction data of a public transaction, unless chain parameters
+/// set another one
+pub const DEFAULT_MAX_INSTRUCTION_DATA_SIZE: usize = 16 * 1024; // 64 KiB
+
+/// Maximum number of bytes of bytecode of deployed programs, unless chain parameters set another
+/// one
+pub const DEFAULT_MAX_PROGRAM_SIZE: usize = 4 * 1024 * 1024; // 4 MiB
+
+/// Reason to reject an instruction without executing its program
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InstructionError {
+    #[error("Instruction data of {size} words exceeds the maximum of {max_size}")]
+    TooLarge { size: usize, max_size: usize },
+    #[error("Instruction addresses account {index}, but the call has {num_accounts} accounts")]
+    AccountIndexOutOfRange { index: usize, num_accounts: usize },
+    #[error("Instruction addresses {expected} accounts, but the call has {num_accounts}")]
+    UnexpectedAccounts {
+        expected: usize,
+        num_accounts: usize,
+    },
+    #[error("Malformed instruction: {0}")]
+    Malformed(String),
+}
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Program {
     id: ProgramId,
     elf: Vec<u8>,
@@ -24,10 +51,12 @@ impl Program {
     pub fn new(bytecode: Vec<u8>) -> Result<Self, NssaError> {
         let binary = risc0_binfmt::ProgramBinary::decode(&bytecode)
             .map_err(|_| NssaError::InvalidProgramBytecode)?;
-        let id = binary
-            .compute_image_id()
-            .map_err(|_| NssaError::InvalidProgramBytecode)?
-            .into();
+        let id = ProgramId::new(
+            binary
+                .compute_image_id()
+                .map_err(|_| NssaError::InvalidProgramBytecode)?
+                .into(),
+        );
         Ok(Self { elf: bytecode, id })
     }
 
@@ -39,28 +68,123 @@ impl Program {
         &self.elf
     }
 
+    /// Checks `instruction_data` of a call with `num_accounts` accounts without executing the
+    /// program, so malformed calls of builtin programs don't burn executor cycles
+    ///
+    /// Builtin programs address accounts by their position, so the call must have exactly the
+    /// accounts its instruction addresses. Instructions of deployed programs are opaque and
+    /// always pass, only their size is limited, see
+    /// [`crate::PublicTransaction::validate_instruction`].
+    pub fn validate_instruction(
+        &self,
+        instruction_data: &[u32],
+        num_accounts: usize,
+    ) -> Result<(), InstructionError> {
+        let Some(builtin_program) = BuiltinProgram::from_id(&self.id) else {
+            return Ok(());
+        };
+
+        let num_addressed = match builtin_program {
+            // Assignment of the only account to the program with the words of the instruction
+            BuiltinProgram::AuthenticatedTransfer if instruction_data.len() == 8 => 1,
+            // Claim of the only account or transfer from the first account to the second one
+            BuiltinProgram::AuthenticatedTransfer => {
+                match u128_instruction(instruction_data, "amount")? {
+                    0 if num_accounts == 1 => 1,
+                    _ => 2,
+                }
+            }
+            BuiltinProgram::Token => {
+                let bytes = instruction_bytes(instruction_data)
+                    .map_err(|err| InstructionError::Malformed(err.to_string()))?;
+                match bytes.as_slice() {
+                    [0..=2, rest @ ..] if rest.len() == 22 => 2,
+                    _ => {
+                        return Err(InstructionError::Malformed(format!(
+                            "Unknown token instruction of {} bytes",
+                            bytes.len()
+                        )));
+                    }
+                }
+            }
+            BuiltinProgram::Escrow => {
+                // Instruction is a byte vector, which is prefixed with its length
+                let bytes = match instruction_data.split_first() {
+                    Some((&len, bytes)) if usize::try_from(len).ok() == Some(bytes.len()) => {
+                        instruction_bytes(bytes)
+                            .map_err(|err| InstructionError::Malformed(err.to_string()))?
+                    }
+                    _ => {
+                        return Err(InstructionError::Malformed(
+                            "Length prefix doesn't match the instruction".to_string(),
+                        ));
+                    }
+                };
+                match bytes.as_slice() {
+                    [0, rest @ ..] if rest.len() == 88 => 2,
+                    [1] | [2] => 3,
+                    _ => {
+                        return Err(InstructionError::Malformed(format!(
+                            "Unknown escrow instruction of {} bytes",
+                            bytes.len()
+                        )));
+                    }
+                }
+            }
+            BuiltinProgram::Pinata => {
+                u128_instruction(instruction_data, "solution")?;
+                2
+            }
+            BuiltinProgram::PinataToken => {
+                u128_instruction(instruction_data, "solution")?;
+                3
+            }
+        };
+
+        if num_accounts < num_addressed {
+            Err(InstructionError::AccountIndexOutOfRange {
+                index: num_addressed - 1,
+                num_accounts,
+            })
+        } else if num_accounts > num_addressed {
+            Err(InstructionError::UnexpectedAccounts {
+                expected: num_addressed,
+                num_accounts,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn serialize_instruction<T: Serialize>(
         instruction: T,
     ) -> Result<InstructionData, NssaError> {
         to_vec(&instruction).map_err(|e| NssaError::InstructionSerializationError(e.to_string()))
     }
 
-    pub(crate) fn execute(
+    /// Executes the program, aborting it after `cycle_limit` cycles. Also returns the number of
+    /// executed cycles.
+    pub(crate) fn execute_with_cycle_limit(
         &self,
         pre_states: &[AccountWithMetadata],
         instruction_data: &InstructionData,
-    ) -> Result<ProgramOutput, NssaError> {
+        cycle_limit: u64,
+    ) -> Result<(ProgramOutput, u64), NssaError> {
         // Write inputs to the program
         let mut env_builder = ExecutorEnv::builder();
-        env_builder.session_limit(Some(MAX_NUM_CYCLES_PUBLIC_EXECUTION));
+        env_builder.session_limit(Some(cycle_limit));
         Self::write_inputs(pre_states, instruction_data, &mut env_builder)?;
         let env = env_builder.build().unwrap();
 
         // Execute the program (without proving)
         let executor = default_executor();
-        let session_info = executor
-            .execute(env, self.elf())
-            .map_err(|e| NssaError::ProgramExecutionFailed(e.to_string()))?;
+        let session_info = executor.execute(env, self.elf()).map_err(|e| {
+            if is_cycle_limit_exceeded(&e) {
+                NssaError::CycleLimitExceeded(cycle_limit)
+            } else {
+                NssaError::ProgramExecutionFailed(e.to_string())
+            }
+        })?;
 
         // Get outputs
         let program_output = session_info
@@ -68,7 +192,25 @@ impl Program {
             .decode()
             .map_err(|e| NssaError::ProgramExecutionFailed(e.to_string()))?;
 
-        Ok(program_output)
+        Ok((program_output, session_info.cycles()))
+    }
+
+    /// Runs the program once with empty input, aborting it after `cycle_limit` cycles, to reject
+    /// images, which can't run at all, before they are deployed
+    ///
+    /// Programs read their inputs first, so a run passes if it exits, panics on the missing
+    /// input or exhausts the cycles. Any other failure, like a fault on loading the image, fails.
+    pub fn smoke_test(&self, cycle_limit: u64) -> Result<(), NssaError> {
+        let env = ExecutorEnv::builder()
+            .session_limit(Some(cycle_limit))
+            .build()
+            .map_err(|e| NssaError::ProgramWriteInputFailed(e.to_string()))?;
+
+        match default_executor().execute(env, self.elf()) {
+            Ok(_) => Ok(()),
+            Err(e) if is_cycle_limit_exceeded(&e) || is_guest_panic(&e) => Ok(()),
+            Err(e) => Err(NssaError::ProgramSmokeTestFailed(format!("{e:#}"))),
+        }
     }
 
     /// Writes inputs to `env_builder` in the order expected by the programs
@@ -96,6 +238,12 @@ impl Program {
         Self::new(TOKEN_ELF.to_vec()).unwrap()
     }
 
+    pub fn escrow() -> Self {
+        // This unwrap won't panic since the `ESCROW_ELF` comes from risc0 build of
+        // `program_methods`
+        Self::new(ESCROW_ELF.to_vec()).unwrap()
+    }
+
     pub fn modified_transfer_program() -> Self {
         // This unwrap won't panic since the `MODIFIED_TRANSFER_ELF` comes from risc0 build of
         // `program_methods`
@@ -103,6 +251,29 @@ impl Program {
     }
 }
 
+/// Instruction, which is a single `u128` named `name` in errors
+fn u128_instruction(instruction_data: &[u32], name: &str) -> Result<u128, InstructionError> {
+    // Serialized `u128` takes exactly 4 words
+    if instruction_data.len() != 4 {
+        return Err(InstructionError::Malformed(format!(
+            "Expected {name} of 4 words, got {}",
+            instruction_data.len()
+        )));
+    }
+    risc0_zkvm::serde::from_slice(instruction_data)
+        .map_err(|err| InstructionError::Malformed(format!("Invalid {name}: {err}")))
+}
+
+/// Risc0 reports exhaustion of the session limit only in the error message
+pub(crate) fn is_cycle_limit_exceeded(err: &impl std::fmt::Display) -> bool {
+    format!("{err:#}").contains("Session limit exceeded")
+}
+
+/// Risc0 reports panics of the guest only in the error message
+fn is_guest_panic(err: &impl std::fmt::Display) -> bool {
+    format!("{err:#}").contains("Guest panicked")
+}
+
 // TODO: Testnet only. Refactor to prevent compilation on mainnet.
 impl Program {
     pub fn pinata() -> Self {
@@ -119,10 +290,15 @@ impl Program {
 
 #[cfg(test)]
 mod tests {
-    use nssa_core::account::{Account, AccountId, AccountWithMetadata};
+    use nssa_core::{
+        account::{Account, AccountId, AccountWithMetadata},
+        program::ProgramId,
+        units::Amount,
+    };
 
     use crate::{
-        program::Program,
+        BuiltinProgram,
+        program::{InstructionError, MAX_NUM_CYCLES_PUBLIC_EXECUTION, Program},
         program_methods::{
             AUTHENTICATED_TRANSFER_ELF, AUTHENTICATED_TRANSFER_ID, PINATA_ELF, PINATA_ID,
             TOKEN_ELF, TOKEN_ID,
@@ -135,7 +311,7 @@ mod tests {
             use test_program_methods::{NONCE_CHANGER_ELF, NONCE_CHANGER_ID};
 
             Program {
-                id: NONCE_CHANGER_ID,
+                id: ProgramId::new(NONCE_CHANGER_ID),
                 elf: NONCE_CHANGER_ELF.to_vec(),
             }
         }
@@ -145,7 +321,7 @@ mod tests {
             use test_program_methods::{EXTRA_OUTPUT_ELF, EXTRA_OUTPUT_ID};
 
             Program {
-                id: EXTRA_OUTPUT_ID,
+                id: ProgramId::new(EXTRA_OUTPUT_ID),
                 elf: EXTRA_OUTPUT_ELF.to_vec(),
             }
         }
@@ -155,7 +331,7 @@ mod tests {
             use test_program_methods::{MISSING_OUTPUT_ELF, MISSING_OUTPUT_ID};
 
             Program {
-                id: MISSING_OUTPUT_ID,
+                id: ProgramId::new(MISSING_OUTPUT_ID),
                 elf: MISSING_OUTPUT_ELF.to_vec(),
             }
         }
@@ -165,7 +341,7 @@ mod tests {
             use test_program_methods::{PROGRAM_OWNER_CHANGER_ELF, PROGRAM_OWNER_CHANGER_ID};
 
             Program {
-                id: PROGRAM_OWNER_CHANGER_ID,
+                id: ProgramId::new(PROGRAM_OWNER_CHANGER_ID),
                 elf: PROGRAM_OWNER_CHANGER_ELF.to_vec(),
             }
         }
@@ -175,7 +351,7 @@ mod tests {
             use test_program_methods::{SIMPLE_BALANCE_TRANSFER_ELF, SIMPLE_BALANCE_TRANSFER_ID};
 
             Program {
-                id: SIMPLE_BALANCE_TRANSFER_ID,
+                id: ProgramId::new(SIMPLE_BALANCE_TRANSFER_ID),
                 elf: SIMPLE_BALANCE_TRANSFER_ELF.to_vec(),
             }
         }
@@ -185,7 +361,7 @@ mod tests {
             use test_program_methods::{DATA_CHANGER_ELF, DATA_CHANGER_ID};
 
             Program {
-                id: DATA_CHANGER_ID,
+                id: ProgramId::new(DATA_CHANGER_ID),
                 elf: DATA_CHANGER_ELF.to_vec(),
             }
         }
@@ -195,7 +371,7 @@ mod tests {
             use test_program_methods::{MINTER_ELF, MINTER_ID};
 
             Program {
-                id: MINTER_ID,
+                id: ProgramId::new(MINTER_ID),
                 elf: MINTER_ELF.to_vec(),
             }
         }
@@ -205,7 +381,7 @@ mod tests {
             use test_program_methods::{BURNER_ELF, BURNER_ID};
 
             Program {
-                id: BURNER_ID,
+                id: ProgramId::new(BURNER_ID),
                 elf: BURNER_ELF.to_vec(),
             }
         }
@@ -214,7 +390,7 @@ mod tests {
             use test_program_methods::{CHAIN_CALLER_ELF, CHAIN_CALLER_ID};
 
             Program {
-                id: CHAIN_CALLER_ID,
+                id: ProgramId::new(CHAIN_CALLER_ID),
                 elf: CHAIN_CALLER_ELF.to_vec(),
             }
         }
@@ -223,10 +399,40 @@ mod tests {
             use test_program_methods::{CLAIMER_ELF, CLAIMER_ID};
 
             Program {
-                id: CLAIMER_ID,
+                id: ProgramId::new(CLAIMER_ID),
                 elf: CLAIMER_ELF.to_vec(),
             }
         }
+
+        /// A program that copies the data of a read-only account into a writable one
+        pub fn data_copier() -> Self {
+            use test_program_methods::{DATA_COPIER_ELF, DATA_COPIER_ID};
+
+            Program {
+                id: ProgramId::new(DATA_COPIER_ID),
+                elf: DATA_COPIER_ELF.to_vec(),
+            }
+        }
+
+        /// A program that spins for the given number of iterations
+        pub fn looper() -> Self {
+            use test_program_methods::{LOOPER_ELF, LOOPER_ID};
+
+            Program {
+                id: ProgramId::new(LOOPER_ID),
+                elf: LOOPER_ELF.to_vec(),
+            }
+        }
+
+        /// A program that stores a frame written after its inputs in account data
+        pub fn frame_reader() -> Self {
+            use test_program_methods::{FRAME_READER_ELF, FRAME_READER_ID};
+
+            Program {
+                id: ProgramId::new(FRAME_READER_ID),
+                elf: FRAME_READER_ELF.to_vec(),
+            }
+        }
     }
 
     #[test]
@@ -236,7 +442,7 @@ mod tests {
         let instruction_data = Program::serialize_instruction(balance_to_move).unwrap();
         let sender = AccountWithMetadata::new(
             Account {
-                balance: 77665544332211,
+                balance: Amount(77665544332211),
                 ..Account::default()
             },
             true,
@@ -246,15 +452,19 @@ mod tests {
             AccountWithMetadata::new(Account::default(), false, AccountId::new([1; 32]));
 
         let expected_sender_post = Account {
-            balance: 77665544332211 - balance_to_move,
+            balance: Amount(77665544332211 - balance_to_move),
             ..Account::default()
         };
         let expected_recipient_post = Account {
-            balance: balance_to_move,
+            balance: Amount(balance_to_move),
             ..Account::default()
         };
-        let program_output = program
-            .execute(&[sender, recipient], &instruction_data)
+        let (program_output, _cycles) = program
+            .execute_with_cycle_limit(
+                &[sender, recipient],
+                &instruction_data,
+                MAX_NUM_CYCLES_PUBLIC_EXECUTION,
+            )
             .unwrap();
 
         let [sender_post, recipient_post] = program_output.post_states.try_into().unwrap();
@@ -269,11 +479,79 @@ mod tests {
         let token_program = Program::token();
         let pinata_program = Program::pinata();
 
-        assert_eq!(auth_transfer_program.id, AUTHENTICATED_TRANSFER_ID);
+        assert_eq!(
+            auth_transfer_program.id,
+            ProgramId::new(AUTHENTICATED_TRANSFER_ID)
+        );
         assert_eq!(auth_transfer_program.elf, AUTHENTICATED_TRANSFER_ELF);
-        assert_eq!(token_program.id, TOKEN_ID);
+        assert_eq!(token_program.id, ProgramId::new(TOKEN_ID));
         assert_eq!(token_program.elf, TOKEN_ELF);
-        assert_eq!(pinata_program.id, PINATA_ID);
+        assert_eq!(pinata_program.id, ProgramId::new(PINATA_ID));
         assert_eq!(pinata_program.elf, PINATA_ELF);
     }
+
+    #[test]
+    fn test_builtin_instructions_are_validated_without_execution() {
+        // Validation doesn't need the bytecode
+        let builtin = |program: BuiltinProgram| Program {
+            id: program.id(),
+            elf: vec![],
+        };
+        let transfer = builtin(BuiltinProgram::AuthenticatedTransfer);
+        let amount = Program::serialize_instruction(10_u128).unwrap();
+        let claim = Program::serialize_instruction(0_u128).unwrap();
+
+        assert_eq!(transfer.validate_instruction(&amount, 2), Ok(()));
+        assert_eq!(transfer.validate_instruction(&claim, 1), Ok(()));
+        assert_eq!(
+            transfer.validate_instruction(&amount, 1),
+            Err(InstructionError::AccountIndexOutOfRange {
+                index: 1,
+                num_accounts: 1,
+            })
+        );
+        assert_eq!(
+            transfer.validate_instruction(&amount, 8),
+            Err(InstructionError::UnexpectedAccounts {
+                expected: 2,
+                num_accounts: 8,
+            })
+        );
+        assert!(matches!(
+            transfer.validate_instruction(&amount[..3], 2),
+            Err(InstructionError::Malformed(_))
+        ));
+        let assign = ProgramId::new([7; 8]).image_id();
+        assert_eq!(transfer.validate_instruction(&assign, 1), Ok(()));
+        assert_eq!(
+            transfer.validate_instruction(&assign, 2),
+            Err(InstructionError::UnexpectedAccounts {
+                expected: 1,
+                num_accounts: 2,
+            })
+        );
+
+        let escrow = builtin(BuiltinProgram::Escrow);
+        let claim = Program::serialize_instruction(vec![1_u8]).unwrap();
+        assert_eq!(escrow.validate_instruction(&claim, 3), Ok(()));
+        assert!(matches!(
+            escrow.validate_instruction(&[5, 1], 3),
+            Err(InstructionError::Malformed(_))
+        ));
+
+        let token = builtin(BuiltinProgram::Token);
+        let mut instruction = [0_u8; 23];
+        instruction[0] = 3;
+        assert!(matches!(
+            token.validate_instruction(&Program::serialize_instruction(instruction).unwrap(), 2),
+            Err(InstructionError::Malformed(_))
+        ));
+
+        // Instructions of deployed programs are opaque
+        let deployed = Program {
+            id: ProgramId::new([7; 8]),
+            elf: vec![],
+        };
+        assert_eq!(deployed.validate_instruction(&[1, 2, 3], 0), Ok(()));
+    }
 }
@@ -0,0 +1,191 @@
+//! Programs, which transactions of a network may call.
+//!
+//! The allow-list is a chain parameter: it's checked by state transitions, so every node
+//! executing blocks agrees on it, and it's committed to in the genesis block, so nodes with
+//! different lists don't accept each other's chains.
+
+use nssa_core::program::ProgramId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+
+use crate::{
+    error::NssaError,
+    program::Program,
+    program_methods::{AUTHENTICATED_TRANSFER_ID, ESCROW_ID, PINATA_ID, PINATA_TOKEN_ID, TOKEN_ID},
+};
+
+/// Program built into the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuiltinProgram {
+    AuthenticatedTransfer,
+    Token,
+    Escrow,
+    Pinata,
+    PinataToken,
+}
+
+impl BuiltinProgram {
+    pub const ALL: [Self; 5] = [
+        Self::AuthenticatedTransfer,
+        Self::Token,
+        Self::Escrow,
+        Self::Pinata,
+        Self::PinataToken,
+    ];
+
+    pub fn id(self) -> ProgramId {
+        match self {
+            Self::AuthenticatedTransfer => ProgramId::new(AUTHENTICATED_TRANSFER_ID),
+            Self::Token => ProgramId::new(TOKEN_ID),
+            Self::Escrow => ProgramId::new(ESCROW_ID),
+            Self::Pinata => ProgramId::new(PINATA_ID),
+            Self::PinataToken => ProgramId::new(PINATA_TOKEN_ID),
+        }
+    }
+
+    pub fn program(self) -> Program {
+        match self {
+            Self::AuthenticatedTransfer => Program::authenticated_transfer_program(),
+            Self::Token => Program::token(),
+            Self::Escrow => Program::escrow(),
+            Self::Pinata => Program::pinata(),
+            Self::PinataToken => Program::pinata_token(),
+        }
+    }
+
+    /// Name of the program, as written in configs
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::AuthenticatedTransfer => "authenticated_transfer",
+            Self::Token => "token",
+            Self::Escrow => "escrow",
+            Self::Pinata => "pinata",
+            Self::PinataToken => "pinata_token",
+        }
+    }
+
+    /// Builtin program with `program_id`, if any
+    pub fn from_id(program_id: &ProgramId) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|program| program.id() == *program_id)
+    }
+}
+
+/// Builtin programs, which may be called, and whether deployed programs may be deployed and called
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramAllowList {
+    pub builtin_programs: Vec<BuiltinProgram>,
+    pub deployed_programs: bool,
+}
+
+impl Default for ProgramAllowList {
+    /// Builtin programs of every network and deployed programs
+    fn default() -> Self {
+        Self {
+            builtin_programs: vec![
+                BuiltinProgram::AuthenticatedTransfer,
+                BuiltinProgram::Token,
+                BuiltinProgram::Escrow,
+            ],
+            deployed_programs: true,
+        }
+    }
+}
+
+impl ProgramAllowList {
+    pub fn is_allowed(&self, program_id: &ProgramId) -> bool {
+        match BuiltinProgram::from_id(program_id) {
+            Some(builtin_program) => self.builtin_programs.contains(&builtin_program),
+            None => self.deployed_programs,
+        }
+    }
+
+    /// Fails if calls of `program_id` are not allowed
+    pub fn check_call(&self, program_id: &ProgramId) -> Result<(), NssaError> {
+        if self.is_allowed(program_id) {
+            Ok(())
+        } else {
+            Err(NssaError::ProgramNotAllowed(*program_id))
+        }
+    }
+
+    /// Fails if programs can't be deployed
+    pub fn check_deployment(&self) -> Result<(), NssaError> {
+        if self.deployed_programs {
+            Ok(())
+        } else {
+            Err(NssaError::InvalidInput(
+                "Program deployment is not allowed".to_string(),
+            ))
+        }
+    }
+
+    /// Hash of the allow-list, which doesn't depend on order or duplicates of builtin programs
+    pub fn commitment(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for program in BuiltinProgram::ALL {
+            if self.builtin_programs.contains(&program) {
+                hasher.update(program.id().to_bytes());
+            }
+        }
+        hasher.update([u8::from(self.deployed_programs)]);
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfers_only() -> ProgramAllowList {
+        ProgramAllowList {
+            builtin_programs: vec![BuiltinProgram::AuthenticatedTransfer],
+            deployed_programs: false,
+        }
+    }
+
+    #[test]
+    fn test_only_listed_builtin_programs_are_allowed() {
+        let allow_list = transfers_only();
+
+        assert!(allow_list.is_allowed(&ProgramId::new(AUTHENTICATED_TRANSFER_ID)));
+        assert!(!allow_list.is_allowed(&ProgramId::new(TOKEN_ID)));
+        assert!(!allow_list.is_allowed(&ProgramId::new([7; 8])));
+        assert!(ProgramAllowList::default().is_allowed(&ProgramId::new([7; 8])));
+    }
+
+    #[test]
+    fn test_commitment_ignores_order_and_duplicates() {
+        let allow_list = ProgramAllowList::default();
+        let reordered = ProgramAllowList {
+            builtin_programs: vec![
+                BuiltinProgram::Escrow,
+                BuiltinProgram::Token,
+                BuiltinProgram::AuthenticatedTransfer,
+                BuiltinProgram::Token,
+            ],
+            deployed_programs: true,
+        };
+
+        assert_eq!(allow_list.commitment(), reordered.commitment());
+        assert_ne!(allow_list.commitment(), transfers_only().commitment());
+        assert_ne!(
+            allow_list.commitment(),
+            ProgramAllowList {
+                deployed_programs: false,
+                ..allow_list.clone()
+            }
+            .commitment()
+        );
+    }
+
+    #[test]
+    fn test_builtin_program_ids() {
+        for program in BuiltinProgram::ALL {
+            assert_eq!(program.program().id(), program.id());
+            assert_eq!(BuiltinProgram::from_id(&program.id()), Some(program));
+        }
+    }
+}
@@ -1,12 +1,55 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use nssa_core::{account::Nonce, units::Amount};
+
+use crate::AccountId;
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct Message {
+    /// Public account, which signs the deployment and pays for it
+    pub(crate) deployer: AccountId,
+    /// Current nonce of `deployer`
+    pub(crate) nonce: Nonce,
+    /// Chain the deployment is valid on, so it can't be replayed on another chain
+    pub(crate) chain_id: u64,
+    /// Maximum fee `deployer` agrees to pay, see [`crate::gas::GasCalculator::deployment_gas`]
+    pub(crate) max_fee: Amount,
     pub(crate) bytecode: Vec<u8>,
 }
 
 impl Message {
-    pub fn new(bytecode: Vec<u8>) -> Self {
-        Self { bytecode }
+    pub fn new(
+        deployer: AccountId,
+        nonce: Nonce,
+        chain_id: u64,
+        max_fee: Amount,
+        bytecode: Vec<u8>,
+    ) -> Self {
+        Self {
+            deployer,
+            nonce,
+            chain_id,
+            max_fee,
+            bytecode,
+        }
+    }
+
+    pub fn deployer(&self) -> AccountId {
+        self.deployer
+    }
+
+    pub fn nonce(&self) -> Nonce {
+        self.nonce
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn max_fee(&self) -> Amount {
+        self.max_fee
+    }
+
+    pub fn bytecode(&self) -> &[u8] {
+        &self.bytecode
     }
 }
@@ -1,5 +1,7 @@
 mod message;
 mod transaction;
+mod witness_set;
 
 pub use message::Message;
 pub use transaction::ProgramDeploymentTransaction;
+pub use witness_set::WitnessSet;
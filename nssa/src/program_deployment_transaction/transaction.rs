@@ -1,29 +1,123 @@
+use std::collections::HashMap;
+
 use borsh::{BorshDeserialize, BorshSerialize};
+use nssa_core::{
+    account::{Account, AccountId, Nonce},
+    units::{Amount, Gas},
+};
 
 use crate::{
-    V02State, error::NssaError, program::Program, program_deployment_transaction::message::Message,
+    PublicTransaction, V02State,
+    error::NssaError,
+    gas::{GasCalculator, PaidFees},
+    program::Program,
+    program_deployment_transaction::{Message, WitnessSet},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct ProgramDeploymentTransaction {
     pub(crate) message: Message,
+    pub(crate) witness_set: WitnessSet,
 }
 
 impl ProgramDeploymentTransaction {
-    pub fn new(message: Message) -> Self {
-        Self { message }
+    pub fn new(message: Message, witness_set: WitnessSet) -> Self {
+        Self {
+            message,
+            witness_set,
+        }
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn witness_set(&self) -> &WitnessSet {
+        &self.witness_set
+    }
+
+    /// Fee the deployer pays for the deployment, if it's applied
+    pub fn fee(&self) -> Gas {
+        GasCalculator::default().deployment_gas(self.message.bytecode.len())
+    }
+
+    /// Checks that the bytecode doesn't exceed `max_program_size` bytes and is a valid image, and
+    /// returns the program with the id computed from the image
+    ///
+    /// Nothing is executed, so mempool admission runs it to reject broken deployments early.
+    pub fn validate_program(&self, max_program_size: usize) -> Result<Program, NssaError> {
+        let size = self.message.bytecode.len();
+        if size > max_program_size {
+            return Err(NssaError::ProgramTooLarge {
+                size,
+                max_size: max_program_size,
+            });
+        }
+
+        // TODO: remove clone
+        Program::new(self.message.bytecode.clone())
     }
 
+    /// Checks the deployment against `state` and returns the deployed program with the accounts
+    /// changed by paying for it
     pub(crate) fn validate_and_produce_public_state_diff(
         &self,
         state: &V02State,
-    ) -> Result<Program, NssaError> {
-        // TODO: remove clone
-        let program = Program::new(self.message.bytecode.clone())?;
+    ) -> Result<(Program, HashMap<AccountId, Account>, PaidFees), NssaError> {
+        state.program_allow_list().check_deployment()?;
+
+        let message = self.message();
+        if !self.witness_set.is_valid_for(message) {
+            return Err(NssaError::InvalidInput(
+                "Deployment must be signed by its deployer only".into(),
+            ));
+        }
+
+        if message.chain_id != state.chain_id() {
+            return Err(NssaError::InvalidInput(format!(
+                "Deployment is for chain {}, but this is chain {}",
+                message.chain_id,
+                state.chain_id()
+            )));
+        }
+
+        let deployer = message.deployer;
+        let current_nonce = state.get_account_by_id(&deployer).nonce;
+        if current_nonce != message.nonce {
+            return Err(NssaError::InvalidInput("Nonce mismatch".into()));
+        }
+        if current_nonce == Nonce::MAX {
+            return Err(NssaError::InvalidInput(format!(
+                "Nonce of account {deployer} is exhausted"
+            )));
+        }
+
+        let fee = self.fee();
+        if Amount::from(fee) > message.max_fee {
+            return Err(NssaError::InvalidInput(format!(
+                "Deployment fee {fee} exceeds max fee {}",
+                message.max_fee
+            )));
+        }
+
+        let program = self.validate_program(state.max_program_size())?;
         if state.programs().contains_key(&program.id()) {
-            Err(NssaError::ProgramAlreadyExists)
-        } else {
-            Ok(program)
+            return Err(NssaError::ProgramAlreadyExists);
         }
+
+        let mut state_diff = HashMap::new();
+        let mut fees = PaidFees {
+            deployment_fee: fee,
+            ..PaidFees::default()
+        };
+        if !fee.is_zero() {
+            let (payer, recipient) =
+                PublicTransaction::charge_fee(state, fee.into(), &[deployer], &mut state_diff)?;
+            fees.payer = Some(payer);
+            fees.recipient = Some(recipient);
+        }
+        state.check_storage_deposits(&state_diff)?;
+
+        Ok((program, state_diff, fees))
     }
 }
@@ -0,0 +1,103 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{
+    PrivateKey, PublicKey, Signature,
+    program_deployment_transaction::Message,
+    signature::{self, SignatureScheme, Signer},
+};
+
+/// Signature of the deployer, kept in a list the same way as in public transactions
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct WitnessSet {
+    pub(crate) scheme: SignatureScheme,
+    pub(crate) signatures_and_public_keys: Vec<(Signature, PublicKey)>,
+}
+
+impl WitnessSet {
+    pub fn for_message(message: &Message, private_key: &PrivateKey) -> Self {
+        Self::for_message_with_signer(message, private_key)
+    }
+
+    /// Same as [`Self::for_message`], but signs with the given auxiliary randomness, so the witness
+    /// set is reproducible
+    pub fn for_message_with_aux_random(
+        message: &Message,
+        private_key: &PrivateKey,
+        aux_random: [u8; 32],
+    ) -> Self {
+        Self {
+            scheme: PrivateKey::SCHEME,
+            signatures_and_public_keys: signature::sign_message(
+                &message.to_bytes(),
+                &[private_key],
+                Some(aux_random),
+            ),
+        }
+    }
+
+    /// Same as [`Self::for_message`], but signs with any signer
+    pub fn for_message_with_signer<S: Signer>(message: &Message, signer: &S) -> Self {
+        Self {
+            scheme: S::SCHEME,
+            signatures_and_public_keys: signature::sign_message(
+                &message.to_bytes(),
+                &[signer],
+                None,
+            ),
+        }
+    }
+
+    /// Whether the message is signed by its deployer and nobody else
+    pub fn is_valid_for(&self, message: &Message) -> bool {
+        let [(signature, public_key)] = self.signatures_and_public_keys() else {
+            return false;
+        };
+        signature::account_id(self.scheme, public_key.value()) == message.deployer
+            && signature::verify(
+                self.scheme,
+                &self.scheme.sighash(&message.to_bytes()),
+                public_key.value(),
+                signature.value(),
+            )
+    }
+
+    pub fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+
+    pub fn signatures_and_public_keys(&self) -> &[(Signature, PublicKey)] {
+        &self.signatures_and_public_keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccountId, Amount};
+
+    #[test]
+    fn test_only_signature_of_deployer_is_valid() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let other_key = PrivateKey::try_new([2; 32]).unwrap();
+        let deployer = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let message = Message::new(deployer, 0, 0, Amount(10), vec![1, 2, 3]);
+
+        assert!(WitnessSet::for_message(&message, &key).is_valid_for(&message));
+        assert!(!WitnessSet::for_message(&message, &other_key).is_valid_for(&message));
+
+        let unsigned = WitnessSet {
+            scheme: SignatureScheme::Schnorr,
+            signatures_and_public_keys: vec![],
+        };
+        assert!(!unsigned.is_valid_for(&message));
+
+        let mut signed_twice = WitnessSet::for_message(&message, &key);
+        signed_twice
+            .signatures_and_public_keys
+            .extend(WitnessSet::for_message(&message, &key).signatures_and_public_keys);
+        assert!(!signed_twice.is_valid_for(&message));
+
+        let other_message = Message::new(deployer, 1, 0, Amount(10), vec![1, 2, 3]);
+        assert!(!WitnessSet::for_message(&message, &key).is_valid_for(&other_message));
+    }
+}
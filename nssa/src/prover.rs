@@ -0,0 +1,136 @@
+//! Backends producing receipts of zkVM executions and verification of the receipts
+
+use risc0_zkvm::{
+    Digest, ExecutorEnv, ExitCode, FakeReceipt, InnerReceipt, Receipt, ReceiptClaim,
+    VerifierContext, default_executor, default_prover,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::NssaError;
+
+/// Backend, which proves executions of programs and of the privacy preserving circuit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProverBackend {
+    /// Proves executions in zkVM
+    #[default]
+    Real,
+    /// Only executes, so journals and cycle counts are real, and produces fake receipts instead
+    /// of proving. They're accepted only by nodes, which accept dev proofs, so it's meant for
+    /// local chains, which are faster to iterate on.
+    DevFake,
+}
+
+impl ProverBackend {
+    /// Runs `elf`, which image id is `image_id`, and returns the receipt of the execution with
+    /// the number of its user cycles
+    pub(crate) fn prove(
+        self,
+        env: ExecutorEnv<'_>,
+        elf: &[u8],
+        image_id: impl Into<Digest>,
+    ) -> anyhow::Result<(Receipt, u64)> {
+        match self {
+            Self::Real => {
+                let prove_info = default_prover().prove(env, elf)?;
+                Ok((prove_info.receipt, prove_info.stats.user_cycles))
+            }
+            Self::DevFake => {
+                let session_info = default_executor().execute(env, elf)?;
+                anyhow::ensure!(
+                    session_info.exit_code == ExitCode::Halted(0),
+                    "Execution ended with {:?}",
+                    session_info.exit_code
+                );
+
+                let journal = session_info.journal.bytes.clone();
+                let claim = ReceiptClaim::ok(image_id, journal.clone());
+                let receipt = Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal);
+                Ok((receipt, session_info.cycles()))
+            }
+        }
+    }
+}
+
+/// Whether `receipt` is produced by [`ProverBackend::DevFake`]
+pub(crate) fn is_dev_receipt(receipt: &InnerReceipt) -> bool {
+    matches!(receipt, InnerReceipt::Fake(_))
+}
+
+/// Verifies `receipt` of the execution of `image_id`
+///
+/// Dev receipts are rejected with [`NssaError::DevProofRejected`] unless `accept_dev_proofs`
+/// is set, or `RISC0_DEV_MODE` is, which makes the zkVM accept them process-wide. Receipts
+/// failing verification are rejected with `invalid`.
+pub(crate) fn verify_receipt(
+    receipt: &Receipt,
+    image_id: impl Into<Digest>,
+    accept_dev_proofs: bool,
+    invalid: NssaError,
+) -> Result<(), NssaError> {
+    let mut ctx = VerifierContext::default();
+    if accept_dev_proofs {
+        ctx = ctx.with_dev_mode(true);
+    }
+    if is_dev_receipt(&receipt.inner) && !ctx.dev_mode() {
+        return Err(NssaError::DevProofRejected);
+    }
+
+    receipt
+        .verify_with_context(&ctx, image_id)
+        .map_err(|_| invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dev_receipt(image_id: [u32; 8], journal: &[u8]) -> Receipt {
+        let claim = ReceiptClaim::ok(image_id, journal.to_vec());
+        Receipt::new(
+            InnerReceipt::Fake(FakeReceipt::new(claim)),
+            journal.to_vec(),
+        )
+    }
+
+    #[test]
+    fn test_dev_receipt_is_rejected_by_strict_node() {
+        let receipt = dev_receipt([1; 8], b"journal");
+
+        if !risc0_zkvm::VerifierContext::default().dev_mode() {
+            assert!(matches!(
+                verify_receipt(
+                    &receipt,
+                    [1; 8],
+                    false,
+                    NssaError::InvalidPrivacyPreservingProof
+                ),
+                Err(NssaError::DevProofRejected)
+            ));
+        }
+        assert!(
+            verify_receipt(
+                &receipt,
+                [1; 8],
+                true,
+                NssaError::InvalidPrivacyPreservingProof
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_dev_receipt_of_another_image_is_invalid() {
+        let receipt = dev_receipt([1; 8], b"journal");
+
+        assert!(matches!(
+            verify_receipt(
+                &receipt,
+                [2; 8],
+                true,
+                NssaError::InvalidPrivacyPreservingProof
+            ),
+            Err(NssaError::InvalidPrivacyPreservingProof)
+        ));
+    }
+}
@@ -0,0 +1,61 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use nssa_core::program::{ProgramId, ProgramOutput};
+use risc0_zkvm::{InnerReceipt, Receipt};
+
+use crate::{
+    error::NssaError,
+    journal::decode_journal,
+    prover::{is_dev_receipt, verify_receipt},
+};
+
+/// Proof of one program execution of a public transaction
+///
+/// Public transactions are applied by executing programs without proving, so these proofs are
+/// produced separately, after the transaction is already applied.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ProgramExecutionProof {
+    program_id: ProgramId,
+    /// Output of the execution, committed by the program
+    journal: Vec<u8>,
+    /// Borsh encoded inner receipt
+    receipt: Vec<u8>,
+}
+
+impl ProgramExecutionProof {
+    pub(crate) fn from_receipt(program_id: ProgramId, receipt: Receipt) -> Result<Self, NssaError> {
+        Ok(Self {
+            program_id,
+            journal: receipt.journal.bytes,
+            receipt: borsh::to_vec(&receipt.inner)?,
+        })
+    }
+
+    pub fn program_id(&self) -> ProgramId {
+        self.program_id
+    }
+
+    /// Output of the proven execution
+    pub fn output(&self) -> Result<ProgramOutput, NssaError> {
+        decode_journal(&self.journal)
+    }
+
+    /// Checks that the output was produced by the program with [`Self::program_id`]. Proofs of
+    /// [`crate::ProverBackend::DevFake`] are valid only if `accept_dev_proofs`.
+    pub fn is_valid(&self, accept_dev_proofs: bool) -> bool {
+        let Ok(inner) = borsh::from_slice::<InnerReceipt>(&self.receipt) else {
+            return false;
+        };
+        verify_receipt(
+            &Receipt::new(inner, self.journal.clone()),
+            self.program_id,
+            accept_dev_proofs,
+            NssaError::InvalidInput("Invalid program execution proof".into()),
+        )
+        .is_ok()
+    }
+
+    /// Whether the proof is produced by [`crate::ProverBackend::DevFake`]
+    pub fn is_dev(&self) -> bool {
+        borsh::from_slice::<InnerReceipt>(&self.receipt).is_ok_and(|inner| is_dev_receipt(&inner))
+    }
+}
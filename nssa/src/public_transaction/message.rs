@@ -2,6 +2,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use nssa_core::{
     account::Nonce,
     program::{InstructionData, ProgramId},
+    units::{Amount, Cycles},
 };
 use serde::Serialize;
 
@@ -11,8 +12,18 @@ use crate::{AccountId, error::NssaError, program::Program};
 pub struct Message {
     pub(crate) program_id: ProgramId,
     pub(crate) account_ids: Vec<AccountId>,
+    /// Accounts the program can read but not modify. They are passed to the program after
+    /// `account_ids`.
+    pub(crate) readonly_account_ids: Vec<AccountId>,
     pub(crate) nonces: Vec<Nonce>,
     pub(crate) instruction_data: InstructionData,
+    /// Paid by the first signer to the sequencer on top of the base cost for faster inclusion
+    pub(crate) priority_fee: Amount,
+    /// Transaction can't be included into blocks with lower ids
+    pub(crate) valid_from_block: Option<u64>,
+    /// Maximum number of cycles of all program executions of the transaction. If set, the first
+    /// signer deposits gas for them and pays for the executed ones, see [`crate::gas`].
+    pub(crate) max_cycles: Option<Cycles>,
 }
 
 impl Message {
@@ -26,8 +37,64 @@ impl Message {
         Ok(Self {
             program_id,
             account_ids,
+            readonly_account_ids: vec![],
             nonces,
             instruction_data,
+            priority_fee: Amount::ZERO,
+            valid_from_block: None,
+            max_cycles: None,
         })
     }
+
+    pub fn with_readonly_account_ids(mut self, readonly_account_ids: Vec<AccountId>) -> Self {
+        self.readonly_account_ids = readonly_account_ids;
+        self
+    }
+
+    pub fn with_priority_fee(mut self, priority_fee: Amount) -> Self {
+        self.priority_fee = priority_fee;
+        self
+    }
+
+    pub fn with_valid_from_block(mut self, valid_from_block: Option<u64>) -> Self {
+        self.valid_from_block = valid_from_block;
+        self
+    }
+
+    pub fn with_max_cycles(mut self, max_cycles: Option<Cycles>) -> Self {
+        self.max_cycles = max_cycles;
+        self
+    }
+
+    pub fn program_id(&self) -> ProgramId {
+        self.program_id
+    }
+
+    pub fn account_ids(&self) -> &[AccountId] {
+        &self.account_ids
+    }
+
+    pub fn readonly_account_ids(&self) -> &[AccountId] {
+        &self.readonly_account_ids
+    }
+
+    pub fn nonces(&self) -> &[Nonce] {
+        &self.nonces
+    }
+
+    pub fn instruction_data(&self) -> &InstructionData {
+        &self.instruction_data
+    }
+
+    pub fn priority_fee(&self) -> Amount {
+        self.priority_fee
+    }
+
+    pub fn valid_from_block(&self) -> Option<u64> {
+        self.valid_from_block
+    }
+
+    pub fn max_cycles(&self) -> Option<Cycles> {
+        self.max_cycles
+    }
 }
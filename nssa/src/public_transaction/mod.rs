@@ -1,7 +1,11 @@
+mod execution_proof;
 mod message;
+mod native_transfer;
 mod transaction;
 mod witness_set;
 
+pub use execution_proof::ProgramExecutionProof;
 pub use message::Message;
+pub use native_transfer::{ACCOUNT_RECORD_SIZE, NativeTransfer, min_account_creation_amount};
 pub use transaction::PublicTransaction;
 pub use witness_set::WitnessSet;
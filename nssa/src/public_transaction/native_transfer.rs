@@ -0,0 +1,233 @@
+use nssa_core::account::Account;
+
+use crate::{AccountId, Amount, BuiltinProgram, error::NssaError, public_transaction::Message};
+
+/// Bytes of an account record, which storage deposit a transfer creating the account must cover:
+/// id, owner, balance and nonce
+pub const ACCOUNT_RECORD_SIZE: u128 = 32 + 32 + 16 + 8;
+
+/// Smallest amount of a transfer, which creates its recipient, at `storage_deposit_per_byte`
+///
+/// It's zero as long as storage deposits are disabled.
+pub fn min_account_creation_amount(storage_deposit_per_byte: Amount) -> Amount {
+    storage_deposit_per_byte.saturating_mul(ACCOUNT_RECORD_SIZE)
+}
+
+/// Human readable view of a public call to the authenticated transfer program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeTransfer {
+    /// Claiming of a default account by the program
+    Initialize { account_id: AccountId },
+    /// Moving of `amount` native balance from `sender` to `recipient`
+    Transfer {
+        sender: AccountId,
+        recipient: AccountId,
+        amount: Amount,
+    },
+}
+
+impl NativeTransfer {
+    /// Decodes `message` if it is a well-formed call to the authenticated transfer program.
+    pub fn decode(message: &Message) -> Option<Self> {
+        if message.program_id() != BuiltinProgram::AuthenticatedTransfer.id() {
+            return None;
+        }
+
+        let amount: u128 = risc0_zkvm::serde::from_slice(message.instruction_data()).ok()?;
+
+        match (message.account_ids(), amount) {
+            ([account_id], 0) => Some(Self::Initialize {
+                account_id: *account_id,
+            }),
+            ([sender, recipient], amount) => Some(Self::Transfer {
+                sender: *sender,
+                recipient: *recipient,
+                amount: Amount(amount),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Account, which the transfer moves balance to, if any
+    pub fn recipient(&self) -> Option<AccountId> {
+        match self {
+            Self::Initialize { .. } => None,
+            Self::Transfer { recipient, .. } => Some(*recipient),
+        }
+    }
+
+    /// Checks the part of the rules of native transfers, which doesn't depend on the state:
+    /// an account can't transfer to itself
+    ///
+    /// Transfers of zero are valid, they pay fees and bump the nonce of the sender like any other.
+    pub fn validate(&self) -> Result<(), NssaError> {
+        match self {
+            Self::Transfer {
+                sender, recipient, ..
+            } if sender == recipient => Err(NssaError::SelfTransfer {
+                account_id: *sender,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the part of the rules of native transfers, which depends on `recipient`, the
+    /// current account of the recipient: a transfer creating the recipient moves at least
+    /// [`min_account_creation_amount`]
+    pub fn check_recipient(
+        &self,
+        recipient: &Account,
+        storage_deposit_per_byte: Amount,
+    ) -> Result<(), NssaError> {
+        let Self::Transfer {
+            recipient: account_id,
+            amount,
+            ..
+        } = self
+        else {
+            return Ok(());
+        };
+
+        let min_amount = min_account_creation_amount(storage_deposit_per_byte);
+        if *recipient == Account::default() && *amount < min_amount {
+            return Err(NssaError::AccountCreationBelowMinimum {
+                account_id: *account_id,
+                amount: *amount,
+                min_amount,
+            });
+        }
+        Ok(())
+    }
+
+    /// All rules of native transfers, see [`Self::validate`] and [`Self::check_recipient`]
+    ///
+    /// Transaction validation runs the same checks, so builders use it to reject transfers
+    /// before signing them.
+    pub fn check(
+        &self,
+        recipient: &Account,
+        storage_deposit_per_byte: Amount,
+    ) -> Result<(), NssaError> {
+        self.validate()?;
+        self.check_recipient(recipient, storage_deposit_per_byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program::Program;
+
+    fn transfer(sender: AccountId, recipient: AccountId, amount: u128) -> NativeTransfer {
+        NativeTransfer::Transfer {
+            sender,
+            recipient,
+            amount: Amount(amount),
+        }
+    }
+
+    fn existing_account() -> Account {
+        Account {
+            program_owner: BuiltinProgram::AuthenticatedTransfer.id(),
+            balance: Amount(1),
+            ..Account::default()
+        }
+    }
+
+    #[test]
+    fn test_decode_transfer() {
+        let sender = AccountId::new([1; 32]);
+        let recipient = AccountId::new([2; 32]);
+        let message = Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![sender, recipient],
+            vec![0],
+            Amount(37),
+        )
+        .unwrap();
+
+        assert_eq!(
+            NativeTransfer::decode(&message),
+            Some(NativeTransfer::Transfer {
+                sender,
+                recipient,
+                amount: Amount(37)
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_initialize() {
+        let account_id = AccountId::new([1; 32]);
+        let message = Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![account_id],
+            vec![0],
+            0u128,
+        )
+        .unwrap();
+
+        assert_eq!(
+            NativeTransfer::decode(&message),
+            Some(NativeTransfer::Initialize { account_id })
+        );
+    }
+
+    #[test]
+    fn test_decode_other_program() {
+        let message = Message::try_new(
+            Program::token().id(),
+            vec![AccountId::new([1; 32]), AccountId::new([2; 32])],
+            vec![],
+            37u128,
+        )
+        .unwrap();
+
+        assert_eq!(NativeTransfer::decode(&message), None);
+    }
+
+    #[test]
+    fn test_zero_amount_transfer_is_valid() {
+        let zero = transfer(AccountId::new([1; 32]), AccountId::new([2; 32]), 0);
+
+        zero.check(&existing_account(), Amount(10)).unwrap();
+        // Zero is the minimum of account creation while storage deposits are disabled
+        zero.check(&Account::default(), Amount::ZERO).unwrap();
+    }
+
+    #[test]
+    fn test_self_transfer_is_rejected() {
+        let account_id = AccountId::new([1; 32]);
+
+        for amount in [0, 37] {
+            assert!(matches!(
+                transfer(account_id, account_id, amount).validate(),
+                Err(NssaError::SelfTransfer { account_id: id }) if id == account_id
+            ));
+        }
+        NativeTransfer::Initialize { account_id }
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_account_creation_requires_minimum_amount() {
+        let recipient = AccountId::new([2; 32]);
+        let rate = Amount(2);
+        let min_amount = min_account_creation_amount(rate);
+        assert_eq!(min_amount, Amount(2 * ACCOUNT_RECORD_SIZE));
+
+        let below = transfer(AccountId::new([1; 32]), recipient, min_amount.0 - 1);
+        assert!(matches!(
+            below.check(&Account::default(), rate),
+            Err(NssaError::AccountCreationBelowMinimum { account_id, amount, min_amount: min })
+                if account_id == recipient && amount == Amount(min_amount.0 - 1) && min == min_amount
+        ));
+        // Existing recipients take any amount
+        below.check(&existing_account(), rate).unwrap();
+
+        transfer(AccountId::new([1; 32]), recipient, min_amount.0)
+            .check(&Account::default(), rate)
+            .unwrap();
+    }
+}
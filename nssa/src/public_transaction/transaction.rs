@@ -2,18 +2,28 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use nssa_core::{
-    account::{Account, AccountId, AccountWithMetadata},
-    program::{ChainedCall, DEFAULT_PROGRAM_ID, PdaSeed, ProgramId, validate_execution},
+    account::{Account, AccountId, AccountWithMetadata, Nonce},
+    program::{
+        ChainedCall, InstructionData, PdaSeed, ProgramId, ProgramOutput, validate_execution,
+    },
+    units::{Amount, Cycles},
 };
 use sha2::{Digest, digest::FixedOutput};
 
 use crate::{
     V02State,
     error::NssaError,
-    public_transaction::{Message, WitnessSet},
+    gas::{GasCalculator, PaidFees},
+    privacy_preserving_transaction::circuit::{ProveOptions, execute_and_prove_program},
+    program::{InstructionError, MAX_NUM_CYCLES_PUBLIC_EXECUTION, Program},
+    prover::ProverBackend,
+    public_transaction::{Message, NativeTransfer, ProgramExecutionProof, WitnessSet},
     state::MAX_NUMBER_CHAINED_CALLS,
 };
 
+/// Public accounts changed by a transaction
+type StateDiff = HashMap<AccountId, Account>;
+
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct PublicTransaction {
     message: Message,
@@ -36,30 +46,52 @@ impl PublicTransaction {
         &self.witness_set
     }
 
-    pub(crate) fn signer_account_ids(&self) -> Vec<AccountId> {
-        self.witness_set
-            .signatures_and_public_keys()
-            .iter()
-            .map(|(_, public_key)| AccountId::from(public_key))
-            .collect()
+    pub fn signer_account_ids(&self) -> Vec<AccountId> {
+        self.witness_set.signer_account_ids()
     }
 
+    /// Identifier of the transaction, which is the hash of its message only
+    ///
+    /// Signatures don't take part in it, so the same transaction re-signed or with its witness
+    /// re-encoded has the same hash.
     pub fn hash(&self) -> [u8; 32] {
-        let bytes = self.to_bytes();
+        let bytes = self.message.to_bytes();
         let mut hasher = sha2::Sha256::new();
         hasher.update(&bytes);
         hasher.finalize_fixed().into()
     }
 
-    pub(crate) fn validate_and_produce_public_state_diff(
-        &self,
-        state: &V02State,
-    ) -> Result<HashMap<AccountId, Account>, NssaError> {
+    /// Hash of the witness set, which distinguishes different signings of the same message
+    pub fn witness_hash(&self) -> [u8; 32] {
+        let bytes =
+            borsh::to_vec(&self.witness_set).expect("Autoderived borsh serialization failure");
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(&bytes);
+        hasher.finalize_fixed().into()
+    }
+
+    /// Checks transaction well-formedness, which doesn't depend on the state and on signatures:
+    /// native transfers aren't self-transfers, account ids are unique and there is a nonce for
+    /// each signature.
+    pub fn validate_structure(&self) -> Result<(), NssaError> {
         let message = self.message();
         let witness_set = self.witness_set();
 
-        // All account_ids must be different
-        if message.account_ids.iter().collect::<HashSet<_>>().len() != message.account_ids.len() {
+        // Self-transfers have duplicate account ids too, but are reported as such
+        if let Some(transfer) = NativeTransfer::decode(message) {
+            transfer.validate()?;
+        }
+
+        // All account_ids, including read-only ones, must be different
+        let num_account_ids = message.account_ids.len() + message.readonly_account_ids.len();
+        if message
+            .account_ids
+            .iter()
+            .chain(&message.readonly_account_ids)
+            .collect::<HashSet<_>>()
+            .len()
+            != num_account_ids
+        {
             return Err(NssaError::InvalidInput(
                 "Duplicate account_ids found in message".into(),
             ));
@@ -72,13 +104,130 @@ impl PublicTransaction {
             ));
         }
 
+        Ok(())
+    }
+
+    /// Checks the instruction of the message against `max_instruction_data_size` and the
+    /// instruction format of its program, see [`Program::validate_instruction`], and native
+    /// transfers against their recipients, see [`NativeTransfer::check_recipient`]
+    ///
+    /// No program is executed, so mempool admission runs it to reject malformed calls before
+    /// they burn executor cycles. Calls of unknown programs pass, as their transactions are
+    /// rejected on execution anyway.
+    pub fn validate_instruction(
+        &self,
+        state: &V02State,
+        max_instruction_data_size: usize,
+    ) -> Result<(), NssaError> {
+        let message = self.message();
+        let size = message.instruction_data.len();
+        if size > max_instruction_data_size {
+            return Err(InstructionError::TooLarge {
+                size,
+                max_size: max_instruction_data_size,
+            }
+            .into());
+        }
+
+        if let Some(program) = state.programs().get(&message.program_id) {
+            let num_accounts = message.account_ids.len() + message.readonly_account_ids.len();
+            program.validate_instruction(&message.instruction_data, num_accounts)?;
+        }
+
+        if let Some(transfer) = NativeTransfer::decode(message)
+            && let Some(recipient) = transfer.recipient()
+        {
+            transfer.check_recipient(
+                &state.get_account_by_id(&recipient),
+                state.storage_deposit_per_byte(),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_and_produce_public_state_diff(
+        &self,
+        state: &V02State,
+    ) -> Result<(StateDiff, PaidFees), NssaError> {
+        self.validate_structure()?;
+
         // Check the signatures are valid
-        if !witness_set.is_valid_for(message) {
+        if !self.witness_set().is_valid_for(self.message()) {
             return Err(NssaError::InvalidInput(
                 "Invalid signature for given message and public key".into(),
             ));
         }
 
+        self.produce_public_state_diff(state)
+    }
+
+    /// Stateful part of the validation. Expects [`Self::validate_structure`] to pass and
+    /// signatures to be valid.
+    pub(crate) fn produce_public_state_diff(
+        &self,
+        state: &V02State,
+    ) -> Result<(StateDiff, PaidFees), NssaError> {
+        self.produce_public_state_diff_with(
+            state,
+            |program, pre_states, instruction_data, cycle_limit| {
+                program.execute_with_cycle_limit(pre_states, instruction_data, cycle_limit)
+            },
+        )
+    }
+
+    /// Same as [`Self::produce_public_state_diff`], but proves every program execution with
+    /// `backend`.
+    pub(crate) fn produce_proven_public_state_diff(
+        &self,
+        state: &V02State,
+        backend: ProverBackend,
+    ) -> Result<(StateDiff, PaidFees, Vec<ProgramExecutionProof>), NssaError> {
+        let mut proofs = vec![];
+        let (state_diff, fees) = self.produce_public_state_diff_with(
+            state,
+            |program, pre_states, instruction_data, cycle_limit| {
+                let (receipt, cycles) = execute_and_prove_program(
+                    program,
+                    pre_states,
+                    instruction_data,
+                    &ProveOptions::default()
+                        .with_cycle_limit(cycle_limit)
+                        .with_backend(backend),
+                )?;
+                let proof = ProgramExecutionProof::from_receipt(program.id(), receipt)?;
+                let output = proof.output()?;
+                proofs.push(proof);
+                Ok((output, cycles))
+            },
+        )?;
+
+        Ok((state_diff, fees, proofs))
+    }
+
+    /// Runs the chain of calls, starting from the message, with `run_program`, which gets the
+    /// cycle limit of the call and returns the number of executed cycles
+    fn produce_public_state_diff_with(
+        &self,
+        state: &V02State,
+        mut run_program: impl FnMut(
+            &Program,
+            &[AccountWithMetadata],
+            &InstructionData,
+            u64,
+        ) -> Result<(ProgramOutput, u64), NssaError>,
+    ) -> Result<(StateDiff, PaidFees), NssaError> {
+        let message = self.message();
+        self.validate_instruction(state, state.max_instruction_data_size())?;
+
+        if let Some(valid_from_block) = message.valid_from_block
+            && state.block_id() < valid_from_block
+        {
+            return Err(NssaError::InvalidInput(format!(
+                "Transaction is not valid before block {valid_from_block}, current block is {}",
+                state.block_id()
+            )));
+        }
+
         let signer_account_ids = self.signer_account_ids();
         // Check nonces corresponds to the current nonces on the public state.
         for (account_id, nonce) in signer_account_ids.iter().zip(&message.nonces) {
@@ -86,12 +235,23 @@ impl PublicTransaction {
             if current_nonce != *nonce {
                 return Err(NssaError::InvalidInput("Nonce mismatch".into()));
             }
+            if current_nonce == Nonce::MAX {
+                return Err(NssaError::InvalidInput(format!(
+                    "Nonce of account {account_id} is exhausted"
+                )));
+            }
+        }
+
+        let gas = GasCalculator::default();
+        if let Some(max_cycles) = message.max_cycles {
+            Self::check_gas_deposit(state, &gas, max_cycles, &signer_account_ids)?;
         }
 
         // Build pre_states for execution
         let input_pre_states: Vec<_> = message
             .account_ids
             .iter()
+            .chain(&message.readonly_account_ids)
             .map(|account_id| {
                 AccountWithMetadata::new(
                     state.get_account_by_id(account_id),
@@ -112,19 +272,54 @@ impl PublicTransaction {
 
         let mut chained_calls = VecDeque::from_iter([(initial_call, None)]);
         let mut chain_calls_counter = 0;
+        let mut executed_cycles = Cycles::ZERO;
 
         while let Some((chained_call, caller_program_id)) = chained_calls.pop_front() {
             if chain_calls_counter > MAX_NUMBER_CHAINED_CALLS {
                 return Err(NssaError::MaxChainedCallsDepthExceeded);
             }
 
+            state
+                .program_allow_list()
+                .check_call(&chained_call.program_id)?;
             // Check the `program_id` corresponds to a deployed program
             let Some(program) = state.programs().get(&chained_call.program_id) else {
                 return Err(NssaError::InvalidInput("Unknown program".into()));
             };
 
-            let mut program_output =
-                program.execute(&chained_call.pre_states, &chained_call.instruction_data)?;
+            // Calls of a metered transaction share its cycles
+            let cycle_limit = match message.max_cycles {
+                Some(max_cycles) => max_cycles.saturating_sub(executed_cycles).0,
+                None => MAX_NUM_CYCLES_PUBLIC_EXECUTION,
+            };
+            let (mut program_output, cycles) = run_program(
+                program,
+                &chained_call.pre_states,
+                &chained_call.instruction_data,
+                cycle_limit,
+            )?;
+            executed_cycles = executed_cycles.saturating_add(Cycles(cycles));
+
+            // Read-only accounts are passed only to the program of the message, after the
+            // writable ones, and have no post-states
+            let num_readonly = if caller_program_id.is_none() {
+                message.readonly_account_ids.len()
+            } else {
+                0
+            };
+            let Some(num_writable) = program_output.pre_states.len().checked_sub(num_readonly)
+            else {
+                return Err(NssaError::InvalidProgramBehavior);
+            };
+            let readonly_pre_states = &program_output.pre_states[num_writable..];
+            if program_output.post_states.len() != num_writable
+                || readonly_pre_states
+                    .iter()
+                    .map(|pre| pre.account_id)
+                    .ne(message.readonly_account_ids[..num_readonly].iter().copied())
+            {
+                return Err(NssaError::InvalidProgramBehavior);
+            }
 
             let authorized_pdas =
                 self.compute_authorized_pdas(&caller_program_id, &chained_call.pda_seeds);
@@ -152,25 +347,20 @@ impl PublicTransaction {
 
             // Verify execution corresponds to a well-behaved program.
             // See the # Programs section for the definition of the `validate_execution` method.
-            if !validate_execution(
-                &program_output.pre_states,
+            validate_execution(
+                &program_output.pre_states[..num_writable],
                 &program_output.post_states,
                 chained_call.program_id,
-            ) {
-                return Err(NssaError::InvalidProgramBehavior);
-            }
+            )
+            .map_err(|_| NssaError::InvalidProgramBehavior)?;
 
+            // Claims were checked to be of accounts with default program id
             for post in program_output
                 .post_states
                 .iter_mut()
                 .filter(|post| post.requires_claim())
             {
-                // The invoked program can only claim accounts with default program id.
-                if post.account().program_owner == DEFAULT_PROGRAM_ID {
-                    post.account_mut().program_owner = chained_call.program_id;
-                } else {
-                    return Err(NssaError::InvalidProgramBehavior);
-                }
+                post.account_mut().program_owner = chained_call.program_id;
             }
 
             // Update the state diff
@@ -189,7 +379,102 @@ impl PublicTransaction {
             chain_calls_counter += 1;
         }
 
-        Ok(state_diff)
+        // Read-only accounts can't be modified by chained calls either
+        for account_id in &message.readonly_account_ids {
+            if state_diff
+                .get(account_id)
+                .is_some_and(|account| *account != state.get_account_by_id(account_id))
+            {
+                return Err(NssaError::InvalidProgramBehavior);
+            }
+        }
+
+        // The deposit is held only during execution, so just the gas of the executed cycles is
+        // paid and the rest is refunded
+        let mut fees = PaidFees {
+            gas: message
+                .max_cycles
+                .map(|max_cycles| gas.settle(max_cycles, executed_cycles)),
+            priority_fee: message.priority_fee,
+            ..PaidFees::default()
+        };
+        let fee = fees.total();
+        if !fee.is_zero() {
+            let (payer, recipient) =
+                Self::charge_fee(state, fee, &signer_account_ids, &mut state_diff)?;
+            fees.payer = Some(payer);
+            fees.recipient = Some(recipient);
+        }
+
+        // Deposits are checked on the outcome of the whole transaction, fees included
+        state.check_storage_deposits(&state_diff)?;
+
+        Ok((state_diff, fees))
+    }
+
+    /// Checks that the first signer can deposit gas for `max_cycles` before execution
+    fn check_gas_deposit(
+        state: &V02State,
+        gas: &GasCalculator,
+        max_cycles: Cycles,
+        signer_account_ids: &[AccountId],
+    ) -> Result<(), NssaError> {
+        if max_cycles > gas.max_cycles_per_transaction {
+            return Err(NssaError::InvalidInput(format!(
+                "Transaction may execute at most {} cycles",
+                gas.max_cycles_per_transaction
+            )));
+        }
+        let Some(payer) = signer_account_ids.first() else {
+            return Err(NssaError::InvalidInput(
+                "Gas requires a signer to pay it".into(),
+            ));
+        };
+        if state.get_account_by_id(payer).balance < gas.cycles_to_gas(max_cycles).into() {
+            return Err(NssaError::InvalidInput(
+                "Insufficient balance to deposit gas".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Moves `fee` from the first signer to the fee recipient of the state, returns both of them
+    pub(crate) fn charge_fee(
+        state: &V02State,
+        fee: Amount,
+        signer_account_ids: &[AccountId],
+        state_diff: &mut HashMap<AccountId, Account>,
+    ) -> Result<(AccountId, AccountId), NssaError> {
+        let Some(fee_recipient) = state.fee_recipient() else {
+            return Err(NssaError::InvalidInput("Fees are not accepted".into()));
+        };
+        let Some(payer) = signer_account_ids.first().copied() else {
+            return Err(NssaError::InvalidInput(
+                "Fee requires a signer to pay it".into(),
+            ));
+        };
+
+        let mut payer_account = state_diff
+            .get(&payer)
+            .cloned()
+            .unwrap_or_else(|| state.get_account_by_id(&payer));
+        payer_account.balance = payer_account
+            .balance
+            .checked_sub(fee)
+            .ok_or_else(|| NssaError::InvalidInput("Insufficient balance to pay fees".into()))?;
+        state_diff.insert(payer, payer_account);
+
+        let mut recipient_account = state_diff
+            .get(&fee_recipient)
+            .cloned()
+            .unwrap_or_else(|| state.get_account_by_id(&fee_recipient));
+        recipient_account.balance = recipient_account
+            .balance
+            .checked_add(fee)
+            .ok_or_else(|| NssaError::InvalidInput("Fee recipient balance overflow".into()))?;
+        state_diff.insert(fee_recipient, recipient_account);
+
+        Ok((payer, fee_recipient))
     }
 
     fn compute_authorized_pdas(
@@ -212,10 +497,14 @@ impl PublicTransaction {
 pub mod tests {
     use sha2::{Digest, digest::FixedOutput};
 
+    use nssa_core::{account::AccountWithMetadata, program::ProgramId};
+
     use crate::{
-        AccountId, PrivateKey, PublicKey, PublicTransaction, Signature, V02State,
+        AccountId, Amount, BuiltinProgram, Cycles, Gas, PrivateKey, PublicKey, PublicTransaction,
+        Signature, V02State,
         error::NssaError,
-        program::Program,
+        gas::{GasCalculator, PaidFees},
+        program::{MAX_NUM_CYCLES_PUBLIC_EXECUTION, Program},
         public_transaction::{Message, WitnessSet},
     };
 
@@ -229,7 +518,7 @@ pub mod tests {
 
     fn state_for_tests() -> V02State {
         let (_, _, addr1, addr2) = keys_for_tests();
-        let initial_data = [(addr1, 10000), (addr2, 20000)];
+        let initial_data = [(addr1, Amount(10000)), (addr2, Amount(20000))];
         V02State::new_with_genesis_accounts(&initial_data, &[])
     }
 
@@ -297,11 +586,11 @@ pub mod tests {
     }
 
     #[test]
-    fn test_hash_is_sha256_of_transaction_bytes() {
+    fn test_hash_is_sha256_of_message_bytes() {
         let tx = transaction_for_tests();
         let hash = tx.hash();
         let expected_hash: [u8; 32] = {
-            let bytes = tx.to_bytes();
+            let bytes = tx.message().to_bytes();
             let mut hasher = sha2::Sha256::new();
             hasher.update(&bytes);
             hasher.finalize_fixed().into()
@@ -309,14 +598,71 @@ pub mod tests {
         assert_eq!(hash, expected_hash);
     }
 
+    #[test]
+    fn test_resigned_transaction_has_same_hash_and_other_witness_hash() {
+        let tx = transaction_for_tests();
+        let resigned = transaction_for_tests();
+        assert_ne!(tx.to_bytes(), resigned.to_bytes());
+
+        assert_eq!(tx.hash(), resigned.hash());
+        assert_ne!(tx.witness_hash(), resigned.witness_hash());
+    }
+
+    #[test]
+    fn test_reordered_witness_set_has_same_hash() {
+        // Both signers use nonce 0, so swapped signatures still pass all checks
+        let tx = transaction_for_tests();
+        let mut reordered = tx.clone();
+        reordered.witness_set.signatures_and_public_keys.reverse();
+        assert!(reordered.witness_set().is_valid_for(reordered.message()));
+
+        assert_eq!(tx.hash(), reordered.hash());
+        assert_ne!(tx.witness_hash(), reordered.witness_hash());
+    }
+
+    #[test]
+    fn test_trailing_bytes_are_rejected() {
+        let mut bytes = transaction_for_tests().to_bytes();
+        bytes.push(0);
+
+        assert!(PublicTransaction::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_malleated_witness_is_rejected_or_keeps_hash() {
+        let tx = transaction_for_tests();
+        let bytes = tx.to_bytes();
+        let witness_start = tx.message().to_bytes().len();
+
+        for index in witness_start..bytes.len() {
+            for bit in 0..8 {
+                let mut malleated = bytes.clone();
+                malleated[index] ^= 1 << bit;
+
+                let Ok(malleated_tx) = PublicTransaction::from_bytes(&malleated) else {
+                    continue;
+                };
+                assert_eq!(malleated_tx.hash(), tx.hash());
+                assert!(
+                    malleated_tx.validate_structure().is_err()
+                        || !malleated_tx
+                            .witness_set()
+                            .is_valid_for(malleated_tx.message()),
+                    "Malleated byte {index} bit {bit} is accepted"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_account_id_list_cant_have_duplicates() {
         let (key1, _, addr1, _) = keys_for_tests();
         let state = state_for_tests();
         let nonces = vec![0, 0];
         let instruction = 1337;
+        // Duplicates in calls of the authenticated transfer program are self-transfers
         let message = Message::try_new(
-            Program::authenticated_transfer_program().id(),
+            Program::token().id(),
             vec![addr1, addr1],
             nonces,
             instruction,
@@ -329,6 +675,25 @@ pub mod tests {
         assert!(matches!(result, Err(NssaError::InvalidInput(_))))
     }
 
+    #[test]
+    fn test_self_transfer_is_rejected_before_duplicates() {
+        let (key1, _, addr1, _) = keys_for_tests();
+        let message = Message::try_new(
+            BuiltinProgram::AuthenticatedTransfer.id(),
+            vec![addr1, addr1],
+            vec![0],
+            0_u128,
+        )
+        .unwrap();
+        let witness_set = WitnessSet::for_message(&message, &[&key1]);
+        let tx = PublicTransaction::new(message, witness_set);
+
+        let result = tx.validate_structure();
+        assert!(
+            matches!(result, Err(NssaError::SelfTransfer { account_id }) if account_id == addr1)
+        );
+    }
+
     #[test]
     fn test_number_of_nonces_must_match_number_of_signatures() {
         let (key1, key2, addr1, addr2) = keys_for_tests();
@@ -396,7 +761,7 @@ pub mod tests {
         let state = state_for_tests();
         let nonces = vec![0, 0];
         let instruction = 1337;
-        let unknown_program_id = [0xdeadbeef; 8];
+        let unknown_program_id = ProgramId::new([0xdeadbeef; 8]);
         let message =
             Message::try_new(unknown_program_id, vec![addr1, addr2], nonces, instruction).unwrap();
 
@@ -405,4 +770,250 @@ pub mod tests {
         let result = tx.validate_and_produce_public_state_diff(&state);
         assert!(matches!(result, Err(NssaError::InvalidInput(_))))
     }
+
+    #[test]
+    fn test_readonly_account_ids_cant_repeat_account_ids() {
+        let (key1, _, addr1, addr2) = keys_for_tests();
+        let message = Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![addr1, addr2],
+            vec![0],
+            100_u128,
+        )
+        .unwrap()
+        .with_readonly_account_ids(vec![addr2]);
+        let witness_set = WitnessSet::for_message(&message, &[&key1]);
+        let tx = PublicTransaction::new(message, witness_set);
+
+        let result = tx.validate_structure();
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_transfer_to_readonly_account_is_rejected() {
+        let (key1, _, addr1, addr2) = keys_for_tests();
+        let state = state_for_tests();
+        let message = Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![addr1],
+            vec![0],
+            100_u128,
+        )
+        .unwrap()
+        .with_readonly_account_ids(vec![addr2]);
+        let witness_set = WitnessSet::for_message(&message, &[&key1]);
+        let tx = PublicTransaction::new(message, witness_set);
+
+        let result = tx.validate_and_produce_public_state_diff(&state);
+        assert!(matches!(result, Err(NssaError::InvalidProgramBehavior)));
+    }
+
+    fn transfer_with_priority_fee(priority_fee: Amount) -> PublicTransaction {
+        let (key1, _, addr1, addr2) = keys_for_tests();
+        let message = Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![addr1, addr2],
+            vec![0],
+            100_u128,
+        )
+        .unwrap()
+        .with_priority_fee(priority_fee);
+
+        let witness_set = WitnessSet::for_message(&message, &[&key1]);
+        PublicTransaction::new(message, witness_set)
+    }
+
+    #[test]
+    fn test_priority_fee_is_paid_by_first_signer_to_fee_recipient() {
+        let (_, _, addr1, addr2) = keys_for_tests();
+        let fee_recipient = AccountId::new([3; 32]);
+        let mut state = state_for_tests();
+        state.set_fee_recipient(fee_recipient);
+
+        let (state_diff, fees) = transfer_with_priority_fee(Amount(5))
+            .validate_and_produce_public_state_diff(&state)
+            .unwrap();
+
+        assert_eq!(state_diff[&addr1].balance, Amount(10000 - 100 - 5));
+        assert_eq!(state_diff[&addr2].balance, Amount(20000 + 100));
+        assert_eq!(state_diff[&fee_recipient].balance, Amount(5));
+        assert_eq!(
+            fees,
+            PaidFees {
+                payer: Some(addr1),
+                recipient: Some(fee_recipient),
+                gas: None,
+                priority_fee: Amount(5),
+                deployment_fee: Gas(0),
+            }
+        );
+    }
+
+    #[test]
+    fn test_priority_fee_is_rejected_without_fee_recipient() {
+        let state = state_for_tests();
+
+        let result =
+            transfer_with_priority_fee(Amount(5)).validate_and_produce_public_state_diff(&state);
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_priority_fee_must_be_covered_by_balance() {
+        let mut state = state_for_tests();
+        state.set_fee_recipient(AccountId::new([3; 32]));
+
+        let result = transfer_with_priority_fee(Amount(10000))
+            .validate_and_produce_public_state_diff(&state);
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_time_locked_transaction_is_valid_from_its_block() {
+        let (key1, _, addr1, addr2) = keys_for_tests();
+        let message = Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![addr1, addr2],
+            vec![0],
+            100_u128,
+        )
+        .unwrap()
+        .with_valid_from_block(Some(6));
+        let witness_set = WitnessSet::for_message(&message, &[&key1]);
+        let tx = PublicTransaction::new(message, witness_set);
+        let mut state = state_for_tests();
+
+        state.set_block_id(5);
+        let result = tx.validate_and_produce_public_state_diff(&state);
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+
+        state.set_block_id(6);
+        let (state_diff, _) = tx.validate_and_produce_public_state_diff(&state).unwrap();
+        assert_eq!(state_diff[&addr2].balance, Amount(20000 + 100));
+    }
+
+    fn transfer_with_max_cycles(max_cycles: u64) -> PublicTransaction {
+        let (key1, _, addr1, addr2) = keys_for_tests();
+        let message = Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![addr1, addr2],
+            vec![0],
+            100_u128,
+        )
+        .unwrap()
+        .with_max_cycles(Some(Cycles(max_cycles)));
+
+        let witness_set = WitnessSet::for_message(&message, &[&key1]);
+        PublicTransaction::new(message, witness_set)
+    }
+
+    /// Cycles executed by [`transfer_with_max_cycles`] on `state`
+    fn transfer_cycles(state: &V02State) -> u64 {
+        let (_, _, addr1, addr2) = keys_for_tests();
+        let pre_states = [
+            AccountWithMetadata::new(state.get_account_by_id(&addr1), true, addr1),
+            AccountWithMetadata::new(state.get_account_by_id(&addr2), false, addr2),
+        ];
+        let (_, cycles) = Program::authenticated_transfer_program()
+            .execute_with_cycle_limit(
+                &pre_states,
+                &Program::serialize_instruction(100_u128).unwrap(),
+                MAX_NUM_CYCLES_PUBLIC_EXECUTION,
+            )
+            .unwrap();
+        cycles
+    }
+
+    #[test]
+    fn test_execution_within_exact_cycle_limit_pays_gas() {
+        let (_, _, addr1, addr2) = keys_for_tests();
+        let fee_recipient = AccountId::new([3; 32]);
+        let mut state = state_for_tests();
+        state.set_fee_recipient(fee_recipient);
+        let cycles = transfer_cycles(&state);
+
+        let (state_diff, fees) = transfer_with_max_cycles(cycles)
+            .validate_and_produce_public_state_diff(&state)
+            .unwrap();
+
+        let gas_fee = GasCalculator::default().cycles_to_gas(Cycles(cycles));
+        assert!(!gas_fee.is_zero());
+        assert_eq!(fees.gas.map(|gas| gas.charged), Some(gas_fee));
+        assert_eq!(fees.total(), Amount::from(gas_fee));
+        assert_eq!(
+            state_diff[&addr1].balance,
+            Amount(10000 - 100 - u128::from(gas_fee.0))
+        );
+        assert_eq!(state_diff[&addr2].balance, Amount(20000 + 100));
+        assert_eq!(state_diff[&fee_recipient].balance, Amount::from(gas_fee));
+    }
+
+    #[test]
+    fn test_execution_over_cycle_limit_is_aborted() {
+        let (_, _, addr1, _) = keys_for_tests();
+        let fee_recipient = AccountId::new([3; 32]);
+        let mut state = state_for_tests();
+        state.set_fee_recipient(fee_recipient);
+        let cycles = transfer_cycles(&state);
+        let tx = transfer_with_max_cycles(cycles - 1);
+
+        let result = state.transition_from_public_transaction(&tx);
+
+        assert!(matches!(result, Err(NssaError::CycleLimitExceeded(limit)) if limit == cycles - 1));
+        // Aborted transaction isn't applied, so its deposit is never taken
+        assert_eq!(state.get_account_by_id(&addr1).balance, Amount(10000));
+        assert_eq!(state.get_account_by_id(&fee_recipient).balance, Amount(0));
+    }
+
+    #[test]
+    fn test_unused_gas_deposit_is_refunded() {
+        let (_, _, addr1, _) = keys_for_tests();
+        let fee_recipient = AccountId::new([3; 32]);
+        let mut state = state_for_tests();
+        state.set_fee_recipient(fee_recipient);
+        let cycles = transfer_cycles(&state);
+        let max_cycles = cycles + 100 * 1024;
+        let tx = transfer_with_max_cycles(max_cycles);
+
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        let settlement = GasCalculator::default().settle(Cycles(max_cycles), Cycles(cycles));
+        assert_eq!(settlement.refund, Gas(100));
+        assert_eq!(
+            state.get_account_by_id(&addr1).balance,
+            Amount(10000 - 100 - u128::from(settlement.charged.0))
+        );
+        assert_eq!(
+            state.get_account_by_id(&fee_recipient).balance,
+            Amount::from(settlement.charged)
+        );
+    }
+
+    #[test]
+    fn test_gas_deposit_must_be_covered_by_balance() {
+        let mut state = state_for_tests();
+        state.set_fee_recipient(AccountId::new([3; 32]));
+        let max_cycles = GasCalculator::default()
+            .max_cycles_for_deposit(Amount(10000))
+            .0;
+
+        assert!(
+            transfer_with_max_cycles(max_cycles)
+                .validate_and_produce_public_state_diff(&state)
+                .is_ok()
+        );
+        let result =
+            transfer_with_max_cycles(max_cycles + 1).validate_and_produce_public_state_diff(&state);
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_max_cycles_are_bounded() {
+        let mut state = state_for_tests();
+        state.set_fee_recipient(AccountId::new([3; 32]));
+
+        let result = transfer_with_max_cycles(MAX_NUM_CYCLES_PUBLIC_EXECUTION + 1)
+            .validate_and_produce_public_state_diff(&state);
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+    }
 }
@@ -1,48 +1,81 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use crate::{PrivateKey, PublicKey, Signature, public_transaction::Message};
+use crate::{
+    AccountId, PrivateKey, PublicKey, Signature,
+    public_transaction::Message,
+    signature::{self, SignatureScheme, Signer},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct WitnessSet {
+    pub(crate) scheme: SignatureScheme,
     pub(crate) signatures_and_public_keys: Vec<(Signature, PublicKey)>,
 }
 
 impl WitnessSet {
     pub fn for_message(message: &Message, private_keys: &[&PrivateKey]) -> Self {
-        let message_bytes = message.to_bytes();
-        let signatures_and_public_keys = private_keys
-            .iter()
-            .map(|&key| {
-                (
-                    Signature::new(key, &message_bytes),
-                    PublicKey::new_from_private_key(key),
-                )
-            })
-            .collect();
+        Self::for_message_with_signers(message, private_keys)
+    }
+
+    /// Same as [`Self::for_message`], but signs with the given auxiliary randomness, so the witness
+    /// set is reproducible
+    pub fn for_message_with_aux_random(
+        message: &Message,
+        private_keys: &[&PrivateKey],
+        aux_random: [u8; 32],
+    ) -> Self {
         Self {
-            signatures_and_public_keys,
+            scheme: PrivateKey::SCHEME,
+            signatures_and_public_keys: signature::sign_message(
+                &message.to_bytes(),
+                private_keys,
+                Some(aux_random),
+            ),
         }
     }
 
-    pub fn is_valid_for(&self, message: &Message) -> bool {
-        let message_bytes = message.to_bytes();
-        for (signature, public_key) in self.signatures_and_public_keys() {
-            if !signature.is_valid_for(&message_bytes, public_key) {
-                return false;
-            }
+    /// Same as [`Self::for_message`], but signs with any signers of the same scheme
+    pub fn for_message_with_signers<S: Signer>(message: &Message, signers: &[&S]) -> Self {
+        Self {
+            scheme: S::SCHEME,
+            signatures_and_public_keys: signature::sign_message(&message.to_bytes(), signers, None),
         }
-        true
+    }
+
+    pub fn is_valid_for(&self, message: &Message) -> bool {
+        signature::verify_batch(
+            self.scheme,
+            &self.scheme.sighash(&message.to_bytes()),
+            self.signatures_and_public_keys
+                .iter()
+                .map(|(signature, public_key)| {
+                    (public_key.value().as_slice(), signature.value().as_slice())
+                }),
+        )
+    }
+
+    pub fn scheme(&self) -> SignatureScheme {
+        self.scheme
     }
 
     pub fn signatures_and_public_keys(&self) -> &[(Signature, PublicKey)] {
         &self.signatures_and_public_keys
     }
+
+    /// Accounts of the signers, in the order of signatures
+    pub fn signer_account_ids(&self) -> Vec<AccountId> {
+        self.signatures_and_public_keys
+            .iter()
+            .map(|(_, public_key)| signature::account_id(self.scheme, public_key.value()))
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use nssa_core::program::DEFAULT_PROGRAM_ID;
+
     use super::*;
-    use crate::AccountId;
 
     #[test]
     fn test_for_message_constructor() {
@@ -54,7 +87,8 @@ mod tests {
         let addr2 = AccountId::from(&pubkey2);
         let nonces = vec![1, 2];
         let instruction = vec![1, 2, 3, 4];
-        let message = Message::try_new([0; 8], vec![addr1, addr2], nonces, instruction).unwrap();
+        let message =
+            Message::try_new(DEFAULT_PROGRAM_ID, vec![addr1, addr2], nonces, instruction).unwrap();
 
         let witness_set = WitnessSet::for_message(&message, &[&key1, &key2]);
 
@@ -70,4 +104,34 @@ mod tests {
             assert!(signature.is_valid_for(&message_bytes, &expected_public_key));
         }
     }
+
+    #[test]
+    fn test_scheme_byte_roundtrip() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let message =
+            Message::try_new(DEFAULT_PROGRAM_ID, vec![account_id], vec![1], 10_u128).unwrap();
+        let witness_set = WitnessSet::for_message(&message, &[&key]);
+
+        let bytes = borsh::to_vec(&witness_set).unwrap();
+        assert_eq!(bytes[0], SignatureScheme::Schnorr.to_byte());
+
+        let decoded: WitnessSet = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.scheme(), SignatureScheme::Schnorr);
+        assert_eq!(decoded, witness_set);
+        assert_eq!(decoded.signer_account_ids(), [account_id]);
+    }
+
+    #[test]
+    fn test_unknown_scheme_byte_is_rejected() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let message =
+            Message::try_new(DEFAULT_PROGRAM_ID, vec![account_id], vec![1], 10_u128).unwrap();
+        let mut bytes = borsh::to_vec(&WitnessSet::for_message(&message, &[&key])).unwrap();
+        bytes[0] = 0xff;
+
+        let err = borsh::from_slice::<WitnessSet>(&bytes).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown signature scheme 255");
+    }
 }
@@ -1,10 +1,18 @@
 mod private_key;
 mod public_key;
+mod scheme;
+mod signed_message;
+mod signer;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 pub use private_key::PrivateKey;
 pub use public_key::PublicKey;
-use rand::{RngCore, rngs::OsRng};
+pub use scheme::{SignatureScheme, account_id, verify, verify_batch};
+pub use signed_message::{
+    MESSAGE_SIGNATURE_LEN, MessageSignature, SIGNED_MESSAGE_PREFIX, signed_message_digest,
+};
+pub use signer::Signer;
+pub(crate) use signer::sign_message;
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct Signature {
@@ -13,19 +21,16 @@ pub struct Signature {
 
 impl Signature {
     pub fn new(key: &PrivateKey, message: &[u8]) -> Self {
-        let mut aux_random = [0u8; 32];
-        OsRng.fill_bytes(&mut aux_random);
-        Self::new_with_aux_random(key, message, aux_random)
+        Self::new_with_aux_random(key, message, crate::entropy::random_bytes())
     }
 
-    pub(crate) fn new_with_aux_random(
-        key: &PrivateKey,
-        message: &[u8],
-        aux_random: [u8; 32],
-    ) -> Self {
+    /// Signs with the given auxiliary randomness instead of a fresh one, so the signature is
+    /// reproducible
+    pub fn new_with_aux_random(key: &PrivateKey, message: &[u8], aux_random: [u8; 32]) -> Self {
         let value = {
             let secp = secp256k1::Secp256k1::new();
-            let secret_key = secp256k1::SecretKey::from_byte_array(*key.value()).unwrap();
+            let secret_key = secp256k1::SecretKey::from_byte_array(*key.value())
+                .expect("Private key is a valid scalar by construction");
             let keypair = secp256k1::Keypair::from_secret_key(&secp, &secret_key);
             let signature = secp.sign_schnorr_with_aux_rand(message, &keypair, &aux_random);
             signature.to_byte_array()
@@ -33,11 +38,17 @@ impl Signature {
         Self { value }
     }
 
+    pub fn value(&self) -> &[u8; 64] {
+        &self.value
+    }
+
     pub fn is_valid_for(&self, bytes: &[u8], public_key: &PublicKey) -> bool {
-        let pk = secp256k1::XOnlyPublicKey::from_byte_array(*public_key.value()).unwrap();
-        let secp = secp256k1::Secp256k1::new();
-        let sig = secp256k1::schnorr::Signature::from_byte_array(self.value);
-        secp.verify_schnorr(&sig, bytes, &pk).is_ok()
+        verify(
+            SignatureScheme::Schnorr,
+            bytes,
+            public_key.value(),
+            &self.value,
+        )
     }
 }
 
@@ -47,7 +58,7 @@ mod bip340_test_vectors;
 #[cfg(test)]
 mod tests {
 
-    use crate::{Signature, signature::bip340_test_vectors};
+    use crate::{PrivateKey, PublicKey, Signature, signature::bip340_test_vectors};
 
     impl Signature {
         pub(crate) fn new_for_tests(value: [u8; 64]) -> Self {
@@ -57,38 +68,76 @@ mod tests {
 
     #[test]
     fn test_signature_generation_from_bip340_test_vectors() {
-        for (i, test_vector) in bip340_test_vectors::test_vectors().into_iter().enumerate() {
+        for test_vector in bip340_test_vectors::test_vectors() {
             let Some(private_key) = test_vector.seckey else {
                 continue;
             };
             let Some(aux_random) = test_vector.aux_rand else {
                 continue;
             };
-            let Some(message) = test_vector.message else {
-                continue;
-            };
+
+            let signature = private_key.sign_with_aux_rand(&test_vector.message, aux_random);
+
+            assert_eq!(
+                signature, test_vector.signature,
+                "Failed test vector {}",
+                test_vector.index
+            );
+            assert_eq!(
+                PublicKey::new_from_private_key(&private_key).value(),
+                &test_vector.pubkey,
+                "Failed test vector {}",
+                test_vector.index
+            );
+        }
+    }
+
+    #[test]
+    fn test_valid_signatures_from_bip340_test_vectors_are_accepted() {
+        for test_vector in bip340_test_vectors::test_vectors() {
             if !test_vector.verification_result {
                 continue;
             }
-            let expected_signature = &test_vector.signature;
 
-            let signature = Signature::new_with_aux_random(&private_key, &message, aux_random);
+            let public_key = PublicKey::try_new(test_vector.pubkey).unwrap();
 
-            assert_eq!(&signature, expected_signature, "Failed test vector {i}");
+            assert!(
+                test_vector
+                    .signature
+                    .is_valid_for(&test_vector.message, &public_key),
+                "Failed test vector {}",
+                test_vector.index
+            );
         }
     }
 
     #[test]
-    fn test_signature_verification_from_bip340_test_vectors() {
-        for (i, test_vector) in bip340_test_vectors::test_vectors().into_iter().enumerate() {
-            let message = test_vector.message.unwrap_or(vec![]);
-            let expected_result = test_vector.verification_result;
+    fn test_invalid_signatures_from_bip340_test_vectors_are_rejected() {
+        for test_vector in bip340_test_vectors::test_vectors() {
+            if test_vector.verification_result {
+                continue;
+            }
 
-            let result = test_vector
-                .signature
-                .is_valid_for(&message, &test_vector.pubkey);
+            // Vectors with invalid public keys are rejected when the key is parsed
+            let accepted = PublicKey::try_new(test_vector.pubkey).is_ok_and(|public_key| {
+                test_vector
+                    .signature
+                    .is_valid_for(&test_vector.message, &public_key)
+            });
 
-            assert_eq!(result, expected_result, "Failed test vector {i}");
+            assert!(!accepted, "Failed test vector {}", test_vector.index);
         }
     }
+
+    #[test]
+    fn test_sign_uses_fresh_aux_rand() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let public_key = PublicKey::new_from_private_key(&key);
+        let message = b"message";
+
+        let signature = key.sign(message);
+
+        assert!(signature.is_valid_for(message, &public_key));
+        assert_ne!(signature, key.sign(message));
+    }
 }
@@ -1,25 +1,54 @@
-use rand::{Rng, rngs::OsRng};
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq as _;
 
-use crate::error::NssaError;
+use crate::{Signature, error::NssaError};
 
-// TODO: Remove Debug, Clone, Serialize, Deserialize, PartialEq and Eq for security reasons
+// TODO: Remove Clone, Serialize and Deserialize for security reasons
 // TODO: Implement Zeroize
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Valid secp256k1 scalar, deserialization rejects zero and values not less than the curve order
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "[u8; 32]")]
 pub struct PrivateKey([u8; 32]);
 
+/// Keys are compared in constant time, so comparison doesn't leak their bytes through timing
+impl PartialEq for PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0).into()
+    }
+}
+
+impl Eq for PrivateKey {}
+
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PrivateKey(<redacted>)")
+    }
+}
+
 impl PrivateKey {
+    /// Key of [`crate::entropy`], which is OS randomness unless deterministic dev mode is on
     pub fn new_os_random() -> Self {
-        let mut rng = OsRng;
-
         loop {
-            match Self::try_new(rng.r#gen()) {
+            match Self::try_new(crate::entropy::random_bytes()) {
                 Ok(key) => break key,
                 Err(_) => continue,
             };
         }
     }
 
+    /// Key drawn from `rng`, so seeded RNGs produce the same keys
+    pub fn new_from_rng(rng: &mut impl rand::RngCore) -> Self {
+        loop {
+            let mut value = [0; 32];
+            rng.fill_bytes(&mut value);
+            if let Ok(key) = Self::try_new(value) {
+                break key;
+            }
+        }
+    }
+
     fn is_valid_key(value: [u8; 32]) -> bool {
         secp256k1::SecretKey::from_byte_array(value).is_ok()
     }
@@ -35,6 +64,24 @@ impl PrivateKey {
     pub fn value(&self) -> &[u8; 32] {
         &self.0
     }
+
+    /// Signs `message` with fresh auxiliary randomness
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        Signature::new(self, message)
+    }
+
+    /// Signs `message` with the given auxiliary randomness, so the signature is reproducible
+    pub fn sign_with_aux_rand(&self, message: &[u8], aux_rand: [u8; 32]) -> Signature {
+        Signature::new_with_aux_random(self, message, aux_rand)
+    }
+}
+
+impl TryFrom<[u8; 32]> for PrivateKey {
+    type Error = NssaError;
+
+    fn try_from(value: [u8; 32]) -> Result<Self, Self::Error> {
+        Self::try_new(value)
+    }
 }
 
 #[cfg(test)]
@@ -46,6 +93,43 @@ mod tests {
         assert_eq!(key.value(), &key.0);
     }
 
+    #[test]
+    fn test_debug_doesnt_reveal_key() {
+        let key = PrivateKey::try_new([0xab; 32]).unwrap();
+        assert_eq!(format!("{key:?}"), "PrivateKey(<redacted>)");
+    }
+
+    #[test]
+    fn test_equality() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        assert_eq!(key, PrivateKey::try_new([1; 32]).unwrap());
+        assert_ne!(key, PrivateKey::try_new([2; 32]).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_scalars_are_rejected() {
+        let curve_order =
+            hex_literal::hex!("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141");
+
+        assert!(PrivateKey::try_new([0; 32]).is_err());
+        assert!(PrivateKey::try_new(curve_order).is_err());
+        assert!(PrivateKey::try_new([0xff; 32]).is_err());
+    }
+
+    #[test]
+    fn test_deserialization_rejects_invalid_scalar() {
+        let zero_key = serde_json::to_string(&[0u8; 32]).unwrap();
+        assert!(serde_json::from_str::<PrivateKey>(&zero_key).is_err());
+
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let serialized = serde_json::to_string(&key).unwrap();
+        assert_eq!(serialized, serde_json::to_string(&[1u8; 32]).unwrap());
+        assert_eq!(
+            serde_json::from_str::<PrivateKey>(&serialized).unwrap(),
+            key
+        );
+    }
+
     #[test]
     fn test_produce_key() {
         let _key = PrivateKey::new_os_random();
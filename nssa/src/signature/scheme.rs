@@ -0,0 +1,229 @@
+use std::borrow::Cow;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use nssa_core::account::AccountId;
+use sha2::{Digest as _, Sha256};
+
+use crate::error::NssaError;
+
+/// Prefix of hashes deriving public account ids, the scheme byte completes it to 32 bytes
+const PUBLIC_ACCOUNT_ID_PREFIX: &[u8; 31] = b"/NSSA/v0.2/AccountId/Public/\x00\x00\x00";
+
+/// Scheme of signatures and public keys of a witness set, encoded as a single byte
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SignatureScheme {
+    /// BIP-340 schnorr signatures over secp256k1
+    #[default]
+    Schnorr,
+}
+
+impl SignatureScheme {
+    pub const ALL: [SignatureScheme; 1] = [SignatureScheme::Schnorr];
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::Schnorr => 0,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Result<Self, NssaError> {
+        Self::ALL
+            .into_iter()
+            .find(|scheme| scheme.to_byte() == byte)
+            .ok_or(NssaError::UnknownSignatureScheme(byte))
+    }
+
+    /// Bytes signed for a message encoded as `message_bytes`
+    ///
+    /// BIP-340 hashes the message with its own tag, so schnorr signs the message bytes as is.
+    /// Signatures made before witness sets carried a scheme stay valid this way.
+    pub fn sighash(self, message_bytes: &[u8]) -> Cow<'_, [u8]> {
+        match self {
+            Self::Schnorr => Cow::Borrowed(message_bytes),
+        }
+    }
+}
+
+impl BorshSerialize for SignatureScheme {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        BorshSerialize::serialize(&self.to_byte(), writer)
+    }
+}
+
+impl BorshDeserialize for SignatureScheme {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let byte = u8::deserialize_reader(reader)?;
+        Self::from_byte(byte)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))
+    }
+}
+
+/// Whether `signature` of `message` is valid for `public_key` under `scheme`
+///
+/// Keys and signatures of the wrong length for the scheme are invalid.
+pub fn verify(
+    scheme: SignatureScheme,
+    message: &[u8],
+    public_key: &[u8],
+    signature: &[u8],
+) -> bool {
+    match scheme {
+        SignatureScheme::Schnorr => verify_schnorr(
+            &secp256k1::Secp256k1::verification_only(),
+            message,
+            public_key,
+            signature,
+        ),
+    }
+}
+
+/// Whether every signature of `message` in `batch` is valid for its public key under `scheme`
+///
+/// secp256k1 has no batch verification of schnorr signatures, so they're verified one by one,
+/// sharing the verification context.
+pub fn verify_batch<'a>(
+    scheme: SignatureScheme,
+    message: &[u8],
+    batch: impl IntoIterator<Item = (&'a [u8], &'a [u8])>,
+) -> bool {
+    match scheme {
+        SignatureScheme::Schnorr => {
+            let secp = secp256k1::Secp256k1::verification_only();
+            batch.into_iter().all(|(public_key, signature)| {
+                verify_schnorr(&secp, message, public_key, signature)
+            })
+        }
+    }
+}
+
+fn verify_schnorr(
+    secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    message: &[u8],
+    public_key: &[u8],
+    signature: &[u8],
+) -> bool {
+    let (Ok(public_key), Ok(signature)) = (
+        <[u8; 32]>::try_from(public_key),
+        <[u8; 64]>::try_from(signature),
+    ) else {
+        return false;
+    };
+    let Ok(public_key) = secp256k1::XOnlyPublicKey::from_byte_array(public_key) else {
+        return false;
+    };
+    let signature = secp256k1::schnorr::Signature::from_byte_array(signature);
+    secp.verify_schnorr(&signature, message, &public_key)
+        .is_ok()
+}
+
+/// Id of the public account owned by `public_key` of `scheme`
+///
+/// The scheme byte is hashed together with the key, so keys of different schemes never own the
+/// same account. The schnorr byte is zero, so ids of schnorr keys are the same as before schemes
+/// were introduced.
+pub fn account_id(scheme: SignatureScheme, public_key: &[u8]) -> AccountId {
+    let mut hasher = Sha256::new();
+    hasher.update(PUBLIC_ACCOUNT_ID_PREFIX);
+    hasher.update([scheme.to_byte()]);
+    hasher.update(public_key);
+    AccountId::new(hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PrivateKey, PublicKey};
+
+    #[test]
+    fn test_scheme_byte_roundtrip() {
+        for scheme in SignatureScheme::ALL {
+            let bytes = borsh::to_vec(&scheme).unwrap();
+
+            assert_eq!(bytes, [scheme.to_byte()]);
+            assert_eq!(
+                borsh::from_slice::<SignatureScheme>(&bytes).unwrap(),
+                scheme
+            );
+        }
+        assert_eq!(SignatureScheme::Schnorr.to_byte(), 0);
+    }
+
+    #[test]
+    fn test_unknown_scheme_byte_is_rejected() {
+        assert!(matches!(
+            SignatureScheme::from_byte(7),
+            Err(NssaError::UnknownSignatureScheme(7))
+        ));
+
+        let err = borsh::from_slice::<SignatureScheme>(&[7]).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown signature scheme 7");
+    }
+
+    #[test]
+    fn test_schnorr_account_id_is_unchanged() {
+        let public_key = PublicKey::new_from_private_key(&PrivateKey::try_new([1; 32]).unwrap());
+
+        let expected: [u8; 32] = Sha256::new()
+            .chain_update(b"/NSSA/v0.2/AccountId/Public/\x00\x00\x00\x00")
+            .chain_update(public_key.value())
+            .finalize()
+            .into();
+        assert_eq!(
+            account_id(SignatureScheme::Schnorr, public_key.value()),
+            AccountId::new(expected)
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_malformed_keys_and_signatures() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let public_key = PublicKey::new_from_private_key(&key);
+        let signature = key.sign(b"message");
+        let scheme = SignatureScheme::Schnorr;
+
+        assert!(verify(
+            scheme,
+            b"message",
+            public_key.value(),
+            signature.value()
+        ));
+        assert!(!verify(
+            scheme,
+            b"other",
+            public_key.value(),
+            signature.value()
+        ));
+        assert!(!verify(
+            scheme,
+            b"message",
+            &public_key.value()[1..],
+            signature.value()
+        ));
+        assert!(!verify(
+            scheme,
+            b"message",
+            public_key.value(),
+            &signature.value()[..63]
+        ));
+
+        let other_key = PrivateKey::try_new([2; 32]).unwrap();
+        let other_public_key = PublicKey::new_from_private_key(&other_key);
+        let other_signature = other_key.sign(b"message");
+        assert!(verify_batch(
+            scheme,
+            b"message",
+            [
+                (public_key.value().as_slice(), signature.value().as_slice()),
+                (other_public_key.value(), other_signature.value()),
+            ]
+        ));
+        assert!(!verify_batch(
+            scheme,
+            b"message",
+            [
+                (public_key.value().as_slice(), signature.value().as_slice()),
+                (other_public_key.value(), signature.value()),
+            ]
+        ));
+    }
+}
@@ -0,0 +1,225 @@
+//! Signatures of arbitrary messages, which prove control of a public account.
+//!
+//! A message is signed as a BIP-340 signature of its digest
+//! `SHA-256("NSSA Signed Message:\n" || decimal length of message || message)`. The digest is 32
+//! bytes, while every encoded transaction message is longer, so a message signature is never
+//! valid for a transaction. Encoded signature is base64 of the public key followed by the
+//! signature, 96 bytes in total.
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use nssa_core::account::AccountId;
+use sha2::{Digest as _, Sha256};
+
+use crate::{PrivateKey, PublicKey, Signature, error::NssaError};
+
+/// Prefix of signed messages, which separates them from other signed data
+pub const SIGNED_MESSAGE_PREFIX: &[u8] = b"NSSA Signed Message:\n";
+
+/// Length of an encoded [`MessageSignature`] before base64
+pub const MESSAGE_SIGNATURE_LEN: usize = 96;
+
+/// Digest, which is signed instead of `message`
+pub fn signed_message_digest(message: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(SIGNED_MESSAGE_PREFIX);
+    hasher.update(message.len().to_string());
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+/// Signature of a message with the public key it's made with, so it's verified by account id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageSignature {
+    public_key: PublicKey,
+    signature: Signature,
+}
+
+impl MessageSignature {
+    /// Signs `message` with fresh auxiliary randomness
+    pub fn sign(key: &PrivateKey, message: &[u8]) -> Self {
+        Self {
+            public_key: PublicKey::new_from_private_key(key),
+            signature: key.sign(&signed_message_digest(message)),
+        }
+    }
+
+    /// Signs `message` with the given auxiliary randomness, so the signature is reproducible
+    pub fn sign_with_aux_rand(key: &PrivateKey, message: &[u8], aux_rand: [u8; 32]) -> Self {
+        Self {
+            public_key: PublicKey::new_from_private_key(key),
+            signature: key.sign_with_aux_rand(&signed_message_digest(message), aux_rand),
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Public account, which the signature is made for
+    pub fn account_id(&self) -> AccountId {
+        AccountId::from(&self.public_key)
+    }
+
+    /// Whether this is a signature of `message` by the key of public account `account_id`
+    pub fn is_valid_for(&self, message: &[u8], account_id: &AccountId) -> bool {
+        self.account_id() == *account_id
+            && self
+                .signature
+                .is_valid_for(&signed_message_digest(message), &self.public_key)
+    }
+
+    pub fn to_bytes(&self) -> [u8; MESSAGE_SIGNATURE_LEN] {
+        let mut bytes = [0; MESSAGE_SIGNATURE_LEN];
+        bytes[..32].copy_from_slice(self.public_key.value());
+        bytes[32..].copy_from_slice(self.signature.value());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NssaError> {
+        let bytes: &[u8; MESSAGE_SIGNATURE_LEN] = bytes.try_into().map_err(|_| {
+            NssaError::InvalidInput(format!(
+                "Message signature is {} bytes, expected {MESSAGE_SIGNATURE_LEN}",
+                bytes.len()
+            ))
+        })?;
+        let (public_key, signature) = bytes.split_at(32);
+
+        Ok(Self {
+            public_key: PublicKey::try_new(public_key.try_into().unwrap())?,
+            signature: Signature {
+                value: signature.try_into().unwrap(),
+            },
+        })
+    }
+
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(self.to_bytes())
+    }
+
+    pub fn from_base64(encoded: &str) -> Result<Self, NssaError> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|err| NssaError::InvalidInput(format!("Invalid base64: {err}")))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+
+    use super::*;
+    use crate::public_transaction;
+
+    struct TestVector {
+        private_key: [u8; 32],
+        aux_rand: [u8; 32],
+        message: &'static [u8],
+        account_id: &'static str,
+        digest: [u8; 32],
+        signature: &'static str,
+    }
+
+    /// Computed independently of this crate, for implementations in other languages to check
+    /// against
+    fn test_vectors() -> [TestVector; 3] {
+        [
+            TestVector {
+                private_key: [1; 32],
+                aux_rand: [0; 32],
+                message: b"",
+                account_id: "F2pRn2BwbTSvzJf6iQQFEybexgJZapMLjED8DimjG55M",
+                digest: hex!("fc793063b745c174ad86c05a6268971bd5f2c2011f388a4b68efab82e212f6a7"),
+                signature: "G4TFVnsSZECZXT7VqroFZdceGDRgSBn/nBf16dXdB4+UzvGmufQ64ZPo2ZrwhNGgkkkFLWTRzkeO\
+                            49i6lr2lJ5XnpM04XY3kN3NoUez9H+oJHr/cQwtsSysSxs2IR1u/",
+            },
+            TestVector {
+                private_key: [2; 32],
+                aux_rand: [7; 32],
+                message: b"Hello, NSSA!",
+                account_id: "GbPUkVbVhu5kBgFFf6PwKAcWT7tJtQpWyv7EXkDZ7YrP",
+                digest: hex!("ec9ed4ace1167387b691f6935b74505047635efab5e3d67f695b393e3cd6ce42"),
+                signature: "TUts0TYQMsqb0q652QCqTUXZ6tgKyUIzdMRRpyVNB2Yn0D9RxkwCLaZ5PrNhypUKCA3lkf19/2Ae\
+                            0oOlRxOzbbin4SOAngUr+5hV1nKh+UYWzRKBD1YqhaN/7HvtE3az",
+            },
+            TestVector {
+                private_key: hex!(
+                    "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20"
+                ),
+                aux_rand: [0; 32],
+                message: "Sign in to example.com\nNonce: 8f3aé".as_bytes(),
+                account_id: "FbCEmsFekX4BMcsdVkEy493rYeJwNVCRTiYQ79DTwLji",
+                digest: hex!("263da99ceb57e7309e3218f9b5b3f06cd94fead8b0728fb6d47cd036972710b7"),
+                signature: "hL91YiYrvWlACFdI875q+lKuMXFVGB7OMbZjUcz/pLCqKJriqndU7iw3Sa1z7OMpkC1gJbvh\
+                            HLzJvSiWXlbtS+exsJExnnavmEhGJPru0uPb86oVGPULLBWivyAacr2g",
+            },
+        ]
+    }
+
+    #[test]
+    fn test_message_signatures_match_test_vectors() {
+        for vector in test_vectors() {
+            let key = PrivateKey::try_new(vector.private_key).unwrap();
+            let account_id: AccountId = vector.account_id.parse().unwrap();
+            assert_eq!(signed_message_digest(vector.message), vector.digest);
+
+            let signature =
+                MessageSignature::sign_with_aux_rand(&key, vector.message, vector.aux_rand);
+            assert_eq!(signature.account_id(), account_id);
+            assert_eq!(signature.to_base64(), vector.signature);
+
+            let decoded = MessageSignature::from_base64(vector.signature).unwrap();
+            assert!(decoded.is_valid_for(vector.message, &account_id));
+        }
+    }
+
+    #[test]
+    fn test_message_signature_is_bound_to_message_and_account() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let signature = MessageSignature::sign(&key, b"challenge");
+        let account_id = signature.account_id();
+
+        assert!(signature.is_valid_for(b"challenge", &account_id));
+        assert!(!signature.is_valid_for(b"challenge2", &account_id));
+        assert!(!signature.is_valid_for(b"challenge", &AccountId::new([0; 32])));
+    }
+
+    #[test]
+    fn test_invalid_encoded_signatures_are_rejected() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let encoded = MessageSignature::sign(&key, b"challenge").to_bytes();
+
+        assert!(MessageSignature::from_base64("not base64!").is_err());
+        assert!(MessageSignature::from_bytes(&encoded[..95]).is_err());
+
+        // Public key isn't a valid point
+        let mut invalid_key = encoded;
+        invalid_key[..32].copy_from_slice(&[0xff; 32]);
+        assert!(MessageSignature::from_bytes(&invalid_key).is_err());
+    }
+
+    #[test]
+    fn test_message_signature_is_not_valid_for_transactions() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let public_key = PublicKey::new_from_private_key(&key);
+        let message = public_transaction::Message::try_new(
+            nssa_core::program::DEFAULT_PROGRAM_ID,
+            vec![],
+            vec![],
+            0_u128,
+        )
+        .unwrap();
+        let message_bytes = message.to_bytes();
+
+        // Even the shortest transaction message is longer than a signed digest
+        assert!(message_bytes.len() > 32);
+
+        // Signing transaction bytes as a message doesn't sign the transaction
+        let signature = MessageSignature::sign(&key, &message_bytes);
+        assert!(
+            !signature
+                .signature
+                .is_valid_for(&message_bytes, &public_key)
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{PrivateKey, PublicKey, Signature, signature::SignatureScheme};
+
+/// Key signing transactions of a wallet
+///
+/// Witness sets are signed through it, so keys kept outside of the wallet, e.g. on hardware
+/// tokens, can sign as well. Every signer of a witness set is of the same scheme.
+pub trait Signer {
+    const SCHEME: SignatureScheme;
+
+    fn public_key(&self) -> PublicKey;
+
+    /// Signs `sighash` of [`Self::SCHEME`] with the given auxiliary randomness
+    fn sign_with_aux_rand(&self, sighash: &[u8], aux_rand: [u8; 32]) -> Signature;
+
+    /// Signs `sighash` of [`Self::SCHEME`] with fresh auxiliary randomness
+    fn sign(&self, sighash: &[u8]) -> Signature {
+        self.sign_with_aux_rand(sighash, crate::entropy::random_bytes())
+    }
+}
+
+impl Signer for PrivateKey {
+    const SCHEME: SignatureScheme = SignatureScheme::Schnorr;
+
+    fn public_key(&self) -> PublicKey {
+        PublicKey::new_from_private_key(self)
+    }
+
+    fn sign_with_aux_rand(&self, sighash: &[u8], aux_rand: [u8; 32]) -> Signature {
+        Signature::new_with_aux_random(self, sighash, aux_rand)
+    }
+}
+
+/// Signatures of `message_bytes` by `signers` with their public keys, each one signed with
+/// `aux_rand` if given and fresh auxiliary randomness otherwise
+pub(crate) fn sign_message<S: Signer>(
+    message_bytes: &[u8],
+    signers: &[&S],
+    aux_rand: Option<[u8; 32]>,
+) -> Vec<(Signature, PublicKey)> {
+    let sighash = S::SCHEME.sighash(message_bytes);
+    signers
+        .iter()
+        .map(|&signer| {
+            let signature = match aux_rand {
+                Some(aux_rand) => signer.sign_with_aux_rand(&sighash, aux_rand),
+                None => signer.sign(&sighash),
+            };
+            (signature, signer.public_key())
+        })
+        .collect()
+}
@@ -1,24 +1,51 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use nssa_core::{
-    Commitment, CommitmentSetDigest, DUMMY_COMMITMENT, MembershipProof, Nullifier,
+    Commitment, CommitmentSetDigest, DUMMY_COMMITMENT, DUMMY_COMMITMENT_HASH, MembershipProof,
+    Nullifier,
     account::{Account, AccountId},
-    program::ProgramId,
+    burn::{BURN_ACCOUNT_ID, burn_account},
+    clock::{CLOCK_ACCOUNT_ID, clock_account},
+    program::{ProgramId, is_storage_deposit_kept},
+    units::Amount,
 };
+use sha2::{Digest as _, Sha256};
 
 use crate::{
-    error::NssaError, merkle_tree::MerkleTree,
-    privacy_preserving_transaction::PrivacyPreservingTransaction, program::Program,
+    account_tree::{AccountProof, AccountTree},
+    error::NssaError,
+    gas::PaidFees,
+    merkle_tree::MerkleTree,
+    privacy_preserving_transaction::PrivacyPreservingTransaction,
+    program::{DEFAULT_MAX_INSTRUCTION_DATA_SIZE, DEFAULT_MAX_PROGRAM_SIZE, Program},
+    program_allow_list::ProgramAllowList,
     program_deployment_transaction::ProgramDeploymentTransaction,
-    public_transaction::PublicTransaction,
+    prover::ProverBackend,
+    public_transaction::{ProgramExecutionProof, PublicTransaction},
 };
 
 pub const MAX_NUMBER_CHAINED_CALLS: usize = 10;
 
+/// Number of the latest blocks, which anchors private transactions may prove membership against
+/// by default, see [`V02State::set_anchor_window`]
+pub const DEFAULT_ANCHOR_WINDOW: u64 = 100;
+
+/// Root of the commitment set at the start of a block, which private transactions may prove
+/// membership of spent commitments against while the block is recent enough
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Anchor {
+    pub block_id: u64,
+    pub digest: CommitmentSetDigest,
+}
+
+#[derive(Clone)]
 pub(crate) struct CommitmentSet {
     merkle_tree: MerkleTree,
     commitments: HashMap<Commitment, usize>,
+    /// Every root the set had, so expired anchors are told apart from unknown roots
     root_history: HashSet<CommitmentSetDigest>,
+    /// Anchors of the latest blocks, from the oldest one
+    anchors: VecDeque<Anchor>,
 }
 
 impl CommitmentSet {
@@ -48,6 +75,35 @@ impl CommitmentSet {
         self.commitments.contains_key(commitment)
     }
 
+    /// Records the current root as the anchor of `block_id` and forgets anchors of blocks, which
+    /// are `window` or more blocks older
+    fn record_anchor(&mut self, block_id: u64, window: u64) {
+        // Block id may be set again, the anchor stays the root at the start of the block
+        if self
+            .anchors
+            .back()
+            .is_none_or(|anchor| anchor.block_id < block_id)
+        {
+            self.anchors.push_back(Anchor {
+                block_id,
+                digest: self.digest(),
+            });
+        }
+        while self
+            .anchors
+            .front()
+            .is_some_and(|anchor| anchor.block_id.saturating_add(window) <= block_id)
+        {
+            self.anchors.pop_front();
+        }
+    }
+
+    /// Whether membership may be proven against `digest`, which is the current root or an anchor
+    /// of a recent block
+    fn is_recent_root(&self, digest: &CommitmentSetDigest) -> bool {
+        *digest == self.digest() || self.anchors.iter().any(|anchor| anchor.digest == *digest)
+    }
+
     /// Initializes an empty `CommitmentSet` with a given capacity.
     /// If the capacity is not a power_of_two, then capacity is taken
     /// to be the next power_of_two.
@@ -56,25 +112,57 @@ impl CommitmentSet {
             merkle_tree: MerkleTree::with_capacity(capacity),
             commitments: HashMap::new(),
             root_history: HashSet::new(),
+            anchors: VecDeque::new(),
         }
     }
 }
 
 type NullifierSet = HashSet<Nullifier>;
 
+/// Size of public state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StateSize {
+    /// Number of stored public accounts
+    pub accounts: usize,
+    /// Total size of data of public accounts
+    pub data_bytes: usize,
+}
+
+#[derive(Clone)]
 pub struct V02State {
     public_state: HashMap<AccountId, Account>,
+    /// Tree over `public_state`, which is updated together with it
+    account_tree: AccountTree,
     private_state: (CommitmentSet, NullifierSet),
     programs: HashMap<ProgramId, Program>,
+    /// Receives priority fees of public transactions and fees of program deployments, which are
+    /// rejected if it's not set
+    fee_recipient: Option<AccountId>,
+    /// Chain, which program deployments must be signed for
+    chain_id: u64,
+    /// Whether proofs of [`crate::ProverBackend::DevFake`] are accepted
+    accept_dev_proofs: bool,
+    program_allow_list: ProgramAllowList,
+    /// Balance locked per byte of data of public accounts, see
+    /// [`nssa_core::account::Account::storage_deposit`]
+    storage_deposit_per_byte: Amount,
+    /// Maximum number of words of instruction data of public transactions, see
+    /// [`PublicTransaction::validate_instruction`]
+    max_instruction_data_size: usize,
+    /// Maximum number of bytes of bytecode of deployed programs, see
+    /// [`ProgramDeploymentTransaction::validate_program`]
+    max_program_size: usize,
+    /// Number of the latest blocks, which anchors are accepted, see [`Anchor`]
+    anchor_window: u64,
 }
 
 impl V02State {
     pub fn new_with_genesis_accounts(
-        initial_data: &[(AccountId, u128)],
+        initial_data: &[(AccountId, Amount)],
         initial_commitments: &[nssa_core::Commitment],
     ) -> Self {
         let authenticated_transfer_program = Program::authenticated_transfer_program();
-        let public_state = initial_data
+        let public_state: HashMap<_, _> = initial_data
             .iter()
             .copied()
             .map(|(account_id, balance)| {
@@ -85,46 +173,209 @@ impl V02State {
                 };
                 (account_id, account)
             })
+            .chain([
+                (CLOCK_ACCOUNT_ID, clock_account(0)),
+                (BURN_ACCOUNT_ID, burn_account()),
+            ])
             .collect();
 
         let mut private_state = CommitmentSet::with_capacity(32);
         private_state.extend(&[DUMMY_COMMITMENT]);
         private_state.extend(initial_commitments);
 
+        let account_tree = public_state.iter().collect();
+
         let mut this = Self {
             public_state,
+            account_tree,
             private_state: (private_state, NullifierSet::new()),
             programs: HashMap::new(),
+            fee_recipient: None,
+            chain_id: 0,
+            accept_dev_proofs: false,
+            program_allow_list: ProgramAllowList::default(),
+            storage_deposit_per_byte: Amount::ZERO,
+            max_instruction_data_size: DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+            max_program_size: DEFAULT_MAX_PROGRAM_SIZE,
+            anchor_window: DEFAULT_ANCHOR_WINDOW,
         };
 
         this.insert_program(Program::authenticated_transfer_program());
         this.insert_program(Program::token());
+        this.insert_program(Program::escrow());
 
         this
     }
 
+    /// Restricts programs, which transactions may call, see [`ProgramAllowList`]
+    ///
+    /// Allowed builtin programs are added to the state.
+    pub fn with_program_allow_list(mut self, program_allow_list: ProgramAllowList) -> Self {
+        for builtin_program in &program_allow_list.builtin_programs {
+            self.insert_program(builtin_program.program());
+        }
+        self.program_allow_list = program_allow_list;
+        self
+    }
+
+    pub fn program_allow_list(&self) -> &ProgramAllowList {
+        &self.program_allow_list
+    }
+
+    /// Set id of the block, which transactions are applied to the state, readable by programs
+    /// from the clock account. The current root of the commitment set becomes the anchor of the
+    /// block, see [`Self::latest_anchors`].
+    pub fn set_block_id(&mut self, block_id: u64) {
+        self.set_public_account(CLOCK_ACCOUNT_ID, clock_account(block_id));
+        self.private_state
+            .0
+            .record_anchor(block_id, self.anchor_window);
+    }
+
+    /// Id of the block, which transactions are applied to the state, see [`Self::set_block_id`]
+    pub fn block_id(&self) -> u64 {
+        self.public_state
+            .get(&CLOCK_ACCOUNT_ID)
+            .and_then(|clock| clock.data.as_ref().try_into().ok())
+            .map(u64::from_le_bytes)
+            .expect("Clock account should keep block id")
+    }
+
+    pub fn set_fee_recipient(&mut self, account_id: AccountId) {
+        self.fee_recipient = Some(account_id);
+    }
+
+    pub fn fee_recipient(&self) -> Option<AccountId> {
+        self.fee_recipient
+    }
+
+    /// Set the chain, which program deployments must be signed for. It's 0 by default.
+    pub fn set_chain_id(&mut self, chain_id: u64) {
+        self.chain_id = chain_id;
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Accept proofs of [`crate::ProverBackend::DevFake`], which prove nothing, so only local
+    /// chains may accept them. They're rejected with [`NssaError::DevProofRejected`] by default.
+    pub fn set_accept_dev_proofs(&mut self, accept_dev_proofs: bool) {
+        self.accept_dev_proofs = accept_dev_proofs;
+    }
+
+    pub fn accept_dev_proofs(&self) -> bool {
+        self.accept_dev_proofs
+    }
+
+    /// Set the rate of storage deposits of the block, which transactions are applied to the
+    /// state. Deposits are not required by default.
+    pub fn set_storage_deposit_per_byte(&mut self, storage_deposit_per_byte: Amount) {
+        self.storage_deposit_per_byte = storage_deposit_per_byte;
+    }
+
+    pub fn storage_deposit_per_byte(&self) -> Amount {
+        self.storage_deposit_per_byte
+    }
+
+    /// Set the limit of instruction data of the block, which transactions are applied to the
+    /// state. It's [`DEFAULT_MAX_INSTRUCTION_DATA_SIZE`] by default.
+    pub fn set_max_instruction_data_size(&mut self, max_instruction_data_size: usize) {
+        self.max_instruction_data_size = max_instruction_data_size;
+    }
+
+    pub fn max_instruction_data_size(&self) -> usize {
+        self.max_instruction_data_size
+    }
+
+    /// Set the limit of program bytecode of the block, which transactions are applied to the
+    /// state. It's [`DEFAULT_MAX_PROGRAM_SIZE`] by default.
+    pub fn set_max_program_size(&mut self, max_program_size: usize) {
+        self.max_program_size = max_program_size;
+    }
+
+    pub fn max_program_size(&self) -> usize {
+        self.max_program_size
+    }
+
+    /// Set the number of the latest blocks, which anchors private transactions may prove
+    /// membership against, counting the block transactions are applied to. Older anchors are
+    /// rejected with [`NssaError::AnchorExpired`]. It's [`DEFAULT_ANCHOR_WINDOW`] by default.
+    pub fn set_anchor_window(&mut self, anchor_window: u64) {
+        self.anchor_window = anchor_window;
+    }
+
+    pub fn anchor_window(&self) -> u64 {
+        self.anchor_window
+    }
+
+    /// Anchors, which private transactions may prove membership against, from the oldest one
+    ///
+    /// Anchors are recorded by [`Self::set_block_id`], so they are rebuilt together with the rest
+    /// of the state by applying the same blocks.
+    pub fn latest_anchors(&self) -> impl DoubleEndedIterator<Item = Anchor> + '_ {
+        self.private_state.0.anchors.iter().copied()
+    }
+
     pub(crate) fn insert_program(&mut self, program: Program) {
         self.programs.insert(program.id(), program);
     }
 
+    /// Applies `tx` and returns the fees it paid
     pub fn transition_from_public_transaction(
         &mut self,
         tx: &PublicTransaction,
-    ) -> Result<(), NssaError> {
-        let state_diff = tx.validate_and_produce_public_state_diff(self)?;
+    ) -> Result<PaidFees, NssaError> {
+        let (state_diff, fees) = tx.validate_and_produce_public_state_diff(self)?;
+        self.apply_public_state_diff(tx, state_diff);
 
-        for (account_id, post) in state_diff.into_iter() {
-            let current_account = self.get_account_by_id_mut(account_id);
+        Ok(fees)
+    }
+
+    /// Same as [`Self::transition_from_public_transaction`], but skips stateless checks.
+    ///
+    /// Caller must ensure [`PublicTransaction::validate_structure`] passes and signatures are
+    /// valid, which allows to do these checks for many transactions in parallel.
+    pub fn transition_from_prevalidated_public_transaction(
+        &mut self,
+        tx: &PublicTransaction,
+    ) -> Result<PaidFees, NssaError> {
+        let (state_diff, fees) = tx.produce_public_state_diff(self)?;
+        self.apply_public_state_diff(tx, state_diff);
+
+        Ok(fees)
+    }
+
+    /// Same as [`Self::transition_from_prevalidated_public_transaction`], but also proves every
+    /// program execution of `tx` with `backend`, which is much slower.
+    pub fn prove_and_transition_from_public_transaction(
+        &mut self,
+        tx: &PublicTransaction,
+        backend: ProverBackend,
+    ) -> Result<(PaidFees, Vec<ProgramExecutionProof>), NssaError> {
+        let (state_diff, fees, proofs) = tx.produce_proven_public_state_diff(self, backend)?;
+        self.apply_public_state_diff(tx, state_diff);
+
+        Ok((fees, proofs))
+    }
 
-            *current_account = post;
+    fn apply_public_state_diff(
+        &mut self,
+        tx: &PublicTransaction,
+        state_diff: HashMap<AccountId, Account>,
+    ) {
+        for (account_id, post) in state_diff.into_iter() {
+            self.set_public_account(account_id, post);
         }
 
         for account_id in tx.signer_account_ids() {
-            let current_account = self.get_account_by_id_mut(account_id);
-            current_account.nonce += 1;
+            let mut account = self.get_account_by_id(&account_id);
+            assert!(
+                account.increment_nonce(),
+                "Transactions of accounts with exhausted nonces are rejected"
+            );
+            self.set_public_account(account_id, account);
         }
-
-        Ok(())
     }
 
     pub fn transition_from_privacy_preserving_transaction(
@@ -150,24 +401,47 @@ impl V02State {
 
         // 4. Update public accounts
         for (account_id, post) in public_state_diff.into_iter() {
-            let current_account = self.get_account_by_id_mut(account_id);
-            *current_account = post;
+            self.set_public_account(account_id, post);
         }
 
         Ok(())
     }
 
+    /// Deploys the program of `tx` and returns the fees it paid
     pub fn transition_from_program_deployment_transaction(
         &mut self,
         tx: &ProgramDeploymentTransaction,
-    ) -> Result<(), NssaError> {
-        let program = tx.validate_and_produce_public_state_diff(self)?;
-        self.insert_program(program);
-        Ok(())
-    }
+    ) -> Result<PaidFees, NssaError> {
+        let (program, state_diff, fees) = tx.validate_and_produce_public_state_diff(self)?;
+        for (account_id, post) in state_diff {
+            self.set_public_account(account_id, post);
+        }
 
-    fn get_account_by_id_mut(&mut self, account_id: AccountId) -> &mut Account {
-        self.public_state.entry(account_id).or_default()
+        let deployer = tx.message().deployer();
+        let mut account = self.get_account_by_id(&deployer);
+        assert!(
+            account.increment_nonce(),
+            "Deployments of accounts with exhausted nonces are rejected"
+        );
+        self.set_public_account(deployer, account);
+
+        self.insert_program(program);
+        Ok(fees)
+    }
+
+    /// Stores `account`, removing it if it's empty, as reading a missing account yields the
+    /// default one anyway
+    ///
+    /// Accounts, which signed a transaction, have non-zero nonce and are never removed, so they
+    /// can't be re-created with zero nonce and have their old transactions replayed.
+    fn set_public_account(&mut self, account_id: AccountId, account: Account) {
+        if account == Account::default() {
+            self.public_state.remove(&account_id);
+            self.account_tree.remove(&account_id);
+        } else {
+            self.account_tree.insert(account_id, &account);
+            self.public_state.insert(account_id, account);
+        }
     }
 
     pub fn get_account_by_id(&self, account_id: &AccountId) -> Account {
@@ -181,14 +455,97 @@ impl V02State {
         self.private_state.0.get_proof_for(commitment)
     }
 
+    /// Sum of balances of all public accounts
+    pub fn total_public_balance(&self) -> Amount {
+        Amount::checked_sum(self.public_state.values().map(|account| account.balance))
+            .expect("Total balance should fit into u128, as it's preserved by transactions")
+    }
+
+    /// Balance transferred to the burn account, which can never be moved, see
+    /// [`nssa_core::burn`]
+    pub fn total_burned(&self) -> Amount {
+        self.get_account_by_id(&BURN_ACCOUNT_ID).balance
+    }
+
+    /// Balance of public accounts locked as storage deposits at the current rate, a part of
+    /// [`Self::total_public_balance`]
+    ///
+    /// Accounts, which don't cover their deposit after the rate was raised, lock all of their
+    /// balance.
+    pub fn total_storage_deposit(&self) -> Amount {
+        Amount::checked_sum(self.public_state.values().map(|account| {
+            account
+                .storage_deposit(self.storage_deposit_per_byte)
+                .min(account.balance)
+        }))
+        .expect("Locked balance should not exceed the total balance")
+    }
+
     pub(crate) fn programs(&self) -> &HashMap<ProgramId, Program> {
         &self.programs
     }
 
+    /// Whether a program with the given id is built in or was deployed
+    pub fn is_program_deployed(&self, program_id: &ProgramId) -> bool {
+        self.programs.contains_key(program_id)
+    }
+
     pub fn commitment_set_digest(&self) -> CommitmentSetDigest {
         self.private_state.0.digest()
     }
 
+    pub fn state_size(&self) -> StateSize {
+        StateSize {
+            accounts: self.public_state.len(),
+            data_bytes: self
+                .public_state
+                .values()
+                .map(|account| account.data.len())
+                .sum(),
+        }
+    }
+
+    /// All non-empty public accounts in arbitrary order
+    pub fn public_accounts(&self) -> impl Iterator<Item = (&AccountId, &Account)> {
+        self.public_state.iter()
+    }
+
+    /// Hash of the root of the public account tree, see [`crate::account_tree`], and of the
+    /// commitment set digest
+    ///
+    /// Equal states have equal roots regardless of the order accounts were inserted in. The tree
+    /// is updated with every account change, so this doesn't iterate over accounts.
+    pub fn state_root(&self) -> [u8; 32] {
+        Self::state_root_of(&self.account_tree.root(), &self.commitment_set_digest())
+    }
+
+    /// State root of a state with `account_tree_root` and `commitment_set_digest`, see
+    /// [`Self::state_root`]
+    ///
+    /// Verifiers of an [`AccountProof`] compute the root with this.
+    pub fn state_root_of(
+        account_tree_root: &[u8; 32],
+        commitment_set_digest: &CommitmentSetDigest,
+    ) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(account_tree_root);
+        hasher.update(commitment_set_digest);
+        hasher.finalize().into()
+    }
+
+    /// Proof of the public account at `account_id` against the root of the public account tree
+    pub fn account_proof(&self, account_id: &AccountId) -> AccountProof {
+        self.account_tree
+            .proof(account_id, |id| self.public_state.get(id))
+    }
+
+    /// Checks the public account tree against its root computed from scratch
+    ///
+    /// This iterates over all public accounts and is meant for tests and debugging.
+    pub fn verify_full_recompute(&self) -> bool {
+        self.account_tree.root() == AccountTree::compute_root(&self.public_state)
+    }
+
     pub(crate) fn check_commitments_are_new(
         &self,
         new_commitments: &[Commitment],
@@ -203,6 +560,25 @@ impl V02State {
         Ok(())
     }
 
+    /// Checks that accounts of `state_diff` keep storage deposits of their data, see
+    /// [`is_storage_deposit_kept`]
+    pub(crate) fn check_storage_deposits(
+        &self,
+        state_diff: &HashMap<AccountId, Account>,
+    ) -> Result<(), NssaError> {
+        for (account_id, post) in state_diff {
+            let pre = self.get_account_by_id(account_id);
+            if !is_storage_deposit_kept(&pre, post, self.storage_deposit_per_byte) {
+                return Err(NssaError::InsufficientStorageDeposit {
+                    account_id: *account_id,
+                    balance: post.balance,
+                    required: post.storage_deposit(self.storage_deposit_per_byte),
+                });
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn check_nullifiers_are_valid(
         &self,
         new_nullifiers: &[(Nullifier, CommitmentSetDigest)],
@@ -213,11 +589,18 @@ impl V02State {
                     "Nullifier already seen".to_string(),
                 ));
             }
-            if !self.private_state.0.root_history.contains(digest) {
-                return Err(NssaError::InvalidInput(
-                    "Unrecognized commitment set digest".to_string(),
-                ));
+            // Initialization nullifiers carry the root of the set with the dummy commitment only
+            if *digest == DUMMY_COMMITMENT_HASH || self.private_state.0.is_recent_root(digest) {
+                continue;
             }
+            if self.private_state.0.root_history.contains(digest) {
+                return Err(NssaError::AnchorExpired {
+                    anchor_window: self.anchor_window,
+                });
+            }
+            return Err(NssaError::InvalidInput(
+                "Unrecognized commitment set digest".to_string(),
+            ));
         }
         Ok(())
     }
@@ -228,11 +611,11 @@ impl V02State {
     pub fn add_pinata_program(&mut self, account_id: AccountId) {
         self.insert_program(Program::pinata());
 
-        self.public_state.insert(
+        self.set_public_account(
             account_id,
             Account {
                 program_owner: Program::pinata().id(),
-                balance: 1500,
+                balance: Amount(1500),
                 // Difficulty: 3
                 data: vec![3; 33].try_into().expect("should fit"),
                 nonce: 0,
@@ -243,7 +626,7 @@ impl V02State {
     pub fn add_pinata_token_program(&mut self, account_id: AccountId) {
         self.insert_program(Program::pinata_token());
 
-        self.public_state.insert(
+        self.set_public_account(
             account_id,
             Account {
                 program_owner: Program::pinata_token().id(),
@@ -263,19 +646,24 @@ pub mod tests {
     use nssa_core::{
         Commitment, Nullifier, NullifierPublicKey, NullifierSecretKey, SharedSecretKey,
         account::{Account, AccountId, AccountWithMetadata, Nonce, data::Data},
+        burn::{BURN_ACCOUNT_ID, BURN_PROGRAM_ID, burn_account},
+        clock::{CLOCK_ACCOUNT_ID, clock_account},
         encryption::{EphemeralPublicKey, IncomingViewingPublicKey, Scalar},
         program::{PdaSeed, ProgramId},
     };
 
     use crate::{
+        Amount, BuiltinProgram, ProgramAllowList, ProgramDeploymentTransaction, ProverBackend,
         PublicKey, PublicTransaction, V02State,
         error::NssaError,
         execute_and_prove,
+        gas::PaidFees,
         privacy_preserving_transaction::{
             PrivacyPreservingTransaction, circuit, message::Message, witness_set::WitnessSet,
         },
-        program::Program,
-        public_transaction,
+        program::{InstructionError, Program},
+        program_deployment_transaction,
+        public_transaction::{self, min_account_creation_amount},
         signature::PrivateKey,
         state::MAX_NUMBER_CHAINED_CALLS,
     };
@@ -283,7 +671,7 @@ pub mod tests {
     fn transfer_transaction(
         from: AccountId,
         from_key: PrivateKey,
-        nonce: u128,
+        nonce: Nonce,
         to: AccountId,
         balance: u128,
     ) -> PublicTransaction {
@@ -302,14 +690,14 @@ pub mod tests {
         let key2 = PrivateKey::try_new([2; 32]).unwrap();
         let addr1 = AccountId::from(&PublicKey::new_from_private_key(&key1));
         let addr2 = AccountId::from(&PublicKey::new_from_private_key(&key2));
-        let initial_data = [(addr1, 100u128), (addr2, 151u128)];
+        let initial_data = [(addr1, Amount(100)), (addr2, Amount(151))];
         let authenticated_transfers_program = Program::authenticated_transfer_program();
         let expected_public_state = {
             let mut this = HashMap::new();
             this.insert(
                 addr1,
                 Account {
-                    balance: 100,
+                    balance: Amount(100),
                     program_owner: authenticated_transfers_program.id(),
                     ..Account::default()
                 },
@@ -317,11 +705,13 @@ pub mod tests {
             this.insert(
                 addr2,
                 Account {
-                    balance: 151,
+                    balance: Amount(151),
                     program_owner: authenticated_transfers_program.id(),
                     ..Account::default()
                 },
             );
+            this.insert(CLOCK_ACCOUNT_ID, clock_account(0));
+            this.insert(BURN_ACCOUNT_ID, burn_account());
             this
         };
         let expected_builtin_programs = {
@@ -331,6 +721,7 @@ pub mod tests {
                 authenticated_transfers_program,
             );
             this.insert(Program::token().id(), Program::token());
+            this.insert(Program::escrow().id(), Program::escrow());
             this
         };
 
@@ -340,6 +731,77 @@ pub mod tests {
         assert_eq!(state.programs, expected_builtin_programs);
     }
 
+    #[test]
+    fn test_state_root_depends_on_accounts_only() {
+        let id1 = AccountId::new([1; 32]);
+        let id2 = AccountId::new([2; 32]);
+        let state =
+            V02State::new_with_genesis_accounts(&[(id1, Amount(100)), (id2, Amount(200))], &[]);
+        let reordered =
+            V02State::new_with_genesis_accounts(&[(id2, Amount(200)), (id1, Amount(100))], &[]);
+        assert_eq!(state.state_root(), reordered.state_root());
+
+        let mut changed =
+            V02State::new_with_genesis_accounts(&[(id1, Amount(100)), (id2, Amount(200))], &[]);
+        changed.force_insert_account(id2, Account::default());
+        assert_ne!(state.state_root(), changed.state_root());
+    }
+
+    #[test]
+    fn test_account_proofs_lead_to_state_root() {
+        let account_id = AccountId::new([1; 32]);
+        let state = V02State::new_with_genesis_accounts(
+            &[
+                (account_id, Amount(100)),
+                (AccountId::new([2; 32]), Amount(200)),
+            ],
+            &[],
+        );
+        let digest = state.commitment_set_digest();
+        let account = state.get_account_by_id(&account_id);
+
+        let proof = state.account_proof(&account_id);
+        let account_tree_root = proof.root(&account_id, &account).unwrap();
+        assert_eq!(
+            V02State::state_root_of(&account_tree_root, &digest),
+            state.state_root()
+        );
+
+        let tampered = Account {
+            balance: account.balance.saturating_add(Amount(1)),
+            ..account
+        };
+        let account_tree_root = proof.root(&account_id, &tampered).unwrap();
+        assert_ne!(
+            V02State::state_root_of(&account_tree_root, &digest),
+            state.state_root()
+        );
+    }
+
+    #[test]
+    fn test_state_root_is_updated_with_accounts() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let to = AccountId::new([2; 32]);
+        let mut state = V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]);
+        assert!(state.verify_full_recompute());
+        let root = state.state_root();
+
+        let tx = transfer_transaction(from, key, 0, to, 100);
+        state.transition_from_public_transaction(&tx).unwrap();
+        state.set_block_id(1);
+
+        assert!(state.verify_full_recompute());
+        assert_ne!(state.state_root(), root);
+
+        let mut rebuilt = V02State::new_with_genesis_accounts(&[], &[]);
+        for account_id in [to, from] {
+            rebuilt.force_insert_account(account_id, state.get_account_by_id(&account_id));
+        }
+        rebuilt.set_block_id(1);
+        assert_eq!(rebuilt.state_root(), state.state_root());
+    }
+
     #[test]
     fn test_insert_program() {
         let mut state = V02State::new_with_genesis_accounts(&[], &[]);
@@ -356,7 +818,7 @@ pub mod tests {
     fn test_get_account_by_account_id_non_default_account() {
         let key = PrivateKey::try_new([1; 32]).unwrap();
         let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
-        let initial_data = [(account_id, 100u128)];
+        let initial_data = [(account_id, Amount(100))];
         let state = V02State::new_with_genesis_accounts(&initial_data, &[]);
         let expected_account = state.public_state.get(&account_id).unwrap();
 
@@ -389,7 +851,7 @@ pub mod tests {
     fn transition_from_authenticated_transfer_program_invocation_default_account_destination() {
         let key = PrivateKey::try_new([1; 32]).unwrap();
         let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
-        let initial_data = [(account_id, 100)];
+        let initial_data = [(account_id, Amount(100))];
         let mut state = V02State::new_with_genesis_accounts(&initial_data, &[]);
         let from = account_id;
         let to = AccountId::new([2; 32]);
@@ -399,30 +861,435 @@ pub mod tests {
         let tx = transfer_transaction(from, key, 0, to, balance_to_move);
         state.transition_from_public_transaction(&tx).unwrap();
 
-        assert_eq!(state.get_account_by_id(&from).balance, 95);
-        assert_eq!(state.get_account_by_id(&to).balance, 5);
+        assert_eq!(state.get_account_by_id(&from).balance, Amount(95));
+        assert_eq!(state.get_account_by_id(&to).balance, Amount(5));
         assert_eq!(state.get_account_by_id(&from).nonce, 1);
         assert_eq!(state.get_account_by_id(&to).nonce, 0);
     }
 
+    #[test]
+    fn test_transfer_to_burn_account_burns_balance() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let mut state = V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]);
+        assert_eq!(state.total_burned(), Amount(0));
+
+        let tx = transfer_transaction(from, key, 0, BURN_ACCOUNT_ID, 30);
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        assert_eq!(state.total_burned(), Amount(30));
+        // Burned balance is still a part of the total supply
+        assert_eq!(state.total_public_balance(), Amount(100));
+        assert_eq!(
+            state.get_account_by_id(&BURN_ACCOUNT_ID).program_owner,
+            BURN_PROGRAM_ID
+        );
+    }
+
+    #[test]
+    fn test_zero_amount_transfer_bumps_nonce() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let to = AccountId::new([2; 32]);
+        let mut state = V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]);
+
+        let tx = transfer_transaction(from, key, 0, to, 0);
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        assert_eq!(state.get_account_by_id(&from).balance, Amount(100));
+        assert_eq!(state.get_account_by_id(&from).nonce, 1);
+    }
+
+    #[test]
+    fn test_self_transfer_is_rejected() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let mut state = V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]);
+
+        let tx = transfer_transaction(from, key, 0, from, 5);
+        let result = state.transition_from_public_transaction(&tx);
+
+        assert!(
+            matches!(result, Err(NssaError::SelfTransfer { account_id }) if account_id == from)
+        );
+        assert_eq!(state.get_account_by_id(&from).nonce, 0);
+    }
+
+    #[test]
+    fn test_account_creation_requires_minimum_amount() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let to = AccountId::new([2; 32]);
+        let mut state = V02State::new_with_genesis_accounts(&[(from, Amount(10_000))], &[]);
+        state.set_storage_deposit_per_byte(Amount(1));
+        let min_amount = min_account_creation_amount(Amount(1));
+
+        let tx = transfer_transaction(from, key.clone(), 0, to, min_amount.0 - 1);
+        let result = state.transition_from_public_transaction(&tx);
+        assert!(matches!(
+            result,
+            Err(NssaError::AccountCreationBelowMinimum { account_id, .. }) if account_id == to
+        ));
+
+        let tx = transfer_transaction(from, key.clone(), 0, to, min_amount.0);
+        state.transition_from_public_transaction(&tx).unwrap();
+        assert_eq!(state.get_account_by_id(&to).balance, min_amount);
+
+        // Transfers to existing accounts take any amount
+        let tx = transfer_transaction(from, key, 1, to, 1);
+        state.transition_from_public_transaction(&tx).unwrap();
+    }
+
+    fn assign_transaction(
+        account_id: AccountId,
+        keys: &[&PrivateKey],
+        program_id: ProgramId,
+    ) -> PublicTransaction {
+        let message = public_transaction::Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![account_id],
+            vec![0],
+            program_id.image_id(),
+        )
+        .unwrap();
+        let witness_set = public_transaction::WitnessSet::for_message(&message, keys);
+        PublicTransaction::new(message, witness_set)
+    }
+
+    #[test]
+    fn test_assign_account_to_program() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let mut state = V02State::new_with_genesis_accounts(&[(account_id, Amount(100))], &[])
+            .with_test_programs();
+        let program_id = Program::simple_balance_transfer().id();
+
+        let tx = assign_transaction(account_id, &[&key], program_id);
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        let account = state.get_account_by_id(&account_id);
+        assert_eq!(account.program_owner, program_id);
+        assert_eq!(account.balance, Amount(100));
+        assert_eq!(account.nonce, 1);
+    }
+
+    #[test]
+    fn test_assign_account_without_signature_fails() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let mut state = V02State::new_with_genesis_accounts(&[(account_id, Amount(100))], &[])
+            .with_test_programs();
+
+        let tx = assign_transaction(account_id, &[], Program::simple_balance_transfer().id());
+
+        assert!(state.transition_from_public_transaction(&tx).is_err());
+        assert_eq!(
+            state.get_account_by_id(&account_id).program_owner,
+            Program::authenticated_transfer_program().id()
+        );
+    }
+
+    #[test]
+    fn test_burned_balance_cant_be_moved() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let mut state =
+            V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]).with_test_programs();
+        let tx = transfer_transaction(from, key, 0, BURN_ACCOUNT_ID, 30);
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        // Program moves balance without checking authorization, but doesn't own the burn account
+        let message = public_transaction::Message::try_new(
+            Program::simple_balance_transfer().id(),
+            vec![BURN_ACCOUNT_ID, from],
+            vec![],
+            30_u128,
+        )
+        .unwrap();
+        let witness_set = public_transaction::WitnessSet::for_message(&message, &[]);
+        let tx = PublicTransaction::new(message, witness_set);
+        let result = state.transition_from_public_transaction(&tx);
+
+        assert!(matches!(result, Err(NssaError::InvalidProgramBehavior)));
+        assert_eq!(state.total_burned(), Amount(30));
+    }
+
+    #[test]
+    fn test_transaction_is_rejected_on_network_not_allowing_its_program() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let to = AccountId::new([2; 32]);
+        let tx = transfer_transaction(from, key, 0, to, 5);
+        let mut devnet_state = V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]);
+        let mut restricted_state = V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[])
+            .with_program_allow_list(ProgramAllowList {
+                builtin_programs: vec![BuiltinProgram::Token],
+                deployed_programs: false,
+            });
+
+        devnet_state
+            .transition_from_public_transaction(&tx)
+            .unwrap();
+        let result = restricted_state.transition_from_public_transaction(&tx);
+
+        assert!(matches!(
+            result,
+            Err(NssaError::ProgramNotAllowed(program_id))
+                if program_id == Program::authenticated_transfer_program().id()
+        ));
+        assert_eq!(
+            restricted_state.get_account_by_id(&from).balance,
+            Amount(100)
+        );
+    }
+
+    #[test]
+    fn test_malformed_instruction_is_rejected_before_execution() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let to = AccountId::new([2; 32]);
+        let mut state = V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]);
+
+        // Transfer addresses the recipient, which isn't passed
+        let message = public_transaction::Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![from],
+            vec![0],
+            5_u128,
+        )
+        .unwrap();
+        let witness_set = public_transaction::WitnessSet::for_message(&message, &[&key]);
+        let result =
+            state.transition_from_public_transaction(&PublicTransaction::new(message, witness_set));
+        assert!(matches!(
+            result,
+            Err(NssaError::InvalidInstruction(
+                InstructionError::AccountIndexOutOfRange {
+                    index: 1,
+                    num_accounts: 1,
+                }
+            ))
+        ));
+
+        // Serialized amount takes 4 words
+        state.set_max_instruction_data_size(3);
+        let result =
+            state.transition_from_public_transaction(&transfer_transaction(from, key, 0, to, 5));
+        assert!(matches!(
+            result,
+            Err(NssaError::InvalidInstruction(InstructionError::TooLarge {
+                size: 4,
+                max_size: 3,
+            }))
+        ));
+        assert_eq!(state.get_account_by_id(&from).balance, Amount(100));
+    }
+
+    fn deployment_transaction(
+        key: &PrivateKey,
+        nonce: Nonce,
+        chain_id: u64,
+        max_fee: Amount,
+    ) -> ProgramDeploymentTransaction {
+        let deployer = AccountId::from(&PublicKey::new_from_private_key(key));
+        let message = program_deployment_transaction::Message::new(
+            deployer,
+            nonce,
+            chain_id,
+            max_fee,
+            Program::nonce_changer_program().elf().to_vec(),
+        );
+        let witness_set = program_deployment_transaction::WitnessSet::for_message(&message, key);
+        ProgramDeploymentTransaction::new(message, witness_set)
+    }
+
+    /// State, where the deployer of [`deployment_transaction`] signed by `key` has `balance` and
+    /// fees go to `fee_recipient`
+    fn state_for_deployment(
+        key: &PrivateKey,
+        balance: Amount,
+        fee_recipient: AccountId,
+    ) -> V02State {
+        let deployer = AccountId::from(&PublicKey::new_from_private_key(key));
+        let mut state = V02State::new_with_genesis_accounts(&[(deployer, balance)], &[]);
+        state.set_fee_recipient(fee_recipient);
+        state
+    }
+
+    #[test]
+    fn test_deployment_is_rejected_on_network_not_allowing_deployed_programs() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let fee_recipient = AccountId::new([9; 32]);
+        let tx = deployment_transaction(&key, 0, 0, Amount::MAX);
+        let mut devnet_state = state_for_deployment(&key, Amount(1_000_000), fee_recipient);
+        let mut restricted_state = state_for_deployment(&key, Amount(1_000_000), fee_recipient)
+            .with_program_allow_list(ProgramAllowList {
+                builtin_programs: vec![BuiltinProgram::AuthenticatedTransfer],
+                deployed_programs: false,
+            });
+
+        devnet_state
+            .transition_from_program_deployment_transaction(&tx)
+            .unwrap();
+        let result = restricted_state.transition_from_program_deployment_transaction(&tx);
+
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_deployment_fee_is_paid_by_deployer() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let deployer = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let fee_recipient = AccountId::new([9; 32]);
+        let mut state = state_for_deployment(&key, Amount(1_000_000), fee_recipient);
+        let tx = deployment_transaction(&key, 0, 0, Amount::MAX);
+        let fee = tx.fee();
+        assert!(!fee.is_zero());
+
+        let fees = state
+            .transition_from_program_deployment_transaction(&tx)
+            .unwrap();
+
+        assert_eq!(
+            fees,
+            PaidFees {
+                payer: Some(deployer),
+                recipient: Some(fee_recipient),
+                gas: None,
+                priority_fee: Amount(0),
+                deployment_fee: fee,
+            }
+        );
+        let deployer_account = state.get_account_by_id(&deployer);
+        assert_eq!(
+            deployer_account.balance,
+            Amount(1_000_000 - u128::from(fee.0))
+        );
+        assert_eq!(deployer_account.nonce, 1);
+        assert_eq!(
+            state.get_account_by_id(&fee_recipient).balance,
+            Amount::from(fee)
+        );
+        assert!(
+            state
+                .programs()
+                .contains_key(&Program::nonce_changer_program().id())
+        );
+    }
+
+    #[test]
+    fn test_unsigned_deployment_is_rejected() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let other_key = PrivateKey::try_new([2; 32]).unwrap();
+        let mut state = state_for_deployment(&key, Amount(1_000_000), AccountId::new([9; 32]));
+        let signed = deployment_transaction(&key, 0, 0, Amount::MAX);
+
+        let unsigned = ProgramDeploymentTransaction::new(
+            signed.message().clone(),
+            program_deployment_transaction::WitnessSet::for_message(signed.message(), &other_key),
+        );
+        let result = state.transition_from_program_deployment_transaction(&unsigned);
+
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+        assert!(
+            !state
+                .programs()
+                .contains_key(&Program::nonce_changer_program().id())
+        );
+    }
+
+    #[test]
+    fn test_deployment_with_wrong_nonce_or_chain_is_rejected() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let mut state = state_for_deployment(&key, Amount(1_000_000), AccountId::new([9; 32]));
+
+        for tx in [
+            deployment_transaction(&key, 1, 0, Amount::MAX),
+            deployment_transaction(&key, 0, 1, Amount::MAX),
+        ] {
+            let result = state.transition_from_program_deployment_transaction(&tx);
+            assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+        }
+    }
+
+    #[test]
+    fn test_deployment_fee_must_be_covered_by_max_fee_and_balance() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let fee = Amount::from(deployment_transaction(&key, 0, 0, Amount::ZERO).fee());
+        let short_of_fee = Amount(fee.0 - 1);
+
+        let mut state = state_for_deployment(&key, Amount(1_000_000), AccountId::new([9; 32]));
+        let result = state.transition_from_program_deployment_transaction(&deployment_transaction(
+            &key,
+            0,
+            0,
+            short_of_fee,
+        ));
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+
+        let mut state = state_for_deployment(&key, short_of_fee, AccountId::new([9; 32]));
+        let result = state.transition_from_program_deployment_transaction(&deployment_transaction(
+            &key, 0, 0, fee,
+        ));
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_oversized_deployment_is_rejected() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let mut state = state_for_deployment(&key, Amount(1_000_000), AccountId::new([9; 32]));
+        let tx = deployment_transaction(&key, 0, 0, Amount::MAX);
+        let size = tx.message().bytecode().len();
+
+        state.set_max_program_size(size - 1);
+        let result = state.transition_from_program_deployment_transaction(&tx);
+        assert!(matches!(
+            result,
+            Err(NssaError::ProgramTooLarge { size: actual, max_size })
+                if actual == size && max_size == size - 1
+        ));
+        assert!(
+            !state
+                .programs()
+                .contains_key(&Program::nonce_changer_program().id())
+        );
+
+        state.set_max_program_size(size);
+        state
+            .transition_from_program_deployment_transaction(&tx)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_allowed_builtin_programs_are_added() {
+        let state = V02State::new_with_genesis_accounts(&[], &[]).with_program_allow_list(
+            ProgramAllowList {
+                builtin_programs: vec![BuiltinProgram::PinataToken],
+                deployed_programs: true,
+            },
+        );
+
+        assert!(state.programs().contains_key(&Program::pinata_token().id()));
+    }
+
     #[test]
     fn transition_from_authenticated_transfer_program_invocation_insuficient_balance() {
         let key = PrivateKey::try_new([1; 32]).unwrap();
         let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
-        let initial_data = [(account_id, 100)];
+        let initial_data = [(account_id, Amount(100))];
         let mut state = V02State::new_with_genesis_accounts(&initial_data, &[]);
         let from = account_id;
         let from_key = key;
         let to = AccountId::new([2; 32]);
         let balance_to_move = 101;
-        assert!(state.get_account_by_id(&from).balance < balance_to_move);
+        assert!(state.get_account_by_id(&from).balance < Amount(balance_to_move));
 
         let tx = transfer_transaction(from, from_key, 0, to, balance_to_move);
         let result = state.transition_from_public_transaction(&tx);
 
         assert!(matches!(result, Err(NssaError::ProgramExecutionFailed(_))));
-        assert_eq!(state.get_account_by_id(&from).balance, 100);
-        assert_eq!(state.get_account_by_id(&to).balance, 0);
+        assert_eq!(state.get_account_by_id(&from).balance, Amount(100));
+        assert_eq!(state.get_account_by_id(&to).balance, Amount(0));
         assert_eq!(state.get_account_by_id(&from).nonce, 0);
         assert_eq!(state.get_account_by_id(&to).nonce, 0);
     }
@@ -433,7 +1300,7 @@ pub mod tests {
         let key2 = PrivateKey::try_new([2; 32]).unwrap();
         let account_id1 = AccountId::from(&PublicKey::new_from_private_key(&key1));
         let account_id2 = AccountId::from(&PublicKey::new_from_private_key(&key2));
-        let initial_data = [(account_id1, 100), (account_id2, 200)];
+        let initial_data = [(account_id1, Amount(100)), (account_id2, Amount(200))];
         let mut state = V02State::new_with_genesis_accounts(&initial_data, &[]);
         let from = account_id2;
         let from_key = key2;
@@ -444,19 +1311,46 @@ pub mod tests {
         let tx = transfer_transaction(from, from_key, 0, to, balance_to_move);
         state.transition_from_public_transaction(&tx).unwrap();
 
-        assert_eq!(state.get_account_by_id(&from).balance, 192);
-        assert_eq!(state.get_account_by_id(&to).balance, 108);
+        assert_eq!(state.get_account_by_id(&from).balance, Amount(192));
+        assert_eq!(state.get_account_by_id(&to).balance, Amount(108));
         assert_eq!(state.get_account_by_id(&from).nonce, 1);
         assert_eq!(state.get_account_by_id(&to).nonce, 0);
     }
 
+    #[test]
+    fn test_proven_transition_matches_executed_one() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let initial_data = [(account_id, Amount(100))];
+        let mut executed = V02State::new_with_genesis_accounts(&initial_data, &[]);
+        let mut proven = V02State::new_with_genesis_accounts(&initial_data, &[]);
+        let to = AccountId::new([2; 32]);
+        let tx = transfer_transaction(account_id, key, 0, to, 5);
+
+        executed.transition_from_public_transaction(&tx).unwrap();
+        let (_, proofs) = proven
+            .prove_and_transition_from_public_transaction(&tx, ProverBackend::Real)
+            .unwrap();
+
+        assert_eq!(proven.state_root(), executed.state_root());
+        assert_eq!(proofs.len(), 1);
+        let proof = &proofs[0];
+        assert_eq!(
+            proof.program_id(),
+            Program::authenticated_transfer_program().id()
+        );
+        assert!(proof.is_valid(false));
+        let output = proof.output().unwrap();
+        assert_eq!(output.post_states[1].account().balance, Amount(5));
+    }
+
     #[test]
     fn transition_from_sequence_of_authenticated_transfer_program_invocations() {
         let key1 = PrivateKey::try_new([8; 32]).unwrap();
         let account_id1 = AccountId::from(&PublicKey::new_from_private_key(&key1));
         let key2 = PrivateKey::try_new([2; 32]).unwrap();
         let account_id2 = AccountId::from(&PublicKey::new_from_private_key(&key2));
-        let initial_data = [(account_id1, 100)];
+        let initial_data = [(account_id1, Amount(100))];
         let mut state = V02State::new_with_genesis_accounts(&initial_data, &[]);
         let account_id3 = AccountId::new([3; 32]);
         let balance_to_move = 5;
@@ -467,16 +1361,107 @@ pub mod tests {
         let tx = transfer_transaction(account_id2, key2, 0, account_id3, balance_to_move);
         state.transition_from_public_transaction(&tx).unwrap();
 
-        assert_eq!(state.get_account_by_id(&account_id1).balance, 95);
-        assert_eq!(state.get_account_by_id(&account_id2).balance, 2);
-        assert_eq!(state.get_account_by_id(&account_id3).balance, 3);
+        assert_eq!(state.get_account_by_id(&account_id1).balance, Amount(95));
+        assert_eq!(state.get_account_by_id(&account_id2).balance, Amount(2));
+        assert_eq!(state.get_account_by_id(&account_id3).balance, Amount(3));
         assert_eq!(state.get_account_by_id(&account_id1).nonce, 1);
         assert_eq!(state.get_account_by_id(&account_id2).nonce, 1);
         assert_eq!(state.get_account_by_id(&account_id3).nonce, 0);
     }
 
+    #[test]
+    fn test_empty_accounts_are_not_stored() {
+        let mut state = V02State::new_with_genesis_accounts(&[], &[]).with_test_programs();
+        let state_size = state.state_size();
+        let account_ids = vec![AccountId::new([10; 32]), AccountId::new([11; 32])];
+        let program_id = Program::simple_balance_transfer().id();
+        let message =
+            public_transaction::Message::try_new(program_id, account_ids.clone(), vec![], 0u128)
+                .unwrap();
+        let witness_set = public_transaction::WitnessSet::for_message(&message, &[]);
+        let tx = PublicTransaction::new(message, witness_set);
+
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        assert_eq!(state.state_size(), state_size);
+        assert!(
+            state
+                .public_accounts()
+                .all(|(account_id, _)| !account_ids.contains(account_id))
+        );
+    }
+
+    #[test]
+    fn test_signer_account_is_kept_and_its_transaction_cant_be_replayed() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let signer = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let recipient = AccountId::new([11; 32]);
+        let mut state = V02State::new_with_genesis_accounts(&[], &[]).with_test_programs();
+        let state_size = state.state_size();
+        let program_id = Program::simple_balance_transfer().id();
+        let message = public_transaction::Message::try_new(
+            program_id,
+            vec![signer, recipient],
+            vec![0],
+            0u128,
+        )
+        .unwrap();
+        let witness_set = public_transaction::WitnessSet::for_message(&message, &[&key]);
+        let tx = PublicTransaction::new(message, witness_set);
+
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        // Signer is empty except for its nonce, but removing it would reset the nonce to zero
+        let expected_signer = Account {
+            nonce: 1,
+            ..Account::default()
+        };
+        assert_eq!(state.get_account_by_id(&signer), expected_signer);
+        assert_eq!(state.state_size().accounts, state_size.accounts + 1);
+
+        let result = state.transition_from_public_transaction(&tx);
+
+        assert!(result.is_err());
+        assert_eq!(state.get_account_by_id(&signer), expected_signer);
+    }
+
+    #[test]
+    fn test_account_with_exhausted_nonce_can_not_transact() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let to = AccountId::new([2; 32]);
+        let mut state = V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]);
+        let exhausted = Account {
+            nonce: Nonce::MAX,
+            ..state.get_account_by_id(&from)
+        };
+        state.force_insert_account(from, exhausted.clone());
+
+        let tx = transfer_transaction(from, key, Nonce::MAX, to, 5);
+        let result = state.transition_from_public_transaction(&tx);
+
+        assert!(matches!(
+            result,
+            Err(NssaError::InvalidInput(reason)) if reason.ends_with("is exhausted")
+        ));
+        assert_eq!(state.get_account_by_id(&from), exhausted);
+    }
+
+    #[test]
+    fn test_state_size_counts_data_bytes() {
+        let state = V02State::new_with_genesis_accounts(&[], &[])
+            .with_non_default_accounts_but_default_program_owners();
+
+        let state_size = state.state_size();
+
+        // Three inserted accounts and the clock account, which keeps 8 bytes of block id
+        assert_eq!(state_size.accounts, 4);
+        assert_eq!(state_size.data_bytes, 2 + 8);
+    }
+
     impl V02State {
         pub fn force_insert_account(&mut self, account_id: AccountId, account: Account) {
+            self.account_tree.insert(account_id, &account);
             self.public_state.insert(account_id, account);
         }
 
@@ -492,12 +1477,13 @@ pub mod tests {
             self.insert_program(Program::burner());
             self.insert_program(Program::chain_caller());
             self.insert_program(Program::claimer());
+            self.insert_program(Program::data_copier());
             self
         }
 
         pub fn with_non_default_accounts_but_default_program_owners(mut self) -> Self {
             let account_with_default_values_except_balance = Account {
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             };
             let account_with_default_values_except_nonce = Account {
@@ -526,7 +1512,7 @@ pub mod tests {
         pub fn with_account_owned_by_burner_program(mut self) -> Self {
             let account = Account {
                 program_owner: Program::burner().id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Default::default()
             };
             self.force_insert_account(AccountId::new([252; 32]), account);
@@ -542,7 +1528,7 @@ pub mod tests {
 
     #[test]
     fn test_program_should_fail_if_modifies_nonces() {
-        let initial_data = [(AccountId::new([1; 32]), 100)];
+        let initial_data = [(AccountId::new([1; 32]), Amount(100))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let account_ids = vec![AccountId::new([1; 32])];
@@ -559,7 +1545,7 @@ pub mod tests {
 
     #[test]
     fn test_program_should_fail_if_output_accounts_exceed_inputs() {
-        let initial_data = [(AccountId::new([1; 32]), 100)];
+        let initial_data = [(AccountId::new([1; 32]), Amount(100))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let account_ids = vec![AccountId::new([1; 32])];
@@ -576,7 +1562,7 @@ pub mod tests {
 
     #[test]
     fn test_program_should_fail_with_missing_output_accounts() {
-        let initial_data = [(AccountId::new([1; 32]), 100)];
+        let initial_data = [(AccountId::new([1; 32]), Amount(100))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let account_ids = vec![AccountId::new([1; 32]), AccountId::new([2; 32])];
@@ -593,7 +1579,7 @@ pub mod tests {
 
     #[test]
     fn test_program_should_fail_if_modifies_program_owner_with_only_non_default_program_owner() {
-        let initial_data = [(AccountId::new([1; 32]), 0)];
+        let initial_data = [(AccountId::new([1; 32]), Amount(0))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let account_id = AccountId::new([1; 32]);
@@ -689,7 +1675,7 @@ pub mod tests {
 
     #[test]
     fn test_program_should_fail_if_transfers_balance_from_non_owned_account() {
-        let initial_data = [(AccountId::new([1; 32]), 100)];
+        let initial_data = [(AccountId::new([1; 32]), Amount(100))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let sender_account_id = AccountId::new([1; 32]);
@@ -715,6 +1701,43 @@ pub mod tests {
         assert!(matches!(result, Err(NssaError::InvalidProgramBehavior)));
     }
 
+    #[test]
+    fn test_data_growth_requires_storage_deposit() {
+        let mut state = V02State::new_with_genesis_accounts(&[], &[]).with_test_programs();
+        state.set_storage_deposit_per_byte(Amount(10));
+        let account_id = AccountId::new([1; 32]);
+        let program_id = Program::data_changer().id();
+        state.force_insert_account(
+            account_id,
+            Account {
+                program_owner: program_id,
+                balance: Amount(30),
+                ..Account::default()
+            },
+        );
+        let change_data = |state: &mut V02State, data: Vec<u8>| {
+            let message =
+                public_transaction::Message::try_new(program_id, vec![account_id], vec![], data)
+                    .unwrap();
+            let witness_set = public_transaction::WitnessSet::for_message(&message, &[]);
+            state.transition_from_public_transaction(&PublicTransaction::new(message, witness_set))
+        };
+
+        change_data(&mut state, vec![1, 2, 3]).unwrap();
+        let result = change_data(&mut state, vec![1, 2, 3, 4]);
+
+        assert!(matches!(
+            result,
+            Err(NssaError::InsufficientStorageDeposit {
+                balance: Amount(30),
+                required: Amount(40),
+                ..
+            })
+        ));
+        assert_eq!(state.get_account_by_id(&account_id).data.len(), 3);
+        assert_eq!(state.total_storage_deposit(), Amount(30));
+    }
+
     #[test]
     fn test_program_should_fail_if_modifies_data_of_non_owned_account() {
         let initial_data = [];
@@ -771,7 +1794,7 @@ pub mod tests {
             program_id
         );
         let balance_to_burn: u128 = 1;
-        assert!(state.get_account_by_id(&account_id).balance > balance_to_burn);
+        assert!(state.get_account_by_id(&account_id).balance > Amount(balance_to_burn));
 
         let message = public_transaction::Message::try_new(
             program_id,
@@ -837,6 +1860,22 @@ pub mod tests {
         recipient_keys: &TestPrivateKeys,
         balance_to_move: u128,
         state: &V02State,
+    ) -> PrivacyPreservingTransaction {
+        shielded_balance_transfer_with_backend_for_tests(
+            sender_keys,
+            recipient_keys,
+            balance_to_move,
+            state,
+            ProverBackend::Real,
+        )
+    }
+
+    fn shielded_balance_transfer_with_backend_for_tests(
+        sender_keys: &TestPublicKeys,
+        recipient_keys: &TestPrivateKeys,
+        balance_to_move: u128,
+        state: &V02State,
+        backend: ProverBackend,
     ) -> PrivacyPreservingTransaction {
         let sender = AccountWithMetadata::new(
             state.get_account_by_id(&sender_keys.account_id()),
@@ -852,7 +1891,7 @@ pub mod tests {
         let shared_secret = SharedSecretKey::new(&esk, &recipient_keys.ivk());
         let epk = EphemeralPublicKey::from_scalar(esk);
 
-        let (output, proof) = circuit::execute_and_prove(
+        let (output, proof, _) = circuit::execute_and_prove_with_options(
             &[sender, recipient],
             &Program::serialize_instruction(balance_to_move).unwrap(),
             &[0, 2],
@@ -860,6 +1899,7 @@ pub mod tests {
             &[(recipient_keys.npk(), shared_secret)],
             &[],
             &Program::authenticated_transfer_program(),
+            &circuit::ProveOptions::default().with_backend(backend),
         )
         .unwrap();
 
@@ -986,7 +2026,7 @@ pub mod tests {
         let recipient_keys = test_private_account_keys_1();
 
         let mut state =
-            V02State::new_with_genesis_accounts(&[(sender_keys.account_id(), 200)], &[]);
+            V02State::new_with_genesis_accounts(&[(sender_keys.account_id(), Amount(200))], &[]);
 
         let balance_to_move = 37;
 
@@ -999,7 +2039,7 @@ pub mod tests {
 
         let expected_sender_post = {
             let mut this = state.get_account_by_id(&sender_keys.account_id());
-            this.balance -= balance_to_move;
+            this.balance.0 -= balance_to_move;
             this.nonce += 1;
             this
         };
@@ -1017,7 +2057,48 @@ pub mod tests {
 
         assert_eq!(
             state.get_account_by_id(&sender_keys.account_id()).balance,
-            200 - balance_to_move
+            Amount(200 - balance_to_move)
+        );
+    }
+
+    #[test]
+    fn test_dev_proof_is_accepted_only_by_node_accepting_dev_proofs() {
+        let sender_keys = test_public_account_keys_1();
+        let recipient_keys = test_private_account_keys_1();
+        let mut strict_state =
+            V02State::new_with_genesis_accounts(&[(sender_keys.account_id(), Amount(200))], &[]);
+        let mut dev_state =
+            V02State::new_with_genesis_accounts(&[(sender_keys.account_id(), Amount(200))], &[]);
+        dev_state.set_accept_dev_proofs(true);
+
+        let tx = shielded_balance_transfer_with_backend_for_tests(
+            &sender_keys,
+            &recipient_keys,
+            37,
+            &strict_state,
+            ProverBackend::DevFake,
+        );
+        assert!(tx.witness_set().proof().is_dev());
+
+        if !risc0_zkvm::VerifierContext::default().dev_mode() {
+            let result = strict_state.transition_from_privacy_preserving_transaction(&tx);
+            assert!(matches!(result, Err(NssaError::DevProofRejected)));
+            assert_eq!(
+                strict_state
+                    .get_account_by_id(&sender_keys.account_id())
+                    .balance,
+                Amount(200)
+            );
+        }
+
+        dev_state
+            .transition_from_privacy_preserving_transaction(&tx)
+            .unwrap();
+        assert_eq!(
+            dev_state
+                .get_account_by_id(&sender_keys.account_id())
+                .balance,
+            Amount(163)
         );
     }
 
@@ -1026,7 +2107,7 @@ pub mod tests {
         let sender_keys = test_private_account_keys_1();
         let sender_private_account = Account {
             program_owner: Program::authenticated_transfer_program().id(),
-            balance: 100,
+            balance: Amount(100),
             nonce: 0xdeadbeef,
             data: Data::default(),
         };
@@ -1051,7 +2132,7 @@ pub mod tests {
             &Account {
                 program_owner: Program::authenticated_transfer_program().id(),
                 nonce: 0xcafecafe,
-                balance: sender_private_account.balance - balance_to_move,
+                balance: Amount(sender_private_account.balance.0 - balance_to_move),
                 data: Data::default(),
             },
         );
@@ -1065,7 +2146,7 @@ pub mod tests {
             &Account {
                 program_owner: Program::authenticated_transfer_program().id(),
                 nonce: 0xfecafeca,
-                balance: balance_to_move,
+                balance: Amount(balance_to_move),
                 ..Account::default()
             },
         );
@@ -1092,14 +2173,17 @@ pub mod tests {
         let sender_keys = test_private_account_keys_1();
         let sender_private_account = Account {
             program_owner: Program::authenticated_transfer_program().id(),
-            balance: 100,
+            balance: Amount(100),
             nonce: 0xdeadbeef,
             data: Data::default(),
         };
         let recipient_keys = test_public_account_keys_1();
         let recipient_initial_balance = 400;
         let mut state = V02State::new_with_genesis_accounts(
-            &[(recipient_keys.account_id(), recipient_initial_balance)],
+            &[(
+                recipient_keys.account_id(),
+                Amount(recipient_initial_balance),
+            )],
             &[],
         )
         .with_private_account(&sender_keys, &sender_private_account);
@@ -1108,7 +2192,7 @@ pub mod tests {
 
         let expected_recipient_post = {
             let mut this = state.get_account_by_id(&recipient_keys.account_id());
-            this.balance += balance_to_move;
+            this.balance.0 += balance_to_move;
             this
         };
 
@@ -1126,7 +2210,7 @@ pub mod tests {
             &Account {
                 program_owner: Program::authenticated_transfer_program().id(),
                 nonce: 0xcafecafe,
-                balance: sender_private_account.balance - balance_to_move,
+                balance: Amount(sender_private_account.balance.0 - balance_to_move),
                 data: Data::default(),
             },
         );
@@ -1152,7 +2236,7 @@ pub mod tests {
             state
                 .get_account_by_id(&recipient_keys.account_id())
                 .balance,
-            recipient_initial_balance + balance_to_move
+            Amount(recipient_initial_balance + balance_to_move)
         );
     }
 
@@ -1162,7 +2246,7 @@ pub mod tests {
         let public_account = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1188,7 +2272,7 @@ pub mod tests {
         let public_account = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 0,
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1214,7 +2298,7 @@ pub mod tests {
         let public_account = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 0,
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1239,8 +2323,8 @@ pub mod tests {
         let program = Program::data_changer();
         let public_account = AccountWithMetadata::new(
             Account {
-                program_owner: [0, 1, 2, 3, 4, 5, 6, 7],
-                balance: 0,
+                program_owner: ProgramId::new([0, 1, 2, 3, 4, 5, 6, 7]),
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1266,7 +2350,7 @@ pub mod tests {
         let public_account = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 0,
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1294,7 +2378,7 @@ pub mod tests {
         let public_account = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 0,
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1320,7 +2404,7 @@ pub mod tests {
         let public_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 0,
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1329,7 +2413,7 @@ pub mod tests {
         let public_account_2 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 0,
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1352,10 +2436,11 @@ pub mod tests {
     #[test]
     fn test_program_owner_changer_should_fail_in_privacy_preserving_circuit() {
         let program = Program::program_owner_changer();
+        // The changer doesn't own the account, so it can't reassign it
         let public_account = AccountWithMetadata::new(
             Account {
-                program_owner: program.id(),
-                balance: 0,
+                program_owner: ProgramId::new([9; 8]),
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1380,8 +2465,8 @@ pub mod tests {
         let program = Program::simple_balance_transfer();
         let public_account_1 = AccountWithMetadata::new(
             Account {
-                program_owner: [0, 1, 2, 3, 4, 5, 6, 7],
-                balance: 100,
+                program_owner: ProgramId::new([0, 1, 2, 3, 4, 5, 6, 7]),
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1390,7 +2475,7 @@ pub mod tests {
         let public_account_2 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 0,
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1416,7 +2501,7 @@ pub mod tests {
         let public_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1425,7 +2510,7 @@ pub mod tests {
         let public_account_2 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 0,
+                balance: Amount(0),
                 ..Account::default()
             },
             true,
@@ -1455,7 +2540,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1495,7 +2580,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1530,7 +2615,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1571,7 +2656,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1619,7 +2704,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1628,7 +2713,7 @@ pub mod tests {
         let private_account_2 = AccountWithMetadata::new(
             Account {
                 // Non default balance
-                balance: 1,
+                balance: Amount(1),
                 ..Account::default()
             },
             false,
@@ -1666,7 +2751,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1675,7 +2760,7 @@ pub mod tests {
         let private_account_2 = AccountWithMetadata::new(
             Account {
                 // Non default program_owner
-                program_owner: [0, 1, 2, 3, 4, 5, 6, 7],
+                program_owner: ProgramId::new([0, 1, 2, 3, 4, 5, 6, 7]),
                 ..Account::default()
             },
             false,
@@ -1712,7 +2797,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1758,7 +2843,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1805,7 +2890,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1846,7 +2931,7 @@ pub mod tests {
         let public_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1877,7 +2962,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1919,7 +3004,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -1965,7 +3050,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -2008,7 +3093,7 @@ pub mod tests {
         let sender_keys = test_private_account_keys_1();
         let sender_private_account = Account {
             program_owner: Program::authenticated_transfer_program().id(),
-            balance: 100,
+            balance: Amount(100),
             nonce: 0xdeadbeef,
             data: Data::default(),
         };
@@ -2034,7 +3119,7 @@ pub mod tests {
 
         let sender_private_account = Account {
             program_owner: Program::authenticated_transfer_program().id(),
-            balance: 100 - balance_to_move,
+            balance: Amount(100 - balance_to_move),
             nonce: 0xcafecafe,
             data: Data::default(),
         };
@@ -2058,6 +3143,77 @@ pub mod tests {
         assert_eq!(error_message, expected_error_message);
     }
 
+    /// State at block 1 with a private account of [`test_private_account_keys_1`], which accepts
+    /// anchors of the last 10 blocks, and a transfer from it proven against the anchor of block 1
+    fn state_with_transfer_proven_at_block_1() -> (V02State, PrivacyPreservingTransaction) {
+        let sender_keys = test_private_account_keys_1();
+        let sender_private_account = Account {
+            program_owner: Program::authenticated_transfer_program().id(),
+            balance: Amount(100),
+            nonce: 0xdeadbeef,
+            data: Data::default(),
+        };
+        let mut state = V02State::new_with_genesis_accounts(&[], &[])
+            .with_private_account(&sender_keys, &sender_private_account);
+        state.set_anchor_window(10);
+        state.set_block_id(1);
+
+        let tx = private_balance_transfer_for_tests(
+            &sender_keys,
+            &sender_private_account,
+            &test_private_account_keys_2(),
+            37,
+            [0xcafecafe, 0xfecafeca],
+            &state,
+        );
+        (state, tx)
+    }
+
+    /// Sets blocks up to `last_block_id`, adding a commitment in each, so every block has another
+    /// anchor
+    fn advance_blocks_with_new_commitments(state: &mut V02State, last_block_id: u64) {
+        for block_id in state.block_id() + 1..=last_block_id {
+            let account = Account {
+                nonce: block_id,
+                ..Account::default()
+            };
+            state.private_state.0.extend(&[Commitment::new(
+                &test_private_account_keys_2().npk(),
+                &account,
+            )]);
+            state.set_block_id(block_id);
+        }
+    }
+
+    #[test]
+    fn test_private_transaction_proven_against_recent_anchor_is_accepted() {
+        let (mut state, tx) = state_with_transfer_proven_at_block_1();
+        let anchor = state.latest_anchors().last().unwrap();
+
+        advance_blocks_with_new_commitments(&mut state, 6);
+
+        assert_eq!(state.latest_anchors().count(), 6);
+        assert_ne!(state.latest_anchors().last().unwrap().digest, anchor.digest);
+        state
+            .transition_from_privacy_preserving_transaction(&tx)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_private_transaction_proven_against_expired_anchor_is_rejected() {
+        let (mut state, tx) = state_with_transfer_proven_at_block_1();
+
+        advance_blocks_with_new_commitments(&mut state, 11);
+
+        assert_eq!(state.latest_anchors().count(), 10);
+        assert_eq!(state.latest_anchors().next().unwrap().block_id, 2);
+        let result = state.transition_from_privacy_preserving_transaction(&tx);
+        assert!(matches!(
+            result,
+            Err(NssaError::AnchorExpired { anchor_window: 10 })
+        ));
+    }
+
     #[test]
     fn test_circuit_should_fail_if_there_are_repeated_ids() {
         let program = Program::simple_balance_transfer();
@@ -2065,7 +3221,7 @@ pub mod tests {
         let private_account_1 = AccountWithMetadata::new(
             Account {
                 program_owner: program.id(),
-                balance: 100,
+                balance: Amount(100),
                 ..Account::default()
             },
             true,
@@ -2100,7 +3256,7 @@ pub mod tests {
         let key = PrivateKey::try_new([1; 32]).unwrap();
         let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
         let initial_balance = 100;
-        let initial_data = [(account_id, initial_balance)];
+        let initial_data = [(account_id, Amount(initial_balance))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let from = account_id;
@@ -2113,7 +3269,7 @@ pub mod tests {
 
         let expected_recipient_post = Account {
             program_owner: program.id(),
-            balance: amount,
+            balance: Amount(amount),
             ..Account::default()
         };
 
@@ -2137,7 +3293,7 @@ pub mod tests {
         let from = AccountId::from(&PublicKey::new_from_private_key(&key));
         let to = AccountId::new([2; 32]);
         let initial_balance = 1000;
-        let initial_data = [(from, initial_balance), (to, 0)];
+        let initial_data = [(from, Amount(initial_balance)), (to, Amount(0))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let from_key = key;
@@ -2151,7 +3307,7 @@ pub mod tests {
 
         let expected_to_post = Account {
             program_owner: Program::authenticated_transfer_program().id(),
-            balance: amount * 2, // The `chain_caller` chains the program twice
+            balance: Amount(amount * 2), // The `chain_caller` chains the program twice
             ..Account::default()
         };
 
@@ -2171,7 +3327,7 @@ pub mod tests {
         let from_post = state.get_account_by_id(&from);
         let to_post = state.get_account_by_id(&to);
         // The `chain_caller` program calls the program twice
-        assert_eq!(from_post.balance, initial_balance - 2 * amount);
+        assert_eq!(from_post.balance, Amount(initial_balance - 2 * amount));
         assert_eq!(to_post, expected_to_post);
     }
 
@@ -2182,7 +3338,7 @@ pub mod tests {
         let from = AccountId::from(&PublicKey::new_from_private_key(&key));
         let to = AccountId::new([2; 32]);
         let initial_balance = 100;
-        let initial_data = [(from, initial_balance), (to, 0)];
+        let initial_data = [(from, Amount(initial_balance)), (to, Amount(0))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let from_key = key;
@@ -2212,6 +3368,39 @@ pub mod tests {
         ));
     }
 
+    #[test]
+    fn test_chained_call_to_unknown_program_fails_atomically() {
+        let program = Program::chain_caller();
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let to = AccountId::new([2; 32]);
+        let initial_data = [(from, Amount(100)), (to, Amount(0))];
+        let mut state =
+            V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
+        let unknown_program_id = ProgramId::new([0xdead; 8]);
+        let instruction: (u128, ProgramId, u32, Option<PdaSeed>) =
+            (10, unknown_program_id, 1, None);
+
+        let message = public_transaction::Message::try_new(
+            program.id(),
+            vec![to, from],
+            vec![0],
+            instruction,
+        )
+        .unwrap();
+        let witness_set = public_transaction::WitnessSet::for_message(&message, &[&key]);
+        let tx = PublicTransaction::new(message, witness_set);
+        let from_pre = state.get_account_by_id(&from);
+        let to_pre = state.get_account_by_id(&to);
+
+        let result = state.transition_from_public_transaction(&tx);
+
+        assert!(matches!(result, Err(NssaError::InvalidInput(_))));
+        // Neither the calling program's changes nor the nonce increment are applied
+        assert_eq!(state.get_account_by_id(&from), from_pre);
+        assert_eq!(state.get_account_by_id(&to), to_pre);
+    }
+
     #[test]
     fn test_execution_that_requires_authentication_of_a_program_derived_account_id_succeeds() {
         let chain_caller = Program::chain_caller();
@@ -2219,7 +3408,7 @@ pub mod tests {
         let from = AccountId::from((&chain_caller.id(), &pda_seed));
         let to = AccountId::new([2; 32]);
         let initial_balance = 1000;
-        let initial_data = [(from, initial_balance), (to, 0)];
+        let initial_data = [(from, Amount(initial_balance)), (to, Amount(0))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let amount: u128 = 58;
@@ -2232,7 +3421,7 @@ pub mod tests {
 
         let expected_to_post = Account {
             program_owner: Program::authenticated_transfer_program().id(),
-            balance: amount, // The `chain_caller` chains the program twice
+            balance: Amount(amount), // The `chain_caller` chains the program twice
             ..Account::default()
         };
         let message = public_transaction::Message::try_new(
@@ -2250,10 +3439,64 @@ pub mod tests {
 
         let from_post = state.get_account_by_id(&from);
         let to_post = state.get_account_by_id(&to);
-        assert_eq!(from_post.balance, initial_balance - amount);
+        assert_eq!(from_post.balance, Amount(initial_balance - amount));
         assert_eq!(to_post, expected_to_post);
     }
 
+    #[test]
+    fn test_program_reads_readonly_account() {
+        let initial_data = [];
+        let mut state = V02State::new_with_genesis_accounts(&initial_data, &[])
+            .with_test_programs()
+            .with_non_default_accounts_but_default_program_owners();
+        let target = AccountId::new([1; 32]);
+        let source = AccountId::new([253; 32]);
+        let source_pre = state.get_account_by_id(&source);
+
+        let message = public_transaction::Message::try_new(
+            Program::data_copier().id(),
+            vec![target],
+            vec![],
+            (),
+        )
+        .unwrap()
+        .with_readonly_account_ids(vec![source]);
+        let witness_set = public_transaction::WitnessSet::for_message(&message, &[]);
+        let tx = PublicTransaction::new(message, witness_set);
+
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        let target_post = state.get_account_by_id(&target);
+        assert_eq!(target_post.data, source_pre.data);
+        assert_eq!(target_post.program_owner, Program::data_copier().id());
+        assert_eq!(state.get_account_by_id(&source), source_pre);
+    }
+
+    #[test]
+    fn test_program_cant_modify_readonly_account() {
+        let initial_data = [];
+        let mut state =
+            V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
+        let account_id = AccountId::new([1; 32]);
+
+        // Would succeed if the account were writable
+        let message = public_transaction::Message::try_new(
+            Program::data_changer().id(),
+            vec![],
+            vec![],
+            vec![0],
+        )
+        .unwrap()
+        .with_readonly_account_ids(vec![account_id]);
+        let witness_set = public_transaction::WitnessSet::for_message(&message, &[]);
+        let tx = PublicTransaction::new(message, witness_set);
+
+        let result = state.transition_from_public_transaction(&tx);
+
+        assert!(matches!(result, Err(NssaError::InvalidProgramBehavior)));
+        assert_eq!(state.get_account_by_id(&account_id), Account::default());
+    }
+
     #[test]
     fn test_claiming_mechanism_within_chain_call() {
         // This test calls the authenticated transfer program through the chain_caller program.
@@ -2265,7 +3508,7 @@ pub mod tests {
         let key = PrivateKey::try_new([1; 32]).unwrap();
         let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
         let initial_balance = 100;
-        let initial_data = [(account_id, initial_balance)];
+        let initial_data = [(account_id, Amount(initial_balance))];
         let mut state =
             V02State::new_with_genesis_accounts(&initial_data, &[]).with_test_programs();
         let from = account_id;
@@ -2279,7 +3522,7 @@ pub mod tests {
         let expected_to_post = Account {
             // The expected program owner is the authenticated transfer program
             program_owner: auth_transfer.id(),
-            balance: amount,
+            balance: Amount(amount),
             ..Account::default()
         };
 
@@ -2306,7 +3549,7 @@ pub mod tests {
 
         let from_post = state.get_account_by_id(&from);
         let to_post = state.get_account_by_id(&to);
-        assert_eq!(from_post.balance, initial_balance - amount);
+        assert_eq!(from_post.balance, Amount(initial_balance - amount));
         assert_eq!(to_post, expected_to_post);
     }
 
@@ -2400,7 +3643,7 @@ pub mod tests {
         state.force_insert_account(
             account_id,
             Account {
-                program_owner: [1, 2, 3, 4, 5, 6, 7, 8],
+                program_owner: ProgramId::new([1, 2, 3, 4, 5, 6, 7, 8]),
                 ..Account::default()
             },
         );
@@ -2430,8 +3673,8 @@ pub mod tests {
 
         let mut state = V02State::new_with_genesis_accounts(
             &[
-                (sender_id, sender_init_balance),
-                (recipient_id, recipient_init_balance),
+                (sender_id, Amount(sender_init_balance)),
+                (recipient_id, Amount(recipient_init_balance)),
             ],
             &[],
         );
@@ -2466,14 +3709,14 @@ pub mod tests {
 
         let expected_sender_post = {
             let mut this = state.get_account_by_id(&sender_id);
-            this.balance = sender_init_balance;
+            this.balance = Amount(sender_init_balance);
             this.nonce = 0;
             this
         };
 
         let expected_recipient_post = {
             let mut this = state.get_account_by_id(&sender_id);
-            this.balance = recipient_init_balance;
+            this.balance = Amount(recipient_init_balance);
             this.nonce = 0;
             this
         };
@@ -2481,4 +3724,148 @@ pub mod tests {
         assert!(expected_sender_post == sender_post);
         assert!(expected_recipient_post == recipient_post);
     }
+
+    fn escrow_create_instruction(amount: u128, timeout: u64, recipient: AccountId) -> Vec<u8> {
+        let mut instruction = vec![0];
+        instruction.extend_from_slice(&amount.to_le_bytes());
+        instruction.extend_from_slice(&timeout.to_le_bytes());
+        instruction.extend_from_slice(recipient.value());
+        instruction.extend_from_slice(&Program::escrow().id().to_bytes());
+        instruction
+    }
+
+    fn escrow_transaction(
+        account_ids: Vec<AccountId>,
+        signer_key: &PrivateKey,
+        nonce: Nonce,
+        instruction: Vec<u8>,
+    ) -> PublicTransaction {
+        let message = public_transaction::Message::try_new(
+            Program::escrow().id(),
+            account_ids,
+            vec![nonce],
+            instruction,
+        )
+        .unwrap();
+        let witness_set = public_transaction::WitnessSet::for_message(&message, &[signer_key]);
+        PublicTransaction::new(message, witness_set)
+    }
+
+    /// State with an escrow of 100 from funder (key `[1; 32]`) to recipient (key `[2; 32]`),
+    /// timing out at block 10
+    fn state_with_escrow() -> (V02State, PrivateKey, PrivateKey, AccountId) {
+        let funder_key = PrivateKey::try_new([1; 32]).unwrap();
+        let funder = AccountId::from(&PublicKey::new_from_private_key(&funder_key));
+        let recipient_key = PrivateKey::try_new([2; 32]).unwrap();
+        let recipient = AccountId::from(&PublicKey::new_from_private_key(&recipient_key));
+        let escrow = AccountId::new([3; 32]);
+        let mut state = V02State::new_with_genesis_accounts(
+            &[(funder, Amount(1000)), (recipient, Amount(0))],
+            &[],
+        );
+        state.set_block_id(1);
+
+        let tx = escrow_transaction(
+            vec![funder, escrow],
+            &funder_key,
+            0,
+            escrow_create_instruction(100, 10, recipient),
+        );
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        (state, funder_key, recipient_key, escrow)
+    }
+
+    #[test]
+    fn test_escrow_create_locks_funds() {
+        let (state, funder_key, _, escrow) = state_with_escrow();
+        let funder = AccountId::from(&PublicKey::new_from_private_key(&funder_key));
+
+        let escrow_account = state.get_account_by_id(&escrow);
+        assert_eq!(escrow_account.program_owner, Program::escrow().id());
+        assert_eq!(escrow_account.balance, Amount(100));
+        assert_eq!(state.get_account_by_id(&funder).balance, Amount(900));
+    }
+
+    #[test]
+    fn test_escrow_claim_before_timeout() {
+        let (mut state, _, recipient_key, escrow) = state_with_escrow();
+        let recipient = AccountId::from(&PublicKey::new_from_private_key(&recipient_key));
+        state.set_block_id(9);
+
+        let tx = escrow_transaction(
+            vec![escrow, recipient, CLOCK_ACCOUNT_ID],
+            &recipient_key,
+            0,
+            vec![1],
+        );
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        assert_eq!(state.get_account_by_id(&escrow).balance, Amount(0));
+        assert_eq!(state.get_account_by_id(&recipient).balance, Amount(100));
+
+        // Double claim
+        let tx = escrow_transaction(
+            vec![escrow, recipient, CLOCK_ACCOUNT_ID],
+            &recipient_key,
+            1,
+            vec![1],
+        );
+        let result = state.transition_from_public_transaction(&tx);
+        assert!(matches!(result, Err(NssaError::ProgramExecutionFailed(_))));
+    }
+
+    #[test]
+    fn test_escrow_claim_exactly_at_timeout_fails() {
+        let (mut state, _, recipient_key, escrow) = state_with_escrow();
+        let recipient = AccountId::from(&PublicKey::new_from_private_key(&recipient_key));
+        state.set_block_id(10);
+
+        let tx = escrow_transaction(
+            vec![escrow, recipient, CLOCK_ACCOUNT_ID],
+            &recipient_key,
+            0,
+            vec![1],
+        );
+        let result = state.transition_from_public_transaction(&tx);
+
+        assert!(matches!(result, Err(NssaError::ProgramExecutionFailed(_))));
+        assert_eq!(state.get_account_by_id(&escrow).balance, Amount(100));
+    }
+
+    #[test]
+    fn test_escrow_refund_before_timeout_fails() {
+        let (mut state, funder_key, _, escrow) = state_with_escrow();
+        let funder = AccountId::from(&PublicKey::new_from_private_key(&funder_key));
+        state.set_block_id(9);
+
+        let tx = escrow_transaction(
+            vec![escrow, funder, CLOCK_ACCOUNT_ID],
+            &funder_key,
+            1,
+            vec![2],
+        );
+        let result = state.transition_from_public_transaction(&tx);
+
+        assert!(matches!(result, Err(NssaError::ProgramExecutionFailed(_))));
+        assert_eq!(state.get_account_by_id(&escrow).balance, Amount(100));
+    }
+
+    #[test]
+    fn test_escrow_refund_after_timeout() {
+        let (mut state, funder_key, _, escrow) = state_with_escrow();
+        let funder = AccountId::from(&PublicKey::new_from_private_key(&funder_key));
+        state.set_block_id(10);
+
+        let tx = escrow_transaction(
+            vec![escrow, funder, CLOCK_ACCOUNT_ID],
+            &funder_key,
+            1,
+            vec![2],
+        );
+        state.transition_from_public_transaction(&tx).unwrap();
+
+        assert_eq!(state.get_account_by_id(&escrow).balance, Amount(0));
+        assert_eq!(state.get_account_by_id(&funder).balance, Amount(1000));
+    }
 }
@@ -15,7 +15,7 @@ fn main() {
 
     let account_pre = &pre.account;
     let mut account_post = account_pre.clone();
-    account_post.balance -= balance_to_burn;
+    account_post.balance.0 -= balance_to_burn;
 
     write_nssa_outputs(vec![pre], vec![AccountPostState::new(account_post)]);
 }
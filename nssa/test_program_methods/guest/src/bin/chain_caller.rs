@@ -39,8 +39,8 @@ fn main() {
         };
         chained_calls.push(new_chained_call);
 
-        running_sender_pre.account.balance -= balance;
-        running_recipient_pre.account.balance += balance;
+        running_sender_pre.account.balance.0 -= balance;
+        running_recipient_pre.account.balance.0 += balance;
     }
 
     write_nssa_outputs_with_chained_call(
@@ -0,0 +1,39 @@
+use nssa_program_sdk::{
+    AccountPostState, DEFAULT_PROGRAM_ID, read_instruction_data, read_pre_states,
+    write_nssa_outputs,
+};
+
+type Instruction = u64;
+
+/// A program that stores a counter in account data and increments it by the instruction value.
+///
+/// Example of a program written with `nssa-program-sdk`. Accounts are set up either by the
+/// counter claiming default ones, or by assigning funded accounts to the counter with
+/// `wallet account assign`, which leaves them without data, so they start counting from zero.
+fn main() {
+    let pre_states = read_pre_states();
+    let increment: Instruction = read_instruction_data();
+
+    let [pre] = pre_states.try_into().expect("expected exactly one account");
+
+    let count = match pre.account.data.as_ref() {
+        [] => 0,
+        bytes => u64::from_le_bytes(bytes.try_into().expect("counter must be 8 bytes")),
+    };
+
+    let mut account_post = pre.account.clone();
+    account_post.data = (count + increment)
+        .to_le_bytes()
+        .to_vec()
+        .try_into()
+        .expect("counter fits into data limit");
+
+    // New accounts are claimed by the counter
+    let post_state = if pre.account.program_owner == DEFAULT_PROGRAM_ID {
+        AccountPostState::new_claimed(account_post)
+    } else {
+        AccountPostState::new(account_post)
+    };
+
+    write_nssa_outputs(vec![pre], vec![post_state]);
+}
@@ -0,0 +1,31 @@
+use nssa_program_sdk::{
+    AccountPostState, DEFAULT_PROGRAM_ID, read_instruction_data, read_pre_states,
+    write_nssa_outputs,
+};
+
+type Instruction = ();
+
+/// A program that copies the data of a read-only account into a writable one.
+///
+/// Expects the writable account followed by the read-only one.
+fn main() {
+    let pre_states = read_pre_states();
+    let _: Instruction = read_instruction_data();
+
+    let [target, source] = pre_states
+        .clone()
+        .try_into()
+        .expect("expected exactly two accounts");
+
+    let mut target_post = target.account.clone();
+    target_post.data = source.account.data.clone();
+
+    let post_state = if target.account.program_owner == DEFAULT_PROGRAM_ID {
+        AccountPostState::new_claimed(target_post)
+    } else {
+        AccountPostState::new(target_post)
+    };
+
+    // The read-only account has no post-state
+    write_nssa_outputs(pre_states, vec![post_state]);
+}
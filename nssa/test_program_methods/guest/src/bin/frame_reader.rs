@@ -0,0 +1,23 @@
+use nssa_core::program::{AccountPostState, ProgramInput, read_nssa_inputs, write_nssa_outputs};
+use risc0_zkvm::guest::env;
+
+type Instruction = ();
+
+/// A program that stores a frame written after its inputs in the data of its only account
+fn main() {
+    let ProgramInput {
+        pre_states,
+        instruction: (),
+    } = read_nssa_inputs::<Instruction>();
+    let frame = env::read_frame();
+
+    let [pre] = match pre_states.try_into() {
+        Ok(array) => array,
+        Err(_) => return,
+    };
+
+    let mut account_post = pre.account.clone();
+    account_post.data = frame.try_into().unwrap();
+
+    write_nssa_outputs(vec![pre], vec![AccountPostState::new(account_post)]);
+}
@@ -0,0 +1,23 @@
+use nssa_core::program::{AccountPostState, ProgramInput, read_nssa_inputs, write_nssa_outputs};
+
+type Instruction = u64;
+
+/// A program that spins for the given number of iterations and leaves its accounts unchanged
+fn main() {
+    let ProgramInput {
+        pre_states,
+        instruction: iterations,
+    } = read_nssa_inputs::<Instruction>();
+
+    let mut acc = 0_u64;
+    for i in 0..iterations {
+        acc = core::hint::black_box(acc.wrapping_add(i));
+    }
+    core::hint::black_box(acc);
+
+    let post_states = pre_states
+        .iter()
+        .map(|pre| AccountPostState::new(pre.account.clone()))
+        .collect();
+    write_nssa_outputs(pre_states, post_states);
+}
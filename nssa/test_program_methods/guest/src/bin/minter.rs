@@ -12,7 +12,7 @@ fn main() {
 
     let account_pre = &pre.account;
     let mut account_post = account_pre.clone();
-    account_post.balance += 1;
+    account_post.balance.0 += 1;
 
     write_nssa_outputs(vec![pre], vec![AccountPostState::new(account_post)]);
 }
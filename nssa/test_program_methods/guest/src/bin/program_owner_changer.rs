@@ -1,4 +1,4 @@
-use nssa_core::program::{read_nssa_inputs, write_nssa_outputs, AccountPostState, ProgramInput};
+use nssa_core::program::{read_nssa_inputs, write_nssa_outputs, AccountPostState, ProgramId, ProgramInput};
 
 type Instruction = ();
 
@@ -12,7 +12,7 @@ fn main() {
 
     let account_pre = &pre.account;
     let mut account_post = account_pre.clone();
-    account_post.program_owner = [0, 1, 2, 3, 4, 5, 6, 7];
+    account_post.program_owner = ProgramId::new([0, 1, 2, 3, 4, 5, 6, 7]);
 
     write_nssa_outputs(vec![pre], vec![AccountPostState::new(account_post)]);
 }
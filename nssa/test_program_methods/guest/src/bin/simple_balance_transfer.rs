@@ -15,8 +15,8 @@ fn main() {
 
     let mut sender_post = sender_pre.account.clone();
     let mut receiver_post = receiver_pre.account.clone();
-    sender_post.balance -= balance;
-    receiver_post.balance += balance;
+    sender_post.balance.0 -= balance;
+    receiver_post.balance.0 += balance;
 
     write_nssa_outputs(
         vec![sender_pre, receiver_pre],
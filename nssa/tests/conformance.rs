@@ -0,0 +1,633 @@
+//! Conformance vectors for the whole lifecycle of transactions: key derivation, message encoding,
+//! signing, transaction encoding and hashing.
+//!
+//! Vectors are committed to [`FIXTURES_PATH`], so other implementations of wallets and indexers can
+//! check themselves against them. Every vector lists its inputs together with everything derived
+//! from them, down to transaction bytes and hash as the sequencer reports them.
+//!
+//! Verdicts of the rules of native transfers on zero amounts, self-transfers and creation of
+//! accounts are listed as well, so every layer can check it agrees with validation of nodes.
+//!
+//! Only program deployments are bound to a chain id. Witness sets are of the schnorr scheme, whose
+//! sighash is the message bytes as is, so signatures are BIP-340 signatures of the message bytes.
+//!
+//! Vectors are checked against fixtures on every test run, so any change of consensus-relevant
+//! encoding shows up as a fixture diff. If the change is intended, run the tests with
+//! [`REGENERATE_ENV_VAR`] set to rewrite the fixtures.
+
+use common::transaction::{EncodedTransaction, NSSATransaction};
+use key_protocol::key_management::key_tree::{
+    chain_index::ChainIndex, keys_public::ChildKeysPublic, traits::KeyNode as _,
+};
+use nssa::{
+    AccountId, Amount, Cycles, PrivateKey, ProgramDeploymentTransaction, PublicKey,
+    PublicTransaction,
+    error::NssaError,
+    program_deployment_transaction,
+    public_transaction::{self, NativeTransfer},
+};
+use nssa_core::{
+    account::{Account, Nonce},
+    program::ProgramId,
+};
+use serde::{Deserialize, Serialize};
+
+/// Path of the fixtures relative to the crate root
+const FIXTURES_PATH: &str = "tests/fixtures/conformance_vectors.json";
+
+const REGENERATE_ENV_VAR: &str = "NSSA_REGENERATE_CONFORMANCE";
+
+/// Messages are encoded in the same way for every program, so vectors use a fixed program id
+/// instead of ids of builtin programs, which change with every rebuild of guest programs
+const PROGRAM_ID: ProgramId = ProgramId::new([
+    0x0101_0101,
+    0x0202_0202,
+    0x0303_0303,
+    0x0404_0404,
+    0x0505_0505,
+    0x0606_0606,
+    0x0707_0707,
+    0x0808_0808,
+]);
+
+const SEED: [u8; 64] = [0x07; 64];
+const OTHER_SEED: [u8; 64] = [0xa5; 64];
+const RECIPIENT: AccountId = AccountId::new([0x42; 32]);
+const AUX_RAND: [u8; 32] = [0x5a; 32];
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Vector {
+    Public(PublicVector),
+    ProgramDeployment(ProgramDeploymentVector),
+    TransferRule(TransferRuleVector),
+}
+
+impl Vector {
+    fn name(&self) -> &str {
+        match self {
+            Self::Public(vector) => &vector.name,
+            Self::ProgramDeployment(vector) => &vector.name,
+            Self::TransferRule(vector) => &vector.name,
+        }
+    }
+}
+
+/// Call of [`PROGRAM_ID`] with the `u128` amount as instruction, the way native transfers are
+/// called
+///
+/// Integers wider than 32 bits are written as decimal strings, so they survive JSON parsers
+/// limited to `f64`. Binary data is written as hex.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct PublicVector {
+    name: String,
+    /// Owners of the first accounts of the message, in the same order
+    signers: Vec<SignerVector>,
+    /// Account following the signers' accounts, if any
+    recipient: Option<String>,
+    readonly_account_ids: Vec<String>,
+    program_id: ProgramId,
+    nonces: Vec<String>,
+    amount: String,
+    priority_fee: String,
+    valid_from_block: Option<String>,
+    max_cycles: Option<String>,
+    /// Auxiliary randomness of all signatures
+    aux_rand: String,
+    instruction_data: Vec<u32>,
+    /// Signed bytes
+    message: String,
+    signatures: Vec<String>,
+    transaction: String,
+    /// Transaction as it is submitted to and stored by the sequencer
+    encoded_transaction: String,
+    transaction_hash: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct SignerVector {
+    seed: String,
+    /// Derivation path of the public key tree
+    path: String,
+    private_key: String,
+    public_key: String,
+    account_id: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ProgramDeploymentVector {
+    name: String,
+    deployer: SignerVector,
+    nonce: String,
+    chain_id: String,
+    max_fee: String,
+    bytecode: String,
+    aux_rand: String,
+    /// Signed bytes
+    message: String,
+    signature: String,
+    transaction: String,
+    /// Transaction as it is submitted to and stored by the sequencer
+    encoded_transaction: String,
+    transaction_hash: String,
+}
+
+/// Verdict of the rules of native transfers, see [`NativeTransfer::check`], on a transfer from the
+/// first account of [`SEED`]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TransferRuleVector {
+    name: String,
+    sender: String,
+    /// Sender itself for self-transfers
+    recipient: String,
+    /// Whether the recipient exists, so the transfer doesn't create it
+    recipient_exists: bool,
+    amount: String,
+    storage_deposit_per_byte: String,
+    min_account_creation_amount: String,
+    /// `valid` or the broken rule: `self_transfer` or `account_creation_below_minimum`
+    verdict: String,
+}
+
+struct PublicScenario {
+    name: &'static str,
+    signers: Vec<([u8; 64], &'static str)>,
+    recipient: Option<AccountId>,
+    readonly_account_ids: Vec<AccountId>,
+    nonces: Vec<Nonce>,
+    amount: u128,
+    priority_fee: Amount,
+    valid_from_block: Option<u64>,
+    max_cycles: Option<Cycles>,
+    aux_rand: [u8; 32],
+}
+
+impl PublicScenario {
+    /// Transfer of 100 from the first account of [`SEED`] to [`RECIPIENT`]
+    fn transfer(name: &'static str) -> Self {
+        Self {
+            name,
+            signers: vec![(SEED, "m/0")],
+            recipient: Some(RECIPIENT),
+            readonly_account_ids: vec![],
+            nonces: vec![0],
+            amount: 100,
+            priority_fee: Amount::ZERO,
+            valid_from_block: None,
+            max_cycles: None,
+            aux_rand: AUX_RAND,
+        }
+    }
+
+    fn into_vector(self) -> Vector {
+        let private_keys = self
+            .signers
+            .iter()
+            .map(|&(seed, path)| derive_private_key(seed, path))
+            .collect::<Vec<_>>();
+        let signers = self
+            .signers
+            .iter()
+            .zip(&private_keys)
+            .map(|(&(seed, path), private_key)| signer_vector(seed, path, private_key))
+            .collect::<Vec<_>>();
+
+        let account_ids = private_keys
+            .iter()
+            .map(|key| AccountId::from(&PublicKey::new_from_private_key(key)))
+            .chain(self.recipient)
+            .collect();
+        let message = public_transaction::Message::try_new(
+            PROGRAM_ID,
+            account_ids,
+            self.nonces.clone(),
+            self.amount,
+        )
+        .unwrap()
+        .with_readonly_account_ids(self.readonly_account_ids.clone())
+        .with_priority_fee(self.priority_fee)
+        .with_valid_from_block(self.valid_from_block)
+        .with_max_cycles(self.max_cycles);
+        let witness_set = public_transaction::WitnessSet::for_message_with_aux_random(
+            &message,
+            &private_keys.iter().collect::<Vec<_>>(),
+            self.aux_rand,
+        );
+        let tx = PublicTransaction::new(message.clone(), witness_set);
+        let encoded_tx = EncodedTransaction::from(NSSATransaction::Public(tx.clone()));
+
+        Vector::Public(PublicVector {
+            name: self.name.to_owned(),
+            signers,
+            recipient: self.recipient.map(|id| hex::encode(id.value())),
+            readonly_account_ids: self
+                .readonly_account_ids
+                .iter()
+                .map(|id| hex::encode(id.value()))
+                .collect(),
+            program_id: PROGRAM_ID,
+            nonces: self.nonces.iter().map(Nonce::to_string).collect(),
+            amount: self.amount.to_string(),
+            priority_fee: self.priority_fee.to_string(),
+            valid_from_block: self.valid_from_block.map(|block_id| block_id.to_string()),
+            max_cycles: self.max_cycles.map(|max_cycles| max_cycles.to_string()),
+            aux_rand: hex::encode(self.aux_rand),
+            instruction_data: message.instruction_data().clone(),
+            message: hex::encode(message.to_bytes()),
+            signatures: tx
+                .witness_set()
+                .signatures_and_public_keys()
+                .iter()
+                .map(|(signature, _)| hex::encode(signature.value()))
+                .collect(),
+            transaction: hex::encode(tx.to_bytes()),
+            encoded_transaction: hex::encode(borsh::to_vec(&encoded_tx).unwrap()),
+            transaction_hash: hex::encode(encoded_tx.hash()),
+        })
+    }
+}
+
+/// Deployment signed by the first account of [`SEED`]
+fn program_deployment_vector(
+    name: &str,
+    nonce: Nonce,
+    chain_id: u64,
+    max_fee: Amount,
+    bytecode: Vec<u8>,
+) -> Vector {
+    let path = "m/0";
+    let private_key = derive_private_key(SEED, path);
+    let deployer = AccountId::from(&PublicKey::new_from_private_key(&private_key));
+    let message = program_deployment_transaction::Message::new(
+        deployer,
+        nonce,
+        chain_id,
+        max_fee,
+        bytecode.clone(),
+    );
+    let witness_set = program_deployment_transaction::WitnessSet::for_message_with_aux_random(
+        &message,
+        &private_key,
+        AUX_RAND,
+    );
+    let tx = ProgramDeploymentTransaction::new(message.clone(), witness_set);
+    let encoded_tx = EncodedTransaction::from(NSSATransaction::ProgramDeployment(tx.clone()));
+
+    Vector::ProgramDeployment(ProgramDeploymentVector {
+        name: name.to_owned(),
+        deployer: signer_vector(SEED, path, &private_key),
+        nonce: nonce.to_string(),
+        chain_id: chain_id.to_string(),
+        max_fee: max_fee.to_string(),
+        bytecode: hex::encode(bytecode),
+        aux_rand: hex::encode(AUX_RAND),
+        message: hex::encode(message.to_bytes()),
+        signature: hex::encode(tx.witness_set().signatures_and_public_keys()[0].0.value()),
+        transaction: hex::encode(tx.to_bytes()),
+        encoded_transaction: hex::encode(borsh::to_vec(&encoded_tx).unwrap()),
+        transaction_hash: hex::encode(encoded_tx.hash()),
+    })
+}
+
+fn transfer_rule_vector(
+    name: &str,
+    self_transfer: bool,
+    recipient_exists: bool,
+    amount: Amount,
+    storage_deposit_per_byte: Amount,
+) -> Vector {
+    let private_key = derive_private_key(SEED, "m/0");
+    let sender = AccountId::from(&PublicKey::new_from_private_key(&private_key));
+    let recipient_id = if self_transfer { sender } else { RECIPIENT };
+    let recipient = if recipient_exists {
+        Account {
+            balance: Amount(1),
+            ..Account::default()
+        }
+    } else {
+        Account::default()
+    };
+
+    let transfer = NativeTransfer::Transfer {
+        sender,
+        recipient: recipient_id,
+        amount,
+    };
+    let verdict = match transfer.check(&recipient, storage_deposit_per_byte) {
+        Ok(()) => "valid",
+        Err(NssaError::SelfTransfer { .. }) => "self_transfer",
+        Err(NssaError::AccountCreationBelowMinimum { .. }) => "account_creation_below_minimum",
+        Err(err) => panic!("Unexpected error of transfer rules: {err}"),
+    };
+
+    Vector::TransferRule(TransferRuleVector {
+        name: name.to_owned(),
+        sender: hex::encode(sender.value()),
+        recipient: hex::encode(recipient_id.value()),
+        recipient_exists,
+        amount: amount.to_string(),
+        storage_deposit_per_byte: storage_deposit_per_byte.to_string(),
+        min_account_creation_amount: public_transaction::min_account_creation_amount(
+            storage_deposit_per_byte,
+        )
+        .to_string(),
+        verdict: verdict.to_owned(),
+    })
+}
+
+fn signer_vector(seed: [u8; 64], path: &str, private_key: &PrivateKey) -> SignerVector {
+    let public_key = PublicKey::new_from_private_key(private_key);
+    SignerVector {
+        seed: hex::encode(seed),
+        path: path.to_owned(),
+        private_key: hex::encode(private_key.value()),
+        public_key: hex::encode(public_key.value()),
+        account_id: hex::encode(AccountId::from(&public_key).value()),
+    }
+}
+
+/// Private key of the public key tree node at `path`, the same way the wallet derives it
+fn derive_private_key(seed: [u8; 64], path: &str) -> PrivateKey {
+    ChainIndex::from_path_str(path)
+        .unwrap()
+        .chain()
+        .iter()
+        .fold(ChildKeysPublic::root(seed), |node, &cci| {
+            node.nth_child(cci)
+        })
+        .csk
+}
+
+fn vectors() -> Vec<Vector> {
+    let public_scenarios = [
+        PublicScenario::transfer("transfer"),
+        PublicScenario {
+            amount: 0,
+            ..PublicScenario::transfer("transfer_zero_amount")
+        },
+        PublicScenario {
+            amount: 1,
+            ..PublicScenario::transfer("transfer_minimal_amount")
+        },
+        PublicScenario {
+            amount: u128::MAX,
+            ..PublicScenario::transfer("transfer_max_amount")
+        },
+        PublicScenario {
+            nonces: vec![1],
+            ..PublicScenario::transfer("transfer_next_nonce")
+        },
+        PublicScenario {
+            nonces: vec![Nonce::MAX],
+            ..PublicScenario::transfer("transfer_max_nonce")
+        },
+        PublicScenario {
+            signers: vec![(SEED, "m")],
+            ..PublicScenario::transfer("transfer_from_root_key")
+        },
+        PublicScenario {
+            signers: vec![(SEED, "m/1/2/3")],
+            ..PublicScenario::transfer("transfer_from_deep_path")
+        },
+        PublicScenario {
+            signers: vec![(SEED, "m/2147483647")],
+            ..PublicScenario::transfer("transfer_from_max_child_index")
+        },
+        PublicScenario {
+            signers: vec![(OTHER_SEED, "m/0")],
+            ..PublicScenario::transfer("transfer_from_other_seed")
+        },
+        PublicScenario {
+            aux_rand: [0; 32],
+            ..PublicScenario::transfer("transfer_zero_aux_rand")
+        },
+        PublicScenario {
+            aux_rand: [0xff; 32],
+            ..PublicScenario::transfer("transfer_max_aux_rand")
+        },
+        PublicScenario {
+            priority_fee: Amount(1_000),
+            ..PublicScenario::transfer("transfer_with_priority_fee")
+        },
+        PublicScenario {
+            priority_fee: Amount::MAX,
+            ..PublicScenario::transfer("transfer_with_max_priority_fee")
+        },
+        PublicScenario {
+            valid_from_block: Some(0),
+            ..PublicScenario::transfer("transfer_valid_from_genesis")
+        },
+        PublicScenario {
+            valid_from_block: Some(u64::MAX),
+            ..PublicScenario::transfer("transfer_valid_from_max_block")
+        },
+        PublicScenario {
+            max_cycles: Some(Cycles(1_000_000)),
+            ..PublicScenario::transfer("transfer_with_max_cycles")
+        },
+        PublicScenario {
+            readonly_account_ids: vec![AccountId::new([0x11; 32]), AccountId::new([0x22; 32])],
+            ..PublicScenario::transfer("transfer_with_readonly_accounts")
+        },
+        PublicScenario {
+            recipient: None,
+            amount: 0,
+            ..PublicScenario::transfer("initialize_account")
+        },
+        PublicScenario {
+            signers: vec![(SEED, "m/0"), (SEED, "m/1")],
+            nonces: vec![0, 7],
+            ..PublicScenario::transfer("two_signers")
+        },
+        PublicScenario {
+            signers: vec![(SEED, "m/0"), (OTHER_SEED, "m/0"), (OTHER_SEED, "m/5/1")],
+            nonces: vec![3, 0, Nonce::MAX],
+            ..PublicScenario::transfer("three_signers_from_two_seeds")
+        },
+        PublicScenario {
+            signers: vec![(SEED, "m/0"), (OTHER_SEED, "m/1")],
+            readonly_account_ids: vec![AccountId::new([0x33; 32])],
+            nonces: vec![12, 34],
+            amount: u128::MAX,
+            priority_fee: Amount(5),
+            valid_from_block: Some(1_000_000),
+            max_cycles: Some(Cycles(4_000_000)),
+            ..PublicScenario::transfer("all_message_fields")
+        },
+    ];
+
+    public_scenarios
+        .into_iter()
+        .map(PublicScenario::into_vector)
+        .chain([
+            program_deployment_vector("deploy_empty_bytecode", 0, 0, Amount::ZERO, vec![]),
+            program_deployment_vector(
+                "deploy_small_bytecode",
+                0,
+                0,
+                Amount(1),
+                vec![0xca, 0xfe, 0xba, 0xbe],
+            ),
+            program_deployment_vector(
+                "deploy_large_bytecode",
+                3,
+                7,
+                Amount::MAX,
+                (0..=u8::MAX).cycle().take(1024).collect(),
+            ),
+            program_deployment_vector(
+                "deploy_max_nonce_and_chain",
+                Nonce::MAX,
+                u64::MAX,
+                Amount(1),
+                vec![0xca, 0xfe],
+            ),
+        ])
+        .chain(transfer_rule_vectors())
+        .collect()
+}
+
+fn transfer_rule_vectors() -> Vec<Vector> {
+    let rate = Amount(10);
+    let min_amount = public_transaction::min_account_creation_amount(rate);
+    vec![
+        transfer_rule_vector("rule_zero_amount", false, true, Amount::ZERO, rate),
+        transfer_rule_vector(
+            "rule_zero_amount_creating_account_without_deposits",
+            false,
+            false,
+            Amount::ZERO,
+            Amount::ZERO,
+        ),
+        transfer_rule_vector("rule_self_transfer", true, true, Amount(100), rate),
+        transfer_rule_vector(
+            "rule_zero_amount_self_transfer",
+            true,
+            true,
+            Amount::ZERO,
+            rate,
+        ),
+        transfer_rule_vector(
+            "rule_zero_amount_creating_account",
+            false,
+            false,
+            Amount::ZERO,
+            rate,
+        ),
+        transfer_rule_vector(
+            "rule_creating_account_below_minimum",
+            false,
+            false,
+            Amount(min_amount.0 - 1),
+            rate,
+        ),
+        transfer_rule_vector(
+            "rule_creating_account_with_minimum",
+            false,
+            false,
+            min_amount,
+            rate,
+        ),
+        transfer_rule_vector(
+            "rule_below_minimum_to_existing_account",
+            false,
+            true,
+            Amount(1),
+            rate,
+        ),
+    ]
+}
+
+fn fixtures_path() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURES_PATH)
+}
+
+fn read_fixtures() -> Vec<Vector> {
+    let fixtures = std::fs::read_to_string(fixtures_path()).unwrap_or_else(|err| {
+        panic!(
+            "Failed to read {FIXTURES_PATH}, run with {REGENERATE_ENV_VAR}=1 to create it: {err}"
+        )
+    });
+    serde_json::from_str(&fixtures).unwrap()
+}
+
+#[test]
+fn test_vectors_match_fixtures() {
+    let vectors = vectors();
+
+    if std::env::var_os(REGENERATE_ENV_VAR).is_some() {
+        let mut fixtures = serde_json::to_string_pretty(&vectors).unwrap();
+        fixtures.push('\n');
+        std::fs::write(fixtures_path(), fixtures).unwrap();
+        return;
+    }
+
+    let fixtures = read_fixtures();
+    assert_eq!(
+        fixtures.iter().map(Vector::name).collect::<Vec<_>>(),
+        vectors.iter().map(Vector::name).collect::<Vec<_>>(),
+        "Vectors don't match fixtures, run with {REGENERATE_ENV_VAR}=1 if the change is intended"
+    );
+    for (fixture, vector) in fixtures.iter().zip(&vectors) {
+        assert_eq!(
+            fixture,
+            vector,
+            "Vector `{}` doesn't match fixture, run with {REGENERATE_ENV_VAR}=1 if the change is \
+             intended",
+            vector.name()
+        );
+    }
+}
+
+#[test]
+fn test_fixture_transactions_decode_and_verify() {
+    for fixture in read_fixtures() {
+        let (transaction, encoded_transaction) = match &fixture {
+            Vector::Public(vector) => (&vector.transaction, &vector.encoded_transaction),
+            Vector::ProgramDeployment(vector) => (&vector.transaction, &vector.encoded_transaction),
+            Vector::TransferRule(_) => continue,
+        };
+        let transaction = hex::decode(transaction).unwrap();
+        let encoded_tx: EncodedTransaction =
+            borsh::from_slice(&hex::decode(encoded_transaction).unwrap()).unwrap();
+        assert_eq!(
+            encoded_tx.encoded_transaction_data,
+            transaction,
+            "Fixture `{}`",
+            fixture.name()
+        );
+
+        match NSSATransaction::try_from(&encoded_tx).unwrap() {
+            NSSATransaction::Public(tx) => {
+                let Vector::Public(vector) = &fixture else {
+                    panic!("Fixture `{}` decodes to public transaction", fixture.name());
+                };
+                assert_eq!(tx.to_bytes(), transaction);
+                assert_eq!(hex::encode(tx.message().to_bytes()), vector.message);
+                assert!(
+                    tx.witness_set().is_valid_for(tx.message()),
+                    "Fixture `{}` has invalid signatures",
+                    fixture.name()
+                );
+            }
+            NSSATransaction::ProgramDeployment(tx) => {
+                let Vector::ProgramDeployment(vector) = &fixture else {
+                    panic!("Fixture `{}` decodes to program deployment", fixture.name());
+                };
+                assert_eq!(tx.to_bytes(), transaction);
+                assert_eq!(hex::encode(tx.message().to_bytes()), vector.message);
+                assert!(
+                    tx.witness_set().is_valid_for(tx.message()),
+                    "Fixture `{}` has invalid signature",
+                    fixture.name()
+                );
+            }
+            NSSATransaction::PrivacyPreserving(_) => {
+                panic!(
+                    "Fixture `{}` decodes to private transaction",
+                    fixture.name()
+                )
+            }
+        }
+    }
+}
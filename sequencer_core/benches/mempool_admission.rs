@@ -0,0 +1,87 @@
+use common::test_utils::{independent_native_transfers, sequencer_sign_key_for_testing};
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use sequencer_core::{
+    SequencerCore,
+    config::{SequencerConfig, default_program_allow_list},
+};
+
+const NUM_TXS: u32 = 1000;
+
+fn config(home: &std::path::Path) -> SequencerConfig {
+    SequencerConfig {
+        home: home.to_path_buf(),
+        override_rust_log: None,
+        genesis_id: 1,
+        is_genesis_random: false,
+        max_num_tx_in_block: NUM_TXS as usize,
+        mempool_max_size: NUM_TXS as usize,
+        block_create_timeout_millis: 1000,
+        port: 0,
+        initial_accounts: vec![],
+        initial_commitments: vec![],
+        signing_key: *sequencer_sign_key_for_testing().value(),
+        check_state_invariants: false,
+        lazy_proving: None,
+        mempool_wal: false,
+        ingest_queue_size: NUM_TXS as usize,
+        prune: None,
+        soft_confirmation_margin_blocks: 10,
+        event_sink: None,
+        program_allow_list: default_program_allow_list(),
+        max_block_timestamp_skew_millis: 30_000,
+        max_pending_per_sender: None,
+        max_consecutive_per_sender: None,
+        max_time_lock_blocks: 100_000,
+        max_parked_per_sender: 16,
+        upgrades: vec![],
+        storage_deposit_per_byte: nssa::Amount::ZERO,
+        max_instruction_data_size: nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+        max_program_size: nssa::program::DEFAULT_MAX_PROGRAM_SIZE,
+        anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+        chain_id: 0,
+        accept_dev_proofs: false,
+        deterministic_dev_mode: None,
+        serve_pending_transfers: false,
+        serve_pending_transactions: false,
+        system_lane: None,
+        tx_audit: Default::default(),
+        deployment_smoke_test_cycles: None,
+        force_params_change: false,
+        repair_indexes: false,
+    }
+}
+
+/// Taking submitted transactions from mempool into pending ones, which pre-checks and orders them
+fn bench_admission(c: &mut Criterion) {
+    let txs = independent_native_transfers(NUM_TXS, 10);
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("mempool_admission");
+    // Every iteration opens a new block store
+    group.sample_size(10);
+
+    group.bench_function(format!("{NUM_TXS}_txs"), |b| {
+        b.iter_batched(
+            || {
+                let home = tempfile::tempdir().unwrap();
+                let (sequencer, mempool_handle) =
+                    SequencerCore::start_from_config(config(home.path()));
+                runtime.block_on(async {
+                    for tx in &txs {
+                        mempool_handle.push(tx.clone()).await.unwrap();
+                    }
+                });
+                (sequencer, mempool_handle, home)
+            },
+            |(mut sequencer, mempool_handle, home)| {
+                sequencer.take_transactions_from_mempool();
+                (sequencer, mempool_handle, home)
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_admission);
+criterion_main!(benches);
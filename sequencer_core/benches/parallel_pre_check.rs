@@ -0,0 +1,28 @@
+use common::test_utils::independent_native_transfers;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use sequencer_core::pre_check_transactions;
+
+const NUM_TXS_IN_BLOCK: u32 = 1000;
+
+fn bench_pre_check(c: &mut Criterion) {
+    let txs = independent_native_transfers(NUM_TXS_IN_BLOCK, 10);
+    let mut group = c.benchmark_group("pre_check_transactions");
+
+    for num_threads in [1, rayon::current_num_threads()] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{num_threads}_threads")),
+            &txs,
+            |b, txs| b.iter(|| pool.install(|| pre_check_transactions(txs))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_pre_check);
+criterion_main!(benches);
@@ -0,0 +1,66 @@
+//! Block, which is built from pending transactions, but isn't committed yet.
+
+use std::collections::HashSet;
+
+use common::{
+    HashType, block::ParamsVersion, receipt::TransactionReceipt, transaction::EncodedTransaction,
+};
+
+use crate::invariants::StateInvariants;
+
+/// Result of selecting and validating pending transactions for the next block
+///
+/// Candidate is built on a copy of the sequencer state, so building one doesn't change anything
+/// until it's passed to [`SequencerCore::commit_block`](crate::SequencerCore::commit_block).
+pub struct BlockCandidate {
+    pub(crate) block_id: u64,
+    /// Version of chain parameters active at `block_id`, which the candidate obeys
+    pub(crate) params_version: ParamsVersion,
+    /// Whether any proof of the block is produced by [`nssa::ProverBackend::DevFake`]
+    pub(crate) dev_proved: bool,
+    pub(crate) transactions: Vec<EncodedTransaction>,
+    /// Receipts of `transactions`, in the same order
+    pub(crate) receipts: Vec<TransactionReceipt>,
+    /// Hashes and failure reasons of transactions, which failed validation
+    pub(crate) dropped: Vec<(HashType, String)>,
+    /// Hashes of `dropped` private transactions, which anchors left the anchor window
+    pub(crate) expired: HashSet<HashType>,
+    /// State after applying `transactions`
+    pub(crate) state: nssa::V02State,
+    pub(crate) state_invariants: StateInvariants,
+}
+
+impl BlockCandidate {
+    /// Id of the block, the candidate would be committed as
+    pub fn block_id(&self) -> u64 {
+        self.block_id
+    }
+
+    /// Transactions of the block in order of application
+    pub fn transactions(&self) -> &[EncodedTransaction] {
+        &self.transactions
+    }
+
+    /// Hashes of transactions, which would be dropped, and reasons of their failures
+    pub fn dropped(&self) -> &[(HashType, String)] {
+        &self.dropped
+    }
+
+    pub fn tx_hashes(&self) -> Vec<HashType> {
+        self.transactions
+            .iter()
+            .map(EncodedTransaction::hash)
+            .collect()
+    }
+
+    /// Total gas of the block
+    ///
+    /// Execution isn't metered yet, so gas of a transaction is the size of its encoded data in
+    /// bytes.
+    pub fn total_gas(&self) -> u64 {
+        self.transactions
+            .iter()
+            .map(|tx| tx.encoded_transaction_data.len() as u64)
+            .sum()
+    }
+}
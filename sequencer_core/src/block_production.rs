@@ -0,0 +1,275 @@
+//! Supervision of the block production loop, shared by the loop, the watchdog and health checks.
+//!
+//! A failed attempt to produce a block is retried on the next tick, unless the state is
+//! corrupted, as every next block would be built on top of it. The watchdog alerts if no block
+//! is produced while transactions wait for inclusion, e.g. because every attempt fails.
+//!
+//! The watchdog runs besides the loop, which holds the sequencer while producing a block, so it
+//! reads [`StallWatch`] without locking the sequencer. A hung attempt doesn't hang it too.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+
+use crate::{
+    health::{BLOCK_PRODUCTION_LOOP_CHECK, HealthCheck},
+    invariants::StateInvariantViolation,
+};
+
+/// Watchdog alerts once no block was produced for this many block intervals
+pub const STALL_ALERT_BLOCK_INTERVALS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The next attempt may succeed
+    Transient,
+    /// State is corrupted, so block production must stop
+    Fatal,
+}
+
+impl FailureKind {
+    pub fn of(err: &anyhow::Error) -> Self {
+        if err
+            .chain()
+            .any(|cause| cause.is::<StateInvariantViolation>())
+        {
+            Self::Fatal
+        } else {
+            Self::Transient
+        }
+    }
+}
+
+/// Progress of block production, which the sequencer publishes and the watchdog checks
+///
+/// Times are kept in milliseconds since creation, so they fit into atomics.
+#[derive(Debug)]
+pub struct StallWatch {
+    created_at: Instant,
+    /// Time of the last block, or of the creation if there was none
+    last_block_at_millis: AtomicU64,
+    /// Pending and system transactions waiting for inclusion
+    num_pending: AtomicUsize,
+    stall_alerts: AtomicU64,
+    /// Time without blocks, set while the stall alert is raised, zero otherwise
+    stalled_for_millis: AtomicU64,
+}
+
+impl Default for StallWatch {
+    fn default() -> Self {
+        Self {
+            created_at: Instant::now(),
+            last_block_at_millis: AtomicU64::new(0),
+            num_pending: AtomicUsize::new(0),
+            stall_alerts: AtomicU64::new(0),
+            stalled_for_millis: AtomicU64::new(0),
+        }
+    }
+}
+
+impl StallWatch {
+    /// Records a block produced at `produced_at`, which clears the stall alert
+    pub fn record_block(&self, produced_at: Instant) {
+        let millis = produced_at
+            .saturating_duration_since(self.created_at)
+            .as_millis();
+        self.last_block_at_millis
+            .store(u64::try_from(millis).unwrap_or(u64::MAX), Ordering::Relaxed);
+        self.stalled_for_millis.store(0, Ordering::Relaxed);
+    }
+
+    pub fn set_num_pending(&self, num_pending: usize) {
+        self.num_pending.store(num_pending, Ordering::Relaxed);
+    }
+
+    /// Raises the stall alert if no block was produced for [`STALL_ALERT_BLOCK_INTERVALS`] block
+    /// intervals until `now` while transactions wait, returns whether it's raised
+    ///
+    /// `num_queued` transactions in mempool, which the sequencer didn't take yet, wait too. Alert
+    /// is logged once per stall and stays raised until the next block.
+    pub fn watch(&self, now: Instant, block_interval: Duration, num_queued: usize) -> bool {
+        let last_block_at = self.created_at
+            + Duration::from_millis(self.last_block_at_millis.load(Ordering::Relaxed));
+        let since_last_block = now.saturating_duration_since(last_block_at);
+        let num_pending = self.num_pending.load(Ordering::Relaxed) + num_queued;
+        let max_delay = block_interval * STALL_ALERT_BLOCK_INTERVALS;
+        if num_pending == 0 || since_last_block <= max_delay {
+            self.stalled_for_millis.store(0, Ordering::Relaxed);
+            return false;
+        }
+
+        // Stall is longer than zero, so it's rounded up to tell it from no stall
+        let stalled_for_millis = u64::try_from(since_last_block.as_millis())
+            .unwrap_or(u64::MAX)
+            .max(1);
+        if self
+            .stalled_for_millis
+            .swap(stalled_for_millis, Ordering::Relaxed)
+            == 0
+        {
+            self.stall_alerts.fetch_add(1, Ordering::Relaxed);
+            error!(
+                "BLOCK PRODUCTION STALLED: no block for {}ms with {num_pending} pending \
+                 transactions, expected one every {}ms",
+                since_last_block.as_millis(),
+                block_interval.as_millis()
+            );
+        }
+        true
+    }
+
+    /// Stall alerts raised since start
+    pub fn stall_alerts(&self) -> u64 {
+        self.stall_alerts.load(Ordering::Relaxed)
+    }
+
+    /// Time without blocks, if the stall alert is raised
+    pub fn stalled_for(&self) -> Option<Duration> {
+        match self.stalled_for_millis.load(Ordering::Relaxed) {
+            0 => None,
+            millis => Some(Duration::from_millis(millis)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BlockProductionMonitor {
+    failed_attempts: u64,
+    consecutive_failures: u64,
+    /// Shared with the watchdog
+    stall_watch: Arc<StallWatch>,
+    /// Failure, which stopped block production
+    fatal_error: Option<String>,
+}
+
+impl BlockProductionMonitor {
+    pub fn record_success(&mut self, block_id: u64) {
+        if self.consecutive_failures > 0 {
+            info!(
+                "Block production recovered with block {block_id} after {} failed attempts",
+                self.consecutive_failures
+            );
+        }
+        self.consecutive_failures = 0;
+    }
+
+    /// Records a failed attempt to produce a block and classifies it
+    pub fn record_failure(&mut self, err: &anyhow::Error) -> FailureKind {
+        self.failed_attempts += 1;
+        self.consecutive_failures += 1;
+
+        let kind = FailureKind::of(err);
+        match kind {
+            FailureKind::Transient => warn!(
+                "Failed to produce block, retrying on the next tick, {} failed attempts in a row: \
+                 {err:#}",
+                self.consecutive_failures
+            ),
+            FailureKind::Fatal => {
+                error!("Block production stopped: {err:#}");
+                self.fatal_error = Some(format!("{err:#}"));
+            }
+        }
+        kind
+    }
+
+    /// Failed attempts to produce a block since start
+    pub fn failed_attempts(&self) -> u64 {
+        self.failed_attempts
+    }
+
+    /// Stall alerts raised since start
+    pub fn stall_alerts(&self) -> u64 {
+        self.stall_watch.stall_alerts()
+    }
+
+    /// Progress of block production, which is checked by the watchdog without locking the
+    /// sequencer
+    pub fn stall_watch(&self) -> &Arc<StallWatch> {
+        &self.stall_watch
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.fatal_error.is_some()
+    }
+
+    pub fn health_check(&self) -> HealthCheck {
+        if let Some(err) = &self.fatal_error {
+            return HealthCheck::failed(
+                BLOCK_PRODUCTION_LOOP_CHECK,
+                format!("Block production stopped: {err}"),
+            );
+        }
+
+        match self.stall_watch.stalled_for() {
+            None => HealthCheck::passed(BLOCK_PRODUCTION_LOOP_CHECK),
+            Some(stalled_for) => HealthCheck::failed(
+                BLOCK_PRODUCTION_LOOP_CHECK,
+                format!(
+                    "No block was produced for {}ms while transactions are pending, {} failed \
+                     attempts in a row",
+                    stalled_for.as_millis(),
+                    self.consecutive_failures
+                ),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_only_invariant_violations_are_fatal() {
+        let err = anyhow::anyhow!("Storage is unavailable");
+        assert_eq!(FailureKind::of(&err), FailureKind::Transient);
+
+        let err = anyhow::Error::from(StateInvariantViolation("Supply changed".to_string()))
+            .context("Failed to build block");
+        assert_eq!(FailureKind::of(&err), FailureKind::Fatal);
+
+        let mut monitor = BlockProductionMonitor::default();
+        monitor.record_failure(&anyhow::anyhow!("Storage is unavailable"));
+        assert!(!monitor.is_stopped());
+        assert!(monitor.health_check().ok);
+
+        monitor.record_failure(&err);
+        assert!(monitor.is_stopped());
+        assert!(!monitor.health_check().ok);
+        assert_eq!(monitor.failed_attempts(), 2);
+    }
+
+    #[test]
+    fn test_watchdog_alerts_once_per_stall_with_pending_transactions() {
+        let monitor = BlockProductionMonitor::default();
+        let watch = monitor.stall_watch();
+        let start = watch.created_at;
+
+        // Idle chain isn't stalled
+        assert!(!watch.watch(start + INTERVAL * 10, INTERVAL, 0));
+        watch.set_num_pending(5);
+        assert!(!watch.watch(start + INTERVAL * 3, INTERVAL, 0));
+
+        assert!(watch.watch(start + INTERVAL * 4, INTERVAL, 0));
+        assert!(watch.watch(start + INTERVAL * 5, INTERVAL, 0));
+        assert_eq!(monitor.stall_alerts(), 1);
+        assert!(!monitor.health_check().ok);
+
+        watch.record_block(start + INTERVAL * 6);
+        assert!(monitor.health_check().ok);
+
+        // Transactions, which are still in mempool, wait too
+        watch.set_num_pending(0);
+        assert!(watch.watch(start + INTERVAL * 10, INTERVAL, 1));
+        assert_eq!(monitor.stall_alerts(), 2);
+    }
+}
@@ -1,15 +1,85 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use anyhow::Result;
-use common::{HashType, block::Block, transaction::EncodedTransaction};
-use storage::RocksDBIO;
+use common::{
+    HashType,
+    address_index::{AddressTxCursor, AddressTxFilter, AddressTxIndex, AddressTxPage, TxPosition},
+    block::Block,
+    receipt::TransactionReceipt,
+    transaction::{EncodedTransaction, NSSATransaction},
+};
+use log::error;
+use nssa::AccountId;
+use nssa_core::CommitmentSetDigest;
+use storage::{BlockHeaderRecord, DbWriteBatch, RocksDBIO, error::DbError};
+
+use crate::{config::PruneConfig, proving::BlockProofRecord, stored_params::StoredParams};
+
+/// Activity of a public account in stored blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountActivity {
+    pub tx_count: u64,
+    pub first_seen_block: u64,
+    pub last_seen_block: u64,
+}
+
+/// Indexes of transactions in stored blocks, which are kept in memory and rebuilt by replaying
+/// stored blocks on restart, see also [`crate::index_repair`]
+#[derive(Default)]
+pub(crate) struct TransactionIndexes {
+    // TODO: Consider adding the hashmap to the database for faster recovery.
+    pub(crate) tx_hash_to_block_map: HashMap<HashType, u64>,
+    pub(crate) account_activity: HashMap<AccountId, AccountActivity>,
+    pub(crate) address_txs: AddressTxIndex,
+    // Receipts are computed while applying transactions, so they are rebuilt by replay as well
+    pub(crate) receipts: HashMap<HashType, TransactionReceipt>,
+}
+
+impl TransactionIndexes {
+    /// Indexes transaction `tx`, which is at `index` in block `block_id`
+    pub(crate) fn insert(&mut self, tx: &EncodedTransaction, block_id: u64, index: usize) {
+        self.tx_hash_to_block_map.insert(tx.hash(), block_id);
+        let position = TxPosition {
+            block_id,
+            index: index as u32,
+        };
+        self.address_txs.record(tx, position);
+        self.record_account_activity(tx, block_id);
+    }
+
+    fn record_account_activity(&mut self, tx: &EncodedTransaction, block_id: u64) {
+        let Ok(tx) = NSSATransaction::try_from(tx) else {
+            return;
+        };
+
+        for account_id in tx.public_account_ids() {
+            self.account_activity
+                .entry(account_id)
+                .and_modify(|activity| {
+                    activity.tx_count += 1;
+                    activity.last_seen_block = block_id;
+                })
+                .or_insert(AccountActivity {
+                    tx_count: 1,
+                    first_seen_block: block_id,
+                    last_seen_block: block_id,
+                });
+        }
+    }
+}
 
 pub struct SequencerBlockStore {
     dbio: RocksDBIO,
-    // TODO: Consider adding the hashmap to the database for faster recovery.
-    tx_hash_to_block_map: HashMap<HashType, u64>,
+    indexes: TransactionIndexes,
     genesis_id: u64,
     signing_key: nssa::PrivateKey,
+    prune: Option<PruneConfig>,
+    /// See [`SequencerBlockStore::missing_block_lookups`]
+    missing_block_lookups: AtomicU64,
 }
 
 impl SequencerBlockStore {
@@ -22,22 +92,26 @@ impl SequencerBlockStore {
         genesis_block: Option<Block>,
         signing_key: nssa::PrivateKey,
     ) -> Result<Self> {
-        let tx_hash_to_block_map = if let Some(block) = &genesis_block {
-            block_to_transactions_map(block)
-        } else {
-            HashMap::new()
-        };
-
-        let dbio = RocksDBIO::open_or_create(location, genesis_block)?;
+        let dbio = RocksDBIO::open_or_create(location, genesis_block.clone())?;
 
         let genesis_id = dbio.get_meta_first_block_in_db()?;
 
-        Ok(Self {
+        let mut this = Self {
             dbio,
             genesis_id,
-            tx_hash_to_block_map,
+            indexes: TransactionIndexes::default(),
             signing_key,
-        })
+            prune: None,
+            missing_block_lookups: AtomicU64::new(0),
+        };
+
+        if let Some(block) = &genesis_block {
+            for (index, tx) in block.body.transactions.iter().enumerate() {
+                this.indexes.insert(tx, block.header.block_id, index);
+            }
+        }
+
+        Ok(this)
     }
 
     /// Reopening existing database
@@ -45,20 +119,143 @@ impl SequencerBlockStore {
         SequencerBlockStore::open_db_with_genesis(location, None, signing_key)
     }
 
+    /// Prune bodies of blocks older than the retention window after each stored block
+    pub fn with_pruning(mut self, prune: Option<PruneConfig>) -> Result<Self> {
+        if let Some(prune) = &prune {
+            // The tip is needed to build the next block on top of it
+            anyhow::ensure!(prune.keep_blocks > 0, "At least one block must be kept");
+        }
+        self.prune = prune;
+        Ok(self)
+    }
+
+    /// Id of the first block, which body is stored. Bodies of the previous ones were pruned.
+    pub fn first_unpruned_block_id(&self) -> Result<u64> {
+        Ok(self.dbio.get_meta_first_unpruned_block()?)
+    }
+
+    /// Header of the block, which is kept even if the block body was pruned
+    pub fn get_block_header_at_id(&self, id: u64) -> Result<BlockHeaderRecord> {
+        Ok(self.dbio.get_block_header(id)?)
+    }
+
     pub fn get_block_at_id(&self, id: u64) -> Result<Block> {
-        Ok(self.dbio.get_block(id)?.into_block(&self.signing_key))
+        match self.dbio.get_block(id) {
+            Ok(block) => Ok(block.into_block(&self.signing_key)),
+            Err(err @ DbError::BlockMissing { .. }) => {
+                error!("Storage is inconsistent: {err}");
+                self.missing_block_lookups.fetch_add(1, Ordering::Relaxed);
+                Err(err.into())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Number of lookups of blocks, which should be stored, but are missing from storage
+    pub fn missing_block_lookups(&self) -> u64 {
+        self.missing_block_lookups.load(Ordering::Relaxed)
     }
 
     pub fn put_block_at_id(&mut self, block: Block) -> Result<()> {
-        let new_transactions_map = block_to_transactions_map(&block);
-        self.dbio.put_block(block, false)?;
-        self.tx_hash_to_block_map.extend(new_transactions_map);
+        self.put_block_with_state_digest(block, None, None)
+    }
+
+    /// Stores `block` together with digest of the state after applying it and its proof record
+    /// in one atomic write, so a crash never leaves the stored tip without its state digest or
+    /// a lazily proven block without a record.
+    pub fn put_block_with_state_digest(
+        &mut self,
+        block: Block,
+        state_digest: Option<CommitmentSetDigest>,
+        proof_record: Option<&BlockProofRecord>,
+    ) -> Result<()> {
+        let block_id = block.header.block_id;
+        let transactions = block.body.transactions.clone();
+
+        let mut batch = DbWriteBatch::default();
+        self.dbio.stage_block(&mut batch, block, false)?;
+        if let Some(state_digest) = state_digest {
+            self.dbio.stage_tip_state_digest(&mut batch, state_digest)?;
+        }
+        if let Some(proof_record) = proof_record {
+            self.dbio.stage_block_proof_record(
+                &mut batch,
+                block_id,
+                &borsh::to_vec(proof_record)?,
+            )?;
+        }
+        let pruned_transactions = self.stage_prune(&mut batch, block_id)?;
+        self.dbio.commit(batch)?;
+
+        for hash in &pruned_transactions {
+            self.indexes.tx_hash_to_block_map.remove(hash);
+            self.indexes.receipts.remove(hash);
+        }
+        for (index, tx) in transactions.iter().enumerate() {
+            self.indexes.insert(tx, block_id, index);
+        }
         Ok(())
     }
 
+    /// Stages pruning of bodies of blocks, which leave the retention window after storing block
+    /// `last_block_id`. Returns hashes of transactions in the pruned blocks.
+    fn stage_prune(&self, batch: &mut DbWriteBatch, last_block_id: u64) -> Result<Vec<HashType>> {
+        let Some(prune) = &self.prune else {
+            return Ok(vec![]);
+        };
+
+        let first_kept_block_id = (last_block_id + 1).saturating_sub(prune.keep_blocks);
+        let mut pruned_transactions = vec![];
+        for block_id in self.first_unpruned_block_id()?..first_kept_block_id {
+            let block = self.dbio.get_block(block_id)?;
+            pruned_transactions.extend(block.transactions.iter().map(EncodedTransaction::hash));
+        }
+        self.dbio
+            .stage_prune_blocks_before(batch, first_kept_block_id)?;
+        Ok(pruned_transactions)
+    }
+
+    /// Id of the last stored block
+    pub fn last_block_id(&self) -> Result<u64> {
+        Ok(self.dbio.get_meta_last_block_in_db()?)
+    }
+
+    /// Digest of the state after applying the last stored block, if it was stored
+    pub fn tip_state_digest(&self) -> Result<Option<CommitmentSetDigest>> {
+        Ok(self.dbio.get_tip_state_digest()?)
+    }
+
+    /// Parameters, which stored blocks are built with, `None` if they weren't recorded yet
+    pub fn stored_params(&self) -> Result<Option<StoredParams>> {
+        self.dbio
+            .get_meta_chain_params()?
+            .map(|record| Ok(borsh::from_slice(&record)?))
+            .transpose()
+    }
+
+    pub fn put_stored_params(&self, stored_params: &StoredParams) -> Result<()> {
+        Ok(self
+            .dbio
+            .put_meta_chain_params(&borsh::to_vec(stored_params)?)?)
+    }
+
+    pub fn put_block_proof_record(&self, block_id: u64, record: &BlockProofRecord) -> Result<()> {
+        Ok(self
+            .dbio
+            .put_block_proof_record(block_id, &borsh::to_vec(record)?)?)
+    }
+
+    /// Proof record of the block, `None` if the block wasn't submitted for proving
+    pub fn get_block_proof_record(&self, block_id: u64) -> Result<Option<BlockProofRecord>> {
+        self.dbio
+            .get_block_proof_record(block_id)?
+            .map(|record| Ok(borsh::from_slice(&record)?))
+            .transpose()
+    }
+
     /// Returns the transaction corresponding to the given hash, if it exists in the blockchain.
     pub fn get_transaction_by_hash(&self, hash: HashType) -> Option<EncodedTransaction> {
-        let block_id = self.tx_hash_to_block_map.get(&hash);
+        let block_id = self.indexes.tx_hash_to_block_map.get(&hash);
         let block = block_id.map(|&id| self.get_block_at_id(id));
         if let Some(Ok(block)) = block {
             for transaction in block.body.transactions.into_iter() {
@@ -70,8 +267,62 @@ impl SequencerBlockStore {
         None
     }
 
-    pub fn insert(&mut self, tx: &EncodedTransaction, block_id: u64) {
-        self.tx_hash_to_block_map.insert(tx.hash(), block_id);
+    /// Returns id of the block, which includes transaction with the given hash
+    pub fn get_block_id_of_transaction(&self, hash: &HashType) -> Option<u64> {
+        self.indexes.tx_hash_to_block_map.get(hash).copied()
+    }
+
+    /// Indexes transaction `tx`, which is at `index` in block `block_id`
+    pub fn insert(&mut self, tx: &EncodedTransaction, block_id: u64, index: usize) {
+        self.indexes.insert(tx, block_id, index);
+    }
+
+    /// Records receipt of stored transaction with hash `hash`
+    pub fn insert_receipt(&mut self, hash: HashType, receipt: TransactionReceipt) {
+        self.indexes.receipts.insert(hash, receipt);
+    }
+
+    /// Receipt of the transaction with the given hash, if it's in a stored block, which body
+    /// wasn't pruned
+    pub fn get_receipt(&self, hash: &HashType) -> Option<&TransactionReceipt> {
+        self.indexes.receipts.get(hash)
+    }
+
+    /// Returns activity of public account in stored blocks, if there is any.
+    pub fn get_account_activity(&self, account_id: &AccountId) -> Option<AccountActivity> {
+        self.indexes.account_activity.get(account_id).copied()
+    }
+
+    /// Page of transactions touching public account `account_id`, see [`AddressTxIndex::page`]
+    pub fn get_transactions_by_address(
+        &self,
+        account_id: &AccountId,
+        filter: &AddressTxFilter,
+        cursor: Option<AddressTxCursor>,
+        limit: usize,
+    ) -> AddressTxPage {
+        self.indexes
+            .address_txs
+            .page(account_id, filter, cursor, limit)
+    }
+
+    /// Number of transactions in stored blocks, which bodies weren't pruned
+    pub fn transaction_count(&self) -> u64 {
+        self.indexes.tx_hash_to_block_map.len() as u64
+    }
+
+    pub(crate) fn indexes(&self) -> &TransactionIndexes {
+        &self.indexes
+    }
+
+    #[cfg(test)]
+    pub(crate) fn indexes_mut(&mut self) -> &mut TransactionIndexes {
+        &mut self.indexes
+    }
+
+    /// Replaces indexes of stored transactions, e.g. with ones rebuilt from stored blocks
+    pub(crate) fn replace_indexes(&mut self, indexes: TransactionIndexes) {
+        self.indexes = indexes;
     }
 
     pub fn genesis_id(&self) -> u64 {
@@ -83,15 +334,6 @@ impl SequencerBlockStore {
     }
 }
 
-pub(crate) fn block_to_transactions_map(block: &Block) -> HashMap<HashType, u64> {
-    block
-        .body
-        .transactions
-        .iter()
-        .map(|transaction| (transaction.hash(), block.header.block_id))
-        .collect()
-}
-
 #[cfg(test)]
 mod tests {
     use common::{block::HashableBlockData, test_utils::sequencer_sign_key_for_testing};
@@ -111,6 +353,8 @@ mod tests {
             prev_block_hash: [0; 32],
             timestamp: 0,
             transactions: vec![],
+            params_version: 0,
+            dev_proved: false,
         };
 
         let genesis_block = genesis_block_hashable_data.into_block(&signing_key);
@@ -131,4 +375,157 @@ mod tests {
         let retrieved_tx = node_store.get_transaction_by_hash(tx.hash());
         assert_eq!(Some(tx), retrieved_tx);
     }
+
+    #[test]
+    fn test_account_activity() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+
+        let signing_key = sequencer_sign_key_for_testing();
+
+        let genesis_block = HashableBlockData {
+            block_id: 0,
+            prev_block_hash: [0; 32],
+            timestamp: 0,
+            transactions: vec![],
+            params_version: 0,
+            dev_proved: false,
+        }
+        .into_block(&signing_key);
+        let mut node_store =
+            SequencerBlockStore::open_db_with_genesis(path, Some(genesis_block), signing_key)
+                .unwrap();
+
+        let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let tx_1 = common::test_utils::create_transaction_native_token_transfer(
+            [1; 32],
+            0,
+            [2; 32],
+            10,
+            key.clone(),
+        );
+        let tx_2 = common::test_utils::create_transaction_native_token_transfer(
+            [1; 32], 1, [3; 32], 10, key,
+        );
+        node_store
+            .put_block_at_id(common::test_utils::produce_dummy_block(1, None, vec![tx_1]))
+            .unwrap();
+        node_store
+            .put_block_at_id(common::test_utils::produce_dummy_block(2, None, vec![tx_2]))
+            .unwrap();
+
+        assert_eq!(
+            node_store.get_account_activity(&AccountId::new([1; 32])),
+            Some(AccountActivity {
+                tx_count: 2,
+                first_seen_block: 1,
+                last_seen_block: 2,
+            })
+        );
+        assert_eq!(
+            node_store.get_account_activity(&AccountId::new([3; 32])),
+            Some(AccountActivity {
+                tx_count: 1,
+                first_seen_block: 2,
+                last_seen_block: 2,
+            })
+        );
+        assert_eq!(
+            node_store.get_account_activity(&AccountId::new([4; 32])),
+            None
+        );
+        assert_eq!(node_store.transaction_count(), 2);
+    }
+
+    #[test]
+    fn test_pruning_removes_old_transactions() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+
+        let signing_key = sequencer_sign_key_for_testing();
+
+        let genesis_block = HashableBlockData {
+            block_id: 0,
+            prev_block_hash: [0; 32],
+            timestamp: 0,
+            transactions: vec![],
+            params_version: 0,
+            dev_proved: false,
+        }
+        .into_block(&signing_key);
+        let mut prev_hash = genesis_block.header.hash;
+        let mut node_store =
+            SequencerBlockStore::open_db_with_genesis(path, Some(genesis_block), signing_key)
+                .unwrap()
+                .with_pruning(Some(PruneConfig { keep_blocks: 2 }))
+                .unwrap();
+
+        let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let mut txs = vec![];
+        for block_id in 1..=5 {
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                [1; 32],
+                block_id - 1,
+                [2; 32],
+                10,
+                key.clone(),
+            );
+            let block = common::test_utils::produce_dummy_block(
+                block_id,
+                Some(prev_hash),
+                vec![tx.clone()],
+            );
+            prev_hash = block.header.hash;
+            node_store.put_block_at_id(block).unwrap();
+            node_store.insert_receipt(tx.hash(), TransactionReceipt::default());
+            txs.push(tx);
+        }
+
+        assert_eq!(node_store.first_unpruned_block_id().unwrap(), 4);
+        assert!(node_store.get_block_at_id(3).is_err());
+        assert_eq!(node_store.get_block_at_id(4).unwrap().header.block_id, 4);
+        assert_eq!(
+            node_store.get_block_header_at_id(5).unwrap().hash,
+            prev_hash
+        );
+        assert_eq!(node_store.get_block_header_at_id(1).unwrap().block_id, 1);
+
+        assert_eq!(node_store.get_block_id_of_transaction(&txs[2].hash()), None);
+        assert_eq!(node_store.get_transaction_by_hash(txs[2].hash()), None);
+        assert_eq!(
+            node_store.get_transaction_by_hash(txs[3].hash()),
+            Some(txs[3].clone())
+        );
+        assert_eq!(node_store.transaction_count(), 2);
+        assert!(node_store.get_receipt(&txs[2].hash()).is_none());
+        assert!(node_store.get_receipt(&txs[3].hash()).is_some());
+        // Aggregated activity is kept
+        assert_eq!(
+            node_store
+                .get_account_activity(&AccountId::new([1; 32]))
+                .unwrap()
+                .tx_count,
+            5
+        );
+    }
+
+    #[test]
+    fn test_pruning_keeps_at_least_one_block() {
+        let temp_dir = tempdir().unwrap();
+        let signing_key = sequencer_sign_key_for_testing();
+        let genesis_block = common::test_utils::produce_dummy_block(0, None, vec![]);
+
+        let node_store = SequencerBlockStore::open_db_with_genesis(
+            temp_dir.path(),
+            Some(genesis_block),
+            signing_key,
+        )
+        .unwrap();
+
+        assert!(
+            node_store
+                .with_pruning(Some(PruneConfig { keep_blocks: 0 }))
+                .is_err()
+        );
+    }
 }
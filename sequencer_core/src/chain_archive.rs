@@ -0,0 +1,381 @@
+//! Portable archive of a whole chain, which moves its history to another machine for replay or
+//! offline analysis.
+//!
+//! Archive starts with [`CHAIN_ARCHIVE_MAGIC`] and a little-endian `u32` format version, records
+//! follow. Every record is a little-endian `u32` length, the borsh encoding of
+//! [`ArchiveRecord`] and SHA-256 of the encoding, so corruption is detected in the record it
+//! happened in. The first record is [`ArchiveRecord::Chain`], then every block from genesis to
+//! the last one in order. Archive is written and read record by record, so the chain is never
+//! held in memory.
+
+use std::{
+    io::{ErrorKind, Read, Write},
+    path::Path,
+};
+
+use anyhow::{Context as _, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use common::block::{BlockHash, HashableBlockData};
+use sha2::{Digest as _, Sha256};
+
+use crate::{
+    block_store::SequencerBlockStore, config::SequencerConfig, genesis_block_data,
+    proving::BlockProofRecord,
+};
+
+/// First bytes of every chain archive
+pub const CHAIN_ARCHIVE_MAGIC: &[u8; 8] = b"NSSACHN\0";
+/// Version of the archive format, which is written
+pub const CHAIN_ARCHIVE_VERSION: u32 = 1;
+/// Max length of an encoded record, so a corrupted length doesn't exhaust memory
+pub const MAX_ARCHIVE_RECORD_LEN: u32 = 1 << 30;
+
+#[derive(Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum ArchiveRecord {
+    /// Range of archived blocks
+    Chain {
+        genesis_id: u64,
+        genesis_hash: BlockHash,
+        last_block_id: u64,
+    },
+    Block(ArchivedBlock),
+}
+
+/// Block with its hash and receipt, i.e. the proof record
+#[derive(Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ArchivedBlock {
+    /// Hash of the block, as it was stored
+    pub hash: BlockHash,
+    /// `None` if the block wasn't submitted for proving
+    pub proof_record: Option<BlockProofRecord>,
+    /// Last field, as encoding of a block omits zero `params_version`, so it ends only where the
+    /// record ends
+    pub block: HashableBlockData,
+}
+
+/// Range of blocks in a validated archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveSummary {
+    pub genesis_id: u64,
+    pub genesis_hash: BlockHash,
+    pub last_block_id: u64,
+    pub last_block_hash: BlockHash,
+}
+
+pub struct ChainArchiveWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> ChainArchiveWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(CHAIN_ARCHIVE_MAGIC)?;
+        writer.write_all(&CHAIN_ARCHIVE_VERSION.to_le_bytes())?;
+        Ok(Self { writer })
+    }
+
+    pub fn write_record(&mut self, record: &ArchiveRecord) -> Result<()> {
+        let encoded = borsh::to_vec(record)?;
+        let len = u32::try_from(encoded.len())
+            .ok()
+            .filter(|len| *len <= MAX_ARCHIVE_RECORD_LEN)
+            .with_context(|| format!("Record of {} bytes is too long", encoded.len()))?;
+
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&encoded)?;
+        self.writer.write_all(&Sha256::digest(&encoded))?;
+        Ok(())
+    }
+
+    /// Flushes the archive and returns the underlying writer
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+pub struct ChainArchiveReader<R: Read> {
+    reader: R,
+    /// Number of records read so far, to point at the corrupted one
+    records_read: u64,
+}
+
+impl<R: Read> ChainArchiveReader<R> {
+    /// Fails if `reader` isn't a chain archive of a supported version
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0; CHAIN_ARCHIVE_MAGIC.len()];
+        reader
+            .read_exact(&mut magic)
+            .context("Failed to read archive header")?;
+        anyhow::ensure!(&magic == CHAIN_ARCHIVE_MAGIC, "Not a chain archive");
+
+        let mut version = [0; 4];
+        reader
+            .read_exact(&mut version)
+            .context("Failed to read archive header")?;
+        let version = u32::from_le_bytes(version);
+        anyhow::ensure!(
+            version == CHAIN_ARCHIVE_VERSION,
+            "Archive format version {version} is not supported, expected {CHAIN_ARCHIVE_VERSION}"
+        );
+
+        Ok(Self {
+            reader,
+            records_read: 0,
+        })
+    }
+
+    /// Next record with a valid checksum, `None` at the end of the archive
+    pub fn next_record(&mut self) -> Result<Option<ArchiveRecord>> {
+        let index = self.records_read;
+        let mut len = [0; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let len = u32::from_le_bytes(len);
+        anyhow::ensure!(
+            len <= MAX_ARCHIVE_RECORD_LEN,
+            "Record {index} is corrupted, its length {len} is too big"
+        );
+
+        let mut encoded = vec![0; len as usize];
+        let mut checksum = [0; 32];
+        self.reader
+            .read_exact(&mut encoded)
+            .and_then(|()| self.reader.read_exact(&mut checksum))
+            .with_context(|| format!("Record {index} is truncated"))?;
+        anyhow::ensure!(
+            Sha256::digest(&encoded).as_slice() == checksum,
+            "Record {index} is corrupted, checksum doesn't match"
+        );
+
+        let record = borsh::from_slice(&encoded)
+            .with_context(|| format!("Record {index} is corrupted, failed to decode it"))?;
+        self.records_read += 1;
+        Ok(Some(record))
+    }
+}
+
+/// Writes all stored blocks with their proof records to `writer`, returns id of the last one
+///
+/// Bodies of all blocks must be stored, i.e. the store must not be pruned.
+pub fn export_chain(block_store: &SequencerBlockStore, writer: impl Write) -> Result<u64> {
+    let genesis_id = block_store.genesis_id();
+    let first_unpruned_block_id = block_store.first_unpruned_block_id()?;
+    anyhow::ensure!(
+        first_unpruned_block_id == genesis_id,
+        "Blocks before {first_unpruned_block_id} are pruned, the chain can't be exported"
+    );
+    let last_block_id = block_store.last_block_id()?;
+
+    let mut archive = ChainArchiveWriter::new(writer)?;
+    archive.write_record(&ArchiveRecord::Chain {
+        genesis_id,
+        genesis_hash: block_store.get_block_header_at_id(genesis_id)?.hash,
+        last_block_id,
+    })?;
+    for block_id in genesis_id..=last_block_id {
+        let hash = block_store.get_block_header_at_id(block_id)?.hash;
+        let block = HashableBlockData::from(block_store.get_block_at_id(block_id)?);
+        archive.write_record(&ArchiveRecord::Block(ArchivedBlock {
+            hash,
+            block,
+            proof_record: block_store.get_block_proof_record(block_id)?,
+        }))?;
+    }
+    archive.finish()?;
+
+    Ok(last_block_id)
+}
+
+/// Reads the archive, checking checksums of records and the hash chain of blocks, and passes
+/// every block to `on_block` in order
+///
+/// Fails at the first invalid record, blocks before it are already passed.
+pub fn read_chain(
+    reader: impl Read,
+    mut on_block: impl FnMut(ArchivedBlock) -> Result<()>,
+) -> Result<ArchiveSummary> {
+    let mut archive = ChainArchiveReader::new(reader)?;
+    let Some(ArchiveRecord::Chain {
+        genesis_id,
+        genesis_hash,
+        last_block_id,
+    }) = archive.next_record()?
+    else {
+        anyhow::bail!("Archive doesn't start with the chain record");
+    };
+
+    let mut prev_block_hash = None;
+    for block_id in genesis_id..=last_block_id {
+        let Some(ArchiveRecord::Block(archived)) = archive.next_record()? else {
+            anyhow::bail!(
+                "Archive ends before block {block_id}, expected blocks up to {last_block_id}"
+            );
+        };
+        let block = &archived.block;
+        anyhow::ensure!(
+            block.block_id == block_id,
+            "Block {} is archived in place of block {block_id}",
+            block.block_id
+        );
+        anyhow::ensure!(
+            block.hash() == archived.hash,
+            "Block {block_id} doesn't match its hash"
+        );
+        match prev_block_hash {
+            None => anyhow::ensure!(
+                archived.hash == genesis_hash,
+                "Genesis block doesn't match the archived genesis hash"
+            ),
+            Some(prev_block_hash) => anyhow::ensure!(
+                block.prev_block_hash == prev_block_hash,
+                "Block {block_id} isn't built on top of block {}",
+                block_id - 1
+            ),
+        }
+
+        prev_block_hash = Some(archived.hash);
+        on_block(archived)?;
+    }
+    anyhow::ensure!(
+        archive.next_record()?.is_none(),
+        "Archive has records after the last block {last_block_id}"
+    );
+
+    Ok(ArchiveSummary {
+        genesis_id,
+        genesis_hash,
+        last_block_id,
+        last_block_hash: prev_block_hash.unwrap_or(genesis_hash),
+    })
+}
+
+/// Validates the archive and stores its blocks into a new block store at `db_path`, which the
+/// sequencer of `config` starts from
+///
+/// Proof records are imported only if `with_proofs` is set. Transactions aren't executed, replay
+/// the store to check them, see [`crate::replay::Replay`].
+pub fn import_chain(
+    config: &SequencerConfig,
+    reader: impl Read,
+    db_path: &Path,
+    with_proofs: bool,
+) -> Result<ArchiveSummary> {
+    anyhow::ensure!(
+        !db_path.exists(),
+        "Block store {} already exists, chain is imported only into a new one",
+        db_path.display()
+    );
+    let signing_key = nssa::PrivateKey::try_new(config.signing_key)?;
+    let expected_genesis_hash = genesis_block_data(config).hash();
+
+    let mut block_store: Option<SequencerBlockStore> = None;
+    read_chain(reader, |archived| {
+        let block_id = archived.block.block_id;
+        let proof_record = archived.proof_record.filter(|_| with_proofs);
+        let block = archived.block.into_block(&signing_key);
+
+        if let Some(block_store) = &mut block_store {
+            return block_store
+                .put_block_with_state_digest(block, None, proof_record.as_ref())
+                .with_context(|| format!("Failed to store block {block_id}"));
+        }
+
+        anyhow::ensure!(
+            block.header.hash == expected_genesis_hash,
+            "Archive belongs to another chain, program allow-list may differ"
+        );
+        let genesis_store =
+            SequencerBlockStore::open_db_with_genesis(db_path, Some(block), signing_key.clone())?;
+        if let Some(proof_record) = &proof_record {
+            genesis_store.put_block_proof_record(block_id, proof_record)?;
+        }
+        block_store = Some(genesis_store);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(block_id: u64, prev_block_hash: BlockHash) -> HashableBlockData {
+        HashableBlockData {
+            block_id,
+            prev_block_hash,
+            timestamp: block_id,
+            transactions: vec![],
+            params_version: 0,
+            dev_proved: false,
+        }
+    }
+
+    /// Archive of blocks `1..=last_block_id`
+    fn archive(last_block_id: u64) -> Vec<u8> {
+        let mut archive = ChainArchiveWriter::new(vec![]).unwrap();
+        let genesis_hash = block(1, [0; 32]).hash();
+        archive
+            .write_record(&ArchiveRecord::Chain {
+                genesis_id: 1,
+                genesis_hash,
+                last_block_id,
+            })
+            .unwrap();
+
+        let mut prev_block_hash = [0; 32];
+        for block_id in 1..=last_block_id {
+            let block = block(block_id, prev_block_hash);
+            prev_block_hash = block.hash();
+            archive
+                .write_record(&ArchiveRecord::Block(ArchivedBlock {
+                    hash: prev_block_hash,
+                    block,
+                    proof_record: Some(BlockProofRecord::Pending),
+                }))
+                .unwrap();
+        }
+        archive.finish().unwrap()
+    }
+
+    #[test]
+    fn test_archive_is_read_block_by_block() {
+        let mut block_ids = vec![];
+        let summary = read_chain(archive(5).as_slice(), |archived| {
+            block_ids.push(archived.block.block_id);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(block_ids, [1, 2, 3, 4, 5]);
+        assert_eq!(summary.last_block_id, 5);
+        assert_eq!(summary.genesis_hash, block(1, [0; 32]).hash());
+    }
+
+    #[test]
+    fn test_corrupted_or_truncated_archive_is_rejected() {
+        let archive = archive(5);
+        for position in [0, 10, archive.len() / 2, archive.len() - 1] {
+            let mut corrupted = archive.clone();
+            corrupted[position] ^= 1;
+            assert!(
+                read_chain(corrupted.as_slice(), |_| Ok(())).is_err(),
+                "Corruption at {position} isn't detected"
+            );
+        }
+
+        let truncated = &archive[..archive.len() - 40];
+        let err = read_chain(truncated, |_| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("truncated"), "{err:#}");
+    }
+
+    #[test]
+    fn test_archive_of_unsupported_version_is_rejected() {
+        let mut archive = archive(1);
+        archive[CHAIN_ARCHIVE_MAGIC.len()] = 2;
+
+        let err = read_chain(archive.as_slice(), |_| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("version 2"), "{err:#}");
+    }
+}
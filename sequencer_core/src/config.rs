@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use nssa::Amount;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -7,7 +8,7 @@ use serde::{Deserialize, Serialize};
 pub struct AccountInitialData {
     /// Hex encoded account id
     pub account_id: String,
-    pub balance: u128,
+    pub balance: Amount,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -17,6 +18,203 @@ pub struct CommitmentsInitialData {
     pub account: nssa_core::account::Account,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Proving of committed blocks in background
+pub struct LazyProvingConfig {
+    /// Maximum number of blocks queued for proving, the rest wait in memory
+    pub queue_size: usize,
+    /// Number of blocks proven simultaneously
+    pub parallelism: usize,
+    /// Backend proving the blocks, blocks proven by [`nssa::ProverBackend::DevFake`] are
+    /// flagged as dev-proved
+    #[serde(default)]
+    pub prover_backend: nssa::ProverBackend,
+    /// Verification of receipts of stored block proofs by replay. Only journals of the proofs
+    /// are checked if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_proofs: Option<ProofVerificationConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// Verification of stored block proofs ahead of replayed blocks, see
+/// [`crate::proof_pipeline::ProofPipeline`]
+pub struct ProofVerificationConfig {
+    /// Maximum number of blocks, which proofs are fetched and verified ahead of the replayed one
+    pub window: usize,
+    /// Number of proofs verified simultaneously
+    pub parallelism: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// Deletion of bodies of old blocks from storage, headers of all blocks are kept
+pub struct PruneConfig {
+    /// Number of the most recent blocks, which bodies are kept
+    pub keep_blocks: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Destination of events of committed blocks, delivered as newline-delimited JSON
+pub enum EventSinkTarget {
+    /// File, to which events are appended
+    File(PathBuf),
+    /// Unix socket, which is listened by the consumer
+    UnixSocket(PathBuf),
+    /// URL, to which batches of events are POSTed
+    Webhook(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+/// Delivery of events of committed blocks to downstream indexers
+pub struct EventSinkConfig {
+    pub target: EventSinkTarget,
+    /// Maximum rate of delivery, events beyond it wait in the event log
+    #[serde(default = "default_max_events_per_second")]
+    pub max_events_per_second: u32,
+}
+
+fn default_max_events_per_second() -> u32 {
+    1000
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// Parameters of the chain, which blocks are built and validated with
+pub struct ChainParams {
+    /// Maximum number of transactions in block
+    pub max_num_tx_in_block: usize,
+    /// Balance locked per byte of data of public accounts, which is refunded once the data is
+    /// removed. Not required if zero.
+    #[serde(default)]
+    pub storage_deposit_per_byte: Amount,
+    /// Maximum number of words of instruction data of public transactions
+    #[serde(default = "default_max_instruction_data_size")]
+    pub max_instruction_data_size: usize,
+    /// Maximum number of bytes of bytecode of deployed programs. Left out of serialization when
+    /// it's the default, so fingerprints of chains, which never set it, don't change.
+    #[serde(
+        default = "default_max_program_size",
+        skip_serializing_if = "is_default_max_program_size"
+    )]
+    pub max_program_size: usize,
+    /// Number of the latest blocks, which commitment set roots private transactions may prove
+    /// membership against, see [`nssa::Anchor`]. Left out of serialization when it's the default
+    /// for the same reason.
+    #[serde(
+        default = "default_anchor_window",
+        skip_serializing_if = "is_default_anchor_window"
+    )]
+    pub anchor_window: u64,
+}
+
+impl ChainParams {
+    /// Makes `state` validate transactions of blocks built with these parameters
+    pub fn apply_to(&self, state: &mut nssa::V02State) {
+        state.set_storage_deposit_per_byte(self.storage_deposit_per_byte);
+        state.set_max_instruction_data_size(self.max_instruction_data_size);
+        state.set_max_program_size(self.max_program_size);
+        state.set_anchor_window(self.anchor_window);
+    }
+}
+
+fn default_max_instruction_data_size() -> usize {
+    nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE
+}
+
+fn default_max_program_size() -> usize {
+    nssa::program::DEFAULT_MAX_PROGRAM_SIZE
+}
+
+fn is_default_max_program_size(max_program_size: &usize) -> bool {
+    *max_program_size == nssa::program::DEFAULT_MAX_PROGRAM_SIZE
+}
+
+fn default_anchor_window() -> u64 {
+    nssa::DEFAULT_ANCHOR_WINDOW
+}
+
+fn is_default_anchor_window(anchor_window: &u64) -> bool {
+    *anchor_window == nssa::DEFAULT_ANCHOR_WINDOW
+}
+
+/// Cycles of the run of deployed programs with empty input, see
+/// [`SequencerConfig::deployment_smoke_test_cycles`]
+pub const DEFAULT_DEPLOYMENT_SMOKE_TEST_CYCLES: u64 = 1024 * 1024; // 1M cycles
+
+fn default_deployment_smoke_test_cycles() -> Option<u64> {
+    Some(DEFAULT_DEPLOYMENT_SMOKE_TEST_CYCLES)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// Chain parameters, which replace the previous ones from the activation height on
+pub struct ParamsUpgrade {
+    /// Id of the first block built with `new_params`
+    pub activation_height: u64,
+    pub new_params: ChainParams,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// Reproducible block production of local chains, so CI can assert exact block hashes
+pub struct DeterministicDevModeConfig {
+    /// Seed of the RNG, which auxiliary randomness of block signatures is drawn from
+    pub seed: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+/// Priority lane of system transactions, like faucet mints, which are submitted by the operator
+/// through the admin RPC, so they aren't starved by user transactions
+pub struct SystemLaneConfig {
+    /// Bearer token, which requests of the admin RPC must carry
+    pub auth_token: String,
+    /// Maximum number of system transactions waiting for inclusion, further submissions are
+    /// rejected
+    #[serde(default = "default_system_lane_queue_size")]
+    pub queue_size: usize,
+    /// Share of `max_num_tx_in_block` in percent, which system transactions take before user
+    /// ones. At least one transaction per block.
+    #[serde(default = "default_system_lane_block_share_percent")]
+    pub block_share_percent: u8,
+}
+
+impl SystemLaneConfig {
+    /// Maximum number of system transactions in a block of `max_num_tx_in_block` transactions
+    pub fn block_budget(&self, max_num_tx_in_block: usize) -> usize {
+        (max_num_tx_in_block * usize::from(self.block_share_percent) / 100)
+            .clamp(1, max_num_tx_in_block.max(1))
+    }
+}
+
+fn default_system_lane_queue_size() -> usize {
+    100
+}
+
+fn default_system_lane_block_share_percent() -> u8 {
+    5
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// Audit log of mempool lifecycle events of transactions, see [`crate::tx_audit`]
+pub struct TxAuditConfig {
+    /// Number of the latest transactions, which events are kept in memory
+    #[serde(default = "default_tx_audit_capacity")]
+    pub capacity: usize,
+    /// If `True`, then events are also appended to `tx_audit.ndjson` in the home directory
+    #[serde(default)]
+    pub log_to_file: bool,
+}
+
+impl Default for TxAuditConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_tx_audit_capacity(),
+            log_to_file: false,
+        }
+    }
+}
+
+fn default_tx_audit_capacity() -> usize {
+    crate::tx_audit::TX_AUDIT_CAPACITY
+}
+
 // TODO: Provide default values
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SequencerConfig {
@@ -42,4 +240,199 @@ pub struct SequencerConfig {
     pub initial_commitments: Vec<CommitmentsInitialData>,
     /// Sequencer own signing key
     pub signing_key: [u8; 32],
+    /// If `True`, then total supply conservation is checked after every block.
+    /// Requires iterating over all public accounts, so disabled by default.
+    #[serde(default)]
+    pub check_state_invariants: bool,
+    /// If set, committed blocks are proven by background workers and their proofs are stored
+    /// next to them. Blocks are not proven otherwise.
+    #[serde(default)]
+    pub lazy_proving: Option<LazyProvingConfig>,
+    /// If `True`, then admitted transactions are logged to disk before submission succeeds and
+    /// are restored into mempool after restart. Disabled by default for speed of devnets.
+    #[serde(default)]
+    pub mempool_wal: bool,
+    /// Maximum number of submissions waiting for admission into mempool. Submissions beyond it
+    /// are rejected as congested.
+    #[serde(default = "default_ingest_queue_size")]
+    pub ingest_queue_size: usize,
+    /// If set, bodies of blocks older than the retention window are deleted after each stored
+    /// block. Such storage can't be replayed after restart, so it's only suitable for nodes,
+    /// which don't need to rebuild the state from stored blocks. The sequencer rebuilds it, so
+    /// it refuses to start with this set, see [`SequencerConfig::check_prune`].
+    #[serde(default)]
+    pub prune: Option<PruneConfig>,
+    /// Number of blocks added to the estimated inclusion of an admitted transaction in its soft
+    /// confirmation, as transactions with higher fee priority submitted later may go first
+    #[serde(default = "default_soft_confirmation_margin_blocks")]
+    pub soft_confirmation_margin_blocks: u64,
+    /// If set, events of committed blocks are logged to disk and delivered to the sink until it
+    /// acknowledges them, so indexers don't need to poll blocks
+    #[serde(default)]
+    pub event_sink: Option<EventSinkConfig>,
+    /// Programs, which transactions may call. The list is committed to in the genesis block, so
+    /// all nodes of a network must have the same one. Programs of every network and deployed
+    /// programs are allowed by default, as well as Piñata on testnet.
+    #[serde(default = "default_program_allow_list")]
+    pub program_allow_list: nssa::ProgramAllowList,
+    /// Maximum time in milliseconds a block timestamp may be ahead of local time, so blocks of
+    /// a sequencer with a slightly different clock are accepted
+    #[serde(default = "default_max_block_timestamp_skew_millis")]
+    pub max_block_timestamp_skew_millis: u64,
+    /// Maximum number of transactions of one sender waiting for inclusion. Transactions beyond
+    /// it are dropped on admission, so one sender can't fill the mempool. Unlimited if not set.
+    #[serde(default)]
+    pub max_pending_per_sender: Option<usize>,
+    /// If set, blocks take at most this number of consecutive transactions of one sender before
+    /// other senders take their turns, so one sender can't fill every block even with higher
+    /// fee priority
+    #[serde(default)]
+    pub max_consecutive_per_sender: Option<usize>,
+    /// Maximum number of blocks after the next one, which a transaction may be time-locked
+    /// until. Transactions locked for longer are rejected on submission. 100K blocks by default.
+    #[serde(default = "default_max_time_lock_blocks")]
+    pub max_time_lock_blocks: u64,
+    /// Maximum number of time-locked transactions of one sender parked until they become valid.
+    /// Transactions beyond it are dropped on admission. Parked transactions don't take room of
+    /// pending ones in the mempool, so they are bounded by this limit instead. 16 by default.
+    #[serde(default = "default_max_parked_per_sender")]
+    pub max_parked_per_sender: usize,
+    /// Scheduled changes of chain parameters in order of activation. Parameters of genesis are
+    /// taken from the fields above. Blocks carry the version of parameters they are built
+    /// with, so all nodes of a network must have the same upgrades and refuse blocks of
+    /// versions they don't know.
+    #[serde(default)]
+    pub upgrades: Vec<ParamsUpgrade>,
+    /// Balance locked per byte of data of public accounts until the data is removed, so state
+    /// growth is paid for. Not required by default.
+    #[serde(default)]
+    pub storage_deposit_per_byte: Amount,
+    /// Maximum number of words of instruction data of public transactions, which is checked
+    /// before programs are executed. 16K words by default.
+    #[serde(default = "default_max_instruction_data_size")]
+    pub max_instruction_data_size: usize,
+    /// Maximum number of bytes of bytecode of deployed programs, which is checked before they
+    /// are admitted. 4 MiB by default.
+    #[serde(default = "default_max_program_size")]
+    pub max_program_size: usize,
+    /// Private transactions may prove membership of spent commitments against roots of the
+    /// commitment set at the start of this many latest blocks, so they survive blocks produced
+    /// while they are proven. 100 blocks by default.
+    #[serde(default = "default_anchor_window")]
+    pub anchor_window: u64,
+    /// Deployed programs are run once with empty input and this many cycles before admission,
+    /// so images, which can't run at all, are rejected before they waste prover time. 1M cycles
+    /// by default, `null` to skip the run.
+    #[serde(default = "default_deployment_smoke_test_cycles")]
+    pub deployment_smoke_test_cycles: Option<u64>,
+    /// Id of the chain, which program deployments are signed for, so they can't be replayed on
+    /// another chain. Zero by default.
+    #[serde(default)]
+    pub chain_id: u64,
+    /// If `True`, then proofs of [`nssa::ProverBackend::DevFake`], which prove nothing, are
+    /// accepted, and blocks with them are flagged as dev-proved. Only for local chains.
+    #[serde(default)]
+    pub accept_dev_proofs: bool,
+    /// If set, blocks are signed with randomness drawn from the seed and stamped with logical
+    /// timestamps derived from their ids, so the same transactions build the same blocks on
+    /// every run. Only accepted by dev and test builds.
+    #[serde(default)]
+    pub deterministic_dev_mode: Option<DeterministicDevModeConfig>,
+    /// If `True`, then native transfers waiting for inclusion are served by account, so wallets
+    /// can show incoming transfers before they land. Disabled by default, as it reveals pending
+    /// transactions before inclusion.
+    #[serde(default)]
+    pub serve_pending_transfers: bool,
+    /// If `True`, then hash, sender, nonce and priority fee of every transaction waiting for
+    /// inclusion are served, so clients can mirror the mempool. Disabled by default for the same
+    /// reason.
+    #[serde(default)]
+    pub serve_pending_transactions: bool,
+    /// If set, the admin RPC accepts system transactions, which are included into blocks before
+    /// user ones. Disabled by default.
+    #[serde(default)]
+    pub system_lane: Option<SystemLaneConfig>,
+    /// Mempool lifecycle events of the latest transactions, which are served by `get_tx_audit`
+    /// of the admin RPC. 10K transactions are kept in memory by default.
+    #[serde(default)]
+    pub tx_audit: TxAuditConfig,
+    /// If `True`, then genesis and chain parameters, which don't match the ones stored blocks
+    /// are built with, replace them from the next block on. Set by `--force-params-change` of
+    /// the runner rather than by the config file, so it doesn't outlive one restart.
+    #[serde(skip)]
+    pub force_params_change: bool,
+    /// If `True`, then indexes of stored transactions are verified against stored blocks and
+    /// rebuilt before the sequencer starts serving. Set by `--repair` of the runner.
+    #[serde(skip)]
+    pub repair_indexes: bool,
+}
+
+fn default_ingest_queue_size() -> usize {
+    1000
+}
+
+fn default_soft_confirmation_margin_blocks() -> u64 {
+    10
+}
+
+fn default_max_block_timestamp_skew_millis() -> u64 {
+    30_000
+}
+
+fn default_max_time_lock_blocks() -> u64 {
+    100_000
+}
+
+fn default_max_parked_per_sender() -> usize {
+    16
+}
+
+/// Allow-list of configs, which don't set one
+pub fn default_program_allow_list() -> nssa::ProgramAllowList {
+    #[allow(unused_mut)]
+    let mut program_allow_list = nssa::ProgramAllowList::default();
+    #[cfg(feature = "testnet")]
+    program_allow_list
+        .builtin_programs
+        .push(nssa::BuiltinProgram::Pinata);
+    program_allow_list
+}
+
+impl SequencerConfig {
+    /// Account of the sequencer signing key, which receives priority and deployment fees
+    pub fn fee_recipient(&self) -> nssa::AccountId {
+        let signing_key = nssa::PrivateKey::try_new(self.signing_key).unwrap();
+        nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(&signing_key))
+    }
+
+    /// Fails if deterministic dev mode is set in a release build, as its blocks are predictable
+    pub fn check_deterministic_dev_mode(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.deterministic_dev_mode.is_none() || cfg!(debug_assertions),
+            "Deterministic dev mode is only available in dev and test builds of the sequencer"
+        );
+        Ok(())
+    }
+
+    /// Fails if pruning is set, as the sequencer replays all stored blocks on restart, which
+    /// pruned storage doesn't have
+    pub fn check_prune(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.prune.is_none(),
+            "Pruning of stored blocks isn't supported by the sequencer, as it replays them on \
+             restart"
+        );
+        Ok(())
+    }
+
+    /// Parameters of the chain until the first upgrade
+    pub fn genesis_params(&self) -> ChainParams {
+        ChainParams {
+            max_num_tx_in_block: self.max_num_tx_in_block,
+            storage_deposit_per_byte: self.storage_deposit_per_byte,
+            max_instruction_data_size: self.max_instruction_data_size,
+            max_program_size: self.max_program_size,
+            anchor_window: self.anchor_window,
+        }
+    }
 }
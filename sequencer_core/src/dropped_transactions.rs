@@ -0,0 +1,69 @@
+//! Bounded record of transactions, rejected by the sequencer after submission.
+
+use std::collections::{HashMap, VecDeque};
+
+use common::HashType;
+
+/// Number of dropped transactions remembered by the sequencer
+pub const DROPPED_TRANSACTIONS_CAPACITY: usize = 10_000;
+
+/// Reasons of the latest dropped transactions, the oldest ones are forgotten first
+#[derive(Debug, Clone)]
+pub struct DroppedTransactions {
+    capacity: usize,
+    order: VecDeque<HashType>,
+    reasons: HashMap<HashType, String>,
+}
+
+impl DroppedTransactions {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::new(),
+            reasons: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, hash: HashType, reason: String) {
+        if self.reasons.insert(hash, reason).is_some() {
+            return;
+        }
+
+        self.order.push_back(hash);
+        if self.order.len() > self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.reasons.remove(&oldest);
+        }
+    }
+
+    pub fn reason(&self, hash: &HashType) -> Option<&str> {
+        self.reasons.get(hash).map(String::as_str)
+    }
+}
+
+impl Default for DroppedTransactions {
+    fn default() -> Self {
+        Self::new(DROPPED_TRANSACTIONS_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oldest_dropped_transactions_are_forgotten() {
+        let mut dropped = DroppedTransactions::new(2);
+        dropped.record([1; 32], "first".to_string());
+        dropped.record([2; 32], "second".to_string());
+        // Recording the same hash again doesn't evict anything
+        dropped.record([2; 32], "second again".to_string());
+        assert_eq!(dropped.reason(&[1; 32]), Some("first"));
+
+        dropped.record([3; 32], "third".to_string());
+        assert_eq!(dropped.reason(&[1; 32]), None);
+        assert_eq!(dropped.reason(&[2; 32]), Some("second again"));
+        assert_eq!(dropped.reason(&[3; 32]), Some("third"));
+    }
+}
@@ -0,0 +1,591 @@
+//! Durable log of events of committed blocks, which are delivered to indexers.
+//!
+//! Events are appended to the log as newline-delimited JSON when a block is committed and stay
+//! there until the sink acknowledges them, so they survive an unreachable sink and restarts.
+//! Delivery is at-least-once: events delivered right before a crash, but not acknowledged, are
+//! delivered again. Every event has a consecutive sequence number, so consumers detect gaps and
+//! duplicates.
+
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write as _},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use common::{
+    HashType,
+    block::{Block, BlockId, TimeStamp},
+    block_explorer::{ExpandedBlock, ExpandedTransaction},
+    json_number::decimal_string,
+    receipt::{BalanceChange, TransactionReceipt},
+};
+use nssa::{Amount, instruction_decoder::DecoderRegistry};
+use serde::{Deserialize, Serialize};
+
+/// Name of the log file in the sequencer home directory
+pub const EVENT_LOG_FILE_NAME: &str = "events.ndjson";
+/// Name of the file with the last acknowledged event in the sequencer home directory
+pub const EVENT_CURSOR_FILE_NAME: &str = "events.cursor";
+
+/// Number of acknowledged events, after which they are removed from the log file
+const COMPACT_AFTER_ACKNOWLEDGED: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    /// Consecutive number of the event, starting from 1
+    pub seq: u64,
+    pub event: EventBody,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventBody {
+    /// Block was committed, events of its transactions and receipts follow
+    Block {
+        block_id: BlockId,
+        /// Hex encoded block hash
+        hash: String,
+        /// Hex encoded hash of previous block
+        prev_block_hash: String,
+        timestamp: TimeStamp,
+        num_transactions: usize,
+    },
+    /// Transaction was applied in the block
+    Transaction {
+        block_id: BlockId,
+        /// Position of the transaction in the block
+        index: usize,
+        transaction: ExpandedTransaction,
+    },
+    /// Outcome of a transaction, processed while building the block
+    Receipt {
+        block_id: BlockId,
+        /// Hex encoded transaction hash
+        tx_hash: String,
+        outcome: ReceiptOutcome,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ReceiptOutcome {
+    Applied {
+        /// Fee charged for the transaction, known only for program deployments
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "decimal_string::option"
+        )]
+        fee: Option<Amount>,
+        /// Balance changes of touched accounts, empty in events logged before they were recorded
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        balance_changes: Vec<BalanceChange>,
+    },
+    Dropped {
+        reason: String,
+    },
+}
+
+impl EventBody {
+    pub fn block_id(&self) -> BlockId {
+        match self {
+            Self::Block { block_id, .. }
+            | Self::Transaction { block_id, .. }
+            | Self::Receipt { block_id, .. } => *block_id,
+        }
+    }
+
+    /// Events of committed `block`, in which `dropped` transactions were dropped, with receipts
+    /// of applied transactions, which `receipt_of` returns by their hash
+    pub fn for_block(
+        block: &Block,
+        dropped: &[(HashType, String)],
+        decoders: &DecoderRegistry,
+        receipt_of: impl Fn(&HashType) -> Option<TransactionReceipt>,
+    ) -> Result<Vec<Self>> {
+        let expanded = ExpandedBlock::from_block_with_receipts(block, decoders, receipt_of)
+            .with_context(|| format!("Failed to decode block {}", block.header.block_id))?;
+        let block_id = expanded.block_id;
+
+        let mut events = vec![Self::Block {
+            block_id,
+            hash: expanded.hash,
+            prev_block_hash: expanded.prev_block_hash,
+            timestamp: expanded.timestamp,
+            num_transactions: expanded.transactions.len(),
+        }];
+        let receipts = expanded
+            .transactions
+            .iter()
+            .map(|transaction| Self::Receipt {
+                block_id,
+                tx_hash: transaction.hash.clone(),
+                outcome: ReceiptOutcome::Applied {
+                    fee: transaction.fee,
+                    balance_changes: transaction
+                        .receipt
+                        .as_ref()
+                        .map(|receipt| receipt.balance_changes.clone())
+                        .unwrap_or_default(),
+                },
+            })
+            .collect::<Vec<_>>();
+        events.extend(
+            expanded
+                .transactions
+                .into_iter()
+                .enumerate()
+                .map(|(index, transaction)| Self::Transaction {
+                    block_id,
+                    index,
+                    transaction,
+                }),
+        );
+        events.extend(receipts);
+        events.extend(dropped.iter().map(|(hash, reason)| Self::Receipt {
+            block_id,
+            tx_hash: hex::encode(hash),
+            outcome: ReceiptOutcome::Dropped {
+                reason: reason.clone(),
+            },
+        }));
+
+        Ok(events)
+    }
+}
+
+/// Last acknowledged event, persisted separately as acknowledged events leave the log file
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct EventCursor {
+    acknowledged_seq: u64,
+    acknowledged_block_id: Option<BlockId>,
+}
+
+pub struct EventLog {
+    path: PathBuf,
+    cursor_path: PathBuf,
+    file: File,
+    cursor: EventCursor,
+    /// Events, which are not acknowledged yet, in order
+    pending: VecDeque<Event>,
+    next_seq: u64,
+    /// Block of the last logged event
+    last_block_id: Option<BlockId>,
+    /// Number of acknowledged events, which are still in the log file
+    num_acknowledged_in_file: usize,
+}
+
+impl EventLog {
+    /// Opens the log in `dir` creating it if necessary
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(EVENT_LOG_FILE_NAME);
+        let cursor_path = dir.join(EVENT_CURSOR_FILE_NAME);
+
+        let cursor = match std::fs::read(&cursor_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).context("Failed to decode event cursor")?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => EventCursor::default(),
+            Err(err) => return Err(err.into()),
+        };
+        let events = read_events(&path)?;
+
+        let next_seq = events
+            .last()
+            .map_or(cursor.acknowledged_seq, |event| event.seq)
+            .max(cursor.acknowledged_seq)
+            + 1;
+        let last_block_id = events
+            .last()
+            .map(|event| event.event.block_id())
+            .or(cursor.acknowledged_block_id);
+        let pending = events
+            .into_iter()
+            .filter(|event| event.seq > cursor.acknowledged_seq)
+            .collect();
+
+        let mut this = Self {
+            file: OpenOptions::new().create(true).append(true).open(&path)?,
+            path,
+            cursor_path,
+            cursor,
+            pending,
+            next_seq,
+            last_block_id,
+            num_acknowledged_in_file: 0,
+        };
+        // Acknowledged events and a truncated tail are dropped
+        this.rewrite()?;
+
+        Ok(this)
+    }
+
+    /// Block of the last logged event, `None` if nothing was logged yet
+    pub fn last_block_id(&self) -> Option<BlockId> {
+        self.last_block_id
+    }
+
+    /// Appends `events` of one block to the log and waits until they reach the disk
+    pub fn append(&mut self, events: Vec<EventBody>) -> Result<()> {
+        let Some(last_event) = events.last() else {
+            return Ok(());
+        };
+        let block_id = last_event.block_id();
+
+        let events = events
+            .into_iter()
+            .zip(self.next_seq..)
+            .map(|(event, seq)| Event { seq, event })
+            .collect::<Vec<_>>();
+        let mut bytes = vec![];
+        for event in &events {
+            bytes.extend(encode_line(event)?);
+        }
+        self.file.write_all(&bytes)?;
+        self.file.sync_data()?;
+
+        self.next_seq += events.len() as u64;
+        self.last_block_id = Some(block_id);
+        self.pending.extend(events);
+        Ok(())
+    }
+
+    /// Up to `limit` oldest events, which are not acknowledged yet
+    pub fn pending(&self, limit: usize) -> Vec<Event> {
+        self.pending.iter().take(limit).cloned().collect()
+    }
+
+    /// Marks events up to `seq` inclusive as delivered, so they are not delivered again
+    pub fn acknowledge(&mut self, seq: u64) -> Result<()> {
+        let mut num_acknowledged = 0;
+        while let Some(event) = self.pending.front()
+            && event.seq <= seq
+        {
+            self.cursor = EventCursor {
+                acknowledged_seq: event.seq,
+                acknowledged_block_id: Some(event.event.block_id()),
+            };
+            self.pending.pop_front();
+            num_acknowledged += 1;
+        }
+        if num_acknowledged == 0 {
+            return Ok(());
+        }
+
+        write_atomically(&self.cursor_path, &serde_json::to_vec(&self.cursor)?)?;
+
+        self.num_acknowledged_in_file += num_acknowledged;
+        if self.num_acknowledged_in_file >= COMPACT_AFTER_ACKNOWLEDGED {
+            self.rewrite()?;
+        }
+        Ok(())
+    }
+
+    /// Atomically replaces the log file with pending events
+    fn rewrite(&mut self) -> Result<()> {
+        let mut bytes = vec![];
+        for event in &self.pending {
+            bytes.extend(encode_line(event)?);
+        }
+        write_atomically(&self.path, &bytes)
+            .with_context(|| format!("Failed to replace event log at {}", self.path.display()))?;
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.num_acknowledged_in_file = 0;
+        Ok(())
+    }
+}
+
+fn encode_line(event: &Event) -> Result<Vec<u8>> {
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        writer.write_all(bytes)?;
+        writer.into_inner()?.sync_all()?;
+    }
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Complete events of the log at `path`, empty if there is no log
+fn read_events(path: &Path) -> Result<Vec<Event>> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut events = vec![];
+    for line in contents.split_inclusive(|byte| *byte == b'\n') {
+        let Some(line) = line.strip_suffix(b"\n") else {
+            log::warn!("Ignoring truncated event at the end of event log");
+            break;
+        };
+        events.push(serde_json::from_slice(line).context("Failed to decode event in event log")?);
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use common::test_utils::{create_transaction_native_token_transfer, produce_dummy_block};
+
+    use super::*;
+
+    fn block_events(block_id: BlockId) -> Vec<EventBody> {
+        let tx = create_transaction_native_token_transfer(
+            [1; 32],
+            block_id,
+            [2; 32],
+            10,
+            nssa::PrivateKey::try_new([1; 32]).unwrap(),
+        );
+        let block = produce_dummy_block(block_id, None, vec![tx]);
+        EventBody::for_block(
+            &block,
+            &[([7; 32], "Nonce mismatch".to_string())],
+            &DecoderRegistry::default(),
+            |_| None,
+        )
+        .unwrap()
+    }
+
+    fn seqs(events: &[Event]) -> Vec<u64> {
+        events.iter().map(|event| event.seq).collect()
+    }
+
+    #[test]
+    fn test_block_events() {
+        let events = block_events(3);
+
+        assert_eq!(events.len(), 4);
+        assert!(matches!(
+            events[0],
+            EventBody::Block {
+                block_id: 3,
+                num_transactions: 1,
+                ..
+            }
+        ));
+        let EventBody::Transaction {
+            index, transaction, ..
+        } = &events[1]
+        else {
+            panic!("Expected transaction event, got {:?}", events[1]);
+        };
+        assert_eq!(*index, 0);
+        assert_eq!(transaction.transfer.as_ref().unwrap().amount, Amount(10));
+        assert_eq!(
+            events[2],
+            EventBody::Receipt {
+                block_id: 3,
+                tx_hash: transaction.hash.clone(),
+                outcome: ReceiptOutcome::Applied {
+                    fee: None,
+                    balance_changes: vec![],
+                },
+            }
+        );
+        assert_eq!(
+            events[3],
+            EventBody::Receipt {
+                block_id: 3,
+                tx_hash: hex::encode([7; 32]),
+                outcome: ReceiptOutcome::Dropped {
+                    reason: "Nonce mismatch".to_string()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_wire_format() {
+        let event = Event {
+            seq: 5,
+            event: EventBody::Receipt {
+                block_id: 3,
+                tx_hash: "00ff".to_string(),
+                outcome: ReceiptOutcome::Dropped {
+                    reason: "Nonce mismatch".to_string(),
+                },
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "seq": 5,
+                "event": {
+                    "receipt": {
+                        "block_id": 3,
+                        "tx_hash": "00ff",
+                        "outcome": { "status": "dropped", "reason": "Nonce mismatch" }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_deployment_receipt_records_fee() {
+        let signing_key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let deployer = nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(&signing_key));
+        let message = nssa::program_deployment_transaction::Message::new(
+            deployer,
+            0,
+            0,
+            Amount(10),
+            vec![0; 3000],
+        );
+        let witness_set =
+            nssa::program_deployment_transaction::WitnessSet::for_message(&message, &signing_key);
+        let tx = common::transaction::EncodedTransaction::from(
+            common::transaction::NSSATransaction::ProgramDeployment(
+                nssa::ProgramDeploymentTransaction::new(message, witness_set),
+            ),
+        );
+        let block = produce_dummy_block(3, None, vec![tx]);
+
+        let events =
+            EventBody::for_block(&block, &[], &DecoderRegistry::default(), |_| None).unwrap();
+
+        let EventBody::Receipt { outcome, .. } = &events[2] else {
+            panic!("Expected receipt event, got {:?}", events[2]);
+        };
+        assert_eq!(
+            *outcome,
+            ReceiptOutcome::Applied {
+                fee: Some(Amount(3)),
+                balance_changes: vec![],
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(outcome).unwrap(),
+            serde_json::json!({ "status": "applied", "fee": "3" })
+        );
+        assert_eq!(
+            serde_json::to_value(ReceiptOutcome::Applied {
+                fee: None,
+                balance_changes: vec![],
+            })
+            .unwrap(),
+            serde_json::json!({ "status": "applied" })
+        );
+    }
+
+    #[test]
+    fn test_receipt_records_balance_changes() {
+        let tx = create_transaction_native_token_transfer(
+            [1; 32],
+            0,
+            [2; 32],
+            10,
+            nssa::PrivateKey::try_new([1; 32]).unwrap(),
+        );
+        let block = produce_dummy_block(3, None, vec![tx.clone()]);
+        let receipt = TransactionReceipt::new(
+            [
+                (nssa::AccountId::new([1; 32]), Amount(100), Amount(90)),
+                (nssa::AccountId::new([2; 32]), Amount(0), Amount(10)),
+            ],
+            &nssa::gas::PaidFees::default(),
+        );
+
+        let events = EventBody::for_block(&block, &[], &DecoderRegistry::default(), |hash| {
+            (*hash == tx.hash()).then(|| receipt.clone())
+        })
+        .unwrap();
+
+        let EventBody::Receipt { outcome, .. } = &events[2] else {
+            panic!("Expected receipt event, got {:?}", events[2]);
+        };
+        assert_eq!(
+            *outcome,
+            ReceiptOutcome::Applied {
+                fee: None,
+                balance_changes: receipt.balance_changes,
+            }
+        );
+        // Receipts logged before balance changes were recorded are still read
+        assert_eq!(
+            serde_json::from_value::<ReceiptOutcome>(serde_json::json!({ "status": "applied" }))
+                .unwrap(),
+            ReceiptOutcome::Applied {
+                fee: None,
+                balance_changes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_unacknowledged_events_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let mut log = EventLog::open(dir.path()).unwrap();
+            assert_eq!(log.last_block_id(), None);
+            log.append(block_events(2)).unwrap();
+            log.append(block_events(3)).unwrap();
+            log.acknowledge(5).unwrap();
+            assert_eq!(seqs(&log.pending(usize::MAX)), [6, 7, 8]);
+        }
+
+        let mut log = EventLog::open(dir.path()).unwrap();
+        assert_eq!(log.last_block_id(), Some(3));
+        assert_eq!(seqs(&log.pending(2)), [6, 7]);
+
+        log.append(block_events(4)).unwrap();
+        assert_eq!(seqs(&log.pending(usize::MAX)), [6, 7, 8, 9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_sequence_continues_after_everything_is_acknowledged() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let mut log = EventLog::open(dir.path()).unwrap();
+            log.append(block_events(2)).unwrap();
+            log.acknowledge(4).unwrap();
+            log.rewrite().unwrap();
+        }
+
+        let mut log = EventLog::open(dir.path()).unwrap();
+        assert!(log.pending(usize::MAX).is_empty());
+        assert_eq!(log.last_block_id(), Some(2));
+
+        log.append(block_events(3)).unwrap();
+        assert_eq!(seqs(&log.pending(usize::MAX)), [5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_truncated_event_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let mut log = EventLog::open(dir.path()).unwrap();
+            log.append(block_events(2)).unwrap();
+        }
+        // Crash in the middle of writing the next block
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(EVENT_LOG_FILE_NAME))
+            .unwrap();
+        file.write_all(br#"{"seq":5,"event""#).unwrap();
+        drop(file);
+
+        let mut log = EventLog::open(dir.path()).unwrap();
+        assert_eq!(seqs(&log.pending(usize::MAX)), [1, 2, 3, 4]);
+
+        log.append(block_events(3)).unwrap();
+        let log = EventLog::open(dir.path()).unwrap();
+        assert_eq!(seqs(&log.pending(usize::MAX)), [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+}
@@ -0,0 +1,55 @@
+//! Health and readiness reports for orchestrators.
+
+use serde::{Deserialize, Serialize};
+
+pub const STORAGE_CHECK: &str = "storage";
+pub const BLOCK_PRODUCTION_CHECK: &str = "block_production";
+pub const SYNC_CHECK: &str = "sync";
+pub const BLOCK_PRODUCTION_LOOP_CHECK: &str = "block_production_loop";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub ok: bool,
+    /// Reason of failure
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HealthCheck {
+    pub fn passed(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ok: true,
+            error: None,
+        }
+    }
+
+    pub fn failed(name: &str, error: String) -> Self {
+        Self {
+            name: name.to_string(),
+            ok: false,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// `true` if all checks passed
+    pub ok: bool,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn new(checks: Vec<HealthCheck>) -> Self {
+        Self {
+            ok: checks.iter().all(|check| check.ok),
+            checks,
+        }
+    }
+
+    pub fn failed_checks(&self) -> impl Iterator<Item = &HealthCheck> {
+        self.checks.iter().filter(|check| !check.ok)
+    }
+}
@@ -0,0 +1,205 @@
+//! Verification and rebuilding of indexes of stored transactions, which are derived from stored
+//! blocks and kept in memory, see [`TransactionIndexes`].
+//!
+//! Indexes are rebuilt by replaying stored blocks on a separate state, so the live indexes keep
+//! serving reads until the rebuilt ones replace them. Blocks are read in batches of
+//! [`INDEX_REBUILD_BATCH_SIZE`], so memory usage doesn't grow with the chain beyond the indexes
+//! themselves.
+
+use std::{collections::HashMap, hash::Hash};
+
+use anyhow::{Context as _, Result};
+use common::{
+    address_index::{AddressTxEntry, AddressTxIndex, TxPosition},
+    block::Block,
+    rpc_types::{IndexDiscrepancies, IndexReport},
+};
+use log::warn;
+use nssa::{AccountId, V02State};
+use nssa_core::fmt::FmtShort as _;
+
+use crate::{
+    block_store::TransactionIndexes, execute_prechecked_transaction, params::ParamsSchedule,
+    pre_check_transactions, public_balances, touched_account_ids, transaction_receipt,
+};
+
+/// Number of stored blocks read at once while rebuilding indexes
+pub const INDEX_REBUILD_BATCH_SIZE: usize = 16;
+
+/// Indexes being rebuilt from stored blocks, see [`crate::SequencerCore::start_index_rebuild`]
+pub struct IndexRebuild {
+    state: V02State,
+    params_schedule: ParamsSchedule,
+    indexes: TransactionIndexes,
+    next_block_id: u64,
+    scanned_blocks: u64,
+}
+
+impl IndexRebuild {
+    pub(crate) fn new(
+        state: V02State,
+        params_schedule: ParamsSchedule,
+        first_block_id: u64,
+    ) -> Self {
+        Self {
+            state,
+            params_schedule,
+            indexes: TransactionIndexes::default(),
+            next_block_id: first_block_id,
+            scanned_blocks: 0,
+        }
+    }
+
+    /// Id of the block, which is applied next
+    pub fn next_block_id(&self) -> u64 {
+        self.next_block_id
+    }
+
+    /// Replays `block` on the rebuilt state and indexes its transactions
+    pub fn apply_block(&mut self, block: &Block) -> Result<()> {
+        let block_id = block.header.block_id;
+        anyhow::ensure!(
+            block_id == self.next_block_id,
+            "Block {} was expected, got block {block_id}",
+            self.next_block_id
+        );
+        self.params_schedule.check_block(block)?;
+        self.params_schedule
+            .params_at(block_id)
+            .1
+            .apply_to(&mut self.state);
+
+        self.state.set_block_id(block_id);
+        for (index, (encoded_transaction, transaction)) in block
+            .body
+            .transactions
+            .iter()
+            .zip(pre_check_transactions(&block.body.transactions))
+            .enumerate()
+        {
+            let tx_hash = encoded_transaction.hash();
+            let transaction = transaction.map_err(|err| {
+                anyhow::anyhow!(
+                    "Transaction {} in block {block_id} is malformed: {err:?}",
+                    hex::encode(tx_hash)
+                )
+            })?;
+
+            let touched_account_ids = touched_account_ids(&self.state, &transaction);
+            let pre_balances = public_balances(&self.state, &touched_account_ids);
+            let (_, fees) = execute_prechecked_transaction(&mut self.state, transaction)
+                .with_context(|| {
+                    format!(
+                        "Transaction {} in block {block_id} failed to execute",
+                        hex::encode(tx_hash)
+                    )
+                })?;
+            self.indexes.insert(encoded_transaction, block_id, index);
+            self.indexes.receipts.insert(
+                tx_hash,
+                transaction_receipt(&self.state, pre_balances, &fees),
+            );
+        }
+
+        self.next_block_id += 1;
+        self.scanned_blocks += 1;
+        Ok(())
+    }
+
+    pub(crate) fn state_root(&self) -> [u8; 32] {
+        self.state.state_root()
+    }
+
+    /// Discrepancies of `current` indexes with the rebuilt ones, which are returned as well
+    pub(crate) fn finish(self, current: &TransactionIndexes) -> (IndexReport, TransactionIndexes) {
+        let rebuilt = self.indexes;
+        let report = IndexReport {
+            last_block: self.next_block_id - 1,
+            scanned_blocks: self.scanned_blocks,
+            tx_hashes: discrepancies(&current.tx_hash_to_block_map, &rebuilt.tx_hash_to_block_map),
+            address_txs: discrepancies(
+                &address_entries(&current.address_txs),
+                &address_entries(&rebuilt.address_txs),
+            ),
+            account_activity: discrepancies(&current.account_activity, &rebuilt.account_activity),
+            receipts: discrepancies(&current.receipts, &rebuilt.receipts),
+        };
+        (report, rebuilt)
+    }
+}
+
+fn discrepancies<K: Eq + Hash, V: PartialEq>(
+    current: &HashMap<K, V>,
+    rebuilt: &HashMap<K, V>,
+) -> IndexDiscrepancies {
+    let mut discrepancies = IndexDiscrepancies::default();
+    for (key, value) in rebuilt {
+        match current.get(key) {
+            None => discrepancies.missing += 1,
+            Some(current_value) if current_value != value => discrepancies.mismatched += 1,
+            Some(_) => {}
+        }
+    }
+    discrepancies.extra = current
+        .keys()
+        .filter(|key| !rebuilt.contains_key(key))
+        .count() as u64;
+    discrepancies
+}
+
+fn address_entries(index: &AddressTxIndex) -> HashMap<(AccountId, TxPosition), &AddressTxEntry> {
+    index
+        .iter()
+        .map(|(account_id, entry)| ((*account_id, entry.position), entry))
+        .collect()
+}
+
+/// Number of index entries of transactions of stored `block`, which are missing or don't match
+/// the block. Every such entry is logged.
+///
+/// Only entries derivable from the block alone are checked, without replaying the chain, so
+/// receipts are only checked to be present and account activity to cover the block.
+pub(crate) fn check_block_entries(indexes: &TransactionIndexes, block: &Block) -> u64 {
+    let block_id = block.header.block_id;
+    let mut mismatched = 0;
+    let mut report = |what: &str, hash: &[u8; 32]| {
+        warn!(
+            "Index of {what} doesn't match transaction {} in block {block_id}",
+            hash.fmt_short()
+        );
+        mismatched += 1;
+    };
+
+    for (index, tx) in block.body.transactions.iter().enumerate() {
+        let hash = tx.hash();
+        if indexes.tx_hash_to_block_map.get(&hash) != Some(&block_id) {
+            report("transaction hashes", &hash);
+        }
+        if !indexes.receipts.contains_key(&hash) {
+            report("receipts", &hash);
+        }
+
+        let position = TxPosition {
+            block_id,
+            index: index as u32,
+        };
+        let mut expected = AddressTxIndex::default();
+        expected.record(tx, position);
+        for (account_id, entry) in expected.iter() {
+            if indexes.address_txs.get(account_id, position) != Some(entry) {
+                report("address transactions", &hash);
+            }
+            let covers_block = indexes
+                .account_activity
+                .get(account_id)
+                .is_some_and(|activity| {
+                    (activity.first_seen_block..=activity.last_seen_block).contains(&block_id)
+                });
+            if !covers_block {
+                report("account activity", &hash);
+            }
+        }
+    }
+
+    mismatched
+}
@@ -0,0 +1,284 @@
+use anyhow::Result;
+use common::transaction::NSSATransaction;
+use log::error;
+use nssa::Amount;
+
+/// Tracks total supply of the native token and checks that it is conserved by state transitions.
+///
+/// There is no minting, and priority fees are moved to the fee recipient, so total supply is
+/// constant. Public supply may only change by shielding balance into private accounts and
+/// deshielding it back, which is tracked as shielded supply. Burned balance stays in the burn
+/// account, so it's a part of public supply, which can only grow.
+#[derive(Debug, Clone)]
+pub struct StateInvariants {
+    total_supply: Amount,
+    shielded_supply: Amount,
+    /// Burned balance at the last check of the state
+    total_burned: Amount,
+    checks_enabled: bool,
+}
+
+impl StateInvariants {
+    /// `initial_shielded_supply` is the sum of balances of initial private accounts
+    pub fn new(
+        state: &nssa::V02State,
+        initial_shielded_supply: Amount,
+        checks_enabled: bool,
+    ) -> Self {
+        Self {
+            total_supply: state
+                .total_public_balance()
+                .checked_add(initial_shielded_supply)
+                .expect("Total supply should fit into u128"),
+            shielded_supply: initial_shielded_supply,
+            total_burned: state.total_burned(),
+            checks_enabled,
+        }
+    }
+
+    /// Total supply of the native token, including shielded one
+    pub fn total_supply(&self) -> Amount {
+        self.total_supply
+    }
+
+    pub fn shielded_supply(&self) -> Amount {
+        self.shielded_supply
+    }
+
+    /// Balance moved to the burn account up to the last checked state, see
+    /// [`nssa::V02State::total_burned`]
+    pub fn total_burned(&self) -> Amount {
+        self.total_burned
+    }
+
+    /// Record applied transaction, given sums of balances of public accounts it touches before
+    /// and after application.
+    pub fn record_transaction(
+        &mut self,
+        tx: &NSSATransaction,
+        pre_balance: Amount,
+        post_balance: Amount,
+    ) -> Result<()> {
+        match tx {
+            NSSATransaction::Public(_) | NSSATransaction::ProgramDeployment(_) => {
+                if self.checks_enabled && pre_balance != post_balance {
+                    return violation(format!(
+                        "Public transaction changed balance of touched accounts from {pre_balance} to {post_balance}"
+                    ));
+                }
+            }
+            NSSATransaction::PrivacyPreserving(_) => {
+                let shielded_supply = self
+                    .shielded_supply
+                    .saturating_add(pre_balance)
+                    .checked_sub(post_balance);
+                match shielded_supply {
+                    Some(shielded_supply) => self.shielded_supply = shielded_supply,
+                    None if self.checks_enabled => {
+                        return violation(format!(
+                            "Privacy preserving transaction deshielded more than shielded supply of {}",
+                            self.shielded_supply
+                        ));
+                    }
+                    None => self.shielded_supply = Amount::ZERO,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that public supply of `state` matches total supply minus shielded supply and
+    /// includes storage deposits, that its state root matches the one computed from scratch, and
+    /// that burned balance didn't decrease since the previous check.
+    ///
+    /// This iterates over all public accounts, so it's a noop unless checks are enabled.
+    pub fn check_state(&mut self, state: &nssa::V02State) -> Result<()> {
+        let prev_total_burned = std::mem::replace(&mut self.total_burned, state.total_burned());
+        if !self.checks_enabled {
+            return Ok(());
+        }
+
+        if self.total_burned < prev_total_burned {
+            return violation(format!(
+                "Burned balance decreased from {prev_total_burned} to {}",
+                self.total_burned
+            ));
+        }
+
+        let public_supply = state.total_public_balance();
+        let expected_public_supply = self.total_supply.saturating_sub(self.shielded_supply);
+        if public_supply != expected_public_supply {
+            return violation(format!(
+                "Public supply is {public_supply}, but expected {expected_public_supply}"
+            ));
+        }
+
+        // Deposits stay a part of the balance of their accounts, so they are never minted
+        let storage_deposit = state.total_storage_deposit();
+        if storage_deposit > public_supply {
+            return violation(format!(
+                "Storage deposits of {storage_deposit} exceed public supply {public_supply}"
+            ));
+        }
+
+        if !state.verify_full_recompute() {
+            return violation("Public account tree doesn't match public accounts".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// State is corrupted, so block production can't go on
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("State invariant violated: {0}")]
+pub struct StateInvariantViolation(pub String);
+
+fn violation(message: String) -> Result<()> {
+    error!("STATE INVARIANT VIOLATED: {message}");
+    Err(StateInvariantViolation(message).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use common::test_utils::{
+        create_transaction_native_token_transfer, produce_dummy_empty_transaction,
+    };
+    use nssa::{AccountId, BURN_ACCOUNT_ID};
+
+    use super::*;
+
+    fn public_tx() -> NSSATransaction {
+        NSSATransaction::try_from(&produce_dummy_empty_transaction()).unwrap()
+    }
+
+    fn state_with_balances(balances: &[u128]) -> nssa::V02State {
+        let initial_data = balances
+            .iter()
+            .enumerate()
+            .map(|(i, balance)| (AccountId::new([i as u8; 32]), Amount(*balance)))
+            .collect::<Vec<_>>();
+        nssa::V02State::new_with_genesis_accounts(&initial_data, &[])
+    }
+
+    #[test]
+    fn test_conserving_transfer_passes() {
+        let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&nssa::PublicKey::new_from_private_key(&key));
+        let to = AccountId::new([2; 32]);
+        let mut state = nssa::V02State::new_with_genesis_accounts(
+            &[(from, Amount(100)), (to, Amount(200))],
+            &[],
+        );
+        let mut invariants = StateInvariants::new(&state, Amount(50), true);
+        assert_eq!(invariants.total_supply(), Amount(350));
+
+        let tx = NSSATransaction::try_from(&create_transaction_native_token_transfer(
+            *from.value(),
+            0,
+            *to.value(),
+            30,
+            key,
+        ))
+        .unwrap();
+        let NSSATransaction::Public(public_tx) = &tx else {
+            unreachable!()
+        };
+        state.transition_from_public_transaction(public_tx).unwrap();
+
+        let post_balance = state
+            .get_account_by_id(&from)
+            .balance
+            .saturating_add(state.get_account_by_id(&to).balance);
+        invariants
+            .record_transaction(&tx, Amount(300), post_balance)
+            .unwrap();
+        invariants.check_state(&state).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_post_state_trips_checker() {
+        let state = state_with_balances(&[100, 200]);
+        let mut invariants = StateInvariants::new(&state, Amount::ZERO, true);
+
+        // Balance appeared out of nowhere
+        let corrupted_state = state_with_balances(&[100, 201]);
+
+        assert!(invariants.check_state(&corrupted_state).is_err());
+    }
+
+    fn burn(state: &mut nssa::V02State, key: nssa::PrivateKey, amount: u128) -> NSSATransaction {
+        let from = AccountId::from(&nssa::PublicKey::new_from_private_key(&key));
+        let nonce = state.get_account_by_id(&from).nonce;
+        let tx = NSSATransaction::try_from(&create_transaction_native_token_transfer(
+            *from.value(),
+            nonce,
+            *BURN_ACCOUNT_ID.value(),
+            amount,
+            key,
+        ))
+        .unwrap();
+        let NSSATransaction::Public(public_tx) = &tx else {
+            unreachable!()
+        };
+        state.transition_from_public_transaction(public_tx).unwrap();
+        tx
+    }
+
+    #[test]
+    fn test_burned_balance_stays_in_total_supply() {
+        let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&nssa::PublicKey::new_from_private_key(&key));
+        let mut state = nssa::V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]);
+        let mut invariants = StateInvariants::new(&state, Amount::ZERO, true);
+
+        let tx = burn(&mut state, key, 40);
+        invariants
+            .record_transaction(&tx, Amount(100), Amount(100))
+            .unwrap();
+        invariants.check_state(&state).unwrap();
+
+        assert_eq!(invariants.total_supply(), Amount(100));
+        assert_eq!(invariants.total_burned(), Amount(40));
+    }
+
+    #[test]
+    fn test_decrease_of_burned_balance_trips_checker() {
+        let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let from = AccountId::from(&nssa::PublicKey::new_from_private_key(&key));
+        let genesis_state = nssa::V02State::new_with_genesis_accounts(&[(from, Amount(100))], &[]);
+        let mut state = genesis_state.clone();
+        let mut invariants = StateInvariants::new(&state, Amount::ZERO, true);
+        burn(&mut state, key, 40);
+        invariants.check_state(&state).unwrap();
+
+        // Burned balance went back to the sender, which conserves public supply
+        assert!(invariants.check_state(&genesis_state).is_err());
+    }
+
+    #[test]
+    fn test_inflating_public_transaction_trips_checker() {
+        let state = state_with_balances(&[100]);
+        let mut invariants = StateInvariants::new(&state, Amount::ZERO, true);
+
+        assert!(
+            invariants
+                .record_transaction(&public_tx(), Amount(100), Amount(101))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_disabled_checks_do_not_trip() {
+        let state = state_with_balances(&[100, 200]);
+        let mut invariants = StateInvariants::new(&state, Amount::ZERO, false);
+
+        invariants
+            .record_transaction(&public_tx(), Amount(100), Amount(101))
+            .unwrap();
+        invariants
+            .check_state(&state_with_balances(&[100, 201]))
+            .unwrap();
+    }
+}
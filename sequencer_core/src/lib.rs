@@ -1,35 +1,172 @@
-use std::{fmt::Display, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 #[cfg(feature = "testnet")]
 use common::PINATA_BASE58;
 use common::{
     HashType,
-    block::HashableBlockData,
+    block::{Block, HashableBlockData, ParamsVersion, logical_timestamp},
+    receipt::TransactionReceipt,
+    rpc_types::{
+        BalanceReservation, BalanceShortfall, IndexReport, PendingTransactionSummary,
+        PendingTransfer, PriorityFeeDistribution, SyncStatus, TransactionStatus, TxAuditEvent,
+        TxAuditRecord,
+    },
+    signed_header::SignedBlockHeader,
+    soft_confirmation::SoftConfirmation,
     transaction::{EncodedTransaction, NSSATransaction},
 };
-use config::SequencerConfig;
+use config::{ChainParams, SequencerConfig};
 use log::warn;
 use mempool::{MemPool, MemPoolHandle};
+use nssa::{Amount, gas::PaidFees, instruction_decoder::DecoderRegistry};
+use rand::Rng as _;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::block_store::SequencerBlockStore;
+use crate::{
+    block_candidate::BlockCandidate,
+    block_production::{BlockProductionMonitor, FailureKind},
+    block_store::SequencerBlockStore,
+    dropped_transactions::DroppedTransactions,
+    event_log::{EventBody, EventLog},
+    health::{BLOCK_PRODUCTION_CHECK, HealthCheck, HealthReport, STORAGE_CHECK, SYNC_CHECK},
+    index_repair::{INDEX_REBUILD_BATCH_SIZE, IndexRebuild, check_block_entries},
+    invariants::StateInvariants,
+    mempool_wal::{MEMPOOL_WAL_FILE_NAME, MempoolWal},
+    ordering::{PendingTransaction, order_pending_transactions},
+    params::ParamsSchedule,
+    proving::{BlockProofRecord, ProvingJob, ProvingWorkers},
+    stored_params::{ForcedParamsChange, StoredParams, params_fingerprint},
+    sync_progress::SyncProgress,
+    tx_audit::TxAuditLog,
+};
 
+pub mod block_candidate;
+pub mod block_production;
 pub mod block_store;
+pub mod chain_archive;
 pub mod config;
-
+pub mod dropped_transactions;
+pub mod event_log;
+pub mod health;
+pub mod index_repair;
+pub mod invariants;
+pub mod mempool_wal;
+pub mod ordering;
+pub mod params;
+pub mod proof_pipeline;
+pub mod proving;
+pub mod replay;
+pub mod stored_params;
+pub mod sync_progress;
+pub mod tx_audit;
+
+/// State of the sequencer and its chain
+///
+/// Every method changing the chain is synchronous, so callers sharing the core behind a mutex,
+/// like the block production loop and RPC handlers, apply their changes in the order they take
+/// the lock, and none of them observes a change half applied.
 pub struct SequencerCore {
     state: nssa::V02State,
+    state_invariants: StateInvariants,
     block_store: SequencerBlockStore,
     mempool: MemPool<EncodedTransaction>,
+    /// Set if mempool WAL is enabled
+    mempool_wal: Option<MempoolWal>,
+    /// Transactions taken from mempool, which didn't fit into previous blocks, in order of
+    /// inclusion
+    pending_transactions: Vec<PendingTransaction>,
+    /// Time-locked transactions taken from mempool, which aren't valid in the next block yet.
+    /// They are not ordered with pending ones until their valid-from block is reached.
+    parked_transactions: Vec<PendingTransaction>,
+    /// Transactions submitted through the admin RPC, in order of submission. Included before
+    /// pending ones, up to the block budget of the system lane.
+    system_transactions: Vec<PendingTransaction>,
+    dropped_transactions: DroppedTransactions,
+    /// Mempool lifecycle events of the latest transactions
+    tx_audit: TxAuditLog,
     sequencer_config: SequencerConfig,
+    params_schedule: ParamsSchedule,
+    /// Changes of parameters forced over stored blocks, see [`StoredParams`]
+    forced_params_changes: Vec<ForcedParamsChange>,
     chain_height: u64,
+    /// Time of the last block production, or of the start if there was none
+    last_block_produced_at: Instant,
+    block_production: BlockProductionMonitor,
+    /// Set if lazy proving is enabled
+    proving_workers: Option<ProvingWorkers>,
+    sync_progress: SyncProgress,
+    /// Set if event sink is configured, shared with the delivery task
+    event_log: Option<Arc<Mutex<EventLog>>>,
+    /// Decoders of instructions in expanded blocks and events
+    instruction_decoders: Arc<DecoderRegistry>,
+    /// Set in deterministic dev mode, auxiliary randomness of block signatures is drawn from it
+    dev_rng: Option<nssa::entropy::DevRng>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TransactionMalformationError {
     InvalidSignature,
-    FailedToDecode { tx: HashType },
+    InvalidStructure {
+        reason: String,
+    },
+    FailedToDecode {
+        tx: HashType,
+    },
+    ProgramNotAllowed {
+        reason: String,
+    },
+    InvalidInstruction {
+        reason: String,
+    },
+    InvalidProgram {
+        reason: String,
+    },
+    /// Transaction is time-locked beyond the horizon of `max_time_lock_blocks`
+    TimeLockTooLong {
+        valid_from_block: u64,
+        max_valid_from_block: u64,
+    },
+    /// Native transfer breaks rules of [`nssa::public_transaction::NativeTransfer::check`]
+    InvalidTransfer {
+        reason: String,
+    },
+}
+
+impl TransactionMalformationError {
+    /// [`Self::InvalidTransfer`] if `err` is a broken rule of native transfers, the result of
+    /// `other` otherwise
+    fn from_nssa_error(err: nssa::error::NssaError, other: impl FnOnce(String) -> Self) -> Self {
+        match err {
+            nssa::error::NssaError::SelfTransfer { .. }
+            | nssa::error::NssaError::AccountCreationBelowMinimum { .. } => Self::InvalidTransfer {
+                reason: err.to_string(),
+            },
+            err => other(err.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SystemLaneError {
+    /// System lane isn't configured
+    Disabled,
+    /// Maximum number of system transactions is waiting for inclusion
+    Full { queue_size: usize },
+}
+
+/// Program of a deployment transaction, which passed [`SequencerCore::check_deployment`]
+#[derive(Debug, Clone)]
+pub struct CheckedDeployment {
+    pub program: nssa::program::Program,
+    /// Whether the program is deployed already, so the deployment would fail on inclusion
+    pub already_deployed: bool,
 }
 
 impl Display for TransactionMalformationError {
@@ -43,15 +180,31 @@ impl std::error::Error for TransactionMalformationError {}
 impl SequencerCore {
     /// Start Sequencer from configuration and construct transaction sender
     pub fn start_from_config(config: SequencerConfig) -> (Self, MemPoolHandle<EncodedTransaction>) {
-        let hashable_data = HashableBlockData {
-            block_id: config.genesis_id,
-            transactions: vec![],
-            prev_block_hash: [0; 32],
-            timestamp: 0,
-        };
+        Self::start_with_decoders(config, DecoderRegistry::default())
+    }
 
+    /// Start Sequencer, which decodes instructions of programs known to `instruction_decoders`,
+    /// e.g. ones of programs deployed by the embedding application
+    pub fn start_with_decoders(
+        config: SequencerConfig,
+        instruction_decoders: DecoderRegistry,
+    ) -> (Self, MemPoolHandle<EncodedTransaction>) {
+        config.check_deterministic_dev_mode().unwrap();
+        config.check_prune().unwrap();
+        let params_schedule = ParamsSchedule::from_config(&config).unwrap();
+        let hashable_data = genesis_block_data(&config);
+        let genesis_hash = hashable_data.hash();
+
+        let mut dev_rng = config
+            .deterministic_dev_mode
+            .map(|dev_mode| nssa::entropy::dev_rng(dev_mode.seed));
         let signing_key = nssa::PrivateKey::try_new(config.signing_key).unwrap();
-        let genesis_block = hashable_data.into_block(&signing_key);
+        let genesis_block = match &mut dev_rng {
+            Some(dev_rng) => {
+                hashable_data.into_block_with_aux_random(&signing_key, dev_rng.r#gen())
+            }
+            None => hashable_data.into_block(&signing_key),
+        };
 
         // Sequencer should panic if unable to open db,
         // as fixing this issue may require actions non-native to program scope
@@ -60,127 +213,918 @@ impl SequencerCore {
             Some(genesis_block),
             signing_key,
         )
+        .and_then(|block_store| block_store.with_pruning(config.prune))
         .unwrap();
-        let mut initial_commitments = vec![];
-
-        for init_comm_data in config.initial_commitments.clone() {
-            let npk = init_comm_data.npk;
-
-            let mut acc = init_comm_data.account;
-
-            acc.program_owner = nssa::program::Program::authenticated_transfer_program().id();
-
-            let comm = nssa_core::Commitment::new(&npk, &acc);
+        // Existing storage may belong to a chain with other parameters
+        let stored_genesis_hash = block_store
+            .get_block_header_at_id(block_store.genesis_id())
+            .unwrap()
+            .hash;
+        assert_eq!(
+            stored_genesis_hash, genesis_hash,
+            "Stored genesis block doesn't match the config, program allow-list may differ"
+        );
+        // Existing storage may be built with other parameters, which must not change silently
+        let last_stored_block_id = block_store.last_block_id().unwrap();
+        let stored_params = block_store.stored_params().unwrap();
+        let stored_params = match StoredParams::reconcile(
+            stored_params.as_ref(),
+            params_fingerprint(&config, genesis_hash),
+            last_stored_block_id,
+            config.force_params_change,
+        ) {
+            Ok(Some(new_params)) => {
+                if stored_params.is_some() {
+                    warn!(
+                        "Chain parameters are changed by force after block {last_stored_block_id}"
+                    );
+                }
+                block_store.put_stored_params(&new_params).unwrap();
+                new_params
+            }
+            Ok(None) => stored_params.expect("Unchanged record is stored"),
+            Err(err) => panic!("{err}"),
+        };
+        let (state, initial_shielded_supply) = genesis_state(&config);
 
-            initial_commitments.push(comm);
-        }
+        let state_invariants = StateInvariants::new(
+            &state,
+            initial_shielded_supply,
+            config.check_state_invariants,
+        );
 
-        let init_accs: Vec<(nssa::AccountId, u128)> = config
-            .initial_accounts
-            .iter()
-            .map(|acc_data| (acc_data.account_id.parse().unwrap(), acc_data.balance))
-            .collect();
+        let sync_progress = SyncProgress::new(
+            config.genesis_id,
+            block_store.last_block_id().unwrap(),
+            Instant::now(),
+        );
 
-        let mut state = nssa::V02State::new_with_genesis_accounts(&init_accs, &initial_commitments);
+        let (mempool_wal, logged_transactions) = if config.mempool_wal {
+            let (mempool_wal, logged_transactions) =
+                MempoolWal::open(&config.home.join(MEMPOOL_WAL_FILE_NAME)).unwrap();
+            (Some(mempool_wal), logged_transactions)
+        } else {
+            (None, vec![])
+        };
 
-        #[cfg(feature = "testnet")]
-        state.add_pinata_program(PINATA_BASE58.parse().unwrap());
+        let event_log = config
+            .event_sink
+            .is_some()
+            .then(|| Arc::new(Mutex::new(EventLog::open(&config.home).unwrap())));
+        let tx_audit = if config.tx_audit.log_to_file {
+            TxAuditLog::open(&config.home, config.tx_audit.capacity).unwrap()
+        } else {
+            TxAuditLog::new(config.tx_audit.capacity)
+        };
 
-        let (mempool, mempool_handle) = MemPool::new(config.mempool_max_size);
+        // Logged transactions arrived before the new ones
+        let (mempool, mempool_handle) = MemPool::new_with_first_arrival_seq(
+            config.mempool_max_size,
+            logged_transactions.len() as u64,
+        );
         let mut this = Self {
             state,
+            state_invariants,
             block_store,
             mempool,
+            mempool_wal,
+            pending_transactions: vec![],
+            parked_transactions: vec![],
+            system_transactions: vec![],
+            dropped_transactions: DroppedTransactions::default(),
+            tx_audit,
             chain_height: config.genesis_id,
+            last_block_produced_at: Instant::now(),
+            block_production: BlockProductionMonitor::default(),
+            proving_workers: config.lazy_proving.as_ref().map(ProvingWorkers::spawn),
+            sync_progress,
+            event_log,
+            instruction_decoders: Arc::new(instruction_decoders),
             sequencer_config: config,
+            params_schedule,
+            forced_params_changes: stored_params.forced_changes,
+            dev_rng,
         };
 
         this.sync_state_with_stored_blocks();
+        // Blocks, which were stored right before the crash, are logged now
+        if let Err(err) = this.log_block_events(&[]) {
+            warn!("Failed to log events of stored blocks: {err:#}");
+        }
+        this.restore_logged_transactions(logged_transactions);
 
         (this, mempool_handle)
     }
 
+    /// Makes transactions from mempool WAL, which were admitted before restart, pending again.
+    /// Admission checks are repeated and transactions included in stored blocks are skipped.
+    fn restore_logged_transactions(&mut self, txs: Vec<EncodedTransaction>) {
+        if txs.is_empty() {
+            return;
+        }
+
+        let num_logged = txs.len();
+        self.admit_transactions((0..num_logged as u64).collect(), txs);
+        log::info!(
+            "Restored {} of {num_logged} transactions from mempool WAL",
+            self.pending_transactions.len()
+        );
+    }
+
+    /// Logs `tx` to mempool WAL, if it's enabled, so it's restored after restart
+    ///
+    /// Must be called before pushing `tx` to mempool. Returns after `tx` reaches the disk.
+    pub fn log_admitted_transaction(&mut self, tx: &EncodedTransaction) -> Result<()> {
+        match &mut self.mempool_wal {
+            Some(mempool_wal) => mempool_wal.append(tx),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes transactions, which won't become pending anymore, from mempool WAL
+    ///
+    /// Failure is only logged: the worst outcome is that stale transactions are checked again
+    /// after restart.
+    fn prune_mempool_wal(&mut self, hashes: &HashSet<HashType>) {
+        if let Some(mempool_wal) = &mut self.mempool_wal
+            && let Err(err) = mempool_wal.prune(hashes)
+        {
+            warn!("Failed to prune mempool WAL: {err:#}");
+        }
+    }
+
+    /// Event log to deliver to the sink, `None` if event sink is not configured
+    pub fn event_log(&self) -> Option<Arc<Mutex<EventLog>>> {
+        self.event_log.clone()
+    }
+
+    pub fn instruction_decoders(&self) -> Arc<DecoderRegistry> {
+        self.instruction_decoders.clone()
+    }
+
+    /// Logs events of stored blocks up to the chain height, which are not logged yet.
+    /// Receipts of `dropped` transactions are logged with the block at the chain height.
+    ///
+    /// Blocks, which failed to be logged, are logged on the next call.
+    fn log_block_events(&self, dropped: &[(HashType, String)]) -> Result<()> {
+        let Some(event_log) = &self.event_log else {
+            return Ok(());
+        };
+        let mut event_log = event_log.lock().unwrap();
+
+        let first_block_id = event_log
+            .last_block_id()
+            .map_or(self.sequencer_config.genesis_id + 1, |block_id| {
+                block_id + 1
+            });
+        for block_id in first_block_id..=self.chain_height {
+            let block = self.block_store.get_block_at_id(block_id)?;
+            let dropped = if block_id == self.chain_height {
+                dropped
+            } else {
+                &[]
+            };
+            event_log.append(EventBody::for_block(
+                &block,
+                dropped,
+                &self.instruction_decoders,
+                |hash| self.block_store.get_receipt(hash).cloned(),
+            )?)?;
+        }
+        Ok(())
+    }
+
     /// If there are stored blocks ahead of the current height, this method will load and process
     /// all transaction in them in the order they are stored. The NSSA state will be updated
     /// accordingly.
     fn sync_state_with_stored_blocks(&mut self) {
         let mut next_block_id = self.sequencer_config.genesis_id + 1;
+        let first_unpruned_block_id = self.block_store.first_unpruned_block_id().unwrap();
+        assert!(
+            first_unpruned_block_id <= next_block_id,
+            "Stored blocks before {first_unpruned_block_id} are pruned, the state can't be replayed"
+        );
         while let Ok(block) = self.block_store.get_block_at_id(next_block_id) {
-            for encoded_transaction in block.body.transactions {
-                let transaction = NSSATransaction::try_from(&encoded_transaction).unwrap();
+            // Stored chain would diverge from the one built with the configured upgrades
+            if let Err(err) = self.params_schedule.check_block(&block) {
+                panic!("{err}");
+            }
+            self.params_schedule
+                .params_at(next_block_id)
+                .1
+                .apply_to(&mut self.state);
+            let pre_checked_transactions = pre_check_transactions(&block.body.transactions);
+            // Proving of blocks, which were pending before restart, starts over
+            if let Some(proving_workers) = &mut self.proving_workers
+                && self
+                    .block_store
+                    .get_block_proof_record(next_block_id)
+                    .unwrap()
+                    == Some(BlockProofRecord::Pending)
+            {
+                proving_workers.submit(ProvingJob {
+                    block_id: next_block_id,
+                    block_hash: block.header.hash,
+                    pre_state: self.state.clone(),
+                    transactions: pre_checked_transactions
+                        .iter()
+                        .cloned()
+                        .collect::<Result<_, _>>()
+                        .unwrap(),
+                });
+            }
+            self.state.set_block_id(next_block_id);
+            for (index, (encoded_transaction, transaction)) in block
+                .body
+                .transactions
+                .iter()
+                .zip(pre_checked_transactions)
+                .enumerate()
+            {
+                let transaction = transaction.unwrap();
                 // Process transaction and update state
-                self.execute_check_transaction_on_state(transaction)
+                let touched_account_ids = touched_account_ids(&self.state, &transaction);
+                let pre_balance = self.public_balance_of(&touched_account_ids);
+                let pre_balances = public_balances(&self.state, &touched_account_ids);
+                let (transaction, fees) = self
+                    .execute_prechecked_transaction_on_state(transaction)
+                    .unwrap();
+                let post_balance = self.public_balance_of(&touched_account_ids);
+                self.state_invariants
+                    .record_transaction(&transaction, pre_balance, post_balance)
                     .unwrap();
                 // Update the tx hash to block id map.
-                self.block_store.insert(&encoded_transaction, next_block_id);
+                self.block_store
+                    .insert(encoded_transaction, next_block_id, index);
+                self.block_store.insert_receipt(
+                    encoded_transaction.hash(),
+                    transaction_receipt(&self.state, pre_balances, &fees),
+                );
             }
+            self.state_invariants.check_state(&self.state).unwrap();
             self.chain_height = next_block_id;
+            self.sync_progress.record(next_block_id, Instant::now());
             next_block_id += 1;
         }
+
+        // Replayed state must match the one stored with the tip
+        if let Some(stored_digest) = self.block_store.tip_state_digest().unwrap() {
+            let replayed_digest = self.state.commitment_set_digest();
+            assert_eq!(
+                replayed_digest, stored_digest,
+                "Commitment set digest after replaying blocks up to {} doesn't match the stored one",
+                self.chain_height
+            );
+        }
     }
 
+    #[cfg(test)]
     fn execute_check_transaction_on_state(
         &mut self,
         tx: NSSATransaction,
     ) -> Result<NSSATransaction, nssa::error::NssaError> {
-        match &tx {
-            NSSATransaction::Public(tx) => self.state.transition_from_public_transaction(tx),
-            NSSATransaction::PrivacyPreserving(tx) => self
-                .state
-                .transition_from_privacy_preserving_transaction(tx),
-            NSSATransaction::ProgramDeployment(tx) => self
-                .state
-                .transition_from_program_deployment_transaction(tx),
-        }
-        .inspect_err(|err| warn!("Error at transition {err:#?}"))?;
+        execute_transaction_on_state(&mut self.state, &tx)
+            .inspect_err(|err| warn!("Error at transition {err}"))?;
 
         Ok(tx)
     }
 
+    /// Same as [`Self::execute_check_transaction_on_state`], but expects `tx` to pass
+    /// [`pre_check_transactions`], so stateless checks of public transactions are not repeated.
+    fn execute_prechecked_transaction_on_state(
+        &mut self,
+        tx: NSSATransaction,
+    ) -> Result<(NSSATransaction, PaidFees), nssa::error::NssaError> {
+        execute_prechecked_transaction(&mut self.state, tx)
+    }
+
+    /// Moves newly arrived transactions from mempool to pending ones, running stateless checks
+    /// on them in parallel. Malformed transactions are dropped, resubmitted ones, which are
+    /// already pending or included, are ignored.
+    ///
+    /// No more than `mempool_max_size` transactions are kept pending, the rest stay in mempool.
+    /// Pending transactions are kept sorted by the ordering policy. Parked time-locked
+    /// transactions are bounded per sender instead, so they don't take room of pending ones.
+    ///
+    /// Parked transactions, which become valid in the next block, are made pending first.
+    pub fn take_transactions_from_mempool(&mut self) {
+        self.promote_parked_transactions();
+
+        let capacity = self
+            .sequencer_config
+            .mempool_max_size
+            .saturating_sub(self.pending_transactions.len());
+        let (arrival_seqs, txs): (Vec<u64>, Vec<EncodedTransaction>) =
+            std::iter::from_fn(|| self.mempool.pop_entry())
+                .take(capacity)
+                .map(|entry| (entry.arrival_seq, entry.item))
+                .unzip();
+
+        self.admit_transactions(arrival_seqs, txs);
+    }
+
+    /// Makes `txs` pending, see [`Self::take_transactions_from_mempool`]
+    ///
+    /// Transactions, which value and fees exceed the balance of their sender not reserved by
+    /// transactions of the sender waiting for inclusion, are dropped, see
+    /// [`Self::check_balance_reservation`].
+    fn admit_transactions(&mut self, arrival_seqs: Vec<u64>, txs: Vec<EncodedTransaction>) {
+        if txs.is_empty() {
+            return;
+        }
+
+        // Transactions, which will never be pending, don't need to be restored
+        let mut unrestorable_hashes = HashSet::new();
+        let mut known_hashes = self
+            .pending_transactions
+            .iter()
+            .chain(&self.parked_transactions)
+            .chain(&self.system_transactions)
+            .map(|pending_tx| pending_tx.hash)
+            .collect::<HashSet<_>>();
+        let mut num_pending_by_sender: HashMap<nssa::AccountId, usize> = HashMap::new();
+        for pending_tx in self
+            .pending_transactions
+            .iter()
+            .chain(&self.parked_transactions)
+        {
+            if let Some((sender, _)) = pending_tx.sender_and_nonce {
+                *num_pending_by_sender.entry(sender).or_default() += 1;
+            }
+        }
+        let mut num_parked_by_sender: HashMap<nssa::AccountId, usize> = HashMap::new();
+        for parked_tx in &self.parked_transactions {
+            if let Some((sender, _)) = parked_tx.sender_and_nonce {
+                *num_parked_by_sender.entry(sender).or_default() += 1;
+            }
+        }
+        let mut reserved_by_sender = self.reserved_balances();
+        let mut admitted_hashes = HashSet::new();
+        for ((arrival_seq, encoded_tx), pre_checked_tx) in arrival_seqs
+            .into_iter()
+            .zip(&txs)
+            .zip(pre_check_transactions(&txs))
+        {
+            let hash = encoded_tx.hash();
+            if known_hashes.contains(&hash) {
+                continue;
+            }
+            if self
+                .block_store
+                .get_block_id_of_transaction(&hash)
+                .is_some()
+            {
+                unrestorable_hashes.insert(hash);
+                continue;
+            }
+
+            let pre_checked_tx =
+                pre_checked_tx.and_then(|tx| self.check_admission(&tx).map(|_| tx));
+            match pre_checked_tx {
+                Ok(tx) => {
+                    if let Err(reason) = self
+                        .check_unreserved_balance(&tx, &reserved_by_sender)
+                        .map_err(|shortfall| shortfall.to_string())
+                        .and_then(|()| self.count_parked_of_sender(&tx, &mut num_parked_by_sender))
+                        .and_then(|()| {
+                            self.count_pending_of_sender(&tx, &mut num_pending_by_sender)
+                        })
+                    {
+                        self.tx_audit.record(
+                            hash,
+                            TxAuditEvent::Evicted {
+                                reason: reason.clone(),
+                            },
+                        );
+                        self.dropped_transactions.record(hash, reason);
+                        unrestorable_hashes.insert(hash);
+                        continue;
+                    }
+
+                    if let Some((sender, _)) = tx.sender_and_nonce() {
+                        let reserved = reserved_by_sender.entry(sender).or_default();
+                        *reserved = reserved.saturating_add(tx.max_sender_outflow());
+                    }
+                    known_hashes.insert(hash);
+                    admitted_hashes.insert(hash);
+                    let pending_tx = PendingTransaction::new(arrival_seq, hash, tx);
+                    if self.is_valid_in_next_block(&pending_tx.tx) {
+                        self.pending_transactions.push(pending_tx);
+                    } else {
+                        self.parked_transactions.push(pending_tx);
+                    }
+                }
+                Err(err) => {
+                    warn!("Transaction failed pre-check {err:?}");
+                    let reason = format!("{err:?}");
+                    self.tx_audit.record(
+                        hash,
+                        TxAuditEvent::Evicted {
+                            reason: reason.clone(),
+                        },
+                    );
+                    self.dropped_transactions.record(hash, reason);
+                    unrestorable_hashes.insert(hash);
+                }
+            }
+        }
+        self.prune_mempool_wal(&unrestorable_hashes);
+
+        self.pending_transactions = order_pending_transactions(
+            std::mem::take(&mut self.pending_transactions),
+            self.sequencer_config.max_consecutive_per_sender,
+        );
+        self.publish_num_pending();
+
+        // Positions are known once pending transactions are ordered, parked ones follow them
+        if admitted_hashes.is_empty() {
+            return;
+        }
+        let admitted = self
+            .pending_transactions
+            .iter()
+            .chain(&self.parked_transactions)
+            .enumerate()
+            .filter(|(_, pending_tx)| admitted_hashes.contains(&pending_tx.hash))
+            .map(|(position, pending_tx)| {
+                let event = TxAuditEvent::Admitted {
+                    priority_fee: pending_tx.tx.priority_fee(),
+                    position: position as u64,
+                };
+                (pending_tx.hash, event)
+            })
+            .collect::<Vec<_>>();
+        for (hash, event) in admitted {
+            self.tx_audit.record(hash, event);
+        }
+    }
+
+    /// Fails if value and fees of `tx` exceed the balance of its sender, which isn't reserved by
+    /// transactions of the sender waiting for inclusion, so transactions doomed to fail for
+    /// balance don't take the place of others. See [`NSSATransaction::max_sender_outflow`].
+    ///
+    /// Reservations are released once transactions are included or dropped. Only transactions
+    /// taken from mempool reserve balance, so callers take newly arrived ones first, see
+    /// [`Self::take_transactions_from_mempool`].
+    pub fn check_balance_reservation(&self, tx: &NSSATransaction) -> Result<(), BalanceShortfall> {
+        self.check_unreserved_balance(tx, &self.reserved_balances())
+    }
+
+    /// Balance reserved by transactions of each sender waiting for inclusion
+    fn reserved_balances(&self) -> HashMap<nssa::AccountId, Amount> {
+        let mut reserved_by_sender: HashMap<nssa::AccountId, Amount> = HashMap::new();
+        for pending_tx in self.waiting_transactions() {
+            if let Some((sender, _)) = pending_tx.sender_and_nonce {
+                let reserved = reserved_by_sender.entry(sender).or_default();
+                *reserved = reserved.saturating_add(pending_tx.tx.max_sender_outflow());
+            }
+        }
+        reserved_by_sender
+    }
+
+    /// See [`Self::check_balance_reservation`]
+    fn check_unreserved_balance(
+        &self,
+        tx: &NSSATransaction,
+        reserved_by_sender: &HashMap<nssa::AccountId, Amount>,
+    ) -> Result<(), BalanceShortfall> {
+        let Some((sender, _)) = tx.sender_and_nonce() else {
+            return Ok(());
+        };
+        let balance = self.state.get_account_by_id(&sender).balance;
+        let reserved = reserved_by_sender.get(&sender).copied().unwrap_or_default();
+        let required = tx.max_sender_outflow();
+        if required <= balance.saturating_sub(reserved) {
+            return Ok(());
+        }
+
+        Err(BalanceShortfall {
+            sender: sender.to_string(),
+            balance,
+            reserved,
+            required,
+            pending: self
+                .waiting_transactions()
+                .filter(|pending_tx| {
+                    pending_tx
+                        .sender_and_nonce
+                        .is_some_and(|(pending_sender, _)| pending_sender == sender)
+                })
+                .map(|pending_tx| BalanceReservation {
+                    hash: hex::encode(pending_tx.hash),
+                    amount: pending_tx.tx.max_sender_outflow(),
+                })
+                .filter(|reservation| reservation.amount > Amount::ZERO)
+                .collect(),
+        })
+    }
+
+    /// Transactions waiting for inclusion: system, pending and parked ones
+    fn waiting_transactions(&self) -> impl Iterator<Item = &PendingTransaction> {
+        self.system_transactions
+            .iter()
+            .chain(&self.pending_transactions)
+            .chain(&self.parked_transactions)
+    }
+
+    /// Queues `tx`, submitted through the admin RPC, into the system lane, so it's included
+    /// before pending transactions. Returns `false` if it's already queued, pending or included.
+    ///
+    /// `tx` must have passed [`pre_check_transaction`] and [`Self::check_admission`]. System
+    /// transactions aren't logged to the mempool WAL, so they are lost on restart.
+    pub fn submit_system_transaction(
+        &mut self,
+        hash: HashType,
+        tx: NSSATransaction,
+    ) -> Result<bool, SystemLaneError> {
+        let Some(queue_size) = self
+            .sequencer_config
+            .system_lane
+            .as_ref()
+            .map(|system_lane| system_lane.queue_size)
+        else {
+            return Err(SystemLaneError::Disabled);
+        };
+        if !matches!(self.transaction_status(&hash), TransactionStatus::Unknown) {
+            return Ok(false);
+        }
+        if self.system_transactions.len() >= queue_size {
+            return Err(SystemLaneError::Full { queue_size });
+        }
+
+        self.tx_audit.record(
+            hash,
+            TxAuditEvent::Admitted {
+                priority_fee: tx.priority_fee(),
+                position: self.system_transactions.len() as u64,
+            },
+        );
+        // System transactions are taken in order of submission, arrival into mempool doesn't
+        // apply to them
+        self.system_transactions
+            .push(PendingTransaction::new(0, hash, tx));
+        self.publish_num_pending();
+        Ok(true)
+    }
+
+    /// Counts `tx` in `num_pending_by_sender`, unless its sender already has the maximum number
+    /// of pending transactions. Returns the reason to drop `tx` otherwise.
+    fn count_pending_of_sender(
+        &self,
+        tx: &NSSATransaction,
+        num_pending_by_sender: &mut HashMap<nssa::AccountId, usize>,
+    ) -> Result<(), String> {
+        let Some((sender, _)) = tx.sender_and_nonce() else {
+            return Ok(());
+        };
+        let num_pending = num_pending_by_sender.entry(sender).or_default();
+        if let Some(max_pending) = self.sequencer_config.max_pending_per_sender
+            && *num_pending >= max_pending
+        {
+            return Err(format!(
+                "Sender already has {max_pending} transactions waiting for inclusion"
+            ));
+        }
+
+        *num_pending += 1;
+        Ok(())
+    }
+
+    /// Counts `tx` in `num_parked_by_sender`, if it would be parked, unless its sender already
+    /// has the maximum number of parked transactions. Returns the reason to drop `tx` otherwise.
+    fn count_parked_of_sender(
+        &self,
+        tx: &NSSATransaction,
+        num_parked_by_sender: &mut HashMap<nssa::AccountId, usize>,
+    ) -> Result<(), String> {
+        if self.is_valid_in_next_block(tx) {
+            return Ok(());
+        }
+        let Some((sender, _)) = tx.sender_and_nonce() else {
+            return Ok(());
+        };
+        let num_parked = num_parked_by_sender.entry(sender).or_default();
+        let max_parked = self.sequencer_config.max_parked_per_sender;
+        if *num_parked >= max_parked {
+            return Err(format!(
+                "Sender already has {max_parked} time-locked transactions parked"
+            ));
+        }
+
+        *num_parked += 1;
+        Ok(())
+    }
+
+    /// Time lock of `tx`, if any, allows including it into the next block
+    fn is_valid_in_next_block(&self, tx: &NSSATransaction) -> bool {
+        tx.valid_from_block()
+            .is_none_or(|valid_from_block| valid_from_block <= self.chain_height + 1)
+    }
+
+    /// Makes parked transactions, which are valid in the next block, pending
+    fn promote_parked_transactions(&mut self) {
+        let (promoted, parked): (Vec<_>, Vec<_>) = std::mem::take(&mut self.parked_transactions)
+            .into_iter()
+            .partition(|parked_tx| self.is_valid_in_next_block(&parked_tx.tx));
+        self.parked_transactions = parked;
+        if promoted.is_empty() {
+            return;
+        }
+
+        self.pending_transactions.extend(promoted);
+        self.pending_transactions = order_pending_transactions(
+            std::mem::take(&mut self.pending_transactions),
+            self.sequencer_config.max_consecutive_per_sender,
+        );
+    }
+
+    /// Whether `tx` carries a proof of [`nssa::ProverBackend::DevFake`]
+    fn is_dev_proved(tx: &NSSATransaction) -> bool {
+        match tx {
+            NSSATransaction::PrivacyPreserving(tx) => tx.witness_set().proof().is_dev(),
+            NSSATransaction::Public(_) | NSSATransaction::ProgramDeployment(_) => false,
+        }
+    }
+
+    /// Sum of public balances of given accounts, each account counted once
+    fn public_balance_of(&self, account_ids: &[nssa::AccountId]) -> Amount {
+        public_balance_of(&self.state, account_ids)
+    }
+
     /// Produces new block from transactions in mempool
     pub fn produce_new_block_with_mempool_transactions(&mut self) -> Result<u64> {
-        let now = Instant::now();
-        let new_block_height = self.chain_height + 1;
+        self.collect_block_proofs()?;
+        self.take_transactions_from_mempool();
+        let candidate = self.build_block_candidate()?;
+        self.commit_block(candidate)
+    }
+
+    /// Produces new block on a tick of the block production loop
+    ///
+    /// Transient failures are logged and counted, so the loop retries on the next tick. Fatal
+    /// ones are returned, block production is stopped then and the health check fails.
+    pub fn produce_block_on_tick(&mut self) -> Result<Option<u64>> {
+        anyhow::ensure!(
+            !self.block_production.is_stopped(),
+            "Block production is stopped"
+        );
+        let produced = self.produce_new_block_with_mempool_transactions();
+        self.supervise_block_production(produced)
+    }
+
+    fn supervise_block_production(&mut self, produced: Result<u64>) -> Result<Option<u64>> {
+        match produced {
+            Ok(block_id) => {
+                self.block_production.record_success(block_id);
+                Ok(Some(block_id))
+            }
+            Err(err) => match self.block_production.record_failure(&err) {
+                FailureKind::Transient => Ok(None),
+                FailureKind::Fatal => Err(err),
+            },
+        }
+    }
+
+    pub fn block_production(&self) -> &BlockProductionMonitor {
+        &self.block_production
+    }
+
+    /// Publishes the number of transactions waiting for inclusion to the watchdog, see
+    /// [`block_production::StallWatch`]
+    fn publish_num_pending(&self) {
+        self.block_production
+            .stall_watch()
+            .set_num_pending(self.pending_transactions.len() + self.system_transactions.len());
+    }
+
+    /// Selects pending transactions for the next block in order defined by the ordering policy
+    /// and validates them against a copy of the current state
+    ///
+    /// System transactions go first, up to the block budget of the system lane, followed by
+    /// pending ones in order of the ordering policy. Time-locked system transactions wait in
+    /// the system lane until they are valid.
+    ///
+    /// Nothing is changed, so the candidate may be inspected and thrown away.
+    pub fn build_block_candidate(&self) -> Result<BlockCandidate> {
+        let block_id = self.chain_height + 1;
+        let mut state = self.state.clone();
+        let mut state_invariants = self.state_invariants.clone();
+
+        let (params_version, params) = self.params_schedule.params_at(block_id);
+        // Parameters go first, as in replay, so anchors are kept for the window of the block
+        params.apply_to(&mut state);
+        state.set_block_id(block_id);
+        let mut transactions = vec![];
+        let mut receipts = vec![];
+        let mut dropped = vec![];
+        let mut expired = HashSet::new();
+        let mut dev_proved = self
+            .sequencer_config
+            .lazy_proving
+            .as_ref()
+            .is_some_and(|config| config.prover_backend == nssa::ProverBackend::DevFake);
+
+        let system_budget = self
+            .sequencer_config
+            .system_lane
+            .as_ref()
+            .map_or(0, |system_lane| {
+                system_lane.block_budget(params.max_num_tx_in_block)
+            });
+        let system_txs = self
+            .system_transactions
+            .iter()
+            .filter(|system_tx| self.is_valid_in_next_block(&system_tx.tx))
+            .take(system_budget);
 
-        let mut valid_transactions = vec![];
+        for pending_tx in system_txs.chain(&self.pending_transactions) {
+            if transactions.len() >= params.max_num_tx_in_block {
+                break;
+            }
 
-        while let Some(tx) = self.mempool.pop() {
-            let nssa_transaction = NSSATransaction::try_from(&tx)
-                .map_err(|_| TransactionMalformationError::FailedToDecode { tx: tx.hash() })?;
+            let touched_account_ids = touched_account_ids(&state, &pending_tx.tx);
+            let pre_balance = public_balance_of(&state, &touched_account_ids);
+            let pre_balances = public_balances(&state, &touched_account_ids);
 
-            if let Ok(valid_tx) = self.execute_check_transaction_on_state(nssa_transaction) {
-                valid_transactions.push(valid_tx.into());
+            match execute_prechecked_transaction(&mut state, pending_tx.tx.clone()) {
+                Ok((valid_tx, fees)) => {
+                    let post_balance = public_balance_of(&state, &touched_account_ids);
+                    state_invariants.record_transaction(&valid_tx, pre_balance, post_balance)?;
 
-                if valid_transactions.len() >= self.sequencer_config.max_num_tx_in_block {
-                    break;
+                    dev_proved |= Self::is_dev_proved(&valid_tx);
+                    receipts.push(transaction_receipt(&state, pre_balances, &fees));
+                    transactions.push(valid_tx.into());
+                }
+                Err(err) => {
+                    if matches!(err, nssa::error::NssaError::AnchorExpired { .. }) {
+                        expired.insert(pending_tx.hash);
+                    }
+                    dropped.push((pending_tx.hash, err.to_string()));
                 }
-            } else {
-                // Probably need to handle unsuccessful transaction execution?
             }
         }
 
-        let prev_block_hash = self
-            .block_store
-            .get_block_at_id(self.chain_height)?
-            .header
-            .hash;
+        // Refuse to build a block, which breaks supply conservation
+        state_invariants.check_state(&state)?;
+
+        Ok(BlockCandidate {
+            block_id,
+            params_version,
+            dev_proved,
+            transactions,
+            receipts,
+            dropped,
+            expired,
+            state,
+            state_invariants,
+        })
+    }
+
+    /// Stores `candidate` as the next block and moves to its state
+    ///
+    /// Fails if another block was committed after the candidate was built.
+    pub fn commit_block(&mut self, candidate: BlockCandidate) -> Result<u64> {
+        let now = Instant::now();
+        let BlockCandidate {
+            block_id,
+            params_version,
+            dev_proved,
+            transactions,
+            receipts,
+            dropped,
+            expired,
+            state,
+            state_invariants,
+        } = candidate;
+
+        anyhow::ensure!(
+            block_id == self.chain_height + 1,
+            "Block candidate {block_id} is stale, chain height is {}",
+            self.chain_height
+        );
 
-        let curr_time = chrono::Utc::now().timestamp_millis() as u64;
+        let prev_block_header = self.block_store.get_block_at_id(self.chain_height)?.header;
+        let prev_block_hash = prev_block_header.hash;
+
+        let curr_time = if self.dev_rng.is_some() {
+            logical_timestamp(block_id)
+        } else {
+            // Block timestamps never decrease, so the previous one is used if the clock went
+            // backwards
+            let wall_time = chrono::Utc::now().timestamp_millis() as u64;
+            if wall_time < prev_block_header.timestamp {
+                warn!(
+                    "Clock is {}ms behind timestamp of block {}, stamping block {block_id} with it",
+                    prev_block_header.timestamp - wall_time,
+                    self.chain_height
+                );
+            }
+            wall_time.max(prev_block_header.timestamp)
+        };
 
-        let num_txs_in_block = valid_transactions.len();
+        let num_txs_in_block = transactions.len();
+        // Transactions are valid, as they were applied to the candidate state
+        let proving_transactions = self
+            .proving_workers
+            .is_some()
+            .then(|| {
+                transactions
+                    .iter()
+                    .map(NSSATransaction::try_from)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        let included_hashes = transactions
+            .iter()
+            .map(EncodedTransaction::hash)
+            .collect::<Vec<_>>();
+        let processed_hashes = included_hashes
+            .iter()
+            .copied()
+            .chain(dropped.iter().map(|(hash, _)| *hash))
+            .collect::<HashSet<_>>();
+        let receipts = included_hashes
+            .iter()
+            .copied()
+            .zip(receipts)
+            .collect::<Vec<_>>();
 
         let hashable_data = HashableBlockData {
-            block_id: new_block_height,
-            transactions: valid_transactions,
+            block_id,
+            transactions,
             prev_block_hash,
             timestamp: curr_time,
+            params_version,
+            dev_proved,
         };
 
-        let block = hashable_data.into_block(self.block_store.signing_key());
-
-        self.block_store.put_block_at_id(block)?;
+        let block = match &mut self.dev_rng {
+            Some(dev_rng) => hashable_data
+                .into_block_with_aux_random(self.block_store.signing_key(), dev_rng.r#gen()),
+            None => hashable_data.into_block(self.block_store.signing_key()),
+        };
+        let block_hash = block.header.hash;
+
+        // Block, the resulting state digest and pending proof record are stored atomically
+        let proof_record = proving_transactions
+            .is_some()
+            .then_some(BlockProofRecord::Pending);
+        self.block_store.put_block_with_state_digest(
+            block,
+            Some(state.commitment_set_digest()),
+            proof_record.as_ref(),
+        )?;
+        for (hash, receipt) in receipts {
+            self.block_store.insert_receipt(hash, receipt);
+        }
 
-        self.chain_height = new_block_height;
+        // Pre-state is proven with the parameters of the block, which aren't a part of its root
+        let mut pre_state = std::mem::replace(&mut self.state, state);
+        self.params_schedule
+            .params_at(block_id)
+            .1
+            .apply_to(&mut pre_state);
+        if let (Some(proving_workers), Some(transactions)) =
+            (&mut self.proving_workers, proving_transactions)
+        {
+            proving_workers.submit(ProvingJob {
+                block_id,
+                block_hash,
+                pre_state,
+                transactions,
+            });
+        }
+        self.state_invariants = state_invariants;
+        let dropped_events = self.dropped_audit_events(&dropped, &expired, &included_hashes);
+        self.pending_transactions
+            .retain(|pending_tx| !processed_hashes.contains(&pending_tx.hash));
+        self.system_transactions
+            .retain(|system_tx| !processed_hashes.contains(&system_tx.hash));
+        self.prune_mempool_wal(&processed_hashes);
+
+        self.chain_height = block_id;
+        self.last_block_produced_at = Instant::now();
+        self.sync_progress
+            .record(block_id, self.last_block_produced_at);
+        self.block_production
+            .stall_watch()
+            .record_block(self.last_block_produced_at);
+        self.publish_num_pending();
+
+        // The block is committed anyway, failed events are logged with the next block
+        if let Err(err) = self.log_block_events(&dropped) {
+            warn!("Failed to log events of block {block_id}: {err:#}");
+        }
+        for hash in included_hashes {
+            self.tx_audit
+                .record(hash, TxAuditEvent::Included { block_id });
+        }
+        for (hash, event) in dropped_events {
+            self.tx_audit.record(hash, event);
+        }
+        for (hash, reason) in dropped {
+            self.dropped_transactions.record(hash, reason);
+        }
 
         // TODO: Consider switching to `tracing` crate to have more structured and consistent logs
         // e.g.
@@ -201,69 +1145,834 @@ impl SequencerCore {
         Ok(self.chain_height)
     }
 
-    pub fn state(&self) -> &nssa::V02State {
-        &self.state
+    /// Audit events of transactions `dropped` from the block, which included `included_hashes`.
+    /// Must be called while they still wait for inclusion.
+    ///
+    /// Transactions are replaced by included ones of the same sender and nonce, and expired if
+    /// they are in `expired`, otherwise they are evicted.
+    fn dropped_audit_events(
+        &self,
+        dropped: &[(HashType, String)],
+        expired: &HashSet<HashType>,
+        included_hashes: &[HashType],
+    ) -> Vec<(HashType, TxAuditEvent)> {
+        let included_hashes = included_hashes.iter().collect::<HashSet<_>>();
+        // Senders and nonces of included transactions and the other way around for others
+        let mut included_by_nonce = HashMap::new();
+        let mut nonces_of_others = HashMap::new();
+        for pending_tx in self.waiting_transactions() {
+            let Some(sender_and_nonce) = pending_tx.sender_and_nonce else {
+                continue;
+            };
+            if included_hashes.contains(&pending_tx.hash) {
+                included_by_nonce.insert(sender_and_nonce, pending_tx.hash);
+            } else {
+                nonces_of_others.insert(pending_tx.hash, sender_and_nonce);
+            }
+        }
+
+        dropped
+            .iter()
+            .map(|(hash, reason)| {
+                let replaced_by = nonces_of_others
+                    .get(hash)
+                    .and_then(|sender_and_nonce| included_by_nonce.get(sender_and_nonce));
+                let event = if let Some(replaced_by) = replaced_by {
+                    TxAuditEvent::Replaced {
+                        by: hex::encode(replaced_by),
+                    }
+                } else if expired.contains(hash) {
+                    TxAuditEvent::Expired {
+                        reason: reason.clone(),
+                    }
+                } else {
+                    TxAuditEvent::Evicted {
+                        reason: reason.clone(),
+                    }
+                };
+                (*hash, event)
+            })
+            .collect()
     }
 
-    pub fn block_store(&self) -> &SequencerBlockStore {
-        &self.block_store
+    /// Mempool lifecycle events of the transaction with the given hash, see [`crate::tx_audit`]
+    ///
+    /// Takes newly arrived transactions from mempool first, so their admission is recorded.
+    pub fn tx_audit(&mut self, hash: &HashType) -> Vec<TxAuditRecord> {
+        self.take_transactions_from_mempool();
+
+        self.tx_audit.events(hash)
     }
 
-    pub fn chain_height(&self) -> u64 {
-        self.chain_height
+    /// Stores records of blocks, proven by background workers since the last call
+    pub fn collect_block_proofs(&mut self) -> Result<()> {
+        let Some(proving_workers) = &mut self.proving_workers else {
+            return Ok(());
+        };
+
+        for (block_id, record) in proving_workers.finished() {
+            if let BlockProofRecord::Failed { reason } = &record {
+                warn!("Failed to prove block {block_id}: {reason}");
+            }
+            self.block_store.put_block_proof_record(block_id, &record)?;
+        }
+        Ok(())
     }
 
-    pub fn sequencer_config(&self) -> &SequencerConfig {
-        &self.sequencer_config
+    /// Proof record of the block, `None` if it wasn't submitted for proving
+    ///
+    /// Collects finished proofs first, so they are reported as soon as possible.
+    pub fn block_proof_status(&mut self, block_id: u64) -> Result<Option<BlockProofRecord>> {
+        self.collect_block_proofs()?;
+        self.block_store.get_block_proof_record(block_id)
     }
-}
 
-// TODO: Introduce type-safe wrapper around checked transaction, e.g. AuthenticatedTransaction
-pub fn transaction_pre_check(
-    tx: NSSATransaction,
-) -> Result<NSSATransaction, TransactionMalformationError> {
-    // Stateless checks here
-    match tx {
-        NSSATransaction::Public(tx) => {
-            if tx.witness_set().is_valid_for(tx.message()) {
-                Ok(NSSATransaction::Public(tx))
-            } else {
-                Err(TransactionMalformationError::InvalidSignature)
+    /// Starts rebuilding indexes of stored transactions by replaying stored blocks from genesis on
+    /// a separate state. Blocks are applied to the rebuild in batches of [`Self::blocks_to_index`],
+    /// which may be done without holding the core, and the rebuild is completed by
+    /// [`Self::finish_index_rebuild`].
+    ///
+    /// Fails if bodies of stored blocks are pruned, as they are needed for the replay.
+    pub fn start_index_rebuild(&self) -> Result<IndexRebuild> {
+        self.check_blocks_replayable()?;
+        let (state, _) = genesis_state(&self.sequencer_config);
+        Ok(IndexRebuild::new(
+            state,
+            self.params_schedule.clone(),
+            self.sequencer_config.genesis_id + 1,
+        ))
+    }
+
+    /// Up to `max_blocks` stored blocks, which `rebuild` applies next
+    pub fn blocks_to_index(&self, rebuild: &IndexRebuild, max_blocks: usize) -> Result<Vec<Block>> {
+        (rebuild.next_block_id()..=self.chain_height)
+            .take(max_blocks)
+            .map(|block_id| self.block_store.get_block_at_id(block_id))
+            .collect()
+    }
+
+    /// Applies blocks stored since the last batch to `rebuild` and replaces indexes of stored
+    /// transactions with the rebuilt ones. Returns discrepancies of the replaced indexes.
+    ///
+    /// Indexes are kept if the state replayed by `rebuild` doesn't match the current one.
+    pub fn finish_index_rebuild(&mut self, mut rebuild: IndexRebuild) -> Result<IndexReport> {
+        loop {
+            let blocks = self.blocks_to_index(&rebuild, INDEX_REBUILD_BATCH_SIZE)?;
+            if blocks.is_empty() {
+                break;
             }
-        }
-        NSSATransaction::PrivacyPreserving(tx) => {
-            if tx.witness_set().signatures_are_valid_for(tx.message()) {
-                Ok(NSSATransaction::PrivacyPreserving(tx))
-            } else {
-                Err(TransactionMalformationError::InvalidSignature)
+            for block in &blocks {
+                rebuild.apply_block(block)?;
             }
         }
-        NSSATransaction::ProgramDeployment(tx) => Ok(NSSATransaction::ProgramDeployment(tx)),
+        // Rebuilt indexes would have entries of blocks pruned meanwhile
+        self.check_blocks_replayable()?;
+        anyhow::ensure!(
+            rebuild.state_root() == self.state.state_root(),
+            "State replayed up to block {} doesn't match the current one, indexes are kept",
+            self.chain_height
+        );
+
+        let (report, indexes) = rebuild.finish(self.block_store.indexes());
+        self.block_store.replace_indexes(indexes);
+        if report.is_consistent() {
+            log::info!(
+                "Indexes of transactions up to block {} match stored blocks",
+                report.last_block
+            );
+        } else {
+            warn!("Indexes of transactions didn't match stored blocks and are rebuilt: {report:?}");
+        }
+        Ok(report)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::pin::pin;
+    /// Same as [`Self::start_index_rebuild`] followed by [`Self::finish_index_rebuild`], holding
+    /// the core all the time
+    pub fn rebuild_indexes(&mut self) -> Result<IndexReport> {
+        let rebuild = self.start_index_rebuild()?;
+        self.finish_index_rebuild(rebuild)
+    }
 
-    use base58::{FromBase58, ToBase58};
-    use common::test_utils::sequencer_sign_key_for_testing;
-    use nssa::PrivateKey;
+    /// Checks index entries of transactions of `num_blocks` random stored blocks, see
+    /// [`index_repair`]. Returns the number of entries, which don't match their blocks.
+    pub fn sample_indexes(&self, num_blocks: usize) -> Result<u64> {
+        let first_block_id = self
+            .block_store
+            .first_unpruned_block_id()?
+            .max(self.sequencer_config.genesis_id + 1);
+        if first_block_id > self.chain_height {
+            return Ok(0);
+        }
 
-    use super::*;
-    use crate::config::AccountInitialData;
+        let mut rng = rand::thread_rng();
+        let mut mismatched = 0;
+        for _ in 0..num_blocks {
+            let block_id = rng.gen_range(first_block_id..=self.chain_height);
+            let block = self.block_store.get_block_at_id(block_id)?;
+            mismatched += check_block_entries(self.block_store.indexes(), &block);
+        }
+        Ok(mismatched)
+    }
 
-    fn parse_unwrap_tx_body_into_nssa_tx(tx_body: EncodedTransaction) -> NSSATransaction {
-        NSSATransaction::try_from(&tx_body)
-            .map_err(|_| TransactionMalformationError::FailedToDecode { tx: tx_body.hash() })
-            .unwrap()
+    fn check_blocks_replayable(&self) -> Result<()> {
+        let first_unpruned_block_id = self.block_store.first_unpruned_block_id()?;
+        anyhow::ensure!(
+            first_unpruned_block_id <= self.sequencer_config.genesis_id + 1,
+            "Stored blocks before {first_unpruned_block_id} are pruned, they can't be replayed"
+        );
+        Ok(())
     }
 
-    fn setup_sequencer_config_variable_initial_accounts(
-        initial_accounts: Vec<AccountInitialData>,
-    ) -> SequencerConfig {
-        let tempdir = tempfile::tempdir().unwrap();
-        let home = tempdir.path().to_path_buf();
+    pub fn state(&self) -> &nssa::V02State {
+        &self.state
+    }
+
+    pub fn state_invariants(&self) -> &StateInvariants {
+        &self.state_invariants
+    }
+
+    pub fn block_store(&self) -> &SequencerBlockStore {
+        &self.block_store
+    }
+
+    pub fn chain_height(&self) -> u64 {
+        self.chain_height
+    }
+
+    /// Header of block `block_id` signed with the key of the sequencer
+    ///
+    /// Only the state at the last block is kept, so only its header carries the state root.
+    pub fn signed_block_header(&self, block_id: u64) -> Result<SignedBlockHeader> {
+        let header = self.block_store.get_block_header_at_id(block_id)?;
+        let state_root = (block_id == self.chain_height).then(|| self.state.state_root());
+
+        Ok(SignedBlockHeader::sign(
+            header.block_id,
+            header.prev_block_hash,
+            header.hash,
+            header.timestamp,
+            state_root,
+            self.block_store.signing_key(),
+        ))
+    }
+
+    /// Fails if `tx` calls or deploys a program, which the program allow-list of the network
+    /// doesn't allow
+    ///
+    /// State transitions check the same allow-list, including programs called by other programs.
+    /// Programs executed in privacy preserving transactions are not revealed, so they aren't
+    /// checked.
+    pub fn check_program_allow_list(
+        &self,
+        tx: &NSSATransaction,
+    ) -> Result<(), TransactionMalformationError> {
+        let program_allow_list = self.state.program_allow_list();
+        match tx {
+            NSSATransaction::Public(tx) => {
+                program_allow_list.check_call(&tx.message().program_id())
+            }
+            NSSATransaction::PrivacyPreserving(_) => Ok(()),
+            NSSATransaction::ProgramDeployment(_) => program_allow_list.check_deployment(),
+        }
+        .map_err(|err| TransactionMalformationError::ProgramNotAllowed {
+            reason: err.to_string(),
+        })
+    }
+
+    /// Fails if the instruction of public `tx` exceeds the limit of the next block, doesn't
+    /// match the format of its builtin program or is a native transfer creating its recipient
+    /// with less than the minimum, see [`nssa::PublicTransaction::validate_instruction`]
+    ///
+    /// Nothing is executed, so malformed calls are rejected before they burn executor cycles.
+    /// State transitions run the same check before executing programs.
+    pub fn check_instruction(
+        &self,
+        tx: &NSSATransaction,
+    ) -> Result<(), TransactionMalformationError> {
+        let NSSATransaction::Public(tx) = tx else {
+            return Ok(());
+        };
+
+        let max_instruction_data_size = self.next_block_params().1.max_instruction_data_size;
+        tx.validate_instruction(&self.state, max_instruction_data_size)
+            .map_err(|err| {
+                TransactionMalformationError::from_nssa_error(err, |reason| {
+                    TransactionMalformationError::InvalidInstruction { reason }
+                })
+            })
+    }
+
+    /// Fails if the program deployed by `tx` exceeds the size limit of the next block or isn't a
+    /// valid image, see [`nssa::ProgramDeploymentTransaction::validate_program`]
+    ///
+    /// Returns the program, which id is computed from the image, for deployments only. Nothing is
+    /// executed, the smoke test run of [`SequencerConfig::deployment_smoke_test_cycles`] is left
+    /// to the caller, so it doesn't hold the sequencer.
+    pub fn check_deployment(
+        &self,
+        tx: &NSSATransaction,
+    ) -> Result<Option<CheckedDeployment>, TransactionMalformationError> {
+        let NSSATransaction::ProgramDeployment(tx) = tx else {
+            return Ok(None);
+        };
+
+        let max_program_size = self.next_block_params().1.max_program_size;
+        let program = tx.validate_program(max_program_size).map_err(|err| {
+            TransactionMalformationError::InvalidProgram {
+                reason: err.to_string(),
+            }
+        })?;
+        let already_deployed = self.state.is_program_deployed(&program.id());
+        Ok(Some(CheckedDeployment {
+            program,
+            already_deployed,
+        }))
+    }
+
+    /// Runs all checks of `tx` against the state, which admission into mempool runs after
+    /// [`pre_check_transaction`]: program allow-list, instruction, time lock and deployment
+    ///
+    /// Submission and validation of submitted transactions use it too, so they never disagree
+    /// with admission. Returns the program of deployments, see [`Self::check_deployment`].
+    pub fn check_admission(
+        &self,
+        tx: &NSSATransaction,
+    ) -> Result<Option<CheckedDeployment>, TransactionMalformationError> {
+        self.check_program_allow_list(tx)?;
+        self.check_instruction(tx)?;
+        self.check_time_lock(tx)?;
+        self.check_deployment(tx)
+    }
+
+    /// Checks, that `tx` becomes valid within `max_time_lock_blocks` after the next block, so
+    /// it isn't parked for ever
+    fn check_time_lock(&self, tx: &NSSATransaction) -> Result<(), TransactionMalformationError> {
+        let max_valid_from_block =
+            (self.chain_height + 1).saturating_add(self.sequencer_config.max_time_lock_blocks);
+        match tx.valid_from_block() {
+            Some(valid_from_block) if valid_from_block > max_valid_from_block => {
+                Err(TransactionMalformationError::TimeLockTooLong {
+                    valid_from_block,
+                    max_valid_from_block,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Changes of parameters forced over stored blocks in order of heights
+    pub fn forced_params_changes(&self) -> &[ForcedParamsChange] {
+        &self.forced_params_changes
+    }
+
+    pub fn sequencer_config(&self) -> &SequencerConfig {
+        &self.sequencer_config
+    }
+
+    /// Version of chain parameters, which the next block is built with, and the parameters
+    pub fn next_block_params(&self) -> (ParamsVersion, ChainParams) {
+        self.params_schedule.params_at(self.chain_height + 1)
+    }
+
+    /// Anchors, which private transactions of the next block may be proven against, from the
+    /// newest one. The newest one is the current root of the commitment set, which becomes the
+    /// anchor of the next block.
+    pub fn latest_anchors(&self) -> Vec<nssa::Anchor> {
+        let next_block_id = self.chain_height + 1;
+        let anchor_window = self.next_block_params().1.anchor_window;
+        let next_anchor = nssa::Anchor {
+            block_id: next_block_id,
+            digest: self.state.commitment_set_digest(),
+        };
+
+        std::iter::once(next_anchor)
+            .chain(self.state.latest_anchors().rev())
+            .filter(|anchor| anchor.block_id.saturating_add(anchor_window) > next_block_id)
+            .collect()
+    }
+
+    /// Native transfers waiting for inclusion, which send to or from `account_id`, in order of
+    /// inclusion
+    ///
+    /// Takes newly arrived transactions from mempool first, so they are reported as well.
+    pub fn pending_transfers(&mut self, account_id: &nssa::AccountId) -> Vec<PendingTransfer> {
+        self.take_transactions_from_mempool();
+
+        let account_id = account_id.to_string();
+        self.system_transactions
+            .iter()
+            .chain(&self.pending_transactions)
+            .chain(&self.parked_transactions)
+            .filter_map(|pending_tx| {
+                PendingTransfer::from_transaction(&pending_tx.hash, &pending_tx.tx)
+            })
+            .filter(|transfer| transfer.involves(&account_id))
+            .collect()
+    }
+
+    /// Summaries of transactions waiting for inclusion: system ones, pending ones in order of the
+    /// ordering policy, then parked ones
+    pub fn pending_transaction_summaries(&mut self) -> Vec<PendingTransactionSummary> {
+        self.take_transactions_from_mempool();
+
+        self.system_transactions
+            .iter()
+            .chain(&self.pending_transactions)
+            .chain(&self.parked_transactions)
+            .map(|pending_tx| {
+                PendingTransactionSummary::from_transaction(&pending_tx.hash, &pending_tx.tx)
+            })
+            .collect()
+    }
+
+    /// Status of transaction with the given hash, pending transactions are estimated to be
+    /// included in order of the ordering policy, `max_num_tx_in_block` of the next block
+    /// parameters per block.
+    ///
+    /// Takes newly arrived transactions from mempool first, so they are reported as pending.
+    /// System transactions are reported at their positions in the system lane, estimated to be
+    /// included at its block budget per block.
+    pub fn transaction_status(&mut self, hash: &HashType) -> TransactionStatus {
+        if let Some(block_id) = self.block_store.get_block_id_of_transaction(hash) {
+            return TransactionStatus::Included {
+                block_id,
+                receipt: self.block_store.get_receipt(hash).cloned(),
+            };
+        }
+
+        self.take_transactions_from_mempool();
+
+        if let (Some(system_lane), Some(position)) = (
+            &self.sequencer_config.system_lane,
+            self.system_transactions
+                .iter()
+                .position(|system_tx| system_tx.hash == *hash),
+        ) {
+            let system_budget =
+                system_lane.block_budget(self.next_block_params().1.max_num_tx_in_block);
+            let blocks_ahead = (position / system_budget) as u64;
+            let estimated_inclusion_block = (self.chain_height + 1 + blocks_ahead).max(
+                self.system_transactions[position]
+                    .tx
+                    .valid_from_block()
+                    .unwrap_or_default(),
+            );
+            return TransactionStatus::Pending {
+                position: position as u64,
+                depth: self.system_transactions.len() as u64,
+                estimated_inclusion_block,
+                estimated_wait_millis: (estimated_inclusion_block - self.chain_height)
+                    * self.sequencer_config.block_create_timeout_millis,
+            };
+        }
+
+        if let Some(position) = self
+            .pending_transactions
+            .iter()
+            .position(|pending_tx| pending_tx.hash == *hash)
+        {
+            let max_num_tx_in_block = self.next_block_params().1.max_num_tx_in_block;
+            let blocks_ahead = (position / max_num_tx_in_block.max(1)) as u64;
+            return TransactionStatus::Pending {
+                position: position as u64,
+                depth: self.pending_transactions.len() as u64,
+                estimated_inclusion_block: self.chain_height + 1 + blocks_ahead,
+                estimated_wait_millis: (blocks_ahead + 1)
+                    * self.sequencer_config.block_create_timeout_millis,
+            };
+        }
+
+        if let Some(position) = self
+            .parked_transactions
+            .iter()
+            .position(|parked_tx| parked_tx.hash == *hash)
+        {
+            let valid_from_block = self.parked_transactions[position]
+                .tx
+                .valid_from_block()
+                .expect("Only time-locked transactions are parked");
+            let pending = self.pending_transactions.len();
+            return TransactionStatus::Pending {
+                position: (pending + position) as u64,
+                depth: (pending + self.parked_transactions.len()) as u64,
+                estimated_inclusion_block: valid_from_block,
+                estimated_wait_millis: (valid_from_block - self.chain_height)
+                    * self.sequencer_config.block_create_timeout_millis,
+            };
+        }
+
+        match self.dropped_transactions.reason(hash) {
+            Some(reason) => TransactionStatus::Dropped {
+                reason: reason.to_string(),
+            },
+            None => TransactionStatus::Unknown,
+        }
+    }
+
+    /// Signed promise to include the pending transaction with the given hash and `status`
+    ///
+    /// The transaction is promised to be included once all transactions pending now fit into
+    /// blocks, but not before it's estimated to, plus the configured margin. `None` for
+    /// transactions, which are not pending.
+    pub fn soft_confirmation(
+        &self,
+        hash: HashType,
+        status: &TransactionStatus,
+    ) -> Option<SoftConfirmation> {
+        let TransactionStatus::Pending {
+            depth,
+            estimated_inclusion_block,
+            ..
+        } = status
+        else {
+            return None;
+        };
+
+        let max_num_tx_in_block = self.next_block_params().1.max_num_tx_in_block.max(1) as u64;
+        let promised_by_block = (*estimated_inclusion_block)
+            .max(self.chain_height + depth.div_ceil(max_num_tx_in_block))
+            + self.sequencer_config.soft_confirmation_margin_blocks;
+        let received_at = chrono::Utc::now().timestamp_millis() as u64;
+
+        Some(SoftConfirmation::sign(
+            hash,
+            received_at,
+            promised_by_block,
+            self.block_store.signing_key(),
+        ))
+    }
+
+    /// Distribution of priority fees of pending transactions
+    pub fn pending_priority_fees(&mut self) -> PriorityFeeDistribution {
+        self.take_transactions_from_mempool();
+
+        let mut fees = self
+            .pending_transactions
+            .iter()
+            .map(|pending_tx| pending_tx.tx.priority_fee())
+            .collect::<Vec<_>>();
+        fees.sort_unstable();
+
+        PriorityFeeDistribution {
+            pending: fees.len() as u64,
+            min: fees.first().copied().unwrap_or_default(),
+            median: fees.get(fees.len() / 2).copied().unwrap_or_default(),
+            max: fees.last().copied().unwrap_or_default(),
+        }
+    }
+
+    /// Process is up, storage is reachable and block production neither stopped nor stalled
+    pub fn health_report(&self) -> HealthReport {
+        HealthReport::new(vec![
+            self.check_storage(),
+            self.block_production.health_check(),
+        ])
+    }
+
+    /// Sequencer is healthy, synced and produced last block within two block intervals before
+    /// `now`
+    pub fn readiness_report(&self, now: Instant) -> HealthReport {
+        HealthReport::new(vec![
+            self.check_storage(),
+            self.block_production.health_check(),
+            self.check_sync(),
+            self.check_block_production(now),
+        ])
+    }
+
+    pub fn sync_status(&self) -> SyncStatus {
+        self.sync_progress.status()
+    }
+
+    fn check_storage(&self) -> HealthCheck {
+        if let Err(err) = self.block_store.get_block_at_id(self.chain_height) {
+            return HealthCheck::failed(
+                STORAGE_CHECK,
+                format!("Failed to read block {}: {err:#}", self.chain_height),
+            );
+        }
+
+        // Blocks below the tip may be lost as well, which is noticed only when they are requested
+        match self.block_store.missing_block_lookups() {
+            0 => HealthCheck::passed(STORAGE_CHECK),
+            lookups => HealthCheck::failed(
+                STORAGE_CHECK,
+                format!("{lookups} lookups of blocks missing from storage, see error logs"),
+            ),
+        }
+    }
+
+    fn check_sync(&self) -> HealthCheck {
+        if self.sync_progress.is_synced() {
+            return HealthCheck::passed(SYNC_CHECK);
+        }
+
+        let status = self.sync_progress.status();
+        HealthCheck::failed(
+            SYNC_CHECK,
+            format!(
+                "Synced up to block {} of {}",
+                status.current_height, status.target_height
+            ),
+        )
+    }
+
+    fn check_block_production(&self, now: Instant) -> HealthCheck {
+        let max_delay =
+            Duration::from_millis(2 * self.sequencer_config.block_create_timeout_millis);
+        let delay = now.saturating_duration_since(self.last_block_produced_at);
+
+        if delay <= max_delay {
+            HealthCheck::passed(BLOCK_PRODUCTION_CHECK)
+        } else {
+            HealthCheck::failed(
+                BLOCK_PRODUCTION_CHECK,
+                format!(
+                    "Last block was produced {}ms ago, expected at most {}ms",
+                    delay.as_millis(),
+                    max_delay.as_millis()
+                ),
+            )
+        }
+    }
+}
+
+/// Data of the genesis block of the chain of `config`
+///
+/// Genesis block has no previous block, so its previous block hash is the commitment to the
+/// program allow-list instead. Chains with different allow-lists have different genesis hashes.
+pub fn genesis_block_data(config: &SequencerConfig) -> HashableBlockData {
+    HashableBlockData {
+        block_id: config.genesis_id,
+        transactions: vec![],
+        prev_block_hash: config.program_allow_list.commitment(),
+        timestamp: 0,
+        params_version: 0,
+        dev_proved: false,
+    }
+}
+
+// TODO: Introduce type-safe wrapper around checked transaction, e.g. AuthenticatedTransaction
+/// State at genesis and its initial shielded supply
+pub(crate) fn genesis_state(config: &SequencerConfig) -> (nssa::V02State, Amount) {
+    let mut initial_commitments = vec![];
+    let mut initial_shielded_supply = Amount::ZERO;
+
+    for init_comm_data in config.initial_commitments.clone() {
+        let npk = init_comm_data.npk;
+
+        let mut acc = init_comm_data.account;
+
+        acc.program_owner = nssa::program::Program::authenticated_transfer_program().id();
+
+        initial_shielded_supply = initial_shielded_supply.saturating_add(acc.balance);
+        let comm = nssa_core::Commitment::new(&npk, &acc);
+
+        initial_commitments.push(comm);
+    }
+
+    let init_accs: Vec<(nssa::AccountId, Amount)> = config
+        .initial_accounts
+        .iter()
+        .map(|acc_data| (acc_data.account_id.parse().unwrap(), acc_data.balance))
+        .collect();
+
+    let mut state = nssa::V02State::new_with_genesis_accounts(&init_accs, &initial_commitments)
+        .with_program_allow_list(config.program_allow_list.clone());
+    state.set_fee_recipient(config.fee_recipient());
+    state.set_chain_id(config.chain_id);
+    state.set_accept_dev_proofs(config.accept_dev_proofs);
+
+    #[cfg(feature = "testnet")]
+    state.add_pinata_program(PINATA_BASE58.parse().unwrap());
+
+    (state, initial_shielded_supply)
+}
+
+/// Applies `tx` to `state` with all checks, returns the fees it paid
+pub(crate) fn execute_transaction_on_state(
+    state: &mut nssa::V02State,
+    tx: &NSSATransaction,
+) -> Result<PaidFees, nssa::error::NssaError> {
+    match tx {
+        NSSATransaction::Public(tx) => state.transition_from_public_transaction(tx),
+        // Privacy preserving transactions pay no fees
+        NSSATransaction::PrivacyPreserving(tx) => state
+            .transition_from_privacy_preserving_transaction(tx)
+            .map(|()| PaidFees::default()),
+        NSSATransaction::ProgramDeployment(tx) => {
+            state.transition_from_program_deployment_transaction(tx)
+        }
+    }
+}
+
+/// Same as [`execute_transaction_on_state`], but expects `tx` to pass [`pre_check_transactions`],
+/// so stateless checks of public transactions are not repeated.
+fn execute_prechecked_transaction(
+    state: &mut nssa::V02State,
+    tx: NSSATransaction,
+) -> Result<(NSSATransaction, PaidFees), nssa::error::NssaError> {
+    let fees = match &tx {
+        NSSATransaction::Public(public_tx) => {
+            state.transition_from_prevalidated_public_transaction(public_tx)
+        }
+        _ => execute_transaction_on_state(state, &tx),
+    }
+    .inspect_err(|err| warn!("Error at transition {err}"))?;
+
+    Ok((tx, fees))
+}
+
+/// Public accounts, which balances may be changed by applying `tx` to `state`
+pub(crate) fn touched_account_ids(
+    state: &nssa::V02State,
+    tx: &NSSATransaction,
+) -> Vec<nssa::AccountId> {
+    let mut account_ids = tx.public_account_ids();
+    let pays_fee = match tx {
+        NSSATransaction::ProgramDeployment(deployment_tx) => !deployment_tx.fee().is_zero(),
+        // Metered execution pays gas
+        NSSATransaction::Public(public_tx) => {
+            !tx.priority_fee().is_zero() || public_tx.message().max_cycles().is_some()
+        }
+        NSSATransaction::PrivacyPreserving(_) => false,
+    };
+    if pays_fee {
+        account_ids.extend(state.fee_recipient());
+    }
+    account_ids
+}
+
+/// Public balances of given accounts, in the same order
+fn public_balances(
+    state: &nssa::V02State,
+    account_ids: &[nssa::AccountId],
+) -> Vec<(nssa::AccountId, Amount)> {
+    account_ids
+        .iter()
+        .map(|account_id| (*account_id, state.get_account_by_id(account_id).balance))
+        .collect()
+}
+
+/// Receipt of a transaction, which paid `fees` and changed `pre_balances` of its touched
+/// accounts to the ones in `state`
+fn transaction_receipt(
+    state: &nssa::V02State,
+    pre_balances: Vec<(nssa::AccountId, Amount)>,
+    fees: &PaidFees,
+) -> TransactionReceipt {
+    TransactionReceipt::new(
+        pre_balances.into_iter().map(|(account_id, pre_balance)| {
+            let post_balance = state.get_account_by_id(&account_id).balance;
+            (account_id, pre_balance, post_balance)
+        }),
+        fees,
+    )
+}
+
+/// Sum of public balances of given accounts, each account counted once
+pub(crate) fn public_balance_of(state: &nssa::V02State, account_ids: &[nssa::AccountId]) -> Amount {
+    Amount::checked_sum(
+        account_ids
+            .iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|account_id| state.get_account_by_id(account_id).balance),
+    )
+    .expect("Total balance should fit into u128, as it's preserved by transactions")
+}
+
+pub fn transaction_pre_check(
+    tx: NSSATransaction,
+) -> Result<NSSATransaction, TransactionMalformationError> {
+    // Stateless checks here
+    match tx {
+        NSSATransaction::Public(tx) => {
+            if tx.witness_set().is_valid_for(tx.message()) {
+                Ok(NSSATransaction::Public(tx))
+            } else {
+                Err(TransactionMalformationError::InvalidSignature)
+            }
+        }
+        NSSATransaction::PrivacyPreserving(tx) => {
+            if tx.witness_set().signatures_are_valid_for(tx.message()) {
+                Ok(NSSATransaction::PrivacyPreserving(tx))
+            } else {
+                Err(TransactionMalformationError::InvalidSignature)
+            }
+        }
+        NSSATransaction::ProgramDeployment(tx) => {
+            if tx.witness_set().is_valid_for(tx.message()) {
+                Ok(NSSATransaction::ProgramDeployment(tx))
+            } else {
+                Err(TransactionMalformationError::InvalidSignature)
+            }
+        }
+    }
+}
+
+/// Runs all stateless checks of block validation on `tx`: decoding, [`transaction_pre_check`]
+/// and structure of public transactions
+pub fn pre_check_transaction(
+    tx: &EncodedTransaction,
+) -> Result<NSSATransaction, TransactionMalformationError> {
+    // Legacy transactions are decoded only to read old blocks, they aren't executed
+    if tx.tx_kind.is_legacy() {
+        return Err(TransactionMalformationError::InvalidStructure {
+            reason: format!("Legacy transaction kind {:?} is not accepted", tx.tx_kind),
+        });
+    }
+    let nssa_transaction = NSSATransaction::try_from(tx)
+        .map_err(|_| TransactionMalformationError::FailedToDecode { tx: tx.hash() })?;
+    let nssa_transaction = transaction_pre_check(nssa_transaction)?;
+
+    if let NSSATransaction::Public(tx) = &nssa_transaction {
+        tx.validate_structure().map_err(|err| {
+            TransactionMalformationError::from_nssa_error(err, |reason| {
+                TransactionMalformationError::InvalidStructure { reason }
+            })
+        })?;
+    }
+
+    Ok(nssa_transaction)
+}
+
+/// Runs [`pre_check_transaction`] on `txs` in parallel
+///
+/// Results are in the same order as `txs`.
+pub fn pre_check_transactions(
+    txs: &[EncodedTransaction],
+) -> Vec<Result<NSSATransaction, TransactionMalformationError>> {
+    txs.par_iter().map(pre_check_transaction).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use base58::{FromBase58, ToBase58};
+    use common::{
+        block::BlockTimestampError, rpc_types::SyncState,
+        test_utils::sequencer_sign_key_for_testing,
+    };
+    use nssa::PrivateKey;
+
+    use super::*;
+    use crate::config::{
+        AccountInitialData, DeterministicDevModeConfig, PruneConfig, SystemLaneConfig,
+    };
+
+    fn parse_unwrap_tx_body_into_nssa_tx(tx_body: EncodedTransaction) -> NSSATransaction {
+        NSSATransaction::try_from(&tx_body)
+            .map_err(|_| TransactionMalformationError::FailedToDecode { tx: tx_body.hash() })
+            .unwrap()
+    }
+
+    fn setup_sequencer_config_variable_initial_accounts(
+        initial_accounts: Vec<AccountInitialData>,
+    ) -> SequencerConfig {
+        let tempdir = tempfile::tempdir().unwrap();
+        let home = tempdir.path().to_path_buf();
 
         SequencerConfig {
             home,
@@ -277,6 +1986,34 @@ mod tests {
             initial_accounts,
             initial_commitments: vec![],
             signing_key: *sequencer_sign_key_for_testing().value(),
+            check_state_invariants: true,
+            lazy_proving: None,
+            mempool_wal: false,
+            ingest_queue_size: 1000,
+            prune: None,
+            soft_confirmation_margin_blocks: 10,
+            event_sink: None,
+            program_allow_list: crate::config::default_program_allow_list(),
+            max_block_timestamp_skew_millis: 30_000,
+            max_pending_per_sender: None,
+            max_consecutive_per_sender: None,
+            max_time_lock_blocks: 100_000,
+            max_parked_per_sender: 16,
+            upgrades: vec![],
+            storage_deposit_per_byte: Amount::ZERO,
+            max_instruction_data_size: nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+            max_program_size: nssa::program::DEFAULT_MAX_PROGRAM_SIZE,
+            anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+            chain_id: 0,
+            accept_dev_proofs: false,
+            deterministic_dev_mode: None,
+            serve_pending_transfers: false,
+            serve_pending_transactions: false,
+            system_lane: None,
+            tx_audit: Default::default(),
+            deployment_smoke_test_cycles: Some(config::DEFAULT_DEPLOYMENT_SMOKE_TEST_CYCLES),
+            force_params_change: false,
+            repair_indexes: false,
         }
     }
 
@@ -293,12 +2030,12 @@ mod tests {
 
         let initial_acc1 = AccountInitialData {
             account_id: acc1_account_id.to_base58(),
-            balance: 10000,
+            balance: Amount(10000),
         };
 
         let initial_acc2 = AccountInitialData {
             account_id: acc2_account_id.to_base58(),
-            balance: 20000,
+            balance: Amount(20000),
         };
 
         let initial_accounts = vec![initial_acc1, initial_acc2];
@@ -367,8 +2104,8 @@ mod tests {
             .get_account_by_id(&nssa::AccountId::new(acc2_account_id))
             .balance;
 
-        assert_eq!(10000, balance_acc_1);
-        assert_eq!(20000, balance_acc_2);
+        assert_eq!(Amount(10000), balance_acc_1);
+        assert_eq!(Amount(20000), balance_acc_2);
     }
 
     #[test]
@@ -385,12 +2122,12 @@ mod tests {
 
         let initial_acc1 = AccountInitialData {
             account_id: acc1_account_id.to_base58(),
-            balance: 10000,
+            balance: Amount(10000),
         };
 
         let initial_acc2 = AccountInitialData {
             account_id: acc2_account_id.to_base58(),
-            balance: 20000,
+            balance: Amount(20000),
         };
 
         let initial_accounts = vec![initial_acc1, initial_acc2];
@@ -414,14 +2151,14 @@ mod tests {
             .unwrap();
 
         assert_eq!(
-            10000,
+            Amount(10000),
             sequencer
                 .state
                 .get_account_by_id(&nssa::AccountId::new(acc1_account_id))
                 .balance
         );
         assert_eq!(
-            20000,
+            Amount(20000),
             sequencer
                 .state
                 .get_account_by_id(&nssa::AccountId::new(acc2_account_id))
@@ -437,6 +2174,132 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_legacy_transaction_is_not_accepted() {
+        let mut tx = common::test_utils::produce_dummy_empty_transaction();
+        tx.tx_kind = common::transaction::TxKind::LegacyPublic;
+
+        let [result] = pre_check_transactions(&[tx]).try_into().unwrap();
+
+        assert!(matches!(
+            result,
+            Err(TransactionMalformationError::InvalidStructure { .. })
+        ));
+    }
+
+    fn token_only_config() -> SequencerConfig {
+        SequencerConfig {
+            program_allow_list: nssa::ProgramAllowList {
+                builtin_programs: vec![nssa::BuiltinProgram::Token],
+                deployed_programs: false,
+            },
+            ..setup_sequencer_config()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transaction_of_program_not_allowed_on_network_is_rejected() {
+        let (devnet_sequencer, _mempool_handle) = common_setup().await;
+        let (restricted_sequencer, _mempool_handle) =
+            common_setup_with_config(token_only_config()).await;
+        let acc1 = devnet_sequencer.sequencer_config.initial_accounts[0]
+            .account_id
+            .clone()
+            .from_base58()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let tx = parse_unwrap_tx_body_into_nssa_tx(
+            common::test_utils::create_transaction_native_token_transfer(
+                acc1,
+                0,
+                [2; 32],
+                10,
+                create_signing_key_for_account1(),
+            ),
+        );
+
+        assert!(devnet_sequencer.check_program_allow_list(&tx).is_ok());
+        assert!(matches!(
+            restricted_sequencer.check_program_allow_list(&tx),
+            Err(TransactionMalformationError::ProgramNotAllowed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_genesis_hash_commits_to_program_allow_list() {
+        let config = setup_sequencer_config();
+
+        assert_ne!(
+            genesis_block_data(&config).hash(),
+            genesis_block_data(&token_only_config()).hash()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "program allow-list may differ")]
+    fn test_storage_of_chain_with_other_program_allow_list_is_refused() {
+        let config = setup_sequencer_config();
+        drop(SequencerCore::start_from_config(config.clone()));
+
+        SequencerCore::start_from_config(SequencerConfig {
+            program_allow_list: token_only_config().program_allow_list,
+            ..config
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Pruning of stored blocks isn't supported")]
+    fn test_pruning_is_refused() {
+        let config = SequencerConfig {
+            prune: Some(PruneConfig { keep_blocks: 1 }),
+            ..setup_sequencer_config()
+        };
+        drop(SequencerCore::start_from_config(config));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "--force-params-change")]
+    async fn test_restart_with_altered_params_is_refused() {
+        let config = setup_sequencer_config();
+        drop(common_setup_with_config(config.clone()).await);
+
+        SequencerCore::start_from_config(SequencerConfig {
+            storage_deposit_per_byte: Amount(1),
+            ..config
+        });
+    }
+
+    #[tokio::test]
+    async fn test_forced_params_change_is_recorded_at_current_height() {
+        let config = setup_sequencer_config();
+        let (sequencer, _mempool_handle) = common_setup_with_config(config.clone()).await;
+        let height = sequencer.chain_height();
+        assert!(sequencer.forced_params_changes().is_empty());
+        drop(sequencer);
+
+        let altered_config = SequencerConfig {
+            storage_deposit_per_byte: Amount(1),
+            ..config
+        };
+        let (sequencer, _mempool_handle) = SequencerCore::start_from_config(SequencerConfig {
+            force_params_change: true,
+            ..altered_config.clone()
+        });
+        let changes = sequencer.forced_params_changes().to_vec();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].block_id, height);
+        assert_eq!(
+            changes[0].fingerprint,
+            params_fingerprint(&altered_config, genesis_block_data(&altered_config).hash())
+        );
+        drop(sequencer);
+
+        // New parameters are the stored ones from now on, so they don't need forcing again
+        let (sequencer, _mempool_handle) = SequencerCore::start_from_config(altered_config);
+        assert_eq!(sequencer.forced_params_changes(), changes.as_slice());
+    }
+
     #[tokio::test]
     async fn test_transaction_pre_check_native_transfer_valid() {
         let (sequencer, _mempool_handle) = common_setup().await;
@@ -580,8 +2443,8 @@ mod tests {
             .get_account_by_id(&nssa::AccountId::new(acc2))
             .balance;
 
-        assert_eq!(bal_from, 9900);
-        assert_eq!(bal_to, 20100);
+        assert_eq!(bal_from, Amount(9900));
+        assert_eq!(bal_to, Amount(20100));
     }
 
     #[tokio::test]
@@ -625,11 +2488,42 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_replay_transactions_are_rejected_in_the_same_block() {
-        let (mut sequencer, mempool_handle) = common_setup().await;
-
-        let acc1 = sequencer.sequencer_config.initial_accounts[0]
-            .account_id
+    async fn test_deterministic_dev_mode_builds_the_same_blocks() {
+        let tx = common::test_utils::produce_dummy_empty_transaction();
+        let produce_block = async |seed| {
+            let config = SequencerConfig {
+                deterministic_dev_mode: Some(DeterministicDevModeConfig { seed }),
+                ..setup_sequencer_config()
+            };
+            let (mut sequencer, mempool_handle) = common_setup_with_config(config).await;
+            mempool_handle.push(tx.clone()).await.unwrap();
+            let block_id = sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+            sequencer.block_store.get_block_at_id(block_id).unwrap()
+        };
+
+        let block = produce_block(7).await;
+        let rerun_block = produce_block(7).await;
+        assert_eq!(block.header.hash, rerun_block.header.hash);
+        assert_eq!(block.header.signature, rerun_block.header.signature);
+        assert_eq!(
+            block.header.timestamp,
+            logical_timestamp(block.header.block_id)
+        );
+
+        // Only the signature depends on the seed
+        let other_seed_block = produce_block(8).await;
+        assert_eq!(block.header.hash, other_seed_block.header.hash);
+        assert_ne!(block.header.signature, other_seed_block.header.signature);
+    }
+
+    #[tokio::test]
+    async fn test_replay_transactions_are_rejected_in_the_same_block() {
+        let (mut sequencer, mempool_handle) = common_setup().await;
+
+        let acc1 = sequencer.sequencer_config.initial_accounts[0]
+            .account_id
             .clone()
             .from_base58()
             .unwrap()
@@ -665,7 +2559,13 @@ mod tests {
             .unwrap();
 
         // Only one should be included in the block
-        assert_eq!(block.body.transactions, vec![tx.clone()]);
+        let block_hashes: Vec<_> = block
+            .body
+            .transactions
+            .iter()
+            .map(EncodedTransaction::hash)
+            .collect();
+        assert_eq!(block_hashes, vec![tx.hash()]);
     }
 
     #[tokio::test]
@@ -702,7 +2602,13 @@ mod tests {
             .block_store
             .get_block_at_id(current_height)
             .unwrap();
-        assert_eq!(block.body.transactions, vec![tx.clone()]);
+        let block_hashes: Vec<_> = block
+            .body
+            .transactions
+            .iter()
+            .map(EncodedTransaction::hash)
+            .collect();
+        assert_eq!(block_hashes, vec![tx.hash()]);
 
         // Add same transaction should fail
         mempool_handle.push(tx.clone()).await.unwrap();
@@ -748,7 +2654,13 @@ mod tests {
                 .block_store
                 .get_block_at_id(current_height)
                 .unwrap();
-            assert_eq!(block.body.transactions, vec![tx.clone()]);
+            let block_hashes: Vec<_> = block
+                .body
+                .transactions
+                .iter()
+                .map(EncodedTransaction::hash)
+                .collect();
+            assert_eq!(block_hashes, vec![tx.hash()]);
         }
 
         // Instantiating a new sequencer from the same config. This should load the existing block
@@ -760,11 +2672,2338 @@ mod tests {
         // Balances should be consistent with the stored block
         assert_eq!(
             balance_acc_1,
-            config.initial_accounts[0].balance - balance_to_move
+            Amount(config.initial_accounts[0].balance.0 - balance_to_move)
         );
         assert_eq!(
             balance_acc_2,
-            config.initial_accounts[1].balance + balance_to_move
+            Amount(config.initial_accounts[1].balance.0 + balance_to_move)
+        );
+
+        // Stored blocks are caught up with before the sequencer is ready
+        let sync_status = sequencer.sync_status();
+        assert_eq!(sync_status.state, SyncState::Synced);
+        assert_eq!(sync_status.start_height, config.genesis_id);
+        assert_eq!(sync_status.current_height, config.genesis_id + 1);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "doesn't match the stored one")]
+    async fn test_restart_detects_state_digest_mismatch() {
+        let config = setup_sequencer_config();
+        {
+            let (mut sequencer, _mempool_handle) = SequencerCore::start_from_config(config.clone());
+            sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        }
+
+        // Stored tip digest doesn't correspond to stored blocks anymore
+        {
+            let dbio =
+                storage::RocksDBIO::open_or_create(&config.home.join("rocksdb"), None).unwrap();
+            let mut batch = storage::DbWriteBatch::default();
+            dbio.stage_tip_state_digest(&mut batch, [0xff; 32]).unwrap();
+            dbio.commit(batch).unwrap();
+        }
+
+        SequencerCore::start_from_config(config);
+    }
+
+    #[tokio::test]
+    async fn test_replay_rebuilds_live_state_and_diff_pinpoints_corruption() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let live_snapshot = {
+            let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+            for nonce in 0..2 {
+                let tx = common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    nonce,
+                    *acc2.value(),
+                    10,
+                    create_signing_key_for_account1(),
+                );
+                mempool_handle.push(tx).await.unwrap();
+                sequencer
+                    .produce_new_block_with_mempool_transactions()
+                    .unwrap();
+            }
+            replay::StateSnapshot::from_state(sequencer.state(), sequencer.chain_height())
+        };
+
+        let mut replay = replay::Replay::open(&config, &config.home.join("rocksdb")).unwrap();
+        let mut replayed_blocks = vec![];
+        while let Some(block_id) = replay.apply_next_block().unwrap() {
+            replayed_blocks.push(block_id);
+        }
+        assert_eq!(replayed_blocks, vec![2, 3]);
+        assert_eq!(replay.snapshot(), live_snapshot);
+        assert!(replay.snapshot().diff(&live_snapshot).is_empty());
+
+        let mut corrupted_snapshot = live_snapshot.clone();
+        corrupted_snapshot
+            .accounts
+            .get_mut(&acc2.to_string())
+            .unwrap()
+            .balance
+            .0 += 1;
+
+        let diff = replay.snapshot().diff(&corrupted_snapshot);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].account_id, acc2.to_string());
+        assert_eq!(diff[0].ours.as_ref().unwrap().balance, Amount(20020));
+        assert_eq!(diff[0].theirs.as_ref().unwrap().balance, Amount(20021));
+    }
+
+    #[test]
+    fn test_latest_anchors_are_limited_by_window_of_next_block() {
+        let config = SequencerConfig {
+            anchor_window: 3,
+            ..setup_sequencer_config()
+        };
+        let (mut sequencer, _mempool_handle) = SequencerCore::start_from_config(config);
+        for _ in 0..4 {
+            sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        }
+
+        let anchors = sequencer.latest_anchors();
+        assert_eq!(
+            anchors
+                .iter()
+                .map(|anchor| anchor.block_id)
+                .collect::<Vec<_>>(),
+            vec![6, 5, 4]
+        );
+        assert_eq!(anchors[0].digest, sequencer.state().commitment_set_digest());
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_indexes_are_detected_and_rebuilt() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        let mut tx_hashes = vec![];
+        for nonce in 0..2 {
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                nonce,
+                *acc2.value(),
+                10,
+                create_signing_key_for_account1(),
+            );
+            tx_hashes.push(tx.hash());
+            mempool_handle.push(tx).await.unwrap();
+            sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        }
+        assert_eq!(sequencer.sample_indexes(16).unwrap(), 0);
+        let report = sequencer.rebuild_indexes().unwrap();
+        assert!(report.is_consistent());
+        assert_eq!((report.last_block, report.scanned_blocks), (3, 2));
+
+        let indexes = sequencer.block_store.indexes_mut();
+        indexes.tx_hash_to_block_map.remove(&tx_hashes[0]);
+        indexes
+            .receipts
+            .insert(tx_hashes[1], TransactionReceipt::default());
+        assert!(
+            sequencer
+                .block_store
+                .get_transaction_by_hash(tx_hashes[0])
+                .is_none()
+        );
+        // Every sampled block has a corrupted entry
+        assert!(sequencer.sample_indexes(16).unwrap() > 0);
+
+        let report = sequencer.rebuild_indexes().unwrap();
+        assert_eq!(report.tx_hashes.missing, 1);
+        assert_eq!(report.receipts.mismatched, 1);
+        assert!(report.address_txs.is_empty());
+        assert!(report.account_activity.is_empty());
+        assert!(
+            sequencer
+                .block_store
+                .get_transaction_by_hash(tx_hashes[0])
+                .is_some()
+        );
+        assert_ne!(
+            sequencer.block_store.get_receipt(&tx_hashes[1]),
+            Some(&TransactionReceipt::default())
+        );
+        assert_eq!(sequencer.sample_indexes(16).unwrap(), 0);
+        assert!(sequencer.rebuild_indexes().unwrap().is_consistent());
+    }
+
+    #[tokio::test]
+    async fn test_indexes_are_rebuilt_in_batches_with_blocks_produced_meanwhile() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        let transfer = |nonce| {
+            common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                nonce,
+                *acc2.value(),
+                10,
+                create_signing_key_for_account1(),
+            )
+        };
+        for nonce in 0..3 {
+            mempool_handle.push(transfer(nonce)).await.unwrap();
+            sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        }
+
+        let mut rebuild = sequencer.start_index_rebuild().unwrap();
+        let blocks = sequencer.blocks_to_index(&rebuild, 2).unwrap();
+        assert_eq!(blocks.len(), 2);
+        for block in &blocks {
+            rebuild.apply_block(block).unwrap();
+        }
+        // Blocks out of order are refused
+        assert!(rebuild.apply_block(&blocks[0]).is_err());
+
+        mempool_handle.push(transfer(3)).await.unwrap();
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        let report = sequencer.finish_index_rebuild(rebuild).unwrap();
+        assert!(report.is_consistent());
+        assert_eq!((report.last_block, report.scanned_blocks), (5, 4));
+    }
+
+    #[tokio::test]
+    async fn test_exported_chain_is_imported_with_the_same_tip_state() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+        for nonce in 0..99 {
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                nonce,
+                *acc2.value(),
+                10,
+                create_signing_key_for_account1(),
+            );
+            mempool_handle.push(tx).await.unwrap();
+            sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        }
+        assert_eq!(sequencer.chain_height(), 100);
+
+        let mut archive = vec![];
+        let last_block_id =
+            chain_archive::export_chain(sequencer.block_store(), &mut archive).unwrap();
+        assert_eq!(last_block_id, 100);
+
+        let import_dir = tempfile::tempdir().unwrap();
+        let mut corrupted = archive.clone();
+        corrupted[archive.len() / 2] ^= 1;
+        assert!(
+            chain_archive::import_chain(
+                &config,
+                corrupted.as_slice(),
+                &import_dir.path().join("corrupted"),
+                true,
+            )
+            .is_err()
+        );
+
+        let db_path = import_dir.path().join("rocksdb");
+        let summary =
+            chain_archive::import_chain(&config, archive.as_slice(), &db_path, true).unwrap();
+        assert_eq!(summary.last_block_id, 100);
+        assert_eq!(
+            summary.last_block_hash,
+            sequencer
+                .block_store()
+                .get_block_header_at_id(100)
+                .unwrap()
+                .hash
+        );
+
+        let mut replay = replay::Replay::open(&config, &db_path).unwrap();
+        while replay.apply_next_block().unwrap().is_some() {}
+        assert_eq!(replay.last_block_id(), 100);
+        assert_eq!(replay.state().state_root(), sequencer.state().state_root());
+    }
+
+    #[tokio::test]
+    async fn test_total_supply_is_conserved_by_produced_block() {
+        let config = setup_sequencer_config();
+        let acc1_account_id: nssa::AccountId =
+            config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2_account_id: nssa::AccountId =
+            config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        let total_supply = sequencer.state_invariants().total_supply();
+        assert_eq!(total_supply, Amount(10000 + 20000));
+
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *acc1_account_id.value(),
+            0,
+            *acc2_account_id.value(),
+            100,
+            create_signing_key_for_account1(),
+        );
+        mempool_handle.push(tx).await.unwrap();
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+
+        assert_eq!(sequencer.state_invariants().total_supply(), total_supply);
+        sequencer
+            .state_invariants()
+            .clone()
+            .check_state(sequencer.state())
+            .unwrap();
+    }
+
+    fn public_tx_signed_by(
+        message: nssa::public_transaction::Message,
+        signing_key: &PrivateKey,
+    ) -> EncodedTransaction {
+        let witness_set =
+            nssa::public_transaction::WitnessSet::for_message(&message, &[signing_key]);
+        NSSATransaction::Public(nssa::PublicTransaction::new(message, witness_set)).into()
+    }
+
+    fn deployment_tx_signed_by(
+        bytecode: Vec<u8>,
+        config: &SequencerConfig,
+        signing_key: &PrivateKey,
+    ) -> EncodedTransaction {
+        let deployer = nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(signing_key));
+        let message = nssa::program_deployment_transaction::Message::new(
+            deployer,
+            0,
+            config.chain_id,
+            Amount::MAX,
+            bytecode,
+        );
+        let witness_set =
+            nssa::program_deployment_transaction::WitnessSet::for_message(&message, signing_key);
+        NSSATransaction::ProgramDeployment(nssa::ProgramDeploymentTransaction::new(
+            message,
+            witness_set,
+        ))
+        .into()
+    }
+
+    fn transfer_message(
+        account_ids: Vec<nssa::AccountId>,
+        nonces: Vec<nssa_core::account::Nonce>,
+        amount: u128,
+    ) -> nssa::public_transaction::Message {
+        nssa::public_transaction::Message::try_new(
+            nssa::program::Program::authenticated_transfer_program().id(),
+            account_ids,
+            nonces,
+            amount,
+        )
+        .unwrap()
+    }
+
+    /// Valid transactions interleaved with ones failing every kind of check
+    fn mixed_transactions(config: &SequencerConfig) -> Vec<EncodedTransaction> {
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let sign_key1 = create_signing_key_for_account1();
+
+        let valid = common::test_utils::create_transaction_native_token_transfer(
+            *acc1.value(),
+            0,
+            *acc2.value(),
+            10,
+            sign_key1.clone(),
+        );
+
+        let invalid_signature = {
+            let message = transfer_message(vec![acc1, acc2], vec![1], 10);
+            let other_message = transfer_message(vec![acc1, acc2], vec![1], 11);
+            let witness_set =
+                nssa::public_transaction::WitnessSet::for_message(&other_message, &[&sign_key1]);
+            NSSATransaction::Public(nssa::PublicTransaction::new(message, witness_set)).into()
+        };
+
+        vec![
+            valid.clone(),
+            // Replay
+            valid,
+            invalid_signature,
+            public_tx_signed_by(transfer_message(vec![acc1, acc1], vec![1], 10), &sign_key1),
+            public_tx_signed_by(
+                transfer_message(vec![acc1, acc2], vec![1, 0], 10),
+                &sign_key1,
+            ),
+            common::test_utils::produce_dummy_empty_transaction(),
+            common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                1,
+                *acc2.value(),
+                20,
+                sign_key1,
+            ),
+            // Not signed by sender
+            common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                2,
+                *acc2.value(),
+                30,
+                create_signing_key_for_account2(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_parallel_pre_check_matches_sequential_validation() {
+        let sequential_config = setup_sequencer_config();
+        let parallel_config = setup_sequencer_config();
+        let txs = mixed_transactions(&sequential_config);
+
+        let (mut sequential, _mempool_handle) = SequencerCore::start_from_config(sequential_config);
+        let sequential_decisions: Vec<bool> = txs
+            .iter()
+            .map(|tx| {
+                let tx = parse_unwrap_tx_body_into_nssa_tx(tx.clone());
+                sequential.execute_check_transaction_on_state(tx).is_ok()
+            })
+            .collect();
+
+        let (mut parallel, _mempool_handle) = SequencerCore::start_from_config(parallel_config);
+        let parallel_decisions: Vec<bool> = pre_check_transactions(&txs)
+            .into_iter()
+            .map(|tx| {
+                tx.is_ok_and(|tx| parallel.execute_prechecked_transaction_on_state(tx).is_ok())
+            })
+            .collect();
+
+        assert_eq!(parallel_decisions, sequential_decisions);
+        assert_eq!(
+            parallel_decisions,
+            vec![true, false, false, false, false, false, true, false]
+        );
+        for initial_account in &parallel.sequencer_config.initial_accounts {
+            let account_id = initial_account.account_id.parse().unwrap();
+            assert_eq!(
+                parallel.state.get_account_by_id(&account_id),
+                sequential.state.get_account_by_id(&account_id)
+            );
+        }
+    }
+
+    #[test]
+    fn test_independent_transfers_are_valid_in_one_block() {
+        use common::test_utils::{
+            account_id_for_index, independent_native_transfers, state_with_funded_accounts,
+        };
+        let mut state = state_with_funded_accounts(10, Amount(100));
+        let txs = independent_native_transfers(10, 5);
+
+        for tx in pre_check_transactions(&txs) {
+            execute_prechecked_transaction(&mut state, tx.unwrap()).unwrap();
+        }
+
+        // Every account sent 5 and, except the first one, received 5
+        assert_eq!(
+            state.get_account_by_id(&account_id_for_index(0)).balance,
+            Amount(95)
+        );
+        assert_eq!(
+            state.get_account_by_id(&account_id_for_index(5)).balance,
+            Amount(100)
+        );
+        assert_eq!(
+            state.get_account_by_id(&account_id_for_index(10)).balance,
+            Amount(5)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pending_transaction_summaries_follow_ordering_policy() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        let cheap = public_tx_signed_by(
+            transfer_message(vec![acc1, acc2], vec![0], 100).with_priority_fee(Amount(1)),
+            &create_signing_key_for_account1(),
+        );
+        let expensive = public_tx_signed_by(
+            transfer_message(vec![acc2, acc1], vec![0], 100).with_priority_fee(Amount(10)),
+            &create_signing_key_for_account2(),
+        );
+        for tx in [cheap.clone(), expensive.clone()] {
+            mempool_handle.push(tx).await.unwrap();
+        }
+
+        let summary = |tx: &EncodedTransaction, sender: nssa::AccountId, priority_fee| {
+            PendingTransactionSummary {
+                hash: hex::encode(tx.hash()),
+                sender: Some(sender.to_string()),
+                nonce: Some(0),
+                priority_fee,
+            }
+        };
+        assert_eq!(
+            sequencer.pending_transaction_summaries(),
+            vec![
+                summary(&expensive, acc2, Amount(10)),
+                summary(&cheap, acc1, Amount(1))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_priority_fees_order_block_and_are_paid_to_fee_recipient() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let fee_recipient = config.fee_recipient();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        let txs = [
+            public_tx_signed_by(
+                transfer_message(vec![acc1, acc2], vec![0], 100).with_priority_fee(Amount(1)),
+                &create_signing_key_for_account1(),
+            ),
+            public_tx_signed_by(
+                transfer_message(vec![acc2, acc1], vec![0], 100).with_priority_fee(Amount(10)),
+                &create_signing_key_for_account2(),
+            ),
+            // Doesn't overtake the previous transaction of the same sender
+            public_tx_signed_by(
+                transfer_message(vec![acc1, acc2], vec![1], 100).with_priority_fee(Amount(50)),
+                &create_signing_key_for_account1(),
+            ),
+        ];
+        for tx in txs.iter().cloned() {
+            mempool_handle.push(tx).await.unwrap();
+        }
+        let current_height = sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        let block = sequencer
+            .block_store
+            .get_block_at_id(current_height)
+            .unwrap();
+
+        assert_eq!(
+            block.body.transactions,
+            vec![txs[1].clone(), txs[0].clone(), txs[2].clone()]
+        );
+        assert_eq!(
+            sequencer.state.get_account_by_id(&acc1).balance,
+            Amount(10000 - 200 - 1 - 50 + 100)
+        );
+        assert_eq!(
+            sequencer.state.get_account_by_id(&acc2).balance,
+            Amount(20000 + 200 - 100 - 10)
+        );
+        assert_eq!(
+            sequencer.state.get_account_by_id(&fee_recipient).balance,
+            Amount(61)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_receipt_breaks_down_balance_changes_and_survives_restart() {
+        use common::receipt::{BalanceComponent, BalanceComponentKind, BalanceSign};
+
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let fee_recipient = config.fee_recipient();
+        let tx = public_tx_signed_by(
+            transfer_message(vec![acc1, acc2], vec![0], 100).with_priority_fee(Amount(5)),
+            &create_signing_key_for_account1(),
+        );
+
+        let receipt = {
+            let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+            mempool_handle.push(tx.clone()).await.unwrap();
+            let block_id = sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+            let TransactionStatus::Included {
+                block_id: included,
+                receipt: Some(receipt),
+            } = sequencer.transaction_status(&tx.hash())
+            else {
+                panic!("Transaction isn't included with a receipt");
+            };
+            assert_eq!(included, block_id);
+            receipt
+        };
+
+        let component = |kind, amount, sign| BalanceComponent {
+            kind,
+            amount: Amount(amount),
+            sign,
+        };
+        let changes = receipt
+            .balance_changes
+            .iter()
+            .map(|change| {
+                assert!(change.is_balanced());
+                (
+                    change.account_id.clone(),
+                    change.pre_balance,
+                    change.post_balance,
+                    change.components.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    acc1.to_string(),
+                    Amount(10000),
+                    Amount(10000 - 100 - 5),
+                    vec![
+                        component(BalanceComponentKind::Transfer, 100, BalanceSign::Debit),
+                        component(BalanceComponentKind::Priority, 5, BalanceSign::Debit),
+                    ]
+                ),
+                (
+                    acc2.to_string(),
+                    Amount(20000),
+                    Amount(20000 + 100),
+                    vec![component(
+                        BalanceComponentKind::Transfer,
+                        100,
+                        BalanceSign::Credit
+                    )]
+                ),
+                (
+                    fee_recipient.to_string(),
+                    Amount(0),
+                    Amount(5),
+                    vec![component(
+                        BalanceComponentKind::Priority,
+                        5,
+                        BalanceSign::Credit
+                    )]
+                ),
+            ]
+        );
+
+        // Receipts are rebuilt by replaying stored blocks
+        let (mut sequencer, _mempool_handle) = SequencerCore::start_from_config(config);
+        assert!(matches!(
+            sequencer.transaction_status(&tx.hash()),
+            TransactionStatus::Included { receipt: Some(replayed), .. } if replayed == receipt
+        ));
+    }
+
+    fn time_locked_transfer(config: &SequencerConfig, valid_from_block: u64) -> EncodedTransaction {
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        public_tx_signed_by(
+            transfer_message(vec![acc1, acc2], vec![0], 100)
+                .with_valid_from_block(Some(valid_from_block)),
+            &create_signing_key_for_account1(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_time_locks_are_bounded_and_parked_per_sender() {
+        let config = SequencerConfig {
+            mempool_max_size: 1,
+            max_time_lock_blocks: 10,
+            max_parked_per_sender: 2,
+            ..setup_sequencer_config()
+        };
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+
+        // Next block is 2, so transactions may be locked until block 12
+        let beyond_horizon = pre_check_transaction(&time_locked_transfer(&config, 13)).unwrap();
+        assert!(matches!(
+            sequencer.check_admission(&beyond_horizon),
+            Err(TransactionMalformationError::TimeLockTooLong {
+                valid_from_block: 13,
+                max_valid_from_block: 12,
+            })
+        ));
+        let at_horizon = pre_check_transaction(&time_locked_transfer(&config, 12)).unwrap();
+        assert!(sequencer.check_admission(&at_horizon).is_ok());
+
+        let parked =
+            [5, 6, 7].map(|valid_from_block| time_locked_transfer(&config, valid_from_block));
+        for tx in &parked {
+            mempool_handle.push(tx.clone()).await.unwrap();
+            sequencer.take_transactions_from_mempool();
+        }
+        for tx in &parked[..2] {
+            assert!(matches!(
+                sequencer.transaction_status(&tx.hash()),
+                TransactionStatus::Pending { .. }
+            ));
+        }
+        assert!(matches!(
+            sequencer.transaction_status(&parked[2].hash()),
+            TransactionStatus::Dropped { .. }
+        ));
+
+        // Parked transactions don't take room of pending ones
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *acc2.value(),
+            0,
+            *acc1.value(),
+            10,
+            create_signing_key_for_account2(),
+        );
+        mempool_handle.push(tx.clone()).await.unwrap();
+        assert!(matches!(
+            sequencer.transaction_status(&tx.hash()),
+            TransactionStatus::Pending { position: 0, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_time_locked_transaction_is_included_from_its_valid_from_block() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let tx = time_locked_transfer(&config, 6);
+        // Parked transaction doesn't hold back other transactions
+        let other_tx = common::test_utils::create_transaction_native_token_transfer(
+            *acc2.value(),
+            0,
+            *acc1.value(),
+            10,
+            create_signing_key_for_account2(),
+        );
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        for _ in 0..2 {
+            sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        }
+        assert_eq!(sequencer.chain_height(), 3);
+
+        mempool_handle.push(tx.clone()).await.unwrap();
+        mempool_handle.push(other_tx.clone()).await.unwrap();
+
+        for block_id in 4..6 {
+            assert_eq!(
+                sequencer
+                    .produce_new_block_with_mempool_transactions()
+                    .unwrap(),
+                block_id
+            );
+            assert_eq!(
+                sequencer.transaction_status(&tx.hash()),
+                TransactionStatus::Pending {
+                    position: 0,
+                    depth: 1,
+                    estimated_inclusion_block: 6,
+                    estimated_wait_millis: (6 - block_id)
+                        * sequencer.sequencer_config.block_create_timeout_millis,
+                }
+            );
+        }
+        assert!(matches!(
+            sequencer.transaction_status(&other_tx.hash()),
+            TransactionStatus::Included { block_id: 4, .. }
+        ));
+
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        assert!(matches!(
+            sequencer.transaction_status(&tx.hash()),
+            TransactionStatus::Included { block_id: 6, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_admission_checks_rules_of_native_transfers() {
+        let rate = Amount(1);
+        let config = SequencerConfig {
+            storage_deposit_per_byte: rate,
+            ..setup_sequencer_config()
+        };
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let new_account = nssa::AccountId::new([7; 32]);
+        let min_amount = nssa::public_transaction::min_account_creation_amount(rate);
+        let (sequencer, _mempool_handle) = SequencerCore::start_from_config(config);
+        let transfer = |to: nssa::AccountId, amount: u128| {
+            common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                0,
+                *to.value(),
+                amount,
+                create_signing_key_for_account1(),
+            )
+        };
+
+        // Zero-amount transfers are admitted
+        let zero = pre_check_transaction(&transfer(acc2, 0)).unwrap();
+        assert!(sequencer.check_admission(&zero).is_ok());
+
+        assert!(matches!(
+            pre_check_transaction(&transfer(acc1, 10)),
+            Err(TransactionMalformationError::InvalidTransfer { .. })
+        ));
+
+        let below_minimum =
+            pre_check_transaction(&transfer(new_account, min_amount.0 - 1)).unwrap();
+        assert!(matches!(
+            sequencer.check_admission(&below_minimum),
+            Err(TransactionMalformationError::InvalidTransfer { .. })
+        ));
+        let at_minimum = pre_check_transaction(&transfer(new_account, min_amount.0)).unwrap();
+        assert!(sequencer.check_admission(&at_minimum).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_block_with_early_time_locked_transaction_is_rejected() {
+        let config = setup_sequencer_config();
+        {
+            let (mut sequencer, _mempool_handle) = SequencerCore::start_from_config(config.clone());
+            for _ in 0..2 {
+                sequencer
+                    .produce_new_block_with_mempool_transactions()
+                    .unwrap();
+            }
+
+            // Block 4 includes transaction valid from block 6
+            let prev_block_header = sequencer.block_store.get_block_at_id(3).unwrap().header;
+            let block = HashableBlockData {
+                block_id: 4,
+                prev_block_hash: prev_block_header.hash,
+                timestamp: prev_block_header.timestamp,
+                transactions: vec![time_locked_transfer(&config, 6)],
+                params_version: 0,
+                dev_proved: false,
+            }
+            .into_block(sequencer.block_store.signing_key());
+            sequencer.block_store.put_block_at_id(block).unwrap();
+        }
+
+        let mut replay = replay::Replay::open(&config, &config.home.join("rocksdb")).unwrap();
+        assert_eq!(replay.apply_next_block().unwrap(), Some(2));
+        assert_eq!(replay.apply_next_block().unwrap(), Some(3));
+        let err = replay.apply_next_block().unwrap_err();
+        assert!(err.to_string().contains("in block 4 failed to execute"));
+    }
+
+    /// Stores block 3 with `timestamp` after block 2 produced by the sequencer and replays them
+    fn replay_block_with_timestamp(timestamp: impl FnOnce(u64) -> u64) -> Result<Option<u64>> {
+        let config = setup_sequencer_config();
+        {
+            let (mut sequencer, _mempool_handle) = SequencerCore::start_from_config(config.clone());
+            sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+
+            let prev_block_header = sequencer.block_store.get_block_at_id(2).unwrap().header;
+            let block = HashableBlockData {
+                block_id: 3,
+                prev_block_hash: prev_block_header.hash,
+                timestamp: timestamp(prev_block_header.timestamp),
+                transactions: vec![],
+                params_version: 0,
+                dev_proved: false,
+            }
+            .into_block(sequencer.block_store.signing_key());
+            sequencer.block_store.put_block_at_id(block).unwrap();
+        }
+
+        let mut replay = replay::Replay::open(&config, &config.home.join("rocksdb")).unwrap();
+        assert_eq!(replay.apply_next_block().unwrap(), Some(2));
+        replay.apply_next_block()
+    }
+
+    #[tokio::test]
+    async fn test_block_stamped_in_future_is_rejected() {
+        let hour_millis = 60 * 60 * 1000;
+        let err =
+            replay_block_with_timestamp(|prev_timestamp| prev_timestamp + hour_millis).unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(BlockTimestampError::InFuture { .. })
+        ));
+        assert!(err.to_string().contains("Block 3 is rejected"));
+    }
+
+    #[tokio::test]
+    async fn test_block_stamped_before_previous_block_is_rejected() {
+        let err = replay_block_with_timestamp(|prev_timestamp| prev_timestamp - 1).unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(BlockTimestampError::BeforePrevious { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_block_stamped_within_clock_skew_is_accepted() {
+        let result =
+            replay_block_with_timestamp(|_| chrono::Utc::now().timestamp_millis() as u64 + 10_000);
+
+        assert_eq!(result.unwrap(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_block_timestamps_dont_decrease_if_clock_goes_backwards() {
+        let (mut sequencer, _mempool_handle) = common_setup().await;
+        let prev_block = sequencer.block_store.get_block_at_id(2).unwrap();
+        // Previous block is stamped ahead of the clock, as if the clock went backwards since
+        let future_timestamp = chrono::Utc::now().timestamp_millis() as u64 + 60_000;
+        let block = HashableBlockData {
+            block_id: 2,
+            prev_block_hash: prev_block.header.prev_block_hash,
+            timestamp: future_timestamp,
+            transactions: prev_block.body.transactions,
+            params_version: 0,
+            dev_proved: false,
+        }
+        .into_block(sequencer.block_store.signing_key());
+        sequencer.block_store.put_block_at_id(block).unwrap();
+
+        let block_id = sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+
+        let block = sequencer.block_store.get_block_at_id(block_id).unwrap();
+        assert_eq!(block.header.timestamp, future_timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_produced_block_keeps_mempool_order_of_valid_transactions() {
+        let config = setup_sequencer_config();
+        let txs = mixed_transactions(&config);
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        for tx in txs.iter().cloned() {
+            mempool_handle.push(tx).await.unwrap();
+        }
+        let current_height = sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        let block = sequencer
+            .block_store
+            .get_block_at_id(current_height)
+            .unwrap();
+
+        assert_eq!(
+            block.body.transactions,
+            vec![txs[0].clone(), txs[6].clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_block_contents_do_not_depend_on_arrival_order() {
+        let mut blocks = vec![];
+
+        for reverse_arrival in [false, true] {
+            let config = setup_sequencer_config();
+            let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+            let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+            let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+            let mut txs: Vec<_> = (0..3)
+                .map(|nonce| {
+                    common::test_utils::create_transaction_native_token_transfer(
+                        *acc1.value(),
+                        nonce,
+                        *acc2.value(),
+                        10 + u128::from(nonce),
+                        create_signing_key_for_account1(),
+                    )
+                })
+                .collect();
+            if reverse_arrival {
+                txs.reverse();
+            }
+            for tx in txs {
+                mempool_handle.push(tx).await.unwrap();
+            }
+
+            let current_height = sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+            let block = sequencer
+                .block_store
+                .get_block_at_id(current_height)
+                .unwrap();
+            blocks.push(block.body.transactions);
+        }
+
+        // All transactions are included in nonce order
+        assert_eq!(blocks[0].len(), 3);
+        assert_eq!(blocks[0], blocks[1]);
+    }
+
+    #[tokio::test]
+    async fn test_flooding_sender_does_not_starve_others() {
+        let config = SequencerConfig {
+            max_consecutive_per_sender: Some(4),
+            ..setup_sequencer_config()
+        };
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        let transfer = |from: nssa::AccountId, to: nssa::AccountId, key, nonce| {
+            common::test_utils::create_transaction_native_token_transfer(
+                *from.value(),
+                nonce,
+                *to.value(),
+                1,
+                key,
+            )
+        };
+        for nonce in 0..500 {
+            let tx = transfer(acc1, acc2, create_signing_key_for_account1(), nonce);
+            mempool_handle.push(tx).await.unwrap();
+        }
+        let acc2_txs: Vec<_> = (0..5)
+            .map(|nonce| transfer(acc2, acc1, create_signing_key_for_account2(), nonce))
+            .collect();
+        for tx in acc2_txs.iter().cloned() {
+            mempool_handle.push(tx).await.unwrap();
+        }
+
+        let mut included = vec![];
+        for _ in 0..2 {
+            let block_id = sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+            included.extend(
+                sequencer
+                    .block_store
+                    .get_block_at_id(block_id)
+                    .unwrap()
+                    .body
+                    .transactions,
+            );
+        }
+
+        for tx in &acc2_txs {
+            assert!(included.contains(tx));
+        }
+    }
+
+    fn system_lane_config(queue_size: usize) -> SequencerConfig {
+        SequencerConfig {
+            system_lane: Some(SystemLaneConfig {
+                auth_token: "admin".to_string(),
+                queue_size,
+                block_share_percent: 5,
+            }),
+            ..setup_sequencer_config()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_system_transaction_lands_in_next_block_under_flood() {
+        let config = system_lane_config(10);
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        // Flood fills several blocks, so the last submitted transaction would wait for them
+        for nonce in 0..30 {
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                nonce,
+                *acc2.value(),
+                1,
+                create_signing_key_for_account1(),
+            );
+            mempool_handle.push(tx).await.unwrap();
+        }
+        sequencer.take_transactions_from_mempool();
+
+        let faucet_tx = common::test_utils::create_transaction_native_token_transfer(
+            *acc2.value(),
+            0,
+            *acc1.value(),
+            100,
+            create_signing_key_for_account2(),
+        );
+        let hash = faucet_tx.hash();
+        let faucet_tx = pre_check_transaction(&faucet_tx).unwrap();
+        assert_eq!(
+            sequencer.submit_system_transaction(hash, faucet_tx.clone()),
+            Ok(true)
+        );
+        assert!(matches!(
+            sequencer.transaction_status(&hash),
+            TransactionStatus::Pending { position: 0, .. }
+        ));
+        // Resubmission is idempotent
+        assert_eq!(
+            sequencer.submit_system_transaction(hash, faucet_tx),
+            Ok(false)
+        );
+
+        let block_id = sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        let block = sequencer.block_store.get_block_at_id(block_id).unwrap();
+
+        assert_eq!(block.body.transactions.len(), 10);
+        assert_eq!(block.body.transactions[0].hash(), hash);
+        assert!(sequencer.system_transactions.is_empty());
+        assert_eq!(sequencer.pending_transactions.len(), 21);
+    }
+
+    #[tokio::test]
+    async fn test_system_lane_is_bounded_and_disabled_by_default() {
+        let config = system_lane_config(1);
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let txs: Vec<_> = (0..2)
+            .map(|nonce| {
+                let tx = common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    nonce,
+                    *acc2.value(),
+                    10,
+                    create_signing_key_for_account1(),
+                );
+                (tx.hash(), pre_check_transaction(&tx).unwrap())
+            })
+            .collect();
+
+        let (mut sequencer, _mempool_handle) = SequencerCore::start_from_config(config);
+        assert_eq!(
+            sequencer.submit_system_transaction(txs[0].0, txs[0].1.clone()),
+            Ok(true)
+        );
+        assert_eq!(
+            sequencer.submit_system_transaction(txs[1].0, txs[1].1.clone()),
+            Err(SystemLaneError::Full { queue_size: 1 })
+        );
+
+        let (mut sequencer, _mempool_handle) =
+            SequencerCore::start_from_config(setup_sequencer_config());
+        assert_eq!(
+            sequencer.submit_system_transaction(txs[0].0, txs[0].1.clone()),
+            Err(SystemLaneError::Disabled)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transactions_beyond_pending_per_sender_limit_are_dropped() {
+        let config = SequencerConfig {
+            max_pending_per_sender: Some(3),
+            ..setup_sequencer_config()
+        };
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        let txs: Vec<_> = (0..5)
+            .map(|nonce| {
+                common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    nonce,
+                    *acc2.value(),
+                    10,
+                    create_signing_key_for_account1(),
+                )
+            })
+            .collect();
+        for tx in txs.iter().cloned() {
+            mempool_handle.push(tx).await.unwrap();
+        }
+        sequencer.take_transactions_from_mempool();
+
+        assert_eq!(sequencer.pending_transactions.len(), 3);
+        for tx in &txs[3..] {
+            assert!(matches!(
+                sequencer.transaction_status(&tx.hash()),
+                TransactionStatus::Dropped { .. }
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transactions_not_fitting_into_block_are_kept_for_next_one() {
+        let config = SequencerConfig {
+            max_num_tx_in_block: 1,
+            ..setup_sequencer_config()
+        };
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        let txs: Vec<_> = (0..2)
+            .map(|nonce| {
+                common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    nonce,
+                    *acc2.value(),
+                    10,
+                    create_signing_key_for_account1(),
+                )
+            })
+            .collect();
+        for tx in txs.iter().cloned() {
+            mempool_handle.push(tx).await.unwrap();
+        }
+
+        for tx in txs {
+            let current_height = sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+            let block = sequencer
+                .block_store
+                .get_block_at_id(current_height)
+                .unwrap();
+            assert_eq!(block.body.transactions, vec![tx]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transaction_status_estimates_inclusion_block() {
+        let config = setup_sequencer_config();
+        let max_num_tx_in_block = config.max_num_tx_in_block;
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        let tip = sequencer.chain_height();
+
+        // One transaction more than fits into a block
+        let txs: Vec<_> = (0..=max_num_tx_in_block as u64)
+            .map(|nonce| {
+                common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    nonce,
+                    *acc2.value(),
+                    10,
+                    create_signing_key_for_account1(),
+                )
+            })
+            .collect();
+        for tx in txs.iter().cloned() {
+            mempool_handle.push(tx).await.unwrap();
+        }
+
+        let first = txs.first().unwrap().hash();
+        let overflow = txs.last().unwrap().hash();
+        assert_eq!(
+            sequencer.transaction_status(&first),
+            TransactionStatus::Pending {
+                position: 0,
+                depth: txs.len() as u64,
+                estimated_inclusion_block: tip + 1,
+                estimated_wait_millis: 1000,
+            }
+        );
+        assert_eq!(
+            sequencer.transaction_status(&overflow),
+            TransactionStatus::Pending {
+                position: max_num_tx_in_block as u64,
+                depth: txs.len() as u64,
+                estimated_inclusion_block: tip + 2,
+                estimated_wait_millis: 2000,
+            }
+        );
+
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        assert!(matches!(
+            sequencer.transaction_status(&first),
+            TransactionStatus::Included { block_id: included, .. } if included == tip + 1
+        ));
+        assert_eq!(
+            sequencer.transaction_status(&overflow),
+            TransactionStatus::Pending {
+                position: 0,
+                depth: 1,
+                estimated_inclusion_block: tip + 2,
+                estimated_wait_millis: 1000,
+            }
+        );
+        assert_eq!(
+            sequencer.transaction_status(&[0; 32]),
+            TransactionStatus::Unknown
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_transaction_is_reported_as_dropped() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        // Nonce is too high, so execution fails
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *acc1.value(),
+            5,
+            *acc2.value(),
+            10,
+            create_signing_key_for_account1(),
+        );
+        mempool_handle.push(tx.clone()).await.unwrap();
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+
+        assert!(matches!(
+            sequencer.transaction_status(&tx.hash()),
+            TransactionStatus::Dropped { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tx_audit_records_admission_and_eviction() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        // Nonce is too high, so the admitted transaction is evicted from the block
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *acc1.value(),
+            5,
+            *acc2.value(),
+            10,
+            create_signing_key_for_account1(),
+        );
+        assert!(sequencer.tx_audit(&tx.hash()).is_empty());
+        mempool_handle.push(tx.clone()).await.unwrap();
+        // Newly arrived transaction is admitted on query
+        let events = sequencer.tx_audit(&tx.hash());
+        assert_eq!(
+            events[0].event,
+            TxAuditEvent::Admitted {
+                priority_fee: Amount::ZERO,
+                position: 0
+            }
+        );
+
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+
+        let events = sequencer.tx_audit(&tx.hash());
+        assert_eq!(events.len(), 2);
+        let TxAuditEvent::Evicted { reason } = &events[1].event else {
+            panic!("Failed transaction is not evicted");
+        };
+        assert_eq!(
+            sequencer.transaction_status(&tx.hash()),
+            TransactionStatus::Dropped {
+                reason: reason.clone()
+            }
+        );
+        assert!(events[0].timestamp_millis <= events[1].timestamp_millis);
+    }
+
+    #[tokio::test]
+    async fn test_tx_audit_records_replacement_and_inclusion() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        let tip = sequencer.chain_height();
+
+        // Transfers of the same nonce, only the first one can be applied
+        let txs: Vec<_> = [10, 20]
+            .into_iter()
+            .map(|amount| {
+                common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    0,
+                    *acc2.value(),
+                    amount,
+                    create_signing_key_for_account1(),
+                )
+            })
+            .collect();
+        for tx in txs.iter().cloned() {
+            mempool_handle.push(tx).await.unwrap();
+        }
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+
+        let events = |sequencer: &mut SequencerCore, hash| {
+            sequencer
+                .tx_audit(&hash)
+                .into_iter()
+                .map(|record| record.event)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            events(&mut sequencer, txs[0].hash()),
+            [
+                TxAuditEvent::Admitted {
+                    priority_fee: Amount::ZERO,
+                    position: 0
+                },
+                TxAuditEvent::Included { block_id: tip + 1 }
+            ]
+        );
+        assert_eq!(
+            events(&mut sequencer, txs[1].hash()),
+            [
+                TxAuditEvent::Admitted {
+                    priority_fee: Amount::ZERO,
+                    position: 1
+                },
+                TxAuditEvent::Replaced {
+                    by: hex::encode(txs[0].hash())
+                }
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transactions_exceeding_unreserved_balance_are_rejected() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        let transfer = |nonce, amount| {
+            common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                nonce,
+                *acc2.value(),
+                amount,
+                create_signing_key_for_account1(),
+            )
+        };
+
+        // Each transfer fits into the balance of 10000, both of them don't
+        let first = transfer(0, 6000);
+        let second = transfer(1, 6000);
+        for tx in [&first, &second] {
+            mempool_handle.push(tx.clone()).await.unwrap();
+        }
+        sequencer.take_transactions_from_mempool();
+
+        assert_eq!(sequencer.pending_transactions.len(), 1);
+        let TransactionStatus::Dropped { reason } = sequencer.transaction_status(&second.hash())
+        else {
+            panic!("Transaction exceeding unreserved balance is not dropped");
+        };
+        assert!(reason.contains("Insufficient unreserved balance"));
+        let shortfall = sequencer
+            .check_balance_reservation(&pre_check_transaction(&second).unwrap())
+            .unwrap_err();
+        assert_eq!(
+            (shortfall.balance, shortfall.reserved, shortfall.required),
+            (Amount(10000), Amount(6000), Amount(6000))
+        );
+        assert_eq!(
+            shortfall.pending,
+            vec![BalanceReservation {
+                hash: hex::encode(first.hash()),
+                amount: Amount(6000),
+            }]
+        );
+
+        // Reservation is released once the transaction is included
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        assert_eq!(
+            sequencer
+                .check_balance_reservation(&pre_check_transaction(&transfer(1, 4000)).unwrap()),
+            Ok(())
+        );
+        assert!(
+            sequencer
+                .check_balance_reservation(&pre_check_transaction(&transfer(1, 4001)).unwrap())
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_malformed_instructions_are_rejected_at_admission() {
+        let config = SequencerConfig {
+            max_instruction_data_size: 8,
+            ..setup_sequencer_config()
+        };
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        let signing_key = create_signing_key_for_account1();
+
+        // Transfer addresses the recipient as the second account, which isn't passed
+        let missing_recipient =
+            public_tx_signed_by(transfer_message(vec![acc1], vec![0], 10), &signing_key);
+        let oversized = public_tx_signed_by(
+            nssa::public_transaction::Message::try_new(
+                nssa::program::Program::authenticated_transfer_program().id(),
+                vec![acc1, acc2],
+                vec![0],
+                [10_u128; 3],
+            )
+            .unwrap(),
+            &signing_key,
+        );
+        for tx in [&missing_recipient, &oversized] {
+            mempool_handle.push(tx.clone()).await.unwrap();
+        }
+        sequencer.take_transactions_from_mempool();
+
+        // Nothing is left for execution
+        assert!(sequencer.pending_transactions.is_empty());
+        let TransactionStatus::Dropped { reason } =
+            sequencer.transaction_status(&missing_recipient.hash())
+        else {
+            panic!("Transaction addressing a missing account is not dropped");
+        };
+        assert!(reason.contains("InvalidInstruction"));
+        assert!(reason.contains("addresses account 1"));
+        let TransactionStatus::Dropped { reason } = sequencer.transaction_status(&oversized.hash())
+        else {
+            panic!("Oversized transaction is not dropped");
+        };
+        assert!(reason.contains("exceeds the maximum of 8"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_programs_are_rejected_at_admission() {
+        let config = SequencerConfig {
+            max_program_size: 4096,
+            ..setup_sequencer_config()
+        };
+        let signing_key = create_signing_key_for_account1();
+        let garbage = deployment_tx_signed_by(vec![0; 3000], &config, &signing_key);
+        let oversized = deployment_tx_signed_by(vec![0; 5000], &config, &signing_key);
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        for tx in [&garbage, &oversized] {
+            mempool_handle.push(tx.clone()).await.unwrap();
+        }
+        sequencer.take_transactions_from_mempool();
+
+        assert!(sequencer.pending_transactions.is_empty());
+        let TransactionStatus::Dropped { reason } = sequencer.transaction_status(&garbage.hash())
+        else {
+            panic!("Deployment of a garbage image is not dropped");
+        };
+        assert!(reason.contains("InvalidProgram"));
+        let TransactionStatus::Dropped { reason } = sequencer.transaction_status(&oversized.hash())
+        else {
+            panic!("Deployment of an oversized image is not dropped");
+        };
+        assert!(reason.contains("InvalidProgram"));
+        assert!(reason.contains("exceeds the maximum of 4096"));
+    }
+
+    #[test]
+    fn test_duplicate_deployment_reports_existing_program() {
+        let config = setup_sequencer_config();
+        let signing_key = create_signing_key_for_account1();
+        let token = nssa::program::Program::token();
+        let duplicate = deployment_tx_signed_by(token.elf().to_vec(), &config, &signing_key);
+        let (sequencer, _mempool_handle) = SequencerCore::start_from_config(config);
+
+        let deployment = sequencer
+            .check_deployment(&NSSATransaction::try_from(&duplicate).unwrap())
+            .unwrap()
+            .unwrap();
+        assert!(deployment.already_deployed);
+        assert_eq!(deployment.program.id(), token.id());
+
+        // Transactions other than deployments aren't checked
+        let transfer = public_tx_signed_by(
+            transfer_message(
+                vec![
+                    nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(&signing_key)),
+                    nssa::AccountId::new([1; 32]),
+                ],
+                vec![0],
+                10,
+            ),
+            &signing_key,
+        );
+        assert!(
+            sequencer
+                .check_deployment(&NSSATransaction::try_from(&transfer).unwrap())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_signed_headers_lead_to_proven_accounts() {
+        let (mut sequencer, _mempool_handle) = common_setup().await;
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        let public_key = nssa::PublicKey::new_from_private_key(&sequencer_sign_key_for_testing());
+        let genesis = sequencer.signed_block_header(1).unwrap();
+        assert!(genesis.state_root.is_none());
+
+        let headers = (2..=sequencer.chain_height())
+            .map(|block_id| sequencer.signed_block_header(block_id).unwrap())
+            .collect::<Vec<_>>();
+        let tip = common::signed_header::verify_header_chain(
+            &common::signed_header::Checkpoint::from(&genesis),
+            &headers,
+            &public_key,
+        )
+        .unwrap();
+        assert_eq!(tip.block_id, sequencer.chain_height());
+
+        let tip_header = headers.last().unwrap();
+        assert_eq!(
+            tip_header.state_root_bytes(),
+            Some(sequencer.state().state_root())
+        );
+        let account_id = nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(
+            &create_signing_key_for_account1(),
+        ));
+        let account = sequencer.state().get_account_by_id(&account_id);
+        let account_tree_root = sequencer
+            .state()
+            .account_proof(&account_id)
+            .root(&account_id, &account)
+            .unwrap();
+        assert_eq!(
+            nssa::V02State::state_root_of(
+                &account_tree_root,
+                &sequencer.state().commitment_set_digest()
+            ),
+            sequencer.state().state_root()
+        );
+    }
+
+    #[test]
+    fn test_stalled_block_production_flips_readiness() {
+        let config = SequencerConfig {
+            block_create_timeout_millis: 20,
+            ..setup_sequencer_config()
+        };
+        let (mut sequencer, _mempool_handle) = SequencerCore::start_from_config(config);
+
+        assert!(sequencer.health_report().ok);
+        assert!(sequencer.readiness_report(Instant::now()).ok);
+
+        // Production loop is stalled for several block intervals
+        std::thread::sleep(Duration::from_millis(100));
+        let report = sequencer.readiness_report(Instant::now());
+        assert!(!report.ok);
+        assert_eq!(
+            report
+                .failed_checks()
+                .map(|check| check.name.as_str())
+                .collect::<Vec<_>>(),
+            vec![health::BLOCK_PRODUCTION_CHECK]
+        );
+        assert!(sequencer.health_report().ok);
+
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        assert!(sequencer.readiness_report(Instant::now()).ok);
+    }
+
+    #[tokio::test]
+    async fn test_block_production_resumes_after_transient_failure() {
+        let (mut sequencer, mempool_handle) = common_setup().await;
+        let tip = sequencer.chain_height();
+        let tx = common::test_utils::produce_dummy_empty_transaction();
+        mempool_handle.push(tx.clone()).await.unwrap();
+
+        // Storage hiccup on one tick
+        let produced = sequencer
+            .supervise_block_production(Err(anyhow::anyhow!("Storage is unavailable")))
+            .unwrap();
+        assert_eq!(produced, None);
+        assert_eq!(sequencer.block_production().failed_attempts(), 1);
+        assert!(sequencer.health_report().ok);
+
+        assert_eq!(sequencer.produce_block_on_tick().unwrap(), Some(tip + 1));
+        assert!(matches!(
+            sequencer.transaction_status(&tx.hash()),
+            TransactionStatus::Included { .. }
+        ));
+    }
+
+    #[test]
+    fn test_invariant_violation_stops_block_production() {
+        let (mut sequencer, _mempool_handle) =
+            SequencerCore::start_from_config(setup_sequencer_config());
+        let tip = sequencer.chain_height();
+
+        let violation = invariants::StateInvariantViolation("Supply changed".to_string());
+        assert!(
+            sequencer
+                .supervise_block_production(Err(violation.into()))
+                .is_err()
+        );
+        assert_eq!(
+            sequencer
+                .health_report()
+                .failed_checks()
+                .map(|check| check.name.as_str())
+                .collect::<Vec<_>>(),
+            vec![health::BLOCK_PRODUCTION_LOOP_CHECK]
+        );
+
+        assert!(sequencer.produce_block_on_tick().is_err());
+        assert_eq!(sequencer.chain_height(), tip);
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_alerts_on_stall_with_pending_transactions() {
+        let config = SequencerConfig {
+            block_create_timeout_millis: 20,
+            ..setup_sequencer_config()
+        };
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        let block_interval = Duration::from_millis(20);
+        let stall_watch = Arc::clone(sequencer.block_production().stall_watch());
+
+        // Nothing waits for inclusion
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!stall_watch.watch(Instant::now(), block_interval, 0));
+
+        // Watchdog only reads what the sequencer published
+        mempool_handle
+            .push(common::test_utils::produce_dummy_empty_transaction())
+            .await
+            .unwrap();
+        assert!(stall_watch.watch(Instant::now(), block_interval, mempool_handle.len()));
+        sequencer.take_transactions_from_mempool();
+        assert!(stall_watch.watch(Instant::now(), block_interval, mempool_handle.len()));
+        assert!(!sequencer.health_report().ok);
+        assert_eq!(sequencer.block_production().stall_alerts(), 1);
+
+        sequencer.produce_block_on_tick().unwrap();
+        assert!(sequencer.health_report().ok);
+        assert!(!stall_watch.watch(Instant::now(), block_interval, mempool_handle.len()));
+    }
+
+    #[tokio::test]
+    async fn test_committed_block_candidate_matches_preview() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        let tip = sequencer.chain_height();
+
+        let txs: Vec<_> = (0..3)
+            .map(|nonce| {
+                common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    nonce,
+                    *acc2.value(),
+                    10,
+                    create_signing_key_for_account1(),
+                )
+            })
+            .collect();
+        for tx in txs.iter().cloned() {
+            mempool_handle.push(tx).await.unwrap();
+        }
+        sequencer.take_transactions_from_mempool();
+
+        let state_root = sequencer.state().state_root();
+        let candidate = sequencer.build_block_candidate().unwrap();
+        let expected_hashes: Vec<_> = txs.iter().map(EncodedTransaction::hash).collect();
+        assert_eq!(candidate.block_id(), tip + 1);
+        assert_eq!(candidate.tx_hashes(), expected_hashes);
+        assert!(candidate.total_gas() > 0);
+
+        // Building a candidate changes nothing
+        assert_eq!(sequencer.chain_height(), tip);
+        assert_eq!(sequencer.state().state_root(), state_root);
+        assert_eq!(
+            sequencer.state().get_account_by_id(&acc1).balance,
+            Amount(10000)
+        );
+
+        let expected_state_root = candidate.state.state_root();
+        let block_id = sequencer.commit_block(candidate).unwrap();
+        assert_eq!(block_id, tip + 1);
+        assert_eq!(sequencer.state().state_root(), expected_state_root);
+        assert_eq!(
+            sequencer.state().get_account_by_id(&acc1).balance,
+            Amount(10000 - 30)
+        );
+
+        let block = sequencer.block_store().get_block_at_id(block_id).unwrap();
+        let stored_hashes: Vec<_> = block
+            .body
+            .transactions
+            .iter()
+            .map(EncodedTransaction::hash)
+            .collect();
+        assert_eq!(stored_hashes, expected_hashes);
+        assert!(sequencer.pending_transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_preview_does_not_consume_mempool_transactions() {
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *acc1.value(),
+            0,
+            *acc2.value(),
+            10,
+            create_signing_key_for_account1(),
+        );
+        mempool_handle.push(tx.clone()).await.unwrap();
+        sequencer.take_transactions_from_mempool();
+
+        let first_preview = sequencer.build_block_candidate().unwrap();
+        let second_preview = sequencer.build_block_candidate().unwrap();
+        assert_eq!(first_preview.tx_hashes(), vec![tx.hash()]);
+        assert_eq!(second_preview.tx_hashes(), first_preview.tx_hashes());
+
+        let block_id = sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        assert!(matches!(
+            sequencer.transaction_status(&tx.hash()),
+            TransactionStatus::Included { block_id: included, .. } if included == block_id
+        ));
+
+        // Candidate built before the commit is stale now
+        let err = sequencer.commit_block(first_preview).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Block candidate {block_id} is stale, chain height is {block_id}")
+        );
+    }
+
+    /// Waits for the proof of `block_id`, which must be lazily proven
+    fn wait_for_block_proof(sequencer: &mut SequencerCore, block_id: u64) -> proving::BlockProof {
+        let deadline = Instant::now() + Duration::from_secs(600);
+        loop {
+            match sequencer.block_proof_status(block_id).unwrap() {
+                Some(BlockProofRecord::Proven(proof)) => return proof,
+                Some(BlockProofRecord::Pending) => {
+                    assert!(Instant::now() < deadline, "Block {block_id} is not proven");
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                record => panic!("Unexpected proof record of block {block_id}: {record:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lazily_proven_blocks_eventually_get_proofs() {
+        let mut config = setup_sequencer_config();
+        config.lazy_proving = Some(config::LazyProvingConfig {
+            queue_size: 1,
+            parallelism: 2,
+            prover_backend: nssa::ProverBackend::Real,
+            verify_proofs: None,
+        });
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+
+        // Blocks are committed without waiting for proofs
+        let mut block_ids = vec![];
+        for nonce in 0..5 {
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                nonce,
+                *acc2.value(),
+                10,
+                create_signing_key_for_account1(),
+            );
+            mempool_handle.push(tx).await.unwrap();
+            block_ids.push(
+                sequencer
+                    .produce_new_block_with_mempool_transactions()
+                    .unwrap(),
+            );
+        }
+        for block_id in &block_ids {
+            assert!(sequencer.block_proof_status(*block_id).unwrap().is_some());
+        }
+        assert_eq!(sequencer.block_proof_status(1).unwrap(), None);
+
+        for block_id in block_ids {
+            let proof = wait_for_block_proof(&mut sequencer, block_id);
+            assert_eq!(proof.block_id, block_id);
+            assert_eq!(proof.program_executions.len(), 1);
+            assert!(proof.is_valid(false));
+
+            let journal: nssa_core::BlockProofJournal =
+                nssa::journal::decode_journal(&proof.journal).unwrap();
+            let block = sequencer.block_store.get_block_at_id(block_id).unwrap();
+            assert_eq!(journal.block_hash, block.header.hash);
+            assert_eq!(journal.tx_count, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocks_proven_by_dev_backend_are_rejected_by_strict_node() {
+        let mut config = setup_sequencer_config();
+        config.accept_dev_proofs = true;
+        config.lazy_proving = Some(config::LazyProvingConfig {
+            queue_size: 1,
+            parallelism: 1,
+            prover_backend: nssa::ProverBackend::DevFake,
+            verify_proofs: None,
+        });
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let db_path = config.home.join("rocksdb");
+        {
+            let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                0,
+                *acc2.value(),
+                10,
+                create_signing_key_for_account1(),
+            );
+            mempool_handle.push(tx).await.unwrap();
+            let block_id = sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+            let block = sequencer.block_store.get_block_at_id(block_id).unwrap();
+            assert!(block.header.dev_proved);
+
+            let proof = wait_for_block_proof(&mut sequencer, block_id);
+            assert!(proof.is_dev());
+            assert!(proof.is_valid(true));
+        }
+
+        let mut replay = replay::Replay::open(&config, &db_path).unwrap();
+        assert_eq!(replay.apply_next_block().unwrap(), Some(2));
+        drop(replay);
+
+        config.accept_dev_proofs = false;
+        let mut replay = replay::Replay::open(&config, &db_path).unwrap();
+        let err = replay.apply_next_block().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(nssa::error::NssaError::DevProofRejected)
+        ));
+        assert!(err.to_string().contains("Block 2 is rejected"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_rejects_proof_with_tampered_journal() {
+        let mut config = setup_sequencer_config();
+        config.lazy_proving = Some(config::LazyProvingConfig {
+            queue_size: 1,
+            parallelism: 1,
+            prover_backend: nssa::ProverBackend::Real,
+            verify_proofs: None,
+        });
+        let db_path = config.home.join("rocksdb");
+        let proof = {
+            let (mut sequencer, _mempool_handle) = SequencerCore::start_from_config(config.clone());
+            let block_id = sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+            wait_for_block_proof(&mut sequencer, block_id)
+        };
+
+        let mut replay = replay::Replay::open(&config, &db_path).unwrap();
+        assert_eq!(replay.apply_next_block().unwrap(), Some(2));
+        drop(replay);
+
+        // Proof commits to another block, while everything else matches
+        let mut journal: nssa_core::BlockProofJournal =
+            nssa::journal::decode_journal(&proof.journal).unwrap();
+        let block_hash = journal.block_hash;
+        journal.block_hash = [7; 32];
+        let tampered = proving::BlockProof {
+            journal: nssa::journal::encode_journal(&journal).unwrap(),
+            ..proof
+        };
+        block_store::SequencerBlockStore::open_db_restart(
+            &db_path,
+            sequencer_sign_key_for_testing(),
+        )
+        .unwrap()
+        .put_block_proof_record(2, &BlockProofRecord::Proven(tampered))
+        .unwrap();
+
+        let mut replay = replay::Replay::open(&config, &db_path).unwrap();
+        let err = replay.apply_next_block().unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&proving::BlockProofError::BlockHashMismatch {
+                proven: [7; 32],
+                local: block_hash,
+            })
+        );
+        assert!(err.to_string().contains("Proof of block 2 is rejected"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_rejects_proof_with_tampered_receipt_at_its_block() {
+        /// Mirror of [`nssa::public_transaction::ProgramExecutionProof`], which fields are private
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize)]
+        struct RawExecutionProof {
+            program_id: nssa_core::program::ProgramId,
+            journal: Vec<u8>,
+            receipt: Vec<u8>,
+        }
+
+        let mut config = setup_sequencer_config();
+        config.lazy_proving = Some(config::LazyProvingConfig {
+            queue_size: 1,
+            parallelism: 2,
+            prover_backend: nssa::ProverBackend::Real,
+            verify_proofs: Some(config::ProofVerificationConfig {
+                window: 4,
+                parallelism: 2,
+            }),
+        });
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let db_path = config.home.join("rocksdb");
+        let proof = {
+            let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+            let mut block_ids = vec![];
+            for nonce in 0..3 {
+                let tx = common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    nonce,
+                    *acc2.value(),
+                    10,
+                    create_signing_key_for_account1(),
+                );
+                mempool_handle.push(tx).await.unwrap();
+                block_ids.push(
+                    sequencer
+                        .produce_new_block_with_mempool_transactions()
+                        .unwrap(),
+                );
+            }
+            assert_eq!(block_ids, vec![2, 3, 4]);
+            for block_id in [2, 4] {
+                wait_for_block_proof(&mut sequencer, block_id);
+            }
+            wait_for_block_proof(&mut sequencer, 3)
+        };
+
+        let mut replay = replay::Replay::open(&config, &db_path).unwrap();
+        while replay.apply_next_block().unwrap().is_some() {}
+        assert_eq!(replay.last_block_id(), 4);
+        drop(replay);
+
+        // Receipt of the program execution doesn't match its output anymore, while the journal
+        // of the block proof is intact
+        let mut execution: RawExecutionProof =
+            borsh::from_slice(&borsh::to_vec(&proof.program_executions[0]).unwrap()).unwrap();
+        execution.journal.push(0);
+        let tampered = proving::BlockProof {
+            program_executions: vec![
+                borsh::from_slice(&borsh::to_vec(&execution).unwrap()).unwrap(),
+            ],
+            ..proof
+        };
+        block_store::SequencerBlockStore::open_db_restart(
+            &db_path,
+            sequencer_sign_key_for_testing(),
+        )
+        .unwrap()
+        .put_block_proof_record(3, &BlockProofRecord::Proven(tampered))
+        .unwrap();
+
+        let mut replay = replay::Replay::open(&config, &db_path).unwrap();
+        assert_eq!(replay.apply_next_block().unwrap(), Some(2));
+        let err = replay.apply_next_block().unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&proof_pipeline::ProofVerificationError::InvalidReceipts { block_id: 3 })
+        );
+        assert!(err.to_string().contains("Proof of block 3 is rejected"));
+        assert_eq!(replay.last_block_id(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resubmitted_transaction_is_pending_once() {
+        let (mut sequencer, mempool_handle) = common_setup().await;
+        let acc1 = sequencer.sequencer_config.initial_accounts[0]
+            .account_id
+            .parse::<nssa::AccountId>()
+            .unwrap();
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *acc1.value(),
+            0,
+            [2; 32],
+            10,
+            create_signing_key_for_account1(),
+        );
+
+        mempool_handle.push(tx.clone()).await.unwrap();
+        mempool_handle.push(tx.clone()).await.unwrap();
+        sequencer.take_transactions_from_mempool();
+        mempool_handle.push(tx.clone()).await.unwrap();
+        assert!(matches!(
+            sequencer.transaction_status(&tx.hash()),
+            TransactionStatus::Pending { depth: 1, .. }
+        ));
+
+        let block_id = sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        let block = sequencer.block_store().get_block_at_id(block_id).unwrap();
+        let block_hashes: Vec<_> = block
+            .body
+            .transactions
+            .iter()
+            .map(EncodedTransaction::hash)
+            .collect();
+        assert_eq!(block_hashes, vec![tx.hash()]);
+
+        // Resubmission after inclusion is ignored too
+        mempool_handle.push(tx.clone()).await.unwrap();
+        sequencer.take_transactions_from_mempool();
+        assert!(sequencer.pending_transactions.is_empty());
+        assert!(matches!(
+            sequencer.transaction_status(&tx.hash()),
+            TransactionStatus::Included { block_id: included, .. } if included == block_id
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_admitted_transactions_survive_restart_with_mempool_wal() {
+        let mut config = setup_sequencer_config();
+        config.mempool_wal = true;
+        let acc1 = config.initial_accounts[0]
+            .account_id
+            .parse::<nssa::AccountId>()
+            .unwrap();
+        let txs: Vec<_> = (0..5)
+            .map(|nonce| {
+                common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    nonce,
+                    [2; 32],
+                    10,
+                    create_signing_key_for_account1(),
+                )
+            })
+            .collect();
+
+        // Sequencer stops before producing a block with admitted transactions
+        {
+            let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+            for tx in &txs {
+                sequencer.log_admitted_transaction(tx).unwrap();
+                mempool_handle.push(tx.clone()).await.unwrap();
+            }
+        }
+
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+        // Resubmission after restart doesn't duplicate the restored transaction
+        sequencer.log_admitted_transaction(&txs[0]).unwrap();
+        mempool_handle.push(txs[0].clone()).await.unwrap();
+
+        let block_id = sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        let block = sequencer.block_store().get_block_at_id(block_id).unwrap();
+        let block_hashes: Vec<_> = block
+            .body
+            .transactions
+            .iter()
+            .map(EncodedTransaction::hash)
+            .collect();
+        let expected_hashes: Vec<_> = txs.iter().map(EncodedTransaction::hash).collect();
+        assert_eq!(block_hashes, expected_hashes);
+        assert!(sequencer.mempool_wal.as_ref().unwrap().is_empty());
+
+        // Included transactions are not restored again
+        drop(sequencer);
+        let (mut sequencer, _mempool_handle) = SequencerCore::start_from_config(config);
+        assert!(sequencer.pending_transactions.is_empty());
+        let block_id = sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        let block = sequencer.block_store().get_block_at_id(block_id).unwrap();
+        assert!(block.body.transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_committed_blocks_are_logged_as_events() {
+        use crate::{
+            config::{EventSinkConfig, EventSinkTarget},
+            event_log::ReceiptOutcome,
+        };
+
+        let config = setup_sequencer_config();
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        let transfer = |nonce| {
+            common::test_utils::create_transaction_native_token_transfer(
+                *acc1.value(),
+                nonce,
+                *acc2.value(),
+                10,
+                create_signing_key_for_account1(),
+            )
+        };
+
+        // Block is committed before event sink is configured
+        {
+            let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+            assert!(sequencer.event_log().is_none());
+            mempool_handle.push(transfer(0)).await.unwrap();
+            sequencer
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        }
+
+        let config = SequencerConfig {
+            event_sink: Some(EventSinkConfig {
+                target: EventSinkTarget::Webhook("http://127.0.0.1:1".to_string()),
+                max_events_per_second: 1000,
+            }),
+            ..config
+        };
+        let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config);
+        // Nonce is too high, so the transaction is dropped
+        let dropped_tx = transfer(5);
+        mempool_handle.push(dropped_tx.clone()).await.unwrap();
+        sequencer
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+
+        let events = sequencer
+            .event_log()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .pending(usize::MAX);
+        let seqs: Vec<_> = events.iter().map(|event| event.seq).collect();
+        let block_ids: Vec<_> = events.iter().map(|event| event.event.block_id()).collect();
+        let genesis_id = sequencer.sequencer_config.genesis_id;
+        // Stored block is logged on start, the dropped transaction with the next block
+        assert_eq!(seqs, [1, 2, 3, 4, 5]);
+        assert_eq!(
+            block_ids,
+            [
+                genesis_id + 1,
+                genesis_id + 1,
+                genesis_id + 1,
+                genesis_id + 2,
+                genesis_id + 2
+            ]
+        );
+        assert!(matches!(
+            &events[4].event,
+            EventBody::Receipt {
+                tx_hash,
+                outcome: ReceiptOutcome::Dropped { .. },
+                ..
+            } if *tx_hash == hex::encode(dropped_tx.hash())
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_per_block_tx_limit_changes_at_activation_height() {
+        let config = SequencerConfig {
+            upgrades: vec![config::ParamsUpgrade {
+                activation_height: 10,
+                new_params: ChainParams {
+                    max_num_tx_in_block: 3,
+                    storage_deposit_per_byte: Amount::ZERO,
+                    max_instruction_data_size: nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+                    max_program_size: nssa::program::DEFAULT_MAX_PROGRAM_SIZE,
+                    anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+                },
+            }],
+            ..setup_sequencer_config()
+        };
+        let acc1: nssa::AccountId = config.initial_accounts[0].account_id.parse().unwrap();
+        let acc2: nssa::AccountId = config.initial_accounts[1].account_id.parse().unwrap();
+        {
+            let (mut sequencer, mempool_handle) = SequencerCore::start_from_config(config.clone());
+            for _ in 2..9 {
+                sequencer
+                    .produce_new_block_with_mempool_transactions()
+                    .unwrap();
+            }
+            for nonce in 0..20 {
+                let tx = common::test_utils::create_transaction_native_token_transfer(
+                    *acc1.value(),
+                    nonce,
+                    *acc2.value(),
+                    1,
+                    create_signing_key_for_account1(),
+                );
+                mempool_handle.push(tx).await.unwrap();
+            }
+
+            for (expected_block_id, expected_version, expected_num_txs) in [(9, 0, 10), (10, 1, 3)]
+            {
+                let block_id = sequencer
+                    .produce_new_block_with_mempool_transactions()
+                    .unwrap();
+                assert_eq!(block_id, expected_block_id);
+                let block = sequencer.block_store.get_block_at_id(block_id).unwrap();
+                assert_eq!(block.header.params_version, expected_version);
+                assert_eq!(block.body.transactions.len(), expected_num_txs);
+            }
+            assert_eq!(
+                sequencer.next_block_params(),
+                (
+                    1,
+                    ChainParams {
+                        max_num_tx_in_block: 3,
+                        storage_deposit_per_byte: Amount::ZERO,
+                        max_instruction_data_size: nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+                        max_program_size: nssa::program::DEFAULT_MAX_PROGRAM_SIZE,
+                        anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+                    }
+                )
+            );
+        }
+
+        // Node with the same upgrades validates both blocks with their parameters
+        let db_path = config.home.join("rocksdb");
+        let mut replay = replay::Replay::open(&config, &db_path).unwrap();
+        for block_id in 2..=10 {
+            assert_eq!(replay.apply_next_block().unwrap(), Some(block_id));
+        }
+
+        // Node without the upgrade refuses block 10 instead of diverging
+        let outdated_config = SequencerConfig {
+            upgrades: vec![],
+            ..config
+        };
+        let mut replay = replay::Replay::open(&outdated_config, &db_path).unwrap();
+        for block_id in 2..=9 {
+            assert_eq!(replay.apply_next_block().unwrap(), Some(block_id));
+        }
+        let err = replay.apply_next_block().unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&params::BlockParamsError::UnknownVersion {
+                block_id: 10,
+                version: 1,
+                latest_version: 0,
+            })
         );
     }
 }
@@ -0,0 +1,210 @@
+//! Append-only log of transactions admitted into the mempool, so they survive restarts.
+//!
+//! Every entry is a little-endian `u32` length followed by the borsh encoding of the transaction.
+//! A partially written entry at the end of the log, left by a crash, is ignored.
+
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Read as _, Write as _},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use common::{HashType, transaction::EncodedTransaction};
+
+/// Name of the log file in the sequencer home directory
+pub const MEMPOOL_WAL_FILE_NAME: &str = "mempool.wal";
+
+pub struct MempoolWal {
+    path: PathBuf,
+    file: File,
+    /// Hashes of transactions in the log
+    logged: HashSet<HashType>,
+}
+
+impl MempoolWal {
+    /// Opens the log at `path` creating it if necessary, returns it together with the logged
+    /// transactions in order of admission
+    pub fn open(path: &Path) -> Result<(Self, Vec<EncodedTransaction>)> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let transactions = read_entries(path)?;
+        let mut this = Self {
+            path: path.to_path_buf(),
+            file: OpenOptions::new().create(true).append(true).open(path)?,
+            logged: HashSet::new(),
+        };
+        // Truncated tail is dropped, so new entries are not appended after garbage
+        this.rewrite(&transactions)?;
+
+        Ok((this, transactions))
+    }
+
+    /// Appends `tx` to the log and waits until it reaches the disk
+    ///
+    /// Transactions, which are already logged, are not appended again.
+    pub fn append(&mut self, tx: &EncodedTransaction) -> Result<()> {
+        if self.logged.contains(&tx.hash()) {
+            return Ok(());
+        }
+
+        self.file.write_all(&encode_entry(tx)?)?;
+        self.file.sync_data()?;
+        self.logged.insert(tx.hash());
+        Ok(())
+    }
+
+    /// Removes transactions with the given hashes from the log
+    pub fn prune(&mut self, hashes: &HashSet<HashType>) -> Result<()> {
+        if self.logged.is_disjoint(hashes) {
+            return Ok(());
+        }
+
+        let transactions = read_entries(&self.path)?
+            .into_iter()
+            .filter(|tx| !hashes.contains(&tx.hash()))
+            .collect::<Vec<_>>();
+        self.rewrite(&transactions)
+    }
+
+    /// Number of logged transactions
+    pub fn len(&self) -> usize {
+        self.logged.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.logged.is_empty()
+    }
+
+    /// Atomically replaces the log with `transactions`
+    fn rewrite(&mut self, transactions: &[EncodedTransaction]) -> Result<()> {
+        let tmp_path = self.path.with_extension("wal.tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            for tx in transactions {
+                writer.write_all(&encode_entry(tx)?)?;
+            }
+            writer.into_inner()?.sync_all()?;
+        }
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to replace mempool log at {}", self.path.display()))?;
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.logged = transactions.iter().map(EncodedTransaction::hash).collect();
+        Ok(())
+    }
+}
+
+fn encode_entry(tx: &EncodedTransaction) -> Result<Vec<u8>> {
+    let bytes = borsh::to_vec(tx)?;
+    let len = u32::try_from(bytes.len()).context("Transaction is too large to be logged")?;
+
+    let mut entry = Vec::with_capacity(4 + bytes.len());
+    entry.extend_from_slice(&len.to_le_bytes());
+    entry.extend_from_slice(&bytes);
+    Ok(entry)
+}
+
+/// Complete entries of the log at `path`, empty if there is no log
+fn read_entries(path: &Path) -> Result<Vec<EncodedTransaction>> {
+    let mut bytes = vec![];
+    match File::open(path) {
+        Ok(mut file) => {
+            file.read_to_end(&mut bytes)?;
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    }
+
+    let mut transactions = vec![];
+    let mut rest = bytes.as_slice();
+    while let Some((len, tail)) = rest.split_first_chunk::<4>() {
+        let len = u32::from_le_bytes(*len) as usize;
+        let Some(entry) = tail.get(..len) else {
+            log::warn!("Ignoring truncated entry at the end of mempool log");
+            break;
+        };
+        transactions
+            .push(borsh::from_slice(entry).context("Failed to decode transaction in mempool log")?);
+        rest = &tail[len..];
+    }
+
+    Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use common::test_utils::produce_dummy_empty_transaction;
+
+    use super::*;
+
+    fn transaction_with_nonce(nonce: nssa_core::account::Nonce) -> EncodedTransaction {
+        common::test_utils::create_transaction_native_token_transfer(
+            [1; 32],
+            nonce,
+            [2; 32],
+            10,
+            nssa::PrivateKey::try_new([1; 32]).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_logged_transactions_are_read_back_until_pruned() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join(MEMPOOL_WAL_FILE_NAME);
+        let txs = [
+            produce_dummy_empty_transaction(),
+            transaction_with_nonce(0),
+            transaction_with_nonce(1),
+        ];
+
+        {
+            let (mut wal, logged) = MempoolWal::open(&path).unwrap();
+            assert!(logged.is_empty());
+            for tx in &txs {
+                wal.append(tx).unwrap();
+            }
+            // Already logged transaction is not duplicated
+            wal.append(&txs[0]).unwrap();
+            assert_eq!(wal.len(), 3);
+        }
+
+        {
+            let (mut wal, logged) = MempoolWal::open(&path).unwrap();
+            assert_eq!(logged, txs);
+            wal.prune(&HashSet::from([txs[1].hash()])).unwrap();
+        }
+
+        let (_wal, logged) = MempoolWal::open(&path).unwrap();
+        assert_eq!(logged, [txs[0].clone(), txs[2].clone()]);
+    }
+
+    #[test]
+    fn test_truncated_entry_is_ignored() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join(MEMPOOL_WAL_FILE_NAME);
+        let tx = transaction_with_nonce(0);
+
+        {
+            let (mut wal, _) = MempoolWal::open(&path).unwrap();
+            wal.append(&tx).unwrap();
+        }
+        // Crash in the middle of writing the second entry
+        let entry = encode_entry(&transaction_with_nonce(1)).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&entry[..entry.len() / 2]).unwrap();
+        drop(file);
+
+        {
+            let (mut wal, logged) = MempoolWal::open(&path).unwrap();
+            assert_eq!(logged, std::slice::from_ref(&tx));
+            wal.append(&transaction_with_nonce(2)).unwrap();
+        }
+
+        let (_wal, logged) = MempoolWal::open(&path).unwrap();
+        assert_eq!(logged, [tx, transaction_with_nonce(2)]);
+    }
+}
@@ -0,0 +1,285 @@
+//! Order in which the sequencer includes pending transactions into blocks.
+//!
+//! The order depends only on the set of pending transactions, so the same submissions always
+//! produce the same blocks:
+//! 1. transactions of the same sender are included in order of their nonces;
+//! 2. among the next transactions of every sender, the one with higher fee priority goes first;
+//! 3. ties are broken by arrival sequence number.
+//!
+//! If the number of consecutive transactions of one sender is limited, senders take turns
+//! instead: a sender, which had fewer turns, goes first, and takes up to the limit of its next
+//! transactions. The rules above apply to senders with the same number of turns.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+};
+
+use common::{HashType, transaction::NSSATransaction};
+use nssa::{AccountId, Amount};
+use nssa_core::account::Nonce;
+
+/// Name of the ordering policy, as reported by `get_chain_info`
+pub const TX_ORDERING_POLICY: &str = "fee_priority_desc,arrival_seq_asc,sender_nonce_asc";
+
+/// Name of the ordering policy with at most `max_consecutive_per_sender` consecutive
+/// transactions of one sender, see module docs
+pub fn tx_ordering_policy(max_consecutive_per_sender: Option<usize>) -> String {
+    match max_consecutive_per_sender {
+        Some(max_consecutive) => format!("sender_turns_{max_consecutive},{TX_ORDERING_POLICY}"),
+        None => TX_ORDERING_POLICY.to_string(),
+    }
+}
+
+/// Transaction, which passed stateless checks and waits for inclusion into a block
+#[derive(Debug, Clone)]
+pub struct PendingTransaction {
+    pub arrival_seq: u64,
+    pub hash: HashType,
+    pub fee_priority: Amount,
+    pub sender_and_nonce: Option<(AccountId, Nonce)>,
+    pub tx: NSSATransaction,
+}
+
+impl PendingTransaction {
+    pub fn new(arrival_seq: u64, hash: HashType, tx: NSSATransaction) -> Self {
+        Self {
+            arrival_seq,
+            hash,
+            fee_priority: fee_priority(&tx),
+            sender_and_nonce: tx.sender_and_nonce(),
+            tx,
+        }
+    }
+}
+
+/// Base cost of every transaction, there is no gas schedule yet
+pub const BASE_FEE: Amount = Amount::ZERO;
+
+/// Priority of transaction, paid by its sender
+pub fn fee_priority(tx: &NSSATransaction) -> Amount {
+    BASE_FEE.saturating_add(tx.priority_fee())
+}
+
+/// Sorts `pending` in order of inclusion, taking at most `max_consecutive_per_sender`
+/// consecutive transactions of one sender if set, see module docs
+pub fn order_pending_transactions(
+    pending: Vec<PendingTransaction>,
+    max_consecutive_per_sender: Option<usize>,
+) -> Vec<PendingTransaction> {
+    let num_pending = pending.len();
+
+    let mut queues: Vec<VecDeque<PendingTransaction>> = vec![];
+    let mut sender_queue_indices: HashMap<AccountId, usize> = HashMap::new();
+    for pending_tx in pending {
+        let Some((sender, _)) = pending_tx.sender_and_nonce else {
+            queues.push(VecDeque::from([pending_tx]));
+            continue;
+        };
+
+        let queue_idx = *sender_queue_indices.entry(sender).or_insert_with(|| {
+            queues.push(VecDeque::new());
+            queues.len() - 1
+        });
+        queues[queue_idx].push_back(pending_tx);
+    }
+
+    for queue in &mut queues {
+        queue.make_contiguous().sort_by_key(|pending_tx| {
+            (
+                pending_tx.sender_and_nonce.map(|(_, nonce)| nonce),
+                Reverse(pending_tx.fee_priority),
+                pending_tx.arrival_seq,
+            )
+        });
+    }
+
+    // Heads of sender queues, the fewest turns and then the highest priority on top. Turns are
+    // counted only if senders take them.
+    let mut heads: BinaryHeap<(Reverse<u64>, Amount, Reverse<u64>, usize)> = queues
+        .iter()
+        .enumerate()
+        .filter_map(|(queue_idx, queue)| {
+            let head = queue.front()?;
+            Some((
+                Reverse(0),
+                head.fee_priority,
+                Reverse(head.arrival_seq),
+                queue_idx,
+            ))
+        })
+        .collect();
+
+    let mut ordered = Vec::with_capacity(num_pending);
+    while let Some((Reverse(turns), _, _, queue_idx)) = heads.pop() {
+        let queue = &mut queues[queue_idx];
+        let turn_len = max_consecutive_per_sender.unwrap_or(1).min(queue.len());
+        ordered.extend(queue.drain(..turn_len));
+
+        if let Some(head) = queue.front() {
+            let turns = turns + u64::from(max_consecutive_per_sender.is_some());
+            heads.push((
+                Reverse(turns),
+                head.fee_priority,
+                Reverse(head.arrival_seq),
+                queue_idx,
+            ));
+        }
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        test_utils::create_transaction_native_token_transfer, transaction::EncodedTransaction,
+    };
+    use nssa::{PrivateKey, PublicKey};
+
+    use super::*;
+
+    fn signing_key(seed: u8) -> PrivateKey {
+        PrivateKey::try_new([seed; 32]).unwrap()
+    }
+
+    fn sender(seed: u8) -> AccountId {
+        AccountId::from(&PublicKey::new_from_private_key(&signing_key(seed)))
+    }
+
+    fn transfer(seed: u8, nonce: Nonce) -> NSSATransaction {
+        let tx: EncodedTransaction = create_transaction_native_token_transfer(
+            *sender(seed).value(),
+            nonce,
+            [99; 32],
+            10,
+            signing_key(seed),
+        );
+        NSSATransaction::try_from(&tx).unwrap()
+    }
+
+    fn arrive_in_order(txs: &[(NSSATransaction, u128)]) -> Vec<PendingTransaction> {
+        txs.iter()
+            .enumerate()
+            .map(|(arrival_seq, (tx, fee_priority))| PendingTransaction {
+                fee_priority: Amount(*fee_priority),
+                ..PendingTransaction::new(
+                    arrival_seq as u64,
+                    EncodedTransaction::from(tx.clone()).hash(),
+                    tx.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn ordered_senders_and_nonces(pending: Vec<PendingTransaction>) -> Vec<(AccountId, Nonce)> {
+        order_pending_transactions(pending, None)
+            .into_iter()
+            .map(|pending_tx| pending_tx.sender_and_nonce.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_ordering_keeps_sender_nonce_order_for_any_arrival_order() {
+        let submissions = [
+            (transfer(1, 0), 0),
+            (transfer(1, 1), 0),
+            (transfer(2, 0), 0),
+            (transfer(1, 2), 0),
+            (transfer(2, 1), 0),
+        ];
+        let mut reversed = submissions.clone();
+        reversed.reverse();
+
+        let ordered = ordered_senders_and_nonces(arrive_in_order(&submissions));
+        assert_eq!(
+            ordered,
+            vec![
+                (sender(1), 0),
+                (sender(1), 1),
+                (sender(2), 0),
+                (sender(1), 2),
+                (sender(2), 1)
+            ]
+        );
+
+        // Later nonces arrived first, but are still included after earlier ones
+        let ordered_reversed = ordered_senders_and_nonces(arrive_in_order(&reversed));
+        for seed in [1, 2] {
+            let nonces = |ordered: &[(AccountId, Nonce)]| {
+                ordered
+                    .iter()
+                    .filter(|(account_id, _)| *account_id == sender(seed))
+                    .map(|(_, nonce)| *nonce)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(nonces(&ordered_reversed), nonces(&ordered));
+        }
+
+        // Same submissions in the same order always give the same result
+        assert_eq!(
+            ordered_senders_and_nonces(arrive_in_order(&reversed)),
+            ordered_reversed
+        );
+    }
+
+    #[test]
+    fn test_ordering_prefers_higher_fee_priority() {
+        let submissions = [
+            (transfer(1, 0), 1),
+            (transfer(2, 0), 5),
+            (transfer(3, 0), 3),
+            // Higher priority doesn't let it overtake earlier nonce of the same sender
+            (transfer(1, 1), 10),
+        ];
+
+        assert_eq!(
+            ordered_senders_and_nonces(arrive_in_order(&submissions)),
+            vec![
+                (sender(2), 0),
+                (sender(3), 0),
+                (sender(1), 0),
+                (sender(1), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_senders_take_turns_if_consecutive_transactions_are_limited() {
+        let submissions = [
+            (transfer(1, 0), 0),
+            (transfer(1, 1), 0),
+            (transfer(1, 2), 0),
+            (transfer(1, 3), 0),
+            (transfer(1, 4), 0),
+            (transfer(2, 0), 0),
+            (transfer(2, 1), 0),
+            (transfer(2, 2), 0),
+            // Priority only matters among senders with the same number of turns
+            (transfer(3, 0), 7),
+        ];
+
+        let ordered = order_pending_transactions(arrive_in_order(&submissions), Some(2))
+            .into_iter()
+            .map(|pending_tx| pending_tx.sender_and_nonce.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ordered,
+            vec![
+                (sender(3), 0),
+                (sender(1), 0),
+                (sender(1), 1),
+                (sender(2), 0),
+                (sender(2), 1),
+                (sender(1), 2),
+                (sender(1), 3),
+                (sender(2), 2),
+                (sender(1), 4),
+            ]
+        );
+        assert_eq!(
+            tx_ordering_policy(Some(2)),
+            "sender_turns_2,fee_priority_desc,arrival_seq_asc,sender_nonce_asc"
+        );
+    }
+}
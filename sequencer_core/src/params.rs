@@ -0,0 +1,242 @@
+//! Chain parameters, which change at activation heights of upgrades scheduled in the config.
+//!
+//! Version `0` is the parameters of genesis and every upgrade increments it, so a block header
+//! tells which parameters the block must obey. A node, which doesn't have an upgrade configured,
+//! refuses blocks built with it rather than validating them with stale parameters.
+
+use common::block::{Block, BlockId, ParamsVersion};
+
+use crate::config::{ChainParams, ParamsUpgrade, SequencerConfig};
+
+/// Reason to reject a block, which doesn't match the parameters schedule
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BlockParamsError {
+    #[error(
+        "Block {block_id} is built with parameters version {version}, which is not configured, \
+         the latest configured version is {latest_version}. Add the missing upgrades to the \
+         config."
+    )]
+    UnknownVersion {
+        block_id: BlockId,
+        version: ParamsVersion,
+        latest_version: ParamsVersion,
+    },
+    #[error(
+        "Block {block_id} is built with parameters version {version}, but version {expected} is \
+         active at its height"
+    )]
+    WrongVersion {
+        block_id: BlockId,
+        version: ParamsVersion,
+        expected: ParamsVersion,
+    },
+    #[error(
+        "Block {block_id} has {num_txs} transactions, but parameters version {version} allow at \
+         most {max_num_tx_in_block}"
+    )]
+    TooManyTransactions {
+        block_id: BlockId,
+        version: ParamsVersion,
+        num_txs: usize,
+        max_num_tx_in_block: usize,
+    },
+}
+
+/// Parameters of every version and heights they are active from
+#[derive(Debug, Clone)]
+pub struct ParamsSchedule {
+    genesis_params: ChainParams,
+    upgrades: Vec<ParamsUpgrade>,
+}
+
+impl ParamsSchedule {
+    /// Fails if upgrades of `config` are not ordered by activation height or activate before
+    /// the first block after genesis
+    pub fn from_config(config: &SequencerConfig) -> anyhow::Result<Self> {
+        Self::new(
+            config.genesis_id,
+            config.genesis_params(),
+            config.upgrades.clone(),
+        )
+    }
+
+    pub fn new(
+        genesis_id: BlockId,
+        genesis_params: ChainParams,
+        upgrades: Vec<ParamsUpgrade>,
+    ) -> anyhow::Result<Self> {
+        let mut prev_height = genesis_id;
+        for (index, upgrade) in upgrades.iter().enumerate() {
+            anyhow::ensure!(
+                upgrade.activation_height > prev_height,
+                "Upgrade to parameters version {} activates at height {}, which is not after {}",
+                index + 1,
+                upgrade.activation_height,
+                prev_height
+            );
+            prev_height = upgrade.activation_height;
+        }
+
+        Ok(Self {
+            genesis_params,
+            upgrades,
+        })
+    }
+
+    /// Version of parameters active at height `block_id`
+    pub fn version_at(&self, block_id: BlockId) -> ParamsVersion {
+        self.upgrades
+            .partition_point(|upgrade| upgrade.activation_height <= block_id)
+            as ParamsVersion
+    }
+
+    /// Version of parameters active at height `block_id` and the parameters themselves
+    pub fn params_at(&self, block_id: BlockId) -> (ParamsVersion, ChainParams) {
+        let version = self.version_at(block_id);
+        (
+            version,
+            self.params(version).expect("Version is configured"),
+        )
+    }
+
+    /// Parameters of `version`, `None` if it's not configured
+    pub fn params(&self, version: ParamsVersion) -> Option<ChainParams> {
+        match version {
+            0 => Some(self.genesis_params),
+            version => self
+                .upgrades
+                .get(version as usize - 1)
+                .map(|upgrade| upgrade.new_params),
+        }
+    }
+
+    /// Checks that `block` is built with the version of parameters active at its height and
+    /// obeys them
+    pub fn check_block(&self, block: &Block) -> Result<(), BlockParamsError> {
+        let block_id = block.header.block_id;
+        let version = block.header.params_version;
+        let Some(params) = self.params(version) else {
+            return Err(BlockParamsError::UnknownVersion {
+                block_id,
+                version,
+                latest_version: self.upgrades.len() as ParamsVersion,
+            });
+        };
+
+        let expected = self.version_at(block_id);
+        if version != expected {
+            return Err(BlockParamsError::WrongVersion {
+                block_id,
+                version,
+                expected,
+            });
+        }
+
+        let num_txs = block.body.transactions.len();
+        if num_txs > params.max_num_tx_in_block {
+            return Err(BlockParamsError::TooManyTransactions {
+                block_id,
+                version,
+                num_txs,
+                max_num_tx_in_block: params.max_num_tx_in_block,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{block::HashableBlockData, test_utils};
+
+    use super::*;
+
+    /// Schedule after genesis 1, where version `n` allows `n + 1` transactions per block
+    fn schedule(activation_heights: &[u64]) -> anyhow::Result<ParamsSchedule> {
+        ParamsSchedule::new(
+            1,
+            ChainParams {
+                max_num_tx_in_block: 10,
+                storage_deposit_per_byte: nssa::Amount::ZERO,
+                max_instruction_data_size: nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+                max_program_size: nssa::program::DEFAULT_MAX_PROGRAM_SIZE,
+                anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+            },
+            activation_heights
+                .iter()
+                .enumerate()
+                .map(|(index, &activation_height)| ParamsUpgrade {
+                    activation_height,
+                    new_params: ChainParams {
+                        max_num_tx_in_block: index + 2,
+                        storage_deposit_per_byte: nssa::Amount::ZERO,
+                        max_instruction_data_size: nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+                        max_program_size: nssa::program::DEFAULT_MAX_PROGRAM_SIZE,
+                        anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+                    },
+                })
+                .collect(),
+        )
+    }
+
+    fn block(block_id: BlockId, params_version: ParamsVersion, num_txs: usize) -> Block {
+        HashableBlockData {
+            params_version,
+            dev_proved: false,
+            transactions: vec![test_utils::produce_dummy_empty_transaction(); num_txs],
+            ..HashableBlockData::from(test_utils::produce_dummy_block(block_id, None, vec![]))
+        }
+        .into_block(&test_utils::sequencer_sign_key_for_testing())
+    }
+
+    #[test]
+    fn test_params_switch_exactly_at_activation_height() {
+        let schedule = schedule(&[10, 20]).unwrap();
+
+        assert_eq!(schedule.version_at(9), 0);
+        assert_eq!(schedule.version_at(10), 1);
+        assert_eq!(schedule.version_at(19), 1);
+        assert_eq!(schedule.params_at(20).0, 2);
+        assert_eq!(schedule.params_at(20).1.max_num_tx_in_block, 3);
+        assert_eq!(schedule.params(3), None);
+    }
+
+    #[test]
+    fn test_unordered_upgrades_are_refused() {
+        assert!(schedule(&[10, 10]).is_err());
+        assert!(schedule(&[1]).is_err());
+    }
+
+    #[test]
+    fn test_blocks_are_checked_against_active_params() {
+        let schedule = schedule(&[10]).unwrap();
+
+        assert_eq!(schedule.check_block(&block(9, 0, 10)), Ok(()));
+        assert_eq!(schedule.check_block(&block(10, 1, 2)), Ok(()));
+        assert_eq!(
+            schedule.check_block(&block(10, 0, 2)),
+            Err(BlockParamsError::WrongVersion {
+                block_id: 10,
+                version: 0,
+                expected: 1,
+            })
+        );
+        assert_eq!(
+            schedule.check_block(&block(10, 1, 3)),
+            Err(BlockParamsError::TooManyTransactions {
+                block_id: 10,
+                version: 1,
+                num_txs: 3,
+                max_num_tx_in_block: 2,
+            })
+        );
+        assert_eq!(
+            schedule.check_block(&block(20, 2, 0)),
+            Err(BlockParamsError::UnknownVersion {
+                block_id: 20,
+                version: 2,
+                latest_version: 1,
+            })
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Pipelined verification of stored block proofs, which keeps replay of long chains from waiting
+//! on every receipt in turn.
+//!
+//! Receipts of a block proof don't depend on the replayed state, so a pool of workers verifies
+//! proofs of up to `window` blocks ahead of the replayed one. Blocks are still applied strictly
+//! in order, each one after the verdict on its own proof.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, SyncSender},
+    },
+};
+
+use anyhow::Result;
+
+use crate::{config::ProofVerificationConfig, proving::BlockProof};
+
+/// Check of a block proof, run by workers
+pub type VerifyProof = Arc<dyn Fn(&BlockProof) -> bool + Send + Sync>;
+
+/// Reason to stop replay at a block, see [`ProofPipeline::take_verdict`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProofVerificationError {
+    #[error("Receipts of the proof of block {block_id} are invalid")]
+    InvalidReceipts { block_id: u64 },
+    #[error("Verification of the proof of block {block_id} didn't finish")]
+    Aborted { block_id: u64 },
+}
+
+struct VerificationJob {
+    proof: BlockProof,
+    verdict: mpsc::Sender<bool>,
+}
+
+/// Block fetched ahead of replay
+struct InFlight {
+    block_id: u64,
+    /// `None` for blocks without proof, which have nothing to wait for
+    verdict: Option<Receiver<bool>>,
+}
+
+/// Workers verifying proofs of the blocks in the window ahead of replay
+///
+/// Workers stop after their current jobs once this is dropped. After the first rejected proof,
+/// jobs left in the window are skipped.
+pub struct ProofPipeline {
+    jobs: SyncSender<VerificationJob>,
+    window: usize,
+    in_flight: VecDeque<InFlight>,
+    /// Next block to fetch
+    next_block_id: u64,
+    /// Set after a rejected proof, so workers skip the rest of the window
+    drained: Arc<AtomicBool>,
+}
+
+impl ProofPipeline {
+    /// Pipeline verifying receipts of proofs, receipts of [`nssa::ProverBackend::DevFake`] are
+    /// valid only if `accept_dev_proofs`
+    pub fn spawn(config: &ProofVerificationConfig, accept_dev_proofs: bool) -> Self {
+        Self::spawn_with(
+            config,
+            Arc::new(move |proof: &BlockProof| proof.is_valid(accept_dev_proofs)),
+        )
+    }
+
+    /// Pipeline checking proofs with `verify`
+    pub fn spawn_with(config: &ProofVerificationConfig, verify: VerifyProof) -> Self {
+        let window = config.window.max(1);
+        let (jobs, jobs_receiver) = mpsc::sync_channel::<VerificationJob>(window);
+        let jobs_receiver = Arc::new(Mutex::new(jobs_receiver));
+        let drained = Arc::new(AtomicBool::new(false));
+
+        for _ in 0..config.parallelism.max(1) {
+            let jobs_receiver = Arc::clone(&jobs_receiver);
+            let verify = Arc::clone(&verify);
+            let drained = Arc::clone(&drained);
+            std::thread::spawn(move || {
+                loop {
+                    // Lock is released before verifying, so other workers may take jobs
+                    let job = jobs_receiver.lock().unwrap().recv();
+                    let Ok(VerificationJob { proof, verdict }) = job else {
+                        break;
+                    };
+                    if drained.load(Ordering::Relaxed) {
+                        continue;
+                    }
+                    // Verdicts of blocks, which aren't waited for anymore, are dropped
+                    let _ = verdict.send(verify(&proof));
+                }
+            });
+        }
+
+        Self {
+            jobs,
+            window,
+            in_flight: VecDeque::new(),
+            next_block_id: 0,
+            drained,
+        }
+    }
+
+    /// Fetches proofs of blocks from `block_id` up to `last_block_id` with `fetch` and passes
+    /// them to workers, until `window` blocks are in flight
+    ///
+    /// Blocks fetched by earlier calls aren't fetched again.
+    pub fn prefetch(
+        &mut self,
+        block_id: u64,
+        last_block_id: u64,
+        mut fetch: impl FnMut(u64) -> Result<Option<BlockProof>>,
+    ) -> Result<()> {
+        self.next_block_id = self.next_block_id.max(block_id);
+        while self.in_flight.len() < self.window && self.next_block_id <= last_block_id {
+            let block_id = self.next_block_id;
+            let verdict = match fetch(block_id)? {
+                Some(proof) => {
+                    let (verdict, receiver) = mpsc::channel();
+                    self.jobs
+                        .send(VerificationJob { proof, verdict })
+                        .map_err(|_| ProofVerificationError::Aborted { block_id })?;
+                    Some(receiver)
+                }
+                None => None,
+            };
+            self.in_flight.push_back(InFlight { block_id, verdict });
+            self.next_block_id += 1;
+        }
+        Ok(())
+    }
+
+    /// Waits for the verdict on the proof of `block_id`, which must be the oldest fetched block
+    ///
+    /// Blocks without proof pass right away. A rejected proof drains the pipeline: the rest of
+    /// the window is dropped and every later call fails.
+    pub fn take_verdict(&mut self, block_id: u64) -> Result<(), ProofVerificationError> {
+        let verdict = match self.in_flight.pop_front() {
+            Some(in_flight) if in_flight.block_id == block_id => match in_flight.verdict {
+                Some(verdict) => verdict.recv().ok(),
+                None => Some(true),
+            },
+            // Blocks are never fetched after a drain
+            _ => None,
+        };
+
+        match verdict {
+            Some(true) => Ok(()),
+            Some(false) => {
+                self.drain();
+                Err(ProofVerificationError::InvalidReceipts { block_id })
+            }
+            None => {
+                self.drain();
+                Err(ProofVerificationError::Aborted { block_id })
+            }
+        }
+    }
+
+    fn drain(&mut self) {
+        self.drained.store(true, Ordering::Relaxed);
+        self.in_flight.clear();
+        self.next_block_id = u64::MAX;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Condvar, time::Duration};
+
+    use sha2::{Digest as _, Sha256};
+
+    use super::*;
+
+    const NUM_BLOCKS: u64 = 16;
+    const PROOF_LATENCY: Duration = Duration::from_millis(40);
+    const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn proof(block_id: u64) -> BlockProof {
+        BlockProof {
+            block_id,
+            journal: block_id.to_le_bytes().to_vec(),
+            program_executions: vec![],
+        }
+    }
+
+    /// Check, which takes [`PROOF_LATENCY`] and rejects proof of `invalid_block_id`
+    fn slow_verify(invalid_block_id: Option<u64>) -> VerifyProof {
+        Arc::new(move |proof: &BlockProof| {
+            std::thread::sleep(PROOF_LATENCY);
+            Some(proof.block_id) != invalid_block_id
+        })
+    }
+
+    /// Verifications of proofs running at once
+    ///
+    /// Each verification waits until `expected` ones run together, so their peak reaches it only
+    /// if proofs are verified concurrently. The wait is bounded, so serial verification fails the
+    /// assertion on the peak instead of hanging.
+    struct VerificationsInFlight {
+        expected: usize,
+        /// Current and peak number of verifications
+        counts: Mutex<(usize, usize)>,
+        changed: Condvar,
+    }
+
+    impl VerificationsInFlight {
+        fn new(expected: usize) -> Arc<Self> {
+            Arc::new(Self {
+                expected,
+                counts: Mutex::new((0, 0)),
+                changed: Condvar::new(),
+            })
+        }
+
+        fn peak(&self) -> usize {
+            self.counts.lock().unwrap().1
+        }
+
+        /// Check counted by `self`, which accepts every proof
+        fn verifier(self: &Arc<Self>) -> VerifyProof {
+            let in_flight = Arc::clone(self);
+            Arc::new(move |_: &BlockProof| {
+                let mut counts = in_flight.counts.lock().unwrap();
+                counts.0 += 1;
+                counts.1 = counts.1.max(counts.0);
+                in_flight.changed.notify_all();
+                let (mut counts, _) = in_flight
+                    .changed
+                    .wait_timeout_while(counts, WAIT_TIMEOUT, |(_, peak)| {
+                        *peak < in_flight.expected
+                    })
+                    .unwrap();
+                counts.0 -= 1;
+                true
+            })
+        }
+    }
+
+    /// Applies blocks in order, each one after the verdict on its proof, and returns the state
+    /// hash chain and the error, which stopped it
+    fn replay(
+        config: ProofVerificationConfig,
+        verify: VerifyProof,
+    ) -> ([u8; 32], Option<ProofVerificationError>) {
+        let mut pipeline = ProofPipeline::spawn_with(&config, verify);
+        let mut state = [0; 32];
+        for block_id in 1..=NUM_BLOCKS {
+            pipeline
+                .prefetch(block_id, NUM_BLOCKS, |block_id| {
+                    // Every third block isn't proven
+                    Ok((block_id % 3 != 0).then(|| proof(block_id)))
+                })
+                .unwrap();
+            if let Err(err) = pipeline.take_verdict(block_id) {
+                return (state, Some(err));
+            }
+            state = Sha256::new()
+                .chain_update(state)
+                .chain_update(block_id.to_le_bytes())
+                .finalize()
+                .into();
+        }
+        (state, None)
+    }
+
+    #[test]
+    fn test_parallel_verification_runs_proofs_concurrently_with_same_state() {
+        let serial = VerificationsInFlight::new(1);
+        let (serial_state, err) = replay(
+            ProofVerificationConfig {
+                window: 1,
+                parallelism: 1,
+            },
+            serial.verifier(),
+        );
+        assert_eq!(err, None);
+        assert_eq!(serial.peak(), 1);
+
+        let parallel = VerificationsInFlight::new(4);
+        let (parallel_state, err) = replay(
+            ProofVerificationConfig {
+                window: 8,
+                parallelism: 4,
+            },
+            parallel.verifier(),
+        );
+        assert_eq!(err, None);
+        assert_eq!(parallel.peak(), 4);
+        assert_eq!(parallel_state, serial_state);
+    }
+
+    #[test]
+    fn test_rejected_proof_stops_replay_at_its_block() {
+        let config = ProofVerificationConfig {
+            window: 8,
+            parallelism: 4,
+        };
+
+        let (state, err) = replay(config, slow_verify(Some(7)));
+        assert_eq!(
+            err,
+            Some(ProofVerificationError::InvalidReceipts { block_id: 7 })
+        );
+
+        // Blocks before the rejected one are applied
+        let (expected_state, _) = replay(
+            ProofVerificationConfig {
+                window: 1,
+                parallelism: 1,
+            },
+            Arc::new(|proof: &BlockProof| proof.block_id < 7),
+        );
+        assert_eq!(state, expected_state);
+    }
+
+    #[test]
+    fn test_pipeline_is_drained_after_rejected_proof() {
+        let config = ProofVerificationConfig {
+            window: 4,
+            parallelism: 2,
+        };
+        let mut pipeline = ProofPipeline::spawn_with(&config, slow_verify(Some(1)));
+        pipeline
+            .prefetch(1, NUM_BLOCKS, |block_id| Ok(Some(proof(block_id))))
+            .unwrap();
+
+        assert_eq!(
+            pipeline.take_verdict(1),
+            Err(ProofVerificationError::InvalidReceipts { block_id: 1 })
+        );
+        pipeline
+            .prefetch(2, NUM_BLOCKS, |_| {
+                panic!("Nothing is fetched after a drain")
+            })
+            .unwrap();
+        assert_eq!(
+            pipeline.take_verdict(2),
+            Err(ProofVerificationError::Aborted { block_id: 2 })
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Lazy proving of committed blocks by a pool of background workers.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+    },
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use common::{block::BlockHash, transaction::NSSATransaction};
+use log::warn;
+use nssa::{
+    ProverBackend, V02State,
+    journal::{decode_journal, encode_journal},
+    public_transaction::ProgramExecutionProof,
+};
+use nssa_core::BlockProofJournal;
+
+use crate::config::LazyProvingConfig;
+
+/// Proving artifacts of a block
+///
+/// Privacy preserving transactions carry their own proofs, so only program executions of public
+/// transactions are proven here.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BlockProof {
+    pub block_id: u64,
+    /// Encoded [`BlockProofJournal`], which binds the proof to the block and its states
+    pub journal: Vec<u8>,
+    /// Proofs of program executions of public transactions in order of execution
+    pub program_executions: Vec<ProgramExecutionProof>,
+}
+
+impl BlockProof {
+    /// Checks proofs of program executions, proofs of [`ProverBackend::DevFake`] are valid only
+    /// if `accept_dev_proofs`
+    pub fn is_valid(&self, accept_dev_proofs: bool) -> bool {
+        self.program_executions
+            .iter()
+            .all(|proof| proof.is_valid(accept_dev_proofs))
+    }
+
+    /// Whether program executions are proven by [`ProverBackend::DevFake`]
+    pub fn is_dev(&self) -> bool {
+        self.program_executions
+            .iter()
+            .any(ProgramExecutionProof::is_dev)
+    }
+
+    /// Checks that the journal commits to the `local` values, which the verifier computed from
+    /// its own copy of the block and states
+    pub fn check_journal(&self, local: &BlockProofJournal) -> Result<(), BlockProofError> {
+        let proven: BlockProofJournal = decode_journal(&self.journal)
+            .map_err(|err| BlockProofError::InvalidJournal(err.to_string()))?;
+
+        if proven.pre_state_root != local.pre_state_root {
+            return Err(BlockProofError::PreStateRootMismatch {
+                proven: proven.pre_state_root,
+                local: local.pre_state_root,
+            });
+        }
+        if proven.post_state_root != local.post_state_root {
+            return Err(BlockProofError::PostStateRootMismatch {
+                proven: proven.post_state_root,
+                local: local.post_state_root,
+            });
+        }
+        if proven.block_hash != local.block_hash {
+            return Err(BlockProofError::BlockHashMismatch {
+                proven: proven.block_hash,
+                local: local.block_hash,
+            });
+        }
+        if proven.tx_count != local.tx_count {
+            return Err(BlockProofError::TxCountMismatch {
+                proven: proven.tx_count,
+                local: local.tx_count,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Reason to reject a block proof, see [`BlockProof::check_journal`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BlockProofError {
+    #[error("Block proof journal is invalid: {0}")]
+    InvalidJournal(String),
+    #[error(
+        "Block proof is for pre-state root {}, but the block is applied to {}",
+        hex::encode(proven),
+        hex::encode(local)
+    )]
+    PreStateRootMismatch { proven: [u8; 32], local: [u8; 32] },
+    #[error(
+        "Block proof is for post-state root {}, but the block results in {}",
+        hex::encode(proven),
+        hex::encode(local)
+    )]
+    PostStateRootMismatch { proven: [u8; 32], local: [u8; 32] },
+    #[error(
+        "Block proof is for block hash {}, but the block has hash {}",
+        hex::encode(proven),
+        hex::encode(local)
+    )]
+    BlockHashMismatch { proven: BlockHash, local: BlockHash },
+    #[error("Block proof is for {proven} transactions, but the block has {local}")]
+    TxCountMismatch { proven: u32, local: u32 },
+}
+
+/// Proving status of a committed block, stored next to the block
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum BlockProofRecord {
+    /// Block is committed, but not proven yet
+    Pending,
+    Proven(BlockProof),
+    /// Block couldn't be proven, which means the sequencer state diverged from the proven one
+    Failed {
+        reason: String,
+    },
+}
+
+/// Block to prove together with the state it was applied to
+pub struct ProvingJob {
+    pub block_id: u64,
+    pub block_hash: BlockHash,
+    pub pre_state: V02State,
+    pub transactions: Vec<NSSATransaction>,
+}
+
+/// Background workers, proving committed blocks
+///
+/// Jobs are queued into a bounded channel. When it's full, jobs wait in the backlog, so block
+/// production is never blocked by proving. Workers stop after their current jobs once this is
+/// dropped.
+pub struct ProvingWorkers {
+    jobs: SyncSender<ProvingJob>,
+    results: Receiver<(u64, BlockProofRecord)>,
+    backlog: VecDeque<ProvingJob>,
+}
+
+impl ProvingWorkers {
+    pub fn spawn(config: &LazyProvingConfig) -> Self {
+        let (jobs_sender, jobs_receiver) = mpsc::sync_channel::<ProvingJob>(config.queue_size);
+        let (results_sender, results) = mpsc::channel();
+        let jobs_receiver = Arc::new(Mutex::new(jobs_receiver));
+        let backend = config.prover_backend;
+
+        for _ in 0..config.parallelism.max(1) {
+            let jobs_receiver = Arc::clone(&jobs_receiver);
+            let results_sender = results_sender.clone();
+            std::thread::spawn(move || {
+                loop {
+                    // Lock is released before proving, so other workers may take jobs
+                    let job = jobs_receiver.lock().unwrap().recv();
+                    let Ok(job) = job else {
+                        break;
+                    };
+                    let block_id = job.block_id;
+                    let record = prove_block(job, backend);
+                    if results_sender.send((block_id, record)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Self {
+            jobs: jobs_sender,
+            results,
+            backlog: VecDeque::new(),
+        }
+    }
+
+    pub fn submit(&mut self, job: ProvingJob) {
+        self.backlog.push_back(job);
+        self.flush_backlog();
+    }
+
+    /// Records of blocks proven since the last call
+    pub fn finished(&mut self) -> Vec<(u64, BlockProofRecord)> {
+        self.flush_backlog();
+        self.results.try_iter().collect()
+    }
+
+    /// Number of submitted blocks, which didn't fit into the queue yet
+    pub fn backlog_len(&self) -> usize {
+        self.backlog.len()
+    }
+
+    fn flush_backlog(&mut self) {
+        while let Some(job) = self.backlog.pop_front() {
+            match self.jobs.try_send(job) {
+                Ok(()) => {}
+                Err(TrySendError::Full(job)) => {
+                    self.backlog.push_front(job);
+                    break;
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    warn!("All proving workers are gone");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Re-executes transactions of the block on its pre-state, proving public program executions
+/// with `backend`
+pub fn prove_block(job: ProvingJob, backend: ProverBackend) -> BlockProofRecord {
+    let ProvingJob {
+        block_id,
+        block_hash,
+        mut pre_state,
+        transactions,
+    } = job;
+    let pre_state_root = pre_state.state_root();
+    pre_state.set_block_id(block_id);
+
+    let mut program_executions = vec![];
+    for (index, tx) in transactions.iter().enumerate() {
+        let res = match tx {
+            NSSATransaction::Public(tx) => pre_state
+                .prove_and_transition_from_public_transaction(tx, backend)
+                .map(|(_, proofs)| program_executions.extend(proofs)),
+            NSSATransaction::PrivacyPreserving(tx) => {
+                pre_state.transition_from_privacy_preserving_transaction(tx)
+            }
+            NSSATransaction::ProgramDeployment(tx) => pre_state
+                .transition_from_program_deployment_transaction(tx)
+                .map(|_| ()),
+        };
+        if let Err(err) = res {
+            return BlockProofRecord::Failed {
+                reason: format!("Transaction {index} of the block failed: {err}"),
+            };
+        }
+    }
+
+    let journal = BlockProofJournal {
+        pre_state_root,
+        post_state_root: pre_state.state_root(),
+        block_hash,
+        tx_count: transactions.len() as u32,
+    };
+    match encode_journal(&journal) {
+        Ok(journal) => BlockProofRecord::Proven(BlockProof {
+            block_id,
+            journal,
+            program_executions,
+        }),
+        Err(err) => BlockProofRecord::Failed {
+            reason: err.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_journal() -> BlockProofJournal {
+        BlockProofJournal {
+            pre_state_root: [1; 32],
+            post_state_root: [2; 32],
+            block_hash: [3; 32],
+            tx_count: 4,
+        }
+    }
+
+    fn proof_with_journal(journal: &BlockProofJournal) -> BlockProof {
+        BlockProof {
+            block_id: 2,
+            journal: encode_journal(journal).unwrap(),
+            program_executions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_journal_matching_local_values_is_accepted() {
+        let proof = proof_with_journal(&local_journal());
+
+        assert_eq!(proof.check_journal(&local_journal()), Ok(()));
+    }
+
+    #[test]
+    fn test_tampered_pre_state_root_is_rejected() {
+        let proof = proof_with_journal(&BlockProofJournal {
+            pre_state_root: [9; 32],
+            ..local_journal()
+        });
+
+        assert_eq!(
+            proof.check_journal(&local_journal()),
+            Err(BlockProofError::PreStateRootMismatch {
+                proven: [9; 32],
+                local: [1; 32],
+            })
+        );
+    }
+
+    #[test]
+    fn test_tampered_post_state_root_is_rejected() {
+        let proof = proof_with_journal(&BlockProofJournal {
+            post_state_root: [9; 32],
+            ..local_journal()
+        });
+
+        assert_eq!(
+            proof.check_journal(&local_journal()),
+            Err(BlockProofError::PostStateRootMismatch {
+                proven: [9; 32],
+                local: [2; 32],
+            })
+        );
+    }
+
+    #[test]
+    fn test_tampered_block_hash_is_rejected() {
+        let proof = proof_with_journal(&BlockProofJournal {
+            block_hash: [9; 32],
+            ..local_journal()
+        });
+
+        assert_eq!(
+            proof.check_journal(&local_journal()),
+            Err(BlockProofError::BlockHashMismatch {
+                proven: [9; 32],
+                local: [3; 32],
+            })
+        );
+    }
+
+    #[test]
+    fn test_tampered_tx_count_is_rejected() {
+        let proof = proof_with_journal(&BlockProofJournal {
+            tx_count: 5,
+            ..local_journal()
+        });
+
+        assert_eq!(
+            proof.check_journal(&local_journal()),
+            Err(BlockProofError::TxCountMismatch {
+                proven: 5,
+                local: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_truncated_journal_is_rejected() {
+        let mut proof = proof_with_journal(&local_journal());
+        proof.journal.truncate(8);
+
+        assert!(matches!(
+            proof.check_journal(&local_journal()),
+            Err(BlockProofError::InvalidJournal(_))
+        ));
+    }
+}
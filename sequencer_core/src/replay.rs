@@ -0,0 +1,246 @@
+//! Deterministic replay of stored blocks, used to debug state divergence between nodes.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{Context as _, Result};
+use nssa::{Account, V02State};
+use nssa_core::BlockProofJournal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    block_store::SequencerBlockStore,
+    config::SequencerConfig,
+    execute_transaction_on_state, genesis_block_data, genesis_state,
+    invariants::StateInvariants,
+    params::ParamsSchedule,
+    pre_check_transactions,
+    proof_pipeline::ProofPipeline,
+    proving::BlockProofRecord,
+    public_balance_of,
+    stored_params::{StoredParams, params_fingerprint},
+    touched_account_ids,
+};
+
+/// Public accounts of a state at some block, exported to compare states of different nodes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub block_id: u64,
+    /// Hex encoded [`V02State::state_root`]
+    pub state_root: String,
+    /// Accounts by base58 encoded ids
+    pub accounts: BTreeMap<String, Account>,
+}
+
+/// Account, which differs between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub account_id: String,
+    /// Account in the snapshot, which diff was called on, `None` if it's missing there
+    pub ours: Option<Account>,
+    /// Account in the other snapshot, `None` if it's missing there
+    pub theirs: Option<Account>,
+}
+
+impl StateSnapshot {
+    pub fn from_state(state: &V02State, block_id: u64) -> Self {
+        Self {
+            block_id,
+            state_root: hex::encode(state.state_root()),
+            accounts: state
+                .public_accounts()
+                .map(|(account_id, account)| (account_id.to_string(), account.clone()))
+                .collect(),
+        }
+    }
+
+    /// Every account, which is different or missing in one of the snapshots, ordered by id
+    pub fn diff(&self, other: &Self) -> Vec<AccountDiff> {
+        let mut account_ids = self.accounts.keys().collect::<Vec<_>>();
+        account_ids.extend(
+            other
+                .accounts
+                .keys()
+                .filter(|account_id| !self.accounts.contains_key(*account_id)),
+        );
+        account_ids.sort_unstable();
+
+        account_ids
+            .into_iter()
+            .filter_map(|account_id| {
+                let ours = self.accounts.get(account_id);
+                let theirs = other.accounts.get(account_id);
+                (ours != theirs).then(|| AccountDiff {
+                    account_id: account_id.clone(),
+                    ours: ours.cloned(),
+                    theirs: theirs.cloned(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Rebuilds state from genesis of `config` by applying stored blocks one by one with full
+/// validation. Journals of stored block proofs are checked against the replayed states, and
+/// receipts of the proofs are verified ahead of replay if
+/// [`crate::config::LazyProvingConfig::verify_proofs`] is set.
+///
+/// Blocks are read from the store lazily, so memory usage doesn't grow with the chain.
+pub struct Replay {
+    state: V02State,
+    state_invariants: StateInvariants,
+    block_store: SequencerBlockStore,
+    last_block_id: u64,
+    /// Timestamp of the last applied block, which the next one must not precede
+    last_timestamp: u64,
+    max_block_timestamp_skew_millis: u64,
+    params_schedule: ParamsSchedule,
+    /// Verification of receipts of stored block proofs, `None` if only journals are checked
+    proof_pipeline: Option<ProofPipeline>,
+}
+
+impl Replay {
+    /// Opens block store at `db_path`, which must not be used by a running sequencer
+    pub fn open(config: &SequencerConfig, db_path: &Path) -> Result<Self> {
+        anyhow::ensure!(
+            db_path.exists(),
+            "Block store {} doesn't exist",
+            db_path.display()
+        );
+
+        let params_schedule = ParamsSchedule::from_config(config)?;
+        let signing_key = nssa::PrivateKey::try_new(config.signing_key)?;
+        let block_store = SequencerBlockStore::open_db_restart(db_path, signing_key)
+            .with_context(|| format!("Failed to open block store {}", db_path.display()))?;
+        let stored_genesis = block_store.get_block_header_at_id(block_store.genesis_id())?;
+        anyhow::ensure!(
+            stored_genesis.hash == genesis_block_data(config).hash(),
+            "Block store {} belongs to another chain, program allow-list may differ",
+            db_path.display()
+        );
+        // Stored blocks are validated with the parameters they are built with, never forced ones
+        StoredParams::reconcile(
+            block_store.stored_params()?.as_ref(),
+            params_fingerprint(config, stored_genesis.hash),
+            block_store.last_block_id()?,
+            false,
+        )?;
+        let (state, initial_shielded_supply) = genesis_state(config);
+        // Invariants are always checked while replaying
+        let state_invariants = StateInvariants::new(&state, initial_shielded_supply, true);
+        let proof_pipeline = config
+            .lazy_proving
+            .as_ref()
+            .and_then(|lazy_proving| lazy_proving.verify_proofs)
+            .map(|verify_proofs| ProofPipeline::spawn(&verify_proofs, state.accept_dev_proofs()));
+
+        Ok(Self {
+            state,
+            state_invariants,
+            last_block_id: block_store.genesis_id(),
+            last_timestamp: stored_genesis.timestamp,
+            max_block_timestamp_skew_millis: config.max_block_timestamp_skew_millis,
+            params_schedule,
+            proof_pipeline,
+            block_store,
+        })
+    }
+
+    /// Applies the next stored block and returns its id, or `None` if there are no more blocks
+    pub fn apply_next_block(&mut self) -> Result<Option<u64>> {
+        let block_id = self.last_block_id + 1;
+        let Ok(block) = self.block_store.get_block_at_id(block_id) else {
+            return Ok(None);
+        };
+
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        block
+            .header
+            .check_timestamp(
+                self.last_timestamp,
+                now,
+                self.max_block_timestamp_skew_millis,
+            )
+            .with_context(|| format!("Block {block_id} is rejected"))?;
+        if block.header.dev_proved && !self.state.accept_dev_proofs() {
+            return Err(anyhow::Error::new(nssa::error::NssaError::DevProofRejected)
+                .context(format!("Block {block_id} is rejected")));
+        }
+        self.params_schedule.check_block(&block)?;
+        if let Some(proof_pipeline) = &mut self.proof_pipeline {
+            let block_store = &self.block_store;
+            proof_pipeline.prefetch(block_id, block_store.last_block_id()?, |block_id| {
+                Ok(match block_store.get_block_proof_record(block_id)? {
+                    Some(BlockProofRecord::Proven(proof)) => Some(proof),
+                    _ => None,
+                })
+            })?;
+            proof_pipeline
+                .take_verdict(block_id)
+                .with_context(|| format!("Proof of block {block_id} is rejected"))?;
+        }
+        self.params_schedule
+            .params_at(block_id)
+            .1
+            .apply_to(&mut self.state);
+
+        // Proven block is checked together with the root of the state it's applied to
+        let proof = match self.block_store.get_block_proof_record(block_id)? {
+            Some(BlockProofRecord::Proven(proof)) => Some((proof, self.state.state_root())),
+            _ => None,
+        };
+
+        self.state.set_block_id(block_id);
+        for (encoded_transaction, transaction) in block
+            .body
+            .transactions
+            .iter()
+            .zip(pre_check_transactions(&block.body.transactions))
+        {
+            let tx_hash = hex::encode(encoded_transaction.hash());
+            let transaction = transaction.map_err(|err| {
+                anyhow::anyhow!("Transaction {tx_hash} in block {block_id} is malformed: {err:?}")
+            })?;
+
+            let touched_account_ids = touched_account_ids(&self.state, &transaction);
+            let pre_balance = public_balance_of(&self.state, &touched_account_ids);
+            execute_transaction_on_state(&mut self.state, &transaction).with_context(|| {
+                format!("Transaction {tx_hash} in block {block_id} failed to execute")
+            })?;
+            let post_balance = public_balance_of(&self.state, &touched_account_ids);
+            self.state_invariants
+                .record_transaction(&transaction, pre_balance, post_balance)
+                .with_context(|| format!("Transaction {tx_hash} in block {block_id}"))?;
+        }
+        self.state_invariants
+            .check_state(&self.state)
+            .with_context(|| format!("State after block {block_id}"))?;
+
+        if let Some((proof, pre_state_root)) = proof {
+            proof
+                .check_journal(&BlockProofJournal {
+                    pre_state_root,
+                    post_state_root: self.state.state_root(),
+                    block_hash: block.header.hash,
+                    tx_count: block.body.transactions.len() as u32,
+                })
+                .with_context(|| format!("Proof of block {block_id} is rejected"))?;
+        }
+
+        self.last_block_id = block_id;
+        self.last_timestamp = block.header.timestamp;
+        Ok(Some(block_id))
+    }
+
+    /// Id of the last applied block, genesis id if none were applied
+    pub fn last_block_id(&self) -> u64 {
+        self.last_block_id
+    }
+
+    pub fn state(&self) -> &V02State {
+        &self.state
+    }
+
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot::from_state(&self.state, self.last_block_id)
+    }
+}
@@ -0,0 +1,156 @@
+//! Record of genesis and chain parameters, which stored blocks are built with.
+//!
+//! A sequencer restarted over existing storage with edited parameters would build blocks, which
+//! nodes replaying the earlier ones validate differently. So the fingerprint of parameters is
+//! stored next to the blocks on the first start and compared on every later one. An operator,
+//! who changes parameters of a running chain on purpose, passes `--force-params-change`, which
+//! records the change at the current height.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use common::block::{BlockHash, BlockId};
+use serde::Serialize;
+use sha2::{Digest as _, Sha256};
+
+use crate::config::{ChainParams, ParamsUpgrade, SequencerConfig};
+
+pub type ParamsFingerprint = [u8; 32];
+
+/// Everything, which blocks built on top of genesis must agree on
+#[derive(Serialize)]
+struct FingerprintedParams<'a> {
+    genesis_hash: BlockHash,
+    chain_id: u64,
+    genesis_params: ChainParams,
+    upgrades: &'a [ParamsUpgrade],
+}
+
+/// Fingerprint of genesis `genesis_hash` and parameters schedule of `config`
+pub fn params_fingerprint(config: &SequencerConfig, genesis_hash: BlockHash) -> ParamsFingerprint {
+    let params = FingerprintedParams {
+        genesis_hash,
+        chain_id: config.chain_id,
+        genesis_params: config.genesis_params(),
+        upgrades: &config.upgrades,
+    };
+    Sha256::digest(serde_json::to_vec(&params).expect("Params are serializable")).into()
+}
+
+/// Parameters change, which was forced over existing storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ForcedParamsChange {
+    /// Last stored block at the time of the change. Blocks after it are built with the new
+    /// parameters.
+    pub block_id: BlockId,
+    pub previous_fingerprint: ParamsFingerprint,
+    pub fingerprint: ParamsFingerprint,
+}
+
+/// Parameters, which stored blocks are built with
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct StoredParams {
+    pub fingerprint: ParamsFingerprint,
+    /// In order of heights
+    pub forced_changes: Vec<ForcedParamsChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Chain parameters of the config (fingerprint {}) don't match the ones stored blocks are built \
+     with (fingerprint {}). Restore genesis and chain parameters of the config, or pass \
+     --force-params-change to build blocks after {block_id} with the new ones.",
+    hex::encode(configured),
+    hex::encode(stored)
+)]
+pub struct ParamsMismatchError {
+    pub stored: ParamsFingerprint,
+    pub configured: ParamsFingerprint,
+    /// Last stored block
+    pub block_id: BlockId,
+}
+
+impl StoredParams {
+    /// Record of parameters after starting with `fingerprint` over `stored` ones, which blocks
+    /// up to `block_id` are built with. `None` if the record doesn't change.
+    ///
+    /// Mismatching parameters are refused unless `force` is set, which records the change.
+    pub fn reconcile(
+        stored: Option<&Self>,
+        fingerprint: ParamsFingerprint,
+        block_id: BlockId,
+        force: bool,
+    ) -> Result<Option<Self>, ParamsMismatchError> {
+        let Some(stored) = stored else {
+            // Storage of a new chain, or one of an older sequencer, which didn't record them
+            return Ok(Some(Self {
+                fingerprint,
+                forced_changes: vec![],
+            }));
+        };
+        if stored.fingerprint == fingerprint {
+            return Ok(None);
+        }
+        if !force {
+            return Err(ParamsMismatchError {
+                stored: stored.fingerprint,
+                configured: fingerprint,
+                block_id,
+            });
+        }
+
+        let mut forced_changes = stored.forced_changes.clone();
+        forced_changes.push(ForcedParamsChange {
+            block_id,
+            previous_fingerprint: stored.fingerprint,
+            fingerprint,
+        });
+        Ok(Some(Self {
+            fingerprint,
+            forced_changes,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mismatching_params_are_refused_unless_forced() {
+        let stored = StoredParams::reconcile(None, [1; 32], 1, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            StoredParams::reconcile(Some(&stored), [1; 32], 5, false),
+            Ok(None)
+        );
+
+        assert_eq!(
+            StoredParams::reconcile(Some(&stored), [2; 32], 5, false),
+            Err(ParamsMismatchError {
+                stored: [1; 32],
+                configured: [2; 32],
+                block_id: 5,
+            })
+        );
+
+        let changed = StoredParams::reconcile(Some(&stored), [2; 32], 5, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            changed,
+            StoredParams {
+                fingerprint: [2; 32],
+                forced_changes: vec![ForcedParamsChange {
+                    block_id: 5,
+                    previous_fingerprint: [1; 32],
+                    fingerprint: [2; 32],
+                }],
+            }
+        );
+        // Forcing the parameters, which are stored already, records nothing
+        assert_eq!(
+            StoredParams::reconcile(Some(&changed), [2; 32], 7, true),
+            Ok(None)
+        );
+    }
+}
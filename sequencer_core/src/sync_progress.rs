@@ -0,0 +1,181 @@
+//! Progress of catching up with the chain tip, shared by logs, RPC and readiness checks.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use common::rpc_types::{SyncState, SyncStatus};
+use log::info;
+
+/// Summary is logged at least every this many applied blocks while syncing
+pub const SYNC_LOG_EVERY_BLOCKS: u64 = 100;
+/// Summary is logged at least this often while syncing
+pub const SYNC_LOG_INTERVAL: Duration = Duration::from_secs(10);
+/// Period, over which the sync rate is measured
+pub const SYNC_RATE_WINDOW: Duration = Duration::from_secs(30);
+
+pub struct SyncProgress {
+    start_height: u64,
+    current_height: u64,
+    target_height: u64,
+    /// Applied heights with times of their application, oldest first. The oldest sample may be
+    /// outside of the window, it's the baseline for the rate.
+    samples: VecDeque<(Instant, u64)>,
+    /// Time and height of the last logged summary
+    last_logged: (Instant, u64),
+}
+
+impl SyncProgress {
+    pub fn new(start_height: u64, target_height: u64, now: Instant) -> Self {
+        Self {
+            start_height,
+            current_height: start_height,
+            target_height: target_height.max(start_height),
+            samples: VecDeque::from([(now, start_height)]),
+            last_logged: (now, start_height),
+        }
+    }
+
+    /// Records that blocks up to `height` were applied at `now`
+    pub fn record(&mut self, height: u64, now: Instant) {
+        let was_synced = self.is_synced();
+        self.current_height = height;
+        self.target_height = self.target_height.max(height);
+
+        self.samples.push_back((now, height));
+        while self.samples.len() > 2
+            && now.saturating_duration_since(self.samples[1].0) >= SYNC_RATE_WINDOW
+        {
+            self.samples.pop_front();
+        }
+
+        if was_synced {
+            return;
+        }
+        let (logged_at, logged_height) = self.last_logged;
+        if self.is_synced()
+            || height.saturating_sub(logged_height) >= SYNC_LOG_EVERY_BLOCKS
+            || now.saturating_duration_since(logged_at) >= SYNC_LOG_INTERVAL
+        {
+            self.log_summary();
+            self.last_logged = (now, height);
+        }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.current_height >= self.target_height
+    }
+
+    /// Applied blocks per second over the last [`SYNC_RATE_WINDOW`]
+    pub fn blocks_per_sec(&self) -> f64 {
+        let (Some((first_at, first_height)), Some((last_at, last_height))) =
+            (self.samples.front(), self.samples.back())
+        else {
+            return 0.0;
+        };
+
+        let elapsed = last_at.saturating_duration_since(*first_at).as_secs_f64();
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+        (last_height - first_height) as f64 / elapsed
+    }
+
+    /// Estimated time left, `None` if the rate isn't known yet
+    pub fn eta(&self) -> Option<Duration> {
+        if self.is_synced() {
+            return Some(Duration::ZERO);
+        }
+
+        let rate = self.blocks_per_sec();
+        (rate > 0.0).then(|| {
+            Duration::from_secs_f64((self.target_height - self.current_height) as f64 / rate)
+        })
+    }
+
+    pub fn status(&self) -> SyncStatus {
+        SyncStatus {
+            state: if self.is_synced() {
+                SyncState::Synced
+            } else {
+                SyncState::Syncing
+            },
+            start_height: self.start_height,
+            current_height: self.current_height,
+            target_height: self.target_height,
+            blocks_per_sec: self.blocks_per_sec(),
+            eta_secs: self.eta().map(|eta| eta.as_secs()),
+        }
+    }
+
+    fn log_summary(&self) {
+        if self.is_synced() {
+            info!(
+                "Synced {} blocks up to {}",
+                self.current_height - self.start_height,
+                self.current_height
+            );
+            return;
+        }
+
+        let eta = self.eta().map_or_else(
+            || "unknown".to_string(),
+            |eta| format!("{}s", eta.as_secs()),
+        );
+        info!(
+            "Syncing: block {} of {}, {:.1} blocks/s, ETA {eta}",
+            self.current_height,
+            self.target_height,
+            self.blocks_per_sec()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_up_reports_rate_and_eta() {
+        let start = Instant::now();
+        let mut progress = SyncProgress::new(0, 1000, start);
+        assert_eq!(progress.status().state, SyncState::Syncing);
+        assert_eq!(progress.eta(), None);
+
+        // One block every 10ms, so 100 blocks per second
+        for height in 1..=500 {
+            progress.record(height, start + Duration::from_millis(10 * height));
+        }
+        let status = progress.status();
+        assert_eq!(status.state, SyncState::Syncing);
+        assert_eq!(status.current_height, 500);
+        assert!((status.blocks_per_sec - 100.0).abs() < 1.0);
+        assert_eq!(status.eta_secs, Some(5));
+
+        // Sync slows down to 10 blocks per second, old samples leave the window
+        let slowdown_at = start + Duration::from_millis(5000);
+        for height in 501..=900 {
+            let since_slowdown = Duration::from_millis(100 * (height - 500));
+            progress.record(height, slowdown_at + since_slowdown);
+        }
+        let status = progress.status();
+        assert!((status.blocks_per_sec - 10.0).abs() < 1.0);
+        assert_eq!(status.eta_secs, Some(10));
+
+        for height in 901..=1000 {
+            let since_slowdown = Duration::from_millis(100 * (height - 500));
+            progress.record(height, slowdown_at + since_slowdown);
+        }
+        let status = progress.status();
+        assert_eq!(status.state, SyncState::Synced);
+        assert_eq!(status.current_height, 1000);
+        assert_eq!(status.eta_secs, Some(0));
+    }
+
+    #[test]
+    fn test_nothing_to_sync() {
+        let progress = SyncProgress::new(5, 5, Instant::now());
+        assert_eq!(progress.status().state, SyncState::Synced);
+    }
+}
@@ -0,0 +1,204 @@
+//! Bounded audit log of mempool lifecycle events of transactions, kept for post-mortem analysis
+//! of transactions, which weren't included as expected.
+//!
+//! Events of the latest transactions are kept in memory and served by `get_tx_audit` of the admin
+//! RPC. If enabled, every event is also appended to a newline-delimited JSON file in the
+//! sequencer home. The file isn't bounded, it's left to the operator to rotate it.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{File, OpenOptions},
+    io::Write as _,
+    path::Path,
+};
+
+use anyhow::Result;
+use common::{
+    HashType,
+    rpc_types::{TxAuditEvent, TxAuditRecord},
+};
+use log::warn;
+use nssa_core::fmt::FmtShort as _;
+use serde::Serialize;
+
+/// Name of the audit file in the sequencer home directory
+pub const TX_AUDIT_FILE_NAME: &str = "tx_audit.ndjson";
+/// Number of transactions, which events are remembered by the sequencer by default
+pub const TX_AUDIT_CAPACITY: usize = 10_000;
+/// Number of the latest events remembered per transaction, so one resubmitted over and over
+/// doesn't grow without bound
+const MAX_EVENTS_PER_TRANSACTION: usize = 16;
+
+/// Line of the audit file
+#[derive(Serialize)]
+struct AuditLine<'a> {
+    /// Hex encoded transaction hash
+    hash: String,
+    #[serde(flatten)]
+    record: &'a TxAuditRecord,
+}
+
+/// Events of the latest transactions, the ones of the oldest transactions are forgotten first
+pub struct TxAuditLog {
+    capacity: usize,
+    order: VecDeque<HashType>,
+    events: HashMap<HashType, VecDeque<TxAuditRecord>>,
+    /// Set if events are appended to the audit file
+    file: Option<File>,
+}
+
+impl TxAuditLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::new(),
+            events: HashMap::new(),
+            file: None,
+        }
+    }
+
+    /// Audit log, which also appends events to the audit file in `home`, creating it if necessary
+    pub fn open(home: &Path, capacity: usize) -> Result<Self> {
+        std::fs::create_dir_all(home)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(home.join(TX_AUDIT_FILE_NAME))?;
+
+        Ok(Self {
+            file: Some(file),
+            ..Self::new(capacity)
+        })
+    }
+
+    /// Records `event` of the transaction with the given hash at the current time
+    ///
+    /// Failure to append it to the audit file is logged, the event is kept in memory anyway.
+    pub fn record(&mut self, hash: HashType, event: TxAuditEvent) {
+        let record = TxAuditRecord {
+            timestamp_millis: chrono::Utc::now().timestamp_millis() as u64,
+            event,
+        };
+        if let Some(file) = &mut self.file
+            && let Err(err) = append_line(file, &hash, &record)
+        {
+            warn!(
+                "Failed to append audit event of transaction {}: {err:#}",
+                hash.fmt_short()
+            );
+        }
+
+        let events = self.events.entry(hash).or_insert_with(|| {
+            self.order.push_back(hash);
+            VecDeque::new()
+        });
+        if events.len() >= MAX_EVENTS_PER_TRANSACTION {
+            events.pop_front();
+        }
+        events.push_back(record);
+
+        if self.order.len() > self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.events.remove(&oldest);
+        }
+    }
+
+    /// Events of the transaction with the given hash in order of recording, empty if it's unknown
+    /// or forgotten
+    pub fn events(&self, hash: &HashType) -> Vec<TxAuditRecord> {
+        self.events
+            .get(hash)
+            .map(|events| events.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for TxAuditLog {
+    fn default() -> Self {
+        Self::new(TX_AUDIT_CAPACITY)
+    }
+}
+
+fn append_line(file: &mut File, hash: &HashType, record: &TxAuditRecord) -> Result<()> {
+    let mut line = serde_json::to_vec(&AuditLine {
+        hash: hex::encode(hash),
+        record,
+    })?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn included(block_id: u64) -> TxAuditEvent {
+        TxAuditEvent::Included { block_id }
+    }
+
+    fn event_kinds(audit: &TxAuditLog, hash: &HashType) -> Vec<TxAuditEvent> {
+        audit
+            .events(hash)
+            .into_iter()
+            .map(|record| record.event)
+            .collect()
+    }
+
+    #[test]
+    fn test_events_of_oldest_transactions_are_forgotten() {
+        let mut audit = TxAuditLog::new(2);
+        audit.record([1; 32], included(1));
+        audit.record([2; 32], included(2));
+        // More events of a known transaction don't evict anything
+        audit.record([1; 32], included(3));
+        assert_eq!(event_kinds(&audit, &[1; 32]), [included(1), included(3)]);
+
+        audit.record([3; 32], included(4));
+        assert!(audit.events(&[1; 32]).is_empty());
+        assert_eq!(event_kinds(&audit, &[2; 32]), [included(2)]);
+        assert_eq!(event_kinds(&audit, &[3; 32]), [included(4)]);
+    }
+
+    #[test]
+    fn test_events_per_transaction_are_bounded() {
+        let mut audit = TxAuditLog::default();
+        for block_id in 0..MAX_EVENTS_PER_TRANSACTION as u64 + 2 {
+            audit.record([1; 32], included(block_id));
+        }
+
+        let events = event_kinds(&audit, &[1; 32]);
+        assert_eq!(events.len(), MAX_EVENTS_PER_TRANSACTION);
+        assert_eq!(events[0], included(2));
+    }
+
+    #[test]
+    fn test_events_are_appended_to_audit_file() {
+        let home = tempdir().unwrap();
+        let mut audit = TxAuditLog::open(home.path(), 1).unwrap();
+        audit.record([1; 32], included(1));
+        audit.record(
+            [2; 32],
+            TxAuditEvent::Evicted {
+                reason: "Nonce mismatch".to_string(),
+            },
+        );
+
+        let contents = std::fs::read_to_string(home.path().join(TX_AUDIT_FILE_NAME)).unwrap();
+        let lines = contents
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["hash"], hex::encode([1; 32]));
+        assert_eq!(lines[0]["event"], "included");
+        assert_eq!(lines[0]["block_id"], 1);
+        assert_eq!(lines[1]["event"], "evicted");
+        assert_eq!(lines[1]["reason"], "Nonce mismatch");
+        // Forgotten events stay in the file
+        assert!(audit.events(&[1; 32]).is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use actix_web::{Error as HttpError, HttpRequest, HttpResponse, http, web};
+use common::{
+    HashType,
+    rpc_primitives::{
+        errors::RpcError,
+        message::{Message, Request},
+        parser::RpcRequest,
+    },
+    rpc_types::{
+        GetTxAuditRequest, GetTxAuditResponse, RebuildIndexesRequest, SendSystemTxRequest,
+        SendTxResponse, TransactionStatus,
+    },
+};
+use log::{info, warn};
+use sequencer_core::index_repair::INDEX_REBUILD_BATCH_SIZE;
+use serde_json::Value;
+use subtle::ConstantTimeEq;
+
+use super::{
+    JsonHandler,
+    ingest::already_submitted_status,
+    process::{
+        CheckedSubmission, PROGRAM_ALREADY_DEPLOYED, TRANSACTION_ALREADY_SUBMITTED,
+        TRANSACTION_SUBMITTED,
+    },
+    respond,
+    types::err_rpc::RpcErr,
+};
+
+pub const SEND_SYSTEM_TX: &str = "send_system_tx";
+pub const REBUILD_INDEXES: &str = "rebuild_indexes";
+pub const GET_TX_AUDIT: &str = "get_tx_audit";
+
+/// Serves methods of the admin RPC to requests carrying the auth token of the system lane as a
+/// bearer token. Not served at all if the system lane isn't configured.
+pub(crate) async fn admin_rpc_handler(
+    http_request: HttpRequest,
+    message: web::Json<Message>,
+    handler: web::Data<JsonHandler>,
+) -> Result<HttpResponse, HttpError> {
+    let Some(auth_token) = handler.admin_auth_token().await else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let bearer_token = http_request
+        .headers()
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    // Compared in constant time, so the token can't be guessed byte by byte
+    if !bearer_token.is_some_and(|bearer_token| {
+        bool::from(auth_token.as_bytes().ct_eq(bearer_token.as_bytes()))
+    }) {
+        warn!("Admin request without valid auth token refused");
+        return Ok(HttpResponse::Unauthorized().finish());
+    }
+
+    let message = handler.process_admin(message.0).await?;
+    Ok(HttpResponse::Ok().json(&message))
+}
+
+impl JsonHandler {
+    async fn admin_auth_token(&self) -> Option<String> {
+        self.sequencer_state
+            .lock()
+            .await
+            .sequencer_config()
+            .system_lane
+            .as_ref()
+            .map(|system_lane| system_lane.auth_token.clone())
+    }
+
+    async fn process_admin(&self, message: Message) -> Result<Message, HttpError> {
+        let id = message.id();
+        let Message::Request(request) = message else {
+            return Ok(Message::error(RpcError::parse_error(
+                "JSON RPC Request format was expected".to_owned(),
+            )));
+        };
+
+        // Admin methods submit transactions or rebuild indexes, so they have the time budget of
+        // submissions
+        let timeout = self.timeouts_config.timeout(&request.method, true);
+        let method = request.method.clone();
+        let message_inner = tokio::time::timeout(timeout, self.process_admin_request(request))
+            .await
+            .unwrap_or_else(|_| Err(RpcErr(RpcError::server_busy(&method, timeout))))
+            .map_err(|e| e.0);
+        Ok(Message::response(id, message_inner))
+    }
+
+    async fn process_admin_request(&self, request: Request) -> Result<Value, RpcErr> {
+        match request.method.as_ref() {
+            SEND_SYSTEM_TX => self.process_send_system_tx(request).await,
+            REBUILD_INDEXES => self.process_rebuild_indexes(request).await,
+            GET_TX_AUDIT => self.process_get_tx_audit(request).await,
+            _ => Err(RpcErr(RpcError::method_not_found(request.method))),
+        }
+    }
+
+    /// Submits the transaction into the system lane after the admission checks of `send_tx`,
+    /// bypassing mempool
+    async fn process_send_system_tx(&self, request: Request) -> Result<Value, RpcErr> {
+        let send_tx_req = SendSystemTxRequest::parse(Some(request.params))?;
+        let CheckedSubmission {
+            tx,
+            hash,
+            program_id,
+            already_deployed,
+        } = self.check_submission(&send_tx_req.transaction).await?;
+        let tx_hash = hex::encode(hash);
+
+        let (status, tx_status) = if already_deployed {
+            (PROGRAM_ALREADY_DEPLOYED, None)
+        } else {
+            let mut state = self.sequencer_state.lock().await;
+            if already_submitted_status(&mut state, &hash).is_none() {
+                state.take_transactions_from_mempool();
+                state.check_balance_reservation(&tx)?;
+            }
+            let status = if state.submit_system_transaction(hash, tx)? {
+                info!("System transaction {tx_hash} submitted");
+                TRANSACTION_SUBMITTED
+            } else {
+                TRANSACTION_ALREADY_SUBMITTED
+            };
+            (status, Some(state.transaction_status(&hash)))
+        };
+        let (mempool_position, mempool_depth, estimated_inclusion_block) = match &tx_status {
+            Some(TransactionStatus::Pending {
+                position,
+                depth,
+                estimated_inclusion_block,
+                ..
+            }) => (
+                Some(*position),
+                Some(*depth),
+                Some(*estimated_inclusion_block),
+            ),
+            _ => (None, None, None),
+        };
+
+        respond(SendTxResponse {
+            status: status.to_string(),
+            tx_hash,
+            mempool_position,
+            mempool_depth,
+            estimated_inclusion_block,
+            tx_status,
+            soft_confirmation: None,
+            program_id,
+        })
+    }
+
+    /// Rebuilds indexes of stored transactions from stored blocks and reports their discrepancies,
+    /// see [`sequencer_core::index_repair`]. Blocks are replayed in batches off the async runtime
+    /// and without holding the sequencer, so reads and block production go on meanwhile.
+    ///
+    /// Takes time proportional to the length of the chain, so its timeout may need to be raised
+    /// with `method_timeout_millis`. Indexes are left as they are if it times out.
+    async fn process_rebuild_indexes(&self, request: Request) -> Result<Value, RpcErr> {
+        let _rebuild_indexes_req = RebuildIndexesRequest::parse(Some(request.params))?;
+        let internal_error =
+            |err: anyhow::Error| RpcError::new_internal_error(None, &err.to_string());
+
+        let mut rebuild = self
+            .sequencer_state
+            .lock()
+            .await
+            .start_index_rebuild()
+            .map_err(internal_error)?;
+        loop {
+            let blocks = self
+                .sequencer_state
+                .lock()
+                .await
+                .blocks_to_index(&rebuild, INDEX_REBUILD_BATCH_SIZE)
+                .map_err(internal_error)?;
+            if blocks.is_empty() {
+                break;
+            }
+            rebuild = tokio::task::spawn_blocking(move || {
+                for block in &blocks {
+                    rebuild.apply_block(block)?;
+                }
+                anyhow::Ok(rebuild)
+            })
+            .await
+            .map_err(|err| RpcError::new_internal_error(None, &err.to_string()))?
+            .map_err(internal_error)?;
+        }
+
+        let report = self
+            .sequencer_state
+            .lock()
+            .await
+            .finish_index_rebuild(rebuild)
+            .map_err(internal_error)?;
+
+        respond(report)
+    }
+
+    /// Mempool lifecycle events of the transaction, see [`sequencer_core::tx_audit`]
+    async fn process_get_tx_audit(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_audit_req = GetTxAuditRequest::parse(Some(request.params))?;
+        let bytes: Vec<u8> = hex::decode(get_audit_req.hash)
+            .map_err(|_| RpcError::invalid_params("invalid hex".to_string()))?;
+        let hash: HashType = bytes
+            .try_into()
+            .map_err(|_| RpcError::invalid_params("invalid length".to_string()))?;
+
+        let events = self.sequencer_state.lock().await.tx_audit(&hash);
+
+        respond(GetTxAuditResponse { events })
+    }
+}
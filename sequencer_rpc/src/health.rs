@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+use actix_web::{HttpResponse, web};
+use sequencer_core::{
+    SequencerCore,
+    health::{HealthCheck, HealthReport},
+};
+
+use super::JsonHandler;
+
+/// Time to wait for sequencer core, which may be busy producing a block
+pub const CORE_LOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub const CORE_LOCK_CHECK: &str = "sequencer_core";
+
+pub(crate) async fn health_handler(handler: web::Data<JsonHandler>) -> HttpResponse {
+    let report = handler.core_report(|core| core.health_report()).await;
+    report_response(&report)
+}
+
+pub(crate) async fn ready_handler(handler: web::Data<JsonHandler>) -> HttpResponse {
+    let report = handler
+        .core_report(|core| core.readiness_report(Instant::now()))
+        .await;
+    report_response(&report)
+}
+
+impl JsonHandler {
+    async fn core_report(
+        &self,
+        report: impl FnOnce(&SequencerCore) -> HealthReport,
+    ) -> HealthReport {
+        match tokio::time::timeout(CORE_LOCK_TIMEOUT, self.sequencer_state.lock()).await {
+            Ok(core) => report(&core),
+            Err(_) => HealthReport::new(vec![HealthCheck::failed(
+                CORE_LOCK_CHECK,
+                format!(
+                    "Sequencer core is locked for more than {}ms",
+                    CORE_LOCK_TIMEOUT.as_millis()
+                ),
+            )]),
+        }
+    }
+}
+
+fn report_response(report: &HealthReport) -> HttpResponse {
+    if report.ok {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
@@ -0,0 +1,162 @@
+//! Admission of submitted transactions into mempool, off the request path.
+//!
+//! Handlers of `send_tx` pass pre-checked transactions to a single ingest task through a bounded
+//! queue and wait for the outcome. If the queue is full, submission is rejected as congested right
+//! away, so bursts of submissions neither pile up in memory nor hold HTTP workers.
+
+use std::{sync::Arc, time::Duration};
+
+use common::{
+    HashType,
+    rpc_primitives::errors::RpcError,
+    rpc_types::TransactionStatus,
+    soft_confirmation::SoftConfirmation,
+    transaction::{EncodedTransaction, NSSATransaction},
+};
+use log::warn;
+use mempool::MemPoolHandle;
+use nssa_core::fmt::FmtShort as _;
+use sequencer_core::SequencerCore;
+use tokio::sync::{Mutex, mpsc, mpsc::error::TrySendError, oneshot};
+
+use crate::types::err_rpc::RpcErr;
+
+/// Outcome of a submission accepted by the ingest task
+#[derive(Debug)]
+pub struct Admission {
+    /// Transaction was pending or included before the submission
+    pub already_submitted: bool,
+    pub status: TransactionStatus,
+    /// Promise of inclusion, given to newly admitted transactions
+    pub soft_confirmation: Option<SoftConfirmation>,
+}
+
+struct IngestRequest {
+    tx: EncodedTransaction,
+    reply: oneshot::Sender<Result<Admission, RpcErr>>,
+}
+
+pub struct IngestHandle {
+    sender: mpsc::Sender<IngestRequest>,
+    /// Suggested delay before retrying a congested submission
+    retry_after: Duration,
+}
+
+impl IngestHandle {
+    /// Spawns the ingest task admitting transactions into the mempool of `sequencer_state`
+    ///
+    /// The task stops once the handle is dropped.
+    pub fn spawn(
+        sequencer_state: Arc<Mutex<SequencerCore>>,
+        mempool_handle: MemPoolHandle<EncodedTransaction>,
+        queue_size: usize,
+        retry_after: Duration,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(queue_size);
+        tokio::spawn(ingest_loop(receiver, sequencer_state, mempool_handle));
+
+        Self {
+            sender,
+            retry_after,
+        }
+    }
+
+    /// Admits `tx` into mempool, unless it's already pending or included
+    ///
+    /// Fails with [`RpcError::mempool_congested`] without waiting, if too many submissions wait for
+    /// admission. If the returned future is dropped before completion, the transaction is not
+    /// admitted.
+    pub async fn submit(&self, tx: EncodedTransaction) -> Result<Admission, RpcErr> {
+        let (reply, outcome) = oneshot::channel();
+        match self.sender.try_send(IngestRequest { tx, reply }) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                return Err(RpcErr(RpcError::mempool_congested(self.retry_after)));
+            }
+            Err(TrySendError::Closed(_)) => panic!("Ingest task stopped, this is a bug"),
+        }
+
+        outcome
+            .await
+            .expect("Ingest task dropped submission, this is a bug")
+    }
+}
+
+/// Status of the transaction with `hash`, if it's pending or included already
+///
+/// Submission is idempotent: such transactions aren't admitted again, their status is reported as
+/// is.
+pub fn already_submitted_status(
+    sequencer_state: &mut SequencerCore,
+    hash: &HashType,
+) -> Option<TransactionStatus> {
+    let status = sequencer_state.transaction_status(hash);
+    matches!(
+        status,
+        TransactionStatus::Pending { .. } | TransactionStatus::Included { .. }
+    )
+    .then_some(status)
+}
+
+async fn ingest_loop(
+    mut receiver: mpsc::Receiver<IngestRequest>,
+    sequencer_state: Arc<Mutex<SequencerCore>>,
+    mempool_handle: MemPoolHandle<EncodedTransaction>,
+) {
+    while let Some(IngestRequest { tx, mut reply }) = receiver.recv().await {
+        let hash = tx.hash();
+        let known_status = already_submitted_status(&mut *sequencer_state.lock().await, &hash);
+        if let Some(status) = known_status {
+            let _ = reply.send(Ok(Admission {
+                already_submitted: true,
+                status,
+                soft_confirmation: None,
+            }));
+            continue;
+        }
+
+        // Submitter may give up while the mempool is full, then the transaction is not admitted.
+        // Mempool slot is reserved before logging, so transaction is logged and pushed without
+        // await points in between.
+        let permit = tokio::select! {
+            permit = mempool_handle.reserve() => permit.expect("Mempool is closed, this is a bug"),
+            () = reply.closed() => continue,
+        };
+
+        let outcome = {
+            let mut sequencer_state = sequencer_state.lock().await;
+            // Submissions are admitted one by one, so each is checked against reservations of the
+            // ones admitted before it
+            NSSATransaction::try_from(&tx)
+                .map_err(|err| RpcErr(RpcError::serialization_error(&err.to_string())))
+                .and_then(|nssa_tx| {
+                    sequencer_state.take_transactions_from_mempool();
+                    sequencer_state
+                        .check_balance_reservation(&nssa_tx)
+                        .map_err(RpcErr::from)
+                })
+                .and_then(|()| {
+                    // Transaction must survive restart once submission succeeds
+                    sequencer_state
+                        .log_admitted_transaction(&tx)
+                        .map_err(RpcErr::from)
+                })
+                .map(|()| {
+                    permit.push(tx);
+                    let status = sequencer_state.transaction_status(&hash);
+                    Admission {
+                        already_submitted: false,
+                        soft_confirmation: sequencer_state.soft_confirmation(hash, &status),
+                        status,
+                    }
+                })
+        };
+
+        if reply.send(outcome).is_err() {
+            warn!(
+                "Submitter of transaction {} left before admission result",
+                hash.fmt_short()
+            );
+        }
+    }
+}
@@ -1,26 +1,29 @@
+pub mod admin;
+pub mod health;
+pub mod ingest;
 pub mod net_utils;
 pub mod process;
 pub mod types;
 
 use std::sync::Arc;
 
-use common::{
-    rpc_primitives::errors::{RpcError, RpcErrorKind},
-    transaction::EncodedTransaction,
+use common::rpc_primitives::{
+    RpcTimeoutsConfig,
+    errors::{RpcError, RpcErrorKind},
 };
-use mempool::MemPoolHandle;
 pub use net_utils::*;
 use sequencer_core::SequencerCore;
 use serde::Serialize;
 use serde_json::Value;
 use tokio::sync::Mutex;
 
-use self::types::err_rpc::RpcErr;
+use self::{ingest::IngestHandle, types::err_rpc::RpcErr};
 
 // ToDo: Add necessary fields
 pub struct JsonHandler {
     sequencer_state: Arc<Mutex<SequencerCore>>,
-    mempool_handle: MemPoolHandle<EncodedTransaction>,
+    ingest_handle: IngestHandle,
+    timeouts_config: RpcTimeoutsConfig,
 }
 
 fn respond<T: Serialize>(val: T) -> Result<Value, RpcErr> {
@@ -1,4 +1,4 @@
-use std::{io, sync::Arc};
+use std::{io, sync::Arc, time::Duration};
 
 use actix_cors::Cors;
 use actix_web::{App, Error as HttpError, HttpResponse, HttpServer, http, middleware, web};
@@ -12,7 +12,12 @@ use mempool::MemPoolHandle;
 use sequencer_core::SequencerCore;
 use tokio::sync::Mutex;
 
-use super::JsonHandler;
+use super::{
+    JsonHandler,
+    admin::admin_rpc_handler,
+    health::{health_handler, ready_handler},
+    ingest::IngestHandle,
+};
 
 pub const SHUTDOWN_TIMEOUT_SECS: u64 = 10;
 
@@ -47,16 +52,26 @@ pub fn new_http_server(
     config: RpcConfig,
     seuquencer_core: Arc<Mutex<SequencerCore>>,
     mempool_handle: MemPoolHandle<EncodedTransaction>,
+    ingest_queue_size: usize,
+    retry_after: Duration,
 ) -> io::Result<actix_web::dev::Server> {
     let RpcConfig {
         addr,
         cors_allowed_origins,
         limits_config,
+        timeouts_config,
     } = config;
     info!(target:NETWORK, "Starting http server at {addr}");
+    let ingest_handle = IngestHandle::spawn(
+        seuquencer_core.clone(),
+        mempool_handle,
+        ingest_queue_size,
+        retry_after,
+    );
     let handler = web::Data::new(JsonHandler {
         sequencer_state: seuquencer_core.clone(),
-        mempool_handle,
+        ingest_handle,
+        timeouts_config,
     });
 
     // HTTP server
@@ -67,6 +82,9 @@ pub fn new_http_server(
             .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
             .wrap(middleware::Logger::default())
             .service(web::resource("/").route(web::post().to(rpc_handler)))
+            .service(web::resource("/admin").route(web::post().to(admin_rpc_handler)))
+            .service(web::resource("/health").route(web::get().to(health_handler)))
+            .service(web::resource("/ready").route(web::get().to(ready_handler)))
     })
     .bind(addr)?
     .shutdown_timeout(SHUTDOWN_TIMEOUT_SECS)
@@ -1,36 +1,61 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use actix_web::Error as HttpError;
 use base58::FromBase58;
 use base64::{Engine, engine::general_purpose};
 use common::{
     HashType,
+    address_index::DEFAULT_ADDRESS_TXS_PAGE_SIZE,
     block::HashableBlockData,
+    block_explorer::ExpandedBlock,
     rpc_primitives::{
         errors::RpcError,
         message::{Message, Request},
         parser::RpcRequest,
-        requests::{
-            GetAccountBalanceRequest, GetAccountBalanceResponse, GetAccountRequest,
-            GetAccountResponse, GetAccountsNoncesRequest, GetAccountsNoncesResponse,
-            GetBlockDataRequest, GetBlockDataResponse, GetBlockRangeDataRequest,
-            GetBlockRangeDataResponse, GetGenesisIdRequest, GetGenesisIdResponse,
-            GetInitialTestnetAccountsRequest, GetLastBlockRequest, GetLastBlockResponse,
-            GetProgramIdsRequest, GetProgramIdsResponse, GetProofForCommitmentRequest,
-            GetProofForCommitmentResponse, GetTransactionByHashRequest,
-            GetTransactionByHashResponse, HelloRequest, HelloResponse, SendTxRequest,
-            SendTxResponse,
-        },
     },
-    transaction::{EncodedTransaction, NSSATransaction},
+    rpc_types::{
+        API_VERSION, BlockDigest, BlockProofStatus, ForcedParamsChange, GetAccountBalanceRequest,
+        GetAccountBalanceResponse, GetAccountRequest, GetAccountResponse,
+        GetAccountWithProofRequest, GetAccountWithProofResponse, GetAccountsNoncesRequest,
+        GetAccountsNoncesResponse, GetAccountsRequest, GetAccountsResponse,
+        GetAddressSummaryRequest, GetAddressSummaryResponse, GetApiVersionRequest,
+        GetApiVersionResponse, GetBlockDataRequest, GetBlockDataResponse, GetBlockExpandedRequest,
+        GetBlockExpandedResponse, GetBlockHeadersRequest, GetBlockHeadersResponse,
+        GetBlockProofRequest, GetBlockProofResponse, GetBlockRangeDataRequest,
+        GetBlockRangeDataResponse, GetChainInfoRequest, GetChainInfoResponse, GetChainStatsRequest,
+        GetChainStatsResponse, GetGenesisIdRequest, GetGenesisIdResponse,
+        GetInitialTestnetAccountsRequest, GetLastBlockRequest, GetLastBlockResponse,
+        GetLatestAnchorsRequest, GetLatestAnchorsResponse, GetPendingTransactionsRequest,
+        GetPendingTransactionsResponse, GetPendingTransfersRequest, GetPendingTransfersResponse,
+        GetPriorityFeesRequest, GetPriorityFeesResponse, GetProgramIdsRequest,
+        GetProgramIdsResponse, GetProofForCommitmentRequest, GetProofForCommitmentResponse,
+        GetSequencerInfoRequest, GetSequencerInfoResponse, GetSyncStatusRequest,
+        GetSyncStatusResponse, GetTransactionByHashRequest, GetTransactionByHashResponse,
+        GetTransactionStatusRequest, GetTransactionStatusResponse, GetTransactionsByAddressRequest,
+        GetTransactionsByAddressResponse, HelloRequest, HelloResponse, MAX_ACCOUNTS_PER_REQUEST,
+        MAX_BLOCK_HEADERS_PER_REQUEST, MIN_SUPPORTED_API_VERSION, PreviewNextBlockRequest,
+        PreviewNextBlockResponse, ReadContext, SendTxRequest, SendTxResponse, SequencerFeature,
+        StateSize, TransactionStatus, ValidateTransactionRequest, ValidateTransactionResponse,
+    },
+    transaction::{EncodedTransaction, NSSATransaction, TxKind},
 };
 use itertools::Itertools as _;
 use log::warn;
 use nssa::{self, program::Program};
-use sequencer_core::{TransactionMalformationError, config::AccountInitialData};
+use sequencer_core::{
+    CheckedDeployment, SequencerCore, TransactionMalformationError,
+    block_store::SequencerBlockStore, config::AccountInitialData, ordering::tx_ordering_policy,
+    proving::BlockProofRecord,
+};
 use serde_json::Value;
+use tokio::{sync::MutexGuard, time::Instant};
 
-use super::{JsonHandler, respond, types::err_rpc::RpcErr};
+use super::{
+    JsonHandler,
+    ingest::{Admission, already_submitted_status},
+    respond,
+    types::err_rpc::RpcErr,
+};
 
 pub const HELLO: &str = "hello";
 pub const SEND_TX: &str = "send_tx";
@@ -42,21 +67,57 @@ pub const GET_ACCOUNT_BALANCE: &str = "get_account_balance";
 pub const GET_TRANSACTION_BY_HASH: &str = "get_transaction_by_hash";
 pub const GET_ACCOUNTS_NONCES: &str = "get_accounts_nonces";
 pub const GET_ACCOUNT: &str = "get_account";
+pub const GET_ACCOUNTS: &str = "get_accounts";
 pub const GET_PROOF_FOR_COMMITMENT: &str = "get_proof_for_commitment";
 pub const GET_PROGRAM_IDS: &str = "get_program_ids";
+pub const GET_BLOCK_EXPANDED: &str = "get_block_expanded";
+pub const GET_ADDRESS_SUMMARY: &str = "get_address_summary";
+pub const GET_TRANSACTIONS_BY_ADDRESS: &str = "get_transactions_by_address";
+pub const GET_PENDING_TRANSFERS: &str = "get_pending_transfers";
+pub const GET_CHAIN_STATS: &str = "get_chain_stats";
+pub const GET_CHAIN_INFO: &str = "get_chain_info";
+pub const GET_API_VERSION: &str = "get_api_version";
+pub const GET_SEQUENCER_INFO: &str = "get_sequencer_info";
+pub const GET_TRANSACTION_STATUS: &str = "get_transaction_status";
+pub const PREVIEW_NEXT_BLOCK: &str = "preview_next_block";
+pub const GET_BLOCK_PROOF: &str = "get_block_proof";
+pub const GET_SYNC_STATUS: &str = "get_sync_status";
+pub const GET_PRIORITY_FEES: &str = "get_priority_fees";
+pub const GET_PENDING_TRANSACTIONS: &str = "get_pending_transactions";
+pub const GET_BLOCK_HEADERS: &str = "get_block_headers";
+pub const GET_ACCOUNT_WITH_PROOF: &str = "get_account_with_proof";
+pub const VALIDATE_TRANSACTION: &str = "validate_transaction";
+pub const GET_LATEST_ANCHORS: &str = "get_latest_anchors";
 
 pub const HELLO_FROM_SEQUENCER: &str = "HELLO_FROM_SEQUENCER";
 
 pub const TRANSACTION_SUBMITTED: &str = "Transaction submitted";
 
+pub const TRANSACTION_ALREADY_SUBMITTED: &str = "Transaction already submitted";
+
+pub const PROGRAM_ALREADY_DEPLOYED: &str = "Program already deployed";
+
 pub const GET_INITIAL_TESTNET_ACCOUNTS: &str = "get_initial_testnet_accounts";
 
+/// How often reads waiting for a block check, whether state reached it
+const SNAPSHOT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Submitted transaction, which passed [`JsonHandler::check_submission`]
+pub(crate) struct CheckedSubmission {
+    pub(crate) tx: NSSATransaction,
+    pub(crate) hash: HashType,
+    /// Id of the program deployed by the transaction
+    pub(crate) program_id: Option<String>,
+    /// Deployed program exists already, so the transaction isn't submitted
+    pub(crate) already_deployed: bool,
+}
+
 impl JsonHandler {
     pub async fn process(&self, message: Message) -> Result<Message, HttpError> {
         let id = message.id();
         if let Message::Request(request) = message {
             let message_inner = self
-                .process_request_internal(request)
+                .process_request_with_timeout(request)
                 .await
                 .map_err(|e| e.0);
             Ok(Message::response(id, message_inner))
@@ -79,45 +140,172 @@ impl JsonHandler {
         respond(response)
     }
 
-    async fn process_send_tx(&self, request: Request) -> Result<Value, RpcErr> {
-        let send_tx_req = SendTxRequest::parse(Some(request.params))?;
-        let tx = borsh::from_slice::<EncodedTransaction>(&send_tx_req.transaction).unwrap();
-        let tx_hash = hex::encode(tx.hash());
+    /// Runs admission checks of `send_tx` on borsh encoded `transaction`
+    ///
+    /// Shared by `send_tx`, `validate_transaction` and `send_system_tx` of the admin RPC, so
+    /// validation gives the same verdict as submission. Stateless checks are the ones of block
+    /// validation, checks against the state are the ones of admission into mempool, see
+    /// [`SequencerCore::check_admission`].
+    pub(crate) async fn check_submission(
+        &self,
+        transaction: &[u8],
+    ) -> Result<CheckedSubmission, RpcErr> {
+        let tx = borsh::from_slice::<EncodedTransaction>(transaction)
+            .map_err(|err| RpcError::parse_error(format!("Failed to decode transaction: {err}")))?;
+        let hash = tx.hash();
+
+        let authenticated_tx = sequencer_core::pre_check_transaction(&tx)
+            .inspect_err(|err| warn!("Error at pre_check {err:?}"))?;
+        let (deployment, smoke_test_cycles) = {
+            let state = self.sequencer_state.lock().await;
+            let deployment = state
+                .check_admission(&authenticated_tx)
+                .inspect_err(|err| warn!("Error at pre_check {err:?}"))?
+                // Included deployments are reported by the ingest task, so resubmission stays
+                // idempotent
+                .map(|deployment| CheckedDeployment {
+                    already_deployed: deployment.already_deployed
+                        && state
+                            .block_store()
+                            .get_block_id_of_transaction(&hash)
+                            .is_none(),
+                    ..deployment
+                });
+            (
+                deployment,
+                state.sequencer_config().deployment_smoke_test_cycles,
+            )
+        };
+
+        let (program_id, already_deployed) = match deployment {
+            Some(CheckedDeployment {
+                program,
+                already_deployed: true,
+            }) => (Some(program.id().to_string()), true),
+            Some(CheckedDeployment { program, .. }) => {
+                let program_id = program.id().to_string();
+                if let Some(cycle_limit) = smoke_test_cycles {
+                    smoke_test_program(program, cycle_limit).await?;
+                }
+                (Some(program_id), false)
+            }
+            None => (None, false),
+        };
 
-        let transaction = NSSATransaction::try_from(&tx)
-            .map_err(|_| TransactionMalformationError::FailedToDecode { tx: tx.hash() })?;
+        Ok(CheckedSubmission {
+            tx: authenticated_tx,
+            hash,
+            program_id,
+            already_deployed,
+        })
+    }
 
-        let authenticated_tx = sequencer_core::transaction_pre_check(transaction)
-            .inspect_err(|err| warn!("Error at pre_check {err:#?}"))?;
+    async fn process_send_tx(&self, request: Request) -> Result<Value, RpcErr> {
+        let send_tx_req = SendTxRequest::parse(Some(request.params))?;
+        let CheckedSubmission {
+            tx,
+            hash,
+            program_id,
+            already_deployed,
+        } = self.check_submission(&send_tx_req.transaction).await?;
+        let tx_hash = hex::encode(hash);
+
+        if already_deployed {
+            return respond(SendTxResponse {
+                status: PROGRAM_ALREADY_DEPLOYED.to_string(),
+                tx_hash,
+                mempool_position: None,
+                mempool_depth: None,
+                estimated_inclusion_block: None,
+                tx_status: None,
+                soft_confirmation: None,
+                program_id,
+            });
+        }
 
-        // TODO: Do we need a timeout here? It will be usable if we have too many transactions to
-        // process
-        self.mempool_handle
-            .push(authenticated_tx.into())
-            .await
-            .expect("Mempool is closed, this is a bug");
+        // Admission checks and logging happen in the ingest task, this only waits for them
+        let Admission {
+            already_submitted,
+            status,
+            soft_confirmation,
+        } = self.ingest_handle.submit(tx.into()).await?;
+        let (mempool_position, mempool_depth, estimated_inclusion_block) = match &status {
+            TransactionStatus::Pending {
+                position,
+                depth,
+                estimated_inclusion_block,
+                ..
+            } => (
+                Some(*position),
+                Some(*depth),
+                Some(*estimated_inclusion_block),
+            ),
+            _ => (None, None, None),
+        };
 
         let response = SendTxResponse {
-            status: TRANSACTION_SUBMITTED.to_string(),
+            status: if already_submitted {
+                TRANSACTION_ALREADY_SUBMITTED
+            } else {
+                TRANSACTION_SUBMITTED
+            }
+            .to_string(),
             tx_hash,
+            mempool_position,
+            mempool_depth,
+            estimated_inclusion_block,
+            tx_status: Some(status),
+            soft_confirmation,
+            program_id,
         };
 
         respond(response)
     }
 
+    /// Verdict `send_tx` would give on the transaction, which isn't submitted
+    async fn process_validate_transaction(&self, request: Request) -> Result<Value, RpcErr> {
+        let validate_req = ValidateTransactionRequest::parse(Some(request.params))?;
+        let CheckedSubmission {
+            tx,
+            hash,
+            program_id,
+            already_deployed,
+        } = self.check_submission(&validate_req.transaction).await?;
+
+        let (status, tx_status) = if already_deployed {
+            (PROGRAM_ALREADY_DEPLOYED, None)
+        } else {
+            let mut state = self.sequencer_state.lock().await;
+            match already_submitted_status(&mut state, &hash) {
+                Some(status) => (TRANSACTION_ALREADY_SUBMITTED, Some(status)),
+                None => {
+                    state.take_transactions_from_mempool();
+                    state.check_balance_reservation(&tx)?;
+                    (TRANSACTION_SUBMITTED, None)
+                }
+            }
+        };
+
+        respond(ValidateTransactionResponse {
+            status: status.to_string(),
+            tx_hash: hex::encode(hash),
+            tx_status,
+            program_id,
+        })
+    }
+
     async fn process_get_block_data(&self, request: Request) -> Result<Value, RpcErr> {
         let get_block_req = GetBlockDataRequest::parse(Some(request.params))?;
 
-        let block = {
+        let (block, digest) = {
             let state = self.sequencer_state.lock().await;
 
-            state
-                .block_store()
-                .get_block_at_id(get_block_req.block_id)?
+            served_block(state.block_store(), get_block_req.block_id)?
         };
 
         let response = GetBlockDataResponse {
-            block: borsh::to_vec(&HashableBlockData::from(block)).unwrap(),
+            block: borsh::to_vec(&block).unwrap(),
+            digest: Some(digest),
         };
 
         respond(response)
@@ -126,18 +314,19 @@ impl JsonHandler {
     async fn process_get_block_range_data(&self, request: Request) -> Result<Value, RpcErr> {
         let get_block_req = GetBlockRangeDataRequest::parse(Some(request.params))?;
 
-        let blocks = {
+        let (blocks, digests) = {
             let state = self.sequencer_state.lock().await;
             (get_block_req.start_block_id..=get_block_req.end_block_id)
-                .map(|block_id| state.block_store().get_block_at_id(block_id))
-                .map_ok(|block| {
-                    borsh::to_vec(&HashableBlockData::from(block))
-                        .expect("derived BorshSerialize should never fail")
+                .map(|block_id| served_block(state.block_store(), block_id))
+                .map_ok(|(block, digest)| {
+                    let block =
+                        borsh::to_vec(&block).expect("derived BorshSerialize should never fail");
+                    (block, digest)
                 })
-                .collect::<Result<Vec<_>, _>>()?
+                .collect::<Result<(Vec<_>, Vec<_>), _>>()?
         };
 
-        let response = GetBlockRangeDataResponse { blocks };
+        let response = GetBlockRangeDataResponse { blocks, digests };
 
         respond(response)
     }
@@ -159,13 +348,19 @@ impl JsonHandler {
     async fn process_get_last_block(&self, request: Request) -> Result<Value, RpcErr> {
         let _get_last_block_req = GetLastBlockRequest::parse(Some(request.params))?;
 
-        let last_block = {
+        let (last_block, last_block_digest) = {
             let state = self.sequencer_state.lock().await;
+            let last_block = state.chain_height();
+            // Body of the last block is never pruned
+            let (_, digest) = served_block(state.block_store(), last_block)?;
 
-            state.chain_height()
+            (last_block, digest)
         };
 
-        let response = GetLastBlockResponse { last_block };
+        let response = GetLastBlockResponse {
+            last_block,
+            last_block_digest: Some(last_block_digest),
+        };
 
         respond(response)
     }
@@ -199,13 +394,16 @@ impl JsonHandler {
                 .map_err(|_| RpcError::invalid_params("invalid length".to_string()))?,
         );
 
-        let balance = {
-            let state = self.sequencer_state.lock().await;
+        let (balance, context) = {
+            let state = self.lock_state_at(get_account_req.min_block_id).await?;
             let account = state.state().get_account_by_id(&account_id);
-            account.balance
+            (account.balance, read_context(&state))
         };
 
-        let response = GetAccountBalanceResponse { balance };
+        let response = GetAccountBalanceResponse {
+            balance,
+            context: Some(context),
+        };
 
         respond(response)
     }
@@ -215,7 +413,7 @@ impl JsonHandler {
     async fn process_get_accounts_nonces(&self, request: Request) -> Result<Value, RpcErr> {
         let get_account_nonces_req = GetAccountsNoncesRequest::parse(Some(request.params))?;
         let mut account_ids = vec![];
-        for account_id_raw in get_account_nonces_req.account_ids {
+        for account_id_raw in &get_account_nonces_req.account_ids {
             let account_id = account_id_raw
                 .parse::<nssa::AccountId>()
                 .map_err(|e| RpcError::invalid_params(e.to_string()))?;
@@ -223,16 +421,22 @@ impl JsonHandler {
             account_ids.push(account_id);
         }
 
-        let nonces = {
-            let state = self.sequencer_state.lock().await;
+        let (nonces, context) = {
+            let state = self
+                .lock_state_at(get_account_nonces_req.min_block_id)
+                .await?;
 
-            account_ids
+            let nonces = account_ids
                 .into_iter()
                 .map(|account_id| state.state().get_account_by_id(&account_id).nonce)
-                .collect()
+                .collect();
+            (nonces, read_context(&state))
         };
 
-        let response = GetAccountsNoncesResponse { nonces };
+        let response = GetAccountsNoncesResponse {
+            nonces,
+            context: Some(context),
+        };
 
         respond(response)
     }
@@ -247,13 +451,49 @@ impl JsonHandler {
             .parse::<nssa::AccountId>()
             .map_err(|e| RpcError::invalid_params(e.to_string()))?;
 
-        let account = {
-            let state = self.sequencer_state.lock().await;
+        let state = self
+            .lock_state_at(get_account_nonces_req.min_block_id)
+            .await?;
+        let response = GetAccountResponse {
+            context: Some(read_context(&state)),
+            ..account_response(state.state(), &account_id)
+        };
+
+        respond(response)
+    }
+
+    /// Returns account structs for given account_ids in the same order.
+    /// Each account_id must be a valid hex string of the correct length.
+    async fn process_get_accounts(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_accounts_req = GetAccountsRequest::parse(Some(request.params))?;
+        if get_accounts_req.account_ids.len() > MAX_ACCOUNTS_PER_REQUEST {
+            return Err(RpcError::invalid_params(format!(
+                "at most {MAX_ACCOUNTS_PER_REQUEST} accounts per request"
+            ))
+            .into());
+        }
+
+        let account_ids = get_accounts_req
+            .account_ids
+            .iter()
+            .map(|account_id| account_id.parse::<nssa::AccountId>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
 
-            state.state().get_account_by_id(&account_id)
+        let (accounts, context) = {
+            let state = self.lock_state_at(get_accounts_req.min_block_id).await?;
+
+            let accounts = account_ids
+                .iter()
+                .map(|account_id| account_response(state.state(), account_id))
+                .collect();
+            (accounts, read_context(&state))
         };
 
-        let response = GetAccountResponse { account };
+        let response = GetAccountsResponse {
+            accounts,
+            context: Some(context),
+        };
 
         respond(response)
     }
@@ -282,6 +522,144 @@ impl JsonHandler {
         respond(response)
     }
 
+    async fn process_get_transaction_status(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_status_req = GetTransactionStatusRequest::parse(Some(request.params))?;
+        let bytes: Vec<u8> = hex::decode(get_status_req.hash)
+            .map_err(|_| RpcError::invalid_params("invalid hex".to_string()))?;
+        let hash: HashType = bytes
+            .try_into()
+            .map_err(|_| RpcError::invalid_params("invalid length".to_string()))?;
+
+        let status = self.sequencer_state.lock().await.transaction_status(&hash);
+
+        respond(GetTransactionStatusResponse { status })
+    }
+
+    /// Returns block, which would be produced from pending transactions, without producing it
+    async fn process_preview_next_block(&self, request: Request) -> Result<Value, RpcErr> {
+        let _preview_req = PreviewNextBlockRequest::parse(Some(request.params))?;
+
+        let candidate = {
+            let mut state = self.sequencer_state.lock().await;
+            // Only moves transactions to pending ones, so they are still included as usual
+            state.take_transactions_from_mempool();
+            state.build_block_candidate()?
+        };
+
+        respond(PreviewNextBlockResponse {
+            block_id: candidate.block_id(),
+            tx_hashes: candidate.tx_hashes().into_iter().map(hex::encode).collect(),
+            total_gas: candidate.total_gas(),
+        })
+    }
+
+    async fn process_get_block_proof(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_block_proof_req = GetBlockProofRequest::parse(Some(request.params))?;
+
+        let record = self
+            .sequencer_state
+            .lock()
+            .await
+            .block_proof_status(get_block_proof_req.block_id)?;
+
+        let status = match record {
+            None => BlockProofStatus::NotRequested,
+            Some(BlockProofRecord::Pending) => BlockProofStatus::Pending,
+            Some(BlockProofRecord::Proven(proof)) => BlockProofStatus::Proven {
+                proof: general_purpose::STANDARD.encode(borsh::to_vec(&proof).unwrap()),
+            },
+            Some(BlockProofRecord::Failed { reason }) => BlockProofStatus::Failed { reason },
+        };
+
+        respond(GetBlockProofResponse { status })
+    }
+
+    async fn process_get_sync_status(&self, request: Request) -> Result<Value, RpcErr> {
+        let _get_sync_status_req = GetSyncStatusRequest::parse(Some(request.params))?;
+
+        let status = self.sequencer_state.lock().await.sync_status();
+
+        respond(GetSyncStatusResponse { status })
+    }
+
+    async fn process_get_priority_fees(&self, request: Request) -> Result<Value, RpcErr> {
+        let _get_priority_fees_req = GetPriorityFeesRequest::parse(Some(request.params))?;
+
+        let distribution = self.sequencer_state.lock().await.pending_priority_fees();
+
+        respond(GetPriorityFeesResponse { distribution })
+    }
+
+    async fn process_get_pending_transactions(&self, request: Request) -> Result<Value, RpcErr> {
+        let _get_pending_txs_req = GetPendingTransactionsRequest::parse(Some(request.params))?;
+
+        let (last_block, transactions) = {
+            let mut state = self.sequencer_state.lock().await;
+            if !state.sequencer_config().serve_pending_transactions {
+                return Err(RpcErr(RpcError::method_not_found(request.method)));
+            }
+
+            (state.chain_height(), state.pending_transaction_summaries())
+        };
+
+        respond(GetPendingTransactionsResponse {
+            last_block,
+            transactions,
+        })
+    }
+
+    /// Returns signed headers of the requested blocks, ending early at the last block
+    async fn process_get_block_headers(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_headers_req = GetBlockHeadersRequest::parse(Some(request.params))?;
+        let start_block_id = get_headers_req.start_block_id;
+        if get_headers_req.end_block_id.saturating_sub(start_block_id)
+            >= MAX_BLOCK_HEADERS_PER_REQUEST
+        {
+            return Err(RpcError::invalid_params(format!(
+                "at most {MAX_BLOCK_HEADERS_PER_REQUEST} headers per request"
+            ))
+            .into());
+        }
+
+        let headers = {
+            let state = self.sequencer_state.lock().await;
+            // At least one header is requested, so a start after the last block fails
+            let end_block_id = get_headers_req
+                .end_block_id
+                .min(state.chain_height().max(start_block_id));
+            (start_block_id..=end_block_id)
+                .map(|block_id| state.signed_block_header(block_id))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        respond(GetBlockHeadersResponse { headers })
+    }
+
+    /// Returns the account with its proof against the state root in the signed header of the
+    /// last block
+    async fn process_get_account_with_proof(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_account_req = GetAccountWithProofRequest::parse(Some(request.params))?;
+
+        let account_id = get_account_req
+            .account_id
+            .parse::<nssa::AccountId>()
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        let response = {
+            let state = self.sequencer_state.lock().await;
+            let proof = state.state().account_proof(&account_id);
+
+            GetAccountWithProofResponse {
+                account: state.state().get_account_by_id(&account_id),
+                proof: borsh::to_vec(&proof).expect("derived BorshSerialize should never fail"),
+                commitment_set_digest: hex::encode(state.state().commitment_set_digest()),
+                header: state.signed_block_header(state.chain_height())?,
+            }
+        };
+
+        respond(response)
+    }
+
     /// Returns the commitment proof, corresponding to commitment
     async fn process_get_proof_by_commitment(&self, request: Request) -> Result<Value, RpcErr> {
         let get_proof_req = GetProofForCommitmentRequest::parse(Some(request.params))?;
@@ -296,6 +674,21 @@ impl JsonHandler {
         respond(response)
     }
 
+    /// Returns roots of the commitment set, which private transactions of the next block may be
+    /// proven against
+    async fn process_get_latest_anchors(&self, request: Request) -> Result<Value, RpcErr> {
+        let _get_anchors_req = GetLatestAnchorsRequest::parse(Some(request.params))?;
+
+        let response = {
+            let state = self.sequencer_state.lock().await;
+            GetLatestAnchorsResponse {
+                anchor_window: state.next_block_params().1.anchor_window,
+                anchors: state.latest_anchors().into_iter().map(Into::into).collect(),
+            }
+        };
+        respond(response)
+    }
+
     async fn process_get_program_ids(&self, request: Request) -> Result<Value, RpcErr> {
         let _get_proof_req = GetProgramIdsRequest::parse(Some(request.params))?;
 
@@ -305,15 +698,300 @@ impl JsonHandler {
             Program::authenticated_transfer_program().id(),
         );
         program_ids.insert("token".to_string(), Program::token().id());
+        program_ids.insert("escrow".to_string(), Program::escrow().id());
         program_ids.insert("pinata".to_string(), Program::pinata().id());
         program_ids.insert(
             "privacy_preserving_circuit".to_string(),
-            nssa::PRIVACY_PRESERVING_CIRCUIT_ID,
+            nssa::ProgramId::new(nssa::PRIVACY_PRESERVING_CIRCUIT_ID),
         );
         let response = GetProgramIdsResponse { program_ids };
         respond(response)
     }
 
+    /// Returns block with decoded transactions
+    async fn process_get_block_expanded(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_block_req = GetBlockExpandedRequest::parse(Some(request.params))?;
+
+        let (block, decoders, receipts) = {
+            let state = self.sequencer_state.lock().await;
+            let block = state
+                .block_store()
+                .get_block_at_id(get_block_req.block_id)?;
+            let receipts = block
+                .body
+                .transactions
+                .iter()
+                .filter_map(|tx| {
+                    let hash = tx.hash();
+                    let receipt = state.block_store().get_receipt(&hash)?.clone();
+                    Some((hash, receipt))
+                })
+                .collect::<HashMap<_, _>>();
+
+            (block, state.instruction_decoders(), receipts)
+        };
+
+        let block = ExpandedBlock::from_block_with_receipts(&block, &decoders, |hash| {
+            receipts.get(hash).cloned()
+        })
+        .map_err(|err| RpcError::new_internal_error(None, &err.to_string()))?;
+
+        let response = GetBlockExpandedResponse { block };
+
+        respond(response)
+    }
+
+    /// Returns current balance and activity of account at the given account_id.
+    async fn process_get_address_summary(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_summary_req = GetAddressSummaryRequest::parse(Some(request.params))?;
+
+        let account_id = get_summary_req
+            .account_id
+            .parse::<nssa::AccountId>()
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        let (balance, activity) = {
+            let state = self.sequencer_state.lock().await;
+
+            (
+                state.state().get_account_by_id(&account_id).balance,
+                state.block_store().get_account_activity(&account_id),
+            )
+        };
+
+        let response = GetAddressSummaryResponse {
+            balance,
+            tx_count: activity.map_or(0, |activity| activity.tx_count),
+            first_seen_block: activity.map(|activity| activity.first_seen_block),
+            last_seen_block: activity.map(|activity| activity.last_seen_block),
+        };
+
+        respond(response)
+    }
+
+    /// Returns a page of transactions touching account at the given account_id, from the newest
+    /// one.
+    async fn process_get_transactions_by_address(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_txs_req = GetTransactionsByAddressRequest::parse(Some(request.params))?;
+
+        let account_id = get_txs_req
+            .account_id
+            .parse::<nssa::AccountId>()
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+        let limit = get_txs_req
+            .limit
+            .map_or(DEFAULT_ADDRESS_TXS_PAGE_SIZE, |limit| {
+                usize::try_from(limit).unwrap_or(usize::MAX)
+            });
+
+        let page = {
+            let state = self.sequencer_state.lock().await;
+
+            state.block_store().get_transactions_by_address(
+                &account_id,
+                &get_txs_req.filter,
+                get_txs_req.cursor,
+                limit,
+            )
+        };
+
+        respond(GetTransactionsByAddressResponse::from(page))
+    }
+
+    /// Returns native transfers waiting for inclusion, which send to or from account at the
+    /// given account_id. Served only if enabled in the config, as if the method didn't exist
+    /// otherwise.
+    async fn process_get_pending_transfers(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_transfers_req = GetPendingTransfersRequest::parse(Some(request.params))?;
+
+        let account_id = get_transfers_req
+            .account_id
+            .parse::<nssa::AccountId>()
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        let transfers = {
+            let mut state = self.sequencer_state.lock().await;
+            if !state.sequencer_config().serve_pending_transfers {
+                return Err(RpcErr(RpcError::method_not_found(request.method)));
+            }
+
+            state.pending_transfers(&account_id)
+        };
+
+        respond(GetPendingTransfersResponse { transfers })
+    }
+
+    async fn process_get_chain_stats(&self, request: Request) -> Result<Value, RpcErr> {
+        let get_chain_stats_req = GetChainStatsRequest::parse(Some(request.params))?;
+
+        let response = {
+            let state = self.lock_state_at(get_chain_stats_req.min_block_id).await?;
+            let state_size = state.state().state_size();
+
+            GetChainStatsResponse {
+                last_block: state.chain_height(),
+                tx_count: state.block_store().transaction_count(),
+                total_supply: state.state_invariants().total_supply(),
+                state_size: StateSize {
+                    accounts: state_size.accounts as u64,
+                    data_bytes: state_size.data_bytes as u64,
+                },
+                total_burned: state.state().total_burned(),
+                total_storage_deposit: state.state().total_storage_deposit(),
+                failed_block_attempts: state.block_production().failed_attempts(),
+                block_production_stall_alerts: state.block_production().stall_alerts(),
+                context: Some(read_context(&state)),
+            }
+        };
+
+        respond(response)
+    }
+
+    async fn process_get_chain_info(&self, request: Request) -> Result<Value, RpcErr> {
+        let _get_chain_info_req = GetChainInfoRequest::parse(Some(request.params))?;
+
+        let response = {
+            let state = self.sequencer_state.lock().await;
+            let (params_version, params) = state.next_block_params();
+
+            GetChainInfoResponse {
+                genesis_id: state.sequencer_config().genesis_id,
+                last_block: state.chain_height(),
+                max_num_tx_in_block: params.max_num_tx_in_block,
+                params_version,
+                tx_ordering_policy: tx_ordering_policy(
+                    state.sequencer_config().max_consecutive_per_sender,
+                ),
+                program_allow_list: Some(state.sequencer_config().program_allow_list.clone()),
+                forced_params_changes: state
+                    .forced_params_changes()
+                    .iter()
+                    .map(|change| ForcedParamsChange {
+                        block_id: change.block_id,
+                        previous_fingerprint: hex::encode(change.previous_fingerprint),
+                        fingerprint: hex::encode(change.fingerprint),
+                    })
+                    .collect(),
+                storage_deposit_per_byte: params.storage_deposit_per_byte,
+            }
+        };
+
+        respond(response)
+    }
+
+    #[allow(clippy::unused_async)]
+    async fn process_get_api_version(&self, request: Request) -> Result<Value, RpcErr> {
+        let _get_api_version_req = GetApiVersionRequest::parse(Some(request.params))?;
+
+        let response = GetApiVersionResponse {
+            api_version: API_VERSION,
+            min_supported_api_version: MIN_SUPPORTED_API_VERSION,
+        };
+
+        respond(response)
+    }
+
+    async fn process_get_sequencer_info(&self, request: Request) -> Result<Value, RpcErr> {
+        let _get_sequencer_info_req = GetSequencerInfoRequest::parse(Some(request.params))?;
+
+        let (
+            lazy_proving,
+            pending_transfers,
+            pending_transactions,
+            chain_id,
+            public_key,
+            genesis_hash,
+        ) = {
+            let state = self.sequencer_state.lock().await;
+            let block_store = state.block_store();
+            (
+                state.sequencer_config().lazy_proving.is_some(),
+                state.sequencer_config().serve_pending_transfers,
+                state.sequencer_config().serve_pending_transactions,
+                state.sequencer_config().chain_id,
+                nssa::PublicKey::new_from_private_key(block_store.signing_key()),
+                block_store
+                    .get_block_header_at_id(block_store.genesis_id())
+                    .map(|header| hex::encode(header.hash))
+                    .inspect_err(|err| warn!("Failed to get genesis block header: {err:#}"))
+                    .ok(),
+            )
+        };
+        let features = [
+            lazy_proving.then_some(SequencerFeature::LazyProofs),
+            Some(SequencerFeature::PrivateTransactions),
+            Some(SequencerFeature::BatchAccounts),
+            pending_transfers.then_some(SequencerFeature::PendingTransfers),
+            pending_transactions.then_some(SequencerFeature::PendingTransactions),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let response = GetSequencerInfoResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            api_version: API_VERSION,
+            tx_kinds: TxKind::SUPPORTED.into_iter().map(TxKind::tag).collect(),
+            features,
+            public_key: Some(hex::encode(public_key.value())),
+            genesis_hash,
+            chain_id: Some(chain_id),
+        };
+
+        respond(response)
+    }
+
+    /// Locks the state, once it reaches block `min_block_id`
+    ///
+    /// Waits for the block to be produced up to the snapshot wait budget, so clients don't read
+    /// state older than the one they already observed.
+    async fn lock_state_at(
+        &self,
+        min_block_id: Option<u64>,
+    ) -> Result<MutexGuard<'_, SequencerCore>, RpcErr> {
+        let Some(min_block_id) = min_block_id else {
+            return Ok(self.sequencer_state.lock().await);
+        };
+        let deadline =
+            Instant::now() + Duration::from_millis(self.timeouts_config.snapshot_wait_millis);
+
+        loop {
+            let state = self.sequencer_state.lock().await;
+            let block_id = state.chain_height();
+            if block_id >= min_block_id {
+                return Ok(state);
+            }
+            drop(state);
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RpcError::stale_snapshot(min_block_id, block_id).into());
+            }
+            tokio::time::sleep(SNAPSHOT_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    /// Handles `request` within the time budget of its method
+    ///
+    /// Handlers are dropped at an await point on expiry, so they must not leave partial state
+    /// behind.
+    pub async fn process_request_with_timeout(&self, request: Request) -> Result<Value, RpcErr> {
+        // Validation runs the same checks as submission, including smoke tests of deployments
+        let is_submission = matches!(request.method.as_str(), SEND_TX | VALIDATE_TRANSACTION);
+        let timeout = self.timeouts_config.timeout(&request.method, is_submission);
+        let method = request.method.clone();
+
+        tokio::time::timeout(timeout, self.process_request_internal(request))
+            .await
+            .unwrap_or_else(|_| {
+                warn!(
+                    "Request {method} was not handled in {}ms",
+                    timeout.as_millis()
+                );
+                Err(RpcErr(RpcError::server_busy(&method, timeout)))
+            })
+    }
+
     pub async fn process_request_internal(&self, request: Request) -> Result<Value, RpcErr> {
         match request.method.as_ref() {
             HELLO => self.process_temp_hello(request).await,
@@ -326,30 +1004,128 @@ impl JsonHandler {
             GET_ACCOUNT_BALANCE => self.process_get_account_balance(request).await,
             GET_ACCOUNTS_NONCES => self.process_get_accounts_nonces(request).await,
             GET_ACCOUNT => self.process_get_account(request).await,
+            GET_ACCOUNTS => self.process_get_accounts(request).await,
             GET_TRANSACTION_BY_HASH => self.process_get_transaction_by_hash(request).await,
             GET_PROOF_FOR_COMMITMENT => self.process_get_proof_by_commitment(request).await,
             GET_PROGRAM_IDS => self.process_get_program_ids(request).await,
+            GET_BLOCK_EXPANDED => self.process_get_block_expanded(request).await,
+            GET_ADDRESS_SUMMARY => self.process_get_address_summary(request).await,
+            GET_TRANSACTIONS_BY_ADDRESS => self.process_get_transactions_by_address(request).await,
+            GET_PENDING_TRANSFERS => self.process_get_pending_transfers(request).await,
+            GET_CHAIN_STATS => self.process_get_chain_stats(request).await,
+            GET_CHAIN_INFO => self.process_get_chain_info(request).await,
+            GET_API_VERSION => self.process_get_api_version(request).await,
+            GET_SEQUENCER_INFO => self.process_get_sequencer_info(request).await,
+            GET_TRANSACTION_STATUS => self.process_get_transaction_status(request).await,
+            PREVIEW_NEXT_BLOCK => self.process_preview_next_block(request).await,
+            GET_BLOCK_PROOF => self.process_get_block_proof(request).await,
+            GET_SYNC_STATUS => self.process_get_sync_status(request).await,
+            GET_PRIORITY_FEES => self.process_get_priority_fees(request).await,
+            GET_PENDING_TRANSACTIONS => self.process_get_pending_transactions(request).await,
+            GET_BLOCK_HEADERS => self.process_get_block_headers(request).await,
+            GET_ACCOUNT_WITH_PROOF => self.process_get_account_with_proof(request).await,
+            VALIDATE_TRANSACTION => self.process_validate_transaction(request).await,
+            GET_LATEST_ANCHORS => self.process_get_latest_anchors(request).await,
             _ => Err(RpcErr(RpcError::method_not_found(request.method))),
         }
     }
 }
 
+/// Stored block with the digest advertised for it
+///
+/// Hash is taken from the separately stored header instead of being recomputed from the block,
+/// so clients detect a block, which contents changed in storage.
+fn served_block(
+    block_store: &SequencerBlockStore,
+    block_id: u64,
+) -> anyhow::Result<(HashableBlockData, BlockDigest)> {
+    let block = HashableBlockData::from(block_store.get_block_at_id(block_id)?);
+    let header = block_store.get_block_header_at_id(block_id)?;
+    let digest = BlockDigest::new(&header.hash, block.transactions.len());
+
+    Ok((block, digest))
+}
+
+fn account_response(state: &nssa::V02State, account_id: &nssa::AccountId) -> GetAccountResponse {
+    let account = state.get_account_by_id(account_id);
+    // Accounts, which don't cover the deposit after the rate was raised, lock all balance
+    let storage_deposit = account
+        .storage_deposit(state.storage_deposit_per_byte())
+        .min(account.balance);
+
+    GetAccountResponse {
+        account,
+        storage_deposit,
+        context: None,
+    }
+}
+
+/// Runs `program` with empty input, see [`Program::smoke_test`], off the async runtime, as it may
+/// take up to `cycle_limit` cycles
+async fn smoke_test_program(program: Program, cycle_limit: u64) -> Result<(), RpcErr> {
+    tokio::task::spawn_blocking(move || program.smoke_test(cycle_limit))
+        .await
+        .map_err(|err| RpcError::new_internal_error(None, &err.to_string()))?
+        .map_err(|err| TransactionMalformationError::InvalidProgram {
+            reason: err.to_string(),
+        })
+        .inspect_err(|err| warn!("Error at pre_check {err:?}"))?;
+    Ok(())
+}
+
+/// Block and state root, which reads under the locked `state` are computed at
+fn read_context(state: &SequencerCore) -> ReadContext {
+    ReadContext {
+        block_id: state.chain_height(),
+        state_root: hex::encode(state.state().state_root()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
     use base58::ToBase58;
     use base64::{Engine, engine::general_purpose};
-    use common::{test_utils::sequencer_sign_key_for_testing, transaction::EncodedTransaction};
+    use common::{
+        block::HashableBlockData,
+        rpc_primitives::{
+            RpcTimeoutsConfig,
+            errors::{
+                BLOCK_NOT_YET_PRODUCED_ERROR_CODE, INSUFFICIENT_UNRESERVED_BALANCE_ERROR_CODE,
+                MEMPOOL_CONGESTED_ERROR_CODE, METHOD_NOT_FOUND_ERROR_CODE, SERVER_BUSY_ERROR_CODE,
+                STALE_SNAPSHOT_ERROR_CODE,
+            },
+        },
+        rpc_types::{
+            GetAccountWithProofResponse, GetBlockDataResponse, GetBlockHeadersResponse,
+            GetBlockRangeDataResponse, GetLastBlockResponse, MAX_ACCOUNTS_PER_REQUEST,
+        },
+        signed_header::{Checkpoint, verify_header_chain},
+        soft_confirmation::SoftConfirmation,
+        test_utils::sequencer_sign_key_for_testing,
+        transaction::{EncodedTransaction, NSSATransaction},
+    };
     use sequencer_core::{
         SequencerCore,
-        config::{AccountInitialData, SequencerConfig},
+        config::{
+            AccountInitialData, DEFAULT_DEPLOYMENT_SMOKE_TEST_CYCLES, SequencerConfig,
+            SystemLaneConfig,
+        },
+        health::{BLOCK_PRODUCTION_CHECK, HealthReport},
     };
     use serde_json::Value;
     use tempfile::tempdir;
     use tokio::sync::Mutex;
 
-    use crate::{JsonHandler, rpc_handler};
+    use super::{PROGRAM_ALREADY_DEPLOYED, TRANSACTION_ALREADY_SUBMITTED, TRANSACTION_SUBMITTED};
+    use crate::{
+        JsonHandler,
+        admin::admin_rpc_handler,
+        health::{health_handler, ready_handler},
+        ingest::IngestHandle,
+        rpc_handler,
+    };
 
     fn sequencer_config_for_tests() -> SequencerConfig {
         let tempdir = tempdir().unwrap();
@@ -366,12 +1142,12 @@ mod tests {
 
         let initial_acc1 = AccountInitialData {
             account_id: acc1_id.to_base58(),
-            balance: 10000,
+            balance: nssa::Amount(10000),
         };
 
         let initial_acc2 = AccountInitialData {
             account_id: acc2_id.to_base58(),
-            balance: 20000,
+            balance: nssa::Amount(20000),
         };
 
         let initial_accounts = vec![initial_acc1, initial_acc2];
@@ -388,11 +1164,44 @@ mod tests {
             initial_accounts,
             initial_commitments: vec![],
             signing_key: *sequencer_sign_key_for_testing().value(),
+            check_state_invariants: true,
+            lazy_proving: None,
+            mempool_wal: false,
+            ingest_queue_size: 1000,
+            prune: None,
+            soft_confirmation_margin_blocks: 10,
+            event_sink: None,
+            program_allow_list: sequencer_core::config::default_program_allow_list(),
+            max_block_timestamp_skew_millis: 30_000,
+            max_pending_per_sender: None,
+            max_consecutive_per_sender: None,
+            max_time_lock_blocks: 100_000,
+            max_parked_per_sender: 16,
+            upgrades: vec![],
+            storage_deposit_per_byte: nssa::Amount::ZERO,
+            max_instruction_data_size: nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE,
+            max_program_size: nssa::program::DEFAULT_MAX_PROGRAM_SIZE,
+            anchor_window: nssa::DEFAULT_ANCHOR_WINDOW,
+            chain_id: 0,
+            accept_dev_proofs: false,
+            deterministic_dev_mode: None,
+            serve_pending_transfers: false,
+            serve_pending_transactions: false,
+            system_lane: None,
+            tx_audit: Default::default(),
+            deployment_smoke_test_cycles: Some(DEFAULT_DEPLOYMENT_SMOKE_TEST_CYCLES),
+            force_params_change: false,
+            repair_indexes: false,
         }
     }
 
     async fn components_for_tests() -> (JsonHandler, Vec<AccountInitialData>, EncodedTransaction) {
-        let config = sequencer_config_for_tests();
+        components_for_tests_with_config(sequencer_config_for_tests()).await
+    }
+
+    async fn components_for_tests_with_config(
+        config: SequencerConfig,
+    ) -> (JsonHandler, Vec<AccountInitialData>, EncodedTransaction) {
         let (mut sequencer_core, mempool_handle) = SequencerCore::start_from_config(config);
         let initial_accounts = sequencer_core.sequencer_config().initial_accounts.clone();
 
@@ -418,18 +1227,36 @@ mod tests {
             .produce_new_block_with_mempool_transactions()
             .unwrap();
 
+        let ingest_queue_size = sequencer_core.sequencer_config().ingest_queue_size;
+        let retry_after = std::time::Duration::from_millis(
+            sequencer_core
+                .sequencer_config()
+                .block_create_timeout_millis,
+        );
         let sequencer_core = Arc::new(Mutex::new(sequencer_core));
+        let ingest_handle = IngestHandle::spawn(
+            sequencer_core.clone(),
+            mempool_handle,
+            ingest_queue_size,
+            retry_after,
+        );
 
         (
             JsonHandler {
                 sequencer_state: sequencer_core,
-                mempool_handle,
+                ingest_handle,
+                timeouts_config: RpcTimeoutsConfig::default(),
             },
             initial_accounts,
             tx,
         )
     }
 
+    /// Context of reads at the current state of `handler`
+    async fn read_context_json(handler: &JsonHandler) -> Value {
+        serde_json::to_value(super::read_context(&*handler.sequencer_state.lock().await)).unwrap()
+    }
+
     async fn call_rpc_handler_with_json(handler: JsonHandler, request_json: Value) -> Value {
         use actix_web::{App, test, web};
 
@@ -460,11 +1287,13 @@ mod tests {
             "params": { "account_id": "11".repeat(16) },
             "id": 1
         });
+        let context = read_context_json(&json_handler).await;
         let expected_response = serde_json::json!({
             "id": 1,
             "jsonrpc": "2.0",
             "result": {
-                "balance": 0
+                "balance": "0",
+                "context": context
             }
         });
 
@@ -531,11 +1360,13 @@ mod tests {
             "params": { "account_id": acc1_id },
             "id": 1
         });
+        let context = read_context_json(&json_handler).await;
         let expected_response = serde_json::json!({
             "id": 1,
             "jsonrpc": "2.0",
             "result": {
-                "balance": 10000 - 10
+                "balance": "9990",
+                "context": context
             }
         });
 
@@ -553,11 +1384,13 @@ mod tests {
             "params": { "account_ids": ["11".repeat(16)] },
             "id": 1
         });
+        let context = read_context_json(&json_handler).await;
         let expected_response = serde_json::json!({
             "id": 1,
             "jsonrpc": "2.0",
             "result": {
-                "nonces": [ 0 ]
+                "nonces": [ 0 ],
+                "context": context
             }
         });
 
@@ -579,11 +1412,13 @@ mod tests {
             "params": { "account_ids": [acc1_id, acc2_id] },
             "id": 1
         });
+        let context = read_context_json(&json_handler).await;
         let expected_response = serde_json::json!({
             "id": 1,
             "jsonrpc": "2.0",
             "result": {
-                "nonces": [ 1, 0 ]
+                "nonces": [ 1, 0 ],
+                "context": context
             }
         });
 
@@ -601,16 +1436,19 @@ mod tests {
             "params": { "account_id": "11".repeat(16) },
             "id": 1
         });
+        let context = read_context_json(&json_handler).await;
         let expected_response = serde_json::json!({
             "id": 1,
             "jsonrpc": "2.0",
             "result": {
                 "account": {
-                    "balance": 0,
+                    "balance": "0",
                     "nonce": 0,
-                    "program_owner": [ 0, 0, 0, 0, 0, 0, 0, 0],
+                    "program_owner": format!("prog_{}", "00".repeat(32)),
                     "data": [],
-                }
+                },
+                "storage_deposit": "0",
+                "context": context
             }
         });
 
@@ -620,34 +1458,35 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_get_transaction_by_hash_for_non_existent_hash() {
-        let (json_handler, _, _) = components_for_tests().await;
+    async fn test_get_accounts_in_requested_order() {
+        let (json_handler, initial_accounts, _) = components_for_tests().await;
+
+        let acc1_id = initial_accounts[0].account_id.clone();
         let request = serde_json::json!({
             "jsonrpc": "2.0",
-            "method": "get_transaction_by_hash",
-            "params": { "hash": "cafe".repeat(16) },
+            "method": "get_accounts",
+            "params": { "account_ids": ["11".repeat(16), acc1_id] },
             "id": 1
         });
-        let expected_response = serde_json::json!({
-            "id": 1,
-            "jsonrpc": "2.0",
-            "result": {
-                "transaction": null
-            }
-        });
 
         let response = call_rpc_handler_with_json(json_handler, request).await;
 
-        assert_eq!(response, expected_response);
+        let accounts = response["result"]["accounts"].as_array().unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0]["account"]["balance"], "0");
+        assert_eq!(accounts[1]["account"]["nonce"], 1);
+        assert_ne!(accounts[1]["account"]["balance"], "0");
+        assert!(accounts[1].get("context").is_none());
+        assert_eq!(response["result"]["context"]["block_id"], 2);
     }
 
     #[actix_web::test]
-    async fn test_get_transaction_by_hash_for_invalid_hex() {
+    async fn test_get_accounts_over_limit() {
         let (json_handler, _, _) = components_for_tests().await;
         let request = serde_json::json!({
             "jsonrpc": "2.0",
-            "method": "get_transaction_by_hash",
-            "params": { "hash": "not_a_valid_hex" },
+            "method": "get_accounts",
+            "params": { "account_ids": vec!["11".repeat(16); MAX_ACCOUNTS_PER_REQUEST + 1] },
             "id": 1
         });
         let expected_response = serde_json::json!({
@@ -656,29 +1495,74 @@ mod tests {
             "error": {
                 "code": -32602,
                 "message": "Invalid params",
-                "data": "invalid hex"
+                "data": format!("at most {MAX_ACCOUNTS_PER_REQUEST} accounts per request")
             }
         });
-
         let response = call_rpc_handler_with_json(json_handler, request).await;
 
         assert_eq!(response, expected_response);
     }
 
     #[actix_web::test]
-    async fn test_get_transaction_by_hash_for_invalid_length() {
+    async fn test_get_transaction_by_hash_for_non_existent_hash() {
         let (json_handler, _, _) = components_for_tests().await;
         let request = serde_json::json!({
             "jsonrpc": "2.0",
             "method": "get_transaction_by_hash",
-            "params": { "hash": "cafecafe" },
+            "params": { "hash": "cafe".repeat(16) },
             "id": 1
         });
         let expected_response = serde_json::json!({
-            "jsonrpc": "2.0",
             "id": 1,
-            "error": {
-                "code": -32602,
+            "jsonrpc": "2.0",
+            "result": {
+                "transaction": null
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_get_transaction_by_hash_for_invalid_hex() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_transaction_by_hash",
+            "params": { "hash": "not_a_valid_hex" },
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": {
+                "code": -32602,
+                "message": "Invalid params",
+                "data": "invalid hex"
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_get_transaction_by_hash_for_invalid_length() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_transaction_by_hash",
+            "params": { "hash": "cafecafe" },
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": {
+                "code": -32602,
                 "message": "Invalid params",
                 "data": "invalid length"
             }
@@ -713,4 +1597,1829 @@ mod tests {
 
         assert_eq!(response, expected_response);
     }
+
+    /// Receipt of the transfer of 10 from the first initial account in [`components_for_tests`]
+    fn transfer_receipt_json(initial_accounts: &[AccountInitialData]) -> Value {
+        let balance = initial_accounts[0].balance;
+        let transfer =
+            |sign: &str| serde_json::json!([{ "kind": "transfer", "amount": "10", "sign": sign }]);
+        serde_json::json!({
+            "balance_changes": [
+                {
+                    "account_id": initial_accounts[0].account_id,
+                    "pre_balance": balance.to_string(),
+                    "post_balance": (balance.0 - 10).to_string(),
+                    "components": transfer("debit"),
+                },
+                {
+                    "account_id": nssa::AccountId::new([2; 32]).to_string(),
+                    "pre_balance": "0",
+                    "post_balance": "10",
+                    "components": transfer("credit"),
+                },
+            ]
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_get_block_expanded() {
+        let (json_handler, initial_accounts, tx) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_block_expanded",
+            "params": { "block_id": 2 },
+            "id": 1
+        });
+        let expected_transaction = serde_json::json!({
+            "hash": hex::encode(tx.hash()),
+            "tx_kind": "Public",
+            "program_id": nssa::program::Program::authenticated_transfer_program().id(),
+            "account_ids": [
+                initial_accounts[0].account_id,
+                nssa::AccountId::new([2; 32]).to_string(),
+            ],
+            "transfer": {
+                "sender": initial_accounts[0].account_id,
+                "recipient": nssa::AccountId::new([2; 32]).to_string(),
+                "amount": "10",
+            },
+            "instruction": {
+                "call": "native_transfer",
+                "sender": initial_accounts[0].account_id,
+                "recipient": nssa::AccountId::new([2; 32]).to_string(),
+                "amount": "10",
+            },
+            "receipt": transfer_receipt_json(&initial_accounts),
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response["result"]["block"]["block_id"], 2);
+        assert_eq!(
+            response["result"]["block"]["transactions"],
+            serde_json::json!([expected_transaction])
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_get_last_block_advertises_digest_of_served_block() {
+        let (json_handler, _, tx) = components_for_tests().await;
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(json_handler))
+                .route("/", actix_web::web::post().to(rpc_handler)),
+        )
+        .await;
+        let call = async |method: &str, params: Value| {
+            let request = actix_web::test::TestRequest::post()
+                .uri("/")
+                .set_json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .to_request();
+            let resp = actix_web::test::call_service(&app, request).await;
+            let response =
+                serde_json::from_slice::<Value>(&actix_web::test::read_body(resp).await).unwrap();
+            response["result"].clone()
+        };
+
+        let tip: GetLastBlockResponse =
+            serde_json::from_value(call("get_last_block", serde_json::json!({})).await).unwrap();
+        let block: GetBlockDataResponse =
+            serde_json::from_value(call("get_block", serde_json::json!({ "block_id": 2 })).await)
+                .unwrap();
+        let range: GetBlockRangeDataResponse = serde_json::from_value(
+            call(
+                "get_block_range",
+                serde_json::json!({ "start_block_id": 1, "end_block_id": 2 }),
+            )
+            .await,
+        )
+        .unwrap();
+
+        let digest = tip.last_block_digest.unwrap();
+        assert_eq!(tip.last_block, 2);
+        assert_eq!(digest.tx_count, 1);
+        assert_eq!(block.digest.as_ref(), Some(&digest));
+        assert_eq!(range.digests.len(), 2);
+        assert_eq!(range.digests[1], digest);
+
+        let block = borsh::from_slice::<HashableBlockData>(&block.block).unwrap();
+        assert_eq!(block.transactions, vec![tx]);
+        assert_eq!(block.check_digest(&digest), Ok(()));
+    }
+
+    #[actix_web::test]
+    async fn test_get_block_not_yet_produced() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(json_handler))
+                .route("/", actix_web::web::post().to(rpc_handler)),
+        )
+        .await;
+        let call = async |method: &str, params: Value| {
+            let request = actix_web::test::TestRequest::post()
+                .uri("/")
+                .set_json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .to_request();
+            let resp = actix_web::test::call_service(&app, request).await;
+            serde_json::from_slice::<Value>(&actix_web::test::read_body(resp).await).unwrap()
+        };
+
+        let response = call("get_block", serde_json::json!({ "block_id": 3 })).await;
+        assert_eq!(response["error"]["code"], BLOCK_NOT_YET_PRODUCED_ERROR_CODE);
+        assert_eq!(
+            response["error"]["data"],
+            serde_json::json!({ "block_id": 3, "last_block_id": 2 })
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_account_proof_leads_to_signed_state_root() {
+        let (json_handler, initial_accounts, _) = components_for_tests().await;
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(json_handler))
+                .route("/", actix_web::web::post().to(rpc_handler)),
+        )
+        .await;
+        let call = async |method: &str, params: Value| {
+            let request = actix_web::test::TestRequest::post()
+                .uri("/")
+                .set_json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .to_request();
+            let resp = actix_web::test::call_service(&app, request).await;
+            let response =
+                serde_json::from_slice::<Value>(&actix_web::test::read_body(resp).await).unwrap();
+            response["result"].clone()
+        };
+        let public_key = nssa::PublicKey::new_from_private_key(&sequencer_sign_key_for_testing());
+
+        let headers: GetBlockHeadersResponse = serde_json::from_value(
+            call(
+                "get_block_headers",
+                serde_json::json!({ "start_block_id": 1, "end_block_id": 10 }),
+            )
+            .await,
+        )
+        .unwrap();
+        assert_eq!(headers.headers.len(), 2);
+        let tip = verify_header_chain(
+            &Checkpoint::from(&headers.headers[0]),
+            &headers.headers[1..],
+            &public_key,
+        )
+        .unwrap();
+        assert_eq!(tip.block_id, 2);
+
+        let account_id = &initial_accounts[0].account_id;
+        let response: GetAccountWithProofResponse = serde_json::from_value(
+            call(
+                "get_account_with_proof",
+                serde_json::json!({ "account_id": account_id }),
+            )
+            .await,
+        )
+        .unwrap();
+        assert_eq!(response.header, headers.headers[1]);
+        assert!(response.header.is_valid_for(&public_key));
+        assert_eq!(response.account.balance, nssa::Amount(9990));
+        assert_eq!(
+            response.proven_state_root(&account_id.parse().unwrap()),
+            response.header.state_root_bytes()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_get_address_summary() {
+        let (json_handler, initial_accounts, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_address_summary",
+            "params": { "account_id": initial_accounts[0].account_id },
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "balance": "9990",
+                "tx_count": 1,
+                "first_seen_block": 2,
+                "last_seen_block": 2,
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_get_address_summary_for_unseen_account() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_address_summary",
+            "params": { "account_id": nssa::AccountId::new([3; 32]).to_string() },
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "balance": "0",
+                "tx_count": 0,
+                "first_seen_block": null,
+                "last_seen_block": null,
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_get_transactions_by_address() {
+        let (json_handler, initial_accounts, tx) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_transactions_by_address",
+            "params": { "account_id": initial_accounts[0].account_id },
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "transactions": [{
+                    "hash": hex::encode(tx.hash()),
+                    "block_id": 2,
+                    "program_id": nssa::program::Program::authenticated_transfer_program().id(),
+                    "direction": "out",
+                    "amount": "10",
+                }],
+                "has_more": false,
+                "next_cursor": null,
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_get_transactions_by_address_filters_direction() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_transactions_by_address",
+            "params": {
+                "account_id": nssa::AccountId::new([2; 32]).to_string(),
+                "filter": { "direction": "out" },
+            },
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "transactions": [],
+                "has_more": false,
+                "next_cursor": null,
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_get_pending_transfers() {
+        use actix_web::{App, test, web};
+
+        let config = SequencerConfig {
+            serve_pending_transfers: true,
+            ..sequencer_config_for_tests()
+        };
+        let (json_handler, initial_accounts, _) = components_for_tests_with_config(config).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/", web::post().to(rpc_handler)),
+        )
+        .await;
+        let call = async |request: Value| -> Value {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .set_json(request)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            serde_json::from_slice(&test::read_body(resp).await).unwrap()
+        };
+
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        let recipient = nssa::AccountId::new([2; 32]);
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *sender.value(),
+            1,
+            *recipient.value(),
+            25,
+            nssa::PrivateKey::try_new([1; 32]).unwrap(),
+        );
+        call(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "send_tx",
+            "params": {
+                "transaction": general_purpose::STANDARD.encode(borsh::to_vec(&tx).unwrap())
+            },
+            "id": 1
+        }))
+        .await;
+
+        let expected = serde_json::json!({
+            "transfers": [{
+                "hash": hex::encode(tx.hash()),
+                "sender": sender.to_string(),
+                "recipient": recipient.to_string(),
+                "amount": "25",
+            }]
+        });
+        for account_id in [sender, recipient] {
+            let response = call(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "get_pending_transfers",
+                "params": { "account_id": account_id.to_string() },
+                "id": 1
+            }))
+            .await;
+            assert_eq!(response["result"], expected);
+        }
+
+        let info = call(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_sequencer_info",
+            "params": {},
+            "id": 1
+        }))
+        .await;
+        assert!(
+            info["result"]["features"]
+                .as_array()
+                .unwrap()
+                .contains(&serde_json::json!("pending_transfers"))
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_get_pending_transfers_is_opt_in() {
+        let (json_handler, initial_accounts, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_pending_transfers",
+            "params": { "account_id": initial_accounts[0].account_id },
+            "id": 1
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response["error"]["code"], -32_601);
+    }
+
+    #[actix_web::test]
+    async fn test_get_pending_transactions() {
+        use actix_web::{App, test, web};
+
+        let config = SequencerConfig {
+            serve_pending_transactions: true,
+            ..sequencer_config_for_tests()
+        };
+        let (json_handler, initial_accounts, _) = components_for_tests_with_config(config).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/", web::post().to(rpc_handler)),
+        )
+        .await;
+        let call = async |request: Value| -> Value {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .set_json(request)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            serde_json::from_slice(&test::read_body(resp).await).unwrap()
+        };
+
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *sender.value(),
+            1,
+            *nssa::AccountId::new([2; 32]).value(),
+            25,
+            nssa::PrivateKey::try_new([1; 32]).unwrap(),
+        );
+        call(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "send_tx",
+            "params": {
+                "transaction": general_purpose::STANDARD.encode(borsh::to_vec(&tx).unwrap())
+            },
+            "id": 1
+        }))
+        .await;
+
+        let response = call(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_pending_transactions",
+            "params": {},
+            "id": 1
+        }))
+        .await;
+
+        assert_eq!(
+            response["result"],
+            serde_json::json!({
+                "last_block": 2,
+                "transactions": [{
+                    "hash": hex::encode(tx.hash()),
+                    "sender": sender.to_string(),
+                    "nonce": 1,
+                    "priority_fee": "0",
+                }]
+            })
+        );
+
+        let info = call(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_sequencer_info",
+            "params": {},
+            "id": 1
+        }))
+        .await;
+        assert!(
+            info["result"]["features"]
+                .as_array()
+                .unwrap()
+                .contains(&serde_json::json!("pending_transactions"))
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_get_pending_transactions_is_opt_in() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_pending_transactions",
+            "params": {},
+            "id": 1
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response["error"]["code"], -32_601);
+    }
+
+    #[actix_web::test]
+    async fn test_get_chain_stats() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_chain_stats",
+            "params": {},
+            "id": 1
+        });
+        let context = read_context_json(&json_handler).await;
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "last_block": 2,
+                "tx_count": 1,
+                "total_supply": "30000",
+                // Genesis accounts, the recipient, the burn account and the clock account with
+                // 8 bytes of data
+                "state_size": { "accounts": 5, "data_bytes": 8 },
+                "total_burned": "0",
+                "total_storage_deposit": "0",
+                "failed_block_attempts": 0,
+                "block_production_stall_alerts": 0,
+                "context": context,
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_get_chain_info() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_chain_info",
+            "params": {},
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "genesis_id": 1,
+                "last_block": 2,
+                "max_num_tx_in_block": 10,
+                "params_version": 0,
+                "tx_ordering_policy": "fee_priority_desc,arrival_seq_asc,sender_nonce_asc",
+                "program_allow_list": {
+                    "builtin_programs": ["authenticated_transfer", "token", "escrow"],
+                    "deployed_programs": true,
+                },
+                "storage_deposit_per_byte": "0",
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    async fn health_report_of(response: actix_web::HttpResponse) -> (u16, HealthReport) {
+        let status = response.status().as_u16();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn test_readiness_of_stalled_sequencer() {
+        let config = SequencerConfig {
+            block_create_timeout_millis: 20,
+            ..sequencer_config_for_tests()
+        };
+        let (json_handler, _, _) = components_for_tests_with_config(config).await;
+        let sequencer_core = json_handler.sequencer_state.clone();
+        let json_handler = actix_web::web::Data::new(json_handler);
+
+        let (status, report) = health_report_of(ready_handler(json_handler.clone()).await).await;
+        assert_eq!(status, 200);
+        assert!(report.ok);
+
+        // Production loop is stalled
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let (status, report) = health_report_of(ready_handler(json_handler.clone()).await).await;
+        assert_eq!(status, 503);
+        assert_eq!(
+            report
+                .failed_checks()
+                .map(|check| check.name.as_str())
+                .collect::<Vec<_>>(),
+            vec![BLOCK_PRODUCTION_CHECK]
+        );
+        let (status, _) = health_report_of(health_handler(json_handler.clone()).await).await;
+        assert_eq!(status, 200);
+
+        // Production is resumed
+        sequencer_core
+            .lock()
+            .await
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+
+        let (status, report) = health_report_of(ready_handler(json_handler).await).await;
+        assert_eq!(status, 200);
+        assert!(report.ok);
+    }
+
+    #[actix_web::test]
+    async fn test_get_api_version() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_api_version",
+            "params": {},
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "api_version": { "major": 2, "minor": 1 },
+                "min_supported_api_version": { "major": 2, "minor": 0 },
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_get_sequencer_info() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let genesis_hash = sequencer_core::genesis_block_data(&sequencer_config_for_tests()).hash();
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_sequencer_info",
+            "params": {},
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "version": env!("CARGO_PKG_VERSION"),
+                "api_version": { "major": 2, "minor": 1 },
+                "tx_kinds": [3, 4, 2],
+                "features": ["private_transactions", "batch_accounts"],
+                "public_key": hex::encode(
+                    nssa::PublicKey::new_from_private_key(&sequencer_sign_key_for_testing())
+                        .value()
+                ),
+                "genesis_hash": hex::encode(genesis_hash),
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_system_transaction_lands_in_next_block_through_admin_rpc_only() {
+        use actix_web::{App, http::StatusCode, test, web};
+
+        let config = SequencerConfig {
+            system_lane: Some(SystemLaneConfig {
+                auth_token: "admin-token".to_string(),
+                queue_size: 10,
+                block_share_percent: 5,
+            }),
+            ..sequencer_config_for_tests()
+        };
+        let max_num_tx_in_block = config.max_num_tx_in_block as u64;
+        let (json_handler, initial_accounts, _) = components_for_tests_with_config(config).await;
+        let sequencer_state = json_handler.sequencer_state.clone();
+        let tip = 2;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/", web::post().to(rpc_handler))
+                .route("/admin", web::post().to(admin_rpc_handler)),
+        )
+        .await;
+        let call = async |uri: &str, auth: Option<&str>, method: &str, tx: &EncodedTransaction| {
+            let transaction = general_purpose::STANDARD.encode(borsh::to_vec(tx).unwrap());
+            let mut req = test::TestRequest::post()
+                .uri(uri)
+                .set_json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": { "transaction": transaction },
+                    "id": 1
+                }));
+            if let Some(auth) = auth {
+                req = req.insert_header(("Authorization", auth));
+            }
+            let resp = test::call_service(&app, req.to_request()).await;
+            let status = resp.status();
+            let body = test::read_body(resp).await;
+            (status, serde_json::from_slice::<Value>(&body).ok())
+        };
+
+        // Flood of user transactions fills the next blocks
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        for nonce in 1..=2 * max_num_tx_in_block {
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                *sender.value(),
+                nonce,
+                [2; 32],
+                10,
+                nssa::PrivateKey::try_new([1; 32]).unwrap(),
+            );
+            let (_, response) = call("/", None, "send_tx", &tx).await;
+            assert_eq!(response.unwrap()["result"]["status"], TRANSACTION_SUBMITTED);
+        }
+
+        let faucet: nssa::AccountId = initial_accounts[1].account_id.parse().unwrap();
+        let faucet_tx = common::test_utils::create_transaction_native_token_transfer(
+            *faucet.value(),
+            0,
+            [3; 32],
+            100,
+            nssa::PrivateKey::try_new([2; 32]).unwrap(),
+        );
+
+        // Public endpoint doesn't know the method, admin one requires the token
+        let (_, response) = call("/", None, "send_system_tx", &faucet_tx).await;
+        assert_eq!(
+            response.unwrap()["error"]["code"],
+            METHOD_NOT_FOUND_ERROR_CODE
+        );
+        let (status, _) = call("/admin", None, "send_system_tx", &faucet_tx).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call(
+            "/admin",
+            Some("Bearer admin-tokem"),
+            "send_system_tx",
+            &faucet_tx,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (_, response) = call("/admin", Some("Bearer admin-token"), "send_tx", &faucet_tx).await;
+        assert_eq!(
+            response.unwrap()["error"]["code"],
+            METHOD_NOT_FOUND_ERROR_CODE
+        );
+
+        let (status, response) = call(
+            "/admin",
+            Some("Bearer admin-token"),
+            "send_system_tx",
+            &faucet_tx,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let result = &response.unwrap()["result"];
+        assert_eq!(result["status"], TRANSACTION_SUBMITTED);
+        assert_eq!(result["mempool_position"], 0);
+        assert_eq!(result["estimated_inclusion_block"], tip + 1);
+
+        let mut state = sequencer_state.lock().await;
+        state.produce_new_block_with_mempool_transactions().unwrap();
+        assert!(matches!(
+            state.transaction_status(&faucet_tx.hash()),
+            common::rpc_types::TransactionStatus::Included { block_id, .. } if block_id == tip + 1
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_indexes_are_rebuilt_through_admin_rpc() {
+        use actix_web::{App, test, web};
+
+        let config = SequencerConfig {
+            system_lane: Some(SystemLaneConfig {
+                auth_token: "admin-token".to_string(),
+                queue_size: 10,
+                block_share_percent: 5,
+            }),
+            ..sequencer_config_for_tests()
+        };
+        let (json_handler, _, _) = components_for_tests_with_config(config).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/admin", web::post().to(admin_rpc_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/admin")
+            .insert_header(("Authorization", "Bearer admin-token"))
+            .set_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "rebuild_indexes",
+                "params": {},
+                "id": 1
+            }))
+            .to_request();
+        let response: Value = test::call_and_read_body_json(&app, req).await;
+
+        let no_discrepancies = serde_json::json!({ "missing": 0, "extra": 0, "mismatched": 0 });
+        assert_eq!(
+            response["result"],
+            serde_json::json!({
+                "last_block": 2,
+                "scanned_blocks": 1,
+                "tx_hashes": no_discrepancies,
+                "address_txs": no_discrepancies,
+                "account_activity": no_discrepancies,
+                "receipts": no_discrepancies
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_tx_audit_is_served_through_admin_rpc() {
+        use actix_web::{App, test, web};
+
+        let config = SequencerConfig {
+            system_lane: Some(SystemLaneConfig {
+                auth_token: "admin-token".to_string(),
+                queue_size: 10,
+                block_share_percent: 5,
+            }),
+            ..sequencer_config_for_tests()
+        };
+        let (json_handler, _, tx) = components_for_tests_with_config(config).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/admin", web::post().to(admin_rpc_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/admin")
+            .insert_header(("Authorization", "Bearer admin-token"))
+            .set_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "get_tx_audit",
+                "params": { "hash": hex::encode(tx.hash()) },
+                "id": 1
+            }))
+            .to_request();
+        let response: Value = test::call_and_read_body_json(&app, req).await;
+
+        let events = response["result"]["events"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event"], "admitted");
+        assert_eq!(events[0]["position"], 0);
+        assert_eq!(events[1]["event"], "included");
+        assert_eq!(events[1]["block_id"], 2);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_rpc_is_not_served_without_system_lane() {
+        use actix_web::{App, http::StatusCode, test, web};
+
+        let (json_handler, _, tx) = components_for_tests().await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/admin", web::post().to(admin_rpc_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/admin")
+            .insert_header(("Authorization", "Bearer "))
+            .set_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "send_system_tx",
+                "params": {
+                    "transaction": general_purpose::STANDARD.encode(borsh::to_vec(&tx).unwrap())
+                },
+                "id": 1
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_send_tx_reports_mempool_position_and_inclusion_estimate() {
+        use actix_web::{App, test, web};
+
+        let (json_handler, initial_accounts, included_tx) = components_for_tests().await;
+        let max_num_tx_in_block = sequencer_config_for_tests().max_num_tx_in_block as u64;
+        let tip = 2;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/", web::post().to(rpc_handler)),
+        )
+        .await;
+        let call = async |request: Value| -> Value {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .set_json(request)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            serde_json::from_slice(&test::read_body(resp).await).unwrap()
+        };
+
+        // Overfill mempool by one transaction more than fits into a block
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        let mut responses = vec![];
+        let mut hashes = vec![];
+        for nonce in 1..=max_num_tx_in_block + 1 {
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                *sender.value(),
+                nonce,
+                [2; 32],
+                10,
+                nssa::PrivateKey::try_new([1; 32]).unwrap(),
+            );
+            hashes.push(hex::encode(tx.hash()));
+            let request = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "send_tx",
+                "params": {
+                    "transaction": general_purpose::STANDARD.encode(borsh::to_vec(&tx).unwrap())
+                },
+                "id": 1
+            });
+            responses.push(call(request).await);
+        }
+
+        let first = &responses.first().unwrap()["result"];
+        assert_eq!(first["mempool_position"], 0);
+        assert_eq!(first["mempool_depth"], 1);
+        assert_eq!(first["estimated_inclusion_block"], tip + 1);
+
+        let overflow = &responses.last().unwrap()["result"];
+        assert_eq!(overflow["mempool_position"], max_num_tx_in_block);
+        assert_eq!(overflow["mempool_depth"], max_num_tx_in_block + 1);
+        assert_eq!(overflow["estimated_inclusion_block"], tip + 2);
+
+        // Soft confirmations are signed by the advertised key and promise inclusion once the
+        // whole mempool fits into blocks, plus the margin
+        let info = call(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_sequencer_info",
+            "params": {},
+            "id": 1
+        }))
+        .await;
+        let public_key = nssa::PublicKey::try_new(
+            hex::decode(info["result"]["public_key"].as_str().unwrap())
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+        let margin = sequencer_config_for_tests().soft_confirmation_margin_blocks;
+        for (response, hash) in responses.iter().zip(&hashes) {
+            let result = &response["result"];
+            let confirmation: SoftConfirmation =
+                serde_json::from_value(result["soft_confirmation"].clone()).unwrap();
+            let depth = result["mempool_depth"].as_u64().unwrap();
+            let estimated_inclusion_block = result["estimated_inclusion_block"].as_u64().unwrap();
+
+            assert!(confirmation.is_valid_for(&public_key));
+            assert_eq!(&confirmation.tx_hash, hash);
+            assert!(confirmation.promised_by_block >= estimated_inclusion_block + margin);
+            assert_eq!(
+                confirmation.promised_by_block,
+                tip as u64 + depth.div_ceil(max_num_tx_in_block) + margin
+            );
+        }
+
+        let status_of = |hash: String| {
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "get_transaction_status",
+                "params": { "hash": hash },
+                "id": 1
+            })
+        };
+        assert_eq!(
+            call(status_of(hashes.last().unwrap().clone())).await["result"],
+            serde_json::json!({
+                "status": {
+                    "state": "pending",
+                    "position": max_num_tx_in_block,
+                    "depth": max_num_tx_in_block + 1,
+                    "estimated_inclusion_block": tip + 2,
+                    "estimated_wait_millis": 2000
+                }
+            })
+        );
+        assert_eq!(
+            call(status_of(hex::encode(included_tx.hash()))).await["result"],
+            serde_json::json!({
+                "status": {
+                    "state": "included",
+                    "block_id": tip,
+                    "receipt": transfer_receipt_json(&initial_accounts),
+                }
+            })
+        );
+        assert_eq!(
+            call(status_of("cafe".repeat(16))).await["result"],
+            serde_json::json!({ "status": { "state": "unknown" } })
+        );
+    }
+
+    fn public_tx_signed_by(
+        message: nssa::public_transaction::Message,
+        signing_key: &nssa::PrivateKey,
+    ) -> EncodedTransaction {
+        let witness_set =
+            nssa::public_transaction::WitnessSet::for_message(&message, &[signing_key]);
+        NSSATransaction::Public(nssa::PublicTransaction::new(message, witness_set)).into()
+    }
+
+    fn transfer_message(
+        account_ids: Vec<nssa::AccountId>,
+        nonces: Vec<u64>,
+        amount: u128,
+    ) -> nssa::public_transaction::Message {
+        nssa::public_transaction::Message::try_new(
+            nssa::program::Program::authenticated_transfer_program().id(),
+            account_ids,
+            nonces,
+            amount,
+        )
+        .unwrap()
+    }
+
+    fn deployment_tx_signed_by(bytecode: Vec<u8>, signing_key: &nssa::PrivateKey) -> Vec<u8> {
+        let deployer = nssa::AccountId::from(&nssa::PublicKey::new_from_private_key(signing_key));
+        let message = nssa::program_deployment_transaction::Message::new(
+            deployer,
+            0,
+            sequencer_config_for_tests().chain_id,
+            nssa::Amount::MAX,
+            bytecode,
+        );
+        let witness_set =
+            nssa::program_deployment_transaction::WitnessSet::for_message(&message, signing_key);
+        let tx: EncodedTransaction = NSSATransaction::ProgramDeployment(
+            nssa::ProgramDeploymentTransaction::new(message, witness_set),
+        )
+        .into();
+        borsh::to_vec(&tx).unwrap()
+    }
+
+    /// Encoded transactions, which pass or fail every kind of admission check, with the verdict
+    /// of their submission in this order: status or `None` if they're rejected
+    fn admission_scenarios(
+        initial_accounts: &[AccountInitialData],
+        included_tx: &EncodedTransaction,
+    ) -> Vec<(&'static str, Vec<u8>, Option<&'static str>)> {
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        let recipient: nssa::AccountId = initial_accounts[1].account_id.parse().unwrap();
+        let sender_key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let other_key = nssa::PrivateKey::try_new([2; 32]).unwrap();
+        let encode = |tx: EncodedTransaction| borsh::to_vec(&tx).unwrap();
+
+        let transfer = public_tx_signed_by(
+            transfer_message(vec![sender, recipient], vec![1], 10),
+            &sender_key,
+        );
+        let invalid_signature = {
+            let witness_set = nssa::public_transaction::WitnessSet::for_message(
+                &transfer_message(vec![sender, recipient], vec![3], 11),
+                &[&sender_key],
+            );
+            NSSATransaction::Public(nssa::PublicTransaction::new(
+                transfer_message(vec![sender, recipient], vec![3], 10),
+                witness_set,
+            ))
+            .into()
+        };
+        let oversized_instruction = nssa::public_transaction::Message::try_new(
+            nssa::program::Program::authenticated_transfer_program().id(),
+            vec![sender, recipient],
+            vec![3],
+            vec![0_u32; nssa::program::DEFAULT_MAX_INSTRUCTION_DATA_SIZE + 1],
+        )
+        .unwrap();
+        let not_allowed_call = nssa::public_transaction::Message::try_new(
+            nssa::program::Program::pinata().id(),
+            vec![sender],
+            vec![3],
+            0_u128,
+        )
+        .unwrap();
+
+        vec![
+            (
+                "transfer",
+                encode(transfer.clone()),
+                Some(TRANSACTION_SUBMITTED),
+            ),
+            (
+                "resubmitted transfer",
+                encode(transfer),
+                Some(TRANSACTION_ALREADY_SUBMITTED),
+            ),
+            (
+                "included transfer",
+                encode(included_tx.clone()),
+                Some(TRANSACTION_ALREADY_SUBMITTED),
+            ),
+            // Nonces and balances are checked on inclusion, such transactions are dropped then
+            (
+                "transfer with stale nonce",
+                encode(public_tx_signed_by(
+                    transfer_message(vec![sender, recipient], vec![0], 11),
+                    &sender_key,
+                )),
+                Some(TRANSACTION_SUBMITTED),
+            ),
+            (
+                "transfer not signed by sender",
+                encode(public_tx_signed_by(
+                    transfer_message(vec![sender, recipient], vec![2], 10),
+                    &other_key,
+                )),
+                Some(TRANSACTION_SUBMITTED),
+            ),
+            (
+                "time locked transfer",
+                encode(public_tx_signed_by(
+                    transfer_message(vec![sender, recipient], vec![2], 10)
+                        .with_valid_from_block(Some(1000)),
+                    &sender_key,
+                )),
+                Some(TRANSACTION_SUBMITTED),
+            ),
+            ("undecodable transaction", vec![1, 2, 3], None),
+            ("invalid signature", encode(invalid_signature), None),
+            (
+                "transfer to itself",
+                encode(public_tx_signed_by(
+                    transfer_message(vec![sender, sender], vec![3], 10),
+                    &sender_key,
+                )),
+                None,
+            ),
+            (
+                "nonce without signature",
+                encode(public_tx_signed_by(
+                    transfer_message(vec![sender, recipient], vec![3, 0], 10),
+                    &sender_key,
+                )),
+                None,
+            ),
+            (
+                "oversized instruction",
+                encode(public_tx_signed_by(oversized_instruction, &sender_key)),
+                None,
+            ),
+            (
+                "call of not allowed program",
+                encode(public_tx_signed_by(not_allowed_call, &sender_key)),
+                None,
+            ),
+            (
+                "invalid program",
+                deployment_tx_signed_by(vec![1, 2, 3], &sender_key),
+                None,
+            ),
+            (
+                "duplicate deployment",
+                deployment_tx_signed_by(
+                    nssa::program::Program::token().elf().to_vec(),
+                    &sender_key,
+                ),
+                Some(PROGRAM_ALREADY_DEPLOYED),
+            ),
+        ]
+    }
+
+    #[actix_web::test]
+    async fn test_send_tx_rejects_transaction_exceeding_unreserved_balance() {
+        use actix_web::{App, test, web};
+
+        let (json_handler, initial_accounts, _) = components_for_tests().await;
+        let sequencer_state = json_handler.sequencer_state.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/", web::post().to(rpc_handler)),
+        )
+        .await;
+        let send_tx = async |tx: &EncodedTransaction| -> Value {
+            let transaction = general_purpose::STANDARD.encode(borsh::to_vec(tx).unwrap());
+            let req = test::TestRequest::post()
+                .uri("/")
+                .set_json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "send_tx",
+                    "params": { "transaction": transaction },
+                    "id": 1
+                }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            serde_json::from_slice(&test::read_body(resp).await).unwrap()
+        };
+        let produce_block = async || {
+            sequencer_state
+                .lock()
+                .await
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        };
+
+        // Sender has 9990 left after the transfer of the setup, each transfer fits into it, both
+        // of them don't
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        let transfer = |nonce| {
+            common::test_utils::create_transaction_native_token_transfer(
+                *sender.value(),
+                nonce,
+                [2; 32],
+                6000,
+                nssa::PrivateKey::try_new([1; 32]).unwrap(),
+            )
+        };
+        let first = transfer(1);
+        let second = transfer(2);
+        let response = send_tx(&first).await;
+        assert_eq!(response["result"]["status"], TRANSACTION_SUBMITTED);
+
+        let response = send_tx(&second).await;
+        let error = &response["error"];
+        assert_eq!(error["code"], INSUFFICIENT_UNRESERVED_BALANCE_ERROR_CODE);
+        assert_eq!(
+            error["data"],
+            serde_json::json!({
+                "sender": sender.to_string(),
+                "balance": "9990",
+                "reserved": "6000",
+                "required": "6000",
+                "pending": [{ "hash": hex::encode(first.hash()), "amount": "6000" }]
+            })
+        );
+        // Rejected submission isn't admitted
+        assert!(matches!(
+            sequencer_state
+                .lock()
+                .await
+                .transaction_status(&second.hash()),
+            common::rpc_types::TransactionStatus::Unknown
+        ));
+
+        // Once the first transfer is included and the sender is funded, the second one fits
+        produce_block().await;
+        let funder: nssa::AccountId = initial_accounts[1].account_id.parse().unwrap();
+        let funding = common::test_utils::create_transaction_native_token_transfer(
+            *funder.value(),
+            0,
+            *sender.value(),
+            6000,
+            nssa::PrivateKey::try_new([2; 32]).unwrap(),
+        );
+        let response = send_tx(&funding).await;
+        assert_eq!(response["result"]["status"], TRANSACTION_SUBMITTED);
+        produce_block().await;
+
+        let response = send_tx(&second).await;
+        assert_eq!(response["result"]["status"], TRANSACTION_SUBMITTED);
+    }
+
+    #[actix_web::test]
+    async fn test_validate_transaction_gives_verdict_of_submission() {
+        use actix_web::{App, test, web};
+
+        let mut config = sequencer_config_for_tests();
+        // Pinata isn't allowed, even in testnet builds
+        config.program_allow_list = nssa::ProgramAllowList::default();
+        let (json_handler, initial_accounts, included_tx) =
+            components_for_tests_with_config(config).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/", web::post().to(rpc_handler)),
+        )
+        .await;
+        let call = async |method: &str, transaction: &[u8]| -> Value {
+            let request = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": { "transaction": general_purpose::STANDARD.encode(transaction) },
+                "id": 1
+            });
+            let req = test::TestRequest::post()
+                .uri("/")
+                .set_json(request)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            serde_json::from_slice(&test::read_body(resp).await).unwrap()
+        };
+
+        // Each transaction is validated right before its submission
+        for (scenario, transaction, expected_status) in
+            admission_scenarios(&initial_accounts, &included_tx)
+        {
+            let verdict = call("validate_transaction", &transaction).await;
+            let submission = call("send_tx", &transaction).await;
+
+            let Some(expected_status) = expected_status else {
+                assert!(submission["error"].is_object(), "{scenario}: {submission}");
+                assert_eq!(verdict["error"], submission["error"], "{scenario}");
+                continue;
+            };
+            let (verdict, submission) = (&verdict["result"], &submission["result"]);
+            assert_eq!(submission["status"], expected_status, "{scenario}");
+            for field in ["status", "tx_hash", "program_id"] {
+                assert_eq!(verdict[field], submission[field], "{field} of {scenario}");
+            }
+            if expected_status == TRANSACTION_SUBMITTED {
+                assert!(verdict["tx_status"].is_null(), "{scenario}");
+            } else {
+                assert_eq!(verdict["tx_status"], submission["tx_status"], "{scenario}");
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_preview_next_block_keeps_transactions_pending() {
+        use actix_web::{App, test, web};
+
+        let (json_handler, initial_accounts, _) = components_for_tests().await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/", web::post().to(rpc_handler)),
+        )
+        .await;
+        let call = async |request: Value| -> Value {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .set_json(request)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            serde_json::from_slice(&test::read_body(resp).await).unwrap()
+        };
+
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *sender.value(),
+            1,
+            [2; 32],
+            10,
+            nssa::PrivateKey::try_new([1; 32]).unwrap(),
+        );
+        let hash = hex::encode(tx.hash());
+        call(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "send_tx",
+            "params": {
+                "transaction": general_purpose::STANDARD.encode(borsh::to_vec(&tx).unwrap())
+            },
+            "id": 1
+        }))
+        .await;
+
+        let preview = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "preview_next_block",
+            "params": {},
+            "id": 1
+        });
+        let expected = serde_json::json!({
+            "block_id": 3,
+            "tx_hashes": [hash],
+            "total_gas": tx.encoded_transaction_data.len(),
+        });
+        assert_eq!(call(preview.clone()).await["result"], expected);
+        // Previewing again sees the same transactions
+        assert_eq!(call(preview).await["result"], expected);
+
+        let status = call(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_transaction_status",
+            "params": { "hash": hash },
+            "id": 1
+        }))
+        .await;
+        assert_eq!(status["result"]["status"]["state"], "pending");
+    }
+
+    #[actix_web::test]
+    async fn test_get_block_proof_of_eagerly_committed_block() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_block_proof",
+            "params": { "block_id": 2 },
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": { "status": { "state": "not_requested" } }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_get_sync_status() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_sync_status",
+            "params": {},
+            "id": 1
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        let status = &response["result"]["status"];
+        assert_eq!(status["state"], "synced");
+        assert_eq!(status["start_height"], 1);
+        assert_eq!(status["current_height"], 2);
+        assert_eq!(status["target_height"], 2);
+        assert_eq!(status["eta_secs"], 0);
+    }
+
+    #[actix_web::test]
+    async fn test_get_latest_anchors() {
+        let (json_handler, _, _) = components_for_tests().await;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_latest_anchors",
+            "params": {},
+            "id": 1
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        let result = &response["result"];
+        assert_eq!(result["anchor_window"], nssa::DEFAULT_ANCHOR_WINDOW);
+        let anchors = result["anchors"].as_array().unwrap();
+        assert_eq!(anchors.len(), 2);
+        assert_eq!(anchors[0]["block_id"], 3);
+        assert_eq!(anchors[1]["block_id"], 2);
+    }
+
+    #[actix_web::test]
+    async fn test_get_priority_fees_of_pending_transactions() {
+        let (json_handler, initial_accounts, _) = components_for_tests().await;
+        let acc1: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        for (nonce, priority_fee) in [(1, 20), (2, 0), (3, 5)] {
+            let tx = common::test_utils::create_transaction_native_token_transfer_with_priority_fee(
+                *acc1.value(),
+                nonce,
+                [2; 32],
+                10,
+                nssa::Amount(priority_fee),
+                nssa::PrivateKey::try_new([1; 32]).unwrap(),
+            );
+            json_handler.ingest_handle.submit(tx).await.ok().unwrap();
+        }
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_priority_fees",
+            "params": {},
+            "id": 1
+        });
+        let expected_response = serde_json::json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "distribution": { "pending": 3, "min": "0", "median": "5", "max": "20" }
+            }
+        });
+
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response, expected_response);
+    }
+
+    #[actix_web::test]
+    async fn test_resubmitted_transaction_is_reported_not_duplicated() {
+        use actix_web::{App, test, web};
+
+        let (json_handler, initial_accounts, included_tx) = components_for_tests().await;
+        let sequencer_state = json_handler.sequencer_state.clone();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/", web::post().to(rpc_handler)),
+        )
+        .await;
+        let send = async |tx: &EncodedTransaction| -> Value {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .set_json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "send_tx",
+                    "params": {
+                        "transaction": general_purpose::STANDARD.encode(borsh::to_vec(tx).unwrap())
+                    },
+                    "id": 1
+                }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            serde_json::from_slice::<Value>(&test::read_body(resp).await).unwrap()["result"].clone()
+        };
+
+        // Response to the first submission is lost, so the client submits again
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            *sender.value(),
+            1,
+            [2; 32],
+            10,
+            nssa::PrivateKey::try_new([1; 32]).unwrap(),
+        );
+        let first = send(&tx).await;
+        let second = send(&tx).await;
+        assert_eq!(first["status"], TRANSACTION_SUBMITTED);
+        assert_eq!(second["status"], TRANSACTION_ALREADY_SUBMITTED);
+        assert_eq!(second["tx_hash"], first["tx_hash"]);
+        assert_eq!(second["tx_status"], first["tx_status"]);
+        assert_eq!(second["tx_status"]["state"], "pending");
+        assert_eq!(second["mempool_depth"], 1);
+
+        let block_id = sequencer_state
+            .lock()
+            .await
+            .produce_new_block_with_mempool_transactions()
+            .unwrap();
+        let block = sequencer_state
+            .lock()
+            .await
+            .block_store()
+            .get_block_at_id(block_id)
+            .unwrap();
+        assert_eq!(block.body.transactions.len(), 1);
+
+        for tx in [&tx, &included_tx] {
+            let resubmitted = send(tx).await;
+            assert_eq!(resubmitted["status"], TRANSACTION_ALREADY_SUBMITTED);
+            assert_eq!(resubmitted["tx_status"]["state"], "included");
+        }
+        assert_eq!(
+            send(&tx).await["tx_status"],
+            serde_json::json!({ "state": "included", "block_id": block_id })
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_chain_stats_reflect_burns_across_blocks() {
+        use actix_web::{App, test, web};
+
+        let (json_handler, initial_accounts, _) = components_for_tests().await;
+        let sequencer_state = json_handler.sequencer_state.clone();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(json_handler))
+                .route("/", web::post().to(rpc_handler)),
+        )
+        .await;
+        let call = async |method: &str, params: Value| -> Value {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .set_json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            serde_json::from_slice::<Value>(&test::read_body(resp).await).unwrap()["result"].clone()
+        };
+
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        for (nonce, amount) in [(1, 10), (2, 15)] {
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                *sender.value(),
+                nonce,
+                *nssa::BURN_ACCOUNT_ID.value(),
+                amount,
+                nssa::PrivateKey::try_new([1; 32]).unwrap(),
+            );
+            let params = serde_json::json!({
+                "transaction": general_purpose::STANDARD.encode(borsh::to_vec(&tx).unwrap())
+            });
+            assert_eq!(
+                call("send_tx", params).await["status"],
+                TRANSACTION_SUBMITTED
+            );
+            sequencer_state
+                .lock()
+                .await
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        }
+
+        let stats = call("get_chain_stats", serde_json::json!({})).await;
+        assert_eq!(stats["last_block"], 4);
+        assert_eq!(stats["total_burned"], "25");
+        assert_eq!(stats["total_supply"], "30000");
+    }
+
+    #[actix_web::test]
+    async fn test_read_exceeding_timeout_is_reported_as_busy() {
+        let (mut json_handler, _, _) = components_for_tests().await;
+        json_handler.timeouts_config.read_timeout_millis = 50;
+        // Core is held as if by a slow block production
+        let sequencer_core = json_handler.sequencer_state.clone();
+        let _guard = sequencer_core.lock().await;
+
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_last_block",
+            "params": {},
+            "id": 1
+        });
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response["error"]["code"], SERVER_BUSY_ERROR_CODE);
+        assert_eq!(response["error"]["cause"]["name"], "SERVER_BUSY");
+        assert_eq!(
+            response["error"]["data"],
+            serde_json::json!({ "method": "get_last_block", "timeout_millis": 50 })
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_read_waits_for_min_block_to_be_produced() {
+        let (json_handler, initial_accounts, _) = components_for_tests().await;
+        let sequencer_core = json_handler.sequencer_state.clone();
+        // Block is applied slower than the read arrives
+        let producer = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            sequencer_core
+                .lock()
+                .await
+                .produce_new_block_with_mempool_transactions()
+                .unwrap();
+        });
+
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_account_balance",
+            "params": { "account_id": initial_accounts[0].account_id, "min_block_id": 3 },
+            "id": 1
+        });
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+        producer.await.unwrap();
+
+        assert_eq!(response["result"]["balance"], "9990");
+        assert_eq!(response["result"]["context"]["block_id"], 3);
+    }
+
+    #[actix_web::test]
+    async fn test_read_of_block_not_produced_in_time_is_stale() {
+        let (mut json_handler, _, _) = components_for_tests().await;
+        json_handler.timeouts_config.snapshot_wait_millis = 50;
+
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_accounts_nonces",
+            "params": { "account_ids": ["11".repeat(16)], "min_block_id": 3 },
+            "id": 1
+        });
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+
+        assert_eq!(response["error"]["code"], STALE_SNAPSHOT_ERROR_CODE);
+        assert_eq!(response["error"]["cause"]["name"], "STALE_SNAPSHOT");
+        assert_eq!(
+            response["error"]["data"],
+            serde_json::json!({ "min_block_id": 3, "block_id": 2 })
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_timed_out_submission_is_not_admitted() {
+        let config = SequencerConfig {
+            mempool_max_size: 1,
+            mempool_wal: true,
+            ..sequencer_config_for_tests()
+        };
+        let wal_path = config
+            .home
+            .join(sequencer_core::mempool_wal::MEMPOOL_WAL_FILE_NAME);
+        let (mut json_handler, initial_accounts, _) =
+            components_for_tests_with_config(config).await;
+        json_handler.timeouts_config.submission_timeout_millis = 50;
+        let sequencer_core = json_handler.sequencer_state.clone();
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        let transfer = |nonce| {
+            common::test_utils::create_transaction_native_token_transfer(
+                *sender.value(),
+                nonce,
+                [2; 32],
+                10,
+                nssa::PrivateKey::try_new([1; 32]).unwrap(),
+            )
+        };
+
+        // Both pending transactions and mempool are full, so the submission waits for a free
+        // slot until the timeout
+        json_handler
+            .ingest_handle
+            .submit(transfer(1))
+            .await
+            .ok()
+            .unwrap();
+        sequencer_core.lock().await.take_transactions_from_mempool();
+        json_handler
+            .ingest_handle
+            .submit(transfer(2))
+            .await
+            .ok()
+            .unwrap();
+        let wal_len = std::fs::metadata(&wal_path).unwrap().len();
+
+        let tx = transfer(3);
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "send_tx",
+            "params": {
+                "transaction": general_purpose::STANDARD.encode(borsh::to_vec(&tx).unwrap())
+            },
+            "id": 1
+        });
+        let response = call_rpc_handler_with_json(json_handler, request).await;
+        assert_eq!(response["error"]["code"], SERVER_BUSY_ERROR_CODE);
+
+        // Transaction is neither logged nor in mempool
+        assert_eq!(std::fs::metadata(&wal_path).unwrap().len(), wal_len);
+        assert_eq!(
+            sequencer_core.lock().await.transaction_status(&tx.hash()),
+            common::rpc_types::TransactionStatus::Unknown
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_submission_burst_is_bounded_by_ingest_queue() {
+        const SUBMISSIONS: u64 = 10_000;
+        const INGEST_QUEUE_SIZE: usize = 100;
+        let config = SequencerConfig {
+            ingest_queue_size: INGEST_QUEUE_SIZE,
+            prune: None,
+            ..sequencer_config_for_tests()
+        };
+        let mempool_max_size = config.mempool_max_size;
+        let (json_handler, initial_accounts, _) = components_for_tests_with_config(config).await;
+        let sequencer_core = json_handler.sequencer_state.clone();
+        let sender: nssa::AccountId = initial_accounts[0].account_id.parse().unwrap();
+        let signing_key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(json_handler))
+                .route("/", actix_web::web::post().to(rpc_handler)),
+        )
+        .await;
+        let submissions = (1..=SUBMISSIONS).map(|nonce| {
+            let tx = common::test_utils::create_transaction_native_token_transfer(
+                *sender.value(),
+                nonce,
+                [2; 32],
+                1,
+                signing_key.clone(),
+            );
+            let request = actix_web::test::TestRequest::post()
+                .uri("/")
+                .set_json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "send_tx",
+                    "params": {
+                        "transaction": general_purpose::STANDARD.encode(borsh::to_vec(&tx).unwrap())
+                    },
+                    "id": 1
+                }))
+                .to_request();
+            let app = &app;
+            async move {
+                let resp = actix_web::test::call_service(app, request).await;
+                serde_json::from_slice::<Value>(&actix_web::test::read_body(resp).await).unwrap()
+            }
+        });
+        let responses = futures::future::join_all(submissions).await;
+
+        let accepted = responses
+            .iter()
+            .filter(|response| response["result"]["status"] == TRANSACTION_SUBMITTED)
+            .count();
+        let congested = responses
+            .iter()
+            .filter(|response| response["error"]["code"] == MEMPOOL_CONGESTED_ERROR_CODE)
+            .count();
+        assert!(accepted > 0);
+        assert!(congested > 0);
+        assert_eq!(accepted + congested, SUBMISSIONS as usize);
+        // Nothing beyond the queue and pending transactions was held in memory
+        assert!(accepted <= INGEST_QUEUE_SIZE + mempool_max_size);
+        assert_eq!(
+            sequencer_core.lock().await.pending_priority_fees().pending,
+            accepted as u64
+        );
+
+        let congested_response = responses
+            .iter()
+            .find(|response| response["error"]["code"] == MEMPOOL_CONGESTED_ERROR_CODE)
+            .unwrap();
+        assert_eq!(
+            congested_response["error"]["data"],
+            serde_json::json!({ "retry_after_millis": 1000 })
+        );
+    }
 }
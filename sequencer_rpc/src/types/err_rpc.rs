@@ -1,6 +1,10 @@
-use common::rpc_primitives::errors::{RpcError, RpcParseError};
+use common::{
+    rpc_primitives::errors::{RpcError, RpcParseError},
+    rpc_types::BalanceShortfall,
+};
 use log::debug;
-use sequencer_core::TransactionMalformationError;
+use sequencer_core::{SystemLaneError, TransactionMalformationError};
+use storage::error::DbError;
 
 pub struct RpcErr(pub RpcError);
 
@@ -36,7 +40,18 @@ impl RpcErrKind for serde_json::Error {
 
 impl RpcErrKind for RpcErrInternal {
     fn into_rpc_err(self) -> RpcError {
-        RpcError::new_internal_error(None, &format!("{self:#?}"))
+        match self.downcast_ref() {
+            Some(DbError::BlockPruned {
+                block_id,
+                first_unpruned_block_id,
+            }) => RpcError::block_pruned(*block_id, *first_unpruned_block_id),
+            Some(DbError::BlockNotYetProduced {
+                block_id,
+                last_block_id,
+            }) => RpcError::block_not_yet_produced(*block_id, *last_block_id),
+            Some(DbError::BlockMissing { block_id }) => RpcError::block_missing(*block_id),
+            _ => RpcError::new_internal_error(None, &format!("{self:#?}")),
+        }
     }
 }
 
@@ -49,6 +64,21 @@ impl RpcErrKind for TransactionMalformationError {
     }
 }
 
+impl RpcErrKind for SystemLaneError {
+    fn into_rpc_err(self) -> RpcError {
+        RpcError::new_internal_error(
+            Some(serde_json::to_value(self).unwrap()),
+            "system transaction not accepted",
+        )
+    }
+}
+
+impl RpcErrKind for BalanceShortfall {
+    fn into_rpc_err(self) -> RpcError {
+        RpcError::insufficient_unreserved_balance(self)
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 pub fn from_rpc_err_into_anyhow_err(rpc_err: RpcError) -> anyhow::Error {
     debug!("Rpc error cast to anyhow error : err {rpc_err:?}");
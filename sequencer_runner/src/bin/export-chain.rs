@@ -0,0 +1,6 @@
+use anyhow::Result;
+use sequencer_runner::chain_archive::export_chain_runner;
+
+fn main() -> Result<()> {
+    export_chain_runner()
+}
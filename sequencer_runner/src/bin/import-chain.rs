@@ -0,0 +1,6 @@
+use anyhow::Result;
+use sequencer_runner::chain_archive::import_chain_runner;
+
+fn main() -> Result<()> {
+    import_chain_runner()
+}
@@ -0,0 +1,6 @@
+use anyhow::Result;
+use sequencer_runner::replay::replay_runner;
+
+fn main() -> Result<()> {
+    replay_runner()
+}
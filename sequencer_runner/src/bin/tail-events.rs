@@ -0,0 +1,6 @@
+use anyhow::Result;
+use sequencer_runner::tail_events::tail_events_runner;
+
+fn main() -> Result<()> {
+    tail_events_runner()
+}
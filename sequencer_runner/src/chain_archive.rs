@@ -0,0 +1,119 @@
+//! `export-chain` and `import-chain` tools, which move the whole chain of a stopped sequencer to
+//! another machine, see [`sequencer_core::chain_archive`].
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use anyhow::{Context as _, Result};
+use clap::Parser;
+use sequencer_core::{
+    block_store::SequencerBlockStore,
+    chain_archive::{export_chain, import_chain, read_chain},
+    replay::Replay,
+};
+
+use crate::config;
+
+#[derive(Parser, Debug)]
+#[clap(version, about)]
+struct ExportArgs {
+    /// Path to configs of the sequencer
+    home_dir: PathBuf,
+    /// Path to the block store, defaults to `rocksdb` in sequencer home
+    ///
+    /// Must not be used by a running sequencer.
+    #[arg(long)]
+    db: Option<PathBuf>,
+    /// Archive to write
+    #[arg(long)]
+    out: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+#[clap(version, about)]
+struct ImportArgs {
+    /// Path to configs of the node to import into, genesis of the archive must match them
+    home_dir: PathBuf,
+    /// Archive to read
+    #[arg(long = "in")]
+    input: PathBuf,
+    /// New block store to import blocks into, the archive is only validated if it's not set
+    ///
+    /// Sequencer starts from the imported chain, if it's `rocksdb` in its home.
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+    /// Import proof records of blocks too
+    #[arg(long)]
+    with_proofs: bool,
+}
+
+pub fn export_chain_runner() -> Result<()> {
+    env_logger::init();
+
+    let ExportArgs { home_dir, db, out } = ExportArgs::parse();
+
+    let app_config = config::from_file(home_dir.join("sequencer_config.json"))?;
+    let db = db.unwrap_or_else(|| app_config.home.join("rocksdb"));
+    anyhow::ensure!(db.exists(), "Block store {} doesn't exist", db.display());
+    let signing_key = nssa::PrivateKey::try_new(app_config.signing_key)?;
+    let block_store = SequencerBlockStore::open_db_restart(&db, signing_key)
+        .with_context(|| format!("Failed to open block store {}", db.display()))?;
+
+    let file = File::create(&out).with_context(|| format!("Failed to create {}", out.display()))?;
+    let last_block_id = export_chain(&block_store, BufWriter::new(file))?;
+    println!(
+        "Exported blocks {} to {last_block_id} to {}",
+        block_store.genesis_id(),
+        out.display()
+    );
+
+    Ok(())
+}
+
+pub fn import_chain_runner() -> Result<()> {
+    env_logger::init();
+
+    let ImportArgs {
+        home_dir,
+        input,
+        data_dir,
+        with_proofs,
+    } = ImportArgs::parse();
+
+    let app_config = config::from_file(home_dir.join("sequencer_config.json"))?;
+    let file = File::open(&input).with_context(|| format!("Failed to open {}", input.display()))?;
+    let reader = BufReader::new(file);
+
+    let Some(data_dir) = data_dir else {
+        let summary = read_chain(reader, |_| Ok(()))?;
+        println!(
+            "Archive is valid, blocks {} to {}, last block hash {}",
+            summary.genesis_id,
+            summary.last_block_id,
+            hex::encode(summary.last_block_hash)
+        );
+        return Ok(());
+    };
+
+    let summary = import_chain(&app_config, reader, &data_dir, with_proofs)?;
+    println!(
+        "Imported blocks {} to {} into {}",
+        summary.genesis_id,
+        summary.last_block_id,
+        data_dir.display()
+    );
+
+    // Transactions of the imported blocks are executed only by replay
+    let mut replay = Replay::open(&app_config, &data_dir)?;
+    while replay.apply_next_block()?.is_some() {}
+    println!(
+        "Tip state root at block {}: {}",
+        replay.last_block_id(),
+        hex::encode(replay.state().state_root())
+    );
+
+    Ok(())
+}
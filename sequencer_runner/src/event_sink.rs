@@ -0,0 +1,286 @@
+//! Delivery of events from the event log to the configured sink.
+//!
+//! Events are delivered in batches of newline-delimited JSON and acknowledged once the sink
+//! accepted them. Failed batches are retried with growing delay, so events wait in the event log
+//! while the sink is unreachable.
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context as _, Result};
+use log::{info, warn};
+use sequencer_core::{
+    config::{EventSinkConfig, EventSinkTarget},
+    event_log::{Event, EventLog},
+};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt as _, net::UnixStream};
+
+/// Maximum number of events in one delivery
+const MAX_BATCH_SIZE: usize = 100;
+/// Interval of checking the event log for new events
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const RETRY_INITIAL_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+enum EventSink {
+    File(PathBuf),
+    UnixSocket {
+        path: PathBuf,
+        /// Connection is kept between deliveries and reestablished after failures
+        stream: Option<UnixStream>,
+    },
+    Webhook {
+        url: String,
+        client: reqwest::Client,
+    },
+}
+
+impl EventSink {
+    fn new(target: EventSinkTarget) -> Result<Self> {
+        Ok(match target {
+            EventSinkTarget::File(path) => Self::File(path),
+            EventSinkTarget::UnixSocket(path) => Self::UnixSocket { path, stream: None },
+            EventSinkTarget::Webhook(url) => Self::Webhook {
+                url,
+                client: reqwest::Client::builder()
+                    .timeout(WEBHOOK_TIMEOUT)
+                    .build()?,
+            },
+        })
+    }
+
+    /// Returns once the sink accepted `events`
+    ///
+    /// Unix socket accepts events once they are written, so events in flight are lost if the
+    /// consumer fails before reading them.
+    async fn deliver(&mut self, events: &[Event]) -> Result<()> {
+        let mut body = vec![];
+        for event in events {
+            serde_json::to_writer(&mut body, event)?;
+            body.push(b'\n');
+        }
+
+        match self {
+            Self::File(path) => {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path.as_path())
+                    .await
+                    .with_context(|| format!("Failed to open {}", path.display()))?;
+                file.write_all(&body).await?;
+                file.sync_data().await?;
+            }
+            Self::UnixSocket { path, stream } => {
+                if stream.is_none() {
+                    *stream = Some(
+                        UnixStream::connect(path.as_path())
+                            .await
+                            .with_context(|| format!("Failed to connect to {}", path.display()))?,
+                    );
+                }
+                let result = stream.as_mut().unwrap().write_all(&body).await;
+                if result.is_err() {
+                    *stream = None;
+                }
+                result?;
+            }
+            Self::Webhook { url, client } => {
+                client
+                    .post(url.as_str())
+                    .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+                    .body(body)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Delivers events from `event_log` to the sink of `config` forever
+pub async fn deliver_events(
+    event_log: Arc<Mutex<EventLog>>,
+    config: EventSinkConfig,
+) -> Result<()> {
+    let mut sink = EventSink::new(config.target)?;
+    let max_events_per_second = config.max_events_per_second.max(1);
+    let batch_size = (max_events_per_second as usize).min(MAX_BATCH_SIZE);
+    let mut retry_delay = RETRY_INITIAL_DELAY;
+
+    loop {
+        let events = event_log.lock().unwrap().pending(batch_size);
+        let Some(last_event) = events.last() else {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        };
+        let last_seq = last_event.seq;
+
+        let started_at = Instant::now();
+        if let Err(err) = sink.deliver(&events).await {
+            warn!("Failed to deliver events to sink, retrying in {retry_delay:?}: {err:#}");
+            tokio::time::sleep(retry_delay).await;
+            retry_delay = (retry_delay * 2).min(RETRY_MAX_DELAY);
+            continue;
+        }
+        if retry_delay != RETRY_INITIAL_DELAY {
+            info!("Event sink is reachable again");
+            retry_delay = RETRY_INITIAL_DELAY;
+        }
+        // Unacknowledged events are delivered again after restart
+        if let Err(err) = event_log.lock().unwrap().acknowledge(last_seq) {
+            warn!("Failed to acknowledge events up to {last_seq}: {err:#}");
+        }
+
+        let min_duration =
+            Duration::from_secs_f64(events.len() as f64 / max_events_per_second as f64);
+        tokio::time::sleep(min_duration.saturating_sub(started_at.elapsed())).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use actix_web::{App, HttpResponse, HttpServer, dev::ServerHandle, web};
+    use common::test_utils::{produce_dummy_block, produce_dummy_empty_transaction};
+    use nssa::instruction_decoder::DecoderRegistry;
+    use sequencer_core::event_log::EventBody;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Webhook {
+        /// Number of the next requests, which fail as if the indexer is down
+        failures_left: AtomicUsize,
+        received: Mutex<Vec<Event>>,
+    }
+
+    impl Webhook {
+        fn received_seqs(&self) -> Vec<u64> {
+            self.received
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|event| event.seq)
+                .collect()
+        }
+    }
+
+    async fn receive_events(webhook: web::Data<Webhook>, body: web::Bytes) -> HttpResponse {
+        if webhook
+            .failures_left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+        {
+            return HttpResponse::ServiceUnavailable().finish();
+        }
+
+        let mut received = webhook.received.lock().unwrap();
+        for line in body
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+        {
+            received.push(serde_json::from_slice(line).unwrap());
+        }
+        HttpResponse::Ok().finish()
+    }
+
+    fn start_webhook(failures: usize) -> (web::Data<Webhook>, EventSinkConfig, ServerHandle) {
+        let webhook = web::Data::new(Webhook {
+            failures_left: AtomicUsize::new(failures),
+            ..Default::default()
+        });
+        let app_webhook = webhook.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_webhook.clone())
+                .route("/events", web::post().to(receive_events))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let server_handle = server.handle();
+        tokio::spawn(server);
+
+        let config = EventSinkConfig {
+            target: EventSinkTarget::Webhook(format!("http://{addr}/events")),
+            max_events_per_second: 1000,
+        };
+        (webhook, config, server_handle)
+    }
+
+    fn append_block(event_log: &Mutex<EventLog>, block_id: u64) {
+        let block = produce_dummy_block(block_id, None, vec![produce_dummy_empty_transaction()]);
+        event_log
+            .lock()
+            .unwrap()
+            .append(
+                EventBody::for_block(&block, &[], &DecoderRegistry::default(), |_| None).unwrap(),
+            )
+            .unwrap();
+    }
+
+    async fn wait_until_delivered(event_log: &Mutex<EventLog>) {
+        for _ in 0..100 {
+            if event_log.lock().unwrap().pending(1).is_empty() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("Events were not delivered in time");
+    }
+
+    #[actix_web::test]
+    async fn test_events_are_delivered_after_webhook_outage() {
+        let dir = tempfile::tempdir().unwrap();
+        let event_log = Arc::new(Mutex::new(EventLog::open(dir.path()).unwrap()));
+        for block_id in 1..=3 {
+            append_block(&event_log, block_id);
+        }
+        let (webhook, config, server_handle) = start_webhook(2);
+
+        let delivery = tokio::spawn(deliver_events(Arc::clone(&event_log), config));
+        wait_until_delivered(&event_log).await;
+
+        assert_eq!(webhook.failures_left.load(Ordering::SeqCst), 0);
+        assert_eq!(webhook.received_seqs(), (1..=9).collect::<Vec<_>>());
+
+        delivery.abort();
+        server_handle.stop(true).await;
+    }
+
+    #[actix_web::test]
+    async fn test_delivery_resumes_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let (webhook, config, server_handle) = start_webhook(0);
+
+        {
+            let event_log = Arc::new(Mutex::new(EventLog::open(dir.path()).unwrap()));
+            append_block(&event_log, 1);
+            let delivery = tokio::spawn(deliver_events(Arc::clone(&event_log), config.clone()));
+            wait_until_delivered(&event_log).await;
+            delivery.abort();
+            let _ = delivery.await;
+            // Next block is committed while delivery is stopped
+            append_block(&event_log, 2);
+        }
+
+        let event_log = Arc::new(Mutex::new(EventLog::open(dir.path()).unwrap()));
+        append_block(&event_log, 3);
+        let delivery = tokio::spawn(deliver_events(Arc::clone(&event_log), config));
+        wait_until_delivered(&event_log).await;
+
+        assert_eq!(webhook.received_seqs(), (1..=9).collect::<Vec<_>>());
+
+        delivery.abort();
+        server_handle.stop(true).await;
+    }
+}
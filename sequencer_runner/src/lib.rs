@@ -3,39 +3,91 @@ use std::{path::PathBuf, sync::Arc};
 use actix_web::dev::ServerHandle;
 use anyhow::Result;
 use clap::Parser;
-use common::rpc_primitives::RpcConfig;
-use log::info;
-use sequencer_core::{SequencerCore, config::SequencerConfig};
+use common::{rpc_primitives::RpcConfig, transaction::EncodedTransaction};
+use log::{error, info, warn};
+use mempool::MemPoolHandle;
+use nssa::instruction_decoder::DecoderRegistry;
+use sequencer_core::{SequencerCore, block_production::StallWatch, config::SequencerConfig};
 use sequencer_rpc::new_http_server;
 use tokio::{sync::Mutex, task::JoinHandle};
 
+pub mod chain_archive;
 pub mod config;
+pub mod event_sink;
+pub mod replay;
+pub mod tail_events;
 
 pub const RUST_LOG: &str = "RUST_LOG";
 
+/// Interval between checks of indexes of stored transactions, see [`sample_indexes`]
+const INDEX_SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Number of random stored blocks, which index entries are checked at once
+const INDEX_SAMPLE_BLOCKS: usize = 4;
+
 #[derive(Parser, Debug)]
 #[clap(version)]
 struct Args {
     /// Path to configs
     home_dir: PathBuf,
+    /// Continue the stored chain with genesis and chain parameters of the config, which don't
+    /// match the ones stored blocks are built with
+    #[arg(long)]
+    force_params_change: bool,
+    /// Verify indexes of stored transactions against stored blocks and rebuild them before
+    /// serving, discrepancies are logged
+    #[arg(long)]
+    repair: bool,
 }
 
 pub async fn startup_sequencer(
     app_config: SequencerConfig,
+) -> Result<(ServerHandle, JoinHandle<Result<()>>)> {
+    startup_sequencer_with_decoders(app_config, DecoderRegistry::default()).await
+}
+
+/// Starts sequencer, which decodes instructions in expanded blocks and events with
+/// `instruction_decoders`, so applications embedding it show calls of their own programs
+pub async fn startup_sequencer_with_decoders(
+    app_config: SequencerConfig,
+    instruction_decoders: DecoderRegistry,
 ) -> Result<(ServerHandle, JoinHandle<Result<()>>)> {
     let block_timeout = app_config.block_create_timeout_millis;
     let port = app_config.port;
+    let ingest_queue_size = app_config.ingest_queue_size;
+    let event_sink_config = app_config.event_sink.clone();
+    let repair_indexes = app_config.repair_indexes;
 
-    let (sequencer_core, mempool_handle) = SequencerCore::start_from_config(app_config);
+    let (mut sequencer_core, mempool_handle) =
+        SequencerCore::start_with_decoders(app_config, instruction_decoders);
 
     info!("Sequencer core set up");
 
+    if repair_indexes {
+        sequencer_core.rebuild_indexes()?;
+    }
+
+    if let (Some(event_log), Some(event_sink_config)) =
+        (sequencer_core.event_log(), event_sink_config)
+    {
+        tokio::spawn(async move {
+            if let Err(err) = event_sink::deliver_events(event_log, event_sink_config).await {
+                error!("Event delivery stopped: {err:#}");
+            }
+        });
+        info!("Event delivery started");
+    }
+
+    let stall_watch = Arc::clone(sequencer_core.block_production().stall_watch());
     let seq_core_wrapped = Arc::new(Mutex::new(sequencer_core));
 
     let http_server = new_http_server(
         RpcConfig::with_port(port),
         Arc::clone(&seq_core_wrapped),
-        mempool_handle,
+        mempool_handle.clone(),
+        ingest_queue_size,
+        // Congestion clears up as the next block takes transactions from mempool
+        std::time::Duration::from_millis(block_timeout),
     )?;
     info!("HTTP server started");
     let http_server_handle = http_server.handle();
@@ -43,19 +95,29 @@ pub async fn startup_sequencer(
 
     info!("Starting main sequencer loop");
 
+    tokio::spawn(watch_block_production(
+        stall_watch,
+        mempool_handle,
+        block_timeout,
+    ));
+    tokio::spawn(sample_indexes(Arc::clone(&seq_core_wrapped)));
+
     let main_loop_handle = tokio::spawn(async move {
         loop {
             tokio::time::sleep(std::time::Duration::from_millis(block_timeout)).await;
 
             info!("Collecting transactions from mempool, block creation");
 
-            let id = {
+            // Transient failures are retried on the next tick, the loop stops on fatal ones
+            let produced = {
                 let mut state = seq_core_wrapped.lock().await;
 
-                state.produce_new_block_with_mempool_transactions()?
+                state.produce_block_on_tick()?
             };
 
-            info!("Block with id {id} created");
+            if let Some(id) = produced {
+                info!("Block with id {id} created");
+            }
 
             info!("Waiting for new transactions");
         }
@@ -64,13 +126,55 @@ pub async fn startup_sequencer(
     Ok((http_server_handle, main_loop_handle))
 }
 
+/// Checks once per block interval that blocks are produced while transactions are pending, see
+/// [`StallWatch::watch`]
+///
+/// The sequencer isn't locked, so the watchdog keeps alerting while block production hangs.
+async fn watch_block_production(
+    stall_watch: Arc<StallWatch>,
+    mempool_handle: MemPoolHandle<EncodedTransaction>,
+    block_timeout: u64,
+) {
+    let block_interval = std::time::Duration::from_millis(block_timeout);
+    loop {
+        tokio::time::sleep(block_interval).await;
+        stall_watch.watch(
+            std::time::Instant::now(),
+            block_interval,
+            mempool_handle.len(),
+        );
+    }
+}
+
+/// Checks index entries of a few random stored blocks once per [`INDEX_SAMPLE_INTERVAL`], see
+/// [`SequencerCore::sample_indexes`]. Mismatched entries are only logged.
+async fn sample_indexes(seq_core: Arc<Mutex<SequencerCore>>) {
+    loop {
+        tokio::time::sleep(INDEX_SAMPLE_INTERVAL).await;
+        match seq_core.lock().await.sample_indexes(INDEX_SAMPLE_BLOCKS) {
+            Ok(0) => {}
+            Ok(mismatched) => warn!(
+                "{mismatched} index entries don't match stored blocks, they are rebuilt by \
+                 `rebuild_indexes` of the admin RPC or on start with `--repair`"
+            ),
+            Err(err) => warn!("Failed to check indexes: {err:#}"),
+        }
+    }
+}
+
 pub async fn main_runner() -> Result<()> {
     env_logger::init();
 
     let args = Args::parse();
-    let Args { home_dir } = args;
+    let Args {
+        home_dir,
+        force_params_change,
+        repair,
+    } = args;
 
-    let app_config = config::from_file(home_dir.join("sequencer_config.json"))?;
+    let mut app_config = config::from_file(home_dir.join("sequencer_config.json"))?;
+    app_config.force_params_change = force_params_change;
+    app_config.repair_indexes = repair;
 
     if let Some(ref rust_log) = app_config.override_rust_log {
         info!("RUST_LOG env var set to {rust_log:?}");
@@ -80,7 +184,7 @@ pub async fn main_runner() -> Result<()> {
         }
     }
 
-    // ToDo: Add restart on failures
+    // Block production loop exits on fatal failures only, which need an operator
     let (_, main_loop_handle) = startup_sequencer(app_config).await?;
 
     main_loop_handle.await??;
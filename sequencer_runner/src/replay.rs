@@ -0,0 +1,111 @@
+//! `replay` tool, which rebuilds state from a stored chain to debug state divergence.
+
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+use clap::Parser;
+use sequencer_core::replay::{Replay, StateSnapshot};
+
+use crate::config;
+
+#[derive(Parser, Debug)]
+#[clap(version, about)]
+struct Args {
+    /// Path to configs, genesis state is built from them
+    home_dir: PathBuf,
+    /// Path to the block store, defaults to `rocksdb` in sequencer home
+    ///
+    /// Must not be used by a running sequencer.
+    #[arg(long)]
+    db: Option<PathBuf>,
+    /// First block to print state root of, earlier blocks are applied silently
+    #[arg(long)]
+    from: Option<u64>,
+    /// Last block to apply, defaults to the last stored one
+    #[arg(long)]
+    to: Option<u64>,
+    /// Write public accounts of the final state to the file
+    #[arg(long)]
+    export: Option<PathBuf>,
+    /// Compare public accounts of the final state with a snapshot, exported by another node
+    #[arg(long)]
+    diff: Option<PathBuf>,
+}
+
+pub fn replay_runner() -> Result<()> {
+    env_logger::init();
+
+    let Args {
+        home_dir,
+        db,
+        from,
+        to,
+        export,
+        diff,
+    } = Args::parse();
+
+    let app_config = config::from_file(home_dir.join("sequencer_config.json"))?;
+    let db = db.unwrap_or_else(|| app_config.home.join("rocksdb"));
+
+    let mut replay = Replay::open(&app_config, &db)?;
+    let from = from.unwrap_or(replay.last_block_id() + 1);
+    while to.is_none_or(|to| replay.last_block_id() < to) {
+        let Some(block_id) = replay.apply_next_block()? else {
+            break;
+        };
+        if block_id >= from {
+            println!("{block_id} {}", hex::encode(replay.state().state_root()));
+        }
+    }
+
+    if let Some(to) = to
+        && replay.last_block_id() < to
+    {
+        anyhow::bail!(
+            "Chain ends at block {}, before requested block {to}",
+            replay.last_block_id()
+        );
+    }
+
+    let snapshot = replay.snapshot();
+
+    if let Some(export) = export {
+        let file = File::create(&export)
+            .with_context(|| format!("Failed to create {}", export.display()))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &snapshot)?;
+        println!(
+            "Exported state at block {} to {}",
+            snapshot.block_id,
+            export.display()
+        );
+    }
+
+    if let Some(diff) = diff {
+        let file =
+            File::open(&diff).with_context(|| format!("Failed to open {}", diff.display()))?;
+        let other: StateSnapshot = serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("Failed to parse snapshot {}", diff.display()))?;
+        if other.block_id != snapshot.block_id {
+            println!(
+                "Warning: comparing state at block {} with snapshot at block {}",
+                snapshot.block_id, other.block_id
+            );
+        }
+
+        let differences = snapshot.diff(&other);
+        for difference in &differences {
+            println!("{}", difference.account_id);
+            println!("  replayed: {:?}", difference.ours);
+            println!("  snapshot: {:?}", difference.theirs);
+        }
+
+        anyhow::ensure!(
+            differences.is_empty(),
+            "{} accounts differ from the snapshot",
+            differences.len()
+        );
+        println!("State matches the snapshot");
+    }
+
+    Ok(())
+}
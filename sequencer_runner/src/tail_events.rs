@@ -0,0 +1,67 @@
+//! `tail-events` tool, which receives events of the sequencer with Unix socket event sink and
+//! prints them, as an example of a downstream consumer.
+
+use std::{
+    io::{BufRead as _, BufReader},
+    os::unix::net::UnixListener,
+    path::PathBuf,
+};
+
+use anyhow::{Context as _, Result};
+use clap::Parser;
+use sequencer_core::event_log::Event;
+
+#[derive(Parser, Debug)]
+#[clap(version, about)]
+struct Args {
+    /// Path to the Unix socket, configured as the event sink of the sequencer
+    socket: PathBuf,
+}
+
+pub fn tail_events_runner() -> Result<()> {
+    env_logger::init();
+
+    let Args { socket } = Args::parse();
+
+    // Socket left after the previous run
+    if socket.exists() {
+        std::fs::remove_file(&socket)
+            .with_context(|| format!("Failed to remove {}", socket.display()))?;
+    }
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("Failed to bind {}", socket.display()))?;
+    eprintln!("Waiting for events at {}", socket.display());
+
+    let mut last_seq = None;
+    // Sequencer reconnects after failures, one connection is served at a time
+    for stream in listener.incoming() {
+        let stream = stream?;
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    eprintln!("Connection lost: {err}");
+                    break;
+                }
+            };
+            let event: Event = serde_json::from_str(&line)
+                .with_context(|| format!("Failed to decode event {line:?}"))?;
+
+            match last_seq {
+                // Delivery is at-least-once, so events may come again after a restart
+                Some(last_seq) if event.seq <= last_seq => {
+                    eprintln!("Skipping duplicate event {}", event.seq);
+                    continue;
+                }
+                Some(last_seq) if event.seq > last_seq + 1 => {
+                    eprintln!("Missed events {} to {}", last_seq + 1, event.seq - 1);
+                }
+                _ => {}
+            }
+            last_seq = Some(event.seq);
+            println!("{line}");
+        }
+    }
+
+    Ok(())
+}
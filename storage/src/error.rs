@@ -12,6 +12,16 @@ pub enum DbError {
     },
     #[error("Logic Error")]
     DbInteractionError { additional_info: String },
+    #[error("Block {block_id} is pruned, first available block is {first_unpruned_block_id}")]
+    BlockPruned {
+        block_id: u64,
+        first_unpruned_block_id: u64,
+    },
+    #[error("Block {block_id} is not produced yet, last block is {last_block_id}")]
+    BlockNotYetProduced { block_id: u64, last_block_id: u64 },
+    /// Block should be stored, but isn't, which means storage lost it
+    #[error("Block {block_id} is missing from storage")]
+    BlockMissing { block_id: u64 },
 }
 
 impl DbError {
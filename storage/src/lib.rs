@@ -1,9 +1,10 @@
 use std::{path::Path, sync::Arc};
 
-use common::block::{Block, HashableBlockData};
+use borsh::{BorshDeserialize, BorshSerialize};
+use common::block::{Block, BlockHash, HashableBlockData};
 use error::DbError;
 use rocksdb::{
-    BoundColumnFamily, ColumnFamilyDescriptor, DBWithThreadMode, MultiThreaded, Options,
+    BoundColumnFamily, ColumnFamilyDescriptor, DBWithThreadMode, MultiThreaded, Options, WriteBatch,
 };
 
 pub mod error;
@@ -26,6 +27,12 @@ pub const DB_META_FIRST_BLOCK_IN_DB_KEY: &str = "first_block_in_db";
 pub const DB_META_LAST_BLOCK_IN_DB_KEY: &str = "last_block_in_db";
 /// Key base for storing metainformation which describe if first block has been set
 pub const DB_META_FIRST_BLOCK_SET_KEY: &str = "first_block_set";
+/// Key base for storing digest of the state after applying the last block in db
+pub const DB_META_TIP_STATE_DIGEST_KEY: &str = "tip_state_digest";
+/// Key base for storing metainformation about id of first block, which body is kept in db
+pub const DB_META_FIRST_UNPRUNED_BLOCK_KEY: &str = "first_unpruned_block";
+/// Key base for storing record of chain parameters, which blocks in db are built with
+pub const DB_META_CHAIN_PARAMS_KEY: &str = "chain_params";
 
 /// Key base for storing snapshot which describe block id
 pub const DB_SNAPSHOT_BLOCK_ID_KEY: &str = "block_id";
@@ -36,9 +43,43 @@ pub const CF_BLOCK_NAME: &str = "cf_block";
 pub const CF_META_NAME: &str = "cf_meta";
 /// Name of snapshot column family
 pub const CF_SNAPSHOT_NAME: &str = "cf_snapshot";
+/// Name of block proof column family
+pub const CF_BLOCK_PROOF_NAME: &str = "cf_block_proof";
+/// Name of block header column family
+pub const CF_BLOCK_HEADER_NAME: &str = "cf_block_header";
 
 pub type DbResult<T> = Result<T, DbError>;
 
+/// Writes staged in memory, which are applied to db atomically by [`RocksDBIO::commit`]
+///
+/// Dropping a batch without committing it discards all staged writes.
+#[derive(Default)]
+pub struct DbWriteBatch {
+    batch: WriteBatch,
+    /// Id of the last staged block, if it's ahead of the last block in db
+    last_block_id: Option<u64>,
+}
+
+/// Header of a stored block, which is kept after its body is pruned
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BlockHeaderRecord {
+    pub block_id: u64,
+    pub prev_block_hash: BlockHash,
+    pub hash: BlockHash,
+    pub timestamp: u64,
+}
+
+impl From<&HashableBlockData> for BlockHeaderRecord {
+    fn from(data: &HashableBlockData) -> Self {
+        Self {
+            block_id: data.block_id,
+            prev_block_hash: data.prev_block_hash,
+            hash: data.hash(),
+            timestamp: data.timestamp,
+        }
+    }
+}
+
 pub struct RocksDBIO {
     pub db: DBWithThreadMode<MultiThreaded>,
 }
@@ -51,6 +92,8 @@ impl RocksDBIO {
         let cfb = ColumnFamilyDescriptor::new(CF_BLOCK_NAME, cf_opts.clone());
         let cfmeta = ColumnFamilyDescriptor::new(CF_META_NAME, cf_opts.clone());
         let cfsnapshot = ColumnFamilyDescriptor::new(CF_SNAPSHOT_NAME, cf_opts.clone());
+        let cfproof = ColumnFamilyDescriptor::new(CF_BLOCK_PROOF_NAME, cf_opts.clone());
+        let cfheader = ColumnFamilyDescriptor::new(CF_BLOCK_HEADER_NAME, cf_opts.clone());
 
         let mut db_opts = Options::default();
         db_opts.create_missing_column_families(true);
@@ -58,7 +101,7 @@ impl RocksDBIO {
         let db = DBWithThreadMode::<MultiThreaded>::open_cf_descriptors(
             &db_opts,
             path,
-            vec![cfb, cfmeta, cfsnapshot],
+            vec![cfb, cfmeta, cfsnapshot, cfproof, cfheader],
         );
 
         let dbio = Self {
@@ -90,6 +133,8 @@ impl RocksDBIO {
         let _cfb = ColumnFamilyDescriptor::new(CF_BLOCK_NAME, cf_opts.clone());
         let _cfmeta = ColumnFamilyDescriptor::new(CF_META_NAME, cf_opts.clone());
         let _cfsnapshot = ColumnFamilyDescriptor::new(CF_SNAPSHOT_NAME, cf_opts.clone());
+        let _cfproof = ColumnFamilyDescriptor::new(CF_BLOCK_PROOF_NAME, cf_opts.clone());
+        let _cfheader = ColumnFamilyDescriptor::new(CF_BLOCK_HEADER_NAME, cf_opts.clone());
 
         let mut db_opts = Options::default();
         db_opts.create_missing_column_families(true);
@@ -110,6 +155,14 @@ impl RocksDBIO {
         self.db.cf_handle(CF_SNAPSHOT_NAME).unwrap()
     }
 
+    pub fn block_proof_column(&self) -> Arc<BoundColumnFamily<'_>> {
+        self.db.cf_handle(CF_BLOCK_PROOF_NAME).unwrap()
+    }
+
+    pub fn block_header_column(&self) -> Arc<BoundColumnFamily<'_>> {
+        self.db.cf_handle(CF_BLOCK_HEADER_NAME).unwrap()
+    }
+
     pub fn get_meta_first_block_in_db(&self) -> DbResult<u64> {
         let cf_meta = self.meta_column();
         let res = self
@@ -250,37 +303,348 @@ impl RocksDBIO {
     }
 
     pub fn put_block(&self, block: Block, first: bool) -> DbResult<()> {
+        let mut batch = DbWriteBatch::default();
+        self.stage_block(&mut batch, block, first)?;
+        self.commit(batch)
+    }
+
+    /// Stage `block` in `batch`, moving last block in db to it, unless it's the `first` one
+    pub fn stage_block(&self, batch: &mut DbWriteBatch, block: Block, first: bool) -> DbResult<()> {
         let cf_block = self.block_column();
+        let block_id = block.header.block_id;
 
         if !first {
-            let last_curr_block = self.get_meta_last_block_in_db()?;
+            let last_curr_block = match batch.last_block_id {
+                Some(block_id) => block_id,
+                None => self.get_meta_last_block_in_db()?,
+            };
+
+            if block_id > last_curr_block {
+                let cf_meta = self.meta_column();
+                batch.batch.put_cf(
+                    &cf_meta,
+                    borsh::to_vec(&DB_META_LAST_BLOCK_IN_DB_KEY).map_err(|err| {
+                        DbError::borsh_cast_message(
+                            err,
+                            Some("Failed to serialize DB_META_LAST_BLOCK_IN_DB_KEY".to_string()),
+                        )
+                    })?,
+                    borsh::to_vec(&block_id).map_err(|err| {
+                        DbError::borsh_cast_message(
+                            err,
+                            Some("Failed to serialize last block id".to_string()),
+                        )
+                    })?,
+                );
+                batch.last_block_id = Some(block_id);
+            }
+        }
+
+        let header = BlockHeaderRecord {
+            block_id,
+            prev_block_hash: block.header.prev_block_hash,
+            hash: block.header.hash,
+            timestamp: block.header.timestamp,
+        };
+        self.stage_block_header(batch, &header)?;
+
+        batch.batch.put_cf(
+            &cf_block,
+            borsh::to_vec(&block_id).map_err(|err| {
+                DbError::borsh_cast_message(err, Some("Failed to serialize block id".to_string()))
+            })?,
+            borsh::to_vec(&HashableBlockData::from(block)).map_err(|err| {
+                DbError::borsh_cast_message(err, Some("Failed to serialize block data".to_string()))
+            })?,
+        );
+        Ok(())
+    }
 
-            if block.header.block_id > last_curr_block {
-                self.put_meta_last_block_in_db(block.header.block_id)?;
+    fn stage_block_header(
+        &self,
+        batch: &mut DbWriteBatch,
+        header: &BlockHeaderRecord,
+    ) -> DbResult<()> {
+        let cf_header = self.block_header_column();
+        batch.batch.put_cf(
+            &cf_header,
+            borsh::to_vec(&header.block_id).map_err(|err| {
+                DbError::borsh_cast_message(err, Some("Failed to serialize block id".to_string()))
+            })?,
+            borsh::to_vec(header).map_err(|err| {
+                DbError::borsh_cast_message(
+                    err,
+                    Some("Failed to serialize block header".to_string()),
+                )
+            })?,
+        );
+        Ok(())
+    }
+
+    /// Stage deletion of bodies and proof records of all blocks before `first_kept_block_id`.
+    /// Their headers are kept, so the hash chain can still be verified.
+    ///
+    /// Does nothing if these blocks are already pruned.
+    pub fn stage_prune_blocks_before(
+        &self,
+        batch: &mut DbWriteBatch,
+        first_kept_block_id: u64,
+    ) -> DbResult<()> {
+        let first_unpruned = self.get_meta_first_unpruned_block()?;
+        if first_kept_block_id <= first_unpruned {
+            return Ok(());
+        }
+
+        let cf_block = self.block_column();
+        let cf_proof = self.block_proof_column();
+        let cf_header = self.block_header_column();
+        for block_id in first_unpruned..first_kept_block_id {
+            let key = borsh::to_vec(&block_id).map_err(|err| {
+                DbError::borsh_cast_message(err, Some("Failed to serialize block id".to_string()))
+            })?;
+
+            // Blocks stored before headers were introduced have them only in their bodies
+            let has_header = self
+                .db
+                .get_pinned_cf(&cf_header, &key)
+                .map_err(|rerr| DbError::rocksdb_cast_message(rerr, None))?
+                .is_some();
+            if !has_header {
+                let data = self.get_block(block_id)?;
+                self.stage_block_header(batch, &BlockHeaderRecord::from(&data))?;
             }
+
+            batch.batch.delete_cf(&cf_block, &key);
+            batch.batch.delete_cf(&cf_proof, &key);
         }
 
+        batch.batch.put_cf(
+            &self.meta_column(),
+            borsh::to_vec(&DB_META_FIRST_UNPRUNED_BLOCK_KEY).map_err(|err| {
+                DbError::borsh_cast_message(
+                    err,
+                    Some("Failed to serialize DB_META_FIRST_UNPRUNED_BLOCK_KEY".to_string()),
+                )
+            })?,
+            borsh::to_vec(&first_kept_block_id).map_err(|err| {
+                DbError::borsh_cast_message(
+                    err,
+                    Some("Failed to serialize first unpruned block id".to_string()),
+                )
+            })?,
+        );
+        Ok(())
+    }
+
+    /// Stage digest of the state after applying the last staged block
+    pub fn stage_tip_state_digest(
+        &self,
+        batch: &mut DbWriteBatch,
+        digest: [u8; 32],
+    ) -> DbResult<()> {
+        let cf_meta = self.meta_column();
+        batch.batch.put_cf(
+            &cf_meta,
+            borsh::to_vec(&DB_META_TIP_STATE_DIGEST_KEY).map_err(|err| {
+                DbError::borsh_cast_message(
+                    err,
+                    Some("Failed to serialize DB_META_TIP_STATE_DIGEST_KEY".to_string()),
+                )
+            })?,
+            digest,
+        );
+        Ok(())
+    }
+
+    /// Stage proof record of block `block_id`, which is opaque to the storage
+    pub fn stage_block_proof_record(
+        &self,
+        batch: &mut DbWriteBatch,
+        block_id: u64,
+        record: &[u8],
+    ) -> DbResult<()> {
+        let cf_proof = self.block_proof_column();
+        batch.batch.put_cf(
+            &cf_proof,
+            borsh::to_vec(&block_id).map_err(|err| {
+                DbError::borsh_cast_message(err, Some("Failed to serialize block id".to_string()))
+            })?,
+            record,
+        );
+        Ok(())
+    }
+
+    pub fn put_block_proof_record(&self, block_id: u64, record: &[u8]) -> DbResult<()> {
+        let mut batch = DbWriteBatch::default();
+        self.stage_block_proof_record(&mut batch, block_id, record)?;
+        self.commit(batch)
+    }
+
+    pub fn get_block_proof_record(&self, block_id: u64) -> DbResult<Option<Vec<u8>>> {
+        let cf_proof = self.block_proof_column();
         self.db
-            .put_cf(
-                &cf_block,
-                borsh::to_vec(&block.header.block_id).map_err(|err| {
+            .get_cf(
+                &cf_proof,
+                borsh::to_vec(&block_id).map_err(|err| {
                     DbError::borsh_cast_message(
                         err,
                         Some("Failed to serialize block id".to_string()),
                     )
                 })?,
-                borsh::to_vec(&HashableBlockData::from(block)).map_err(|err| {
+            )
+            .map_err(|rerr| DbError::rocksdb_cast_message(rerr, None))
+    }
+
+    /// Apply all writes staged in `batch` atomically
+    pub fn commit(&self, batch: DbWriteBatch) -> DbResult<()> {
+        self.db
+            .write(batch.batch)
+            .map_err(|rerr| DbError::rocksdb_cast_message(rerr, None))
+    }
+
+    /// Digest of the state after applying the last block in db, if it was stored
+    pub fn get_tip_state_digest(&self) -> DbResult<Option<[u8; 32]>> {
+        let cf_meta = self.meta_column();
+        let res = self
+            .db
+            .get_cf(
+                &cf_meta,
+                borsh::to_vec(&DB_META_TIP_STATE_DIGEST_KEY).map_err(|err| {
                     DbError::borsh_cast_message(
                         err,
-                        Some("Failed to serialize block data".to_string()),
+                        Some("Failed to serialize DB_META_TIP_STATE_DIGEST_KEY".to_string()),
                     )
                 })?,
             )
             .map_err(|rerr| DbError::rocksdb_cast_message(rerr, None))?;
-        Ok(())
+
+        res.map(|data| {
+            data.try_into().map_err(|_| {
+                DbError::db_interaction_error("Malformed tip state digest".to_string())
+            })
+        })
+        .transpose()
+    }
+
+    /// Store record of chain parameters, which is opaque to the storage
+    pub fn put_meta_chain_params(&self, record: &[u8]) -> DbResult<()> {
+        let cf_meta = self.meta_column();
+        self.db
+            .put_cf(
+                &cf_meta,
+                borsh::to_vec(&DB_META_CHAIN_PARAMS_KEY).map_err(|err| {
+                    DbError::borsh_cast_message(
+                        err,
+                        Some("Failed to serialize DB_META_CHAIN_PARAMS_KEY".to_string()),
+                    )
+                })?,
+                record,
+            )
+            .map_err(|rerr| DbError::rocksdb_cast_message(rerr, None))
+    }
+
+    /// Record of chain parameters, `None` if db was created before it was stored
+    pub fn get_meta_chain_params(&self) -> DbResult<Option<Vec<u8>>> {
+        let cf_meta = self.meta_column();
+        self.db
+            .get_cf(
+                &cf_meta,
+                borsh::to_vec(&DB_META_CHAIN_PARAMS_KEY).map_err(|err| {
+                    DbError::borsh_cast_message(
+                        err,
+                        Some("Failed to serialize DB_META_CHAIN_PARAMS_KEY".to_string()),
+                    )
+                })?,
+            )
+            .map_err(|rerr| DbError::rocksdb_cast_message(rerr, None))
+    }
+
+    /// Id of the first block, which body is kept in db. Equals the first block in db, unless
+    /// blocks were pruned.
+    pub fn get_meta_first_unpruned_block(&self) -> DbResult<u64> {
+        let cf_meta = self.meta_column();
+        let res = self
+            .db
+            .get_cf(
+                &cf_meta,
+                borsh::to_vec(&DB_META_FIRST_UNPRUNED_BLOCK_KEY).map_err(|err| {
+                    DbError::borsh_cast_message(
+                        err,
+                        Some("Failed to serialize DB_META_FIRST_UNPRUNED_BLOCK_KEY".to_string()),
+                    )
+                })?,
+            )
+            .map_err(|rerr| DbError::rocksdb_cast_message(rerr, None))?;
+
+        if let Some(data) = res {
+            Ok(borsh::from_slice::<u64>(&data).map_err(|err| {
+                DbError::borsh_cast_message(
+                    err,
+                    Some("Failed to deserialize first unpruned block".to_string()),
+                )
+            })?)
+        } else {
+            self.get_meta_first_block_in_db()
+        }
     }
 
+    /// Header of the block, which is available even if the block body was pruned
+    pub fn get_block_header(&self, block_id: u64) -> DbResult<BlockHeaderRecord> {
+        let cf_header = self.block_header_column();
+        let res = self
+            .db
+            .get_cf(
+                &cf_header,
+                borsh::to_vec(&block_id).map_err(|err| {
+                    DbError::borsh_cast_message(
+                        err,
+                        Some("Failed to serialize block id".to_string()),
+                    )
+                })?,
+            )
+            .map_err(|rerr| DbError::rocksdb_cast_message(rerr, None))?;
+
+        match res {
+            Some(data) => Ok(
+                borsh::from_slice::<BlockHeaderRecord>(&data).map_err(|serr| {
+                    DbError::borsh_cast_message(
+                        serr,
+                        Some("Failed to deserialize block header".to_string()),
+                    )
+                })?,
+            ),
+            // Blocks stored before headers were introduced have them only in their bodies
+            None => Ok(BlockHeaderRecord::from(&self.get_block(block_id)?)),
+        }
+    }
+
+    /// Body of the stored block
+    ///
+    /// Blocks after the last one and pruned blocks are reported by [`DbError::BlockNotYetProduced`]
+    /// and [`DbError::BlockPruned`], while [`DbError::BlockMissing`] means a hole in the stored
+    /// chain.
     pub fn get_block(&self, block_id: u64) -> DbResult<HashableBlockData> {
+        let first_block = self.get_meta_first_block_in_db()?;
+        if block_id < first_block {
+            return Err(DbError::db_interaction_error(format!(
+                "Block {block_id} precedes the first block {first_block}"
+            )));
+        }
+        let first_unpruned = self.get_meta_first_unpruned_block()?;
+        if block_id < first_unpruned {
+            return Err(DbError::BlockPruned {
+                block_id,
+                first_unpruned_block_id: first_unpruned,
+            });
+        }
+        let last_block = self.get_meta_last_block_in_db()?;
+        if block_id > last_block {
+            return Err(DbError::BlockNotYetProduced {
+                block_id,
+                last_block_id: last_block,
+            });
+        }
+
         let cf_block = self.block_column();
         let res = self
             .db
@@ -305,9 +669,7 @@ impl RocksDBIO {
                 })?,
             )
         } else {
-            Err(DbError::db_interaction_error(
-                "Block on this id not found".to_string(),
-            ))
+            Err(DbError::BlockMissing { block_id })
         }
     }
 
@@ -340,3 +702,192 @@ impl RocksDBIO {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common::test_utils::produce_dummy_block;
+
+    use super::*;
+
+    fn open_with_genesis(path: &Path) -> RocksDBIO {
+        RocksDBIO::open_or_create(path, Some(produce_dummy_block(1, None, vec![]))).unwrap()
+    }
+
+    #[test]
+    fn test_committed_batch_is_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbio = open_with_genesis(dir.path());
+
+        let mut batch = DbWriteBatch::default();
+        dbio.stage_block(&mut batch, produce_dummy_block(2, None, vec![]), false)
+            .unwrap();
+        dbio.stage_tip_state_digest(&mut batch, [7; 32]).unwrap();
+        dbio.stage_block_proof_record(&mut batch, 2, b"pending")
+            .unwrap();
+        // Nothing is written until commit
+        assert_eq!(dbio.get_meta_last_block_in_db().unwrap(), 1);
+        dbio.commit(batch).unwrap();
+        drop(dbio);
+
+        let dbio = RocksDBIO::open_or_create(dir.path(), None).unwrap();
+        assert_eq!(dbio.get_meta_last_block_in_db().unwrap(), 2);
+        assert_eq!(dbio.get_block(2).unwrap().block_id, 2);
+        assert_eq!(dbio.get_tip_state_digest().unwrap(), Some([7; 32]));
+        assert_eq!(
+            dbio.get_block_proof_record(2).unwrap().as_deref(),
+            Some(&b"pending"[..])
+        );
+
+        // Proof record is updated after the block is stored
+        dbio.put_block_proof_record(2, b"proven").unwrap();
+        assert_eq!(
+            dbio.get_block_proof_record(2).unwrap().as_deref(),
+            Some(&b"proven"[..])
+        );
+        assert_eq!(dbio.get_block_proof_record(3).unwrap(), None);
+    }
+
+    #[test]
+    fn test_crash_mid_batch_keeps_previous_consistent_height() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbio = open_with_genesis(dir.path());
+        dbio.put_block(produce_dummy_block(2, None, vec![]), false)
+            .unwrap();
+
+        let mut batch = DbWriteBatch::default();
+        dbio.stage_block(&mut batch, produce_dummy_block(3, None, vec![]), false)
+            .unwrap();
+        // Failpoint: process dies after staging the block, but before staging the state digest
+        // and committing
+        drop(batch);
+        drop(dbio);
+
+        let dbio = RocksDBIO::open_or_create(dir.path(), None).unwrap();
+        assert_eq!(dbio.get_meta_last_block_in_db().unwrap(), 2);
+        assert!(dbio.get_block(3).is_err());
+        assert_eq!(dbio.get_tip_state_digest().unwrap(), None);
+    }
+
+    #[test]
+    fn test_hole_in_stored_blocks_is_reported_as_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbio = open_with_genesis(dir.path());
+        for block_id in 2..=4 {
+            dbio.put_block(produce_dummy_block(block_id, None, vec![]), false)
+                .unwrap();
+        }
+        // Storage loses a body in the middle of the chain
+        dbio.db
+            .delete_cf(&dbio.block_column(), borsh::to_vec(&3_u64).unwrap())
+            .unwrap();
+
+        assert_eq!(dbio.get_block(2).unwrap().block_id, 2);
+        assert!(matches!(
+            dbio.get_block(3),
+            Err(DbError::BlockMissing { block_id: 3 })
+        ));
+        assert_eq!(dbio.get_block(4).unwrap().block_id, 4);
+        assert!(matches!(
+            dbio.get_block(5),
+            Err(DbError::BlockNotYetProduced {
+                block_id: 5,
+                last_block_id: 4,
+            })
+        ));
+        assert!(matches!(
+            dbio.get_block(0),
+            Err(DbError::DbInteractionError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_pruning_keeps_recent_bodies_and_all_headers() {
+        const KEEP_BLOCKS: u64 = 50;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dbio = open_with_genesis(dir.path());
+        let mut prev_hash = dbio.get_block_header(1).unwrap().hash;
+        for block_id in 2..=200 {
+            let block = produce_dummy_block(block_id, Some(prev_hash), vec![]);
+            prev_hash = block.header.hash;
+
+            let mut batch = DbWriteBatch::default();
+            dbio.stage_block(&mut batch, block, false).unwrap();
+            dbio.stage_block_proof_record(&mut batch, block_id, b"proof")
+                .unwrap();
+            dbio.stage_prune_blocks_before(&mut batch, (block_id + 1).saturating_sub(KEEP_BLOCKS))
+                .unwrap();
+            dbio.commit(batch).unwrap();
+        }
+        drop(dbio);
+
+        let dbio = RocksDBIO::open_or_create(dir.path(), None).unwrap();
+        assert_eq!(dbio.get_meta_first_unpruned_block().unwrap(), 151);
+        assert_eq!(
+            dbio.db
+                .iterator_cf(&dbio.block_column(), rocksdb::IteratorMode::Start)
+                .count(),
+            50
+        );
+        assert_eq!(
+            dbio.db
+                .iterator_cf(&dbio.block_proof_column(), rocksdb::IteratorMode::Start)
+                .count(),
+            50
+        );
+
+        // Pruned blocks are distinguished from missing ones
+        for block_id in 1..=150 {
+            assert!(matches!(
+                dbio.get_block(block_id),
+                Err(DbError::BlockPruned {
+                    block_id: pruned_id,
+                    first_unpruned_block_id: 151,
+                }) if pruned_id == block_id
+            ));
+            assert_eq!(dbio.get_block_proof_record(block_id).unwrap(), None);
+        }
+        for block_id in 151..=200 {
+            assert_eq!(dbio.get_block(block_id).unwrap().block_id, block_id);
+        }
+        assert!(matches!(
+            dbio.get_block(201),
+            Err(DbError::BlockNotYetProduced {
+                block_id: 201,
+                last_block_id: 200,
+            })
+        ));
+
+        // Hash chain can still be verified from the genesis
+        for block_id in 2..=200 {
+            let header = dbio.get_block_header(block_id).unwrap();
+            let prev_header = dbio.get_block_header(block_id - 1).unwrap();
+            assert_eq!(header.prev_block_hash, prev_header.hash);
+        }
+        let tip = dbio.get_block(200).unwrap();
+        assert_eq!(dbio.get_block_header(200).unwrap().hash, tip.hash());
+    }
+
+    #[test]
+    fn test_pruning_keeps_headers_of_blocks_stored_without_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbio = open_with_genesis(dir.path());
+        dbio.put_block(produce_dummy_block(2, None, vec![]), false)
+            .unwrap();
+        // Block stored before headers were stored separately
+        dbio.db
+            .delete_cf(&dbio.block_header_column(), borsh::to_vec(&2_u64).unwrap())
+            .unwrap();
+        let expected_header = dbio.get_block_header(2).unwrap();
+
+        let mut batch = DbWriteBatch::default();
+        dbio.stage_prune_blocks_before(&mut batch, 3).unwrap();
+        dbio.commit(batch).unwrap();
+
+        assert!(matches!(
+            dbio.get_block(2),
+            Err(DbError::BlockPruned { .. })
+        ));
+        assert_eq!(dbio.get_block_header(2).unwrap(), expected_header);
+    }
+}
@@ -0,0 +1,208 @@
+//! Fetching of many public accounts with a bounded number of requests in flight.
+//!
+//! Accounts are fetched in batches of [`MAX_ACCOUNTS_PER_REQUEST`] if the sequencer advertises
+//! [`SequencerFeature::BatchAccounts`], otherwise one by one. Accounts of a failed batch are
+//! fetched one by one, so a failure is isolated to the accounts it's about.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use common::{
+    rpc_types::{MAX_ACCOUNTS_PER_REQUEST, SequencerFeature},
+    sequencer_api::SequencerApi,
+};
+use futures::{StreamExt as _, stream};
+use log::warn;
+use nssa::{Account, AccountId};
+
+#[derive(Clone)]
+pub struct AccountFetcher {
+    client: Arc<dyn SequencerApi>,
+    /// Max number of requests in flight
+    concurrency: usize,
+    batch_accounts: bool,
+}
+
+impl AccountFetcher {
+    pub fn new(client: Arc<dyn SequencerApi>, concurrency: usize, batch_accounts: bool) -> Self {
+        Self {
+            client,
+            concurrency: concurrency.max(1),
+            batch_accounts,
+        }
+    }
+
+    /// Fetcher, which uses batches if `features` advertise them
+    pub fn for_features(
+        client: Arc<dyn SequencerApi>,
+        concurrency: usize,
+        features: &[SequencerFeature],
+    ) -> Self {
+        Self::new(
+            client,
+            concurrency,
+            features.contains(&SequencerFeature::BatchAccounts),
+        )
+    }
+
+    /// Accounts in order of `account_ids`, each one fetched or failed on its own
+    pub async fn fetch(&self, account_ids: &[AccountId]) -> Vec<Result<Account>> {
+        let mut accounts: Vec<Option<Result<Account>>> = account_ids.iter().map(|_| None).collect();
+
+        if self.batch_accounts {
+            let batches =
+                account_ids
+                    .chunks(MAX_ACCOUNTS_PER_REQUEST)
+                    .enumerate()
+                    .map(|(batch_index, batch)| async move {
+                        (batch_index, self.fetch_batch(batch).await)
+                    })
+                    .collect::<Vec<_>>();
+            let mut fetched = stream::iter(batches).buffer_unordered(self.concurrency);
+
+            while let Some((batch_index, batch_accounts)) = fetched.next().await {
+                let Some(batch_accounts) = batch_accounts else {
+                    continue;
+                };
+                let offset = batch_index * MAX_ACCOUNTS_PER_REQUEST;
+                for (index, account) in batch_accounts.into_iter().enumerate() {
+                    accounts[offset + index] = Some(Ok(account));
+                }
+            }
+        }
+
+        let singles = accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, account)| account.is_none())
+            .map(|(index, _)| async move { (index, self.fetch_single(account_ids[index]).await) })
+            .collect::<Vec<_>>();
+        let mut fetched = stream::iter(singles).buffer_unordered(self.concurrency);
+
+        while let Some((index, account)) = fetched.next().await {
+            accounts[index] = Some(account);
+        }
+
+        accounts
+            .into_iter()
+            .map(|account| account.expect("Every account is fetched"))
+            .collect()
+    }
+
+    /// Accounts of the batch, `None` if it failed
+    async fn fetch_batch(&self, account_ids: &[AccountId]) -> Option<Vec<Account>> {
+        let response = self
+            .client
+            .get_accounts(account_ids.iter().map(ToString::to_string).collect())
+            .await;
+
+        match response {
+            Ok(response) if response.accounts.len() == account_ids.len() => Some(
+                response
+                    .accounts
+                    .into_iter()
+                    .map(|response| response.account)
+                    .collect(),
+            ),
+            Ok(response) => {
+                warn!(
+                    "Sequencer returned {} accounts for {} requested, fetching them one by one",
+                    response.accounts.len(),
+                    account_ids.len()
+                );
+                None
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to fetch batch of {} accounts, fetching them one by one: {err}",
+                    account_ids.len()
+                );
+                None
+            }
+        }
+    }
+
+    async fn fetch_single(&self, account_id: AccountId) -> Result<Account> {
+        let response = self.client.get_account(account_id.to_string()).await?;
+        Ok(response.account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use common::{mock_chain::MockChain, test_utils::account_id_for_index};
+
+    use super::*;
+
+    const LATENCY: Duration = Duration::from_millis(50);
+
+    fn account_ids(num_accounts: u32) -> Vec<AccountId> {
+        (0..num_accounts).map(account_id_for_index).collect()
+    }
+
+    #[tokio::test]
+    async fn test_parallel_fetch_is_faster_than_sequential() {
+        let account_ids = account_ids(16);
+        let client: Arc<dyn SequencerApi> =
+            Arc::new(MockChain::new(&[]).with_account_query_latency(LATENCY));
+
+        let started = Instant::now();
+        let accounts = AccountFetcher::new(client.clone(), 8, false)
+            .fetch(&account_ids)
+            .await;
+        let parallel = started.elapsed();
+
+        let started = Instant::now();
+        AccountFetcher::new(client, 1, false)
+            .fetch(&account_ids)
+            .await;
+        let sequential = started.elapsed();
+
+        assert_eq!(accounts.len(), 16);
+        assert!(accounts.iter().all(Result::is_ok));
+        // 2 rounds of 8 requests instead of 16 requests one after another
+        assert!(sequential >= LATENCY * 16);
+        assert!(parallel < LATENCY * 4, "Took {parallel:?}");
+    }
+
+    #[tokio::test]
+    async fn test_failed_accounts_are_reported_on_their_own() {
+        let account_ids = account_ids(6);
+        let chain = Arc::new(
+            MockChain::new(&[])
+                .with_batch_accounts()
+                .with_account_query_latency(LATENCY),
+        );
+        chain.fail_account_queries(account_ids[2]);
+
+        let started = Instant::now();
+        let accounts = AccountFetcher::new(chain, 8, true)
+            .fetch(&account_ids)
+            .await;
+
+        // Failed batch falls back to parallel single requests
+        assert!(started.elapsed() < LATENCY * 4);
+        let failed = accounts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, account)| account.is_err().then_some(index))
+            .collect::<Vec<_>>();
+        assert_eq!(failed, [2]);
+    }
+
+    #[tokio::test]
+    async fn test_batches_are_used_only_if_advertised() {
+        let chain = Arc::new(MockChain::new(&[]).with_batch_accounts());
+        let features = chain.get_sequencer_info().await.unwrap().features;
+        assert!(AccountFetcher::for_features(chain.clone(), 8, &features).batch_accounts);
+        assert!(!AccountFetcher::for_features(chain, 8, &[]).batch_accounts);
+
+        // Sequencer without batches serves accounts one by one
+        let account_ids = account_ids(4);
+        let fetcher = AccountFetcher::new(Arc::new(MockChain::new(&[])), 8, true);
+        assert!(fetcher.fetch_batch(&account_ids).await.is_none());
+        assert!(fetcher.fetch(&account_ids).await.iter().all(Result::is_ok));
+    }
+}
@@ -0,0 +1,479 @@
+//! Address book of named counterparty addresses.
+//!
+//! Contacts are kept in a JSON file next to the wallet file, not in the wallet file itself, so the
+//! book holds no keys and can be shared. Every place accepting an external address also accepts
+//! a contact name. Addresses always win: a contact, which name is also a valid address, is never
+//! resolved by that name.
+//!
+//! Contacts are exchanged as JSON arrays or as CSV files with rows `name,address[,note]`, where
+//! fields containing commas or quotes are quoted. Empty lines and a `name,address[,note]` header
+//! are skipped.
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use nssa::AccountId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    helperfunctions::parse_addr_with_privacy_prefix,
+    paths::{create_private_dir_all, write_private},
+    payout::csv_field,
+};
+
+pub const ADDRESS_BOOK_FILE_NAME: &str = "contacts.json";
+
+/// Address book file, which is kept next to `wallet_file`
+pub fn address_book_file(wallet_file: &Path) -> PathBuf {
+    wallet_file.with_file_name(ADDRESS_BOOK_FILE_NAME)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    pub name: String,
+    /// Account id with privacy prefix, e.g. `Public/...`
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Contact {
+    /// Contact with surrounding whitespace trimmed and an empty note dropped, checked to have a
+    /// valid address
+    pub fn new(name: &str, address: &str, note: Option<&str>) -> Result<Self, String> {
+        let contact = Self {
+            name: name.trim().to_string(),
+            address: address.trim().to_string(),
+            note: note
+                .map(str::trim)
+                .filter(|note| !note.is_empty())
+                .map(str::to_string),
+        };
+
+        if contact.name.is_empty() {
+            return Err("Contact name must not be empty".to_string());
+        }
+        if contact.name.chars().any(char::is_control) {
+            return Err(format!(
+                "Contact name `{}` contains control characters",
+                contact.name
+            ));
+        }
+        if !is_address(&contact.address) {
+            return Err(format!(
+                "Invalid address `{}`, expected account id with `Public/` or `Private/` prefix",
+                contact.address
+            ));
+        }
+        if contact
+            .note
+            .as_ref()
+            .is_some_and(|note| note.chars().any(char::is_control))
+        {
+            return Err("Note must not contain control characters".to_string());
+        }
+
+        Ok(contact)
+    }
+}
+
+/// Whether `input` is an account id with privacy prefix
+pub fn is_address(input: &str) -> bool {
+    parse_addr_with_privacy_prefix(input)
+        .is_ok_and(|(account_id, _)| account_id.parse::<AccountId>().is_ok())
+}
+
+/// Address given by user, after contact names are resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedAddress {
+    /// Either the given input or the address of the contact named by it
+    pub address: String,
+    /// Name of the contact with the address
+    pub contact: Option<String>,
+    /// Name of a contact, which isn't resolved, as the input is an address
+    pub shadowed_contact: Option<String>,
+}
+
+impl Display for ResolvedAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.contact {
+            Some(contact) => write!(f, "{contact} ({})", self.address),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+/// Address book stored in a JSON file
+#[derive(Debug, Clone)]
+pub struct AddressBook {
+    path: PathBuf,
+}
+
+impl AddressBook {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All contacts ordered by name, the book is empty if its file doesn't exist
+    pub fn contacts(&self) -> Result<Vec<Contact>> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut contacts = contacts_from_json(&contents).map_err(|err| {
+            anyhow::anyhow!("Invalid address book {}: {err:#}", self.path.display())
+        })?;
+        // The file may be edited by hand
+        contacts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(contacts)
+    }
+
+    /// Adds a new contact, failing if the name is taken
+    pub fn add(&self, contact: Contact) -> Result<()> {
+        let mut contacts = self.contacts()?;
+        anyhow::ensure!(
+            contacts.iter().all(|known| known.name != contact.name),
+            "Contact `{}` already exists",
+            contact.name
+        );
+
+        contacts.push(contact);
+        self.save(contacts)
+    }
+
+    /// Returns `false` if there is no contact named `name`
+    pub fn remove(&self, name: &str) -> Result<bool> {
+        let mut contacts = self.contacts()?;
+        let len_before = contacts.len();
+        contacts.retain(|contact| contact.name != name);
+        if contacts.len() == len_before {
+            return Ok(false);
+        }
+
+        self.save(contacts)?;
+        Ok(true)
+    }
+
+    /// Adds `imported` contacts, replacing known ones with the same names, and returns the
+    /// number of replaced contacts
+    pub fn import(&self, imported: Vec<Contact>) -> Result<usize> {
+        let mut contacts = self.contacts()?;
+        let len_before = contacts.len();
+        contacts.retain(|known| imported.iter().all(|contact| contact.name != known.name));
+        let replaced = len_before - contacts.len();
+
+        contacts.extend(imported);
+        self.save(contacts)?;
+        Ok(replaced)
+    }
+
+    /// Resolves `input`, which is either an address or a contact name
+    ///
+    /// Input, which is neither, is returned as is, so it's rejected where the address is parsed.
+    pub fn resolve(&self, input: &str) -> Result<ResolvedAddress> {
+        let contacts = self.contacts()?;
+
+        if is_address(input) {
+            return Ok(ResolvedAddress {
+                address: input.to_string(),
+                contact: contacts
+                    .iter()
+                    .find(|contact| contact.address == input)
+                    .map(|contact| contact.name.clone()),
+                shadowed_contact: contacts
+                    .iter()
+                    .find(|contact| contact.name == input)
+                    .map(|contact| contact.name.clone()),
+            });
+        }
+
+        let resolved = match contacts.into_iter().find(|contact| contact.name == input) {
+            Some(contact) => ResolvedAddress {
+                address: contact.address,
+                contact: Some(contact.name),
+                shadowed_contact: None,
+            },
+            None => ResolvedAddress {
+                address: input.to_string(),
+                contact: None,
+                shadowed_contact: None,
+            },
+        };
+        Ok(resolved)
+    }
+
+    fn save(&self, mut contacts: Vec<Contact>) -> Result<()> {
+        contacts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if let Some(parent) = self.path.parent() {
+            create_private_dir_all(parent)?;
+        }
+        write_private(&self.path, contacts_to_json(&contacts)?)?;
+
+        Ok(())
+    }
+}
+
+/// Format of exported and imported contacts
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactsFileFormat {
+    Csv,
+    Json,
+}
+
+impl ContactsFileFormat {
+    /// Format given by extension of `path`, JSON unless it's `.csv`
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Parses JSON array of contacts, checking every one of them
+pub fn contacts_from_json(contents: &str) -> Result<Vec<Contact>> {
+    let contacts: Vec<Contact> = serde_json::from_str(contents)?;
+
+    let mut checked: Vec<Contact> = vec![];
+    for contact in contacts {
+        let contact = Contact::new(&contact.name, &contact.address, contact.note.as_deref())
+            .map_err(anyhow::Error::msg)?;
+        anyhow::ensure!(
+            checked.iter().all(|known| known.name != contact.name),
+            "Contact `{}` is listed twice",
+            contact.name
+        );
+        checked.push(contact);
+    }
+
+    Ok(checked)
+}
+
+pub fn contacts_to_json(contacts: &[Contact]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(contacts)?)
+}
+
+/// Problem with a row of a contacts CSV file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContactRowError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ContactRowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parses contacts CSV `contents`, reporting problems of all rows at once
+pub fn contacts_from_csv(contents: &str) -> Result<Vec<Contact>, Vec<ContactRowError>> {
+    let mut contacts: Vec<Contact> = vec![];
+    let mut errors = vec![];
+    let mut header_allowed = true;
+
+    for (idx, line) in contents.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if std::mem::take(&mut header_allowed)
+            && matches!(
+                line.to_ascii_lowercase().as_str(),
+                "name,address" | "name,address,note"
+            )
+        {
+            continue;
+        }
+
+        let contact = split_csv_row(line).and_then(|fields| match fields.as_slice() {
+            [name, address] => Contact::new(name, address, None),
+            [name, address, note] => Contact::new(name, address, Some(note)),
+            _ => Err("Expected `name,address[,note]`".to_string()),
+        });
+        match contact {
+            Ok(contact) if contacts.iter().any(|known| known.name == contact.name) => {
+                errors.push(ContactRowError {
+                    line: line_number,
+                    message: format!("Contact `{}` is listed twice", contact.name),
+                });
+            }
+            Ok(contact) => contacts.push(contact),
+            Err(message) => errors.push(ContactRowError {
+                line: line_number,
+                message,
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(contacts)
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn contacts_to_csv(contacts: &[Contact]) -> String {
+    let mut csv = "name,address,note\n".to_string();
+    for contact in contacts {
+        let fields = [
+            csv_field(&contact.name),
+            contact.address.clone(),
+            csv_field(contact.note.as_deref().unwrap_or_default()),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Splits CSV `row` into fields, unquoting quoted ones
+fn split_csv_row(row: &str) -> Result<Vec<String>, String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (false, ',') => fields.push(std::mem::take(&mut field)),
+            (false, '"') if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => {
+                quoted = false;
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                if chars.peek().is_some_and(|c| *c != ',') {
+                    return Err("Unexpected characters after closing quote".to_string());
+                }
+            }
+            (_, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Quoted field is not closed".to_string());
+    }
+
+    fields.push(field);
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "Public/BLgCRDXYdQPMMWVHYRFGQZbgeHx9frkipa8GtpG2Syqy";
+    const BOB: &str = "Private/Gj1mJy5W7J5pfmLRujmQaLfLMWidNxQ6uwnhb666ZwHw";
+
+    fn contact(name: &str, address: &str, note: Option<&str>) -> Contact {
+        Contact::new(name, address, note).unwrap()
+    }
+
+    #[test]
+    fn test_resolution_precedence() {
+        let home = tempfile::tempdir().unwrap();
+        let book = AddressBook::new(home.path().join(ADDRESS_BOOK_FILE_NAME));
+        book.add(contact("alice", ALICE, None)).unwrap();
+        // Name, which is a valid address, is shadowed by the address
+        book.add(contact(ALICE, BOB, None)).unwrap();
+        assert!(book.add(contact("alice", BOB, None)).is_err());
+
+        assert_eq!(
+            book.resolve("alice").unwrap(),
+            ResolvedAddress {
+                address: ALICE.to_string(),
+                contact: Some("alice".to_string()),
+                shadowed_contact: None,
+            }
+        );
+        assert_eq!(
+            book.resolve(ALICE).unwrap(),
+            ResolvedAddress {
+                address: ALICE.to_string(),
+                contact: Some("alice".to_string()),
+                shadowed_contact: Some(ALICE.to_string()),
+            }
+        );
+        assert_eq!(
+            book.resolve(BOB).unwrap(),
+            ResolvedAddress {
+                address: BOB.to_string(),
+                contact: Some(ALICE.to_string()),
+                shadowed_contact: None,
+            }
+        );
+        // Neither address nor contact, rejected where the address is parsed
+        assert_eq!(book.resolve("carol").unwrap().address, "carol");
+        assert_eq!(book.resolve("carol").unwrap().contact, None);
+
+        assert_eq!(
+            book.resolve("alice").unwrap().to_string(),
+            format!("alice ({ALICE})")
+        );
+        assert!(book.remove("alice").unwrap());
+        assert!(!book.remove("alice").unwrap());
+        assert_eq!(book.resolve("alice").unwrap().contact, None);
+    }
+
+    #[test]
+    fn test_malformed_csv_is_rejected_with_all_problems() {
+        let csv = "\
+name,address,note
+alice,Public/BLgCRDXYdQPMMWVHYRFGQZbgeHx9frkipa8GtpG2Syqy
+bob,Public/not-an-account
+carol
+\"dave,Public/BLgCRDXYdQPMMWVHYRFGQZbgeHx9frkipa8GtpG2Syqy
+alice,Private/Gj1mJy5W7J5pfmLRujmQaLfLMWidNxQ6uwnhb666ZwHw
+";
+
+        let errors = contacts_from_csv(csv).unwrap_err();
+
+        let lines: Vec<usize> = errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![3, 4, 5, 6]);
+        assert!(errors[0].message.contains("Invalid address"));
+        assert_eq!(errors[1].message, "Expected `name,address[,note]`");
+        assert_eq!(errors[2].message, "Quoted field is not closed");
+        assert_eq!(errors[3].message, "Contact `alice` is listed twice");
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let contacts = vec![
+            contact("alice", ALICE, Some("Rent, \"monthly\"")),
+            contact("bob", BOB, None),
+            contact("Bob, Jr.", ALICE, Some("Son of bob")),
+        ];
+
+        let csv = contacts_to_csv(&contacts);
+        assert_eq!(contacts_from_csv(&csv).unwrap(), contacts);
+
+        let json = contacts_to_json(&contacts).unwrap();
+        assert_eq!(contacts_from_json(&json).unwrap(), contacts);
+
+        let home = tempfile::tempdir().unwrap();
+        let book = AddressBook::new(home.path().join(ADDRESS_BOOK_FILE_NAME));
+        book.add(contact("bob", ALICE, Some("Outdated"))).unwrap();
+        assert_eq!(book.import(contacts.clone()).unwrap(), 1);
+
+        let mut expected = contacts;
+        expected.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(book.contacts().unwrap(), expected);
+    }
+}
@@ -0,0 +1,218 @@
+//! Transactions of a public account, fetched from the sequencer index page by page.
+//!
+//! Pages are requested from the newest transaction, so transactions of blocks applied while
+//! fetching are left out instead of shifting the pages, see [`common::address_index`].
+
+use anyhow::Result;
+use common::{
+    address_index::{AddressTxCursor, AddressTxFilter, MAX_ADDRESS_TXS_PAGE_SIZE},
+    rpc_types::AddressTransaction,
+    sequencer_api::SequencerApi,
+};
+use nssa::AccountId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressHistory {
+    /// From the newest one
+    pub transactions: Vec<AddressTransaction>,
+    /// Whether older transactions matching the filter weren't fetched
+    pub has_more: bool,
+}
+
+/// Pages of transactions of a public account matching a filter, from the newest one, which are
+/// fetched one at a time, so they are processed without keeping all of them
+pub struct AddressTxPages<'a> {
+    client: &'a dyn SequencerApi,
+    account_id: AccountId,
+    filter: AddressTxFilter,
+    cursor: Option<AddressTxCursor>,
+    has_more: bool,
+}
+
+impl<'a> AddressTxPages<'a> {
+    pub fn new(
+        client: &'a dyn SequencerApi,
+        account_id: AccountId,
+        filter: AddressTxFilter,
+    ) -> Self {
+        Self {
+            client,
+            account_id,
+            filter,
+            cursor: None,
+            has_more: true,
+        }
+    }
+
+    /// Whether older transactions matching the filter weren't fetched yet
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    /// Next page of up to `limit` transactions, which is empty after the oldest one
+    pub async fn next_page(&mut self, limit: usize) -> Result<Vec<AddressTransaction>> {
+        if !self.has_more {
+            return Ok(vec![]);
+        }
+
+        let page = self
+            .client
+            .get_transactions_by_address(
+                self.account_id.to_string(),
+                self.cursor,
+                Some(limit.min(MAX_ADDRESS_TXS_PAGE_SIZE) as u64),
+                self.filter.clone(),
+            )
+            .await?;
+
+        // Otherwise a misbehaving sequencer would keep the wallet fetching forever
+        anyhow::ensure!(
+            !page.has_more || (!page.transactions.is_empty() && page.next_cursor.is_some()),
+            "Sequencer reported more transactions without returning any"
+        );
+        self.has_more = page.has_more;
+        self.cursor = page.next_cursor;
+        Ok(page.transactions)
+    }
+}
+
+/// Up to `limit` newest transactions of `account_id` matching `filter`, or every one of them
+/// without a limit
+pub async fn fetch_address_history(
+    client: &dyn SequencerApi,
+    account_id: AccountId,
+    filter: &AddressTxFilter,
+    limit: Option<usize>,
+) -> Result<AddressHistory> {
+    let mut pages = AddressTxPages::new(client, account_id, filter.clone());
+    let mut transactions = vec![];
+
+    while pages.has_more() && limit.is_none_or(|limit| transactions.len() < limit) {
+        let page_size = limit.map_or(MAX_ADDRESS_TXS_PAGE_SIZE, |limit| {
+            limit - transactions.len()
+        });
+        transactions.extend(pages.next_page(page_size).await?);
+    }
+
+    Ok(AddressHistory {
+        transactions,
+        has_more: pages.has_more(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use common::{
+        address_index::TxDirection,
+        mock_chain::MockChain,
+        test_utils::{
+            account_id_for_index, create_transaction_native_token_transfer, signing_key_for_index,
+        },
+    };
+    use nssa::Amount;
+
+    use super::*;
+
+    const NUM_TRANSFERS: u64 = 2500;
+    const TRANSFERS_PER_BLOCK: u64 = 100;
+
+    /// Chain, where account 0 sent [`NUM_TRANSFERS`] transfers to account 1, transfer `n` of
+    /// amount `n + 1`
+    async fn chain_with_transfers() -> MockChain {
+        let chain = MockChain::new(&[(account_id_for_index(0), Amount(u128::MAX))]);
+        for nonce in 0..NUM_TRANSFERS {
+            let tx = create_transaction_native_token_transfer(
+                *account_id_for_index(0).value(),
+                nonce,
+                *account_id_for_index(1).value(),
+                u128::from(nonce) + 1,
+                signing_key_for_index(0),
+            );
+            chain.send_tx(tx).await.unwrap();
+            if (nonce + 1).is_multiple_of(TRANSFERS_PER_BLOCK) {
+                chain.produce_block();
+            }
+        }
+        chain
+    }
+
+    fn amounts(history: &AddressHistory) -> Vec<u128> {
+        history
+            .transactions
+            .iter()
+            .map(|tx| tx.amount.unwrap().0)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_all_pages_are_fetched_without_gaps_or_duplicates() {
+        let chain = chain_with_transfers().await;
+
+        let history = fetch_address_history(
+            &chain,
+            account_id_for_index(1),
+            &AddressTxFilter::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(!history.has_more);
+        assert_eq!(
+            amounts(&history),
+            (1..=u128::from(NUM_TRANSFERS)).rev().collect::<Vec<_>>()
+        );
+        let hashes: HashSet<_> = history.transactions.iter().map(|tx| &tx.hash).collect();
+        assert_eq!(hashes.len(), NUM_TRANSFERS as usize);
+        assert!(
+            history
+                .transactions
+                .iter()
+                .all(|tx| tx.direction == Some(TxDirection::In))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_limit_spanning_pages_returns_newest_transactions() {
+        let chain = chain_with_transfers().await;
+        let limit = MAX_ADDRESS_TXS_PAGE_SIZE + 200;
+
+        let history = fetch_address_history(
+            &chain,
+            account_id_for_index(0),
+            &AddressTxFilter::default(),
+            Some(limit),
+        )
+        .await
+        .unwrap();
+
+        assert!(history.has_more);
+        assert_eq!(
+            amounts(&history),
+            (u128::from(NUM_TRANSFERS) - limit as u128 + 1..=u128::from(NUM_TRANSFERS))
+                .rev()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filters_are_applied_by_sequencer() {
+        let chain = chain_with_transfers().await;
+        let filter = AddressTxFilter {
+            direction: Some(TxDirection::Out),
+            min_amount: Some(Amount(2001)),
+            ..AddressTxFilter::default()
+        };
+
+        let history = fetch_address_history(&chain, account_id_for_index(0), &filter, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            amounts(&history),
+            (2001..=u128::from(NUM_TRANSFERS)).rev().collect::<Vec<_>>()
+        );
+    }
+}
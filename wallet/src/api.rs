@@ -0,0 +1,312 @@
+//! Wallet operations for embedding the wallet into other programs
+//!
+//! Nothing here prints or depends on the CLI, results are returned to the caller.
+
+use std::{collections::HashMap, str::FromStr, time::Duration};
+
+use anyhow::Result;
+use common::{
+    error::ExecutionFailureKind,
+    rpc_types::{TransactionStatus, ValidateTransactionResponse},
+};
+use key_protocol::key_management::key_tree::chain_index::ChainIndex;
+use log::warn;
+use nssa::{AccountId, Amount};
+
+use crate::{
+    WalletCore,
+    helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
+    program_facades::native_token_transfer::NativeTokenTransfer,
+    tx_log::TxStatus,
+};
+
+/// Hex encoded transaction hash
+pub type TxHash = String;
+
+/// Account, which keys are kept by the wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedAccount {
+    pub account_id: AccountId,
+    pub privacy: AccountPrivacyKind,
+    /// Path in the key tree, `None` for preconfigured accounts
+    pub chain_index: Option<ChainIndex>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TransferOptions {
+    /// Privacy of the receiver. If not set, receiver is private if it's a private account owned
+    /// by the wallet, and public otherwise.
+    pub to_privacy: Option<AccountPrivacyKind>,
+}
+
+/// Where the rest of the sender balance goes after a public transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeAddress {
+    /// Fresh address of the internal chain of the sender account
+    Auto,
+    Fixed(AccountId),
+}
+
+impl FromStr for ChangeAddress {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            account_id => {
+                let (account_id, privacy) = parse_addr_with_privacy_prefix(account_id)?;
+                anyhow::ensure!(
+                    privacy == AccountPrivacyKind::Public,
+                    "Change address must be public"
+                );
+                Ok(Self::Fixed(account_id.parse()?))
+            }
+        }
+    }
+}
+
+/// Progress of a sent transaction towards inclusion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InclusionProgress {
+    /// Transaction was accepted into mempool
+    Submitted {
+        position: u64,
+        depth: u64,
+        estimated_inclusion_block: u64,
+    },
+    /// Status of the transaction changed while waiting for it
+    StatusChanged(TransactionStatus),
+}
+
+/// Verdict of the sequencer on a transaction, which isn't submitted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxDryRun {
+    pub hash: TxHash,
+    /// Response of submission, or the reason the sequencer would reject the transaction
+    pub verdict: Result<ValidateTransactionResponse, String>,
+}
+
+/// Receives progress of transactions by their hashes
+pub type InclusionProgressReporter = fn(&str, &InclusionProgress);
+
+/// Fee paid by the sender of a public transfer for faster inclusion
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PriorityFee {
+    #[default]
+    None,
+    /// Median priority fee of currently pending transactions
+    Auto,
+    Fixed(Amount),
+}
+
+impl FromStr for PriorityFee {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            fee => Ok(Self::Fixed(fee.parse().map_err(|_| {
+                anyhow::anyhow!("Priority fee must be `auto` or a number, got `{fee}`")
+            })?)),
+        }
+    }
+}
+
+impl WalletCore {
+    /// All accounts owned by the wallet, preconfigured ones first
+    pub fn list_accounts(&self) -> Vec<OwnedAccount> {
+        let user_data = &self.storage.user_data;
+        let preconfigured = |privacy, account_id: &AccountId| OwnedAccount {
+            account_id: *account_id,
+            privacy,
+            chain_index: None,
+        };
+        let from_tree =
+            |privacy, (account_id, chain_index): (&AccountId, &ChainIndex)| OwnedAccount {
+                account_id: *account_id,
+                privacy,
+                chain_index: Some(chain_index.clone()),
+            };
+
+        // Ordered by path, so addresses are grouped by their accounts and chains
+        fn by_path(
+            account_id_map: &HashMap<AccountId, ChainIndex>,
+        ) -> Vec<(&AccountId, &ChainIndex)> {
+            let mut entries = account_id_map.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(_, chain_index)| *chain_index);
+            entries
+        }
+
+        user_data
+            .default_pub_account_signing_keys
+            .keys()
+            .map(|account_id| preconfigured(AccountPrivacyKind::Public, account_id))
+            .chain(
+                user_data
+                    .default_user_private_accounts
+                    .keys()
+                    .map(|account_id| preconfigured(AccountPrivacyKind::Private, account_id)),
+            )
+            .chain(
+                by_path(&user_data.public_key_tree.account_id_map)
+                    .into_iter()
+                    .map(|entry| from_tree(AccountPrivacyKind::Public, entry)),
+            )
+            .chain(
+                by_path(&user_data.private_key_tree.account_id_map)
+                    .into_iter()
+                    .map(|entry| from_tree(AccountPrivacyKind::Private, entry)),
+            )
+            .collect()
+    }
+
+    /// Privacy of the account, `None` if the wallet doesn't own it
+    pub fn account_privacy(&self, account_id: &AccountId) -> Option<AccountPrivacyKind> {
+        if self.get_account_public_signing_key(account_id).is_some() {
+            Some(AccountPrivacyKind::Public)
+        } else if self.get_account_private(account_id).is_some() {
+            Some(AccountPrivacyKind::Private)
+        } else {
+            None
+        }
+    }
+
+    /// Balance of the account
+    ///
+    /// Balances of owned private accounts are taken from the wallet, so they are as fresh as the
+    /// last [`Self::sync`]. Other accounts are queried from the sequencer.
+    pub async fn balance(&self, account_id: AccountId) -> Result<Amount> {
+        match self.get_account_private(&account_id) {
+            Some(account) => Ok(account.balance),
+            None => self.get_account_balance(account_id).await,
+        }
+    }
+
+    /// Sends native tokens from the owned account `from` to `to`
+    ///
+    /// Returns as soon as the transaction is accepted by the sequencer, use
+    /// [`Self::wait_for_inclusion`] to wait for it and [`Self::sync`] to pick up new states of
+    /// private accounts.
+    pub async fn transfer(
+        &self,
+        from: AccountId,
+        to: AccountId,
+        amount: Amount,
+        opts: TransferOptions,
+    ) -> Result<TxHash, ExecutionFailureKind> {
+        let from_privacy = self
+            .account_privacy(&from)
+            .ok_or(ExecutionFailureKind::KeyNotFoundError)?;
+        let to_privacy = opts.to_privacy.unwrap_or_else(|| {
+            if self.get_account_private(&to).is_some() {
+                AccountPrivacyKind::Private
+            } else {
+                AccountPrivacyKind::Public
+            }
+        });
+
+        let transfer = NativeTokenTransfer(self);
+        let response = match (from_privacy, to_privacy) {
+            (AccountPrivacyKind::Public, AccountPrivacyKind::Public) => {
+                transfer.send_public_transfer(from, to, amount).await?
+            }
+            (AccountPrivacyKind::Public, AccountPrivacyKind::Private) => {
+                transfer.send_shielded_transfer(from, to, amount).await?.0
+            }
+            (AccountPrivacyKind::Private, AccountPrivacyKind::Public) => {
+                transfer.send_deshielded_transfer(from, to, amount).await?.0
+            }
+            (AccountPrivacyKind::Private, AccountPrivacyKind::Private) => {
+                transfer
+                    .send_private_transfer_to_owned_account(from, to, amount)
+                    .await?
+                    .0
+            }
+        };
+
+        Ok(response.tx_hash)
+    }
+
+    /// Waits until the transaction is included and returns id of the including block
+    ///
+    /// Fails if the sequencer dropped the transaction or it wasn't included within `timeout`.
+    pub async fn wait_for_inclusion(&self, hash: &str, timeout: Duration) -> Result<u64> {
+        let poll_delay = Duration::from_millis(self.storage.wallet_config.seq_poll_timeout_millis);
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut last_status = None;
+
+        loop {
+            let status = self
+                .sequencer_client
+                .get_transaction_status(hash.to_string())
+                .await?
+                .status;
+
+            if last_status.as_ref() != Some(&status) {
+                self.report_progress(hash, &InclusionProgress::StatusChanged(status.clone()));
+            }
+
+            match &status {
+                TransactionStatus::Included { block_id, .. } => {
+                    if let Err(err) =
+                        self.tx_log
+                            .update_status(hash, TxStatus::Included, Some(*block_id))
+                    {
+                        warn!("Failed to update transaction log for {hash}: {err:#}");
+                    }
+                    return Ok(*block_id);
+                }
+                TransactionStatus::Dropped { reason } => {
+                    if let Err(err) = self.tx_log.update_status(hash, TxStatus::Failed, None) {
+                        warn!("Failed to update transaction log for {hash}: {err:#}");
+                    }
+                    anyhow::bail!("Transaction {hash} was dropped by sequencer: {reason}");
+                }
+                TransactionStatus::Pending { .. } | TransactionStatus::Unknown => {}
+            }
+
+            if tokio::time::Instant::now() + poll_delay > deadline {
+                anyhow::bail!("Transaction {hash} wasn't included within {timeout:?}");
+            }
+
+            last_status = Some(status);
+            tokio::time::sleep(poll_delay).await;
+        }
+    }
+
+    /// Syncs private accounts up to the last block of the sequencer and returns its id
+    pub async fn sync(&mut self) -> Result<u64> {
+        let tip = self.sequencer_client.get_last_block().await?;
+        self.sync_to_tip_with_progress(&tip, |_| {}).await?;
+
+        Ok(tip.last_block)
+    }
+
+    /// Priority fee to pay for the next public transfer
+    pub async fn resolve_priority_fee(&self) -> Result<Amount> {
+        match self.priority_fee {
+            PriorityFee::None => Ok(Amount::ZERO),
+            PriorityFee::Fixed(fee) => Ok(fee),
+            PriorityFee::Auto => {
+                if self.ensure_mempool_mirror().await
+                    && let Some(mirror) = &self.mempool_mirror
+                {
+                    return Ok(mirror.lock().unwrap().priority_fees().median);
+                }
+
+                Ok(self
+                    .sequencer_client
+                    .get_priority_fees()
+                    .await?
+                    .distribution
+                    .median)
+            }
+        }
+    }
+
+    pub(crate) fn report_progress(&self, hash: &str, progress: &InclusionProgress) {
+        if let Some(reporter) = self.inclusion_progress_reporter {
+            reporter(hash, progress);
+        }
+    }
+}
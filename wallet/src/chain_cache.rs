@@ -0,0 +1,362 @@
+//! On-disk cache of chain data, which rarely changes.
+//!
+//! API version, sequencer info and chain info would otherwise be fetched anew by every wallet
+//! invocation, adding round trips to even trivial commands. They are kept in a JSON file in the
+//! data directory, each for its own TTL, after which it's fetched again. Entries are only ever
+//! served within their TTL, including the chain id signed into program deployments.
+//!
+//! Entries belong to the configured sequencer and are dropped once it's changed. All of them are
+//! also dropped once a synced block or fetched chain info shows a newer version of chain
+//! parameters, as the parameters they describe may have changed with it.
+//!
+//! Gas of transactions is computed by [`nssa::gas::GasCalculator`] of the wallet, so there are no
+//! gas parameters to fetch. `--no-cache` bypasses the cache, `wallet cache clear` wipes it.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use common::{
+    block::ParamsVersion,
+    rpc_types::{GetApiVersionResponse, GetChainInfoResponse, GetSequencerInfoResponse},
+};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::paths::{create_private_dir_all, write_private};
+
+pub const CHAIN_CACHE_FILE_NAME: &str = "chain_cache.json";
+/// Env var, which bypasses the cache if set, see `--no-cache`
+pub const NO_CACHE_ENV_VAR: &str = "NSSA_WALLET_NO_CACHE";
+/// TTL of API version and sequencer info, which change with upgrades of the sequencer only
+pub const SEQUENCER_INFO_TTL: Duration = Duration::from_secs(60 * 60);
+/// TTL of chain info, which also reports the last block, so it's kept shortly
+pub const CHAIN_INFO_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedEntry<T> {
+    value: T,
+    /// Unix time in seconds
+    fetched_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheContents {
+    /// Address of the sequencer, which served the entries
+    sequencer_addr: String,
+    /// Newest version of chain parameters seen, `None` before any is seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    params_version: Option<ParamsVersion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_version: Option<CachedEntry<GetApiVersionResponse>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sequencer_info: Option<CachedEntry<GetSequencerInfoResponse>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chain_info: Option<CachedEntry<GetChainInfoResponse>>,
+}
+
+impl CacheContents {
+    fn new(sequencer_addr: &str) -> Self {
+        Self {
+            sequencer_addr: sequencer_addr.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.api_version = None;
+        self.sequencer_info = None;
+        self.chain_info = None;
+    }
+}
+
+#[derive(Debug)]
+pub struct ChainCache {
+    /// `None` if the cache is bypassed
+    path: Option<PathBuf>,
+    contents: Mutex<CacheContents>,
+}
+
+impl ChainCache {
+    /// Cache stored in `path` of the sequencer at `sequencer_addr`, entries of another sequencer
+    /// are dropped
+    ///
+    /// A cache, which can't be read, is started anew.
+    pub fn open(path: PathBuf, sequencer_addr: &str) -> Self {
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<CacheContents>(&contents)
+                .inspect_err(|err| warn!("Ignoring invalid chain cache {}: {err}", path.display()))
+                .ok(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!("Failed to read chain cache {}: {err}", path.display());
+                None
+            }
+        };
+        let contents = contents
+            .filter(|contents| contents.sequencer_addr == sequencer_addr)
+            .unwrap_or_else(|| CacheContents::new(sequencer_addr));
+
+        Self {
+            path: Some(path),
+            contents: Mutex::new(contents),
+        }
+    }
+
+    /// Cache, which serves nothing and stores nothing
+    pub fn bypassed() -> Self {
+        Self {
+            path: None,
+            contents: Mutex::default(),
+        }
+    }
+
+    /// Cache in `data_dir` of the sequencer at `sequencer_addr`, bypassed if [`NO_CACHE_ENV_VAR`]
+    /// is set
+    pub fn from_env(data_dir: &Path, sequencer_addr: &str) -> Self {
+        if std::env::var_os(NO_CACHE_ENV_VAR).is_some() {
+            Self::bypassed()
+        } else {
+            Self::open(data_dir.join(CHAIN_CACHE_FILE_NAME), sequencer_addr)
+        }
+    }
+
+    /// Removes the cache file in `data_dir`, returns whether there was one
+    pub fn clear(data_dir: &Path) -> Result<bool> {
+        match std::fs::remove_file(data_dir.join(CHAIN_CACHE_FILE_NAME)) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn api_version(&self) -> Option<GetApiVersionResponse> {
+        self.get_at(
+            |contents| &contents.api_version,
+            SEQUENCER_INFO_TTL,
+            unix_now(),
+        )
+    }
+
+    /// Caches API version of the sequencer, which the wallet is compatible with
+    pub fn set_api_version(&self, api_version: GetApiVersionResponse) {
+        self.set_at(
+            |contents| &mut contents.api_version,
+            api_version,
+            unix_now(),
+        );
+    }
+
+    pub fn sequencer_info(&self) -> Option<GetSequencerInfoResponse> {
+        self.get_at(
+            |contents| &contents.sequencer_info,
+            SEQUENCER_INFO_TTL,
+            unix_now(),
+        )
+    }
+
+    pub fn set_sequencer_info(&self, info: GetSequencerInfoResponse) {
+        self.set_at(|contents| &mut contents.sequencer_info, info, unix_now());
+    }
+
+    pub fn chain_info(&self) -> Option<GetChainInfoResponse> {
+        self.get_at(|contents| &contents.chain_info, CHAIN_INFO_TTL, unix_now())
+    }
+
+    pub fn set_chain_info(&self, info: GetChainInfoResponse) {
+        self.observe_params_version(info.params_version);
+        self.set_at(|contents| &mut contents.chain_info, info, unix_now());
+    }
+
+    /// Drops all entries if `params_version` is newer than the one seen before, returns whether
+    /// they were dropped
+    pub fn observe_params_version(&self, params_version: ParamsVersion) -> bool {
+        if self.path.is_none() {
+            return false;
+        }
+
+        let mut contents = self.contents.lock().unwrap();
+        let invalidated = match contents.params_version {
+            Some(seen) if params_version <= seen => return false,
+            Some(_) => {
+                contents.invalidate();
+                true
+            }
+            None => false,
+        };
+        contents.params_version = Some(params_version);
+        self.store(&contents);
+        invalidated
+    }
+
+    fn get_at<T: Clone>(
+        &self,
+        entry: impl FnOnce(&CacheContents) -> &Option<CachedEntry<T>>,
+        ttl: Duration,
+        now: u64,
+    ) -> Option<T> {
+        self.path.as_ref()?;
+
+        let contents = self.contents.lock().unwrap();
+        entry(&contents)
+            .as_ref()
+            .filter(|entry| now.saturating_sub(entry.fetched_at) < ttl.as_secs())
+            .map(|entry| entry.value.clone())
+    }
+
+    fn set_at<T>(
+        &self,
+        entry: impl FnOnce(&mut CacheContents) -> &mut Option<CachedEntry<T>>,
+        value: T,
+        now: u64,
+    ) {
+        if self.path.is_none() {
+            return;
+        }
+
+        let mut contents = self.contents.lock().unwrap();
+        *entry(&mut contents) = Some(CachedEntry {
+            value,
+            fetched_at: now,
+        });
+        self.store(&contents);
+    }
+
+    /// Writes `contents`, failures are only logged, as the cache is fetched again anyway
+    fn store(&self, contents: &CacheContents) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), create_private_dir_all)
+            .map_err(anyhow::Error::from)
+            .and_then(|()| Ok(serde_json::to_vec_pretty(contents)?))
+            .and_then(|bytes| Ok(write_private(path, bytes)?));
+        if let Err(err) = result {
+            warn!("Failed to store chain cache {}: {err:#}", path.display());
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use common::rpc_types::ApiVersion;
+
+    use super::*;
+
+    fn sequencer_info() -> GetSequencerInfoResponse {
+        GetSequencerInfoResponse {
+            version: "test".to_string(),
+            api_version: ApiVersion { major: 2, minor: 0 },
+            tx_kinds: vec![],
+            features: vec![],
+            public_key: None,
+            genesis_hash: None,
+            chain_id: Some(7),
+        }
+    }
+
+    fn chain_info(params_version: ParamsVersion) -> GetChainInfoResponse {
+        GetChainInfoResponse {
+            genesis_id: 1,
+            last_block: 10,
+            max_num_tx_in_block: 20,
+            params_version,
+            tx_ordering_policy: "arrival_seq_asc".to_string(),
+            program_allow_list: None,
+            forced_params_changes: vec![],
+            storage_deposit_per_byte: nssa::Amount::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_entries_expire_after_ttl() {
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join(CHAIN_CACHE_FILE_NAME);
+        let cache = ChainCache::open(path.clone(), "http://seq");
+        let now = unix_now();
+
+        cache.set_at(
+            |contents| &mut contents.sequencer_info,
+            sequencer_info(),
+            now - SEQUENCER_INFO_TTL.as_secs() + 10,
+        );
+        cache.set_at(
+            |contents| &mut contents.chain_info,
+            chain_info(0),
+            now - CHAIN_INFO_TTL.as_secs() - 1,
+        );
+        assert_eq!(cache.sequencer_info().unwrap().chain_id, Some(7));
+        assert!(cache.chain_info().is_none());
+
+        // Entries are stored with their age
+        let reopened = ChainCache::open(path.clone(), "http://seq");
+        assert!(reopened.sequencer_info().is_some());
+        assert!(reopened.chain_info().is_none());
+        assert!(
+            reopened
+                .get_at(
+                    |contents| &contents.sequencer_info,
+                    SEQUENCER_INFO_TTL,
+                    now + 11
+                )
+                .is_none()
+        );
+
+        // Entries of another sequencer aren't served
+        assert!(
+            ChainCache::open(path, "http://other")
+                .sequencer_info()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_newer_params_version_drops_entries() {
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join(CHAIN_CACHE_FILE_NAME);
+        let cache = ChainCache::open(path.clone(), "http://seq");
+        cache.set_sequencer_info(sequencer_info());
+        cache.set_chain_info(chain_info(2));
+        assert!(cache.sequencer_info().is_some());
+
+        // Blocks built before the version seen are synced
+        assert!(!cache.observe_params_version(1));
+        assert!(!cache.observe_params_version(2));
+        assert!(cache.chain_info().is_some());
+
+        assert!(cache.observe_params_version(3));
+        assert!(cache.sequencer_info().is_none());
+        assert!(cache.chain_info().is_none());
+        assert!(ChainCache::open(path, "http://seq").chain_info().is_none());
+
+        // Chain info of the new version is cached again
+        cache.set_chain_info(chain_info(3));
+        assert_eq!(cache.chain_info().unwrap().params_version, 3);
+    }
+
+    #[test]
+    fn test_bypassed_cache_serves_nothing() {
+        let home = tempfile::tempdir().unwrap();
+        let cache = ChainCache::bypassed();
+        cache.set_sequencer_info(sequencer_info());
+        assert!(cache.sequencer_info().is_none());
+
+        let cache = ChainCache::open(home.path().join(CHAIN_CACHE_FILE_NAME), "http://seq");
+        cache.set_sequencer_info(sequencer_info());
+        assert!(ChainCache::clear(home.path()).unwrap());
+        assert!(!ChainCache::clear(home.path()).unwrap());
+    }
+}
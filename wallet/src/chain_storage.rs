@@ -10,6 +10,7 @@ use key_protocol::{
 };
 use log::debug;
 use nssa::program::Program;
+use nssa_core::fmt::FmtShort as _;
 
 use crate::config::{InitialAccountData, PersistentAccountData, WalletConfig};
 
@@ -89,6 +90,11 @@ impl WalletChainStore {
     }
 
     pub fn new_storage(config: WalletConfig, password: String) -> Result<Self> {
+        Self::new_storage_from_seed(config, &SeedHolder::new_mnemonic(password))
+    }
+
+    /// Storage with initial accounts of `config` and key trees derived from `seed`
+    pub fn new_storage_from_seed(config: WalletConfig, seed: &SeedHolder) -> Result<Self> {
         let mut public_init_acc_map = HashMap::new();
         let mut private_init_acc_map = HashMap::new();
 
@@ -109,8 +115,8 @@ impl WalletChainStore {
             }
         }
 
-        let public_tree = KeyTreePublic::new(&SeedHolder::new_mnemonic(password.clone()));
-        let private_tree = KeyTreePrivate::new(&SeedHolder::new_mnemonic(password));
+        let public_tree = KeyTreePublic::new(seed);
+        let private_tree = KeyTreePrivate::new(seed);
 
         Ok(Self {
             user_data: NSSAUserData::new_with_accounts(
@@ -128,7 +134,11 @@ impl WalletChainStore {
         account_id: nssa::AccountId,
         account: nssa_core::account::Account,
     ) {
-        debug!("inserting at address {account_id}, this account {account:?}");
+        debug!(
+            "Inserting private account {}, nonce {}",
+            account_id.fmt_short(),
+            account.nonce
+        );
 
         let entry = self
             .user_data
@@ -265,6 +275,13 @@ mod tests {
             seq_block_poll_max_amount: 100,
             initial_accounts: create_initial_accounts(),
             basic_auth: None,
+            fallback_sequencer_addrs: vec![],
+            seq_failover_after_failures: 3,
+            seq_reconnect_max_delay_millis: 60_000,
+            account_fetch_concurrency: 8,
+            prover_backend: nssa::ProverBackend::Real,
+            mempool_mirror_max_age_blocks: None,
+            admin_auth_token: None,
         }
     }
 
@@ -293,4 +310,38 @@ mod tests {
 
         let _ = WalletChainStore::new(config.clone(), accs).unwrap();
     }
+
+    #[test]
+    fn test_initial_account_with_key_of_another_account_is_rejected() {
+        let mut config = create_sample_wallet_config();
+        let InitialAccountData::Public(first) = config.initial_accounts[0].clone() else {
+            unreachable!()
+        };
+        let InitialAccountData::Public(second) = &mut config.initial_accounts[1] else {
+            unreachable!()
+        };
+        second.pub_sign_key = first.pub_sign_key;
+        let second_account_id = second.account_id.clone();
+
+        let err = WalletChainStore::new_storage_from_seed(
+            config.clone(),
+            &SeedHolder::new_mnemonic("test".to_string()),
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains(&second_account_id));
+
+        let mut persistent_accounts = create_sample_persistent_accounts();
+        persistent_accounts.extend(
+            config
+                .initial_accounts
+                .iter()
+                .cloned()
+                .map(PersistentAccountData::Preconfigured),
+        );
+        let err = WalletChainStore::new(config, persistent_accounts)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains(&second_account_id));
+    }
 }
@@ -1,15 +1,26 @@
 use anyhow::Result;
 use base58::ToBase58;
-use clap::Subcommand;
-use itertools::Itertools as _;
-use key_protocol::key_management::key_tree::chain_index::ChainIndex;
-use nssa::{Account, AccountId, program::Program};
-use serde::Serialize;
+use clap::{Subcommand, ValueEnum};
+use common::address_index::{AddressTxFilter, TxDirection};
+use key_protocol::key_management::key_tree::chain_index::{AddressChain, ChainIndex};
+use nssa::{Account, AccountId, Amount, ProgramId, program::Program};
 
 use crate::{
     WalletCore,
-    cli::{SubcommandReturnValue, WalletSubcommand},
-    helperfunctions::{AccountPrivacyKind, HumanReadableAccount, parse_addr_with_privacy_prefix},
+    address_history::fetch_address_history,
+    api::OwnedAccount,
+    cli::{
+        SubcommandReturnValue, WalletSubcommand, confirm_from_stdin, sync_to_tip_printing_progress,
+    },
+    helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
+    light_client::VerificationError,
+    output::{
+        AccountFetchFailure, AccountHistoryOutput, AccountListEntry, AccountListOutput,
+        AccountOutput, AccountView, NewAccountOutput, SyncedOutput, TokedDefinitionAccountView,
+        TokedHoldingAccountView, TxSubmittedOutput, print_output,
+    },
+    pending_balance::PendingBalance,
+    program_facades::native_token_transfer::NativeTokenTransfer,
 };
 
 const TOKEN_DEFINITION_TYPE: u8 = 0;
@@ -82,11 +93,96 @@ pub enum AccountSubcommand {
     /// Produce new public or private account
     #[command(subcommand)]
     New(NewSubcommand),
+    /// Derive the next receive address of a public account
+    NewAddress {
+        /// Account node to derive the address of
+        #[arg(long, default_value = "m/0")]
+        account: ChainIndex,
+        /// Label of the address, shown by `account list`
+        #[arg(long)]
+        label: Option<String>,
+        /// Also print the address as a QR code
+        #[arg(long)]
+        qr: bool,
+    },
     /// Sync private accounts
     SyncPrivate {},
-    /// List all accounts owned by the wallet
+    /// List all accounts owned by the wallet, grouped by accounts and chains
     #[command(visible_alias = "ls")]
-    List {},
+    List {
+        /// Also list change addresses, which are empty
+        #[arg(long)]
+        all: bool,
+        /// Verify public accounts by their proofs against headers signed by the sequencer,
+        /// flagging ones, which fail
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Transactions touching a public account, from the newest one
+    History {
+        /// Valid 32 byte base58 string with privacy prefix, only public accounts are indexed
+        #[arg(short, long)]
+        account_id: String,
+        /// Number of the newest transactions to show
+        #[arg(
+            long,
+            default_value_t = DEFAULT_HISTORY_LIMIT,
+            value_parser = clap::value_parser!(u64).range(1..),
+            conflicts_with = "all"
+        )]
+        limit: u64,
+        /// Show every transaction
+        #[arg(long)]
+        all: bool,
+        /// Only native transfers in this direction
+        #[arg(long)]
+        direction: Option<HistoryDirection>,
+        /// Only native transfers of at least this amount
+        #[arg(long)]
+        min_amount: Option<Amount>,
+        /// Only transactions of blocks from this one (inclusive)
+        #[arg(long)]
+        from_block: Option<u64>,
+        /// Only transactions of blocks up to this one (inclusive)
+        #[arg(long)]
+        to_block: Option<u64>,
+        /// Only public transactions calling this program, e.g. `prog_1a2b...`
+        #[arg(long)]
+        program_id: Option<ProgramId>,
+    },
+    /// Assign a public account of the authenticated transfer program to another program
+    ///
+    /// The program takes over the balance of the account, and only the program can move it out
+    /// afterwards. Only accounts without data can be assigned.
+    Assign {
+        /// Valid 32 byte base58 string with `Public/` prefix
+        #[arg(long)]
+        address: String,
+        /// Program to own the account, e.g. `prog_1a2b...`
+        #[arg(long)]
+        program: ProgramId,
+        /// Assign without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+/// Number of transactions `account history` shows by default
+const DEFAULT_HISTORY_LIMIT: u64 = 20;
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum HistoryDirection {
+    In,
+    Out,
+}
+
+impl From<HistoryDirection> for TxDirection {
+    fn from(direction: HistoryDirection) -> Self {
+        match direction {
+            HistoryDirection::In => Self::In,
+            HistoryDirection::Out => Self::Out,
+        }
+    }
 }
 
 /// Represents generic register CLI subcommand
@@ -94,14 +190,17 @@ pub enum AccountSubcommand {
 pub enum NewSubcommand {
     /// Register new public account
     Public {
-        #[arg(long)]
-        /// Chain index of a parent node
+        #[arg(long, visible_alias = "path")]
+        /// Chain index of a parent node, e.g. `m/0/3`
         cci: Option<ChainIndex>,
+        /// Derive the next receive address of the account instead, e.g. `m/0`
+        #[arg(long, conflicts_with = "cci")]
+        account: Option<ChainIndex>,
     },
     /// Register new private account
     Private {
-        #[arg(long)]
-        /// Chain index of a parent node
+        #[arg(long, visible_alias = "path")]
+        /// Chain index of a parent node, e.g. `m/0/3`
         cci: Option<ChainIndex>,
     },
 }
@@ -112,16 +211,33 @@ impl WalletSubcommand for NewSubcommand {
         wallet_core: &mut WalletCore,
     ) -> Result<SubcommandReturnValue> {
         match self {
-            NewSubcommand::Public { cci } => {
-                let (account_id, chain_index) = wallet_core.create_new_account_public(cci);
-
-                println!(
-                    "Generated new account with account_id Public/{account_id} at path {chain_index}"
-                );
+            NewSubcommand::Public { cci, account } => {
+                let (account_id, chain_index) = match account {
+                    Some(account) => wallet_core
+                        .create_new_receive_address_public(&account)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "Account {} is not found in the key tree",
+                                account.to_path_string()
+                            )
+                        })?,
+                    None => wallet_core.create_new_account_public(cci),
+                };
 
                 let path = wallet_core.store_persistent_data().await?;
 
-                println!("Stored persistent accounts at {path:#?}");
+                print_output(
+                    wallet_core.output_format,
+                    &NewAccountOutput {
+                        account_id: format!("Public/{account_id}"),
+                        path: chain_index.to_path_string(),
+                        label: None,
+                        npk: None,
+                        ipk: None,
+                        wallet_file: path.display().to_string(),
+                        qr: false,
+                    },
+                )?;
 
                 Ok(SubcommandReturnValue::RegisterAccount { account_id })
             }
@@ -133,20 +249,23 @@ impl WalletSubcommand for NewSubcommand {
                     .user_data
                     .get_private_account(&account_id)
                     .unwrap();
-
-                println!(
-                    "Generated new account with account_id Private/{} at path {chain_index}",
-                    account_id.to_bytes().to_base58()
-                );
-                println!("With npk {}", hex::encode(key.nullifer_public_key.0));
-                println!(
-                    "With ipk {}",
-                    hex::encode(key.incoming_viewing_public_key.to_bytes())
-                );
+                let npk = hex::encode(key.nullifer_public_key.0);
+                let ipk = hex::encode(key.incoming_viewing_public_key.to_bytes());
 
                 let path = wallet_core.store_persistent_data().await?;
 
-                println!("Stored persistent accounts at {path:#?}");
+                print_output(
+                    wallet_core.output_format,
+                    &NewAccountOutput {
+                        account_id: format!("Private/{}", account_id.to_bytes().to_base58()),
+                        path: chain_index.to_path_string(),
+                        label: None,
+                        npk: Some(npk),
+                        ipk: Some(ipk),
+                        wallet_file: path.display().to_string(),
+                        qr: false,
+                    },
+                )?;
 
                 Ok(SubcommandReturnValue::RegisterAccount { account_id })
             }
@@ -154,30 +273,9 @@ impl WalletSubcommand for NewSubcommand {
     }
 }
 
-#[derive(Debug, Serialize)]
-pub struct AuthenticatedTransferAccountView {
-    pub balance: u128,
-}
-
-impl From<nssa::Account> for AuthenticatedTransferAccountView {
-    fn from(value: nssa::Account) -> Self {
-        Self {
-            balance: value.balance,
-        }
-    }
-}
-
-#[derive(Debug, Serialize)]
-pub struct TokedDefinitionAccountView {
-    pub account_type: String,
-    pub name: String,
-    pub total_supply: u128,
-}
-
 impl From<TokenDefinition> for TokedDefinitionAccountView {
     fn from(value: TokenDefinition) -> Self {
         Self {
-            account_type: "Token definition".to_string(),
             name: {
                 // Assuming, that name does not have UTF-8 NULL and all zeroes are padding.
                 let name_trimmed: Vec<_> =
@@ -189,17 +287,9 @@ impl From<TokenDefinition> for TokedDefinitionAccountView {
     }
 }
 
-#[derive(Debug, Serialize)]
-pub struct TokedHoldingAccountView {
-    pub account_type: String,
-    pub definition_id: String,
-    pub balance: u128,
-}
-
 impl From<TokenHolding> for TokedHoldingAccountView {
     fn from(value: TokenHolding) -> Self {
         Self {
-            account_type: "Token holding".to_string(),
             definition_id: value.definition_id.to_string(),
             balance: value.balance,
         }
@@ -212,8 +302,11 @@ impl WalletSubcommand for AccountSubcommand {
         wallet_core: &mut WalletCore,
     ) -> Result<SubcommandReturnValue> {
         match self {
-            AccountSubcommand::Get { raw, account_id } => {
-                let (account_id, addr_kind) = parse_addr_with_privacy_prefix(&account_id)?;
+            AccountSubcommand::Get {
+                raw,
+                account_id: prefixed_account_id,
+            } => {
+                let (account_id, addr_kind) = parse_addr_with_privacy_prefix(&prefixed_account_id)?;
 
                 let account_id = account_id.parse()?;
 
@@ -226,68 +319,114 @@ impl WalletSubcommand for AccountSubcommand {
                         .ok_or(anyhow::anyhow!("Private account not found in storage"))?,
                 };
 
-                if account == Account::default() {
-                    println!("Account is Uninitialized");
-
-                    return Ok(SubcommandReturnValue::Empty);
-                }
-
-                if raw {
-                    let account_hr: HumanReadableAccount = account.clone().into();
-                    println!("{}", serde_json::to_string(&account_hr).unwrap());
-
-                    return Ok(SubcommandReturnValue::Empty);
-                }
-
                 let auth_tr_prog_id = Program::authenticated_transfer_program().id();
                 let token_prog_id = Program::token().id();
 
-                let acc_view = match &account.program_owner {
+                let view = match &account.program_owner {
+                    _ if account == Account::default() => AccountView::Uninitialized,
+                    _ if raw => AccountView::Raw(account.into()),
                     _ if account.program_owner == auth_tr_prog_id => {
-                        let acc_view: AuthenticatedTransferAccountView = account.into();
-
-                        println!("Account owned by authenticated transfer program");
-
-                        serde_json::to_string(&acc_view)?
+                        AccountView::AuthenticatedTransfer(account.into())
                     }
                     _ if account.program_owner == token_prog_id => {
                         if let Some(token_def) = TokenDefinition::parse(&account.data) {
-                            let acc_view: TokedDefinitionAccountView = token_def.into();
-
-                            println!("Definition account owned by token program");
-
-                            serde_json::to_string(&acc_view)?
+                            AccountView::TokenDefinition(token_def.into())
                         } else if let Some(token_hold) = TokenHolding::parse(&account.data) {
-                            let acc_view: TokedHoldingAccountView = token_hold.into();
-
-                            println!("Holding account owned by token program");
-
-                            serde_json::to_string(&acc_view)?
+                            AccountView::TokenHolding(token_hold.into())
                         } else {
                             anyhow::bail!(
                                 "Invalid data for account {account_id:#?} with token program"
                             );
                         }
                     }
-                    _ => {
-                        let account_hr: HumanReadableAccount = account.clone().into();
-                        serde_json::to_string(&account_hr).unwrap()
-                    }
+                    _ => AccountView::Raw(account.into()),
                 };
 
-                println!("{}", acc_view);
+                print_output(
+                    wallet_core.output_format,
+                    &AccountOutput {
+                        account_id: prefixed_account_id,
+                        view,
+                    },
+                )?;
 
                 Ok(SubcommandReturnValue::Empty)
             }
             AccountSubcommand::New(new_subcommand) => {
                 new_subcommand.handle_subcommand(wallet_core).await
             }
+            AccountSubcommand::NewAddress { account, label, qr } => {
+                let (account_id, chain_index) = wallet_core
+                    .create_new_receive_address_public(&account)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Account {} is not found in the key tree",
+                            account.to_path_string()
+                        )
+                    })?;
+                if let Some(label) = &label {
+                    wallet_core
+                        .address_labels
+                        .insert(account_id.to_string(), label.clone());
+                }
+
+                let path = wallet_core.store_persistent_data().await?;
+
+                print_output(
+                    wallet_core.output_format,
+                    &NewAccountOutput {
+                        account_id: format!("Public/{account_id}"),
+                        path: chain_index.to_path_string(),
+                        label,
+                        npk: None,
+                        ipk: None,
+                        wallet_file: path.display().to_string(),
+                        qr,
+                    },
+                )?;
+
+                Ok(SubcommandReturnValue::RegisterAccount { account_id })
+            }
+            AccountSubcommand::Assign {
+                address,
+                program,
+                yes,
+            } => {
+                let account_id = match parse_addr_with_privacy_prefix(&address)? {
+                    (account_id, AccountPrivacyKind::Public) => account_id.parse()?,
+                    (_, AccountPrivacyKind::Private) => {
+                        anyhow::bail!("Only public accounts can be assigned, got {address}")
+                    }
+                };
+
+                eprintln!(
+                    "WARNING: {account_id} will be owned by {program}. Its balance can only be \
+                     moved by that program afterwards."
+                );
+                if !yes && !confirm_from_stdin("Assign the account?")? {
+                    anyhow::bail!("Assignment is cancelled");
+                }
+
+                let res = NativeTokenTransfer(wallet_core)
+                    .assign_account(account_id, program)
+                    .await?;
+                wallet_core
+                    .poll_native_token_transfer(res.tx_hash.clone())
+                    .await?;
+
+                print_output(
+                    wallet_core.output_format,
+                    &TxSubmittedOutput {
+                        hash: res.tx_hash,
+                        valid_from_block: None,
+                    },
+                )?;
+
+                Ok(SubcommandReturnValue::Empty)
+            }
             AccountSubcommand::SyncPrivate {} => {
-                let curr_last_block = wallet_core
-                    .sequencer_client
-                    .get_last_block()
-                    .await?
-                    .last_block;
+                let tip = wallet_core.sequencer_client.get_last_block().await?;
+                let curr_last_block = tip.last_block;
 
                 if wallet_core
                     .storage
@@ -298,53 +437,194 @@ impl WalletSubcommand for AccountSubcommand {
                 {
                     wallet_core.last_synced_block = curr_last_block;
 
-                    let path = wallet_core.store_persistent_data().await?;
-
-                    println!("Stored persistent data at {path:#?}");
+                    wallet_core.store_persistent_data().await?;
                 } else {
-                    wallet_core.sync_to_block(curr_last_block).await?;
+                    sync_to_tip_printing_progress(wallet_core, &tip).await?;
                 }
 
+                print_output(
+                    wallet_core.output_format,
+                    &SyncedOutput {
+                        synced_to_block: curr_last_block,
+                    },
+                )?;
+
                 Ok(SubcommandReturnValue::SyncedToBlock(curr_last_block))
             }
-            AccountSubcommand::List {} => {
-                let user_data = &wallet_core.storage.user_data;
-                let accounts = user_data
-                    .default_pub_account_signing_keys
-                    .keys()
-                    .map(|id| format!("Preconfigured Public/{id}"))
-                    .chain(
-                        user_data
-                            .default_user_private_accounts
-                            .keys()
-                            .map(|id| format!("Preconfigured Private/{id}")),
-                    )
-                    .chain(
-                        user_data
-                            .public_key_tree
-                            .account_id_map
-                            .iter()
-                            .map(|(id, chain_index)| format!("{chain_index} Public/{id}")),
-                    )
-                    .chain(
-                        user_data
-                            .private_key_tree
-                            .account_id_map
-                            .iter()
-                            .map(|(id, chain_index)| format!("{chain_index} Private/{id}")),
-                    )
-                    .format(",\n");
-
-                println!("{accounts}");
+            AccountSubcommand::List { all, verify } => {
+                let output = account_list(wallet_core, all, verify).await?;
+                if verify {
+                    // Verification advances the trusted checkpoint
+                    wallet_core.store_persistent_data().await?;
+                }
+                print_output(wallet_core.output_format, &output)?;
                 Ok(SubcommandReturnValue::Empty)
             }
+            AccountSubcommand::History {
+                account_id: prefixed_account_id,
+                limit,
+                all,
+                direction,
+                min_amount,
+                from_block,
+                to_block,
+                program_id,
+            } => {
+                let (account_id, addr_kind) = parse_addr_with_privacy_prefix(&prefixed_account_id)?;
+                anyhow::ensure!(
+                    addr_kind == AccountPrivacyKind::Public,
+                    "History is indexed only for public accounts"
+                );
+
+                let filter = AddressTxFilter {
+                    direction: direction.map(Into::into),
+                    min_amount,
+                    from_block,
+                    to_block,
+                    program_id,
+                };
+                let limit = (!all).then(|| usize::try_from(limit).unwrap_or(usize::MAX));
+                let history = fetch_address_history(
+                    wallet_core.sequencer_client.as_ref(),
+                    account_id.parse()?,
+                    &filter,
+                    limit,
+                )
+                .await?;
+
+                print_output(
+                    wallet_core.output_format,
+                    &AccountHistoryOutput {
+                        account_id: prefixed_account_id,
+                        transactions: history.transactions.into_iter().map(Into::into).collect(),
+                        has_more: history.has_more,
+                    },
+                )?;
+                Ok(SubcommandReturnValue::Empty)
+            }
+        }
+    }
+}
+
+fn is_change_address(account: &OwnedAccount) -> bool {
+    account
+        .chain_index
+        .as_ref()
+        .and_then(ChainIndex::as_chain_address)
+        .is_some_and(|(_, chain, _)| chain == AddressChain::Internal)
+}
+
+/// Accounts of `account list`, `all` to also include empty change addresses
+/// Accounts of the wallet with their states, public ones are proven if `verify` is set, see
+/// [`crate::light_client`]
+async fn account_list(
+    wallet_core: &WalletCore,
+    all: bool,
+    verify: bool,
+) -> Result<AccountListOutput> {
+    let owned = wallet_core.list_accounts();
+    let states = account_states(wallet_core, &owned.iter().collect::<Vec<_>>(), verify).await;
+    let mut accounts = vec![];
+    let mut failed = vec![];
+    let mut unverified = vec![];
+    for (account, state) in owned.into_iter().zip(states) {
+        let verified = (verify && account.privacy == AccountPrivacyKind::Public)
+            .then(|| !matches!(&state, Err(err) if err.is::<VerificationError>()));
+        let state = match state {
+            Ok(state) => Some(state),
+            Err(err) if verified == Some(false) => {
+                unverified.push(AccountFetchFailure::new(&account, &err));
+                None
+            }
+            Err(err) => {
+                failed.push(AccountFetchFailure::new(&account, &err));
+                None
+            }
+        };
+        // Change addresses are listed only if they were used
+        if !all && is_change_address(&account) && state.as_ref() == Some(&Account::default()) {
+            continue;
         }
+
+        let used = state.as_ref().map(|state| *state != Account::default());
+        let label = wallet_core
+            .address_label(&account.account_id)
+            .map(str::to_string);
+        let balance = match state {
+            Some(state) if account.privacy == AccountPrivacyKind::Public => Some(PendingBalance {
+                confirmed: state.balance,
+                pending_outgoing: wallet_core.pending_outgoing(account.account_id).await?,
+                pending_incoming: wallet_core.pending_incoming(account.account_id).await?,
+            }),
+            _ => None,
+        };
+        accounts.push(
+            AccountListEntry::from(account)
+                .with_balance(balance)
+                .with_usage(used, label)
+                .with_verification(verified),
+        );
     }
+
+    Ok(AccountListOutput {
+        accounts,
+        failed,
+        unverified,
+    })
+}
+
+/// States of `accounts` in their order, public ones are fetched concurrently, or proven one by
+/// one if `verify` is set
+async fn account_states(
+    wallet_core: &WalletCore,
+    accounts: &[&OwnedAccount],
+    verify: bool,
+) -> Vec<Result<Account>> {
+    let public_ids = accounts
+        .iter()
+        .filter(|account| account.privacy == AccountPrivacyKind::Public)
+        .map(|account| account.account_id)
+        .collect::<Vec<_>>();
+    let public_states = if verify {
+        let mut states = vec![];
+        for account_id in public_ids {
+            states.push(wallet_core.get_account_public_verified(account_id).await);
+        }
+        states
+    } else {
+        wallet_core.get_accounts_public(&public_ids).await
+    };
+    let mut public_states = public_states.into_iter();
+
+    accounts
+        .iter()
+        .map(|account| match account.privacy {
+            AccountPrivacyKind::Public => public_states
+                .next()
+                .expect("Every public account is fetched"),
+            AccountPrivacyKind::Private => Ok(wallet_core
+                .get_account_private(&account.account_id)
+                .unwrap_or_default()),
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::cli::account::{TokedDefinitionAccountView, TokenDefinition};
+    use std::sync::Arc;
+
+    use common::fault_plan::FaultPlan;
+    use nssa::{AccountId, Amount};
+
+    use crate::{
+        api::TransferOptions,
+        cli::{
+            SubcommandReturnValue, WalletSubcommand as _,
+            account::{AccountSubcommand, TokenDefinition, account_list},
+        },
+        output::TokedDefinitionAccountView,
+        test_utils,
+    };
 
     #[test]
     fn test_invalid_utf_8_name_of_token() {
@@ -384,4 +664,159 @@ mod tests {
 
         assert_eq!(token_def_view.name, "NAME");
     }
+
+    #[tokio::test]
+    async fn test_get_and_list_query_public_accounts_from_chain() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let mut wallet_core = test_utils::mock_wallet(chain.clone(), home.path());
+        let (account_id, _) = test_utils::preconfigured_public_accounts();
+
+        AccountSubcommand::Get {
+            raw: false,
+            account_id: format!("Public/{account_id}"),
+        }
+        .handle_subcommand(&mut wallet_core)
+        .await
+        .unwrap();
+        AccountSubcommand::List {
+            all: false,
+            verify: false,
+        }
+        .handle_subcommand(&mut wallet_core)
+        .await
+        .unwrap();
+
+        chain.set_unavailable(true);
+        let result = AccountSubcommand::Get {
+            raw: false,
+            account_id: format!("Public/{account_id}"),
+        }
+        .handle_subcommand(&mut wallet_core)
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sync_private_syncs_to_last_block() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        chain.produce_block();
+        chain.produce_block();
+        let mut wallet_core = test_utils::mock_wallet(chain.clone(), home.path());
+
+        let result = AccountSubcommand::SyncPrivate {}
+            .handle_subcommand(&mut wallet_core)
+            .await
+            .unwrap();
+
+        assert!(matches!(result, SubcommandReturnValue::SyncedToBlock(3)));
+        assert_eq!(wallet_core.last_synced_block, 3);
+        assert!(wallet_core.wallet_file.exists());
+    }
+
+    #[tokio::test]
+    async fn test_new_addresses_are_used_once_paid() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let mut wallet_core = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, _) = test_utils::preconfigured_public_accounts();
+        let (_, account) = wallet_core.create_new_account_public(None);
+
+        let mut addresses = vec![];
+        for label in ["first", "second", "third"] {
+            let result = AccountSubcommand::NewAddress {
+                account: account.clone(),
+                label: Some(label.to_string()),
+                qr: true,
+            }
+            .handle_subcommand(&mut wallet_core)
+            .await
+            .unwrap();
+            let SubcommandReturnValue::RegisterAccount { account_id } = result else {
+                panic!("New address is not registered");
+            };
+            addresses.push(account_id);
+        }
+
+        wallet_core
+            .transfer(from, addresses[1], Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        chain.produce_block();
+        wallet_core.sync().await.unwrap();
+
+        let list = account_list(&wallet_core, false, false).await.unwrap();
+        let usage = addresses
+            .iter()
+            .map(|account_id| {
+                let entry = list
+                    .accounts
+                    .iter()
+                    .find(|entry| entry.account_id == format!("Public/{account_id}"))
+                    .unwrap();
+                (entry.used, entry.label.as_deref())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            usage,
+            [
+                (Some(false), Some("first")),
+                (Some(true), Some("second")),
+                (Some(false), Some("third")),
+            ]
+        );
+
+        let suggested = wallet_core
+            .suggested_receive_address(&account)
+            .await
+            .unwrap();
+        assert_eq!(
+            suggested.map(|(account_id, _)| account_id),
+            Some(addresses[2])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verified_list_flags_tampered_balance() {
+        let home = tempfile::tempdir().unwrap();
+        let (from, to) = test_utils::preconfigured_public_accounts();
+        let chain = Arc::new(
+            test_utils::funded_chain(1000)
+                .with_fault_plan(FaultPlan::default().tamper_proven_balance(from)),
+        );
+        let wallet_core = test_utils::mock_wallet(chain.clone(), home.path());
+        wallet_core
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        chain.produce_block();
+
+        let list = account_list(&wallet_core, false, true).await.unwrap();
+        let entry = |account_id: AccountId| {
+            list.accounts
+                .iter()
+                .find(|entry| entry.account_id == format!("Public/{account_id}"))
+                .unwrap()
+        };
+
+        assert_eq!(entry(to).verified, Some(true));
+        assert_eq!(entry(to).balance.as_ref().unwrap().confirmed, Amount(1100));
+        // Served balance doesn't match the proof, so it's not shown
+        assert_eq!(entry(from).verified, Some(false));
+        assert!(entry(from).balance.is_none());
+        assert_eq!(list.unverified.len(), 1);
+        assert_eq!(list.unverified[0].account_id, format!("Public/{from}"));
+        assert!(
+            list.unverified[0]
+                .error
+                .contains("doesn't match the state root")
+        );
+        assert!(list.failed.is_empty());
+
+        // Balances aren't verified without `--verify`
+        let list = account_list(&wallet_core, false, false).await.unwrap();
+        assert!(list.accounts.iter().all(|entry| entry.verified.is_none()));
+        assert!(list.unverified.is_empty());
+    }
 }
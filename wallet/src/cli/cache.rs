@@ -0,0 +1,28 @@
+use std::path::Path;
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::chain_cache::ChainCache;
+
+/// Represents CLI subcommand for the cache of chain data
+#[derive(Subcommand, Debug, Clone)]
+pub enum CacheSubcommand {
+    /// Wipe the cache, so chain data is fetched anew by the next command
+    Clear {},
+}
+
+/// Executes `subcommand` on the cache in `data_dir`, which doesn't need a wallet
+pub fn execute_cache(subcommand: CacheSubcommand, data_dir: &Path) -> Result<()> {
+    match subcommand {
+        CacheSubcommand::Clear {} => {
+            if ChainCache::clear(data_dir)? {
+                println!("Cleared chain cache");
+            } else {
+                println!("Chain cache is empty");
+            }
+        }
+    }
+
+    Ok(())
+}
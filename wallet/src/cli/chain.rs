@@ -1,20 +1,35 @@
 use anyhow::Result;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use clap::Subcommand;
+use common::{
+    block::HashableBlockData,
+    error::{BlockUnavailable, SequencerClientError},
+    transaction::EncodedTransaction,
+};
 
 use crate::{
     WalletCore,
     cli::{SubcommandReturnValue, WalletSubcommand},
+    output::{
+        BlockOutput, ChainInfoOutput, LastBlockOutput, SyncStatusOutput, TimeDisplay,
+        TransactionOutput, print_output,
+    },
 };
 
 /// Represents generic chain CLI subcommand
 #[derive(Subcommand, Debug, Clone)]
 pub enum ChainSubcommand {
+    /// Get chain parameters and versions of wallet and sequencer
+    Info {},
     /// Get current block id from sequencer
     CurrentBlockId {},
     /// Get block at id from sequencer
     Block {
         #[arg(short, long)]
         id: u64,
+        /// Show the timestamp as UTC time instead of relative to now
+        #[arg(long)]
+        utc: bool,
     },
     /// Get transaction at hash from sequencer
     Transaction {
@@ -22,6 +37,8 @@ pub enum ChainSubcommand {
         #[arg(short, long)]
         hash: String,
     },
+    /// Get progress of the sequencer catching up with stored blocks
+    SyncStatus {},
 }
 
 impl WalletSubcommand for ChainSubcommand {
@@ -30,25 +47,120 @@ impl WalletSubcommand for ChainSubcommand {
         wallet_core: &mut WalletCore,
     ) -> Result<SubcommandReturnValue> {
         match self {
+            ChainSubcommand::Info {} => {
+                let chain_info = wallet_core.get_chain_info().await?;
+                // Older sequencers don't report their version
+                let sequencer_info = wallet_core
+                    .get_sequencer_info()
+                    .await
+                    .inspect_err(|err| log::warn!("Failed to get sequencer info: {err}"))
+                    .ok();
+
+                print_output(
+                    wallet_core.output_format,
+                    &ChainInfoOutput::new(&chain_info, sequencer_info.as_ref()),
+                )?;
+            }
             ChainSubcommand::CurrentBlockId {} => {
                 let latest_block_res = wallet_core.sequencer_client.get_last_block().await?;
 
-                println!("Last block id is {}", latest_block_res.last_block);
+                print_output(
+                    wallet_core.output_format,
+                    &LastBlockOutput::from(&latest_block_res),
+                )?;
             }
-            ChainSubcommand::Block { id } => {
-                let block_res = wallet_core.sequencer_client.get_block(id).await?;
+            ChainSubcommand::Block { id, utc } => {
+                let block_res = wallet_core
+                    .sequencer_client
+                    .get_block(id)
+                    .await
+                    .map_err(|err| block_request_error(id, err))?;
+                let block: HashableBlockData = borsh::from_slice(&block_res.block)?;
+                if let Some(digest) = &block_res.digest {
+                    block.check_digest(digest)?;
+                }
 
-                println!("Last block id is {:#?}", block_res.block);
+                print_output(
+                    wallet_core.output_format,
+                    &BlockOutput::new(block, TimeDisplay::new(utc)),
+                )?;
             }
             ChainSubcommand::Transaction { hash } => {
                 let tx_res = wallet_core
                     .sequencer_client
-                    .get_transaction_by_hash(hash)
+                    .get_transaction_by_hash(hash.clone())
                     .await?;
+                let transaction: Option<EncodedTransaction> = tx_res
+                    .transaction
+                    .map(|tx_b64| -> Result<_> { Ok(borsh::from_slice(&BASE64.decode(tx_b64)?)?) })
+                    .transpose()?;
 
-                println!("Last block id is {:#?}", tx_res.transaction);
+                print_output(
+                    wallet_core.output_format,
+                    &TransactionOutput::new(hash, transaction.as_ref()),
+                )?;
+            }
+            ChainSubcommand::SyncStatus {} => {
+                let status = wallet_core.sequencer_client.get_sync_status().await?.status;
+
+                print_output(wallet_core.output_format, &SyncStatusOutput::from(status))?;
             }
         }
         Ok(SubcommandReturnValue::Empty)
     }
 }
+
+/// Explains why sequencer didn't serve block `block_id`, if it reported the reason
+fn block_request_error(block_id: u64, err: SequencerClientError) -> anyhow::Error {
+    match err.block_unavailable() {
+        Some(BlockUnavailable::NotYetProduced { tip }) => {
+            anyhow::anyhow!("Block {block_id} is not produced yet, the last block is {tip}")
+        }
+        Some(BlockUnavailable::Pruned { earliest_available }) => anyhow::anyhow!(
+            "Block {block_id} is pruned by sequencer, the earliest available block is \
+             {earliest_available}"
+        ),
+        Some(BlockUnavailable::Missing) => anyhow::anyhow!(
+            "Block {block_id} is missing from sequencer storage, please report it to the \
+             sequencer operator"
+        ),
+        None => err.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common::mock_chain::MockChain;
+
+    use super::*;
+    use crate::test_utils;
+
+    #[tokio::test]
+    async fn test_unserved_blocks_are_explained() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(MockChain::new(&[]));
+        chain.produce_block();
+        chain.produce_block();
+        chain.lose_block(2);
+        let mut wallet_core = test_utils::mock_wallet(chain, home.path());
+
+        let mut block_error = async |id| {
+            ChainSubcommand::Block { id, utc: false }
+                .handle_subcommand(&mut wallet_core)
+                .await
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            block_error(2).await,
+            "Block 2 is missing from sequencer storage, please report it to the sequencer operator"
+        );
+        assert_eq!(
+            block_error(999_999).await,
+            "Block 999999 is not produced yet, the last block is 3"
+        );
+    }
+}
@@ -71,6 +71,64 @@ impl WalletSubcommand for ConfigSubcommand {
                         println!("Not set");
                     }
                 }
+                "fallback_sequencer_addrs" => {
+                    println!(
+                        "{}",
+                        wallet_core
+                            .storage
+                            .wallet_config
+                            .fallback_sequencer_addrs
+                            .join(",")
+                    );
+                }
+                "seq_failover_after_failures" => {
+                    println!(
+                        "{}",
+                        wallet_core
+                            .storage
+                            .wallet_config
+                            .seq_failover_after_failures
+                    );
+                }
+                "seq_reconnect_max_delay_millis" => {
+                    println!(
+                        "{}",
+                        wallet_core
+                            .storage
+                            .wallet_config
+                            .seq_reconnect_max_delay_millis
+                    );
+                }
+                "account_fetch_concurrency" => {
+                    println!(
+                        "{}",
+                        wallet_core.storage.wallet_config.account_fetch_concurrency
+                    );
+                }
+                "prover_backend" => {
+                    println!(
+                        "{}",
+                        serde_json::to_value(wallet_core.storage.wallet_config.prover_backend)?
+                    );
+                }
+                "mempool_mirror_max_age_blocks" => {
+                    if let Some(max_age) = wallet_core
+                        .storage
+                        .wallet_config
+                        .mempool_mirror_max_age_blocks
+                    {
+                        println!("{max_age}");
+                    } else {
+                        println!("Not set");
+                    }
+                }
+                "admin_auth_token" => {
+                    if let Some(token) = &wallet_core.storage.wallet_config.admin_auth_token {
+                        println!("{token}");
+                    } else {
+                        println!("Not set");
+                    }
+                }
                 _ => {
                     println!("Unknown field");
                 }
@@ -100,6 +158,44 @@ impl WalletSubcommand for ConfigSubcommand {
                     "basic_auth" => {
                         wallet_core.storage.wallet_config.basic_auth = Some(value.parse()?);
                     }
+                    "fallback_sequencer_addrs" => {
+                        wallet_core.storage.wallet_config.fallback_sequencer_addrs = value
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|addr| !addr.is_empty())
+                            .map(str::to_string)
+                            .collect();
+                    }
+                    "seq_failover_after_failures" => {
+                        wallet_core
+                            .storage
+                            .wallet_config
+                            .seq_failover_after_failures = value.parse()?;
+                    }
+                    "seq_reconnect_max_delay_millis" => {
+                        wallet_core
+                            .storage
+                            .wallet_config
+                            .seq_reconnect_max_delay_millis = value.parse()?;
+                    }
+                    "account_fetch_concurrency" => {
+                        let concurrency = value.parse()?;
+                        anyhow::ensure!(concurrency > 0, "Concurrency must be positive");
+                        wallet_core.storage.wallet_config.account_fetch_concurrency = concurrency;
+                    }
+                    "prover_backend" => {
+                        wallet_core.storage.wallet_config.prover_backend =
+                            serde_json::from_value(serde_json::Value::String(value))?;
+                    }
+                    "mempool_mirror_max_age_blocks" => {
+                        wallet_core
+                            .storage
+                            .wallet_config
+                            .mempool_mirror_max_age_blocks = Some(value.parse()?);
+                    }
+                    "admin_auth_token" => {
+                        wallet_core.storage.wallet_config.admin_auth_token = Some(value);
+                    }
                     "initial_accounts" => {
                         anyhow::bail!("Setting this field from wallet is not supported");
                     }
@@ -145,6 +241,41 @@ impl WalletSubcommand for ConfigSubcommand {
                 "basic_auth" => {
                     println!("Basic authentication credentials for sequencer HTTP requests");
                 }
+                "fallback_sequencer_addrs" => {
+                    println!(
+                        "Comma separated sequencer URLs to fail over to in continuous run, they must serve the same chain"
+                    );
+                }
+                "seq_failover_after_failures" => {
+                    println!(
+                        "Continuous run variable: number of consecutive connection failures before failing over to the next sequencer"
+                    );
+                }
+                "seq_reconnect_max_delay_millis" => {
+                    println!(
+                        "Continuous run variable: max delay between reconnection attempts in milliseconds"
+                    );
+                }
+                "account_fetch_concurrency" => {
+                    println!(
+                        "Max number of account requests in flight while listing or rescanning accounts"
+                    );
+                }
+                "prover_backend" => {
+                    println!(
+                        "Backend proving privacy preserving transactions: `real`, or `dev_fake`, which only executes and is accepted by local chains only"
+                    );
+                }
+                "mempool_mirror_max_age_blocks" => {
+                    println!(
+                        "Mirror pending transactions of the sequencer for nonce conflict warnings and priority fee estimation, ignoring ones pending for more than this number of blocks"
+                    );
+                }
+                "admin_auth_token" => {
+                    println!(
+                        "Bearer token of the sequencer admin RPC, lets `tx status` of a single transaction show its mempool lifecycle events"
+                    );
+                }
                 _ => {
                     println!("Unknown field");
                 }
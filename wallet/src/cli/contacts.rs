@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use clap::Subcommand;
+use itertools::Itertools as _;
+use nssa::AccountId;
+
+use crate::{
+    WalletCore,
+    address_book::{
+        AddressBook, Contact, ContactsFileFormat, ResolvedAddress, contacts_from_csv,
+        contacts_from_json, contacts_to_csv, contacts_to_json, is_address,
+    },
+    helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
+    output::{ContactListOutput, OutputFormat, print_output},
+    paths::write_private,
+};
+
+/// Represents CLI subcommand for the address book
+#[derive(Subcommand, Debug, Clone)]
+pub enum ContactsSubcommand {
+    /// Add a named address, which can be used instead of the address, e.g. `--to alice`
+    Add {
+        /// Name of the contact
+        name: String,
+        /// address - valid 32 byte base58 string with privacy prefix
+        #[arg(long)]
+        address: String,
+        /// Free-form note about the contact
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Remove a contact
+    Remove {
+        /// Name of the contact
+        name: String,
+    },
+    /// List all contacts
+    #[command(visible_alias = "ls")]
+    List {},
+    /// Import contacts from a file, replacing contacts with the same names
+    ///
+    /// Nothing is imported if any contact of the file is invalid.
+    Import {
+        /// Path of the file with contacts
+        file: PathBuf,
+        /// Format of the file, defaults to CSV for `.csv` files and to JSON otherwise
+        #[arg(long, value_enum)]
+        format: Option<ContactsFileFormat>,
+    },
+    /// Export all contacts to a file
+    Export {
+        /// Path of the file to write contacts to
+        file: PathBuf,
+        /// Format of the file, defaults to CSV for `.csv` files and to JSON otherwise
+        #[arg(long, value_enum)]
+        format: Option<ContactsFileFormat>,
+    },
+}
+
+/// Executes `subcommand` on `address_book`, which doesn't need a wallet
+pub fn execute_contacts(
+    subcommand: ContactsSubcommand,
+    address_book: &AddressBook,
+    output_format: OutputFormat,
+) -> Result<()> {
+    match subcommand {
+        ContactsSubcommand::Add {
+            name,
+            address,
+            note,
+        } => {
+            let contact =
+                Contact::new(&name, &address, note.as_deref()).map_err(anyhow::Error::msg)?;
+            if is_address(&contact.name) {
+                eprintln!(
+                    "WARNING: Contact name `{}` is an address, so the contact is never resolved \
+                     by it",
+                    contact.name
+                );
+            }
+            address_book.add(contact)?;
+
+            println!("Added contact {name}");
+        }
+        ContactsSubcommand::Remove { name } => {
+            anyhow::ensure!(address_book.remove(&name)?, "Contact `{name}` not found");
+
+            println!("Removed contact {name}");
+        }
+        ContactsSubcommand::List {} => {
+            print_output(
+                output_format,
+                &ContactListOutput {
+                    contacts: address_book.contacts()?,
+                },
+            )?;
+        }
+        ContactsSubcommand::Import { file, format } => {
+            let contents = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read contacts file {}", file.display()))?;
+            let contacts = match format.unwrap_or_else(|| ContactsFileFormat::from_path(&file)) {
+                ContactsFileFormat::Csv => contacts_from_csv(&contents).map_err(|errors| {
+                    anyhow::anyhow!(
+                        "Invalid contacts file {}:\n{}",
+                        file.display(),
+                        errors.iter().join("\n")
+                    )
+                })?,
+                ContactsFileFormat::Json => contacts_from_json(&contents)
+                    .with_context(|| format!("Invalid contacts file {}", file.display()))?,
+            };
+            let num_imported = contacts.len();
+            let num_replaced = address_book.import(contacts)?;
+
+            println!(
+                "Imported {num_imported} contacts from {}, {num_replaced} of them replaced \
+                 known ones",
+                file.display()
+            );
+        }
+        ContactsSubcommand::Export { file, format } => {
+            let contacts = address_book.contacts()?;
+            let contents = match format.unwrap_or_else(|| ContactsFileFormat::from_path(&file)) {
+                ContactsFileFormat::Csv => contacts_to_csv(&contacts),
+                ContactsFileFormat::Json => contacts_to_json(&contacts)?,
+            };
+            write_private(&file, contents)
+                .with_context(|| format!("Failed to write contacts to {}", file.display()))?;
+
+            println!("Exported {} contacts to {}", contacts.len(), file.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves `input`, which is either an address with privacy prefix or a contact name, warning
+/// if it shadows a contact
+pub(crate) fn resolve_recipient(wallet_core: &WalletCore, input: &str) -> Result<ResolvedAddress> {
+    let resolved = wallet_core.address_book.resolve(input)?;
+    if let Some(shadowed) = &resolved.shadowed_contact {
+        eprintln!(
+            "WARNING: `{shadowed}` is used as an address, not as the contact with the same name"
+        );
+    }
+    Ok(resolved)
+}
+
+/// Resolves `input`, which is either a public account id without privacy prefix or a contact
+/// name with a public address
+pub(crate) fn resolve_public_recipient(wallet_core: &WalletCore, input: &str) -> Result<AccountId> {
+    if let Ok(account_id) = input.parse() {
+        return Ok(account_id);
+    }
+
+    let resolved = resolve_recipient(wallet_core, input)?;
+    match parse_addr_with_privacy_prefix(&resolved.address)? {
+        (account_id, AccountPrivacyKind::Public) => Ok(account_id.parse()?),
+        (_, AccountPrivacyKind::Private) => {
+            anyhow::bail!("Only public accounts are supported, got {resolved}")
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    os::unix::process::CommandExt as _,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use clap::Subcommand;
+
+use crate::{
+    WalletCore,
+    cli::Command,
+    daemon::{DaemonClient, DaemonRequest, DaemonResponse, serve, socket_path},
+    helperfunctions::{fetch_config, get_wallet_file, merge_auth_config},
+    output::{DaemonStatusOutput, OutputFormat, print_output},
+    paths::private_open_options,
+    wallet_lock::WalletLock,
+};
+
+/// Delay between checks whether the daemon started or stopped
+const DAEMON_POLL_DELAY: Duration = Duration::from_millis(100);
+
+/// Time the daemon is given to store the wallet and exit after it's stopped
+const DAEMON_STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Represents CLI subcommand for the wallet daemon
+#[derive(Subcommand, Debug, Clone)]
+pub enum DaemonSubcommand {
+    /// Start a background daemon, which keeps the wallet synced
+    ///
+    /// While it runs, `account get`, `account list`, `chain-info`, `keys` and `tx status` are
+    /// executed by the daemon, other commands using the wallet are refused.
+    Start {},
+    /// Stop the daemon, once it stores the wallet
+    Stop {},
+    /// Show whether the daemon is running
+    Status {},
+    /// Run the daemon in foreground, used by `daemon start`
+    #[command(hide = true)]
+    Run {},
+}
+
+/// Executes `subcommand`, which manages the daemon of the resolved wallet file
+pub async fn execute_daemon(
+    subcommand: DaemonSubcommand,
+    auth: Option<String>,
+    output_format: OutputFormat,
+) -> Result<()> {
+    let wallet_file = get_wallet_file()?;
+    match subcommand {
+        DaemonSubcommand::Start {} => {
+            anyhow::ensure!(
+                DaemonClient::connect(&wallet_file).await.is_none(),
+                "Daemon of wallet {} is already running",
+                wallet_file.display()
+            );
+            let client = start_daemon(&wallet_file, auth).await?;
+            print_output(output_format, &daemon_status(Some(client)).await?)?;
+        }
+        DaemonSubcommand::Stop {} => {
+            let client = DaemonClient::connect(&wallet_file)
+                .await
+                .context("Daemon is not running")?;
+            match client.request(&DaemonRequest::Stop).await? {
+                DaemonResponse::Stopping => {}
+                response => anyhow::bail!("Unexpected response of daemon {response:?}"),
+            }
+            wait_for_lock_release(&wallet_file).await?;
+
+            println!("Daemon stopped");
+        }
+        DaemonSubcommand::Status {} => {
+            let client = DaemonClient::connect(&wallet_file).await;
+            print_output(output_format, &daemon_status(client).await?)?;
+        }
+        DaemonSubcommand::Run {} => {
+            let _wallet_lock = WalletLock::acquire(&wallet_file)?;
+
+            let config = fetch_config().await?;
+            let config = merge_auth_config(config, auth)?;
+            let wallet_core = WalletCore::start_from_config_update_chain(config).await?;
+
+            let socket_path = socket_path(&wallet_file);
+            eprintln!("Daemon is listening on {}", socket_path.display());
+            serve(wallet_core, &socket_path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Executes `command` by the running daemon of the wallet, given the whole command line `args`
+///
+/// Returns `false` if the command is to be executed directly, because there is no daemon or the
+/// command doesn't need the wallet. Fails on commands, which need the wallet, but aren't executed
+/// by the daemon, as the daemon holds the wallet lock.
+pub async fn route_through_daemon(command: &Command, args: Vec<String>) -> Result<bool> {
+    if !command.needs_wallet() {
+        return Ok(false);
+    }
+    let Some(client) = DaemonClient::connect(&get_wallet_file()?).await else {
+        return Ok(false);
+    };
+    anyhow::ensure!(
+        command.is_routed_through_daemon(),
+        "Wallet daemon is running and doesn't execute this command, stop it with `wallet daemon \
+         stop` first"
+    );
+
+    match client.request(&DaemonRequest::Run { args }).await? {
+        DaemonResponse::Output { stdout } => print!("{stdout}"),
+        DaemonResponse::Error { message } => anyhow::bail!(message),
+        response => anyhow::bail!("Unexpected response of daemon {response:?}"),
+    }
+    Ok(true)
+}
+
+/// Spawns a detached `daemon run` process, logging to `<wallet file>.daemon.log`, and waits
+/// until it listens
+async fn start_daemon(wallet_file: &Path, auth: Option<String>) -> Result<DaemonClient> {
+    let log_path = daemon_log_path(wallet_file);
+    let log = private_open_options()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Failed to open daemon log {}", log_path.display()))?;
+
+    let mut daemon = std::process::Command::new(std::env::current_exe()?);
+    daemon.args(["daemon", "run"]);
+    if let Some(auth) = auth {
+        daemon.args(["--auth", &auth]);
+    }
+    // Own process group keeps the daemon alive after terminal interrupts
+    let mut daemon = daemon
+        .env(crate::WALLET_FILE_ENV_VAR, wallet_file)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .process_group(0)
+        .spawn()
+        .context("Failed to spawn daemon")?;
+
+    loop {
+        if let Some(status) = daemon.try_wait()? {
+            anyhow::bail!(
+                "Daemon exited with {status}, see {} for details",
+                log_path.display()
+            );
+        }
+        if let Some(client) = DaemonClient::connect(wallet_file).await {
+            return Ok(client);
+        }
+        tokio::time::sleep(DAEMON_POLL_DELAY).await;
+    }
+}
+
+async fn daemon_status(client: Option<DaemonClient>) -> Result<DaemonStatusOutput> {
+    let Some(client) = client else {
+        return Ok(DaemonStatusOutput {
+            running: false,
+            pid: None,
+            last_synced_block: None,
+        });
+    };
+
+    match client.request(&DaemonRequest::Status).await? {
+        DaemonResponse::Status {
+            pid,
+            last_synced_block,
+        } => Ok(DaemonStatusOutput {
+            running: true,
+            pid: Some(pid),
+            last_synced_block: Some(last_synced_block),
+        }),
+        response => anyhow::bail!("Unexpected response of daemon {response:?}"),
+    }
+}
+
+/// Waits until the stopped daemon releases the wallet lock
+async fn wait_for_lock_release(wallet_file: &Path) -> Result<()> {
+    let deadline = tokio::time::Instant::now() + DAEMON_STOP_TIMEOUT;
+    loop {
+        match WalletLock::acquire(wallet_file) {
+            Ok(_lock) => return Ok(()),
+            Err(err) if tokio::time::Instant::now() >= deadline => {
+                return Err(err.context("Daemon didn't exit in time"));
+            }
+            Err(_) => tokio::time::sleep(DAEMON_POLL_DELAY).await,
+        }
+    }
+}
+
+fn daemon_log_path(wallet_file: &Path) -> PathBuf {
+    let mut path = wallet_file.as_os_str().to_owned();
+    path.push(".daemon.log");
+    PathBuf::from(path)
+}
@@ -0,0 +1,343 @@
+use std::{
+    fmt::Write as _,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use bip39::Mnemonic;
+use key_protocol::key_management::secret_holders::SeedHolder;
+use rand::{Rng, rngs::OsRng};
+use zeroize::Zeroize as _;
+
+use crate::{
+    chain_storage::WalletChainStore,
+    config::WalletConfig,
+    helperfunctions::{
+        create_persistent_storage, fetch_config, merge_auth_config, produce_data_for_storage,
+    },
+    wallet_lock::WalletLock,
+};
+
+/// Number of words of the backup sheet, which user re-enters to confirm the backup
+pub const CONFIRMATION_WORDS: usize = 3;
+
+/// Number of columns of words on the backup sheet
+const BACKUP_SHEET_COLUMNS: usize = 4;
+
+/// How the mnemonic is backed up before the wallet is created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backup {
+    /// Show the backup sheet and ask for some of its words
+    Confirm,
+    /// Show the backup sheet without asking anything
+    Show,
+    /// Mnemonic is entered by user, so it's backed up already
+    Restored,
+}
+
+/// Create a new wallet file at `wallet_file`
+///
+/// Keys are derived from a mnemonic generated from OS randomness, which is shown as a backup
+/// sheet. Nothing is written until user re-enters some words of the sheet, unless `no_confirm`
+/// is set. With `from_mnemonic` the mnemonic is read from stdin instead, to recover a wallet.
+pub async fn execute_init_with_auth(
+    wallet_file: PathBuf,
+    no_confirm: bool,
+    from_mnemonic: bool,
+    auth: Option<String>,
+) -> Result<()> {
+    let _wallet_lock = WalletLock::acquire(&wallet_file)?;
+
+    if tokio::fs::try_exists(&wallet_file).await? {
+        anyhow::bail!(
+            "Wallet file {} already exists, refusing to overwrite it",
+            wallet_file.display()
+        );
+    }
+
+    let config = fetch_config().await?;
+    let config = merge_auth_config(config, auth)?;
+
+    let mut input = std::io::BufReader::new(std::io::stdin());
+    let mut output = std::io::stderr();
+    let (mnemonic, backup) = if from_mnemonic {
+        (read_mnemonic(&mut input, &mut output)?, Backup::Restored)
+    } else if no_confirm {
+        (SeedHolder::generate_mnemonic(), Backup::Show)
+    } else {
+        (SeedHolder::generate_mnemonic(), Backup::Confirm)
+    };
+
+    init_wallet(
+        &wallet_file,
+        config,
+        &mnemonic,
+        backup,
+        &mut input,
+        &mut output,
+        &mut OsRng,
+    )
+    .await?;
+
+    println!("Created wallet at {}", wallet_file.display());
+
+    Ok(())
+}
+
+/// Backs up `mnemonic` and only then writes the wallet file with keys derived from it
+async fn init_wallet(
+    wallet_file: &Path,
+    config: WalletConfig,
+    mnemonic: &Mnemonic,
+    backup: Backup,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    rng: &mut impl Rng,
+) -> Result<()> {
+    match backup {
+        Backup::Confirm => confirm_backup(mnemonic, input, output, rng)
+            .context("Backup is not confirmed, wallet is not created")?,
+        Backup::Show => write_backup_sheet(mnemonic, output)?,
+        Backup::Restored => {}
+    }
+
+    let seed = SeedHolder::from_mnemonic(mnemonic);
+    let storage = WalletChainStore::new_storage_from_seed(config, &seed)?;
+    let data = produce_data_for_storage(&storage.user_data, 0);
+    create_persistent_storage(wallet_file, &data).await
+}
+
+/// Shows the backup sheet and asks for [`CONFIRMATION_WORDS`] random words of it, until all of
+/// them are entered correctly
+fn confirm_backup(
+    mnemonic: &Mnemonic,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    rng: &mut impl Rng,
+) -> Result<()> {
+    let words = mnemonic.words().collect::<Vec<_>>();
+    loop {
+        write_backup_sheet(mnemonic, output)?;
+        writeln!(
+            output,
+            "Write the words down, they are not shown again. To confirm the backup, enter some of \
+             them."
+        )?;
+
+        let mut confirmed = true;
+        for position in challenge_positions(words.len(), rng) {
+            write!(output, "Word #{}: ", position + 1)?;
+            let mut answer = read_line(input, output)?;
+            confirmed &= answer.trim() == words[position];
+            answer.zeroize();
+        }
+        if confirmed {
+            return Ok(());
+        }
+
+        writeln!(
+            output,
+            "Words don't match the backup sheet, check it and try again"
+        )?;
+    }
+}
+
+/// Sorted distinct positions of words to ask for
+fn challenge_positions(word_count: usize, rng: &mut impl Rng) -> Vec<usize> {
+    let mut positions = rand::seq::index::sample(rng, word_count, CONFIRMATION_WORDS).into_vec();
+    positions.sort_unstable();
+    positions
+}
+
+fn write_backup_sheet(mnemonic: &Mnemonic, output: &mut impl Write) -> Result<()> {
+    let words = mnemonic.words().collect::<Vec<_>>();
+    let rows = words.len().div_ceil(BACKUP_SHEET_COLUMNS);
+
+    let mut sheet = String::new();
+    writeln!(
+        sheet,
+        "==================== WALLET BACKUP SHEET ===================="
+    )?;
+    writeln!(
+        sheet,
+        "Created {}. Anyone with these words controls the wallet.",
+        chrono::Utc::now().format("%Y-%m-%d")
+    )?;
+    writeln!(sheet)?;
+    for row in 0..rows {
+        for column in 0..BACKUP_SHEET_COLUMNS {
+            let position = column * rows + row;
+            if let Some(word) = words.get(position) {
+                write!(sheet, "{:>3}. {word:<10}", position + 1)?;
+            }
+        }
+        writeln!(sheet)?;
+    }
+    writeln!(sheet)?;
+    writeln!(
+        sheet,
+        "============================================================="
+    )?;
+
+    let written = output.write_all(sheet.as_bytes());
+    sheet.zeroize();
+    Ok(written?)
+}
+
+fn read_mnemonic(input: &mut impl BufRead, output: &mut impl Write) -> Result<Mnemonic> {
+    write!(output, "Input mnemonic: ")?;
+    let mut phrase = read_line(input, output)?;
+    let mnemonic = Mnemonic::parse(phrase.trim()).context("Invalid mnemonic");
+    phrase.zeroize();
+    mnemonic
+}
+
+fn read_line(input: &mut impl BufRead, output: &mut impl Write) -> Result<String> {
+    output.flush()?;
+
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        anyhow::bail!("Input ended unexpectedly");
+    }
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng as _, rngs::StdRng};
+
+    use super::*;
+    use crate::config::PersistentStorage;
+
+    fn stored_account_ids(wallet_file: &Path) -> Vec<nssa::AccountId> {
+        let storage: PersistentStorage =
+            serde_json::from_slice(&std::fs::read(wallet_file).unwrap()).unwrap();
+        let mut account_ids = storage
+            .accounts
+            .iter()
+            .map(|account| account.account_id())
+            .collect::<Vec<_>>();
+        account_ids.sort();
+        account_ids
+    }
+
+    fn config() -> WalletConfig {
+        WalletConfig {
+            initial_accounts: vec![],
+            ..WalletConfig::default()
+        }
+    }
+
+    /// Answers for confirmation rounds, the first `failed_rounds` of which are wrong
+    fn answers(mnemonic: &Mnemonic, rng: &StdRng, rounds: usize, failed_rounds: usize) -> String {
+        let words = mnemonic.words().collect::<Vec<_>>();
+        let mut rng = rng.clone();
+        let mut answers = String::new();
+        for round in 0..rounds {
+            for position in challenge_positions(words.len(), &mut rng) {
+                let word = if round < failed_rounds {
+                    "wrong"
+                } else {
+                    words[position]
+                };
+                answers.push_str(word);
+                answers.push('\n');
+            }
+        }
+        answers
+    }
+
+    #[tokio::test]
+    async fn test_failed_confirmation_restarts_prompt() {
+        let home = tempfile::tempdir().unwrap();
+        let wallet_file = home.path().join("storage.json");
+        let mnemonic = Mnemonic::from_entropy(&[7; 32]).unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+        let input = answers(&mnemonic, &rng, 2, 1);
+        let mut output = vec![];
+
+        init_wallet(
+            &wallet_file,
+            config(),
+            &mnemonic,
+            Backup::Confirm,
+            &mut input.as_bytes(),
+            &mut output,
+            &mut rng,
+        )
+        .await
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("WALLET BACKUP SHEET").count(), 2);
+        assert_eq!(output.matches("Words don't match").count(), 1);
+        assert!(!stored_account_ids(&wallet_file).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_nothing_is_persisted_without_confirmation() {
+        let home = tempfile::tempdir().unwrap();
+        let wallet_file = home.path().join("storage.json");
+        let mnemonic = Mnemonic::from_entropy(&[7; 32]).unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+        let input = answers(&mnemonic, &rng, 1, 1);
+
+        let err = init_wallet(
+            &wallet_file,
+            config(),
+            &mnemonic,
+            Backup::Confirm,
+            &mut input.as_bytes(),
+            &mut vec![],
+            &mut rng,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Backup is not confirmed, wallet is not created"
+        );
+        assert!(!wallet_file.exists());
+    }
+
+    #[tokio::test]
+    async fn test_restored_wallet_has_keys_of_backed_up_one() {
+        let home = tempfile::tempdir().unwrap();
+        let mnemonic = Mnemonic::from_entropy(&[7; 32]).unwrap();
+        let backed_up_file = home.path().join("backed_up.json");
+        let restored_file = home.path().join("restored.json");
+
+        init_wallet(
+            &backed_up_file,
+            config(),
+            &mnemonic,
+            Backup::Show,
+            &mut "".as_bytes(),
+            &mut vec![],
+            &mut OsRng,
+        )
+        .await
+        .unwrap();
+        let mut input = format!("{mnemonic}\n");
+        let restored_mnemonic = read_mnemonic(&mut input.as_bytes(), &mut vec![]).unwrap();
+        input.zeroize();
+        init_wallet(
+            &restored_file,
+            config(),
+            &restored_mnemonic,
+            Backup::Restored,
+            &mut "".as_bytes(),
+            &mut vec![],
+            &mut OsRng,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            stored_account_ids(&backed_up_file),
+            stored_account_ids(&restored_file)
+        );
+    }
+}
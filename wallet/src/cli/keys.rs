@@ -0,0 +1,69 @@
+use anyhow::{Result, anyhow};
+use clap::Subcommand;
+use key_protocol::key_management::secret_holders::SeedHolder;
+
+use crate::{
+    WalletCore,
+    cli::{SubcommandReturnValue, WalletSubcommand, read_password_from_stdin},
+    output::{KeyAuditOutput, print_output},
+};
+
+/// Represents generic CLI subcommand for stored keys
+#[derive(Subcommand, Debug, Clone)]
+pub enum KeysSubcommand {
+    /// Check that stored keys are consistent and derive addresses of their accounts
+    ///
+    /// Fails if any problem is found.
+    Audit {
+        /// Re-derive corrupted keys of the key trees from the wallet password
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+impl WalletSubcommand for KeysSubcommand {
+    async fn handle_subcommand(
+        self,
+        wallet_core: &mut WalletCore,
+    ) -> Result<SubcommandReturnValue> {
+        match self {
+            KeysSubcommand::Audit { repair } => {
+                let issues = wallet_core.storage.user_data.audit();
+                let mut output = KeyAuditOutput {
+                    issues: issues.iter().map(Into::into).collect(),
+                    repaired: vec![],
+                    remaining: issues.iter().map(Into::into).collect(),
+                };
+
+                if repair && !issues.is_empty() {
+                    let password = read_password_from_stdin()?;
+                    let seed = SeedHolder::new_mnemonic(password);
+                    let repaired = wallet_core.storage.user_data.repair(&seed)?;
+                    if !repaired.is_empty() {
+                        wallet_core.store_persistent_data().await?;
+                    }
+
+                    output.repaired = repaired.iter().map(ToString::to_string).collect();
+                    output.remaining = wallet_core
+                        .storage
+                        .user_data
+                        .audit()
+                        .iter()
+                        .map(Into::into)
+                        .collect();
+                }
+
+                print_output(wallet_core.output_format, &output)?;
+
+                if !output.remaining.is_empty() {
+                    return Err(anyhow!(
+                        "Key audit found {} problems",
+                        output.remaining.len()
+                    ));
+                }
+            }
+        }
+
+        Ok(SubcommandReturnValue::Empty)
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{
+    config::{PersistentAccountData, PersistentStorage},
+    keystore::{KEYSTORE_FORMAT_VERSION, inspect_keystore},
+    output::{KeystoreContentsOutput, KeystoreInfoOutput, OutputFormat, print_output},
+};
+
+/// Represents CLI subcommand for the format of the wallet file
+#[derive(Subcommand, Debug, Clone)]
+pub enum KeystoreSubcommand {
+    /// Print format version of the wallet file and a summary of its contents
+    ///
+    /// The file is only read, also if its version is newer than the wallet supports.
+    Info {},
+}
+
+/// Executes `subcommand` on the wallet file at `wallet_file`, which doesn't need a wallet
+pub fn execute_keystore(
+    subcommand: KeystoreSubcommand,
+    wallet_file: &Path,
+    output_format: OutputFormat,
+) -> Result<()> {
+    match subcommand {
+        KeystoreSubcommand::Info {} => {
+            let inspection = inspect_keystore(wallet_file)?;
+            print_output(
+                output_format,
+                &KeystoreInfoOutput {
+                    wallet_file: wallet_file.display().to_string(),
+                    format_version: inspection.format_version,
+                    supported_format_version: KEYSTORE_FORMAT_VERSION,
+                    contents: inspection.storage.as_ref().map(contents_summary),
+                },
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn contents_summary(storage: &PersistentStorage) -> KeystoreContentsOutput {
+    let count = |is_kind: fn(&PersistentAccountData) -> bool| {
+        storage
+            .accounts
+            .iter()
+            .filter(|account| is_kind(account))
+            .count()
+    };
+
+    KeystoreContentsOutput {
+        last_synced_block: storage.last_synced_block,
+        public_accounts: count(|account| matches!(account, PersistentAccountData::Public(_))),
+        private_accounts: count(|account| matches!(account, PersistentAccountData::Private(_))),
+        preconfigured_accounts: count(|account| {
+            matches!(account, PersistentAccountData::Preconfigured(_))
+        }),
+        labels: storage.address_labels.len(),
+        trusted_checkpoint_block: storage
+            .trusted_checkpoint
+            .as_ref()
+            .map(|trusted| trusted.checkpoint.block_id),
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context as _, Result};
+use nssa::{AccountId, signature::MessageSignature};
+
+use crate::{
+    WalletCore,
+    helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
+    output::{MessageSignatureOutput, MessageVerificationOutput, OutputFormat, print_output},
+};
+
+/// Signs `message` with the key of public account `address`, see [`MessageSignature`]
+pub fn execute_sign_message(wallet_core: &WalletCore, address: &str, message: &str) -> Result<()> {
+    let account_id = parse_signing_account_id(address)?;
+    let key = wallet_core
+        .get_account_public_signing_key(&account_id)
+        .with_context(|| format!("Signing key of account {address} not found in storage"))?;
+
+    let signature = MessageSignature::sign(key, message.as_bytes());
+    print_output(
+        wallet_core.output_format,
+        &MessageSignatureOutput {
+            address: address.to_string(),
+            signature: signature.to_base64(),
+        },
+    )
+}
+
+/// Prints whether `signature` of `message` is made by public account `address`, failing if it
+/// isn't. Doesn't need a wallet.
+pub fn execute_verify_message(
+    address: &str,
+    message: &str,
+    signature: &str,
+    output_format: OutputFormat,
+) -> Result<()> {
+    let account_id = parse_signing_account_id(address)?;
+    let signature = MessageSignature::from_base64(signature)?;
+
+    let valid = signature.is_valid_for(message.as_bytes(), &account_id);
+    print_output(
+        output_format,
+        &MessageVerificationOutput {
+            address: address.to_string(),
+            valid,
+        },
+    )?;
+    anyhow::ensure!(valid, "Signature is not made by {address}");
+    Ok(())
+}
+
+/// Private accounts have no signing keys, so only public ones sign messages
+fn parse_signing_account_id(address: &str) -> Result<AccountId> {
+    match parse_addr_with_privacy_prefix(address)? {
+        (account_id, AccountPrivacyKind::Public) => Ok(account_id.parse()?),
+        (_, AccountPrivacyKind::Private) => {
+            anyhow::bail!("Only public accounts sign messages, got private account {address}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_message_checks_address_and_message() {
+        let key = nssa::PrivateKey::try_new([1; 32]).unwrap();
+        let signature = MessageSignature::sign(&key, b"challenge");
+        let address = format!("Public/{}", signature.account_id());
+        let encoded = signature.to_base64();
+
+        execute_verify_message(&address, "challenge", &encoded, OutputFormat::Json).unwrap();
+        assert!(execute_verify_message(&address, "other", &encoded, OutputFormat::Json).is_err());
+        assert!(
+            execute_verify_message(
+                &format!("Public/{}", AccountId::new([0; 32])),
+                "challenge",
+                &encoded,
+                OutputFormat::Json
+            )
+            .is_err()
+        );
+        assert!(
+            execute_verify_message(
+                &format!("Private/{}", signature.account_id()),
+                "challenge",
+                &encoded,
+                OutputFormat::Json
+            )
+            .is_err()
+        );
+    }
+}
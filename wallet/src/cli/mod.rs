@@ -1,27 +1,67 @@
-use std::{io::Write, path::PathBuf};
+use std::{
+    io::{IsTerminal as _, Write},
+    path::PathBuf,
+    time::Duration,
+};
 
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use nssa::{ProgramDeploymentTransaction, program::Program};
+use anyhow::Result;
+use clap::{CommandFactory as _, Parser, Subcommand};
+use clap_complete::Shell;
+use common::{
+    error::{ExecutionFailureKind, SequencerClientError},
+    rpc_types::{GetLastBlockResponse, TransactionStatus},
+};
+use key_protocol::key_management::key_tree::chain_index::{AddressChain, ChainIndex};
+use nssa::{Account, Cycles, program::Program};
 
 use crate::{
     WalletCore,
+    address_book::{AddressBook, address_book_file},
+    api::{InclusionProgress, PriorityFee},
     cli::{
         account::AccountSubcommand,
+        cache::CacheSubcommand,
         chain::ChainSubcommand,
         config::ConfigSubcommand,
+        contacts::ContactsSubcommand,
+        daemon::DaemonSubcommand,
+        keys::KeysSubcommand,
+        keystore::KeystoreSubcommand,
         programs::{
+            deployment::ProgramSubcommand, escrow::EscrowSubcommand,
             native_token_transfer::AuthTransferSubcommand, pinata::PinataProgramAgnosticSubcommand,
             token::TokenProgramAgnosticSubcommand,
         },
+        report::{ReportArgs, ReportSubcommand},
+        tx::TxSubcommand,
+    },
+    helperfunctions::{fetch_config, get_wallet_file, merge_auth_config},
+    output::OutputFormat,
+    paths::{
+        MigrationOutcome, PlatformDirs, WalletPaths, legacy_dirs, plan_migration, run_migration,
     },
-    helperfunctions::{fetch_config, fetch_persistent_storage, merge_auth_config},
+    sync_connection::{
+        RECONNECT_INITIAL_DELAY, ReconnectBackoff, SequencerEndpoints, is_connection_error,
+    },
+    wallet_lock::WalletLock,
 };
 
 pub mod account;
+pub mod cache;
 pub mod chain;
 pub mod config;
+pub mod contacts;
+pub mod daemon;
+pub mod init;
+pub mod keys;
+pub mod keystore;
+pub mod message;
 pub mod programs;
+pub mod report;
+pub mod tx;
+
+/// Number of consecutive unused addresses, after which scan of an account chain stops
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
 
 pub(crate) trait WalletSubcommand {
     async fn handle_subcommand(self, wallet_core: &mut WalletCore)
@@ -47,12 +87,27 @@ pub enum Command {
     /// Token program interaction subcommand
     #[command(subcommand)]
     Token(TokenProgramAgnosticSubcommand),
+    /// Escrow program interaction subcommand
+    #[command(subcommand)]
+    Escrow(EscrowSubcommand),
     /// Check the wallet can connect to the node and builtin local programs
     /// match the remote versions
     CheckHealth {},
     /// Command to setup config, get and set config fields
     #[command(subcommand)]
     Config(ConfigSubcommand),
+    /// Stored keys subcommand
+    #[command(subcommand)]
+    Keys(KeysSubcommand),
+    /// Format of the wallet file, which keys and accounts are stored in
+    #[command(subcommand)]
+    Keystore(KeystoreSubcommand),
+    /// Address book of named counterparty addresses
+    #[command(subcommand)]
+    Contacts(ContactsSubcommand),
+    /// Cache of chain data, which rarely changes
+    #[command(subcommand)]
+    Cache(CacheSubcommand),
     /// Restoring keys from given password at given `depth`
     ///
     /// !!!WARNING!!! will rewrite current storage
@@ -60,13 +115,99 @@ pub enum Command {
         #[arg(short, long)]
         /// Indicates, how deep in tree accounts may be. Affects command complexity.
         depth: u32,
+        /// Gap limit of scans of receive and change addresses of accounts
+        #[arg(long, default_value_t = DEFAULT_GAP_LIMIT)]
+        gap_limit: u32,
     },
-    /// Deploy a program
-    DeployProgram { binary_filepath: PathBuf },
+    /// Program deployment subcommand
+    #[command(subcommand)]
+    Program(ProgramSubcommand),
+    /// Local transaction log subcommand
+    #[command(subcommand)]
+    Tx(TxSubcommand),
+    /// Create a new wallet file with fresh keys, showing a backup sheet of their mnemonic
+    Init {
+        /// Path of the new wallet file, defaults to the resolved wallet file
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Don't ask for words of the backup sheet before creating the wallet
+        #[arg(long, conflicts_with = "from_mnemonic")]
+        no_confirm: bool,
+        /// Recover keys from a mnemonic read from stdin instead of generating new ones
+        #[arg(long)]
+        from_mnemonic: bool,
+    },
+    /// Sign a message with the key of a public account, proving control of its address
+    ///
+    /// Signature can't be used to sign a transaction.
+    SignMessage {
+        /// Valid 32 byte base58 string with `Public/` prefix
+        #[arg(long)]
+        address: String,
+        #[arg(long)]
+        message: String,
+    },
+    /// Verify a signature made by `sign-message`, fails if it's invalid. Doesn't need a wallet.
+    VerifyMessage {
+        /// Valid 32 byte base58 string with `Public/` prefix
+        #[arg(long)]
+        address: String,
+        #[arg(long)]
+        message: String,
+        /// Base64 encoded signature
+        #[arg(long)]
+        signature: String,
+    },
+    /// Write a report of transactions of a public account over a range of blocks with opening
+    /// and closing balances, signed with the key of the account
+    ///
+    /// Recipients verify the report with `report verify`.
+    Report(ReportArgs),
+    /// Background daemon, which keeps the wallet synced and executes read-only commands
+    #[command(subcommand)]
+    Daemon(DaemonSubcommand),
+    /// Print completions script for `shell`
+    #[command(hide = true)]
+    Completions { shell: Shell },
+}
+
+impl Command {
+    /// Whether the command loads the wallet, so it contends for the wallet lock
+    pub fn needs_wallet(&self) -> bool {
+        !matches!(
+            self,
+            Self::Init { .. }
+                | Self::Completions { .. }
+                | Self::Contacts(_)
+                | Self::Keystore(_)
+                | Self::Cache(_)
+                | Self::Daemon(_)
+                | Self::VerifyMessage { .. }
+                | Self::Report(ReportArgs {
+                    command: Some(ReportSubcommand::Verify { .. }),
+                    ..
+                })
+                | Self::Tx(TxSubcommand::Decode { .. })
+        )
+    }
+
+    /// Whether a running daemon executes the command, which only reads the wallet
+    pub fn is_routed_through_daemon(&self) -> bool {
+        matches!(
+            self,
+            Self::Account(AccountSubcommand::Get { .. } | AccountSubcommand::List { .. })
+                | Self::ChainInfo(_)
+                | Self::Keys(_)
+                | Self::SignMessage { .. }
+                | Self::Tx(TxSubcommand::Status { .. })
+        )
+    }
 }
 
 /// To execute commands, env var NSSA_WALLET_HOME_DIR must be set into directory with config
 ///
+/// Keys and accounts are kept in a wallet file, which is locked while a command runs.
+///
 /// All account addresses must be valid 32 byte base58 strings.
 ///
 /// All account account_ids must be provided as {privacy_prefix}/{account_id},
@@ -80,6 +221,29 @@ pub struct Args {
     /// Basic authentication in the format `user` or `user:password`
     #[arg(long)]
     pub auth: Option<String>,
+    /// Directory of all wallet files, defaults to the data and config directories of the
+    /// platform
+    #[arg(long, global = true, env = "NSSA_WALLET_HOME_DIR")]
+    pub data_dir: Option<PathBuf>,
+    /// Path to the wallet file, defaults to `storage.json` in wallet data directory
+    #[arg(long, global = true, env = "WALLET_FILE")]
+    pub wallet_file: Option<PathBuf>,
+    /// Print estimated inclusion block of sent transactions and update it while waiting
+    #[arg(long, global = true)]
+    pub wait: bool,
+    /// Priority fee of public transfers, `auto` to pay the median fee of pending transactions
+    #[arg(long, global = true)]
+    pub priority: Option<PriorityFee>,
+    /// Maximum cycles public transfers may execute, gas for them is deposited upfront and the
+    /// unused part is refunded. Transfers aren't metered if omitted
+    #[arg(long, global = true)]
+    pub max_cycles: Option<Cycles>,
+    /// Format of command output, `json` output is stable across wallet versions
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub output: OutputFormat,
+    /// Fetch chain info and sequencer info anew instead of using the cache in the data directory
+    #[arg(long, global = true)]
+    pub no_cache: bool,
     /// Wallet command
     #[command(subcommand)]
     pub command: Option<Command>,
@@ -95,15 +259,111 @@ pub enum SubcommandReturnValue {
 }
 
 pub async fn execute_subcommand(command: Command) -> Result<SubcommandReturnValue> {
-    execute_subcommand_with_auth(command, None).await
+    execute_subcommand_with_auth(
+        command,
+        None,
+        false,
+        PriorityFee::None,
+        None,
+        OutputFormat::Human,
+    )
+    .await
 }
 
+/// Execute `command`, reporting inclusion progress of sent transactions if `wait` is set
 pub async fn execute_subcommand_with_auth(
     command: Command,
     auth: Option<String>,
+    wait: bool,
+    priority_fee: PriorityFee,
+    max_cycles: Option<Cycles>,
+    output_format: OutputFormat,
 ) -> Result<SubcommandReturnValue> {
-    if fetch_persistent_storage().await.is_err() {
-        println!("Persistent storage not found, need to execute setup");
+    if let Command::Completions { shell } = command {
+        let mut command = Args::command();
+        let bin_name = command.get_name().to_string();
+        clap_complete::generate(shell, &mut command, bin_name, &mut std::io::stdout());
+
+        return Ok(SubcommandReturnValue::Empty);
+    }
+
+    if let Command::Init {
+        file,
+        no_confirm,
+        from_mnemonic,
+    } = &command
+    {
+        let wallet_file = match file {
+            Some(file) => file.clone(),
+            None => get_wallet_file()?,
+        };
+        init::execute_init_with_auth(wallet_file, *no_confirm, *from_mnemonic, auth).await?;
+
+        return Ok(SubcommandReturnValue::Empty);
+    }
+
+    // Decoding works offline, without a wallet
+    if let Command::Tx(TxSubcommand::Decode { hex, file }) = command {
+        tx::execute_decode(hex, file, output_format)?;
+
+        return Ok(SubcommandReturnValue::Empty);
+    }
+
+    // Anyone verifies signatures, without keys
+    if let Command::VerifyMessage {
+        address,
+        message,
+        signature,
+    } = &command
+    {
+        message::execute_verify_message(address, message, signature, output_format)?;
+
+        return Ok(SubcommandReturnValue::Empty);
+    }
+
+    if let Command::Report(ReportArgs {
+        command: Some(ReportSubcommand::Verify { file }),
+        ..
+    }) = command
+    {
+        report::execute_verify(file, output_format)?;
+
+        return Ok(SubcommandReturnValue::Empty);
+    }
+
+    // Address book is kept apart from keys, so it's managed without a wallet
+    if let Command::Contacts(contacts_subcommand) = command {
+        let address_book = AddressBook::new(address_book_file(&get_wallet_file()?));
+        contacts::execute_contacts(contacts_subcommand, &address_book, output_format)?;
+
+        return Ok(SubcommandReturnValue::Empty);
+    }
+
+    // Wallet file is only read, so files of newer versions are inspected too
+    if let Command::Keystore(keystore_subcommand) = command {
+        keystore::execute_keystore(keystore_subcommand, &get_wallet_file()?, output_format)?;
+
+        return Ok(SubcommandReturnValue::Empty);
+    }
+
+    if let Command::Cache(cache_subcommand) = command {
+        cache::execute_cache(cache_subcommand, &WalletPaths::from_env()?.data_dir)?;
+
+        return Ok(SubcommandReturnValue::Empty);
+    }
+
+    if let Command::Daemon(daemon_subcommand) = command {
+        daemon::execute_daemon(daemon_subcommand, auth, output_format).await?;
+
+        return Ok(SubcommandReturnValue::Empty);
+    }
+
+    let wallet_file = get_wallet_file()?;
+    let _wallet_lock = WalletLock::acquire(&wallet_file)?;
+
+    // Wallet files, which fail to load, e.g. of a newer format version, are never set up anew
+    if !wallet_file.try_exists()? {
+        eprintln!("Persistent storage not found, need to execute setup");
 
         let password = read_password_from_stdin()?;
         execute_setup_with_auth(password, auth.clone()).await?;
@@ -112,25 +372,62 @@ pub async fn execute_subcommand_with_auth(
     let wallet_config = fetch_config().await?;
     let wallet_config = merge_auth_config(wallet_config, auth.clone())?;
     let mut wallet_core = WalletCore::start_from_config_update_chain(wallet_config).await?;
+    if wait {
+        wallet_core.inclusion_progress_reporter = Some(print_inclusion_progress);
+    }
+    wallet_core.priority_fee = priority_fee;
+    wallet_core.max_cycles = max_cycles;
+    wallet_core.output_format = output_format;
 
+    execute_with_wallet_core(command, &mut wallet_core, auth).await
+}
+
+/// Execute `command`, which needs a wallet, on `wallet_core`
+pub(crate) async fn execute_with_wallet_core(
+    command: Command,
+    wallet_core: &mut WalletCore,
+    auth: Option<String>,
+) -> Result<SubcommandReturnValue> {
+    execute_command_with_wallet_core(command, wallet_core, auth)
+        .await
+        .map_err(explain_rejection)
+}
+
+/// Explains why sequencer rejected the submitted transaction, if it reported the reason
+fn explain_rejection(err: anyhow::Error) -> anyhow::Error {
+    let shortfall = err.chain().find_map(|cause| {
+        cause
+            .downcast_ref::<SequencerClientError>()
+            .or_else(|| match cause.downcast_ref::<ExecutionFailureKind>() {
+                Some(ExecutionFailureKind::SequencerClientError(err)) => Some(err),
+                _ => None,
+            })
+            .and_then(SequencerClientError::balance_shortfall)
+    });
+
+    match shortfall {
+        Some(shortfall) => anyhow::anyhow!("{shortfall}"),
+        None => err,
+    }
+}
+
+async fn execute_command_with_wallet_core(
+    command: Command,
+    wallet_core: &mut WalletCore,
+    auth: Option<String>,
+) -> Result<SubcommandReturnValue> {
     let subcommand_ret = match command {
         Command::AuthTransfer(transfer_subcommand) => {
-            transfer_subcommand
-                .handle_subcommand(&mut wallet_core)
-                .await?
+            transfer_subcommand.handle_subcommand(wallet_core).await?
         }
         Command::ChainInfo(chain_subcommand) => {
-            chain_subcommand.handle_subcommand(&mut wallet_core).await?
+            chain_subcommand.handle_subcommand(wallet_core).await?
         }
         Command::Account(account_subcommand) => {
-            account_subcommand
-                .handle_subcommand(&mut wallet_core)
-                .await?
+            account_subcommand.handle_subcommand(wallet_core).await?
         }
         Command::Pinata(pinata_subcommand) => {
-            pinata_subcommand
-                .handle_subcommand(&mut wallet_core)
-                .await?
+            pinata_subcommand.handle_subcommand(wallet_core).await?
         }
         Command::CheckHealth {} => {
             let remote_program_ids = wallet_core
@@ -151,10 +448,16 @@ pub async fn execute_subcommand_with_auth(
             if token_id != &Program::token().id() {
                 panic!("Local ID for token program is different from remote");
             }
+            let Some(escrow_id) = remote_program_ids.get("escrow") else {
+                panic!("Missing escrow program ID from remote");
+            };
+            if escrow_id != &Program::escrow().id() {
+                panic!("Local ID for escrow program is different from remote");
+            }
             let Some(circuit_id) = remote_program_ids.get("privacy_preserving_circuit") else {
                 panic!("Missing privacy preserving circuit ID from remote");
             };
-            if circuit_id != &nssa::PRIVACY_PRESERVING_CIRCUIT_ID {
+            if *circuit_id != nssa::ProgramId::new(nssa::PRIVACY_PRESERVING_CIRCUIT_ID) {
                 panic!("Local ID for privacy preserving circuit is different from remote");
             }
 
@@ -162,35 +465,43 @@ pub async fn execute_subcommand_with_auth(
 
             SubcommandReturnValue::Empty
         }
-        Command::Token(token_subcommand) => {
-            token_subcommand.handle_subcommand(&mut wallet_core).await?
+        Command::Token(token_subcommand) => token_subcommand.handle_subcommand(wallet_core).await?,
+        Command::Escrow(escrow_subcommand) => {
+            escrow_subcommand.handle_subcommand(wallet_core).await?
         }
         Command::Config(config_subcommand) => {
-            config_subcommand
-                .handle_subcommand(&mut wallet_core)
-                .await?
+            config_subcommand.handle_subcommand(wallet_core).await?
         }
-        Command::RestoreKeys { depth } => {
+        Command::Keys(keys_subcommand) => keys_subcommand.handle_subcommand(wallet_core).await?,
+        Command::RestoreKeys { depth, gap_limit } => {
             let password = read_password_from_stdin()?;
-            execute_keys_restoration_with_auth(password, depth, auth).await?;
+            execute_keys_restoration_with_auth(password, depth, gap_limit, auth).await?;
 
             SubcommandReturnValue::Empty
         }
-        Command::DeployProgram { binary_filepath } => {
-            let bytecode: Vec<u8> = std::fs::read(&binary_filepath).context(format!(
-                "Failed to read program binary at {}",
-                binary_filepath.display()
-            ))?;
-            let message = nssa::program_deployment_transaction::Message::new(bytecode);
-            let transaction = ProgramDeploymentTransaction::new(message);
-            let _response = wallet_core
-                .sequencer_client
-                .send_tx_program(transaction)
-                .await
-                .context("Transaction submission error");
+        Command::Program(program_subcommand) => {
+            program_subcommand.handle_subcommand(wallet_core).await?
+        }
+        Command::Tx(tx_subcommand) => tx_subcommand.handle_subcommand(wallet_core).await?,
+        Command::SignMessage { address, message } => {
+            message::execute_sign_message(wallet_core, &address, &message)?;
 
             SubcommandReturnValue::Empty
         }
+        Command::Report(report_args) => {
+            report::execute_report(report_args, wallet_core).await?;
+
+            SubcommandReturnValue::Empty
+        }
+        Command::Init { .. }
+        | Command::Completions { .. }
+        | Command::Contacts(_)
+        | Command::Keystore(_)
+        | Command::Cache(_)
+        | Command::VerifyMessage { .. }
+        | Command::Daemon(_) => {
+            unreachable!("Command is handled before wallet is loaded")
+        }
     };
 
     Ok(subcommand_ret)
@@ -200,35 +511,236 @@ pub async fn execute_continuous_run() -> Result<()> {
     execute_continuous_run_with_auth(None).await
 }
 pub async fn execute_continuous_run_with_auth(auth: Option<String>) -> Result<()> {
+    let _wallet_lock = WalletLock::acquire(&get_wallet_file()?)?;
+
     let config = fetch_config().await?;
     let config = merge_auth_config(config, auth)?;
-    let mut wallet_core = WalletCore::start_from_config_update_chain(config.clone()).await?;
+    let mut wallet_core = WalletCore::start_from_config_update_chain(config).await?;
+
+    sync_continuously(&mut wallet_core).await
+}
 
+/// Keep the wallet synced with the sequencer, polling it for new blocks
+///
+/// On connection errors sync is retried with exponential backoff, resuming from the last synced
+/// block. After `seq_failover_after_failures` consecutive failures it fails over to the next
+/// sequencer from `fallback_sequencer_addrs`, which serves the same chain. Returns only on other
+/// errors.
+pub async fn sync_continuously(wallet_core: &mut WalletCore) -> Result<()> {
+    let mut sync = ContinuousSync::start(wallet_core).await?;
     loop {
-        let latest_block_num = wallet_core
-            .sequencer_client
-            .get_last_block()
-            .await?
-            .last_block;
-        wallet_core.sync_to_block(latest_block_num).await?;
-
-        tokio::time::sleep(std::time::Duration::from_millis(
-            config.seq_poll_timeout_millis,
-        ))
+        let delay = sync.step(wallet_core).await?;
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// State of [`sync_continuously`] between its steps
+pub(crate) struct ContinuousSync {
+    endpoints: SequencerEndpoints,
+    backoff: ReconnectBackoff,
+    poll_delay: Duration,
+}
+
+impl ContinuousSync {
+    pub(crate) async fn start(wallet_core: &mut WalletCore) -> Result<Self> {
+        let config = &wallet_core.storage.wallet_config;
+        let sync = Self {
+            endpoints: SequencerEndpoints::new(
+                config.sequencer_addr.clone(),
+                config.fallback_sequencer_addrs.clone(),
+                config.seq_failover_after_failures,
+            ),
+            backoff: ReconnectBackoff::new(
+                RECONNECT_INITIAL_DELAY,
+                Duration::from_millis(config.seq_reconnect_max_delay_millis),
+            ),
+            poll_delay: Duration::from_millis(config.seq_poll_timeout_millis),
+        };
+
+        // Genesis of the primary sequencer is the reference for fallback ones
+        match wallet_core.sequencer_client.get_sequencer_info().await {
+            Ok(info) => wallet_core.record_sequencer_info(info)?,
+            Err(err) => eprintln!("Failed to get sequencer info: {err}"),
+        }
+
+        Ok(sync)
+    }
+
+    /// Syncs to the current tip, returns delay before the next step
+    pub(crate) async fn step(&mut self, wallet_core: &mut WalletCore) -> Result<Duration> {
+        let synced = async {
+            let tip = wallet_core.sequencer_client.get_last_block().await?;
+            sync_to_tip_printing_progress(wallet_core, &tip).await
+        }
         .await;
+
+        match synced {
+            Ok(()) => {
+                if self.endpoints.consecutive_failures() > 0 {
+                    eprintln!("Reconnected to sequencer {}", self.endpoints.current());
+                }
+                self.endpoints.record_success();
+                self.backoff.reset();
+
+                if let Err(err) = wallet_core.refresh_mempool_mirror().await {
+                    log::warn!("Failed to mirror pending transactions of sequencer: {err:#}");
+                }
+
+                Ok(self.poll_delay)
+            }
+            Err(err) if is_connection_error(&err) => {
+                let should_fail_over = self.endpoints.record_failure();
+                eprintln!(
+                    "Lost connection to sequencer {} after block {}, {} consecutive failures: {err:#}",
+                    self.endpoints.current(),
+                    wallet_core.last_synced_block,
+                    self.endpoints.consecutive_failures()
+                );
+
+                if should_fail_over && fail_over(wallet_core, &mut self.endpoints).await {
+                    self.backoff.reset();
+                    return Ok(Duration::ZERO);
+                }
+
+                let delay = self.backoff.next_delay();
+                eprintln!("Reconnecting in {}ms", delay.as_millis());
+                Ok(delay)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Switch to the first failover candidate serving the same chain, returns whether it succeeded
+async fn fail_over(wallet_core: &mut WalletCore, endpoints: &mut SequencerEndpoints) -> bool {
+    for (index, addr) in endpoints.failover_candidates() {
+        match wallet_core.switch_sequencer(&addr).await {
+            Ok(()) => {
+                eprintln!("Failed over to sequencer {addr}");
+                endpoints.fail_over_to(index);
+                return true;
+            }
+            Err(err) => eprintln!("Can't fail over to sequencer {addr}: {err:#}"),
+        }
+    }
+
+    false
+}
+
+/// Print estimated inclusion of sent transactions to stderr, used with `--wait`
+fn print_inclusion_progress(hash: &str, progress: &InclusionProgress) {
+    match progress {
+        InclusionProgress::Submitted {
+            position,
+            depth,
+            estimated_inclusion_block,
+        } => eprintln!(
+            "Transaction {hash} is {} of {depth} pending, expected in block {estimated_inclusion_block}",
+            position + 1
+        ),
+        InclusionProgress::StatusChanged(TransactionStatus::Pending {
+            position,
+            depth,
+            estimated_inclusion_block,
+            estimated_wait_millis,
+        }) => eprintln!(
+            "Transaction {hash} is {} of {depth} pending, expected in block {estimated_inclusion_block} in about {}s",
+            position + 1,
+            estimated_wait_millis.div_ceil(1000)
+        ),
+        InclusionProgress::StatusChanged(TransactionStatus::Included { block_id, .. }) => {
+            eprintln!("Transaction {hash} is included in block {block_id}")
+        }
+        // Dropped transactions are reported as errors
+        InclusionProgress::StatusChanged(
+            TransactionStatus::Dropped { .. } | TransactionStatus::Unknown,
+        ) => {}
     }
 }
 
+/// Sync wallet to `block_id`, showing a progress bar on stderr
+/// Syncs to the last block of `tip` response, printing progress to stderr
+pub(crate) async fn sync_to_tip_printing_progress(
+    wallet_core: &mut WalletCore,
+    tip: &GetLastBlockResponse,
+) -> Result<()> {
+    let block_id = tip.last_block;
+    if wallet_core.last_synced_block >= block_id {
+        return Ok(());
+    }
+
+    let before_polling = std::time::Instant::now();
+    let num_of_blocks = block_id - wallet_core.last_synced_block;
+    eprintln!("Syncing to block {block_id}. Blocks to sync: {num_of_blocks}");
+
+    let bar = indicatif::ProgressBar::new(num_of_blocks);
+    wallet_core
+        .sync_to_tip_with_progress(tip, |_| bar.inc(1))
+        .await?;
+    bar.finish();
+
+    eprintln!(
+        "Synced to block {block_id} in {:?}",
+        before_polling.elapsed()
+    );
+
+    Ok(())
+}
+
 pub fn read_password_from_stdin() -> Result<String> {
     let mut password = String::new();
 
-    print!("Input password: ");
-    std::io::stdout().flush()?;
+    eprint!("Input password: ");
+    std::io::stderr().flush()?;
     std::io::stdin().read_line(&mut password)?;
 
     Ok(password.trim().to_string())
 }
 
+/// Ask `question` on stderr, returns whether the answer read from stdin is yes
+pub fn confirm_from_stdin(question: &str) -> Result<bool> {
+    let mut answer = String::new();
+
+    eprint!("{question} [y/N]: ");
+    std::io::stderr().flush()?;
+    std::io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Offer to move files of an older wallet to the platform directories, once
+///
+/// Without a terminal to ask in, the files are only reported, and the offer is repeated later.
+pub fn offer_legacy_migration() -> Result<()> {
+    let paths = WalletPaths::from_env()?;
+    let legacy_dirs = legacy_dirs(&PlatformDirs::current());
+
+    if !std::io::stdin().is_terminal() {
+        if let Some(plan) = plan_migration(&paths, &legacy_dirs) {
+            eprintln!(
+                "Found wallet files of an older version in {}, run wallet in a terminal to move \
+                 them",
+                plan.source_dir.display()
+            );
+        }
+        return Ok(());
+    }
+
+    match run_migration(&paths, &legacy_dirs, |plan| {
+        confirm_from_stdin(&plan.question())
+    })? {
+        MigrationOutcome::Migrated(plan) => {
+            eprintln!("Moved {} wallet files", plan.moves.len());
+        }
+        MigrationOutcome::Declined(plan) => eprintln!(
+            "Kept wallet files in {}, pass --data-dir to use them",
+            plan.source_dir.display()
+        ),
+        MigrationOutcome::NothingToMigrate => {}
+    }
+    Ok(())
+}
+
 pub async fn execute_setup(password: String) -> Result<()> {
     execute_setup_with_auth(password, None).await
 }
@@ -244,12 +756,15 @@ pub async fn execute_setup_with_auth(password: String, auth: Option<String>) ->
 }
 
 pub async fn execute_keys_restoration(password: String, depth: u32) -> Result<()> {
-    execute_keys_restoration_with_auth(password, depth, None).await
+    execute_keys_restoration_with_auth(password, depth, DEFAULT_GAP_LIMIT, None).await
 }
 
+/// Restore keys of accounts up to `depth`, and addresses of chains of accounts, which are
+/// children of the root, up to `gap_limit` consecutive unused ones
 pub async fn execute_keys_restoration_with_auth(
     password: String,
     depth: u32,
+    gap_limit: u32,
     auth: Option<String>,
 ) -> Result<()> {
     let config = fetch_config().await?;
@@ -273,6 +788,52 @@ pub async fn execute_keys_restoration_with_auth(
 
     println!("Private tree generated");
 
+    let chain_accounts = (0..depth.saturating_sub(1))
+        .map(ChainIndex::account)
+        .collect::<Vec<_>>();
+
+    let fetcher = wallet_core.account_fetcher().await;
+    for account in &chain_accounts {
+        wallet_core
+            .storage
+            .user_data
+            .public_key_tree
+            .scan_account_chains(account, gap_limit, |account_ids| {
+                let fetcher = fetcher.clone();
+                async move {
+                    let states = fetcher.fetch(&account_ids).await;
+                    Ok(account_ids
+                        .iter()
+                        .zip(states)
+                        .map(|(account_id, state)| match state {
+                            Ok(state) => state != Account::default(),
+                            // Address may be used, so it's kept
+                            Err(err) => {
+                                println!("Failed to fetch {account_id}, keeping it: {err:#}");
+                                true
+                            }
+                        })
+                        .collect())
+                }
+            })
+            .await?;
+    }
+
+    println!("Public account chains scanned");
+
+    // Private addresses are found by sync, so the first ones are derived beforehand
+    for account in &chain_accounts {
+        for chain in AddressChain::ALL {
+            for index in 0..gap_limit {
+                wallet_core
+                    .storage
+                    .user_data
+                    .private_key_tree
+                    .fill_chain_address(account, chain, index);
+            }
+        }
+    }
+
     wallet_core
         .storage
         .user_data
@@ -282,15 +843,21 @@ pub async fn execute_keys_restoration_with_auth(
 
     println!("Public tree cleaned up");
 
-    let last_block = wallet_core
-        .sequencer_client
-        .get_last_block()
-        .await?
-        .last_block;
+    let tip = wallet_core.sequencer_client.get_last_block().await?;
+
+    println!("Last block is {}", tip.last_block);
 
-    println!("Last block is {last_block}");
+    sync_to_tip_printing_progress(&mut wallet_core, &tip).await?;
+
+    for account in &chain_accounts {
+        wallet_core
+            .storage
+            .user_data
+            .private_key_tree
+            .scan_account_chains(account, gap_limit);
+    }
 
-    wallet_core.sync_to_block(last_block).await?;
+    println!("Private account chains scanned");
 
     println!("Private tree clean up start");
 
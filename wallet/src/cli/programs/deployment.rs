@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use clap::Subcommand;
+
+use crate::{
+    WalletCore,
+    cli::{SubcommandReturnValue, WalletSubcommand},
+    helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
+    program_facades::program_deployment::ProgramDeployment,
+};
+
+/// Represents generic CLI subcommand for a wallet managing programs
+#[derive(Subcommand, Debug, Clone)]
+pub enum ProgramSubcommand {
+    /// Deploy a program, signed and paid for by a public account
+    Deploy {
+        /// elf - path of the program binary
+        #[arg(long)]
+        elf: PathBuf,
+        /// from - valid 32 byte base58 string with `Public/` prefix
+        #[arg(long)]
+        from: String,
+    },
+}
+
+impl WalletSubcommand for ProgramSubcommand {
+    async fn handle_subcommand(
+        self,
+        wallet_core: &mut WalletCore,
+    ) -> Result<SubcommandReturnValue> {
+        match self {
+            ProgramSubcommand::Deploy { elf, from } => {
+                let deployer = match parse_addr_with_privacy_prefix(&from)? {
+                    (account_id, AccountPrivacyKind::Public) => account_id.parse()?,
+                    (_, AccountPrivacyKind::Private) => {
+                        anyhow::bail!("Programs are deployed by public accounts only, got {from}")
+                    }
+                };
+                let bytecode = std::fs::read(&elf).with_context(|| {
+                    format!("Failed to read program binary at {}", elf.display())
+                })?;
+
+                let res = ProgramDeployment(wallet_core)
+                    .deploy(deployer, bytecode)
+                    .await?;
+
+                if let Some(program_id) = &res.program_id {
+                    if res.is_already_deployed() {
+                        println!("Program {program_id} is deployed already, nothing is submitted");
+                        return Ok(SubcommandReturnValue::Empty);
+                    }
+                    println!("Deploying program {program_id}");
+                }
+                println!("Results of tx send are {res:#?}");
+
+                let deployment_tx = wallet_core.poll_native_token_transfer(res.tx_hash).await?;
+
+                println!("Transaction data is {deployment_tx:?}");
+
+                let path = wallet_core.store_persistent_data().await?;
+
+                println!("Stored persistent accounts at {path:#?}");
+
+                Ok(SubcommandReturnValue::Empty)
+            }
+        }
+    }
+}
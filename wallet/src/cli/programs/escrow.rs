@@ -0,0 +1,108 @@
+use anyhow::Result;
+use clap::Subcommand;
+use nssa::Amount;
+
+use crate::{
+    WalletCore,
+    cli::{SubcommandReturnValue, WalletSubcommand, contacts::resolve_public_recipient},
+    program_facades::escrow::Escrow,
+};
+
+/// Represents generic CLI subcommand for a wallet working with escrow program
+///
+/// Escrow is supported for public accounts only.
+#[derive(Subcommand, Debug, Clone)]
+pub enum EscrowSubcommand {
+    /// Lock funds in a new escrow account
+    Create {
+        /// from - valid 32 byte base58 string
+        #[arg(long)]
+        from: String,
+        /// escrow - valid 32 byte base58 string of an uninitialized account
+        #[arg(long)]
+        escrow: String,
+        /// recipient - valid 32 byte base58 string or a contact name
+        #[arg(long)]
+        recipient: String,
+        /// amount - amount of balance to lock
+        #[arg(long)]
+        amount: Amount,
+        /// timeout - id of the block, starting from which escrow can't be claimed, but can be
+        /// refunded
+        #[arg(long)]
+        timeout: u64,
+    },
+    /// Claim escrow before its timeout
+    Claim {
+        /// escrow - valid 32 byte base58 string
+        #[arg(long)]
+        escrow: String,
+        /// recipient - valid 32 byte base58 string or a contact name
+        #[arg(long)]
+        recipient: String,
+    },
+    /// Refund escrow after its timeout
+    Refund {
+        /// escrow - valid 32 byte base58 string
+        #[arg(long)]
+        escrow: String,
+        /// funder - valid 32 byte base58 string or a contact name
+        #[arg(long)]
+        funder: String,
+    },
+}
+
+impl WalletSubcommand for EscrowSubcommand {
+    async fn handle_subcommand(
+        self,
+        wallet_core: &mut WalletCore,
+    ) -> Result<SubcommandReturnValue> {
+        let res = match self {
+            EscrowSubcommand::Create {
+                from,
+                escrow,
+                recipient,
+                amount,
+                timeout,
+            } => {
+                Escrow(wallet_core)
+                    .create(
+                        from.parse()?,
+                        escrow.parse()?,
+                        resolve_public_recipient(wallet_core, &recipient)?,
+                        amount,
+                        timeout,
+                    )
+                    .await?
+            }
+            EscrowSubcommand::Claim { escrow, recipient } => {
+                Escrow(wallet_core)
+                    .claim(
+                        escrow.parse()?,
+                        resolve_public_recipient(wallet_core, &recipient)?,
+                    )
+                    .await?
+            }
+            EscrowSubcommand::Refund { escrow, funder } => {
+                Escrow(wallet_core)
+                    .refund(
+                        escrow.parse()?,
+                        resolve_public_recipient(wallet_core, &funder)?,
+                    )
+                    .await?
+            }
+        };
+
+        println!("Results of tx send are {res:#?}");
+
+        let transfer_tx = wallet_core.poll_native_token_transfer(res.tx_hash).await?;
+
+        println!("Transaction data is {transfer_tx:?}");
+
+        let path = wallet_core.store_persistent_data().await?;
+
+        println!("Stored persistent accounts at {path:#?}");
+
+        Ok(SubcommandReturnValue::Empty)
+    }
+}
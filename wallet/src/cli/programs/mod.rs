@@ -1,3 +1,5 @@
+pub mod deployment;
+pub mod escrow;
 pub mod native_token_transfer;
 pub mod pinata;
 pub mod token;
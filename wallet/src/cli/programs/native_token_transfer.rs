@@ -1,11 +1,12 @@
 use anyhow::Result;
 use clap::Subcommand;
 use common::transaction::NSSATransaction;
-use nssa::AccountId;
+use nssa::{AccountId, Amount};
 
 use crate::{
     WalletCore,
-    cli::{SubcommandReturnValue, WalletSubcommand},
+    api::ChangeAddress,
+    cli::{SubcommandReturnValue, WalletSubcommand, contacts::resolve_recipient},
     helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
     program_facades::native_token_transfer::NativeTokenTransfer,
 };
@@ -29,7 +30,7 @@ pub enum AuthTransferSubcommand {
         /// from - valid 32 byte base58 string with privacy prefix
         #[arg(long)]
         from: String,
-        /// to - valid 32 byte base58 string with privacy prefix
+        /// to - valid 32 byte base58 string with privacy prefix or a contact name
         #[arg(long)]
         to: Option<String>,
         /// to_npk - valid 32 byte hex string
@@ -40,7 +41,13 @@ pub enum AuthTransferSubcommand {
         to_ipk: Option<String>,
         /// amount - amount of balance to move
         #[arg(long)]
-        amount: u128,
+        amount: Amount,
+        /// Send the rest of the sender balance to a change address: `auto` for a fresh address
+        /// of the sender account, or a public account id with privacy prefix
+        ///
+        /// Only for public transfers from accounts of the key tree when `auto` is used.
+        #[arg(long)]
+        change_address: Option<ChangeAddress>,
     },
 }
 
@@ -109,7 +116,31 @@ impl WalletSubcommand for AuthTransferSubcommand {
                 to_npk,
                 to_ipk,
                 amount,
+                change_address,
             } => {
+                let to = match to {
+                    Some(to) => {
+                        let to = resolve_recipient(wallet_core, &to)?;
+                        eprintln!("Sending {amount} to {to}");
+                        Some(to.address)
+                    }
+                    None => None,
+                };
+
+                if let Some(change_address) = change_address {
+                    let (Some(to), None, None) = (to, to_npk, to_ipk) else {
+                        anyhow::bail!("Change address requires `to` account id of receiver");
+                    };
+                    return send_public_transfer_with_change(
+                        wallet_core,
+                        &from,
+                        &to,
+                        amount,
+                        change_address,
+                    )
+                    .await;
+                }
+
                 let underlying_subcommand = match (to, to_npk, to_ipk) {
                     (None, None, None) => {
                         anyhow::bail!(
@@ -193,6 +224,66 @@ impl WalletSubcommand for AuthTransferSubcommand {
     }
 }
 
+async fn send_public_transfer_with_change(
+    wallet_core: &mut WalletCore,
+    from: &str,
+    to: &str,
+    amount: Amount,
+    change_address: ChangeAddress,
+) -> Result<SubcommandReturnValue> {
+    let (from, from_privacy) = parse_addr_with_privacy_prefix(from)?;
+    let (to, to_privacy) = parse_addr_with_privacy_prefix(to)?;
+    anyhow::ensure!(
+        from_privacy == AccountPrivacyKind::Public && to_privacy == AccountPrivacyKind::Public,
+        "Change address is only supported for public transfers"
+    );
+    anyhow::ensure!(
+        wallet_core.max_cycles.is_none(),
+        "Change address can't be used with `--max-cycles`, as the charged gas isn't known upfront"
+    );
+    let from: AccountId = from.parse()?;
+    let to: AccountId = to.parse()?;
+
+    let change = match change_address {
+        ChangeAddress::Fixed(change) => change,
+        ChangeAddress::Auto => {
+            let (change, chain_index) = wallet_core.create_change_address_public(from).ok_or_else(
+                || {
+                    anyhow::anyhow!(
+                        "{from} isn't an account of the key tree, pass change address explicitly"
+                    )
+                },
+            )?;
+            wallet_core.store_persistent_data().await?;
+            println!(
+                "Change goes to Public/{change} at {}",
+                chain_index.to_path_string()
+            );
+            change
+        }
+    };
+
+    let (res, change_res) = NativeTokenTransfer(wallet_core)
+        .send_public_transfer_with_change(from, to, amount, change)
+        .await?;
+
+    println!("Results of tx send are {res:#?}");
+    match change_res {
+        Some(change_res) => println!("Results of change tx send are {change_res:#?}"),
+        None => println!("Nothing is left for change"),
+    }
+
+    let transfer_tx = wallet_core.poll_native_token_transfer(res.tx_hash).await?;
+
+    println!("Transaction data is {transfer_tx:?}");
+
+    let path = wallet_core.store_persistent_data().await?;
+
+    println!("Stored persistent accounts at {path:#?}");
+
+    Ok(SubcommandReturnValue::Empty)
+}
+
 /// Represents generic CLI subcommand for a wallet working with native token transfer program
 #[derive(Subcommand, Debug, Clone)]
 pub enum NativeTokenTransferProgramSubcommand {
@@ -208,7 +299,7 @@ pub enum NativeTokenTransferProgramSubcommand {
         to: String,
         /// amount - amount of balance to move
         #[arg(long)]
-        amount: u128,
+        amount: Amount,
     },
     /// Private execution
     #[command(subcommand)]
@@ -225,7 +316,7 @@ pub enum NativeTokenTransferProgramSubcommand {
         to: String,
         /// amount - amount of balance to move
         #[arg(long)]
-        amount: u128,
+        amount: Amount,
     },
     /// Shielded execution
     #[command(subcommand)]
@@ -248,7 +339,7 @@ pub enum NativeTokenTransferProgramSubcommandShielded {
         to: String,
         /// amount - amount of balance to move
         #[arg(long)]
-        amount: u128,
+        amount: Amount,
     },
     /// Send native token transfer from `from` to `to` for `amount`
     ///
@@ -265,7 +356,7 @@ pub enum NativeTokenTransferProgramSubcommandShielded {
         to_ipk: String,
         /// amount - amount of balance to move
         #[arg(long)]
-        amount: u128,
+        amount: Amount,
     },
 }
 
@@ -285,7 +376,7 @@ pub enum NativeTokenTransferProgramSubcommandPrivate {
         to: String,
         /// amount - amount of balance to move
         #[arg(long)]
-        amount: u128,
+        amount: Amount,
     },
     /// Send native token transfer from `from` to `to` for `amount`
     ///
@@ -302,7 +393,7 @@ pub enum NativeTokenTransferProgramSubcommandPrivate {
         to_ipk: String,
         /// amount - amount of balance to move
         #[arg(long)]
-        amount: u128,
+        amount: Amount,
     },
 }
 
@@ -528,3 +619,105 @@ impl WalletSubcommand for NativeTokenTransferProgramSubcommand {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nssa::program::Program;
+
+    use super::*;
+    use crate::{address_book::Contact, test_utils};
+
+    #[tokio::test]
+    async fn test_public_send_moves_balance_and_stores_wallet() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000).with_auto_block_production());
+        let mut wallet_core = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        AuthTransferSubcommand::Send {
+            from: format!("Public/{from}"),
+            to: Some(format!("Public/{to}")),
+            to_npk: None,
+            to_ipk: None,
+            amount: Amount(250),
+            change_address: None,
+        }
+        .handle_subcommand(&mut wallet_core)
+        .await
+        .unwrap();
+
+        assert_eq!(chain.account(&from).balance, Amount(750));
+        assert_eq!(chain.account(&to).balance, Amount(1250));
+        assert!(wallet_core.wallet_file.exists());
+    }
+
+    #[tokio::test]
+    async fn test_send_to_contact_name() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000).with_auto_block_production());
+        let mut wallet_core = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+        let contact = Contact::new("alice", &format!("Public/{to}"), None).unwrap();
+        wallet_core.address_book.add(contact).unwrap();
+
+        AuthTransferSubcommand::Send {
+            from: format!("Public/{from}"),
+            to: Some("alice".to_string()),
+            to_npk: None,
+            to_ipk: None,
+            amount: Amount(250),
+            change_address: None,
+        }
+        .handle_subcommand(&mut wallet_core)
+        .await
+        .unwrap();
+
+        assert_eq!(chain.account(&to).balance, Amount(1250));
+    }
+
+    #[tokio::test]
+    async fn test_send_without_receiver_is_rejected() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let mut wallet_core = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, _) = test_utils::preconfigured_public_accounts();
+
+        let result = AuthTransferSubcommand::Send {
+            from: format!("Public/{from}"),
+            to: None,
+            to_npk: None,
+            to_ipk: None,
+            amount: Amount(250),
+            change_address: None,
+        }
+        .handle_subcommand(&mut wallet_core)
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(chain.mempool_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_init_claims_new_public_account() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000).with_auto_block_production());
+        let mut wallet_core = test_utils::mock_wallet(chain.clone(), home.path());
+        let (account_id, _) = wallet_core.create_new_account_public(None);
+
+        AuthTransferSubcommand::Init {
+            account_id: format!("Public/{account_id}"),
+        }
+        .handle_subcommand(&mut wallet_core)
+        .await
+        .unwrap();
+
+        let account = chain.account(&account_id);
+        assert_eq!(
+            account.program_owner,
+            Program::authenticated_transfer_program().id()
+        );
+        assert_eq!(account.nonce, 1);
+    }
+}
@@ -4,7 +4,7 @@ use common::{PINATA_BASE58, transaction::NSSATransaction};
 
 use crate::{
     WalletCore,
-    cli::{SubcommandReturnValue, WalletSubcommand},
+    cli::{SubcommandReturnValue, WalletSubcommand, contacts::resolve_recipient},
     helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
     program_facades::pinata::Pinata,
 };
@@ -14,7 +14,7 @@ use crate::{
 pub enum PinataProgramAgnosticSubcommand {
     /// Claim pinata
     Claim {
-        /// to - valid 32 byte base58 string with privacy prefix
+        /// to - valid 32 byte base58 string with privacy prefix or a contact name
         #[arg(long)]
         to: String,
     },
@@ -27,6 +27,7 @@ impl WalletSubcommand for PinataProgramAgnosticSubcommand {
     ) -> Result<SubcommandReturnValue> {
         let underlying_subcommand = match self {
             PinataProgramAgnosticSubcommand::Claim { to } => {
+                let to = resolve_recipient(wallet_core, &to)?.address;
                 let (to, to_addr_privacy) = parse_addr_with_privacy_prefix(&to)?;
 
                 match to_addr_privacy {
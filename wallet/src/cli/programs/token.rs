@@ -5,7 +5,7 @@ use nssa::AccountId;
 
 use crate::{
     WalletCore,
-    cli::{SubcommandReturnValue, WalletSubcommand},
+    cli::{SubcommandReturnValue, WalletSubcommand, contacts::resolve_recipient},
     helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
     program_facades::token::Token,
 };
@@ -36,7 +36,7 @@ pub enum TokenProgramAgnosticSubcommand {
         /// from - valid 32 byte base58 string with privacy prefix
         #[arg(long)]
         from: String,
-        /// to - valid 32 byte base58 string with privacy prefix
+        /// to - valid 32 byte base58 string with privacy prefix or a contact name
         #[arg(long)]
         to: Option<String>,
         /// to_npk - valid 32 byte hex string
@@ -120,6 +120,15 @@ impl WalletSubcommand for TokenProgramAgnosticSubcommand {
                 to_ipk,
                 amount,
             } => {
+                let to = match to {
+                    Some(to) => {
+                        let to = resolve_recipient(wallet_core, &to)?;
+                        eprintln!("Sending {amount} tokens to {to}");
+                        Some(to.address)
+                    }
+                    None => None,
+                };
+
                 let underlying_subcommand = match (to, to_npk, to_ipk) {
                     (None, None, None) => {
                         anyhow::bail!(
@@ -0,0 +1,116 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+use clap::{Args, Subcommand};
+
+use crate::{
+    WalletCore,
+    output::{OutputFormat, ReportOutput, print_output},
+    report::{parse_reported_account_id, verify_report, write_report},
+};
+
+/// Arguments of `report`, which writes a signed report unless `verify` is given
+#[derive(Args, Debug, Clone)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct ReportArgs {
+    #[command(subcommand)]
+    pub command: Option<ReportSubcommand>,
+    /// address - valid 32 byte base58 string with `Public/` prefix, its key signs the report
+    #[arg(long, required = true)]
+    pub address: Option<String>,
+    /// First block of the reported range
+    #[arg(long, required = true)]
+    pub from_block: Option<u64>,
+    /// Last block of the reported range (inclusive)
+    #[arg(long, required = true)]
+    pub to_block: Option<u64>,
+    /// Path of the written report
+    #[arg(long, required = true)]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ReportSubcommand {
+    /// Verify a report written by `report`, fails if it's altered or doesn't add up. Doesn't
+    /// need a wallet.
+    Verify {
+        /// Path of the report
+        #[arg(long)]
+        file: PathBuf,
+    },
+}
+
+/// Writes the report of transactions of the address in the range of blocks, signed with the key
+/// of the address
+pub async fn execute_report(args: ReportArgs, wallet_core: &WalletCore) -> Result<()> {
+    if let Some(ReportSubcommand::Verify { file }) = args.command {
+        return execute_verify(file, wallet_core.output_format);
+    }
+    let (Some(address), Some(from_block), Some(to_block), Some(out)) =
+        (args.address, args.from_block, args.to_block, args.out)
+    else {
+        anyhow::bail!("--address, --from-block, --to-block and --out are required");
+    };
+    let account_id = parse_reported_account_id(&address)?;
+    let key = wallet_core
+        .get_account_public_signing_key(&account_id)
+        .with_context(|| format!("Signing key of account {address} not found in storage"))?;
+
+    let file = File::create(&out)
+        .with_context(|| format!("Failed to create report file {}", out.display()))?;
+    let written = write_report(
+        wallet_core.sequencer_client.as_ref(),
+        account_id,
+        from_block..=to_block,
+        key,
+        BufWriter::new(file),
+    )
+    .await;
+    let (header, summary) = match written {
+        Ok(written) => written,
+        Err(err) => {
+            // Partial report is never left behind, as it can't be verified
+            let _ = std::fs::remove_file(&out);
+            return Err(err);
+        }
+    };
+
+    print_output(
+        wallet_core.output_format,
+        &ReportOutput {
+            file: out.display().to_string(),
+            address: address.clone(),
+            signer: address,
+            from_block,
+            to_block,
+            transactions: summary.transactions,
+            opening_balance: summary.opening_balance,
+            closing_balance: summary.closing_balance,
+            proven: header.closing_proof.is_some(),
+        },
+    )
+}
+
+/// Prints the verified report at `file`, failing if it's altered or doesn't add up. Doesn't need
+/// a wallet.
+pub fn execute_verify(file: PathBuf, output_format: OutputFormat) -> Result<()> {
+    let report = File::open(&file)
+        .with_context(|| format!("Failed to open report file {}", file.display()))?;
+    let verified = verify_report(report)
+        .with_context(|| format!("Report {} failed verification", file.display()))?;
+
+    print_output(
+        output_format,
+        &ReportOutput {
+            file: file.display().to_string(),
+            address: verified.header.address,
+            signer: format!("Public/{}", verified.signer),
+            from_block: verified.header.from_block,
+            to_block: verified.header.to_block,
+            transactions: verified.summary.transactions,
+            opening_balance: verified.summary.opening_balance,
+            closing_balance: verified.summary.closing_balance,
+            proven: verified.header.closing_proof.is_some(),
+        },
+    )
+}
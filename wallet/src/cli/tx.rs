@@ -0,0 +1,319 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{Context as _, Result};
+use clap::Subcommand;
+use itertools::Itertools as _;
+use nssa::{AccountId, Amount, instruction_decoder::DecoderRegistry};
+
+use crate::{
+    WalletCore,
+    cli::{
+        SubcommandReturnValue, WalletSubcommand, confirm_from_stdin, contacts::resolve_recipient,
+    },
+    helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
+    output::{
+        OutputFormat, PayoutOutput, TimeDisplay, TxDryRunOutput, TxStatusEntry, TxStatusOutput,
+        TxSubmittedOutput, print_output,
+    },
+    paths::write_private,
+    payout::{PayoutStatus, parse_payout_csv, payout_results_csv},
+    program_facades::native_token_transfer::NativeTokenTransfer,
+    tx_decode::{decode_transaction, parse_hex, read_transaction_file},
+    tx_log::TxStatus,
+};
+
+/// Represents generic CLI subcommand for the local transaction log
+#[derive(Subcommand, Debug, Clone)]
+pub enum TxSubcommand {
+    /// Reconcile logged transactions with sequencer and show their statuses
+    Status {
+        /// hash - valid 32 byte hex string, all logged transactions are shown if omitted
+        #[arg(long)]
+        hash: Option<String>,
+        /// Show times of status changes as UTC time instead of relative to now
+        #[arg(long)]
+        utc: bool,
+    },
+    /// Cancel a pending logged transaction locally, releasing its nonce for new transactions
+    ///
+    /// Sequencer isn't told about it, so the transaction may still be included.
+    CancelLocal {
+        /// hash - valid 32 byte hex string
+        #[arg(long)]
+        hash: String,
+    },
+    /// Build public transfer with extra message options, then sign and submit it
+    ///
+    /// With `--dry-run`, the signed transfer is checked by the sequencer like on submission, but
+    /// isn't submitted.
+    Build {
+        /// from - valid 32 byte base58 string with `Public/` prefix
+        #[arg(long)]
+        from: String,
+        /// to - valid 32 byte base58 string with `Public/` prefix or a contact name
+        #[arg(long)]
+        to: String,
+        /// amount - amount of balance to move
+        #[arg(long)]
+        amount: Amount,
+        /// Block height, before which the transfer can't be included into blocks
+        #[arg(long)]
+        not_before: Option<u64>,
+        /// Ask the sequencer whether it would accept the transfer instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Destroy balance of a public account by transferring it to the burn account
+    ///
+    /// Burned balance can never be spent or recovered by anyone.
+    Burn {
+        /// from - valid 32 byte base58 string with `Public/` prefix
+        #[arg(long)]
+        from: String,
+        /// amount - amount of balance to burn
+        #[arg(long)]
+        amount: Amount,
+        /// Burn without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Pay every row `address,amount[,memo]` of a CSV file with public transfers from one account
+    ///
+    /// All rows are validated before anything is sent. Transaction hashes and final statuses of
+    /// the rows are written to a results CSV file.
+    Payout {
+        /// from - valid 32 byte base58 string with `Public/` prefix
+        #[arg(long)]
+        from: String,
+        /// Path of the CSV file with payouts
+        #[arg(long)]
+        csv: PathBuf,
+        /// Path of the results CSV file, defaults to the payout file with `.results.csv` extension
+        #[arg(long)]
+        results: Option<PathBuf>,
+        /// Seconds to wait for inclusion of sent transfers
+        #[arg(long, default_value_t = 60)]
+        timeout_secs: u64,
+        /// Send without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Decode a raw transaction and print its fields without sending it
+    ///
+    /// Signatures are checked against the decoded message. Doesn't need a wallet.
+    Decode {
+        /// Hex encoded transaction, `0x` prefix is optional
+        #[arg(long, required_unless_present = "file", conflicts_with = "file")]
+        hex: Option<String>,
+        /// Path of a file with the transaction, either hex encoded or raw bytes
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+}
+
+impl WalletSubcommand for TxSubcommand {
+    async fn handle_subcommand(
+        self,
+        wallet_core: &mut WalletCore,
+    ) -> Result<SubcommandReturnValue> {
+        match self {
+            TxSubcommand::Status { hash, utc } => {
+                let entries = wallet_core.reconcile_tx_log(hash.as_deref()).await?;
+                let mut transactions = entries
+                    .into_iter()
+                    .map(|entry| TxStatusEntry::new(entry, &DecoderRegistry::default()))
+                    .collect::<Vec<_>>();
+                // Receipts and audits are fetched for a single transaction only, not for the whole
+                // log
+                if hash.is_some() {
+                    for entry in &mut transactions {
+                        if entry.status == TxStatus::Included {
+                            entry.receipt =
+                                wallet_core.get_transaction_receipt(&entry.hash).await?;
+                        }
+                        // Admin RPC may be unreachable or refuse the token, status is shown anyway
+                        match wallet_core.get_tx_audit(&entry.hash).await {
+                            Ok(audit) => entry.audit = audit,
+                            Err(err) => eprintln!(
+                                "WARNING: mempool audit of transaction {} is unavailable: {err:#}",
+                                entry.hash
+                            ),
+                        }
+                    }
+                }
+
+                print_output(
+                    wallet_core.output_format,
+                    &TxStatusOutput {
+                        transactions,
+                        time_display: TimeDisplay::new(utc),
+                    },
+                )?;
+            }
+            TxSubcommand::CancelLocal { hash } => {
+                let entry = wallet_core.cancel_local_tx(&hash)?;
+                eprintln!(
+                    "WARNING: transaction {hash} is cancelled locally only, sequencer may still \
+                     include it"
+                );
+
+                print_output(
+                    wallet_core.output_format,
+                    &TxStatusOutput {
+                        transactions: vec![TxStatusEntry::new(entry, &DecoderRegistry::default())],
+                        time_display: TimeDisplay::new(false),
+                    },
+                )?;
+            }
+            TxSubcommand::Build {
+                from,
+                to,
+                amount,
+                not_before,
+                dry_run,
+            } => {
+                let from = parse_public_account_id(&from)?;
+                let to = resolve_recipient(wallet_core, &to)?;
+                if dry_run {
+                    let to = parse_public_account_id(&to.address)?;
+                    let dry_run = NativeTokenTransfer(wallet_core)
+                        .dry_run_time_locked_public_transfer(from, to, amount, not_before)
+                        .await?;
+                    let accepted = dry_run.verdict.is_ok();
+
+                    print_output(wallet_core.output_format, &TxDryRunOutput::from(dry_run))?;
+                    anyhow::ensure!(accepted, "Sequencer would reject the transfer");
+                    return Ok(SubcommandReturnValue::Empty);
+                }
+                eprintln!("Sending {amount} to {to}");
+                let to = parse_public_account_id(&to.address)?;
+
+                let res = NativeTokenTransfer(wallet_core)
+                    .send_time_locked_public_transfer(from, to, amount, not_before)
+                    .await?;
+
+                print_output(
+                    wallet_core.output_format,
+                    &TxSubmittedOutput {
+                        hash: res.tx_hash,
+                        valid_from_block: not_before,
+                    },
+                )?;
+            }
+            TxSubcommand::Burn { from, amount, yes } => {
+                let from = parse_public_account_id(&from)?;
+
+                eprintln!(
+                    "WARNING: {amount} will be burned from {from}. Burned balance is destroyed \
+                     forever, nobody can ever spend or recover it."
+                );
+                if !yes && !confirm_from_stdin("Irreversibly burn the balance?")? {
+                    anyhow::bail!("Burn is cancelled");
+                }
+
+                let res = NativeTokenTransfer(wallet_core)
+                    .send_public_transfer(from, nssa::BURN_ACCOUNT_ID, amount)
+                    .await?;
+
+                print_output(
+                    wallet_core.output_format,
+                    &TxSubmittedOutput {
+                        hash: res.tx_hash,
+                        valid_from_block: None,
+                    },
+                )?;
+            }
+            TxSubcommand::Payout {
+                from,
+                csv,
+                results,
+                timeout_secs,
+                yes,
+            } => {
+                let from = parse_public_account_id(&from)?;
+                let contents = std::fs::read_to_string(&csv)
+                    .with_context(|| format!("Failed to read payout file {}", csv.display()))?;
+                let rows = parse_payout_csv(&contents).map_err(|errors| {
+                    anyhow::anyhow!(
+                        "Invalid payout file {}:\n{}",
+                        csv.display(),
+                        errors.iter().join("\n")
+                    )
+                })?;
+
+                let plan = wallet_core.prepare_payout(from, rows).await?;
+                eprintln!("{plan}");
+                if !yes && !confirm_from_stdin("Send the payout?")? {
+                    anyhow::bail!("Payout is cancelled");
+                }
+
+                let payout_results = wallet_core
+                    .send_payout(plan, Duration::from_secs(timeout_secs))
+                    .await;
+
+                let results_file = results.unwrap_or_else(|| csv.with_extension("results.csv"));
+                write_private(&results_file, payout_results_csv(&payout_results)).with_context(
+                    || {
+                        format!(
+                            "Failed to write payout results to {}",
+                            results_file.display()
+                        )
+                    },
+                )?;
+
+                let num_failed = payout_results
+                    .iter()
+                    .filter(|result| {
+                        matches!(
+                            result.status,
+                            PayoutStatus::Failed | PayoutStatus::NotSubmitted
+                        )
+                    })
+                    .count();
+                let num_rows = payout_results.len();
+                print_output(
+                    wallet_core.output_format,
+                    &PayoutOutput {
+                        results_file: results_file.display().to_string(),
+                        payouts: payout_results.into_iter().map(Into::into).collect(),
+                    },
+                )?;
+
+                anyhow::ensure!(
+                    num_failed == 0,
+                    "{num_failed} of {num_rows} payouts weren't made"
+                );
+            }
+            TxSubcommand::Decode { hex, file } => {
+                execute_decode(hex, file, wallet_core.output_format)?;
+            }
+        }
+
+        Ok(SubcommandReturnValue::Empty)
+    }
+}
+
+/// Prints the transaction given to `tx decode`, failing if it can't be decoded
+pub fn execute_decode(
+    hex: Option<String>,
+    file: Option<PathBuf>,
+    output_format: OutputFormat,
+) -> Result<()> {
+    let bytes = match (hex, file) {
+        (Some(hex), _) => parse_hex(&hex)?,
+        (None, Some(file)) => read_transaction_file(&file)?,
+        (None, None) => anyhow::bail!("Either --hex or --file is required"),
+    };
+    let output = decode_transaction(&bytes, &DecoderRegistry::default())
+        .map_err(|err| anyhow::anyhow!("Failed to decode transaction {err}"))?;
+    print_output(output_format, &output)
+}
+
+fn parse_public_account_id(account_id: &str) -> Result<AccountId> {
+    match parse_addr_with_privacy_prefix(account_id)? {
+        (account_id, AccountPrivacyKind::Public) => Ok(account_id.parse()?),
+        (_, AccountPrivacyKind::Private) => {
+            anyhow::bail!("Only public transfers can be built, got private account {account_id}")
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{collections::BTreeMap, str::FromStr};
 
 use key_protocol::key_management::{
     KeyChain,
@@ -8,6 +8,8 @@ use key_protocol::key_management::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::light_client::TrustedCheckpoint;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BasicAuth {
     pub username: String,
@@ -101,8 +103,16 @@ pub enum PersistentAccountData {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersistentStorage {
+    /// Format version of the file, see [`crate::keystore`]
+    pub format_version: u32,
     pub accounts: Vec<PersistentAccountData>,
     pub last_synced_block: u64,
+    /// Labels of own addresses by their base58 ids
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub address_labels: BTreeMap<String, String>,
+    /// Block, which light verification follows headers from, see [`crate::light_client`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trusted_checkpoint: Option<TrustedCheckpoint>,
 }
 
 impl InitialAccountData {
@@ -190,6 +200,42 @@ pub struct WalletConfig {
     pub initial_accounts: Vec<InitialAccountData>,
     /// Basic authentication credentials
     pub basic_auth: Option<BasicAuth>,
+    /// Sequencer URLs to fail over to, in order, if `sequencer_addr` is unreachable
+    #[serde(default)]
+    pub fallback_sequencer_addrs: Vec<String>,
+    /// Number of consecutive connection failures in continuous run before failing over
+    #[serde(default = "default_seq_failover_after_failures")]
+    pub seq_failover_after_failures: u64,
+    /// Max delay between reconnection attempts in continuous run in milliseconds
+    #[serde(default = "default_seq_reconnect_max_delay_millis")]
+    pub seq_reconnect_max_delay_millis: u64,
+    /// Max number of account requests in flight while fetching many accounts
+    #[serde(default = "default_account_fetch_concurrency")]
+    pub account_fetch_concurrency: usize,
+    /// Backend proving privacy preserving transactions. Proofs of `dev_fake` are accepted only
+    /// by sequencers of local chains.
+    #[serde(default)]
+    pub prover_backend: nssa::ProverBackend,
+    /// Mirror pending transactions of the sequencer, ignoring ones pending for more than this
+    /// number of blocks. `None` disables the mirror.
+    #[serde(default)]
+    pub mempool_mirror_max_age_blocks: Option<u64>,
+    /// Bearer token of the admin RPC of the sequencer. If set, `tx status` of a single
+    /// transaction also shows its mempool lifecycle events recorded by the sequencer.
+    #[serde(default)]
+    pub admin_auth_token: Option<String>,
+}
+
+fn default_seq_failover_after_failures() -> u64 {
+    3
+}
+
+fn default_seq_reconnect_max_delay_millis() -> u64 {
+    60_000
+}
+
+fn default_account_fetch_concurrency() -> usize {
+    8
 }
 
 impl Default for WalletConfig {
@@ -202,6 +248,13 @@ impl Default for WalletConfig {
             seq_poll_max_retries: 5,
             seq_block_poll_max_amount: 100,
             basic_auth: None,
+            fallback_sequencer_addrs: vec![],
+            seq_failover_after_failures: default_seq_failover_after_failures(),
+            seq_reconnect_max_delay_millis: default_seq_reconnect_max_delay_millis(),
+            account_fetch_concurrency: default_account_fetch_concurrency(),
+            prover_backend: nssa::ProverBackend::default(),
+            mempool_mirror_max_age_blocks: None,
+            admin_auth_token: None,
             initial_accounts: {
                 let init_acc_json = r#"
                 [
@@ -0,0 +1,331 @@
+//! Wallet daemon, which keeps a wallet synced and executes commands of other wallet invocations.
+//!
+//! Daemon holds the wallet lock for its whole lifetime and listens on a sibling
+//! `<wallet file>.sock` Unix socket, accessible by the owner only. Every connection carries a
+//! single [`DaemonRequest`] and a single [`DaemonResponse`], each is a line of JSON.
+
+use std::{
+    fs::Permissions,
+    os::unix::fs::PermissionsExt as _,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result};
+use clap::Parser as _;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::{Mutex, Notify},
+};
+
+use crate::{
+    WalletCore,
+    cli::{Args, ContinuousSync, execute_with_wallet_core},
+    output::capture_output,
+};
+
+/// Request of a wallet invocation to the daemon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum DaemonRequest {
+    Status,
+    /// Store the wallet and exit
+    Stop,
+    /// Execute a wallet command given by its command line, including the binary name
+    Run {
+        args: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum DaemonResponse {
+    Status {
+        pid: u32,
+        last_synced_block: u64,
+    },
+    Stopping,
+    /// Stdout of an executed command
+    Output {
+        stdout: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Path of the control socket of the daemon of `wallet_file`
+pub fn socket_path(wallet_file: &Path) -> PathBuf {
+    let mut path = wallet_file.as_os_str().to_owned();
+    path.push(".sock");
+    PathBuf::from(path)
+}
+
+/// Connection to a running daemon
+#[derive(Debug, Clone)]
+pub struct DaemonClient {
+    socket_path: PathBuf,
+}
+
+impl DaemonClient {
+    /// Client of the daemon of `wallet_file`, `None` if it's not running
+    pub async fn connect(wallet_file: &Path) -> Option<Self> {
+        let socket_path = socket_path(wallet_file);
+        UnixStream::connect(&socket_path).await.ok()?;
+        Some(Self { socket_path })
+    }
+
+    pub async fn request(&self, request: &DaemonRequest) -> Result<DaemonResponse> {
+        let stream = UnixStream::connect(&self.socket_path)
+            .await
+            .with_context(|| format!("Failed to connect to {}", self.socket_path.display()))?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+
+        let mut line = String::new();
+        if BufReader::new(reader).read_line(&mut line).await? == 0 {
+            anyhow::bail!("Daemon closed connection without response");
+        }
+        serde_json::from_str(&line).context("Invalid response of daemon")
+    }
+}
+
+/// Keep `wallet_core` synced and serve requests on `socket_path` until [`DaemonRequest::Stop`]
+///
+/// Caller must hold the wallet lock, which is released after return. Wallet is stored before
+/// return, transaction log needs no flush as its records are synced once written. Returns an
+/// error if sync fails with a non-connection error, after storing the wallet as well.
+pub async fn serve(mut wallet_core: WalletCore, socket_path: &Path) -> Result<()> {
+    let sync = ContinuousSync::start(&mut wallet_core).await?;
+    let listener = bind(socket_path)?;
+
+    let wallet = Arc::new(Mutex::new(wallet_core));
+    let stop = Arc::new(Notify::new());
+    let sync_task = tokio::spawn(keep_synced(sync, wallet.clone(), stop.clone()));
+    let accept_task = tokio::spawn(accept_requests(listener, wallet.clone(), stop.clone()));
+
+    stop.notified().await;
+
+    // Sync steps and commands hold the wallet, so none of them is interrupted by aborts
+    let wallet_core = wallet.lock().await;
+    accept_task.abort();
+    sync_task.abort();
+    let synced = match sync_task.await {
+        Ok(result) => result,
+        Err(err) if err.is_cancelled() => Ok(()),
+        Err(err) => Err(err.into()),
+    };
+
+    if let Err(err) = std::fs::remove_file(socket_path) {
+        warn!("Failed to remove {}: {err}", socket_path.display());
+    }
+    wallet_core.store_persistent_data().await?;
+
+    synced
+}
+
+fn bind(socket_path: &Path) -> Result<UnixListener> {
+    // Socket is left behind by a daemon, which didn't stop cleanly. It's stale, as the wallet
+    // lock is held
+    match std::fs::remove_file(socket_path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed to remove stale {}", socket_path.display()));
+        }
+    }
+
+    let listener = UnixListener::bind(socket_path)
+        .with_context(|| format!("Failed to listen on {}", socket_path.display()))?;
+    std::fs::set_permissions(socket_path, Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+async fn keep_synced(
+    mut sync: ContinuousSync,
+    wallet: Arc<Mutex<WalletCore>>,
+    stop: Arc<Notify>,
+) -> Result<()> {
+    loop {
+        let step = sync.step(&mut *wallet.lock().await).await;
+        match step {
+            Ok(delay) => tokio::time::sleep(delay).await,
+            Err(err) => {
+                stop.notify_one();
+                return Err(err);
+            }
+        }
+    }
+}
+
+async fn accept_requests(
+    listener: UnixListener,
+    wallet: Arc<Mutex<WalletCore>>,
+    stop: Arc<Notify>,
+) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let wallet = wallet.clone();
+                let stop = stop.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = serve_connection(stream, &wallet, &stop).await {
+                        warn!("Failed to serve daemon client: {err:#}");
+                    }
+                });
+            }
+            Err(err) => warn!("Failed to accept daemon client: {err}"),
+        }
+    }
+}
+
+async fn serve_connection(
+    stream: UnixStream,
+    wallet: &Mutex<WalletCore>,
+    stop: &Notify,
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    // Clients connect without a request to check whether the daemon is running
+    if BufReader::new(reader).read_line(&mut line).await? == 0 {
+        return Ok(());
+    }
+
+    let request = serde_json::from_str(&line);
+    let response = match &request {
+        Ok(DaemonRequest::Status) => DaemonResponse::Status {
+            pid: std::process::id(),
+            last_synced_block: wallet.lock().await.last_synced_block,
+        },
+        Ok(DaemonRequest::Stop) => DaemonResponse::Stopping,
+        Ok(DaemonRequest::Run { args }) => match run_command(args, wallet).await {
+            Ok(stdout) => DaemonResponse::Output { stdout },
+            Err(err) => DaemonResponse::Error {
+                message: format!("{err:#}"),
+            },
+        },
+        Err(err) => DaemonResponse::Error {
+            message: format!("Invalid request: {err}"),
+        },
+    };
+
+    let mut line = serde_json::to_vec(&response)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+
+    // Stop is acknowledged first, as the process exits once the wallet is stored
+    if matches!(request, Ok(DaemonRequest::Stop)) {
+        stop.notify_one();
+    }
+    Ok(())
+}
+
+async fn run_command(args: &[String], wallet: &Mutex<WalletCore>) -> Result<String> {
+    let args = Args::try_parse_from(args)?;
+    let command = args.command.context("Command is missing")?;
+    anyhow::ensure!(
+        command.is_routed_through_daemon(),
+        "Command is not executed by the daemon"
+    );
+
+    let mut wallet_core = wallet.lock().await;
+    wallet_core.output_format = args.output;
+    let (result, stdout) =
+        capture_output(execute_with_wallet_core(command, &mut wallet_core, None)).await;
+    result?;
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_utils;
+
+    async fn wait_for_daemon(wallet_file: &Path) -> DaemonClient {
+        loop {
+            if let Some(client) = DaemonClient::connect(wallet_file).await {
+                return client;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_daemon_serves_balance_query_until_stopped() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet_core = test_utils::mock_wallet(chain, home.path());
+        let wallet_file = wallet_core.wallet_file.clone();
+        let socket = socket_path(&wallet_file);
+        let (account_id, _) = test_utils::preconfigured_public_accounts();
+
+        let daemon = tokio::spawn({
+            let socket = socket.clone();
+            async move { serve(wallet_core, &socket).await }
+        });
+        let client = wait_for_daemon(&wallet_file).await;
+
+        assert!(matches!(
+            client.request(&DaemonRequest::Status).await.unwrap(),
+            DaemonResponse::Status { pid, .. } if pid == std::process::id()
+        ));
+
+        let response = client
+            .request(&DaemonRequest::Run {
+                args: [
+                    "wallet",
+                    "--output",
+                    "json",
+                    "account",
+                    "get",
+                    "--account-id",
+                ]
+                .into_iter()
+                .map(String::from)
+                .chain([format!("Public/{account_id}")])
+                .collect(),
+            })
+            .await
+            .unwrap();
+        let DaemonResponse::Output { stdout } = response else {
+            panic!("Unexpected response {response:?}");
+        };
+        let account: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(account["balance"], 1000);
+
+        let response = client
+            .request(&DaemonRequest::Run {
+                args: vec![
+                    "wallet".to_string(),
+                    "program".to_string(),
+                    "deploy".to_string(),
+                    "--elf".to_string(),
+                    "a".to_string(),
+                    "--from".to_string(),
+                    "Public/a".to_string(),
+                ],
+            })
+            .await
+            .unwrap();
+        assert!(matches!(response, DaemonResponse::Error { .. }));
+
+        assert_eq!(
+            client.request(&DaemonRequest::Stop).await.unwrap(),
+            DaemonResponse::Stopping
+        );
+        daemon.await.unwrap().unwrap();
+
+        assert!(!socket.exists());
+        assert!(DaemonClient::connect(&wallet_file).await.is_none());
+        assert!(wallet_file.exists());
+    }
+}
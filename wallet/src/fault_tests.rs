@@ -0,0 +1,190 @@
+//! Scenarios of the wallet served by a failing [`MockChain`], each of which must end the same way
+//! as its fault-free run
+
+use std::sync::Arc;
+
+use common::{
+    fault_plan::FaultPlan,
+    mock_chain::{MOCK_GENESIS_ID, MockChain},
+};
+use nssa::{Account, Amount};
+
+use crate::{WalletCore, api::TransferOptions, cli::ContinuousSync, test_utils, tx_log::TxStatus};
+
+/// Steps of continuous sync, after which it must have caught up despite faults
+const MAX_SYNC_STEPS: usize = 10;
+
+/// Outcome of a scenario, which faults must not change
+#[derive(Debug, PartialEq, Eq)]
+struct FinalState {
+    last_block_id: u64,
+    last_synced_block: u64,
+    /// Accounts of [`test_utils::preconfigured_public_accounts`]
+    accounts: [Account; 2],
+    /// Of journaled transactions in order of sending
+    statuses: Vec<TxStatus>,
+}
+
+impl FinalState {
+    fn of(chain: &MockChain, wallet: &WalletCore) -> Self {
+        let (from, to) = test_utils::preconfigured_public_accounts();
+        Self {
+            last_block_id: chain.last_block_id(),
+            last_synced_block: wallet.last_synced_block,
+            accounts: [chain.account(&from), chain.account(&to)],
+            statuses: wallet
+                .tx_log
+                .entries()
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.status)
+                .collect(),
+        }
+    }
+}
+
+async fn sync_continuously_to_tip(
+    sync: &mut ContinuousSync,
+    wallet: &mut WalletCore,
+    chain: &MockChain,
+) {
+    for _ in 0..MAX_SYNC_STEPS {
+        if wallet.last_synced_block == chain.last_block_id() {
+            return;
+        }
+        sync.step(wallet).await.unwrap();
+    }
+    panic!(
+        "Continuous sync is stuck at block {}, while the chain is at {}",
+        wallet.last_synced_block,
+        chain.last_block_id()
+    );
+}
+
+async fn transfers_synced_continuously(plan: FaultPlan) -> FinalState {
+    let home = tempfile::tempdir().unwrap();
+    let chain = Arc::new(test_utils::funded_chain(1000).with_fault_plan(plan));
+    let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+    let (from, to) = test_utils::preconfigured_public_accounts();
+    let mut sync = ContinuousSync::start(&mut wallet).await.unwrap();
+
+    for _ in 0..3 {
+        wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        chain.produce_block();
+        sync_continuously_to_tip(&mut sync, &mut wallet, &chain).await;
+    }
+
+    FinalState::of(&chain, &wallet)
+}
+
+#[tokio::test]
+async fn test_continuous_sync_survives_sequencer_restart() {
+    // Restart outlasts the failures, after which continuous sync fails over
+    let plan = FaultPlan::default().restart_at_block(MOCK_GENESIS_ID + 1, 4);
+
+    assert_eq!(
+        transfers_synced_continuously(plan).await,
+        transfers_synced_continuously(FaultPlan::default()).await
+    );
+}
+
+async fn transfer_submitted(plan: FaultPlan) -> FinalState {
+    let home = tempfile::tempdir().unwrap();
+    let chain = Arc::new(test_utils::funded_chain(1000).with_fault_plan(plan));
+    let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+    wallet.storage.wallet_config.seq_poll_max_retries = 3;
+    let (from, to) = test_utils::preconfigured_public_accounts();
+
+    wallet
+        .transfer(from, to, Amount(100), TransferOptions::default())
+        .await
+        .unwrap();
+    chain.produce_block();
+    wallet.sync().await.unwrap();
+
+    FinalState::of(&chain, &wallet)
+}
+
+#[tokio::test]
+async fn test_submission_during_blackout_lands() {
+    let plan = FaultPlan::default().drop_calls("send_tx", 3);
+
+    assert_eq!(
+        transfer_submitted(plan).await,
+        transfer_submitted(FaultPlan::default()).await
+    );
+}
+
+async fn transfers_synced(plan: FaultPlan) -> FinalState {
+    let home = tempfile::tempdir().unwrap();
+    let chain = Arc::new(
+        test_utils::funded_chain(1000)
+            .with_auto_block_production()
+            .with_fault_plan(plan),
+    );
+    let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+    let (from, to) = test_utils::preconfigured_public_accounts();
+
+    for _ in 0..2 {
+        wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+    }
+    wallet.sync().await.unwrap();
+
+    FinalState::of(&chain, &wallet)
+}
+
+#[tokio::test]
+async fn test_corrupted_block_fetch_is_retried() {
+    let plan = FaultPlan::default().corrupt_block_fetch(MOCK_GENESIS_ID + 1);
+
+    assert_eq!(
+        transfers_synced(plan).await,
+        transfers_synced(FaultPlan::default()).await
+    );
+}
+
+async fn transfer_synced_across_storage_pause(plan: FaultPlan) -> FinalState {
+    let home = tempfile::tempdir().unwrap();
+    let chain = Arc::new(test_utils::funded_chain(1000).with_fault_plan(plan));
+    let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+    let (from, to) = test_utils::preconfigured_public_accounts();
+
+    wallet
+        .transfer(from, to, Amount(100), TransferOptions::default())
+        .await
+        .unwrap();
+    chain.produce_block();
+
+    // Whatever the wallet reads, it belongs to a single written block
+    wallet.sync().await.unwrap();
+    let expected_balance = if wallet.last_synced_block == MOCK_GENESIS_ID {
+        1000
+    } else {
+        900
+    };
+    assert_eq!(
+        wallet.balance(from).await.unwrap(),
+        Amount(expected_balance)
+    );
+
+    chain.resume_storage();
+    wallet.sync().await.unwrap();
+
+    FinalState::of(&chain, &wallet)
+}
+
+#[tokio::test]
+async fn test_storage_pause_does_not_corrupt_state() {
+    let plan = FaultPlan::default().pause_storage_at_block(MOCK_GENESIS_ID + 1);
+
+    assert_eq!(
+        transfer_synced_across_storage_pause(plan).await,
+        transfer_synced_across_storage_pause(FaultPlan::default()).await
+    );
+}
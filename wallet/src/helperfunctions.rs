@@ -1,51 +1,43 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Result;
-use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use key_protocol::key_protocol_core::NSSAUserData;
-use nssa::Account;
 use nssa_core::account::Nonce;
-use rand::{RngCore, rngs::OsRng};
-use serde::Serialize;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::{
-    HOME_DIR_ENV_VAR,
     config::{
         BasicAuth, InitialAccountData, InitialAccountDataPrivate, InitialAccountDataPublic,
         PersistentAccountDataPrivate, PersistentAccountDataPublic, PersistentStorage, WalletConfig,
     },
+    keystore::{KEYSTORE_FORMAT_VERSION, read_keystore},
+    paths::{CONFIG_FILE_NAME, WalletPaths, create_private_dir_all, private_open_options},
 };
 
-/// Get home dir for wallet. Env var `NSSA_WALLET_HOME_DIR` must be set before execution to succeed.
-pub fn get_home_nssa_var() -> Result<PathBuf> {
-    Ok(PathBuf::from_str(&std::env::var(HOME_DIR_ENV_VAR)?)?)
-}
-
-/// Get home dir for wallet. Env var `HOME` must be set before execution to succeed.
-pub fn get_home_default_path() -> Result<PathBuf> {
-    std::env::home_dir()
-        .map(|path| path.join(".nssa").join("wallet"))
-        .ok_or(anyhow::anyhow!("Failed to get HOME"))
+/// Get data dir of wallet, see [`WalletPaths`].
+pub fn get_home() -> Result<PathBuf> {
+    Ok(WalletPaths::from_env()?.data_dir)
 }
 
-/// Get home dir for wallet.
-pub fn get_home() -> Result<PathBuf> {
-    if let Ok(home) = get_home_nssa_var() {
-        Ok(home)
-    } else {
-        get_home_default_path()
-    }
+/// Get path of wallet file with keys and accounts.
+///
+/// Env var `WALLET_FILE` overrides default file in wallet data dir.
+pub fn get_wallet_file() -> Result<PathBuf> {
+    Ok(WalletPaths::from_env()?.wallet_file)
 }
 
-/// Fetch config from default home
+/// Fetch config from config dir of wallet
 pub async fn fetch_config() -> Result<WalletConfig> {
-    let config_home = get_home()?;
+    let paths = WalletPaths::from_env()?;
+    let config_home = paths.config_dir;
     let mut config_needs_setup = false;
 
     let config = match tokio::fs::OpenOptions::new()
         .read(true)
-        .open(config_home.join("wallet_config.json"))
+        .open(config_home.join(CONFIG_FILE_NAME))
         .await
     {
         Ok(mut file) => {
@@ -67,15 +59,15 @@ pub async fn fetch_config() -> Result<WalletConfig> {
     };
 
     if config_needs_setup {
-        tokio::fs::create_dir_all(&config_home).await?;
+        create_private_dir_all(&config_home)?;
 
         println!("Created configs dir at path {config_home:#?}");
 
-        let mut file = tokio::fs::OpenOptions::new()
+        let mut file = tokio::fs::OpenOptions::from(private_open_options())
             .write(true)
             .create(true)
             .truncate(true)
-            .open(config_home.join("wallet_config.json"))
+            .open(config_home.join(CONFIG_FILE_NAME))
             .await?;
 
         let default_config_serialized =
@@ -106,28 +98,44 @@ pub fn merge_auth_config(
     Ok(config)
 }
 
-/// Fetch data stored at home
+/// Fetch data stored in wallet file, see [`read_keystore`]
 ///
 /// File must be created through setup beforehand.
 pub async fn fetch_persistent_storage() -> Result<PersistentStorage> {
-    let home = get_home()?;
-    let accs_path = home.join("storage.json");
-    let mut storage_content = vec![];
+    let accs_path = get_wallet_file()?;
+    if !tokio::fs::try_exists(&accs_path).await? {
+        anyhow::bail!("Not found, please setup roots from config command beforehand");
+    }
 
-    match tokio::fs::File::open(accs_path).await {
-        Ok(mut file) => {
-            file.read_to_end(&mut storage_content).await?;
-            Ok(serde_json::from_slice(&storage_content)?)
-        }
-        Err(err) => match err.kind() {
-            std::io::ErrorKind::NotFound => {
-                anyhow::bail!("Not found, please setup roots from config command beforehand");
-            }
-            _ => {
-                anyhow::bail!("IO error {err:#?}");
-            }
-        },
+    read_keystore(&accs_path)
+}
+
+/// Write data to a new wallet file at `path`, refusing to overwrite an existing one
+pub async fn create_persistent_storage(path: &Path, storage: &PersistentStorage) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        create_private_dir_all(parent)?;
     }
+
+    let mut file = match tokio::fs::OpenOptions::from(private_open_options())
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .await
+    {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+            anyhow::bail!(
+                "Wallet file {} already exists, refusing to overwrite it",
+                path.display()
+            );
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    file.write_all(&serde_json::to_vec_pretty(storage)?).await?;
+    file.sync_all().await?;
+
+    Ok(())
 }
 
 /// Produces data for storage
@@ -185,14 +193,19 @@ pub fn produce_data_for_storage(
     }
 
     PersistentStorage {
+        format_version: KEYSTORE_FORMAT_VERSION,
         accounts: vec_for_storage,
         last_synced_block,
+        address_labels: BTreeMap::new(),
+        trusted_checkpoint: None,
     }
 }
 
 pub(crate) fn produce_random_nonces(size: usize) -> Vec<Nonce> {
-    let mut result = vec![[0; 16]; size];
-    result.iter_mut().for_each(|bytes| OsRng.fill_bytes(bytes));
+    let mut result = vec![[0; 8]; size];
+    result
+        .iter_mut()
+        .for_each(|bytes| nssa::entropy::fill_bytes(bytes));
     result.into_iter().map(Nonce::from_le_bytes).collect()
 }
 
@@ -220,31 +233,10 @@ pub(crate) fn parse_addr_with_privacy_prefix(
     }
 }
 
-/// Human-readable representation of an account.
-#[derive(Serialize)]
-pub(crate) struct HumanReadableAccount {
-    balance: u128,
-    program_owner_b64: String,
-    data_b64: String,
-    nonce: u128,
-}
-
-impl From<Account> for HumanReadableAccount {
-    fn from(account: Account) -> Self {
-        let program_owner_b64 = BASE64.encode(bytemuck::cast_slice(&account.program_owner));
-        let data_b64 = BASE64.encode(account.data);
-        Self {
-            balance: account.balance,
-            program_owner_b64,
-            data_b64,
-            nonce: account.nonce,
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::HOME_DIR_ENV_VAR;
 
     #[test]
     fn test_get_home_get_env_var() {
@@ -254,13 +246,42 @@ mod tests {
 
         let home = get_home().unwrap();
 
-        assert_eq!(PathBuf::from_str("/path/to/configs").unwrap(), home);
+        assert_eq!(PathBuf::from("/path/to/configs"), home);
 
         unsafe {
             std::env::remove_var(HOME_DIR_ENV_VAR);
         }
     }
 
+    #[tokio::test]
+    async fn test_create_persistent_storage_refuses_to_overwrite() {
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join("wallets").join("testing.json");
+        let storage = PersistentStorage {
+            format_version: KEYSTORE_FORMAT_VERSION,
+            accounts: vec![],
+            last_synced_block: 5,
+            address_labels: BTreeMap::new(),
+            trusted_checkpoint: None,
+        };
+
+        create_persistent_storage(&path, &storage).await.unwrap();
+        let contents = std::fs::read(&path).unwrap();
+
+        let other_storage = PersistentStorage {
+            format_version: KEYSTORE_FORMAT_VERSION,
+            accounts: vec![],
+            last_synced_block: 0,
+            address_labels: BTreeMap::new(),
+            trusted_checkpoint: None,
+        };
+        let err = create_persistent_storage(&path, &other_storage)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("refusing to overwrite"));
+        assert_eq!(std::fs::read(&path).unwrap(), contents);
+    }
+
     #[test]
     fn test_addr_parse_with_privacy() {
         let addr_base58 = "Public/BLgCRDXYdQPMMWVHYRFGQZbgeHx9frkipa8GtpG2Syqy";
@@ -0,0 +1,251 @@
+//! Versioned format of the wallet file, which keys and accounts are stored in.
+//!
+//! The file starts with `format_version`, files written before it was introduced are version 0.
+//! A wallet refuses files of a newer version than [`KEYSTORE_FORMAT_VERSION`] and never writes
+//! them, as it would drop fields it doesn't know of when storing the file back. Files of older
+//! versions are migrated on load one version at a time, and the original is copied next to the
+//! file before the migrated one is written.
+
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use log::info;
+use serde_json::Value;
+
+use crate::{
+    config::PersistentStorage,
+    paths::{private_open_options, write_private},
+};
+
+/// Format version of wallet files written by this wallet
+pub const KEYSTORE_FORMAT_VERSION: u32 = 1;
+
+/// Migrations of file contents, the one at index `n` migrates version `n` to version `n + 1`
+const MIGRATIONS: [fn(Value) -> Result<Value>; KEYSTORE_FORMAT_VERSION as usize] = [migrate_v0];
+
+/// Wallet file of a newer format version, which this wallet can't read without losing data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewerFormatError {
+    pub path: PathBuf,
+    pub format_version: u32,
+}
+
+impl std::fmt::Display for NewerFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Wallet file {} has format version {}, but this wallet supports versions up to \
+             {KEYSTORE_FORMAT_VERSION}, upgrade the wallet to use it",
+            self.path.display(),
+            self.format_version
+        )
+    }
+}
+
+impl std::error::Error for NewerFormatError {}
+
+/// Wallet file read without migrating it on disk, see [`inspect_keystore`]
+#[derive(Debug, Clone)]
+pub struct KeystoreInspection {
+    pub format_version: u32,
+    /// Contents migrated to the current version, `None` if the file is of a newer one
+    pub storage: Option<PersistentStorage>,
+}
+
+/// Reads the wallet file at `path`, migrating it to [`KEYSTORE_FORMAT_VERSION`] first
+///
+/// Files of newer versions fail with [`NewerFormatError`] and stay untouched.
+pub fn read_keystore(path: &Path) -> Result<PersistentStorage> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read wallet file {}", path.display()))?;
+    let contents = serde_json::from_slice::<Value>(&bytes)
+        .with_context(|| format!("Malformed wallet file {}", path.display()))?;
+    let format_version = format_version(&contents)?;
+    if format_version > KEYSTORE_FORMAT_VERSION {
+        return Err(NewerFormatError {
+            path: path.to_path_buf(),
+            format_version,
+        }
+        .into());
+    }
+
+    let storage = migrate(contents, format_version)
+        .with_context(|| format!("Malformed wallet file {}", path.display()))?;
+    if format_version < KEYSTORE_FORMAT_VERSION {
+        let backup = backup_path(path, format_version);
+        write_backup(&backup, &bytes)?;
+        write_private(path, serde_json::to_vec_pretty(&storage)?)?;
+        info!(
+            "Migrated wallet file {} from format version {format_version} to \
+             {KEYSTORE_FORMAT_VERSION}, original is kept in {}",
+            path.display(),
+            backup.display()
+        );
+    }
+
+    Ok(storage)
+}
+
+/// Reads the format version of the wallet file at `path` and its contents, if the version is
+/// supported
+///
+/// The file isn't migrated on disk, nor written otherwise.
+pub fn inspect_keystore(path: &Path) -> Result<KeystoreInspection> {
+    let contents = serde_json::from_slice::<Value>(
+        &std::fs::read(path)
+            .with_context(|| format!("Failed to read wallet file {}", path.display()))?,
+    )
+    .with_context(|| format!("Malformed wallet file {}", path.display()))?;
+    let format_version = format_version(&contents)?;
+
+    let storage = (format_version <= KEYSTORE_FORMAT_VERSION)
+        .then(|| migrate(contents, format_version))
+        .transpose()
+        .with_context(|| format!("Malformed wallet file {}", path.display()))?;
+    Ok(KeystoreInspection {
+        format_version,
+        storage,
+    })
+}
+
+/// Path of the copy of the wallet file at `path` kept before migrating it from `format_version`
+pub fn backup_path(path: &Path, format_version: u32) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{format_version}.bak"));
+    PathBuf::from(backup)
+}
+
+fn format_version(contents: &Value) -> Result<u32> {
+    match contents.get("format_version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .with_context(|| format!("Malformed format version {version} of wallet file")),
+    }
+}
+
+fn migrate(mut contents: Value, format_version: u32) -> Result<PersistentStorage> {
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(format_version as usize) {
+        contents = migration(contents)
+            .with_context(|| format!("Failed to migrate format version {version}"))?;
+        contents
+            .as_object_mut()
+            .context("Wallet file isn't a JSON object")?
+            .insert("format_version".to_string(), (version + 1).into());
+    }
+
+    Ok(serde_json::from_value(contents)?)
+}
+
+/// Files written before versioning already have the layout of version 1, which fields added
+/// since, like address labels and the trusted checkpoint, are optional in
+fn migrate_v0(contents: Value) -> Result<Value> {
+    Ok(contents)
+}
+
+/// Writes the original file to `backup`, an existing backup is kept, as it's the older one
+fn write_backup(backup: &Path, original: &[u8]) -> Result<()> {
+    let mut file = match private_open_options()
+        .write(true)
+        .create_new(true)
+        .open(backup)
+    {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => return Ok(()),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed to write backup {}", backup.display()));
+        }
+    };
+    file.write_all(original)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V0: &str = include_str!("../tests/fixtures/keystore/v0.json");
+    /// Unversioned file written after address labels and the trusted checkpoint were added
+    const V0_WITH_LABELS_AND_CHECKPOINT: &str =
+        include_str!("../tests/fixtures/keystore/v0_with_labels_and_checkpoint.json");
+    const V1: &str = include_str!("../tests/fixtures/keystore/v1.json");
+
+    const LABELED_ADDRESS: &str = "BS8VLAix3GrdB3j6XWpaZH2DHugHDCkoVSNy3sdFKNqU";
+
+    #[test]
+    fn test_historical_versions_are_read() {
+        for (fixture, format_version, labels, checkpoint) in [
+            (V0, 0, 0, None),
+            (V0_WITH_LABELS_AND_CHECKPOINT, 0, 1, Some(9)),
+            (V1, 1, 1, None),
+        ] {
+            let home = tempfile::tempdir().unwrap();
+            let path = home.path().join("storage.json");
+            std::fs::write(&path, fixture).unwrap();
+
+            let inspection = inspect_keystore(&path).unwrap();
+            assert_eq!(inspection.format_version, format_version);
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), fixture);
+
+            let storage = read_keystore(&path).unwrap();
+            assert_eq!(storage.format_version, KEYSTORE_FORMAT_VERSION);
+            assert_eq!(storage.accounts.len(), 4);
+            assert_eq!(storage.last_synced_block, 12);
+            assert_eq!(storage.address_labels.len(), labels);
+            if labels > 0 {
+                assert_eq!(storage.address_labels[LABELED_ADDRESS], "rent");
+            }
+            assert_eq!(
+                storage
+                    .trusted_checkpoint
+                    .map(|trusted| trusted.checkpoint.block_id),
+                checkpoint
+            );
+
+            let backup = backup_path(&path, format_version);
+            if format_version < KEYSTORE_FORMAT_VERSION {
+                assert_eq!(std::fs::read_to_string(&backup).unwrap(), fixture);
+                let migrated = std::fs::read_to_string(&path).unwrap();
+                assert!(migrated.starts_with("{\n  \"format_version\": 1,"));
+                assert_eq!(inspect_keystore(&path).unwrap().format_version, 1);
+            } else {
+                assert!(!backup.exists());
+                assert_eq!(std::fs::read_to_string(&path).unwrap(), fixture);
+            }
+        }
+    }
+
+    #[test]
+    fn test_newer_version_is_refused_without_writing() {
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join("storage.json");
+        let mut contents = serde_json::from_str::<Value>(V1).unwrap();
+        contents["format_version"] = 2.into();
+        contents["retired_accounts"] = serde_json::json!([LABELED_ADDRESS]);
+        let newer = serde_json::to_string_pretty(&contents).unwrap();
+        std::fs::write(&path, &newer).unwrap();
+
+        let err = read_keystore(&path).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<NewerFormatError>(),
+            Some(&NewerFormatError {
+                path: path.clone(),
+                format_version: 2
+            })
+        );
+        assert!(err.to_string().contains("upgrade the wallet"));
+
+        let inspection = inspect_keystore(&path).unwrap();
+        assert_eq!(inspection.format_version, 2);
+        assert!(inspection.storage.is_none());
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), newer);
+        assert_eq!(std::fs::read_dir(home.path()).unwrap().count(), 1);
+    }
+}
@@ -1,127 +1,261 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
 use anyhow::Result;
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use chain_storage::WalletChainStore;
 use common::{
-    error::ExecutionFailureKind,
-    rpc_primitives::requests::SendTxResponse,
+    block::HashableBlockData,
+    error::{ExecutionFailureKind, SequencerClientError},
+    receipt::TransactionReceipt,
+    rpc_types::{
+        BlockDigest, GetChainInfoResponse, GetLastBlockResponse, GetSequencerInfoResponse,
+        ReadContext, SendTxResponse, TransactionStatus, TxAuditRecord,
+    },
+    sequencer_api::SequencerApi,
     sequencer_client::SequencerClient,
+    soft_confirmation::SoftConfirmation,
     transaction::{EncodedTransaction, NSSATransaction},
 };
 use config::WalletConfig;
-use key_protocol::key_management::key_tree::{chain_index::ChainIndex, traits::KeyNode as _};
-use log::info;
+use key_protocol::key_management::key_tree::{
+    chain_index::{AddressChain, ChainIndex},
+    traits::KeyNode as _,
+};
+use log::{info, warn};
 use nssa::{
-    Account, AccountId, PrivacyPreservingTransaction,
+    Account, AccountId, Amount, Cycles, PrivacyPreservingTransaction,
     privacy_preserving_transaction::message::EncryptedAccountData, program::Program,
 };
-use nssa_core::{Commitment, MembershipProof, SharedSecretKey, program::InstructionData};
+use nssa_core::{
+    Commitment, MembershipProof, SharedSecretKey, account::Nonce, fmt::FmtShort as _,
+    program::InstructionData,
+};
 pub use privacy_preserving_tx::PrivacyPreservingAccount;
-use tokio::io::AsyncWriteExt;
 
 use crate::{
+    account_fetcher::AccountFetcher,
+    address_book::{ADDRESS_BOOK_FILE_NAME, AddressBook},
+    api::{InclusionProgress, InclusionProgressReporter, PriorityFee, TxDryRun},
+    chain_cache::ChainCache,
     config::PersistentStorage,
-    helperfunctions::{
-        fetch_persistent_storage, get_home, produce_data_for_storage, produce_random_nonces,
-    },
+    helperfunctions::{fetch_persistent_storage, produce_data_for_storage, produce_random_nonces},
+    light_client::TrustedCheckpoint,
+    mempool_mirror::{MempoolMirror, NonceConflict},
+    nonce_manager::NonceReservation,
+    output::OutputFormat,
+    paths::{WalletPaths, create_private_dir_all, write_private},
     poller::TxPoller,
+    tx_log::{TX_LOG_FILE_NAME, TxDetails, TxLog, TxLogEntry, TxStatus},
 };
 
 pub const HOME_DIR_ENV_VAR: &str = "NSSA_WALLET_HOME_DIR";
+pub const WALLET_FILE_ENV_VAR: &str = "WALLET_FILE";
+pub const STORAGE_FILE_NAME: &str = "storage.json";
 
+pub mod account_fetcher;
+pub mod address_book;
+pub mod address_history;
+pub mod api;
+pub mod chain_cache;
 pub mod chain_storage;
 pub mod cli;
 pub mod config;
+pub mod daemon;
+#[cfg(test)]
+mod fault_tests;
 pub mod helperfunctions;
+pub mod keystore;
+pub mod light_client;
+pub mod mempool_mirror;
+pub mod nonce_manager;
+pub mod output;
+pub mod paths;
+pub mod payout;
+pub mod pending_balance;
 pub mod poller;
 mod privacy_preserving_tx;
 pub mod program_facades;
+pub mod report;
+pub mod sync_connection;
+#[cfg(test)]
+mod test_utils;
+pub mod tx_decode;
+pub mod tx_log;
+pub mod wallet_lock;
 
 pub struct WalletCore {
     pub storage: WalletChainStore,
     pub poller: TxPoller,
-    pub sequencer_client: Arc<SequencerClient>,
+    pub sequencer_client: Arc<dyn SequencerApi>,
     pub last_synced_block: u64,
+    /// File, which keys and accounts are stored to
+    pub wallet_file: PathBuf,
+    pub tx_log: TxLog,
+    /// Named counterparty addresses, kept next to the wallet file
+    pub address_book: AddressBook,
+    /// Labels of own addresses by their base58 ids, stored in the wallet file
+    pub address_labels: BTreeMap<String, String>,
+    /// Receives estimated inclusion of sent transactions while waiting for them
+    pub inclusion_progress_reporter: Option<InclusionProgressReporter>,
+    /// Priority fee of sent public transfers
+    pub priority_fee: PriorityFee,
+    /// Maximum cycles of sent public transfers, `None` to send them unmetered
+    pub max_cycles: Option<Cycles>,
+    /// Format subcommands print their results in
+    pub output_format: OutputFormat,
+    /// Version and features of the sequencer, recorded at the start of the last sync
+    pub sequencer_info: Option<GetSequencerInfoResponse>,
+    /// Chain data, which rarely changes, kept between invocations
+    pub chain_cache: ChainCache,
+    /// Pending transactions of the sequencer, `None` if mirroring is disabled
+    pub mempool_mirror: Option<Mutex<MempoolMirror>>,
+    /// Block, which light verification follows headers from, stored in the wallet file
+    trusted_checkpoint: Mutex<Option<TrustedCheckpoint>>,
+    /// Held while reserved nonces are being used, so they aren't handed out twice
+    nonce_lock: Arc<tokio::sync::Mutex<()>>,
+    /// Newest block, which state was read at, so later reads don't observe older state
+    observed_block_id: AtomicU64,
 }
 
 impl WalletCore {
     pub async fn start_from_config_update_chain(config: WalletConfig) -> Result<Self> {
-        let basic_auth = config
-            .basic_auth
-            .as_ref()
-            .map(|auth| (auth.username.clone(), auth.password.clone()));
-        let client = Arc::new(SequencerClient::new_with_auth(
-            config.sequencer_addr.clone(),
-            basic_auth,
-        )?);
-        let tx_poller = TxPoller::new(config.clone(), client.clone());
+        let client = Arc::new(new_sequencer_client(&config, &config.sequencer_addr)?);
 
         let PersistentStorage {
+            format_version: _,
             accounts: persistent_accounts,
             last_synced_block,
+            address_labels,
+            trusted_checkpoint,
         } = fetch_persistent_storage().await?;
 
         let storage = WalletChainStore::new(config, persistent_accounts)?;
 
-        Ok(Self {
-            storage,
-            poller: tx_poller,
-            sequencer_client: client.clone(),
+        let paths = WalletPaths::from_env()?;
+
+        let wallet = Self {
             last_synced_block,
-        })
+            address_labels,
+            address_book: AddressBook::new(paths.address_book_file()),
+            wallet_file: paths.wallet_file.clone(),
+            trusted_checkpoint: Mutex::new(trusted_checkpoint),
+            ..Self::new_with_sequencer_client(storage, client, &paths.data_dir)
+        };
+        wallet.negotiate_api_version().await?;
+
+        Ok(wallet)
     }
 
     pub async fn start_from_config_new_storage(
         config: WalletConfig,
         password: String,
     ) -> Result<Self> {
-        let basic_auth = config
-            .basic_auth
-            .as_ref()
-            .map(|auth| (auth.username.clone(), auth.password.clone()));
-        let client = Arc::new(SequencerClient::new_with_auth(
-            config.sequencer_addr.clone(),
-            basic_auth,
-        )?);
-        let tx_poller = TxPoller::new(config.clone(), client.clone());
+        let client = Arc::new(new_sequencer_client(&config, &config.sequencer_addr)?);
 
         let storage = WalletChainStore::new_storage(config, password)?;
 
+        let paths = WalletPaths::from_env()?;
+
         Ok(Self {
+            address_book: AddressBook::new(paths.address_book_file()),
+            wallet_file: paths.wallet_file.clone(),
+            ..Self::new_with_sequencer_client(storage, client, &paths.data_dir)
+        })
+    }
+
+    /// Wallet talking to `sequencer_client`, which keeps its files in `home`
+    ///
+    /// Nothing is synced yet, and no request is sent to the sequencer.
+    pub fn new_with_sequencer_client(
+        storage: WalletChainStore,
+        sequencer_client: Arc<dyn SequencerApi>,
+        home: &Path,
+    ) -> Self {
+        let chain_cache = ChainCache::from_env(home, &storage.wallet_config.sequencer_addr);
+        let mempool_mirror = storage
+            .wallet_config
+            .mempool_mirror_max_age_blocks
+            .map(|max_age_blocks| Mutex::new(MempoolMirror::new(max_age_blocks)));
+
+        Self {
+            poller: TxPoller::new(storage.wallet_config.clone(), sequencer_client.clone()),
             storage,
-            poller: tx_poller,
-            sequencer_client: client.clone(),
+            sequencer_client,
             last_synced_block: 0,
-        })
+            wallet_file: home.join(STORAGE_FILE_NAME),
+            tx_log: TxLog::new(home.join(TX_LOG_FILE_NAME)),
+            address_book: AddressBook::new(home.join(ADDRESS_BOOK_FILE_NAME)),
+            address_labels: BTreeMap::new(),
+            inclusion_progress_reporter: None,
+            priority_fee: PriorityFee::None,
+            max_cycles: None,
+            output_format: OutputFormat::Human,
+            sequencer_info: None,
+            chain_cache,
+            mempool_mirror,
+            trusted_checkpoint: Mutex::default(),
+            nonce_lock: Arc::default(),
+            observed_block_id: AtomicU64::new(0),
+        }
     }
 
-    /// Store persistent data at home
+    /// Fails if the sequencer isn't compatible with the wallet
+    ///
+    /// Version of a compatible sequencer is cached, so it's only negotiated once in a while.
+    /// Failures to reach the sequencer are only logged.
+    pub async fn negotiate_api_version(&self) -> Result<()> {
+        if self.chain_cache.api_version().is_some() {
+            return Ok(());
+        }
+
+        match self.sequencer_client.check_api_version().await {
+            Ok(api_version) => self.chain_cache.set_api_version(api_version),
+            Err(err @ SequencerClientError::IncompatibleApiVersion { .. }) => {
+                return Err(err.into());
+            }
+            Err(err) => warn!("Failed to negotiate API version with sequencer: {err}"),
+        }
+
+        Ok(())
+    }
+
+    /// Store persistent data to wallet file
     pub async fn store_persistent_data(&self) -> Result<PathBuf> {
-        let home = get_home()?;
-        let storage_path = home.join("storage.json");
+        if let Some(parent) = self.wallet_file.parent() {
+            create_private_dir_all(parent)?;
+        }
 
-        let data = produce_data_for_storage(&self.storage.user_data, self.last_synced_block);
+        let data = PersistentStorage {
+            address_labels: self.address_labels.clone(),
+            trusted_checkpoint: self.trusted_checkpoint.lock().unwrap().clone(),
+            ..produce_data_for_storage(&self.storage.user_data, self.last_synced_block)
+        };
         let storage = serde_json::to_vec_pretty(&data)?;
 
-        let mut storage_file = tokio::fs::File::create(storage_path.as_path()).await?;
-        storage_file.write_all(&storage).await?;
+        write_private(&self.wallet_file, storage)?;
 
-        info!("Stored data at {storage_path:#?}");
+        info!("Stored data at {}", self.wallet_file.display());
 
-        Ok(storage_path)
+        Ok(self.wallet_file.clone())
     }
 
-    /// Store persistent data at home
+    /// Store config to config dir of wallet
     pub async fn store_config_changes(&self) -> Result<PathBuf> {
-        let home = get_home()?;
-        let config_path = home.join("wallet_config.json");
+        let paths = WalletPaths::from_env()?;
+        let config_path = paths.config_file();
         let config = serde_json::to_vec_pretty(&self.storage.wallet_config)?;
 
-        let mut config_file = tokio::fs::File::create(config_path.as_path()).await?;
-        config_file.write_all(&config).await?;
+        create_private_dir_all(&paths.config_dir)?;
+        write_private(&config_path, config)?;
 
-        info!("Stored data at {config_path:#?}");
+        info!("Stored data at {}", config_path.display());
 
         Ok(config_path)
     }
@@ -144,33 +278,216 @@ impl WalletCore {
             .generate_new_privacy_preserving_transaction_key_chain(chain_index)
     }
 
+    /// Derive the next receive address of the public account at `account`
+    ///
+    /// `None` if there is no such account in the key tree.
+    pub fn create_new_receive_address_public(
+        &mut self,
+        account: &ChainIndex,
+    ) -> Option<(AccountId, ChainIndex)> {
+        self.storage
+            .user_data
+            .public_key_tree
+            .generate_new_chain_address(account, AddressChain::External)
+    }
+
+    /// Receive addresses of the public account at `account`, which are derived already, in order
+    /// of their indices
+    pub fn receive_addresses_public(&self, account: &ChainIndex) -> Vec<(AccountId, ChainIndex)> {
+        let receive_chain = account.address_chain(AddressChain::External);
+        self.storage
+            .user_data
+            .public_key_tree
+            .key_map
+            .iter()
+            .filter(|(chain_index, _)| chain_index.parent().as_ref() == Some(&receive_chain))
+            .map(|(chain_index, node)| (node.account_id(), chain_index.clone()))
+            .collect()
+    }
+
+    /// Derived receive address of the public account at `account` to hand out next: the first
+    /// one after the last used address
+    ///
+    /// `None` if every derived address is used up, so a new one has to be derived.
+    pub async fn suggested_receive_address(
+        &self,
+        account: &ChainIndex,
+    ) -> Result<Option<(AccountId, ChainIndex)>> {
+        let addresses = self.receive_addresses_public(account);
+        let account_ids = addresses
+            .iter()
+            .map(|(account_id, _)| *account_id)
+            .collect::<Vec<_>>();
+        let mut unused_len = 0;
+        for state in self.get_accounts_public(&account_ids).await {
+            if state? == Account::default() {
+                unused_len += 1;
+            } else {
+                unused_len = 0;
+            }
+        }
+
+        Ok(addresses.into_iter().nth(account_ids.len() - unused_len))
+    }
+
+    /// Label of the own address `account_id`
+    pub fn address_label(&self, account_id: &AccountId) -> Option<&str> {
+        self.address_labels
+            .get(&account_id.to_string())
+            .map(String::as_str)
+    }
+
+    /// Derive a fresh change address of the public account, which `account_id` belongs to
+    ///
+    /// `None` if `account_id` is neither an account of the key tree nor an address of one.
+    pub fn create_change_address_public(
+        &mut self,
+        account_id: AccountId,
+    ) -> Option<(AccountId, ChainIndex)> {
+        let tree = &mut self.storage.user_data.public_key_tree;
+        let account = tree.account_id_map.get(&account_id)?.owning_account()?;
+        tree.generate_new_chain_address(&account, AddressChain::Internal)
+    }
+
+    /// Block, which the next read must not be older than, if anything was read yet
+    fn read_min_block_id(&self) -> Option<u64> {
+        match self.observed_block_id.load(Ordering::Relaxed) {
+            0 => None,
+            block_id => Some(block_id),
+        }
+    }
+
+    /// Records the block a read was computed at, sequencers before API 2.10 don't report it
+    fn observe_read(&self, context: Option<&ReadContext>) {
+        if let Some(context) = context {
+            self.observed_block_id
+                .fetch_max(context.block_id, Ordering::Relaxed);
+        }
+    }
+
     /// Get account balance
-    pub async fn get_account_balance(&self, acc: AccountId) -> Result<u128> {
-        Ok(self
+    ///
+    /// Balance is read at the newest block observed by previous reads or later, so transactions
+    /// aren't built from a state older than the one already seen.
+    pub async fn get_account_balance(&self, acc: AccountId) -> Result<Amount> {
+        let response = self
             .sequencer_client
-            .get_account_balance(acc.to_string())
-            .await?
-            .balance)
+            .get_account_balance_at(acc.to_string(), self.read_min_block_id())
+            .await?;
+        self.observe_read(response.context.as_ref());
+        Ok(response.balance)
     }
 
-    /// Get accounts nonces
-    pub async fn get_accounts_nonces(&self, accs: Vec<AccountId>) -> Result<Vec<u128>> {
-        Ok(self
+    /// Get accounts nonces, read the same way as [`Self::get_account_balance`]
+    pub async fn get_accounts_nonces(&self, accs: Vec<AccountId>) -> Result<Vec<Nonce>> {
+        let response = self
             .sequencer_client
-            .get_accounts_nonces(accs.into_iter().map(|acc| acc.to_string()).collect())
-            .await?
-            .nonces)
+            .get_accounts_nonces_at(
+                accs.into_iter().map(|acc| acc.to_string()).collect(),
+                self.read_min_block_id(),
+            )
+            .await?;
+        self.observe_read(response.context.as_ref());
+        Ok(response.nonces)
+    }
+
+    /// Nonces of the next transactions of `accs`, skipping the ones reserved by journaled
+    /// transactions
+    ///
+    /// Stale reservations are reconciled with sequencer first. The ones, which nonces are
+    /// consumed by transactions unknown to the wallet, are invalidated with a warning.
+    pub async fn next_accounts_nonces(&self, accs: Vec<AccountId>) -> Result<Vec<Nonce>> {
+        let on_chain_nonces = self.get_accounts_nonces(accs.clone()).await?;
+        let entries = match self.tx_log.entries() {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Failed to read transaction log, using on-chain nonces: {err:#}");
+                return Ok(on_chain_nonces);
+            }
+        };
+
+        let mut nonces = Vec::with_capacity(accs.len());
+        for (account_id, on_chain) in accs.iter().zip(on_chain_nonces) {
+            for stale in nonce_manager::stale_reservations(&entries, account_id, on_chain) {
+                self.invalidate_stale_reservation(stale, on_chain).await;
+            }
+            nonces.push(nonce_manager::next_nonce(&entries, account_id, on_chain));
+        }
+
+        Ok(nonces)
+    }
+
+    /// Same as [`Self::next_accounts_nonces`], but nonces aren't handed out again until the
+    /// returned reservation is dropped
+    pub async fn reserve_accounts_nonces(
+        &self,
+        accs: Vec<AccountId>,
+    ) -> Result<(Vec<Nonce>, NonceReservation)> {
+        let guard = self.nonce_lock.clone().lock_owned().await;
+        let nonces = self.next_accounts_nonces(accs).await?;
+
+        Ok((nonces, NonceReservation::new(guard)))
+    }
+
+    async fn invalidate_stale_reservation(&self, stale: &TxLogEntry, on_chain: Nonce) {
+        let reconciled = match self.reconcile_tx_log(Some(&stale.hash)).await {
+            Ok(reconciled) => reconciled,
+            Err(err) => {
+                warn!(
+                    "Failed to reconcile transaction {} with stale nonce: {err:#}",
+                    stale.hash
+                );
+                return;
+            }
+        };
+
+        if reconciled
+            .first()
+            .is_some_and(|entry| entry.status == TxStatus::Failed)
+        {
+            warn!(
+                "Nonce {} of {} is used by a transaction unknown to this wallet, on-chain nonce is \
+                 {on_chain} now. Was the key used by another wallet? Transaction {} will never be \
+                 included",
+                stale.nonce.unwrap_or_default(),
+                stale.sender.as_deref().unwrap_or_default(),
+                stale.hash
+            );
+        }
     }
 
     /// Get account
     pub async fn get_account_public(&self, account_id: AccountId) -> Result<Account> {
         let response = self
             .sequencer_client
-            .get_account(account_id.to_string())
+            .get_account_at(account_id.to_string(), self.read_min_block_id())
             .await?;
+        self.observe_read(response.context.as_ref());
         Ok(response.account)
     }
 
+    /// Fetcher of many public accounts, which uses batches if the sequencer serves them
+    pub async fn account_fetcher(&self) -> AccountFetcher {
+        let features = match self.get_sequencer_info().await {
+            Ok(info) => info.features,
+            Err(err) => {
+                warn!("Failed to get sequencer features, fetching accounts one by one: {err}");
+                vec![]
+            }
+        };
+
+        AccountFetcher::for_features(
+            self.sequencer_client.clone(),
+            self.storage.wallet_config.account_fetch_concurrency,
+            &features,
+        )
+    }
+
+    /// Public accounts in order of `account_ids`, a failure to fetch one doesn't affect others
+    pub async fn get_accounts_public(&self, account_ids: &[AccountId]) -> Vec<Result<Account>> {
+        self.account_fetcher().await.fetch(account_ids).await
+    }
+
     pub fn get_account_public_signing_key(
         &self,
         account_id: &AccountId,
@@ -194,13 +511,373 @@ impl WalletCore {
 
     /// Poll transactions
     pub async fn poll_native_token_transfer(&self, hash: String) -> Result<NSSATransaction> {
-        let transaction_encoded = self.poller.poll_tx(hash).await?;
+        if self.inclusion_progress_reporter.is_some() {
+            self.report_inclusion_progress(&hash).await?;
+        }
+
+        let transaction_encoded = self.poller.poll_tx(hash.clone()).await?;
         let tx_base64_decode = BASE64.decode(transaction_encoded)?;
         let pub_tx = borsh::from_slice::<EncodedTransaction>(&tx_base64_decode).unwrap();
 
+        if let Err(err) = self.tx_log.update_status(&hash, TxStatus::Included, None) {
+            warn!("Failed to update transaction log for {hash}: {err:#}");
+        }
+
         Ok(NSSATransaction::try_from(&pub_tx)?)
     }
 
+    /// Report estimated inclusion of transaction and update it as blocks pass, until transaction
+    /// leaves mempool
+    ///
+    /// Fails if sequencer dropped the transaction.
+    pub async fn report_inclusion_progress(&self, hash: &str) -> Result<()> {
+        let poll_delay =
+            std::time::Duration::from_millis(self.storage.wallet_config.seq_poll_timeout_millis);
+        let mut last_status = None;
+
+        loop {
+            let status = self
+                .sequencer_client
+                .get_transaction_status(hash.to_string())
+                .await?
+                .status;
+
+            if last_status.as_ref() != Some(&status) {
+                self.report_progress(hash, &InclusionProgress::StatusChanged(status.clone()));
+                match &status {
+                    TransactionStatus::Pending { .. } => {}
+                    TransactionStatus::Included { .. } => return Ok(()),
+                    TransactionStatus::Dropped { reason } => {
+                        if let Err(err) = self.tx_log.update_status(hash, TxStatus::Failed, None) {
+                            warn!("Failed to update transaction log for {hash}: {err:#}");
+                        }
+                        anyhow::bail!("Transaction {hash} was dropped by sequencer: {reason}");
+                    }
+                    // Nothing to report, leave it to the poller
+                    TransactionStatus::Unknown => return Ok(()),
+                }
+            }
+
+            last_status = Some(status);
+            tokio::time::sleep(poll_delay).await;
+        }
+    }
+
+    /// Send transaction to sequencer, recording it in the transaction log
+    ///
+    /// Transaction log failures are reported, but never prevent transaction from being sent.
+    pub async fn send_tx_logged(
+        &self,
+        tx: NSSATransaction,
+        details: TxDetails,
+    ) -> Result<SendTxResponse, ExecutionFailureKind> {
+        let entry = TxLogEntry::new_built(&EncodedTransaction::from(tx.clone()), details);
+        let hash = entry.hash.clone();
+        if let Err(err) = self.tx_log.append(&entry) {
+            warn!("Failed to record transaction {hash} in transaction log: {err:#}");
+        }
+
+        for conflict in self.mempool_nonce_conflicts(&hash, &tx).await {
+            let origin = if conflict.journaled {
+                "sent by this wallet"
+            } else {
+                "unknown to this wallet"
+            };
+            warn!(
+                "Transaction {hash} uses nonce {} of {} like pending transaction {}, {origin}, \
+                 at most one of them will be included",
+                conflict.nonce, conflict.sender, conflict.hash
+            );
+        }
+
+        let response = self.submit_tx(&hash, tx).await;
+
+        if let Ok(SendTxResponse {
+            mempool_position: Some(position),
+            mempool_depth: Some(depth),
+            estimated_inclusion_block: Some(block_id),
+            ..
+        }) = &response
+        {
+            self.report_progress(
+                &hash,
+                &InclusionProgress::Submitted {
+                    position: *position,
+                    depth: *depth,
+                    estimated_inclusion_block: *block_id,
+                },
+            );
+        }
+
+        let status = if response.is_ok() {
+            TxStatus::Submitted
+        } else {
+            TxStatus::Failed
+        };
+        if let Err(err) = self.tx_log.update_status(&hash, status, None) {
+            warn!("Failed to update transaction log for {hash}: {err:#}");
+        }
+
+        if let Ok(SendTxResponse {
+            soft_confirmation: Some(soft_confirmation),
+            ..
+        }) = &response
+        {
+            self.record_soft_confirmation(&hash, soft_confirmation)
+                .await;
+        }
+
+        Ok(response?)
+    }
+
+    /// Ask sequencer for its verdict on `tx` without submitting it
+    ///
+    /// Transactions, which the sequencer would reject, get a verdict too. Fails if the sequencer
+    /// can't be asked, e.g. it doesn't serve `validate_transaction` yet.
+    pub async fn dry_run_tx(&self, tx: NSSATransaction) -> Result<TxDryRun, SequencerClientError> {
+        let tx = EncodedTransaction::from(tx);
+        let hash = hex::encode(tx.hash());
+        let verdict = match self.sequencer_client.validate_transaction(tx).await {
+            Ok(response) => Ok(response),
+            Err(err) if err.is_server_busy() || err.is_method_not_found() => return Err(err),
+            Err(SequencerClientError::InternalError(err)) => Err(err
+                .error
+                .data
+                .map_or(err.error.message, |data| data.to_string())),
+            Err(err) => return Err(err),
+        };
+
+        Ok(TxDryRun { hash, verdict })
+    }
+
+    /// Submit transaction `tx` with `hash` to sequencer
+    ///
+    /// Submission is idempotent, so it's retried up to the max number of retries while sequencer
+    /// is unreachable.
+    async fn submit_tx(
+        &self,
+        hash: &str,
+        tx: NSSATransaction,
+    ) -> Result<SendTxResponse, SequencerClientError> {
+        let config = &self.storage.wallet_config;
+        let mut try_error_counter = 0;
+        loop {
+            let response = self.sequencer_client.send_tx(tx.clone().into()).await;
+            match response {
+                Err(err)
+                    if err.is_connection_error()
+                        && try_error_counter < config.seq_poll_max_retries =>
+                {
+                    warn!("Failed to submit transaction {hash}, resubmitting: {err}");
+                    try_error_counter += 1;
+                    tokio::time::sleep(std::time::Duration::from_millis(
+                        config.seq_poll_timeout_millis,
+                    ))
+                    .await;
+                }
+                response => return response,
+            }
+        }
+    }
+
+    /// Replace mirrored pending transactions with the current ones of the sequencer, if the mirror
+    /// is enabled
+    pub async fn refresh_mempool_mirror(&self) -> Result<()> {
+        let Some(mirror) = &self.mempool_mirror else {
+            return Ok(());
+        };
+
+        let snapshot = self.sequencer_client.get_pending_transactions().await?;
+        mirror.lock().unwrap().update(snapshot);
+        Ok(())
+    }
+
+    /// Whether the mempool mirror is enabled and has a snapshot, taking the first one if needed
+    pub(crate) async fn ensure_mempool_mirror(&self) -> bool {
+        let Some(mirror) = &self.mempool_mirror else {
+            return false;
+        };
+        if mirror.lock().unwrap().is_refreshed() {
+            return true;
+        }
+
+        match self.refresh_mempool_mirror().await {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("Failed to mirror pending transactions of sequencer: {err:#}");
+                false
+            }
+        }
+    }
+
+    /// Mirrored pending transactions, which use the nonce of transaction `tx` with `hash`
+    ///
+    /// Empty if the mirror is disabled. The mirror may lag behind the mempool, so conflicts are
+    /// only worth a warning.
+    pub async fn mempool_nonce_conflicts(
+        &self,
+        hash: &str,
+        tx: &NSSATransaction,
+    ) -> Vec<NonceConflict> {
+        let Some((sender, nonce)) = tx.sender_and_nonce() else {
+            return vec![];
+        };
+        if !self.ensure_mempool_mirror().await {
+            return vec![];
+        }
+
+        let journal = self.tx_log.entries().unwrap_or_else(|err| {
+            warn!("Failed to read transaction log: {err:#}");
+            vec![]
+        });
+        let Some(mirror) = &self.mempool_mirror else {
+            return vec![];
+        };
+        mirror
+            .lock()
+            .unwrap()
+            .nonce_conflicts(hash, &sender, nonce, &journal)
+    }
+
+    /// Journal `soft_confirmation` of transaction `hash`, if it's signed by the sequencer
+    async fn record_soft_confirmation(&self, hash: &str, soft_confirmation: &SoftConfirmation) {
+        let advertised_key = match self.get_sequencer_info().await {
+            Ok(info) => info.public_key,
+            Err(err) => {
+                warn!("Failed to get sequencer key to verify soft confirmation: {err}");
+                return;
+            }
+        };
+        let public_key = advertised_key
+            .and_then(|key| hex::decode(key).ok())
+            .and_then(|bytes| bytes.try_into().ok())
+            .and_then(|bytes| nssa::PublicKey::try_new(bytes).ok());
+        let Some(public_key) = public_key else {
+            warn!(
+                "Sequencer advertises no valid public key, soft confirmation of {hash} is ignored"
+            );
+            return;
+        };
+
+        if soft_confirmation.tx_hash != hash || !soft_confirmation.is_valid_for(&public_key) {
+            warn!("Soft confirmation of transaction {hash} is not signed by the sequencer");
+            return;
+        }
+
+        if let Err(err) = self
+            .tx_log
+            .record_soft_confirmation(hash, soft_confirmation.clone())
+        {
+            warn!("Failed to record soft confirmation of {hash} in transaction log: {err:#}");
+        }
+    }
+
+    /// Check non-final transaction log entries against sequencer and update stale statuses
+    ///
+    /// If `hash` is given, only this transaction is reconciled.
+    pub async fn reconcile_tx_log(&self, hash: Option<&str>) -> Result<Vec<TxLogEntry>> {
+        let entries = self.tx_log.entries()?;
+        if let Some(hash) = hash
+            && !entries.iter().any(|entry| entry.hash == hash)
+        {
+            anyhow::bail!("Transaction {hash} is not found in transaction log");
+        }
+
+        let mut reconciled = vec![];
+        for entry in entries
+            .iter()
+            .filter(|entry| hash.is_none_or(|hash| entry.hash == hash))
+        {
+            let mut entry = entry.clone();
+            if !entry.status.is_final() {
+                let found_on_chain = self
+                    .sequencer_client
+                    .get_transaction_by_hash(entry.hash.clone())
+                    .await?
+                    .transaction
+                    .is_some();
+                let current_nonce = match &entry.sender {
+                    Some(sender) if !found_on_chain => self
+                        .get_accounts_nonces(vec![sender.parse()?])
+                        .await?
+                        .first()
+                        .copied(),
+                    _ => None,
+                };
+
+                if let Some(status) =
+                    tx_log::reconcile_status(&entry, found_on_chain, current_nonce, &entries)
+                {
+                    self.tx_log.update_status(&entry.hash, status, None)?;
+                    entry.status = status;
+                }
+
+                if !entry.status.is_final()
+                    && let Some(soft_confirmation) = &entry.soft_confirmation
+                {
+                    let last_block = self.sequencer_client.get_last_block().await?.last_block;
+                    if last_block > soft_confirmation.promised_by_block {
+                        warn!(
+                            "Sequencer promised to include transaction {} by block {}, but it's \
+                             not included at block {last_block}",
+                            entry.hash, soft_confirmation.promised_by_block
+                        );
+                    }
+                }
+            }
+
+            reconciled.push(entry);
+        }
+
+        Ok(reconciled)
+    }
+
+    /// Receipt of transaction `hash`, if it's included and the sequencer still has the receipt
+    pub async fn get_transaction_receipt(&self, hash: &str) -> Result<Option<TransactionReceipt>> {
+        let status = self
+            .sequencer_client
+            .get_transaction_status(hash.to_string())
+            .await?
+            .status;
+        Ok(match status {
+            TransactionStatus::Included { receipt, .. } => receipt,
+            _ => None,
+        })
+    }
+
+    /// Mempool lifecycle events of transaction `hash` recorded by the sequencer, fetched from its
+    /// admin RPC. `None` if the admin auth token isn't configured.
+    pub async fn get_tx_audit(&self, hash: &str) -> Result<Option<Vec<TxAuditRecord>>> {
+        let config = &self.storage.wallet_config;
+        let Some(auth_token) = &config.admin_auth_token else {
+            return Ok(None);
+        };
+
+        let events = new_sequencer_client(config, &config.sequencer_addr)?
+            .get_tx_audit(hash.to_string(), auth_token)
+            .await?
+            .events;
+        Ok(Some(events))
+    }
+
+    /// Mark non-final journaled transaction `hash` as cancelled, releasing its nonce
+    ///
+    /// Sequencer isn't told about it, so the transaction may still be included.
+    pub fn cancel_local_tx(&self, hash: &str) -> Result<TxLogEntry> {
+        let entry = self
+            .tx_log
+            .entry(hash)?
+            .ok_or_else(|| anyhow::anyhow!("Transaction {hash} is not found in transaction log"))?;
+        anyhow::ensure!(
+            !entry.status.is_final(),
+            "Transaction {hash} is already {:?}",
+            entry.status
+        );
+
+        self.tx_log.update_status(hash, TxStatus::Cancelled, None)?;
+        Ok(self.tx_log.entry(hash)?.unwrap_or(entry))
+    }
+
     pub async fn check_private_account_initialized(
         &self,
         account_id: &AccountId,
@@ -232,13 +909,16 @@ impl WalletCore {
             )
             .unwrap();
 
-            println!("Received new acc {res_acc:#?}");
+            info!(
+                "Received new state of account {}",
+                acc_account_id.fmt_short()
+            );
 
             self.storage
                 .insert_private_account_data(*acc_account_id, res_acc);
         }
 
-        println!("Transaction data is {:?}", tx.message);
+        info!("Transaction data is {:?}", tx.message);
 
         Ok(())
     }
@@ -273,19 +953,23 @@ impl WalletCore {
         )?;
 
         let private_account_keys = acc_manager.private_account_keys();
-        let (output, proof) = nssa::privacy_preserving_transaction::circuit::execute_and_prove(
-            &pre_states,
-            instruction_data,
-            acc_manager.visibility_mask(),
-            &produce_random_nonces(private_account_keys.len()),
-            &private_account_keys
-                .iter()
-                .map(|keys| (keys.npk.clone(), keys.ssk.clone()))
-                .collect::<Vec<_>>(),
-            &acc_manager.private_account_auth(),
-            program,
-        )
-        .unwrap();
+        let prove_options = nssa::privacy_preserving_transaction::circuit::ProveOptions::default()
+            .with_backend(self.storage.wallet_config.prover_backend);
+        let (output, proof, _) =
+            nssa::privacy_preserving_transaction::circuit::execute_and_prove_with_options(
+                &pre_states,
+                instruction_data,
+                acc_manager.visibility_mask(),
+                &produce_random_nonces(private_account_keys.len()),
+                &private_account_keys
+                    .iter()
+                    .map(|keys| (keys.npk.clone(), keys.ssk.clone()))
+                    .collect::<Vec<_>>(),
+                &acc_manager.private_account_auth(),
+                program,
+                &prove_options,
+            )
+            .unwrap();
 
         let message =
             nssa::privacy_preserving_transaction::message::Message::try_from_circuit_output(
@@ -313,12 +997,45 @@ impl WalletCore {
             .collect();
 
         Ok((
-            self.sequencer_client.send_tx_private(tx).await?,
+            self.send_tx_logged(tx.into(), TxDetails::default()).await?,
             shared_secrets,
         ))
     }
 
     pub async fn sync_to_block(&mut self, block_id: u64) -> Result<()> {
+        self.sync_to_block_with_progress(block_id, |_| {}).await
+    }
+
+    /// Same as [`Self::sync_to_block`], but calls `on_block_synced` with id of every synced block
+    pub async fn sync_to_block_with_progress(
+        &mut self,
+        block_id: u64,
+        on_block_synced: impl FnMut(u64),
+    ) -> Result<()> {
+        self.sync_blocks(block_id, None, on_block_synced).await
+    }
+
+    /// Same as [`Self::sync_to_block_with_progress`] to the last block of `tip` response, which
+    /// is checked to be the advertised one
+    pub async fn sync_to_tip_with_progress(
+        &mut self,
+        tip: &GetLastBlockResponse,
+        on_block_synced: impl FnMut(u64),
+    ) -> Result<()> {
+        self.sync_blocks(
+            tip.last_block,
+            tip.last_block_digest.clone(),
+            on_block_synced,
+        )
+        .await
+    }
+
+    async fn sync_blocks(
+        &mut self,
+        block_id: u64,
+        advertised_last: Option<BlockDigest>,
+        mut on_block_synced: impl FnMut(u64),
+    ) -> Result<()> {
         use futures::TryStreamExt as _;
 
         if self.last_synced_block >= block_id {
@@ -326,27 +1043,70 @@ impl WalletCore {
         }
 
         let before_polling = std::time::Instant::now();
-        let num_of_blocks = block_id - self.last_synced_block;
-        println!("Syncing to block {block_id}. Blocks to sync: {num_of_blocks}");
+        info!(
+            "Syncing to block {block_id}. Blocks to sync: {}",
+            block_id - self.last_synced_block
+        );
+
+        match self.sequencer_client.get_sequencer_info().await {
+            Ok(info) => self.record_sequencer_info(info)?,
+            Err(err) => warn!("Failed to get sequencer info: {err}"),
+        }
 
         let poller = self.poller.clone();
-        let mut blocks =
-            std::pin::pin!(poller.poll_block_range(self.last_synced_block + 1..=block_id));
+        let mut blocks = std::pin::pin!(
+            poller.poll_block_range(self.last_synced_block + 1..=block_id, advertised_last)
+        );
+
+        let pending_hashes = self.tx_log.pending_hashes().unwrap_or_else(|err| {
+            warn!("Failed to read transaction log: {err:#}");
+            vec![]
+        });
+
+        let mut address_lookahead = self.public_address_lookahead();
 
-        let bar = indicatif::ProgressBar::new(num_of_blocks);
         while let Some(block) = blocks.try_next().await? {
+            // Nothing from the block is applied, so sync stops right before it
+            ensure_block_supported(&block, self.sequencer_info.as_ref())?;
+            if self
+                .chain_cache
+                .observe_params_version(block.params_version)
+            {
+                info!(
+                    "Chain parameters changed to version {} at block {}, chain cache is dropped",
+                    block.params_version, block.block_id
+                );
+            }
+
+            let mut touched = vec![];
+
             for tx in block.transactions {
+                let hash = hex::encode(tx.hash());
+                if pending_hashes.contains(&hash)
+                    && let Err(err) =
+                        self.tx_log
+                            .update_status(&hash, TxStatus::Included, Some(block.block_id))
+                {
+                    warn!("Failed to update transaction log for {hash}: {err:#}");
+                }
+
+                // Private post-states of legacy transactions are encrypted in the legacy
+                // account encoding, so they can't be decrypted
+                if tx.tx_kind.predates_u64_nonces() {
+                    continue;
+                }
                 let nssa_tx = NSSATransaction::try_from(&tx)?;
+                touched.extend(nssa_tx.public_account_ids());
                 self.sync_private_accounts_with_tx(nssa_tx);
             }
+            self.extend_touched_address_chains(&touched, &mut address_lookahead);
 
             self.last_synced_block = block.block_id;
             self.store_persistent_data().await?;
-            bar.inc(1);
+            on_block_synced(block.block_id);
         }
-        bar.finish();
 
-        println!(
+        info!(
             "Synced to block {block_id} in {:?}",
             before_polling.elapsed()
         );
@@ -354,6 +1114,148 @@ impl WalletCore {
         Ok(())
     }
 
+    /// Public addresses within the gap limit after the derived ones of every account chain, by
+    /// their ids
+    fn public_address_lookahead(&self) -> HashMap<AccountId, ChainIndex> {
+        let tree = &self.storage.user_data.public_key_tree;
+        let mut lookahead = HashMap::new();
+        for account in tree
+            .key_map
+            .keys()
+            .filter(|chain_index| chain_index.chain().len() == 1)
+        {
+            for chain in AddressChain::ALL {
+                let addresses = tree
+                    .chain_lookahead(account, chain, cli::DEFAULT_GAP_LIMIT)
+                    .unwrap_or_default();
+                for (account_id, index) in addresses {
+                    lookahead.insert(account_id, account.chain_address(chain, index));
+                }
+            }
+        }
+
+        lookahead
+    }
+
+    /// Derives public addresses up to the ones of `lookahead`, which `touched` has, as another
+    /// wallet with the same seed must have handed them out, and refreshes `lookahead`
+    fn extend_touched_address_chains(
+        &mut self,
+        touched: &[AccountId],
+        lookahead: &mut HashMap<AccountId, ChainIndex>,
+    ) {
+        let hits = touched
+            .iter()
+            .filter_map(|account_id| lookahead.get(account_id))
+            .filter_map(ChainIndex::as_chain_address)
+            .collect::<Vec<_>>();
+        if hits.is_empty() {
+            return;
+        }
+
+        let tree = &mut self.storage.user_data.public_key_tree;
+        for (account, chain, index) in hits {
+            let derived = tree
+                .extend_chain_to(&account, chain, index)
+                .unwrap_or_default();
+            for (account_id, chain_index) in derived {
+                info!(
+                    "Derived address {account_id} at {} after activity on it",
+                    chain_index.to_path_string()
+                );
+            }
+        }
+        *lookahead = self.public_address_lookahead();
+    }
+
+    /// Switches to the sequencer at `addr`, which must serve the same chain as the current one
+    ///
+    /// Nothing is synced from the new sequencer before its genesis is verified. Configured
+    /// `sequencer_addr` is left untouched.
+    pub async fn switch_sequencer(&mut self, addr: &str) -> Result<()> {
+        let Some(genesis_hash) = self
+            .sequencer_info
+            .as_ref()
+            .and_then(|info| info.genesis_hash.clone())
+        else {
+            anyhow::bail!("Genesis of the current sequencer is unknown, can't verify {addr}");
+        };
+
+        let client: Arc<dyn SequencerApi> =
+            Arc::new(new_sequencer_client(&self.storage.wallet_config, addr)?);
+        client.check_api_version().await?;
+        let info = client.get_sequencer_info().await?;
+        anyhow::ensure!(
+            info.genesis_hash.as_ref() == Some(&genesis_hash),
+            "Sequencer {addr} serves another chain with genesis {:?}, expected {genesis_hash}",
+            info.genesis_hash
+        );
+
+        self.poller = TxPoller::new(self.storage.wallet_config.clone(), client.clone());
+        self.sequencer_client = client;
+        self.record_sequencer_info(info)
+    }
+
+    /// Version and features of the sequencer: the recorded ones, the cached ones within their
+    /// TTL or fetched ones otherwise
+    pub async fn get_sequencer_info(
+        &self,
+    ) -> Result<GetSequencerInfoResponse, SequencerClientError> {
+        if let Some(info) = &self.sequencer_info {
+            return Ok(info.clone());
+        }
+        if let Some(info) = self.chain_cache.sequencer_info() {
+            return Ok(info);
+        }
+
+        let info = self.sequencer_client.get_sequencer_info().await?;
+        self.chain_cache.set_sequencer_info(info.clone());
+        Ok(info)
+    }
+
+    /// Chain parameters, cached ones within their TTL or fetched ones otherwise
+    ///
+    /// Last block of cached chain info lags behind by up to [`chain_cache::CHAIN_INFO_TTL`].
+    pub async fn get_chain_info(&self) -> Result<GetChainInfoResponse, SequencerClientError> {
+        if let Some(info) = self.chain_cache.chain_info() {
+            return Ok(info);
+        }
+
+        let info = self.sequencer_client.get_chain_info().await?;
+        self.chain_cache.set_chain_info(info.clone());
+        Ok(info)
+    }
+
+    /// Records version and features of the sequencer, failing if it serves another chain than
+    /// the one recorded before
+    pub fn record_sequencer_info(&mut self, info: GetSequencerInfoResponse) -> Result<()> {
+        if let Some(known_genesis_hash) = self
+            .sequencer_info
+            .as_ref()
+            .and_then(|known_info| known_info.genesis_hash.as_ref())
+            && let Some(genesis_hash) = &info.genesis_hash
+        {
+            anyhow::ensure!(
+                known_genesis_hash == genesis_hash,
+                "Sequencer switched to another chain with genesis {genesis_hash}, expected \
+                 {known_genesis_hash}"
+            );
+        }
+
+        let unsupported_tx_kinds = info.unsupported_tx_kinds();
+        if !unsupported_tx_kinds.is_empty() {
+            warn!(
+                "Sequencer {} accepts transaction kinds {unsupported_tx_kinds:?} unsupported by \
+                 the wallet, blocks containing them can't be synced",
+                info.version
+            );
+        }
+        self.chain_cache.set_sequencer_info(info.clone());
+        self.sequencer_info = Some(info);
+
+        Ok(())
+    }
+
     fn sync_private_accounts_with_tx(&mut self, tx: NSSATransaction) {
         let NSSATransaction::PrivacyPreserving(tx) = tx else {
             return;
@@ -405,10 +1307,634 @@ impl WalletCore {
 
         for (affected_account_id, new_acc) in affected_accounts {
             info!(
-                "Received new account for account_id {affected_account_id:#?} with account object {new_acc:#?}"
+                "Received new state of account {}",
+                affected_account_id.fmt_short()
             );
             self.storage
                 .insert_private_account_data(affected_account_id, new_acc);
         }
     }
 }
+
+/// Client of the sequencer at `addr`, authenticated with credentials from `config`
+fn new_sequencer_client(config: &WalletConfig, addr: &str) -> Result<SequencerClient> {
+    let basic_auth = config
+        .basic_auth
+        .as_ref()
+        .map(|auth| (auth.username.clone(), auth.password.clone()));
+    SequencerClient::new_with_auth(addr.to_string(), basic_auth)
+}
+
+/// Fails with an "upgrade required" error, if `block` contains transactions of kinds unsupported by
+/// the wallet
+fn ensure_block_supported(
+    block: &HashableBlockData,
+    sequencer_info: Option<&GetSequencerInfoResponse>,
+) -> Result<()> {
+    let Some(tx) = block
+        .transactions
+        .iter()
+        .find(|tx| !tx.tx_kind.is_supported())
+    else {
+        return Ok(());
+    };
+
+    let sequencer_version = sequencer_info
+        .map(|info| format!(" of sequencer {}", info.version))
+        .unwrap_or_default();
+    anyhow::bail!(
+        "Upgrade required: block {} contains transaction of kind {} unsupported by wallet {}, \
+         synced up to block {}{sequencer_version}",
+        block.block_id,
+        tx.tx_kind.tag(),
+        env!("CARGO_PKG_VERSION"),
+        block.block_id.saturating_sub(1),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::{mock_chain::MockChain, transaction::TxKind};
+    use nssa::error::NssaError;
+
+    use super::*;
+    use crate::{
+        api::TransferOptions, program_facades::native_token_transfer::NativeTokenTransfer,
+        test_utils,
+    };
+
+    fn block_with_kinds(block_id: u64, kinds: &[TxKind]) -> HashableBlockData {
+        HashableBlockData {
+            block_id,
+            prev_block_hash: [0; 32],
+            timestamp: 0,
+            transactions: kinds
+                .iter()
+                .map(|tx_kind| EncodedTransaction {
+                    tx_kind: *tx_kind,
+                    encoded_transaction_data: vec![],
+                })
+                .collect(),
+            params_version: 0,
+            dev_proved: false,
+        }
+    }
+
+    #[test]
+    fn test_sync_halts_at_block_with_unknown_tx_kind() {
+        let supported = block_with_kinds(4, &TxKind::DECODABLE);
+        assert!(ensure_block_supported(&supported, None).is_ok());
+
+        // Block of a newer sequencer, as received by the wallet
+        let bytes =
+            borsh::to_vec(&block_with_kinds(5, &[TxKind::Public, TxKind::Unknown(10)])).unwrap();
+        let block: HashableBlockData = borsh::from_slice(&bytes).unwrap();
+
+        let err = ensure_block_supported(&block, None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Upgrade required: block 5 contains transaction of kind 10"));
+        assert!(err.contains("synced up to block 4"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_gives_verdict_without_submitting() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        let dry_run = NativeTokenTransfer(&wallet)
+            .dry_run_time_locked_public_transfer(from, to, Amount(300), None)
+            .await
+            .unwrap();
+        assert_eq!(dry_run.verdict.unwrap().status, "Transaction submitted");
+        assert_eq!(chain.mempool_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_builder_checks_rules_of_native_transfers() {
+        let home = tempfile::tempdir().unwrap();
+        let rate = Amount(1);
+        let chain = Arc::new(test_utils::funded_chain(1000).with_storage_deposit_per_byte(rate));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+        let new_account = AccountId::new([7; 32]);
+        let min_amount = nssa::public_transaction::min_account_creation_amount(rate);
+        let transfers = NativeTokenTransfer(&wallet);
+
+        // Zero-amount transfers are valid
+        transfers
+            .dry_run_time_locked_public_transfer(from, to, Amount::ZERO, None)
+            .await
+            .unwrap();
+
+        let err = transfers
+            .dry_run_time_locked_public_transfer(from, from, Amount(5), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionFailureKind::InvalidTransfer(NssaError::SelfTransfer { .. })
+        ));
+
+        let err = transfers
+            .send_public_transfer_with_nonce(
+                from,
+                new_account,
+                Amount(min_amount.0 - 1),
+                0,
+                Amount::ZERO,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionFailureKind::InvalidTransfer(NssaError::AccountCreationBelowMinimum { .. })
+        ));
+        assert_eq!(chain.mempool_len(), 0);
+
+        transfers
+            .send_public_transfer_with_nonce(from, new_account, min_amount, 0, Amount::ZERO)
+            .await
+            .unwrap();
+        chain.produce_block();
+        assert_eq!(wallet.balance(new_account).await.unwrap(), min_amount);
+    }
+
+    #[tokio::test]
+    async fn test_public_transfer_is_included_and_logged() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        let hash = wallet
+            .transfer(from, to, Amount(300), TransferOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(chain.mempool_len(), 1);
+
+        let block_id = chain.produce_block();
+        assert_eq!(
+            wallet
+                .wait_for_inclusion(&hash, Duration::from_secs(1))
+                .await
+                .unwrap(),
+            block_id
+        );
+        assert_eq!(wallet.balance(from).await.unwrap(), Amount(700));
+        assert_eq!(wallet.balance(to).await.unwrap(), Amount(1300));
+
+        let entry = wallet.tx_log.entry(&hash).unwrap().unwrap();
+        assert_eq!(entry.status, TxStatus::Included);
+        assert_eq!(entry.block_id, Some(block_id));
+        assert_eq!(entry.nonce, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_transfers_use_nonce_of_the_chain() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000).with_auto_block_production());
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        let hash = wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(wallet.tx_log.entry(&hash).unwrap().unwrap().nonce, Some(1));
+        assert_eq!(chain.account(&from).nonce, 2);
+        assert_eq!(chain.account(&from).balance, Amount(800));
+    }
+
+    #[tokio::test]
+    async fn test_reads_dont_go_back_to_older_state() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, _) = test_utils::preconfigured_public_accounts();
+
+        chain.produce_block();
+        assert_eq!(
+            wallet.get_account_balance(from).await.unwrap(),
+            Amount(1000)
+        );
+
+        // Sequencer, which is behind the state already read, e.g. one restored from a backup
+        wallet.sequencer_client = Arc::new(test_utils::funded_chain(1000));
+        let err = wallet.get_accounts_nonces(vec![from]).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<SequencerClientError>()
+                .and_then(SequencerClientError::stale_snapshot_block_id),
+            Some(common::mock_chain::MOCK_GENESIS_ID)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_inclusion_fails_for_dropped_transfer() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        // Second transfer is built with the nonce of the first one, so only the first one applies
+        let first = wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        let second = NativeTokenTransfer(&wallet)
+            .send_public_transfer_with_nonce(from, to, Amount(200), 0, Amount::ZERO)
+            .await
+            .unwrap()
+            .tx_hash;
+        chain.produce_block();
+
+        assert!(
+            wallet
+                .wait_for_inclusion(&first, Duration::from_secs(1))
+                .await
+                .is_ok()
+        );
+        let err = wallet
+            .wait_for_inclusion(&second, Duration::from_secs(1))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("was dropped by sequencer"));
+        assert_eq!(
+            wallet.tx_log.entry(&second).unwrap().unwrap().status,
+            TxStatus::Failed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_inclusion_times_out_for_pending_transfer() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        let hash = wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+
+        let err = wallet
+            .wait_for_inclusion(&hash, Duration::from_millis(20))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("wasn't included within"));
+        assert_eq!(chain.mempool_len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_errors_are_reported_before_sending() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        let overspending = wallet
+            .transfer(from, to, Amount(5000), TransferOptions::default())
+            .await;
+        assert!(matches!(
+            overspending,
+            Err(ExecutionFailureKind::InsufficientFundsError)
+        ));
+
+        let not_owned = wallet
+            .transfer(
+                AccountId::new([7; 32]),
+                to,
+                Amount(10),
+                TransferOptions::default(),
+            )
+            .await;
+        assert!(matches!(
+            not_owned,
+            Err(ExecutionFailureKind::KeyNotFoundError)
+        ));
+
+        chain.set_unavailable(true);
+        let unavailable = wallet
+            .transfer(from, to, Amount(10), TransferOptions::default())
+            .await;
+        assert!(matches!(
+            unavailable,
+            Err(ExecutionFailureKind::SequencerError)
+        ));
+        assert_eq!(chain.mempool_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sync_follows_chain_and_stores_wallet() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000).with_auto_block_production());
+        let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+        wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+
+        let last_block = wallet.sync().await.unwrap();
+
+        assert_eq!(last_block, chain.last_block_id());
+        assert_eq!(wallet.last_synced_block, last_block);
+        assert!(wallet.wallet_file.exists());
+        assert_eq!(wallet.sequencer_info.as_ref().unwrap().version, "mock");
+    }
+
+    #[tokio::test]
+    async fn test_cached_chain_data_is_served_offline() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        wallet.negotiate_api_version().await.unwrap();
+        wallet.get_chain_info().await.unwrap();
+        wallet.get_sequencer_info().await.unwrap();
+
+        // Next invocation doesn't reach the sequencer
+        chain.set_unavailable(true);
+        let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        assert!(wallet.chain_cache.api_version().is_some());
+        assert_eq!(
+            wallet.get_chain_info().await.unwrap().genesis_id,
+            common::mock_chain::MOCK_GENESIS_ID
+        );
+        assert_eq!(wallet.get_sequencer_info().await.unwrap().version, "mock");
+
+        wallet.chain_cache = ChainCache::bypassed();
+        assert!(wallet.get_chain_info().await.is_err());
+        assert!(wallet.get_sequencer_info().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sync_drops_cache_on_params_version_bump() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        assert_eq!(wallet.get_chain_info().await.unwrap().params_version, 0);
+
+        chain.bump_params_version();
+        chain.produce_block();
+        // Cached chain info is served within its TTL
+        assert_eq!(wallet.get_chain_info().await.unwrap().params_version, 0);
+
+        wallet.sync().await.unwrap();
+        assert_eq!(wallet.get_chain_info().await.unwrap().params_version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_detects_block_changed_after_advertising() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000).with_auto_block_production());
+        let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+        wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        let corrupted_block = chain.last_block_id();
+        chain.corrupt_block(corrupted_block);
+
+        let err = wallet.sync().await.unwrap_err();
+
+        assert!(
+            err.to_string().starts_with(&format!(
+                "Sequencer served inconsistent data: block {corrupted_block} has hash"
+            )),
+            "{err:#}"
+        );
+        assert!(wallet.last_synced_block < corrupted_block);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_tx_log_picks_up_inclusion() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+        let hash = wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+
+        let pending = wallet.reconcile_tx_log(Some(hash.as_str())).await.unwrap();
+        assert_eq!(pending[0].status, TxStatus::Submitted);
+
+        chain.produce_block();
+        let reconciled = wallet.reconcile_tx_log(None).await.unwrap();
+        assert_eq!(reconciled.len(), 1);
+        assert_eq!(reconciled[0].status, TxStatus::Included);
+        assert!(wallet.reconcile_tx_log(Some("00")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rapid_transfers_reserve_consecutive_nonces() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        // None of the transfers is included before the next one is built
+        let (first, second) = tokio::join!(
+            wallet.transfer(from, to, Amount(100), TransferOptions::default()),
+            wallet.transfer(from, to, Amount(100), TransferOptions::default()),
+        );
+        let third = wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+
+        let mut nonces = [first.unwrap(), second.unwrap(), third]
+            .map(|hash| wallet.tx_log.entry(&hash).unwrap().unwrap().nonce.unwrap());
+        nonces.sort_unstable();
+        assert_eq!(nonces, [0, 1, 2]);
+
+        chain.produce_block();
+        assert_eq!(chain.account(&from).nonce, 3);
+        assert_eq!(chain.account(&from).balance, Amount(700));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_transfer_frees_its_nonce() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        // Overspending transfer is accepted, but dropped once a block is produced
+        let dropped = NativeTokenTransfer(&wallet)
+            .send_public_transfer_with_nonce(from, to, Amount(5000), 0, Amount::ZERO)
+            .await
+            .unwrap()
+            .tx_hash;
+        assert_eq!(wallet.next_accounts_nonces(vec![from]).await.unwrap(), [1]);
+        chain.produce_block();
+        assert!(
+            wallet
+                .wait_for_inclusion(&dropped, Duration::from_secs(1))
+                .await
+                .is_err()
+        );
+
+        let hash = wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(wallet.tx_log.entry(&hash).unwrap().unwrap().nonce, Some(0));
+        chain.produce_block();
+        assert_eq!(chain.account(&from).balance, Amount(900));
+    }
+
+    #[tokio::test]
+    async fn test_external_nonce_jump_invalidates_stale_reservations() {
+        let home = tempfile::tempdir().unwrap();
+        let other_home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        // Another wallet with the same keys, but its own transaction log
+        let other_wallet = test_utils::mock_wallet(chain.clone(), other_home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        for _ in 0..2 {
+            other_wallet
+                .transfer(from, to, Amount(100), TransferOptions::default())
+                .await
+                .unwrap();
+        }
+        // Wallet doesn't know about the transfers of the other one and reuses their nonce
+        let stale = wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        chain.produce_block();
+        assert_eq!(chain.account(&from).nonce, 2);
+
+        let hash = wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            wallet.tx_log.entry(&stale).unwrap().unwrap().status,
+            TxStatus::Failed
+        );
+        assert_eq!(wallet.tx_log.entry(&hash).unwrap().unwrap().nonce, Some(2));
+        chain.produce_block();
+        assert_eq!(chain.account(&from).nonce, 3);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_transaction_releases_its_nonce() {
+        let home = tempfile::tempdir().unwrap();
+        let wallet = test_utils::mock_wallet(Arc::new(MockChain::new(&[])), home.path());
+        let account_id = AccountId::new([1; 32]);
+        let mut tx = common::test_utils::produce_dummy_empty_transaction();
+        tx.encoded_transaction_data.push(1);
+        let entry = TxLogEntry::new_built(
+            &tx,
+            TxDetails {
+                sender: Some(account_id),
+                nonce: Some(0),
+                ..Default::default()
+            },
+        );
+        wallet.tx_log.append(&entry).unwrap();
+
+        let (nonces, _reservation) = wallet
+            .reserve_accounts_nonces(vec![account_id])
+            .await
+            .unwrap();
+        assert_eq!(nonces, [1]);
+
+        let cancelled = wallet.cancel_local_tx(&entry.hash).unwrap();
+        assert_eq!(cancelled.status, TxStatus::Cancelled);
+        assert_eq!(
+            wallet.next_accounts_nonces(vec![account_id]).await.unwrap(),
+            [0]
+        );
+        assert!(wallet.cancel_local_tx(&entry.hash).is_err());
+        assert!(wallet.cancel_local_tx("00").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mempool_mirror_reports_transactions_unknown_to_journal() {
+        let home = tempfile::tempdir().unwrap();
+        let other_home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000).with_pending_transactions());
+        let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        wallet.mempool_mirror = Some(Mutex::new(MempoolMirror::new(10)));
+        // Another wallet with the same keys, but its own transaction log
+        let other_wallet = test_utils::mock_wallet(chain.clone(), other_home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        let foreign = other_wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        // Journal has nothing at nonce 0, and the mirror only warns, so the transfer is sent
+        let hash = wallet
+            .transfer(from, to, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(chain.mempool_len(), 2);
+
+        wallet.refresh_mempool_mirror().await.unwrap();
+        let journal = wallet.tx_log.entries().unwrap();
+        let conflicts = wallet
+            .mempool_mirror
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .nonce_conflicts(&hash, &from, 0, &journal);
+        assert_eq!(
+            conflicts,
+            [NonceConflict {
+                hash: foreign,
+                sender: from,
+                nonce: 0,
+                journaled: false,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_derives_addresses_up_to_paid_one() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let mut wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, _) = test_utils::preconfigured_public_accounts();
+        let (_, account) = wallet.create_new_account_public(None);
+
+        // Third receive address, which another wallet with the same seed handed out
+        let (paid, _) = wallet
+            .storage
+            .user_data
+            .public_key_tree
+            .chain_lookahead(&account, AddressChain::External, cli::DEFAULT_GAP_LIMIT)
+            .unwrap()[2];
+        wallet
+            .transfer(from, paid, Amount(100), TransferOptions::default())
+            .await
+            .unwrap();
+        chain.produce_block();
+        wallet.sync().await.unwrap();
+
+        let addresses = wallet.receive_addresses_public(&account);
+        assert_eq!(addresses.len(), 3);
+        assert_eq!(
+            addresses[2],
+            (paid, account.chain_address(AddressChain::External, 2))
+        );
+    }
+}
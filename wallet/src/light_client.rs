@@ -0,0 +1,275 @@
+//! Light verification of public accounts, which trusts the sequencer only for its signatures.
+//!
+//! Blocks aren't fetched, only their headers signed by the sequencer, see
+//! [`common::signed_header`]. Headers are followed from a trusted checkpoint stored in the wallet
+//! file, which is the genesis block on first use. Accounts are fetched with their proofs against
+//! the state root in the signed header of the last block, so a sequencer serving an account,
+//! which doesn't match its state, is caught.
+//!
+//! The checkpoint binds the sequencer key, so a sequencer advertising another key is rejected
+//! until the checkpoint is dropped from the wallet file.
+
+use std::fmt::Display;
+
+use anyhow::{Context as _, Result};
+use common::{
+    rpc_types::MAX_BLOCK_HEADERS_PER_REQUEST,
+    signed_header::{Checkpoint, SignedBlockHeader, verify_header_chain},
+};
+use log::info;
+use nssa::{Account, AccountId, PublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::WalletCore;
+
+/// Block, which headers are followed from, with the key of the sequencer signing them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedCheckpoint {
+    #[serde(flatten)]
+    pub checkpoint: Checkpoint,
+    /// Hex encoded public key of the sequencer
+    pub sequencer_public_key: String,
+}
+
+impl TrustedCheckpoint {
+    pub fn public_key(&self) -> Result<PublicKey> {
+        parse_public_key(&self.sequencer_public_key)
+    }
+}
+
+/// Response of the sequencer, which doesn't match what it signed
+///
+/// Other failures, like unreachable sequencer, are reported as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationError {
+    pub reason: String,
+}
+
+impl Display for VerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+impl WalletCore {
+    /// Public account proven against a header signed by the sequencer, which follows the trusted
+    /// checkpoint
+    ///
+    /// The checkpoint advances to the verified header, it's stored with persistent data.
+    /// Responses, which fail verification, fail with [`VerificationError`].
+    pub async fn get_account_public_verified(&self, account_id: AccountId) -> Result<Account> {
+        let trusted = self.trusted_checkpoint_or_genesis().await?;
+        let public_key = trusted.public_key()?;
+        let response = self
+            .sequencer_client
+            .get_account_with_proof(account_id.to_string())
+            .await?;
+
+        let checkpoint = self
+            .follow_headers(&trusted.checkpoint, &response.header, &public_key)
+            .await?;
+        let state_root = response
+            .header
+            .state_root_bytes()
+            .ok_or_else(|| verification_failure("Header of the last block has no state root"))?;
+        if response.proven_state_root(&account_id) != Some(state_root) {
+            return Err(verification_failure(format!(
+                "Proof of account {account_id} doesn't match the state root of block {}",
+                response.header.block_id
+            )));
+        }
+
+        // Concurrent verifications may have advanced it further
+        let mut stored = self.trusted_checkpoint.lock().unwrap();
+        if stored
+            .as_ref()
+            .is_none_or(|stored| stored.checkpoint.block_id < checkpoint.block_id)
+        {
+            *stored = Some(TrustedCheckpoint {
+                checkpoint,
+                ..trusted
+            });
+        }
+        Ok(response.account)
+    }
+
+    /// Trusted checkpoint, or the genesis block with the key advertised by the sequencer if
+    /// there is none yet
+    ///
+    /// A trusted sequencer key, which differs from the advertised one, fails.
+    async fn trusted_checkpoint_or_genesis(&self) -> Result<TrustedCheckpoint> {
+        let info = self.get_sequencer_info().await?;
+        let advertised_key = info
+            .public_key
+            .context("Sequencer doesn't advertise its public key")?;
+
+        if let Some(trusted) = self.trusted_checkpoint.lock().unwrap().clone() {
+            if !trusted
+                .sequencer_public_key
+                .eq_ignore_ascii_case(&advertised_key)
+            {
+                return Err(verification_failure(format!(
+                    "Sequencer advertises key {advertised_key}, but headers since block {} are \
+                     trusted for key {}",
+                    trusted.checkpoint.block_id, trusted.sequencer_public_key
+                )));
+            }
+            return Ok(trusted);
+        }
+
+        let genesis_id = self.get_chain_info().await?.genesis_id;
+        let genesis = self
+            .sequencer_client
+            .get_block_headers(genesis_id..=genesis_id)
+            .await?
+            .headers
+            .into_iter()
+            .next()
+            .with_context(|| format!("Sequencer serves no header of genesis {genesis_id}"))?;
+        if !genesis.is_valid_for(&parse_public_key(&advertised_key)?) {
+            return Err(verification_failure(
+                "Genesis header isn't signed by the sequencer",
+            ));
+        }
+        if let Some(genesis_hash) = &info.genesis_hash
+            && !genesis_hash.eq_ignore_ascii_case(&genesis.hash)
+        {
+            return Err(verification_failure(format!(
+                "Genesis header has hash {}, but the sequencer advertises {genesis_hash}",
+                genesis.hash
+            )));
+        }
+
+        info!("Trusting genesis block {genesis_id} on first use");
+        Ok(TrustedCheckpoint {
+            checkpoint: Checkpoint::from(&genesis),
+            sequencer_public_key: advertised_key,
+        })
+    }
+
+    /// Follows headers from `checkpoint` to `header`, returns the checkpoint at `header`
+    async fn follow_headers(
+        &self,
+        checkpoint: &Checkpoint,
+        header: &SignedBlockHeader,
+        public_key: &PublicKey,
+    ) -> Result<Checkpoint> {
+        if header.block_id <= checkpoint.block_id {
+            if header.block_id == checkpoint.block_id
+                && header.hash.eq_ignore_ascii_case(&checkpoint.hash)
+                && header.is_valid_for(public_key)
+            {
+                return Ok(checkpoint.clone());
+            }
+            return Err(verification_failure(format!(
+                "Header of block {} doesn't follow the trusted block {}",
+                header.block_id, checkpoint.block_id
+            )));
+        }
+
+        let mut tip = checkpoint.clone();
+        while tip.block_id + 1 < header.block_id {
+            let end_block_id =
+                (tip.block_id + MAX_BLOCK_HEADERS_PER_REQUEST).min(header.block_id - 1);
+            let headers = self
+                .sequencer_client
+                .get_block_headers(tip.block_id + 1..=end_block_id)
+                .await?
+                .headers;
+            if headers.is_empty() {
+                return Err(verification_failure(format!(
+                    "Sequencer serves no headers after block {}",
+                    tip.block_id
+                )));
+            }
+            tip = verify_header_chain(&tip, &headers, public_key)
+                .map_err(|err| verification_failure(err.to_string()))?;
+        }
+
+        verify_header_chain(&tip, std::slice::from_ref(header), public_key)
+            .map_err(|err| verification_failure(err.to_string()))
+    }
+}
+
+fn verification_failure(reason: impl Into<String>) -> anyhow::Error {
+    VerificationError {
+        reason: reason.into(),
+    }
+    .into()
+}
+
+fn parse_public_key(public_key: &str) -> Result<PublicKey> {
+    let bytes = hex::decode(public_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .with_context(|| format!("Malformed sequencer key {public_key}"))?;
+    Ok(PublicKey::try_new(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common::mock_chain::MOCK_GENESIS_ID;
+    use nssa::Amount;
+
+    use super::*;
+    use crate::{api::TransferOptions, config::PersistentStorage, test_utils};
+
+    #[tokio::test]
+    async fn test_checkpoint_follows_verified_headers() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        assert_eq!(
+            wallet.get_account_public_verified(to).await.unwrap(),
+            chain.account(&to)
+        );
+        let trusted = wallet.trusted_checkpoint.lock().unwrap().clone().unwrap();
+        assert_eq!(trusted.checkpoint.block_id, MOCK_GENESIS_ID);
+
+        for _ in 0..3 {
+            wallet
+                .transfer(from, to, Amount(100), TransferOptions::default())
+                .await
+                .unwrap();
+            chain.produce_block();
+        }
+        let account = wallet.get_account_public_verified(to).await.unwrap();
+        assert_eq!(account.balance, Amount(1300));
+        let trusted = wallet.trusted_checkpoint.lock().unwrap().clone().unwrap();
+        assert_eq!(trusted.checkpoint.block_id, chain.last_block_id());
+
+        // Checkpoint is stored with the wallet
+        wallet.store_persistent_data().await.unwrap();
+        let stored = serde_json::from_slice::<PersistentStorage>(
+            &std::fs::read(&wallet.wallet_file).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(stored.trusted_checkpoint, Some(trusted));
+    }
+
+    #[tokio::test]
+    async fn test_other_sequencer_key_is_rejected() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain, home.path());
+        let (from, _) = test_utils::preconfigured_public_accounts();
+        let other_key =
+            PublicKey::new_from_private_key(&nssa::PrivateKey::try_new([9; 32]).unwrap());
+        *wallet.trusted_checkpoint.lock().unwrap() = Some(TrustedCheckpoint {
+            checkpoint: Checkpoint {
+                block_id: MOCK_GENESIS_ID,
+                hash: hex::encode([0; 32]),
+            },
+            sequencer_public_key: hex::encode(other_key.value()),
+        });
+
+        let err = wallet.get_account_public_verified(from).await.unwrap_err();
+        assert!(err.is::<VerificationError>(), "{err:#}");
+    }
+}
@@ -1,7 +1,14 @@
 use anyhow::Result;
 use clap::{CommandFactory as _, Parser as _};
 use tokio::runtime::Builder;
-use wallet::cli::{Args, execute_continuous_run_with_auth, execute_subcommand_with_auth};
+use wallet::{
+    HOME_DIR_ENV_VAR, WALLET_FILE_ENV_VAR,
+    chain_cache::NO_CACHE_ENV_VAR,
+    cli::{
+        Args, daemon::route_through_daemon, execute_continuous_run_with_auth,
+        execute_subcommand_with_auth, offer_legacy_migration,
+    },
+};
 
 pub const NUM_THREADS: usize = 2;
 
@@ -11,19 +18,52 @@ pub const NUM_THREADS: usize = 2;
 // TODO #172: Why it requires config as env var while sequencer_runner accepts as
 // argument?
 fn main() -> Result<()> {
+    let args = Args::parse();
+
+    // Resolve wallet paths before any subcommand runs and before other threads are spawned
+    if let Some(data_dir) = &args.data_dir {
+        unsafe {
+            std::env::set_var(HOME_DIR_ENV_VAR, data_dir);
+        }
+    }
+    if let Some(wallet_file) = &args.wallet_file {
+        unsafe {
+            std::env::set_var(WALLET_FILE_ENV_VAR, wallet_file);
+        }
+    }
+    if args.no_cache {
+        unsafe {
+            std::env::set_var(NO_CACHE_ENV_VAR, "1");
+        }
+    }
+    if args.command.is_some() || args.continuous_run {
+        offer_legacy_migration()?;
+    }
+
     let runtime = Builder::new_multi_thread()
         .worker_threads(NUM_THREADS)
         .enable_all()
         .build()
         .unwrap();
 
-    let args = Args::parse();
-
     env_logger::init();
 
     runtime.block_on(async move {
         if let Some(command) = args.command {
-            let _output = execute_subcommand_with_auth(command, args.auth).await?;
+            // Running daemon holds the wallet, so commands using it are executed by the daemon
+            if route_through_daemon(&command, std::env::args().collect()).await? {
+                return Ok(());
+            }
+
+            let _output = execute_subcommand_with_auth(
+                command,
+                args.auth,
+                args.wait,
+                args.priority.unwrap_or_default(),
+                args.max_cycles,
+                args.output,
+            )
+            .await?;
             Ok(())
         } else if args.continuous_run {
             execute_continuous_run_with_auth(args.auth).await
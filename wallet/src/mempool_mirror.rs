@@ -0,0 +1,258 @@
+//! Advisory mirror of the sequencer mempool.
+//!
+//! With `mempool_mirror_max_age_blocks` configured, the wallet polls summaries of pending
+//! transactions from `get_pending_transactions` of the sequencer on every sync step of continuous
+//! run and daemon, and once before use otherwise. The mirror warns about pending transactions,
+//! which use the nonce of a transaction being sent, and estimates priority fees locally.
+//!
+//! The mirror may lag behind or diverge from the mempool, so it's only ever used for warnings and
+//! estimates, never to refuse a transaction. Transactions seen pending for more than the max age
+//! are likely stuck and are ignored until they leave the mempool.
+
+use std::collections::HashMap;
+
+use common::rpc_types::{
+    GetPendingTransactionsResponse, PendingTransactionSummary, PriorityFeeDistribution,
+};
+use nssa::AccountId;
+use nssa_core::account::Nonce;
+
+use crate::tx_log::TxLogEntry;
+
+#[derive(Debug, Clone)]
+struct MirroredTransaction {
+    summary: PendingTransactionSummary,
+    /// Last block at the first snapshot, which had the transaction
+    first_seen_block: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MempoolMirror {
+    max_age_blocks: u64,
+    /// Last block at the latest snapshot, `None` before the first one
+    last_block: Option<u64>,
+    /// By hex encoded hashes
+    entries: HashMap<String, MirroredTransaction>,
+}
+
+/// Pending transaction, which uses the same sender and nonce as another one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceConflict {
+    /// Hex encoded hash of the pending transaction
+    pub hash: String,
+    pub sender: AccountId,
+    pub nonce: Nonce,
+    /// Whether the pending transaction is in the wallet's journal. Otherwise it's sent by another
+    /// wallet with the same key, or the journal lost it.
+    pub journaled: bool,
+}
+
+impl MempoolMirror {
+    pub fn new(max_age_blocks: u64) -> Self {
+        Self {
+            max_age_blocks,
+            last_block: None,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Whether a snapshot was taken yet
+    pub fn is_refreshed(&self) -> bool {
+        self.last_block.is_some()
+    }
+
+    /// Replace pending transactions with the ones of `snapshot`, remembering since when the
+    /// remaining ones are pending
+    pub fn update(&mut self, snapshot: GetPendingTransactionsResponse) {
+        let mut entries = HashMap::with_capacity(snapshot.transactions.len());
+        for summary in snapshot.transactions {
+            let first_seen_block = self
+                .entries
+                .get(&summary.hash)
+                .map_or(snapshot.last_block, |entry| entry.first_seen_block);
+            entries.insert(
+                summary.hash.clone(),
+                MirroredTransaction {
+                    summary,
+                    first_seen_block,
+                },
+            );
+        }
+
+        self.entries = entries;
+        self.last_block = Some(snapshot.last_block);
+    }
+
+    /// Pending transactions, which aren't older than the max age
+    pub fn transactions(&self) -> impl Iterator<Item = &PendingTransactionSummary> {
+        let last_block = self.last_block.unwrap_or_default();
+        self.entries
+            .values()
+            .filter(move |entry| {
+                last_block.saturating_sub(entry.first_seen_block) <= self.max_age_blocks
+            })
+            .map(|entry| &entry.summary)
+    }
+
+    /// Same as the sequencer's `get_priority_fees`, but of mirrored transactions
+    pub fn priority_fees(&self) -> PriorityFeeDistribution {
+        let mut fees = self
+            .transactions()
+            .map(|summary| summary.priority_fee)
+            .collect::<Vec<_>>();
+        fees.sort_unstable();
+
+        PriorityFeeDistribution {
+            pending: fees.len() as u64,
+            min: fees.first().copied().unwrap_or_default(),
+            median: fees.get(fees.len() / 2).copied().unwrap_or_default(),
+            max: fees.last().copied().unwrap_or_default(),
+        }
+    }
+
+    /// Pending transactions other than `hash`, which use `nonce` of `sender`, so at most one of
+    /// them can be included
+    pub fn nonce_conflicts(
+        &self,
+        hash: &str,
+        sender: &AccountId,
+        nonce: Nonce,
+        journal: &[TxLogEntry],
+    ) -> Vec<NonceConflict> {
+        let sender_str = sender.to_string();
+        let mut conflicts = self
+            .transactions()
+            .filter(|summary| {
+                summary.hash != hash
+                    && summary.sender.as_ref() == Some(&sender_str)
+                    && summary.nonce == Some(nonce)
+            })
+            .map(|summary| NonceConflict {
+                hash: summary.hash.clone(),
+                sender: *sender,
+                nonce,
+                journaled: journal.iter().any(|entry| entry.hash == summary.hash),
+            })
+            .collect::<Vec<_>>();
+        conflicts.sort_by(|a, b| a.hash.cmp(&b.hash));
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::transaction::{EncodedTransaction, TxKind};
+    use nssa::Amount;
+
+    use super::*;
+    use crate::tx_log::TxDetails;
+
+    fn summary(
+        hash: &str,
+        sender: &AccountId,
+        nonce: Nonce,
+        priority_fee: u128,
+    ) -> PendingTransactionSummary {
+        PendingTransactionSummary {
+            hash: hash.to_string(),
+            sender: Some(sender.to_string()),
+            nonce: Some(nonce),
+            priority_fee: Amount(priority_fee),
+        }
+    }
+
+    fn snapshot(
+        last_block: u64,
+        transactions: Vec<PendingTransactionSummary>,
+    ) -> GetPendingTransactionsResponse {
+        GetPendingTransactionsResponse {
+            last_block,
+            transactions,
+        }
+    }
+
+    #[test]
+    fn test_stale_transactions_are_ignored() {
+        let sender = AccountId::new([1; 32]);
+        let mut mirror = MempoolMirror::new(2);
+        assert!(!mirror.is_refreshed());
+
+        mirror.update(snapshot(10, vec![summary("aa", &sender, 0, 5)]));
+        mirror.update(snapshot(
+            12,
+            vec![summary("aa", &sender, 0, 5), summary("bb", &sender, 1, 7)],
+        ));
+        assert!(mirror.is_refreshed());
+        assert_eq!(mirror.transactions().count(), 2);
+
+        // Pending since block 10, so stuck after block 12
+        mirror.update(snapshot(
+            13,
+            vec![summary("aa", &sender, 0, 5), summary("bb", &sender, 1, 7)],
+        ));
+        let hashes = mirror
+            .transactions()
+            .map(|summary| summary.hash.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, ["bb"]);
+        assert_eq!(mirror.priority_fees().median, Amount(7));
+
+        // Transactions, which left the mempool, are forgotten
+        mirror.update(snapshot(14, vec![]));
+        assert_eq!(mirror.transactions().count(), 0);
+        assert_eq!(mirror.priority_fees(), PriorityFeeDistribution::default());
+    }
+
+    #[test]
+    fn test_conflicts_with_transactions_unknown_to_journal() {
+        let sender = AccountId::new([1; 32]);
+        let other = AccountId::new([2; 32]);
+        let journaled = TxLogEntry::new_built(
+            &EncodedTransaction {
+                tx_kind: TxKind::Public,
+                encoded_transaction_data: vec![1],
+            },
+            TxDetails {
+                sender: Some(sender),
+                nonce: Some(3),
+                ..Default::default()
+            },
+        );
+        let mut mirror = MempoolMirror::new(10);
+        // Journal reserves nonce 3 by its own transaction, while the mempool also has one of
+        // another wallet with the same key
+        mirror.update(snapshot(
+            5,
+            vec![
+                summary(&journaled.hash, &sender, 3, 0),
+                summary("ff", &sender, 3, 0),
+                summary("ee", &sender, 4, 0),
+                summary("dd", &other, 3, 0),
+            ],
+        ));
+        let journal = [journaled.clone()];
+
+        // Sending the journaled transaction again conflicts with the foreign one only
+        assert_eq!(
+            mirror.nonce_conflicts(&journaled.hash, &sender, 3, &journal),
+            [NonceConflict {
+                hash: "ff".to_string(),
+                sender,
+                nonce: 3,
+                journaled: false,
+            }]
+        );
+
+        // A replacement conflicts with both
+        let conflicts = mirror.nonce_conflicts("00", &sender, 3, &journal);
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts.iter().any(|conflict| conflict.journaled));
+        assert!(conflicts.iter().any(|conflict| !conflict.journaled));
+
+        assert!(
+            mirror
+                .nonce_conflicts("00", &sender, 5, &journal)
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Local-first nonces of public accounts.
+//!
+//! Transactions, which are built or submitted but not final yet, reserve their nonces in the
+//! transaction log. Next nonce of an account is the on-chain one, unless the wallet has already
+//! handed it out, so several transactions can be sent before the first of them is included.
+//! Reservation is released, as soon as its transaction is included, dropped or cancelled.
+//!
+//! Reservations below the on-chain nonce are stale: their nonces are consumed by transactions,
+//! which the wallet either hasn't reconciled yet or doesn't know about (e.g. sent by another
+//! wallet with the same key).
+
+use nssa::AccountId;
+use nssa_core::account::Nonce;
+use tokio::sync::OwnedMutexGuard;
+
+use crate::tx_log::TxLogEntry;
+
+/// Holds nonces handed out by [`crate::WalletCore::reserve_accounts_nonces`]
+///
+/// Other nonces aren't handed out by the wallet until it's dropped, so the transaction with the
+/// reserved nonces should be journaled before.
+#[must_use]
+pub struct NonceReservation {
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl NonceReservation {
+    pub(crate) fn new(guard: OwnedMutexGuard<()>) -> Self {
+        Self { _guard: guard }
+    }
+}
+
+/// Non-final journaled transactions of `account_id`, which reserve a nonce
+fn reservations<'a>(
+    entries: &'a [TxLogEntry],
+    account_id: &AccountId,
+) -> impl Iterator<Item = (&'a TxLogEntry, Nonce)> {
+    let sender = account_id.to_string();
+    entries.iter().filter_map(move |entry| {
+        let nonce = entry.nonce?;
+        (entry.sender.as_ref() == Some(&sender) && !entry.status.is_final())
+            .then_some((entry, nonce))
+    })
+}
+
+/// Highest nonce of `account_id` reserved by the journal, which isn't consumed on chain yet
+pub fn reserved_nonce(
+    entries: &[TxLogEntry],
+    account_id: &AccountId,
+    on_chain: Nonce,
+) -> Option<Nonce> {
+    reservations(entries, account_id)
+        .map(|(_, nonce)| nonce)
+        .filter(|nonce| *nonce >= on_chain)
+        .max()
+}
+
+/// Nonce of the next transaction of `account_id`
+pub fn next_nonce(entries: &[TxLogEntry], account_id: &AccountId, on_chain: Nonce) -> Nonce {
+    reserved_nonce(entries, account_id, on_chain)
+        .map_or(on_chain, |reserved| on_chain.max(reserved + 1))
+}
+
+/// Reservations of `account_id`, which nonces are already consumed on chain
+pub fn stale_reservations<'a>(
+    entries: &'a [TxLogEntry],
+    account_id: &AccountId,
+    on_chain: Nonce,
+) -> Vec<&'a TxLogEntry> {
+    reservations(entries, account_id)
+        .filter(|(_, nonce)| *nonce < on_chain)
+        .map(|(entry, _)| entry)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use common::test_utils::produce_dummy_empty_transaction;
+
+    use super::*;
+    use crate::tx_log::{TxDetails, TxStatus};
+
+    fn sender() -> AccountId {
+        AccountId::new([1; 32])
+    }
+
+    fn entry(sender: AccountId, nonce: Nonce, status: TxStatus) -> TxLogEntry {
+        let mut tx = produce_dummy_empty_transaction();
+        // Make hashes distinct
+        tx.encoded_transaction_data
+            .extend_from_slice(&nonce.to_le_bytes());
+
+        let mut entry = TxLogEntry::new_built(
+            &tx,
+            TxDetails {
+                sender: Some(sender),
+                nonce: Some(nonce),
+                ..Default::default()
+            },
+        );
+        entry.status = status;
+        entry
+    }
+
+    #[test]
+    fn test_next_nonce_follows_reservations() {
+        let entries = [
+            entry(sender(), 3, TxStatus::Built),
+            entry(sender(), 4, TxStatus::Submitted),
+        ];
+
+        assert_eq!(next_nonce(&[], &sender(), 3), 3);
+        assert_eq!(next_nonce(&entries, &sender(), 3), 5);
+        assert_eq!(reserved_nonce(&entries, &sender(), 3), Some(4));
+        // Reservations of other accounts don't matter
+        assert_eq!(next_nonce(&entries, &AccountId::new([2; 32]), 3), 3);
+    }
+
+    #[test]
+    fn test_final_transactions_release_their_nonces() {
+        for status in [
+            TxStatus::Included,
+            TxStatus::Failed,
+            TxStatus::Replaced,
+            TxStatus::Cancelled,
+        ] {
+            let entries = [entry(sender(), 3, status)];
+
+            assert_eq!(next_nonce(&entries, &sender(), 3), 3, "{status:?}");
+        }
+    }
+
+    #[test]
+    fn test_reservations_below_on_chain_nonce_are_stale() {
+        let entries = [
+            entry(sender(), 1, TxStatus::Submitted),
+            entry(sender(), 2, TxStatus::Included),
+            entry(sender(), 6, TxStatus::Submitted),
+        ];
+
+        // Nonce jumped ahead of the first reservation, but not of the last one
+        let stale = stale_reservations(&entries, &sender(), 5);
+        assert_eq!(stale, [&entries[0]]);
+        assert_eq!(next_nonce(&entries, &sender(), 5), 7);
+
+        // Nonce jumped ahead of all reservations
+        assert_eq!(stale_reservations(&entries, &sender(), 10).len(), 2);
+        assert_eq!(next_nonce(&entries, &sender(), 10), 10);
+    }
+}
@@ -0,0 +1,2697 @@
+//! Output of wallet subcommands.
+//!
+//! Every subcommand reports its result with a serde struct from this module. With `--output json`
+//! the struct is printed as a single JSON document, which is a stable contract for scripts: field
+//! names and value formats only change together with the schema snapshots in tests. Otherwise it
+//! is printed for humans, with lists aligned in columns by [`Table`].
+//!
+//! Progress and prompts are printed to stderr, so stdout holds the output only.
+
+use std::{cell::RefCell, fmt::Display};
+
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use clap::ValueEnum;
+use common::{
+    address_index::TxDirection,
+    receipt::{BalanceSign, TransactionReceipt},
+    rpc_types::{AddressTransaction, TxAuditEvent, TxAuditRecord},
+};
+use itertools::Itertools as _;
+use key_protocol::key_management::{
+    audit::{KeyAuditIssue, KeyProblem},
+    key_tree::chain_index::ChainIndex,
+};
+use nssa::{Amount, Cycles, instruction_decoder::DecoderRegistry};
+use nssa_core::account::Nonce;
+use serde::Serialize;
+
+use crate::{
+    address_book::Contact,
+    api::{OwnedAccount, TxDryRun},
+    helperfunctions::AccountPrivacyKind,
+    payout::{PayoutResult, PayoutStatus},
+    pending_balance::PendingBalance,
+    tx_decode::public_instruction,
+    tx_log::{TxLogEntry, TxStatus},
+};
+
+/// Format of subcommand output
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+/// Result of a subcommand, printable in every [`OutputFormat`]
+pub trait CommandOutput: Serialize {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+}
+
+tokio::task_local! {
+    /// Output of a command executed by the wallet daemon, which is sent to its client
+    static CAPTURED_OUTPUT: RefCell<String>;
+}
+
+/// Print `output` to stdout in `format`, or to the captured output inside [`capture_output`]
+pub fn print_output(format: OutputFormat, output: &impl CommandOutput) -> Result<()> {
+    let printed = match format {
+        OutputFormat::Human => Human(output).to_string(),
+        OutputFormat::Json => serde_json::to_string(output)?,
+    };
+    let captured = CAPTURED_OUTPUT.try_with(|captured| {
+        let mut captured = captured.borrow_mut();
+        captured.push_str(&printed);
+        captured.push('\n');
+    });
+    if captured.is_err() {
+        println!("{printed}");
+    }
+    Ok(())
+}
+
+/// Runs `future`, collecting what it prints with [`print_output`] instead of printing it
+pub async fn capture_output<F: Future>(future: F) -> (F::Output, String) {
+    CAPTURED_OUTPUT
+        .scope(RefCell::new(String::new()), async {
+            let result = future.await;
+            (result, CAPTURED_OUTPUT.with(RefCell::take))
+        })
+        .await
+}
+
+struct Human<'a, T>(&'a T);
+
+impl<T: CommandOutput> Display for Human<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_human(f)
+    }
+}
+
+/// Rows of cells printed in columns padded to the widest cell
+#[derive(Debug, Default)]
+pub struct Table {
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Table, which first row is `header`
+    pub fn with_header(header: &[&str]) -> Self {
+        let mut table = Self::default();
+        table.push_row(header.iter().map(|cell| cell.to_string()).collect());
+        table
+    }
+
+    pub fn push_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut widths: Vec<usize> = vec![];
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                let width = cell.chars().count();
+                match widths.get_mut(i) {
+                    Some(max) => *max = (*max).max(width),
+                    None => widths.push(width),
+                }
+            }
+        }
+
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            if row_idx > 0 {
+                writeln!(f)?;
+            }
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                line.push_str(cell);
+                if i + 1 < row.len() {
+                    let padding = widths[i] - cell.chars().count();
+                    line.extend(std::iter::repeat_n(' ', padding));
+                }
+            }
+            write!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+fn privacy_name(privacy: AccountPrivacyKind) -> &'static str {
+    match privacy {
+        AccountPrivacyKind::Public => "Public",
+        AccountPrivacyKind::Private => "Private",
+    }
+}
+
+fn optional_cell(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+/// Timestamps ahead of local time by less than this are shown as `just now`, as clocks of the
+/// wallet and the sequencer may differ a bit
+const CLOCK_SKEW_TOLERANCE_MILLIS: u64 = 30_000;
+
+/// How timestamps are shown in human readable output, JSON output has Unix time in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeDisplay {
+    /// Relative to local Unix time in milliseconds, e.g. `3 minutes ago`
+    Relative { now_millis: u64 },
+    /// Absolute UTC time, e.g. `2025-06-01 12:30:00 UTC`
+    Utc,
+}
+
+impl TimeDisplay {
+    /// Absolute UTC time if `utc` is set, relative to current time otherwise
+    pub fn new(utc: bool) -> Self {
+        if utc {
+            Self::Utc
+        } else {
+            Self::Relative {
+                now_millis: chrono::Utc::now().timestamp_millis() as u64,
+            }
+        }
+    }
+
+    pub fn format(self, timestamp_millis: u64) -> String {
+        match self {
+            Self::Relative { now_millis } => {
+                if timestamp_millis > now_millis + CLOCK_SKEW_TOLERANCE_MILLIS {
+                    format!(
+                        "{} in the future",
+                        duration_words(timestamp_millis - now_millis)
+                    )
+                } else {
+                    match now_millis.saturating_sub(timestamp_millis) {
+                        elapsed if elapsed < 60_000 => "just now".to_string(),
+                        elapsed => format!("{} ago", duration_words(elapsed)),
+                    }
+                }
+            }
+            Self::Utc => i64::try_from(timestamp_millis)
+                .ok()
+                .and_then(chrono::DateTime::from_timestamp_millis)
+                .map_or_else(
+                    || format!("{timestamp_millis}ms"),
+                    |time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+                ),
+        }
+    }
+}
+
+/// Duration in the largest whole unit, e.g. `2 hours`
+fn duration_words(millis: u64) -> String {
+    let seconds = millis / 1000;
+    let (count, unit) = if seconds < 60 {
+        (seconds, "second")
+    } else if seconds < 3600 {
+        (seconds / 60, "minute")
+    } else if seconds < 86_400 {
+        (seconds / 3600, "hour")
+    } else {
+        (seconds / 86_400, "day")
+    };
+    if count == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
+
+/// Output of `chain-info current-block-id`
+#[derive(Debug, Serialize)]
+pub struct LastBlockOutput {
+    pub last_block_id: u64,
+    /// Hex encoded hash of the last block, `null` for sequencers older than API 2.3
+    pub last_block_hash: Option<String>,
+}
+
+impl From<&common::rpc_types::GetLastBlockResponse> for LastBlockOutput {
+    fn from(response: &common::rpc_types::GetLastBlockResponse) -> Self {
+        Self {
+            last_block_id: response.last_block,
+            last_block_hash: response
+                .last_block_digest
+                .as_ref()
+                .map(|digest| digest.hash.clone()),
+        }
+    }
+}
+
+impl CommandOutput for LastBlockOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Last block id is {}", self.last_block_id)?;
+        if let Some(hash) = &self.last_block_hash {
+            write!(f, ", its hash is {hash}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Output of `chain-info block`
+#[derive(Debug, Serialize)]
+pub struct BlockOutput {
+    pub block_id: u64,
+    /// Hex encoded hash of the previous block
+    pub prev_block_hash: String,
+    /// Unix time of the block in milliseconds
+    pub timestamp: u64,
+    pub transactions: Vec<BlockTransactionOutput>,
+    #[serde(skip)]
+    pub time_display: TimeDisplay,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlockTransactionOutput {
+    /// Hex encoded transaction hash
+    pub hash: String,
+    pub kind: TransactionKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    Public,
+    PrivacyPreserving,
+    ProgramDeployment,
+    /// Kind unsupported by the wallet, with its tag
+    Unknown(u8),
+}
+
+impl From<common::transaction::TxKind> for TransactionKind {
+    fn from(value: common::transaction::TxKind) -> Self {
+        match value {
+            common::transaction::TxKind::Public
+            | common::transaction::TxKind::LegacyPublic
+            | common::transaction::TxKind::UnschemedPublic
+            | common::transaction::TxKind::NarrowFeePublic => Self::Public,
+            common::transaction::TxKind::PrivacyPreserving
+            | common::transaction::TxKind::LegacyPrivacyPreserving
+            | common::transaction::TxKind::UnschemedPrivacyPreserving => Self::PrivacyPreserving,
+            common::transaction::TxKind::ProgramDeployment
+            | common::transaction::TxKind::LegacyProgramDeployment
+            | common::transaction::TxKind::UnschemedProgramDeployment => Self::ProgramDeployment,
+            common::transaction::TxKind::Unknown(tag) => Self::Unknown(tag),
+        }
+    }
+}
+
+impl Display for TransactionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Public => write!(f, "Public"),
+            Self::PrivacyPreserving => write!(f, "Privacy preserving"),
+            Self::ProgramDeployment => write!(f, "Program deployment"),
+            Self::Unknown(tag) => write!(f, "Unknown ({tag})"),
+        }
+    }
+}
+
+impl BlockOutput {
+    pub fn new(block: common::block::HashableBlockData, time_display: TimeDisplay) -> Self {
+        Self {
+            block_id: block.block_id,
+            prev_block_hash: hex::encode(block.prev_block_hash),
+            timestamp: block.timestamp,
+            transactions: block
+                .transactions
+                .iter()
+                .map(|tx| BlockTransactionOutput {
+                    hash: hex::encode(tx.hash()),
+                    kind: tx.tx_kind.into(),
+                })
+                .collect(),
+            time_display,
+        }
+    }
+}
+
+impl CommandOutput for BlockOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Block {}", self.block_id)?;
+        writeln!(f, "Previous block hash {}", self.prev_block_hash)?;
+        writeln!(f, "Timestamp {}", self.time_display.format(self.timestamp))?;
+        if self.transactions.is_empty() {
+            return write!(f, "No transactions");
+        }
+
+        let mut table = Table::with_header(&["HASH", "KIND"]);
+        for tx in &self.transactions {
+            table.push_row(vec![tx.hash.clone(), tx.kind.to_string()]);
+        }
+        write!(f, "{table}")
+    }
+}
+
+/// Output of `chain-info transaction`
+#[derive(Debug, Serialize)]
+pub struct TransactionOutput {
+    pub hash: String,
+    /// `false` if sequencer doesn't know the transaction, other fields are `null` then
+    pub found: bool,
+    pub kind: Option<TransactionKind>,
+    /// Base64 encoded borsh serialization of the transaction
+    pub transaction_b64: Option<String>,
+}
+
+impl TransactionOutput {
+    pub fn new(
+        hash: String,
+        transaction: Option<&common::transaction::EncodedTransaction>,
+    ) -> Self {
+        Self {
+            hash,
+            found: transaction.is_some(),
+            kind: transaction.map(|tx| tx.tx_kind.into()),
+            transaction_b64: transaction.map(|tx| BASE64.encode(borsh::to_vec(tx).unwrap())),
+        }
+    }
+}
+
+impl CommandOutput for TransactionOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.kind, &self.transaction_b64) {
+            (Some(kind), Some(transaction_b64)) => {
+                writeln!(f, "{kind} transaction {}", self.hash)?;
+                write!(f, "{transaction_b64}")
+            }
+            _ => write!(f, "Transaction {} is not found", self.hash),
+        }
+    }
+}
+
+/// Output of `chain-info info`
+#[derive(Debug, Serialize)]
+pub struct ChainInfoOutput {
+    pub genesis_id: u64,
+    pub last_block: u64,
+    pub wallet_version: String,
+    pub wallet_api_version: String,
+    /// Sequencer version fields are `null` for sequencers older than API 1.10
+    pub sequencer_version: Option<String>,
+    pub sequencer_api_version: Option<String>,
+    /// Optional functionality of the sequencer, e.g. `lazy_proofs`
+    pub sequencer_features: Vec<String>,
+}
+
+impl ChainInfoOutput {
+    pub fn new(
+        chain_info: &common::rpc_types::GetChainInfoResponse,
+        sequencer_info: Option<&common::rpc_types::GetSequencerInfoResponse>,
+    ) -> Self {
+        Self {
+            genesis_id: chain_info.genesis_id,
+            last_block: chain_info.last_block,
+            wallet_version: env!("CARGO_PKG_VERSION").to_string(),
+            wallet_api_version: common::rpc_types::API_VERSION.to_string(),
+            sequencer_version: sequencer_info.map(|info| info.version.clone()),
+            sequencer_api_version: sequencer_info.map(|info| info.api_version.to_string()),
+            sequencer_features: sequencer_info
+                .map(|info| info.features.iter().map(ToString::to_string).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl CommandOutput for ChainInfoOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Genesis block: {}", self.genesis_id)?;
+        writeln!(f, "Last block: {}", self.last_block)?;
+        writeln!(
+            f,
+            "Wallet version: {} (API {})",
+            self.wallet_version, self.wallet_api_version
+        )?;
+        match (&self.sequencer_version, &self.sequencer_api_version) {
+            (Some(version), Some(api_version)) => {
+                writeln!(f, "Sequencer version: {version} (API {api_version})")?;
+            }
+            _ => writeln!(f, "Sequencer version: unknown")?,
+        }
+        if self.sequencer_features.is_empty() {
+            write!(f, "Sequencer features: none")
+        } else {
+            write!(
+                f,
+                "Sequencer features: {}",
+                self.sequencer_features.join(", ")
+            )
+        }
+    }
+}
+
+/// Output of `chain-info sync-status`
+#[derive(Debug, Serialize)]
+pub struct SyncStatusOutput {
+    pub synced: bool,
+    pub current_height: u64,
+    pub target_height: u64,
+    pub blocks_per_sec: f64,
+    /// Estimated seconds left, `null` if unknown or synced
+    pub eta_secs: Option<u64>,
+}
+
+impl From<common::rpc_types::SyncStatus> for SyncStatusOutput {
+    fn from(status: common::rpc_types::SyncStatus) -> Self {
+        Self {
+            synced: status.state == common::rpc_types::SyncState::Synced,
+            current_height: status.current_height,
+            target_height: status.target_height,
+            blocks_per_sec: status.blocks_per_sec,
+            eta_secs: status.eta_secs,
+        }
+    }
+}
+
+impl CommandOutput for SyncStatusOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.synced {
+            return write!(f, "Synced up to block {}", self.current_height);
+        }
+
+        let eta = self
+            .eta_secs
+            .map_or_else(|| "unknown".to_string(), |eta| format!("{eta}s"));
+        write!(
+            f,
+            "Syncing: block {} of {}, {:.1} blocks/s, ETA {eta}",
+            self.current_height, self.target_height, self.blocks_per_sec
+        )
+    }
+}
+
+/// Output of `account get`
+#[derive(Debug, Serialize)]
+pub struct AccountOutput {
+    /// Account id with privacy prefix
+    pub account_id: String,
+    #[serde(flatten)]
+    pub view: AccountView,
+}
+
+/// Account state interpreted by the program owning it
+#[derive(Debug, Serialize)]
+#[serde(tag = "owner", rename_all = "snake_case")]
+pub enum AccountView {
+    Uninitialized,
+    AuthenticatedTransfer(AuthenticatedTransferAccountView),
+    TokenDefinition(TokedDefinitionAccountView),
+    TokenHolding(TokedHoldingAccountView),
+    /// Account owned by another program or requested raw
+    Raw(RawAccountView),
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthenticatedTransferAccountView {
+    pub balance: Amount,
+}
+
+impl From<nssa::Account> for AuthenticatedTransferAccountView {
+    fn from(value: nssa::Account) -> Self {
+        Self {
+            balance: value.balance,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokedDefinitionAccountView {
+    pub name: String,
+    pub total_supply: u128,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokedHoldingAccountView {
+    pub definition_id: String,
+    pub balance: u128,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RawAccountView {
+    pub balance: Amount,
+    /// Id of the owner program, see [`nssa_core::program::ProgramId`] for its format
+    pub program_owner: String,
+    pub data_b64: String,
+    pub nonce: Nonce,
+}
+
+impl From<nssa::Account> for RawAccountView {
+    fn from(account: nssa::Account) -> Self {
+        Self {
+            balance: account.balance,
+            program_owner: account.program_owner.to_string(),
+            data_b64: BASE64.encode(account.data),
+            nonce: account.nonce,
+        }
+    }
+}
+
+impl CommandOutput for AccountOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::default();
+        table.push_row(vec!["Account".to_string(), self.account_id.clone()]);
+        match &self.view {
+            AccountView::Uninitialized => {
+                table.push_row(vec!["State".to_string(), "Uninitialized".to_string()]);
+            }
+            AccountView::AuthenticatedTransfer(view) => {
+                table.push_row(vec![
+                    "Owner".to_string(),
+                    "Authenticated transfer program".to_string(),
+                ]);
+                table.push_row(vec!["Balance".to_string(), view.balance.to_string()]);
+            }
+            AccountView::TokenDefinition(view) => {
+                table.push_row(vec![
+                    "Owner".to_string(),
+                    "Token program, definition".to_string(),
+                ]);
+                table.push_row(vec!["Name".to_string(), view.name.clone()]);
+                table.push_row(vec![
+                    "Total supply".to_string(),
+                    view.total_supply.to_string(),
+                ]);
+            }
+            AccountView::TokenHolding(view) => {
+                table.push_row(vec![
+                    "Owner".to_string(),
+                    "Token program, holding".to_string(),
+                ]);
+                table.push_row(vec!["Definition".to_string(), view.definition_id.clone()]);
+                table.push_row(vec!["Balance".to_string(), view.balance.to_string()]);
+            }
+            AccountView::Raw(view) => {
+                table.push_row(vec!["Owner".to_string(), view.program_owner.clone()]);
+                table.push_row(vec!["Balance".to_string(), view.balance.to_string()]);
+                table.push_row(vec!["Nonce".to_string(), view.nonce.to_string()]);
+                table.push_row(vec!["Data".to_string(), view.data_b64.clone()]);
+            }
+        }
+        write!(f, "{table}")
+    }
+}
+
+/// Output of `account new public`, `account new private` and `account new-address`
+#[derive(Debug, Serialize)]
+pub struct NewAccountOutput {
+    /// Account id with privacy prefix
+    pub account_id: String,
+    /// Path in the key tree, e.g. `m/0/3`
+    pub path: String,
+    /// Label given to the address, if any
+    pub label: Option<String>,
+    /// Hex encoded nullifier public key of a private account
+    pub npk: Option<String>,
+    /// Hex encoded incoming viewing public key of a private account
+    pub ipk: Option<String>,
+    /// Wallet file the account is stored to
+    pub wallet_file: String,
+    /// Whether human output ends with the account id as a QR code
+    #[serde(skip)]
+    pub qr: bool,
+}
+
+impl CommandOutput for NewAccountOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::default();
+        table.push_row(vec!["Account".to_string(), self.account_id.clone()]);
+        table.push_row(vec!["Path".to_string(), self.path.clone()]);
+        if let Some(label) = &self.label {
+            table.push_row(vec!["Label".to_string(), label.clone()]);
+        }
+        if let Some(npk) = &self.npk {
+            table.push_row(vec!["Npk".to_string(), npk.clone()]);
+        }
+        if let Some(ipk) = &self.ipk {
+            table.push_row(vec!["Ipk".to_string(), ipk.clone()]);
+        }
+        table.push_row(vec!["Stored to".to_string(), self.wallet_file.clone()]);
+        write!(f, "{table}")?;
+        if self.qr {
+            let code = qrcode::QrCode::new(&self.account_id).map_err(|_| std::fmt::Error)?;
+            let image = code
+                .render::<qrcode::render::unicode::Dense1x2>()
+                .quiet_zone(true)
+                .build();
+            write!(f, "\n{image}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Output of `account sync-private`
+#[derive(Debug, Serialize)]
+pub struct SyncedOutput {
+    pub synced_to_block: u64,
+}
+
+impl CommandOutput for SyncedOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Synced to block {}", self.synced_to_block)
+    }
+}
+
+/// Output of `daemon status`
+#[derive(Debug, Serialize)]
+pub struct DaemonStatusOutput {
+    pub running: bool,
+    /// `null` if not running
+    pub pid: Option<u32>,
+    /// Last block the daemon synced, `null` if not running
+    pub last_synced_block: Option<u64>,
+}
+
+impl CommandOutput for DaemonStatusOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.pid, self.last_synced_block) {
+            (Some(pid), Some(block)) if self.running => {
+                write!(
+                    f,
+                    "Daemon is running with pid {pid}, synced to block {block}"
+                )
+            }
+            _ => write!(f, "Daemon is not running"),
+        }
+    }
+}
+
+/// Output of `sign-message`
+#[derive(Debug, Serialize)]
+pub struct MessageSignatureOutput {
+    /// Account id with privacy prefix
+    pub address: String,
+    /// Base64 encoded public key and signature
+    pub signature: String,
+}
+
+impl CommandOutput for MessageSignatureOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::default();
+        table.push_row(vec!["Address".to_string(), self.address.clone()]);
+        table.push_row(vec!["Signature".to_string(), self.signature.clone()]);
+        write!(f, "{table}")
+    }
+}
+
+/// Output of `verify-message`
+#[derive(Debug, Serialize)]
+pub struct MessageVerificationOutput {
+    /// Account id with privacy prefix
+    pub address: String,
+    /// Signature is made by the address for the message
+    pub valid: bool,
+}
+
+impl CommandOutput for MessageVerificationOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.valid {
+            write!(f, "Signature is valid for {}", self.address)
+        } else {
+            write!(f, "Signature is NOT valid for {}", self.address)
+        }
+    }
+}
+
+/// Output of `account list`
+#[derive(Debug, Serialize)]
+pub struct AccountListOutput {
+    pub accounts: Vec<AccountListEntry>,
+    /// Accounts, which state couldn't be fetched, so they are listed even if unused
+    pub failed: Vec<AccountFetchFailure>,
+    /// Public accounts, which failed verification, so their states aren't trusted, only listed
+    /// with `--verify`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unverified: Vec<AccountFetchFailure>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountFetchFailure {
+    /// Account id with privacy prefix
+    pub account_id: String,
+    pub error: String,
+}
+
+impl AccountFetchFailure {
+    pub fn new(account: &OwnedAccount, error: &anyhow::Error) -> Self {
+        Self {
+            account_id: format!("{}/{}", privacy_name(account.privacy), account.account_id),
+            error: format!("{error:#}"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountListEntry {
+    /// Account id with privacy prefix
+    pub account_id: String,
+    /// Path in the key tree, `null` for preconfigured accounts
+    pub path: Option<String>,
+    /// Path of the account node, which the address belongs to, `null` outside of address chains
+    pub account: Option<String>,
+    /// `external` for receive addresses, `internal` for change addresses, `null` otherwise
+    pub chain: Option<String>,
+    /// Label given to the address, if any
+    pub label: Option<String>,
+    /// Whether the account has ever been touched on chain, `null` if its state couldn't be
+    /// fetched
+    pub used: Option<bool>,
+    /// Balance with the impact of pending transactions, `null` for private accounts and ones,
+    /// which state couldn't be fetched
+    pub balance: Option<PendingBalanceView>,
+    /// Whether the state of a public account is proven, only reported with `--verify`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+impl AccountListEntry {
+    pub fn with_balance(mut self, balance: Option<PendingBalance>) -> Self {
+        self.balance = balance.map(Into::into);
+        self
+    }
+
+    pub fn with_usage(mut self, used: Option<bool>, label: Option<String>) -> Self {
+        self.used = used;
+        self.label = label;
+        self
+    }
+
+    pub fn with_verification(mut self, verified: Option<bool>) -> Self {
+        self.verified = verified;
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PendingBalanceView {
+    pub confirmed: Amount,
+    /// Sum of the wallet's submissions, which aren't included yet
+    pub pending_outgoing: Amount,
+    /// Sum of pending transfers to the account, `null` if the sequencer doesn't serve them
+    pub pending_incoming: Option<Amount>,
+    /// Confirmed balance without pending outgoing, which transfers are checked against
+    pub spendable: Amount,
+}
+
+impl From<PendingBalance> for PendingBalanceView {
+    fn from(balance: PendingBalance) -> Self {
+        Self {
+            confirmed: balance.confirmed,
+            pending_outgoing: balance.pending_outgoing,
+            pending_incoming: balance.pending_incoming,
+            spendable: balance.spendable(),
+        }
+    }
+}
+
+impl From<OwnedAccount> for AccountListEntry {
+    fn from(account: OwnedAccount) -> Self {
+        let chain_address = account
+            .chain_index
+            .as_ref()
+            .and_then(ChainIndex::as_chain_address);
+
+        Self {
+            account_id: format!("{}/{}", privacy_name(account.privacy), account.account_id),
+            path: account
+                .chain_index
+                .map(|chain_index| chain_index.to_path_string()),
+            account: chain_address
+                .as_ref()
+                .map(|(account, _, _)| account.to_path_string()),
+            chain: chain_address.map(|(_, chain, _)| chain.to_string()),
+            label: None,
+            used: None,
+            balance: None,
+            verified: None,
+        }
+    }
+}
+
+impl CommandOutput for AccountListOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::with_header(&[
+            "PATH",
+            "CHAIN",
+            "ACCOUNT",
+            "USED",
+            "BALANCE",
+            "PENDING OUT",
+            "PENDING IN",
+            "SPENDABLE",
+            "LABEL",
+        ]);
+        for account in &self.accounts {
+            let path = account.path.as_deref().unwrap_or("Preconfigured");
+            let chain = account.chain.as_deref().unwrap_or("");
+            let used = match account.used {
+                Some(true) => "yes",
+                Some(false) => "no",
+                None => "",
+            };
+            let mut row = vec![
+                path.to_string(),
+                chain.to_string(),
+                account.account_id.clone(),
+                used.to_string(),
+            ];
+            match &account.balance {
+                Some(balance) => row.extend([
+                    balance.confirmed.to_string(),
+                    balance.pending_outgoing.to_string(),
+                    balance
+                        .pending_incoming
+                        .map_or_else(|| "n/a".to_string(), |amount| amount.to_string()),
+                    balance.spendable.to_string(),
+                ]),
+                None => row.extend(std::iter::repeat_n(String::new(), 4)),
+            }
+            row.push(account.label.clone().unwrap_or_default());
+            table.push_row(row);
+        }
+        write!(f, "{table}")?;
+        for failure in &self.failed {
+            write!(
+                f,
+                "\nFailed to fetch {}: {}",
+                failure.account_id, failure.error
+            )?;
+        }
+        for failure in &self.unverified {
+            write!(f, "\nUNVERIFIED {}: {}", failure.account_id, failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Output of `account history`
+#[derive(Debug, Serialize)]
+pub struct AccountHistoryOutput {
+    /// Account id with privacy prefix
+    pub account_id: String,
+    /// From the newest one
+    pub transactions: Vec<AccountHistoryEntry>,
+    /// Whether there are older transactions, which weren't fetched
+    pub has_more: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountHistoryEntry {
+    pub hash: String,
+    pub block_id: u64,
+    /// Called program of a public transaction, see [`nssa_core::program::ProgramId`] for its
+    /// format
+    pub program_id: Option<String>,
+    /// `in` or `out` for native transfers of the account, `null` otherwise
+    pub direction: Option<TxDirection>,
+    pub amount: Option<Amount>,
+}
+
+impl From<AddressTransaction> for AccountHistoryEntry {
+    fn from(tx: AddressTransaction) -> Self {
+        Self {
+            hash: tx.hash,
+            block_id: tx.block_id,
+            program_id: tx.program_id.map(|program_id| program_id.to_string()),
+            direction: tx.direction,
+            amount: tx.amount,
+        }
+    }
+}
+
+impl CommandOutput for AccountHistoryOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.transactions.is_empty() {
+            return write!(f, "No transactions of {}", self.account_id);
+        }
+
+        let mut table = Table::with_header(&["HASH", "BLOCK", "DIRECTION", "AMOUNT", "PROGRAM"]);
+        for tx in &self.transactions {
+            let direction = tx.direction.map(|direction| match direction {
+                TxDirection::In => "in",
+                TxDirection::Out => "out",
+            });
+            table.push_row(vec![
+                tx.hash.clone(),
+                tx.block_id.to_string(),
+                optional_cell(direction),
+                optional_cell(tx.amount),
+                optional_cell(tx.program_id.as_ref()),
+            ]);
+        }
+        write!(f, "{table}")?;
+        if self.has_more {
+            write!(
+                f,
+                "\nOlder transactions are not shown, see `--limit` and `--all`"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Output of `contacts list`
+#[derive(Debug, Serialize)]
+pub struct ContactListOutput {
+    pub contacts: Vec<Contact>,
+}
+
+impl CommandOutput for ContactListOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.contacts.is_empty() {
+            return write!(f, "Address book is empty");
+        }
+
+        let mut table = Table::with_header(&["NAME", "ADDRESS", "NOTE"]);
+        for contact in &self.contacts {
+            table.push_row(vec![
+                contact.name.clone(),
+                contact.address.clone(),
+                contact.note.clone().unwrap_or_default(),
+            ]);
+        }
+        write!(f, "{table}")
+    }
+}
+
+/// Output of `keys audit`
+#[derive(Debug, Serialize)]
+pub struct KeyAuditOutput {
+    /// Problems found before repair
+    pub issues: Vec<KeyAuditIssueOutput>,
+    /// Account ids, which keys were re-derived
+    pub repaired: Vec<String>,
+    /// Problems left after repair, equal to `issues` if repair wasn't requested
+    pub remaining: Vec<KeyAuditIssueOutput>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyAuditIssueOutput {
+    pub account_id: String,
+    /// Path in the key tree, `null` for preconfigured accounts
+    pub path: Option<String>,
+    pub problem: KeyProblemKind,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyProblemKind {
+    AccountIdMismatch,
+    InconsistentKeys,
+    NotDerivedFromParent,
+    MissingNode,
+}
+
+impl From<&KeyAuditIssue> for KeyAuditIssueOutput {
+    fn from(issue: &KeyAuditIssue) -> Self {
+        let problem = match issue.problem {
+            KeyProblem::AccountIdMismatch { .. } => KeyProblemKind::AccountIdMismatch,
+            KeyProblem::InconsistentKeys { .. } => KeyProblemKind::InconsistentKeys,
+            KeyProblem::NotDerivedFromParent => KeyProblemKind::NotDerivedFromParent,
+            KeyProblem::MissingNode => KeyProblemKind::MissingNode,
+        };
+        Self {
+            account_id: issue.account_id.to_string(),
+            path: issue
+                .chain_index
+                .as_ref()
+                .map(|chain_index| chain_index.to_path_string()),
+            problem,
+            description: issue.to_string(),
+        }
+    }
+}
+
+impl CommandOutput for KeyAuditOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines: Vec<String> = self
+            .issues
+            .iter()
+            .map(|issue| issue.description.clone())
+            .collect();
+        lines.extend(
+            self.repaired
+                .iter()
+                .map(|account_id| format!("Repaired keys of account {account_id}")),
+        );
+        if self.remaining.is_empty() {
+            lines.push("✅All keys are consistent".to_string());
+        } else if !self.repaired.is_empty() {
+            lines.extend(
+                self.remaining
+                    .iter()
+                    .map(|issue| format!("Not repaired: {}", issue.description)),
+            );
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Output of `keystore info`
+#[derive(Debug, Serialize)]
+pub struct KeystoreInfoOutput {
+    pub wallet_file: String,
+    pub format_version: u32,
+    /// Newest format version the wallet reads
+    pub supported_format_version: u32,
+    /// Summary of the contents, `null` if the file is of a newer version
+    pub contents: Option<KeystoreContentsOutput>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeystoreContentsOutput {
+    pub last_synced_block: u64,
+    pub public_accounts: usize,
+    pub private_accounts: usize,
+    pub preconfigured_accounts: usize,
+    /// Number of labeled addresses
+    pub labels: usize,
+    /// Block light verification follows headers from, if any
+    pub trusted_checkpoint_block: Option<u64>,
+}
+
+impl CommandOutput for KeystoreInfoOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::default();
+        table.push_row(vec!["Wallet file".to_string(), self.wallet_file.clone()]);
+        table.push_row(vec![
+            "Format version".to_string(),
+            format!(
+                "{} (supported up to {})",
+                self.format_version, self.supported_format_version
+            ),
+        ]);
+        let Some(contents) = &self.contents else {
+            return write!(f, "{table}\nUpgrade the wallet to read the file");
+        };
+
+        for (name, count) in [
+            ("Public accounts", contents.public_accounts),
+            ("Private accounts", contents.private_accounts),
+            ("Preconfigured accounts", contents.preconfigured_accounts),
+            ("Labels", contents.labels),
+        ] {
+            table.push_row(vec![name.to_string(), count.to_string()]);
+        }
+        table.push_row(vec![
+            "Last synced block".to_string(),
+            contents.last_synced_block.to_string(),
+        ]);
+        if let Some(block_id) = contents.trusted_checkpoint_block {
+            table.push_row(vec!["Trusted block".to_string(), block_id.to_string()]);
+        }
+        write!(f, "{table}")
+    }
+}
+
+/// Output of `tx status`
+#[derive(Debug, Serialize)]
+pub struct TxStatusOutput {
+    pub transactions: Vec<TxStatusEntry>,
+    #[serde(skip)]
+    pub time_display: TimeDisplay,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TxStatusEntry {
+    pub hash: String,
+    pub status: TxStatus,
+    pub block_id: Option<u64>,
+    pub target: Option<String>,
+    pub amount: Option<Amount>,
+    /// Block the sequencer promised to include the transaction by
+    pub promised_by_block: Option<u64>,
+    /// Unix time of the last status change in milliseconds
+    pub updated_at: u64,
+    /// Instruction of a public transaction, `null` for other transactions
+    pub instruction: Option<InstructionView>,
+    /// Balance changes of an included transaction, shown when a single transaction is queried
+    pub receipt: Option<TransactionReceipt>,
+    /// Mempool lifecycle events recorded by the sequencer, shown when a single transaction is
+    /// queried and the admin auth token is configured
+    pub audit: Option<Vec<TxAuditRecord>>,
+}
+
+impl TxStatusEntry {
+    /// Status of logged transaction, which instruction is decoded with `decoders`
+    pub fn new(entry: TxLogEntry, decoders: &DecoderRegistry) -> Self {
+        let instruction = BASE64
+            .decode(&entry.transaction_b64)
+            .ok()
+            .and_then(|bytes| borsh::from_slice(&bytes).ok())
+            .and_then(|tx| public_instruction(&tx, decoders));
+
+        Self {
+            hash: entry.hash,
+            status: entry.status,
+            block_id: entry.block_id,
+            target: entry.target,
+            amount: entry.amount,
+            promised_by_block: entry
+                .soft_confirmation
+                .map(|confirmation| confirmation.promised_by_block),
+            updated_at: entry.timestamp_millis,
+            instruction,
+            receipt: None,
+            audit: None,
+        }
+    }
+}
+
+impl CommandOutput for TxStatusOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.transactions.is_empty() {
+            return write!(f, "Transaction log is empty");
+        }
+
+        let mut table = Table::with_header(&[
+            "HASH", "CALL", "STATUS", "BLOCK", "TARGET", "AMOUNT", "UPDATED",
+        ]);
+        for entry in &self.transactions {
+            table.push_row(vec![
+                entry.hash.clone(),
+                optional_cell(entry.instruction.as_ref().map(InstructionView::call)),
+                format!("{:?}", entry.status),
+                optional_cell(entry.block_id),
+                optional_cell(entry.target.as_ref()),
+                optional_cell(entry.amount),
+                self.time_display.format(entry.updated_at),
+            ]);
+        }
+        write!(f, "{table}")?;
+
+        for entry in &self.transactions {
+            let Some(receipt) = &entry.receipt else {
+                continue;
+            };
+            let mut table = Table::with_header(&["ACCOUNT", "BEFORE", "AFTER", "CHANGE", "PARTS"]);
+            for change in &receipt.balance_changes {
+                let (sign, amount) = if change.post_balance >= change.pre_balance {
+                    (
+                        BalanceSign::Credit,
+                        change.post_balance.saturating_sub(change.pre_balance),
+                    )
+                } else {
+                    (
+                        BalanceSign::Debit,
+                        change.pre_balance.saturating_sub(change.post_balance),
+                    )
+                };
+                table.push_row(vec![
+                    change.account_id.clone(),
+                    change.pre_balance.to_string(),
+                    change.post_balance.to_string(),
+                    format!("{sign}{amount}"),
+                    if change.components.is_empty() {
+                        "-".to_string()
+                    } else {
+                        change.components.iter().join(", ")
+                    },
+                ]);
+            }
+            write!(f, "\n\nBalance changes of {}\n{table}", entry.hash)?;
+        }
+
+        for entry in &self.transactions {
+            let Some(audit) = &entry.audit else {
+                continue;
+            };
+            if audit.is_empty() {
+                write!(
+                    f,
+                    "\n\nSequencer has no mempool events of {}, it may have forgotten them",
+                    entry.hash
+                )?;
+                continue;
+            }
+            let mut table = Table::with_header(&["TIME", "EVENT", "DETAILS"]);
+            for record in audit {
+                let (event, details) = match &record.event {
+                    TxAuditEvent::Admitted {
+                        priority_fee,
+                        position,
+                    } => (
+                        "admitted",
+                        format!("position {position}, priority fee {priority_fee}"),
+                    ),
+                    TxAuditEvent::Evicted { reason } => ("evicted", reason.clone()),
+                    TxAuditEvent::Expired { reason } => ("expired", reason.clone()),
+                    TxAuditEvent::Replaced { by } => ("replaced", format!("by {by}")),
+                    TxAuditEvent::Included { block_id } => {
+                        ("included", format!("block {block_id}"))
+                    }
+                };
+                table.push_row(vec![
+                    self.time_display.format(record.timestamp_millis),
+                    event.to_string(),
+                    details,
+                ]);
+            }
+            write!(f, "\n\nMempool events of {}\n{table}", entry.hash)?;
+        }
+        Ok(())
+    }
+}
+
+/// Output of `tx build`
+#[derive(Debug, Serialize)]
+pub struct TxSubmittedOutput {
+    pub hash: String,
+    /// First block the transaction may be included into, `null` if it isn't time-locked
+    pub valid_from_block: Option<u64>,
+}
+
+impl CommandOutput for TxSubmittedOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Submitted transaction {}", self.hash)?;
+        if let Some(valid_from_block) = self.valid_from_block {
+            write!(f, ", it can't be included before block {valid_from_block}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Output of `tx build --dry-run`
+#[derive(Debug, Serialize)]
+pub struct TxDryRunOutput {
+    pub hash: String,
+    /// Status submission would respond with, `null` if the sequencer would reject the transaction
+    pub status: Option<String>,
+    /// Why the sequencer would reject the transaction
+    pub rejection: Option<String>,
+}
+
+impl From<TxDryRun> for TxDryRunOutput {
+    fn from(dry_run: TxDryRun) -> Self {
+        let (status, rejection) = match dry_run.verdict {
+            Ok(response) => (Some(response.status), None),
+            Err(reason) => (None, Some(reason)),
+        };
+        Self {
+            hash: dry_run.hash,
+            status,
+            rejection,
+        }
+    }
+}
+
+impl CommandOutput for TxDryRunOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.status, &self.rejection) {
+            (Some(status), _) => write!(
+                f,
+                "Sequencer would accept transaction {}: {status}",
+                self.hash
+            ),
+            (None, rejection) => write!(
+                f,
+                "Sequencer would reject transaction {}: {}",
+                self.hash,
+                rejection.as_deref().unwrap_or("unknown reason")
+            ),
+        }
+    }
+}
+
+/// Output of `tx payout`
+#[derive(Debug, Serialize)]
+pub struct PayoutOutput {
+    /// Path of the written results CSV
+    pub results_file: String,
+    pub payouts: Vec<PayoutEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PayoutEntry {
+    /// Line of the row in the payout file
+    pub line: usize,
+    pub to: String,
+    pub amount: Amount,
+    pub memo: Option<String>,
+    pub hash: Option<String>,
+    pub status: PayoutStatus,
+    pub block_id: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl From<PayoutResult> for PayoutEntry {
+    fn from(result: PayoutResult) -> Self {
+        Self {
+            line: result.row.line,
+            to: format!("Public/{}", result.row.to),
+            amount: result.row.amount,
+            memo: result.row.memo,
+            hash: result.tx_hash,
+            status: result.status,
+            block_id: result.block_id,
+            error: result.error,
+        }
+    }
+}
+
+impl CommandOutput for PayoutOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::with_header(&["LINE", "TO", "AMOUNT", "HASH", "STATUS", "BLOCK"]);
+        for entry in &self.payouts {
+            table.push_row(vec![
+                entry.line.to_string(),
+                entry.to.clone(),
+                entry.amount.to_string(),
+                optional_cell(entry.hash.as_ref()),
+                format!("{:?}", entry.status),
+                optional_cell(entry.block_id),
+            ]);
+        }
+        writeln!(f, "{table}")?;
+        for entry in &self.payouts {
+            if let Some(error) = &entry.error {
+                writeln!(f, "Line {} failed: {error}", entry.line)?;
+            }
+        }
+        write!(f, "Results are written to {}", self.results_file)
+    }
+}
+
+/// Output of `report` and `report verify`
+#[derive(Debug, Serialize)]
+pub struct ReportOutput {
+    /// Path of the report
+    pub file: String,
+    /// Reported account id with privacy prefix
+    pub address: String,
+    /// Account id with privacy prefix, which key signed the report
+    pub signer: String,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub transactions: u64,
+    pub opening_balance: Amount,
+    pub closing_balance: Amount,
+    /// Closing balance is proven against the state root of the last block of the range
+    pub proven: bool,
+}
+
+impl CommandOutput for ReportOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::default();
+        table.push_row(vec!["File".to_string(), self.file.clone()]);
+        table.push_row(vec!["Address".to_string(), self.address.clone()]);
+        table.push_row(vec!["Signed by".to_string(), self.signer.clone()]);
+        table.push_row(vec![
+            "Blocks".to_string(),
+            format!("{}..={}", self.from_block, self.to_block),
+        ]);
+        table.push_row(vec![
+            "Transactions".to_string(),
+            self.transactions.to_string(),
+        ]);
+        table.push_row(vec![
+            "Opening balance".to_string(),
+            self.opening_balance.to_string(),
+        ]);
+        let proven = if self.proven { " (proven)" } else { "" };
+        table.push_row(vec![
+            "Closing balance".to_string(),
+            format!("{}{proven}", self.closing_balance),
+        ]);
+        write!(f, "{table}")
+    }
+}
+
+/// Output of `tx decode`
+#[derive(Debug, Serialize)]
+pub struct TxDecodeOutput {
+    /// Hex encoded transaction hash, as reported by the sequencer
+    pub hash: String,
+    /// Tag of the transaction encoding
+    pub tag: u8,
+    /// Encoding is no longer accepted by the sequencer
+    pub legacy: bool,
+    #[serde(flatten)]
+    pub body: DecodedTxBody,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DecodedTxBody {
+    Public(DecodedPublicTx),
+    PrivacyPreserving(DecodedPrivacyPreservingTx),
+    ProgramDeployment(DecodedProgramDeploymentTx),
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedPublicTx {
+    /// Id of the called program
+    pub program_id: String,
+    /// Name of the called program, `null` unless it's a builtin one
+    pub program: Option<String>,
+    /// Account ids with privacy prefix, signers' accounts first
+    pub accounts: Vec<String>,
+    pub readonly_accounts: Vec<String>,
+    /// Nonces of the signers' accounts
+    pub nonces: Vec<Nonce>,
+    pub instruction: InstructionView,
+    pub priority_fee: Amount,
+    pub valid_from_block: Option<u64>,
+    pub max_cycles: Option<Cycles>,
+    pub signatures: Vec<SignatureView>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedPrivacyPreservingTx {
+    /// Public account ids with privacy prefix
+    pub accounts: Vec<String>,
+    /// Nonces of the signers' accounts
+    pub nonces: Vec<Nonce>,
+    pub encrypted_post_states: usize,
+    pub new_commitments: usize,
+    pub new_nullifiers: usize,
+    pub signatures: Vec<SignatureView>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedProgramDeploymentTx {
+    /// Account id of the deployer with privacy prefix
+    pub deployer: String,
+    pub nonce: Nonce,
+    pub chain_id: u64,
+    pub max_fee: Amount,
+    /// Fee charged for the deployment
+    pub fee: Amount,
+    pub bytecode_size: usize,
+    /// Id of the deployed program, `null` if bytecode is invalid
+    pub program_id: Option<String>,
+    pub signatures: Vec<SignatureView>,
+}
+
+/// Instruction of a public transaction, decoded if its program is known, see
+/// [`nssa::instruction_decoder::DecoderRegistry`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct InstructionView(pub serde_json::Value);
+
+impl InstructionView {
+    /// Name of the call, `unknown` if its program has no decoder
+    pub fn call(&self) -> &str {
+        self.0["call"].as_str().unwrap_or("unknown")
+    }
+}
+
+impl Display for InstructionView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(fields) = self.0.as_object() else {
+            return write!(f, "{}", self.0);
+        };
+
+        let args = fields
+            .iter()
+            .filter(|(name, _)| *name != "call")
+            .map(|(name, value)| match value {
+                serde_json::Value::String(value) => format!("{name} {value}"),
+                value => format!("{name} {value}"),
+            })
+            .collect::<Vec<_>>();
+        if args.is_empty() {
+            write!(f, "{}", self.call())
+        } else {
+            write!(f, "{}: {}", self.call(), args.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignatureView {
+    /// Account id of the signer with privacy prefix
+    pub signer: String,
+    /// Hex encoded public key
+    pub public_key: String,
+    /// Hex encoded signature
+    pub signature: String,
+    /// Whether the signature is valid for the message, `null` if the transaction is in a legacy
+    /// encoding, which can't be checked
+    pub valid: Option<bool>,
+}
+
+impl CommandOutput for TxDecodeOutput {
+    fn fmt_human(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (kind, signatures) = match &self.body {
+            DecodedTxBody::Public(tx) => (TransactionKind::Public, tx.signatures.as_slice()),
+            DecodedTxBody::PrivacyPreserving(tx) => {
+                (TransactionKind::PrivacyPreserving, tx.signatures.as_slice())
+            }
+            DecodedTxBody::ProgramDeployment(tx) => {
+                (TransactionKind::ProgramDeployment, tx.signatures.as_slice())
+            }
+        };
+        let mut kind = kind.to_string();
+        if self.legacy {
+            kind.push_str(" (legacy)");
+        }
+
+        let mut table = Table::default();
+        table.push_row(vec!["Hash".to_string(), self.hash.clone()]);
+        table.push_row(vec![
+            "Kind".to_string(),
+            format!("{kind}, tag {}", self.tag),
+        ]);
+        match &self.body {
+            DecodedTxBody::Public(tx) => {
+                let program = match &tx.program {
+                    Some(name) => format!("{name} ({})", tx.program_id),
+                    None => tx.program_id.clone(),
+                };
+                table.push_row(vec!["Program".to_string(), program]);
+                table.push_row(vec!["Accounts".to_string(), tx.accounts.join(", ")]);
+                if !tx.readonly_accounts.is_empty() {
+                    table.push_row(vec![
+                        "Read-only accounts".to_string(),
+                        tx.readonly_accounts.join(", "),
+                    ]);
+                }
+                table.push_row(vec!["Nonces".to_string(), format!("{:?}", tx.nonces)]);
+                table.push_row(vec!["Instruction".to_string(), tx.instruction.to_string()]);
+                table.push_row(vec![
+                    "Priority fee".to_string(),
+                    tx.priority_fee.to_string(),
+                ]);
+                table.push_row(vec![
+                    "Valid from block".to_string(),
+                    optional_cell(tx.valid_from_block),
+                ]);
+                table.push_row(vec!["Max cycles".to_string(), optional_cell(tx.max_cycles)]);
+            }
+            DecodedTxBody::PrivacyPreserving(tx) => {
+                table.push_row(vec!["Public accounts".to_string(), tx.accounts.join(", ")]);
+                table.push_row(vec!["Nonces".to_string(), format!("{:?}", tx.nonces)]);
+                table.push_row(vec![
+                    "Private post-states".to_string(),
+                    tx.encrypted_post_states.to_string(),
+                ]);
+                table.push_row(vec![
+                    "New commitments".to_string(),
+                    tx.new_commitments.to_string(),
+                ]);
+                table.push_row(vec![
+                    "New nullifiers".to_string(),
+                    tx.new_nullifiers.to_string(),
+                ]);
+            }
+            DecodedTxBody::ProgramDeployment(tx) => {
+                table.push_row(vec!["Deployer".to_string(), tx.deployer.clone()]);
+                table.push_row(vec!["Nonce".to_string(), tx.nonce.to_string()]);
+                table.push_row(vec!["Chain id".to_string(), tx.chain_id.to_string()]);
+                table.push_row(vec![
+                    "Fee".to_string(),
+                    format!("{} (max {})", tx.fee, tx.max_fee),
+                ]);
+                table.push_row(vec![
+                    "Bytecode size".to_string(),
+                    tx.bytecode_size.to_string(),
+                ]);
+                table.push_row(vec![
+                    "Program".to_string(),
+                    tx.program_id
+                        .clone()
+                        .unwrap_or_else(|| "Invalid bytecode".to_string()),
+                ]);
+            }
+        }
+        for (i, signature) in signatures.iter().enumerate() {
+            let validity = match signature.valid {
+                Some(true) => "valid",
+                Some(false) => "INVALID",
+                None => "not checked",
+            };
+            table.push_row(vec![
+                format!("Signature {}", i + 1),
+                format!(
+                    "{} by {} ({validity})",
+                    signature.signature, signature.signer
+                ),
+            ]);
+        }
+        write!(f, "{table}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        block::HashableBlockData, rpc_types::SyncState, test_utils::produce_dummy_empty_transaction,
+    };
+    use key_protocol::key_management::key_tree::chain_index::AddressChain;
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn assert_schema(output: &impl CommandOutput, snapshot: Value) {
+        assert_eq!(serde_json::to_value(output).unwrap(), snapshot);
+    }
+
+    fn human(output: &impl CommandOutput) -> String {
+        Human(output).to_string()
+    }
+
+    #[test]
+    fn test_table_aligns_columns() {
+        let mut table = Table::with_header(&["PATH", "ACCOUNT"]);
+        table.push_row(vec!["m/0".to_string(), "Public/abc".to_string()]);
+        table.push_row(vec!["Preconfigured".to_string(), "Private/d".to_string()]);
+
+        assert_eq!(
+            table.to_string(),
+            "PATH           ACCOUNT\n\
+             m/0            Public/abc\n\
+             Preconfigured  Private/d"
+        );
+    }
+
+    #[test]
+    fn test_last_block_schema() {
+        let output = LastBlockOutput {
+            last_block_id: 7,
+            last_block_hash: Some("00ff".to_string()),
+        };
+
+        assert_schema(
+            &output,
+            json!({ "last_block_id": 7, "last_block_hash": "00ff" }),
+        );
+        assert_eq!(human(&output), "Last block id is 7, its hash is 00ff");
+    }
+
+    #[test]
+    fn test_block_schema() {
+        let tx = produce_dummy_empty_transaction();
+        let block = HashableBlockData {
+            block_id: 3,
+            prev_block_hash: [1; 32],
+            timestamp: 100,
+            transactions: vec![tx.clone()],
+            params_version: 0,
+            dev_proved: false,
+        };
+
+        let output = BlockOutput::new(block, TimeDisplay::Utc);
+
+        assert_schema(
+            &output,
+            json!({
+                "block_id": 3,
+                "prev_block_hash": hex::encode([1; 32]),
+                "timestamp": 100,
+                "transactions": [{ "hash": hex::encode(tx.hash()), "kind": "public" }],
+            }),
+        );
+        assert!(human(&output).contains("Timestamp 1970-01-01 00:00:00 UTC"));
+    }
+
+    #[test]
+    fn test_relative_time() {
+        let now_millis = 1_000_000_000;
+        let display = TimeDisplay::Relative { now_millis };
+
+        assert_eq!(display.format(now_millis - 5_000), "just now");
+        // Sequencer clock may be a bit ahead of the wallet one
+        assert_eq!(display.format(now_millis + 20_000), "just now");
+        assert_eq!(display.format(now_millis - 60_000), "1 minute ago");
+        assert_eq!(
+            display.format(now_millis - 3 * 60_000 - 5_000),
+            "3 minutes ago"
+        );
+        assert_eq!(display.format(now_millis - 2 * 3_600_000), "2 hours ago");
+        assert_eq!(display.format(now_millis - 86_400_000), "1 day ago");
+        assert_eq!(
+            display.format(now_millis + 3_600_000),
+            "1 hour in the future"
+        );
+    }
+
+    #[test]
+    fn test_utc_time() {
+        assert_eq!(
+            TimeDisplay::Utc.format(1_748_781_000_000),
+            "2025-06-01 12:30:00 UTC"
+        );
+    }
+
+    #[test]
+    fn test_transaction_schema() {
+        let tx = produce_dummy_empty_transaction();
+
+        assert_schema(
+            &TransactionOutput::new("ab".to_string(), Some(&tx)),
+            json!({
+                "hash": "ab",
+                "found": true,
+                "kind": "public",
+                "transaction_b64": BASE64.encode(borsh::to_vec(&tx).unwrap()),
+            }),
+        );
+        assert_schema(
+            &TransactionOutput::new("ab".to_string(), None),
+            json!({ "hash": "ab", "found": false, "kind": null, "transaction_b64": null }),
+        );
+    }
+
+    #[test]
+    fn test_chain_info_schema() {
+        let chain_info = common::rpc_types::GetChainInfoResponse {
+            genesis_id: 1,
+            last_block: 7,
+            max_num_tx_in_block: 10,
+            params_version: 0,
+            tx_ordering_policy: "fifo".to_string(),
+            program_allow_list: None,
+            forced_params_changes: vec![],
+            storage_deposit_per_byte: nssa::Amount::ZERO,
+        };
+        let sequencer_info = common::rpc_types::GetSequencerInfoResponse {
+            version: "0.2.0".to_string(),
+            api_version: common::rpc_types::ApiVersion {
+                major: 1,
+                minor: 10,
+            },
+            tx_kinds: vec![0, 1, 2],
+            features: vec![common::rpc_types::SequencerFeature::LazyProofs],
+            public_key: None,
+            genesis_hash: None,
+            chain_id: None,
+        };
+
+        let output = ChainInfoOutput::new(&chain_info, Some(&sequencer_info));
+        assert_schema(
+            &output,
+            json!({
+                "genesis_id": 1,
+                "last_block": 7,
+                "wallet_version": env!("CARGO_PKG_VERSION"),
+                "wallet_api_version": common::rpc_types::API_VERSION.to_string(),
+                "sequencer_version": "0.2.0",
+                "sequencer_api_version": "1.10",
+                "sequencer_features": ["lazy_proofs"],
+            }),
+        );
+        assert!(human(&output).contains("Sequencer version: 0.2.0 (API 1.10)"));
+
+        let output = ChainInfoOutput::new(&chain_info, None);
+        assert_eq!(output.sequencer_version, None);
+        assert!(human(&output).contains("Sequencer version: unknown"));
+    }
+
+    #[test]
+    fn test_sync_status_schema() {
+        let status = common::rpc_types::SyncStatus {
+            state: SyncState::Syncing,
+            start_height: 1,
+            current_height: 5,
+            target_height: 10,
+            blocks_per_sec: 2.5,
+            eta_secs: Some(2),
+        };
+
+        let output = SyncStatusOutput::from(status);
+        assert_schema(
+            &output,
+            json!({
+                "synced": false,
+                "current_height": 5,
+                "target_height": 10,
+                "blocks_per_sec": 2.5,
+                "eta_secs": 2,
+            }),
+        );
+        assert_eq!(
+            human(&output),
+            "Syncing: block 5 of 10, 2.5 blocks/s, ETA 2s"
+        );
+    }
+
+    #[test]
+    fn test_account_schema() {
+        let account_id = "Public/abc".to_string();
+
+        assert_schema(
+            &AccountOutput {
+                account_id: account_id.clone(),
+                view: AccountView::Uninitialized,
+            },
+            json!({ "account_id": "Public/abc", "owner": "uninitialized" }),
+        );
+        assert_schema(
+            &AccountOutput {
+                account_id: account_id.clone(),
+                view: AccountView::AuthenticatedTransfer(AuthenticatedTransferAccountView {
+                    balance: Amount(10),
+                }),
+            },
+            json!({ "account_id": "Public/abc", "owner": "authenticated_transfer", "balance": 10 }),
+        );
+        assert_schema(
+            &AccountOutput {
+                account_id: account_id.clone(),
+                view: AccountView::TokenDefinition(TokedDefinitionAccountView {
+                    name: "NAME".to_string(),
+                    total_supply: 100,
+                }),
+            },
+            json!({
+                "account_id": "Public/abc",
+                "owner": "token_definition",
+                "name": "NAME",
+                "total_supply": 100,
+            }),
+        );
+        assert_schema(
+            &AccountOutput {
+                account_id: account_id.clone(),
+                view: AccountView::TokenHolding(TokedHoldingAccountView {
+                    definition_id: "def".to_string(),
+                    balance: 5,
+                }),
+            },
+            json!({
+                "account_id": "Public/abc",
+                "owner": "token_holding",
+                "definition_id": "def",
+                "balance": 5,
+            }),
+        );
+        assert_schema(
+            &AccountOutput {
+                account_id,
+                view: AccountView::Raw(RawAccountView::from(nssa::Account {
+                    balance: Amount(1),
+                    nonce: 2,
+                    data: vec![3].try_into().unwrap(),
+                    ..Default::default()
+                })),
+            },
+            json!({
+                "account_id": "Public/abc",
+                "owner": "raw",
+                "balance": 1,
+                "program_owner": format!("prog_{}", "00".repeat(32)),
+                "data_b64": "Aw==",
+                "nonce": 2,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_new_account_schema() {
+        assert_schema(
+            &NewAccountOutput {
+                account_id: "Private/abc".to_string(),
+                path: "m/0".to_string(),
+                label: None,
+                npk: Some("01".to_string()),
+                ipk: Some("02".to_string()),
+                wallet_file: "/home/storage.json".to_string(),
+                qr: false,
+            },
+            json!({
+                "account_id": "Private/abc",
+                "path": "m/0",
+                "label": null,
+                "npk": "01",
+                "ipk": "02",
+                "wallet_file": "/home/storage.json",
+            }),
+        );
+    }
+
+    #[test]
+    fn test_synced_schema() {
+        assert_schema(
+            &SyncedOutput { synced_to_block: 4 },
+            json!({ "synced_to_block": 4 }),
+        );
+    }
+
+    #[test]
+    fn test_daemon_status_schema() {
+        assert_schema(
+            &DaemonStatusOutput {
+                running: true,
+                pid: Some(42),
+                last_synced_block: Some(7),
+            },
+            json!({ "running": true, "pid": 42, "last_synced_block": 7 }),
+        );
+        assert_schema(
+            &DaemonStatusOutput {
+                running: false,
+                pid: None,
+                last_synced_block: None,
+            },
+            json!({ "running": false, "pid": null, "last_synced_block": null }),
+        );
+    }
+
+    #[test]
+    fn test_message_signature_schemas() {
+        assert_schema(
+            &MessageSignatureOutput {
+                address: "Public/abc".to_string(),
+                signature: "c2ln".to_string(),
+            },
+            json!({ "address": "Public/abc", "signature": "c2ln" }),
+        );
+
+        let output = MessageVerificationOutput {
+            address: "Public/abc".to_string(),
+            valid: true,
+        };
+        assert_schema(&output, json!({ "address": "Public/abc", "valid": true }));
+        assert_eq!(human(&output), "Signature is valid for Public/abc");
+    }
+
+    #[test]
+    fn test_account_list_schema() {
+        let account_id = nssa::AccountId::new([1; 32]);
+        let output = AccountListOutput {
+            accounts: vec![
+                AccountListEntry::from(OwnedAccount {
+                    account_id,
+                    privacy: AccountPrivacyKind::Public,
+                    chain_index: None,
+                })
+                .with_balance(Some(PendingBalance {
+                    confirmed: Amount(1000),
+                    pending_outgoing: Amount(300),
+                    pending_incoming: Some(Amount(50)),
+                }))
+                .with_usage(Some(true), Some("savings".to_string())),
+                OwnedAccount {
+                    account_id,
+                    privacy: AccountPrivacyKind::Private,
+                    chain_index: Some(ChainIndex::root().nth_child(2)),
+                }
+                .into(),
+                OwnedAccount {
+                    account_id,
+                    privacy: AccountPrivacyKind::Public,
+                    chain_index: Some(
+                        ChainIndex::account(2).chain_address(AddressChain::Internal, 4),
+                    ),
+                }
+                .into(),
+            ],
+            failed: vec![AccountFetchFailure::new(
+                &OwnedAccount {
+                    account_id,
+                    privacy: AccountPrivacyKind::Public,
+                    chain_index: None,
+                },
+                &anyhow::anyhow!("Sequencer is busy"),
+            )],
+            unverified: vec![],
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "accounts": [
+                    {
+                        "account_id": format!("Public/{account_id}"),
+                        "path": null,
+                        "account": null,
+                        "chain": null,
+                        "label": "savings",
+                        "used": true,
+                        "balance": {
+                            "confirmed": 1000,
+                            "pending_outgoing": 300,
+                            "pending_incoming": 50,
+                            "spendable": 700,
+                        },
+                    },
+                    {
+                        "account_id": format!("Private/{account_id}"),
+                        "path": "m/2",
+                        "account": null,
+                        "chain": null,
+                        "label": null,
+                        "used": null,
+                        "balance": null,
+                    },
+                    {
+                        "account_id": format!("Public/{account_id}"),
+                        "path": "m/2/1/4",
+                        "account": "m/2",
+                        "chain": "internal",
+                        "label": null,
+                        "used": null,
+                        "balance": null,
+                    },
+                ],
+                "failed": [
+                    {
+                        "account_id": format!("Public/{account_id}"),
+                        "error": "Sequencer is busy",
+                    },
+                ],
+            }),
+        );
+        assert!(human(&output).ends_with(&format!(
+            "Failed to fetch Public/{account_id}: Sequencer is busy"
+        )));
+    }
+
+    #[test]
+    fn test_account_history_schema() {
+        let account_id = format!("Public/{}", nssa::AccountId::new([1; 32]));
+        let program_id = nssa_core::program::ProgramId::new([1; 8]);
+        let output = AccountHistoryOutput {
+            account_id: account_id.clone(),
+            transactions: vec![
+                AddressTransaction {
+                    hash: "abcd".to_string(),
+                    block_id: 7,
+                    program_id: Some(program_id),
+                    direction: Some(TxDirection::In),
+                    amount: Some(Amount(10)),
+                }
+                .into(),
+                AddressTransaction {
+                    hash: "ef01".to_string(),
+                    block_id: 5,
+                    program_id: None,
+                    direction: None,
+                    amount: None,
+                }
+                .into(),
+            ],
+            has_more: true,
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "account_id": account_id,
+                "transactions": [
+                    {
+                        "hash": "abcd",
+                        "block_id": 7,
+                        "program_id": program_id.to_string(),
+                        "direction": "in",
+                        "amount": 10,
+                    },
+                    {
+                        "hash": "ef01",
+                        "block_id": 5,
+                        "program_id": null,
+                        "direction": null,
+                        "amount": null,
+                    },
+                ],
+                "has_more": true,
+            }),
+        );
+        assert!(human(&output).ends_with("see `--limit` and `--all`"));
+    }
+
+    #[test]
+    fn test_contact_list_schema() {
+        let address = format!("Public/{}", nssa::AccountId::new([1; 32]));
+        let output = ContactListOutput {
+            contacts: vec![
+                Contact {
+                    name: "alice".to_string(),
+                    address: address.clone(),
+                    note: Some("Rent".to_string()),
+                },
+                Contact {
+                    name: "bob".to_string(),
+                    address: address.clone(),
+                    note: None,
+                },
+            ],
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "contacts": [
+                    { "name": "alice", "address": address, "note": "Rent" },
+                    { "name": "bob", "address": address },
+                ],
+            }),
+        );
+    }
+
+    #[test]
+    fn test_key_audit_schema() {
+        let issue = KeyAuditIssue {
+            account_id: nssa::AccountId::new([1; 32]),
+            chain_index: Some(ChainIndex::root().nth_child(0)),
+            problem: KeyProblem::MissingNode,
+        };
+        let output = KeyAuditOutput {
+            issues: vec![(&issue).into()],
+            repaired: vec![issue.account_id.to_string()],
+            remaining: vec![],
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "issues": [{
+                    "account_id": issue.account_id.to_string(),
+                    "path": "m/0",
+                    "problem": "missing_node",
+                    "description": issue.to_string(),
+                }],
+                "repaired": [issue.account_id.to_string()],
+                "remaining": [],
+            }),
+        );
+    }
+
+    #[test]
+    fn test_keystore_info_schema() {
+        assert_schema(
+            &KeystoreInfoOutput {
+                wallet_file: "/home/storage.json".to_string(),
+                format_version: 1,
+                supported_format_version: 1,
+                contents: Some(KeystoreContentsOutput {
+                    last_synced_block: 7,
+                    public_accounts: 2,
+                    private_accounts: 1,
+                    preconfigured_accounts: 3,
+                    labels: 1,
+                    trusted_checkpoint_block: None,
+                }),
+            },
+            json!({
+                "wallet_file": "/home/storage.json",
+                "format_version": 1,
+                "supported_format_version": 1,
+                "contents": {
+                    "last_synced_block": 7,
+                    "public_accounts": 2,
+                    "private_accounts": 1,
+                    "preconfigured_accounts": 3,
+                    "labels": 1,
+                    "trusted_checkpoint_block": null,
+                },
+            }),
+        );
+        assert_schema(
+            &KeystoreInfoOutput {
+                wallet_file: "/home/storage.json".to_string(),
+                format_version: 5,
+                supported_format_version: 1,
+                contents: None,
+            },
+            json!({
+                "wallet_file": "/home/storage.json",
+                "format_version": 5,
+                "supported_format_version": 1,
+                "contents": null,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_tx_status_schema() {
+        let output = TxStatusOutput {
+            transactions: vec![TxStatusEntry {
+                hash: "ab".to_string(),
+                status: TxStatus::Included,
+                block_id: Some(3),
+                target: None,
+                amount: Some(Amount(10)),
+                promised_by_block: Some(13),
+                updated_at: 1_000_000,
+                instruction: Some(InstructionView(json!({
+                    "call": "native_transfer",
+                    "amount": "10",
+                }))),
+                receipt: None,
+                audit: None,
+            }],
+            time_display: TimeDisplay::Relative {
+                now_millis: 1_000_000 + 5 * 60_000,
+            },
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "transactions": [{
+                    "hash": "ab",
+                    "status": "Included",
+                    "block_id": 3,
+                    "target": null,
+                    "amount": 10,
+                    "promised_by_block": 13,
+                    "updated_at": 1_000_000,
+                    "instruction": { "call": "native_transfer", "amount": "10" },
+                    "receipt": null,
+                    "audit": null,
+                }],
+            }),
+        );
+        assert_eq!(
+            human(&output),
+            "HASH  CALL             STATUS    BLOCK  TARGET  AMOUNT  UPDATED\n\
+             ab    native_transfer  Included  3      -       10      5 minutes ago"
+        );
+    }
+
+    #[test]
+    fn test_tx_status_shows_balance_changes() {
+        use common::receipt::{BalanceChange, BalanceComponent, BalanceComponentKind};
+
+        let component = |kind, amount, sign| BalanceComponent { kind, amount, sign };
+        let output = TxStatusOutput {
+            transactions: vec![TxStatusEntry {
+                hash: "ab".to_string(),
+                status: TxStatus::Included,
+                block_id: Some(3),
+                target: None,
+                amount: None,
+                promised_by_block: None,
+                updated_at: 1_000_000,
+                instruction: None,
+                receipt: Some(TransactionReceipt {
+                    balance_changes: vec![
+                        BalanceChange {
+                            account_id: "payer".to_string(),
+                            pre_balance: Amount(1000),
+                            post_balance: Amount(842),
+                            components: vec![
+                                component(
+                                    BalanceComponentKind::Transfer,
+                                    Amount(150),
+                                    BalanceSign::Debit,
+                                ),
+                                component(
+                                    BalanceComponentKind::Deposit,
+                                    Amount(10),
+                                    BalanceSign::Debit,
+                                ),
+                                component(
+                                    BalanceComponentKind::Refund,
+                                    Amount(7),
+                                    BalanceSign::Credit,
+                                ),
+                                component(
+                                    BalanceComponentKind::Priority,
+                                    Amount(5),
+                                    BalanceSign::Debit,
+                                ),
+                            ],
+                        },
+                        // Receipts of old sequencers have no components
+                        BalanceChange {
+                            account_id: "to".to_string(),
+                            pre_balance: Amount(0),
+                            post_balance: Amount(150),
+                            components: vec![],
+                        },
+                    ],
+                }),
+                audit: None,
+            }],
+            time_display: TimeDisplay::Utc,
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "transactions": [{
+                    "hash": "ab",
+                    "status": "Included",
+                    "block_id": 3,
+                    "target": null,
+                    "amount": null,
+                    "promised_by_block": null,
+                    "updated_at": 1_000_000,
+                    "instruction": null,
+                    "receipt": {
+                        "balance_changes": [
+                            {
+                                "account_id": "payer",
+                                "pre_balance": "1000",
+                                "post_balance": "842",
+                                "components": [
+                                    { "kind": "transfer", "amount": "150", "sign": "debit" },
+                                    { "kind": "deposit", "amount": "10", "sign": "debit" },
+                                    { "kind": "refund", "amount": "7", "sign": "credit" },
+                                    { "kind": "priority", "amount": "5", "sign": "debit" },
+                                ],
+                            },
+                            {
+                                "account_id": "to",
+                                "pre_balance": "0",
+                                "post_balance": "150",
+                                "components": [],
+                            },
+                        ],
+                    },
+                    "audit": null,
+                }],
+            }),
+        );
+        let human = human(&output);
+        assert!(
+            human.ends_with(
+                "Balance changes of ab\n\
+                 ACCOUNT  BEFORE  AFTER  CHANGE  PARTS\n\
+                 payer    1000    842    -158    -150 transfer, -10 deposit, +7 refund, -5 priority\n\
+                 to       0       150    +150    -"
+            ),
+            "{human}"
+        );
+    }
+
+    #[test]
+    fn test_tx_status_shows_mempool_audit() {
+        let record = |minutes_ago: u64, event| TxAuditRecord {
+            timestamp_millis: 1_000_000 - minutes_ago * 60_000,
+            event,
+        };
+        let output = TxStatusOutput {
+            transactions: vec![TxStatusEntry {
+                hash: "ab".to_string(),
+                status: TxStatus::Replaced,
+                block_id: None,
+                target: None,
+                amount: None,
+                promised_by_block: None,
+                updated_at: 1_000_000,
+                instruction: None,
+                receipt: None,
+                audit: Some(vec![
+                    record(
+                        3,
+                        TxAuditEvent::Admitted {
+                            priority_fee: Amount(5),
+                            position: 2,
+                        },
+                    ),
+                    record(
+                        2,
+                        TxAuditEvent::Replaced {
+                            by: "cd".to_string(),
+                        },
+                    ),
+                ]),
+            }],
+            time_display: TimeDisplay::Relative {
+                now_millis: 1_000_000,
+            },
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "transactions": [{
+                    "hash": "ab",
+                    "status": "Replaced",
+                    "block_id": null,
+                    "target": null,
+                    "amount": null,
+                    "promised_by_block": null,
+                    "updated_at": 1_000_000,
+                    "instruction": null,
+                    "receipt": null,
+                    "audit": [
+                        {
+                            "timestamp_millis": 820_000,
+                            "event": "admitted",
+                            "priority_fee": "5",
+                            "position": 2,
+                        },
+                        { "timestamp_millis": 880_000, "event": "replaced", "by": "cd" },
+                    ],
+                }],
+            }),
+        );
+        let human = human(&output);
+        assert!(
+            human.ends_with(
+                "Mempool events of ab\n\
+                 TIME           EVENT     DETAILS\n\
+                 3 minutes ago  admitted  position 2, priority fee 5\n\
+                 2 minutes ago  replaced  by cd"
+            ),
+            "{human}"
+        );
+    }
+
+    #[test]
+    fn test_tx_submitted_schema() {
+        assert_schema(
+            &TxSubmittedOutput {
+                hash: "ab".to_string(),
+                valid_from_block: Some(6),
+            },
+            json!({ "hash": "ab", "valid_from_block": 6 }),
+        );
+    }
+
+    #[test]
+    fn test_tx_dry_run_schema() {
+        let accepted = TxDryRunOutput::from(TxDryRun {
+            hash: "ab".to_string(),
+            verdict: Ok(common::rpc_types::ValidateTransactionResponse {
+                status: "Transaction submitted".to_string(),
+                tx_hash: "ab".to_string(),
+                tx_status: None,
+                program_id: None,
+            }),
+        });
+        assert_schema(
+            &accepted,
+            json!({ "hash": "ab", "status": "Transaction submitted", "rejection": null }),
+        );
+        assert_eq!(
+            human(&accepted),
+            "Sequencer would accept transaction ab: Transaction submitted"
+        );
+
+        let rejected = TxDryRunOutput::from(TxDryRun {
+            hash: "ab".to_string(),
+            verdict: Err("\"InvalidSignature\"".to_string()),
+        });
+        assert_schema(
+            &rejected,
+            json!({ "hash": "ab", "status": null, "rejection": "\"InvalidSignature\"" }),
+        );
+        assert_eq!(
+            human(&rejected),
+            "Sequencer would reject transaction ab: \"InvalidSignature\""
+        );
+    }
+
+    #[test]
+    fn test_payout_schema() {
+        let output = PayoutOutput {
+            results_file: "payout.results.csv".to_string(),
+            payouts: vec![
+                PayoutEntry {
+                    line: 2,
+                    to: "Public/abc".to_string(),
+                    amount: Amount(10),
+                    memo: Some("Week 3".to_string()),
+                    hash: Some("ab".to_string()),
+                    status: PayoutStatus::Included,
+                    block_id: Some(4),
+                    error: None,
+                },
+                PayoutEntry {
+                    line: 3,
+                    to: "Public/d".to_string(),
+                    amount: Amount(5),
+                    memo: None,
+                    hash: None,
+                    status: PayoutStatus::Failed,
+                    block_id: None,
+                    error: Some("Mempool is congested".to_string()),
+                },
+            ],
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "results_file": "payout.results.csv",
+                "payouts": [
+                    {
+                        "line": 2,
+                        "to": "Public/abc",
+                        "amount": 10,
+                        "memo": "Week 3",
+                        "hash": "ab",
+                        "status": "Included",
+                        "block_id": 4,
+                        "error": null,
+                    },
+                    {
+                        "line": 3,
+                        "to": "Public/d",
+                        "amount": 5,
+                        "memo": null,
+                        "hash": null,
+                        "status": "Failed",
+                        "block_id": null,
+                        "error": "Mempool is congested",
+                    },
+                ],
+            }),
+        );
+        assert_eq!(
+            human(&output),
+            "LINE  TO          AMOUNT  HASH  STATUS    BLOCK\n\
+             2     Public/abc  10      ab    Included  4\n\
+             3     Public/d    5       -     Failed    -\n\
+             Line 3 failed: Mempool is congested\n\
+             Results are written to payout.results.csv"
+        );
+    }
+
+    #[test]
+    fn test_tx_decode_schema() {
+        let output = TxDecodeOutput {
+            hash: "ab".to_string(),
+            tag: 3,
+            legacy: false,
+            body: DecodedTxBody::Public(DecodedPublicTx {
+                program_id: "prog_01".to_string(),
+                program: Some("authenticated_transfer".to_string()),
+                accounts: vec!["Public/abc".to_string(), "Public/d".to_string()],
+                readonly_accounts: vec![],
+                nonces: vec![5],
+                instruction: InstructionView(json!({
+                    "call": "native_transfer",
+                    "recipient": "d",
+                    "amount": "10",
+                })),
+                priority_fee: Amount::ZERO,
+                valid_from_block: None,
+                max_cycles: Some(Cycles(100)),
+                signatures: vec![SignatureView {
+                    signer: "Public/abc".to_string(),
+                    public_key: "01".to_string(),
+                    signature: "02".to_string(),
+                    valid: Some(false),
+                }],
+            }),
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "hash": "ab",
+                "tag": 3,
+                "legacy": false,
+                "kind": "public",
+                "program_id": "prog_01",
+                "program": "authenticated_transfer",
+                "accounts": ["Public/abc", "Public/d"],
+                "readonly_accounts": [],
+                "nonces": [5],
+                "instruction": { "call": "native_transfer", "recipient": "d", "amount": "10" },
+                "priority_fee": 0,
+                "valid_from_block": null,
+                "max_cycles": 100,
+                "signatures": [{
+                    "signer": "Public/abc",
+                    "public_key": "01",
+                    "signature": "02",
+                    "valid": false,
+                }],
+            }),
+        );
+        assert_eq!(
+            human(&output),
+            "Hash              ab\n\
+             Kind              Public, tag 3\n\
+             Program           authenticated_transfer (prog_01)\n\
+             Accounts          Public/abc, Public/d\n\
+             Nonces            [5]\n\
+             Instruction       native_transfer: amount 10, recipient d\n\
+             Priority fee      0\n\
+             Valid from block  -\n\
+             Max cycles        100\n\
+             Signature 1       02 by Public/abc (INVALID)"
+        );
+    }
+
+    #[test]
+    fn test_program_deployment_decode_schema() {
+        let output = TxDecodeOutput {
+            hash: "ab".to_string(),
+            tag: 5,
+            legacy: false,
+            body: DecodedTxBody::ProgramDeployment(DecodedProgramDeploymentTx {
+                deployer: "Public/abc".to_string(),
+                nonce: 3,
+                chain_id: 7,
+                max_fee: Amount(10),
+                fee: Amount(1),
+                bytecode_size: 4,
+                program_id: None,
+                signatures: vec![],
+            }),
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "hash": "ab",
+                "tag": 5,
+                "legacy": false,
+                "kind": "program_deployment",
+                "deployer": "Public/abc",
+                "nonce": 3,
+                "chain_id": 7,
+                "max_fee": 10,
+                "fee": 1,
+                "bytecode_size": 4,
+                "program_id": null,
+                "signatures": [],
+            }),
+        );
+    }
+
+    #[test]
+    fn test_report_schema() {
+        let output = ReportOutput {
+            file: "report.json".to_string(),
+            address: "Public/abc".to_string(),
+            signer: "Public/abc".to_string(),
+            from_block: 2,
+            to_block: 5,
+            transactions: 3,
+            opening_balance: Amount(10),
+            closing_balance: Amount(40),
+            proven: true,
+        };
+
+        assert_schema(
+            &output,
+            json!({
+                "file": "report.json",
+                "address": "Public/abc",
+                "signer": "Public/abc",
+                "from_block": 2,
+                "to_block": 5,
+                "transactions": 3,
+                "opening_balance": 10,
+                "closing_balance": 40,
+                "proven": true,
+            }),
+        );
+        assert!(human(&output).contains("Closing balance  40 (proven)"));
+    }
+}
@@ -0,0 +1,502 @@
+//! Locations of the wallet files and creation of them with restrictive permissions
+//!
+//! By default the keystore, transaction journal and address book live in the data directory of
+//! the platform, and the config in its config directory: XDG directories on Linux,
+//! `Application Support` on macOS and `AppData` on Windows. `--data-dir` (`NSSA_WALLET_HOME_DIR`)
+//! keeps all of them in one directory instead, and `--wallet-file` (`WALLET_FILE`) moves the
+//! keystore only.
+//!
+//! Older wallets kept their files in the working directory or in `~/.nssa/wallet`.
+//! [`run_migration`] offers to move them once.
+
+use std::{
+    fs::{DirBuilder, File, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    HOME_DIR_ENV_VAR, STORAGE_FILE_NAME, WALLET_FILE_ENV_VAR,
+    address_book::{ADDRESS_BOOK_FILE_NAME, address_book_file},
+    tx_log::TX_LOG_FILE_NAME,
+};
+
+/// Name of the wallet directory inside platform directories
+pub const APP_DIR_NAME: &str = "nssa-wallet";
+pub const CONFIG_FILE_NAME: &str = "wallet_config.json";
+/// Record of the offered migration of legacy files, kept in the data directory
+pub const MIGRATION_RECORD_FILE_NAME: &str = "legacy_migration.json";
+
+/// Platform directories, which the wallet directories are placed in by default
+#[derive(Debug, Clone, Default)]
+pub struct PlatformDirs {
+    pub data_dir: Option<PathBuf>,
+    pub config_dir: Option<PathBuf>,
+    pub home_dir: Option<PathBuf>,
+}
+
+impl PlatformDirs {
+    /// Directories of the current user
+    pub fn current() -> Self {
+        Self {
+            data_dir: dirs::data_dir(),
+            config_dir: dirs::config_dir(),
+            home_dir: dirs::home_dir(),
+        }
+    }
+}
+
+/// Resolved locations of all wallet files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletPaths {
+    /// Directory of the transaction journal, and of keystore and address book by default
+    pub data_dir: PathBuf,
+    pub config_dir: PathBuf,
+    /// File, which keys and accounts are stored to
+    pub wallet_file: PathBuf,
+    /// Whether directories are the platform default ones rather than `--data-dir`
+    pub is_platform_default: bool,
+}
+
+impl WalletPaths {
+    /// Locations of wallet files with `data_dir` and `wallet_file` overrides
+    ///
+    /// `data_dir` takes the place of both platform directories.
+    pub fn resolve(
+        data_dir: Option<PathBuf>,
+        wallet_file: Option<PathBuf>,
+        platform: &PlatformDirs,
+    ) -> Result<Self> {
+        let (data_dir, config_dir, is_platform_default) = match data_dir {
+            Some(data_dir) => (data_dir.clone(), data_dir, false),
+            None => {
+                let data_dir = platform
+                    .data_dir
+                    .as_ref()
+                    .context("Failed to find data directory of the platform, pass --data-dir")?
+                    .join(APP_DIR_NAME);
+                let config_dir = platform
+                    .config_dir
+                    .as_ref()
+                    .map_or_else(|| data_dir.clone(), |dir| dir.join(APP_DIR_NAME));
+                (data_dir, config_dir, true)
+            }
+        };
+
+        Ok(Self {
+            wallet_file: wallet_file.unwrap_or_else(|| data_dir.join(STORAGE_FILE_NAME)),
+            data_dir,
+            config_dir,
+            is_platform_default,
+        })
+    }
+
+    /// Locations with overrides of `NSSA_WALLET_HOME_DIR` and `WALLET_FILE` env vars
+    ///
+    /// CLI sets them from `--data-dir` and `--wallet-file` before running anything.
+    pub fn from_env() -> Result<Self> {
+        Self::resolve(
+            env_path(HOME_DIR_ENV_VAR),
+            env_path(WALLET_FILE_ENV_VAR),
+            &PlatformDirs::current(),
+        )
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config_dir.join(CONFIG_FILE_NAME)
+    }
+
+    pub fn tx_log_file(&self) -> PathBuf {
+        self.data_dir.join(TX_LOG_FILE_NAME)
+    }
+
+    pub fn address_book_file(&self) -> PathBuf {
+        address_book_file(&self.wallet_file)
+    }
+
+    pub fn migration_record_file(&self) -> PathBuf {
+        self.data_dir.join(MIGRATION_RECORD_FILE_NAME)
+    }
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Options, which create files readable and writable by the owner only
+pub fn private_open_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+}
+
+/// Create `path` and its missing parents, accessible by the owner only
+pub fn create_private_dir_all(path: &Path) -> std::io::Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(path)
+}
+
+/// Make `file` readable and writable by the owner only, e.g. one created by an older wallet
+pub fn restrict_permissions(file: &File) -> std::io::Result<()> {
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    #[cfg(not(unix))]
+    let _ = file;
+    Ok(())
+}
+
+/// Replace contents of the file at `path` with `contents`, creating it if needed, so only the
+/// owner can access it
+pub fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let mut file = private_open_options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    restrict_permissions(&file)?;
+    file.write_all(contents.as_ref())
+}
+
+/// Directories, which older wallets kept their files in
+pub fn legacy_dirs(platform: &PlatformDirs) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(current_dir) = std::env::current_dir() {
+        dirs.push(current_dir);
+    }
+    if let Some(home_dir) = &platform.home_dir {
+        dirs.push(home_dir.join(".nssa").join("wallet"));
+    }
+    dirs
+}
+
+/// Legacy file and its new location
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Legacy files of one directory, which can be moved to the platform directories
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationPlan {
+    pub source_dir: PathBuf,
+    pub moves: Vec<FileMove>,
+    /// Legacy files, which are kept in place, as their new locations are taken already
+    pub conflicts: Vec<FileMove>,
+}
+
+impl MigrationPlan {
+    /// Question, which the user confirms the migration with
+    pub fn question(&self) -> String {
+        let mut question = format!(
+            "Found wallet files of an older version in {}:\n",
+            self.source_dir.display()
+        );
+        for file_move in &self.moves {
+            question.push_str(&format!(
+                "  {} -> {}\n",
+                file_move.from.display(),
+                file_move.to.display()
+            ));
+        }
+        for conflict in &self.conflicts {
+            question.push_str(&format!(
+                "  {} is kept, {} exists already\n",
+                conflict.from.display(),
+                conflict.to.display()
+            ));
+        }
+        question.push_str("Move them?");
+        question
+    }
+}
+
+/// Outcome of [`run_migration`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationOutcome {
+    /// Migration was offered already, or there are no legacy files
+    NothingToMigrate,
+    Migrated(MigrationPlan),
+    Declined(MigrationPlan),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MigrationRecord {
+    source_dir: PathBuf,
+    accepted: bool,
+    moved: Vec<FileMove>,
+}
+
+/// Legacy files of the first of `legacy_dirs` having any, if migration wasn't offered yet
+///
+/// Only wallets using platform directories are migrated, an explicit `--data-dir` is used as is.
+pub fn plan_migration(paths: &WalletPaths, legacy_dirs: &[PathBuf]) -> Option<MigrationPlan> {
+    if !paths.is_platform_default || paths.migration_record_file().exists() {
+        return None;
+    }
+
+    let targets = [
+        (STORAGE_FILE_NAME, paths.data_dir.join(STORAGE_FILE_NAME)),
+        (TX_LOG_FILE_NAME, paths.tx_log_file()),
+        (
+            ADDRESS_BOOK_FILE_NAME,
+            paths.data_dir.join(ADDRESS_BOOK_FILE_NAME),
+        ),
+        (CONFIG_FILE_NAME, paths.config_file()),
+    ];
+
+    legacy_dirs
+        .iter()
+        .filter(|dir| **dir != paths.data_dir && **dir != paths.config_dir)
+        .find_map(|dir| {
+            let (conflicts, moves) = targets
+                .iter()
+                .map(|(name, to)| FileMove {
+                    from: dir.join(name),
+                    to: to.clone(),
+                })
+                .filter(|file_move| file_move.from.is_file())
+                .partition::<Vec<_>, _>(|file_move| file_move.to.exists());
+
+            (!moves.is_empty()).then(|| MigrationPlan {
+                source_dir: dir.clone(),
+                moves,
+                conflicts,
+            })
+        })
+}
+
+/// Offer to move legacy files of `legacy_dirs` with `confirm`, once
+///
+/// Both accepted and declined migrations are recorded, so the offer isn't repeated.
+pub fn run_migration(
+    paths: &WalletPaths,
+    legacy_dirs: &[PathBuf],
+    confirm: impl FnOnce(&MigrationPlan) -> Result<bool>,
+) -> Result<MigrationOutcome> {
+    let Some(plan) = plan_migration(paths, legacy_dirs) else {
+        return Ok(MigrationOutcome::NothingToMigrate);
+    };
+
+    let accepted = confirm(&plan)?;
+    if accepted {
+        for file_move in &plan.moves {
+            move_file(&file_move.from, &file_move.to).with_context(|| {
+                format!(
+                    "Failed to move {} to {}",
+                    file_move.from.display(),
+                    file_move.to.display()
+                )
+            })?;
+        }
+    }
+
+    let record = MigrationRecord {
+        source_dir: plan.source_dir.clone(),
+        accepted,
+        moved: if accepted { plan.moves.clone() } else { vec![] },
+    };
+    create_private_dir_all(&paths.data_dir)?;
+    write_private(
+        &paths.migration_record_file(),
+        serde_json::to_vec_pretty(&record)?,
+    )?;
+
+    Ok(if accepted {
+        MigrationOutcome::Migrated(plan)
+    } else {
+        MigrationOutcome::Declined(plan)
+    })
+}
+
+/// Move `from` to `to`, copying it if they are on different filesystems
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        create_private_dir_all(parent)?;
+    }
+
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    restrict_permissions(&File::open(to)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn platform(root: &Path) -> PlatformDirs {
+        PlatformDirs {
+            data_dir: Some(root.join("data")),
+            config_dir: Some(root.join("config")),
+            home_dir: Some(root.join("home")),
+        }
+    }
+
+    fn default_paths(root: &Path) -> WalletPaths {
+        WalletPaths::resolve(None, None, &platform(root)).unwrap()
+    }
+
+    fn create_file(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_overrides_take_precedence_over_platform_dirs() {
+        let root = Path::new("/root_dir");
+
+        let paths = default_paths(root);
+        assert!(paths.is_platform_default);
+        assert_eq!(paths.data_dir, root.join("data").join(APP_DIR_NAME));
+        assert_eq!(paths.wallet_file, paths.data_dir.join(STORAGE_FILE_NAME));
+        assert_eq!(
+            paths.config_file(),
+            root.join("config")
+                .join(APP_DIR_NAME)
+                .join(CONFIG_FILE_NAME)
+        );
+
+        // Data dir override keeps all files together
+        let data_dir = PathBuf::from("/custom");
+        let paths = WalletPaths::resolve(Some(data_dir.clone()), None, &platform(root)).unwrap();
+        assert!(!paths.is_platform_default);
+        assert_eq!(paths.wallet_file, data_dir.join(STORAGE_FILE_NAME));
+        assert_eq!(paths.config_file(), data_dir.join(CONFIG_FILE_NAME));
+        assert_eq!(paths.tx_log_file(), data_dir.join(TX_LOG_FILE_NAME));
+
+        // Wallet file override moves the keystore and its address book only
+        let wallet_file = PathBuf::from("/keys/mine.json");
+        let paths = WalletPaths::resolve(
+            Some(data_dir.clone()),
+            Some(wallet_file.clone()),
+            &platform(root),
+        )
+        .unwrap();
+        assert_eq!(paths.wallet_file, wallet_file);
+        assert_eq!(
+            paths.address_book_file(),
+            Path::new("/keys").join(ADDRESS_BOOK_FILE_NAME)
+        );
+        assert_eq!(paths.tx_log_file(), data_dir.join(TX_LOG_FILE_NAME));
+
+        // Without platform dirs the data dir must be passed
+        assert!(WalletPaths::resolve(None, None, &PlatformDirs::default()).is_err());
+        assert!(WalletPaths::resolve(Some(data_dir), None, &PlatformDirs::default()).is_ok());
+    }
+
+    #[test]
+    fn test_migration_is_offered_once_and_moves_files() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = default_paths(root.path());
+        let legacy_dir = root.path().join("cwd");
+        create_file(&legacy_dir.join(STORAGE_FILE_NAME), "keys");
+        create_file(&legacy_dir.join(CONFIG_FILE_NAME), "config");
+
+        let outcome = run_migration(&paths, std::slice::from_ref(&legacy_dir), |plan| {
+            assert_eq!(plan.source_dir, legacy_dir);
+            assert_eq!(plan.moves.len(), 2);
+            Ok(true)
+        })
+        .unwrap();
+
+        assert!(matches!(outcome, MigrationOutcome::Migrated(_)));
+        assert_eq!(std::fs::read_to_string(&paths.wallet_file).unwrap(), "keys");
+        assert_eq!(
+            std::fs::read_to_string(paths.config_file()).unwrap(),
+            "config"
+        );
+        assert!(!legacy_dir.join(STORAGE_FILE_NAME).exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            let mode = std::fs::metadata(&paths.wallet_file)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Files of an older wallet showing up again don't re-prompt
+        create_file(&legacy_dir.join(STORAGE_FILE_NAME), "other keys");
+        let outcome = run_migration(&paths, &[legacy_dir], |_| panic!("Prompted again")).unwrap();
+        assert_eq!(outcome, MigrationOutcome::NothingToMigrate);
+    }
+
+    #[test]
+    fn test_declined_migration_is_recorded_and_keeps_files() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = default_paths(root.path());
+        let legacy_dir = root.path().join("home").join(".nssa").join("wallet");
+        create_file(&legacy_dir.join(TX_LOG_FILE_NAME), "{}\n");
+
+        let outcome =
+            run_migration(&paths, &legacy_dirs(&platform(root.path())), |_| Ok(false)).unwrap();
+
+        assert!(matches!(outcome, MigrationOutcome::Declined(_)));
+        assert!(legacy_dir.join(TX_LOG_FILE_NAME).exists());
+        assert!(!paths.tx_log_file().exists());
+        assert!(paths.migration_record_file().exists());
+        assert_eq!(plan_migration(&paths, &[legacy_dir]), None);
+    }
+
+    #[test]
+    fn test_migration_keeps_files_with_taken_locations() {
+        let root = tempfile::tempdir().unwrap();
+        let paths = default_paths(root.path());
+        let legacy_dir = root.path().join("cwd");
+        create_file(&legacy_dir.join(STORAGE_FILE_NAME), "old keys");
+        create_file(&legacy_dir.join(ADDRESS_BOOK_FILE_NAME), "[]");
+        create_file(&paths.wallet_file, "new keys");
+
+        let plan = plan_migration(&paths, std::slice::from_ref(&legacy_dir)).unwrap();
+        assert_eq!(
+            plan.moves,
+            vec![FileMove {
+                from: legacy_dir.join(ADDRESS_BOOK_FILE_NAME),
+                to: paths.address_book_file(),
+            }]
+        );
+        assert_eq!(plan.conflicts.len(), 1);
+
+        run_migration(&paths, std::slice::from_ref(&legacy_dir), |_| Ok(true)).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&paths.wallet_file).unwrap(),
+            "new keys"
+        );
+        assert!(legacy_dir.join(STORAGE_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn test_migration_isnt_offered_for_explicit_data_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let legacy_dir = root.path().join("cwd");
+        create_file(&legacy_dir.join(STORAGE_FILE_NAME), "keys");
+
+        let paths = WalletPaths::resolve(
+            Some(root.path().join("custom")),
+            None,
+            &platform(root.path()),
+        )
+        .unwrap();
+        assert_eq!(
+            plan_migration(&paths, std::slice::from_ref(&legacy_dir)),
+            None
+        );
+
+        // Legacy dir being the data dir itself isn't migrated either
+        let mut paths = default_paths(root.path());
+        paths.data_dir = legacy_dir.clone();
+        assert_eq!(plan_migration(&paths, &[legacy_dir]), None);
+    }
+}
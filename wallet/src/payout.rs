@@ -0,0 +1,566 @@
+//! Batch payouts from a CSV file.
+//!
+//! Every row of the file is `address,amount[,memo]`, where `address` is a public account id with
+//! `Public/` prefix. The memo isn't sent anywhere, it's only copied to the results, and may
+//! contain commas. Empty lines and a header line starting with `address` are skipped.
+//!
+//! There are no transfers with several recipients, so a payout is a sequence of public transfers
+//! from one account with consecutive nonces.
+
+use std::{fmt::Display, time::Duration};
+
+use anyhow::Result;
+use common::rpc_types::TransactionStatus;
+use nssa::{AccountId, Amount};
+use nssa_core::account::Nonce;
+use serde::Serialize;
+
+use crate::{
+    WalletCore,
+    api::TxHash,
+    helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
+    program_facades::native_token_transfer::NativeTokenTransfer,
+};
+
+/// Row of a payout file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayoutRow {
+    /// Line number in the file, starting from 1
+    pub line: usize,
+    pub to: AccountId,
+    pub amount: Amount,
+    pub memo: Option<String>,
+}
+
+/// Problem with a row of a payout file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayoutRowError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for PayoutRowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parses payout file `contents`, reporting problems of all rows at once
+pub fn parse_payout_csv(contents: &str) -> Result<Vec<PayoutRow>, Vec<PayoutRowError>> {
+    let mut rows = vec![];
+    let mut errors = vec![];
+    let mut header_allowed = true;
+
+    for (idx, line) in contents.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if std::mem::take(&mut header_allowed) && line.to_ascii_lowercase().starts_with("address") {
+            continue;
+        }
+
+        match parse_row(line_number, line) {
+            Ok(row) => rows.push(row),
+            Err(message) => errors.push(PayoutRowError {
+                line: line_number,
+                message,
+            }),
+        }
+    }
+
+    if rows.is_empty() && errors.is_empty() {
+        errors.push(PayoutRowError {
+            line: 0,
+            message: "File has no payouts".to_string(),
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors)
+    }
+}
+
+fn parse_row(line: usize, row: &str) -> Result<PayoutRow, String> {
+    let mut fields = row.splitn(3, ',');
+    let address = fields.next().unwrap_or_default().trim();
+    let Some(amount) = fields.next().map(str::trim) else {
+        return Err("Expected `address,amount[,memo]`".to_string());
+    };
+    let memo = fields
+        .next()
+        .map(str::trim)
+        .filter(|memo| !memo.is_empty())
+        .map(str::to_string);
+
+    let to = match parse_addr_with_privacy_prefix(address) {
+        Ok((account_id, AccountPrivacyKind::Public)) => account_id
+            .parse()
+            .map_err(|err| format!("Invalid address `{address}`: {err}"))?,
+        Ok((_, AccountPrivacyKind::Private)) => {
+            return Err(format!("Only public accounts can be paid, got `{address}`"));
+        }
+        Err(err) => return Err(format!("Invalid address `{address}`: {err}")),
+    };
+    let amount = match amount.parse::<Amount>() {
+        Ok(amount) if amount.is_zero() => return Err("Amount must be positive".to_string()),
+        Ok(amount) => amount,
+        Err(_) => return Err(format!("Invalid amount `{amount}`")),
+    };
+
+    Ok(PayoutRow {
+        line,
+        to,
+        amount,
+        memo,
+    })
+}
+
+/// Validated payout, which is ready to be sent
+#[derive(Debug, Clone)]
+pub struct PayoutPlan {
+    pub from: AccountId,
+    pub rows: Vec<PayoutRow>,
+    /// Nonce of the transfer of the first row
+    pub first_nonce: Nonce,
+    /// Priority fee of every transfer
+    pub priority_fee: Amount,
+    /// Sum of amounts of all rows
+    pub total: Amount,
+}
+
+impl Display for PayoutPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Paying {} in total from Public/{} in {} transfers",
+            self.total,
+            self.from,
+            self.rows.len()
+        )?;
+        if !self.priority_fee.is_zero() {
+            write!(f, ", with priority fee {} each", self.priority_fee)?;
+        }
+        Ok(())
+    }
+}
+
+/// Final state of a payout row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PayoutStatus {
+    Included,
+    /// Accepted by the sequencer, but not included while the payout waited
+    Pending,
+    /// Rejected by the sequencer, either on submission or later
+    Failed,
+    /// Not sent, because the transfer of an earlier row failed
+    NotSubmitted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayoutResult {
+    pub row: PayoutRow,
+    pub tx_hash: Option<TxHash>,
+    pub status: PayoutStatus,
+    pub block_id: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Results as CSV with a header, one line per row of the payout
+pub fn payout_results_csv(results: &[PayoutResult]) -> String {
+    let mut csv = "line,address,amount,memo,tx_hash,status,block_id,error\n".to_string();
+    for result in results {
+        let fields = [
+            result.row.line.to_string(),
+            format!("Public/{}", result.row.to),
+            result.row.amount.to_string(),
+            csv_field(result.row.memo.as_deref().unwrap_or_default()),
+            result.tx_hash.clone().unwrap_or_default(),
+            format!("{:?}", result.status),
+            result
+                .block_id
+                .map(|block_id| block_id.to_string())
+                .unwrap_or_default(),
+            csv_field(result.error.as_deref().unwrap_or_default()),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Final state of a submitted transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TransferOutcome {
+    Included { block_id: u64 },
+    Pending,
+    Failed { reason: String },
+}
+
+/// Operations a payout needs from the wallet
+pub(crate) trait PayoutClient {
+    /// Sends transfer of `amount` to `to` with `nonce` of the payer
+    async fn submit_transfer(&self, to: AccountId, amount: Amount, nonce: Nonce) -> Result<TxHash>;
+
+    /// Waits for the transfer to settle, giving up on waiting at some point
+    async fn transfer_outcome(&self, hash: &str) -> TransferOutcome;
+}
+
+/// Sends transfers of `rows` in order with nonces starting from `first_nonce`, then waits for
+/// the sent ones
+///
+/// Transfers after a failed one are not sent, as their nonces would never be reached.
+pub(crate) async fn run_payout(
+    client: &impl PayoutClient,
+    rows: Vec<PayoutRow>,
+    first_nonce: Nonce,
+) -> Vec<PayoutResult> {
+    let mut results = Vec::with_capacity(rows.len());
+    let mut failed = false;
+
+    for (row, nonce) in rows.into_iter().zip(first_nonce..) {
+        let mut result = PayoutResult {
+            row,
+            tx_hash: None,
+            status: PayoutStatus::NotSubmitted,
+            block_id: None,
+            error: None,
+        };
+        if !failed {
+            match client
+                .submit_transfer(result.row.to, result.row.amount, nonce)
+                .await
+            {
+                Ok(hash) => {
+                    result.tx_hash = Some(hash);
+                    result.status = PayoutStatus::Pending;
+                }
+                Err(err) => {
+                    failed = true;
+                    result.status = PayoutStatus::Failed;
+                    result.error = Some(format!("{err:#}"));
+                }
+            }
+        }
+        results.push(result);
+    }
+
+    for result in &mut results {
+        let Some(hash) = &result.tx_hash else {
+            continue;
+        };
+        match client.transfer_outcome(hash).await {
+            TransferOutcome::Included { block_id } => {
+                result.status = PayoutStatus::Included;
+                result.block_id = Some(block_id);
+            }
+            TransferOutcome::Pending => result.status = PayoutStatus::Pending,
+            TransferOutcome::Failed { reason } => {
+                result.status = PayoutStatus::Failed;
+                result.error = Some(reason);
+            }
+        }
+    }
+
+    results
+}
+
+struct WalletPayoutClient<'w> {
+    wallet_core: &'w WalletCore,
+    from: AccountId,
+    priority_fee: Amount,
+    /// Waiting for inclusion of all transfers ends at this point
+    deadline: tokio::time::Instant,
+}
+
+impl PayoutClient for WalletPayoutClient<'_> {
+    async fn submit_transfer(&self, to: AccountId, amount: Amount, nonce: Nonce) -> Result<TxHash> {
+        let response = NativeTokenTransfer(self.wallet_core)
+            .send_public_transfer_with_nonce(self.from, to, amount, nonce, self.priority_fee)
+            .await?;
+        Ok(response.tx_hash)
+    }
+
+    async fn transfer_outcome(&self, hash: &str) -> TransferOutcome {
+        let timeout = self
+            .deadline
+            .saturating_duration_since(tokio::time::Instant::now());
+        match self.wallet_core.wait_for_inclusion(hash, timeout).await {
+            Ok(block_id) => TransferOutcome::Included { block_id },
+            Err(err) => {
+                let status = self
+                    .wallet_core
+                    .sequencer_client
+                    .get_transaction_status(hash.to_string())
+                    .await
+                    .map(|response| response.status);
+                match status {
+                    Ok(TransactionStatus::Pending { .. }) => TransferOutcome::Pending,
+                    _ => TransferOutcome::Failed {
+                        reason: format!("{err:#}"),
+                    },
+                }
+            }
+        }
+    }
+}
+
+impl WalletCore {
+    /// Checks that the wallet can pay all `rows` from `from` and resolves nonce and fee of the
+    /// transfers
+    pub async fn prepare_payout(
+        &self,
+        from: AccountId,
+        rows: Vec<PayoutRow>,
+    ) -> Result<PayoutPlan> {
+        anyhow::ensure!(
+            self.get_account_public_signing_key(&from).is_some(),
+            "Wallet doesn't own public account {from}"
+        );
+
+        let total = Amount::checked_sum(rows.iter().map(|row| row.amount))
+            .ok_or_else(|| anyhow::anyhow!("Total amount of the payout overflows"))?;
+        let priority_fee = self.resolve_priority_fee().await?;
+        let fees = priority_fee.saturating_mul(rows.len() as u128);
+        let balance = self.get_spendable_balance(from).await?;
+        anyhow::ensure!(
+            total.checked_add(fees).is_some_and(|cost| cost <= balance),
+            "Spendable balance {balance} of {from} isn't enough to pay {total} and fees {fees}"
+        );
+
+        let first_nonce = *self
+            .next_accounts_nonces(vec![from])
+            .await?
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Sequencer returned no nonce for {from}"))?;
+
+        Ok(PayoutPlan {
+            from,
+            rows,
+            first_nonce,
+            priority_fee,
+            total,
+        })
+    }
+
+    /// Sends the payout and waits up to `timeout` for inclusion of its transfers
+    ///
+    /// Rows are reported in the order of the plan, including the ones, which weren't sent.
+    pub async fn send_payout(&self, plan: PayoutPlan, timeout: Duration) -> Vec<PayoutResult> {
+        let client = WalletPayoutClient {
+            wallet_core: self,
+            from: plan.from,
+            priority_fee: plan.priority_fee,
+            deadline: tokio::time::Instant::now() + timeout,
+        };
+        run_payout(&client, plan.rows, plan.first_nonce).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use super::*;
+
+    fn account(byte: u8) -> AccountId {
+        AccountId::new([byte; 32])
+    }
+
+    fn row(line: usize, byte: u8, amount: u128) -> PayoutRow {
+        PayoutRow {
+            line,
+            to: account(byte),
+            amount: Amount(amount),
+            memo: None,
+        }
+    }
+
+    /// Client, which fails submission of chosen nonces and settles transfers as told
+    #[derive(Default)]
+    struct MockClient {
+        failing_nonces: Vec<Nonce>,
+        outcomes: HashMap<TxHash, TransferOutcome>,
+        submitted: RefCell<Vec<(AccountId, Amount, Nonce)>>,
+    }
+
+    impl PayoutClient for MockClient {
+        async fn submit_transfer(
+            &self,
+            to: AccountId,
+            amount: Amount,
+            nonce: Nonce,
+        ) -> Result<TxHash> {
+            anyhow::ensure!(
+                !self.failing_nonces.contains(&nonce),
+                "Mempool is congested"
+            );
+            self.submitted.borrow_mut().push((to, amount, nonce));
+            Ok(format!("tx{nonce}"))
+        }
+
+        async fn transfer_outcome(&self, hash: &str) -> TransferOutcome {
+            self.outcomes
+                .get(hash)
+                .cloned()
+                .unwrap_or(TransferOutcome::Included { block_id: 1 })
+        }
+    }
+
+    #[test]
+    fn test_parse_payout_csv() {
+        let contents = "address,amount,memo\n\
+             Public/11111111111111111111111111111111,10\n\
+             \n\
+             Public/11111111111111111111111111111112, 20 ,Week 3, bugfixes\n";
+
+        let rows = parse_payout_csv(contents).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            (rows[0].line, rows[0].amount, rows[0].memo.clone()),
+            (2, Amount(10), None)
+        );
+        assert_eq!(
+            (rows[1].line, rows[1].amount, rows[1].memo.as_deref()),
+            (4, Amount(20), Some("Week 3, bugfixes"))
+        );
+        assert_eq!(rows[0].to, AccountId::new([0; 32]));
+    }
+
+    #[test]
+    fn test_parse_payout_csv_reports_all_errors() {
+        let contents = "Public/11111111111111111111111111111111,10\n\
+             Private/11111111111111111111111111111111,10\n\
+             Public/notbase58!,10\n\
+             Public/11111111111111111111111111111111,0\n\
+             Public/11111111111111111111111111111111,ten\n\
+             Public/11111111111111111111111111111111\n\
+             11111111111111111111111111111111,10\n";
+
+        let errors = parse_payout_csv(contents).unwrap_err();
+
+        assert_eq!(
+            errors.iter().map(|error| error.line).collect::<Vec<_>>(),
+            [2, 3, 4, 5, 6, 7]
+        );
+        assert!(errors[0].message.contains("Only public accounts"));
+        assert_eq!(errors[2].to_string(), "line 4: Amount must be positive");
+        assert_eq!(errors[3].message, "Invalid amount `ten`");
+    }
+
+    #[test]
+    fn test_parse_empty_payout_csv_fails() {
+        assert!(parse_payout_csv("address,amount\n\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_payout_uses_consecutive_nonces() {
+        let client = MockClient {
+            outcomes: HashMap::from([("tx6".to_string(), TransferOutcome::Pending)]),
+            ..Default::default()
+        };
+        let rows = vec![row(1, 1, 10), row(2, 2, 20), row(3, 1, 30)];
+
+        let results = run_payout(&client, rows, 5).await;
+
+        assert_eq!(
+            *client.submitted.borrow(),
+            [
+                (account(1), Amount(10), 5),
+                (account(2), Amount(20), 6),
+                (account(1), Amount(30), 7)
+            ]
+        );
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| (result.tx_hash.as_deref(), result.status, result.block_id))
+                .collect::<Vec<_>>(),
+            [
+                (Some("tx5"), PayoutStatus::Included, Some(1)),
+                (Some("tx6"), PayoutStatus::Pending, None),
+                (Some("tx7"), PayoutStatus::Included, Some(1)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_payout_reports_earlier_rows_after_failed_submission() {
+        let client = MockClient {
+            // Row 17 fails admission
+            failing_nonces: vec![16],
+            outcomes: HashMap::from([(
+                "tx3".to_string(),
+                TransferOutcome::Failed {
+                    reason: "Dropped".to_string(),
+                },
+            )]),
+            ..Default::default()
+        };
+        let rows = (1..=20).map(|line| row(line, 1, 1)).collect();
+
+        let results = run_payout(&client, rows, 0).await;
+
+        assert_eq!(client.submitted.borrow().len(), 16);
+        assert_eq!(results.len(), 20);
+        for (idx, result) in results.iter().enumerate() {
+            let expected_status = match idx {
+                3 => PayoutStatus::Failed,
+                0..16 => PayoutStatus::Included,
+                16 => PayoutStatus::Failed,
+                _ => PayoutStatus::NotSubmitted,
+            };
+            assert_eq!(result.status, expected_status, "Row {}", result.row.line);
+            assert_eq!(
+                result.tx_hash.is_some(),
+                idx < 16,
+                "Row {}",
+                result.row.line
+            );
+        }
+        assert_eq!(results[3].error.as_deref(), Some("Dropped"));
+        assert_eq!(results[16].error.as_deref(), Some("Mempool is congested"));
+    }
+
+    #[test]
+    fn test_payout_results_csv() {
+        let results = vec![
+            PayoutResult {
+                row: PayoutRow {
+                    memo: Some("Week 3, \"bugfixes\"".to_string()),
+                    ..row(2, 0, 10)
+                },
+                tx_hash: Some("ab".to_string()),
+                status: PayoutStatus::Included,
+                block_id: Some(4),
+                error: None,
+            },
+            PayoutResult {
+                row: row(3, 0, 20),
+                tx_hash: None,
+                status: PayoutStatus::Failed,
+                block_id: None,
+                error: Some("Rejected, try later".to_string()),
+            },
+        ];
+
+        assert_eq!(
+            payout_results_csv(&results),
+            "line,address,amount,memo,tx_hash,status,block_id,error\n\
+             2,Public/11111111111111111111111111111111,10,\"Week 3, \"\"bugfixes\"\"\",ab,Included,4,\n\
+             3,Public/11111111111111111111111111111111,20,,,Failed,,\"Rejected, try later\"\n"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Balances of public accounts together with the effect of transactions waiting for inclusion.
+//!
+//! A submitted transfer changes the confirmed balance only once its block lands, so unconfirmed
+//! submissions of the transaction journal are counted as pending outgoing. Transfers paying the
+//! account are counted as pending incoming if the sequencer serves them, see
+//! [`SequencerFeature::PendingTransfers`].
+
+use anyhow::Result;
+use common::rpc_types::{SequencerFeature, TransactionStatus};
+use log::warn;
+use nssa::{AccountId, Amount};
+
+use crate::{
+    WalletCore,
+    tx_log::{TxLogEntry, TxStatus},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PendingBalance {
+    /// Balance at the last applied block
+    pub confirmed: Amount,
+    /// Sum of the wallet's submissions from the account, which aren't included yet
+    pub pending_outgoing: Amount,
+    /// Sum of transfers to the account waiting for inclusion, `None` if the sequencer doesn't
+    /// serve them
+    pub pending_incoming: Option<Amount>,
+}
+
+impl PendingBalance {
+    /// Balance, which isn't spent by pending submissions yet. Pending incoming transfers may
+    /// still be dropped, so they aren't spendable.
+    pub fn spendable(&self) -> Amount {
+        self.confirmed.saturating_sub(self.pending_outgoing)
+    }
+}
+
+/// Whether the journal counts `entry` as a possibly pending submission from `account_id`
+fn is_unconfirmed_from(entry: &TxLogEntry, account_id: &str) -> bool {
+    !entry.status.is_final() && entry.sender.as_deref() == Some(account_id)
+}
+
+impl WalletCore {
+    /// Balance of public account `account_id` with its pending outgoing and incoming transfers
+    pub async fn get_pending_balance(&self, account_id: AccountId) -> Result<PendingBalance> {
+        Ok(PendingBalance {
+            confirmed: self.get_account_balance(account_id).await?,
+            pending_outgoing: self.pending_outgoing(account_id).await?,
+            pending_incoming: self.pending_incoming(account_id).await?,
+        })
+    }
+
+    /// Balance of public account `account_id`, which isn't spent by pending submissions, see
+    /// [`PendingBalance::spendable`]
+    pub async fn get_spendable_balance(&self, account_id: AccountId) -> Result<Amount> {
+        let confirmed = self.get_account_balance(account_id).await?;
+        let pending_outgoing = self.pending_outgoing(account_id).await?;
+
+        Ok(PendingBalance {
+            confirmed,
+            pending_outgoing,
+            pending_incoming: None,
+        }
+        .spendable())
+    }
+
+    /// Sum of amounts of journaled submissions from `account_id`, which the sequencer still has
+    /// pending
+    ///
+    /// Submissions, which the sequencer included or dropped, are reconciled in the journal, so
+    /// they aren't counted again. The ones unknown to the sequencer, e.g. never sent ones, aren't
+    /// counted either, so stale entries don't depress the spendable balance.
+    pub async fn pending_outgoing(&self, account_id: AccountId) -> Result<Amount> {
+        let account_id = account_id.to_string();
+        let entries = match self.tx_log.entries() {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Failed to read transaction log, ignoring pending submissions: {err:#}");
+                return Ok(Amount::ZERO);
+            }
+        };
+
+        let mut pending_outgoing = Amount::ZERO;
+        for entry in entries
+            .iter()
+            .filter(|entry| is_unconfirmed_from(entry, &account_id))
+        {
+            let status = self
+                .sequencer_client
+                .get_transaction_status(entry.hash.clone())
+                .await?
+                .status;
+            match status {
+                TransactionStatus::Pending { .. } => {
+                    pending_outgoing =
+                        pending_outgoing.saturating_add(entry.amount.unwrap_or_default());
+                }
+                TransactionStatus::Included { block_id, .. } => {
+                    self.tx_log
+                        .update_status(&entry.hash, TxStatus::Included, Some(block_id))?;
+                }
+                TransactionStatus::Dropped { .. } => {
+                    self.tx_log
+                        .update_status(&entry.hash, TxStatus::Failed, None)?;
+                }
+                TransactionStatus::Unknown => {}
+            }
+        }
+
+        Ok(pending_outgoing)
+    }
+
+    /// Sum of amounts of transfers to `account_id` waiting for inclusion, if the sequencer serves
+    /// them
+    pub async fn pending_incoming(&self, account_id: AccountId) -> Result<Option<Amount>> {
+        let serves_pending_transfers = self
+            .get_sequencer_info()
+            .await?
+            .features
+            .contains(&SequencerFeature::PendingTransfers);
+        if !serves_pending_transfers {
+            return Ok(None);
+        }
+
+        let account_id = account_id.to_string();
+        let transfers = self
+            .sequencer_client
+            .get_pending_transfers(account_id.clone())
+            .await?
+            .transfers;
+
+        Ok(Some(
+            transfers
+                .iter()
+                .filter(|transfer| transfer.recipient == account_id)
+                .fold(Amount::ZERO, |total, transfer| {
+                    total.saturating_add(transfer.amount)
+                }),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common::mock_chain::MockChain;
+
+    use super::*;
+    use crate::{
+        api::TransferOptions, program_facades::native_token_transfer::NativeTokenTransfer,
+        test_utils,
+    };
+
+    #[test]
+    fn test_spendable_doesnt_count_pending_incoming() {
+        let balance = PendingBalance {
+            confirmed: Amount(100),
+            pending_outgoing: Amount(30),
+            pending_incoming: Some(Amount(50)),
+        };
+        assert_eq!(balance.spendable(), Amount(70));
+
+        // Submissions may outspend the confirmed balance, e.g. ones sent with explicit nonces
+        let overspent = PendingBalance {
+            confirmed: Amount(10),
+            pending_outgoing: Amount(30),
+            pending_incoming: None,
+        };
+        assert_eq!(overspent.spendable(), Amount(0));
+    }
+
+    #[tokio::test]
+    async fn test_pending_balance_between_submission_and_inclusion() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000).with_pending_transfers());
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        wallet
+            .transfer(from, to, Amount(300), TransferOptions::default())
+            .await
+            .unwrap();
+
+        // Submitted, but not included yet
+        assert_eq!(
+            wallet.get_pending_balance(from).await.unwrap(),
+            PendingBalance {
+                confirmed: Amount(1000),
+                pending_outgoing: Amount(300),
+                pending_incoming: Some(Amount(0)),
+            }
+        );
+        assert_eq!(
+            wallet.get_pending_balance(to).await.unwrap(),
+            PendingBalance {
+                confirmed: Amount(1000),
+                pending_outgoing: Amount(0),
+                pending_incoming: Some(Amount(300)),
+            }
+        );
+        assert_eq!(
+            wallet.get_spendable_balance(from).await.unwrap(),
+            Amount(700)
+        );
+
+        // Double-sending beyond the spendable balance is refused before sending
+        let double_send = wallet
+            .transfer(from, to, Amount(800), TransferOptions::default())
+            .await;
+        assert!(matches!(
+            double_send,
+            Err(common::error::ExecutionFailureKind::InsufficientFundsError)
+        ));
+        assert_eq!(chain.mempool_len(), 1);
+
+        chain.produce_block();
+
+        assert_eq!(
+            wallet.get_pending_balance(from).await.unwrap(),
+            PendingBalance {
+                confirmed: Amount(700),
+                pending_outgoing: Amount(0),
+                pending_incoming: Some(Amount(0)),
+            }
+        );
+        let entries = wallet.tx_log.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, TxStatus::Included);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_submission_doesnt_depress_spendable_balance() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(test_utils::funded_chain(1000));
+        let wallet = test_utils::mock_wallet(chain.clone(), home.path());
+        let (from, to) = test_utils::preconfigured_public_accounts();
+
+        // Nonce of the future can't be applied, so the submission is dropped on inclusion
+        NativeTokenTransfer(&wallet)
+            .send_public_transfer_with_nonce(from, to, Amount(600), 5, Amount::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(
+            wallet.get_spendable_balance(from).await.unwrap(),
+            Amount(400)
+        );
+
+        chain.produce_block();
+
+        assert_eq!(
+            wallet.get_spendable_balance(from).await.unwrap(),
+            Amount(1000)
+        );
+        let entries = wallet.tx_log.entries().unwrap();
+        assert_eq!(entries[0].status, TxStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_pending_incoming_is_unknown_without_sequencer_support() {
+        let home = tempfile::tempdir().unwrap();
+        let chain = Arc::new(MockChain::new(&[]));
+        let wallet = test_utils::mock_wallet(chain, home.path());
+        let (from, _) = test_utils::preconfigured_public_accounts();
+
+        assert_eq!(wallet.pending_incoming(from).await.unwrap(), None);
+    }
+}
@@ -1,7 +1,11 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use common::{block::HashableBlockData, sequencer_client::SequencerClient};
+use common::{
+    block::HashableBlockData,
+    rpc_types::{BlockDigest, GetBlockRangeDataResponse},
+    sequencer_api::SequencerApi,
+};
 use log::{info, warn};
 
 use crate::config::WalletConfig;
@@ -14,11 +18,11 @@ pub struct TxPoller {
     // TODO: This should be Duration
     polling_delay_millis: u64,
     block_poll_max_amount: u64,
-    client: Arc<SequencerClient>,
+    client: Arc<dyn SequencerApi>,
 }
 
 impl TxPoller {
-    pub fn new(config: WalletConfig, client: Arc<SequencerClient>) -> Self {
+    pub fn new(config: WalletConfig, client: Arc<dyn SequencerApi>) -> Self {
         Self {
             polling_delay_millis: config.seq_poll_timeout_millis,
             polling_max_blocks_to_query: config.seq_tx_poll_max_blocks,
@@ -31,7 +35,7 @@ impl TxPoller {
     pub async fn poll_tx(&self, tx_hash: String) -> Result<String> {
         let max_blocks_to_query = self.polling_max_blocks_to_query;
 
-        info!("Starting poll for transaction {tx_hash:#?}");
+        info!("Starting poll for transaction {tx_hash}");
         for poll_id in 1..max_blocks_to_query {
             info!("Poll {poll_id}");
 
@@ -43,7 +47,7 @@ impl TxPoller {
                     .get_transaction_by_hash(tx_hash.clone())
                     .await
                     .inspect_err(|err| {
-                        warn!("Failed to get transaction by hash {tx_hash:#?} with error: {err:#?}")
+                        warn!("Failed to get transaction by hash {tx_hash} with error: {err:#}")
                     });
 
                 if let Ok(tx_obj) = tx_obj {
@@ -67,9 +71,12 @@ impl TxPoller {
         anyhow::bail!("Transaction not found in preconfigured amount of blocks");
     }
 
+    /// Streams blocks of `range`, checking each one against the digest served with it and the
+    /// last one also against `advertised_last`, e.g. the digest of the tip
     pub fn poll_block_range(
         &self,
         range: std::ops::RangeInclusive<u64>,
+        advertised_last: Option<BlockDigest>,
     ) -> impl futures::Stream<Item = Result<HashableBlockData>> {
         async_stream::stream! {
             let mut chunk_start = *range.start();
@@ -77,9 +84,14 @@ impl TxPoller {
             loop {
                 let chunk_end = std::cmp::min(chunk_start + self.block_poll_max_amount - 1, *range.end());
 
-                let blocks = self.client.get_block_range(chunk_start..=chunk_end).await?.blocks;
+                let blocks = self
+                    .fetch_checked_blocks(
+                        chunk_start..=chunk_end,
+                        *range.end(),
+                        advertised_last.as_ref(),
+                    )
+                    .await?;
                 for block in blocks {
-                    let block = borsh::from_slice::<HashableBlockData>(&block)?;
                     yield Ok(block);
                 }
 
@@ -90,4 +102,56 @@ impl TxPoller {
             }
         }
     }
+
+    /// Blocks of `range` checked as [`Self::poll_block_range`] does, with `last_block_id` being
+    /// the last one of the polled range
+    ///
+    /// Blocks may be corrupted on the way, so mismatching ones are refetched up to the max number
+    /// of retries before the mismatch is reported.
+    async fn fetch_checked_blocks(
+        &self,
+        range: std::ops::RangeInclusive<u64>,
+        last_block_id: u64,
+        advertised_last: Option<&BlockDigest>,
+    ) -> Result<Vec<HashableBlockData>> {
+        let mut try_error_counter = 0;
+        loop {
+            let GetBlockRangeDataResponse { blocks, digests } =
+                self.client.get_block_range(range.clone()).await?;
+            match check_blocks(&blocks, &digests, last_block_id, advertised_last) {
+                Ok(blocks) => return Ok(blocks),
+                Err(err) if try_error_counter < self.polling_max_error_attempts => {
+                    warn!("Sequencer served inconsistent blocks {range:?}, refetching: {err:#}");
+                    try_error_counter += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Decodes `blocks`, checking each one against its digest of `digests` and block
+/// `last_block_id` also against `advertised_last`
+fn check_blocks(
+    blocks: &[Vec<u8>],
+    digests: &[BlockDigest],
+    last_block_id: u64,
+    advertised_last: Option<&BlockDigest>,
+) -> Result<Vec<HashableBlockData>> {
+    blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| {
+            let block = borsh::from_slice::<HashableBlockData>(block)?;
+            if let Some(digest) = digests.get(index) {
+                block.check_digest(digest)?;
+            }
+            if block.block_id == last_block_id
+                && let Some(digest) = advertised_last
+            {
+                block.check_digest(digest)?;
+            }
+            Ok(block)
+        })
+        .collect()
 }
@@ -0,0 +1,112 @@
+use common::{error::ExecutionFailureKind, rpc_types::SendTxResponse};
+use nssa::{
+    AccountId, Amount, PublicTransaction,
+    program::Program,
+    public_transaction::{Message, WitnessSet},
+};
+use nssa_core::clock::CLOCK_ACCOUNT_ID;
+
+use crate::{WalletCore, tx_log::TxDetails};
+
+pub struct Escrow<'w>(pub &'w WalletCore);
+
+impl Escrow<'_> {
+    /// Lock `amount` of `funder` in a new `escrow` account, claimable by `recipient` before block
+    /// `timeout` and refundable to `funder` after it
+    pub async fn create(
+        &self,
+        funder: AccountId,
+        escrow: AccountId,
+        recipient: AccountId,
+        amount: Amount,
+        timeout: u64,
+    ) -> Result<SendTxResponse, ExecutionFailureKind> {
+        // Instruction must be: [0x00 || amount (little-endian 16 bytes) || timeout (little-endian
+        // 8 bytes) || recipient (32 bytes) || escrow program id (8 little-endian u32 words)]
+        let mut instruction = vec![0];
+        instruction.extend_from_slice(&amount.0.to_le_bytes());
+        instruction.extend_from_slice(&timeout.to_le_bytes());
+        instruction.extend_from_slice(recipient.value());
+        instruction.extend_from_slice(&Program::escrow().id().to_bytes());
+
+        let details = TxDetails {
+            target: Some(escrow),
+            amount: Some(amount),
+            ..Default::default()
+        };
+        self.send(vec![funder, escrow], funder, instruction, details)
+            .await
+    }
+
+    /// Claim `escrow` by its `recipient`
+    pub async fn claim(
+        &self,
+        escrow: AccountId,
+        recipient: AccountId,
+    ) -> Result<SendTxResponse, ExecutionFailureKind> {
+        let details = TxDetails {
+            target: Some(recipient),
+            ..Default::default()
+        };
+        self.send(
+            vec![escrow, recipient, CLOCK_ACCOUNT_ID],
+            recipient,
+            vec![1],
+            details,
+        )
+        .await
+    }
+
+    /// Return timed out `escrow` to its `funder`
+    pub async fn refund(
+        &self,
+        escrow: AccountId,
+        funder: AccountId,
+    ) -> Result<SendTxResponse, ExecutionFailureKind> {
+        let details = TxDetails {
+            target: Some(funder),
+            ..Default::default()
+        };
+        self.send(
+            vec![escrow, funder, CLOCK_ACCOUNT_ID],
+            funder,
+            vec![2],
+            details,
+        )
+        .await
+    }
+
+    async fn send(
+        &self,
+        account_ids: Vec<AccountId>,
+        signer: AccountId,
+        instruction: Vec<u8>,
+        details: TxDetails,
+    ) -> Result<SendTxResponse, ExecutionFailureKind> {
+        let Ok((nonces, _reservation)) = self.0.reserve_accounts_nonces(vec![signer]).await else {
+            return Err(ExecutionFailureKind::SequencerError);
+        };
+        let details = TxDetails {
+            sender: Some(signer),
+            nonce: nonces.first().copied(),
+            ..details
+        };
+
+        let message =
+            Message::try_new(Program::escrow().id(), account_ids, nonces, instruction).unwrap();
+
+        let Some(signing_key) = self
+            .0
+            .storage
+            .user_data
+            .get_pub_account_signing_key(&signer)
+        else {
+            return Err(ExecutionFailureKind::KeyNotFoundError);
+        };
+
+        let witness_set = WitnessSet::for_message(&message, &[signing_key]);
+        let tx = PublicTransaction::new(message, witness_set);
+
+        self.0.send_tx_logged(tx.into(), details).await
+    }
+}
@@ -1,6 +1,8 @@
 //! This module contains [`WalletCore`](crate::WalletCore) facades for interacting with various
 //! on-chain programs.
 
+pub mod escrow;
 pub mod native_token_transfer;
 pub mod pinata;
+pub mod program_deployment;
 pub mod token;
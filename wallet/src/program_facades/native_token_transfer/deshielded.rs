@@ -1,5 +1,5 @@
-use common::{error::ExecutionFailureKind, rpc_primitives::requests::SendTxResponse};
-use nssa::AccountId;
+use common::{error::ExecutionFailureKind, rpc_types::SendTxResponse};
+use nssa::{AccountId, Amount};
 
 use super::{NativeTokenTransfer, auth_transfer_preparation};
 use crate::PrivacyPreservingAccount;
@@ -9,7 +9,7 @@ impl NativeTokenTransfer<'_> {
         &self,
         from: AccountId,
         to: AccountId,
-        balance_to_move: u128,
+        balance_to_move: Amount,
     ) -> Result<(SendTxResponse, nssa_core::SharedSecretKey), ExecutionFailureKind> {
         let (instruction_data, program, tx_pre_check) = auth_transfer_preparation(balance_to_move);
 
@@ -1,5 +1,5 @@
 use common::error::ExecutionFailureKind;
-use nssa::{Account, program::Program};
+use nssa::{Account, Amount, program::Program};
 use nssa_core::program::InstructionData;
 
 use crate::WalletCore;
@@ -12,7 +12,7 @@ pub mod shielded;
 pub struct NativeTokenTransfer<'w>(pub &'w WalletCore);
 
 fn auth_transfer_preparation(
-    balance_to_move: u128,
+    balance_to_move: Amount,
 ) -> (
     InstructionData,
     Program,
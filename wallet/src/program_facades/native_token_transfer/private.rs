@@ -1,7 +1,7 @@
 use std::vec;
 
-use common::{error::ExecutionFailureKind, rpc_primitives::requests::SendTxResponse};
-use nssa::{AccountId, program::Program};
+use common::{error::ExecutionFailureKind, rpc_types::SendTxResponse};
+use nssa::{AccountId, Amount, program::Program};
 use nssa_core::{NullifierPublicKey, SharedSecretKey, encryption::IncomingViewingPublicKey};
 
 use super::{NativeTokenTransfer, auth_transfer_preparation};
@@ -34,7 +34,7 @@ impl NativeTokenTransfer<'_> {
         from: AccountId,
         to_npk: NullifierPublicKey,
         to_ipk: IncomingViewingPublicKey,
-        balance_to_move: u128,
+        balance_to_move: Amount,
     ) -> Result<(SendTxResponse, [SharedSecretKey; 2]), ExecutionFailureKind> {
         let (instruction_data, program, tx_pre_check) = auth_transfer_preparation(balance_to_move);
 
@@ -64,7 +64,7 @@ impl NativeTokenTransfer<'_> {
         &self,
         from: AccountId,
         to: AccountId,
-        balance_to_move: u128,
+        balance_to_move: Amount,
     ) -> Result<(SendTxResponse, [SharedSecretKey; 2]), ExecutionFailureKind> {
         let (instruction_data, program, tx_pre_check) = auth_transfer_preparation(balance_to_move);
 
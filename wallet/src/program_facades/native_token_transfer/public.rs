@@ -1,32 +1,99 @@
-use common::{error::ExecutionFailureKind, rpc_primitives::requests::SendTxResponse};
+use common::{error::ExecutionFailureKind, rpc_types::SendTxResponse};
 use nssa::{
-    AccountId, PublicTransaction,
+    AccountId, Amount, Gas, ProgramId, PublicTransaction,
+    gas::GasCalculator,
     program::Program,
-    public_transaction::{Message, WitnessSet},
+    public_transaction::{Message, NativeTransfer, WitnessSet},
 };
+use nssa_core::account::Nonce;
 
 use super::NativeTokenTransfer;
+use crate::{api::TxDryRun, nonce_manager::NonceReservation, tx_log::TxDetails};
 
 impl NativeTokenTransfer<'_> {
     pub async fn send_public_transfer(
         &self,
         from: AccountId,
         to: AccountId,
-        balance_to_move: u128,
+        balance_to_move: Amount,
     ) -> Result<SendTxResponse, ExecutionFailureKind> {
-        let Ok(balance) = self.0.get_account_balance(from).await else {
+        self.send_time_locked_public_transfer(from, to, balance_to_move, None)
+            .await
+    }
+
+    /// Same as [`Self::send_public_transfer`], but the transfer can't be included into blocks
+    /// before `valid_from_block`
+    pub async fn send_time_locked_public_transfer(
+        &self,
+        from: AccountId,
+        to: AccountId,
+        balance_to_move: Amount,
+        valid_from_block: Option<u64>,
+    ) -> Result<SendTxResponse, ExecutionFailureKind> {
+        let (tx, details, _reservation) = self
+            .build_time_locked_public_transfer(from, to, balance_to_move, valid_from_block)
+            .await?;
+
+        self.0.send_tx_logged(tx.into(), details).await
+    }
+
+    /// Verdict of the sequencer on the transfer of [`Self::send_time_locked_public_transfer`],
+    /// which is neither sent nor logged
+    pub async fn dry_run_time_locked_public_transfer(
+        &self,
+        from: AccountId,
+        to: AccountId,
+        balance_to_move: Amount,
+        valid_from_block: Option<u64>,
+    ) -> Result<TxDryRun, ExecutionFailureKind> {
+        let (tx, _, _reservation) = self
+            .build_time_locked_public_transfer(from, to, balance_to_move, valid_from_block)
+            .await?;
+
+        Ok(self.0.dry_run_tx(tx.into()).await?)
+    }
+
+    /// Signed transfer of [`Self::send_time_locked_public_transfer`], its nonce is reserved until
+    /// the returned reservation is dropped
+    async fn build_time_locked_public_transfer(
+        &self,
+        from: AccountId,
+        to: AccountId,
+        balance_to_move: Amount,
+        valid_from_block: Option<u64>,
+    ) -> Result<(PublicTransaction, TxDetails, NonceReservation), ExecutionFailureKind> {
+        self.check_transfer_rules(from, to, balance_to_move).await?;
+
+        let Ok(balance) = self.0.get_spendable_balance(from).await else {
+            return Err(ExecutionFailureKind::SequencerError);
+        };
+        let Ok(priority_fee) = self.0.resolve_priority_fee().await else {
             return Err(ExecutionFailureKind::SequencerError);
         };
 
-        if balance >= balance_to_move {
-            let Ok(nonces) = self.0.get_accounts_nonces(vec![from]).await else {
+        let gas_deposit = self.0.max_cycles.map_or(Gas::ZERO, |max_cycles| {
+            GasCalculator::default().cycles_to_gas(max_cycles)
+        });
+        let required = Amount::checked_sum([balance_to_move, priority_fee, gas_deposit.into()]);
+
+        if required.is_some_and(|required| balance >= required) {
+            let Ok((nonces, reservation)) = self.0.reserve_accounts_nonces(vec![from]).await else {
                 return Err(ExecutionFailureKind::SequencerError);
             };
+            let details = TxDetails {
+                sender: Some(from),
+                nonce: nonces.first().copied(),
+                target: Some(to),
+                amount: Some(balance_to_move),
+            };
 
             let account_ids = vec![from, to];
             let program_id = Program::authenticated_transfer_program().id();
-            let message =
-                Message::try_new(program_id, account_ids, nonces, balance_to_move).unwrap();
+            let message = Message::try_new(program_id, account_ids, nonces, balance_to_move)
+                .unwrap()
+                .with_priority_fee(priority_fee)
+                .with_valid_from_block(valid_from_block)
+                .with_max_cycles(self.0.max_cycles);
 
             let signing_key = self.0.storage.user_data.get_pub_account_signing_key(&from);
 
@@ -36,22 +103,151 @@ impl NativeTokenTransfer<'_> {
 
             let witness_set = WitnessSet::for_message(&message, &[signing_key]);
 
-            let tx = PublicTransaction::new(message, witness_set);
-
-            Ok(self.0.sequencer_client.send_tx_public(tx).await?)
+            Ok((
+                PublicTransaction::new(message, witness_set),
+                details,
+                reservation,
+            ))
         } else {
             Err(ExecutionFailureKind::InsufficientFundsError)
         }
     }
 
+    /// Public transfer with the given nonce of `from`, so several transfers from `from` can be
+    /// sent before the first of them is included
+    ///
+    /// Balance of `from` isn't checked.
+    pub async fn send_public_transfer_with_nonce(
+        &self,
+        from: AccountId,
+        to: AccountId,
+        balance_to_move: Amount,
+        nonce: Nonce,
+        priority_fee: Amount,
+    ) -> Result<SendTxResponse, ExecutionFailureKind> {
+        self.check_transfer_rules(from, to, balance_to_move).await?;
+
+        let details = TxDetails {
+            sender: Some(from),
+            nonce: Some(nonce),
+            target: Some(to),
+            amount: Some(balance_to_move),
+        };
+
+        let account_ids = vec![from, to];
+        let program_id = Program::authenticated_transfer_program().id();
+        let message = Message::try_new(program_id, account_ids, vec![nonce], balance_to_move)
+            .unwrap()
+            .with_priority_fee(priority_fee)
+            .with_max_cycles(self.0.max_cycles);
+
+        let signing_key = self.0.storage.user_data.get_pub_account_signing_key(&from);
+
+        let Some(signing_key) = signing_key else {
+            return Err(ExecutionFailureKind::KeyNotFoundError);
+        };
+
+        let witness_set = WitnessSet::for_message(&message, &[signing_key]);
+
+        let tx = PublicTransaction::new(message, witness_set);
+
+        self.0.send_tx_logged(tx.into(), details).await
+    }
+
+    /// Checks the transfer against the rules of native transfers, which validation of transactions
+    /// runs, see [`NativeTransfer::check`], with the recipient and the storage deposit rate of the
+    /// sequencer
+    async fn check_transfer_rules(
+        &self,
+        from: AccountId,
+        to: AccountId,
+        balance_to_move: Amount,
+    ) -> Result<(), ExecutionFailureKind> {
+        let transfer = NativeTransfer::Transfer {
+            sender: from,
+            recipient: to,
+            amount: balance_to_move,
+        };
+        transfer
+            .validate()
+            .map_err(ExecutionFailureKind::InvalidTransfer)?;
+
+        let Ok(recipient) = self.0.get_account_public(to).await else {
+            return Err(ExecutionFailureKind::SequencerError);
+        };
+        let Ok(chain_info) = self.0.get_chain_info().await else {
+            return Err(ExecutionFailureKind::SequencerError);
+        };
+        transfer
+            .check_recipient(&recipient, chain_info.storage_deposit_per_byte)
+            .map_err(ExecutionFailureKind::InvalidTransfer)
+    }
+
+    /// Public transfer, followed by a transfer of the rest of the balance of `from` to `change`
+    ///
+    /// Change isn't sent if the rest doesn't cover its priority fee. Expects unmetered
+    /// transfers, as the charged gas isn't known upfront.
+    pub async fn send_public_transfer_with_change(
+        &self,
+        from: AccountId,
+        to: AccountId,
+        balance_to_move: Amount,
+        change: AccountId,
+    ) -> Result<(SendTxResponse, Option<SendTxResponse>), ExecutionFailureKind> {
+        let Ok(balance) = self.0.get_spendable_balance(from).await else {
+            return Err(ExecutionFailureKind::SequencerError);
+        };
+        let Ok(priority_fee) = self.0.resolve_priority_fee().await else {
+            return Err(ExecutionFailureKind::SequencerError);
+        };
+        let Ok((nonces, _reservation)) = self.0.reserve_accounts_nonces(vec![from]).await else {
+            return Err(ExecutionFailureKind::SequencerError);
+        };
+        let nonce = nonces
+            .first()
+            .copied()
+            .ok_or(ExecutionFailureKind::SequencerError)?;
+
+        let rest = balance_to_move
+            .checked_add(priority_fee)
+            .and_then(|outflow| balance.checked_sub(outflow))
+            .ok_or(ExecutionFailureKind::InsufficientFundsError)?;
+
+        let transfer = self
+            .send_public_transfer_with_nonce(from, to, balance_to_move, nonce, priority_fee)
+            .await?;
+
+        let change = match rest.checked_sub(priority_fee) {
+            Some(change_amount) if !change_amount.is_zero() => Some(
+                self.send_public_transfer_with_nonce(
+                    from,
+                    change,
+                    change_amount,
+                    nonce + 1,
+                    priority_fee,
+                )
+                .await?,
+            ),
+            _ => None,
+        };
+
+        Ok((transfer, change))
+    }
+
     pub async fn register_account(
         &self,
         from: AccountId,
     ) -> Result<SendTxResponse, ExecutionFailureKind> {
-        let Ok(nonces) = self.0.get_accounts_nonces(vec![from]).await else {
+        let Ok((nonces, _reservation)) = self.0.reserve_accounts_nonces(vec![from]).await else {
             return Err(ExecutionFailureKind::SequencerError);
         };
 
+        let details = TxDetails {
+            sender: Some(from),
+            nonce: nonces.first().copied(),
+            ..Default::default()
+        };
+
         let instruction: u128 = 0;
         let account_ids = vec![from];
         let program_id = Program::authenticated_transfer_program().id();
@@ -67,6 +263,50 @@ impl NativeTokenTransfer<'_> {
 
         let tx = PublicTransaction::new(message, witness_set);
 
-        Ok(self.0.sequencer_client.send_tx_public(tx).await?)
+        self.0.send_tx_logged(tx.into(), details).await
+    }
+
+    /// Assign account `account_id` of the authenticated transfer program to `program_id`, which
+    /// takes over its balance. Only accounts without data can be assigned.
+    pub async fn assign_account(
+        &self,
+        account_id: AccountId,
+        program_id: ProgramId,
+    ) -> Result<SendTxResponse, ExecutionFailureKind> {
+        let Ok((nonces, _reservation)) = self.0.reserve_accounts_nonces(vec![account_id]).await
+        else {
+            return Err(ExecutionFailureKind::SequencerError);
+        };
+
+        let details = TxDetails {
+            sender: Some(account_id),
+            nonce: nonces.first().copied(),
+            ..Default::default()
+        };
+
+        // The program tells assignments from amounts by the words of the new owner
+        let instruction = program_id.image_id();
+        let message = Message::try_new(
+            Program::authenticated_transfer_program().id(),
+            vec![account_id],
+            nonces,
+            instruction,
+        )
+        .unwrap();
+
+        let Some(signing_key) = self
+            .0
+            .storage
+            .user_data
+            .get_pub_account_signing_key(&account_id)
+        else {
+            return Err(ExecutionFailureKind::KeyNotFoundError);
+        };
+
+        let witness_set = WitnessSet::for_message(&message, &[signing_key]);
+
+        let tx = PublicTransaction::new(message, witness_set);
+
+        self.0.send_tx_logged(tx.into(), details).await
     }
 }
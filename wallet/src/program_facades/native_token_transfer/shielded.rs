@@ -1,5 +1,5 @@
-use common::{error::ExecutionFailureKind, rpc_primitives::requests::SendTxResponse};
-use nssa::AccountId;
+use common::{error::ExecutionFailureKind, rpc_types::SendTxResponse};
+use nssa::{AccountId, Amount};
 use nssa_core::{NullifierPublicKey, SharedSecretKey, encryption::IncomingViewingPublicKey};
 
 use super::{NativeTokenTransfer, auth_transfer_preparation};
@@ -10,7 +10,7 @@ impl NativeTokenTransfer<'_> {
         &self,
         from: AccountId,
         to: AccountId,
-        balance_to_move: u128,
+        balance_to_move: Amount,
     ) -> Result<(SendTxResponse, SharedSecretKey), ExecutionFailureKind> {
         let (instruction_data, program, tx_pre_check) = auth_transfer_preparation(balance_to_move);
 
@@ -39,7 +39,7 @@ impl NativeTokenTransfer<'_> {
         from: AccountId,
         to_npk: NullifierPublicKey,
         to_ipk: IncomingViewingPublicKey,
-        balance_to_move: u128,
+        balance_to_move: Amount,
     ) -> Result<(SendTxResponse, SharedSecretKey), ExecutionFailureKind> {
         let (instruction_data, program, tx_pre_check) = auth_transfer_preparation(balance_to_move);
 
@@ -1,8 +1,8 @@
-use common::{error::ExecutionFailureKind, rpc_primitives::requests::SendTxResponse};
+use common::{error::ExecutionFailureKind, rpc_types::SendTxResponse};
 use nssa::AccountId;
 use nssa_core::SharedSecretKey;
 
-use crate::{PrivacyPreservingAccount, WalletCore};
+use crate::{PrivacyPreservingAccount, WalletCore, tx_log::TxDetails};
 
 pub struct Pinata<'w>(pub &'w WalletCore);
 
@@ -22,7 +22,12 @@ impl Pinata<'_> {
         let witness_set = nssa::public_transaction::WitnessSet::for_message(&message, &[]);
         let tx = nssa::PublicTransaction::new(message, witness_set);
 
-        Ok(self.0.sequencer_client.send_tx_public(tx).await?)
+        let details = TxDetails {
+            target: Some(winner_account_id),
+            ..Default::default()
+        };
+
+        self.0.send_tx_logged(tx.into(), details).await
     }
 
     pub async fn claim_private_owned_account(
@@ -0,0 +1,77 @@
+use common::{error::ExecutionFailureKind, rpc_types::SendTxResponse};
+use log::warn;
+use nssa::{
+    AccountId, ProgramDeploymentTransaction,
+    gas::GasCalculator,
+    program_deployment_transaction::{Message, WitnessSet},
+};
+
+use crate::{
+    WalletCore,
+    tx_log::{TxDetails, TxStatus},
+};
+
+pub struct ProgramDeployment<'w>(pub &'w WalletCore);
+
+impl ProgramDeployment<'_> {
+    /// Deploy `bytecode`, signed and paid for by public account `deployer`
+    ///
+    /// Max fee of the deployment is its exact fee, as it depends on the bytecode size only. If the
+    /// program is deployed already, nothing is submitted and the response has the id of the
+    /// existing program.
+    pub async fn deploy(
+        &self,
+        deployer: AccountId,
+        bytecode: Vec<u8>,
+    ) -> Result<SendTxResponse, ExecutionFailureKind> {
+        // Cached chain id is only used within its TTL, as the deployment is signed for it
+        let chain_id = self
+            .0
+            .get_sequencer_info()
+            .await?
+            .chain_id
+            // Sequencers, which don't advertise chain id, run on the default one
+            .unwrap_or_default();
+
+        let Ok((nonces, _reservation)) = self.0.reserve_accounts_nonces(vec![deployer]).await
+        else {
+            return Err(ExecutionFailureKind::SequencerError);
+        };
+        let nonce = nonces[0];
+
+        let fee = GasCalculator::default().deployment_gas(bytecode.len());
+        let message = Message::new(deployer, nonce, chain_id, fee.into(), bytecode);
+
+        let Some(signing_key) = self
+            .0
+            .storage
+            .user_data
+            .get_pub_account_signing_key(&deployer)
+        else {
+            return Err(ExecutionFailureKind::KeyNotFoundError);
+        };
+
+        let witness_set = WitnessSet::for_message(&message, signing_key);
+        let tx = ProgramDeploymentTransaction::new(message, witness_set);
+
+        let details = TxDetails {
+            sender: Some(deployer),
+            nonce: Some(nonce),
+            ..Default::default()
+        };
+        let response = self.0.send_tx_logged(tx.into(), details).await?;
+        if response.is_already_deployed()
+            && let Err(err) = self
+                .0
+                .tx_log
+                .update_status(&response.tx_hash, TxStatus::Failed, None)
+        {
+            warn!(
+                "Failed to update transaction log for {}: {err:#}",
+                response.tx_hash
+            );
+        }
+
+        Ok(response)
+    }
+}
@@ -1,11 +1,11 @@
-use common::{error::ExecutionFailureKind, rpc_primitives::requests::SendTxResponse};
+use common::{error::ExecutionFailureKind, rpc_types::SendTxResponse};
 use nssa::{AccountId, program::Program};
 use nssa_core::{
     NullifierPublicKey, SharedSecretKey, encryption::IncomingViewingPublicKey,
     program::InstructionData,
 };
 
-use crate::{PrivacyPreservingAccount, WalletCore};
+use crate::{PrivacyPreservingAccount, WalletCore, tx_log::TxDetails};
 
 pub struct Token<'w>(pub &'w WalletCore);
 
@@ -34,8 +34,12 @@ impl Token<'_> {
         let witness_set = nssa::public_transaction::WitnessSet::for_message(&message, &[]);
 
         let tx = nssa::PublicTransaction::new(message, witness_set);
+        let details = TxDetails {
+            target: Some(supply_account_id),
+            ..Default::default()
+        };
 
-        Ok(self.0.sequencer_client.send_tx_public(tx).await?)
+        self.0.send_tx_logged(tx.into(), details).await
     }
 
     pub async fn send_new_definition_private_owned_supply(
@@ -134,9 +138,19 @@ impl Token<'_> {
         let mut instruction = [0; 23];
         instruction[0] = 0x01;
         instruction[1..17].copy_from_slice(&amount.to_le_bytes());
-        let Ok(nonces) = self.0.get_accounts_nonces(vec![sender_account_id]).await else {
+        let Ok((nonces, _reservation)) = self
+            .0
+            .reserve_accounts_nonces(vec![sender_account_id])
+            .await
+        else {
             return Err(ExecutionFailureKind::SequencerError);
         };
+        let details = TxDetails {
+            sender: Some(sender_account_id),
+            nonce: nonces.first().copied(),
+            target: Some(recipient_account_id),
+            amount: None,
+        };
         let message = nssa::public_transaction::Message::try_new(
             program_id,
             account_ids,
@@ -158,7 +172,7 @@ impl Token<'_> {
 
         let tx = nssa::PublicTransaction::new(message, witness_set);
 
-        Ok(self.0.sequencer_client.send_tx_public(tx).await?)
+        self.0.send_tx_logged(tx.into(), details).await
     }
 
     pub async fn send_transfer_transaction_private_owned_account(
@@ -0,0 +1,676 @@
+//! Signed reports of transactions touching a public account over a range of blocks, so their
+//! recipient can audit the account and verify the wallet owning the signing key made them.
+//!
+//! A report is a JSON object of a header, the transactions with their receipts from the newest
+//! one, a summary with opening and closing balances and a signature. Transactions are fetched,
+//! written and verified one page at a time, so large ranges don't accumulate in memory.
+//!
+//! The signature is a [`MessageSignature`] of the digest
+//! `SHA-256("NSSA Wallet Report:\n" || header || "\n" || transaction || "\n" || ... || summary ||
+//! "\n")` of compact JSON of the parts, so reports are verified however they are formatted.
+
+use std::{
+    fmt,
+    io::{BufReader, Read, Write},
+    ops::RangeInclusive,
+};
+
+use anyhow::{Context as _, Result};
+use common::{
+    address_index::{AddressTxFilter, DEFAULT_ADDRESS_TXS_PAGE_SIZE, TxDirection},
+    json_number::decimal_string,
+    receipt::{BalanceChange, TransactionReceipt},
+    rpc_types::{AddressTransaction, GetAccountWithProofResponse, TransactionStatus},
+    sequencer_api::SequencerApi,
+    signed_header::SignedBlockHeader,
+};
+use nssa::{AccountId, Amount, PrivateKey, signature::MessageSignature};
+use nssa_core::program::ProgramId;
+use serde::{
+    Deserialize, Serialize,
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+};
+use sha2::{Digest as _, Sha256};
+
+use crate::{
+    address_history::AddressTxPages,
+    helperfunctions::{AccountPrivacyKind, parse_addr_with_privacy_prefix},
+};
+
+/// Version of the report format, reports of other versions aren't verified
+pub const REPORT_VERSION: u32 = 1;
+
+/// Prefix of the signed digest, which separates reports from other signed data
+const REPORT_DIGEST_PREFIX: &[u8] = b"NSSA Wallet Report:\n";
+
+/// Fields of a report in the order they are written and verified in
+const REPORT_FIELDS: &[&str] = &["header", "transactions", "summary", "signature"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReportHeader {
+    pub version: u32,
+    /// Reported account id with privacy prefix
+    pub address: String,
+    pub from_block: u64,
+    pub to_block: u64,
+    /// Header of block `from_block` signed by the sequencer
+    pub first_header: SignedBlockHeader,
+    /// Header of block `to_block` signed by the sequencer, it has a state root only if it's the
+    /// last block of the chain
+    pub last_header: SignedBlockHeader,
+    /// Account proven against the state root of `last_header`, only available if the range ends
+    /// at the last block of the chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closing_proof: Option<GetAccountWithProofResponse>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReportTransaction {
+    /// Hex encoded transaction hash
+    pub hash: String,
+    pub block_id: u64,
+    /// Called program, known only for public transactions
+    pub program_id: Option<ProgramId>,
+    /// Direction of a native transfer of the account
+    pub direction: Option<TxDirection>,
+    /// Amount of a native transfer of the account
+    #[serde(default, with = "decimal_string::option")]
+    pub amount: Option<Amount>,
+    pub receipt: TransactionReceipt,
+}
+
+impl ReportTransaction {
+    /// Change of the balance of `account_id`, if the transaction changed it or paid fees from it
+    pub fn balance_change(&self, account_id: &AccountId) -> Option<&BalanceChange> {
+        let account_id = account_id.to_string();
+        self.receipt
+            .balance_changes
+            .iter()
+            .find(|change| change.account_id == account_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReportSummary {
+    /// Number of reported transactions
+    pub transactions: u64,
+    /// Balance before the first block of the range
+    #[serde(with = "decimal_string")]
+    pub opening_balance: Amount,
+    /// Balance after the last block of the range
+    #[serde(with = "decimal_string")]
+    pub closing_balance: Amount,
+}
+
+/// Report, which passed [`verify_report`]
+#[derive(Debug, Clone)]
+pub struct VerifiedReport {
+    /// Public account, which key signed the report
+    pub signer: AccountId,
+    pub header: ReportHeader,
+    pub summary: ReportSummary,
+}
+
+/// Writes the report of transactions of public account `account_id` in blocks `blocks` to
+/// `out`, signed with `signing_key`, returning its header and summary
+///
+/// Everything is read at the last block at the start, so blocks produced meanwhile don't change
+/// the report. Balances are derived from receipts, so transactions, which receipts the sequencer
+/// didn't keep, fail the report.
+pub async fn write_report(
+    client: &dyn SequencerApi,
+    account_id: AccountId,
+    blocks: RangeInclusive<u64>,
+    signing_key: &PrivateKey,
+    out: impl Write,
+) -> Result<(ReportHeader, ReportSummary)> {
+    let (from_block, to_block) = (*blocks.start(), *blocks.end());
+    anyhow::ensure!(
+        from_block <= to_block,
+        "Range of blocks starts at block {from_block} after its end at block {to_block}"
+    );
+
+    let proof = client
+        .get_account_with_proof(account_id.to_string())
+        .await?;
+    let last_block_id = proof.header.block_id;
+    anyhow::ensure!(
+        to_block <= last_block_id,
+        "Block {to_block} isn't produced yet, the last block is {last_block_id}"
+    );
+    anyhow::ensure!(
+        proof.header.state_root_bytes().is_some()
+            && proof.proven_state_root(&account_id) == proof.header.state_root_bytes(),
+        "Proof of account {account_id} doesn't match the state root of block {last_block_id}"
+    );
+    let last_balance = proof.account.balance;
+
+    let (last_header, closing_proof) = if to_block == last_block_id {
+        (proof.header.clone(), Some(proof))
+    } else {
+        (block_header(client, to_block).await?, None)
+    };
+    let header = ReportHeader {
+        version: REPORT_VERSION,
+        address: format!("Public/{account_id}"),
+        from_block,
+        to_block,
+        first_header: block_header(client, from_block).await?,
+        last_header,
+        closing_proof,
+    };
+    let mut writer = ReportWriter::new(out, &header)?;
+
+    let mut opening_balance = None;
+    let mut closing_balance = None;
+    let mut pages = AddressTxPages::new(client, account_id, blocks_filter(from_block, to_block));
+    while pages.has_more() {
+        for transaction in pages.next_page(DEFAULT_ADDRESS_TXS_PAGE_SIZE).await? {
+            let transaction = report_transaction(client, transaction).await?;
+            if let Some(change) = transaction.balance_change(&account_id) {
+                closing_balance.get_or_insert(change.post_balance);
+                opening_balance = Some(change.pre_balance);
+            }
+            writer.write_transaction(&transaction)?;
+        }
+    }
+
+    let closing_balance = match closing_balance {
+        Some(balance) => balance,
+        None => balance_after(client, account_id, to_block, last_block_id, last_balance).await?,
+    };
+    let summary = ReportSummary {
+        transactions: writer.transactions,
+        opening_balance: opening_balance.unwrap_or(closing_balance),
+        closing_balance,
+    };
+    writer.finish(&summary, signing_key)?;
+    Ok((header, summary))
+}
+
+/// Reads the report from `reader` and checks it's signed, its balances follow from receipts of
+/// its transactions and the closing proof matches them
+///
+/// Signatures of block headers aren't checked, as the key of the sequencer isn't known here.
+pub fn verify_report(reader: impl Read) -> Result<VerifiedReport> {
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let report = serde::Deserializer::deserialize_map(&mut deserializer, ReportVisitor)
+        .context("Report is malformed or doesn't add up")?;
+    deserializer
+        .end()
+        .context("Report is followed by trailing data")?;
+    Ok(report)
+}
+
+/// Private accounts have no public transactions and balances, so only public ones are reported
+pub fn parse_reported_account_id(address: &str) -> Result<AccountId> {
+    match parse_addr_with_privacy_prefix(address)? {
+        (account_id, AccountPrivacyKind::Public) => Ok(account_id.parse()?),
+        (_, AccountPrivacyKind::Private) => {
+            anyhow::bail!("Only public accounts are reported, got private account {address}")
+        }
+    }
+}
+
+fn blocks_filter(from_block: u64, to_block: u64) -> AddressTxFilter {
+    AddressTxFilter {
+        from_block: Some(from_block),
+        to_block: Some(to_block),
+        ..AddressTxFilter::default()
+    }
+}
+
+async fn block_header(client: &dyn SequencerApi, block_id: u64) -> Result<SignedBlockHeader> {
+    client
+        .get_block_headers(block_id..=block_id)
+        .await?
+        .headers
+        .into_iter()
+        .find(|header| header.block_id == block_id)
+        .with_context(|| format!("Sequencer didn't serve header of block {block_id}"))
+}
+
+async fn report_transaction(
+    client: &dyn SequencerApi,
+    transaction: AddressTransaction,
+) -> Result<ReportTransaction> {
+    let status = client
+        .get_transaction_status(transaction.hash.clone())
+        .await?
+        .status;
+    let TransactionStatus::Included {
+        receipt: Some(receipt),
+        ..
+    } = status
+    else {
+        anyhow::bail!(
+            "Sequencer has no receipt of transaction {}, so balances can't be derived",
+            transaction.hash
+        );
+    };
+
+    Ok(ReportTransaction {
+        hash: transaction.hash,
+        block_id: transaction.block_id,
+        program_id: transaction.program_id,
+        direction: transaction.direction,
+        amount: transaction.amount,
+        receipt,
+    })
+}
+
+/// Balance of `account_id` after block `block_id`, which is the balance before the oldest later
+/// transaction changing it, or `last_balance` at block `last_block_id` if there is none
+async fn balance_after(
+    client: &dyn SequencerApi,
+    account_id: AccountId,
+    block_id: u64,
+    last_block_id: u64,
+    last_balance: Amount,
+) -> Result<Amount> {
+    let mut balance = last_balance;
+    if block_id == last_block_id {
+        return Ok(balance);
+    }
+
+    let mut pages = AddressTxPages::new(
+        client,
+        account_id,
+        blocks_filter(block_id + 1, last_block_id),
+    );
+    while pages.has_more() {
+        for transaction in pages.next_page(DEFAULT_ADDRESS_TXS_PAGE_SIZE).await? {
+            let transaction = report_transaction(client, transaction).await?;
+            if let Some(change) = transaction.balance_change(&account_id) {
+                balance = change.pre_balance;
+            }
+        }
+    }
+    Ok(balance)
+}
+
+/// Digest of report parts, see the [module docs](self)
+struct ReportDigest(Sha256);
+
+impl ReportDigest {
+    fn new() -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(REPORT_DIGEST_PREFIX);
+        Self(hasher)
+    }
+
+    /// Adds `part` to the digest, returning its compact JSON
+    fn update(&mut self, part: &impl Serialize) -> Result<Vec<u8>> {
+        let json = serde_json::to_vec(part)?;
+        self.0.update(&json);
+        self.0.update(b"\n");
+        Ok(json)
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+}
+
+/// Writes parts of a report as they are produced
+struct ReportWriter<W> {
+    out: W,
+    digest: ReportDigest,
+    transactions: u64,
+}
+
+impl<W: Write> ReportWriter<W> {
+    fn new(mut out: W, header: &ReportHeader) -> Result<Self> {
+        let mut digest = ReportDigest::new();
+        out.write_all(b"{\"header\":")?;
+        out.write_all(&digest.update(header)?)?;
+        out.write_all(b",\n\"transactions\":[")?;
+        Ok(Self {
+            out,
+            digest,
+            transactions: 0,
+        })
+    }
+
+    fn write_transaction(&mut self, transaction: &ReportTransaction) -> Result<()> {
+        let separator: &[u8] = if self.transactions == 0 {
+            b"\n"
+        } else {
+            b",\n"
+        };
+        self.out.write_all(separator)?;
+        self.out.write_all(&self.digest.update(transaction)?)?;
+        self.transactions += 1;
+        Ok(())
+    }
+
+    fn finish(mut self, summary: &ReportSummary, signing_key: &PrivateKey) -> Result<()> {
+        self.out.write_all(b"\n],\n\"summary\":")?;
+        self.out.write_all(&self.digest.update(summary)?)?;
+        let signature = MessageSignature::sign(signing_key, &self.digest.finalize());
+        self.out.write_all(b",\n\"signature\":")?;
+        serde_json::to_writer(&mut self.out, &signature.to_base64())?;
+        self.out.write_all(b"}\n")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Checks of a report, which are made while its transactions are read
+struct ReportVerifier {
+    account_id: AccountId,
+    blocks: RangeInclusive<u64>,
+    digest: ReportDigest,
+    transactions: u64,
+    /// Block of the previous transaction, transactions go from the newest one
+    last_block_id: u64,
+    /// Balance after the newest transaction changing it
+    newest_balance: Option<Amount>,
+    /// Balance before the oldest transaction changing it so far
+    oldest_balance: Option<Amount>,
+}
+
+impl ReportVerifier {
+    fn new(header: &ReportHeader) -> Result<Self> {
+        anyhow::ensure!(
+            header.version == REPORT_VERSION,
+            "Report version {} isn't supported, expected {REPORT_VERSION}",
+            header.version
+        );
+        anyhow::ensure!(
+            header.first_header.block_id == header.from_block
+                && header.last_header.block_id == header.to_block,
+            "Headers don't match range of blocks {}..={}",
+            header.from_block,
+            header.to_block
+        );
+
+        let mut digest = ReportDigest::new();
+        digest.update(header)?;
+        Ok(Self {
+            account_id: parse_reported_account_id(&header.address)?,
+            blocks: header.from_block..=header.to_block,
+            digest,
+            transactions: 0,
+            last_block_id: header.to_block,
+            newest_balance: None,
+            oldest_balance: None,
+        })
+    }
+
+    fn add_transaction(&mut self, transaction: &ReportTransaction) -> Result<()> {
+        let hash = &transaction.hash;
+        anyhow::ensure!(
+            self.blocks.contains(&transaction.block_id)
+                && transaction.block_id <= self.last_block_id,
+            "Transaction {hash} of block {} is out of range or order",
+            transaction.block_id
+        );
+        anyhow::ensure!(
+            transaction
+                .receipt
+                .balance_changes
+                .iter()
+                .all(BalanceChange::is_balanced),
+            "Balance changes of transaction {hash} don't add up"
+        );
+        if let Some(change) = transaction.balance_change(&self.account_id) {
+            anyhow::ensure!(
+                self.oldest_balance
+                    .is_none_or(|balance| balance == change.post_balance),
+                "Balance after transaction {hash} doesn't match balance before the next one"
+            );
+            self.newest_balance.get_or_insert(change.post_balance);
+            self.oldest_balance = Some(change.pre_balance);
+        }
+
+        self.digest.update(transaction)?;
+        self.transactions += 1;
+        self.last_block_id = transaction.block_id;
+        Ok(())
+    }
+
+    fn finish(
+        mut self,
+        header: ReportHeader,
+        summary: ReportSummary,
+        signature: &str,
+    ) -> Result<VerifiedReport> {
+        anyhow::ensure!(
+            summary.transactions == self.transactions,
+            "Summary counts {} transactions, but report has {}",
+            summary.transactions,
+            self.transactions
+        );
+        anyhow::ensure!(
+            self.newest_balance.unwrap_or(summary.opening_balance) == summary.closing_balance,
+            "Closing balance doesn't match balance after the last transaction"
+        );
+        anyhow::ensure!(
+            self.oldest_balance.unwrap_or(summary.closing_balance) == summary.opening_balance,
+            "Opening balance doesn't match balance before the first transaction"
+        );
+        if let Some(proof) = &header.closing_proof {
+            anyhow::ensure!(
+                proof.header == header.last_header
+                    && proof.header.state_root_bytes().is_some()
+                    && proof.proven_state_root(&self.account_id) == proof.header.state_root_bytes(),
+                "Closing proof doesn't match the state root of block {}",
+                header.to_block
+            );
+            anyhow::ensure!(
+                proof.account.balance == summary.closing_balance,
+                "Closing balance doesn't match the proven balance"
+            );
+        }
+
+        self.digest.update(&summary)?;
+        let signature = MessageSignature::from_base64(signature)?;
+        anyhow::ensure!(
+            signature.is_valid_for(&self.digest.finalize(), &signature.account_id()),
+            "Signature doesn't match contents of the report"
+        );
+
+        Ok(VerifiedReport {
+            signer: signature.account_id(),
+            header,
+            summary,
+        })
+    }
+}
+
+/// Reads report fields in the order they are written in, so transactions are verified as they
+/// are read
+struct ReportVisitor;
+
+impl<'de> Visitor<'de> for ReportVisitor {
+    type Value = VerifiedReport;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a wallet report")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let header: ReportHeader = next_field(&mut map, "header")?;
+        let mut verifier = ReportVerifier::new(&header).map_err(de::Error::custom)?;
+        expect_field(&mut map, "transactions")?;
+        map.next_value_seed(TransactionsSeed(&mut verifier))?;
+        let summary = next_field(&mut map, "summary")?;
+        let signature: String = next_field(&mut map, "signature")?;
+        if let Some(field) = map.next_key::<String>()? {
+            return Err(de::Error::unknown_field(&field, REPORT_FIELDS));
+        }
+
+        verifier
+            .finish(header, summary, &signature)
+            .map_err(de::Error::custom)
+    }
+}
+
+struct TransactionsSeed<'a>(&'a mut ReportVerifier);
+
+impl<'de> DeserializeSeed<'de> for TransactionsSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for TransactionsSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a list of report transactions")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(transaction) = seq.next_element::<ReportTransaction>()? {
+            self.0
+                .add_transaction(&transaction)
+                .map_err(de::Error::custom)?;
+        }
+        Ok(())
+    }
+}
+
+fn expect_field<'de, A: MapAccess<'de>>(
+    map: &mut A,
+    expected: &'static str,
+) -> Result<(), A::Error> {
+    match map.next_key::<String>()? {
+        Some(field) if field == expected => Ok(()),
+        Some(field) => Err(de::Error::custom(format!(
+            "Expected field `{expected}`, got `{field}`"
+        ))),
+        None => Err(de::Error::missing_field(expected)),
+    }
+}
+
+fn next_field<'de, A: MapAccess<'de>, T: Deserialize<'de>>(
+    map: &mut A,
+    field: &'static str,
+) -> Result<T, A::Error> {
+    expect_field(map, field)?;
+    map.next_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        mock_chain::MockChain,
+        test_utils::{
+            account_id_for_index, create_transaction_native_token_transfer, signing_key_for_index,
+        },
+    };
+
+    use super::*;
+
+    const TRANSFERS: u64 = 30;
+    const TRANSFERS_PER_BLOCK: u64 = 3;
+
+    /// Chain of blocks 2 to 11, where account 0 sent [`TRANSFERS`] transfers to account 1,
+    /// transfer `n` of amount `n + 1`, and block 12 without transactions
+    async fn chain_with_transfers() -> MockChain {
+        let chain = MockChain::new(&[(account_id_for_index(0), Amount(10_000))]);
+        for nonce in 0..TRANSFERS {
+            let tx = create_transaction_native_token_transfer(
+                *account_id_for_index(0).value(),
+                nonce,
+                *account_id_for_index(1).value(),
+                u128::from(nonce) + 1,
+                signing_key_for_index(0),
+            );
+            chain.send_tx(tx).await.unwrap();
+            if (nonce + 1).is_multiple_of(TRANSFERS_PER_BLOCK) {
+                chain.produce_block();
+            }
+        }
+        chain.produce_block();
+        chain
+    }
+
+    /// Report of account 1 over `blocks` signed by account 1
+    async fn report(chain: &MockChain, blocks: RangeInclusive<u64>) -> Vec<u8> {
+        let mut report = vec![];
+        write_report(
+            chain,
+            account_id_for_index(1),
+            blocks,
+            &signing_key_for_index(1),
+            &mut report,
+        )
+        .await
+        .unwrap();
+        report
+    }
+
+    /// Sum of amounts of transfers `from..to`
+    fn transferred(transfers: std::ops::Range<u128>) -> Amount {
+        Amount(transfers.map(|nonce| nonce + 1).sum())
+    }
+
+    #[tokio::test]
+    async fn test_report_is_verified_with_balances_of_its_range() {
+        let chain = chain_with_transfers().await;
+
+        // Blocks 4 to 6 include transfers 6 to 14
+        let verified = verify_report(report(&chain, 4..=6).await.as_slice()).unwrap();
+
+        assert_eq!(verified.signer, account_id_for_index(1));
+        assert_eq!(
+            verified.header.address,
+            format!("Public/{}", account_id_for_index(1))
+        );
+        assert!(verified.header.closing_proof.is_none());
+        assert_eq!(
+            verified.summary,
+            ReportSummary {
+                transactions: 9,
+                opening_balance: transferred(0..6),
+                closing_balance: transferred(0..15),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_report_ending_at_last_block_is_proven() {
+        let chain = chain_with_transfers().await;
+        let last_block_id = chain.last_block_id();
+
+        let verified = verify_report(
+            report(&chain, last_block_id..=last_block_id)
+                .await
+                .as_slice(),
+        )
+        .unwrap();
+
+        let proof = verified.header.closing_proof.unwrap();
+        assert_eq!(proof.header.block_id, last_block_id);
+        assert_eq!(
+            verified.summary,
+            ReportSummary {
+                transactions: 0,
+                opening_balance: transferred(0..30),
+                closing_balance: transferred(0..30),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tampered_amount_fails_verification() {
+        let chain = chain_with_transfers().await;
+        let report = String::from_utf8(report(&chain, 2..=11).await).unwrap();
+        verify_report(report.as_bytes()).unwrap();
+
+        // Transfer 9 of amount 10 is reported as a transfer of 1000
+        let tampered = report.replacen(r#""amount":"10""#, r#""amount":"1000""#, 1);
+        assert_ne!(tampered, report);
+
+        let err = verify_report(tampered.as_bytes()).unwrap_err();
+        assert!(format!("{err:#}").contains("Signature doesn't match"));
+    }
+}
@@ -0,0 +1,223 @@
+//! Connection of the continuous sync to sequencers
+//!
+//! Continuous run retries failed syncs with exponential backoff and, after several consecutive
+//! failures, fails over to the next configured sequencer. Sync resumes from the last synced
+//! block, which is persisted after every block.
+
+use std::time::Duration;
+
+use common::error::SequencerClientError;
+
+/// Delay before the first reconnection attempt
+pub const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(500);
+
+/// Whether sync failed due to an unreachable or overloaded sequencer, so it may be retried
+pub fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<SequencerClientError>()
+            .is_some_and(SequencerClientError::is_connection_error)
+    })
+}
+
+/// Exponentially growing delay between reconnection attempts
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    initial_delay: Duration,
+    max_delay: Duration,
+    next_delay: Duration,
+}
+
+impl ReconnectBackoff {
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Self {
+        let initial_delay = initial_delay.min(max_delay);
+        Self {
+            initial_delay,
+            max_delay,
+            next_delay: initial_delay,
+        }
+    }
+
+    /// Delay before the next attempt, doubled for the one after it up to the max delay
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next_delay;
+        self.next_delay = (delay * 2).min(self.max_delay);
+        delay
+    }
+
+    /// Start over from the initial delay, called once the connection is restored
+    pub fn reset(&mut self) {
+        self.next_delay = self.initial_delay;
+    }
+}
+
+/// Sequencers to sync from: the primary one followed by fallbacks in order of preference
+#[derive(Debug, Clone)]
+pub struct SequencerEndpoints {
+    addrs: Vec<String>,
+    current: usize,
+    consecutive_failures: u64,
+    failover_after_failures: u64,
+}
+
+impl SequencerEndpoints {
+    pub fn new(primary: String, fallbacks: Vec<String>, failover_after_failures: u64) -> Self {
+        let mut addrs = vec![primary];
+        for addr in fallbacks {
+            if !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+
+        Self {
+            addrs,
+            current: 0,
+            consecutive_failures: 0,
+            failover_after_failures: failover_after_failures.max(1),
+        }
+    }
+
+    /// Address of the sequencer currently synced from
+    pub fn current(&self) -> &str {
+        &self.addrs[self.current]
+    }
+
+    pub fn consecutive_failures(&self) -> u64 {
+        self.consecutive_failures
+    }
+
+    /// Records a failed attempt, returns `true` if it's time to fail over
+    pub fn record_failure(&mut self) -> bool {
+        self.consecutive_failures += 1;
+        self.addrs.len() > 1 && self.consecutive_failures >= self.failover_after_failures
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+    }
+
+    /// Other sequencers to fail over to, starting from the one after the current
+    ///
+    /// The primary sequencer is among them while a fallback one is used, so sync gets back to it.
+    pub fn failover_candidates(&self) -> Vec<(usize, String)> {
+        (1..self.addrs.len())
+            .map(|offset| (self.current + offset) % self.addrs.len())
+            .map(|index| (index, self.addrs[index].clone()))
+            .collect()
+    }
+
+    /// Switches to the sequencer at `index` of [`Self::failover_candidates`]
+    pub fn fail_over_to(&mut self, index: usize) {
+        self.current = index;
+        self.consecutive_failures = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{error::SequencerRpcError, rpc_primitives::errors::RpcError};
+
+    use super::*;
+
+    fn endpoints() -> SequencerEndpoints {
+        SequencerEndpoints::new(
+            "http://primary".to_string(),
+            vec![
+                "http://fallback1".to_string(),
+                "http://fallback2".to_string(),
+            ],
+            3,
+        )
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max_delay() {
+        let mut backoff =
+            ReconnectBackoff::new(Duration::from_millis(500), Duration::from_millis(3000));
+
+        let delays = (0..5).map(|_| backoff.next_delay()).collect::<Vec<_>>();
+
+        assert_eq!(
+            delays,
+            [500, 1000, 2000, 3000, 3000].map(Duration::from_millis)
+        );
+    }
+
+    #[test]
+    fn test_backoff_reset() {
+        let mut backoff =
+            ReconnectBackoff::new(Duration::from_millis(500), Duration::from_millis(3000));
+        backoff.next_delay();
+        backoff.next_delay();
+
+        backoff.reset();
+
+        assert_eq!(backoff.next_delay(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_fail_over_after_consecutive_failures() {
+        let mut endpoints = endpoints();
+
+        assert!(!endpoints.record_failure());
+        assert!(!endpoints.record_failure());
+        endpoints.record_success();
+        assert!(!endpoints.record_failure());
+        assert!(!endpoints.record_failure());
+        assert!(endpoints.record_failure());
+        assert_eq!(endpoints.consecutive_failures(), 3);
+    }
+
+    #[test]
+    fn test_no_fail_over_without_fallbacks() {
+        let mut endpoints = SequencerEndpoints::new(
+            "http://primary".to_string(),
+            vec!["http://primary".to_string()],
+            1,
+        );
+
+        assert!(!endpoints.record_failure());
+        assert!(endpoints.failover_candidates().is_empty());
+    }
+
+    #[test]
+    fn test_failover_candidates_wrap_around_to_primary() {
+        let mut endpoints = endpoints();
+        assert_eq!(
+            endpoints.failover_candidates(),
+            [
+                (1, "http://fallback1".to_string()),
+                (2, "http://fallback2".to_string())
+            ]
+        );
+
+        endpoints.record_failure();
+        endpoints.fail_over_to(1);
+
+        assert_eq!(endpoints.current(), "http://fallback1");
+        assert_eq!(endpoints.consecutive_failures(), 0);
+        assert_eq!(
+            endpoints.failover_candidates(),
+            [
+                (2, "http://fallback2".to_string()),
+                (0, "http://primary".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_connection_errors() {
+        let busy: anyhow::Error = SequencerClientError::InternalError(SequencerRpcError {
+            jsonrpc: "2.0".to_string(),
+            error: RpcError::server_busy("get_block_range", Duration::from_secs(1)),
+            id: 1,
+        })
+        .into();
+        let malformed: anyhow::Error =
+            SequencerClientError::SerdeError(serde_json::from_str::<u64>("x").unwrap_err()).into();
+
+        assert!(is_connection_error(&busy.context("Failed to sync")));
+        assert!(!is_connection_error(&malformed));
+        assert!(!is_connection_error(&anyhow::anyhow!("Unsupported block")));
+    }
+}
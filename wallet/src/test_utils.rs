@@ -0,0 +1,39 @@
+//! Wallets served by [`MockChain`], so wallet logic is tested without a sequencer
+
+use std::{path::Path, sync::Arc};
+
+use common::mock_chain::MockChain;
+use nssa::{AccountId, Amount};
+
+use crate::{WalletCore, chain_storage::WalletChainStore, config::WalletConfig};
+
+/// Preconfigured public accounts of [`WalletConfig::default`]
+pub(crate) fn preconfigured_public_accounts() -> (AccountId, AccountId) {
+    (
+        "BLgCRDXYdQPMMWVHYRFGQZbgeHx9frkipa8GtpG2Syqy"
+            .parse()
+            .unwrap(),
+        "Gj1mJy5W7J5pfmLRujmQaLfLMWidNxQ6uwnhb666ZwHw"
+            .parse()
+            .unwrap(),
+    )
+}
+
+/// Chain, where both [`preconfigured_public_accounts`] have `balance`
+pub(crate) fn funded_chain(balance: u128) -> MockChain {
+    let (first, second) = preconfigured_public_accounts();
+    MockChain::new(&[(first, Amount(balance)), (second, Amount(balance))])
+}
+
+/// Wallet with the preconfigured accounts, which keeps its files in `home` and polls `chain`
+/// without delays
+pub(crate) fn mock_wallet(chain: Arc<MockChain>, home: &Path) -> WalletCore {
+    let config = WalletConfig {
+        seq_poll_timeout_millis: 1,
+        seq_poll_max_retries: 1,
+        ..WalletConfig::default()
+    };
+    let storage = WalletChainStore::new_storage(config, "test".to_string()).unwrap();
+
+    WalletCore::new_with_sequencer_client(storage, chain, home)
+}
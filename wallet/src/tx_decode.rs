@@ -0,0 +1,515 @@
+//! Decoding of raw transactions for inspection by `tx decode`.
+//!
+//! Input is a transaction the way it is submitted to and stored by the sequencer, i.e. borsh
+//! encoded [`EncodedTransaction`]. It's the `encoded_transaction` of conformance vectors and the
+//! decoded `transaction_b64` of the transaction log.
+//!
+//! Transactions are not bound to a chain id and account ids have no checksum, so neither is
+//! reported.
+
+use std::{fmt::Display, path::Path};
+
+use anyhow::{Context as _, Result};
+use borsh::BorshDeserialize;
+use common::transaction::{EncodedTransaction, NSSATransaction, TxKind};
+use nssa::{
+    AccountId, BuiltinProgram, PrivacyPreservingTransaction, ProgramDeploymentTransaction,
+    PublicKey, PublicTransaction, Signature, SignatureScheme, instruction_decoder::DecoderRegistry,
+    program::Program, public_transaction,
+};
+use nssa_core::program::ProgramId;
+
+use crate::output::{
+    DecodedPrivacyPreservingTx, DecodedProgramDeploymentTx, DecodedPublicTx, DecodedTxBody,
+    InstructionView, SignatureView, TxDecodeOutput,
+};
+
+/// Offset of the transaction data in [`EncodedTransaction`], following the kind tag and the
+/// length of the data
+const DATA_OFFSET: usize = 1 + 4;
+
+/// Position and reason of a decoding failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Offset of the byte decoding stopped at, which is usually right past the malformed value
+    pub offset: usize,
+    pub reason: String,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at byte {}: {}", self.offset, self.reason)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Parses hex encoded bytes, optionally prefixed with `0x`
+pub fn parse_hex(hex: &str) -> Result<Vec<u8>> {
+    let hex = hex.trim();
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    hex::decode(hex).context("Invalid hex")
+}
+
+/// Reads transaction bytes from `path`, which holds either raw bytes or hex text
+pub fn read_transaction_file(path: &Path) -> Result<Vec<u8>> {
+    let contents = std::fs::read(path)
+        .with_context(|| format!("Failed to read transaction file {}", path.display()))?;
+
+    match std::str::from_utf8(&contents).map(parse_hex) {
+        Ok(Ok(bytes)) => Ok(bytes),
+        _ => Ok(contents),
+    }
+}
+
+/// Decodes transaction `bytes` and checks its signatures. Instructions are decoded with
+/// `decoders`.
+pub fn decode_transaction(
+    bytes: &[u8],
+    decoders: &DecoderRegistry,
+) -> Result<TxDecodeOutput, DecodeError> {
+    let encoded: EncodedTransaction = decode_at(bytes, 0)?;
+    if let TxKind::Unknown(tag) = encoded.tx_kind {
+        return Err(DecodeError {
+            offset: 0,
+            reason: format!("Transaction kind {tag} is unsupported by this wallet"),
+        });
+    }
+    let nssa_tx = NSSATransaction::try_from(&encoded).map_err(|err| locate_error(&encoded, err))?;
+
+    let legacy = encoded.tx_kind.is_legacy();
+    // Transactions with `u128` nonces and public ones with `u64` priority fees are signed over
+    // their legacy encoding, which isn't reproduced here. Other ones without a signature scheme
+    // are signed over the current one.
+    let signed_over_legacy_encoding =
+        encoded.tx_kind.predates_u64_nonces() || encoded.tx_kind.predates_amount_priority_fees();
+    let check_signature = |scheme: SignatureScheme,
+                           message_bytes: &[u8],
+                           signature: &Signature,
+                           public_key: &PublicKey| {
+        (!signed_over_legacy_encoding).then(|| {
+            nssa::signature::verify(
+                scheme,
+                &scheme.sighash(message_bytes),
+                public_key.value(),
+                signature.value(),
+            )
+        })
+    };
+
+    let body = match &nssa_tx {
+        NSSATransaction::Public(tx) => {
+            let message = tx.message();
+            let message_bytes = message.to_bytes();
+            let scheme = tx.witness_set().scheme();
+            let program_id = message.program_id();
+            DecodedTxBody::Public(DecodedPublicTx {
+                program_id: program_id.to_string(),
+                program: builtin_program_name(&program_id),
+                accounts: prefixed_account_ids(message.account_ids()),
+                readonly_accounts: prefixed_account_ids(message.readonly_account_ids()),
+                nonces: message.nonces().to_vec(),
+                instruction: decode_instruction(message, decoders),
+                priority_fee: message.priority_fee(),
+                valid_from_block: message.valid_from_block(),
+                max_cycles: message.max_cycles(),
+                signatures: tx
+                    .witness_set()
+                    .signatures_and_public_keys()
+                    .iter()
+                    .map(|(signature, public_key)| {
+                        signature_view(
+                            scheme,
+                            signature,
+                            public_key,
+                            check_signature(scheme, &message_bytes, signature, public_key),
+                        )
+                    })
+                    .collect(),
+            })
+        }
+        NSSATransaction::PrivacyPreserving(tx) => {
+            let message = tx.message();
+            let message_bytes = message.to_bytes();
+            let scheme = tx.witness_set().scheme();
+            DecodedTxBody::PrivacyPreserving(DecodedPrivacyPreservingTx {
+                accounts: prefixed_account_ids(message.public_account_ids()),
+                nonces: message.nonces().to_vec(),
+                encrypted_post_states: message.encrypted_private_post_states.len(),
+                new_commitments: message.new_commitments.len(),
+                new_nullifiers: message.new_nullifiers().len(),
+                signatures: tx
+                    .witness_set()
+                    .signatures_and_public_keys()
+                    .iter()
+                    .map(|(signature, public_key)| {
+                        signature_view(
+                            scheme,
+                            signature,
+                            public_key,
+                            check_signature(scheme, &message_bytes, signature, public_key),
+                        )
+                    })
+                    .collect(),
+            })
+        }
+        NSSATransaction::ProgramDeployment(tx) => {
+            let message = tx.message();
+            let message_bytes = message.to_bytes();
+            let scheme = tx.witness_set().scheme();
+            let bytecode = message.bytecode();
+            DecodedTxBody::ProgramDeployment(DecodedProgramDeploymentTx {
+                deployer: format!("Public/{}", message.deployer()),
+                nonce: message.nonce(),
+                chain_id: message.chain_id(),
+                max_fee: message.max_fee(),
+                fee: tx.fee().into(),
+                bytecode_size: bytecode.len(),
+                // Deployment of invalid bytecode is rejected by the sequencer
+                program_id: Program::new(bytecode.to_vec())
+                    .ok()
+                    .map(|program| program.id().to_string()),
+                signatures: tx
+                    .witness_set()
+                    .signatures_and_public_keys()
+                    .iter()
+                    .map(|(signature, public_key)| {
+                        signature_view(
+                            scheme,
+                            signature,
+                            public_key,
+                            check_signature(scheme, &message_bytes, signature, public_key),
+                        )
+                    })
+                    .collect(),
+            })
+        }
+    };
+
+    Ok(TxDecodeOutput {
+        hash: hex::encode(encoded.hash()),
+        tag: encoded.tx_kind.tag(),
+        legacy,
+        body,
+    })
+}
+
+/// Decodes `bytes` located at `offset` of the input, which must hold nothing else
+fn decode_at<T: BorshDeserialize>(bytes: &[u8], offset: usize) -> Result<T, DecodeError> {
+    let mut rest = bytes;
+    let value = T::deserialize_reader(&mut rest).map_err(|err| DecodeError {
+        offset: offset + bytes.len() - rest.len(),
+        reason: err.to_string(),
+    })?;
+    if !rest.is_empty() {
+        return Err(DecodeError {
+            offset: offset + bytes.len() - rest.len(),
+            reason: format!("{} unexpected trailing bytes", rest.len()),
+        });
+    }
+    Ok(value)
+}
+
+/// Finds where the transaction data of `encoded` is malformed, given the error of its decoder
+///
+/// Data of legacy transactions and non-canonical encodings are reported at the start of the data.
+fn locate_error(encoded: &EncodedTransaction, err: nssa::error::NssaError) -> DecodeError {
+    let data = &encoded.encoded_transaction_data;
+    let located = match encoded.tx_kind {
+        TxKind::Public => decode_at::<PublicTransaction>(data, DATA_OFFSET).err(),
+        TxKind::PrivacyPreserving => {
+            decode_at::<PrivacyPreservingTransaction>(data, DATA_OFFSET).err()
+        }
+        TxKind::ProgramDeployment => {
+            decode_at::<ProgramDeploymentTransaction>(data, DATA_OFFSET).err()
+        }
+        TxKind::LegacyPublic
+        | TxKind::LegacyPrivacyPreserving
+        | TxKind::LegacyProgramDeployment
+        | TxKind::UnschemedPublic
+        | TxKind::UnschemedPrivacyPreserving
+        | TxKind::UnschemedProgramDeployment
+        | TxKind::NarrowFeePublic
+        | TxKind::Unknown(_) => None,
+    };
+    located.unwrap_or(DecodeError {
+        offset: DATA_OFFSET,
+        reason: err.to_string(),
+    })
+}
+
+/// Decodes instruction of `message` with the decoder of its program
+fn decode_instruction(
+    message: &public_transaction::Message,
+    decoders: &DecoderRegistry,
+) -> InstructionView {
+    InstructionView(decoders.decode(
+        &message.program_id(),
+        message.instruction_data(),
+        message.account_ids(),
+    ))
+}
+
+/// Instruction of `tx`, if it's a public transaction
+pub fn public_instruction(
+    tx: &EncodedTransaction,
+    decoders: &DecoderRegistry,
+) -> Option<InstructionView> {
+    match NSSATransaction::try_from(tx).ok()? {
+        NSSATransaction::Public(tx) => Some(decode_instruction(tx.message(), decoders)),
+        NSSATransaction::PrivacyPreserving(_) | NSSATransaction::ProgramDeployment(_) => None,
+    }
+}
+
+fn builtin_program_name(program_id: &ProgramId) -> Option<String> {
+    BuiltinProgram::from_id(program_id).map(|program| program.name().to_string())
+}
+
+fn prefixed_account_ids(account_ids: &[AccountId]) -> Vec<String> {
+    account_ids
+        .iter()
+        .map(|account_id| format!("Public/{account_id}"))
+        .collect()
+}
+
+fn signature_view(
+    scheme: SignatureScheme,
+    signature: &Signature,
+    public_key: &PublicKey,
+    valid: Option<bool>,
+) -> SignatureView {
+    SignatureView {
+        signer: format!(
+            "Public/{}",
+            nssa::signature::account_id(scheme, public_key.value())
+        ),
+        public_key: hex::encode(public_key.value()),
+        signature: hex::encode(signature.value()),
+        valid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nssa::{Amount, Cycles, PrivateKey, privacy_preserving_transaction};
+    use nssa_core::{PrivacyPreservingCircuitOutput, account::Account};
+
+    use super::*;
+
+    /// `encoded_transaction` of the conformance vector `name`
+    fn conformance_transaction(name: &str) -> Vec<u8> {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../nssa/tests/fixtures/conformance_vectors.json"
+        );
+        let vectors: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let vector = vectors
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|vector| vector["name"] == name)
+            .unwrap();
+        hex::decode(vector["encoded_transaction"].as_str().unwrap()).unwrap()
+    }
+
+    fn privacy_preserving_transaction() -> EncodedTransaction {
+        let key = PrivateKey::try_new([3; 32]).unwrap();
+        let account_id = AccountId::from(&PublicKey::new_from_private_key(&key));
+        let message = privacy_preserving_transaction::message::Message::try_from_circuit_output(
+            vec![account_id],
+            vec![4],
+            vec![],
+            PrivacyPreservingCircuitOutput {
+                public_pre_states: vec![],
+                public_post_states: vec![Account::default()],
+                ciphertexts: vec![],
+                new_commitments: vec![],
+                new_nullifiers: vec![],
+            },
+        )
+        .unwrap();
+        // Proofs are not checked by decoding
+        let proof = borsh::from_slice(&borsh::to_vec(&vec![0_u8; 4]).unwrap()).unwrap();
+        let witness_set = privacy_preserving_transaction::witness_set::WitnessSet::for_message(
+            &message,
+            proof,
+            &[&key],
+        );
+        NSSATransaction::PrivacyPreserving(PrivacyPreservingTransaction::new(message, witness_set))
+            .into()
+    }
+
+    #[test]
+    fn test_decode_public_transaction() {
+        let bytes = conformance_transaction("all_message_fields");
+
+        let decoded = decode_transaction(&bytes, &DecoderRegistry::default()).unwrap();
+
+        assert_eq!(decoded.tag, 9);
+        assert!(!decoded.legacy);
+        let DecodedTxBody::Public(tx) = decoded.body else {
+            panic!("Expected public transaction, got {:?}", decoded.body);
+        };
+        assert_eq!(tx.accounts.len(), 3);
+        assert_eq!(tx.readonly_accounts.len(), 1);
+        assert_eq!(tx.nonces, [12, 34]);
+        assert_eq!(tx.priority_fee, Amount(5));
+        assert_eq!(tx.valid_from_block, Some(1_000_000));
+        assert_eq!(tx.max_cycles, Some(Cycles(4_000_000)));
+        assert_eq!(tx.program, None);
+        assert_eq!(tx.signatures.len(), 2);
+        assert!(tx.signatures.iter().all(|sig| sig.valid == Some(true)));
+        assert_eq!(tx.signatures[0].signer, tx.accounts[0]);
+    }
+
+    #[test]
+    fn test_decode_native_transfer() {
+        let key = PrivateKey::try_new([1; 32]).unwrap();
+        let tx = common::test_utils::create_transaction_native_token_transfer(
+            [1; 32], 7, [2; 32], 42, key,
+        );
+
+        let decoded =
+            decode_transaction(&borsh::to_vec(&tx).unwrap(), &DecoderRegistry::default()).unwrap();
+
+        assert_eq!(decoded.hash, hex::encode(tx.hash()));
+        let DecodedTxBody::Public(public_tx) = decoded.body else {
+            panic!("Expected public transaction, got {:?}", decoded.body);
+        };
+        assert_eq!(public_tx.program.as_deref(), Some("authenticated_transfer"));
+        assert_eq!(public_tx.nonces, [7]);
+        assert_eq!(public_tx.instruction.call(), "native_transfer");
+        assert_eq!(public_tx.instruction.0["amount"], "42");
+        assert_eq!(
+            public_tx.instruction.0["recipient"],
+            AccountId::new([2; 32]).to_string()
+        );
+    }
+
+    #[test]
+    fn test_instruction_of_unknown_program_is_hex() {
+        let bytes = conformance_transaction("all_message_fields");
+
+        let decoded = decode_transaction(&bytes, &DecoderRegistry::default()).unwrap();
+
+        let DecodedTxBody::Public(tx) = decoded.body else {
+            panic!("Expected public transaction, got {:?}", decoded.body);
+        };
+        assert_eq!(tx.instruction.call(), "unknown");
+        assert!(tx.instruction.0["program_id"].is_string());
+        assert!(tx.instruction.0["data"].is_string());
+    }
+
+    #[test]
+    fn test_decode_privacy_preserving_transaction() {
+        let tx = privacy_preserving_transaction();
+
+        let decoded =
+            decode_transaction(&borsh::to_vec(&tx).unwrap(), &DecoderRegistry::default()).unwrap();
+
+        assert_eq!(decoded.tag, 7);
+        let DecodedTxBody::PrivacyPreserving(pp_tx) = decoded.body else {
+            panic!(
+                "Expected privacy preserving transaction, got {:?}",
+                decoded.body
+            );
+        };
+        assert_eq!(pp_tx.nonces, [4]);
+        assert_eq!(pp_tx.signatures.len(), 1);
+        assert_eq!(pp_tx.signatures[0].valid, Some(true));
+        assert_eq!(pp_tx.signatures[0].signer, pp_tx.accounts[0]);
+    }
+
+    #[test]
+    fn test_decode_program_deployment_transaction() {
+        let bytes = conformance_transaction("deploy_small_bytecode");
+
+        let decoded = decode_transaction(&bytes, &DecoderRegistry::default()).unwrap();
+
+        assert_eq!(decoded.tag, 8);
+        let DecodedTxBody::ProgramDeployment(deployment) = decoded.body else {
+            panic!("Expected program deployment, got {:?}", decoded.body);
+        };
+        assert_eq!(deployment.bytecode_size, 4);
+        assert_eq!(deployment.program_id, None);
+        assert_eq!(deployment.fee, Amount(1));
+        assert_eq!(deployment.signatures.len(), 1);
+        assert_eq!(deployment.signatures[0].valid, Some(true));
+        assert_eq!(deployment.signatures[0].signer, deployment.deployer);
+    }
+
+    #[test]
+    fn test_invalid_signature_is_reported() {
+        let mut bytes = conformance_transaction("transfer");
+        // Witness set of one signature is 100 bytes. Message ends with the amount, which is 4
+        // instruction words, followed by `u128` priority fee and two empty options.
+        let message_len = bytes.len() - DATA_OFFSET - 4 - 96;
+        let amount_offset = DATA_OFFSET + message_len - 16 - 1 - 1 - 4 * 4;
+        bytes[amount_offset] ^= 1;
+
+        let decoded = decode_transaction(&bytes, &DecoderRegistry::default()).unwrap();
+
+        let DecodedTxBody::Public(tx) = decoded.body else {
+            panic!("Expected public transaction, got {:?}", decoded.body);
+        };
+        assert_eq!(tx.signatures[0].valid, Some(false));
+    }
+
+    #[test]
+    fn test_truncated_transaction_is_rejected_with_offset() {
+        let bytes = conformance_transaction("transfer");
+        let truncated = &bytes[..bytes.len() - 10];
+
+        let err = decode_transaction(truncated, &DecoderRegistry::default()).unwrap_err();
+
+        assert_eq!(err.offset, truncated.len());
+    }
+
+    #[test]
+    fn test_corrupted_public_key_is_rejected_with_offset() {
+        let mut bytes = conformance_transaction("transfer");
+        // Public key is the last field, x coordinates above the field size are invalid
+        let public_key_offset = bytes.len() - 32;
+        bytes[public_key_offset..].fill(0xff);
+
+        let err = decode_transaction(&bytes, &DecoderRegistry::default()).unwrap_err();
+
+        assert_eq!(err.offset, bytes.len());
+    }
+
+    #[test]
+    fn test_trailing_and_unknown_bytes_are_rejected() {
+        let mut bytes = conformance_transaction("transfer");
+        bytes.push(0);
+        assert_eq!(
+            decode_transaction(&bytes, &DecoderRegistry::default())
+                .unwrap_err()
+                .offset,
+            bytes.len() - 1
+        );
+
+        let mut bytes = conformance_transaction("transfer");
+        bytes[0] = 10;
+        assert_eq!(
+            decode_transaction(&bytes, &DecoderRegistry::default())
+                .unwrap_err()
+                .offset,
+            0
+        );
+
+        assert_eq!(
+            decode_transaction(&[], &DecoderRegistry::default())
+                .unwrap_err()
+                .offset,
+            0
+        );
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex(" 0x00ff\n").unwrap(), [0, 0xff]);
+        assert_eq!(parse_hex("00FF").unwrap(), [0, 0xff]);
+        assert!(parse_hex("0g").is_err());
+    }
+}
@@ -0,0 +1,448 @@
+//! Local journal of transactions built and submitted by the wallet.
+//!
+//! The journal is an append-only file of line-delimited JSON records. Every status change of a
+//! transaction appends a new record, which is fsync'd before returning, so the journal survives
+//! crashes. The current state of a transaction is the last record with its hash.
+
+use std::{
+    fs::File,
+    io::{BufRead as _, BufReader, Read as _, Seek as _, SeekFrom, Write as _},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use common::{soft_confirmation::SoftConfirmation, transaction::EncodedTransaction};
+use log::warn;
+use nssa::{AccountId, Amount};
+use nssa_core::account::Nonce;
+use serde::{Deserialize, Serialize};
+
+use crate::paths::private_open_options;
+
+pub const TX_LOG_FILE_NAME: &str = "tx_log.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxStatus {
+    /// Transaction is built and about to be sent
+    Built,
+    /// Sequencer accepted transaction
+    Submitted,
+    /// Transaction is found on chain
+    Included,
+    /// Transaction was rejected or will never be included
+    Failed,
+    /// Another transaction with the same sender and nonce was included instead
+    Replaced,
+    /// Transaction was cancelled locally and its nonce is released, though sequencer may still
+    /// include it
+    Cancelled,
+}
+
+impl TxStatus {
+    /// Final statuses are never changed by reconciliation
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            Self::Included | Self::Failed | Self::Replaced | Self::Cancelled
+        )
+    }
+}
+
+/// Optional human-meaningful details of a journaled transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxDetails {
+    /// Signer of a public transaction
+    pub sender: Option<AccountId>,
+    /// Nonce of `sender` used by the transaction
+    pub nonce: Option<Nonce>,
+    pub target: Option<AccountId>,
+    /// Native tokens moved from `sender`, token program amounts aren't native tokens
+    pub amount: Option<Amount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxLogEntry {
+    /// Hex encoded transaction hash, as returned by sequencer
+    pub hash: String,
+    /// Unix time of the record in milliseconds
+    pub timestamp_millis: u64,
+    /// Base64 encoded borsh serialization of [`EncodedTransaction`]
+    pub transaction_b64: String,
+    pub sender: Option<String>,
+    pub nonce: Option<Nonce>,
+    pub target: Option<String>,
+    pub amount: Option<Amount>,
+    pub status: TxStatus,
+    pub block_id: Option<u64>,
+    /// Promise of the sequencer to include the transaction, verified against its public key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_confirmation: Option<SoftConfirmation>,
+}
+
+impl TxLogEntry {
+    pub fn new_built(tx: &EncodedTransaction, details: TxDetails) -> Self {
+        Self {
+            hash: hex::encode(tx.hash()),
+            timestamp_millis: now_millis(),
+            transaction_b64: BASE64.encode(borsh::to_vec(tx).unwrap()),
+            sender: details.sender.map(|id| id.to_string()),
+            nonce: details.nonce,
+            target: details.target.map(|id| id.to_string()),
+            amount: details.amount,
+            status: TxStatus::Built,
+            block_id: None,
+            soft_confirmation: None,
+        }
+    }
+}
+
+/// Append-only transaction journal stored in wallet home
+#[derive(Debug, Clone)]
+pub struct TxLog {
+    path: PathBuf,
+}
+
+impl TxLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append record to the journal and fsync it
+    pub fn append(&self, entry: &TxLogEntry) -> Result<()> {
+        let mut file = private_open_options()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+
+        let mut line = Vec::new();
+        // Last record may be torn by a crash, start a fresh line so it doesn't corrupt this one
+        if !ends_with_newline(&mut file)? {
+            line.push(b'\n');
+        }
+        serde_json::to_writer(&mut line, entry)?;
+        line.push(b'\n');
+
+        file.write_all(&line)?;
+        file.sync_data()?;
+
+        Ok(())
+    }
+
+    /// Latest record of every journaled transaction, in order of first appearance
+    ///
+    /// Malformed records (e.g. torn by a crash) are skipped.
+    pub fn entries(&self) -> Result<Vec<TxLogEntry>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut entries: Vec<TxLogEntry> = vec![];
+        for (line_num, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: TxLogEntry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(err) => {
+                    warn!("Skipping malformed transaction log record at line {line_num}: {err}");
+                    continue;
+                }
+            };
+
+            match entries.iter_mut().find(|known| known.hash == entry.hash) {
+                Some(known) => *known = entry,
+                None => entries.push(entry),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    pub fn entry(&self, hash: &str) -> Result<Option<TxLogEntry>> {
+        Ok(self.entries()?.into_iter().find(|entry| entry.hash == hash))
+    }
+
+    /// Append new record for transaction with updated status
+    ///
+    /// Returns `false` if transaction is not in the journal.
+    pub fn update_status(
+        &self,
+        hash: &str,
+        status: TxStatus,
+        block_id: Option<u64>,
+    ) -> Result<bool> {
+        let Some(mut entry) = self.entry(hash)? else {
+            return Ok(false);
+        };
+
+        if entry.status == status && (block_id.is_none() || entry.block_id == block_id) {
+            return Ok(true);
+        }
+
+        entry.timestamp_millis = now_millis();
+        entry.status = status;
+        entry.block_id = block_id.or(entry.block_id);
+        self.append(&entry)?;
+
+        Ok(true)
+    }
+
+    /// Append new record for transaction with the soft confirmation of the sequencer
+    ///
+    /// Returns `false` if transaction is not in the journal.
+    pub fn record_soft_confirmation(
+        &self,
+        hash: &str,
+        soft_confirmation: SoftConfirmation,
+    ) -> Result<bool> {
+        let Some(mut entry) = self.entry(hash)? else {
+            return Ok(false);
+        };
+
+        entry.timestamp_millis = now_millis();
+        entry.soft_confirmation = Some(soft_confirmation);
+        self.append(&entry)?;
+
+        Ok(true)
+    }
+
+    /// Hashes of transactions, which status is not final yet
+    pub fn pending_hashes(&self) -> Result<Vec<String>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| !entry.status.is_final())
+            .map(|entry| entry.hash)
+            .collect())
+    }
+}
+
+/// Decide new status of non-final `entry` given what the node knows about it
+///
+/// `current_nonce` is the node's nonce of entry sender, if known. A transaction, which nonce is
+/// already consumed but which is not on chain, either was replaced by another journaled
+/// transaction or will never be included.
+pub fn reconcile_status(
+    entry: &TxLogEntry,
+    found_on_chain: bool,
+    current_nonce: Option<Nonce>,
+    all_entries: &[TxLogEntry],
+) -> Option<TxStatus> {
+    if entry.status.is_final() {
+        return None;
+    }
+
+    if found_on_chain {
+        return Some(TxStatus::Included);
+    }
+
+    let (Some(nonce), Some(current_nonce)) = (entry.nonce, current_nonce) else {
+        return None;
+    };
+
+    if current_nonce <= nonce {
+        return None;
+    }
+
+    let replaced = all_entries.iter().any(|other| {
+        other.hash != entry.hash
+            && other.sender == entry.sender
+            && other.nonce == entry.nonce
+            && other.status == TxStatus::Included
+    });
+
+    if replaced {
+        Some(TxStatus::Replaced)
+    } else {
+        Some(TxStatus::Failed)
+    }
+}
+
+fn ends_with_newline(file: &mut File) -> Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+
+    let mut last_byte = [0; 1];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last_byte)?;
+
+    Ok(last_byte[0] == b'\n')
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+
+    use common::test_utils::produce_dummy_empty_transaction;
+
+    use super::*;
+
+    fn sender() -> AccountId {
+        AccountId::new([1; 32])
+    }
+
+    fn built_entry(nonce: Nonce, amount: u128) -> TxLogEntry {
+        let mut tx = produce_dummy_empty_transaction();
+        // Make hashes distinct
+        tx.encoded_transaction_data
+            .extend_from_slice(&amount.to_le_bytes());
+
+        TxLogEntry::new_built(
+            &tx,
+            TxDetails {
+                sender: Some(sender()),
+                nonce: Some(nonce),
+                target: Some(AccountId::new([2; 32])),
+                amount: Some(Amount(amount)),
+            },
+        )
+    }
+
+    #[test]
+    fn test_entries_of_missing_log_are_empty() {
+        let home = tempfile::tempdir().unwrap();
+        let tx_log = TxLog::new(home.path().join(TX_LOG_FILE_NAME));
+
+        assert!(tx_log.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_latest_record_wins() {
+        let home = tempfile::tempdir().unwrap();
+        let tx_log = TxLog::new(home.path().join(TX_LOG_FILE_NAME));
+        let first = built_entry(0, 10);
+        let second = built_entry(1, 20);
+
+        tx_log.append(&first).unwrap();
+        tx_log.append(&second).unwrap();
+        assert!(
+            tx_log
+                .update_status(&first.hash, TxStatus::Included, Some(5))
+                .unwrap()
+        );
+        assert!(
+            !tx_log
+                .update_status("unknown", TxStatus::Failed, None)
+                .unwrap()
+        );
+
+        let entries = tx_log.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].hash, first.hash);
+        assert_eq!(entries[0].status, TxStatus::Included);
+        assert_eq!(entries[0].block_id, Some(5));
+        assert_eq!(entries[1].status, TxStatus::Built);
+        assert_eq!(tx_log.pending_hashes().unwrap(), vec![second.hash]);
+    }
+
+    #[test]
+    fn test_crash_between_submit_and_confirm() {
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join(TX_LOG_FILE_NAME);
+        let entry = built_entry(0, 10);
+
+        {
+            let tx_log = TxLog::new(path.clone());
+            tx_log.append(&entry).unwrap();
+            tx_log
+                .update_status(&entry.hash, TxStatus::Submitted, None)
+                .unwrap();
+        }
+
+        // Process died in the middle of writing the next record
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"hash":"abc","timesta"#).unwrap();
+        drop(file);
+
+        let tx_log = TxLog::new(path);
+        let restored = tx_log.entry(&entry.hash).unwrap().unwrap();
+        assert_eq!(restored.status, TxStatus::Submitted);
+        assert_eq!(restored.transaction_b64, entry.transaction_b64);
+
+        let new_status = reconcile_status(&restored, true, Some(1), &tx_log.entries().unwrap());
+        assert_eq!(new_status, Some(TxStatus::Included));
+        tx_log
+            .update_status(&entry.hash, new_status.unwrap(), None)
+            .unwrap();
+
+        let entries = tx_log.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, TxStatus::Included);
+    }
+
+    #[test]
+    fn test_reconcile_transaction_unknown_to_node() {
+        let built = built_entry(3, 10);
+
+        // Nonce is not consumed yet, transaction may still be included
+        assert_eq!(reconcile_status(&built, false, Some(3), &[]), None);
+        // No information about sender nonce
+        assert_eq!(reconcile_status(&built, false, None, &[]), None);
+        // Nonce is consumed by something else
+        assert_eq!(
+            reconcile_status(&built, false, Some(4), std::slice::from_ref(&built)),
+            Some(TxStatus::Failed)
+        );
+
+        let mut replacement = built_entry(3, 20);
+        replacement.status = TxStatus::Included;
+        assert_eq!(
+            reconcile_status(&built, false, Some(4), &[built.clone(), replacement]),
+            Some(TxStatus::Replaced)
+        );
+    }
+
+    #[test]
+    fn test_soft_confirmation_survives_status_updates() {
+        let home = tempfile::tempdir().unwrap();
+        let tx_log = TxLog::new(home.path().join(TX_LOG_FILE_NAME));
+        let entry = built_entry(0, 10);
+        let soft_confirmation = SoftConfirmation::sign(
+            [3; 32],
+            1_700_000_000_000,
+            12,
+            &nssa::PrivateKey::try_new([7; 32]).unwrap(),
+        );
+
+        tx_log.append(&entry).unwrap();
+        assert!(
+            tx_log
+                .record_soft_confirmation(&entry.hash, soft_confirmation.clone())
+                .unwrap()
+        );
+        tx_log
+            .update_status(&entry.hash, TxStatus::Submitted, None)
+            .unwrap();
+
+        let restored = tx_log.entry(&entry.hash).unwrap().unwrap();
+        assert_eq!(restored.status, TxStatus::Submitted);
+        assert_eq!(restored.soft_confirmation, Some(soft_confirmation));
+    }
+
+    #[test]
+    fn test_final_status_is_not_reconciled() {
+        let mut entry = built_entry(0, 10);
+        entry.status = TxStatus::Failed;
+
+        assert_eq!(reconcile_status(&entry, true, Some(1), &[]), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! Advisory lock of a wallet file, which prevents concurrent wallet processes from corrupting it.
+
+use std::{
+    fs::{File, TryLockError},
+    io::{Read as _, Seek as _, SeekFrom, Write as _},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+
+use crate::paths::{create_private_dir_all, private_open_options};
+
+/// Exclusive lock of a wallet file, released on drop
+///
+/// Lock is taken on a sibling `<wallet file>.lock` file, which also stores pid of the owner.
+#[derive(Debug)]
+pub struct WalletLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl WalletLock {
+    /// Lock `wallet_file` or fail immediately if it's locked by another process
+    pub fn acquire(wallet_file: &Path) -> Result<Self> {
+        let path = lock_file_path(wallet_file);
+        if let Some(parent) = path.parent() {
+            create_private_dir_all(parent)?;
+        }
+
+        let mut file = private_open_options()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut owner_pid = String::new();
+                file.read_to_string(&mut owner_pid)?;
+                let owner_pid = owner_pid.trim();
+                let owner_pid = if owner_pid.is_empty() {
+                    "unknown"
+                } else {
+                    owner_pid
+                };
+
+                anyhow::bail!(
+                    "Wallet {} is in use by pid {owner_pid}",
+                    wallet_file.display()
+                );
+            }
+            Err(TryLockError::Error(err)) => {
+                return Err(err).with_context(|| format!("Failed to lock {}", path.display()));
+            }
+        }
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(file, "{}", std::process::id())?;
+        file.sync_data()?;
+
+        Ok(Self { _file: file, path })
+    }
+
+    /// Path of the lock file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn lock_file_path(wallet_file: &Path) -> PathBuf {
+    let mut path = wallet_file.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_lock_reports_owner_pid() {
+        let home = tempfile::tempdir().unwrap();
+        let wallet_file = home.path().join("storage.json");
+
+        let lock = WalletLock::acquire(&wallet_file).unwrap();
+        assert_eq!(lock.path(), home.path().join("storage.json.lock"));
+
+        let err = WalletLock::acquire(&wallet_file).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Wallet {} is in use by pid {}",
+                wallet_file.display(),
+                std::process::id()
+            )
+        );
+
+        drop(lock);
+        WalletLock::acquire(&wallet_file).unwrap();
+    }
+
+    #[test]
+    fn test_contending_threads() {
+        let home = tempfile::tempdir().unwrap();
+        let wallet_file = home.path().join("storage.json");
+
+        let handles = (0..4)
+            .map(|_| {
+                let wallet_file = wallet_file.clone();
+                std::thread::spawn(move || WalletLock::acquire(&wallet_file))
+            })
+            .collect::<Vec<_>>();
+
+        let locks = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        // Exactly one thread owns the wallet while locks are held
+        assert_eq!(locks.iter().filter(|lock| lock.is_ok()).count(), 1);
+    }
+
+    #[test]
+    fn test_different_wallet_files_do_not_contend() {
+        let home = tempfile::tempdir().unwrap();
+
+        let _personal = WalletLock::acquire(&home.path().join("personal.json")).unwrap();
+        let _testing = WalletLock::acquire(&home.path().join("testing.json")).unwrap();
+    }
+}